@@ -2,9 +2,10 @@
 
 # Extract the ordinal number from the pod hostname
 ORDINAL="${HOSTNAME##*-}"
+STORAGE="${LINERA_STORAGE:-scylladb:tcp:scylla-client.scylla.svc.cluster.local:9042}"
 
 exec ./linera-server run \
-  --storage scylladb:tcp:scylla-client.scylla.svc.cluster.local:9042 \
+  --storage "$STORAGE" \
   --server /config/server.json \
   --shard $ORDINAL \
   --genesis /config/genesis.json
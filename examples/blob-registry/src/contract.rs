@@ -0,0 +1,224 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use blob_registry::{BlobRegistryAbi, Operation, Registration};
+use linera_sdk::{
+    base::{Owner, WithContractAbi},
+    views::{RootView, View, ViewStorageContext},
+    Contract, ContractRuntime,
+};
+
+use self::state::BlobRegistry;
+
+pub struct BlobRegistryContract {
+    state: BlobRegistry,
+    runtime: ContractRuntime<Self>,
+}
+
+linera_sdk::contract!(BlobRegistryContract);
+
+impl WithContractAbi for BlobRegistryContract {
+    type Abi = BlobRegistryAbi;
+}
+
+impl Contract for BlobRegistryContract {
+    type Message = ();
+    type InstantiationArgument = ();
+    type Parameters = ();
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = BlobRegistry::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        BlobRegistryContract { state, runtime }
+    }
+
+    async fn instantiate(&mut self, _argument: ()) {
+        // Validate that the application parameters were configured correctly.
+        self.runtime.application_parameters();
+    }
+
+    async fn execute_operation(&mut self, operation: Operation) {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Blob registry operations must be signed");
+        match operation {
+            Operation::Register { name, blob_id } => {
+                let owner = match self.registration(&name).await {
+                    Some(registration) => {
+                        Self::check_ownership(&name, &registration, signer);
+                        registration.owner
+                    }
+                    None => signer,
+                };
+                let registration = Registration { blob_id, owner };
+                self.state
+                    .registrations
+                    .insert(&name, registration)
+                    .expect("Failed to register the name");
+            }
+            Operation::Transfer { name, new_owner } => {
+                let mut registration = self.owned_registration(&name, signer).await;
+                registration.owner = new_owner;
+                self.state
+                    .registrations
+                    .insert(&name, registration)
+                    .expect("Failed to transfer the name");
+            }
+            Operation::Unregister { name } => {
+                self.owned_registration(&name, signer).await;
+                self.state
+                    .registrations
+                    .remove(&name)
+                    .expect("Failed to unregister the name");
+            }
+        }
+    }
+
+    async fn execute_message(&mut self, _message: ()) {
+        panic!("Blob registry application doesn't support any cross-chain messages");
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
+
+impl BlobRegistryContract {
+    async fn registration(&self, name: &str) -> Option<Registration> {
+        self.state
+            .registrations
+            .get(name)
+            .await
+            .expect("Failed to read the registration")
+    }
+
+    /// Returns the registration of a name, checking that it is owned by the signer.
+    async fn owned_registration(&self, name: &str, signer: Owner) -> Registration {
+        let registration = self
+            .registration(name)
+            .await
+            .unwrap_or_else(|| panic!("The name {name:?} is not registered"));
+        Self::check_ownership(name, &registration, signer);
+        registration
+    }
+
+    fn check_ownership(name: &str, registration: &Registration, signer: Owner) {
+        assert_eq!(
+            registration.owner, signer,
+            "The name {name:?} is owned by another user"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blob_registry::{Operation, Registration};
+    use futures::FutureExt;
+    use linera_sdk::{
+        base::{BlobId, CryptoHash, Owner, PublicKey},
+        util::BlockingWait,
+        views::{View, ViewStorageContext},
+        Contract, ContractRuntime,
+    };
+
+    use super::{BlobRegistry, BlobRegistryContract};
+
+    #[test]
+    fn register_update_and_transfer() {
+        let owner = Owner::from(PublicKey::test_key(1));
+        let other_owner = Owner::from(PublicKey::test_key(2));
+        let mut registry = create_and_instantiate_registry(owner);
+        let name = "home".to_string();
+
+        execute(&mut registry, register(&name, "first"));
+        execute(&mut registry, register(&name, "second"));
+        assert_eq!(
+            registration(&registry, &name),
+            Some(Registration {
+                blob_id: blob_id("second"),
+                owner,
+            })
+        );
+
+        let operation = Operation::Transfer {
+            name: name.clone(),
+            new_owner: other_owner,
+        };
+        execute(&mut registry, operation);
+        assert_eq!(
+            registration(&registry, &name).map(|registration| registration.owner),
+            Some(other_owner)
+        );
+
+        registry.runtime.set_authenticated_signer(other_owner);
+        let operation = Operation::Unregister { name: name.clone() };
+        execute(&mut registry, operation);
+        assert_eq!(registration(&registry, &name), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The name \"home\" is owned by another user")]
+    fn update_by_another_user() {
+        let owner = Owner::from(PublicKey::test_key(1));
+        let mut registry = create_and_instantiate_registry(owner);
+        execute(&mut registry, register("home", "first"));
+
+        registry
+            .runtime
+            .set_authenticated_signer(Owner::from(PublicKey::test_key(2)));
+        execute(&mut registry, register("home", "second"));
+    }
+
+    fn blob_id(content: &str) -> BlobId {
+        BlobId(CryptoHash::test_hash(content))
+    }
+
+    fn register(name: &str, content: &str) -> Operation {
+        Operation::Register {
+            name: name.to_string(),
+            blob_id: blob_id(content),
+        }
+    }
+
+    fn execute(registry: &mut BlobRegistryContract, operation: Operation) {
+        registry
+            .execute_operation(operation)
+            .now_or_never()
+            .expect("Execution of registry operation should not await anything")
+    }
+
+    fn registration(registry: &BlobRegistryContract, name: &str) -> Option<Registration> {
+        registry
+            .state
+            .registrations
+            .get(name)
+            .now_or_never()
+            .expect("Reading the registration should not await anything")
+            .expect("Failed to read the registration")
+    }
+
+    fn create_and_instantiate_registry(owner: Owner) -> BlobRegistryContract {
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(())
+            .with_authenticated_signer(owner);
+        let mut contract = BlobRegistryContract {
+            state: BlobRegistry::load(ViewStorageContext::from(runtime.key_value_store()))
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+
+        contract
+            .instantiate(())
+            .now_or_never()
+            .expect("Initialization of the registry should not await anything");
+
+        contract
+    }
+}
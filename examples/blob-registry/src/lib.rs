@@ -0,0 +1,122 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/*!
+# Blob Registry Example Application
+
+This example application maps names to the IDs of blobs, so that content stored as a blob
+can be found under a stable name while its owner publishes new versions of it.
+
+# How It Works
+
+A name is owned by the user who registers it first. Only the owner of a name can register
+another blob under it, transfer it to another user, or unregister it. Operations must be
+signed, since the authenticated signer is the owner.
+
+The service answers the query `resolve(name: String!)` with the ID of the blob registered
+under a name. The node service uses this query to serve the content of the blob at
+`/chains/<chain-id>/applications/<application-id>/blobs/<name>`, provided that the blob is
+in its storage. Blobs can also be fetched directly by ID at `/blobs/<blob-id>`.
+
+# Usage
+
+## Setting Up
+
+Before getting started, make sure that the binary tools `linera*` corresponding to
+your version of `linera-sdk` are in your PATH. For scripting purposes, we also assume
+that the BASH function `linera_spawn_and_read_wallet_variables` is defined.
+
+From the root of Linera repository, this can be achieved as follows:
+
+```bash
+export PATH="$PWD/target/debug:$PATH"
+source /dev/stdin <<<"$(linera net helper 2>/dev/null)"
+```
+
+To start the local Linera network:
+
+```bash
+linera_spawn_and_read_wallet_variables linera net up --testing-prng-seed 37
+```
+
+We use the test-only CLI option `--testing-prng-seed` to make keys deterministic and simplify our
+explanation.
+
+```bash
+CHAIN_1=e476187f6ddfeb9d588c7b45d3df334d5501d6499b3f9ad5595cae86cce16a65
+```
+
+Now, compile the `blob-registry` application WebAssembly binaries, publish and create an
+application instance.
+
+```bash
+(cd examples/blob-registry && cargo build --release --target wasm32-unknown-unknown)
+
+APPLICATION_ID=$(linera publish-and-create \
+  examples/target/wasm32-unknown-unknown/release/blob_registry_{contract,service}.wasm)
+```
+
+## Using the Blob Registry Application
+
+First, a node service for the current wallet has to be started:
+
+```bash
+PORT=8080
+linera service --port $PORT &
+```
+
+- Navigate to `http://localhost:8080/chains/$CHAIN_1/applications/$APPLICATION_ID`.
+- To register a blob under the name `home`, perform the `register` operation.
+```gql,uri=http://localhost:8080/chains/$CHAIN_1/applications/$APPLICATION_ID
+    mutation {
+        register(
+            name: "home",
+            blobId: "f3a6cf2ad2c1f391bbbe2bc8a2d7474b6b2e67e253cd595bbc760d0e8b5e5d34"
+        )
+    }
+```
+- To find the blob registered under `home`, run the query:
+```gql,uri=http://localhost:8080/chains/$CHAIN_1/applications/$APPLICATION_ID
+    query {
+        resolve(name: "home")
+    }
+```
+*/
+
+use async_graphql::{Request, Response, SimpleObject};
+use linera_sdk::{
+    base::{BlobId, ContractAbi, Owner, ServiceAbi},
+    graphql::GraphQLMutationRoot,
+};
+use serde::{Deserialize, Serialize};
+
+pub struct BlobRegistryAbi;
+
+impl ContractAbi for BlobRegistryAbi {
+    type Operation = Operation;
+    type Response = ();
+}
+
+impl ServiceAbi for BlobRegistryAbi {
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+/// An operation.
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum Operation {
+    /// Registers a blob under a name. If the name is already registered, the signer must
+    /// own it.
+    Register { name: String, blob_id: BlobId },
+    /// Transfers the ownership of a name to another user.
+    Transfer { name: String, new_owner: Owner },
+    /// Removes the registration of a name.
+    Unregister { name: String },
+}
+
+/// The blob registered under a name, and the owner of the name.
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject, PartialEq, Eq)]
+pub struct Registration {
+    pub blob_id: BlobId,
+    pub owner: Owner,
+}
@@ -0,0 +1,121 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
+use blob_registry::{Operation, Registration};
+use linera_sdk::{
+    base::{BlobId, WithServiceAbi},
+    graphql::GraphQLMutationRoot,
+    views::{View, ViewStorageContext},
+    Service, ServiceRuntime,
+};
+
+use self::state::BlobRegistry;
+
+pub struct BlobRegistryService {
+    state: Arc<BlobRegistry>,
+}
+
+linera_sdk::service!(BlobRegistryService);
+
+impl WithServiceAbi for BlobRegistryService {
+    type Abi = blob_registry::BlobRegistryAbi;
+}
+
+impl Service for BlobRegistryService {
+    type Parameters = ();
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = BlobRegistry::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        BlobRegistryService {
+            state: Arc::new(state),
+        }
+    }
+
+    async fn handle_query(&self, request: Request) -> Response {
+        let schema = Schema::build(
+            QueryRoot {
+                state: self.state.clone(),
+            },
+            Operation::mutation_root(),
+            EmptySubscription,
+        )
+        .finish();
+        schema.execute(request).await
+    }
+}
+
+struct QueryRoot {
+    state: Arc<BlobRegistry>,
+}
+
+#[Object]
+impl QueryRoot {
+    /// Returns the ID of the blob registered under the name, if any.
+    async fn resolve(&self, name: String) -> async_graphql::Result<Option<BlobId>> {
+        let registration = self.state.registrations.get(&name).await?;
+        Ok(registration.map(|registration| registration.blob_id))
+    }
+
+    /// Returns the registration of the name, if any.
+    async fn registration(&self, name: String) -> async_graphql::Result<Option<Registration>> {
+        Ok(self.state.registrations.get(&name).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_graphql::{Request, Response, Value};
+    use blob_registry::Registration;
+    use futures::FutureExt;
+    use linera_sdk::{
+        base::{BlobId, CryptoHash, Owner, PublicKey},
+        util::BlockingWait,
+        views::{View, ViewStorageContext},
+        Service, ServiceRuntime,
+    };
+    use serde_json::json;
+
+    use super::{BlobRegistry, BlobRegistryService};
+
+    #[test]
+    fn resolve() {
+        let runtime = ServiceRuntime::<BlobRegistryService>::new();
+        let mut state = BlobRegistry::load(ViewStorageContext::from(runtime.key_value_store()))
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        let blob_id = BlobId(CryptoHash::test_hash("content"));
+        let registration = Registration {
+            blob_id,
+            owner: Owner::from(PublicKey::test_key(1)),
+        };
+        state.registrations.insert("home", registration).unwrap();
+
+        let service = BlobRegistryService {
+            state: Arc::new(state),
+        };
+        let request =
+            Request::new(r#"{ home: resolve(name: "home"), other: resolve(name: "other") }"#);
+
+        let response = service
+            .handle_query(request)
+            .now_or_never()
+            .expect("Query should not await anything");
+
+        let expected = Response::new(
+            Value::from_json(json!({"home": blob_id.to_string(), "other": null})).unwrap(),
+        );
+
+        assert_eq!(response, expected)
+    }
+}
@@ -0,0 +1,13 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use blob_registry::Registration;
+use linera_sdk::views::{linera_views, MapView, RootView, ViewStorageContext};
+
+/// The application state.
+#[derive(RootView)]
+#[view(context = "ViewStorageContext")]
+pub struct BlobRegistry {
+    /// The registration of each name.
+    pub registrations: MapView<String, Registration>,
+}
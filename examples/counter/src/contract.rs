@@ -63,6 +63,7 @@ impl Contract for CounterContract {
 mod tests {
     use futures::FutureExt;
     use linera_sdk::{
+        test::load_state,
         util::BlockingWait,
         views::{View, ViewStorageContext},
         Contract, ContractRuntime,
@@ -118,6 +119,25 @@ mod tests {
         assert_eq!(*counter.state.value.get(), expected_value);
     }
 
+    #[test]
+    fn stored_state() {
+        let initial_value = 5_u64;
+        let mut counter = create_and_instantiate_counter(initial_value);
+        counter
+            .execute_operation(3)
+            .now_or_never()
+            .expect("Execution of counter operation should not await anything");
+
+        let key_value_store = counter.runtime.key_value_store();
+        counter
+            .store()
+            .now_or_never()
+            .expect("Storing the counter state should not await anything");
+
+        let state = load_state::<Counter>(key_value_store);
+        assert_eq!(*state.value.get(), initial_value + 3);
+    }
+
     fn create_and_instantiate_counter(initial_value: u64) -> CounterContract {
         let runtime = ContractRuntime::new().with_application_parameters(());
         let mut contract = CounterContract {
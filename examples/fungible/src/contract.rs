@@ -158,9 +158,7 @@ impl FungibleTokenContract {
                 target_account,
             };
             self.runtime
-                .prepare_message(message)
-                .with_authentication()
-                .send_to(source_account.chain_id);
+                .send_application_message(source_account.chain_id, message);
         }
     }
 
@@ -180,10 +178,7 @@ impl FungibleTokenContract {
                 source,
             };
             self.runtime
-                .prepare_message(message)
-                .with_authentication()
-                .with_tracking()
-                .send_to(target_account.chain_id);
+                .send_application_message(target_account.chain_id, message);
         }
     }
 }
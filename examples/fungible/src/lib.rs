@@ -224,7 +224,10 @@ second page.
 */
 
 pub use linera_sdk::abis::fungible::*;
-use linera_sdk::base::{AccountOwner, Amount};
+use linera_sdk::{
+    base::{AccountOwner, Amount},
+    ApplicationMessage,
+};
 use serde::{Deserialize, Serialize};
 #[cfg(all(any(test, feature = "test"), not(target_arch = "wasm32")))]
 use {
@@ -237,10 +240,12 @@ use {
 };
 
 /// A message.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ApplicationMessage)]
+#[message(authenticated, direct)]
 pub enum Message {
     /// Credits the given `target` account, unless the message is bouncing, in which case
     /// `source` is credited instead.
+    #[message(tracked)]
     Credit {
         /// Target account to credit amount to
         target: AccountOwner,
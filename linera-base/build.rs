@@ -6,9 +6,11 @@ fn main() {
         web: { all(target_arch = "wasm32", feature = "web") },
         with_metrics: { all(not(target_arch = "wasm32"), feature = "metrics") },
         with_testing: { any(test, feature = "test") },
+        with_bls: { feature = "bls" },
 
         // the old version of `getrandom` we pin here is available on all targets, but
         // using it will panic if no suitable source of entropy is found
         with_getrandom: { any(web, not(target_arch = "wasm32")) },
+        with_wall_clock: { any(web, not(target_arch = "wasm32")) },
     };
 }
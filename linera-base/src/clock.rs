@@ -0,0 +1,140 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Clocks giving the current [`Timestamp`] to the validators and clients: the system clock,
+//! and a clock that tests set explicitly to make time deterministic.
+
+use async_trait::async_trait;
+#[cfg(with_testing)]
+use {
+    futures::channel::oneshot::{self, Receiver},
+    std::{cmp::Reverse, collections::BTreeMap, sync::Arc},
+};
+
+use crate::data_types::{TimeDelta, Timestamp};
+
+/// A clock that can be used to get the current `Timestamp`.
+#[async_trait]
+pub trait Clock {
+    /// Returns the current time.
+    fn current_time(&self) -> Timestamp;
+
+    /// Waits until `delta` has elapsed.
+    async fn sleep(&self, delta: TimeDelta);
+
+    /// Waits until the current time is at least `timestamp`.
+    async fn sleep_until(&self, timestamp: Timestamp);
+}
+
+/// A `Clock` implementation using the system clock.
+#[cfg(with_wall_clock)]
+#[derive(Clone)]
+pub struct WallClock;
+
+#[cfg(with_wall_clock)]
+#[async_trait]
+impl Clock for WallClock {
+    fn current_time(&self) -> Timestamp {
+        Timestamp::now()
+    }
+
+    async fn sleep(&self, delta: TimeDelta) {
+        cfg_if::cfg_if! {
+            if #[cfg(web)] {
+                wasmtimer::tokio::sleep(delta.as_duration()).await
+            } else {
+                tokio::time::sleep(delta.as_duration()).await
+            }
+        }
+    }
+
+    async fn sleep_until(&self, timestamp: Timestamp) {
+        let delta = timestamp.delta_since(Timestamp::now());
+        if delta > TimeDelta::ZERO {
+            self.sleep(delta).await
+        }
+    }
+}
+
+#[cfg(with_testing)]
+#[derive(Default)]
+struct TestClockInner {
+    time: Timestamp,
+    sleeps: BTreeMap<Reverse<Timestamp>, Vec<oneshot::Sender<()>>>,
+}
+
+#[cfg(with_testing)]
+impl TestClockInner {
+    fn set(&mut self, time: Timestamp) {
+        self.time = time;
+        let senders = self.sleeps.split_off(&Reverse(time));
+        for sender in senders.into_values().flatten() {
+            let _ = sender.send(());
+        }
+    }
+
+    fn add_sleep(&mut self, delta: TimeDelta) -> Receiver<()> {
+        self.add_sleep_until(self.time.saturating_add(delta))
+    }
+
+    fn add_sleep_until(&mut self, time: Timestamp) -> Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        if self.time >= time {
+            let _ = sender.send(());
+        } else {
+            self.sleeps.entry(Reverse(time)).or_default().push(sender);
+        }
+        receiver
+    }
+}
+
+/// A clock implementation that uses a stored number of microseconds and that can be updated
+/// explicitly. All clones share the same time, and setting it in one clone updates all the others.
+#[cfg(with_testing)]
+#[derive(Clone, Default)]
+pub struct TestClock(Arc<std::sync::Mutex<TestClockInner>>);
+
+#[cfg(with_testing)]
+#[async_trait]
+impl Clock for TestClock {
+    fn current_time(&self) -> Timestamp {
+        self.lock().time
+    }
+
+    async fn sleep(&self, delta: TimeDelta) {
+        if delta == TimeDelta::ZERO {
+            return;
+        }
+        let receiver = self.lock().add_sleep(delta);
+        let _ = receiver.await;
+    }
+
+    async fn sleep_until(&self, timestamp: Timestamp) {
+        let receiver = self.lock().add_sleep_until(timestamp);
+        let _ = receiver.await;
+    }
+}
+
+#[cfg(with_testing)]
+impl TestClock {
+    /// Creates a new clock with its time set to 0, i.e. the Unix epoch.
+    pub fn new() -> Self {
+        TestClock(Arc::default())
+    }
+
+    /// Sets the current time.
+    pub fn set(&self, time: Timestamp) {
+        self.lock().set(time);
+    }
+
+    /// Advances the current time by the specified delta.
+    pub fn add(&self, delta: TimeDelta) {
+        let mut guard = self.lock();
+        let time = guard.time.saturating_add(delta);
+        guard.set(time);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<TestClockInner> {
+        self.0.lock().expect("poisoned TestClock mutex")
+    }
+}
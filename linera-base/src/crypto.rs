@@ -6,7 +6,7 @@
 
 use std::{borrow::Cow, num::ParseIntError, str::FromStr};
 
-use ed25519_dalek::{self as dalek, Signer, Verifier};
+use ed25519_dalek as dalek;
 use generic_array::typenum::Unsigned;
 use linera_witty::{
     GuestPointer, HList, InstanceWithMemory, Layout, Memory, Runtime, RuntimeError, RuntimeMemory,
@@ -26,6 +26,10 @@ use {
 
 use crate::doc_scalar;
 
+#[cfg(with_bls)]
+pub mod bls;
+pub mod provider;
+
 /// A signature key-pair.
 pub struct KeyPair(dalek::SigningKey);
 
@@ -67,6 +71,14 @@ pub enum CryptoError {
     IncorrectPublicKeySize(usize),
     #[error("Could not parse integer")]
     ParseIntError(#[from] ParseIntError),
+    #[error("The crypto provider {0} is already in use")]
+    ProviderAlreadyInstalled(&'static str),
+    #[cfg(with_bls)]
+    #[error("Byte slice has length {0} but a `BlsPublicKey` requires exactly 48 bytes")]
+    IncorrectBlsPublicKeySize(usize),
+    #[cfg(with_bls)]
+    #[error("Byte slice has length {0} but a `BlsSignature` requires exactly 96 bytes")]
+    IncorrectBlsSignatureSize(usize),
 }
 
 impl PublicKey {
@@ -447,18 +459,17 @@ impl Signature {
     {
         let mut message = Vec::new();
         value.write(&mut message);
-        let signature = secret.0.sign(&message);
-        Signature(signature)
+        let signature = provider::provider().sign(secret.0.as_bytes(), &message);
+        Signature(dalek::Signature::from_bytes(&signature))
     }
 
-    fn check_internal<T>(&self, value: &T, author: PublicKey) -> Result<(), dalek::SignatureError>
+    fn check_internal<T>(&self, value: &T, author: PublicKey) -> Result<(), String>
     where
         T: BcsSignable,
     {
         let mut message = Vec::new();
         value.write(&mut message);
-        let public_key = dalek::VerifyingKey::from_bytes(&author.0)?;
-        public_key.verify(&message, &self.0)
+        provider::provider().verify(&author.0, &message, &self.0.to_bytes())
     }
 
     /// Checks a signature.
@@ -468,7 +479,7 @@ impl Signature {
     {
         self.check_internal(value, author)
             .map_err(|error| CryptoError::InvalidSignature {
-                error: error.to_string(),
+                error,
                 type_name: T::type_name().to_string(),
             })
     }
@@ -490,22 +501,18 @@ impl Signature {
         }
     }
 
-    fn verify_batch_internal<'a, T, I>(value: &'a T, votes: I) -> Result<(), dalek::SignatureError>
+    fn verify_batch_internal<'a, T, I>(value: &'a T, votes: I) -> Result<(), String>
     where
         T: BcsSignable,
         I: IntoIterator<Item = (&'a PublicKey, &'a Signature)>,
     {
         let mut msg = Vec::new();
         value.write(&mut msg);
-        let mut messages = Vec::new();
-        let mut signatures = Vec::new();
-        let mut public_keys = Vec::new();
-        for (addr, sig) in votes.into_iter() {
-            messages.push(msg.as_slice());
-            signatures.push(sig.0);
-            public_keys.push(dalek::VerifyingKey::from_bytes(&addr.0)?);
-        }
-        dalek::verify_batch(&messages[..], &signatures[..], &public_keys[..])
+        let votes = votes
+            .into_iter()
+            .map(|(addr, sig)| (addr.0, sig.0.to_bytes()))
+            .collect::<Vec<_>>();
+        provider::provider().verify_batch(&msg, &votes)
     }
 
     /// Verifies a batch of signatures.
@@ -516,11 +523,38 @@ impl Signature {
     {
         Signature::verify_batch_internal(value, votes).map_err(|error| {
             CryptoError::InvalidSignature {
-                error: format!("batched {}", error),
+                error,
                 type_name: T::type_name().to_string(),
             }
         })
     }
+
+    /// Verifies a batch of signatures of different values, each by its author. This is faster
+    /// than checking them one by one.
+    pub fn verify_messages_batch<'a, T, I>(items: I) -> Result<(), CryptoError>
+    where
+        T: BcsSignable + 'a,
+        I: IntoIterator<Item = (&'a T, &'a PublicKey, &'a Signature)>,
+    {
+        let items = items
+            .into_iter()
+            .map(|(value, author, signature)| {
+                let mut message = Vec::new();
+                value.write(&mut message);
+                (message, author.0, signature.0.to_bytes())
+            })
+            .collect::<Vec<_>>();
+        let items = items
+            .iter()
+            .map(|(message, author, signature)| (&message[..], *author, *signature))
+            .collect::<Vec<_>>();
+        provider::provider()
+            .verify_messages_batch(&items)
+            .map_err(|error| CryptoError::InvalidSignature {
+                error,
+                type_name: T::type_name().to_string(),
+            })
+    }
 }
 
 impl WitType for CryptoHash {
@@ -732,6 +766,24 @@ fn test_signatures() {
     assert!(s.check(&foo, addr1).is_err());
 }
 
+#[cfg(with_getrandom)]
+#[test]
+fn test_messages_batch() {
+    let key1 = KeyPair::generate();
+    let addr1 = key1.public();
+    let key2 = KeyPair::generate();
+    let addr2 = key2.public();
+
+    let ts = TestString("hello".into());
+    let tsx = TestString("hellox".into());
+    let s1 = Signature::new(&ts, &key1);
+    let s2 = Signature::new(&tsx, &key2);
+
+    assert!(Signature::verify_messages_batch([(&ts, &addr1, &s1), (&tsx, &addr2, &s2)]).is_ok());
+    assert!(Signature::verify_messages_batch([(&ts, &addr1, &s1), (&ts, &addr2, &s2)]).is_err());
+    assert!(Signature::verify_messages_batch([(&ts, &addr2, &s1), (&tsx, &addr1, &s2)]).is_err());
+}
+
 /// Reads the `bytes` as four little-endian unsigned 64-bit integers and returns them.
 fn le_bytes_to_u64_array(bytes: &[u8]) -> [u64; 4] {
     let mut integers = [0u64; 4];
@@ -0,0 +1,341 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! BLS12-381 signatures, which can be aggregated.
+//!
+//! Public keys are in G1 and signatures in G2, using the proof-of-possession ciphersuite of
+//! the IETF BLS draft. Before the aggregate of several signatures is checked, each public key
+//! must have been checked with the [`BlsPublicKey::check_proof_of_possession`] of its owner.
+//! Signatures are computed and checked by the installed
+//! [`CryptoProvider`](super::provider::CryptoProvider).
+
+use std::{fmt, str::FromStr};
+
+use blst::min_pk as blst_bls;
+use serde::{Deserialize, Serialize};
+
+pub use super::provider::{BLS_PUBLIC_KEY_LENGTH, BLS_SIGNATURE_LENGTH};
+#[cfg(with_getrandom)]
+use super::CryptoRng;
+use super::{provider, BcsSignable, CryptoError, HasTypeName, Hashable};
+
+/// A BLS12-381 key-pair.
+pub struct BlsKeyPair(blst_bls::SecretKey);
+
+/// A BLS12-381 public key.
+#[derive(Eq, PartialEq, Copy, Clone, Hash)]
+pub struct BlsPublicKey(pub [u8; BLS_PUBLIC_KEY_LENGTH]);
+
+/// A BLS12-381 signature, or the aggregate of several signatures of the same message.
+#[derive(Eq, PartialEq, Copy, Clone)]
+pub struct BlsSignature(pub [u8; BLS_SIGNATURE_LENGTH]);
+
+impl BlsKeyPair {
+    #[cfg(all(with_getrandom, with_testing))]
+    /// Generates a new key-pair.
+    pub fn generate() -> Self {
+        let mut rng = rand::rngs::OsRng;
+        Self::generate_from(&mut rng)
+    }
+
+    #[cfg(with_getrandom)]
+    /// Generates a new key-pair from the given RNG. Use with care.
+    pub fn generate_from<R: CryptoRng>(rng: &mut R) -> Self {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        let secret_key =
+            blst_bls::SecretKey::key_gen(&seed, &[]).expect("seed should have 32 bytes");
+        BlsKeyPair(secret_key)
+    }
+
+    /// Obtains the public key of a key-pair.
+    pub fn public(&self) -> BlsPublicKey {
+        BlsPublicKey(self.0.sk_to_pk().compress())
+    }
+
+    /// Proves the possession of the secret key, by signing the public key.
+    pub fn proof_of_possession(&self) -> BlsSignature {
+        let proof = provider::provider()
+            .bls_prove_possession(&self.0.to_bytes())
+            .expect("key-pairs should hold valid secret keys");
+        BlsSignature(proof)
+    }
+
+    /// Copies the key-pair, **including the secret key**.
+    ///
+    /// The `Clone` and `Copy` traits are deliberately not implemented for `BlsKeyPair` to
+    /// prevent accidental copies of secret keys.
+    pub fn copy(&self) -> BlsKeyPair {
+        BlsKeyPair(self.0.clone())
+    }
+}
+
+impl BlsPublicKey {
+    /// Checks that the owner of the public key has its secret key.
+    pub fn check_proof_of_possession(&self, proof: &BlsSignature) -> Result<(), CryptoError> {
+        provider::provider()
+            .bls_check_possession(&self.0, &proof.0)
+            .map_err(|error| CryptoError::InvalidSignature {
+                error,
+                type_name: "BlsPublicKey".to_string(),
+            })
+    }
+}
+
+impl BlsSignature {
+    /// Computes a signature.
+    pub fn new<T>(value: &T, secret: &BlsKeyPair) -> Self
+    where
+        T: BcsSignable,
+    {
+        let mut message = Vec::new();
+        value.write(&mut message);
+        let signature = provider::provider()
+            .bls_sign(&secret.0.to_bytes(), &message)
+            .expect("key-pairs should hold valid secret keys");
+        BlsSignature(signature)
+    }
+
+    /// Checks a signature.
+    pub fn check<T>(&self, value: &T, author: BlsPublicKey) -> Result<(), CryptoError>
+    where
+        T: BcsSignable,
+    {
+        self.check_aggregate(value, [&author])
+    }
+
+    /// Combines the signatures of the same value by several authors into one.
+    pub fn aggregate<'a>(
+        signatures: impl IntoIterator<Item = &'a BlsSignature>,
+    ) -> Result<Self, CryptoError> {
+        let signatures = signatures
+            .into_iter()
+            .map(|signature| signature.0)
+            .collect::<Vec<_>>();
+        let aggregate = provider::provider()
+            .bls_aggregate(&signatures)
+            .map_err(|error| CryptoError::InvalidSignature {
+                error,
+                type_name: "BlsSignature".to_string(),
+            })?;
+        Ok(BlsSignature(aggregate))
+    }
+
+    /// Checks the aggregate of the signatures of a value by all the authors. The proof of
+    /// possession of each author must have been checked beforehand.
+    pub fn check_aggregate<'a, T>(
+        &self,
+        value: &T,
+        authors: impl IntoIterator<Item = &'a BlsPublicKey>,
+    ) -> Result<(), CryptoError>
+    where
+        T: BcsSignable,
+    {
+        let mut message = Vec::new();
+        value.write(&mut message);
+        let public_keys = authors
+            .into_iter()
+            .map(|author| author.0)
+            .collect::<Vec<_>>();
+        provider::provider()
+            .bls_verify_aggregate(&public_keys, &message, &self.0)
+            .map_err(|error| CryptoError::InvalidSignature {
+                error,
+                type_name: T::type_name().to_string(),
+            })
+    }
+}
+
+impl Serialize for BlsPublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            serializer.serialize_newtype_struct("BlsPublicKey", serde_bytes::Bytes::new(&self.0))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsPublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::from_str(&s).map_err(serde::de::Error::custom)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "BlsPublicKey")]
+            struct Foo(#[serde(with = "serde_bytes")] Vec<u8>);
+
+            let value = Foo::deserialize(deserializer)?;
+            Self::try_from(&value.0[..]).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+impl Serialize for BlsSignature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            serializer.serialize_newtype_struct("BlsSignature", serde_bytes::Bytes::new(&self.0))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsSignature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let bytes = if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            hex::decode(s).map_err(serde::de::Error::custom)?
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "BlsSignature")]
+            struct Foo(#[serde(with = "serde_bytes")] Vec<u8>);
+
+            Foo::deserialize(deserializer)?.0
+        };
+        let signature = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| CryptoError::IncorrectBlsSignatureSize(bytes.len()))
+            .map_err(serde::de::Error::custom)?;
+        Ok(BlsSignature(signature))
+    }
+}
+
+impl Serialize for BlsKeyPair {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        // This is only used for JSON configuration.
+        assert!(serializer.is_human_readable());
+        serializer.serialize_str(&hex::encode(self.0.to_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsKeyPair {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        // This is only used for JSON configuration.
+        assert!(deserializer.is_human_readable());
+        let s = String::deserialize(deserializer)?;
+        let value = hex::decode(s).map_err(serde::de::Error::custom)?;
+        let secret_key = blst_bls::SecretKey::from_bytes(&value)
+            .map_err(|error| serde::de::Error::custom(format!("{:?}", error)))?;
+        Ok(BlsKeyPair(secret_key))
+    }
+}
+
+impl FromStr for BlsPublicKey {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = hex::decode(s)?;
+        (value.as_slice()).try_into()
+    }
+}
+
+impl TryFrom<&[u8]> for BlsPublicKey {
+    type Error = CryptoError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let public_key = value
+            .try_into()
+            .map_err(|_| CryptoError::IncorrectBlsPublicKeySize(value.len()))?;
+        Ok(BlsPublicKey(public_key))
+    }
+}
+
+impl fmt::Display for BlsPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Display for BlsSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for BlsPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..8]))
+    }
+}
+
+impl fmt::Debug for BlsSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..8]))
+    }
+}
+
+#[cfg(with_getrandom)]
+#[test]
+fn test_bls_signatures() {
+    use super::TestString;
+
+    let key_pair = BlsKeyPair::generate();
+    let public_key = key_pair.public();
+    let value = TestString::new("hello");
+    let signature = BlsSignature::new(&value, &key_pair);
+    assert!(signature.check(&value, public_key).is_ok());
+    assert!(signature
+        .check(&TestString::new("hellox"), public_key)
+        .is_err());
+    let other_public_key = BlsKeyPair::generate().public();
+    assert!(signature.check(&value, other_public_key).is_err());
+
+    let bytes = bcs::to_bytes(&signature).unwrap();
+    assert_eq!(bcs::from_bytes::<BlsSignature>(&bytes).unwrap(), signature);
+    let bytes = bcs::to_bytes(&public_key).unwrap();
+    assert_eq!(bcs::from_bytes::<BlsPublicKey>(&bytes).unwrap(), public_key);
+    assert_eq!(
+        public_key.to_string().parse::<BlsPublicKey>().unwrap(),
+        public_key
+    );
+}
+
+#[cfg(with_getrandom)]
+#[test]
+fn test_bls_aggregate_signatures() {
+    use super::TestString;
+
+    let key_pairs = (0..4).map(|_| BlsKeyPair::generate()).collect::<Vec<_>>();
+    let public_keys = key_pairs.iter().map(BlsKeyPair::public).collect::<Vec<_>>();
+    for key_pair in &key_pairs {
+        let proof = key_pair.proof_of_possession();
+        assert!(key_pair.public().check_proof_of_possession(&proof).is_ok());
+    }
+    let proof = key_pairs[0].proof_of_possession();
+    assert!(public_keys[1].check_proof_of_possession(&proof).is_err());
+
+    let value = TestString::new("hello");
+    let signatures = key_pairs
+        .iter()
+        .map(|key_pair| BlsSignature::new(&value, key_pair))
+        .collect::<Vec<_>>();
+    let aggregate = BlsSignature::aggregate(&signatures).unwrap();
+    assert!(aggregate.check_aggregate(&value, &public_keys).is_ok());
+    assert!(aggregate
+        .check_aggregate(&value, &public_keys[1..])
+        .is_err());
+    assert!(aggregate
+        .check_aggregate(&TestString::new("hellox"), &public_keys)
+        .is_err());
+    assert!(BlsSignature::aggregate(std::iter::empty()).is_err());
+}
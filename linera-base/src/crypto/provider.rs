@@ -0,0 +1,449 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pluggable implementations of the signature schemes.
+//!
+//! Signatures are computed and checked by the [`CryptoProvider`] installed in the process, so
+//! that an alternate implementation of Ed25519 (e.g. a certified module) can be selected
+//! without changing the code using [`Signature`](super::Signature). With the `bls` feature,
+//! the provider also handles the BLS12-381 signatures of [`BlsSignature`](super::bls::BlsSignature),
+//! which can be aggregated. Both schemes are deterministic: all providers must produce the
+//! same bytes, which [`check_test_vectors`] verifies against known answers.
+
+use std::sync::OnceLock;
+
+#[cfg(with_bls)]
+use blst::{min_pk as blst_bls, BLST_ERROR};
+use ed25519_dalek::{self as dalek, Signer, Verifier};
+
+use super::CryptoError;
+
+/// The length of a secret key, in bytes.
+pub const SECRET_KEY_LENGTH: usize = dalek::SECRET_KEY_LENGTH;
+
+/// The length of a public key, in bytes.
+pub const PUBLIC_KEY_LENGTH: usize = dalek::PUBLIC_KEY_LENGTH;
+
+/// The length of a signature, in bytes.
+pub const SIGNATURE_LENGTH: usize = dalek::SIGNATURE_LENGTH;
+
+/// The length of a BLS12-381 secret key, in bytes.
+#[cfg(with_bls)]
+pub const BLS_SECRET_KEY_LENGTH: usize = 32;
+
+/// The length of a compressed BLS12-381 public key, in bytes.
+#[cfg(with_bls)]
+pub const BLS_PUBLIC_KEY_LENGTH: usize = 48;
+
+/// The length of a compressed BLS12-381 signature, in bytes.
+#[cfg(with_bls)]
+pub const BLS_SIGNATURE_LENGTH: usize = 96;
+
+/// The domain separation tag of BLS12-381 signatures.
+#[cfg(with_bls)]
+const BLS_SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The domain separation tag of BLS12-381 proofs of possession.
+#[cfg(with_bls)]
+const BLS_PROOF_OF_POSSESSION_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// An implementation of Ed25519 signing and verification and, with the `bls` feature, of
+/// BLS12-381 signing, aggregation and verification.
+pub trait CryptoProvider: Send + Sync {
+    /// The name of the implementation.
+    fn name(&self) -> &'static str;
+
+    /// Signs the message with the secret key.
+    fn sign(&self, secret_key: &[u8; SECRET_KEY_LENGTH], message: &[u8]) -> [u8; SIGNATURE_LENGTH];
+
+    /// Checks the signature of the message by the public key.
+    fn verify(
+        &self,
+        public_key: &[u8; PUBLIC_KEY_LENGTH],
+        message: &[u8],
+        signature: &[u8; SIGNATURE_LENGTH],
+    ) -> Result<(), String>;
+
+    /// Checks the signatures of the same message by several public keys. Implementations
+    /// supporting batch verification should override this.
+    fn verify_batch(
+        &self,
+        message: &[u8],
+        votes: &[([u8; PUBLIC_KEY_LENGTH], [u8; SIGNATURE_LENGTH])],
+    ) -> Result<(), String> {
+        for (public_key, signature) in votes {
+            self.verify(public_key, message, signature)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the signatures of different messages, each by its public key. Implementations
+    /// supporting batch verification should override this.
+    fn verify_messages_batch(
+        &self,
+        items: &[(&[u8], [u8; PUBLIC_KEY_LENGTH], [u8; SIGNATURE_LENGTH])],
+    ) -> Result<(), String> {
+        for (message, public_key, signature) in items {
+            self.verify(public_key, message, signature)?;
+        }
+        Ok(())
+    }
+
+    /// Signs the message with the BLS12-381 secret key. The default implementation is based
+    /// on `blst`.
+    #[cfg(with_bls)]
+    fn bls_sign(
+        &self,
+        secret_key: &[u8; BLS_SECRET_KEY_LENGTH],
+        message: &[u8],
+    ) -> Result<[u8; BLS_SIGNATURE_LENGTH], String> {
+        blst_sign(secret_key, message, BLS_SIGNATURE_DST)
+    }
+
+    /// Combines BLS12-381 signatures of the same message into one. The default implementation
+    /// is based on `blst`.
+    #[cfg(with_bls)]
+    fn bls_aggregate(
+        &self,
+        signatures: &[[u8; BLS_SIGNATURE_LENGTH]],
+    ) -> Result<[u8; BLS_SIGNATURE_LENGTH], String> {
+        let signatures = signatures
+            .iter()
+            .map(blst_signature)
+            .collect::<Result<Vec<_>, _>>()?;
+        let signatures = signatures.iter().collect::<Vec<_>>();
+        let aggregate = blst_bls::AggregateSignature::aggregate(&signatures, true)
+            .map_err(|error| format!("{:?}", error))?;
+        Ok(aggregate.to_signature().compress())
+    }
+
+    /// Checks the aggregate of the BLS12-381 signatures of the message by all the public keys.
+    /// With a single public key, this checks a plain signature. The default implementation is
+    /// based on `blst`.
+    #[cfg(with_bls)]
+    fn bls_verify_aggregate(
+        &self,
+        public_keys: &[[u8; BLS_PUBLIC_KEY_LENGTH]],
+        message: &[u8],
+        signature: &[u8; BLS_SIGNATURE_LENGTH],
+    ) -> Result<(), String> {
+        let public_keys = public_keys
+            .iter()
+            .map(blst_public_key)
+            .collect::<Result<Vec<_>, _>>()?;
+        if public_keys.is_empty() {
+            return Err("no public keys for the aggregate signature".to_string());
+        }
+        let public_keys = public_keys.iter().collect::<Vec<_>>();
+        let signature = blst_signature(signature)?;
+        blst_check(signature.fast_aggregate_verify(true, message, BLS_SIGNATURE_DST, &public_keys))
+    }
+
+    /// Proves the possession of the BLS12-381 secret key, by signing its public key. The
+    /// default implementation is based on `blst`.
+    #[cfg(with_bls)]
+    fn bls_prove_possession(
+        &self,
+        secret_key: &[u8; BLS_SECRET_KEY_LENGTH],
+    ) -> Result<[u8; BLS_SIGNATURE_LENGTH], String> {
+        let public_key = blst_bls::SecretKey::from_bytes(secret_key)
+            .map_err(|error| format!("{:?}", error))?
+            .sk_to_pk()
+            .compress();
+        blst_sign(secret_key, &public_key, BLS_PROOF_OF_POSSESSION_DST)
+    }
+
+    /// Checks the proof that the owner of the BLS12-381 public key has its secret key. The
+    /// default implementation is based on `blst`.
+    #[cfg(with_bls)]
+    fn bls_check_possession(
+        &self,
+        public_key: &[u8; BLS_PUBLIC_KEY_LENGTH],
+        proof: &[u8; BLS_SIGNATURE_LENGTH],
+    ) -> Result<(), String> {
+        let point = blst_public_key(public_key)?;
+        let proof = blst_signature(proof)?;
+        blst_check(proof.verify(
+            true,
+            public_key,
+            BLS_PROOF_OF_POSSESSION_DST,
+            &[],
+            &point,
+            false,
+        ))
+    }
+}
+
+#[cfg(with_bls)]
+fn blst_check(result: BLST_ERROR) -> Result<(), String> {
+    match result {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        error => Err(format!("{:?}", error)),
+    }
+}
+
+#[cfg(with_bls)]
+fn blst_public_key(bytes: &[u8; BLS_PUBLIC_KEY_LENGTH]) -> Result<blst_bls::PublicKey, String> {
+    blst_bls::PublicKey::key_validate(bytes).map_err(|error| format!("{:?}", error))
+}
+
+#[cfg(with_bls)]
+fn blst_signature(bytes: &[u8; BLS_SIGNATURE_LENGTH]) -> Result<blst_bls::Signature, String> {
+    blst_bls::Signature::uncompress(bytes).map_err(|error| format!("{:?}", error))
+}
+
+#[cfg(with_bls)]
+fn blst_sign(
+    secret_key: &[u8; BLS_SECRET_KEY_LENGTH],
+    message: &[u8],
+    dst: &[u8],
+) -> Result<[u8; BLS_SIGNATURE_LENGTH], String> {
+    let secret_key =
+        blst_bls::SecretKey::from_bytes(secret_key).map_err(|error| format!("{:?}", error))?;
+    Ok(secret_key.sign(message, dst, &[]).compress())
+}
+
+/// The default provider, based on `ed25519-dalek` and, for BLS12-381, on `blst`.
+pub struct DalekProvider;
+
+impl CryptoProvider for DalekProvider {
+    fn name(&self) -> &'static str {
+        "ed25519-dalek"
+    }
+
+    fn sign(&self, secret_key: &[u8; SECRET_KEY_LENGTH], message: &[u8]) -> [u8; SIGNATURE_LENGTH] {
+        dalek::SigningKey::from_bytes(secret_key)
+            .sign(message)
+            .to_bytes()
+    }
+
+    fn verify(
+        &self,
+        public_key: &[u8; PUBLIC_KEY_LENGTH],
+        message: &[u8],
+        signature: &[u8; SIGNATURE_LENGTH],
+    ) -> Result<(), String> {
+        let public_key =
+            dalek::VerifyingKey::from_bytes(public_key).map_err(|error| error.to_string())?;
+        public_key
+            .verify(message, &dalek::Signature::from_bytes(signature))
+            .map_err(|error| error.to_string())
+    }
+
+    fn verify_batch(
+        &self,
+        message: &[u8],
+        votes: &[([u8; PUBLIC_KEY_LENGTH], [u8; SIGNATURE_LENGTH])],
+    ) -> Result<(), String> {
+        let mut public_keys = Vec::new();
+        let mut signatures = Vec::new();
+        for (public_key, signature) in votes {
+            public_keys.push(
+                dalek::VerifyingKey::from_bytes(public_key).map_err(|error| error.to_string())?,
+            );
+            signatures.push(dalek::Signature::from_bytes(signature));
+        }
+        let messages = vec![message; votes.len()];
+        dalek::verify_batch(&messages, &signatures, &public_keys)
+            .map_err(|error| format!("batched {}", error))
+    }
+
+    fn verify_messages_batch(
+        &self,
+        items: &[(&[u8], [u8; PUBLIC_KEY_LENGTH], [u8; SIGNATURE_LENGTH])],
+    ) -> Result<(), String> {
+        let mut messages = Vec::new();
+        let mut public_keys = Vec::new();
+        let mut signatures = Vec::new();
+        for (message, public_key, signature) in items {
+            messages.push(*message);
+            public_keys.push(
+                dalek::VerifyingKey::from_bytes(public_key).map_err(|error| error.to_string())?,
+            );
+            signatures.push(dalek::Signature::from_bytes(signature));
+        }
+        dalek::verify_batch(&messages, &signatures, &public_keys)
+            .map_err(|error| format!("batched {}", error))
+    }
+}
+
+static PROVIDER: OnceLock<&'static dyn CryptoProvider> = OnceLock::new();
+
+/// Installs the provider used by this process.
+///
+/// This must be called before the first signature is computed or checked, typically at the
+/// start of `main`. Otherwise, [`DalekProvider`] is used.
+pub fn install(provider: &'static dyn CryptoProvider) -> Result<(), CryptoError> {
+    PROVIDER
+        .set(provider)
+        .map_err(|_| CryptoError::ProviderAlreadyInstalled(self::provider().name()))
+}
+
+/// Returns the provider used by this process.
+pub fn provider() -> &'static dyn CryptoProvider {
+    *PROVIDER.get_or_init(|| &DalekProvider)
+}
+
+/// Known answers for Ed25519: secret key, public key, message and signature, in hexadecimal.
+#[cfg(with_testing)]
+const TEST_VECTORS: [(&str, &str, &str, &str); 4] = [
+    // Test 1 of RFC 8032, section 7.1.
+    (
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "",
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+    ),
+    // Test 2 of RFC 8032, section 7.1.
+    (
+        "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        "72",
+        "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+    ),
+    // Test 3 of RFC 8032, section 7.1.
+    (
+        "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+        "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+        "af82",
+        "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+    ),
+    // The message signed for `TestString::new("hello")`, with the key of test 1.
+    (
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "54657374537472696e673a3a0568656c6c6f",
+        "ca84990f9d720b5ff41011569866dd9a653007e8165f21c27effc34aa44526c1d19220e562c78cb5017885e9ca6a84b0bc000e15fae8e274240e5c7479b9140a",
+    ),
+];
+
+/// Known answers for BLS12-381: secret key, public key, message, signature and proof of
+/// possession, in hexadecimal.
+#[cfg(all(with_bls, with_testing))]
+const BLS_TEST_VECTORS: [(&str, &str, &str, &str, &str); 2] = [
+    // The key generated from the seed `[1; 32]`.
+    (
+        "144b27828e305a2d67fc7f4eea6de706b405cdd1ab8ad2daec046ccdeeec8b79",
+        "95a254501b7733239ed3cec4d56737977bd09ede881d8a234560e83e5525017add3b1dcc3eabfb85e12a4131b19c253b",
+        "",
+        "83c996d73bfeed7ffdbccb8eb9cf9eed53a9ce9fff8e217d627bbcf86a138ca895efadf8816f32daa0dea613e833a04b190ba3069bf05a0a2264e6b669474dfd75023deef1a3a00683f9ae342e206f287b8c461793916e2312faf146a7b22159",
+        "846aa12a4402eb67cb92a497e0716db573c817a4163783153f0ddca475f4870200049d8e9ed35087c786059c1f26fc9d0d39e3098f1bae074c062f84f24353210666bd58c0d9be3ff76ba9dd9ce905c5b602a12e78a04350275faacce8b7137d",
+    ),
+    // The message signed for `TestString::new("hello")`, with the key generated from the
+    // seed `[2; 32]`.
+    (
+        "1ff56eef5220c383a6522aa9a92776e3034bf1153839d54c9e3d2bcb6c04948e",
+        "ac80a5e08c712d5f08f0306ad743f7d8c215d982489b84a1d6ba805733d94c006e8938f9089a75db3ffa135af33bc69a",
+        "54657374537472696e673a3a0568656c6c6f",
+        "9058a7998278207ede42cde7648c68108569e0fbfa5770e86f152d390c6796bb82cfdbda7d8c1d42be43de930d9c840c035466e9f08748731d24a5ca2df5aba6e01e6b4f1ef684e4a6ea9a30d3ab26de3e44391cca02fce0e642a552274296a2",
+        "b1b22261eeb641b36d4f701f7e5635c5dd0ee53102e7ad8c11594be0d785f0bb5d75bd063ec2caa415e953f85e6e18e110d7ae595d18940e60894bd0a39eb157c1f646ee0f2079d64bd7f4e3c6cbc297e74ce69f3ae4e0728f915f1aac3cdf9b",
+    ),
+];
+
+#[cfg(with_testing)]
+fn decode<const N: usize>(hex_string: &str) -> [u8; N] {
+    hex::decode(hex_string)
+        .expect("test vectors should be hexadecimal")
+        .try_into()
+        .expect("test vectors should have the right length")
+}
+
+/// Checks that the provider produces and accepts the known signatures, and rejects altered
+/// ones. Alternate providers should be tested with this.
+#[cfg(with_testing)]
+pub fn check_test_vectors(provider: &dyn CryptoProvider) {
+    let mut votes = Vec::new();
+    let mut items = Vec::new();
+    for (secret_key, public_key, message, signature) in TEST_VECTORS {
+        let secret_key = decode(secret_key);
+        let public_key = decode(public_key);
+        let message = hex::decode(message).expect("test vectors should be hexadecimal");
+        let signature = decode(signature);
+        assert_eq!(provider.sign(&secret_key, &message), signature);
+        assert!(provider.verify(&public_key, &message, &signature).is_ok());
+        let mut altered_message = message.clone();
+        altered_message.push(0);
+        assert!(provider
+            .verify(&public_key, &altered_message, &signature)
+            .is_err());
+        let mut altered_signature = signature;
+        altered_signature[0] ^= 1;
+        assert!(provider
+            .verify(&public_key, &message, &altered_signature)
+            .is_err());
+        votes.push((public_key, provider.sign(&secret_key, b"batch")));
+        items.push((message, public_key, signature));
+    }
+    assert!(provider.verify_batch(b"batch", &votes).is_ok());
+    assert!(provider.verify_batch(b"other batch", &votes).is_err());
+    let mut batch = items
+        .iter()
+        .map(|(message, public_key, signature)| (&message[..], *public_key, *signature))
+        .collect::<Vec<_>>();
+    assert!(provider.verify_messages_batch(&batch).is_ok());
+    // Each signature must be checked with the key of its own message.
+    let first_key = batch[0].1;
+    batch[0].1 = batch[1].1;
+    batch[1].1 = first_key;
+    assert!(provider.verify_messages_batch(&batch).is_err());
+
+    #[cfg(with_bls)]
+    check_bls_test_vectors(provider);
+}
+
+#[cfg(all(with_bls, with_testing))]
+fn check_bls_test_vectors(provider: &dyn CryptoProvider) {
+    let mut public_keys = Vec::new();
+    let mut signatures = Vec::new();
+    for (secret_key, public_key, message, signature, proof) in BLS_TEST_VECTORS {
+        let secret_key = decode(secret_key);
+        let public_key = decode(public_key);
+        let message = hex::decode(message).expect("test vectors should be hexadecimal");
+        let signature = decode(signature);
+        let proof = decode(proof);
+        assert_eq!(provider.bls_sign(&secret_key, &message), Ok(signature));
+        assert!(provider
+            .bls_verify_aggregate(&[public_key], &message, &signature)
+            .is_ok());
+        let mut altered_message = message.clone();
+        altered_message.push(0);
+        assert!(provider
+            .bls_verify_aggregate(&[public_key], &altered_message, &signature)
+            .is_err());
+        let mut altered_signature = signature;
+        altered_signature[1] ^= 1;
+        assert!(provider
+            .bls_verify_aggregate(&[public_key], &message, &altered_signature)
+            .is_err());
+        assert_eq!(provider.bls_prove_possession(&secret_key), Ok(proof));
+        assert!(provider.bls_check_possession(&public_key, &proof).is_ok());
+        // A proof of possession is not a signature of the public key.
+        assert!(provider
+            .bls_check_possession(
+                &public_key,
+                &provider.bls_sign(&secret_key, &public_key).unwrap()
+            )
+            .is_err());
+        public_keys.push(public_key);
+        signatures.push(provider.bls_sign(&secret_key, b"aggregate").unwrap());
+    }
+    let aggregate = provider.bls_aggregate(&signatures).unwrap();
+    assert!(provider
+        .bls_verify_aggregate(&public_keys, b"aggregate", &aggregate)
+        .is_ok());
+    assert!(provider
+        .bls_verify_aggregate(&public_keys, b"other aggregate", &aggregate)
+        .is_err());
+    assert!(provider
+        .bls_verify_aggregate(&public_keys[1..], b"aggregate", &aggregate)
+        .is_err());
+    assert!(provider
+        .bls_verify_aggregate(&[], b"aggregate", &aggregate)
+        .is_err());
+    assert!(provider.bls_aggregate(&[]).is_err());
+}
+
+#[test]
+fn test_dalek_provider() {
+    check_test_vectors(&DalekProvider);
+}
@@ -15,7 +15,7 @@ use thiserror::Error;
 
 use crate::{
     doc_scalar,
-    identifiers::{ApplicationId, Destination, GenericApplicationId},
+    identifiers::{ApplicationId, BlobId, Destination, GenericApplicationId, Owner},
     time::{Duration, SystemTime},
 };
 
@@ -270,6 +270,8 @@ pub struct SendMessageRequest<Message> {
     pub is_tracked: bool,
     /// The grant resources forwarded with the message.
     pub grant: Resources,
+    /// The time after which the message can no longer be executed, if any.
+    pub expiration: Option<Timestamp>,
     /// The message itself.
     pub message: Message,
 }
@@ -287,6 +289,7 @@ where
             authenticated: self.authenticated,
             is_tracked: self.is_tracked,
             grant: self.grant,
+            expiration: self.expiration,
             message,
         }
     }
@@ -657,8 +660,16 @@ pub struct ApplicationPermissions {
     /// If it is `Some`, only operations from the specified applications are allowed, and
     /// no system operations.
     pub execute_operations: Option<Vec<ApplicationId>>,
+    /// At least one operation or incoming message from each of these applications must occur in
+    /// every block. The application can then approve a block, e.g. check the signatures of
+    /// a multisig or a spending limit, or reject it by failing.
+    pub mandatory_applications: Vec<ApplicationId>,
     /// These applications are allowed to close the current chain using the system API.
     pub close_chain: Vec<ApplicationId>,
+    /// If this is `None`, any signer of a block can create applications on this chain and
+    /// request applications from other chains. If it is `Some`, only the specified owners
+    /// can.
+    pub register_applications: Option<Vec<Owner>>,
 }
 
 impl ApplicationPermissions {
@@ -667,7 +678,9 @@ impl ApplicationPermissions {
     pub fn new_single(app_id: ApplicationId) -> Self {
         Self {
             execute_operations: Some(vec![app_id]),
+            mandatory_applications: vec![],
             close_chain: vec![app_id],
+            register_applications: None,
         }
     }
 
@@ -684,6 +697,16 @@ impl ApplicationPermissions {
     pub fn can_close_chain(&self, app_id: &ApplicationId) -> bool {
         self.close_chain.contains(app_id)
     }
+
+    /// Returns whether the given signer of a block is allowed to register new applications
+    /// on this chain.
+    pub fn can_register_applications(&self, signer: Option<&Owner>) -> bool {
+        match (signer, &self.register_applications) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(owner), Some(owners)) => owners.contains(owner),
+        }
+    }
 }
 
 /// A record of oracle responses from the execution of a transaction.
@@ -700,6 +723,8 @@ pub enum OracleResponse {
     Service(Vec<u8>),
     /// The JSON response from an HTTP GET request.
     Json(String),
+    /// The ID of a blob that was read. Its content is found in the storage.
+    Blob(BlobId),
 }
 
 impl fmt::Display for OracleResponse {
@@ -709,6 +734,7 @@ impl fmt::Display for OracleResponse {
                 write!(f, "Service:{}", STANDARD_NO_PAD.encode(bytes))?
             }
             OracleResponse::Json(json) => write!(f, "Json:\"{}\"", json)?,
+            OracleResponse::Blob(blob_id) => write!(f, "Blob:{}", blob_id)?,
         };
 
         Ok(())
@@ -727,10 +753,69 @@ impl std::str::FromStr for OracleResponse {
         if let Some(json) = s.strip_prefix("Json:\"").and_then(|s| s.strip_suffix('"')) {
             return Ok(OracleResponse::Json(json.to_string()));
         }
+        if let Some(string) = s.strip_prefix("Blob:") {
+            return Ok(OracleResponse::Blob(
+                string.parse::<BlobId>().context("Invalid blob ID")?,
+            ));
+        }
         Err(anyhow::anyhow!("Invalid enum! Enum: {}", s))
     }
 }
 
+/// A version of the Linera software, e.g. the minimum version that the validators of a network
+/// must run.
+#[derive(
+    Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash, Default, Debug, Serialize, Deserialize,
+)]
+pub struct SoftwareVersion {
+    /// The major version number.
+    pub major: u32,
+    /// The minor version number.
+    pub minor: u32,
+    /// The patch version number.
+    pub patch: u32,
+}
+
+impl SoftwareVersion {
+    /// Returns the version of this build of the Linera crates.
+    pub fn current() -> Self {
+        let parse = |number: &str| number.parse().expect("Cargo versions are numbers");
+        SoftwareVersion {
+            major: parse(env!("CARGO_PKG_VERSION_MAJOR")),
+            minor: parse(env!("CARGO_PKG_VERSION_MINOR")),
+            patch: parse(env!("CARGO_PKG_VERSION_PATCH")),
+        }
+    }
+}
+
+impl fmt::Display for SoftwareVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl std::str::FromStr for SoftwareVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut numbers = s.trim().split('.');
+        let mut next = |name: &str| -> Result<u32, Self::Err> {
+            numbers
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Missing {name} version number in {s:?}"))?
+                .parse()
+                .with_context(|| format!("Invalid {name} version number in {s:?}"))
+        };
+        let version = SoftwareVersion {
+            major: next("major")?,
+            minor: next("minor")?,
+            patch: next("patch")?,
+        };
+        anyhow::ensure!(numbers.next().is_none(), "Invalid version {s:?}");
+        Ok(version)
+    }
+}
+
 doc_scalar!(Amount, "A non-negative amount of tokens.");
 doc_scalar!(BlockHeight, "A block height to identify blocks in a chain");
 doc_scalar!(
@@ -747,7 +832,7 @@ doc_scalar!(OracleResponse, "A record of a single oracle response.");
 mod tests {
     use std::str::FromStr;
 
-    use super::Amount;
+    use super::{Amount, SoftwareVersion};
 
     #[test]
     fn display_amount() {
@@ -774,4 +859,22 @@ mod tests {
             format!("{:~^+9.1}", Amount::from_str("12.34").unwrap())
         );
     }
+
+    #[test]
+    fn software_version_from_str() {
+        let version = SoftwareVersion::from_str("0.11.2").unwrap();
+        assert_eq!(
+            version,
+            SoftwareVersion {
+                major: 0,
+                minor: 11,
+                patch: 2
+            }
+        );
+        assert_eq!(version.to_string(), "0.11.2");
+        assert!(SoftwareVersion::from_str("0.11").is_err());
+        assert!(SoftwareVersion::from_str("0.11.2.1").is_err());
+        assert!(SoftwareVersion::from_str("0.x.2").is_err());
+        assert!(version < SoftwareVersion::from_str("0.12.0").unwrap());
+    }
 }
@@ -142,18 +142,55 @@ impl ChainDescription {
 #[cfg_attr(with_testing, derive(Default))]
 pub struct ChainId(pub CryptoHash);
 
-/// A blob ID.
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
-#[cfg_attr(with_testing, derive(Default))]
+/// A blob ID, i.e. the hash of the content of a blob.
+#[derive(
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Clone,
+    Copy,
+    Hash,
+    Debug,
+    Serialize,
+    Deserialize,
+    WitLoad,
+    WitStore,
+    WitType,
+)]
+#[cfg_attr(with_testing, derive(test_strategy::Arbitrary, Default))]
 pub struct BlobId(pub CryptoHash);
 
-/// A blob.
-#[derive(Serialize, Deserialize)]
+/// A blob of data, e.g. the media of an NFT, stored outside of the blocks and referenced
+/// by its [`BlobId`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Blob {
     #[serde(with = "serde_bytes")]
     bytes: Vec<u8>,
 }
 
+impl Blob {
+    /// Creates a blob with the given content.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Blob { bytes }
+    }
+
+    /// Returns the content of the blob.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the content of the blob, consuming it.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the ID of the blob, i.e. the hash of its content.
+    pub fn id(&self) -> BlobId {
+        BlobId(CryptoHash::new(self))
+    }
+}
+
 impl BcsSignable for Blob {}
 
 /// The index of a message in a chain.
@@ -740,6 +777,20 @@ impl FromStr for ChainId {
     }
 }
 
+impl Display for BlobId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for BlobId {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(BlobId(CryptoHash::from_str(s)?))
+    }
+}
+
 impl TryFrom<&[u8]> for ChainId {
     type Error = CryptoError;
 
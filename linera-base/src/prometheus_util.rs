@@ -6,8 +6,8 @@
 use std::time::Instant;
 
 use prometheus::{
-    histogram_opts, register_histogram_vec, register_int_counter_vec, Error, HistogramVec,
-    IntCounterVec, Opts,
+    histogram_opts, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec,
+    Error, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
 };
 
 const LINERA_NAMESPACE: &str = "linera";
@@ -22,6 +22,16 @@ pub fn register_int_counter_vec(
     register_int_counter_vec!(counter_opts, label_names)
 }
 
+/// Wrapper arount prometheus register_int_gauge_vec! macro which also sets the linera namespace
+pub fn register_int_gauge_vec(
+    name: &str,
+    description: &str,
+    label_names: &[&str],
+) -> Result<IntGaugeVec, Error> {
+    let gauge_opts = Opts::new(name, description).namespace(LINERA_NAMESPACE);
+    register_int_gauge_vec!(gauge_opts, label_names)
+}
+
 /// Wrapper arount prometheus register_histogram_vec! macro which also sets the linera namespace
 pub fn register_histogram_vec(
     name: &str,
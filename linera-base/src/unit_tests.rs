@@ -5,10 +5,12 @@
 
 use std::{collections::BTreeMap, fmt::Debug};
 
+use futures::FutureExt as _;
 use linera_witty::{Layout, WitLoad, WitStore};
 use test_case::test_case;
 
 use crate::{
+    clock::{Clock as _, TestClock},
     crypto::{CryptoHash, PublicKey},
     data_types::{Amount, BlockHeight, Resources, SendMessageRequest, TimeDelta, Timestamp},
     identifiers::{
@@ -75,6 +77,7 @@ fn send_message_request_test_case() -> SendMessageRequest<Vec<u8>> {
             write_operations: 0,
             storage_size_delta: 0,
         },
+        expiration: Some(Timestamp::from(1_000)),
         message: (0..=255).cycle().take(2_000).collect(),
     }
 }
@@ -170,3 +173,26 @@ fn chain_ownership_test_case() -> ChainOwnership {
         },
     }
 }
+
+/// Tests that the sleeps on a [`TestClock`] only end once its time is set past their end.
+#[test]
+fn test_test_clock_sleeps() {
+    let clock = TestClock::new();
+    assert_eq!(clock.current_time(), Timestamp::from(0));
+
+    let mut sleep = clock.sleep(TimeDelta::from_secs(10)).boxed();
+    let mut sleep_until = clock.sleep_until(Timestamp::from(20_000_000)).boxed();
+    assert!((&mut sleep).now_or_never().is_none());
+
+    clock.add(TimeDelta::from_secs(10));
+    assert_eq!(clock.current_time(), Timestamp::from(10_000_000));
+    assert!(sleep.now_or_never().is_some());
+    assert!((&mut sleep_until).now_or_never().is_none());
+
+    clock.set(Timestamp::from(30_000_000));
+    assert!(sleep_until.now_or_never().is_some());
+    assert!(clock
+        .sleep_until(Timestamp::from(0))
+        .now_or_never()
+        .is_some());
+}
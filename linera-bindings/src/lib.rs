@@ -0,0 +1,54 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module exposes the client operations of Linera to JavaScript and TypeScript, compiled
+//! to WebAssembly with `wasm-bindgen`.
+//!
+//! Wallets written in other languages can generate keys, sign block proposals, verify
+//! certificates and talk to validators without reimplementing BCS and the signing scheme:
+//! - A [`Signer`] holds a key pair and signs block proposals.
+//! - A [`Verifier`] checks certificates against the trusted committees of a light client.
+//! - A [`Validator`] sends queries, proposals and certificates to a validator, over gRPC-Web.
+//!
+//! Structured values, e.g. blocks and chain information, are exchanged as plain JavaScript
+//! objects in the JSON format of the Rust types. Signed values, i.e. proposals and
+//! certificates, are exchanged as their BCS bytes, so that they are forwarded without
+//! changes.
+
+mod signer;
+mod validator;
+mod verifier;
+
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::prelude::*;
+
+pub use crate::{signer::Signer, validator::Validator, verifier::Verifier};
+
+/// Converts a JavaScript object into a value of a Rust type.
+fn from_js<T: DeserializeOwned>(value: JsValue) -> Result<T, JsError> {
+    serde_wasm_bindgen::from_value(value).map_err(|error| JsError::new(&error.to_string()))
+}
+
+/// Converts a value of a Rust type into a JavaScript object.
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|error| JsError::new(&error.to_string()))
+}
+
+/// Decodes the BCS bytes of a value of a Rust type.
+fn from_bcs<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, JsError> {
+    bcs::from_bytes(bytes).map_err(JsError::from)
+}
+
+/// Decodes the BCS bytes of a certificate, e.g. to display it.
+#[wasm_bindgen(js_name = decodeCertificate)]
+pub fn decode_certificate(bytes: &[u8]) -> Result<JsValue, JsError> {
+    to_js(&from_bcs::<linera_chain::data_types::Certificate>(bytes)?)
+}
+
+/// Decodes the BCS bytes of a block proposal, e.g. to display it.
+#[wasm_bindgen(js_name = decodeProposal)]
+pub fn decode_proposal(bytes: &[u8]) -> Result<JsValue, JsError> {
+    to_js(&from_bcs::<linera_chain::data_types::BlockProposal>(bytes)?)
+}
@@ -0,0 +1,64 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::{CryptoRng, KeyPair},
+    identifiers::Owner,
+};
+use linera_chain::data_types::{BlockAndRound, BlockProposal};
+use wasm_bindgen::prelude::*;
+
+use crate::{from_js, to_js};
+
+/// A key pair, signing the block proposals of the chains it owns.
+#[wasm_bindgen]
+pub struct Signer {
+    key_pair: KeyPair,
+}
+
+#[wasm_bindgen]
+impl Signer {
+    /// Generates a new key pair from the random number generator of the platform.
+    #[wasm_bindgen(constructor)]
+    pub fn generate() -> Signer {
+        let mut rng = Box::<dyn CryptoRng>::from(None);
+        Signer {
+            key_pair: KeyPair::generate_from(&mut rng),
+        }
+    }
+
+    /// Restores a key pair from its secret key, in hexadecimal.
+    #[wasm_bindgen(js_name = fromSecretKey)]
+    pub fn from_secret_key(secret_key: &str) -> Result<Signer, JsError> {
+        let key_pair = from_js(JsValue::from_str(secret_key))?;
+        Ok(Signer { key_pair })
+    }
+
+    /// Returns the secret key, in hexadecimal, to be stored securely.
+    #[wasm_bindgen(js_name = secretKey)]
+    pub fn secret_key(&self) -> Result<String, JsError> {
+        to_js(&self.key_pair)?
+            .as_string()
+            .ok_or_else(|| JsError::new("the secret key is not serialized as a string"))
+    }
+
+    /// Returns the public key, in hexadecimal.
+    #[wasm_bindgen(js_name = publicKey)]
+    pub fn public_key(&self) -> String {
+        self.key_pair.public().to_string()
+    }
+
+    /// Returns the owner of the chains controlled by this key pair, in hexadecimal.
+    pub fn owner(&self) -> String {
+        Owner::from(self.key_pair.public()).to_string()
+    }
+
+    /// Signs the proposal of a block in a round, given as an object with the fields `block`
+    /// and `round`, and returns the BCS bytes of the signed proposal.
+    #[wasm_bindgen(js_name = signProposal)]
+    pub fn sign_proposal(&self, content: JsValue) -> Result<Vec<u8>, JsError> {
+        let content = from_js::<BlockAndRound>(content)?;
+        let proposal = BlockProposal::new(content, &self.key_pair, vec![], vec![], None);
+        bcs::to_bytes(&proposal).map_err(JsError::from)
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::str::FromStr as _;
+
+use linera_chain::data_types::{BlockProposal, Certificate};
+use linera_core::{
+    data_types::{ChainInfoQuery, ChainInfoResponse},
+    node::{CrossChainMessageDelivery, LocalValidatorNode as _},
+};
+use linera_execution::committee::ValidatorName;
+use linera_rpc::{
+    config::{RetryPolicy, TimeoutPolicy, ValidatorPublicNetworkConfig},
+    grpc::GrpcClient,
+    node_provider::NodeOptions,
+};
+use wasm_bindgen::prelude::*;
+
+use crate::{from_bcs, from_js, to_js};
+
+/// A connection to a validator, through the gRPC-Web endpoint of its proxy.
+#[wasm_bindgen]
+pub struct Validator {
+    client: GrpcClient,
+    /// The name of the validator, to check the signatures of its answers, if known.
+    name: Option<ValidatorName>,
+}
+
+#[wasm_bindgen]
+impl Validator {
+    /// Connects to the validator at `address`, e.g. `grpcs:validator.example.com:443`.
+    ///
+    /// If the `name` of the validator is given, i.e. its public key in hexadecimal, the
+    /// answers that are not signed by the validator are rejected.
+    #[wasm_bindgen(constructor)]
+    pub fn new(address: &str, name: Option<String>) -> Result<Validator, JsError> {
+        let network = ValidatorPublicNetworkConfig::from_str(address)
+            .map_err(|error| JsError::new(&error.to_string()))?;
+        let options = NodeOptions {
+            timeout: TimeoutPolicy::default(),
+            notification_retry: RetryPolicy::NOTIFICATIONS,
+            direct_to_shards: false,
+        };
+        let client = GrpcClient::new(network, options)?;
+        let name = name.as_deref().map(ValidatorName::from_str).transpose()?;
+        Ok(Validator { client, name })
+    }
+
+    /// Queries the validator about a chain, with an object in the format of a
+    /// `ChainInfoQuery`, and returns the `ChainInfo` of the answer.
+    #[wasm_bindgen(js_name = chainInfo)]
+    pub async fn chain_info(&self, query: JsValue) -> Result<JsValue, JsError> {
+        let query = from_js::<ChainInfoQuery>(query)?;
+        let response = self.client.clone().handle_chain_info_query(query).await?;
+        self.info(response)
+    }
+
+    /// Submits a signed block proposal, given as BCS bytes, and returns the `ChainInfo` of
+    /// the answer.
+    #[wasm_bindgen(js_name = submitProposal)]
+    pub async fn submit_proposal(&self, proposal: Vec<u8>) -> Result<JsValue, JsError> {
+        let proposal = from_bcs::<BlockProposal>(&proposal)?;
+        let response = self.client.clone().handle_block_proposal(proposal).await?;
+        self.info(response)
+    }
+
+    /// Submits a certificate, given as BCS bytes, and returns the `ChainInfo` of the answer.
+    #[wasm_bindgen(js_name = submitCertificate)]
+    pub async fn submit_certificate(&self, certificate: Vec<u8>) -> Result<JsValue, JsError> {
+        let certificate = from_bcs::<Certificate>(&certificate)?;
+        let response = self
+            .client
+            .clone()
+            .handle_certificate(certificate, vec![], CrossChainMessageDelivery::NonBlocking)
+            .await?;
+        self.info(response)
+    }
+
+    /// Returns the hash of the genesis configuration of the validator's network.
+    #[wasm_bindgen(js_name = genesisConfigHash)]
+    pub async fn genesis_config_hash(&self) -> Result<String, JsError> {
+        let hash = self.client.clone().get_genesis_config_hash().await?;
+        Ok(hash.to_string())
+    }
+}
+
+impl Validator {
+    /// Checks the signature of the answer, if the validator is known, and returns its
+    /// chain information.
+    fn info(&self, response: ChainInfoResponse) -> Result<JsValue, JsError> {
+        if let Some(name) = self.name {
+            response.check(name)?;
+        }
+        to_js(&response.info)
+    }
+}
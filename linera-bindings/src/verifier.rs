@@ -0,0 +1,79 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::str::FromStr as _;
+
+use linera_base::identifiers::ChainId;
+use linera_chain::data_types::Certificate;
+use linera_execution::committee::Epoch;
+use linera_light_client::LightClient;
+use wasm_bindgen::prelude::*;
+
+use crate::{from_bcs, from_js, to_js};
+
+/// The trusted committees and the tracked chains of a light client, checking the
+/// certificates received from validators.
+#[wasm_bindgen]
+pub struct Verifier {
+    light_client: LightClient,
+}
+
+#[wasm_bindgen]
+impl Verifier {
+    /// Creates a verifier trusting the given committee of the given epoch, e.g. from the
+    /// genesis configuration of the network.
+    #[wasm_bindgen(constructor)]
+    pub fn new(admin_id: &str, epoch: u32, committee: JsValue) -> Result<Verifier, JsError> {
+        let admin_id = ChainId::from_str(admin_id)?;
+        let committee = from_js(committee)?;
+        Ok(Verifier {
+            light_client: LightClient::new(admin_id, Epoch::from(epoch), committee),
+        })
+    }
+
+    /// Restores a verifier from the object returned by [`Verifier::state`].
+    #[wasm_bindgen(js_name = fromState)]
+    pub fn from_state(state: JsValue) -> Result<Verifier, JsError> {
+        Ok(Verifier {
+            light_client: from_js(state)?,
+        })
+    }
+
+    /// Returns the state of the verifier, to be persisted between sessions.
+    pub fn state(&self) -> Result<JsValue, JsError> {
+        to_js(&self.light_client)
+    }
+
+    /// Starts tracking a chain from its first block.
+    #[wasm_bindgen(js_name = trackChain)]
+    pub fn track_chain(&mut self, chain_id: &str) -> Result<(), JsError> {
+        self.light_client.track_chain(ChainId::from_str(chain_id)?);
+        Ok(())
+    }
+
+    /// Returns the height and hash of the latest verified block of a chain, or `undefined`
+    /// if none was verified yet.
+    #[wasm_bindgen(js_name = chainTip)]
+    pub fn chain_tip(&self, chain_id: &str) -> Result<JsValue, JsError> {
+        let tip = self.light_client.chain_tip(ChainId::from_str(chain_id)?);
+        match tip {
+            Some(tip) => to_js(&tip),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
+
+    /// Checks that the BCS bytes are a certificate for a confirmed block, signed by a quorum
+    /// of a trusted committee, and returns the executed block.
+    #[wasm_bindgen(js_name = checkCertificate)]
+    pub fn check_certificate(&self, certificate: &[u8]) -> Result<JsValue, JsError> {
+        let certificate = from_bcs::<Certificate>(certificate)?;
+        to_js(self.light_client.check_certificate(&certificate)?)
+    }
+
+    /// Verifies the certificate of the next block of a tracked chain, given as BCS bytes,
+    /// and returns the new latest verified block of the chain.
+    pub fn follow(&mut self, certificate: &[u8]) -> Result<JsValue, JsError> {
+        let certificate = from_bcs::<Certificate>(certificate)?;
+        to_js(&self.light_client.follow(&certificate)?)
+    }
+}
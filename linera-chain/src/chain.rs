@@ -15,9 +15,10 @@ use linera_base::{
     identifiers::{ChainId, Destination, GenericApplicationId, MessageId},
 };
 use linera_execution::{
-    system::SystemMessage, ExecutionOutcome, ExecutionRuntimeContext, ExecutionStateView, Message,
-    MessageContext, OperationContext, Query, QueryContext, RawExecutionOutcome, RawOutgoingMessage,
-    ResourceController, ResourceTracker, Response, UserApplicationDescription, UserApplicationId,
+    committee::ValidatorName, system::SystemMessage, ExecutionOutcome, ExecutionRuntimeContext,
+    ExecutionStateView, Message, MessageContext, Operation, OperationContext, Query, QueryContext,
+    RawExecutionOutcome, RawOutgoingMessage, ResourceController, ResourceTracker, Response,
+    SystemOperation, UserApplicationDescription, UserApplicationId,
 };
 use linera_views::{
     common::Context,
@@ -32,8 +33,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     data_types::{
-        Block, BlockExecutionOutcome, ChainAndHeight, ChannelFullName, Event, IncomingMessage,
-        MessageAction, MessageBundle, Origin, OutgoingMessage, Target,
+        ApplicationEvent, Block, BlockExecutionOutcome, ChainAndHeight, ChannelFullName,
+        EquivocationEvidence, Event, IncomingMessage, MessageAction, MessageBundle, Origin,
+        OutgoingMessage, Target, TransactionOutcome,
     },
     inbox::{Cursor, InboxError, InboxStateView},
     manager::ChainManager,
@@ -64,6 +66,19 @@ static NUM_BLOCKS_EXECUTED: Lazy<IntCounterVec> = Lazy::new(|| {
     .expect("Counter creation should not fail")
 });
 
+#[cfg(with_metrics)]
+static INBOX_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "inbox_size",
+        "Number of events waiting in an inbox, after receiving new messages",
+        &[],
+        Some(vec![
+            0.0, 1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 1000.0, 10000.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
 #[cfg(with_metrics)]
 static BLOCK_EXECUTION_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     prometheus_util::register_histogram_vec(
@@ -264,6 +279,14 @@ where
     pub outbox_counters: RegisterView<C, BTreeMap<BlockHeight, u32>>,
     /// Channels able to multicast messages to subscribers.
     pub channels: ReentrantCollectionView<C, ChannelFullName, ChannelStateView<C>>,
+    /// Hashes of the certificates of all blocks received from other chains, in the order they
+    /// were first received (local ordering), so that clients can fetch them from a cursor.
+    pub received_certificates: LogView<C, CryptoHash>,
+    /// The outcomes of the transactions of all confirmed blocks, in order, so that consumers
+    /// can read each of them exactly once from a sequence number. This is served by the
+    /// validators' chain information queries rather than GraphQL.
+    #[graphql(skip)]
+    pub event_log: LogView<C, TransactionOutcome>,
 }
 
 /// Block-chaining state.
@@ -538,6 +561,7 @@ where
                 grant,
                 refund_grant_to,
                 kind,
+                expiration,
                 message,
             } = outgoing_message;
             // See if the chain needs initialization.
@@ -560,6 +584,7 @@ where
                 grant,
                 refund_grant_to,
                 kind,
+                expiration,
                 timestamp: bundle.timestamp,
                 message,
             });
@@ -578,24 +603,38 @@ where
         let mut inbox = self.inboxes.try_load_entry_mut(origin).await?;
         for event in events {
             let entry = InboxEntry::new(origin.clone(), &event);
-            let skippable = event.is_skippable();
+            let must_be_handled = event.must_be_handled();
             let newly_added = inbox.add_event(event).await.map_err(|error| match error {
                 InboxError::ViewError(error) => ChainError::ViewError(error),
                 error => ChainError::InternalError(format!(
                     "while processing messages in certified block: {error}"
                 )),
             })?;
-            if newly_added && !skippable {
+            if newly_added && must_be_handled {
                 let seen = local_time;
                 self.unskippable
                     .push_back(TimestampedInboxEntry { entry, seen });
             }
         }
+        #[cfg(with_metrics)]
+        INBOX_SIZE
+            .with_label_values(&[])
+            .observe(inbox.added_events.count() as f64);
         // Remember the certificate for future validator/client synchronizations.
         self.received_log.push(ChainAndHeight {
             chain_id: origin.sender,
             height: bundle.height,
         });
+        // A block can send messages to several origins of this chain, e.g. directly and
+        // through a channel, but its certificate is only listed once.
+        let count = self.received_certificates.count();
+        let last_hash = match count.checked_sub(1) {
+            Some(index) => self.received_certificates.get(index).await?,
+            None => None,
+        };
+        if last_hash != Some(bundle.hash) {
+            self.received_certificates.push(bundle.hash);
+        }
         Ok(())
     }
 
@@ -631,7 +670,12 @@ where
     pub async fn remove_events_from_inboxes(&mut self, block: &Block) -> Result<(), ChainError> {
         let chain_id = self.chain_id();
         let mut events_by_origin: BTreeMap<_, Vec<&Event>> = Default::default();
-        for IncomingMessage { event, origin, .. } in &block.incoming_messages {
+        for IncomingMessage {
+            event,
+            origin,
+            action,
+        } in &block.incoming_messages
+        {
             ensure!(
                 event.timestamp <= block.timestamp,
                 ChainError::IncorrectEventTimestamp {
@@ -640,6 +684,14 @@ where
                     block_timestamp: block.timestamp,
                 }
             );
+            ensure!(
+                *action == MessageAction::Reject || !event.is_expired(block.timestamp),
+                ChainError::ExpiredMessage {
+                    chain_id,
+                    origin: Box::new(origin.clone()),
+                    event: event.clone(),
+                }
+            );
             let events = events_by_origin.entry(origin).or_default();
             events.push(event);
         }
@@ -654,7 +706,7 @@ where
                     .remove_event(event)
                     .await
                     .map_err(|error| ChainError::from((chain_id, origin.clone(), error)))?;
-                if was_present && !event.is_skippable() {
+                if was_present && event.must_be_handled() {
                     removed_unskippable.insert(InboxEntry::new(origin.clone(), event));
                 }
             }
@@ -681,6 +733,32 @@ where
         Ok(())
     }
 
+    /// Verifies that `evidence` is a serialized [`EquivocationEvidence`] against `validator`,
+    /// who must be a member of the committee of the conflicting blocks' epoch.
+    fn check_equivocation_evidence(
+        &self,
+        validator: ValidatorName,
+        evidence: &[u8],
+    ) -> Result<(), ChainError> {
+        let evidence = bcs::from_bytes::<EquivocationEvidence>(evidence)
+            .map_err(|_| ChainError::InvalidEquivocationEvidence)?;
+        ensure!(
+            evidence.validator() == validator,
+            ChainError::InvalidEquivocationEvidence
+        );
+        evidence.check()?;
+        let committees = self.execution_state.system.committees.get();
+        let committee = evidence
+            .epoch()
+            .and_then(|epoch| committees.get(&epoch))
+            .ok_or(ChainError::InvalidEquivocationEvidence)?;
+        ensure!(
+            committee.weight(&validator) > 0,
+            ChainError::InvalidEquivocationEvidence
+        );
+        Ok(())
+    }
+
     /// Executes a block: first the incoming messages, then the main operation.
     /// * Modifies the state of inboxes, outboxes, and channels, if needed.
     /// * As usual, in case of errors, `self` may not be consistent any more and should be thrown
@@ -705,6 +783,7 @@ where
         let Some((_, committee)) = self.execution_state.system.current_committee() else {
             return Err(ChainError::InactiveChain(chain_id));
         };
+        block.check_limits(committee.policy().block_limits())?;
         let mut resource_controller = ResourceController {
             policy: Arc::new(committee.policy().clone()),
             tracker: ResourceTracker::default(),
@@ -712,6 +791,7 @@ where
         };
         let mut messages = Vec::new();
         let mut message_counts = Vec::new();
+        let mut events = Vec::new();
 
         if self.is_closed() {
             ensure!(
@@ -719,6 +799,10 @@ where
                 ChainError::ClosedChain
             );
         }
+        // The ephemeral keys of the applications expire before anything else is executed.
+        self.execution_state
+            .delete_expired_keys(block.height)
+            .await?;
 
         // The first incoming message of any child chain must be `OpenChain`. A root chain must
         // already be initialized
@@ -745,6 +829,18 @@ where
                 ChainError::InactiveChain(self.chain_id())
             );
         }
+        // Each of the mandatory applications must approve the block, by executing at least one
+        // of its operations or incoming messages. Closed chains only reject messages.
+        let mut mandatory = if self.is_closed() {
+            HashSet::new()
+        } else {
+            let app_permissions = self.execution_state.system.application_permissions.get();
+            app_permissions
+                .mandatory_applications
+                .iter()
+                .cloned()
+                .collect::<HashSet<_>>()
+        };
         let mut oracle_records = oracle_records.map(Vec::into_iter);
         let mut new_oracle_records = Vec::new();
         for (index, message) in block.incoming_messages.iter().enumerate() {
@@ -771,6 +867,11 @@ where
             };
             let outcomes = match message.action {
                 MessageAction::Accept => {
+                    if let GenericApplicationId::User(app_id) =
+                        message.event.message.application_id()
+                    {
+                        mandatory.remove(&app_id);
+                    }
                     let mut grant = message.event.grant;
                     let (mut outcomes, oracle_record) = self
                         .execution_state
@@ -855,7 +956,7 @@ where
                 }
             };
             let mut messages_out = self
-                .process_execution_outcomes(context.height, outcomes)
+                .process_execution_outcomes(context.height, outcomes, index, &mut events)
                 .await?;
             if let MessageAction::Accept = message.action {
                 for message_out in &messages_out {
@@ -879,6 +980,16 @@ where
                     app_permissions.execute_operations.clone().unwrap()
                 )
             );
+            if let Operation::System(SystemOperation::ReportEquivocation {
+                validator,
+                evidence,
+            }) = operation
+            {
+                self.check_equivocation_evidence(*validator, evidence)?;
+            }
+            if let GenericApplicationId::User(app_id) = operation.application_id() {
+                mandatory.remove(&app_id);
+            }
             #[cfg(with_metrics)]
             let _operation_latency = OPERATION_EXECUTION_LATENCY.measure_latency();
             let index = u32::try_from(index).map_err(|_| ArithmeticError::Overflow)?;
@@ -888,6 +999,7 @@ where
             let context = OperationContext {
                 chain_id,
                 height: block.height,
+                previous_block_hash: block.previous_block_hash,
                 index: Some(index),
                 authenticated_signer: block.authenticated_signer,
                 authenticated_caller_id: None,
@@ -911,8 +1023,17 @@ where
                 .await
                 .map_err(|err| ChainError::ExecutionError(err, chain_execution_context))?;
             new_oracle_records.push(oracle_record);
+            let transaction_index = u32::try_from(block.incoming_messages.len())
+                .ok()
+                .and_then(|count| count.checked_add(index))
+                .ok_or(ArithmeticError::Overflow)?;
             let mut messages_out = self
-                .process_execution_outcomes(context.height, outcomes)
+                .process_execution_outcomes(
+                    context.height,
+                    outcomes,
+                    transaction_index,
+                    &mut events,
+                )
                 .await?;
             resource_controller
                 .with_state(&mut self.execution_state)
@@ -931,6 +1052,11 @@ where
                 .push(u32::try_from(messages.len()).map_err(|_| ArithmeticError::Overflow)?);
         }
 
+        ensure!(
+            mandatory.is_empty(),
+            ChainError::MissingMandatoryApplications(mandatory.into_iter().collect())
+        );
+
         // Finally, charge for the block fee, except if the chain is closed. Closed chains should
         // always be able to reject incoming messages.
         if !self.is_closed() {
@@ -940,6 +1066,15 @@ where
                 .track_block()
                 .map_err(|err| ChainError::ExecutionError(err, ChainExecutionContext::Block))?;
         }
+        let fees = resource_controller.tracker.fees;
+        if fees > Amount::ZERO {
+            self.execution_state
+                .system
+                .collected_fees
+                .get_mut_or_default(&block.epoch)
+                .await?
+                .try_add_assign(fees)?;
+        }
 
         // Recompute the state hash.
         let state_hash = {
@@ -984,13 +1119,19 @@ where
             message_counts,
             state_hash,
             oracle_records: new_oracle_records,
+            fuel_used: resource_controller.tracker.fuel,
+            events,
         })
     }
 
+    /// Processes the outcomes of the transaction with the given index, returning its outgoing
+    /// messages and adding its events to `events`.
     async fn process_execution_outcomes(
         &mut self,
         height: BlockHeight,
         results: Vec<ExecutionOutcome>,
+        transaction_index: u32,
+        events: &mut Vec<ApplicationEvent>,
     ) -> Result<Vec<OutgoingMessage>, ChainError> {
         let mut messages = Vec::new();
         for result in results {
@@ -1005,7 +1146,18 @@ where
                     )
                     .await?;
                 }
-                ExecutionOutcome::User(application_id, result) => {
+                ExecutionOutcome::User(application_id, mut result) => {
+                    events.extend(
+                        result
+                            .events
+                            .drain(..)
+                            .map(|(key, value)| ApplicationEvent {
+                                transaction_index,
+                                application_id,
+                                key,
+                                value,
+                            }),
+                    );
                     self.process_raw_execution_outcome(
                         GenericApplicationId::User(application_id),
                         |bytes| Message::User {
@@ -1044,6 +1196,7 @@ where
             authenticated,
             grant,
             kind,
+            expiration,
             message,
         } in raw_outcome.messages
         {
@@ -1064,6 +1217,7 @@ where
                 grant,
                 refund_grant_to,
                 kind,
+                expiration,
                 message: lift(message),
             });
         }
@@ -10,15 +10,16 @@ use std::{
 use async_graphql::{Object, SimpleObject};
 use linera_base::{
     crypto::{BcsHashable, BcsSignable, CryptoError, CryptoHash, KeyPair, PublicKey, Signature},
-    data_types::{Amount, BlockHeight, OracleRecord, Round, Timestamp},
+    data_types::{Amount, BlockHeight, OracleRecord, OracleResponse, Round, Timestamp},
     doc_scalar, ensure,
     identifiers::{
-        Account, ChainId, ChannelName, Destination, GenericApplicationId, MessageId, Owner,
+        Account, ApplicationId, Blob, BlobId, ChainId, ChannelName, Destination,
+        GenericApplicationId, MessageId, Owner,
     },
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
-    BytecodeLocation, Message, MessageKind, Operation,
+    BlockLimits, BytecodeLocation, Message, MessageKind, Operation, SystemOperation,
 };
 use serde::{de::Deserializer, Deserialize, Serialize};
 
@@ -78,6 +79,36 @@ impl Block {
         locations
     }
 
+    /// Returns the IDs of the blobs published by this block's operations.
+    pub fn published_blob_ids(&self) -> HashSet<BlobId> {
+        self.operations
+            .iter()
+            .flat_map(|operation| match operation {
+                Operation::System(SystemOperation::PublishBlob { blob_id }) => vec![*blob_id],
+                Operation::System(SystemOperation::PublishBytecodeBlobs { contract, service }) => {
+                    vec![*contract, *service]
+                }
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    /// Returns the IDs of the blobs containing the bytecodes that this block uses and that are
+    /// published in the given `values`. They are sent to the validators along with the values.
+    pub fn bytecode_blob_ids(&self, values: &[HashedCertificateValue]) -> HashSet<BlobId> {
+        let mut blob_ids = HashSet::new();
+        for location in self.bytecode_locations().into_keys() {
+            let Some(value) = values
+                .iter()
+                .find(|value| value.hash() == location.certificate_hash)
+            else {
+                continue;
+            };
+            blob_ids.extend(value.inner().bytecode_blob_ids(location.operation_index));
+        }
+        blob_ids
+    }
+
     /// Returns whether the block contains only rejected incoming messages, which
     /// makes it admissible even on closed chains.
     pub fn has_only_rejected_messages(&self) -> bool {
@@ -87,6 +118,26 @@ impl Block {
                 .iter()
                 .all(|message| message.action == MessageAction::Reject)
     }
+
+    /// Returns an error if the block has more operations or incoming messages, or is larger,
+    /// than the committee allows.
+    pub fn check_limits(&self, limits: BlockLimits) -> Result<(), ChainError> {
+        ensure!(
+            self.operations.len() as u64 <= limits.maximum_operations,
+            ChainError::TooManyOperations(limits.maximum_operations)
+        );
+        ensure!(
+            self.incoming_messages.len() as u64 <= limits.maximum_incoming_messages,
+            ChainError::TooManyIncomingMessages(limits.maximum_incoming_messages)
+        );
+        let size = bcs::serialized_size(self)
+            .map_err(|error| ChainError::InternalError(error.to_string()))?;
+        ensure!(
+            size as u64 <= limits.maximum_size,
+            ChainError::BlockTooLarge(limits.maximum_size)
+        );
+        Ok(())
+    }
 }
 
 /// A chain ID with a block height.
@@ -153,6 +204,8 @@ pub struct Event {
     pub refund_grant_to: Option<Account>,
     /// The kind of event being delivered.
     pub kind: MessageKind,
+    /// The time after which the message can no longer be executed, if any.
+    pub expiration: Option<Timestamp>,
     /// The timestamp of the block that caused the message.
     pub timestamp: Timestamp,
     /// The message of the event (i.e. the actual payload of a message).
@@ -221,6 +274,8 @@ pub struct BlockProposal {
     pub owner: Owner,
     pub signature: Signature,
     pub hashed_certificate_values: Vec<HashedCertificateValue>,
+    /// The blobs published or read by the block that the validators may not have yet.
+    pub blobs: Vec<Blob>,
     pub validated: Option<Certificate>,
 }
 
@@ -237,6 +292,8 @@ pub struct OutgoingMessage {
     pub refund_grant_to: Option<Account>,
     /// The kind of event being sent.
     pub kind: MessageKind,
+    /// The time after which the message can no longer be executed, if any.
+    pub expiration: Option<Timestamp>,
     /// The message itself.
     pub message: Message,
 }
@@ -280,6 +337,52 @@ pub struct BlockExecutionOutcome {
     pub state_hash: CryptoHash,
     /// The record of oracle responses for each transaction.
     pub oracle_records: Vec<OracleRecord>,
+    /// The fuel used by the applications executed in the block.
+    pub fuel_used: u64,
+    /// The events emitted by the applications executed in the block, in order.
+    pub events: Vec<ApplicationEvent>,
+}
+
+/// An event emitted by an application while executing a block, for indexers.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct ApplicationEvent {
+    /// The index of the transaction that emitted the event, counting the incoming messages
+    /// first and then the operations of the block.
+    pub transaction_index: u32,
+    /// The application that emitted the event.
+    pub application_id: ApplicationId,
+    /// The key chosen by the application, e.g. to tell different kinds of events apart.
+    pub key: Vec<u8>,
+    /// The serialized event.
+    pub value: Vec<u8>,
+}
+
+/// The outcome of a transaction of a confirmed block, as recorded in the event log of its
+/// chain. Its index in the log is its sequence number.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct TransactionOutcome {
+    /// The hash of the certificate of the block.
+    pub certificate_hash: CryptoHash,
+    /// The height of the block.
+    pub height: BlockHeight,
+    /// The index of the transaction, counting the incoming messages first and then the
+    /// operations of the block.
+    pub transaction_index: u32,
+    /// The executed transaction.
+    pub transaction: Transaction,
+    /// The messages sent by the transaction.
+    pub messages: Vec<OutgoingMessage>,
+    /// The events emitted by the applications during the transaction.
+    pub events: Vec<ApplicationEvent>,
+}
+
+/// A transaction of a block.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum Transaction {
+    /// An incoming message, with what the block did with it.
+    ReceiveMessage(IncomingMessage),
+    /// An operation.
+    ExecuteOperation(Operation),
 }
 
 /// A statement to be certified by the validators.
@@ -408,6 +511,64 @@ impl LiteVote {
     }
 }
 
+/// Proof that a validator signed two different blocks for the same chain and height, in the
+/// same round.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EquivocationEvidence {
+    pub first: Vote,
+    pub second: Vote,
+}
+
+impl EquivocationEvidence {
+    /// Returns the evidence that the two votes conflict, if they do. The signatures are not
+    /// checked.
+    pub fn new(first: Vote, second: Vote) -> Option<Self> {
+        let evidence = EquivocationEvidence { first, second };
+        evidence.is_conflicting().then_some(evidence)
+    }
+
+    /// Returns the validator that signed both votes.
+    pub fn validator(&self) -> ValidatorName {
+        self.first.validator
+    }
+
+    /// Returns the block that was voted for first.
+    fn first_block(&self) -> Option<&Block> {
+        self.first.value().block()
+    }
+
+    /// Returns the epoch of the conflicting blocks, if they are blocks.
+    pub fn epoch(&self) -> Option<Epoch> {
+        Some(self.first_block()?.epoch)
+    }
+
+    /// Verifies that the votes conflict and are both signed by the validator.
+    pub fn check(&self) -> Result<(), ChainError> {
+        ensure!(
+            self.is_conflicting(),
+            ChainError::InvalidEquivocationEvidence
+        );
+        self.first.lite().check()?;
+        self.second.lite().check()?;
+        Ok(())
+    }
+
+    fn is_conflicting(&self) -> bool {
+        let (Some(first), Some(second)) = (
+            self.first.value().executed_block(),
+            self.second.value().executed_block(),
+        ) else {
+            return false;
+        };
+        self.first.validator == self.second.validator
+            && self.first.round == self.second.round
+            && first.block.chain_id == second.block.chain_id
+            && first.block.height == second.block.height
+            && first.block.epoch == second.block.epoch
+            && first != second
+    }
+}
+
 /// A certified statement from the committee, without the value.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
@@ -611,6 +772,23 @@ impl CertificateValue {
             && executed_block.messages().len() > index
     }
 
+    /// Returns the IDs of the blobs containing the bytecode published by the operation at
+    /// `operation_index` of this block, if it is a `PublishBytecodeBlobs` operation.
+    pub fn bytecode_blob_ids(&self, operation_index: u32) -> Vec<BlobId> {
+        let operation = self
+            .block()
+            .and_then(|block| block.operations.get(usize::try_from(operation_index).ok()?));
+        match operation {
+            Some(Operation::System(SystemOperation::PublishBytecodeBlobs {
+                contract,
+                service,
+            })) => {
+                vec![*contract, *service]
+            }
+            _ => vec![],
+        }
+    }
+
     pub fn is_confirmed(&self) -> bool {
         matches!(self, CertificateValue::ConfirmedBlock { .. })
     }
@@ -670,6 +848,19 @@ impl Event {
     pub fn is_bouncing(&self) -> bool {
         matches!(self.kind, MessageKind::Bouncing)
     }
+
+    /// Returns whether the message can no longer be executed in a block with the given
+    /// timestamp.
+    pub fn is_expired(&self, timestamp: Timestamp) -> bool {
+        self.expiration
+            .is_some_and(|expiration| expiration < timestamp)
+    }
+
+    /// Returns whether the event must eventually be handled: it can neither be skipped now nor
+    /// after it expires.
+    pub fn must_be_handled(&self) -> bool {
+        !self.is_skippable() && self.expiration.is_none()
+    }
 }
 
 impl ExecutedBlock {
@@ -677,6 +868,14 @@ impl ExecutedBlock {
         &self.outcome.messages
     }
 
+    /// Returns the IDs of the blobs this block needs: the blobs published by its operations
+    /// and the blobs read by its applications.
+    pub fn required_blob_ids(&self) -> HashSet<BlobId> {
+        let mut blob_ids = self.block.published_blob_ids();
+        blob_ids.extend(self.outcome.read_blob_ids());
+        blob_ids
+    }
+
     /// Returns the `message_index`th outgoing message created by the `operation_index`th operation,
     /// or `None` if there is no such operation or message.
     pub fn message_id_for_operation(
@@ -711,6 +910,52 @@ impl ExecutedBlock {
         self.messages().get(usize::try_from(*index).ok()?)
     }
 
+    /// Returns the outcomes of the transactions of this block, in order, as recorded in the
+    /// event log of the chain once the block with the given certificate is confirmed.
+    pub fn transaction_outcomes(&self, certificate_hash: CryptoHash) -> Vec<TransactionOutcome> {
+        let transactions = self
+            .block
+            .incoming_messages
+            .iter()
+            .cloned()
+            .map(Transaction::ReceiveMessage)
+            .chain(
+                self.block
+                    .operations
+                    .iter()
+                    .cloned()
+                    .map(Transaction::ExecuteOperation),
+            );
+        let mut first_message_index = 0;
+        (0..)
+            .zip(transactions)
+            .zip(&self.outcome.message_counts)
+            .map(|((transaction_index, transaction), message_count)| {
+                let messages = self
+                    .messages()
+                    .get(first_message_index as usize..*message_count as usize)
+                    .unwrap_or_default()
+                    .to_vec();
+                first_message_index = *message_count;
+                let events = self
+                    .outcome
+                    .events
+                    .iter()
+                    .filter(|event| event.transaction_index == transaction_index)
+                    .cloned()
+                    .collect();
+                TransactionOutcome {
+                    certificate_hash,
+                    height: self.block.height,
+                    transaction_index,
+                    transaction,
+                    messages,
+                    events,
+                }
+            })
+            .collect()
+    }
+
     /// Returns the message ID belonging to the `index`th outgoing message in this block.
     fn message_id(&self, index: u32) -> MessageId {
         MessageId {
@@ -728,6 +973,18 @@ impl BlockExecutionOutcome {
             outcome: self,
         }
     }
+
+    /// Returns the IDs of the blobs that were read by the applications.
+    pub fn read_blob_ids(&self) -> HashSet<BlobId> {
+        self.oracle_records
+            .iter()
+            .flat_map(|record| &record.responses)
+            .filter_map(|response| match response {
+                OracleResponse::Blob(blob_id) => Some(*blob_id),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 impl HashedCertificateValue {
@@ -800,6 +1057,7 @@ impl BlockProposal {
         content: BlockAndRound,
         secret: &KeyPair,
         hashed_certificate_values: Vec<HashedCertificateValue>,
+        blobs: Vec<Blob>,
         validated: Option<Certificate>,
     ) -> Self {
         let outcome = validated
@@ -818,6 +1076,7 @@ impl BlockProposal {
             owner: secret.public().into(),
             signature,
             hashed_certificate_values,
+            blobs,
             validated,
         }
     }
@@ -856,6 +1115,28 @@ impl LiteVote {
         let hash_and_round = ValueHashAndRound(self.value.value_hash, self.round);
         Ok(self.signature.check(&hash_and_round, self.validator.0)?)
     }
+
+    /// Verifies the signatures of votes for the same value and round at once. This is faster
+    /// than checking each vote.
+    pub fn check_batch(votes: &[LiteVote]) -> Result<(), ChainError> {
+        let Some(first) = votes.first() else {
+            return Ok(());
+        };
+        ensure!(
+            votes
+                .iter()
+                .all(|vote| vote.value == first.value && vote.round == first.round),
+            ChainError::InvalidBatchOfVotes
+        );
+        let hash_and_round = ValueHashAndRound(first.value.value_hash, first.round);
+        Signature::verify_batch(
+            &hash_and_round,
+            votes
+                .iter()
+                .map(|vote| (&vote.validator.0, &vote.signature)),
+        )?;
+        Ok(())
+    }
 }
 
 pub struct SignatureAggregator<'a> {
@@ -990,6 +1271,31 @@ impl Certificate {
         Ok(&self.value)
     }
 
+    /// Verifies several certificates signed by the same committee at once. This is faster
+    /// than checking each certificate.
+    pub fn check_batch<'a>(
+        certificates: impl IntoIterator<Item = &'a Certificate>,
+        committee: &Committee,
+    ) -> Result<(), ChainError> {
+        let mut signed_values = Vec::new();
+        for certificate in certificates {
+            check_quorum(&certificate.signatures, committee)?;
+            let hash_and_round = ValueHashAndRound(certificate.hash(), certificate.round);
+            signed_values.push((hash_and_round, &certificate.signatures));
+        }
+        if signed_values.is_empty() {
+            return Ok(());
+        }
+        Signature::verify_messages_batch(signed_values.iter().flat_map(
+            |(hash_and_round, signatures)| {
+                signatures
+                    .iter()
+                    .map(move |(validator, signature)| (hash_and_round, &validator.0, signature))
+            },
+        ))?;
+        Ok(())
+    }
+
     /// Returns the certificate without the full value.
     pub fn lite_certificate(&self) -> LiteCertificate {
         LiteCertificate {
@@ -1051,7 +1357,19 @@ fn check_signatures(
     signatures: &[(ValidatorName, Signature)],
     committee: &Committee,
 ) -> Result<(), ChainError> {
-    // Check the quorum.
+    check_quorum(signatures, committee)?;
+    // All that is left is checking signatures!
+    let hash_and_round = ValueHashAndRound(value.value_hash, round);
+    Signature::verify_batch(&hash_and_round, signatures.iter().map(|(v, s)| (&v.0, s)))?;
+    Ok(())
+}
+
+/// Verifies that the signers of a certificate are distinct members of the committee, with
+/// a quorum of votes.
+fn check_quorum(
+    signatures: &[(ValidatorName, Signature)],
+    committee: &Committee,
+) -> Result<(), ChainError> {
     let mut weight = 0;
     let mut used_validators = HashSet::new();
     for (validator, _) in signatures {
@@ -1070,9 +1388,6 @@ fn check_signatures(
         weight >= committee.quorum_threshold(),
         ChainError::CertificateRequiresQuorum
     );
-    // All that is left is checking signatures!
-    let hash_and_round = ValueHashAndRound(value.value_hash, round);
-    Signature::verify_batch(&hash_and_round, signatures.iter().map(|(v, s)| (&v.0, s)))?;
     Ok(())
 }
 
@@ -33,8 +33,9 @@ mod inbox_tests;
 /// the events removed by anticipation are tracked in a separate queue. Any event added
 /// later will be required to match the first removed event and so on.
 /// * The cursors of added events (resp. removed events) must be increasing over time.
-/// * Reconciliation of added and removed events is allowed to skip some added events.
-/// However, the opposite is not true: every removed event must be eventually added.
+/// * Reconciliation of added and removed events is allowed to skip some added events: the
+/// skippable ones, and those that expired before the next event from the same sender was
+/// created. However, the opposite is not true: every removed event must be eventually added.
 #[derive(Debug, View, async_graphql::SimpleObject)]
 pub struct InboxStateView<C>
 where
@@ -173,7 +174,7 @@ where
                 break;
             }
             ensure!(
-                previous_event.is_skippable(),
+                previous_event.is_skippable() || previous_event.is_expired(event.timestamp),
                 InboxError::UnskippableEvent {
                     event: previous_event
                 }
@@ -242,7 +243,8 @@ where
                     // The receiver has already executed a later event from the same
                     // sender ahead of time so we should skip this one.
                     ensure!(
-                        cursor < Cursor::from(&previous_event) && event.is_skippable(),
+                        cursor < Cursor::from(&previous_event)
+                            && (event.is_skippable() || event.is_expired(previous_event.timestamp)),
                         InboxError::UnexpectedEvent {
                             previous_event,
                             event,
@@ -82,6 +82,15 @@ pub enum ChainError {
         origin: Box<Origin>,
         event: Event,
     },
+    #[error(
+        "Block proposed to {chain_id:?} is attempting to accept a message \
+         that expired before the block timestamp: {event:?}"
+    )]
+    ExpiredMessage {
+        chain_id: ChainId,
+        origin: Box<Origin>,
+        event: Event,
+    },
     #[error(
         "Incoming message in block proposed to {chain_id:?} has timestamp {message_timestamp:}, \
          which is later than the block timestamp {block_timestamp:}."
@@ -124,6 +133,8 @@ pub enum ChainError {
     CertificateValidatorReuse,
     #[error("Signatures in a certificate must form a quorum")]
     CertificateRequiresQuorum,
+    #[error("Votes checked in a batch must be for the same value and round")]
+    InvalidBatchOfVotes,
     #[error("Certificate signature verification failed: {error}")]
     CertificateSignatureVerificationFailed { error: String },
     #[error("Internal error {0}")]
@@ -136,10 +147,20 @@ pub enum ChainError {
     ClosedChain,
     #[error("All operations on this chain must be from one of the following applications: {0:?}")]
     AuthorizedApplications(Vec<ApplicationId>),
+    #[error("Missing operations or messages from mandatory applications: {0:?}")]
+    MissingMandatoryApplications(Vec<ApplicationId>),
     #[error("Can't use grant across different broadcast messages")]
     GrantUseOnBroadcast,
     #[error("ExecutedBlock contains fewer oracle responses than requests")]
     MissingOracleRecord,
+    #[error("Blocks can have at most {0} operations")]
+    TooManyOperations(u64),
+    #[error("Blocks can have at most {0} incoming messages")]
+    TooManyIncomingMessages(u64),
+    #[error("Blocks can be at most {0} bytes large")]
+    BlockTooLarge(u64),
+    #[error("Invalid equivocation evidence")]
+    InvalidEquivocationEvidence,
 }
 
 #[derive(Copy, Clone, Debug)]
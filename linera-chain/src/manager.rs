@@ -68,7 +68,7 @@
 //! certificate known to any honest validator and include that in their block proposal, just like
 //! in the cooperative case.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use linera_base::{
     crypto::{KeyPair, PublicKey},
@@ -534,6 +534,24 @@ impl ChainManager {
         }
     }
 
+    /// Returns the owners who are allowed to propose a block in the given round.
+    fn round_proposers(&self, round: Round) -> BTreeSet<Owner> {
+        let mut proposers = self
+            .ownership
+            .super_owners
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        match round {
+            Round::Fast => {}
+            Round::MultiLeader(_) => proposers.extend(self.ownership.owners.keys().cloned()),
+            Round::SingleLeader(_) | Round::Validator(_) => {
+                proposers.extend(self.round_leader(round).cloned())
+            }
+        }
+        proposers
+    }
+
     /// Returns the index of the leader who is allowed to propose a block in the given round.
     fn round_leader_index(&self, round: u32) -> Option<usize> {
         let seed = u64::from(round).rotate_left(32).wrapping_add(self.seed);
@@ -583,6 +601,24 @@ pub struct ChainManagerInfo {
     pub leader: Option<Owner>,
     /// The timestamp when the current round times out.
     pub round_timeout: Option<Timestamp>,
+    /// A summary of the consensus state, if requested.
+    pub requested_status: Option<Box<ChainManagerStatus>>,
+}
+
+/// A summary of the consensus state of a chain, explaining what the next block is waiting for.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct ChainManagerStatus {
+    /// The owners who are allowed to propose a block in the current round.
+    pub current_proposers: BTreeSet<Owner>,
+    /// The round and the signer of the highest proposal we received, if any.
+    pub proposed: Option<(Round, Owner)>,
+    /// The round of the validated block that we voted to confirm, if any.
+    pub locked_round: Option<Round>,
+    /// The round that starts when the current one times out, if any.
+    pub next_round: Option<Round>,
+    /// The leader of the next round, or `None` if every owner can propose in that round.
+    pub next_round_leader: Option<Owner>,
 }
 
 impl From<&ChainManager> for ChainManagerInfo {
@@ -600,6 +636,25 @@ impl From<&ChainManager> for ChainManagerInfo {
             current_round,
             leader: manager.round_leader(current_round).cloned(),
             round_timeout: manager.round_timeout,
+            requested_status: None,
+        }
+    }
+}
+
+impl From<&ChainManager> for ChainManagerStatus {
+    fn from(manager: &ChainManager) -> Self {
+        let next_round = manager.ownership.next_round(manager.current_round);
+        ChainManagerStatus {
+            current_proposers: manager.round_proposers(manager.current_round),
+            proposed: manager
+                .proposed
+                .as_ref()
+                .map(|proposal| (proposal.content.round, proposal.owner)),
+            locked_round: manager.locked.as_ref().map(|certificate| certificate.round),
+            next_round,
+            next_round_leader: next_round
+                .and_then(|round| manager.round_leader(round))
+                .cloned(),
         }
     }
 }
@@ -615,6 +670,11 @@ impl ChainManagerInfo {
             .map(|vote| Box::new(vote.value.clone()));
     }
 
+    /// Adds a summary of the consensus state to the `ChainManagerInfo`.
+    pub fn add_status(&mut self, manager: &ChainManager) {
+        self.requested_status = Some(Box::new(ChainManagerStatus::from(manager)));
+    }
+
     /// Returns the highest known validated block certificate.
     pub fn highest_validated(&self) -> Option<&Certificate> {
         self.requested_locked
@@ -3,6 +3,8 @@
 
 //! Test utilities
 
+pub mod strategies;
+
 use linera_base::{
     crypto::{CryptoHash, KeyPair},
     data_types::{Amount, BlockHeight, Round, Timestamp},
@@ -15,8 +17,9 @@ use linera_execution::{
 };
 
 use crate::data_types::{
-    Block, BlockAndRound, BlockProposal, Certificate, Event, HashedCertificateValue,
-    IncomingMessage, MessageAction, Origin, SignatureAggregator, Vote,
+    Block, BlockAndRound, BlockExecutionOutcome, BlockProposal, Certificate, Event,
+    HashedCertificateValue, IncomingMessage, LiteVote, MessageAction, Origin, OutgoingMessage,
+    SignatureAggregator, Vote,
 };
 
 /// Creates a new child of the given block, with the same timestamp.
@@ -49,6 +52,38 @@ pub fn make_first_block(chain_id: ChainId) -> Block {
     }
 }
 
+/// Returns a certificate for `block` with the given outgoing `messages`, signed in `round` by
+/// each of the `key_pairs`.
+pub fn make_certificate(
+    block: Block,
+    messages: Vec<OutgoingMessage>,
+    round: Round,
+    key_pairs: &[&KeyPair],
+) -> Certificate {
+    let outcome = BlockExecutionOutcome {
+        messages,
+        ..BlockExecutionOutcome::default()
+    };
+    let value = HashedCertificateValue::new_confirmed(outcome.with(block));
+    make_certificate_for_value(value, round, key_pairs)
+}
+
+/// Returns a certificate for `value`, signed in `round` by each of the `key_pairs`.
+pub fn make_certificate_for_value(
+    value: HashedCertificateValue,
+    round: Round,
+    key_pairs: &[&KeyPair],
+) -> Certificate {
+    let signatures = key_pairs
+        .iter()
+        .map(|key_pair| {
+            let vote = LiteVote::new(value.lite(), round, key_pair);
+            (vote.validator, vote.signature)
+        })
+        .collect();
+    Certificate::new(value, round, signatures)
+}
+
 /// A helper trait to simplify constructing blocks for tests.
 pub trait BlockTestExt: Sized {
     /// Returns the block with the given operation appended at the end.
@@ -122,7 +157,7 @@ impl BlockTestExt for Block {
 
     fn into_proposal_with_round(self, key_pair: &KeyPair, round: Round) -> BlockProposal {
         let content = BlockAndRound { block: self, round };
-        BlockProposal::new(content, key_pair, vec![], None)
+        BlockProposal::new(content, key_pair, vec![], vec![], None)
     }
 
     fn into_justified_proposal(
@@ -132,7 +167,7 @@ impl BlockTestExt for Block {
         validated: Certificate,
     ) -> BlockProposal {
         let content = BlockAndRound { block: self, round };
-        BlockProposal::new(content, key_pair, vec![], Some(validated))
+        BlockProposal::new(content, key_pair, vec![], vec![], Some(validated))
     }
 }
 
@@ -175,6 +210,7 @@ impl<T: Into<Message>> MessageTestExt for T {
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Protected,
+                expiration: None,
                 timestamp: Timestamp::from(0),
                 message: self.into(),
             },
@@ -0,0 +1,317 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Proptest strategies generating the protocol messages sent between clients and validators,
+//! to test their conversions and the code deserializing them.
+//!
+//! The generated values are well-formed but not meaningful: they are signed with arbitrary
+//! keys, and the execution outcomes don't match the blocks.
+
+use linera_base::{
+    crypto::{CryptoHash, CryptoRng, KeyPair},
+    data_types::{Amount, BlockHeight, Round, Timestamp},
+    identifiers::{
+        BytecodeId, ChainId, ChannelName, Destination, GenericApplicationId, MessageId, Owner,
+    },
+};
+use linera_execution::{
+    committee::Epoch, system::Recipient, Message, MessageKind, Operation, SystemMessage,
+    SystemOperation, UserApplicationId,
+};
+use proptest::{collection::vec, option, prelude::*};
+
+use crate::data_types::{
+    Block, BlockAndRound, BlockExecutionOutcome, BlockProposal, Certificate, ChannelFullName,
+    Event, HashedCertificateValue, IncomingMessage, LiteCertificate, Medium, MessageAction,
+    MessageBundle, Origin, OutgoingMessage, Vote,
+};
+
+/// The maximal number of elements in the generated collections.
+pub const MAX_LENGTH: usize = 4;
+
+/// Returns the key pair generated from the given seed. Key pairs don't implement `Debug`, so
+/// the strategies generate their seeds instead.
+fn key_pair(seed: u64) -> KeyPair {
+    KeyPair::generate_from(&mut Box::<dyn CryptoRng>::from(Some(seed)))
+}
+
+pub fn round() -> impl Strategy<Value = Round> {
+    prop_oneof![
+        Just(Round::Fast),
+        any::<u32>().prop_map(Round::MultiLeader),
+        any::<u32>().prop_map(Round::SingleLeader),
+        any::<u32>().prop_map(Round::Validator),
+    ]
+}
+
+pub fn amount() -> impl Strategy<Value = Amount> {
+    any::<u128>().prop_map(Amount::from_attos)
+}
+
+pub fn application_id() -> impl Strategy<Value = UserApplicationId> {
+    (message_id(), message_id()).prop_map(|(bytecode, creation)| UserApplicationId {
+        bytecode_id: BytecodeId::new(bytecode),
+        creation,
+    })
+}
+
+pub fn message_id() -> impl Strategy<Value = MessageId> {
+    (any::<ChainId>(), any::<BlockHeight>(), any::<u32>()).prop_map(|(chain_id, height, index)| {
+        MessageId {
+            chain_id,
+            height,
+            index,
+        }
+    })
+}
+
+/// Generates transfers and user operations with arbitrary bytes.
+pub fn operation() -> impl Strategy<Value = Operation> {
+    prop_oneof![
+        (option::of(any::<Owner>()), any::<ChainId>(), amount()).prop_map(
+            |(owner, chain_id, amount)| {
+                Operation::System(SystemOperation::Transfer {
+                    owner,
+                    recipient: Recipient::chain(chain_id),
+                    amount,
+                    user_data: Default::default(),
+                })
+            }
+        ),
+        Just(Operation::System(SystemOperation::CloseChain)),
+        (application_id(), vec(any::<u8>(), 0..64)).prop_map(|(application_id, bytes)| {
+            Operation::User {
+                application_id,
+                bytes,
+            }
+        }),
+    ]
+}
+
+/// Generates credits and user messages with arbitrary bytes.
+pub fn message() -> impl Strategy<Value = Message> {
+    prop_oneof![
+        (
+            option::of(any::<Owner>()),
+            amount(),
+            option::of(any::<Owner>())
+        )
+            .prop_map(|(target, amount, source)| {
+                Message::System(SystemMessage::Credit {
+                    target,
+                    amount,
+                    source,
+                })
+            }),
+        (application_id(), vec(any::<u8>(), 0..64)).prop_map(|(application_id, bytes)| {
+            Message::User {
+                application_id,
+                bytes,
+            }
+        }),
+    ]
+}
+
+pub fn message_kind() -> impl Strategy<Value = MessageKind> {
+    prop_oneof![
+        Just(MessageKind::Simple),
+        Just(MessageKind::Protected),
+        Just(MessageKind::Tracked),
+        Just(MessageKind::Bouncing),
+    ]
+}
+
+pub fn incoming_message() -> impl Strategy<Value = IncomingMessage> {
+    (
+        any::<ChainId>(),
+        any::<CryptoHash>(),
+        any::<BlockHeight>(),
+        any::<u32>(),
+        option::of(any::<Owner>()),
+        amount(),
+        message_kind(),
+        any::<u64>(),
+        message(),
+    )
+        .prop_map(
+            |(sender, certificate_hash, height, index, signer, grant, kind, timestamp, message)| {
+                IncomingMessage {
+                    origin: Origin::chain(sender),
+                    event: Event {
+                        certificate_hash,
+                        height,
+                        index,
+                        authenticated_signer: signer,
+                        grant,
+                        refund_grant_to: None,
+                        kind,
+                        expiration: None,
+                        timestamp: Timestamp::from(timestamp),
+                        message,
+                    },
+                    action: MessageAction::Accept,
+                }
+            },
+        )
+}
+
+pub fn outgoing_message() -> impl Strategy<Value = OutgoingMessage> {
+    (
+        any::<ChainId>(),
+        option::of(any::<Owner>()),
+        amount(),
+        message_kind(),
+        message(),
+    )
+        .prop_map(
+            |(recipient, authenticated_signer, grant, kind, message)| OutgoingMessage {
+                destination: Destination::Recipient(recipient),
+                authenticated_signer,
+                grant,
+                refund_grant_to: None,
+                kind,
+                expiration: None,
+                message,
+            },
+        )
+}
+
+pub fn block() -> impl Strategy<Value = Block> {
+    (
+        any::<ChainId>(),
+        any::<u32>(),
+        vec(incoming_message(), 0..MAX_LENGTH),
+        vec(operation(), 0..MAX_LENGTH),
+        any::<BlockHeight>(),
+        any::<u64>(),
+        option::of(any::<Owner>()),
+        option::of(any::<CryptoHash>()),
+    )
+        .prop_map(
+            |(
+                chain_id,
+                epoch,
+                incoming_messages,
+                operations,
+                height,
+                timestamp,
+                authenticated_signer,
+                previous_block_hash,
+            )| Block {
+                chain_id,
+                epoch: Epoch(epoch),
+                incoming_messages,
+                operations,
+                height,
+                timestamp: Timestamp::from(timestamp),
+                authenticated_signer,
+                previous_block_hash,
+            },
+        )
+}
+
+pub fn block_execution_outcome() -> impl Strategy<Value = BlockExecutionOutcome> {
+    (
+        vec(outgoing_message(), 0..MAX_LENGTH),
+        vec(any::<u32>(), 0..MAX_LENGTH),
+        any::<CryptoHash>(),
+        any::<u64>(),
+    )
+        .prop_map(
+            |(messages, message_counts, state_hash, fuel_used)| BlockExecutionOutcome {
+                messages,
+                message_counts,
+                state_hash,
+                oracle_records: Vec::new(),
+                fuel_used,
+                events: Vec::new(),
+            },
+        )
+}
+
+/// Generates validated and confirmed blocks, and timeouts.
+pub fn certificate_value() -> impl Strategy<Value = HashedCertificateValue> {
+    prop_oneof![
+        (block(), block_execution_outcome()).prop_map(|(block, outcome)| {
+            HashedCertificateValue::new_validated(outcome.with(block))
+        }),
+        (block(), block_execution_outcome()).prop_map(|(block, outcome)| {
+            HashedCertificateValue::new_confirmed(outcome.with(block))
+        }),
+        (any::<ChainId>(), any::<BlockHeight>(), any::<u32>()).prop_map(
+            |(chain_id, height, epoch)| {
+                HashedCertificateValue::new_timeout(chain_id, height, Epoch(epoch))
+            }
+        ),
+    ]
+}
+
+/// Generates certificates with valid signatures from arbitrary validators.
+pub fn certificate() -> impl Strategy<Value = Certificate> {
+    (
+        certificate_value(),
+        round(),
+        vec(any::<u64>(), 0..MAX_LENGTH),
+    )
+        .prop_map(|(value, round, seeds)| {
+            let mut signatures = seeds
+                .into_iter()
+                .map(|seed| {
+                    let vote = Vote::new(value.clone(), round, &key_pair(seed));
+                    (vote.validator, vote.signature)
+                })
+                .collect::<Vec<_>>();
+            signatures.sort_by_key(|(validator, _)| *validator);
+            signatures.dedup_by_key(|(validator, _)| *validator);
+            Certificate::new(value, round, signatures)
+        })
+}
+
+pub fn lite_certificate() -> impl Strategy<Value = LiteCertificate<'static>> {
+    certificate().prop_map(|certificate| certificate.lite_certificate().cloned())
+}
+
+/// Generates block proposals signed by their owner, possibly with a validated block
+/// certificate.
+pub fn block_proposal() -> impl Strategy<Value = BlockProposal> {
+    (block(), round(), any::<u64>(), option::of(certificate())).prop_map(
+        |(block, round, seed, validated)| {
+            BlockProposal::new(
+                BlockAndRound { block, round },
+                &key_pair(seed),
+                Vec::new(),
+                Vec::new(),
+                validated,
+            )
+        },
+    )
+}
+
+pub fn message_bundle() -> impl Strategy<Value = MessageBundle> {
+    (
+        any::<BlockHeight>(),
+        any::<u32>(),
+        any::<u64>(),
+        any::<CryptoHash>(),
+        vec((any::<u32>(), outgoing_message()), 0..MAX_LENGTH),
+    )
+        .prop_map(|(height, epoch, timestamp, hash, messages)| MessageBundle {
+            height,
+            epoch: Epoch(epoch),
+            timestamp: Timestamp::from(timestamp),
+            hash,
+            messages,
+        })
+}
+
+pub fn medium() -> impl Strategy<Value = Medium> {
+    prop_oneof![
+        Just(Medium::Direct),
+        vec(any::<u8>(), 0..16).prop_map(|name| {
+            Medium::Channel(ChannelFullName {
+                application_id: GenericApplicationId::System,
+                name: ChannelName::from(name),
+            })
+        }),
+    ]
+}
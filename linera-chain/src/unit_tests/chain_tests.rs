@@ -1,20 +1,26 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{iter, sync::Arc};
+use std::{
+    iter,
+    sync::{Arc, Mutex},
+};
 
 use assert_matches::assert_matches;
 use linera_base::{
     crypto::{CryptoHash, PublicKey},
     data_types::{Amount, ApplicationPermissions, BlockHeight, Timestamp},
-    identifiers::{ApplicationId, BytecodeId, ChainId, MessageId},
+    identifiers::{
+        ApplicationId, BytecodeId, ChainId, Destination, GenericApplicationId, MessageId,
+    },
     ownership::ChainOwnership,
 };
 use linera_execution::{
     committee::{Committee, Epoch},
-    system::OpenChainConfig,
+    system::{topic_channel_name, OpenChainConfig},
     test_utils::{ExpectedCall, MockApplication},
-    BytecodeLocation, ExecutionRuntimeConfig, ExecutionRuntimeContext, Operation, SystemMessage,
+    BytecodeLocation, ContractRuntime, ExecutionError, ExecutionRuntimeConfig,
+    ExecutionRuntimeContext, Message, MessageKind, Operation, SystemMessage,
     TestExecutionRuntimeContext, UserApplicationDescription,
 };
 use linera_views::{
@@ -23,7 +29,7 @@ use linera_views::{
 };
 
 use crate::{
-    data_types::HashedCertificateValue,
+    data_types::{ChannelFullName, HashedCertificateValue, IncomingMessage, Origin, Target},
     test::{make_child_block, make_first_block, BlockTestExt, MessageTestExt},
     ChainError, ChainStateView,
 };
@@ -55,6 +61,7 @@ fn make_app_description() -> UserApplicationDescription {
         creation: make_admin_message_id(BlockHeight(2)),
         required_application_ids: vec![],
         parameters: vec![],
+        upgrades: vec![],
     }
 }
 
@@ -153,3 +160,207 @@ async fn test_application_permissions() {
     let valid_block = make_child_block(&value).with_operation(app_operation);
     chain.execute_block(&valid_block, time, None).await.unwrap();
 }
+
+#[tokio::test]
+async fn test_mandatory_applications() {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    // Create a mock application.
+    let app_description = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let application = Arc::new(MockApplication::default());
+    let extra = &chain.context().extra;
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone());
+
+    // Initialize the chain, with a mandatory application.
+    let config = OpenChainConfig {
+        application_permissions: ApplicationPermissions {
+            mandatory_applications: vec![application_id],
+            ..ApplicationPermissions::default()
+        },
+        ..make_open_chain_config()
+    };
+    let message = SystemMessage::OpenChain(config).into();
+    chain
+        .execute_init_message(message_id, &message, time, time)
+        .await
+        .unwrap();
+
+    let open_chain_message = message.to_simple_incoming(admin_id(), BlockHeight(1));
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description],
+    }
+    .to_simple_incoming(admin_id(), BlockHeight(2));
+
+    // A block without an operation of the app isn't allowed.
+    let invalid_block = make_first_block(chain_id)
+        .with_incoming_message(open_chain_message.clone())
+        .with_incoming_message(register_app_message.clone())
+        .with_simple_transfer(chain_id, Amount::ONE);
+    let result = chain.execute_block(&invalid_block, time, None).await;
+    assert_matches!(result, Err(ChainError::MissingMandatoryApplications(app_ids))
+        if app_ids == vec![application_id]
+    );
+
+    // The app can approve a block with other operations.
+    application.expect_call(ExpectedCall::execute_operation(|_, _, _| Ok(vec![])));
+    application.expect_call(ExpectedCall::default_finalize());
+    let app_operation = Operation::User {
+        application_id,
+        bytes: b"approve".to_vec(),
+    };
+    let valid_block = make_first_block(chain_id)
+        .with_incoming_message(open_chain_message)
+        .with_incoming_message(register_app_message)
+        .with_operation(app_operation)
+        .with_simple_transfer(chain_id, Amount::ONE);
+    let outcome = chain.execute_block(&valid_block, time, None).await.unwrap();
+    let value = HashedCertificateValue::new_confirmed(outcome.with(valid_block));
+
+    // Or reject it by failing.
+    application.expect_call(ExpectedCall::execute_operation(|_, _, _| {
+        Err(ExecutionError::UserError(
+            "over the spending limit".to_string(),
+        ))
+    }));
+    let app_operation = Operation::User {
+        application_id,
+        bytes: b"reject".to_vec(),
+    };
+    let invalid_block = make_child_block(&value)
+        .with_operation(app_operation)
+        .with_simple_transfer(chain_id, Amount::ONE);
+    let result = chain.execute_block(&invalid_block, time, None).await;
+    assert_matches!(result, Err(ChainError::ExecutionError(_, _)));
+}
+
+/// Creates a chain, opened by the admin chain at `height`, that runs a mock application with
+/// the description of [`make_app_description`]. Returns the chain, the application and the
+/// `OpenChain` message to include in the first block.
+async fn make_chain_with_application(
+    height: BlockHeight,
+) -> (
+    ChainStateView<MemoryContext<TestExecutionRuntimeContext>>,
+    Arc<MockApplication>,
+    IncomingMessage,
+) {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(height);
+    let mut chain = ChainStateView::new(ChainId::child(message_id)).await;
+    let application_id = ApplicationId::from(&make_app_description());
+    let application = Arc::new(MockApplication::default());
+    let extra = &chain.context().extra;
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone());
+    let message = SystemMessage::OpenChain(make_open_chain_config()).into();
+    chain
+        .execute_init_message(message_id, &message, time, time)
+        .await
+        .unwrap();
+    let open_chain_message = message.to_simple_incoming(admin_id(), height);
+    (chain, application, open_chain_message)
+}
+
+#[tokio::test]
+async fn test_topic_payload_delivered_to_subscriber() {
+    let time = Timestamp::from(0);
+    let app_description = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description],
+    }
+    .to_simple_incoming(admin_id(), BlockHeight(2));
+    let (mut subscriber, subscriber_app, subscriber_open_chain_message) =
+        make_chain_with_application(BlockHeight(3)).await;
+    let (mut publisher, publisher_app, publisher_open_chain_message) =
+        make_chain_with_application(BlockHeight(4)).await;
+    let subscriber_id = subscriber.chain_id();
+    let publisher_id = publisher.chain_id();
+    let topic_channel = ChannelFullName {
+        application_id: GenericApplicationId::User(application_id),
+        name: topic_channel_name("prices"),
+    };
+
+    // The application on the subscriber chain subscribes to the topic on the publisher chain.
+    subscriber_app.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.subscribe_to_topic(publisher_id, "prices".to_string())?;
+            Ok(vec![])
+        },
+    ));
+    subscriber_app.expect_call(ExpectedCall::default_finalize());
+    let block = make_first_block(subscriber_id)
+        .with_incoming_message(subscriber_open_chain_message)
+        .with_incoming_message(register_app_message.clone())
+        .with_operation(Operation::User {
+            application_id,
+            bytes: vec![],
+        });
+    let outcome = subscriber.execute_block(&block, time, None).await.unwrap();
+    let subscribe_message = SystemMessage::SubscribeToTopic {
+        id: subscriber_id,
+        application_id,
+        topic: "prices".to_string(),
+    };
+    assert!(outcome.messages.iter().any(|message| {
+        message.destination == Destination::Recipient(publisher_id)
+            && message.message == Message::System(subscribe_message.clone())
+    }));
+    let subscriber_value = HashedCertificateValue::new_confirmed(outcome.with(block));
+
+    // The publisher chain receives the subscription, and the application publishes a payload.
+    publisher_app.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _operation| {
+            runtime.publish_to_topic("prices".to_string(), b"payload".to_vec())?;
+            Ok(vec![])
+        },
+    ));
+    publisher_app.expect_call(ExpectedCall::default_finalize());
+    let block = make_first_block(publisher_id)
+        .with_incoming_message(publisher_open_chain_message)
+        .with_incoming_message(register_app_message)
+        .with_incoming_message(subscribe_message.to_simple_incoming(subscriber_id, BlockHeight(0)))
+        .with_operation(Operation::User {
+            application_id,
+            bytes: vec![],
+        });
+    let outcome = publisher.execute_block(&block, time, None).await.unwrap();
+    let payload_message = Message::User {
+        application_id,
+        bytes: b"payload".to_vec(),
+    };
+    assert!(outcome.messages.iter().any(|message| {
+        message.destination == Destination::Subscribers(topic_channel.name.clone())
+            && message.message == payload_message
+    }));
+    let target = Target::channel(subscriber_id, topic_channel.clone());
+    assert!(publisher
+        .outboxes
+        .indices()
+        .await
+        .unwrap()
+        .contains(&target));
+
+    // The application on the subscriber chain executes the payload as a message.
+    let received = Arc::new(Mutex::new(None));
+    subscriber_app.expect_call(ExpectedCall::execute_message({
+        let received = received.clone();
+        move |_runtime, _context, bytes| {
+            *received.lock().unwrap() = Some(bytes);
+            Ok(())
+        }
+    }));
+    subscriber_app.expect_call(ExpectedCall::default_finalize());
+    let mut incoming_message = payload_message.to_simple_incoming(publisher_id, BlockHeight(0));
+    incoming_message.origin = Origin::channel(publisher_id, topic_channel);
+    incoming_message.event.kind = MessageKind::Simple;
+    let block = make_child_block(&subscriber_value).with_incoming_message(incoming_message);
+    subscriber.execute_block(&block, time, None).await.unwrap();
+    assert_eq!(*received.lock().unwrap(), Some(b"payload".to_vec()));
+}
@@ -2,6 +2,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use assert_matches::assert_matches;
 use linera_base::data_types::Amount;
 
 use super::*;
@@ -20,6 +21,8 @@ fn test_signed_values() {
         message_counts: vec![1],
         state_hash: CryptoHash::test_hash("state"),
         oracle_records: vec![OracleRecord::default()],
+        fuel_used: 0,
+        events: Vec::new(),
     }
     .with(block);
     let value = HashedCertificateValue::new_confirmed(executed_block);
@@ -32,6 +35,35 @@ fn test_signed_values() {
     assert!(v.check().is_err());
 }
 
+#[test]
+fn test_check_batch_of_votes() {
+    let key1 = KeyPair::generate();
+    let key2 = KeyPair::generate();
+    let name1 = ValidatorName(key1.public());
+
+    let block =
+        make_first_block(ChainId::root(1)).with_simple_transfer(ChainId::root(2), Amount::ONE);
+    let value = HashedCertificateValue::new_confirmed(BlockExecutionOutcome::default().with(block));
+
+    let votes = vec![
+        LiteVote::new(value.lite(), Round::Fast, &key1),
+        LiteVote::new(value.lite(), Round::Fast, &key2),
+    ];
+    assert!(LiteVote::check_batch(&votes).is_ok());
+    assert!(LiteVote::check_batch(&[]).is_ok());
+
+    let mut invalid_votes = votes.clone();
+    invalid_votes[1].validator = name1;
+    assert!(LiteVote::check_batch(&invalid_votes).is_err());
+
+    let mut mixed_votes = votes;
+    mixed_votes.push(LiteVote::new(value.lite(), Round::MultiLeader(0), &key1));
+    assert_matches!(
+        LiteVote::check_batch(&mixed_votes),
+        Err(ChainError::InvalidBatchOfVotes)
+    );
+}
+
 #[test]
 fn test_certificates() {
     let key1 = KeyPair::generate();
@@ -49,6 +81,8 @@ fn test_certificates() {
         message_counts: vec![1],
         state_hash: CryptoHash::test_hash("state"),
         oracle_records: vec![OracleRecord::default()],
+        fuel_used: 0,
+        events: Vec::new(),
     }
     .with(block);
     let value = HashedCertificateValue::new_confirmed(executed_block);
@@ -74,3 +108,70 @@ fn test_certificates() {
         .is_none());
     assert!(builder.append(v3.validator, v3.signature).is_err());
 }
+
+#[test]
+fn test_check_batch_of_certificates() {
+    let key1 = KeyPair::generate();
+    let key2 = KeyPair::generate();
+    let name1 = ValidatorName(key1.public());
+    let name2 = ValidatorName(key2.public());
+    let committee = Committee::make_simple(vec![name1, name2]);
+
+    let make_certificate = |chain_index: u32, keys: &[&KeyPair]| {
+        let block = make_first_block(ChainId::root(chain_index));
+        let value =
+            HashedCertificateValue::new_confirmed(BlockExecutionOutcome::default().with(block));
+        let signatures = keys
+            .iter()
+            .map(|key| {
+                let vote = LiteVote::new(value.lite(), Round::Fast, key);
+                (vote.validator, vote.signature)
+            })
+            .collect();
+        Certificate::new(value, Round::Fast, signatures)
+    };
+    let certificate1 = make_certificate(1, &[&key1, &key2]);
+    let certificate2 = make_certificate(2, &[&key1, &key2]);
+    assert!(Certificate::check_batch([&certificate1, &certificate2], &committee).is_ok());
+    assert!(Certificate::check_batch([], &committee).is_ok());
+
+    // A certificate without a quorum.
+    let unsigned = make_certificate(3, &[&key1]);
+    assert_matches!(
+        Certificate::check_batch([&certificate1, &unsigned], &committee),
+        Err(ChainError::CertificateRequiresQuorum)
+    );
+
+    // A certificate with the signatures of another one.
+    let mut forged = make_certificate(4, &[]);
+    forged.signatures = certificate2.signatures.clone();
+    assert_matches!(
+        Certificate::check_batch([&certificate1, &forged], &committee),
+        Err(ChainError::CryptoError(_))
+    );
+}
+
+#[test]
+fn test_block_limits() {
+    let block = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::ONE)
+        .with_simple_transfer(ChainId::root(3), Amount::ONE);
+    let size = bcs::serialized_size(&block).unwrap() as u64;
+    let limits = BlockLimits {
+        maximum_operations: 2,
+        maximum_incoming_messages: 0,
+        maximum_size: size,
+    };
+    assert!(block.check_limits(limits).is_ok());
+
+    let result = block.check_limits(BlockLimits {
+        maximum_operations: 1,
+        ..limits
+    });
+    assert_matches!(result, Err(ChainError::TooManyOperations(1)));
+    let result = block.check_limits(BlockLimits {
+        maximum_size: size - 1,
+        ..limits
+    });
+    assert_matches!(result, Err(ChainError::BlockTooLarge(_)));
+}
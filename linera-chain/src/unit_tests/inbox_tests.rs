@@ -25,6 +25,7 @@ fn make_event(
         grant: Amount::ZERO,
         refund_grant_to: None,
         kind: MessageKind::Simple,
+        expiration: None,
         timestamp: Timestamp::default(),
         message: Message::User {
             application_id: UserApplicationId::default(),
@@ -343,3 +344,71 @@ async fn test_inbox_add_then_remove_mixed() {
     assert_eq!(view.added_events.count(), 0);
     assert_eq!(view.removed_events.count(), 0);
 }
+
+fn make_expiring_event(
+    certificate_hash: CryptoHash,
+    height: u64,
+    index: u32,
+    expiration: u64,
+) -> Event {
+    let mut event = make_event(certificate_hash, height, index, [0]);
+    event.kind = MessageKind::Tracked;
+    event.expiration = Some(Timestamp::from(expiration));
+    event
+}
+
+fn make_event_at(certificate_hash: CryptoHash, height: u64, index: u32, timestamp: u64) -> Event {
+    let mut event = make_event(certificate_hash, height, index, [1]);
+    event.timestamp = Timestamp::from(timestamp);
+    event
+}
+
+#[tokio::test]
+async fn test_inbox_skip_expired() {
+    let hash = CryptoHash::test_hash("1");
+    let mut view = InboxStateView::new().await;
+    assert!(view
+        .add_event(make_expiring_event(hash, 0, 0, 10))
+        .await
+        .unwrap());
+    assert!(view
+        .add_event(make_expiring_event(hash, 0, 1, 10))
+        .await
+        .unwrap());
+    assert!(view.add_event(make_event_at(hash, 1, 0, 5)).await.unwrap());
+    assert!(view.add_event(make_event_at(hash, 2, 0, 20)).await.unwrap());
+    // The events have not expired when the next one was created.
+    assert_matches!(
+        view.remove_event(&make_event_at(hash, 1, 0, 5)).await,
+        Err(InboxError::UnskippableEvent { event })
+        if event == make_expiring_event(hash, 0, 0, 10)
+    );
+    // Once expired, they can be skipped.
+    assert!(view
+        .remove_event(&make_event_at(hash, 2, 0, 20))
+        .await
+        .unwrap());
+    assert_eq!(view.added_events.count(), 0);
+
+    let mut view = InboxStateView::new().await;
+    assert!(!view
+        .remove_event(&make_event_at(hash, 1, 0, 5))
+        .await
+        .unwrap());
+    assert_matches!(
+        view.add_event(make_expiring_event(hash, 0, 0, 10)).await,
+        Err(InboxError::UnexpectedEvent { .. })
+    );
+
+    let mut view = InboxStateView::new().await;
+    assert!(!view
+        .remove_event(&make_event_at(hash, 1, 0, 20))
+        .await
+        .unwrap());
+    assert!(!view
+        .add_event(make_expiring_event(hash, 0, 0, 10))
+        .await
+        .unwrap());
+    assert!(!view.add_event(make_event_at(hash, 1, 0, 20)).await.unwrap());
+    assert_eq!(view.removed_events.count(), 0);
+}
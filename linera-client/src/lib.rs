@@ -0,0 +1,395 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module lets programs embed a Linera client, e.g. exchanges and services, without
+//! going through the `linera` command-line tool.
+//!
+//! A [`Client`] acts on the chains of a wallet, in the same format as the wallets of the
+//! command-line tool, and keeps the state of these chains in a storage of its own. Each
+//! operation synchronizes with the validators of the network as needed, waits for its block
+//! to be confirmed, and saves the wallet.
+//!
+//! The storage must be initialized with the genesis configuration of the network, e.g. by
+//! [`Client::create_wallet`] or with `linera wallet init`.
+
+use std::path::Path;
+
+use futures::{lock::OwnedMutexGuard, Future};
+use linera_base::{
+    crypto::{CryptoRng, KeyPair, PublicKey},
+    data_types::{Amount, BlockHeight, Timestamp},
+    identifiers::{Account, ChainId, MessageId},
+    ownership::ChainOwnership,
+};
+use linera_chain::data_types::Certificate;
+use linera_core::{
+    client::{AbortOnDrop, ArcChainClient, ChainClient, ChainClientBuilder, ChainClientError},
+    data_types::ClientOutcome,
+    node::{CrossChainMessageDelivery, NotificationStream},
+};
+use linera_execution::system::UserData;
+use linera_rpc::{
+    config::RetryPolicy,
+    node_provider::{NodeOptions, NodeProvider},
+};
+use linera_service::node_service::wait_for_next_round;
+pub use linera_service::{
+    config::{GenesisConfig, WalletState},
+    wallet::{UserChain, Wallet},
+};
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+use thiserror::Error;
+use tracing::info;
+
+#[cfg(test)]
+#[path = "unit_tests/client_tests.rs"]
+mod tests;
+
+/// An error of a [`Client`].
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("Chain {0} is not in the wallet")]
+    UnknownChain(ChainId),
+    #[error("The wallet has no default chain")]
+    NoDefaultChain,
+    #[error("Failed to access the wallet: {0}")]
+    Wallet(anyhow::Error),
+    #[error("Failed to initialize the storage: {0}")]
+    Storage(anyhow::Error),
+    #[error(transparent)]
+    ChainClient(#[from] ChainClientError),
+}
+
+/// The options of a [`Client`].
+#[derive(Clone, Copy, Debug)]
+pub struct ClientOptions {
+    /// The timeouts and retries of the requests to the validators, or `None` to use the
+    /// policies of the wallet.
+    pub node_options: Option<NodeOptions>,
+    /// The maximum number of incoming messages to include in a block.
+    pub max_pending_messages: usize,
+    /// Whether to wait until a quorum of validators has confirmed that all sent cross-chain
+    /// messages have been delivered.
+    pub wait_for_outgoing_messages: bool,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        ClientOptions {
+            node_options: None,
+            max_pending_messages: 10,
+            wait_for_outgoing_messages: false,
+        }
+    }
+}
+
+/// A client of a Linera network, acting on the chains of a wallet.
+pub struct Client<S> {
+    wallet_state: WalletState,
+    storage: S,
+    chain_client_builder: ChainClientBuilder<NodeProvider>,
+    prng: Box<dyn CryptoRng>,
+}
+
+impl<S> Client<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
+{
+    /// Creates a client using the wallet `wallet_state` and the `storage`, which must
+    /// already be initialized with the genesis configuration of the wallet.
+    pub fn new(wallet_state: WalletState, storage: S, options: ClientOptions) -> Self {
+        let wallet = wallet_state.inner();
+        let prng = wallet.make_prng();
+        let node_options = options
+            .node_options
+            .unwrap_or_else(|| Self::wallet_node_options(wallet));
+        let delivery = CrossChainMessageDelivery::new(options.wait_for_outgoing_messages);
+        let chain_client_builder = ChainClientBuilder::new(
+            NodeProvider::new(node_options),
+            options.max_pending_messages,
+            delivery,
+        );
+        Client {
+            wallet_state,
+            storage,
+            chain_client_builder,
+            prng,
+        }
+    }
+
+    /// Creates a client using the wallet at `path`, e.g. as created by the command-line
+    /// tool, and the `storage`, which must already be initialized.
+    pub fn from_wallet_file(
+        path: &Path,
+        storage: S,
+        options: ClientOptions,
+    ) -> Result<Self, ClientError> {
+        let wallet_state = WalletState::from_file(path).map_err(ClientError::Wallet)?;
+        Ok(Self::new(wallet_state, storage, options))
+    }
+
+    /// Creates a wallet at `path` for the network of the `genesis_config`, with the given
+    /// `chains`, initializes the `storage` with the genesis configuration and returns a
+    /// client using them.
+    pub async fn create_wallet(
+        path: &Path,
+        genesis_config: GenesisConfig,
+        chains: Vec<UserChain>,
+        mut storage: S,
+        options: ClientOptions,
+    ) -> Result<Self, ClientError> {
+        genesis_config
+            .initialize_storage(&mut storage)
+            .await
+            .map_err(ClientError::Storage)?;
+        let mut wallet_state =
+            WalletState::create(path, genesis_config, None).map_err(ClientError::Wallet)?;
+        for chain in chains {
+            wallet_state.inner_mut().insert(chain);
+        }
+        let mut client = Self::new(wallet_state, storage, options);
+        client.save_wallet()?;
+        Ok(client)
+    }
+
+    /// Returns the node options configured by the RPC policies of the `wallet`.
+    fn wallet_node_options(wallet: &Wallet) -> NodeOptions {
+        let policies = wallet.rpc_policies();
+        NodeOptions {
+            timeout: policies.timeout.unwrap_or_default(),
+            notification_retry: policies
+                .notification_retry
+                .unwrap_or(RetryPolicy::NOTIFICATIONS),
+            direct_to_shards: false,
+        }
+    }
+
+    /// Returns the wallet.
+    pub fn wallet(&self) -> &Wallet {
+        self.wallet_state.inner()
+    }
+
+    /// Returns the default chain of the wallet.
+    pub fn default_chain(&self) -> Result<ChainId, ClientError> {
+        self.wallet()
+            .default_chain()
+            .ok_or(ClientError::NoDefaultChain)
+    }
+
+    /// Returns the storage of the client's local node.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Returns a client for the chain `chain_id` of the wallet, e.g. for the operations not
+    /// covered by this type. Call [`Client::update_wallet`] after using it.
+    pub fn chain_client(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<ChainClient<NodeProvider, S>, ClientError> {
+        let chain = self
+            .wallet()
+            .get(chain_id)
+            .ok_or(ClientError::UnknownChain(chain_id))?;
+        let known_key_pairs = chain
+            .key_pair
+            .as_ref()
+            .map(KeyPair::copy)
+            .into_iter()
+            .collect();
+        Ok(self.chain_client_builder.build(
+            chain_id,
+            known_key_pairs,
+            self.storage.clone(),
+            self.wallet().genesis_admin_chain(),
+            chain.block_hash,
+            chain.timestamp,
+            chain.next_block_height,
+            chain.pending_block.clone(),
+        ))
+    }
+
+    /// Updates the wallet with the state of the `chain_client`, and saves it.
+    pub async fn update_wallet(
+        &mut self,
+        chain_client: &mut ChainClient<NodeProvider, S>,
+    ) -> Result<(), ClientError> {
+        self.wallet_state
+            .inner_mut()
+            .update_from_state(chain_client)
+            .await;
+        self.save_wallet()
+    }
+
+    /// Saves the wallet, unless it is ephemeral.
+    fn save_wallet(&mut self) -> Result<(), ClientError> {
+        self.wallet_state
+            .inner_mut()
+            .refresh_prng_seed(&mut self.prng);
+        if self.wallet_state.is_ephemeral() {
+            return Ok(());
+        }
+        self.wallet_state.write().map_err(ClientError::Wallet)
+    }
+
+    /// Downloads the blocks and incoming messages of the chain `chain_id` from the
+    /// validators.
+    pub async fn synchronize(&mut self, chain_id: ChainId) -> Result<(), ClientError> {
+        let mut chain_client = self.chain_client(chain_id)?;
+        let result = chain_client.synchronize_from_validators().await;
+        self.update_wallet(&mut chain_client).await?;
+        result?;
+        Ok(())
+    }
+
+    /// Returns the balance of the `account`, including the transfers in the incoming
+    /// messages of its chain that are known locally.
+    ///
+    /// This does not synchronize with the validators: see [`Client::synchronize`].
+    pub async fn balance(&self, account: Account) -> Result<Amount, ClientError> {
+        let mut chain_client = self.chain_client(account.chain_id)?;
+        let balance = match account.owner {
+            Some(owner) => chain_client.query_owner_balance(owner).await?,
+            None => chain_client.query_balance().await?,
+        };
+        Ok(balance)
+    }
+
+    /// Transfers `amount` tokens from the `sender` account, which must be on a chain of
+    /// the wallet, to the `recipient` account, and returns the certificate of the block.
+    pub async fn transfer(
+        &mut self,
+        sender: Account,
+        recipient: Account,
+        amount: Amount,
+    ) -> Result<Certificate, ClientError> {
+        let chain_client = self.chain_client(sender.chain_id)?.into_arc();
+        info!("Transferring {amount} tokens from {sender} to {recipient}");
+        self.apply(&chain_client, |mut chain_client| async move {
+            chain_client
+                .transfer_to_account(sender.owner, amount, recipient, UserData::default())
+                .await
+        })
+        .await
+    }
+
+    /// Opens a new chain from the chain `parent_id` of the wallet, with an initial
+    /// `balance` taken from the parent chain, and adds it to the wallet.
+    ///
+    /// The new chain is owned by `owner` or, if it is `None`, by a new key pair kept in the
+    /// wallet. Returns the ID of the message creating the chain, and the ID of the chain.
+    pub async fn open_chain(
+        &mut self,
+        parent_id: ChainId,
+        owner: Option<PublicKey>,
+        balance: Amount,
+    ) -> Result<(MessageId, ChainId), ClientError> {
+        let chain_client = self.chain_client(parent_id)?.into_arc();
+        let (public_key, key_pair) = match owner {
+            Some(public_key) => (public_key, None),
+            None => {
+                let key_pair = KeyPair::generate_from(&mut self.prng);
+                (key_pair.public(), Some(key_pair))
+            }
+        };
+        info!("Opening a new chain from chain {parent_id}");
+        let (message_id, certificate) = self
+            .apply(&chain_client, |mut chain_client| async move {
+                let ownership = ChainOwnership::single(public_key);
+                chain_client.open_chain(ownership, balance).await
+            })
+            .await?;
+        let chain_id = ChainId::child(message_id);
+        let timestamp = certificate
+            .value()
+            .block()
+            .map_or_else(Timestamp::now, |block| block.timestamp);
+        if self.wallet().get(chain_id).is_none() {
+            self.wallet_state.inner_mut().insert(UserChain {
+                chain_id,
+                key_pair,
+                block_hash: None,
+                timestamp,
+                next_block_height: BlockHeight::ZERO,
+                pending_block: None,
+                balance: None,
+            });
+        }
+        self.save_wallet()?;
+        Ok((message_id, chain_id))
+    }
+
+    /// Downloads the incoming messages of the chain `chain_id` and executes them in new
+    /// blocks. Returns the certificates of these blocks.
+    pub async fn process_inbox(
+        &mut self,
+        chain_id: ChainId,
+    ) -> Result<Vec<Certificate>, ClientError> {
+        let chain_client = self.chain_client(chain_id)?.into_arc();
+        chain_client
+            .lock()
+            .await
+            .synchronize_from_validators()
+            .await?;
+        let mut certificates = Vec::new();
+        let mut notification_stream = None;
+        loop {
+            let result = chain_client.lock().await.process_inbox().await;
+            self.update_wallet(&mut *chain_client.lock().await).await?;
+            let (new_certificates, maybe_timeout) = result?;
+            certificates.extend(new_certificates);
+            let Some(timeout) = maybe_timeout else {
+                return Ok(certificates);
+            };
+            // Listen for notifications, so we learn about new rounds and blocks.
+            if notification_stream.is_none() {
+                notification_stream = Some(chain_client.listen().await?);
+            }
+            if let Some((_, stream)) = &mut notification_stream {
+                wait_for_next_round(stream, timeout).await;
+            }
+        }
+    }
+
+    /// Returns the notifications about the chain `chain_id` from the validators. The local
+    /// state of the chain is synchronized as they arrive, until the returned handle is
+    /// dropped.
+    pub async fn subscribe(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<(AbortOnDrop, NotificationStream), ClientError> {
+        let chain_client = self.chain_client(chain_id)?.into_arc();
+        Ok(chain_client.listen().await?)
+    }
+
+    /// Applies `f` to the chain client, and saves the wallet regardless of the outcome. As
+    /// long as `f` returns a round timeout, waits for the next round and retries.
+    async fn apply<F, Fut, T>(
+        &mut self,
+        chain_client: &ArcChainClient<NodeProvider, S>,
+        mut f: F,
+    ) -> Result<T, ClientError>
+    where
+        F: FnMut(OwnedMutexGuard<ChainClient<NodeProvider, S>>) -> Fut,
+        Fut: Future<Output = Result<ClientOutcome<T>, ChainClientError>>,
+    {
+        let mut notification_stream = None;
+        loop {
+            let result = f(chain_client.0.clone().lock_owned().await).await;
+            self.update_wallet(&mut *chain_client.lock().await).await?;
+            let timeout = match result? {
+                ClientOutcome::Committed(t) => return Ok(t),
+                ClientOutcome::WaitForTimeout(timeout) => timeout,
+            };
+            // Listen for notifications, so we learn about new rounds and blocks.
+            if notification_stream.is_none() {
+                notification_stream = Some(chain_client.listen().await?);
+            }
+            if let Some((_, stream)) = &mut notification_stream {
+                wait_for_next_round(stream, timeout).await;
+            }
+        }
+    }
+}
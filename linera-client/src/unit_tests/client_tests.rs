@@ -0,0 +1,56 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::{CryptoRng, KeyPair},
+    identifiers::ChainDescription,
+};
+use linera_execution::ResourceControlPolicy;
+use linera_service::config::CommitteeConfig;
+use linera_storage::{MemoryStorage, TestClock};
+
+use super::*;
+
+#[tokio::test]
+async fn test_create_wallet_and_query_balance() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("wallet.json");
+    let mut rng = Box::<dyn CryptoRng>::from(Some(37));
+    let chain = UserChain::make_initial(&mut rng, ChainDescription::Root(0), Timestamp::from(0));
+    let chain_id = chain.chain_id;
+    let public_key = chain.key_pair.as_ref().map(KeyPair::public).unwrap();
+    let mut genesis_config = GenesisConfig::new(
+        CommitteeConfig::default(),
+        chain_id,
+        Timestamp::from(0),
+        ResourceControlPolicy::default(),
+        "test".to_string(),
+    );
+    genesis_config
+        .chains
+        .push((public_key, Amount::from_tokens(10)));
+    let storage = MemoryStorage::<TestClock>::make_test_storage(None).await;
+
+    let client = Client::create_wallet(
+        &path,
+        genesis_config,
+        vec![chain],
+        storage,
+        ClientOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(client.wallet().chain_ids(), vec![chain_id]);
+    assert!(matches!(
+        client.chain_client(ChainId::root(1)),
+        Err(ClientError::UnknownChain(_))
+    ));
+    assert_eq!(
+        client.balance(Account::chain(chain_id)).await.unwrap(),
+        Amount::from_tokens(10)
+    );
+
+    drop(client);
+    let wallet_state = WalletState::from_file(&path).unwrap();
+    assert!(wallet_state.inner().get(chain_id).is_some());
+}
@@ -0,0 +1,274 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Periodic reconciliation of the chain states of a validator with the other validators.
+//!
+//! A validator that missed the delivery of some certificates, e.g. because it was offline,
+//! normally only catches up when a client sends it the missing certificates. The
+//! [`AntiEntropy`] task instead regularly picks a sample of the chains in the storage,
+//! compares their tips with those of another validator of the committee, and pulls the
+//! missing certificates of the chains that are lagging behind.
+
+use std::{sync::Arc, time::Duration};
+
+use linera_base::identifiers::ChainId;
+use linera_chain::data_types::{Certificate, HashedCertificateValue};
+use linera_execution::{committee::ValidatorName, BytecodeLocation};
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+use rand::seq::IteratorRandom as _;
+use tracing::{debug, info, warn};
+
+use crate::{
+    data_types::{BlockHeightRange, ChainInfoQuery},
+    node::{CrossChainMessageDelivery, LocalValidatorNodeProvider, NodeError, ValidatorNode},
+};
+
+/// The configuration of the anti-entropy task of a validator.
+#[derive(Clone, Debug)]
+pub struct AntiEntropyConfig {
+    /// How long to wait between two rounds of reconciliation.
+    pub interval: Duration,
+    /// How many chains to compare with another validator in each round.
+    pub sample_size: usize,
+    /// The maximum number of certificates to pull for one chain in one round.
+    pub max_certificates_per_chain: u64,
+}
+
+impl Default for AntiEntropyConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            sample_size: 10,
+            max_certificates_per_chain: 100,
+        }
+    }
+}
+
+/// Compares the chains of a validator with the other validators of their committees, and
+/// submits the missing certificates to the validator.
+pub struct AntiEntropy<P, S>
+where
+    P: LocalValidatorNodeProvider,
+{
+    /// The name of this validator, to never pick it as a peer.
+    name: ValidatorName,
+    /// The node of this validator, that the missing certificates are submitted to.
+    own_node: P::Node,
+    /// Connects to the other validators.
+    node_provider: P,
+    storage: S,
+    /// Which chains to reconcile, e.g. only the chains of the shards of this process.
+    chain_filter: Arc<dyn Fn(ChainId) -> bool + Send + Sync>,
+    config: AntiEntropyConfig,
+}
+
+impl<P, S> AntiEntropy<P, S>
+where
+    P: LocalValidatorNodeProvider + Send + Sync + 'static,
+    P::Node: ValidatorNode + Send + Sync,
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
+{
+    /// Creates the task of the validator `name`, reconciling all the chains of `storage`.
+    pub fn new(
+        name: ValidatorName,
+        own_node: P::Node,
+        node_provider: P,
+        storage: S,
+        config: AntiEntropyConfig,
+    ) -> Self {
+        Self {
+            name,
+            own_node,
+            node_provider,
+            storage,
+            chain_filter: Arc::new(|_| true),
+            config,
+        }
+    }
+
+    /// Only reconciles the chains for which `filter` returns `true`.
+    pub fn with_chain_filter(
+        mut self,
+        filter: impl Fn(ChainId) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.chain_filter = Arc::new(filter);
+        self
+    }
+
+    /// Runs rounds of reconciliation forever, at the configured interval.
+    pub async fn run(mut self) {
+        info!(
+            "Starting anti-entropy every {:?} with {} chains per round",
+            self.config.interval, self.config.sample_size
+        );
+        loop {
+            tokio::time::sleep(self.config.interval).await;
+            match self.reconcile_sample().await {
+                Ok(0) => debug!("Anti-entropy found no missing certificates"),
+                Ok(count) => info!("Anti-entropy pulled {count} missing certificates"),
+                Err(error) => warn!("Anti-entropy failed to list the chains: {error}"),
+            }
+        }
+    }
+
+    /// Reconciles a random sample of the chains, and returns the number of certificates
+    /// that were pulled from other validators.
+    pub async fn reconcile_sample(&mut self) -> Result<u64, ViewError> {
+        let chain_ids = self.storage.list_chain_ids().await?;
+        let sample = chain_ids
+            .into_iter()
+            .filter(|chain_id| (self.chain_filter)(*chain_id))
+            .choose_multiple(&mut rand::thread_rng(), self.config.sample_size);
+        let mut count = 0;
+        for chain_id in sample {
+            match self.reconcile_chain(chain_id).await {
+                Ok(pulled) => count += pulled,
+                Err(error) => warn!("Anti-entropy failed for chain {chain_id}: {error}"),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Compares the tip of a chain with a random other validator of its committee, and
+    /// submits the certificates that this validator is missing. Returns the number of
+    /// certificates that were pulled.
+    ///
+    /// The local state of the chain is queried from this validator's own node rather than
+    /// loaded from the storage, because the worker may keep the chain locked in its cache.
+    pub async fn reconcile_chain(&mut self, chain_id: ChainId) -> Result<u64, NodeError> {
+        let response = self
+            .own_node
+            .handle_chain_info_query(ChainInfoQuery::new(chain_id).with_committees())
+            .await?;
+        if response.info.description.is_none() {
+            return Ok(0);
+        }
+        let Some(committee) = response.latest_committee() else {
+            return Ok(0);
+        };
+        let local_height = response.info.next_block_height;
+        let peer = committee
+            .validator_addresses()
+            .filter(|(name, _)| *name != self.name)
+            .choose(&mut rand::thread_rng())
+            .map(|(name, address)| (name, address.to_string()));
+        let Some((peer_name, peer_address)) = peer else {
+            return Ok(0);
+        };
+        let mut peer_node = self
+            .node_provider
+            .make_nodes_from_list::<Vec<_>, _>([(peer_name, peer_address)])?
+            .pop()
+            .map(|(_, node)| node)
+            .ok_or(NodeError::InvalidChainInfoResponse)?;
+
+        let response = peer_node
+            .handle_chain_info_query(ChainInfoQuery::new(chain_id))
+            .await?;
+        response.check(peer_name)?;
+        let peer_height = response.info.next_block_height;
+        if peer_height <= local_height {
+            return Ok(0);
+        }
+        let missing = peer_height.0 - local_height.0;
+        debug!("Chain {chain_id} is {missing} blocks behind validator {peer_name}");
+
+        let query =
+            ChainInfoQuery::new(chain_id).with_sent_certificates_in_range(BlockHeightRange {
+                start: local_height,
+                limit: Some(missing.min(self.config.max_certificates_per_chain)),
+            });
+        let response = peer_node.handle_chain_info_query(query).await?;
+        response.check(peer_name)?;
+        let mut next_height = local_height;
+        let mut count = 0;
+        for certificate in response.info.requested_sent_certificates {
+            if certificate.value().chain_id() != chain_id
+                || certificate.value().height() != next_height
+                || !certificate.value().is_confirmed()
+            {
+                warn!("Validator {peer_name} sent an unexpected certificate for chain {chain_id}");
+                return Err(NodeError::InvalidChainInfoResponse);
+            }
+            self.submit_certificate(&mut peer_node, certificate).await?;
+            next_height = next_height.try_add_one()?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Submits a certificate to this validator, with the bytecodes and blobs it is missing,
+    /// fetched from the peer.
+    async fn submit_certificate(
+        &mut self,
+        peer_node: &mut P::Node,
+        certificate: Certificate,
+    ) -> Result<(), NodeError> {
+        let chain_id = certificate.value().chain_id();
+        let mut values = Vec::new();
+        let mut result = self
+            .own_node
+            .handle_certificate(
+                certificate.clone(),
+                vec![],
+                CrossChainMessageDelivery::NonBlocking,
+            )
+            .await;
+        if let Err(NodeError::ApplicationBytecodesNotFound(locations)) = result {
+            values = Self::download_values(peer_node, chain_id, locations).await?;
+            result = self
+                .own_node
+                .handle_certificate(
+                    certificate.clone(),
+                    values.clone(),
+                    CrossChainMessageDelivery::NonBlocking,
+                )
+                .await;
+        }
+        if let Err(NodeError::BlobsNotFound(blob_ids)) = result {
+            let mut blobs = Vec::new();
+            for blob_id in blob_ids {
+                let blob = peer_node.download_blob(chain_id, blob_id).await?;
+                if blob.id() != blob_id {
+                    return Err(NodeError::InvalidChainInfoResponse);
+                }
+                blobs.push(blob);
+            }
+            self.storage.write_blobs(&blobs).await?;
+            result = self
+                .own_node
+                .handle_certificate(certificate, values, CrossChainMessageDelivery::NonBlocking)
+                .await;
+        }
+        result.map(drop)
+    }
+
+    /// Fetches the values containing the given bytecodes from the peer.
+    async fn download_values(
+        peer_node: &mut P::Node,
+        chain_id: ChainId,
+        locations: Vec<BytecodeLocation>,
+    ) -> Result<Vec<HashedCertificateValue>, NodeError> {
+        let mut values = Vec::new();
+        for location in locations {
+            let query = ChainInfoQuery::new(chain_id)
+                .with_hashed_certificate_value(location.certificate_hash);
+            let response = peer_node.handle_chain_info_query(query).await?;
+            let value = response
+                .info
+                .requested_hashed_certificate_value
+                .ok_or(NodeError::InvalidChainInfoResponse)?;
+            if value.hash() != location.certificate_hash {
+                return Err(NodeError::InvalidChainInfoResponse);
+            }
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+#[path = "unit_tests/anti_entropy.rs"]
+mod tests;
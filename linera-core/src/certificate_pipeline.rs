@@ -0,0 +1,153 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The stages of the processing of a certificate by a worker.
+//!
+//! A confirmed block goes through the following stages, each reporting its latency in the
+//! `certificate_stage_latency` metric:
+//!
+//! 1. [`CertificateStage::VerifySignatures`]: the signatures of the certificate are checked
+//!    against the committee of the chain's epoch. This is the only stage that doesn't need
+//!    the chain state, and the one using the most CPU, so it can run on the blocking thread
+//!    pool instead of the worker's task.
+//! 2. [`CertificateStage::Execute`]: the certificate and the values it needs are persisted,
+//!    and the block is executed again to check its outcome.
+//! 3. [`CertificateStage::Commit`]: the new state of the chain is saved.
+//! 4. [`CertificateStage::FanOut`]: the cross-chain requests delivering the outgoing messages
+//!    of the chain are prepared for the recipients.
+//!
+//! Validated blocks and timeouts only go through the first stage before updating the chain
+//! manager.
+
+use std::sync::Arc;
+
+use linera_base::time::Instant;
+use linera_chain::data_types::{Certificate, LiteCertificate};
+use linera_execution::committee::{Committee, Epoch};
+use tokio::sync::Semaphore;
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::HistogramVec,
+};
+
+use crate::{committee_cache::CommitteeCache, worker::WorkerError};
+
+#[cfg(test)]
+#[path = "unit_tests/certificate_pipeline.rs"]
+mod unit_tests;
+
+#[cfg(with_metrics)]
+static CERTIFICATE_STAGE_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "certificate_stage_latency",
+        "Time (ms) spent by a worker in each stage of the processing of a certificate",
+        &["stage"],
+        Some(vec![
+            0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+/// A stage of the processing of a certificate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CertificateStage {
+    VerifySignatures,
+    Execute,
+    Commit,
+    FanOut,
+}
+
+impl CertificateStage {
+    /// Returns the label of the stage in the metrics.
+    #[cfg(any(with_metrics, test))]
+    pub fn name(&self) -> &'static str {
+        match self {
+            CertificateStage::VerifySignatures => "verify_signatures",
+            CertificateStage::Execute => "execute",
+            CertificateStage::Commit => "commit",
+            CertificateStage::FanOut => "fan_out",
+        }
+    }
+
+    /// Starts measuring the latency of this stage, until the returned value is dropped.
+    pub fn start(self) -> StageTimer {
+        StageTimer {
+            stage: self,
+            start: Instant::now(),
+        }
+    }
+}
+
+/// Measures the latency of a stage, until it is dropped.
+pub struct StageTimer {
+    stage: CertificateStage,
+    start: Instant,
+}
+
+impl Drop for StageTimer {
+    fn drop(&mut self) {
+        #[cfg(with_metrics)]
+        CERTIFICATE_STAGE_LATENCY
+            .with_label_values(&[self.stage.name()])
+            .observe(self.start.elapsed().as_secs_f64() * 1000.0);
+        #[cfg(not(with_metrics))]
+        let _ = (self.stage, self.start);
+    }
+}
+
+/// Where a worker runs the stages of the certificates it handles, shared by all the clones
+/// of the worker.
+#[derive(Clone, Default)]
+pub struct CertificatePipeline {
+    /// Limits the number of certificates verified at once on the blocking thread pool, or
+    /// `None` if the signatures are verified on the worker's task.
+    verifiers: Option<Arc<Semaphore>>,
+}
+
+impl CertificatePipeline {
+    /// Creates a pipeline verifying up to `verification_threads` certificates at once on the
+    /// blocking thread pool. Zero verifies them on the worker's task.
+    pub fn new(verification_threads: usize) -> Self {
+        let verifiers =
+            (verification_threads > 0).then(|| Arc::new(Semaphore::new(verification_threads)));
+        CertificatePipeline { verifiers }
+    }
+
+    /// Verifies the signatures of a certificate with the `committee` of its `epoch`, unless
+    /// the `committees` cache already did.
+    pub async fn verify_signatures(
+        &self,
+        committees: &Arc<CommitteeCache>,
+        certificate: &Certificate,
+        epoch: Epoch,
+        committee: &Committee,
+    ) -> Result<(), WorkerError> {
+        let _timer = CertificateStage::VerifySignatures.start();
+        let Some(verifiers) = &self.verifiers else {
+            return Ok(committees.check(certificate, epoch, committee)?);
+        };
+        if committees.is_verified(certificate) {
+            // Not worth a trip to the thread pool.
+            return Ok(committees.check(certificate, epoch, committee)?);
+        }
+        let _permit = verifiers
+            .acquire()
+            .await
+            .expect("the semaphore is never closed");
+        let committees = committees.clone();
+        let certificate = LiteCertificate::new(
+            certificate.lite_value(),
+            certificate.round,
+            certificate.signatures().clone(),
+        );
+        let committee = committee.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            committees.check_lite(certificate, epoch, &committee)
+        })
+        .await
+        .unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()));
+        Ok(result?)
+    }
+}
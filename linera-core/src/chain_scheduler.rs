@@ -0,0 +1,295 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scheduling of the requests executed by a worker.
+//!
+//! Requests for the same chain are executed one at a time, in the order in which they arrive.
+//! Requests for different chains are executed concurrently, but only up to a bounded number
+//! at once. A request only competes for one of the shared slots once it is the next one for
+//! its chain: a busy chain never holds more than one slot, and the chains that are ready take
+//! turns in the order in which they became ready.
+//!
+//! To diagnose stuck chains, the scheduler remembers which request holds each chain and how
+//! many are waiting for it. A warning is logged whenever a request holds its chain, or waits
+//! for it, longer than a threshold, and requests can be made to fail with a diagnostic
+//! instead of waiting forever.
+
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use linera_base::identifiers::ChainId;
+use tokio::sync::{Mutex, OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+#[cfg(with_metrics)]
+use {
+    linera_base::{
+        prometheus_util::{self, MeasureLatency as _},
+        sync::Lazy,
+    },
+    prometheus::HistogramVec,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/chain_scheduler.rs"]
+mod unit_tests;
+
+cfg_if::cfg_if! {
+    if #[cfg(web)] {
+        use wasmtimer::tokio::timeout;
+    } else {
+        use tokio::time::timeout;
+    }
+}
+
+#[cfg(with_metrics)]
+static CHAIN_SCHEDULER_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "chain_scheduler_latency",
+        "Time (ms) a request waits before the worker starts executing it",
+        &[],
+        Some(vec![
+            0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+/// The queue of every chain with a pending request, as a weak reference.
+type ChainQueues = DashMap<ChainId, Weak<ChainQueue>>;
+
+/// The requests of a chain: the one being executed, holding the lock, and the ones waiting.
+#[derive(Default)]
+struct ChainQueue {
+    lock: Arc<Mutex<()>>,
+    holder: std::sync::Mutex<Option<Holder>>,
+    waiters: AtomicUsize,
+}
+
+/// The request being executed for a chain.
+#[derive(Clone, Copy)]
+struct Holder {
+    request: &'static str,
+    since: Instant,
+}
+
+/// The thresholds at which the scheduler reports the requests waiting for their chain.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ChainLockDiagnostics {
+    /// A warning is logged when a request holds its chain, or waits for it, longer than this.
+    pub(crate) warn_after: Option<Duration>,
+    /// A request fails when it has waited this long for its chain.
+    pub(crate) abort_after: Option<Duration>,
+}
+
+impl ChainLockDiagnostics {
+    /// Returns whether any of the thresholds is set.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.warn_after.is_some() || self.abort_after.is_some()
+    }
+}
+
+/// A description of a chain that a request waited for too long.
+#[derive(Clone, Debug)]
+pub(crate) struct ChainLockReport {
+    chain_id: ChainId,
+    waited: Duration,
+    holder: Option<(&'static str, Duration)>,
+    waiters: usize,
+}
+
+impl fmt::Display for ChainLockReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "waited {:?} for chain {}", self.waited, self.chain_id)?;
+        if let Some((request, held_for)) = self.holder {
+            write!(f, ", held by a {request} request for {held_for:?}")?;
+        }
+        write!(f, ", with {} requests waiting", self.waiters)
+    }
+}
+
+/// A scheduler shared by all the clones of a worker.
+pub(crate) struct ChainScheduler {
+    queues: Arc<ChainQueues>,
+    slots: Arc<Semaphore>,
+    diagnostics: ChainLockDiagnostics,
+}
+
+/// The right to execute a request for a chain. The next request for the same chain, and
+/// possibly for another chain, can start when it is dropped.
+pub(crate) struct ChainTurn {
+    chain_id: ChainId,
+    queues: Arc<ChainQueues>,
+    _slot: OwnedSemaphorePermit,
+    queue: Option<Arc<ChainQueue>>,
+    lock: Option<OwnedMutexGuard<()>>,
+    warn_after: Option<Duration>,
+}
+
+/// Counts a request among the waiters of a chain while it exists.
+struct Waiting<'a>(&'a ChainQueue);
+
+impl<'a> Waiting<'a> {
+    fn new(queue: &'a ChainQueue) -> Self {
+        queue.waiters.fetch_add(1, Ordering::Relaxed);
+        Waiting(queue)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.waiters.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ChainScheduler {
+    /// Creates a scheduler executing requests for at most `max_concurrent_chains` chains at
+    /// once.
+    pub(crate) fn new(max_concurrent_chains: NonZeroUsize) -> Self {
+        ChainScheduler {
+            queues: Arc::default(),
+            slots: Arc::new(Semaphore::new(max_concurrent_chains.get())),
+            diagnostics: ChainLockDiagnostics::default(),
+        }
+    }
+
+    /// Creates a scheduler that only executes the requests of each chain one at a time.
+    pub(crate) fn unbounded() -> Self {
+        Self::new(NonZeroUsize::new(Semaphore::MAX_PERMITS).expect("the maximum is positive"))
+    }
+
+    /// Returns a scheduler reporting the requests that wait for their chain as configured.
+    pub(crate) fn with_diagnostics(mut self, diagnostics: ChainLockDiagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Waits until a `request` for `chain_id` can be executed.
+    ///
+    /// Fails if the request waited for the chain longer than the configured limit.
+    pub(crate) async fn schedule(
+        &self,
+        chain_id: ChainId,
+        request: &'static str,
+    ) -> Result<ChainTurn, ChainLockReport> {
+        #[cfg(with_metrics)]
+        let _latency = CHAIN_SCHEDULER_LATENCY.measure_latency();
+        let queue = self.queue(chain_id);
+        let lock = self.lock(chain_id, &queue).await?;
+        let slot = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        *queue.holder.lock().unwrap() = Some(Holder {
+            request,
+            since: Instant::now(),
+        });
+        Ok(ChainTurn {
+            chain_id,
+            queues: self.queues.clone(),
+            _slot: slot,
+            queue: Some(queue),
+            lock: Some(lock),
+            warn_after: self.diagnostics.warn_after,
+        })
+    }
+
+    /// Waits for the lock of the chain's `queue`, logging a warning every time the request
+    /// has waited for the warning threshold, until the abort threshold.
+    async fn lock(
+        &self,
+        chain_id: ChainId,
+        queue: &ChainQueue,
+    ) -> Result<OwnedMutexGuard<()>, ChainLockReport> {
+        let ChainLockDiagnostics {
+            warn_after,
+            abort_after,
+        } = self.diagnostics;
+        let mut lock = pin!(queue.lock.clone().lock_owned());
+        let Some(interval) = warn_after.into_iter().chain(abort_after).min() else {
+            return Ok(lock.await);
+        };
+        let _waiting = Waiting::new(queue);
+        let start = Instant::now();
+        loop {
+            let waited = start.elapsed();
+            let delay = match abort_after {
+                Some(abort_after) => interval.min(abort_after.saturating_sub(waited)),
+                None => interval,
+            };
+            if let Ok(lock) = timeout(delay, &mut lock).await {
+                return Ok(lock);
+            }
+            let report = queue.report(chain_id, start.elapsed());
+            if abort_after.is_some_and(|abort_after| report.waited >= abort_after) {
+                warn!("Aborting a request that {report}");
+                return Err(report);
+            }
+            warn!("A request {report}");
+        }
+    }
+
+    /// Returns the chain's queue, creating it if no request is pending.
+    fn queue(&self, chain_id: ChainId) -> Arc<ChainQueue> {
+        let mut entry = self.queues.entry(chain_id).or_default();
+        match entry.upgrade() {
+            Some(queue) => queue,
+            None => {
+                let queue = Arc::new(ChainQueue::default());
+                *entry = Arc::downgrade(&queue);
+                queue
+            }
+        }
+    }
+
+    /// Returns the number of chains with a pending request.
+    #[cfg(test)]
+    pub(crate) fn pending_chains(&self) -> usize {
+        self.queues.len()
+    }
+}
+
+impl ChainQueue {
+    /// Describes the state of the queue to a request that waited for it.
+    fn report(&self, chain_id: ChainId, waited: Duration) -> ChainLockReport {
+        let holder = *self.holder.lock().unwrap();
+        ChainLockReport {
+            chain_id,
+            waited,
+            holder: holder.map(|holder| (holder.request, holder.since.elapsed())),
+            waiters: self.waiters.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for ChainTurn {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            if let Some(holder) = queue.holder.lock().unwrap().take() {
+                let held_for = holder.since.elapsed();
+                if self
+                    .warn_after
+                    .is_some_and(|warn_after| held_for > warn_after)
+                {
+                    warn!(
+                        "A {} request held chain {} for {held_for:?}",
+                        holder.request, self.chain_id
+                    );
+                }
+            }
+        }
+        self.lock.take();
+        self.queues
+            .remove_if(&self.chain_id, |_, queue| queue.strong_count() == 0);
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A bounded cache of the chain states loaded by a worker.
+
+use std::{
+    any::Any,
+    iter,
+    marker::PhantomData,
+    num::NonZeroUsize,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use linera_base::{identifiers::ChainId, time::Instant};
+use linera_chain::ChainStateView;
+use linera_storage::Storage;
+use linera_views::views::{RootView, View, ViewError};
+use lru::LruCache;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::IntCounterVec,
+};
+
+use crate::load_monitor::LoadMonitor;
+
+#[cfg(with_metrics)]
+static CHAIN_STATE_CACHE_HIT: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "chain_state_cache_hit",
+        "Number of chain states found in the worker's cache",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static CHAIN_STATE_CACHE_MISS: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "chain_state_cache_miss",
+        "Number of chain states loaded from storage by the worker's cache",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
+/// The slot of one chain in the cache. It is empty until the chain state is loaded.
+type Slot<S> = Mutex<Option<ChainStateView<<S as Storage>::Context>>>;
+
+/// A bounded LRU cache of chain states, shared by all the clones of a worker.
+///
+/// A cached chain state keeps the storage lock of its chain. The cache must therefore only be
+/// used by a worker that has exclusive access to its chains, e.g. a validator shard.
+pub(crate) struct ChainStateCache<S> {
+    /// The slots of the most recently used chains. They are type-erased so that the worker
+    /// type does not need a `Storage` bound.
+    slots: std::sync::Mutex<LruCache<ChainId, Arc<dyn Any + Send + Sync>>>,
+    _storage: PhantomData<fn() -> S>,
+}
+
+impl<S> ChainStateCache<S> {
+    /// Creates an empty cache holding at most `capacity` chain states.
+    pub(crate) fn new(capacity: NonZeroUsize) -> Self {
+        ChainStateCache {
+            slots: std::sync::Mutex::new(LruCache::new(capacity)),
+            _storage: PhantomData,
+        }
+    }
+}
+
+impl<S> ChainStateCache<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
+{
+    /// Returns the chain state of `chain_id`, loading it from `storage` if it is not cached,
+    /// in which case the latency of the storage is recorded in the `load` monitor.
+    ///
+    /// The chain state is locked until the returned value is dropped.
+    pub(crate) async fn load(
+        &self,
+        storage: &S,
+        chain_id: ChainId,
+        load: &LoadMonitor,
+    ) -> Result<CachedChainState<S>, ViewError> {
+        let mut slot = self.slot(chain_id).lock_owned().await;
+        if slot.is_some() {
+            #[cfg(with_metrics)]
+            CHAIN_STATE_CACHE_HIT.with_label_values(&[]).inc();
+        } else {
+            #[cfg(with_metrics)]
+            CHAIN_STATE_CACHE_MISS.with_label_values(&[]).inc();
+            let start = Instant::now();
+            *slot = Some(storage.load_chain(chain_id).await?);
+            load.record_storage_load(start.elapsed());
+        }
+        Ok(CachedChainState(slot))
+    }
+
+    /// Removes the chain state of `chain_id` from the cache, waiting until it is unlocked.
+    pub(crate) async fn evict(&self, chain_id: ChainId) {
+        let slot = self.slots.lock().unwrap().pop(&chain_id);
+        if let Some(slot) = slot {
+            *Self::downcast(slot).lock().await = None;
+        }
+    }
+
+    /// Removes all the chain states from the cache, waiting until each of them is unlocked.
+    pub(crate) async fn clear(&self) {
+        let slots = {
+            let mut slots = self.slots.lock().unwrap();
+            iter::from_fn(|| slots.pop_lru())
+                .map(|(_, slot)| slot)
+                .collect::<Vec<_>>()
+        };
+        for slot in slots {
+            *Self::downcast(slot).lock().await = None;
+        }
+    }
+
+    /// Returns the slot of `chain_id`, creating an empty one if needed.
+    ///
+    /// A least recently used slot that is pushed out by this is simply dropped once its
+    /// current user, if any, is done with it.
+    fn slot(&self, chain_id: ChainId) -> Arc<Slot<S>> {
+        let slot = self
+            .slots
+            .lock()
+            .unwrap()
+            .get_or_insert(chain_id, || {
+                let slot: Arc<dyn Any + Send + Sync> = Arc::new(Slot::<S>::new(None));
+                slot
+            })
+            .clone();
+        Self::downcast(slot)
+    }
+
+    fn downcast(slot: Arc<dyn Any + Send + Sync>) -> Arc<Slot<S>> {
+        slot.downcast()
+            .expect("chain state cache slots should have the storage's context type")
+    }
+}
+
+/// A chain state checked out from a [`ChainStateCache`], or loaded directly from storage.
+///
+/// Changes that were not saved when this is dropped are rolled back, so that the next user of
+/// the chain state only sees what was persisted.
+pub(crate) struct CachedChainState<S>(OwnedMutexGuard<Option<ChainStateView<S::Context>>>)
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>;
+
+impl<S> CachedChainState<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
+{
+    /// Wraps a chain state that is not shared with a cache.
+    pub(crate) fn uncached(chain: ChainStateView<S::Context>) -> Self {
+        let slot = Arc::new(Slot::<S>::new(Some(chain)));
+        CachedChainState(
+            slot.try_lock_owned()
+                .expect("a new slot should not be locked"),
+        )
+    }
+
+    /// Saves the chain state. On failure, the chain state is evicted from the cache, since
+    /// it may no longer match the storage.
+    pub(crate) async fn save(&mut self) -> Result<(), ViewError> {
+        let result = self.deref_mut().save().await;
+        if result.is_err() {
+            *self.0 = None;
+        }
+        result
+    }
+}
+
+impl<S> Deref for CachedChainState<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
+{
+    type Target = ChainStateView<S::Context>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+            .as_ref()
+            .expect("a checked-out chain state should be loaded")
+    }
+}
+
+impl<S> DerefMut for CachedChainState<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+            .as_mut()
+            .expect("a checked-out chain state should be loaded")
+    }
+}
+
+impl<S> Drop for CachedChainState<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
+{
+    fn drop(&mut self) {
+        if let Some(chain) = self.0.as_mut() {
+            chain.rollback();
+        }
+    }
+}
@@ -19,35 +19,44 @@ use futures::{
 use linera_base::{
     abi::Abi,
     crypto::{CryptoHash, KeyPair, PublicKey},
-    data_types::{Amount, ApplicationPermissions, ArithmeticError, BlockHeight, Round, Timestamp},
+    data_types::{
+        Amount, ApplicationPermissions, ArithmeticError, BlockHeight, Round, SoftwareVersion,
+        TimeDelta, Timestamp,
+    },
     ensure,
-    identifiers::{Account, ApplicationId, BytecodeId, ChainId, MessageId, Owner},
+    identifiers::{
+        Account, ApplicationId, Blob, BlobId, BytecodeId, ChainDescription, ChainId, MessageId,
+        Owner,
+    },
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_chain::{
     data_types::{
-        Block, BlockAndRound, BlockProposal, Certificate, CertificateValue, ExecutedBlock,
-        HashedCertificateValue, IncomingMessage, LiteCertificate, LiteVote, MessageAction,
+        Block, BlockAndRound, BlockProposal, Certificate, CertificateValue, EquivocationEvidence,
+        ExecutedBlock, HashedCertificateValue, IncomingMessage, LiteCertificate, LiteVote,
+        MessageAction, TransactionOutcome,
     },
     ChainError, ChainExecutionContext, ChainStateView,
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
     system::{
-        AdminOperation, OpenChainConfig, Recipient, SystemChannel, SystemOperation, UserData,
-        CREATE_APPLICATION_MESSAGE_INDEX, OPEN_CHAIN_MESSAGE_INDEX, PUBLISH_BYTECODE_MESSAGE_INDEX,
+        AdminOperation, CommitteeChange, CommitteeProposal, FeeSource, OpenChainConfig, Recipient,
+        SystemChannel, SystemOperation, UserData, CREATE_APPLICATION_MESSAGE_INDEX,
+        OPEN_CHAIN_MESSAGE_INDEX, PUBLISH_BYTECODE_MESSAGE_INDEX,
     },
-    Bytecode, ExecutionError, Message, Operation, Query, Response, SystemExecutionError,
-    SystemMessage, SystemQuery, SystemResponse, UserApplicationId,
+    BlockLimits, Bytecode, ExecutionError, Message, Operation, Query, Response,
+    SystemExecutionError, SystemMessage, SystemQuery, SystemResponse, UserApplicationId,
 };
 use linera_storage::Storage;
 use linera_views::views::ViewError;
 use lru::LruCache;
 use serde::Serialize;
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
+    committee_cache::CommitteeCache,
     data_types::{
         BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse, ClientOutcome, RoundTimeout,
     },
@@ -58,6 +67,7 @@ use crate::{
     },
     notifier::Notifier,
     updater::{communicate_with_quorum, CommunicateAction, CommunicationError, ValidatorUpdater},
+    validator_scores::{ValidatorReputation, ValidatorScores},
     worker::{
         DeliveryNotifiers, Notification, Reason, WorkerError, WorkerState, DEFAULT_VALUE_CACHE_SIZE,
     },
@@ -67,6 +77,13 @@ use crate::{
 #[path = "unit_tests/client_tests.rs"]
 mod client_tests;
 
+/// The maximum number of ancestors of a sender chain that are downloaded to verify its blocks.
+const MAX_SENDER_CHAIN_ANCESTORS: usize = 16;
+
+/// The default number of times a block is rebuilt on top of blocks that other clients
+/// committed at the same height, before giving up.
+pub const DEFAULT_MAX_CONFLICT_RETRIES: usize = 5;
+
 /// A builder that creates `ChainClients` which share the cache and notifiers.
 pub struct ChainClientBuilder<ValidatorNodeProvider> {
     /// How to talk to the validators.
@@ -75,6 +92,10 @@ pub struct ChainClientBuilder<ValidatorNodeProvider> {
     max_pending_messages: usize,
     /// The policy for automatically handling incoming messages.
     message_policy: MessagePolicy,
+    /// How long to keep retrying a pending block before abandoning it, if at all.
+    proposal_ttl: Option<TimeDelta>,
+    /// How many times to rebuild a block if other clients commit blocks at the same height.
+    max_conflict_retries: usize,
     /// Whether to block on cross-chain message delivery.
     cross_chain_message_delivery: CrossChainMessageDelivery,
     /// Cached values by hash.
@@ -84,6 +105,10 @@ pub struct ChainClientBuilder<ValidatorNodeProvider> {
     delivery_notifiers: Arc<tokio::sync::Mutex<DeliveryNotifiers>>,
     /// References to clients waiting for chain notifications.
     notifier: Arc<Notifier<Notification>>,
+    /// The scores of the validators that certificates are downloaded from.
+    validator_scores: Arc<ValidatorScores>,
+    /// The committees of the known epochs and the certificates verified recently.
+    committees: Arc<CommitteeCache>,
 }
 
 impl<ValidatorNodeProvider: Clone> ChainClientBuilder<ValidatorNodeProvider> {
@@ -100,10 +125,14 @@ impl<ValidatorNodeProvider: Clone> ChainClientBuilder<ValidatorNodeProvider> {
             validator_node_provider,
             max_pending_messages,
             message_policy: MessagePolicy::Accept,
+            proposal_ttl: None,
+            max_conflict_retries: DEFAULT_MAX_CONFLICT_RETRIES,
             cross_chain_message_delivery,
             recent_values,
             delivery_notifiers: Arc::new(tokio::sync::Mutex::new(DeliveryNotifiers::default())),
             notifier: Arc::new(Notifier::default()),
+            validator_scores: Arc::default(),
+            committees: Arc::default(),
         }
     }
 
@@ -113,6 +142,36 @@ impl<ValidatorNodeProvider: Clone> ChainClientBuilder<ValidatorNodeProvider> {
         self
     }
 
+    /// Returns this builder with the given proposal TTL: Pending blocks that are not
+    /// confirmed within that time are abandoned, if it is safe to do so.
+    pub fn with_proposal_ttl(mut self, proposal_ttl: Option<TimeDelta>) -> Self {
+        self.proposal_ttl = proposal_ttl;
+        self
+    }
+
+    /// Returns this builder with the given number of times a block is rebuilt and proposed
+    /// again if other clients commit blocks to the same chain first.
+    pub fn with_max_conflict_retries(mut self, max_conflict_retries: usize) -> Self {
+        self.max_conflict_retries = max_conflict_retries;
+        self
+    }
+
+    /// Returns this builder with the reputations of the validators learned in previous
+    /// sessions, e.g. kept in a wallet.
+    pub fn with_validator_reputations(
+        mut self,
+        reputations: impl IntoIterator<Item = (ValidatorName, ValidatorReputation)>,
+    ) -> Self {
+        self.validator_scores = Arc::new(ValidatorScores::from_reputations(reputations));
+        self
+    }
+
+    /// Returns the reputations of the validators, including what the chain clients learned
+    /// in this session.
+    pub fn validator_reputations(&self) -> BTreeMap<ValidatorName, ValidatorReputation> {
+        self.validator_scores.reputations()
+    }
+
     /// Creates a new `ChainClient`.
     #[allow(clippy::too_many_arguments)]
     pub fn build<Storage>(
@@ -137,8 +196,10 @@ impl<ValidatorNodeProvider: Clone> ChainClientBuilder<ValidatorNodeProvider> {
             self.delivery_notifiers.clone(),
         )
         .with_allow_inactive_chains(true)
-        .with_allow_messages_from_deprecated_epochs(true);
-        let node_client = LocalNodeClient::new(state, self.notifier.clone());
+        .with_allow_messages_from_deprecated_epochs(true)
+        .with_committee_cache(self.committees.clone());
+        let node_client = LocalNodeClient::new(state, self.notifier.clone())
+            .with_validator_scores(self.validator_scores.clone());
         ChainClient {
             chain_id,
             known_key_pairs,
@@ -146,13 +207,17 @@ impl<ValidatorNodeProvider: Clone> ChainClientBuilder<ValidatorNodeProvider> {
             admin_id,
             max_pending_messages: self.max_pending_messages,
             message_policy: self.message_policy,
+            proposal_ttl: self.proposal_ttl,
+            max_conflict_retries: self.max_conflict_retries,
             cross_chain_message_delivery: self.cross_chain_message_delivery,
             received_certificate_trackers: HashMap::new(),
+            message_decisions: HashMap::new(),
             block_hash,
             timestamp,
             next_block_height,
             pending_block,
             node_client,
+            committees: self.committees.clone(),
         }
     }
 }
@@ -172,6 +237,18 @@ pub enum MessagePolicy {
     Ignore,
 }
 
+/// The owner's decision about a specific incoming message, taking precedence over the
+/// [`MessagePolicy`], e.g. to get rid of unwanted messages that would otherwise be accepted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MessageDecision {
+    /// Include the message in the next block without executing it. Tracked messages bounce
+    /// back to their sender. Protected messages cannot be rejected.
+    Reject,
+    /// Leave the message in the inbox. Only messages that are neither tracked nor protected,
+    /// and don't carry a grant, can be skipped.
+    Skip,
+}
+
 impl MessagePolicy {
     fn is_ignore(&self) -> bool {
         matches!(self, Self::Ignore)
@@ -182,6 +259,43 @@ impl MessagePolicy {
     }
 }
 
+/// A block assembled and executed locally without proposing it, to check it against the
+/// limits of the current committee before the validators reject it.
+#[derive(Clone, Debug)]
+pub struct BlockPreview {
+    /// The block, without the incoming messages that would be rejected if it was executed.
+    pub block: Block,
+    /// The size of the serialized block, in bytes.
+    pub size: u64,
+    /// The limits on the contents of a block.
+    pub limits: BlockLimits,
+    /// The maximum fuel that the applications can use in a block.
+    pub maximum_fuel_per_block: u64,
+    /// The fuel used by the applications, if the block could be executed.
+    pub fuel_used: Option<u64>,
+    /// The fees paid by the block, if it could be executed.
+    pub fees: Option<Amount>,
+}
+
+impl BlockPreview {
+    /// Returns whether the number of operations, incoming messages and the size of the block
+    /// are within the limits. Blocks exceeding them are not executed.
+    pub fn fits_contents(&self) -> bool {
+        self.block.operations.len() as u64 <= self.limits.maximum_operations
+            && self.block.incoming_messages.len() as u64 <= self.limits.maximum_incoming_messages
+            && self.size <= self.limits.maximum_size
+    }
+
+    /// Returns whether the block would be accepted by the validators, as far as its limits
+    /// are concerned.
+    pub fn fits_limits(&self) -> bool {
+        self.fits_contents()
+            && self
+                .fuel_used
+                .map_or(false, |fuel| fuel <= self.maximum_fuel_per_block)
+    }
+}
+
 /// Client to operate a chain by interacting with validators and the given local storage
 /// implementation.
 /// * The chain being operated is called the "local chain" or just the "chain".
@@ -210,13 +324,23 @@ pub struct ChainClient<ValidatorNodeProvider, Storage> {
     max_pending_messages: usize,
     /// The policy for automatically handling incoming messages.
     message_policy: MessagePolicy,
+    /// How long to keep retrying a pending block before abandoning it, if at all.
+    proposal_ttl: Option<TimeDelta>,
+    /// How many times to rebuild a block if other clients commit blocks at the same height.
+    max_conflict_retries: usize,
     /// Whether to block on cross-chain message delivery.
     cross_chain_message_delivery: CrossChainMessageDelivery,
-    /// Support synchronization of received certificates.
+    /// Support synchronization of received certificates. These are also kept in the local
+    /// storage, so that a restarted client doesn't download the received logs again.
     received_certificate_trackers: HashMap<ValidatorName, u64>,
+    /// The decisions about specific incoming messages, taking precedence over the policy.
+    message_decisions: HashMap<MessageId, MessageDecision>,
     /// Local node to manage the execution state and the local storage of the chains that we are
     /// tracking.
     node_client: LocalNodeClient<Storage>,
+    /// The committees of the known epochs and the certificates verified recently, shared
+    /// with the local node.
+    committees: Arc<CommitteeCache>,
 }
 
 /// Error type for [`ChainClient`].
@@ -234,6 +358,9 @@ pub enum ChainClientError {
     #[error("JSON (de)serialization error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("BCS (de)serialization error: {0}")]
+    BcsError(#[from] bcs::Error),
+
     #[error("Chain operation failed: {0}")]
     ChainError(#[from] ChainError),
 
@@ -269,6 +396,67 @@ pub enum ChainClientError {
 
     #[error("Found several possible identities to interact with chain {0}")]
     FoundMultipleKeysForChain(ChainId),
+
+    #[error("Validator {0} is not in the current committee")]
+    UnknownValidator(ValidatorName),
+
+    #[error("Not enough validators answered to synchronize chain {0}")]
+    NotEnoughValidatorResponses(ChainId),
+
+    #[error("Message {0} is tracked, protected or carries a grant, and cannot be skipped")]
+    CannotSkipMessage(MessageId),
+
+    #[error("Message {0} is protected and cannot be rejected")]
+    CannotRejectMessage(MessageId),
+
+    #[error(
+        "Other clients kept committing blocks to chain {chain_id} first; \
+         gave up after {attempts} attempts"
+    )]
+    TooManyConflicts { chain_id: ChainId, attempts: usize },
+
+    #[error(
+        "This client runs version {version} of the software, but the admin chain requires at \
+         least version {minimum}"
+    )]
+    OutdatedSoftware {
+        version: SoftwareVersion,
+        minimum: SoftwareVersion,
+    },
+}
+
+impl ChainClientError {
+    /// Returns whether the error may be caused by another client committing a block at the
+    /// same height first, e.g. because the validators rejected our proposal.
+    fn may_be_height_conflict(&self) -> bool {
+        match self {
+            ChainClientError::CommunicationError(_) | ChainClientError::BlockProposalError(_) => {
+                true
+            }
+            ChainClientError::LocalNodeError(LocalNodeError::WorkerError(
+                WorkerError::ChainError(error),
+            )) => matches!(
+                **error,
+                ChainError::UnexpectedBlockHeight { .. } | ChainError::UnexpectedPreviousBlockHash
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the error may be caused by the validators having moved to a committee
+    /// that is not known locally yet.
+    fn may_be_stale_committee(&self) -> bool {
+        match self {
+            ChainClientError::CommitteeSynchronizationError
+            | ChainClientError::CommunicationError(CommunicationError::Trusted(
+                NodeError::InvalidEpoch { .. },
+            )) => true,
+            ChainClientError::CommunicationError(CommunicationError::Sample(errors)) => errors
+                .iter()
+                .any(|(error, _)| matches!(error, NodeError::InvalidEpoch { .. })),
+            _ => false,
+        }
+    }
 }
 
 impl From<Infallible> for ChainClientError {
@@ -296,9 +484,21 @@ impl<P, S> ChainClient<P, S> {
         self.next_block_height
     }
 
+    /// Sets how to handle the incoming message `message_id` in the next blocks, instead of
+    /// following the message policy.
+    pub fn set_message_decision(&mut self, message_id: MessageId, decision: MessageDecision) {
+        self.message_decisions.insert(message_id, decision);
+    }
+
     pub fn pending_block(&self) -> &Option<Block> {
         &self.pending_block
     }
+
+    /// Returns how many entries of the received log of this chain were processed, for each
+    /// validator.
+    pub fn received_certificate_trackers(&self) -> &HashMap<ValidatorName, u64> {
+        &self.received_certificate_trackers
+    }
 }
 
 enum ReceiveCertificateMode {
@@ -329,6 +529,20 @@ where
         self.node_client.subscribe(vec![self.chain_id]).await
     }
 
+    /// Notifies the subscribers to this client's chain of its latest block, e.g. after it was
+    /// refreshed from the validators. Does nothing if the chain has no blocks yet.
+    pub async fn notify_latest_block(&mut self) {
+        let (Some(hash), Ok(height)) = (self.block_hash, self.next_block_height.try_sub_one())
+        else {
+            return;
+        };
+        let notification = Notification {
+            chain_id: self.chain_id,
+            reason: Reason::NewBlock { height, hash },
+        };
+        self.node_client.notify(&[notification]).await;
+    }
+
     /// Returns the storage client used by this client's local node.
     pub async fn storage_client(&self) -> S {
         self.node_client.storage_client().await
@@ -350,6 +564,16 @@ where
         Ok(response.info)
     }
 
+    /// Obtains the basic `ChainInfo` data for the local chain, with a summary of the chain
+    /// manager's consensus state.
+    pub async fn chain_info_with_manager_status(
+        &mut self,
+    ) -> Result<Box<ChainInfo>, LocalNodeError> {
+        let query = ChainInfoQuery::new(self.chain_id).with_manager_status();
+        let response = self.node_client.handle_chain_info_query(query).await?;
+        Ok(response.info)
+    }
+
     /// Obtains up to `self.max_pending_messages` pending messages for the local chain.
     ///
     /// Messages known to be redundant are filtered out: A `RegisterApplications` message whose
@@ -388,14 +612,45 @@ where
         if self.message_policy.is_ignore() {
             return Ok(pending_messages); // Ignore messages other than OpenChain.
         }
+        let max_pending_messages = usize::try_from(info.block_limits.maximum_incoming_messages)
+            .map_or(self.max_pending_messages, |maximum| {
+                maximum.min(self.max_pending_messages)
+            });
+        // The next block's timestamp is at least the current time, so messages expired by now
+        // can only be rejected.
+        let local_time = self.storage_client().await.clock().current_time();
         for mut message in requested_pending_messages {
-            if pending_messages.len() >= self.max_pending_messages {
+            if pending_messages.len() >= max_pending_messages {
                 tracing::warn!(
                     "Limiting block to {} incoming messages",
-                    self.max_pending_messages
+                    max_pending_messages
                 );
                 break;
             }
+            match self.message_decisions.get(&message.id()) {
+                Some(MessageDecision::Skip) => {
+                    ensure!(
+                        message.event.is_skippable(),
+                        ChainClientError::CannotSkipMessage(message.id())
+                    );
+                    continue;
+                }
+                Some(MessageDecision::Reject) => {
+                    ensure!(
+                        !message.event.is_protected(),
+                        ChainClientError::CannotRejectMessage(message.id())
+                    );
+                    message.action = MessageAction::Reject;
+                    pending_messages.push(message);
+                    continue;
+                }
+                None => {}
+            }
+            if message.event.is_expired(local_time) && !message.event.is_protected() {
+                message.action = MessageAction::Reject;
+                pending_messages.push(message);
+                continue;
+            }
             if self.message_policy.is_reject() {
                 if message.event.is_skippable() {
                     continue;
@@ -475,20 +730,57 @@ where
         Ok((committees, epoch))
     }
 
-    /// Obtains the validators trusted by the local chain.
-    async fn validator_nodes(&mut self) -> Result<Vec<(ValidatorName, P::Node)>, ChainClientError> {
-        match self.local_committee().await {
-            Ok(committee) => Ok(self.validator_node_provider.make_nodes(&committee)?),
-            Err(LocalNodeError::InactiveChain(_)) => Ok(Vec::new()),
+    /// Obtains the latest committee known to the local chain or its admin chain, and its
+    /// epoch. This is the committee that requests should be sent to, even if the local chain
+    /// has not migrated to it yet.
+    async fn latest_committee(&mut self) -> Result<(Epoch, Committee), LocalNodeError> {
+        let (epoch, mut committees) = self.epoch_and_committees(self.chain_id).await?;
+        let epoch = epoch.ok_or(LocalNodeError::InactiveChain(self.chain_id))?;
+        if let Ok((Some(admin_epoch), mut admin_committees)) =
+            self.epoch_and_committees(self.admin_id).await
+        {
+            if admin_epoch > epoch {
+                if let Some(committee) = admin_committees.remove(&admin_epoch) {
+                    return Ok((admin_epoch, committee));
+                }
+            }
+        }
+        let committee = committees
+            .remove(&epoch)
+            .ok_or(LocalNodeError::InactiveChain(self.chain_id))?;
+        Ok((epoch, committee))
+    }
+
+    /// Obtains the latest committee known to the local chain or its admin chain, together
+    /// with its validators, or `None` if the local chain is not active.
+    async fn validator_committee(
+        &mut self,
+    ) -> Result<Option<(Committee, Vec<(ValidatorName, P::Node)>)>, ChainClientError> {
+        match self.latest_committee().await {
+            Ok((_, committee)) => {
+                self.node_client.update_validator_stakes(&committee);
+                let nodes = self.validator_node_provider.make_nodes(&committee)?;
+                Ok(Some((committee, nodes)))
+            }
+            Err(LocalNodeError::InactiveChain(_)) => Ok(None),
             Err(LocalNodeError::WorkerError(WorkerError::ChainError(error)))
                 if matches!(*error, ChainError::InactiveChain(_)) =>
             {
-                Ok(Vec::new())
+                Ok(None)
             }
             Err(e) => Err(e.into()),
         }
     }
 
+    /// Obtains the validators of the latest committee known to the local chain or its admin
+    /// chain.
+    async fn validator_nodes(&mut self) -> Result<Vec<(ValidatorName, P::Node)>, ChainClientError> {
+        Ok(self
+            .validator_committee()
+            .await?
+            .map_or_else(Vec::new, |(_, nodes)| nodes))
+    }
+
     /// Obtains the current epoch of the local chain.
     async fn epoch(&mut self) -> Result<Epoch, LocalNodeError> {
         self.chain_info()
@@ -534,6 +826,67 @@ where
         Ok(self.key_pair().await?.public())
     }
 
+    /// Checks whether the validators report a newer epoch for the admin chain than the one
+    /// known locally. If so, downloads the new blocks of the admin chain, verifying each of
+    /// them against the committees it already trusts, so that the next requests are sent to
+    /// the new committee. Returns the epoch of the new committee, if one was found.
+    pub async fn synchronize_committees(&mut self) -> Result<Option<Epoch>, ChainClientError> {
+        let nodes = self.validator_nodes().await?;
+        if nodes.is_empty() {
+            // The local chain is not active, so there is no committee to compare with.
+            return Ok(None);
+        }
+        let (_, max_epoch) = self.known_committees().await?;
+        let admin_id = self.admin_id;
+        let mut responses =
+            future::join_all(nodes.iter().cloned().map(|(name, mut node)| async move {
+                let query = ChainInfoQuery::new(admin_id);
+                let response = node.handle_chain_info_query(query).await.ok()?;
+                Some((name, response))
+            }))
+            .await
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        let batch = responses.iter().map(|(name, response)| (*name, response));
+        if ChainInfoResponse::check_batch(batch).is_err() {
+            responses.retain(|(name, response)| response.check(*name).is_ok());
+        }
+        // The reported epochs are not trusted: only certificates are.
+        if responses
+            .iter()
+            .filter_map(|(_, response)| response.info.epoch)
+            .all(|epoch| epoch <= max_epoch)
+        {
+            return Ok(None);
+        }
+        self.node_client
+            .synchronize_chain_state(nodes, admin_id)
+            .await?;
+        let (_, new_max_epoch) = self.known_committees().await?;
+        if new_max_epoch <= max_epoch {
+            return Ok(None);
+        }
+        info!(
+            chain_id = %self.chain_id,
+            "Found new committee for epoch {new_max_epoch} (was {max_epoch})."
+        );
+        Ok(Some(new_max_epoch))
+    }
+
+    /// Synchronizes the committees after an epoch mismatch or an error suggesting that the
+    /// validators have moved to a new committee. Failures are only logged, since the caller
+    /// is already handling a failure.
+    async fn try_synchronize_committees(&mut self) -> Option<Epoch> {
+        match self.synchronize_committees().await {
+            Ok(epoch) => epoch,
+            Err(error) => {
+                warn!(chain_id = %self.chain_id, %error, "Failed to synchronize the committees");
+                None
+            }
+        }
+    }
+
     /// Prepares the chain for the next operation.
     async fn prepare_chain(&mut self) -> Result<Box<ChainInfo>, ChainClientError> {
         // Verify that our local storage contains enough history compared to the
@@ -660,7 +1013,7 @@ where
     ) -> Result<Certificate, ChainClientError> {
         let storage_client = self.storage_client().await;
         let nodes: Vec<_> = self.validator_node_provider.make_nodes(committee)?;
-        let ((votes_hash, votes_round), votes) = communicate_with_quorum(
+        let ((votes_hash, votes_round), mut votes) = communicate_with_quorum(
             &nodes,
             committee,
             |vote: &LiteVote| (vote.value.value_hash, vote.round),
@@ -684,10 +1037,22 @@ where
             (votes_hash, votes_round) == (value.hash(), round),
             ChainClientError::ProtocolError("Unexpected response from validators")
         );
+        if LiteVote::check_batch(&votes).is_err() {
+            // Discard the invalid votes: the remaining ones may still form a quorum.
+            votes.retain(|vote| vote.check().is_ok());
+            let weight = votes
+                .iter()
+                .map(|vote| committee.weight(&vote.validator))
+                .sum::<u64>();
+            ensure!(
+                weight >= committee.quorum_threshold(),
+                ChainClientError::ProtocolError("Validators returned invalid votes")
+            );
+        }
         // Certificate is valid because
         // * `communicate_with_quorum` ensured a sufficient "weight" of
         // (non-error) answers were returned by validators.
-        // * each answer is a vote signed by the expected validator.
+        // * each answer is a vote signed by the expected validator, as checked above.
         let certificate = LiteCertificate::try_from_votes(votes)
             .ok_or_else(|| {
                 ChainClientError::InternalError("Vote values or rounds don't match; this is a bug")
@@ -711,7 +1076,10 @@ where
         };
         let block = &executed_block.block;
         // Verify the certificate before doing any expensive networking.
-        let (committees, max_epoch) = self.known_committees().await?;
+        let (mut committees, mut max_epoch) = self.known_committees().await?;
+        if block.epoch > max_epoch && self.try_synchronize_committees().await.is_some() {
+            (committees, max_epoch) = self.known_committees().await?;
+        }
         ensure!(
             block.epoch <= max_epoch,
             ChainClientError::CommitteeSynchronizationError
@@ -720,16 +1088,24 @@ where
             .get(&block.epoch)
             .ok_or_else(|| ChainClientError::CommitteeDeprecationError)?;
         if let ReceiveCertificateMode::NeedsCheck = mode {
-            certificate.check(remote_committee)?;
+            self.committees
+                .check(&certificate, block.epoch, remote_committee)?;
         }
         // Recover history from the network. We assume that the committee that signed the
         // certificate is still active.
         let nodes: Vec<_> = self.validator_node_provider.make_nodes(remote_committee)?;
-        self.node_client
-            .download_certificates(nodes.clone(), block.chain_id, block.height)
+        self.download_sender_chain(nodes.clone(), block.chain_id, block.height)
             .await?;
-        // Process the received operations. Download required hashed certificate values if necessary.
-        if let Err(err) = self.process_certificate(certificate.clone(), vec![]).await {
+        // Process the received operations. Download required blobs and hashed certificate
+        // values if necessary.
+        let mut result = self.process_certificate(certificate.clone(), vec![]).await;
+        if let Err(LocalNodeError::WorkerError(WorkerError::BlobsNotFound(blob_ids))) = &result {
+            self.node_client
+                .download_blobs(nodes.clone(), block.chain_id, blob_ids)
+                .await?;
+            result = self.process_certificate(certificate.clone(), vec![]).await;
+        }
+        if let Err(err) = result {
             if let LocalNodeError::WorkerError(WorkerError::ApplicationBytecodesNotFound(
                 locations,
             )) = &err
@@ -767,6 +1143,70 @@ where
         Ok(())
     }
 
+    /// Downloads the blocks of a chain we received messages from, up to
+    /// `target_next_block_height`. If the chain is not known locally, first downloads the
+    /// blocks of its ancestors that created it, so that its own blocks can be verified.
+    async fn download_sender_chain(
+        &mut self,
+        nodes: Vec<(ValidatorName, P::Node)>,
+        chain_id: ChainId,
+        target_next_block_height: BlockHeight,
+    ) -> Result<Box<ChainInfo>, ChainClientError> {
+        let mut ancestors = Vec::new();
+        let mut current_id = chain_id;
+        for _ in 0..MAX_SENDER_CHAIN_ANCESTORS {
+            let info = self.node_client.local_chain_info(current_id).await?;
+            if info.description.is_some() {
+                break;
+            }
+            let Some(ChainDescription::Child(message_id)) =
+                Self::download_chain_description(&nodes, current_id).await
+            else {
+                break;
+            };
+            // The chain was opened by this message: the parent's block must be known.
+            ancestors.push((message_id.chain_id, message_id.height.try_add_one()?));
+            current_id = message_id.chain_id;
+        }
+        for (ancestor_id, next_block_height) in ancestors.into_iter().rev() {
+            debug!(%chain_id, %ancestor_id, "Downloading the ancestor of a sender chain");
+            self.node_client
+                .download_certificates(nodes.clone(), ancestor_id, next_block_height)
+                .await?;
+        }
+        Ok(self
+            .node_client
+            .download_certificates(nodes, chain_id, target_next_block_height)
+            .await?)
+    }
+
+    /// Asks the validators how a chain was created. The description is checked against the
+    /// chain ID, so a single validator's answer can be trusted.
+    async fn download_chain_description(
+        nodes: &[(ValidatorName, P::Node)],
+        chain_id: ChainId,
+    ) -> Option<ChainDescription> {
+        for (name, node) in nodes {
+            let mut node = node.clone();
+            let Ok(response) = node
+                .handle_chain_info_query(ChainInfoQuery::new(chain_id))
+                .await
+            else {
+                continue;
+            };
+            if response.check(*name).is_err() {
+                continue;
+            }
+            if let Some(description) = response.info.description {
+                if ChainId::from(description) == chain_id {
+                    return Some(description);
+                }
+            }
+        }
+        None
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn synchronize_received_certificates_from_validator<A>(
         chain_id: ChainId,
         name: ValidatorName,
@@ -775,6 +1215,7 @@ where
         max_epoch: Epoch,
         mut node: A,
         mut node_client: LocalNodeClient<S>,
+        committee_cache: Arc<CommitteeCache>,
     ) -> Result<(ValidatorName, u64, Vec<Certificate>), NodeError>
     where
         A: LocalValidatorNode + Clone + 'static,
@@ -785,6 +1226,7 @@ where
         // Responses are authenticated for accountability.
         response.check(name)?;
         let mut certificates = Vec::new();
+        let mut epochs = Vec::new();
         let mut new_tracker = tracker;
         for entry in response.info.requested_received_log {
             let query = ChainInfoQuery::new(entry.chain_id)
@@ -822,10 +1264,10 @@ where
                 break;
             }
             match committees.get(&block.epoch) {
-                Some(committee) => {
-                    // This epoch is recognized by our chain. Let's verify the
-                    // certificate.
-                    certificate.check(committee)?;
+                Some(_) => {
+                    // This epoch is recognized by our chain. The certificate is verified
+                    // below, in a batch with the others.
+                    epochs.push(block.epoch);
                     certificates.push(certificate);
                     new_tracker += 1;
                 }
@@ -842,6 +1284,12 @@ where
                 }
             }
         }
+        committee_cache.check_batch(
+            certificates
+                .iter()
+                .zip(epochs)
+                .map(|(certificate, epoch)| (certificate, epoch, &committees[&epoch])),
+        )?;
         Ok((name, new_tracker, certificates))
     }
 
@@ -875,6 +1323,30 @@ where
                 }
             })
             .or_insert(tracker);
+        if let Err(e) = self
+            .storage_client()
+            .await
+            .write_received_certificate_trackers(self.chain_id, &self.received_certificate_trackers)
+            .await
+        {
+            tracing::warn!("Failed to save the received certificate trackers: {e}");
+        }
+    }
+
+    /// Merges the received certificate trackers saved in the local storage, e.g. by a
+    /// previous run of the client, into the current ones.
+    async fn load_received_certificate_trackers(&mut self) -> Result<(), ChainClientError> {
+        let trackers = self
+            .storage_client()
+            .await
+            .read_received_certificate_trackers(self.chain_id)
+            .await
+            .map_err(LocalNodeError::from)?;
+        for (name, tracker) in trackers {
+            let current = self.received_certificate_trackers.entry(name).or_default();
+            *current = (*current).max(tracker);
+        }
+        Ok(())
     }
 
     /// Attempts to download new received certificates.
@@ -895,9 +1367,11 @@ where
             .synchronize_chain_state(nodes.clone(), self.admin_id)
             .await?;
         let node_client = self.node_client.clone();
+        let committee_cache = self.committees.clone();
         // Now we should have a complete view of all committees in the system.
         let (committees, max_epoch) = self.known_committees().await?;
         // Proceed to downloading received certificates.
+        self.load_received_certificate_trackers().await?;
         let trackers = &self.received_certificate_trackers;
         let result = communicate_with_quorum(
             &nodes,
@@ -907,6 +1381,7 @@ where
                 let tracker = *trackers.get(&name).unwrap_or(&0);
                 let committees = committees.clone();
                 let node_client = node_client.clone();
+                let committee_cache = committee_cache.clone();
                 Box::pin(Self::synchronize_received_certificates_from_validator(
                     chain_id,
                     name,
@@ -915,6 +1390,7 @@ where
                     max_epoch,
                     node,
                     node_client,
+                    committee_cache,
                 ))
             },
         )
@@ -938,6 +1414,136 @@ where
         Ok(())
     }
 
+    /// Fetches the certificates received by this chain according to the validator `name`,
+    /// skipping the first `cursor` ones, and returns them with the cursor to continue from.
+    ///
+    /// This allows several wallets using the same chain to find the incoming transfers
+    /// without downloading the whole chain. Cursors are specific to each validator. The
+    /// certificates are checked, but not processed: see `receive_certificate`.
+    pub async fn received_certificates_after(
+        &mut self,
+        name: ValidatorName,
+        cursor: u64,
+    ) -> Result<(Vec<Certificate>, u64), ChainClientError> {
+        let mut node = self
+            .validator_nodes()
+            .await?
+            .into_iter()
+            .find_map(|(validator, node)| (validator == name).then_some(node))
+            .ok_or(ChainClientError::UnknownValidator(name))?;
+        let query = ChainInfoQuery::new(self.chain_id).with_received_certificates_after(cursor);
+        let response = node.handle_chain_info_query(query).await?;
+        // Responses are authenticated for accountability.
+        response.check(name).map_err(NodeError::from)?;
+        let ChainInfo {
+            requested_received_certificates: certificates,
+            received_certificates_cursor,
+            ..
+        } = *response.info;
+        let next_cursor = received_certificates_cursor
+            .filter(|next_cursor| *next_cursor >= cursor)
+            .ok_or(NodeError::InvalidChainInfoResponse)?;
+        let (committees, max_epoch) = self.known_committees().await?;
+        let mut checks = Vec::new();
+        for certificate in &certificates {
+            let CertificateValue::ConfirmedBlock { executed_block, .. } = certificate.value()
+            else {
+                return Err(NodeError::InvalidChainInfoResponse.into());
+            };
+            let epoch = executed_block.block.epoch;
+            ensure!(
+                epoch <= max_epoch,
+                ChainClientError::CommitteeSynchronizationError
+            );
+            let committee = committees
+                .get(&epoch)
+                .ok_or(ChainClientError::CommitteeDeprecationError)?;
+            checks.push((certificate, epoch, committee));
+        }
+        self.committees.check_batch(checks)?;
+        Ok((certificates, next_cursor))
+    }
+
+    /// Reads the event log of this chain from the validator `name`, starting at the given
+    /// sequence number, and returns the outcomes of the transactions with the sequence number
+    /// to continue from.
+    ///
+    /// The log only contains confirmed blocks, and sequence numbers are the same for all
+    /// validators, so consumers that persist the returned sequence number process each
+    /// outcome exactly once, even if they miss notifications or switch validators.
+    pub async fn read_events(
+        &mut self,
+        name: ValidatorName,
+        from: u64,
+    ) -> Result<(Vec<TransactionOutcome>, u64), ChainClientError> {
+        let mut node = self
+            .validator_nodes()
+            .await?
+            .into_iter()
+            .find_map(|(validator, node)| (validator == name).then_some(node))
+            .ok_or(ChainClientError::UnknownValidator(name))?;
+        let query = ChainInfoQuery::new(self.chain_id).with_events_from(from);
+        let response = node.handle_chain_info_query(query).await?;
+        // Responses are authenticated for accountability.
+        response.check(name).map_err(NodeError::from)?;
+        let ChainInfo {
+            requested_events: events,
+            events_cursor,
+            ..
+        } = *response.info;
+        let next = events_cursor
+            .filter(|next| next.checked_sub(from) == Some(events.len() as u64))
+            .ok_or(NodeError::InvalidChainInfoResponse)?;
+        Ok((events, next))
+    }
+
+    /// Uploads to the validator `name` the certificates of this chain that it is missing,
+    /// preceded by the certificates of the sender chains that its inboxes are waiting for.
+    ///
+    /// This repairs a validator that fell behind, e.g. after an outage, without waiting for
+    /// the next block proposal to update it. Returns the next block height of the chain
+    /// according to the validator, before and after the update.
+    pub async fn sync_validator(
+        &mut self,
+        name: ValidatorName,
+    ) -> Result<(BlockHeight, BlockHeight), ChainClientError> {
+        self.synchronize_from_validators().await?;
+        let node = self
+            .validator_nodes()
+            .await?
+            .into_iter()
+            .find_map(|(validator, node)| (validator == name).then_some(node))
+            .ok_or(ChainClientError::UnknownValidator(name))?;
+        let chain_id = self.chain_id;
+        let target_height = self.chain_info().await?.next_block_height;
+        let mut updater = ValidatorUpdater {
+            name,
+            node,
+            storage: self.storage_client().await,
+        };
+        let query = ChainInfoQuery::new(chain_id);
+        let response = updater.node.handle_chain_info_query(query.clone()).await?;
+        response.check(name).map_err(NodeError::from)?;
+        let initial_height = response.info.next_block_height;
+        if initial_height < target_height {
+            info!(
+                "Validator {name} is at height {initial_height} of chain {chain_id}, \
+                 uploading the blocks up to {target_height}"
+            );
+            updater.send_chain_information_for_senders(chain_id).await?;
+            updater
+                .send_chain_information(
+                    chain_id,
+                    target_height,
+                    CrossChainMessageDelivery::Blocking,
+                )
+                .await?;
+        }
+        let response = updater.node.handle_chain_info_query(query).await?;
+        response.check(name).map_err(NodeError::from)?;
+        Ok((initial_height, response.info.next_block_height))
+    }
+
     /// Sends money.
     pub async fn transfer(
         &mut self,
@@ -975,6 +1581,54 @@ where
         .await
     }
 
+    /// Sends money to several recipients in a single operation.
+    pub async fn transfer_many(
+        &mut self,
+        owner: Option<Owner>,
+        transfers: Vec<(Recipient, Amount)>,
+        user_data: UserData,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::TransferMany {
+            owner,
+            transfers,
+            user_data,
+        }))
+        .await
+    }
+
+    /// Allows `spender` to transfer up to `amount` from `owner`'s account on this chain.
+    pub async fn approve(
+        &mut self,
+        owner: Owner,
+        spender: Owner,
+        amount: Amount,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Approve {
+            owner,
+            spender,
+            amount,
+        }))
+        .await
+    }
+
+    /// Sends money from `owner`'s account on this chain, using the allowance they gave to the
+    /// signer of the block.
+    pub async fn transfer_from(
+        &mut self,
+        owner: Owner,
+        amount: Amount,
+        recipient: Recipient,
+        user_data: UserData,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::TransferFrom {
+            owner,
+            recipient,
+            amount,
+            user_data,
+        }))
+        .await
+    }
+
     async fn process_certificate(
         &mut self,
         certificate: Certificate,
@@ -1040,6 +1694,16 @@ where
     ) -> Result<(ExecutedBlock, ChainInfoResponse), ChainClientError> {
         loop {
             let result = self.node_client.stage_block_execution(block.clone()).await;
+            if let Err(LocalNodeError::WorkerError(WorkerError::BlobsNotFound(blob_ids))) = &result
+            {
+                // Download the blobs that the applications read, and execute again.
+                let committee = self.local_committee().await?;
+                let nodes = self.validator_node_provider.make_nodes(&committee)?;
+                self.node_client
+                    .download_blobs(nodes, block.chain_id, blob_ids)
+                    .await?;
+                continue;
+            }
             if let Err(LocalNodeError::WorkerError(WorkerError::ChainError(chain_error))) = &result
             {
                 if let ChainError::ExecutionError(
@@ -1123,6 +1787,7 @@ where
             .stage_block_execution_and_discard_failing_messages(block)
             .await?;
         let block = executed_block.block.clone();
+        let mut blob_ids = executed_block.required_blob_ids();
         let hashed_value = if round.is_fast() {
             HashedCertificateValue::new_confirmed(executed_block)
         } else {
@@ -1130,10 +1795,17 @@ where
         };
         // Collect the hashed certificate values required for execution.
         let committee = self.local_committee().await?;
-        let nodes = self.validator_node_provider.make_nodes(&committee)?;
+        let nodes: Vec<_> = self.validator_node_provider.make_nodes(&committee)?;
         let values = self
             .node_client
-            .read_or_download_hashed_certificate_values(nodes, block.bytecode_locations())
+            .read_or_download_hashed_certificate_values(nodes.clone(), block.bytecode_locations())
+            .await?;
+        // Collect the blobs required for execution, and the ones containing the bytecodes in
+        // the values, for the validators that are missing them.
+        blob_ids.extend(block.bytecode_blob_ids(&values));
+        let blobs = self
+            .node_client
+            .read_or_download_blobs(nodes, block.chain_id, blob_ids)
             .await?;
         // Create the final block proposal.
         let key_pair = self.key_pair().await?;
@@ -1144,6 +1816,7 @@ where
             },
             key_pair,
             values,
+            blobs,
             validated,
         );
         // Check the final block proposal. This will be cheaper after #1401.
@@ -1191,26 +1864,60 @@ where
     }
 
     /// Executes a list of operations, without calling `prepare_chain`.
+    ///
+    /// If another block is committed at the same height first, e.g. by another client
+    /// owning the same chain, the block is rebuilt on top of it and proposed again, up to
+    /// the configured number of times.
     pub async fn execute_with_messages(
         &mut self,
         operations: Vec<Operation>,
     ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        let mut conflicts = 0;
         loop {
+            let height = self.next_block_height;
             let messages = self.pending_messages().await?;
-            match self.execute_block(messages, operations.clone()).await? {
-                ExecuteBlockOutcome::Executed(certificate) => {
+            match Box::pin(self.execute_block(messages, operations.clone())).await {
+                Ok(ExecuteBlockOutcome::Executed(certificate)) => {
                     return Ok(ClientOutcome::Committed(certificate));
                 }
-                ExecuteBlockOutcome::Conflict(certificate) => {
+                Ok(ExecuteBlockOutcome::Conflict(certificate)) => {
                     info!(
                         height = %certificate.value().height(),
                         "Another block was committed; retrying."
                     );
                 }
-                ExecuteBlockOutcome::WaitForTimeout(timeout) => {
+                Ok(ExecuteBlockOutcome::WaitForTimeout(timeout)) => {
                     return Ok(ClientOutcome::WaitForTimeout(timeout));
                 }
+                Err(error) if error.may_be_stale_committee() => {
+                    // Only retry if there is a new committee to send the next attempt to.
+                    let Some(epoch) = self.try_synchronize_committees().await else {
+                        return Err(error);
+                    };
+                    info!(%height, %epoch, "Found a new committee; retrying.");
+                }
+                Err(error) if error.may_be_height_conflict() => {
+                    // Only retry if the validators confirm that the chain has moved on. The
+                    // outdated pending block is then dropped and a new one is built.
+                    if self.synchronize_chain_state().await.is_err()
+                        || self.next_block_height <= height
+                    {
+                        return Err(error);
+                    }
+                    info!(
+                        %height,
+                        "Another client committed a block at this height; retrying."
+                    );
+                }
+                Err(error) => return Err(error),
             };
+            conflicts += 1;
+            if conflicts > self.max_conflict_retries {
+                return Err(ChainClientError::TooManyConflicts {
+                    chain_id: self.chain_id,
+                    attempts: conflicts,
+                });
+            }
         }
     }
 
@@ -1230,7 +1937,9 @@ where
         incoming_messages: Vec<IncomingMessage>,
         operations: Vec<Operation>,
     ) -> Result<ExecuteBlockOutcome, ChainClientError> {
-        match self.process_pending_block_without_prepare().await? {
+        // The futures of block proposals are large: They are boxed so that nesting them
+        // doesn't overflow the stack.
+        match Box::pin(self.process_pending_block_without_prepare()).await? {
             ClientOutcome::Committed(Some(certificate)) => {
                 return Ok(ExecuteBlockOutcome::Conflict(certificate))
             }
@@ -1242,7 +1951,7 @@ where
         let confirmed_value = self
             .set_pending_block(incoming_messages, operations)
             .await?;
-        match self.process_pending_block_without_prepare().await? {
+        match Box::pin(self.process_pending_block_without_prepare()).await? {
             ClientOutcome::Committed(Some(certificate))
                 if certificate.hash() == confirmed_value.hash() =>
             {
@@ -1266,6 +1975,9 @@ where
         incoming_messages: Vec<IncomingMessage>,
         operations: Vec<Operation>,
     ) -> Result<HashedCertificateValue, ChainClientError> {
+        if !operations.is_empty() {
+            self.check_software_version().await?;
+        }
         let timestamp = self.next_timestamp(&incoming_messages).await;
         let block = Block {
             epoch: self.epoch().await?,
@@ -1286,6 +1998,22 @@ where
         Ok(HashedCertificateValue::new_confirmed(executed_block))
     }
 
+    /// Returns an error if the admin chain requires a newer version of the software than this
+    /// client's. The admin chain itself is exempt, and so are the blocks that only receive
+    /// messages, so that the minimum version can always be lowered again.
+    async fn check_software_version(&mut self) -> Result<(), ChainClientError> {
+        if self.chain_id == self.admin_id {
+            return Ok(());
+        }
+        let version = SoftwareVersion::current();
+        match self.chain_info().await?.minimum_version {
+            Some(minimum) if version < minimum => {
+                Err(ChainClientError::OutdatedSoftware { version, minimum })
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Returns a suitable timestamp for the next block.
     ///
     /// This will usually be the current time according to the local clock, but may be slightly
@@ -1419,6 +2147,95 @@ where
         }
     }
 
+    /// Estimates the fees of a block with the given operations and the pending incoming
+    /// messages, by executing it locally without proposing it.
+    ///
+    /// Does not attempt to synchronize with validators, so the fees of the actual block may
+    /// differ if the state of the chain changes in the meantime.
+    pub async fn estimate_fees(
+        &mut self,
+        operations: Vec<Operation>,
+    ) -> Result<Amount, ChainClientError> {
+        let block = self.next_block(operations).await?;
+        let (_, fees) = self.execute_without_proposing(block).await?;
+        Ok(fees)
+    }
+
+    /// Assembles the block this client would propose next and executes it locally without
+    /// proposing it, to check it against the limits of the current committee.
+    ///
+    /// Without `operations`, this is the pending block if there is one, or a block with the
+    /// pending incoming messages. Otherwise it is a new block with the pending incoming
+    /// messages and the `operations`. The block is only executed if its contents are within
+    /// the limits.
+    ///
+    /// Does not attempt to synchronize with validators.
+    pub async fn preview_block(
+        &mut self,
+        operations: Vec<Operation>,
+    ) -> Result<BlockPreview, ChainClientError> {
+        let block = match &self.pending_block {
+            Some(block) if operations.is_empty() => block.clone(),
+            _ => self.next_block(operations).await?,
+        };
+        let policy = self.local_committee().await?.policy().clone();
+        let mut preview = BlockPreview {
+            size: bcs::serialized_size(&block)? as u64,
+            block,
+            limits: policy.block_limits(),
+            maximum_fuel_per_block: policy.maximum_fuel_per_block,
+            fuel_used: None,
+            fees: None,
+        };
+        if !preview.fits_contents() {
+            return Ok(preview);
+        }
+        let (executed_block, fees) = self.execute_without_proposing(preview.block).await?;
+        preview.size = bcs::serialized_size(&executed_block.block)? as u64;
+        preview.block = executed_block.block;
+        preview.fuel_used = Some(executed_block.outcome.fuel_used);
+        preview.fees = Some(fees);
+        Ok(preview)
+    }
+
+    /// Returns a new block at the next height with the pending incoming messages and the given
+    /// operations.
+    async fn next_block(&mut self, operations: Vec<Operation>) -> Result<Block, ChainClientError> {
+        let incoming_messages = self.pending_messages().await?;
+        let timestamp = self.next_timestamp(&incoming_messages).await;
+        Ok(Block {
+            epoch: self.epoch().await?,
+            chain_id: self.chain_id,
+            incoming_messages,
+            operations,
+            previous_block_hash: self.block_hash,
+            height: self.next_block_height,
+            authenticated_signer: Some(self.identity().await?),
+            timestamp,
+        })
+    }
+
+    /// Executes the block locally without saving the new state of the chain, and returns the
+    /// executed block with the fees it paid. Failing incoming messages are rejected.
+    async fn execute_without_proposing(
+        &mut self,
+        block: Block,
+    ) -> Result<(ExecutedBlock, Amount), ChainClientError> {
+        let epoch = block.epoch;
+        let query = ChainInfoQuery::new(self.chain_id).with_collected_fees();
+        let info = self.node_client.handle_chain_info_query(query).await?.info;
+        let (executed_block, response) = self
+            .stage_block_execution_and_discard_failing_messages(block)
+            .await?;
+        let fees_before = info.requested_collected_fees.get(&epoch);
+        let fees_after = response.info.requested_collected_fees.get(&epoch);
+        let fees = fees_after
+            .copied()
+            .unwrap_or_default()
+            .try_sub(fees_before.copied().unwrap_or_default())?;
+        Ok((executed_block, fees))
+    }
+
     /// Reads the local balance of the chain account.
     ///
     /// Does not process the inbox or attempt to synchronize with validators.
@@ -1518,7 +2335,61 @@ where
         &mut self,
     ) -> Result<Box<ChainInfo>, ChainClientError> {
         self.find_received_certificates().await?;
-        self.prepare_chain().await
+        let info = self.prepare_chain().await?;
+        match self.synchronize_chain_state().await {
+            // Synchronizing with the validators is a best effort here: the chain is already
+            // as recent as the wallet expects.
+            Err(ChainClientError::NotEnoughValidatorResponses(_)) => Ok(info),
+            result => result,
+        }
+    }
+
+    /// Synchronizes the chain with the validators, e.g. when the client starts. Queries all
+    /// of them for the chain's latest height. Then downloads the missing certificates in
+    /// parallel from several validators, and updates the local state.
+    ///
+    /// Validators may be behind or lie about the height. So the chain is synchronized up to
+    /// the highest height reported by validators with enough weight to include an honest
+    /// one.
+    pub async fn synchronize_chain_state(&mut self) -> Result<Box<ChainInfo>, ChainClientError> {
+        let chain_id = self.chain_id;
+        let Some((committee, nodes)) = self.validator_committee().await? else {
+            // The local chain is not active, so there is no committee to ask.
+            return Ok(self.chain_info().await?);
+        };
+        let mut responses =
+            future::join_all(nodes.iter().cloned().map(|(name, mut node)| async move {
+                let query = ChainInfoQuery::new(chain_id);
+                let response = node.handle_chain_info_query(query).await.ok()?;
+                Some((name, response))
+            }))
+            .await
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        let batch = responses.iter().map(|(name, response)| (*name, response));
+        if ChainInfoResponse::check_batch(batch).is_err() {
+            responses.retain(|(name, response)| response.check(*name).is_ok());
+        }
+        let mut heights = responses
+            .iter()
+            .map(|(name, response)| (response.info.next_block_height, committee.weight(name)))
+            .collect::<Vec<_>>();
+        heights.sort_by_key(|(height, _)| std::cmp::Reverse(*height));
+        let mut weight = 0;
+        let target_next_block_height = heights
+            .into_iter()
+            .find_map(|(height, votes)| {
+                weight += votes;
+                (weight >= committee.validity_threshold()).then_some(height)
+            })
+            .ok_or(ChainClientError::NotEnoughValidatorResponses(chain_id))?;
+        let info = self
+            .node_client
+            .download_certificates_in_parallel(nodes, chain_id, target_next_block_height)
+            .await?;
+        self.update_from_info(&info);
+        Ok(info)
     }
 
     /// Processes the last pending block
@@ -1551,6 +2422,28 @@ where
                 self.pending_block = None;
             }
         }
+        // Abandon the pending block if it was not confirmed within the proposal TTL. This is
+        // only safe if no block has been validated and there is no proposal in the fast round:
+        // Otherwise that block could still be confirmed, so we must keep proposing it.
+        if let Some(ttl) = self.proposal_ttl {
+            let now = self.storage_client().await.clock().current_time();
+            let is_locked = manager.highest_validated().is_some()
+                || manager
+                    .requested_proposed
+                    .as_ref()
+                    .map_or(false, |proposal| proposal.content.round.is_fast());
+            let is_expired = self
+                .pending_block
+                .as_ref()
+                .map_or(false, |block| block.timestamp.saturating_add(ttl) <= now);
+            if is_expired && !is_locked {
+                info!(
+                    height = %info.next_block_height,
+                    "Abandoning a pending block that was not confirmed within the proposal TTL."
+                );
+                self.pending_block = None;
+            }
+        }
         // If there is a validated block in the current round, finalize it.
         if let Some(certificate) = &manager.requested_locked {
             if certificate.round == manager.current_round {
@@ -1621,7 +2514,7 @@ where
             Round::SingleLeader(_) | Round::Validator(_) => manager.leader == Some(identity),
         };
         if can_propose {
-            let certificate = self.propose_block(block.clone(), round).await?;
+            let certificate = Box::pin(self.propose_block(block.clone(), round)).await?;
             Ok(ClientOutcome::Committed(Some(certificate)))
         } else {
             // TODO(#1424): Local timeout might not match validators' exactly.
@@ -1702,7 +2595,7 @@ where
                 multi_leader_rounds: ownership.multi_leader_rounds,
                 timeout_config: ownership.timeout_config,
             })];
-            match self.execute_block(messages, operations).await? {
+            match Box::pin(self.execute_block(messages, operations)).await? {
                 ExecuteBlockOutcome::Executed(certificate) => {
                     return Ok(ClientOutcome::Committed(certificate));
                 }
@@ -1734,6 +2627,38 @@ where
         .await
     }
 
+    /// Starts transferring this chain to the owner of `new_public_key`. The current owners
+    /// keep their keys until the new owner accepts the transfer.
+    pub async fn start_ownership_transfer(
+        &mut self,
+        new_public_key: PublicKey,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        let operation = SystemOperation::TransferOwnership { new_public_key };
+        self.execute_operation(operation.into()).await
+    }
+
+    /// Accepts the pending transfer of this chain to our key, removing the previous owners.
+    pub async fn accept_ownership(
+        &mut self,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::AcceptOwnership.into())
+            .await
+    }
+
+    /// Publishes a blob of data, e.g. the media of an NFT, in a block of this chain, so that
+    /// the applications can read it. Returns the ID of the blob.
+    pub async fn publish_blob(
+        &mut self,
+        bytes: Vec<u8>,
+    ) -> Result<ClientOutcome<(BlobId, Certificate)>, ChainClientError> {
+        let blob_id = self.node_client.write_blob(&Blob::new(bytes)).await?;
+        let operation = SystemOperation::PublishBlob { blob_id };
+        Ok(self
+            .execute_operation(operation.into())
+            .await?
+            .map(|certificate| (blob_id, certificate)))
+    }
+
     /// Changes the application permissions configuration on this chain.
     pub async fn change_application_permissions(
         &mut self,
@@ -1743,42 +2668,70 @@ where
         self.execute_operation(operation.into()).await
     }
 
+    /// Changes which account pays the fees of this chain's blocks.
+    pub async fn change_fee_source(
+        &mut self,
+        fee_source: FeeSource,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        let operation = SystemOperation::ChangeFeeSource(fee_source);
+        self.execute_operation(operation.into()).await
+    }
+
     /// Opens a new chain with a derived UID.
     pub async fn open_chain(
         &mut self,
         ownership: ChainOwnership,
         balance: Amount,
     ) -> Result<ClientOutcome<(MessageId, Certificate)>, ChainClientError> {
+        let outcome = self.open_chains(vec![(ownership, balance)]).await?;
+        Ok(outcome.map(|(message_ids, certificate)| (message_ids[0], certificate)))
+    }
+
+    /// Creates new chains with the given ownerships and initial balances, all in a single block.
+    /// Returns the IDs of the messages that created them, in the same order.
+    pub async fn open_chains(
+        &mut self,
+        chains: Vec<(ChainOwnership, Amount)>,
+    ) -> Result<ClientOutcome<(Vec<MessageId>, Certificate)>, ChainClientError> {
         self.prepare_chain().await?;
         loop {
             let (epoch, committees) = self.epoch_and_committees(self.chain_id).await?;
             let epoch = epoch.ok_or(LocalNodeError::InactiveChain(self.chain_id))?;
             let messages = self.pending_messages().await?;
-            let config = OpenChainConfig {
-                ownership: ownership.clone(),
-                committees,
-                admin_id: self.admin_id,
-                epoch,
-                balance,
-                application_permissions: Default::default(),
-            };
-            let operation = Operation::System(SystemOperation::OpenChain(config));
-            let certificate = match self.execute_block(messages, vec![operation]).await? {
+            let operations = chains
+                .iter()
+                .map(|(ownership, balance)| {
+                    let config = OpenChainConfig {
+                        ownership: ownership.clone(),
+                        committees: committees.clone(),
+                        admin_id: self.admin_id,
+                        epoch,
+                        balance: *balance,
+                        application_permissions: Default::default(),
+                    };
+                    Operation::System(SystemOperation::OpenChain(config))
+                })
+                .collect();
+            let certificate = match Box::pin(self.execute_block(messages, operations)).await? {
                 ExecuteBlockOutcome::Executed(certificate) => certificate,
                 ExecuteBlockOutcome::Conflict(_) => continue,
                 ExecuteBlockOutcome::WaitForTimeout(timeout) => {
                     return Ok(ClientOutcome::WaitForTimeout(timeout));
                 }
             };
-            // The first message of the only operation created the new chain.
-            let message_id = certificate
+            // The first message of each operation created a new chain.
+            let message_ids = certificate
                 .value()
                 .executed_block()
                 .and_then(|executed_block| {
-                    executed_block.message_id_for_operation(0, OPEN_CHAIN_MESSAGE_INDEX)
+                    (0..chains.len())
+                        .map(|index| {
+                            executed_block.message_id_for_operation(index, OPEN_CHAIN_MESSAGE_INDEX)
+                        })
+                        .collect::<Option<Vec<_>>>()
                 })
                 .ok_or_else(|| ChainClientError::InternalError("Failed to create new chain"))?;
-            return Ok(ClientOutcome::Committed((message_id, certificate)));
+            return Ok(ClientOutcome::Committed((message_ids, certificate)));
         }
     }
 
@@ -1788,15 +2741,46 @@ where
             .await
     }
 
-    /// Publishes some bytecode.
+    /// Transfers the chain's balance to the `recipient`, then closes the chain.
+    pub async fn reclaim_and_close_chain(
+        &mut self,
+        recipient: Account,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::ReclaimAndCloseChain {
+            recipient,
+        }))
+        .await
+    }
+
+    /// Reports to the admin chain that validators signed conflicting blocks, in a single block.
+    pub async fn report_equivocations(
+        &mut self,
+        evidence: Vec<EquivocationEvidence>,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        let operations = evidence
+            .iter()
+            .map(|evidence| {
+                Ok(Operation::System(SystemOperation::ReportEquivocation {
+                    validator: evidence.validator(),
+                    evidence: bcs::to_bytes(evidence)?,
+                }))
+            })
+            .collect::<Result<_, bcs::Error>>()?;
+        self.execute_operations(operations).await
+    }
+
+    /// Publishes some bytecode. The contract and the service are published as blobs, so that
+    /// the certificate only references them, and the nodes fetch them when they need them.
     pub async fn publish_bytecode(
         &mut self,
         contract: Bytecode,
         service: Bytecode,
     ) -> Result<ClientOutcome<(BytecodeId, Certificate)>, ChainClientError> {
-        self.execute_operation(Operation::System(SystemOperation::PublishBytecode {
-            contract: contract.clone(),
-            service: service.clone(),
+        let contract = self.node_client.write_blob(&contract.into()).await?;
+        let service = self.node_client.write_blob(&service.into()).await?;
+        self.execute_operation(Operation::System(SystemOperation::PublishBytecodeBlobs {
+            contract,
+            service,
         }))
         .await?
         .try_map(|certificate| {
@@ -1869,6 +2853,37 @@ where
         })
     }
 
+    /// Upgrades an application created by this chain to a published bytecode. The other chains
+    /// pass the `migration_argument` to the `migrate` entrypoint of the new contract when they
+    /// migrate the application's state.
+    pub async fn upgrade_application(
+        &mut self,
+        application_id: UserApplicationId,
+        bytecode_id: BytecodeId,
+        migration_argument: Vec<u8>,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::UpgradeApplication {
+            application_id,
+            bytecode_id,
+            migration_argument,
+        }))
+        .await
+    }
+
+    /// Pins the version of an application that this chain runs, or lets the chain follow the
+    /// upgrades of the application again if `version` is `None`.
+    pub async fn pin_application_version(
+        &mut self,
+        application_id: UserApplicationId,
+        version: Option<u32>,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::PinApplicationVersion {
+            application_id,
+            version,
+        }))
+        .await
+    }
+
     /// Creates a new committee and starts using it (admin chains only).
     pub async fn stage_new_committee(
         &mut self,
@@ -1878,17 +2893,16 @@ where
             self.prepare_chain().await?;
             let epoch = self.epoch().await?;
             let messages = self.pending_messages().await?;
-            match self
-                .execute_block(
-                    messages,
-                    vec![Operation::System(SystemOperation::Admin(
-                        AdminOperation::CreateCommittee {
-                            epoch: epoch.try_add_one()?,
-                            committee: committee.clone(),
-                        },
-                    ))],
-                )
-                .await?
+            match Box::pin(self.execute_block(
+                messages,
+                vec![Operation::System(SystemOperation::Admin(
+                    AdminOperation::CreateCommittee {
+                        epoch: epoch.try_add_one()?,
+                        committee: committee.clone(),
+                    },
+                ))],
+            ))
+            .await?
             {
                 ExecuteBlockOutcome::Executed(certificate) => {
                     return Ok(ClientOutcome::Committed(certificate))
@@ -1901,6 +2915,101 @@ where
         }
     }
 
+    /// Proposes a change of the validator set, and votes for it (admin chains only).
+    pub async fn propose_committee_change(
+        &mut self,
+        change: CommitteeChange,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Admin(
+            AdminOperation::ProposeCommitteeChange { change },
+        )))
+        .await
+    }
+
+    /// Votes for a pending change of the validator set (admin chains only).
+    pub async fn vote_committee_change(
+        &mut self,
+        proposal_id: u32,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Admin(
+            AdminOperation::VoteCommitteeChange { proposal_id },
+        )))
+        .await
+    }
+
+    /// Creates a new committee with a change of the validator set that a majority of the
+    /// chain owners voted for, and starts using it (admin chains only).
+    pub async fn finalize_committee_change(
+        &mut self,
+        proposal_id: u32,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Admin(
+            AdminOperation::FinalizeCommitteeChange { proposal_id },
+        )))
+        .await
+    }
+
+    /// Sets the minimum version of the software that the validators must run (admin chains
+    /// only).
+    pub async fn set_minimum_version(
+        &mut self,
+        version: SoftwareVersion,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Admin(
+            AdminOperation::SetMinimumVersion { version },
+        )))
+        .await
+    }
+
+    /// Returns the validators of the current committee that advertise an older version of the
+    /// software than the minimum version known to the local chain, with the version they
+    /// advertise. Validators that don't answer are skipped.
+    pub async fn outdated_validators(
+        &mut self,
+    ) -> Result<Vec<(ValidatorName, SoftwareVersion)>, ChainClientError> {
+        let Some(minimum) = self.chain_info().await?.minimum_version else {
+            return Ok(Vec::new());
+        };
+        let query = ChainInfoQuery::new(self.chain_id);
+        let mut outdated = Vec::new();
+        for (name, mut node) in self.validator_nodes().await? {
+            let response = match node.handle_chain_info_query(query.clone()).await {
+                Ok(response) if response.check(name).is_ok() => response,
+                Ok(_) => {
+                    warn!("Validator {name} sent an invalid chain information response");
+                    continue;
+                }
+                Err(error) => {
+                    warn!("Failed to query the version of validator {name}: {error}");
+                    continue;
+                }
+            };
+            let version = response.info.software_version;
+            if version < minimum {
+                outdated.push((name, version));
+            }
+        }
+        Ok(outdated)
+    }
+
+    /// Returns the pending proposals to change the validator set of the local chain, by ID.
+    pub async fn committee_proposals(
+        &self,
+    ) -> Result<BTreeMap<u32, CommitteeProposal>, LocalNodeError> {
+        let chain_state_view = self.chain_state_view().await?;
+        let mut proposals = BTreeMap::new();
+        chain_state_view
+            .execution_state
+            .system
+            .committee_proposals
+            .for_each_index_value(|proposal_id, proposal| {
+                proposals.insert(proposal_id, proposal);
+                Ok(())
+            })
+            .await?;
+        Ok(proposals)
+    }
+
     /// Creates an empty block to process all incoming messages. This may require several blocks.
     ///
     /// If not all certificates could be processed due to a timeout, the timestamp for when to retry
@@ -1915,7 +3024,7 @@ where
             if incoming_messages.is_empty() {
                 return Ok((certificates, None));
             }
-            match self.execute_block(incoming_messages, vec![]).await {
+            match Box::pin(self.execute_block(incoming_messages, vec![])).await {
                 Ok(ExecuteBlockOutcome::Executed(certificate))
                 | Ok(ExecuteBlockOutcome::Conflict(certificate)) => certificates.push(certificate),
                 Ok(ExecuteBlockOutcome::WaitForTimeout(timeout)) => {
@@ -1986,6 +3095,38 @@ where
         .await
     }
 
+    /// Starts receiving the payloads that an application publishes to a topic on the given
+    /// chain. They are delivered as messages to the same application on this chain.
+    pub async fn subscribe_to_topic(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        topic: String,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::SubscribeToTopic {
+            chain_id,
+            application_id,
+            topic,
+        }))
+        .await
+    }
+
+    /// Stops receiving the payloads that an application publishes to a topic on the given
+    /// chain.
+    pub async fn unsubscribe_from_topic(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        topic: String,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::UnsubscribeFromTopic {
+            chain_id,
+            application_id,
+            topic,
+        }))
+        .await
+    }
+
     /// Deprecates all the configurations of voting rights but the last one (admin chains
     /// only). Currently, each individual chain is still entitled to wait before accepting
     /// this command. However, it is expected that deprecated validators stop functioning
@@ -2295,12 +3436,14 @@ where
         node: <P as ValidatorNodeProvider>::Node,
         node_client: LocalNodeClient<S>,
     ) -> Result<(), ChainClientError> {
-        let ((committees, max_epoch), chain_id, current_tracker) = {
+        let ((committees, max_epoch), chain_id, current_tracker, committee_cache) = {
             let mut guard = self.lock().await;
+            guard.load_received_certificate_trackers().await?;
             (
                 guard.known_committees().await?,
                 guard.chain_id(),
                 *guard.received_certificate_trackers.get(&name).unwrap_or(&0),
+                guard.committees.clone(),
             )
         };
         // Proceed to downloading received certificates.
@@ -2313,6 +3456,7 @@ where
                 max_epoch,
                 node,
                 node_client,
+                committee_cache,
             )
             .await?;
         // Process received certificates. If the client state has changed during the
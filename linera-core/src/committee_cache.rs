@@ -0,0 +1,211 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A cache of the committees of the known epochs, and of the certificates recently verified
+//! with them.
+//!
+//! The committee of an epoch is the same for all the chains, so a single cache is shared by
+//! the chain clients and the workers of their local node. A certificate verified by a client
+//! when downloading it from the validators is then not verified again when the local node
+//! processes it. Whether a chain still trusts the epoch of a certificate is checked
+//! separately, against the state of the chain.
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use linera_base::{
+    crypto::{CryptoHash, Signature},
+    data_types::Round,
+    identifiers::ChainId,
+};
+use linera_chain::{
+    data_types::{Certificate, CertificateValue, LiteCertificate},
+    ChainError,
+};
+use linera_execution::{
+    committee::{Committee, Epoch, ValidatorName},
+    Message, SystemMessage,
+};
+use lru::LruCache;
+
+#[cfg(test)]
+#[path = "unit_tests/committee_cache.rs"]
+mod unit_tests;
+
+/// The default number of verified certificates remembered by a [`CommitteeCache`].
+pub const DEFAULT_VERIFIED_CERTIFICATES: usize = 1_000;
+
+/// The signatures of the certificates recently verified, by value hash and round.
+type VerifiedSignatures = LruCache<(CryptoHash, Round), Vec<(ValidatorName, Signature)>>;
+
+/// The committees of the known epochs, and the signatures of the certificates recently
+/// verified with them.
+pub struct CommitteeCache {
+    committees: RwLock<BTreeMap<Epoch, Arc<Committee>>>,
+    verified: Mutex<VerifiedSignatures>,
+}
+
+impl Default for CommitteeCache {
+    fn default() -> Self {
+        Self::new(NonZeroUsize::try_from(DEFAULT_VERIFIED_CERTIFICATES).unwrap())
+    }
+}
+
+impl CommitteeCache {
+    /// Creates a cache that remembers up to `capacity` verified certificates.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        CommitteeCache {
+            committees: RwLock::default(),
+            verified: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Returns the committee of the `epoch`, if it is known.
+    pub fn committee(&self, epoch: Epoch) -> Option<Arc<Committee>> {
+        self.committees.read().unwrap().get(&epoch).cloned()
+    }
+
+    /// Adds the committee of the `epoch`, which must come from a trusted source, such as the
+    /// state of a chain in the local storage.
+    pub fn insert(&self, epoch: Epoch, committee: &Committee) {
+        if self.committees.read().unwrap().contains_key(&epoch) {
+            return;
+        }
+        self.committees
+            .write()
+            .unwrap()
+            .entry(epoch)
+            .or_insert_with(|| Arc::new(committee.clone()));
+    }
+
+    /// Verifies a certificate with the `committee` of its `epoch`, unless the same
+    /// certificate was verified recently.
+    pub fn check(
+        &self,
+        certificate: &Certificate,
+        epoch: Epoch,
+        committee: &Committee,
+    ) -> Result<(), ChainError> {
+        self.check_lite(certificate.lite_certificate(), epoch, committee)
+    }
+
+    /// Verifies a certificate without its value, like [`CommitteeCache::check`].
+    pub fn check_lite(
+        &self,
+        certificate: LiteCertificate,
+        epoch: Epoch,
+        committee: &Committee,
+    ) -> Result<(), ChainError> {
+        self.insert(epoch, committee);
+        let key = (certificate.value.value_hash, certificate.round);
+        if self.has_verified(&key, &certificate.signatures) {
+            return Ok(());
+        }
+        let signatures = certificate.signatures.to_vec();
+        certificate.check(committee)?;
+        self.verified.lock().unwrap().put(key, signatures);
+        Ok(())
+    }
+
+    /// Verifies several certificates, each with the committee of its epoch. The signatures
+    /// of the certificates from the same epoch are verified at once, which is faster than
+    /// checking each certificate.
+    pub fn check_batch<'a>(
+        &self,
+        certificates: impl IntoIterator<Item = (&'a Certificate, Epoch, &'a Committee)>,
+    ) -> Result<(), ChainError> {
+        let mut unverified = BTreeMap::<Epoch, (&Committee, Vec<&Certificate>)>::new();
+        for (certificate, epoch, committee) in certificates {
+            self.insert(epoch, committee);
+            if !self.is_verified(certificate) {
+                unverified
+                    .entry(epoch)
+                    .or_insert_with(|| (committee, Vec::new()))
+                    .1
+                    .push(certificate);
+            }
+        }
+        for (committee, certificates) in unverified.into_values() {
+            Certificate::check_batch(certificates.iter().copied(), committee)?;
+            for certificate in certificates {
+                self.record_verified(certificate);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the committees announced by a confirmed block of the admin chain `admin_id`,
+    /// after verifying its certificate with the committee of the block's epoch. Returns the
+    /// epochs of the added committees.
+    ///
+    /// Nothing is added if the committee of the block's epoch is not known, since the
+    /// certificate can't be trusted then.
+    pub fn add_committees_from(
+        &self,
+        admin_id: ChainId,
+        certificate: &Certificate,
+    ) -> Result<Vec<Epoch>, ChainError> {
+        let CertificateValue::ConfirmedBlock { executed_block, .. } = certificate.value() else {
+            return Ok(Vec::new());
+        };
+        let block = &executed_block.block;
+        if block.chain_id != admin_id {
+            return Ok(Vec::new());
+        }
+        let announced = executed_block
+            .messages()
+            .iter()
+            .filter_map(|message| match &message.message {
+                Message::System(SystemMessage::SetCommittees { committees, .. }) => {
+                    Some(committees)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if announced.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(committee) = self.committee(block.epoch) else {
+            return Ok(Vec::new());
+        };
+        self.check(certificate, block.epoch, &committee)?;
+        let mut added = Vec::new();
+        for (epoch, committee) in announced.into_iter().flatten() {
+            if self.committee(*epoch).is_none() {
+                self.insert(*epoch, committee);
+                added.push(*epoch);
+            }
+        }
+        Ok(added)
+    }
+
+    /// Returns whether the certificate, with the same signatures, was verified recently.
+    pub fn is_verified(&self, certificate: &Certificate) -> bool {
+        let key = (certificate.hash(), certificate.round);
+        self.has_verified(&key, certificate.signatures())
+    }
+
+    fn has_verified(
+        &self,
+        key: &(CryptoHash, Round),
+        signatures: &[(ValidatorName, Signature)],
+    ) -> bool {
+        self.verified
+            .lock()
+            .unwrap()
+            .get(key)
+            .map_or(false, |verified| verified == signatures)
+    }
+
+    /// Records that the certificate was verified.
+    fn record_verified(&self, certificate: &Certificate) {
+        let key = (certificate.hash(), certificate.round);
+        self.verified
+            .lock()
+            .unwrap()
+            .put(key, certificate.signatures().clone());
+    }
+}
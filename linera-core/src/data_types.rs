@@ -5,23 +5,26 @@
 use std::collections::BTreeMap;
 
 use linera_base::{
-    crypto::{BcsSignable, CryptoError, CryptoHash, KeyPair, Signature},
-    data_types::{Amount, BlockHeight, Round, Timestamp},
+    crypto::{BcsSignable, CryptoError, CryptoHash, HasTypeName, KeyPair, Signature},
+    data_types::{Amount, BlockHeight, Round, SoftwareVersion, Timestamp},
     identifiers::{ChainDescription, ChainId, Owner},
 };
+#[cfg(with_testing)]
+use linera_chain::test::strategies;
 use linera_chain::{
     data_types::{
-        Certificate, ChainAndHeight, HashedCertificateValue, IncomingMessage, Medium, MessageBundle,
+        Certificate, ChainAndHeight, HashedCertificateValue, IncomingMessage, Medium,
+        MessageBundle, TransactionOutcome,
     },
     manager::ChainManagerInfo,
     ChainStateView,
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
-    ExecutionRuntimeContext,
+    BlockLimits, ExecutionRuntimeContext, UserApplicationId,
 };
 use linera_storage::ChainRuntimeContext;
-use linera_views::{common::Context, views::ViewError};
+use linera_views::{common::Context, merkle::KeyInclusionProof, views::ViewError};
 use serde::{Deserialize, Serialize};
 
 use crate::client::ChainClientError;
@@ -58,18 +61,31 @@ pub struct ChainInfoQuery {
     pub request_committees: bool,
     /// Query the received messages that are waiting be picked in the next block.
     pub request_pending_messages: bool,
-    /// Query a range of certificates sent from the chain.
+    /// Query a range of certificates sent from the chain. Validators may return fewer
+    /// certificates than requested, and then indicate the range to query next.
     pub request_sent_certificates_in_range: Option<BlockHeightRange>,
     /// Query new certificate sender chain IDs and block heights received from the chain.
     pub request_received_log_excluding_first_nth: Option<u64>,
     /// Query values from the chain manager, not just votes.
     pub request_manager_values: bool,
+    /// Query a summary of the chain manager's consensus state.
+    pub request_manager_status: bool,
     /// Include a timeout vote for the current round, if appropriate.
     pub request_leader_timeout: bool,
     /// Include a vote to switch to fallback mode, if appropriate.
     pub request_fallback: bool,
     /// Query a value that contains a binary hashed certificate value (e.g. bytecode) required by this chain.
     pub request_hashed_certificate_value: Option<CryptoHash>,
+    /// Query the number of bytes stored by each application.
+    pub request_application_storage: bool,
+    /// Query the certificates received by the chain, after the given cursor.
+    pub request_received_certificates_after: Option<u64>,
+    /// Query the fees paid by the blocks of the chain in each epoch.
+    pub request_collected_fees: bool,
+    /// Query the event log of the chain, starting at the given sequence number.
+    pub request_events_from: Option<u64>,
+    /// Request a proof of the balance of a given `Owner` against the state hash.
+    pub request_owner_balance_proof: Option<Owner>,
 }
 
 impl ChainInfoQuery {
@@ -83,9 +99,15 @@ impl ChainInfoQuery {
             request_sent_certificates_in_range: None,
             request_received_log_excluding_first_nth: None,
             request_manager_values: false,
+            request_manager_status: false,
             request_leader_timeout: false,
             request_fallback: false,
             request_hashed_certificate_value: None,
+            request_application_storage: false,
+            request_received_certificates_after: None,
+            request_collected_fees: false,
+            request_events_from: None,
+            request_owner_balance_proof: None,
         }
     }
 
@@ -104,6 +126,13 @@ impl ChainInfoQuery {
         self
     }
 
+    /// Requests a proof of the balance of `owner`, which can be checked against the
+    /// `state_hash` of the response without trusting the validator.
+    pub fn with_owner_balance_proof(mut self, owner: Owner) -> Self {
+        self.request_owner_balance_proof = Some(owner);
+        self
+    }
+
     pub fn with_pending_messages(mut self) -> Self {
         self.request_pending_messages = true;
         self
@@ -119,11 +148,23 @@ impl ChainInfoQuery {
         self
     }
 
+    /// Requests the certificates received by the chain, skipping the first `cursor` ones.
+    /// Cursors are returned with the certificates, and are specific to each validator.
+    pub fn with_received_certificates_after(mut self, cursor: u64) -> Self {
+        self.request_received_certificates_after = Some(cursor);
+        self
+    }
+
     pub fn with_manager_values(mut self) -> Self {
         self.request_manager_values = true;
         self
     }
 
+    pub fn with_manager_status(mut self) -> Self {
+        self.request_manager_status = true;
+        self
+    }
+
     pub fn with_timeout(mut self) -> Self {
         self.request_leader_timeout = true;
         self
@@ -138,6 +179,29 @@ impl ChainInfoQuery {
         self.request_hashed_certificate_value = Some(hash);
         self
     }
+
+    pub fn with_application_storage(mut self) -> Self {
+        self.request_application_storage = true;
+        self
+    }
+
+    pub fn with_collected_fees(mut self) -> Self {
+        self.request_collected_fees = true;
+        self
+    }
+
+    /// Requests the outcomes of the chain's transactions, starting at the given sequence
+    /// number. Unlike cursors, sequence numbers are the same for all validators.
+    pub fn with_events_from(mut self, sequence_number: u64) -> Self {
+        self.request_events_from = Some(sequence_number);
+        self
+    }
+
+    /// Returns whether answering the query doesn't change the chain, i.e. it doesn't ask
+    /// for a vote, so that it can be answered by a read replica.
+    pub fn is_read_only(&self) -> bool {
+        !self.request_leader_timeout && !self.request_fallback
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -169,12 +233,37 @@ pub struct ChainInfo {
     pub requested_pending_messages: Vec<IncomingMessage>,
     /// The response to `request_sent_certificates_in_range`
     pub requested_sent_certificates: Vec<Certificate>,
+    /// The rest of the requested range, if the response was truncated: this is the range to
+    /// query next to continue fetching the certificates.
+    pub requested_sent_certificates_next_range: Option<BlockHeightRange>,
     /// The current number of received certificates (useful for `request_received_log_excluding_first_nth`)
     pub count_received_log: usize,
     /// The response to `request_received_certificates_excluding_first_nth`
     pub requested_received_log: Vec<ChainAndHeight>,
+    /// The response to `request_received_certificates_after`.
+    pub requested_received_certificates: Vec<Certificate>,
+    /// The cursor to continue from after `requested_received_certificates`, if requested.
+    pub received_certificates_cursor: Option<u64>,
     /// The requested hashed certificate value, if any.
     pub requested_hashed_certificate_value: Option<HashedCertificateValue>,
+    /// The number of bytes stored by each application, if requested.
+    pub requested_application_storage: BTreeMap<UserApplicationId, u64>,
+    /// The fees paid by the blocks of the chain in each epoch, if requested.
+    pub requested_collected_fees: BTreeMap<Epoch, Amount>,
+    /// The response to `request_events_from`.
+    pub requested_events: Vec<TransactionOutcome>,
+    /// The sequence number to continue from after `requested_events`, if requested.
+    pub events_cursor: Option<u64>,
+    /// The proof of the requested owner's balance against `state_hash`, if the owner has a
+    /// balance.
+    pub requested_owner_balance_proof: Option<KeyInclusionProof>,
+    /// The limits on the contents of the next block, according to the current committee.
+    pub block_limits: BlockLimits,
+    /// The minimum version of the software that the validators must run, as last set by the
+    /// admin chain.
+    pub minimum_version: Option<SoftwareVersion>,
+    /// The version of the software of the validator that created this information.
+    pub software_version: SoftwareVersion,
 }
 
 /// The response to an `ChainInfoQuery`
@@ -187,19 +276,33 @@ pub struct ChainInfoResponse {
 
 /// An internal request between chains within a validator.
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+#[cfg_attr(with_testing, derive(test_strategy::Arbitrary, Eq, PartialEq))]
 pub enum CrossChainRequest {
     /// Communicate a number of confirmed blocks from the sender to the recipient.
     /// Blocks must be given by increasing heights.
     UpdateRecipient {
         sender: ChainId,
         recipient: ChainId,
+        #[cfg_attr(
+            with_testing,
+            strategy(proptest::collection::vec(
+                (strategies::medium(), proptest::collection::vec(strategies::message_bundle(), 0..4)),
+                0..4
+            ))
+        )]
         bundle_vecs: Vec<(Medium, Vec<MessageBundle>)>,
     },
     /// Acknowledge the height of the highest confirmed blocks communicated with `UpdateRecipient`.
     ConfirmUpdatedRecipient {
         sender: ChainId,
         recipient: ChainId,
+        #[cfg_attr(
+            with_testing,
+            strategy(proptest::collection::vec(
+                (strategies::medium(), proptest::arbitrary::any::<BlockHeight>()),
+                0..4
+            ))
+        )]
         latest_heights: Vec<(Medium, BlockHeight)>,
     },
 }
@@ -251,9 +354,23 @@ where
             requested_owner_balance: None,
             requested_pending_messages: Vec::new(),
             requested_sent_certificates: Vec::new(),
+            requested_sent_certificates_next_range: None,
             count_received_log: view.received_log.count(),
             requested_received_log: Vec::new(),
+            requested_received_certificates: Vec::new(),
+            received_certificates_cursor: None,
             requested_hashed_certificate_value: None,
+            requested_application_storage: BTreeMap::new(),
+            requested_collected_fees: BTreeMap::new(),
+            requested_events: Vec::new(),
+            events_cursor: None,
+            requested_owner_balance_proof: None,
+            block_limits: system_state
+                .current_committee()
+                .map(|(_, committee)| committee.policy().block_limits())
+                .unwrap_or_default(),
+            minimum_version: *system_state.minimum_version.get(),
+            software_version: SoftwareVersion::current(),
         }
     }
 }
@@ -269,6 +386,29 @@ impl ChainInfoResponse {
         Signature::check_optional_signature(self.signature.as_ref(), &*self.info, name.0)
     }
 
+    /// Checks the signatures of responses from several validators at once. This is faster
+    /// than checking each response.
+    pub fn check_batch<'a>(
+        responses: impl IntoIterator<Item = (ValidatorName, &'a ChainInfoResponse)>,
+    ) -> Result<(), CryptoError> {
+        let mut items = Vec::new();
+        for (name, response) in responses {
+            let signature =
+                response
+                    .signature
+                    .as_ref()
+                    .ok_or_else(|| CryptoError::MissingSignature {
+                        type_name: ChainInfo::type_name().to_string(),
+                    })?;
+            items.push((name, &*response.info, signature));
+        }
+        Signature::verify_messages_batch(
+            items
+                .iter()
+                .map(|(name, info, signature)| (*info, &name.0, *signature)),
+        )
+    }
+
     /// Returns the committee in the latest epoch.
     pub fn latest_committee(&self) -> Option<&Committee> {
         let committees = self.info.requested_committees.as_ref()?;
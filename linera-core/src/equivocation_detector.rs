@@ -0,0 +1,133 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detection of validators that sign different blocks for the same chain and height in the same
+//! round.
+//!
+//! The worker records the signatures of the certificates it has verified. If a validator's
+//! signature is found on two conflicting blocks, the evidence is kept so that it can be fetched
+//! and reported to the admin chain, where it can later be used to slash the validator.
+//!
+//! Only the signatures of the most recent certificates are remembered, so conflicting
+//! certificates that are far apart are not detected.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use linera_base::{
+    crypto::Signature,
+    data_types::{BlockHeight, Round},
+    identifiers::ChainId,
+};
+use linera_chain::data_types::{Certificate, EquivocationEvidence, HashedCertificateValue, Vote};
+use linera_execution::committee::ValidatorName;
+
+#[cfg(test)]
+#[path = "unit_tests/equivocation_detector.rs"]
+mod unit_tests;
+
+/// The default number of signatures remembered by an [`EquivocationDetector`].
+pub const DEFAULT_SIGNATURE_CAPACITY: usize = 10_000;
+
+/// The signatures of recent certificates, and the equivocations found among them.
+pub struct EquivocationDetector {
+    capacity: usize,
+    inner: Mutex<DetectorInner>,
+}
+
+#[derive(Default)]
+struct DetectorInner {
+    signatures: HashMap<SignatureKey, (Arc<HashedCertificateValue>, Signature)>,
+    /// The keys of `signatures`, oldest first.
+    order: VecDeque<SignatureKey>,
+    /// The first evidence found against each validator.
+    evidence: BTreeMap<ValidatorName, EquivocationEvidence>,
+}
+
+/// A validator is only allowed to sign one block for each of these.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct SignatureKey {
+    chain_id: ChainId,
+    height: BlockHeight,
+    round: Round,
+    validator: ValidatorName,
+}
+
+impl Default for EquivocationDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIGNATURE_CAPACITY)
+    }
+}
+
+impl EquivocationDetector {
+    /// Creates a detector that remembers up to `capacity` signatures.
+    pub fn new(capacity: usize) -> Self {
+        EquivocationDetector {
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Records the signatures of a certificate for a block. The certificate must have been
+    /// verified.
+    pub(crate) fn record_certificate(&self, certificate: &Certificate) {
+        let Some(block) = certificate.value().block() else {
+            return;
+        };
+        let value = Arc::new(certificate.value.clone());
+        let inner = &mut *self.inner.lock().unwrap();
+        for (validator, signature) in certificate.signatures() {
+            let key = SignatureKey {
+                chain_id: block.chain_id,
+                height: block.height,
+                round: certificate.round,
+                validator: *validator,
+            };
+            let Some((previous_value, previous_signature)) = inner.signatures.get(&key) else {
+                inner.signatures.insert(key, (value.clone(), *signature));
+                inner.order.push_back(key);
+                continue;
+            };
+            if inner.evidence.contains_key(validator) {
+                continue;
+            }
+            let vote = |value: &HashedCertificateValue, signature: Signature| Vote {
+                value: value.clone(),
+                round: key.round,
+                validator: key.validator,
+                signature,
+            };
+            if let Some(evidence) = EquivocationEvidence::new(
+                vote(previous_value, *previous_signature),
+                vote(&value, *signature),
+            ) {
+                tracing::warn!(
+                    validator = %validator,
+                    chain_id = %key.chain_id,
+                    height = %key.height,
+                    "Validator signed two different blocks in {:?}",
+                    key.round
+                );
+                inner.evidence.insert(*validator, evidence);
+            }
+        }
+        while inner.order.len() > self.capacity {
+            if let Some(key) = inner.order.pop_front() {
+                inner.signatures.remove(&key);
+            }
+        }
+    }
+
+    /// Returns the evidence found against each validator that equivocated.
+    pub fn evidence(&self) -> Vec<EquivocationEvidence> {
+        self.inner
+            .lock()
+            .unwrap()
+            .evidence
+            .values()
+            .cloned()
+            .collect()
+    }
+}
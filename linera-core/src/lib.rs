@@ -4,14 +4,25 @@
 
 //! This module defines the core Linera protocol.
 
+#[cfg(not(web))]
+pub mod anti_entropy;
 pub mod client;
+pub mod committee_cache;
 pub mod data_types;
+pub mod equivocation_detector;
+pub mod load_monitor;
 pub mod local_node;
 pub mod node;
 pub mod notifier;
+pub mod proposal_registry;
 #[cfg(with_testing)]
 #[path = "unit_tests/test_utils.rs"]
 pub mod test_utils;
+pub mod validator_scores;
 pub mod worker;
 
+pub(crate) mod certificate_pipeline;
+pub(crate) mod chain_scheduler;
+pub(crate) mod chain_state_cache;
+pub(crate) mod response_cache;
 pub(crate) mod updater;
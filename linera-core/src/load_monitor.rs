@@ -0,0 +1,191 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measurement of the load of a worker, for the operators of a validator to decide when to
+//! add shards.
+//!
+//! The requests handled by the worker and the loads of chain states from the storage are
+//! counted in fixed windows of [`LOAD_WINDOW`]. A report describes the last complete window,
+//! or the current one if none is complete yet, so that it only reflects recent traffic.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use linera_base::{
+    identifiers::ChainId,
+    time::{Duration, Instant},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "unit_tests/load_monitor.rs"]
+mod unit_tests;
+
+/// The duration over which the load is averaged.
+pub const LOAD_WINDOW: Duration = Duration::from_secs(10);
+
+/// The number of chains listed in the reports as the busiest ones.
+const BUSIEST_CHAIN_COUNT: usize = 10;
+
+/// The load of a worker, shared by all the clones of the worker.
+pub struct LoadMonitor {
+    /// The number of requests received and not answered yet.
+    pending: AtomicUsize,
+    windows: Mutex<Windows>,
+}
+
+/// The current window and the previous one, if it just ended.
+struct Windows {
+    current: Window,
+    previous: Option<Window>,
+}
+
+/// What happened during a window.
+struct Window {
+    start: Instant,
+    requests: u64,
+    storage_loads: u64,
+    storage_latency: Duration,
+    chains: HashMap<ChainId, u64>,
+}
+
+/// The load of a worker, averaged over the last [`LOAD_WINDOW`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoadReport {
+    /// The number of requests handled per second.
+    pub requests_per_second: f64,
+    /// The number of requests received and not answered yet, whether they are waiting for
+    /// their chain's turn or being executed.
+    pub queue_depth: usize,
+    /// The average time to load the state of a chain from the storage, in milliseconds, if
+    /// any was loaded.
+    pub storage_latency_ms: Option<f64>,
+    /// The chains with the most requests, with their number of requests, busiest first.
+    pub busiest_chains: Vec<(ChainId, u64)>,
+}
+
+/// A request being handled by a worker. It is no longer pending when this is dropped.
+pub(crate) struct PendingRequest(Arc<LoadMonitor>);
+
+impl Default for LoadMonitor {
+    fn default() -> Self {
+        LoadMonitor {
+            pending: AtomicUsize::new(0),
+            windows: Mutex::new(Windows {
+                current: Window::new(Instant::now()),
+                previous: None,
+            }),
+        }
+    }
+}
+
+impl LoadMonitor {
+    /// Counts a new request for `chain_id`, pending until the returned value is dropped.
+    pub(crate) fn start_request(self: &Arc<Self>, chain_id: ChainId) -> PendingRequest {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        self.count_request(chain_id, Instant::now());
+        PendingRequest(self.clone())
+    }
+
+    /// Records that loading the state of a chain from the storage took `latency`.
+    pub(crate) fn record_storage_load(&self, latency: Duration) {
+        self.count_storage_load(latency, Instant::now());
+    }
+
+    fn count_request(&self, chain_id: ChainId, now: Instant) {
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.current_at(now);
+        window.requests += 1;
+        *window.chains.entry(chain_id).or_default() += 1;
+    }
+
+    fn count_storage_load(&self, latency: Duration, now: Instant) {
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.current_at(now);
+        window.storage_loads += 1;
+        window.storage_latency += latency;
+    }
+
+    /// Returns the current load.
+    pub fn report(&self) -> LoadReport {
+        self.report_at(Instant::now())
+    }
+
+    fn report_at(&self, now: Instant) -> LoadReport {
+        let queue_depth = self.pending.load(Ordering::Relaxed);
+        let mut windows = self.windows.lock().unwrap();
+        windows.current_at(now);
+        let (window, duration) = match &windows.previous {
+            Some(previous) => (previous, LOAD_WINDOW),
+            None => (
+                &windows.current,
+                now.saturating_duration_since(windows.current.start),
+            ),
+        };
+        let requests_per_second = if duration.is_zero() {
+            0.0
+        } else {
+            window.requests as f64 / duration.as_secs_f64()
+        };
+        let storage_latency_ms = (window.storage_loads > 0)
+            .then(|| window.storage_latency.as_secs_f64() * 1000.0 / window.storage_loads as f64);
+        let mut busiest_chains = window
+            .chains
+            .iter()
+            .map(|(chain_id, requests)| (*chain_id, *requests))
+            .collect::<Vec<_>>();
+        busiest_chains.sort_by(|(id1, requests1), (id2, requests2)| {
+            requests2.cmp(requests1).then(id1.cmp(id2))
+        });
+        busiest_chains.truncate(BUSIEST_CHAIN_COUNT);
+        LoadReport {
+            requests_per_second,
+            queue_depth,
+            storage_latency_ms,
+            busiest_chains,
+        }
+    }
+}
+
+impl Windows {
+    /// Returns the window including `now`, starting new windows if the current one ended.
+    fn current_at(&mut self, now: Instant) -> &mut Window {
+        let elapsed = now.saturating_duration_since(self.current.start);
+        if elapsed >= LOAD_WINDOW {
+            // The windows are aligned on the first one. If a whole window passed without
+            // any activity, the previous window is an empty one.
+            let skipped = (elapsed.as_nanos() / LOAD_WINDOW.as_nanos()) as u32;
+            let start = self.current.start + LOAD_WINDOW * skipped;
+            let ended = std::mem::replace(&mut self.current, Window::new(start));
+            self.previous = if skipped == 1 {
+                Some(ended)
+            } else {
+                Some(Window::new(start - LOAD_WINDOW))
+            };
+        }
+        &mut self.current
+    }
+}
+
+impl Window {
+    fn new(start: Instant) -> Self {
+        Window {
+            start,
+            requests: 0,
+            storage_loads: 0,
+            storage_latency: Duration::ZERO,
+            chains: HashMap::new(),
+        }
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        self.0.pending.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -7,14 +7,15 @@ use std::{borrow::Cow, sync::Arc};
 use futures::{future, lock::Mutex};
 use linera_base::{
     data_types::{ArithmeticError, BlockHeight},
-    identifiers::{ChainId, MessageId},
+    identifiers::{Blob, BlobId, ChainId, MessageId},
+    time::Instant,
 };
 use linera_chain::data_types::{
     Block, BlockProposal, Certificate, ExecutedBlock, HashedCertificateValue, LiteCertificate,
 };
 use linera_execution::{
-    committee::ValidatorName, BytecodeLocation, Query, Response, UserApplicationDescription,
-    UserApplicationId,
+    committee::{Committee, ValidatorName},
+    BytecodeLocation, Query, Response, UserApplicationDescription, UserApplicationId,
 };
 use linera_storage::Storage;
 use linera_views::views::ViewError;
@@ -26,9 +27,14 @@ use crate::{
     data_types::{BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse},
     node::{LocalValidatorNode, NotificationStream},
     notifier::Notifier,
+    validator_scores::ValidatorScores,
     worker::{Notification, ValidatorWorker, WorkerError, WorkerState},
 };
 
+/// The number of heights of a chain requested from each validator when downloading
+/// certificates in parallel.
+const PARALLEL_DOWNLOAD_BATCH_SIZE: u64 = 100;
+
 /// A local node with a single worker, typically used by clients.
 pub struct LocalNode<S> {
     state: WorkerState<S>,
@@ -39,6 +45,8 @@ pub struct LocalNode<S> {
 #[derive(Clone)]
 pub struct LocalNodeClient<S> {
     node: Arc<Mutex<LocalNode<S>>>,
+    /// The scores of the validators we download certificates from.
+    validator_scores: Arc<ValidatorScores>,
 }
 
 /// Error type for the operations on a local node.
@@ -67,6 +75,9 @@ pub enum LocalNodeError {
 
     #[error("The chain info response received from the local node is invalid")]
     InvalidChainInfoResponse,
+
+    #[error("Failed to download the following blobs: {0:?}")]
+    CannotDownloadBlobs(Vec<BlobId>),
 }
 
 impl<S> LocalNodeClient<S>
@@ -140,6 +151,12 @@ where
         let rx = node.notifier.subscribe(chains);
         Ok(Box::pin(UnboundedReceiverStream::new(rx)))
     }
+
+    /// Sends the given notifications to the subscribers of their chains.
+    pub async fn notify(&mut self, notifications: &[Notification]) {
+        let node = self.node.lock().await;
+        node.notifier.handle_notifications(notifications);
+    }
 }
 
 impl<S> LocalNodeClient<S> {
@@ -148,8 +165,21 @@ impl<S> LocalNodeClient<S> {
 
         Self {
             node: Arc::new(Mutex::new(node)),
+            validator_scores: Arc::default(),
         }
     }
+
+    /// Shares the scores of the validators with other local node clients.
+    pub(crate) fn with_validator_scores(mut self, validator_scores: Arc<ValidatorScores>) -> Self {
+        self.validator_scores = validator_scores;
+        self
+    }
+
+    /// Weighs the validators of the `committee` by their voting power when choosing which
+    /// ones to download certificates from.
+    pub fn update_validator_stakes(&self, committee: &Committee) {
+        self.validator_scores.update_stakes(committee);
+    }
 }
 
 impl<S> LocalNodeClient<S>
@@ -176,6 +206,88 @@ where
         Ok((executed_block, info))
     }
 
+    /// Reads the blobs with the given IDs from the storage.
+    pub(crate) async fn read_blobs(
+        &self,
+        blob_ids: impl IntoIterator<Item = BlobId>,
+    ) -> Result<Vec<Blob>, LocalNodeError> {
+        let storage = self.storage_client().await;
+        let blobs = future::try_join_all(
+            blob_ids
+                .into_iter()
+                .map(|blob_id| storage.read_blob(blob_id)),
+        )
+        .await?;
+        Ok(blobs)
+    }
+
+    /// Reads the blobs with the given IDs from the storage, after downloading the missing ones
+    /// from the validators.
+    pub(crate) async fn read_or_download_blobs<A>(
+        &self,
+        validators: Vec<(ValidatorName, A)>,
+        chain_id: ChainId,
+        blob_ids: impl IntoIterator<Item = BlobId>,
+    ) -> Result<Vec<Blob>, LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let blob_ids = blob_ids.into_iter().collect::<Vec<_>>();
+        let storage = self.storage_client().await;
+        let results =
+            future::join_all(blob_ids.iter().map(|blob_id| storage.read_blob(*blob_id))).await;
+        let mut missing_blob_ids = Vec::new();
+        for (blob_id, result) in blob_ids.iter().zip(results) {
+            match result {
+                Ok(_) => {}
+                Err(ViewError::NotFound(_)) => missing_blob_ids.push(*blob_id),
+                Err(error) => return Err(error.into()),
+            }
+        }
+        if !missing_blob_ids.is_empty() {
+            self.download_blobs(validators, chain_id, &missing_blob_ids)
+                .await?;
+        }
+        self.read_blobs(blob_ids).await
+    }
+
+    /// Writes a blob to the storage, so that it can be published in a block.
+    pub(crate) async fn write_blob(&self, blob: &Blob) -> Result<BlobId, LocalNodeError> {
+        Ok(self.storage_client().await.write_blob(blob).await?)
+    }
+
+    /// Downloads the blobs with the given IDs from the validators, and writes them to the
+    /// storage.
+    pub async fn download_blobs<A>(
+        &self,
+        validators: Vec<(ValidatorName, A)>,
+        chain_id: ChainId,
+        blob_ids: &[BlobId],
+    ) -> Result<(), LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let maybe_blobs = future::join_all(
+            blob_ids
+                .iter()
+                .map(|blob_id| Self::download_blob(validators.clone(), chain_id, *blob_id)),
+        )
+        .await;
+        let mut blobs = Vec::new();
+        let mut missing_blob_ids = Vec::new();
+        for (blob_id, maybe_blob) in blob_ids.iter().zip(maybe_blobs) {
+            match maybe_blob {
+                Some(blob) => blobs.push(blob),
+                None => missing_blob_ids.push(*blob_id),
+            }
+        }
+        if !missing_blob_ids.is_empty() {
+            return Err(LocalNodeError::CannotDownloadBlobs(missing_blob_ids));
+        }
+        self.storage_client().await.write_blobs(&blobs).await?;
+        Ok(())
+    }
+
     async fn try_process_certificates<A>(
         &mut self,
         name: ValidatorName,
@@ -194,13 +306,13 @@ where
                 tracing::warn!("Failed to process network certificate {}", hash);
                 return info;
             }
+            let mut values = Vec::new();
             let mut result = self.handle_certificate(certificate.clone(), vec![]).await;
             if let Err(LocalNodeError::WorkerError(WorkerError::ApplicationBytecodesNotFound(
                 locations,
             ))) = &result
             {
                 let chain_id = certificate.value().chain_id();
-                let mut values = Vec::new();
                 let maybe_values = future::join_all(locations.iter().map(|location| {
                     let mut node = node.clone();
                     async move {
@@ -220,6 +332,35 @@ where
                         return info;
                     }
                 }
+                result = self
+                    .handle_certificate(certificate.clone(), values.clone())
+                    .await;
+            }
+            if let Err(LocalNodeError::WorkerError(WorkerError::BlobsNotFound(blob_ids))) = &result
+            {
+                let maybe_blobs = future::join_all(blob_ids.iter().map(|blob_id| {
+                    let mut node = node.clone();
+                    async move {
+                        Self::try_download_blob_from(name, &mut node, chain_id, *blob_id).await
+                    }
+                }))
+                .await;
+                let Some(blobs) = maybe_blobs.into_iter().collect::<Option<Vec<_>>>() else {
+                    // The certificate is not as expected. Give up.
+                    tracing::warn!(
+                        "Failed to download the blobs of network certificate {}",
+                        hash
+                    );
+                    return info;
+                };
+                if let Err(error) = self.storage_client().await.write_blobs(&blobs).await {
+                    tracing::warn!(
+                        "Failed to write the blobs of certificate {}: {}",
+                        hash,
+                        error
+                    );
+                    return info;
+                }
                 result = self.handle_certificate(certificate.clone(), values).await;
             }
             match result {
@@ -268,16 +409,15 @@ where
 
     pub async fn download_certificates<A>(
         &mut self,
-        mut validators: Vec<(ValidatorName, A)>,
+        validators: Vec<(ValidatorName, A)>,
         chain_id: ChainId,
         target_next_block_height: BlockHeight,
     ) -> Result<Box<ChainInfo>, LocalNodeError>
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        // Sequentially try each validator in random order.
-        validators.shuffle(&mut rand::thread_rng());
-        for (name, node) in validators {
+        // Sequentially try each validator in a random order weighted by their scores.
+        for (name, node) in self.validator_scores.order(validators) {
             let info = self.local_chain_info(chain_id).await?;
             if target_next_block_height <= info.next_block_height {
                 return Ok(info);
@@ -302,6 +442,124 @@ where
         }
     }
 
+    /// Downloads the certificates of the chain up to `target_next_block_height` from several
+    /// validators in parallel, each of them providing a different range of heights, and
+    /// processes them in order. The ranges a validator fails to provide are requested from the
+    /// next ones, and the blocks that could still not be processed from all the validators in
+    /// turn.
+    pub async fn download_certificates_in_parallel<A>(
+        &mut self,
+        validators: Vec<(ValidatorName, A)>,
+        chain_id: ChainId,
+        target_next_block_height: BlockHeight,
+    ) -> Result<Box<ChainInfo>, LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let info = self.local_chain_info(chain_id).await?;
+        if target_next_block_height <= info.next_block_height || validators.is_empty() {
+            return self
+                .download_certificates(validators, chain_id, target_next_block_height)
+                .await;
+        }
+        let mut ranges = Vec::new();
+        let mut start = info.next_block_height;
+        while start < target_next_block_height {
+            let end = start
+                .try_add(BlockHeight(PARALLEL_DOWNLOAD_BATCH_SIZE))
+                .map_or(target_next_block_height, |end| {
+                    end.min(target_next_block_height)
+                });
+            ranges.push(BlockHeightRange {
+                start,
+                limit: Some(u64::from(end) - u64::from(start)),
+            });
+            start = end;
+        }
+        // Each range is requested from validators picked in a random order weighted by their
+        // scores, so that the load is spread across the committee.
+        let downloads = ranges.into_iter().map(|range| {
+            let scores = self.validator_scores.clone();
+            let validators = scores.order(validators.clone());
+            async move {
+                for (name, mut node) in validators {
+                    let start = Instant::now();
+                    if let Some(certificates) =
+                        Self::try_fetch_certificates_from(name, &mut node, chain_id, range.clone())
+                            .await
+                    {
+                        scores.record_success(name, start.elapsed());
+                        return Some((name, node, certificates));
+                    }
+                    scores.record_failure(name);
+                }
+                None
+            }
+        });
+        for download in future::join_all(downloads).await {
+            let Some((name, mut node, certificates)) = download else {
+                break;
+            };
+            if self
+                .try_process_certificates(name, &mut node, chain_id, certificates)
+                .await
+                .is_none()
+            {
+                self.validator_scores.record_failure(name);
+                break;
+            }
+        }
+        self.download_certificates(validators, chain_id, target_next_block_height)
+            .await
+    }
+
+    /// Requests the certificates of a range of heights of the chain from a validator, without
+    /// processing them. Returns `None` if the validator does not provide all of them, or not
+    /// as expected.
+    async fn try_fetch_certificates_from<A>(
+        name: ValidatorName,
+        node: &mut A,
+        chain_id: ChainId,
+        range: BlockHeightRange,
+    ) -> Option<Vec<Certificate>>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let mut certificates = Vec::new();
+        let mut next_height = range.start;
+        let mut next_range = Some(range.clone());
+        while let Some(range) = next_range.take() {
+            if range.start != next_height {
+                tracing::warn!("Ignoring invalid continuation range from validator");
+                return None;
+            }
+            let query = ChainInfoQuery::new(chain_id).with_sent_certificates_in_range(range);
+            let info = match node.handle_chain_info_query(query).await {
+                Ok(response) if response.check(name).is_ok() => response.info,
+                Ok(_) => {
+                    tracing::warn!("Ignoring invalid response from validator");
+                    return None;
+                }
+                Err(err) => {
+                    tracing::warn!("Ignoring error from validator: {}", err);
+                    return None;
+                }
+            };
+            for certificate in info.requested_sent_certificates {
+                let value = certificate.value();
+                if value.chain_id() != chain_id || value.height() != next_height {
+                    tracing::warn!("Ignoring unexpected certificate from validator");
+                    return None;
+                }
+                next_height = next_height.try_add_one().ok()?;
+                certificates.push(certificate);
+            }
+            next_range = info.requested_sent_certificates_next_range;
+        }
+        let expected = u64::from(range.start) + range.limit.unwrap_or_default();
+        (u64::from(next_height) == expected).then_some(certificates)
+    }
+
     /// Downloads and stores the specified hashed certificate values, unless they are already in the cache or storage.
     ///
     /// Does not fail if a hashed certificate value can't be downloaded; it just gets omitted from the result.
@@ -407,24 +665,93 @@ where
             limit: Some(limit),
         };
         let query = ChainInfoQuery::new(chain_id).with_sent_certificates_in_range(range);
-        if let Ok(response) = node.handle_chain_info_query(query).await {
-            if response.check(name).is_ok() {
-                let ChainInfo {
-                    requested_sent_certificates,
-                    ..
-                } = *response.info;
-                self.try_process_certificates(
-                    name,
-                    &mut node,
-                    chain_id,
-                    requested_sent_certificates,
-                )
-                .await;
+        let start = Instant::now();
+        let response = match node.handle_chain_info_query(query).await {
+            Ok(response) if response.check(name).is_ok() => response,
+            _ => {
+                self.validator_scores.record_failure(name);
+                return Ok(());
             }
+        };
+        let latency = start.elapsed();
+        let ChainInfo {
+            requested_sent_certificates,
+            requested_sent_certificates_next_range,
+            ..
+        } = *response.info;
+        if self
+            .try_process_sent_certificates(
+                name,
+                &mut node,
+                chain_id,
+                requested_sent_certificates,
+                requested_sent_certificates_next_range,
+            )
+            .await
+        {
+            self.validator_scores.record_success(name, latency);
+        } else {
+            self.validator_scores.record_failure(name);
         }
         Ok(())
     }
 
+    /// Processes the certificates returned by a validator for a range of sent certificates,
+    /// then queries the rest of the range for as long as the validator's responses are
+    /// truncated. Returns `false` if the validator did not provide the certificates as
+    /// expected.
+    async fn try_process_sent_certificates<A>(
+        &mut self,
+        name: ValidatorName,
+        node: &mut A,
+        chain_id: ChainId,
+        mut certificates: Vec<Certificate>,
+        mut next_range: Option<BlockHeightRange>,
+    ) -> bool
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        loop {
+            let Some(last) = certificates.last() else {
+                // An empty response must not ask for more, or we would never stop.
+                return next_range.is_none();
+            };
+            let Some(range) = next_range.take() else {
+                return self
+                    .try_process_certificates(name, node, chain_id, certificates)
+                    .await
+                    .is_some();
+            };
+            if last.value().height().try_add_one().ok() != Some(range.start) {
+                tracing::warn!("Ignoring invalid continuation range from validator");
+                return false;
+            }
+            if self
+                .try_process_certificates(name, node, chain_id, certificates)
+                .await
+                .is_none()
+            {
+                return false;
+            }
+            let query = ChainInfoQuery::new(chain_id).with_sent_certificates_in_range(range);
+            match node.handle_chain_info_query(query).await {
+                Ok(response) if response.check(name).is_ok() => {
+                    let info = *response.info;
+                    certificates = info.requested_sent_certificates;
+                    next_range = info.requested_sent_certificates_next_range;
+                }
+                Ok(_) => {
+                    tracing::warn!("Ignoring invalid response from validator");
+                    return false;
+                }
+                Err(err) => {
+                    tracing::warn!("Ignoring error from validator: {}", err);
+                    return false;
+                }
+            }
+        }
+    }
+
     pub async fn synchronize_chain_state<A>(
         &mut self,
         validators: Vec<(ValidatorName, A)>,
@@ -479,15 +806,15 @@ where
             }
         };
         if !info.requested_sent_certificates.is_empty()
-            && self
-                .try_process_certificates(
+            && !self
+                .try_process_sent_certificates(
                     name,
                     &mut node,
                     chain_id,
                     info.requested_sent_certificates,
+                    info.requested_sent_certificates_next_range,
                 )
                 .await
-                .is_none()
         {
             return Ok(());
         };
@@ -550,4 +877,48 @@ where
         }
         None
     }
+
+    pub async fn download_blob<A>(
+        mut validators: Vec<(ValidatorName, A)>,
+        chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Option<Blob>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        // Sequentially try each validator in random order.
+        validators.shuffle(&mut rand::thread_rng());
+        for (name, mut node) in validators {
+            if let Some(blob) =
+                Self::try_download_blob_from(name, &mut node, chain_id, blob_id).await
+            {
+                return Some(blob);
+            }
+        }
+        None
+    }
+
+    /// Downloads a blob from a validator, and checks that its content matches its ID, so
+    /// that the validator doesn't need to sign it.
+    async fn try_download_blob_from<A>(
+        name: ValidatorName,
+        node: &mut A,
+        chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Option<Blob>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        match node.download_blob(chain_id, blob_id).await {
+            Ok(blob) if blob.id() == blob_id => Some(blob),
+            Ok(_) => {
+                tracing::warn!("Validator {name} sent an invalid blob {blob_id}");
+                None
+            }
+            Err(error) => {
+                tracing::debug!("Failed to download blob {blob_id} from validator {name}: {error}");
+                None
+            }
+        }
+    }
 }
@@ -4,16 +4,19 @@
 
 use futures::stream::{BoxStream, LocalBoxStream, Stream};
 use linera_base::{
-    crypto::CryptoError,
+    crypto::{CryptoError, CryptoHash},
     data_types::{ArithmeticError, BlockHeight},
-    identifiers::ChainId,
+    identifiers::{Blob, BlobId, ChainId},
 };
 use linera_chain::{
-    data_types::{BlockProposal, Certificate, HashedCertificateValue, LiteCertificate, Origin},
+    data_types::{
+        Block, BlockProposal, Certificate, ExecutedBlock, HashedCertificateValue, LiteCertificate,
+        Origin,
+    },
     ChainError,
 };
 use linera_execution::{
-    committee::{Committee, ValidatorName},
+    committee::{Committee, Epoch, ValidatorName},
     BytecodeLocation,
 };
 use linera_version::VersionInfo;
@@ -71,9 +74,25 @@ pub trait LocalValidatorNode {
         query: ChainInfoQuery,
     ) -> Result<ChainInfoResponse, NodeError>;
 
+    /// Executes a block as if it had been proposed, without voting on it or saving the
+    /// result, and returns its outcome.
+    async fn simulate_block_proposal(&mut self, block: Block) -> Result<ExecutedBlock, NodeError>;
+
+    /// Downloads a blob written or published by this validator. The `chain_id` is the chain
+    /// that published it, which is used to route the request to a shard.
+    async fn download_blob(
+        &mut self,
+        chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Result<Blob, NodeError>;
+
     /// Gets the version info for this validator node.
     async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError>;
 
+    /// Gets the hash of the genesis configuration of the network this validator node belongs
+    /// to.
+    async fn get_genesis_config_hash(&mut self) -> Result<CryptoHash, NodeError>;
+
     /// Subscribes to receiving notifications for a collection of chains.
     async fn subscribe(
         &mut self,
@@ -197,6 +216,18 @@ pub enum NodeError {
 
     #[error("Failed to make a chain info query on the local node: {error}")]
     LocalNodeQuery { error: String },
+
+    // This error must be normalized during conversions.
+    #[error("The following blobs are missing: {0:?}.")]
+    BlobsNotFound(Vec<BlobId>),
+
+    // This error must be normalized during conversions.
+    #[error("Unexpected epoch {epoch}: chain {chain_id} is at {chain_epoch}")]
+    InvalidEpoch {
+        chain_id: ChainId,
+        chain_epoch: Epoch,
+        epoch: Epoch,
+    },
 }
 
 impl From<tonic::Status> for NodeError {
@@ -277,6 +308,16 @@ impl From<WorkerError> for NodeError {
             WorkerError::ApplicationBytecodesNotFound(locations) => {
                 NodeError::ApplicationBytecodesNotFound(locations)
             }
+            WorkerError::BlobsNotFound(blob_ids) => NodeError::BlobsNotFound(blob_ids),
+            WorkerError::InvalidEpoch {
+                chain_id,
+                chain_epoch,
+                epoch,
+            } => NodeError::InvalidEpoch {
+                chain_id,
+                chain_epoch,
+                epoch,
+            },
             error => Self::WorkerError {
                 error: error.to_string(),
             },
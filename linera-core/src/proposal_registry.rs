@@ -0,0 +1,142 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Introspection and cancellation of the block proposals handled by a worker.
+//!
+//! Every block proposal is registered from the moment it is received until its response is
+//! ready, whether it is still waiting for its chain's turn or already being executed. An
+//! operator can list them to find a chain that is wedged, e.g. by an application that never
+//! returns, and cancel the proposal: the handling of the proposal is then abandoned, which
+//! releases the chain's lock and lets the next requests for the chain proceed.
+//!
+//! Cancelling a proposal only drops the future handling it. Application code that is running in
+//! a blocking thread keeps running until it next calls into the runtime, but it no longer holds
+//! the chain.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::future::{AbortHandle, AbortRegistration};
+use linera_base::{data_types::BlockHeight, identifiers::ChainId};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "unit_tests/proposal_registry.rs"]
+mod unit_tests;
+
+/// The block proposals a worker is currently handling, shared by all the clones of the worker.
+#[derive(Default)]
+pub struct ProposalRegistry {
+    next_id: AtomicU64,
+    proposals: Mutex<BTreeMap<u64, RegisteredProposal>>,
+}
+
+/// A proposal in the registry.
+struct RegisteredProposal {
+    chain_id: ChainId,
+    height: BlockHeight,
+    received: Instant,
+    executing: bool,
+    abort_handle: AbortHandle,
+}
+
+/// The status of a block proposal handled by a worker.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProposalStatus {
+    /// The identifier used to cancel the proposal.
+    pub id: u64,
+    /// The chain the block is proposed for.
+    pub chain_id: ChainId,
+    /// The height of the proposed block.
+    pub height: BlockHeight,
+    /// How long ago the proposal was received.
+    pub age: Duration,
+    /// Whether it is being executed, as opposed to waiting for its chain's turn.
+    pub executing: bool,
+}
+
+/// The entry of a proposal in the registry. It is removed when this is dropped.
+pub(crate) struct ProposalRegistration {
+    id: u64,
+    registry: Arc<ProposalRegistry>,
+}
+
+impl ProposalRegistry {
+    /// Adds a new proposal for the block at `height` on `chain_id`.
+    ///
+    /// The handling of the proposal must be wrapped in an `Abortable` with the returned
+    /// registration, for it to be cancelled.
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        chain_id: ChainId,
+        height: BlockHeight,
+    ) -> (ProposalRegistration, AbortRegistration) {
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let proposal = RegisteredProposal {
+            chain_id,
+            height,
+            received: Instant::now(),
+            executing: false,
+            abort_handle,
+        };
+        self.proposals.lock().unwrap().insert(id, proposal);
+        let registration = ProposalRegistration {
+            id,
+            registry: self.clone(),
+        };
+        (registration, abort_registration)
+    }
+
+    /// Returns the proposals currently handled, in the order they were received.
+    pub fn list(&self) -> Vec<ProposalStatus> {
+        self.proposals
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, proposal)| proposal.status(*id))
+            .collect()
+    }
+
+    /// Cancels the proposal with the given `id`. Returns its status, or `None` if no such
+    /// proposal is being handled.
+    pub fn cancel(&self, id: u64) -> Option<ProposalStatus> {
+        let proposals = self.proposals.lock().unwrap();
+        let proposal = proposals.get(&id)?;
+        proposal.abort_handle.abort();
+        Some(proposal.status(id))
+    }
+}
+
+impl RegisteredProposal {
+    fn status(&self, id: u64) -> ProposalStatus {
+        ProposalStatus {
+            id,
+            chain_id: self.chain_id,
+            height: self.height,
+            age: self.received.elapsed(),
+            executing: self.executing,
+        }
+    }
+}
+
+impl ProposalRegistration {
+    /// Records that the proposal's chain is now executing it.
+    pub(crate) fn start_executing(&self) {
+        if let Some(proposal) = self.registry.proposals.lock().unwrap().get_mut(&self.id) {
+            proposal.executing = true;
+        }
+    }
+}
+
+impl Drop for ProposalRegistration {
+    fn drop(&mut self) {
+        self.registry.proposals.lock().unwrap().remove(&self.id);
+    }
+}
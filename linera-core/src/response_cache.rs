@@ -0,0 +1,112 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A bounded cache of the latest block proposals and certificates handled successfully by a
+//! worker.
+//!
+//! Clients retry requests when they time out or lose their connection. The worker answers an
+//! identical request with the current state of the chain instead of validating and executing
+//! it again.
+
+use std::{borrow::Cow, num::NonZeroUsize, sync::Mutex};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::Round,
+    identifiers::{ChainId, Owner},
+};
+use linera_chain::data_types::{BlockProposal, Certificate, ProposalPayload};
+use lru::LruCache;
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::IntCounterVec,
+};
+
+#[cfg(with_metrics)]
+static RESPONSE_CACHE_HIT: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "response_cache_hit",
+        "Number of duplicate requests answered from the worker's response cache",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
+/// The identity of a request, as far as its validation and execution are concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum RequestKey {
+    /// A block proposal with the given payload, signed by the given owner.
+    Proposal {
+        payload_hash: CryptoHash,
+        owner: Owner,
+        signature: [u8; 64],
+    },
+    /// A certificate for the given value in the given round.
+    Certificate {
+        value_hash: CryptoHash,
+        round: Round,
+    },
+}
+
+impl RequestKey {
+    pub(crate) fn proposal(proposal: &BlockProposal) -> (ChainId, Self) {
+        let outcome = proposal
+            .validated
+            .as_ref()
+            .and_then(|certificate| certificate.value().executed_block())
+            .map(|executed_block| Cow::Borrowed(&executed_block.outcome));
+        let payload = ProposalPayload {
+            content: Cow::Borrowed(&proposal.content),
+            outcome,
+        };
+        let key = RequestKey::Proposal {
+            payload_hash: CryptoHash::new(&payload),
+            owner: proposal.owner,
+            signature: proposal.signature.0.to_bytes(),
+        };
+        (proposal.content.block.chain_id, key)
+    }
+
+    pub(crate) fn certificate(certificate: &Certificate) -> (ChainId, Self) {
+        let key = RequestKey::Certificate {
+            value_hash: certificate.hash(),
+            round: certificate.round,
+        };
+        (certificate.value().chain_id(), key)
+    }
+}
+
+/// A bounded LRU cache of the successful requests, shared by all the clones of a worker.
+pub(crate) struct ResponseCache {
+    requests: Mutex<LruCache<(ChainId, RequestKey), ()>>,
+}
+
+impl ResponseCache {
+    /// Creates an empty cache holding at most `capacity` requests.
+    pub(crate) fn new(capacity: NonZeroUsize) -> Self {
+        ResponseCache {
+            requests: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Returns whether an identical request was handled successfully, if it is still cached.
+    pub(crate) fn contains(&self, key: &(ChainId, RequestKey)) -> bool {
+        let contains = self.requests.lock().unwrap().get(key).is_some();
+        #[cfg(with_metrics)]
+        if contains {
+            RESPONSE_CACHE_HIT.with_label_values(&[]).inc();
+        }
+        contains
+    }
+
+    /// Records a request that was handled successfully.
+    pub(crate) fn insert(&self, key: (ChainId, RequestKey)) {
+        self.requests.lock().unwrap().put(key, ());
+    }
+
+    /// Forgets all the requests.
+    pub(crate) fn clear(&self) {
+        self.requests.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    data_types::{Amount, BlockHeight},
+    identifiers::{Account, ChainDescription, ChainId},
+};
+use linera_execution::system::UserData;
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+use test_case::test_case;
+
+use super::{AntiEntropy, AntiEntropyConfig};
+use crate::test_utils::{FaultType, MemoryStorageBuilder, StorageBuilder, TestBuilder};
+
+#[test_case(MemoryStorageBuilder::default(), 0; "memory")]
+#[test_case(MemoryStorageBuilder::default(), 10; "memory with chain state cache")]
+#[test_log::test(tokio::test)]
+async fn test_anti_entropy_pulls_missing_certificates<B>(
+    storage_builder: B,
+    chain_state_cache_size: usize,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0)
+        .await?
+        .with_chain_state_cache_size(chain_state_cache_size)
+        .await;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::ZERO)
+        .await?;
+
+    // The last validator misses the block of the sender.
+    builder.set_fault_type(3..4, FaultType::Offline).await;
+    sender
+        .transfer_to_account(
+            None,
+            Amount::ONE,
+            Account::chain(ChainId::root(2)),
+            UserData::default(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    builder.set_fault_type(3..4, FaultType::Honest).await;
+    builder
+        .check_that_validators_have_certificate(ChainId::root(1), BlockHeight::ZERO, 3)
+        .await
+        .unwrap();
+
+    let (node, storage) = builder.validator(3);
+    let mut anti_entropy = AntiEntropy::new(
+        node.name(),
+        node,
+        builder.make_node_provider(),
+        storage,
+        AntiEntropyConfig::default(),
+    );
+    assert_eq!(anti_entropy.reconcile_chain(ChainId::root(1)).await?, 1);
+    builder
+        .check_that_validators_have_certificate(ChainId::root(1), BlockHeight::ZERO, 4)
+        .await
+        .unwrap();
+
+    // Once the validator caught up, there is nothing left to pull.
+    assert_eq!(anti_entropy.reconcile_chain(ChainId::root(1)).await?, 0);
+    assert_eq!(anti_entropy.reconcile_sample().await?, 0);
+    Ok(())
+}
@@ -0,0 +1,211 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests of the worker with invalid inputs, as a Byzantine client or validator could send them.
+//!
+//! The worker must reject all of them with the appropriate error, without changing the state
+//! of the chains.
+
+use assert_matches::assert_matches;
+use linera_base::{
+    crypto::{CryptoHash, KeyPair, PublicKey},
+    data_types::{Amount, BlockHeight},
+    identifiers::{ChainDescription, ChainId},
+};
+use linera_chain::{
+    data_types::{BlockExecutionOutcome, Certificate, HashedCertificateValue, LiteVote},
+    test::{make_first_block, BlockTestExt},
+    ChainError,
+};
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+use test_case::test_case;
+
+use super::{init_worker_with_chains, make_simple_transfer_certificate};
+#[cfg(feature = "dynamodb")]
+use crate::test_utils::DynamoDbStorageBuilder;
+#[cfg(feature = "rocksdb")]
+use crate::test_utils::RocksDbStorageBuilder;
+#[cfg(feature = "scylladb")]
+use crate::test_utils::ScyllaDbStorageBuilder;
+use crate::{
+    test_utils::{MemoryStorageBuilder, StorageBuilder},
+    worker::{ValidatorWorker, WorkerError},
+};
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_certificates_with_bad_signatures<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let key_pair = KeyPair::generate();
+    let (committee, mut worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &key_pair,
+        ChainId::root(2),
+        Amount::from_tokens(5),
+        Vec::new(),
+        &committee,
+        Amount::ZERO,
+        &worker,
+        None,
+    )
+    .await;
+    let value = certificate.value.clone();
+    let round = certificate.round;
+    let validator_key_pair = worker.key_pair.clone().unwrap();
+
+    // No signatures at all.
+    let unsigned = Certificate::new(value.clone(), round, vec![]);
+    assert_matches!(
+        worker.fully_handle_certificate(unsigned, vec![]).await,
+        Err(WorkerError::ChainError(error)) if matches!(*error, ChainError::CertificateRequiresQuorum)
+    );
+
+    // A signature by a key that is not in the committee.
+    let vote = LiteVote::new(value.lite(), round, &KeyPair::generate());
+    let unknown_signer =
+        Certificate::new(value.clone(), round, vec![(vote.validator, vote.signature)]);
+    assert_matches!(
+        worker.fully_handle_certificate(unknown_signer, vec![]).await,
+        Err(WorkerError::ChainError(error)) if matches!(*error, ChainError::InvalidSigner)
+    );
+
+    // The validator's signature of another value.
+    let other_value = HashedCertificateValue::new_confirmed(
+        BlockExecutionOutcome::default().with(make_first_block(ChainId::root(1))),
+    );
+    let vote = LiteVote::new(other_value.lite(), round, &validator_key_pair);
+    let wrong_value =
+        Certificate::new(value.clone(), round, vec![(vote.validator, vote.signature)]);
+    assert_matches!(
+        worker.fully_handle_certificate(wrong_value, vec![]).await,
+        Err(WorkerError::ChainError(error)) if matches!(*error, ChainError::CryptoError(_))
+    );
+
+    // The same signature twice.
+    let signature = certificate.signatures()[0];
+    let reused = Certificate::new(value, round, vec![signature, signature]);
+    assert_matches!(
+        worker.fully_handle_certificate(reused, vec![]).await,
+        Err(WorkerError::ChainError(error)) if matches!(*error, ChainError::CertificateValidatorReuse)
+    );
+
+    // None of them changed the chain, so the valid certificate is still accepted.
+    let info = worker
+        .fully_handle_certificate(certificate, vec![])
+        .await?
+        .info;
+    assert_eq!(info.next_block_height, BlockHeight::from(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_certificates_for_the_wrong_chain<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let key_pair = KeyPair::generate();
+    let (committee, mut worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+
+    // A certificate for a chain that this validator doesn't know.
+    let unknown_chain = make_simple_transfer_certificate(
+        ChainDescription::Root(9),
+        &key_pair,
+        ChainId::root(2),
+        Amount::from_tokens(5),
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(5),
+        &worker,
+        None,
+    )
+    .await;
+    assert_matches!(
+        worker.fully_handle_certificate(unknown_chain, vec![]).await,
+        Err(WorkerError::ChainError(error)) if matches!(*error, ChainError::InactiveChain {..})
+    );
+
+    // A proposal for a chain that this validator doesn't know.
+    let proposal = make_first_block(ChainId::root(9))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(5))
+        .into_fast_proposal(&key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(proposal).await,
+        Err(WorkerError::ChainError(error)) if matches!(*error, ChainError::InactiveChain {..})
+    );
+
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &key_pair,
+        ChainId::root(2),
+        Amount::from_tokens(5),
+        Vec::new(),
+        &committee,
+        Amount::ZERO,
+        &worker,
+        None,
+    )
+    .await;
+    worker
+        .fully_handle_certificate(certificate.clone(), vec![])
+        .await?;
+
+    // A lite certificate whose value is known, but claims to be for another chain.
+    let mut wrong_chain = certificate.lite_certificate().cloned();
+    wrong_chain.value.chain_id = ChainId::root(2);
+    assert_matches!(
+        worker.handle_lite_certificate(wrong_chain, None).await,
+        Err(WorkerError::InvalidLiteCertificate)
+    );
+
+    // A lite certificate for a value that this validator has never seen.
+    let mut unknown_value = certificate.lite_certificate().cloned();
+    unknown_value.value.value_hash = CryptoHash::test_hash("unknown value");
+    assert_matches!(
+        worker.handle_lite_certificate(unknown_value, None).await,
+        Err(WorkerError::MissingCertificateValue)
+    );
+    Ok(())
+}
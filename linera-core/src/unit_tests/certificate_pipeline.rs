@@ -0,0 +1,57 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use linera_base::{crypto::KeyPair, data_types::Round, identifiers::ChainId};
+use linera_chain::{
+    test::{make_certificate, make_first_block},
+    ChainError,
+};
+use linera_execution::committee::{Committee, Epoch, ValidatorName};
+
+use super::{CertificatePipeline, CertificateStage};
+use crate::{committee_cache::CommitteeCache, worker::WorkerError};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_signatures_are_verified_on_the_thread_pool() {
+    let key_pair = KeyPair::generate();
+    let committee = Committee::make_simple(vec![ValidatorName(key_pair.public())]);
+    let other_committee = Committee::make_simple(vec![ValidatorName(KeyPair::generate().public())]);
+    for pipeline in [CertificatePipeline::default(), CertificatePipeline::new(2)] {
+        let committees = Arc::new(CommitteeCache::default());
+        let block = make_first_block(ChainId::root(0));
+        let certificate = make_certificate(block, Vec::new(), Round::Fast, &[&key_pair]);
+        assert_matches!(
+            pipeline
+                .verify_signatures(&committees, &certificate, Epoch::ZERO, &other_committee)
+                .await,
+            Err(WorkerError::ChainError(error)) if matches!(*error, ChainError::InvalidSigner)
+        );
+        assert!(!committees.is_verified(&certificate));
+
+        pipeline
+            .verify_signatures(&committees, &certificate, Epoch::ZERO, &committee)
+            .await
+            .expect("certificate should be valid");
+        // The verification is remembered, whichever task it ran on.
+        assert!(committees.is_verified(&certificate));
+        pipeline
+            .verify_signatures(&committees, &certificate, Epoch::ZERO, &committee)
+            .await
+            .expect("certificate should be valid");
+    }
+}
+
+#[test]
+fn test_stage_names() {
+    let stages = [
+        CertificateStage::VerifySignatures,
+        CertificateStage::Execute,
+        CertificateStage::Commit,
+        CertificateStage::FanOut,
+    ];
+    let names = stages.map(|stage| stage.name());
+    assert_eq!(names, ["verify_signatures", "execute", "commit", "fan_out"]);
+}
@@ -0,0 +1,85 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{num::NonZeroUsize, time::Duration};
+
+use futures::FutureExt as _;
+use linera_base::identifiers::ChainId;
+
+use super::{ChainLockDiagnostics, ChainScheduler};
+
+fn scheduler(max_concurrent_chains: usize) -> ChainScheduler {
+    ChainScheduler::new(NonZeroUsize::new(max_concurrent_chains).unwrap())
+}
+
+/// Tests that the queue of a chain is removed when its last turn ends.
+#[tokio::test]
+async fn finished_turn_does_not_leak() {
+    let scheduler = scheduler(2);
+    let turn = scheduler.schedule(ChainId::root(0), "test").await.unwrap();
+    assert_eq!(scheduler.pending_chains(), 1);
+    drop(turn);
+    assert_eq!(scheduler.pending_chains(), 0);
+}
+
+/// Tests that requests for the same chain are executed one at a time.
+#[tokio::test]
+async fn same_chain_is_executed_sequentially() {
+    let scheduler = scheduler(2);
+    let turn = scheduler.schedule(ChainId::root(0), "test").await.unwrap();
+    let mut next = Box::pin(scheduler.schedule(ChainId::root(0), "test"));
+    assert!((&mut next).now_or_never().is_none());
+    drop(turn);
+    assert!(next.now_or_never().is_some());
+}
+
+/// Tests that requests for different chains are executed concurrently, up to the limit.
+#[tokio::test]
+async fn different_chains_are_executed_concurrently_up_to_the_limit() {
+    let scheduler = scheduler(2);
+    let first = scheduler.schedule(ChainId::root(0), "test").await.unwrap();
+    let _second = scheduler.schedule(ChainId::root(1), "test").await.unwrap();
+    let mut third = Box::pin(scheduler.schedule(ChainId::root(2), "test"));
+    assert!((&mut third).now_or_never().is_none());
+    drop(first);
+    assert!(third.now_or_never().is_some());
+}
+
+/// Tests that a chain with queued requests does not hold more than one slot.
+#[tokio::test]
+async fn busy_chain_does_not_starve_other_chains() {
+    let scheduler = scheduler(2);
+    let busy = scheduler.schedule(ChainId::root(0), "test").await.unwrap();
+    let mut queued = Box::pin(scheduler.schedule(ChainId::root(0), "test"));
+    assert!((&mut queued).now_or_never().is_none());
+    assert!(scheduler
+        .schedule(ChainId::root(1), "test")
+        .now_or_never()
+        .is_some());
+    drop(busy);
+    assert!(queued.now_or_never().is_some());
+}
+
+/// Tests that a request waiting too long for its chain fails with a description of the holder.
+#[tokio::test]
+async fn waiting_too_long_aborts_with_a_report() {
+    let scheduler = scheduler(2).with_diagnostics(ChainLockDiagnostics {
+        warn_after: Some(Duration::from_millis(5)),
+        abort_after: Some(Duration::from_millis(20)),
+    });
+    let turn = scheduler.schedule(ChainId::root(0), "stuck").await.unwrap();
+    let report = scheduler
+        .schedule(ChainId::root(0), "test")
+        .await
+        .err()
+        .expect("the chain is held");
+    assert_eq!(report.holder.map(|(request, _)| request), Some("stuck"));
+    assert_eq!(report.waiters, 1);
+    assert!(report.waited >= Duration::from_millis(20));
+    drop(turn);
+    assert_eq!(scheduler.pending_chains(), 0);
+    assert!(scheduler
+        .schedule(ChainId::root(0), "test")
+        .now_or_never()
+        .is_some());
+}
@@ -5,6 +5,8 @@
 #[path = "./wasm_client_tests.rs"]
 mod wasm;
 
+use std::collections::BTreeMap;
+
 use assert_matches::assert_matches;
 use futures::StreamExt;
 use linera_base::{
@@ -18,7 +20,7 @@ use linera_chain::{
     ChainError, ChainExecutionContext,
 };
 use linera_execution::{
-    committee::{Committee, Epoch},
+    committee::{Committee, Epoch, ValidatorName},
     system::{Recipient, SystemOperation, UserData},
     ExecutionError, Message, MessageKind, Operation, ResourceControlPolicy, SystemExecutionError,
     SystemMessage, SystemQuery, SystemResponse,
@@ -36,13 +38,18 @@ use crate::test_utils::ScyllaDbStorageBuilder;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::test_utils::ServiceStorageBuilder;
 use crate::{
-    client::{ArcChainClient, ChainClientError, ClientOutcome, MessageAction, MessagePolicy},
+    client::{
+        ArcChainClient, ChainClientBuilder, ChainClientError, ClientOutcome, MessageAction,
+        MessageDecision, MessagePolicy, DEFAULT_MAX_CONFLICT_RETRIES,
+    },
     local_node::LocalNodeError,
     node::{
         CrossChainMessageDelivery,
         NodeError::{self, ClientIoError},
     },
-    test_utils::{FaultType, MemoryStorageBuilder, StorageBuilder, TestBuilder},
+    test_utils::{
+        FaultType, MemoryStorageBuilder, StorageBuilder, TestBuilder, TestValidatorNetwork,
+    },
     updater::CommunicationError,
     worker::{Notification, Reason, WorkerError},
 };
@@ -107,6 +114,109 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_estimate_and_collect_fees<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(ResourceControlPolicy::all_categories());
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let operation = Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::chain(ChainId::root(2)),
+        amount: Amount::from_tokens(3),
+        user_data: UserData::default(),
+    });
+    // The block, the operation and the outgoing message are paid for.
+    let expected_fees = Amount::from_millis(1).try_add(Amount::from_attos(20))?;
+    assert_eq!(
+        sender.estimate_fees(vec![operation.clone()]).await?,
+        expected_fees
+    );
+    // Estimating the fees does not change the chain.
+    assert_eq!(sender.next_block_height, BlockHeight::ZERO);
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(4));
+
+    sender.execute_operation(operation).await?.unwrap();
+    assert_eq!(
+        sender.local_balance().await?,
+        Amount::from_tokens(1).try_sub(expected_fees)?
+    );
+    let collected_fees = sender
+        .chain_state_view()
+        .await?
+        .execution_state
+        .system
+        .collected_fees()
+        .await?;
+    assert_eq!(
+        collected_fees,
+        BTreeMap::from([(Epoch::ZERO, expected_fees)])
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_preview_block<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let policy = ResourceControlPolicy {
+        maximum_operations_per_block: 2,
+        ..ResourceControlPolicy::all_categories()
+    };
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(policy);
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let operation = Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::chain(ChainId::root(2)),
+        amount: Amount::ONE,
+        user_data: UserData::default(),
+    });
+
+    let preview = sender.preview_block(vec![operation.clone()]).await?;
+    assert!(preview.fits_limits());
+    assert_eq!(preview.block.operations, vec![operation.clone()]);
+    assert_eq!(preview.size, bcs::serialized_size(&preview.block)? as u64);
+    assert_eq!(preview.limits.maximum_operations, 2);
+    assert!(preview.fuel_used.is_some());
+    assert_eq!(
+        preview.fees,
+        Some(sender.estimate_fees(vec![operation.clone()]).await?)
+    );
+
+    // Blocks with too many operations are not executed.
+    let preview = sender.preview_block(vec![operation.clone(); 3]).await?;
+    assert!(!preview.fits_contents());
+    assert!(!preview.fits_limits());
+    assert_eq!(preview.fuel_used, None);
+
+    // Previewing does not change the chain.
+    assert_eq!(sender.next_block_height, BlockHeight::ZERO);
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(4));
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -283,6 +393,200 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_with_weighted_committee<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    // The quorum is 5 votes out of 6: it is reached without one of the small validators, but
+    // not without the large one.
+    let mut network = TestValidatorNetwork::builder(storage_builder)
+        .with_votes([3, 1, 1, 1])
+        .with_fault_type(1, FaultType::Offline)
+        .with_initial_chain(Amount::ZERO)
+        .with_initial_chain(Amount::from_tokens(4))
+        .with_initial_chain(Amount::ZERO)
+        .build()
+        .await?;
+    assert_eq!(network.committee().quorum_threshold(), 5);
+    assert_eq!(network.clients().len(), 3);
+
+    let sender = network.client(1);
+    sender
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(ChainId::root(2)),
+            UserData::default(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sender.next_block_height, BlockHeight::from(1));
+    assert_eq!(sender.local_balance().await.unwrap(), Amount::ONE);
+    network
+        .test_builder()
+        .check_that_validators_have_certificate(ChainId::root(1), BlockHeight::ZERO, 3)
+        .await
+        .unwrap();
+
+    network
+        .test_builder()
+        .set_fault_type(0..1, FaultType::Offline)
+        .await;
+    let result = network
+        .client(1)
+        .transfer_to_account(
+            None,
+            Amount::ONE,
+            Account::chain(ChainId::root(2)),
+            UserData::default(),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainClientError::CommunicationError(
+            CommunicationError::Trusted(ClientIoError { .. }),
+        ))
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_sync_validator<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let mut receiver = builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::ZERO)
+        .await?;
+
+    // The last validator misses both the transfer and the block receiving it.
+    builder.set_fault_type(3..4, FaultType::Offline).await;
+    sender
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(ChainId::root(2)),
+            UserData::default(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 1);
+    builder.set_fault_type(3..4, FaultType::Honest).await;
+
+    // Uploading the receiver chain also uploads the sender's block it depends on.
+    let name = builder.validator(3).0.name();
+    let (initial_height, final_height) = receiver.sync_validator(name).await?;
+    assert_eq!(initial_height, BlockHeight::ZERO);
+    assert_eq!(final_height, BlockHeight::from(1));
+    builder
+        .check_that_validators_have_certificate(ChainId::root(1), BlockHeight::ZERO, 4)
+        .await
+        .unwrap();
+    builder
+        .check_that_validators_have_certificate(ChainId::root(2), BlockHeight::ZERO, 4)
+        .await
+        .unwrap();
+
+    assert_matches!(
+        receiver
+            .sync_validator(ValidatorName(KeyPair::generate().public()))
+            .await,
+        Err(ChainClientError::UnknownValidator(_))
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_received_certificate_trackers_after_restart<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let mut receiver = builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::ZERO)
+        .await?;
+    sender
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(ChainId::root(2)),
+            UserData::default(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+    let trackers = receiver.received_certificate_trackers().clone();
+    let name = builder.validator(3).0.name();
+    assert_eq!(trackers.get(&name), Some(&1));
+    let storage = receiver.storage_client().await;
+    assert_eq!(
+        storage
+            .read_received_certificate_trackers(ChainId::root(2))
+            .await?,
+        trackers
+    );
+
+    // After a restart, the client resumes from the saved trackers, including for the
+    // validators that are not reachable.
+    builder.set_fault_type(3..4, FaultType::Offline).await;
+    let mut receiver = ChainClientBuilder::new(
+        builder.make_node_provider(),
+        10,
+        CrossChainMessageDelivery::NonBlocking,
+    )
+    .build(
+        ChainId::root(2),
+        vec![receiver.key_pair().await?.copy()],
+        storage,
+        builder.admin_id(),
+        receiver.block_hash(),
+        receiver.timestamp(),
+        receiver.next_block_height(),
+        None,
+    );
+    assert!(receiver.received_certificate_trackers().is_empty());
+    receiver.synchronize_from_validators().await?;
+    assert_eq!(receiver.received_certificate_trackers(), &trackers);
+    builder.set_fault_type(3..4, FaultType::Honest).await;
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 1);
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(3));
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -486,6 +790,46 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_synchronize_chain_state<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..3 {
+        sender
+            .transfer_to_account(
+                None,
+                Amount::ONE,
+                Account::chain(ChainId::root(2)),
+                UserData::default(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    // A client that doesn't know about the new blocks, e.g. on another device.
+    let key_pair = sender.key_pair().await?.copy();
+    let mut client = builder
+        .make_client(sender.chain_id, key_pair, None, BlockHeight::ZERO)
+        .await?;
+    let info = client.synchronize_chain_state().await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(3));
+    assert_eq!(client.next_block_height, BlockHeight::from(3));
+    assert_eq!(client.block_hash, sender.block_hash);
+    assert_eq!(client.local_balance().await?, Amount::ONE);
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -526,6 +870,103 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_receive_from_unknown_child_chain<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    // New chains use the admin chain to verify their creation certificate.
+    builder
+        .add_initial_chain(ChainDescription::Root(0), Amount::ZERO)
+        .await?;
+    let mut parent = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let mut receiver = builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::ZERO)
+        .await?;
+    let new_key_pair = KeyPair::generate();
+    let (message_id, certificate) = parent
+        .open_chain(
+            ChainOwnership::single(new_key_pair.public()),
+            Amount::from_tokens(3),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let new_id = ChainId::child(message_id);
+    let mut child = builder
+        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
+        .await?;
+    child.receive_certificate(certificate).await.unwrap();
+    child
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(2),
+            Account::chain(receiver.chain_id),
+            UserData::default(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    // The receiver has never heard of the child chain or of the block of the parent chain
+    // that created it: both are downloaded to verify the transfer.
+    receiver.synchronize_from_validators().await.unwrap();
+    receiver.process_inbox().await.unwrap();
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(2));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_open_chains<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    // New chains use the admin chain to verify their creation certificate.
+    builder
+        .add_initial_chain(ChainDescription::Root(0), Amount::ZERO)
+        .await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let new_key_pairs = (0..3).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+    // Open three chains in a single block.
+    let chains = new_key_pairs
+        .iter()
+        .map(|key_pair| (ChainOwnership::single(key_pair.public()), Amount::ONE))
+        .collect();
+    let (message_ids, certificate) = sender.open_chains(chains).await.unwrap().unwrap();
+    assert_eq!(message_ids.len(), 3);
+    assert_eq!(sender.next_block_height, BlockHeight::from(1));
+    assert_eq!(sender.local_balance().await?, Amount::ONE);
+    for (message_id, key_pair) in message_ids.into_iter().zip(new_key_pairs) {
+        let new_id = ChainId::child(message_id);
+        let mut client = builder
+            .make_client(new_id, key_pair, None, BlockHeight::ZERO)
+            .await?;
+        client
+            .receive_certificate(certificate.clone())
+            .await
+            .unwrap();
+        assert_eq!(client.query_balance().await.unwrap(), Amount::ONE);
+    }
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -1266,11 +1707,8 @@ where
         .unwrap();
 
     // User is still at the initial epoch, but we can receive transfers from future
-    // epochs AFTER synchronizing the client with the admin chain.
-    assert_matches!(
-        user.receive_certificate(cert).await,
-        Err(ChainClientError::CommitteeSynchronizationError)
-    );
+    // epochs: the certificate makes the client fetch the new committee.
+    user.receive_certificate(cert).await.unwrap();
     assert_eq!(user.epoch().await.unwrap(), Epoch::from(1));
     user.synchronize_from_validators().await.unwrap();
 
@@ -1329,6 +1767,132 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[test_log::test(tokio::test)]
+async fn test_synchronize_committees<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut admin = builder
+        .add_initial_chain(ChainDescription::Root(0), Amount::from_tokens(3))
+        .await?;
+    let mut user = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::ZERO)
+        .await?;
+    let mut other_user = builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::ZERO)
+        .await?;
+    let validators = builder.initial_committee.validators().clone();
+
+    // Nothing to do as long as the committee does not change.
+    assert_eq!(user.synchronize_committees().await.unwrap(), None);
+    assert_eq!(other_user.synchronize_committees().await.unwrap(), None);
+
+    // The admin chain creates a new committee, and sends money from the new epoch.
+    let committee = Committee::new(validators, ResourceControlPolicy::only_fuel());
+    admin.stage_new_committee(committee).await.unwrap();
+    assert_eq!(admin.epoch().await.unwrap(), Epoch::from(1));
+    let cert = admin
+        .transfer_to_account(
+            None,
+            Amount::ONE,
+            Account::chain(ChainId::root(1)),
+            UserData(None),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The validators report the new epoch of the admin chain, so the other user fetches it.
+    assert_eq!(
+        other_user.synchronize_committees().await.unwrap(),
+        Some(Epoch::from(1))
+    );
+    assert_eq!(other_user.synchronize_committees().await.unwrap(), None);
+
+    // The user does not know the new committee yet: the certificate from the new epoch
+    // makes it fetch it.
+    user.receive_certificate(cert).await.unwrap();
+    assert_eq!(user.synchronize_committees().await.unwrap(), None);
+    assert_eq!(user.epoch().await.unwrap(), Epoch::ZERO);
+
+    // The user chain then migrates to the new epoch.
+    user.process_inbox().await.unwrap();
+    assert_eq!(user.epoch().await.unwrap(), Epoch::from(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[test_log::test(tokio::test)]
+async fn test_minimum_version<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let mut admin = builder
+        .add_initial_chain(ChainDescription::Root(0), Amount::from_tokens(3))
+        .await?;
+    let mut user = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(3))
+        .await?;
+    let version = SoftwareVersion::current();
+    let newer_version = SoftwareVersion {
+        major: version.major + 1,
+        minor: 0,
+        patch: 0,
+    };
+
+    // The admin chain requires a version that no one runs yet.
+    admin.set_minimum_version(newer_version).await.unwrap();
+    user.synchronize_from_validators().await.unwrap();
+    user.process_inbox().await.unwrap();
+    assert_eq!(
+        user.chain_info().await?.minimum_version,
+        Some(newer_version)
+    );
+    let outdated = user.outdated_validators().await?;
+    assert_eq!(outdated.len(), 4);
+    assert!(outdated
+        .iter()
+        .all(|(_, validator_version)| *validator_version == version));
+
+    // The user's client is outdated too, so it refuses to propose blocks with operations.
+    let result = user
+        .transfer_to_account(
+            None,
+            Amount::ONE,
+            Account::chain(ChainId::root(0)),
+            UserData(None),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainClientError::OutdatedSoftware { version: client_version, minimum })
+            if client_version == version && minimum == newer_version
+    );
+
+    // The admin chain can still lower the minimum version again.
+    admin.set_minimum_version(version).await.unwrap();
+    user.synchronize_from_validators().await.unwrap();
+    user.process_inbox().await.unwrap();
+    assert!(user.outdated_validators().await?.is_empty());
+    user.transfer_to_account(
+        None,
+        Amount::ONE,
+        Account::chain(ChainId::root(0)),
+        UserData(None),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
 #[test_log::test(tokio::test)]
@@ -1648,6 +2212,116 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[test_log::test(tokio::test)]
+async fn test_abandon_pending_block_after_ttl<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    // Configure a chain with two regular and no super owners, so there is no fast round.
+    let clock = storage_builder.clock().clone();
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let description = ChainDescription::Root(1);
+    let mut client = builder
+        .add_initial_chain(description, Amount::from_tokens(10))
+        .await?;
+    let pub_key0 = client.public_key().await.unwrap();
+    let pub_key1 = KeyPair::generate().public();
+    let owner_change_op = SystemOperation::ChangeOwnership {
+        super_owners: Vec::new(),
+        owners: vec![(pub_key0, 100), (pub_key1, 100)],
+        multi_leader_rounds: 10,
+        timeout_config: TimeoutConfig::default(),
+    }
+    .into();
+    client.execute_operation(owner_change_op).await.unwrap();
+    client.proposal_ttl = Some(TimeDelta::from_secs(1));
+
+    // The client tries to burn 3 tokens. Two validators are offline, so nothing will get
+    // validated or confirmed. However, the client now has a pending block.
+    builder
+        .set_fault_type(2..3, FaultType::OfflineWithInfo)
+        .await;
+    let result = client
+        .burn(None, Amount::from_tokens(3), UserData::default())
+        .await;
+    assert!(result.is_err());
+    assert!(client.pending_block.is_some());
+
+    // The validators are back, but the proposal TTL has passed: Instead of finalizing the
+    // pending block, the client abandons it and only burns the one token.
+    builder.set_fault_type(2..3, FaultType::Honest).await;
+    clock.add(TimeDelta::from_secs(2));
+    client
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap();
+    client.synchronize_from_validators().await.unwrap();
+    client.process_inbox().await.unwrap();
+    assert_eq!(
+        client.local_balance().await.unwrap(),
+        Amount::from_tokens(9)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[test_log::test(tokio::test)]
+async fn test_retry_after_conflicting_blocks_of_other_clients<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut client1 = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    // Another client of the same chain, e.g. another process using the same key.
+    let key_pair = client1.key_pair().await?.copy();
+    let mut client2 = builder
+        .make_client(client1.chain_id, key_pair, None, BlockHeight::ZERO)
+        .await?;
+
+    // The first client commits a block, so the second client's block at the same height is
+    // rejected. Without retries, the second client gives up, but it is up to date afterwards.
+    client1
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap();
+    client2.max_conflict_retries = 0;
+    let result = client2.burn(None, Amount::ONE, UserData::default()).await;
+    assert_matches!(
+        result,
+        Err(ChainClientError::TooManyConflicts { attempts: 1, .. })
+    );
+    assert_eq!(client2.next_block_height, BlockHeight::from(1));
+
+    // With retries, the second client rebuilds its block on top of the first client's one.
+    client1
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap();
+    client2.max_conflict_retries = DEFAULT_MAX_CONFLICT_RETRIES;
+    let certificate = client2
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(certificate.value().height(), BlockHeight::from(2));
+    assert_eq!(client2.next_block_height, BlockHeight::from(3));
+    assert_eq!(client2.local_balance().await?, Amount::ONE);
+
+    client1.synchronize_from_validators().await.unwrap();
+    assert_eq!(client1.next_block_height, BlockHeight::from(3));
+    assert_eq!(client1.local_balance().await?, Amount::ONE);
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
 #[test_log::test(tokio::test)]
@@ -1703,3 +2377,63 @@ where
 
     Ok(())
 }
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(not(target_arch = "wasm32"), test_case(ServiceStorageBuilder::new().await; "service"))]
+#[test_log::test(tokio::test)]
+async fn test_message_decisions<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(ResourceControlPolicy::only_fuel());
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let mut receiver = builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::ZERO)
+        .await?;
+    let recipient = Recipient::chain(ChainId::root(2));
+    for amount in [Amount::ONE, Amount::from_tokens(2)] {
+        let cert = sender
+            .transfer(None, amount, recipient, UserData(None))
+            .await
+            .unwrap()
+            .unwrap();
+        receiver.receive_certificate(cert).await?;
+    }
+    let first_transfer = MessageId {
+        chain_id: ChainId::root(1),
+        height: BlockHeight::ZERO,
+        index: 0,
+    };
+
+    // Transfers are tracked: They can be rejected, but not skipped.
+    receiver.set_message_decision(first_transfer, MessageDecision::Skip);
+    assert_matches!(
+        receiver.process_inbox().await,
+        Err(ChainClientError::CannotSkipMessage(message_id)) if message_id == first_transfer
+    );
+
+    receiver.set_message_decision(first_transfer, MessageDecision::Reject);
+    let certs = receiver.process_inbox().await?.0;
+    assert_eq!(certs.len(), 1);
+    // Only the second transfer was accepted.
+    assert_eq!(
+        receiver.local_balance().await.unwrap(),
+        Amount::from_tokens(2)
+    );
+    sender
+        .receive_certificate(certs.into_iter().next().unwrap())
+        .await?;
+    // The first transfer bounces.
+    assert_eq!(sender.process_inbox().await?.0.len(), 1);
+    assert_eq!(
+        sender.local_balance().await.unwrap(),
+        Amount::from_tokens(2)
+    );
+
+    Ok(())
+}
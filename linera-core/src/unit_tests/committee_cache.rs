@@ -0,0 +1,154 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use assert_matches::assert_matches;
+use linera_base::{
+    crypto::KeyPair,
+    data_types::{Amount, Round},
+    identifiers::{ChainId, Destination},
+};
+use linera_chain::{
+    data_types::{Certificate, OutgoingMessage},
+    test::{make_certificate, make_first_block},
+    ChainError,
+};
+use linera_execution::{
+    committee::{Committee, Epoch, ValidatorName},
+    system::{SystemChannel, SystemMessage},
+    Message, MessageKind,
+};
+
+use super::CommitteeCache;
+
+#[test]
+fn test_verified_certificates_are_not_checked_again() {
+    let key_pair = KeyPair::generate();
+    let committee = Committee::make_simple(vec![ValidatorName(key_pair.public())]);
+    let cache = CommitteeCache::default();
+    let certificate = make_certificate(
+        make_first_block(ChainId::root(1)),
+        Vec::new(),
+        Round::Fast,
+        &[&key_pair],
+    );
+    let other_committee = Committee::make_simple(vec![ValidatorName(KeyPair::generate().public())]);
+    let other_certificate = make_certificate(
+        make_first_block(ChainId::root(2)),
+        Vec::new(),
+        Round::Fast,
+        &[&key_pair],
+    );
+    assert_matches!(
+        cache.check(&other_certificate, Epoch::from(1), &other_committee),
+        Err(ChainError::InvalidSigner)
+    );
+
+    cache
+        .check(&certificate, Epoch::ZERO, &committee)
+        .expect("certificate should be valid");
+    assert_eq!(*cache.committee(Epoch::ZERO).unwrap(), committee);
+    // Once verified, it is accepted without checking the signatures again.
+    assert!(cache
+        .check(&certificate, Epoch::from(1), &other_committee)
+        .is_ok());
+    // But not with other signatures.
+    let mut forged = certificate.clone();
+    forged.add_signature(other_certificate.signatures()[0]);
+    assert!(cache.check(&forged, Epoch::ZERO, &committee).is_err());
+}
+
+#[test]
+fn test_check_batch_of_certificates() {
+    let key_pair = KeyPair::generate();
+    let committee = Committee::make_simple(vec![ValidatorName(key_pair.public())]);
+    let cache = CommitteeCache::default();
+    let certificates = (1..4)
+        .map(|index| {
+            make_certificate(
+                make_first_block(ChainId::root(index)),
+                Vec::new(),
+                Round::Fast,
+                &[&key_pair],
+            )
+        })
+        .collect::<Vec<_>>();
+    cache
+        .check(&certificates[0], Epoch::ZERO, &committee)
+        .expect("certificate should be valid");
+    let batch = certificates
+        .iter()
+        .map(|certificate| (certificate, Epoch::ZERO, &committee));
+    assert!(cache.check_batch(batch).is_ok());
+
+    let unsigned = Certificate::new(certificates[1].value.clone(), Round::Fast, Vec::new());
+    let batch = [
+        (&certificates[2], Epoch::ZERO, &committee),
+        (&unsigned, Epoch::ZERO, &committee),
+    ];
+    assert_matches!(
+        cache.check_batch(batch),
+        Err(ChainError::CertificateRequiresQuorum)
+    );
+}
+
+#[test]
+fn test_committees_are_added_from_the_admin_chain() {
+    let key_pair = KeyPair::generate();
+    let committee = Committee::make_simple(vec![ValidatorName(key_pair.public())]);
+    let new_committee = Committee::make_simple(vec![ValidatorName(KeyPair::generate().public())]);
+    let admin_id = ChainId::root(0);
+    let set_committees = OutgoingMessage {
+        destination: Destination::Subscribers(SystemChannel::Admin.name()),
+        authenticated_signer: None,
+        grant: Amount::ZERO,
+        refund_grant_to: None,
+        kind: MessageKind::Protected,
+        expiration: None,
+        message: Message::System(SystemMessage::SetCommittees {
+            epoch: Epoch::from(1),
+            committees: BTreeMap::from([
+                (Epoch::ZERO, committee.clone()),
+                (Epoch::from(1), new_committee.clone()),
+            ]),
+        }),
+    };
+    let certificate = make_certificate(
+        make_first_block(admin_id),
+        vec![set_committees.clone()],
+        Round::Fast,
+        &[&key_pair],
+    );
+    let cache = CommitteeCache::default();
+
+    // The certificate can't be verified without the committee of its epoch.
+    assert!(cache
+        .add_committees_from(admin_id, &certificate)
+        .unwrap()
+        .is_empty());
+    assert!(cache.committee(Epoch::from(1)).is_none());
+
+    cache.insert(Epoch::ZERO, &committee);
+    // Other chains can't create committees.
+    let other_chain = make_certificate(
+        make_first_block(ChainId::root(1)),
+        vec![set_committees],
+        Round::Fast,
+        &[&key_pair],
+    );
+    assert!(cache
+        .add_committees_from(admin_id, &other_chain)
+        .unwrap()
+        .is_empty());
+    // Certificates not signed by the committee of their epoch are rejected.
+    let unsigned = Certificate::new(certificate.value.clone(), Round::Fast, Vec::new());
+    assert!(cache.add_committees_from(admin_id, &unsigned).is_err());
+    assert!(cache.committee(Epoch::from(1)).is_none());
+
+    assert_eq!(
+        cache.add_committees_from(admin_id, &certificate).unwrap(),
+        vec![Epoch::from(1)]
+    );
+    assert_eq!(*cache.committee(Epoch::from(1)).unwrap(), new_committee);
+}
@@ -0,0 +1,97 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::KeyPair,
+    data_types::{Amount, Round},
+    identifiers::ChainId,
+};
+use linera_chain::{
+    data_types::{BlockExecutionOutcome, Certificate, HashedCertificateValue},
+    test::{self, make_first_block, BlockTestExt},
+};
+use linera_execution::committee::ValidatorName;
+
+use super::EquivocationDetector;
+
+/// Returns a certificate for a first block on the chain, signed by the given validators.
+fn make_certificate(
+    key_pairs: &[&KeyPair],
+    round: Round,
+    amount: Amount,
+    validated: bool,
+) -> Certificate {
+    let block = make_first_block(ChainId::root(1)).with_simple_transfer(ChainId::root(2), amount);
+    if validated {
+        let value =
+            HashedCertificateValue::new_validated(BlockExecutionOutcome::default().with(block));
+        test::make_certificate_for_value(value, round, key_pairs)
+    } else {
+        test::make_certificate(block, Vec::new(), round, key_pairs)
+    }
+}
+
+/// Tests that only validators who signed two different blocks in the same round are reported.
+#[test]
+fn conflicting_signatures_are_detected() {
+    let honest = KeyPair::generate();
+    let faulty = KeyPair::generate();
+    let detector = EquivocationDetector::default();
+
+    let round = Round::MultiLeader(0);
+    detector.record_certificate(&make_certificate(
+        &[&honest, &faulty],
+        round,
+        Amount::ONE,
+        true,
+    ));
+    // Confirming the validated block is not an equivocation.
+    detector.record_certificate(&make_certificate(
+        &[&honest, &faulty],
+        round,
+        Amount::ONE,
+        false,
+    ));
+    // Signing a different block in a later round is allowed, too.
+    detector.record_certificate(&make_certificate(
+        &[&honest],
+        Round::MultiLeader(1),
+        Amount::from_tokens(2),
+        true,
+    ));
+    assert!(detector.evidence().is_empty());
+
+    detector.record_certificate(&make_certificate(
+        &[&faulty],
+        round,
+        Amount::from_tokens(2),
+        true,
+    ));
+    let evidence = detector.evidence();
+    assert_eq!(evidence.len(), 1);
+    assert_eq!(evidence[0].validator(), ValidatorName(faulty.public()));
+    evidence[0].check().unwrap();
+}
+
+/// Tests that the signatures of old certificates are forgotten.
+#[test]
+fn old_signatures_are_forgotten() {
+    let faulty = KeyPair::generate();
+    let detector = EquivocationDetector::new(1);
+
+    let round = Round::Fast;
+    detector.record_certificate(&make_certificate(&[&faulty], round, Amount::ONE, true));
+    detector.record_certificate(&make_certificate(
+        &[&faulty],
+        Round::MultiLeader(0),
+        Amount::ONE,
+        true,
+    ));
+    detector.record_certificate(&make_certificate(
+        &[&faulty],
+        round,
+        Amount::from_tokens(2),
+        true,
+    ));
+    assert!(detector.evidence().is_empty());
+}
@@ -0,0 +1,60 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use linera_base::{identifiers::ChainId, time::Duration};
+
+use super::{LoadMonitor, LOAD_WINDOW};
+
+/// Tests that requests are pending until they are answered.
+#[test]
+fn pending_requests_are_counted() {
+    let monitor = Arc::new(LoadMonitor::default());
+    let first = monitor.start_request(ChainId::root(0));
+    let second = monitor.start_request(ChainId::root(0));
+    assert_eq!(monitor.report().queue_depth, 2);
+    drop(first);
+    assert_eq!(monitor.report().queue_depth, 1);
+    drop(second);
+    assert_eq!(monitor.report().queue_depth, 0);
+}
+
+/// Tests that the load is averaged over the last complete window.
+#[test]
+fn load_is_averaged_over_the_last_window() {
+    let monitor = LoadMonitor::default();
+    let start = monitor.windows.lock().unwrap().current.start;
+    for _ in 0..30 {
+        monitor.count_request(ChainId::root(1), start);
+    }
+    for _ in 0..10 {
+        monitor.count_request(ChainId::root(0), start + Duration::from_secs(1));
+    }
+    monitor.count_storage_load(Duration::from_millis(2), start);
+    monitor.count_storage_load(Duration::from_millis(4), start);
+
+    let report = monitor.report_at(start + Duration::from_secs(5));
+    assert_eq!(report.requests_per_second, 8.0);
+    assert_eq!(report.storage_latency_ms, Some(3.0));
+    assert_eq!(
+        report.busiest_chains,
+        vec![(ChainId::root(1), 30), (ChainId::root(0), 10)]
+    );
+
+    // Requests in the next window don't change the report until it is complete.
+    monitor.count_request(ChainId::root(2), start + LOAD_WINDOW);
+    let report = monitor.report_at(start + LOAD_WINDOW + Duration::from_secs(5));
+    assert_eq!(report.requests_per_second, 4.0);
+    assert_eq!(report.busiest_chains[0], (ChainId::root(1), 30));
+
+    let report = monitor.report_at(start + LOAD_WINDOW * 2);
+    assert_eq!(report.requests_per_second, 0.1);
+    assert_eq!(report.storage_latency_ms, None);
+    assert_eq!(report.busiest_chains, vec![(ChainId::root(2), 1)]);
+
+    // After a whole window without requests, there is no load left.
+    let report = monitor.report_at(start + LOAD_WINDOW * 4);
+    assert_eq!(report.requests_per_second, 0.0);
+    assert!(report.busiest_chains.is_empty());
+}
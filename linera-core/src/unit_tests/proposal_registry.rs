@@ -0,0 +1,48 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use futures::future::{self, Abortable, Aborted};
+use linera_base::{data_types::BlockHeight, identifiers::ChainId};
+
+use super::ProposalRegistry;
+
+/// Tests that proposals are listed until their registration is dropped.
+#[test]
+fn registered_proposals_are_listed() {
+    let registry = Arc::new(ProposalRegistry::default());
+    let (first, _) = registry.register(ChainId::root(0), BlockHeight(3));
+    let (second, _) = registry.register(ChainId::root(1), BlockHeight(0));
+    second.start_executing();
+
+    let proposals = registry.list();
+    assert_eq!(proposals.len(), 2);
+    assert_eq!(proposals[0].chain_id, ChainId::root(0));
+    assert_eq!(proposals[0].height, BlockHeight(3));
+    assert!(!proposals[0].executing);
+    assert_eq!(proposals[1].chain_id, ChainId::root(1));
+    assert!(proposals[1].executing);
+
+    drop(first);
+    let proposals = registry.list();
+    assert_eq!(proposals.len(), 1);
+    assert_eq!(proposals[0].chain_id, ChainId::root(1));
+    drop(second);
+    assert!(registry.list().is_empty());
+}
+
+/// Tests that cancelling a proposal aborts the future handling it.
+#[tokio::test]
+async fn cancelled_proposal_is_aborted() {
+    let registry = Arc::new(ProposalRegistry::default());
+    let (registration, abort_registration) = registry.register(ChainId::root(0), BlockHeight(0));
+    let handling = Abortable::new(future::pending::<()>(), abort_registration);
+
+    let status = registry.cancel(registration.id).unwrap();
+    assert_eq!(status.chain_id, ChainId::root(0));
+    assert_eq!(handling.await, Err(Aborted));
+
+    drop(registration);
+    assert_eq!(registry.cancel(status.id), None);
+}
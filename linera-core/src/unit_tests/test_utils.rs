@@ -12,13 +12,14 @@ use futures::{lock::Mutex, Future};
 use linera_base::{
     crypto::*,
     data_types::*,
-    identifiers::{ChainDescription, ChainId},
+    identifiers::{Blob, BlobId, ChainDescription, ChainId},
+    ownership::ChainOwnership,
 };
 use linera_chain::data_types::{
-    BlockProposal, Certificate, HashedCertificateValue, LiteCertificate,
+    Block, BlockProposal, Certificate, ExecutedBlock, HashedCertificateValue, LiteCertificate,
 };
 use linera_execution::{
-    committee::{Committee, ValidatorName},
+    committee::{Committee, ValidatorName, ValidatorState},
     ResourceControlPolicy, WasmRuntime,
 };
 use linera_storage::{MemoryStorage, Storage, TestClock};
@@ -37,6 +38,7 @@ use {
     linera_storage::RocksDbStorage,
     linera_views::rocks_db::create_rocks_db_common_config,
     linera_views::rocks_db::RocksDbStoreConfig,
+    linera_views::rocks_db::RocksDbTuningConfig,
     tokio::sync::{Semaphore, SemaphorePermit},
 };
 #[cfg(feature = "scylladb")]
@@ -144,6 +146,22 @@ where
         .await
     }
 
+    async fn simulate_block_proposal(&mut self, block: Block) -> Result<ExecutedBlock, NodeError> {
+        self.spawn_and_receive(move |validator, sender| {
+            validator.do_simulate_block_proposal(block, sender)
+        })
+        .await
+    }
+
+    async fn download_blob(
+        &mut self,
+        _chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Result<Blob, NodeError> {
+        self.spawn_and_receive(move |validator, sender| validator.do_download_blob(blob_id, sender))
+            .await
+    }
+
     async fn subscribe(&mut self, chains: Vec<ChainId>) -> Result<NotificationStream, NodeError> {
         self.spawn_and_receive(move |validator, sender| validator.do_subscribe(chains, sender))
             .await
@@ -152,6 +170,10 @@ where
     async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError> {
         Ok(Default::default())
     }
+
+    async fn get_genesis_config_hash(&mut self) -> Result<CryptoHash, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
 }
 
 impl<S> LocalValidatorClient<S>
@@ -171,6 +193,10 @@ where
         }
     }
 
+    pub fn name(&self) -> ValidatorName {
+        self.name
+    }
+
     async fn set_fault_type(&self, fault_type: FaultType) {
         self.client.lock().await.fault_type = fault_type;
     }
@@ -179,6 +205,11 @@ where
         self.client.lock().await.fault_type
     }
 
+    async fn set_chain_state_cache_size(&self, size: usize) {
+        let mut validator = self.client.lock().await;
+        validator.state = validator.state.clone().with_chain_state_cache_size(size);
+    }
+
     /// Executes the future produced by `f` in a new thread in a new Tokio runtime.
     /// Returns the value that the future puts into the sender.
     async fn spawn_and_receive<F, R, T>(&self, f: F) -> T
@@ -305,6 +336,47 @@ where
         sender.send(result.map(|(info, _actions)| info))
     }
 
+    async fn do_simulate_block_proposal(
+        self,
+        block: Block,
+        sender: oneshot::Sender<Result<ExecutedBlock, NodeError>>,
+    ) -> Result<(), Result<ExecutedBlock, NodeError>> {
+        let mut validator = self.client.lock().await;
+        let result = if validator.fault_type == FaultType::Offline {
+            Err(NodeError::ClientIoError {
+                error: "offline".to_string(),
+            })
+        } else {
+            validator
+                .state
+                .simulate_block_proposal(block)
+                .await
+                .map_err(Into::into)
+        };
+        sender.send(result)
+    }
+
+    async fn do_download_blob(
+        self,
+        blob_id: BlobId,
+        sender: oneshot::Sender<Result<Blob, NodeError>>,
+    ) -> Result<(), Result<Blob, NodeError>> {
+        let validator = self.client.lock().await;
+        let result = match validator.fault_type {
+            FaultType::Offline => Err(NodeError::ClientIoError {
+                error: "offline".to_string(),
+            }),
+            // Malicious validators answer with the wrong content.
+            FaultType::Malicious => Ok(Blob::new(b"malicious".to_vec())),
+            _ => validator
+                .state
+                .download_blob(blob_id)
+                .await
+                .map_err(Into::into),
+        };
+        sender.send(result)
+    }
+
     async fn do_subscribe(
         self,
         chains: Vec<ChainId>,
@@ -421,7 +493,7 @@ impl GenesisStorageBuilder {
                     initial_committee.clone(),
                     admin_id,
                     account.description,
-                    account.public_key,
+                    ChainOwnership::single(account.public_key),
                     account.balance,
                     Timestamp::from(0),
                 )
@@ -438,19 +510,32 @@ where
     ViewError: From<<B::Storage as Storage>::ContextError>,
 {
     pub async fn new(
-        mut storage_builder: B,
+        storage_builder: B,
         count: usize,
         with_faulty_validators: usize,
+    ) -> Result<Self, anyhow::Error> {
+        Self::with_votes(storage_builder, vec![1; count], with_faulty_validators).await
+    }
+
+    /// Creates one validator for each entry of `votes`, with that voting power.
+    pub async fn with_votes(
+        mut storage_builder: B,
+        votes: Vec<u64>,
+        with_faulty_validators: usize,
     ) -> Result<Self, anyhow::Error> {
         let mut key_pairs = Vec::new();
-        let mut validators = Vec::new();
-        for _ in 0..count {
+        let mut validators = BTreeMap::new();
+        for votes in votes {
             let key_pair = KeyPair::generate();
             let name = ValidatorName(key_pair.public());
-            validators.push(name);
+            let state = ValidatorState {
+                network_address: name.to_string(),
+                votes,
+            };
+            validators.insert(name, state);
             key_pairs.push(key_pair);
         }
-        let initial_committee = Committee::make_simple(validators);
+        let initial_committee = Committee::new(validators, ResourceControlPolicy::default());
         let mut validator_clients = Vec::new();
         let mut validator_storages = HashMap::new();
         let mut faulty_validators = HashSet::new();
@@ -489,6 +574,15 @@ where
         self
     }
 
+    /// Makes the workers of the validators keep up to `size` chain states in memory between
+    /// requests.
+    pub async fn with_chain_state_cache_size(self, size: usize) -> Self {
+        for validator in &self.validator_clients {
+            validator.set_chain_state_cache_size(size).await;
+        }
+        self
+    }
+
     pub async fn set_fault_type<I>(&mut self, range: I, fault_type: FaultType)
     where
         I: SliceIndex<
@@ -526,7 +620,7 @@ where
                         self.initial_committee.clone(),
                         self.admin_id,
                         description,
-                        public_key,
+                        ChainOwnership::single(public_key),
                         Amount::ZERO,
                         Timestamp::from(0),
                     )
@@ -538,7 +632,7 @@ where
                         self.initial_committee.clone(),
                         self.admin_id,
                         description,
-                        public_key,
+                        ChainOwnership::single(public_key),
                         balance,
                         Timestamp::from(0),
                     )
@@ -552,7 +646,7 @@ where
                     self.initial_committee.clone(),
                     self.admin_id,
                     description,
-                    public_key,
+                    ChainOwnership::single(public_key),
                     balance,
                     Timestamp::from(0),
                 )
@@ -579,6 +673,13 @@ where
         self.admin_id
     }
 
+    /// Returns the node and the storage of the validator with the given index.
+    pub fn validator(&self, index: usize) -> (LocalValidatorClient<B::Storage>, B::Storage) {
+        let validator = self.validator_clients[index].clone();
+        let storage = self.validator_storages[&validator.name].clone();
+        (validator, storage)
+    }
+
     pub fn make_node_provider(&self) -> NodeProvider<B::Storage> {
         self.validator_clients.iter().cloned().collect()
     }
@@ -682,6 +783,116 @@ where
     }
 }
 
+/// A network of validators running in the current process, and clients of its initial chains.
+///
+/// Unlike a local network of validator binaries, it needs no ports or external processes, so
+/// that application and protocol tests can use it directly.
+pub struct TestValidatorNetwork<B: StorageBuilder> {
+    builder: TestBuilder<B>,
+    clients: Vec<ChainClient<NodeProvider<B::Storage>, B::Storage>>,
+}
+
+/// Configures a [`TestValidatorNetwork`]. By default, there are four honest validators with one
+/// vote each, and no initial chain.
+pub struct TestValidatorNetworkBuilder<B> {
+    storage_builder: B,
+    votes: Vec<u64>,
+    policy: ResourceControlPolicy,
+    fault_types: BTreeMap<usize, FaultType>,
+    initial_balances: Vec<Amount>,
+}
+
+impl<B: StorageBuilder> TestValidatorNetwork<B> {
+    /// Starts configuring a network whose validators and clients use the given storage.
+    pub fn builder(storage_builder: B) -> TestValidatorNetworkBuilder<B> {
+        TestValidatorNetworkBuilder {
+            storage_builder,
+            votes: vec![1; 4],
+            policy: ResourceControlPolicy::default(),
+            fault_types: BTreeMap::new(),
+            initial_balances: Vec::new(),
+        }
+    }
+
+    /// Returns the clients of the initial chains, in order: the `i`-th one is the client of
+    /// `ChainId::root(i)`.
+    pub fn clients(&mut self) -> &mut [ChainClient<NodeProvider<B::Storage>, B::Storage>] {
+        &mut self.clients
+    }
+
+    /// Returns the client of `ChainId::root(index)`.
+    pub fn client(
+        &mut self,
+        index: usize,
+    ) -> &mut ChainClient<NodeProvider<B::Storage>, B::Storage> {
+        &mut self.clients[index]
+    }
+
+    pub fn committee(&self) -> &Committee {
+        &self.builder.initial_committee
+    }
+
+    /// Returns the underlying [`TestBuilder`], e.g. to add clients or change fault types.
+    pub fn test_builder(&mut self) -> &mut TestBuilder<B> {
+        &mut self.builder
+    }
+}
+
+impl<B> TestValidatorNetworkBuilder<B>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    /// Uses `count` validators with one vote each.
+    pub fn with_validators(mut self, count: usize) -> Self {
+        self.votes = vec![1; count];
+        self
+    }
+
+    /// Uses one validator for each entry of `votes`, with that voting power.
+    pub fn with_votes(mut self, votes: impl IntoIterator<Item = u64>) -> Self {
+        self.votes = votes.into_iter().collect();
+        self
+    }
+
+    pub fn with_policy(mut self, policy: ResourceControlPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Makes the `index`-th validator behave according to `fault_type`.
+    pub fn with_fault_type(mut self, index: usize, fault_type: FaultType) -> Self {
+        self.fault_types.insert(index, fault_type);
+        self
+    }
+
+    /// Adds the next initial root chain, with the given balance.
+    pub fn with_initial_chain(mut self, balance: Amount) -> Self {
+        self.initial_balances.push(balance);
+        self
+    }
+
+    /// Creates the validators and the initial chains, and returns the network.
+    pub async fn build(self) -> Result<TestValidatorNetwork<B>, anyhow::Error> {
+        let count = self.votes.len();
+        if let Some(index) = self.fault_types.keys().find(|index| **index >= count) {
+            anyhow::bail!("there is no validator {index} among {count} validators");
+        }
+        let mut builder = TestBuilder::with_votes(self.storage_builder, self.votes, 0)
+            .await?
+            .with_policy(self.policy);
+        for (index, fault_type) in self.fault_types {
+            builder.set_fault_type(index..=index, fault_type).await;
+        }
+        let mut clients = Vec::new();
+        for (index, balance) in self.initial_balances.into_iter().enumerate() {
+            let description = ChainDescription::Root(u32::try_from(index)?);
+            clients.push(builder.add_initial_chain(description, balance).await?);
+        }
+        Ok(TestValidatorNetwork { builder, clients })
+    }
+}
+
 #[cfg(feature = "rocksdb")]
 /// Limit concurrency for RocksDB tests to avoid "too many open files" errors.
 static ROCKS_DB_SEMAPHORE: Semaphore = Semaphore::const_new(5);
@@ -765,6 +976,7 @@ impl StorageBuilder for RocksDbStorageBuilder {
         let store_config = RocksDbStoreConfig {
             path_buf,
             common_config,
+            tuning: RocksDbTuningConfig::default(),
         };
         let namespace = generate_test_namespace();
         let storage = RocksDbStorage::new_for_testing(
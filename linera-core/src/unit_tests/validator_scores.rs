@@ -0,0 +1,124 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use linera_base::{crypto::KeyPair, time::Duration};
+use linera_execution::{
+    committee::{Committee, ValidatorName, ValidatorState},
+    ResourceControlPolicy,
+};
+
+use super::{ValidatorReputation, ValidatorScores};
+
+/// Returns the names of `count` new validators.
+fn make_validators(count: usize) -> Vec<ValidatorName> {
+    (0..count)
+        .map(|_| ValidatorName(KeyPair::generate().public()))
+        .collect()
+}
+
+/// Returns the names of the validators in the order chosen by the scores.
+fn order(scores: &ValidatorScores, validators: &[ValidatorName]) -> Vec<ValidatorName> {
+    let validators = validators.iter().map(|name| (*name, ())).collect();
+    scores
+        .order(validators)
+        .into_iter()
+        .map(|(name, ())| name)
+        .collect()
+}
+
+/// Tests that validators are picked first in proportion to their stake.
+#[test]
+fn validators_are_picked_by_stake() {
+    let validators = make_validators(2);
+    let committee = Committee::new(
+        BTreeMap::from([
+            (
+                validators[0],
+                ValidatorState {
+                    network_address: String::new(),
+                    votes: 1,
+                },
+            ),
+            (
+                validators[1],
+                ValidatorState {
+                    network_address: String::new(),
+                    votes: 9,
+                },
+            ),
+        ]),
+        ResourceControlPolicy::default(),
+    );
+    let scores = ValidatorScores::default();
+    scores.update_stakes(&committee);
+
+    let first_picks = (0..1_000)
+        .filter(|_| order(&scores, &validators)[0] == validators[1])
+        .count();
+    assert!((800..=980).contains(&first_picks), "{first_picks}");
+}
+
+/// Tests that validators failing repeatedly are tried last, and are not promoted again.
+#[test]
+fn failing_validators_are_demoted() {
+    let validators = make_validators(3);
+    let scores = ValidatorScores::default();
+    scores.record_failure(validators[0]);
+    scores.record_failure(validators[0]);
+    scores.record_success(validators[0], Duration::from_millis(10));
+    scores.record_failure(validators[0]);
+    scores.record_failure(validators[0]);
+    assert!((0..100).any(|_| order(&scores, &validators)[0] == validators[0]));
+
+    scores.record_failure(validators[0]);
+    scores.record_success(validators[0], Duration::from_millis(10));
+    for _ in 0..100 {
+        assert_eq!(order(&scores, &validators)[2], validators[0]);
+    }
+}
+
+/// Tests that validators much slower than the others are tried last.
+#[test]
+fn slow_validators_are_demoted() {
+    let validators = make_validators(4);
+    let scores = ValidatorScores::default();
+    for name in &validators[..3] {
+        scores.record_success(*name, Duration::from_millis(10));
+    }
+    scores.record_success(validators[3], Duration::from_millis(20));
+    assert!((0..100).any(|_| order(&scores, &validators)[0] == validators[3]));
+
+    scores.record_success(validators[3], Duration::from_secs(1));
+    for _ in 0..100 {
+        assert_eq!(order(&scores, &validators)[3], validators[3]);
+    }
+}
+
+/// Tests that the reputations of the validators are restored in a new session, but not their
+/// demotions.
+#[test]
+fn reputations_are_restored_without_demotions() {
+    let validators = make_validators(2);
+    let scores = ValidatorScores::default();
+    scores.record_success(validators[0], Duration::from_millis(10));
+    for _ in 0..3 {
+        scores.record_failure(validators[1]);
+    }
+    let reputations = scores.reputations();
+    assert_eq!(
+        reputations[&validators[0]],
+        ValidatorReputation {
+            latency: Some(Duration::from_millis(10)),
+            successes: 1,
+            failures: 0,
+            consecutive_failures: 0,
+        }
+    );
+    assert_eq!(reputations[&validators[1]].failures, 3);
+
+    let scores = ValidatorScores::from_reputations(reputations.clone());
+    assert_eq!(scores.reputations(), reputations);
+    assert!((0..100).any(|_| order(&scores, &validators)[0] == validators[1]));
+}
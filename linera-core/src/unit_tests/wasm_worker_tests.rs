@@ -144,11 +144,14 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Protected,
+                expiration: None,
                 message: Message::System(publish_message.clone()),
             }],
             message_counts: vec![1],
             state_hash: publisher_state_hash,
             oracle_records: vec![OracleRecord::default()],
+            fuel_used: 0,
+            events: Vec::new(),
         }
         .with(publish_block),
     );
@@ -177,6 +180,7 @@ where
             grant: Amount::ZERO,
             refund_grant_to: None,
             kind: MessageKind::Protected,
+            expiration: None,
             timestamp: Timestamp::from(1),
             message: Message::System(publish_message),
         },
@@ -211,6 +215,7 @@ where
         grant: Amount::ONE, // Can't have grants on broadcast messages
         refund_grant_to: None,
         kind: MessageKind::Simple,
+        expiration: None,
         message: Message::System(broadcast_message.clone()),
     };
     let failing_broadcast_block_proposal = HashedCertificateValue::new_confirmed(
@@ -219,6 +224,8 @@ where
             message_counts: vec![1],
             state_hash: publisher_state_hash,
             oracle_records: vec![OracleRecord::default()],
+            fuel_used: 0,
+            events: Vec::new(),
         }
         .with(broadcast_block.clone()),
     );
@@ -236,6 +243,7 @@ where
         grant: Amount::ZERO,
         refund_grant_to: None,
         kind: MessageKind::Simple,
+        expiration: None,
         message: Message::System(broadcast_message.clone()),
     };
     let broadcast_block_proposal = HashedCertificateValue::new_confirmed(
@@ -244,6 +252,8 @@ where
             message_counts: vec![1],
             state_hash: publisher_state_hash,
             oracle_records: vec![OracleRecord::default()],
+            fuel_used: 0,
+            events: Vec::new(),
         }
         .with(broadcast_block),
     );
@@ -294,11 +304,14 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Protected,
+                expiration: None,
                 message: Message::System(subscribe_message.clone()),
             }],
             message_counts: vec![1],
             state_hash: creator_state.crypto_hash().await?,
             oracle_records: vec![OracleRecord::default()],
+            fuel_used: 0,
+            events: Vec::new(),
         }
         .with(subscribe_block),
     );
@@ -327,6 +340,7 @@ where
             grant: Amount::ZERO,
             refund_grant_to: None,
             kind: MessageKind::Protected,
+            expiration: None,
             timestamp: Timestamp::from(2),
             message: subscribe_message.into(),
         },
@@ -345,6 +359,7 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Protected,
+                expiration: None,
                 message: Message::System(SystemMessage::Notify {
                     id: creator_chain.into(),
                 }),
@@ -352,6 +367,8 @@ where
             message_counts: vec![1],
             state_hash: publisher_state_hash,
             oracle_records: vec![OracleRecord::default()],
+            fuel_used: 0,
+            events: Vec::new(),
         }
         .with(accept_block),
     );
@@ -393,6 +410,7 @@ where
         creation: application_id.creation,
         required_application_ids: vec![],
         parameters: parameters_bytes,
+        upgrades: vec![],
     };
     let publish_admin_channel = ChannelFullName {
         application_id: GenericApplicationId::System,
@@ -411,6 +429,7 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Simple,
+                expiration: None,
                 timestamp: Timestamp::from(1),
                 message: Message::System(broadcast_message),
             },
@@ -426,7 +445,7 @@ where
         .insert(application_id, application_description.clone());
     creator_system_state.timestamp = Timestamp::from(4);
     let mut creator_state = creator_system_state.into_view().await;
-    creator_state
+    let instantiation_resources = creator_state
         .simulate_instantiation(
             contract,
             application_description,
@@ -441,11 +460,14 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Protected,
+                expiration: None,
                 message: Message::System(SystemMessage::ApplicationCreated),
             }],
             message_counts: vec![0, 1],
             state_hash: creator_state.crypto_hash().await?,
             oracle_records: vec![OracleRecord::default(); 2],
+            fuel_used: instantiation_resources.fuel,
+            events: Vec::new(),
         }
         .with(create_block),
     );
@@ -477,6 +499,7 @@ where
         authenticated_signer: None,
         authenticated_caller_id: None,
         height: run_block.height,
+        previous_block_hash: run_block.previous_block_hash,
         index: Some(0),
         next_message_index: 0,
     };
@@ -499,6 +522,8 @@ where
             message_counts: vec![0],
             state_hash: creator_state.crypto_hash().await?,
             oracle_records: vec![OracleRecord::default()],
+            fuel_used: controller.tracker.fuel,
+            events: Vec::new(),
         }
         .with(run_block),
     );
@@ -2,6 +2,8 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+#[path = "./byzantine_worker_tests.rs"]
+mod byzantine;
 #[path = "./wasm_worker_tests.rs"]
 mod wasm;
 
@@ -9,6 +11,7 @@ use std::{
     borrow::Cow,
     collections::{BTreeMap, BTreeSet},
     iter,
+    sync::Arc,
     time::Duration,
 };
 
@@ -17,7 +20,7 @@ use linera_base::{
     crypto::{CryptoHash, *},
     data_types::*,
     identifiers::{
-        Account, ChainDescription, ChainId, ChannelName, Destination, GenericApplicationId,
+        Account, Blob, ChainDescription, ChainId, ChannelName, Destination, GenericApplicationId,
         MessageId, Owner,
     },
     ownership::{ChainOwnership, TimeoutConfig},
@@ -26,7 +29,7 @@ use linera_chain::{
     data_types::{
         Block, BlockExecutionOutcome, BlockProposal, Certificate, ChainAndHeight, ChannelFullName,
         Event, HashedCertificateValue, IncomingMessage, LiteVote, Medium, MessageAction, Origin,
-        OutgoingMessage, ProposalPayload, SignatureAggregator,
+        OutgoingMessage, ProposalPayload, SignatureAggregator, Transaction,
     },
     test::{make_child_block, make_first_block, BlockTestExt, VoteTestExt},
     ChainError, ChainExecutionContext,
@@ -40,8 +43,14 @@ use linera_execution::{
     ChannelSubscription, ExecutionError, Message, MessageKind, Query, Response,
     SystemExecutionError, SystemQuery, SystemResponse,
 };
-use linera_storage::{MemoryStorage, Storage, TestClock};
-use linera_views::{memory::TEST_MEMORY_MAX_STREAM_QUERIES, views::ViewError};
+use linera_storage::{
+    archive_inactive_chains, ArchivalPolicy, MemoryColdStore, MemoryStorage, Storage, TestClock,
+    WalEntry,
+};
+use linera_views::{
+    memory::TEST_MEMORY_MAX_STREAM_QUERIES,
+    views::{RootView as _, View as _, ViewError},
+};
 use test_case::test_case;
 use test_log::test;
 
@@ -55,7 +64,7 @@ use crate::{
     data_types::*,
     test_utils::{MemoryStorageBuilder, StorageBuilder},
     worker::{
-        CrossChainUpdateHelper, Notification,
+        AdminCommand, CrossChainUpdateHelper, Notification,
         Reason::{self, NewBlock, NewIncomingMessage},
         ValidatorWorker, WorkerError, WorkerState,
     },
@@ -95,7 +104,7 @@ where
                 committee.clone(),
                 ChainId::root(0),
                 description,
-                pubk,
+                ChainOwnership::single(pubk),
                 balance,
                 Timestamp::from(0),
             )
@@ -242,6 +251,7 @@ async fn make_transfer_certificate_for_epoch<S>(
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Bouncing,
+                expiration: None,
                 message: incoming_message.event.message.clone(),
             });
             message_count += 1;
@@ -282,6 +292,8 @@ async fn make_transfer_certificate_for_epoch<S>(
             message_counts,
             state_hash,
             oracle_records,
+            fuel_used: 0,
+            events: Vec::new(),
         }
         .with(block),
     );
@@ -299,6 +311,7 @@ fn direct_outgoing_message(
         grant: Amount::ZERO,
         refund_grant_to: None,
         kind,
+        expiration: None,
         message: Message::System(message),
     }
 }
@@ -314,6 +327,7 @@ fn channel_outgoing_message(
         grant: Amount::ZERO,
         refund_grant_to: None,
         kind,
+        expiration: None,
         message: Message::System(message),
     }
 }
@@ -498,7 +512,7 @@ where
         // Timestamp too far in the future
         assert_matches!(
             worker.handle_block_proposal(block_proposal).await,
-            Err(WorkerError::InvalidTimestamp)
+            Err(WorkerError::InvalidTimestamp { .. })
         );
     }
 
@@ -544,6 +558,158 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_sign_with_next_key_once_in_committee<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let storage = storage_builder.build().await?;
+    let key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker(storage, /* is_client */ false);
+    let old_name = ValidatorName(worker.key_pair.as_ref().unwrap().public());
+    let next_key_pair = KeyPair::generate();
+    let new_name = ValidatorName(next_key_pair.public());
+    let mut worker = worker.with_next_key_pair(Some(next_key_pair));
+
+    let mut validators = committee.validators().clone();
+    let state = validators.remove(&old_name).unwrap();
+    validators.insert(new_name, state);
+    let rotated_committee = Committee::new(validators, committee.policy().clone());
+    for (description, committee) in [
+        (ChainDescription::Root(1), committee),
+        (ChainDescription::Root(2), rotated_committee),
+    ] {
+        worker
+            .storage
+            .create_chain(
+                committee,
+                ChainId::root(0),
+                description,
+                ChainOwnership::single(key_pair.public()),
+                Amount::from_tokens(5),
+                Timestamp::from(0),
+            )
+            .await?;
+    }
+
+    // The committee of chain 1 doesn't contain the new key yet.
+    let query = ChainInfoQuery::new(ChainId::root(1));
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    response.check(old_name)?;
+    assert!(response.check(new_name).is_err());
+
+    // The committee of chain 2 does, so its responses and votes are signed with the new key.
+    let query = ChainInfoQuery::new(ChainId::root(2));
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    response.check(new_name)?;
+    let block_proposal = make_first_block(ChainId::root(2))
+        .with_simple_transfer(ChainId::root(1), Amount::ONE)
+        .into_fast_proposal(&key_pair);
+    let (response, _actions) = worker.handle_block_proposal(block_proposal).await?;
+    response.check(new_name)?;
+    assert_eq!(response.info.manager.pending.unwrap().validator, new_name);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_with_clock_skew_tolerance<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    const TOLERANCE_MICROS: u64 = 200_000;
+    let storage = storage_builder.build().await?;
+    let clock = storage_builder.clock();
+    let key_pair = KeyPair::generate();
+    let balances = vec![(
+        ChainDescription::Root(1),
+        key_pair.public(),
+        Amount::from_tokens(5),
+    )];
+    let (_, worker) = init_worker_with_chains(storage, balances).await;
+    let mut worker = worker.with_clock_skew_tolerance(Duration::from_micros(TOLERANCE_MICROS));
+
+    {
+        let block_proposal = make_first_block(ChainId::root(1))
+            .with_timestamp(Timestamp::from(
+                TEST_GRACE_PERIOD_MICROS + TOLERANCE_MICROS + 1,
+            ))
+            .into_fast_proposal(&key_pair);
+        // Timestamp too far in the future, even with the tolerance
+        assert_matches!(
+            worker.handle_block_proposal(block_proposal).await,
+            Err(WorkerError::InvalidTimestamp { .. })
+        );
+    }
+
+    // The validator votes as soon as its clock is within the tolerance of the timestamp.
+    let block_proposal = make_first_block(ChainId::root(1))
+        .with_timestamp(Timestamp::from(TEST_GRACE_PERIOD_MICROS + TOLERANCE_MICROS))
+        .into_fast_proposal(&key_pair);
+    let future = worker.handle_block_proposal(block_proposal);
+    clock.set(Timestamp::from(TEST_GRACE_PERIOD_MICROS));
+    let (response, _) = future.await?;
+    assert!(response.info.manager.pending.is_some());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_cancel_block_proposal<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let storage = storage_builder.build().await?;
+    let clock = storage_builder.clock();
+    let key_pair = KeyPair::generate();
+    let balances = vec![(
+        ChainDescription::Root(1),
+        key_pair.public(),
+        Amount::from_tokens(5),
+    )];
+    let (_, mut worker) = init_worker_with_chains(storage, balances).await;
+    let block_time = Timestamp::from(TEST_GRACE_PERIOD_MICROS);
+    let block_proposal = make_first_block(ChainId::root(1))
+        .with_timestamp(block_time)
+        .into_fast_proposal(&key_pair);
+
+    // The proposal holds the chain while it waits for its timestamp.
+    let mut stuck_worker = worker.clone();
+    let mut stuck = Box::pin(stuck_worker.handle_block_proposal(block_proposal.clone()));
+    assert!(futures::poll!(&mut stuck).is_pending());
+    let proposals = worker.proposals().list();
+    assert_eq!(proposals.len(), 1);
+    assert_eq!(proposals[0].chain_id, ChainId::root(1));
+    assert_eq!(proposals[0].height, BlockHeight::ZERO);
+    assert!(proposals[0].executing);
+
+    assert!(worker.proposals().cancel(proposals[0].id).is_some());
+    assert_matches!(stuck.await, Err(WorkerError::ProposalCancelled));
+    assert!(worker.proposals().list().is_empty());
+
+    // The chain was released, so the proposal can be handled again.
+    let future = worker.handle_block_proposal(block_proposal);
+    clock.set(block_time);
+    let (response, _) = future.await?;
+    assert!(response.info.manager.pending.is_some());
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -577,7 +743,7 @@ where
     let unknown_key = KeyPair::generate();
 
     let unknown_sender_block_proposal =
-        BlockProposal::new(block_proposal.content, &unknown_key, vec![], None);
+        BlockProposal::new(block_proposal.content, &unknown_key, vec![], vec![], None);
     assert_matches!(
         worker
             .handle_block_proposal(unknown_sender_block_proposal)
@@ -729,6 +895,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default(); 2],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(
                 make_first_block(ChainId::root(1))
@@ -756,6 +924,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default()],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(
                 make_child_block(&certificate0.value)
@@ -853,6 +1023,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: system_credit_message(Amount::ONE),
                 },
@@ -868,6 +1039,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: system_credit_message(Amount::from_tokens(2)),
                 },
@@ -883,6 +1055,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: system_credit_message(Amount::from_tokens(2)), // wrong amount
                 },
@@ -909,6 +1082,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: system_credit_message(Amount::from_tokens(2)),
                 },
@@ -935,6 +1109,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: system_credit_message(Amount::from_tokens(3)),
                 },
@@ -950,6 +1125,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: system_credit_message(Amount::ONE),
                 },
@@ -965,6 +1141,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: system_credit_message(Amount::from_tokens(2)),
                 },
@@ -991,6 +1168,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: system_credit_message(Amount::ONE),
                 },
@@ -1014,50 +1192,738 @@ where
                     .into_hash()
                     .await,
                     oracle_records: vec![OracleRecord::default(); 2],
+                    fuel_used: 0,
+                    events: Vec::new(),
                 }
                 .with(block_proposal.content.block),
             ),
-        );
-        worker
+        );
+        worker
+            .handle_certificate(certificate.clone(), vec![], None)
+            .await?;
+
+        // Then receive the next two messages.
+        let block_proposal = make_child_block(&certificate.value)
+            .with_simple_transfer(ChainId::root(3), Amount::from_tokens(3))
+            .with_incoming_message(IncomingMessage {
+                origin: Origin::chain(ChainId::root(1)),
+                event: Event {
+                    certificate_hash: certificate0.value.hash(),
+                    height: BlockHeight::from(0),
+                    index: 1,
+                    authenticated_signer: None,
+                    grant: Amount::ZERO,
+                    refund_grant_to: None,
+                    kind: MessageKind::Tracked,
+                    expiration: None,
+                    timestamp: Timestamp::from(0),
+                    message: system_credit_message(Amount::from_tokens(2)),
+                },
+                action: MessageAction::Accept,
+            })
+            .with_incoming_message(IncomingMessage {
+                origin: Origin::chain(ChainId::root(1)),
+                event: Event {
+                    certificate_hash: certificate1.value.hash(),
+                    height: BlockHeight::from(1),
+                    index: 0,
+                    authenticated_signer: None,
+                    grant: Amount::ZERO,
+                    refund_grant_to: None,
+                    kind: MessageKind::Tracked,
+                    expiration: None,
+                    timestamp: Timestamp::from(0),
+                    message: system_credit_message(Amount::from_tokens(3)),
+                },
+                action: MessageAction::Accept,
+            })
+            .into_fast_proposal(&recipient_key_pair);
+        worker.handle_block_proposal(block_proposal.clone()).await?;
+    }
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_exceed_balance<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (_, mut worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let block_proposal = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(1000))
+        .into_fast_proposal(&sender_key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(block_proposal).await,
+        Err(
+            WorkerError::ChainError(error)
+        ) if matches!(
+            *error,
+            ChainError::ExecutionError(
+                ExecutionError::SystemError(SystemExecutionError::InsufficientFunding { .. }),
+                ChainExecutionContext::Operation(_)
+            )
+        )
+    );
+    assert!(worker
+        .storage
+        .load_active_chain(ChainId::root(1))
+        .await?
+        .manager
+        .get()
+        .pending()
+        .is_none());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (_, mut worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![(
+            ChainDescription::Root(1),
+            sender_key_pair.public(),
+            Amount::from_tokens(5),
+        )],
+    )
+    .await;
+    let block_proposal = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(5))
+        .into_fast_proposal(&sender_key_pair);
+
+    let (chain_info_response, _actions) = worker.handle_block_proposal(block_proposal).await?;
+    chain_info_response.check(ValidatorName(worker.key_pair.unwrap().public()))?;
+    let pending_value = worker
+        .storage
+        .load_active_chain(ChainId::root(1))
+        .await?
+        .manager
+        .get()
+        .pending()
+        .unwrap()
+        .lite();
+    assert_eq!(
+        chain_info_response.info.manager.pending.unwrap(),
+        pending_value
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_publishing_blob<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (committee, mut worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![(
+            ChainDescription::Root(1),
+            sender_key_pair.public(),
+            Amount::from_tokens(5),
+        )],
+    )
+    .await;
+    let blob = Blob::new(b"media".to_vec());
+    let blob_id = blob.id();
+    let mut block_proposal = make_first_block(ChainId::root(1))
+        .with_operation(SystemOperation::PublishBlob { blob_id })
+        .into_fast_proposal(&sender_key_pair);
+
+    // The content of the blob must be sent with the proposal.
+    assert_matches!(
+        worker.handle_block_proposal(block_proposal.clone()).await,
+        Err(WorkerError::BlobsNotFound(blob_ids)) if blob_ids == vec![blob_id]
+    );
+    // Blobs that the block doesn't need are rejected.
+    let unneeded_blob = Blob::new(b"unneeded".to_vec());
+    block_proposal.blobs = vec![blob.clone(), unneeded_blob.clone()];
+    assert_matches!(
+        worker.handle_block_proposal(block_proposal.clone()).await,
+        Err(WorkerError::UnneededBlob { blob_id }) if blob_id == unneeded_blob.id()
+    );
+    block_proposal.blobs = vec![blob.clone()];
+    worker.handle_block_proposal(block_proposal).await?;
+    assert!(worker.storage.contains_blob(blob_id).await?);
+
+    // Once the block is confirmed, the blob is kept, unlike the unneeded one.
+    let value = worker
+        .storage
+        .load_active_chain(ChainId::root(1))
+        .await?
+        .manager
+        .get()
+        .pending()
+        .unwrap()
+        .value
+        .clone();
+    let certificate = make_certificate(&committee, &worker, value);
+    worker.fully_handle_certificate(certificate, vec![]).await?;
+    assert_eq!(
+        worker.storage.collect_garbage_blobs(Duration::ZERO).await?,
+        1
+    );
+    assert!(worker.storage.contains_blob(blob_id).await?);
+    assert!(!worker.storage.contains_blob(unneeded_blob.id()).await?);
+
+    // The nodes missing the published blob can download it.
+    assert_eq!(worker.download_blob(blob_id).await?, blob);
+    assert_matches!(
+        worker.download_blob(unneeded_blob.id()).await,
+        Err(WorkerError::BlobsNotFound(blob_ids)) if blob_ids == vec![unneeded_blob.id()]
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_replay<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (_, mut worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let block_proposal = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(5))
+        .into_fast_proposal(&sender_key_pair);
+
+    let (response, _actions) = worker.handle_block_proposal(block_proposal.clone()).await?;
+    response.check(ValidatorName(worker.key_pair.as_ref().unwrap().public()))?;
+    let (replay_response, _actions) = worker.handle_block_proposal(block_proposal).await?;
+    // Workaround lack of equality.
+    assert_eq!(
+        CryptoHash::new(&*response.info),
+        CryptoHash::new(&*replay_response.info)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_with_chain_state_cache<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (_, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![(
+            ChainDescription::Root(1),
+            sender_key_pair.public(),
+            Amount::from_tokens(5),
+        )],
+    )
+    .await;
+    let mut worker = worker.with_chain_state_cache_size(10);
+    let block = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(5));
+
+    // Executing the block without saving it must not leave changes in the cached chain state.
+    worker.stage_block_execution(block.clone()).await?;
+    let query = ChainInfoQuery::new(ChainId::root(1));
+    let (response, _actions) = worker.handle_chain_info_query(query.clone()).await?;
+    assert_eq!(response.info.chain_balance, Amount::from_tokens(5));
+    assert!(response.info.manager.pending.is_none());
+
+    // A block proposal is saved, and visible to the next request.
+    let block_proposal = block.into_fast_proposal(&sender_key_pair);
+    let (response, _actions) = worker.handle_block_proposal(block_proposal).await?;
+    let (cached_response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(cached_response.info.chain_balance, Amount::from_tokens(5));
+    assert_eq!(
+        cached_response.info.manager.pending,
+        response.info.manager.pending
+    );
+    assert!(cached_response.info.manager.pending.is_some());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_archival_skips_cached_chains<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut storage = storage_builder.build().await?;
+    storage.set_cold_store(Arc::new(MemoryColdStore::default()));
+    let clock = storage_builder.clock();
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chains(
+        storage,
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let mut worker = worker.with_chain_state_cache_size(10);
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    worker.handle_certificate(certificate, vec![], None).await?;
+
+    // Chain 1 is inactive, but its state is held by the cache, so it is skipped instead of
+    // blocking the archival.
+    clock.set(Timestamp::from(u64::MAX / 2));
+    let policy = ArchivalPolicy {
+        inactivity: Duration::from_secs(1),
+        keep_latest: 0,
+    };
+    let archival = archive_inactive_chains(&worker.storage, &policy);
+    let count = tokio::time::timeout(Duration::from_secs(10), archival).await??;
+    assert_eq!(count, 0);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_replay_write_ahead_log<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let mut worker = worker.with_write_ahead_log(true);
+    let certificate0 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+
+    // The message to the recipient is recorded until it is delivered.
+    let (_, actions) = worker
+        .handle_certificate(certificate0.clone(), vec![], None)
+        .await?;
+    assert_eq!(actions.cross_chain_requests.len(), 1);
+    let pending_deliveries = WalEntry {
+        certificate: None,
+        pending_deliveries: true,
+    };
+    assert_eq!(
+        worker.storage.read_wal_entries().await?,
+        BTreeMap::from([(ChainId::root(1), pending_deliveries)])
+    );
+
+    // If the shard restarts before the delivery, the request is sent again.
+    let actions = worker.replay_write_ahead_log(|_| true).await?;
+    assert_eq!(actions.cross_chain_requests.len(), 1);
+    let actions = worker.replay_write_ahead_log(|_| false).await?;
+    assert!(actions.cross_chain_requests.is_empty());
+
+    // A certificate whose execution was interrupted is executed when the log is replayed.
+    let certificate1 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(3),
+        &worker,
+        Some(&certificate0),
+    )
+    .await;
+    worker.storage.write_certificate(&certificate1).await?;
+    let interrupted = WalEntry {
+        certificate: Some(certificate1.hash()),
+        pending_deliveries: true,
+    };
+    worker
+        .storage
+        .write_wal_entry(ChainId::root(1), &interrupted)
+        .await?;
+    let actions = worker.replay_write_ahead_log(|_| true).await?;
+    let chain = worker.storage.load_active_chain(ChainId::root(1)).await?;
+    assert_eq!(
+        chain.tip_state.get().next_block_height,
+        BlockHeight::from(2)
+    );
+    assert_eq!(Some(certificate1.hash()), chain.tip_state.get().block_hash);
+    drop(chain);
+
+    // Once the messages are delivered, the entry is removed.
+    for request in actions.cross_chain_requests {
+        let actions = worker.handle_cross_chain_request(request).await?;
+        for request in actions.cross_chain_requests {
+            worker.handle_cross_chain_request(request).await?;
+        }
+    }
+    assert!(worker.storage.read_wal_entries().await?.is_empty());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_chain_checkpoints<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let mut worker = worker.with_checkpoint_interval(2);
+    let certificate0 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    worker
+        .handle_certificate(certificate0.clone(), vec![], None)
+        .await?;
+    assert!(worker
+        .storage
+        .chain_checkpoint_heights(ChainId::root(1))
+        .await?
+        .is_empty());
+
+    let certificate1 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(3),
+        &worker,
+        Some(&certificate0),
+    )
+    .await;
+    worker
+        .handle_certificate(certificate1.clone(), vec![], None)
+        .await?;
+    assert_eq!(
+        worker
+            .storage
+            .chain_checkpoint_heights(ChainId::root(1))
+            .await?,
+        vec![BlockHeight::from(2)]
+    );
+
+    // There is no checkpoint before the second block.
+    assert!(worker
+        .storage
+        .load_chain_checkpoint(ChainId::root(1), BlockHeight::from(1))
+        .await?
+        .is_none());
+    let checkpoint = worker
+        .storage
+        .load_chain_checkpoint(ChainId::root(1), BlockHeight::from(5))
+        .await?
+        .unwrap();
+    assert_eq!(
+        checkpoint.tip_state.get().next_block_height,
+        BlockHeight::from(2)
+    );
+    assert_eq!(
+        checkpoint.tip_state.get().block_hash,
+        Some(certificate1.hash())
+    );
+    assert_eq!(
+        *checkpoint.execution_state.system.balance.get(),
+        Amount::from_tokens(3)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_chain_checkpoints_are_pruned_and_restored<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let mut worker = worker.with_checkpoint_interval(1).with_max_checkpoints(2);
+    let mut previous_certificate = None;
+    for balance in [4, 3, 2] {
+        let certificate = make_simple_transfer_certificate(
+            ChainDescription::Root(1),
+            &sender_key_pair,
+            ChainId::root(2),
+            Amount::ONE,
+            Vec::new(),
+            &committee,
+            Amount::from_tokens(balance),
+            &worker,
+            previous_certificate.as_ref(),
+        )
+        .await;
+        worker
+            .handle_certificate(certificate.clone(), vec![], None)
+            .await?;
+        previous_certificate = Some(certificate);
+    }
+
+    // Only the latest two checkpoints are kept.
+    assert_eq!(
+        worker
+            .storage
+            .chain_checkpoint_heights(ChainId::root(1))
+            .await?,
+        vec![BlockHeight::from(2), BlockHeight::from(3)]
+    );
+
+    // A checkpoint can be read while the chain is locked.
+    let mut chain = worker.storage.load_chain(ChainId::root(1)).await?;
+    let checkpoint = worker
+        .storage
+        .load_chain_checkpoint(ChainId::root(1), BlockHeight::from(2))
+        .await?
+        .unwrap();
+    assert_eq!(
+        *checkpoint.execution_state.system.balance.get(),
+        Amount::from_tokens(3)
+    );
+
+    // If the state of the chain is lost, it can't be loaded until it is explicitly restored
+    // from the latest checkpoint.
+    chain.clear();
+    chain.save().await?;
+    drop(chain);
+    assert_matches!(
+        worker.storage.load_chain(ChainId::root(1)).await.err(),
+        Some(ViewError::MissingEntries)
+    );
+    assert_eq!(
+        worker
+            .storage
+            .restore_chain_checkpoint(ChainId::root(1))
+            .await?,
+        Some(BlockHeight::from(3))
+    );
+    let chain = worker.storage.load_chain(ChainId::root(1)).await?;
+    assert_eq!(
+        chain.tip_state.get().next_block_height,
+        BlockHeight::from(3)
+    );
+    assert_eq!(
+        chain.tip_state.get().block_hash,
+        Some(previous_certificate.unwrap().hash())
+    );
+    assert_eq!(
+        *chain.execution_state.system.balance.get(),
+        Amount::from_tokens(2)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_read_only_replica<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (committee, mut worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let mut replica =
+        WorkerState::new("Replica".to_string(), None, worker.storage.clone()).with_read_only(true);
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    assert_matches!(
+        replica
             .handle_certificate(certificate.clone(), vec![], None)
-            .await?;
+            .await,
+        Err(WorkerError::ReadOnlyReplica)
+    );
+    let (_, actions) = worker.handle_certificate(certificate, vec![], None).await?;
+    assert!(!actions.cross_chain_requests.is_empty());
 
-        // Then receive the next two messages.
-        let block_proposal = make_child_block(&certificate.value)
-            .with_simple_transfer(ChainId::root(3), Amount::from_tokens(3))
-            .with_incoming_message(IncomingMessage {
-                origin: Origin::chain(ChainId::root(1)),
-                event: Event {
-                    certificate_hash: certificate0.value.hash(),
-                    height: BlockHeight::from(0),
-                    index: 1,
-                    authenticated_signer: None,
-                    grant: Amount::ZERO,
-                    refund_grant_to: None,
-                    kind: MessageKind::Tracked,
-                    timestamp: Timestamp::from(0),
-                    message: system_credit_message(Amount::from_tokens(2)),
-                },
-                action: MessageAction::Accept,
-            })
-            .with_incoming_message(IncomingMessage {
-                origin: Origin::chain(ChainId::root(1)),
-                event: Event {
-                    certificate_hash: certificate1.value.hash(),
-                    height: BlockHeight::from(1),
-                    index: 0,
-                    authenticated_signer: None,
-                    grant: Amount::ZERO,
-                    refund_grant_to: None,
-                    kind: MessageKind::Tracked,
-                    timestamp: Timestamp::from(0),
-                    message: system_credit_message(Amount::from_tokens(3)),
-                },
-                action: MessageAction::Accept,
-            })
-            .into_fast_proposal(&recipient_key_pair);
-        worker.handle_block_proposal(block_proposal.clone()).await?;
-    }
+    // The replica sees the block written by the other worker, but leaves the delivery of
+    // its messages to it.
+    let (response, actions) = replica
+        .handle_chain_info_query(ChainInfoQuery::new(ChainId::root(1)))
+        .await?;
+    assert_eq!(response.info.next_block_height, BlockHeight::from(1));
+    assert!(actions.cross_chain_requests.is_empty());
+
+    // Voting on a timeout would change the chain.
+    assert_matches!(
+        replica
+            .handle_chain_info_query(ChainInfoQuery::new(ChainId::root(1)).with_timeout())
+            .await,
+        Err(WorkerError::ReadOnlyReplica)
+    );
     Ok(())
 }
 
@@ -1066,13 +1932,13 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_handle_block_proposal_exceed_balance<B>(mut storage_builder: B) -> anyhow::Result<()>
+async fn test_admin_commands<B>(mut storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
     ViewError: From<<B::Storage as Storage>::ContextError>,
 {
     let sender_key_pair = KeyPair::generate();
-    let (_, mut worker) = init_worker_with_chains(
+    let (committee, worker) = init_worker_with_chains(
         storage_builder.build().await?,
         vec![
             (
@@ -1088,29 +1954,49 @@ where
         ],
     )
     .await;
-    let block_proposal = make_first_block(ChainId::root(1))
-        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(1000))
-        .into_fast_proposal(&sender_key_pair);
+    let mut worker = worker
+        .with_chain_state_cache_size(10)
+        .with_response_cache_size(10);
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    let (_, actions) = worker.handle_certificate(certificate, vec![], None).await?;
+    assert_eq!(actions.cross_chain_requests.len(), 1);
+
+    // Until the recipient confirms the delivery, the operators can have the request sent
+    // again, e.g. if it was dropped.
+    let command = AdminCommand::RedeliverMessages(ChainId::root(1));
+    let actions = worker.handle_admin_command(command).await?;
+    assert_eq!(actions.cross_chain_requests.len(), 1);
+    let actions = worker
+        .handle_admin_command(AdminCommand::RedeliverMessages(ChainId::root(2)))
+        .await?;
+    assert!(actions.cross_chain_requests.is_empty());
+    let replica =
+        WorkerState::new("Replica".to_string(), None, worker.storage.clone()).with_read_only(true);
     assert_matches!(
-        worker.handle_block_proposal(block_proposal).await,
-        Err(
-            WorkerError::ChainError(error)
-        ) if matches!(
-            *error,
-            ChainError::ExecutionError(
-                ExecutionError::SystemError(SystemExecutionError::InsufficientFunding { .. }),
-                ChainExecutionContext::Operation(_)
-            )
-        )
+        replica.handle_admin_command(command).await,
+        Err(WorkerError::ReadOnlyReplica)
     );
-    assert!(worker
-        .storage
-        .load_active_chain(ChainId::root(1))
-        .await?
-        .manager
-        .get()
-        .pending()
-        .is_none());
+
+    // After flushing the caches, the chain is loaded from the storage again.
+    let actions = worker
+        .handle_admin_command(AdminCommand::FlushCaches)
+        .await?;
+    assert!(actions.cross_chain_requests.is_empty());
+    let (response, _) = worker
+        .handle_chain_info_query(ChainInfoQuery::new(ChainId::root(1)))
+        .await?;
+    assert_eq!(response.info.next_block_height, BlockHeight::from(1));
     Ok(())
 }
 
@@ -1119,7 +2005,7 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_handle_block_proposal<B>(mut storage_builder: B) -> anyhow::Result<()>
+async fn test_simulate_block_proposal<B>(mut storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
     ViewError: From<<B::Storage as Storage>::ContextError>,
@@ -1134,25 +2020,24 @@ where
         )],
     )
     .await;
-    let block_proposal = make_first_block(ChainId::root(1))
-        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(5))
-        .into_fast_proposal(&sender_key_pair);
+    let block = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(5));
 
-    let (chain_info_response, _actions) = worker.handle_block_proposal(block_proposal).await?;
-    chain_info_response.check(ValidatorName(worker.key_pair.unwrap().public()))?;
-    let pending_value = worker
-        .storage
-        .load_active_chain(ChainId::root(1))
-        .await?
-        .manager
-        .get()
-        .pending()
-        .unwrap()
-        .lite();
-    assert_eq!(
-        chain_info_response.info.manager.pending.unwrap(),
-        pending_value
-    );
+    let executed_block = worker.simulate_block_proposal(block.clone()).await?;
+    assert_eq!(executed_block.block, block);
+    assert_eq!(executed_block.messages().len(), 1);
+
+    // Nothing was voted on or saved.
+    let query = ChainInfoQuery::new(ChainId::root(1));
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(response.info.next_block_height, BlockHeight::ZERO);
+    assert_eq!(response.info.chain_balance, Amount::from_tokens(5));
+    assert!(response.info.manager.pending.is_none());
+
+    // A block that can't be executed is rejected.
+    let block = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(6));
+    assert!(worker.simulate_block_proposal(block).await.is_err());
     Ok(())
 }
 
@@ -1161,40 +2046,158 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_handle_block_proposal_replay<B>(mut storage_builder: B) -> anyhow::Result<()>
+async fn test_handle_retried_requests_with_response_cache<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
 where
     B: StorageBuilder,
     ViewError: From<<B::Storage as Storage>::ContextError>,
 {
     let sender_key_pair = KeyPair::generate();
-    let (_, mut worker) = init_worker_with_chains(
+    let (_, worker) = init_worker_with_chains(
         storage_builder.build().await?,
-        vec![
-            (
-                ChainDescription::Root(1),
-                sender_key_pair.public(),
-                Amount::from_tokens(5),
-            ),
-            (
-                ChainDescription::Root(2),
-                PublicKey::test_key(2),
-                Amount::ZERO,
-            ),
-        ],
+        vec![(
+            ChainDescription::Root(1),
+            sender_key_pair.public(),
+            Amount::from_tokens(5),
+        )],
     )
     .await;
-    let block_proposal = make_first_block(ChainId::root(1))
-        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(5))
-        .into_fast_proposal(&sender_key_pair);
-
+    let mut worker = worker.with_response_cache_size(10);
+    let block = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(5));
+    let block_proposal = block.clone().into_fast_proposal(&sender_key_pair);
     let (response, _actions) = worker.handle_block_proposal(block_proposal.clone()).await?;
-    response.check(ValidatorName(worker.key_pair.as_ref().unwrap().public()))?;
-    let (replay_response, _actions) = worker.handle_block_proposal(block_proposal).await?;
+
+    // Confirm the block, so that the chain is at the next height.
+    let vote = response.info.manager.pending.clone().unwrap();
+    let value = worker.recent_value(&vote.value.value_hash).await.unwrap();
+    let certificate = vote.with_value(value).unwrap().into_certificate();
+    let (certificate_response, _actions) = worker
+        .handle_certificate(certificate.clone(), vec![], None)
+        .await?;
+    assert_eq!(certificate_response.info.next_block_height, BlockHeight(1));
+
+    // Retrying the proposal returns the current state of the chain, instead of failing
+    // because the block was already confirmed, and retries the cross-chain requests.
+    let (replay_response, actions) = worker.handle_block_proposal(block_proposal).await?;
     // Workaround lack of equality.
     assert_eq!(
-        CryptoHash::new(&*response.info),
+        CryptoHash::new(&*certificate_response.info),
+        CryptoHash::new(&*replay_response.info)
+    );
+    assert!(!actions.cross_chain_requests.is_empty());
+    let (replay_response, actions) = worker.handle_certificate(certificate, vec![], None).await?;
+    assert_eq!(
+        CryptoHash::new(&*certificate_response.info),
         CryptoHash::new(&*replay_response.info)
     );
+    assert!(!actions.cross_chain_requests.is_empty());
+
+    // A proposal with the same block but another signer is not identical.
+    let other_proposal = block.into_fast_proposal(&KeyPair::generate());
+    assert_matches!(
+        worker.handle_block_proposal(other_proposal).await,
+        Err(WorkerError::InvalidOwner)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_sent_certificates_in_range_are_paginated<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (_, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![(
+            ChainDescription::Root(1),
+            sender_key_pair.public(),
+            Amount::from_tokens(5),
+        )],
+    )
+    .await;
+    let mut worker = worker.with_max_sent_certificates_per_query(2);
+    let mut block = make_first_block(ChainId::root(1));
+    for _ in 0..3 {
+        let proposal = block
+            .clone()
+            .with_simple_transfer(ChainId::root(2), Amount::ONE)
+            .into_fast_proposal(&sender_key_pair);
+        let (response, _actions) = worker.handle_block_proposal(proposal).await?;
+        let vote = response.info.manager.pending.clone().unwrap();
+        let value = worker.recent_value(&vote.value.value_hash).await.unwrap();
+        let certificate = vote.with_value(value).unwrap().into_certificate();
+        block = make_child_block(&certificate.value);
+        worker.handle_certificate(certificate, vec![], None).await?;
+    }
+
+    let heights = |info: &ChainInfo| {
+        info.requested_sent_certificates
+            .iter()
+            .map(|certificate| certificate.value().height())
+            .collect::<Vec<_>>()
+    };
+
+    // Without a limit, the response is truncated and indicates where to continue.
+    let query =
+        ChainInfoQuery::new(ChainId::root(1)).with_sent_certificates_in_range(BlockHeightRange {
+            start: BlockHeight(0),
+            limit: None,
+        });
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(heights(&response.info), [BlockHeight(0), BlockHeight(1)]);
+    let next_range = response
+        .info
+        .requested_sent_certificates_next_range
+        .clone()
+        .unwrap();
+    assert_eq!(next_range.start, BlockHeight(2));
+    assert_eq!(next_range.limit, None);
+
+    let query = ChainInfoQuery::new(ChainId::root(1)).with_sent_certificates_in_range(next_range);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(heights(&response.info), [BlockHeight(2)]);
+    assert!(response
+        .info
+        .requested_sent_certificates_next_range
+        .is_none());
+
+    // The continuation range keeps the rest of the requested limit.
+    let query =
+        ChainInfoQuery::new(ChainId::root(1)).with_sent_certificates_in_range(BlockHeightRange {
+            start: BlockHeight(0),
+            limit: Some(3),
+        });
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(heights(&response.info), [BlockHeight(0), BlockHeight(1)]);
+    let next_range = response
+        .info
+        .requested_sent_certificates_next_range
+        .unwrap();
+    assert_eq!(next_range.start, BlockHeight(2));
+    assert_eq!(next_range.limit, Some(1));
+
+    // A range that fits in one response is not truncated.
+    let query =
+        ChainInfoQuery::new(ChainId::root(1)).with_sent_certificates_in_range(BlockHeightRange {
+            start: BlockHeight(1),
+            limit: Some(2),
+        });
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(heights(&response.info), [BlockHeight(1), BlockHeight(2)]);
+    assert!(response
+        .info
+        .requested_sent_certificates_next_range
+        .is_none());
     Ok(())
 }
 
@@ -1291,6 +2294,7 @@ where
             grant: Amount::ZERO,
             refund_grant_to: None,
             kind: MessageKind::Protected,
+            expiration: None,
             timestamp: Timestamp::from(0),
             message: Message::System(SystemMessage::OpenChain(OpenChainConfig {
                 ownership,
@@ -1309,6 +2313,8 @@ where
             message_counts: vec![0],
             state_hash: state.into_hash().await,
             oracle_records: vec![OracleRecord::default()],
+            fuel_used: 0,
+            events: Vec::new(),
         }
         .with(make_first_block(chain_id).with_incoming_message(open_chain_message)),
     );
@@ -1454,6 +2460,7 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Tracked,
+                expiration: None,
                 timestamp: Timestamp::from(0),
                 message: system_credit_message(Amount::from_tokens(995)),
             },
@@ -1494,6 +2501,7 @@ where
             grant: Amount::ZERO,
             refund_grant_to: None,
             kind: MessageKind::Tracked,
+            expiration: None,
             timestamp,
             message: Message::System(SystemMessage::Credit { amount, .. }),
         } if certificate_hash == CryptoHash::test_hash("certificate")
@@ -1625,6 +2633,7 @@ where
             grant: Amount::ZERO,
             refund_grant_to: None,
             kind: MessageKind::Tracked,
+            expiration: None,
             timestamp,
             message: Message::System(SystemMessage::Credit { amount, .. })
         } if certificate_hash == certificate.hash()
@@ -1699,6 +2708,7 @@ where
             grant: Amount::ZERO,
             refund_grant_to: None,
             kind: MessageKind::Tracked,
+            expiration: None,
             timestamp,
             message: Message::System(SystemMessage::Credit { amount, .. })
         } if certificate_hash == certificate.hash()
@@ -1882,6 +2892,8 @@ where
         })
     );
 
+    let sender_certificate_hash = certificate.hash();
+
     // Try to use the money. This requires selecting the incoming message in a next block.
     let certificate = make_simple_transfer_certificate(
         ChainDescription::Root(2),
@@ -1898,6 +2910,7 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Tracked,
+                expiration: None,
                 timestamp: Timestamp::from(0),
                 message: system_credit_message(Amount::from_tokens(5)),
             },
@@ -1954,6 +2967,49 @@ where
             height: BlockHeight::ZERO
         }
     );
+
+    let query = ChainInfoQuery::new(ChainId::root(2)).with_received_certificates_after(0);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    let hashes = response
+        .info
+        .requested_received_certificates
+        .iter()
+        .map(Certificate::hash)
+        .collect::<Vec<_>>();
+    assert_eq!(hashes, [sender_certificate_hash]);
+    assert_eq!(response.info.received_certificates_cursor, Some(1));
+    // Nothing new was received after the returned cursor.
+    let query = ChainInfoQuery::new(ChainId::root(2)).with_received_certificates_after(1);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert!(response.info.requested_received_certificates.is_empty());
+    assert_eq!(response.info.received_certificates_cursor, Some(1));
+
+    // The event log has the outcomes of the incoming message and of the transfer.
+    let query = ChainInfoQuery::new(ChainId::root(2)).with_events_from(0);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(response.info.events_cursor, Some(2));
+    let events = &response.info.requested_events;
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| event.certificate_hash == certificate.hash()));
+    assert_matches!(
+        &events[0].transaction,
+        Transaction::ReceiveMessage(message)
+            if message.event.certificate_hash == sender_certificate_hash
+    );
+    assert!(events[0].messages.is_empty());
+    assert_matches!(events[1].transaction, Transaction::ExecuteOperation(_));
+    assert_eq!(events[1].transaction_index, 1);
+    assert_eq!(events[1].messages.len(), 1);
+    assert_eq!(
+        events[1].messages[0].destination,
+        Destination::Recipient(ChainId::root(3))
+    );
+    let query = ChainInfoQuery::new(ChainId::root(2)).with_events_from(2);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert!(response.info.requested_events.is_empty());
+    assert_eq!(response.info.events_cursor, Some(2));
     Ok(())
 }
 
@@ -2084,6 +3140,7 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Tracked,
+                expiration: None,
                 timestamp: Timestamp::from(0),
                 message: Message::System(SystemMessage::Credit {
                     source: None,
@@ -2110,6 +3167,21 @@ where
         chain.validate_incoming_messages().await?;
     }
 
+    // The sender's balance can be proven against the state hash of the chain.
+    let query = ChainInfoQuery::new(ChainId::root(1)).with_owner_balance_proof(sender);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    let state_hash = response.info.state_hash.unwrap();
+    let proof = response.info.requested_owner_balance_proof.unwrap();
+    assert_eq!(
+        bcs::from_bytes::<Amount>(&proof.value)?,
+        Amount::from_tokens(5)
+    );
+    assert!(proof.verify_crypto_hash("ExecutionStateView", &state_hash));
+    let other_owner = Owner::from(recipient_key_pair.public());
+    let query = ChainInfoQuery::new(ChainId::root(1)).with_owner_balance_proof(other_owner);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert!(response.info.requested_owner_balance_proof.is_none());
+
     // Then, make two transfers to the recipient.
     let certificate1 = make_transfer_certificate(
         ChainDescription::Root(1),
@@ -2167,6 +3239,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: Message::System(SystemMessage::Credit {
                         source: Some(sender),
@@ -2186,6 +3259,7 @@ where
                     grant: Amount::ZERO,
                     refund_grant_to: None,
                     kind: MessageKind::Tracked,
+                    expiration: None,
                     timestamp: Timestamp::from(0),
                     message: Message::System(SystemMessage::Credit {
                         source: Some(sender),
@@ -2230,6 +3304,7 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Bouncing,
+                expiration: None,
                 timestamp: Timestamp::from(0),
                 message: Message::System(SystemMessage::Credit {
                     source: Some(sender),
@@ -2340,6 +3415,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default()],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(make_first_block(admin_id).with_operation(
                 SystemOperation::OpenChain(OpenChainConfig {
@@ -2403,6 +3480,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default(); 2],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(
                 make_child_block(&certificate0.value)
@@ -2439,6 +3518,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default()],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(
                 make_child_block(&certificate1.value)
@@ -2453,6 +3534,7 @@ where
                             grant: Amount::ZERO,
                             refund_grant_to: None,
                             kind: MessageKind::Protected,
+                            expiration: None,
                             timestamp: Timestamp::from(0),
                             message: Message::System(SystemMessage::Subscribe {
                                 id: user_id,
@@ -2566,6 +3648,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default(); 4],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(
                 make_first_block(user_id)
@@ -2579,6 +3663,7 @@ where
                             grant: Amount::ZERO,
                             refund_grant_to: None,
                             kind: MessageKind::Protected,
+                            expiration: None,
                             timestamp: Timestamp::from(0),
                             message: Message::System(SystemMessage::OpenChain(OpenChainConfig {
                                 ownership: ChainOwnership::single(key_pair.public()),
@@ -2601,6 +3686,7 @@ where
                             grant: Amount::ZERO,
                             refund_grant_to: None,
                             kind: MessageKind::Protected,
+                            expiration: None,
                             timestamp: Timestamp::from(0),
                             message: Message::System(SystemMessage::SetCommittees {
                                 epoch: Epoch::from(1),
@@ -2619,6 +3705,7 @@ where
                             grant: Amount::ZERO,
                             refund_grant_to: None,
                             kind: MessageKind::Tracked,
+                            expiration: None,
                             timestamp: Timestamp::from(0),
                             message: system_credit_message(Amount::from_tokens(2)),
                         },
@@ -2634,6 +3721,7 @@ where
                             grant: Amount::ZERO,
                             refund_grant_to: None,
                             kind: MessageKind::Protected,
+                            expiration: None,
                             timestamp: Timestamp::from(0),
                             message: Message::System(SystemMessage::Notify { id: user_id }),
                         },
@@ -2735,6 +3823,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default()],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(make_first_block(user_id).with_simple_transfer(admin_id, Amount::ONE)),
         ),
@@ -2762,6 +3852,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default()],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(
                 make_first_block(admin_id).with_operation(SystemOperation::Admin(
@@ -2862,6 +3954,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default()],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(make_first_block(user_id).with_simple_transfer(admin_id, Amount::ONE)),
         ),
@@ -2896,6 +3990,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default(); 2],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(
                 make_first_block(admin_id)
@@ -2956,6 +4052,8 @@ where
                 .into_hash()
                 .await,
                 oracle_records: vec![OracleRecord::default()],
+                fuel_used: 0,
+                events: Vec::new(),
             }
             .with(
                 make_child_block(&certificate1.value)
@@ -2970,6 +4068,7 @@ where
                             grant: Amount::ZERO,
                             refund_grant_to: None,
                             kind: MessageKind::Tracked,
+                            expiration: None,
                             timestamp: Timestamp::from(0),
                             message: system_credit_message(Amount::ONE),
                         },
@@ -3315,6 +4414,22 @@ where
     assert_eq!(response.info.manager.leader, Some(Owner::from(pub_key1)));
     assert_eq!(response.info.manager.current_round, Round::SingleLeader(5));
 
+    // The manager status explains who can propose now, and who leads the next round.
+    let query_status = ChainInfoQuery::new(chain_id).with_manager_status();
+    let (response, _) = worker.handle_chain_info_query(query_status).await?;
+    let status = response.info.manager.requested_status.unwrap();
+    assert_eq!(
+        status.current_proposers,
+        BTreeSet::from([Owner::from(pub_key1)])
+    );
+    assert_eq!(
+        status.proposed,
+        Some((Round::SingleLeader(1), Owner::from(pub_key0)))
+    );
+    assert_eq!(status.locked_round, Some(Round::SingleLeader(1)));
+    assert_eq!(status.next_round, Some(Round::SingleLeader(6)));
+    assert_eq!(status.next_round_leader, Some(Owner::from(pub_key0)));
+
     // Create block2, also at height 1, but different from block 1.
     let amount = Amount::from_tokens(1);
     let block2 = make_child_block(&value0).with_simple_transfer(ChainId::root(1), amount);
@@ -3671,7 +4786,7 @@ where
     // Now we are in fallback mode, and the validator is the leader.
     let (response, _) = worker.handle_chain_info_query(query.clone()).await?;
     let manager = response.info.manager;
-    let validator_key = worker.key_pair().unwrap().public();
+    let validator_key = worker.key_pair.as_ref().unwrap().public();
     assert_eq!(manager.current_round, Round::Validator(0));
     assert_eq!(manager.leader, Some(Owner::from(validator_key)));
     Ok(())
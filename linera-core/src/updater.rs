@@ -329,7 +329,9 @@ where
         Ok(())
     }
 
-    async fn send_chain_information_for_senders(
+    /// Sends the certificates of the sender chains that the inboxes of the given chain are
+    /// waiting for, so that the validator can accept the blocks receiving these messages.
+    pub async fn send_chain_information_for_senders(
         &mut self,
         chain_id: ChainId,
     ) -> Result<(), NodeError> {
@@ -393,11 +395,9 @@ where
                 info.manager.timeout_vote
             }
         };
+        // The signature is checked by the caller, together with the other votes.
         match vote {
-            Some(vote) if vote.validator == self.name => {
-                vote.check()?;
-                Ok(vote)
-            }
+            Some(vote) if vote.validator == self.name => Ok(vote),
             Some(_) | None => Err(NodeError::MissingVoteInValidatorResponse),
         }
     }
@@ -0,0 +1,197 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scores of the validators a client downloads certificates from.
+//!
+//! Instead of always asking the same validators first, the client picks them at random, in
+//! proportion to their weight: their voting power in the committee, reduced by their average
+//! latency and by their recent failures. Validators that fail too many times in a row, or that
+//! are much slower than the others, are demoted for the rest of the session, i.e. they are only
+//! tried after all the others.
+//!
+//! The latencies and failures of the validators, but not their demotions, can be kept across
+//! sessions as their [`ValidatorReputation`]s.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use linera_base::time::Duration;
+use linera_execution::committee::{Committee, ValidatorName};
+use rand::Rng as _;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+#[cfg(test)]
+#[path = "unit_tests/validator_scores.rs"]
+mod unit_tests;
+
+/// The number of consecutive failures after which a validator is demoted.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+/// How many times slower than the median of the other validators a validator must be on
+/// average to be demoted.
+const SLOW_LATENCY_FACTOR: u32 = 4;
+/// The number of validators whose latency must be known before any of them is considered slow.
+const MIN_LATENCY_SAMPLES: usize = 3;
+/// The weight of a new measurement in the average latency of a validator.
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// The scores of the validators, shared by all the chain clients of a session.
+#[derive(Default)]
+pub struct ValidatorScores {
+    scores: Mutex<HashMap<ValidatorName, ValidatorScore>>,
+}
+
+/// How a validator answered the requests of a client so far.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorReputation {
+    /// The exponential moving average of the latency of the successful requests.
+    pub latency: Option<Duration>,
+    /// The number of successful requests.
+    pub successes: u64,
+    /// The number of failed requests, including those with an invalid answer.
+    pub failures: u64,
+    /// The number of failed requests since the last successful one.
+    pub consecutive_failures: u32,
+}
+
+/// What is known about a validator.
+struct ValidatorScore {
+    /// The voting power of the validator in the latest committee we know of.
+    stake: u64,
+    reputation: ValidatorReputation,
+    /// Whether the validator is only tried after all the others.
+    demoted: bool,
+}
+
+impl Default for ValidatorScore {
+    fn default() -> Self {
+        Self {
+            stake: 1,
+            reputation: ValidatorReputation::default(),
+            demoted: false,
+        }
+    }
+}
+
+impl ValidatorScore {
+    /// Returns the weight of the validator in the random selection.
+    fn weight(&self) -> f64 {
+        let reputation = &self.reputation;
+        let latency = reputation
+            .latency
+            .map_or(0.0, |latency| latency.as_secs_f64());
+        let failures = f64::from(reputation.consecutive_failures) + 1.0;
+        (self.stake.max(1) as f64) / ((1.0 + latency) * failures)
+    }
+}
+
+impl ValidatorScores {
+    /// Creates the scores of a new session, from the reputations of the validators in the
+    /// previous ones.
+    pub fn from_reputations(
+        reputations: impl IntoIterator<Item = (ValidatorName, ValidatorReputation)>,
+    ) -> Self {
+        let scores = reputations
+            .into_iter()
+            .map(|(name, reputation)| {
+                let score = ValidatorScore {
+                    reputation,
+                    ..ValidatorScore::default()
+                };
+                (name, score)
+            })
+            .collect();
+        Self {
+            scores: Mutex::new(scores),
+        }
+    }
+
+    /// Returns the reputations of the validators, to be restored in the next sessions.
+    pub fn reputations(&self) -> BTreeMap<ValidatorName, ValidatorReputation> {
+        let scores = self.scores.lock().unwrap();
+        scores
+            .iter()
+            .map(|(name, score)| (*name, score.reputation.clone()))
+            .collect()
+    }
+
+    /// Uses the voting power of the validators of the `committee` as their stake.
+    pub(crate) fn update_stakes(&self, committee: &Committee) {
+        let mut scores = self.scores.lock().unwrap();
+        for name in committee.validators().keys() {
+            scores.entry(*name).or_default().stake = committee.weight(name);
+        }
+    }
+
+    /// Records that a request to the validator succeeded after `latency`.
+    pub(crate) fn record_success(&self, name: ValidatorName, latency: Duration) {
+        let mut scores = self.scores.lock().unwrap();
+        let score = scores.entry(name).or_default();
+        let reputation = &mut score.reputation;
+        reputation.successes += 1;
+        reputation.consecutive_failures = 0;
+        let average = reputation.latency.map_or(latency, |average| {
+            average.mul_f64(1.0 - LATENCY_SMOOTHING) + latency.mul_f64(LATENCY_SMOOTHING)
+        });
+        reputation.latency = Some(average);
+        if score.demoted {
+            return;
+        }
+        let mut latencies = scores
+            .iter()
+            .filter(|(other, score)| **other != name && !score.demoted)
+            .filter_map(|(_, score)| score.reputation.latency)
+            .collect::<Vec<_>>();
+        if latencies.len() < MIN_LATENCY_SAMPLES {
+            return;
+        }
+        latencies.sort_unstable();
+        let median = latencies[latencies.len() / 2];
+        if average > median * SLOW_LATENCY_FACTOR {
+            warn!("Demoting validator {name}: its average latency {average:?} is too high");
+            scores.entry(name).or_default().demoted = true;
+        }
+    }
+
+    /// Records that a request to the validator failed, or that its answer was invalid.
+    pub(crate) fn record_failure(&self, name: ValidatorName) {
+        let mut scores = self.scores.lock().unwrap();
+        let score = scores.entry(name).or_default();
+        score.reputation.failures += 1;
+        score.reputation.consecutive_failures += 1;
+        let consecutive_failures = score.reputation.consecutive_failures;
+        if !score.demoted && consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            warn!("Demoting validator {name} after {consecutive_failures} consecutive failures");
+            score.demoted = true;
+        }
+    }
+
+    /// Returns the validators in a random order, where each of them is more likely to come
+    /// first the greater its weight, followed by the demoted validators in a random order too.
+    pub(crate) fn order<A>(&self, validators: Vec<(ValidatorName, A)>) -> Vec<(ValidatorName, A)> {
+        let scores = self.scores.lock().unwrap();
+        let mut rng = rand::thread_rng();
+        let mut keyed = validators
+            .into_iter()
+            .map(|(name, node)| {
+                let (demoted, weight) = scores
+                    .get(&name)
+                    .map_or((false, 1.0), |score| (score.demoted, score.weight()));
+                // Sorting by decreasing `u^(1/w)`, where `u` is uniform in `[0, 1)`, yields a
+                // random permutation where each item comes first with a probability
+                // proportional to its weight `w`.
+                let key = rng.gen::<f64>().powf(1.0 / weight);
+                (demoted, key, name, node)
+            })
+            .collect::<Vec<_>>();
+        keyed.sort_by(|(demoted1, key1, ..), (demoted2, key2, ..)| {
+            demoted1.cmp(demoted2).then(key2.total_cmp(key1))
+        });
+        keyed
+            .into_iter()
+            .map(|(_, _, name, node)| (name, node))
+            .collect()
+    }
+}
@@ -11,12 +11,16 @@ use std::{
 };
 
 use async_trait::async_trait;
-use futures::{future, FutureExt};
+use futures::{
+    future::{self, Abortable},
+    FutureExt,
+};
 use linera_base::{
     crypto::{CryptoHash, KeyPair},
-    data_types::{ArithmeticError, BlockHeight, Round},
+    data_types::{ArithmeticError, BlockHeight, OracleResponse, Round, Timestamp},
     doc_scalar, ensure,
-    identifiers::{ChainId, Owner},
+    identifiers::{Blob, BlobId, ChainId, Owner},
+    time::Instant,
 };
 use linera_chain::{
     data_types::{
@@ -27,13 +31,14 @@ use linera_chain::{
     manager, ChainError, ChainStateView,
 };
 use linera_execution::{
-    committee::{Committee, Epoch},
-    BytecodeLocation, Query, Response, UserApplicationDescription, UserApplicationId,
+    committee::{Committee, Epoch, ValidatorName},
+    BytecodeLocation, ExecutionError, Query, Response, UserApplicationDescription,
+    UserApplicationId,
 };
-use linera_storage::Storage;
+use linera_storage::{Storage, WalEntry};
 use linera_views::{
     log_view::LogView,
-    views::{RootView, View, ViewError},
+    views::{View, ViewError},
 };
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
@@ -48,11 +53,26 @@ use {
 };
 #[cfg(with_metrics)]
 use {
-    linera_base::{prometheus_util, sync::Lazy},
+    linera_base::{
+        prometheus_util::{self, MeasureLatency as _},
+        sync::Lazy,
+    },
     prometheus::{HistogramVec, IntCounterVec},
 };
 
-use crate::data_types::{ChainInfo, ChainInfoQuery, ChainInfoResponse, CrossChainRequest};
+use crate::{
+    certificate_pipeline::{CertificatePipeline, CertificateStage},
+    chain_scheduler::{ChainLockDiagnostics, ChainScheduler, ChainTurn},
+    chain_state_cache::{CachedChainState, ChainStateCache},
+    committee_cache::CommitteeCache,
+    data_types::{
+        BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse, CrossChainRequest,
+    },
+    equivocation_detector::EquivocationDetector,
+    load_monitor::LoadMonitor,
+    proposal_registry::{ProposalRegistration, ProposalRegistry},
+    response_cache::{RequestKey, ResponseCache},
+};
 
 #[cfg(test)]
 #[path = "unit_tests/worker_tests.rs"]
@@ -90,6 +110,45 @@ static TRANSACTION_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
         .expect("Counter creation should not fail")
 });
 
+#[cfg(with_metrics)]
+static CERTIFIED_BLOCK_CLOCK_SKEW: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "certified_block_clock_skew",
+        "Time (ms) by which the timestamps of certified blocks are ahead of the local clock",
+        &[],
+        Some(vec![
+            0.0, 1.0, 5.0, 10.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static CONFIRMED_BLOCK_PROCESSING_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "confirmed_block_processing_latency",
+        "Time (ms) to check, execute and persist the certificate of a confirmed block",
+        &[],
+        Some(vec![
+            0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0,
+            5000.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static OUTBOX_PENDING_BLOCKS: Lazy<HistogramVec> =
+    Lazy::new(|| {
+        prometheus_util::register_histogram_vec(
+        "outbox_pending_blocks",
+        "Number of blocks of a chain with outgoing messages not yet delivered, after a new block",
+        &[],
+        Some(vec![0.0, 1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 1000.0]),
+    )
+    .expect("Histogram creation should not fail")
+    });
+
 #[cfg(with_metrics)]
 static NUM_BLOCKS: Lazy<IntCounterVec> = Lazy::new(|| {
     prometheus_util::register_int_counter_vec("num_blocks", "Number of blocks added to chains", &[])
@@ -147,6 +206,26 @@ pub struct NetworkActions {
     pub notifications: Vec<Notification>,
 }
 
+impl NetworkActions {
+    /// Appends the requests and notifications of `other`.
+    pub fn extend(&mut self, other: NetworkActions) {
+        self.cross_chain_requests.extend(other.cross_chain_requests);
+        self.notifications.extend(other.notifications);
+    }
+}
+
+/// An operation that the operators of a validator ask a worker to perform, e.g. through the
+/// admin API.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AdminCommand {
+    /// Sends the cross-chain requests for the outgoing messages of a chain that are not
+    /// confirmed as delivered yet, e.g. if their recipients' shards dropped them.
+    RedeliverMessages(ChainId),
+    /// Clears the cached values, chain states and responses, so that they are read from the
+    /// storage again.
+    FlushCaches,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 /// Notification that a chain has a new certified block or a new message.
 pub struct Notification {
@@ -236,8 +315,23 @@ pub enum WorkerError {
     },
     #[error("The given message counts are not what we computed after executing the block")]
     IncorrectMessageCounts,
-    #[error("The timestamp of a Tick operation is in the future.")]
-    InvalidTimestamp,
+    #[error("The given events are not what we computed after executing the block")]
+    IncorrectEvents,
+    #[error(
+        "The given fuel usage {submitted} is not what we computed after executing the block: \
+        {computed}"
+    )]
+    IncorrectFuelUsed { computed: u64, submitted: u64 },
+    #[error(
+        "The block timestamp {block_timestamp} is ahead of the validator's clock \
+        ({local_time}) by more than the tolerated {max_skew:?}. The clock of the client \
+        or of the validator may be out of sync"
+    )]
+    InvalidTimestamp {
+        block_timestamp: Timestamp,
+        local_time: Timestamp,
+        max_skew: Duration,
+    },
     #[error("We don't have the value for the certificate.")]
     MissingCertificateValue,
     #[error("The hash certificate doesn't match its value.")]
@@ -246,20 +340,44 @@ pub enum WorkerError {
     UnneededValue { value_hash: CryptoHash },
     #[error("The following values containing application bytecode are missing: {0:?}.")]
     ApplicationBytecodesNotFound(Vec<BytecodeLocation>),
+    #[error("The following blobs are missing: {0:?}.")]
+    BlobsNotFound(Vec<BlobId>),
+    #[error("A blob was provided that is not required: {blob_id}.")]
+    UnneededBlob { blob_id: BlobId },
     #[error("The certificate in the block proposal is not a ValidatedBlock")]
     MissingExecutedBlockInProposal,
     #[error("Fast blocks cannot query oracles")]
     FastBlockUsingOracles,
+    #[error("The block proposal was cancelled by the validator's operator")]
+    ProposalCancelled,
+    #[error("The chain is busy: the request {0}")]
+    ChainLockTimeout(String),
+    #[error("This worker is a read-only replica: it only answers queries that don't vote")]
+    ReadOnlyReplica,
 }
 
 impl From<linera_chain::ChainError> for WorkerError {
     fn from(chain_error: linera_chain::ChainError) -> Self {
-        WorkerError::ChainError(Box::new(chain_error))
+        match chain_error {
+            ChainError::ExecutionError(ExecutionError::BlobNotFound(blob_id), _) => {
+                WorkerError::BlobsNotFound(vec![blob_id])
+            }
+            chain_error => WorkerError::ChainError(Box::new(chain_error)),
+        }
     }
 }
 
 pub(crate) const DEFAULT_VALUE_CACHE_SIZE: usize = 1000;
 
+/// The default maximum number of certificates returned for a `ChainInfoQuery`.
+pub const DEFAULT_MAX_SENT_CERTIFICATES_PER_QUERY: u64 = 1000;
+
+/// The default maximum number of checkpoints kept for each chain.
+pub const DEFAULT_MAX_CHECKPOINTS: usize = 2;
+
+/// The default maximum size in bytes of the state of a chain copied into a checkpoint.
+pub const DEFAULT_MAX_CHECKPOINT_BYTES: u64 = 64 << 20;
+
 /// State of a worker in a validator or a local node.
 #[derive(Clone)]
 pub struct WorkerState<StorageClient> {
@@ -268,6 +386,9 @@ pub struct WorkerState<StorageClient> {
     /// The signature key pair of the validator. The key may be missing for replicas
     /// without voting rights (possibly with a partial view of chains).
     key_pair: Option<Arc<KeyPair>>,
+    /// The new signature key pair of the validator, if it is being registered on the admin
+    /// chain. It is used for the chains whose current committee contains it.
+    next_key_pair: Option<Arc<KeyPair>>,
     /// Access to local persistent storage.
     storage: StorageClient,
     /// Whether inactive chains are allowed in storage.
@@ -277,8 +398,45 @@ pub struct WorkerState<StorageClient> {
     /// Blocks with a timestamp this far in the future will still be accepted, but the validator
     /// will wait until that timestamp before voting.
     grace_period: Duration,
+    /// How far the local clock is allowed to be behind the clocks of the other validators.
+    clock_skew_tolerance: Duration,
     /// Cached values by hash.
     recent_values: Arc<Mutex<LruCache<CryptoHash, HashedCertificateValue>>>,
+    /// Cached chain states, if enabled.
+    chain_states: Option<Arc<ChainStateCache<StorageClient>>>,
+    /// The recent block proposals and certificates handled successfully, if enabled.
+    responses: Option<Arc<ResponseCache>>,
+    /// The scheduler bounding the number of chains executing requests at once, if enabled.
+    scheduler: Option<Arc<ChainScheduler>>,
+    /// The maximal number of chains executing requests at once, or zero for no limit.
+    max_concurrent_chains: usize,
+    /// How the scheduler reports the requests waiting too long for their chain.
+    chain_lock_diagnostics: ChainLockDiagnostics,
+    /// The block proposals being handled, so that they can be listed and cancelled.
+    proposals: Arc<ProposalRegistry>,
+    /// The equivocations found in the certificates checked by this worker.
+    equivocations: Arc<EquivocationDetector>,
+    /// The recent load of this worker.
+    load: Arc<LoadMonitor>,
+    /// The committees of the known epochs and the certificates verified recently.
+    committees: Arc<CommitteeCache>,
+    /// Where the stages of the certificates are run.
+    pipeline: CertificatePipeline,
+    /// The maximum number of certificates returned for a range of sent certificates.
+    max_sent_certificates_per_query: u64,
+    /// Whether the unfinished work is recorded in the write-ahead log of the storage.
+    write_ahead_log: bool,
+    /// Every how many blocks the state of a chain is copied into a checkpoint, or zero for
+    /// no checkpoints.
+    checkpoint_interval: u64,
+    /// How many of the latest checkpoints of each chain are kept.
+    max_checkpoints: usize,
+    /// The maximum size in bytes of the state of a chain copied into a checkpoint. Larger
+    /// chains are not checkpointed.
+    max_checkpoint_bytes: u64,
+    /// Whether this worker is a read replica, only answering the queries that don't change
+    /// the chains, while another worker writes them to the shared storage.
+    read_only: bool,
     /// One-shot channels to notify callers when messages of a particular chain have been
     /// delivered.
     delivery_notifiers: Arc<Mutex<DeliveryNotifiers>>,
@@ -295,11 +453,29 @@ impl<StorageClient> WorkerState<StorageClient> {
         WorkerState {
             nickname,
             key_pair: key_pair.map(Arc::new),
+            next_key_pair: None,
             storage,
             allow_inactive_chains: false,
             allow_messages_from_deprecated_epochs: false,
             grace_period: Duration::ZERO,
+            clock_skew_tolerance: Duration::ZERO,
             recent_values,
+            chain_states: None,
+            responses: None,
+            scheduler: None,
+            max_concurrent_chains: 0,
+            chain_lock_diagnostics: ChainLockDiagnostics::default(),
+            proposals: Arc::default(),
+            equivocations: Arc::default(),
+            load: Arc::default(),
+            committees: Arc::default(),
+            pipeline: CertificatePipeline::default(),
+            max_sent_certificates_per_query: DEFAULT_MAX_SENT_CERTIFICATES_PER_QUERY,
+            write_ahead_log: false,
+            checkpoint_interval: 0,
+            max_checkpoints: DEFAULT_MAX_CHECKPOINTS,
+            max_checkpoint_bytes: DEFAULT_MAX_CHECKPOINT_BYTES,
+            read_only: false,
             delivery_notifiers: Arc::default(),
         }
     }
@@ -313,15 +489,54 @@ impl<StorageClient> WorkerState<StorageClient> {
         WorkerState {
             nickname,
             key_pair: None,
+            next_key_pair: None,
             storage,
             allow_inactive_chains: false,
             allow_messages_from_deprecated_epochs: false,
             grace_period: Duration::ZERO,
+            clock_skew_tolerance: Duration::ZERO,
             recent_values,
+            chain_states: None,
+            responses: None,
+            scheduler: None,
+            max_concurrent_chains: 0,
+            chain_lock_diagnostics: ChainLockDiagnostics::default(),
+            proposals: Arc::default(),
+            equivocations: Arc::default(),
+            load: Arc::default(),
+            committees: Arc::default(),
+            pipeline: CertificatePipeline::default(),
+            max_sent_certificates_per_query: DEFAULT_MAX_SENT_CERTIFICATES_PER_QUERY,
+            write_ahead_log: false,
+            checkpoint_interval: 0,
+            max_checkpoints: DEFAULT_MAX_CHECKPOINTS,
+            max_checkpoint_bytes: DEFAULT_MAX_CHECKPOINT_BYTES,
+            read_only: false,
             delivery_notifiers,
         }
     }
 
+    /// Sets the new key pair of the validator, used once it is in the committee of a chain.
+    pub fn with_next_key_pair(mut self, next_key_pair: Option<KeyPair>) -> Self {
+        self.next_key_pair = next_key_pair.map(Arc::new);
+        self
+    }
+
+    /// Shares the cache of committees and verified certificates, e.g. with the clients of
+    /// this worker's local node.
+    pub fn with_committee_cache(mut self, committees: Arc<CommitteeCache>) -> Self {
+        self.committees = committees;
+        self
+    }
+
+    /// Returns an instance that verifies the signatures of up to `threads` certificates at
+    /// once on the blocking thread pool, so that they don't hold up the other requests. Zero
+    /// verifies them on the task handling the certificate.
+    pub fn with_signature_verification_threads(mut self, threads: usize) -> Self {
+        self.pipeline = CertificatePipeline::new(threads);
+        self
+    }
+
     pub fn with_allow_inactive_chains(mut self, value: bool) -> Self {
         self.allow_inactive_chains = value;
         self
@@ -341,10 +556,150 @@ impl<StorageClient> WorkerState<StorageClient> {
         self
     }
 
+    /// Returns an instance that tolerates its clock being up to `tolerance` behind the clocks
+    /// of the clients and of the other validators.
+    ///
+    /// Block proposals with a timestamp up to `tolerance` beyond the grace period are
+    /// accepted, and the validator votes on a block or a leader timeout when its own clock is
+    /// at most `tolerance` before the time it would otherwise wait for.
+    pub fn with_clock_skew_tolerance(mut self, tolerance: Duration) -> Self {
+        self.clock_skew_tolerance = tolerance;
+        self
+    }
+
+    /// Returns an instance that keeps up to `size` chain states in memory between requests.
+    /// A size of zero disables the cache.
+    ///
+    /// Cached chain states keep their chains locked in storage, so this must only be used if no
+    /// other worker accesses the same chains.
+    pub fn with_chain_state_cache_size(mut self, size: usize) -> Self {
+        self.chain_states =
+            NonZeroUsize::new(size).map(|size| Arc::new(ChainStateCache::new(size)));
+        self
+    }
+
+    /// Returns an instance that remembers the latest `size` block proposals and certificates
+    /// it handled successfully, and answers an identical request with the current state of
+    /// the chain instead of handling it again, e.g. when a client retries. A size of zero
+    /// disables the cache.
+    pub fn with_response_cache_size(mut self, size: usize) -> Self {
+        self.responses = NonZeroUsize::new(size).map(|size| Arc::new(ResponseCache::new(size)));
+        self
+    }
+
+    /// Returns an instance that executes block proposals, certificates and cross-chain requests
+    /// for at most `max` chains at once, and for each chain one request at a time, in the order
+    /// they were received. Zero means no limit.
+    pub fn with_max_concurrent_chains(mut self, max: usize) -> Self {
+        self.max_concurrent_chains = max;
+        self.reset_scheduler();
+        self
+    }
+
+    /// Returns an instance that logs a warning whenever a request holds its chain, or waits
+    /// for it, longer than `warn_after`, and that fails the requests that waited for their
+    /// chain for `abort_after`, with a description of the request holding it.
+    ///
+    /// This requires executing the requests of each chain one at a time, as if the number of
+    /// concurrent chains were limited.
+    pub fn with_chain_lock_diagnostics(
+        mut self,
+        warn_after: Option<Duration>,
+        abort_after: Option<Duration>,
+    ) -> Self {
+        self.chain_lock_diagnostics = ChainLockDiagnostics {
+            warn_after,
+            abort_after,
+        };
+        self.reset_scheduler();
+        self
+    }
+
+    /// Creates the scheduler for the current configuration.
+    fn reset_scheduler(&mut self) {
+        let scheduler = match NonZeroUsize::new(self.max_concurrent_chains) {
+            Some(max) => ChainScheduler::new(max),
+            None if self.chain_lock_diagnostics.is_enabled() => ChainScheduler::unbounded(),
+            None => {
+                self.scheduler = None;
+                return;
+            }
+        };
+        self.scheduler = Some(Arc::new(
+            scheduler.with_diagnostics(self.chain_lock_diagnostics),
+        ));
+    }
+
+    /// Returns an instance that returns at most `max` certificates for a range of sent
+    /// certificates. Clients fetch the rest of the range with further queries.
+    pub fn with_max_sent_certificates_per_query(mut self, max: u64) -> Self {
+        self.max_sent_certificates_per_query = max.max(1);
+        self
+    }
+
+    /// Returns an instance that records the confirmed certificates it accepts and the
+    /// outgoing messages that are not delivered yet in the write-ahead log of the storage, so
+    /// that [`WorkerState::replay_write_ahead_log`] can resume them after a crash.
+    pub fn with_write_ahead_log(mut self, enabled: bool) -> Self {
+        self.write_ahead_log = enabled;
+        self
+    }
+
+    /// Returns an instance that copies the state of each chain into a checkpoint of the
+    /// storage every `interval` blocks, so that the state after these blocks can be loaded
+    /// with `Storage::load_chain_checkpoint`. Zero disables the checkpoints.
+    pub fn with_checkpoint_interval(mut self, interval: u64) -> Self {
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// Returns an instance that only keeps the latest `count` checkpoints of each chain,
+    /// deleting the older ones when a new one is written. At least one is kept.
+    pub fn with_max_checkpoints(mut self, count: usize) -> Self {
+        self.max_checkpoints = count;
+        self
+    }
+
+    /// Returns an instance that doesn't checkpoint the chains whose state is larger than
+    /// `max_bytes`. Each checkpoint copies the whole state of the chain, so this bounds the
+    /// cost of writing it.
+    pub fn with_max_checkpoint_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_checkpoint_bytes = max_bytes;
+        self
+    }
+
+    /// Returns an instance that only answers the chain information queries that don't
+    /// vote, and rejects the other requests. The chains are written to the shared storage
+    /// by another worker, so the chain state cache of a read replica must be disabled.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
     pub fn nickname(&self) -> &str {
         &self.nickname
     }
 
+    /// Returns the registry of the block proposals being handled by this worker.
+    pub fn proposals(&self) -> &Arc<ProposalRegistry> {
+        &self.proposals
+    }
+
+    /// Returns the equivocations found by this worker.
+    pub fn equivocations(&self) -> &Arc<EquivocationDetector> {
+        &self.equivocations
+    }
+
+    /// Returns the cache of committees and verified certificates of this worker.
+    pub fn committees(&self) -> &Arc<CommitteeCache> {
+        &self.committees
+    }
+
+    /// Returns the monitor of the recent load of this worker.
+    pub fn load(&self) -> &Arc<LoadMonitor> {
+        &self.load
+    }
+
     /// Returns the storage client so that it can be manipulated or queried.
     #[cfg(not(feature = "test"))]
     pub(crate) fn storage_client(&self) -> &StorageClient {
@@ -384,6 +739,29 @@ impl<StorageClient> WorkerState<StorageClient> {
     ) -> Option<HashedCertificateValue> {
         self.recent_values.lock().await.get(hash).cloned()
     }
+
+    /// Waits for the turn of a chain to execute a `request`, if the scheduler is enabled.
+    async fn schedule(
+        &self,
+        chain_id: ChainId,
+        request: &'static str,
+    ) -> Result<Option<ChainTurn>, WorkerError> {
+        let Some(scheduler) = &self.scheduler else {
+            return Ok(None);
+        };
+        let turn = scheduler
+            .schedule(chain_id, request)
+            .await
+            .map_err(|report| WorkerError::ChainLockTimeout(report.to_string()))?;
+        Ok(Some(turn))
+    }
+
+    /// Records a request handled successfully, if the response cache is enabled.
+    fn cache_response(&self, key: Option<(ChainId, RequestKey)>) {
+        if let (Some(responses), Some(key)) = (&self.responses, key) {
+            responses.insert(key);
+        }
+    }
 }
 
 impl<StorageClient> WorkerState<StorageClient>
@@ -391,6 +769,265 @@ where
     StorageClient: Storage + Clone + Send + Sync + 'static,
     ViewError: From<StorageClient::ContextError>,
 {
+    /// Loads the state of a chain, from the chain state cache if it is enabled.
+    async fn load_chain(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<CachedChainState<StorageClient>, WorkerError> {
+        let chain = match &self.chain_states {
+            Some(chain_states) => {
+                chain_states
+                    .load(&self.storage, chain_id, &self.load)
+                    .await?
+            }
+            None => {
+                let start = Instant::now();
+                let chain = self.storage.load_chain(chain_id).await?;
+                self.load.record_storage_load(start.elapsed());
+                CachedChainState::uncached(chain)
+            }
+        };
+        Ok(chain)
+    }
+
+    /// Returns the local time at which `timestamp` may already have been reached by the
+    /// clocks of the other validators.
+    fn skew_tolerant(&self, timestamp: Timestamp) -> Timestamp {
+        timestamp.saturating_sub_micros(self.clock_skew_tolerance_micros())
+    }
+
+    fn clock_skew_tolerance_micros(&self) -> u64 {
+        u64::try_from(self.clock_skew_tolerance.as_micros()).unwrap_or(u64::MAX)
+    }
+
+    /// Compares the timestamp of a block certified by a quorum of validators with the local
+    /// clock. If the block is ahead of the local time by more than this validator would accept
+    /// in a proposal, the local clock is likely to be behind the clocks of its peers.
+    fn observe_clock_skew(&self, block: &Block) {
+        let local_time = self.storage.clock().current_time();
+        let skew = block.timestamp.duration_since(local_time);
+        #[cfg(with_metrics)]
+        CERTIFIED_BLOCK_CLOCK_SKEW
+            .with_label_values(&[])
+            .observe(skew.as_secs_f64() * 1000.0);
+        if self.key_pair.is_some() && skew > self.grace_period + self.clock_skew_tolerance {
+            warn!(
+                nickname = self.nickname,
+                chain_id = %block.chain_id,
+                height = %block.height,
+                ?skew,
+                "A quorum of validators certified a block whose timestamp is ahead of the local \
+                clock by more than the grace period and the clock skew tolerance. The local \
+                clock may be out of sync."
+            );
+        }
+    }
+
+    /// Handles a block proposal registered in the worker's [`ProposalRegistry`].
+    async fn process_block_proposal(
+        &mut self,
+        proposal: BlockProposal,
+        registration: &ProposalRegistration,
+    ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
+        let request_key = self
+            .responses
+            .as_ref()
+            .map(|_| RequestKey::proposal(&proposal));
+        if let Some(response) = self.cached_response(&request_key).await? {
+            return Ok(response);
+        }
+        let _turn = self
+            .schedule(proposal.content.block.chain_id, "block proposal")
+            .await?;
+        let BlockProposal {
+            content: BlockAndRound { block, round },
+            owner,
+            hashed_certificate_values,
+            blobs,
+            validated,
+            signature: _,
+        } = &proposal;
+        let chain_id = block.chain_id;
+        let mut chain = self.load_active_chain(chain_id).await?;
+        registration.start_executing();
+        // Check the epoch.
+        let (epoch, committee) = chain
+            .execution_state
+            .system
+            .current_committee()
+            .expect("chain is active");
+        Self::check_block_epoch(epoch, block)?;
+        if let Some(validated) = validated {
+            self.committees.check(validated, epoch, committee)?;
+        }
+        // Check the authentication of the block.
+        let public_key = chain
+            .manager
+            .get()
+            .verify_owner(&proposal)
+            .ok_or(WorkerError::InvalidOwner)?;
+        proposal.check_signature(public_key)?;
+        // Check the authentication of the operations in the block.
+        if let Some(signer) = block.authenticated_signer {
+            ensure!(signer == *owner, WorkerError::InvalidSigner(signer));
+        }
+        // Check if the chain is ready for this new block proposal.
+        // This should always pass for nodes without voting key.
+        chain.tip_state.get().verify_block_chaining(block)?;
+        if chain.manager.get().check_proposed_block(&proposal)? == manager::Outcome::Skip {
+            // If we just processed the same pending block, return the chain info unchanged.
+            return Ok((
+                ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain)),
+                NetworkActions::default(),
+            ));
+        }
+        // Update the inboxes so that we can verify the provided hashed certificate values are legitimately required.
+        // Actual execution happens below, after other validity checks.
+        chain.remove_events_from_inboxes(block).await?;
+        // Verify that all required bytecode hashed certificate values are available, and no unrelated ones provided.
+        self.check_no_missing_bytecode(block, hashed_certificate_values)
+            .await?;
+        // Write the values so that the bytecode is available during execution.
+        self.storage
+            .write_hashed_certificate_values(hashed_certificate_values)
+            .await?;
+        // Write the blobs so that they are available during execution. They are collected as
+        // garbage unless the block gets confirmed.
+        self.storage.write_blobs(blobs).await?;
+        let local_time = self.storage.clock().current_time();
+        let max_skew = self.grace_period + self.clock_skew_tolerance;
+        ensure!(
+            block.timestamp.duration_since(local_time) <= max_skew,
+            WorkerError::InvalidTimestamp {
+                block_timestamp: block.timestamp,
+                local_time,
+                max_skew,
+            }
+        );
+        self.storage
+            .clock()
+            .sleep_until(self.skew_tolerant(block.timestamp))
+            .await;
+        let local_time = self.storage.clock().current_time();
+        let outcome = if let Some(validated) = validated {
+            validated
+                .value()
+                .executed_block()
+                .ok_or_else(|| WorkerError::MissingExecutedBlockInProposal)?
+                .outcome
+                .clone()
+        } else {
+            chain.execute_block(block, local_time, None).await?
+        };
+        // Verify that all the blobs the block needs are available, and no unrelated ones provided.
+        let mut required_blob_ids = block.published_blob_ids();
+        required_blob_ids.extend(outcome.read_blob_ids());
+        // The bytecodes in the provided values can be in blobs that this validator is missing.
+        required_blob_ids.extend(block.bytecode_blob_ids(hashed_certificate_values));
+        for blob in blobs {
+            let blob_id = blob.id();
+            ensure!(
+                required_blob_ids.contains(&blob_id),
+                WorkerError::UnneededBlob { blob_id }
+            );
+        }
+        self.check_no_missing_blobs(required_blob_ids).await?;
+        if round.is_fast() {
+            // Reading a blob doesn't depend on the validator, so it is allowed in fast blocks.
+            let mut records = outcome.oracle_records.iter();
+            ensure!(
+                records.all(|record| record
+                    .responses
+                    .iter()
+                    .all(|response| matches!(response, OracleResponse::Blob(_)))),
+                WorkerError::FastBlockUsingOracles
+            );
+        }
+        // Check if the counters of tip_state would be valid.
+        chain.tip_state.get().verify_counters(block, &outcome)?;
+        // Verify that the resulting chain would have no unconfirmed incoming messages.
+        chain.validate_incoming_messages().await?;
+        // Reset all the staged changes as we were only validating things.
+        chain.rollback();
+        // Create the vote and store it in the chain state.
+        let key_pair = self.chain_key_pair(&chain);
+        let manager = chain.manager.get_mut();
+        #[cfg(with_metrics)]
+        let round = proposal.content.round;
+        manager.create_vote(proposal, outcome, key_pair, local_time);
+        // Cache the value we voted on, so the client doesn't have to send it again.
+        if let Some(vote) = manager.pending() {
+            self.cache_validated(&vote.value).await;
+        }
+        let info = ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain));
+        chain.save().await?;
+        self.cache_response(request_key);
+        // Trigger any outgoing cross-chain messages that haven't been confirmed yet.
+        let actions = self.create_network_actions(&chain).await?;
+        #[cfg(with_metrics)]
+        NUM_ROUNDS_IN_BLOCK_PROPOSAL
+            .with_label_values(&[round.type_name()])
+            .observe(round.number() as f64);
+        Ok((info, actions))
+    }
+
+    /// Performs an operation requested by the operators of the validator, and returns the
+    /// resulting cross-chain requests.
+    pub async fn handle_admin_command(
+        &self,
+        command: AdminCommand,
+    ) -> Result<NetworkActions, WorkerError> {
+        match command {
+            AdminCommand::RedeliverMessages(chain_id) => {
+                // The worker writing the chain sends its cross-chain messages.
+                ensure!(!self.read_only, WorkerError::ReadOnlyReplica);
+                let _turn = self.schedule(chain_id, "message redelivery").await?;
+                let chain = self.load_active_chain(chain_id).await?;
+                self.create_network_actions(&chain).await
+            }
+            AdminCommand::FlushCaches => {
+                self.recent_values.lock().await.clear();
+                if let Some(chain_states) = &self.chain_states {
+                    chain_states.clear().await;
+                }
+                if let Some(responses) = &self.responses {
+                    responses.clear();
+                }
+                Ok(NetworkActions::default())
+            }
+        }
+    }
+
+    /// If an identical request was already handled successfully, returns the current
+    /// information and network actions of its chain, without validating and executing the
+    /// request again.
+    async fn cached_response(
+        &self,
+        key: &Option<(ChainId, RequestKey)>,
+    ) -> Result<Option<(ChainInfoResponse, NetworkActions)>, WorkerError> {
+        let (Some(responses), Some(key)) = (&self.responses, key) else {
+            return Ok(None);
+        };
+        if !responses.contains(key) {
+            return Ok(None);
+        }
+        let chain = self.load_chain(key.0).await?;
+        let info = ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain));
+        // The retried request may be due to lost cross-chain requests.
+        let actions = self.create_network_actions(&chain).await?;
+        Ok(Some((info, actions)))
+    }
+
+    /// Loads the state of a chain and checks that it is active.
+    async fn load_active_chain(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<CachedChainState<StorageClient>, WorkerError> {
+        let chain = self.load_chain(chain_id).await?;
+        chain.ensure_is_active()?;
+        Ok(chain)
+    }
+
     // NOTE: This only works for non-sharded workers!
     #[cfg(with_testing)]
     pub async fn fully_handle_certificate(
@@ -435,22 +1072,57 @@ where
         &mut self,
         block: Block,
     ) -> Result<(ExecutedBlock, ChainInfoResponse), WorkerError> {
-        let mut chain = self.storage.load_active_chain(block.chain_id).await?;
+        let mut chain = self.load_active_chain(block.chain_id).await?;
         let local_time = self.storage.clock().current_time();
         let signer = block.authenticated_signer;
         let executed_block = chain
             .execute_block(&block, local_time, None)
             .await?
             .with(block);
-        let mut response = ChainInfoResponse::new(&chain, None);
+        let mut response = ChainInfoResponse::new(&*chain, None);
         if let Some(signer) = signer {
             response.info.requested_owner_balance =
                 chain.execution_state.system.balances.get(&signer).await?;
         }
+        response.info.requested_collected_fees =
+            chain.execution_state.system.collected_fees().await?;
         // Do not save the new state.
         Ok((executed_block, response))
     }
 
+    /// Executes a block as if it had been proposed, without voting on it or saving the chain
+    /// state, so that clients can check its outcome before signing it.
+    pub async fn simulate_block_proposal(
+        &mut self,
+        block: Block,
+    ) -> Result<ExecutedBlock, WorkerError> {
+        let _turn = self.schedule(block.chain_id, "block simulation").await?;
+        let mut chain = self.load_active_chain(block.chain_id).await?;
+        let (epoch, _) = chain
+            .execution_state
+            .system
+            .current_committee()
+            .expect("chain is active");
+        Self::check_block_epoch(epoch, &block)?;
+        chain.tip_state.get().verify_block_chaining(&block)?;
+        chain.remove_events_from_inboxes(&block).await?;
+        let local_time = self.storage.clock().current_time();
+        let outcome = chain.execute_block(&block, local_time, None).await?;
+        chain.tip_state.get().verify_counters(&block, &outcome)?;
+        // The staged changes are discarded when the chain state is dropped.
+        Ok(outcome.with(block))
+    }
+
+    /// Returns a blob from the storage, so that the validators and clients missing it can
+    /// fetch it on demand.
+    pub async fn download_blob(&self, blob_id: BlobId) -> Result<Blob, WorkerError> {
+        match self.storage.read_blob(blob_id).await {
+            Ok(blob) => Ok(blob),
+            Err(ViewError::NotFound(_)) => Err(WorkerError::BlobsNotFound(vec![blob_id])),
+            Err(error) => Err(error.into()),
+        }
+    }
+
     // Schedule a notification when cross-chain messages are delivered up to the given height.
     async fn register_delivery_notifier(
         &mut self,
@@ -489,7 +1161,7 @@ where
         chain_id: ChainId,
         query: Query,
     ) -> Result<Response, WorkerError> {
-        let mut chain = self.storage.load_active_chain(chain_id).await?;
+        let mut chain = self.load_active_chain(chain_id).await?;
         let response = chain.query_application(query).await?;
         Ok(response)
     }
@@ -499,13 +1171,27 @@ where
         chain_id: ChainId,
         application_id: UserApplicationId,
     ) -> Result<UserApplicationDescription, WorkerError> {
-        let mut chain = self.storage.load_active_chain(chain_id).await?;
+        let mut chain = self.load_active_chain(chain_id).await?;
         let response = chain.describe_application(application_id).await?;
         Ok(response)
     }
 
-    /// Gets a reference to the [`KeyPair`], if available.
-    fn key_pair(&self) -> Option<&KeyPair> {
+    /// Gets a reference to the [`KeyPair`] to sign with for the given chain, if available.
+    ///
+    /// While a new key is being registered on the admin chain, this is the next key once it is
+    /// in the chain's current committee, and the current key otherwise.
+    fn chain_key_pair(&self, chain: &ChainStateView<StorageClient::Context>) -> Option<&KeyPair> {
+        if let Some(next_key_pair) = &self.next_key_pair {
+            let name = ValidatorName(next_key_pair.public());
+            let is_member = chain
+                .execution_state
+                .system
+                .current_committee()
+                .is_some_and(|(_, committee)| committee.validators().contains_key(&name));
+            if is_member {
+                return Some(next_key_pair);
+            }
+        }
         self.key_pair.as_ref().map(Arc::as_ref)
     }
 
@@ -594,6 +1280,91 @@ where
         Ok(actions)
     }
 
+    /// Updates the entry of a chain in the write-ahead log, if it is enabled: the
+    /// `certificate` being executed, if any, and whether some outgoing messages are not
+    /// delivered yet. The entry is removed when there is no work left.
+    async fn update_wal_entry(
+        &self,
+        chain: &ChainStateView<StorageClient::Context>,
+        certificate: Option<CryptoHash>,
+    ) -> Result<(), WorkerError> {
+        if !self.write_ahead_log {
+            return Ok(());
+        }
+        let chain_id = chain.chain_id();
+        let pending_deliveries = !chain.outbox_counters.get().is_empty();
+        if certificate.is_none() && !pending_deliveries {
+            self.storage.remove_wal_entry(chain_id).await?;
+        } else {
+            let entry = WalEntry {
+                certificate,
+                pending_deliveries,
+            };
+            self.storage.write_wal_entry(chain_id, &entry).await?;
+        }
+        Ok(())
+    }
+
+    /// Resumes the work recorded in the write-ahead log for the chains selected by `filter`,
+    /// e.g. the chains of a shard when it restarts: executes the certificates whose execution
+    /// was interrupted, and returns the cross-chain requests for the outgoing messages that are
+    /// not confirmed as delivered yet.
+    ///
+    /// The chains whose work can't be resumed are logged and skipped.
+    pub async fn replay_write_ahead_log(
+        &mut self,
+        filter: impl Fn(ChainId) -> bool,
+    ) -> Result<NetworkActions, WorkerError> {
+        let mut actions = NetworkActions::default();
+        if !self.write_ahead_log {
+            return Ok(actions);
+        }
+        for (chain_id, entry) in self.storage.read_wal_entries().await? {
+            if !filter(chain_id) {
+                continue;
+            }
+            let _turn = self.schedule(chain_id, "write-ahead log").await?;
+            let result = async {
+                let chain_actions = match entry.certificate {
+                    Some(hash) => {
+                        let certificate = self.storage.read_certificate(hash).await?;
+                        // If the block was already executed, this only returns its pending
+                        // cross-chain requests.
+                        let (_, actions) =
+                            self.process_confirmed_block(certificate, &[], None).await?;
+                        actions
+                    }
+                    None => {
+                        let chain = self.load_chain(chain_id).await?;
+                        self.create_network_actions(&chain).await?
+                    }
+                };
+                let chain = self.load_chain(chain_id).await?;
+                self.update_wal_entry(&chain, None).await?;
+                Ok::<_, WorkerError>(chain_actions)
+            }
+            .await;
+            match result {
+                Ok(chain_actions) => {
+                    debug!(
+                        nickname = self.nickname,
+                        %chain_id,
+                        requests = chain_actions.cross_chain_requests.len(),
+                        "Replayed the write-ahead log of a chain",
+                    );
+                    actions.extend(chain_actions);
+                }
+                Err(error) => warn!(
+                    nickname = self.nickname,
+                    %chain_id,
+                    %error,
+                    "Failed to replay the write-ahead log of a chain",
+                ),
+            }
+        }
+        Ok(actions)
+    }
+
     /// Processes a confirmed block (aka a commit).
     async fn process_confirmed_block(
         &mut self,
@@ -601,6 +1372,8 @@ where
         hashed_certificate_values: &[HashedCertificateValue],
         notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
+        #[cfg(with_metrics)]
+        let _latency = CONFIRMED_BLOCK_PROCESSING_LATENCY.measure_latency();
         let CertificateValue::ConfirmedBlock { executed_block, .. } = certificate.value() else {
             panic!("Expecting a confirmation certificate");
         };
@@ -610,8 +1383,10 @@ where
             message_counts,
             state_hash,
             oracle_records,
+            fuel_used,
+            events,
         } = &executed_block.outcome;
-        let mut chain = self.storage.load_chain(block.chain_id).await?;
+        let mut chain = self.load_chain(block.chain_id).await?;
         // Check that the chain is active and ready for this confirmation.
         let tip = chain.tip_state.get().clone();
         if tip.next_block_height < block.height {
@@ -621,7 +1396,7 @@ where
         }
         if tip.next_block_height > block.height {
             // Block was already confirmed.
-            let info = ChainInfoResponse::new(&chain, self.key_pair());
+            let info = ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain));
             let actions = self.create_network_actions(&chain).await?;
             self.register_delivery_notifier(
                 block.chain_id,
@@ -656,7 +1431,16 @@ where
             .current_committee()
             .expect("chain is active");
         Self::check_block_epoch(epoch, block)?;
-        certificate.check(committee)?;
+        self.pipeline
+            .verify_signatures(&self.committees, &certificate, epoch, committee)
+            .await?;
+        if let Some(admin_id) = *chain.execution_state.system.admin_id.get() {
+            // The committees created by the admin chain are known as soon as they are
+            // certified, before the chains migrate to them.
+            self.committees
+                .add_committees_from(admin_id, &certificate)?;
+        }
+        self.equivocations.record_certificate(&certificate);
         // This should always be true for valid certificates.
         ensure!(
             tip.block_hash == block.previous_block_hash,
@@ -665,6 +1449,11 @@ where
         // Verify that all required bytecode hashed certificate values are available, and no unrelated ones provided.
         self.check_no_missing_bytecode(block, hashed_certificate_values)
             .await?;
+        // Verify that all the blobs the block needs are available.
+        let required_blob_ids = executed_block.required_blob_ids();
+        self.check_no_missing_blobs(required_blob_ids.iter().copied())
+            .await?;
+        let execute_timer = CertificateStage::Execute.start();
         // Persist certificate and hashed certificate values.
         for value in hashed_certificate_values {
             self.cache_recent_value(Cow::Borrowed(value)).await;
@@ -676,6 +1465,12 @@ where
         );
         result_hashed_certificate_value?;
         result_certificate?;
+        // The blobs of a confirmed block must be kept.
+        let blob_ids = required_blob_ids.into_iter().collect::<Vec<_>>();
+        self.storage.publish_blobs(&blob_ids).await?;
+        // Record the certificate until the chain is saved, to resume it after a crash.
+        self.update_wal_entry(&chain, Some(certificate.hash()))
+            .await?;
         // Execute the block and update inboxes.
         chain.remove_events_from_inboxes(block).await?;
         let local_time = self.storage.clock().current_time();
@@ -698,6 +1493,18 @@ where
             *state_hash == verified_outcome.state_hash,
             WorkerError::IncorrectStateHash
         );
+        ensure!(
+            *fuel_used == verified_outcome.fuel_used,
+            WorkerError::IncorrectFuelUsed {
+                computed: verified_outcome.fuel_used,
+                submitted: *fuel_used,
+            }
+        );
+        ensure!(
+            *events == verified_outcome.events,
+            WorkerError::IncorrectEvents
+        );
+        drop(execute_timer);
         // Advance to next block height.
         let tip = chain.tip_state.get_mut();
         tip.block_hash = Some(certificate.hash());
@@ -706,7 +1513,28 @@ where
         tip.num_operations += block.operations.len() as u32;
         tip.num_outgoing_messages += messages.len() as u32;
         chain.confirmed_log.push(certificate.hash());
-        let info = ChainInfoResponse::new(&chain, self.key_pair());
+        for outcome in executed_block.transaction_outcomes(certificate.hash()) {
+            chain.event_log.push(outcome);
+        }
+        let info = ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain));
+        // Persist chain.
+        let commit_timer = CertificateStage::Commit.start();
+        chain.save().await?;
+        self.update_wal_entry(&chain, None).await?;
+        let next_block_height = chain.tip_state.get().next_block_height;
+        if self.checkpoint_interval > 0 && next_block_height.0 % self.checkpoint_interval == 0 {
+            self.storage
+                .write_chain_checkpoint(
+                    block.chain_id,
+                    next_block_height,
+                    self.max_checkpoints,
+                    self.max_checkpoint_bytes,
+                )
+                .await?;
+        }
+        drop(commit_timer);
+        // Prepare the delivery of the outgoing messages.
+        let fan_out_timer = CertificateStage::FanOut.start();
         let mut actions = self.create_network_actions(&chain).await?;
         actions.notifications.push(Notification {
             chain_id: block.chain_id,
@@ -715,8 +1543,7 @@ where
                 hash: certificate.value.hash(),
             },
         });
-        // Persist chain.
-        chain.save().await?;
+        drop(fan_out_timer);
         // Notify the caller when cross-chain messages are delivered.
         self.register_delivery_notifier(
             block.chain_id,
@@ -728,7 +1555,12 @@ where
         self.cache_recent_value(Cow::Owned(certificate.value)).await;
 
         #[cfg(with_metrics)]
-        NUM_BLOCKS.with_label_values(&[]).inc();
+        {
+            NUM_BLOCKS.with_label_values(&[]).inc();
+            OUTBOX_PENDING_BLOCKS
+                .with_label_values(&[])
+                .observe(chain.outbox_counters.get().len() as f64);
+        }
 
         Ok((info, actions))
     }
@@ -787,6 +1619,32 @@ where
         }
     }
 
+    /// Returns an error if some of the given blobs are not in the storage.
+    async fn check_no_missing_blobs(
+        &self,
+        blob_ids: impl IntoIterator<Item = BlobId>,
+    ) -> Result<(), WorkerError> {
+        let tasks = blob_ids
+            .into_iter()
+            .map(|blob_id| {
+                self.storage
+                    .contains_blob(blob_id)
+                    .map(move |result| (blob_id, result))
+            })
+            .collect::<Vec<_>>();
+        let mut missing_blob_ids = vec![];
+        for (blob_id, result) in future::join_all(tasks).await {
+            if !result? {
+                missing_blob_ids.push(blob_id);
+            }
+        }
+        if missing_blob_ids.is_empty() {
+            Ok(())
+        } else {
+            Err(WorkerError::BlobsNotFound(missing_blob_ids))
+        }
+    }
+
     /// Processes a validated block issued from a multi-owner chain.
     async fn process_validated_block(
         &mut self,
@@ -802,33 +1660,37 @@ where
         let height = block.height;
         // Check that the chain is active and ready for this confirmation.
         // Verify the certificate. Returns a catch-all error to make client code more robust.
-        let mut chain = self.storage.load_active_chain(chain_id).await?;
+        let mut chain = self.load_active_chain(chain_id).await?;
         let (epoch, committee) = chain
             .execution_state
             .system
             .current_committee()
             .expect("chain is active");
         Self::check_block_epoch(epoch, block)?;
-        certificate.check(committee)?;
+        self.pipeline
+            .verify_signatures(&self.committees, &certificate, epoch, committee)
+            .await?;
+        self.equivocations.record_certificate(&certificate);
         let mut actions = NetworkActions::default();
         if chain.tip_state.get().already_validated_block(height)?
             || chain.manager.get().check_validated_block(&certificate)? == manager::Outcome::Skip
         {
             // If we just processed the same pending block, return the chain info unchanged.
             return Ok((
-                ChainInfoResponse::new(&chain, self.key_pair()),
+                ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain)),
                 actions,
                 true,
             ));
         }
         self.cache_validated(&certificate.value).await;
         let old_round = chain.manager.get().current_round;
+        let key_pair = self.chain_key_pair(&chain);
         chain.manager.get_mut().create_final_vote(
             certificate,
-            self.key_pair(),
+            key_pair,
             self.storage.clock().current_time(),
         );
-        let info = ChainInfoResponse::new(&chain, self.key_pair());
+        let info = ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain));
         chain.save().await?;
         let round = chain.manager.get().current_round;
         if round > old_round {
@@ -856,7 +1718,7 @@ where
         };
         // Check that the chain is active and ready for this confirmation.
         // Verify the certificate. Returns a catch-all error to make client code more robust.
-        let mut chain = self.storage.load_active_chain(chain_id).await?;
+        let mut chain = self.load_active_chain(chain_id).await?;
         let (chain_epoch, committee) = chain
             .execution_state
             .system
@@ -870,10 +1732,15 @@ where
                 epoch
             }
         );
-        certificate.check(committee)?;
+        self.pipeline
+            .verify_signatures(&self.committees, &certificate, epoch, committee)
+            .await?;
         let mut actions = NetworkActions::default();
         if chain.tip_state.get().already_validated_block(height)? {
-            return Ok((ChainInfoResponse::new(&chain, self.key_pair()), actions));
+            return Ok((
+                ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain)),
+                actions,
+            ));
         }
         let old_round = chain.manager.get().current_round;
         chain
@@ -887,7 +1754,7 @@ where
                 reason: Reason::NewRound { height, round },
             })
         }
-        let info = ChainInfoResponse::new(&chain, self.key_pair());
+        let info = ChainInfoResponse::new(&*chain, self.chain_key_pair(&chain));
         chain.save().await?;
         Ok((info, actions))
     }
@@ -898,7 +1765,7 @@ where
         recipient: ChainId,
         bundles: Vec<MessageBundle>,
     ) -> Result<Option<BlockHeight>, WorkerError> {
-        let mut chain = self.storage.load_chain(recipient).await?;
+        let mut chain = self.load_chain(recipient).await?;
         // Only process certificates with relevant heights and epochs.
         let next_height_to_receive = chain.next_block_height_to_receive(origin).await?;
         let last_anticipated_block_height = chain.last_anticipated_block_height(origin).await?;
@@ -969,7 +1836,7 @@ where
         chain_id: ChainId,
         height: BlockHeight,
     ) -> Result<Option<Certificate>, WorkerError> {
-        let chain = self.storage.load_active_chain(chain_id).await?;
+        let chain = self.load_active_chain(chain_id).await?;
         let certificate_hash = match chain.confirmed_log.get(height.try_into()?).await? {
             Some(hash) => hash,
             None => return Ok(None),
@@ -989,6 +1856,10 @@ where
         &self,
         chain_id: ChainId,
     ) -> Result<ApplicationRegistryView<StorageClient::Context>, WorkerError> {
+        // The registry is moved out of the chain state, so it must not stay in the cache.
+        if let Some(chain_states) = &self.chain_states {
+            chain_states.evict(chain_id).await;
+        }
         let chain = self.storage.load_active_chain(chain_id).await?;
         Ok(chain.execution_state.system.registry)
     }
@@ -1025,7 +1896,7 @@ where
         };
         let origin = Origin { sender, medium };
 
-        let mut chain = self.storage.load_active_chain(chain_id).await?;
+        let mut chain = self.load_active_chain(chain_id).await?;
         let mut inbox = chain.inboxes.try_load_entry_mut(&origin).await?;
 
         let certificate_hash = certificate.hash();
@@ -1082,104 +1953,23 @@ where
         proposal: BlockProposal,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
         trace!("{} <-- {:?}", self.nickname, proposal);
-        let BlockProposal {
-            content: BlockAndRound { block, round },
-            owner,
-            hashed_certificate_values,
-            validated,
-            signature: _,
-        } = &proposal;
-        let chain_id = block.chain_id;
-        let mut chain = self.storage.load_active_chain(chain_id).await?;
-        // Check the epoch.
-        let (epoch, committee) = chain
-            .execution_state
-            .system
-            .current_committee()
-            .expect("chain is active");
-        Self::check_block_epoch(epoch, block)?;
-        if let Some(validated) = validated {
-            validated.check(committee)?;
-        }
-        // Check the authentication of the block.
-        let public_key = chain
-            .manager
-            .get()
-            .verify_owner(&proposal)
-            .ok_or(WorkerError::InvalidOwner)?;
-        proposal.check_signature(public_key)?;
-        // Check the authentication of the operations in the block.
-        if let Some(signer) = block.authenticated_signer {
-            ensure!(signer == *owner, WorkerError::InvalidSigner(signer));
-        }
-        // Check if the chain is ready for this new block proposal.
-        // This should always pass for nodes without voting key.
-        chain.tip_state.get().verify_block_chaining(block)?;
-        if chain.manager.get().check_proposed_block(&proposal)? == manager::Outcome::Skip {
-            // If we just processed the same pending block, return the chain info unchanged.
-            return Ok((
-                ChainInfoResponse::new(&chain, self.key_pair()),
-                NetworkActions::default(),
-            ));
-        }
-        // Update the inboxes so that we can verify the provided hashed certificate values are legitimately required.
-        // Actual execution happens below, after other validity checks.
-        chain.remove_events_from_inboxes(block).await?;
-        // Verify that all required bytecode hashed certificate values are available, and no unrelated ones provided.
-        self.check_no_missing_bytecode(block, hashed_certificate_values)
-            .await?;
-        // Write the values so that the bytecode is available during execution.
-        self.storage
-            .write_hashed_certificate_values(hashed_certificate_values)
-            .await?;
-        let local_time = self.storage.clock().current_time();
-        ensure!(
-            block.timestamp.duration_since(local_time) <= self.grace_period,
-            WorkerError::InvalidTimestamp
-        );
-        self.storage.clock().sleep_until(block.timestamp).await;
-        let local_time = self.storage.clock().current_time();
-        let outcome = if let Some(validated) = validated {
-            validated
-                .value()
-                .executed_block()
-                .ok_or_else(|| WorkerError::MissingExecutedBlockInProposal)?
-                .outcome
-                .clone()
-        } else {
-            chain.execute_block(block, local_time, None).await?
+        let chain_id = proposal.content.block.chain_id;
+        let _request = self.load.start_request(chain_id);
+        ensure!(!self.read_only, WorkerError::ReadOnlyReplica);
+        let (registration, abort_registration) = self
+            .proposals
+            .register(chain_id, proposal.content.block.height);
+        let handling = self.process_block_proposal(proposal, &registration);
+        let result = Abortable::new(handling, abort_registration).await;
+        let Ok(result) = result else {
+            warn!(nickname = self.nickname, %chain_id, "Block proposal cancelled");
+            // The proposal may have been cancelled while the chain state was being saved.
+            if let Some(chain_states) = &self.chain_states {
+                chain_states.evict(chain_id).await;
+            }
+            return Err(WorkerError::ProposalCancelled);
         };
-        if round.is_fast() {
-            let mut records = outcome.oracle_records.iter();
-            ensure!(
-                records.all(|record| record.responses.is_empty()),
-                WorkerError::FastBlockUsingOracles
-            );
-        }
-        // Check if the counters of tip_state would be valid.
-        chain.tip_state.get().verify_counters(block, &outcome)?;
-        // Verify that the resulting chain would have no unconfirmed incoming messages.
-        chain.validate_incoming_messages().await?;
-        // Reset all the staged changes as we were only validating things.
-        chain.rollback();
-        // Create the vote and store it in the chain state.
-        let manager = chain.manager.get_mut();
-        #[cfg(with_metrics)]
-        let round = proposal.content.round;
-        manager.create_vote(proposal, outcome, self.key_pair(), local_time);
-        // Cache the value we voted on, so the client doesn't have to send it again.
-        if let Some(vote) = manager.pending() {
-            self.cache_validated(&vote.value).await;
-        }
-        let info = ChainInfoResponse::new(&chain, self.key_pair());
-        chain.save().await?;
-        // Trigger any outgoing cross-chain messages that haven't been confirmed yet.
-        let actions = self.create_network_actions(&chain).await?;
-        #[cfg(with_metrics)]
-        NUM_ROUNDS_IN_BLOCK_PROPOSAL
-            .with_label_values(&[round.type_name()])
-            .observe(round.number() as f64);
-        Ok((info, actions))
+        result
     }
 
     // Other fields will be included in handle_certificate's span.
@@ -1208,6 +1998,24 @@ where
         notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
         trace!("{} <-- {:?}", self.nickname, certificate);
+        // Lite certificates are counted here, once their value is found.
+        let _request = self.load.start_request(certificate.value().chain_id());
+        ensure!(!self.read_only, WorkerError::ReadOnlyReplica);
+        // Callers waiting for the delivery of the messages must go through the chain state.
+        let request_key = self
+            .responses
+            .as_ref()
+            .filter(|_| notify_when_messages_are_delivered.is_none())
+            .map(|_| RequestKey::certificate(&certificate));
+        if let Some(response) = self.cached_response(&request_key).await? {
+            return Ok(response);
+        }
+        let _turn = self
+            .schedule(certificate.value().chain_id(), "certificate")
+            .await?;
+        if let Some(executed_block) = certificate.value().executed_block() {
+            self.observe_clock_skew(&executed_block.block);
+        }
         ensure!(
             certificate.value().is_confirmed() || hashed_certificate_values.is_empty(),
             WorkerError::UnneededValue {
@@ -1268,6 +2076,7 @@ where
                     .inc_by(confirmed_transactions);
             }
         }
+        self.cache_response(request_key);
         Ok((info, actions))
     }
 
@@ -1280,37 +2089,44 @@ where
         query: ChainInfoQuery,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
         trace!("{} <-- {:?}", self.nickname, query);
+        let _request = self.load.start_request(query.chain_id);
+        ensure!(
+            !self.read_only || query.is_read_only(),
+            WorkerError::ReadOnlyReplica
+        );
         let chain_id = query.chain_id;
-        let mut chain = self.storage.load_chain(chain_id).await?;
+        let mut chain = self.load_chain(chain_id).await?;
         if query.request_leader_timeout {
-            if let Some(epoch) = chain.execution_state.system.epoch.get() {
+            if let Some(epoch) = *chain.execution_state.system.epoch.get() {
                 let height = chain.tip_state.get().next_block_height;
-                let key_pair = self.key_pair();
+                let key_pair = self.chain_key_pair(&chain);
                 let local_time = self.storage.clock().current_time();
+                let local_time =
+                    local_time.saturating_add_micros(self.clock_skew_tolerance_micros());
                 let manager = chain.manager.get_mut();
-                if manager.vote_timeout(chain_id, height, *epoch, key_pair, local_time) {
+                if manager.vote_timeout(chain_id, height, epoch, key_pair, local_time) {
                     chain.save().await?;
                 }
             }
         }
         if query.request_fallback {
             if let (Some(epoch), Some(entry)) = (
-                chain.execution_state.system.epoch.get(),
+                *chain.execution_state.system.epoch.get(),
                 chain.unskippable.front().await?,
             ) {
                 let ownership = chain.execution_state.system.ownership.get();
                 let elapsed = self.storage.clock().current_time().delta_since(entry.seen);
                 if elapsed >= ownership.timeout_config.fallback_duration {
                     let height = chain.tip_state.get().next_block_height;
-                    let key_pair = self.key_pair();
+                    let key_pair = self.chain_key_pair(&chain);
                     let manager = chain.manager.get_mut();
-                    if manager.vote_fallback(chain_id, height, *epoch, key_pair) {
+                    if manager.vote_fallback(chain_id, height, epoch, key_pair) {
                         chain.save().await?;
                     }
                 }
             }
         }
-        let mut info = ChainInfo::from(&chain);
+        let mut info = ChainInfo::from(&*chain);
         if query.request_committees {
             info.requested_committees = Some(chain.execution_state.system.committees.get().clone());
         }
@@ -1318,6 +2134,10 @@ where
             info.requested_owner_balance =
                 chain.execution_state.system.balances.get(&owner).await?;
         }
+        if let Some(owner) = query.request_owner_balance_proof {
+            info.requested_owner_balance_proof =
+                chain.execution_state.balance_proof(&owner).await?;
+        }
         if let Some(next_block_height) = query.test_next_block_height {
             ensure!(
                 chain.tip_state.get().next_block_height == next_block_height,
@@ -1350,13 +2170,24 @@ where
         }
         if let Some(range) = query.request_sent_certificates_in_range {
             let start: usize = range.start.try_into()?;
-            let end = match range.limit {
-                None => chain.confirmed_log.count(),
+            let count = chain.confirmed_log.count();
+            let requested_end = match range.limit {
+                None => count,
                 Some(limit) => start
                     .checked_add(usize::try_from(limit).map_err(|_| ArithmeticError::Overflow)?)
                     .ok_or(ArithmeticError::Overflow)?
-                    .min(chain.confirmed_log.count()),
+                    .min(count),
             };
+            let max = usize::try_from(self.max_sent_certificates_per_query)
+                .map_err(|_| ArithmeticError::Overflow)?;
+            let end = requested_end.min(start.saturating_add(max));
+            if end < requested_end {
+                let returned = (end - start) as u64;
+                info.requested_sent_certificates_next_range = Some(BlockHeightRange {
+                    start: BlockHeight::from(end as u64),
+                    limit: range.limit.map(|limit| limit - returned),
+                });
+            }
             let keys = chain.confirmed_log.read(start..end).await?;
             let certs = self.storage.read_certificates(keys).await?;
             info.requested_sent_certificates = certs;
@@ -1365,15 +2196,50 @@ where
             let start = usize::try_from(start).map_err(|_| ArithmeticError::Overflow)?;
             info.requested_received_log = chain.received_log.read(start..).await?;
         }
+        if let Some(cursor) = query.request_received_certificates_after {
+            let start = usize::try_from(cursor).map_err(|_| ArithmeticError::Overflow)?;
+            let max = usize::try_from(self.max_sent_certificates_per_query)
+                .map_err(|_| ArithmeticError::Overflow)?;
+            let count = chain.received_certificates.count();
+            let start = start.min(count);
+            let end = count.min(start.saturating_add(max));
+            let hashes = chain.received_certificates.read(start..end).await?;
+            info.received_certificates_cursor = Some(cursor.max(end as u64));
+            info.requested_received_certificates = self.storage.read_certificates(hashes).await?;
+        }
+        if let Some(sequence_number) = query.request_events_from {
+            let start = usize::try_from(sequence_number).map_err(|_| ArithmeticError::Overflow)?;
+            let max = usize::try_from(self.max_sent_certificates_per_query)
+                .map_err(|_| ArithmeticError::Overflow)?;
+            let count = chain.event_log.count();
+            let start = start.min(count);
+            let end = count.min(start.saturating_add(max));
+            info.requested_events = chain.event_log.read(start..end).await?;
+            info.events_cursor = Some(sequence_number.max(end as u64));
+        }
         if let Some(hash) = query.request_hashed_certificate_value {
             info.requested_hashed_certificate_value =
                 Some(self.storage.read_hashed_certificate_value(hash).await?);
         }
+        if query.request_application_storage {
+            info.requested_application_storage =
+                chain.execution_state.application_storage().await?;
+        }
+        if query.request_collected_fees {
+            info.requested_collected_fees = chain.execution_state.system.collected_fees().await?;
+        }
         if query.request_manager_values {
             info.manager.add_values(chain.manager.get());
         }
-        let response = ChainInfoResponse::new(info, self.key_pair());
+        if query.request_manager_status {
+            info.manager.add_status(chain.manager.get());
+        }
+        let response = ChainInfoResponse::new(info, self.chain_key_pair(&chain));
         trace!("{} --> {:?}", self.nickname, response);
+        if self.read_only {
+            // The worker writing the chain sends its cross-chain messages.
+            return Ok((response, NetworkActions::default()));
+        }
         // Trigger any outgoing cross-chain messages that haven't been confirmed yet.
         let actions = self.create_network_actions(&chain).await?;
         Ok((response, actions))
@@ -1388,6 +2254,11 @@ where
         request: CrossChainRequest,
     ) -> Result<NetworkActions, WorkerError> {
         trace!("{} <-- {:?}", self.nickname, request);
+        let _request = self.load.start_request(request.target_chain_id());
+        ensure!(!self.read_only, WorkerError::ReadOnlyReplica);
+        let _turn = self
+            .schedule(request.target_chain_id(), "cross-chain")
+            .await?;
         match request {
             CrossChainRequest::UpdateRecipient {
                 sender,
@@ -1431,7 +2302,7 @@ where
                 recipient,
                 latest_heights,
             } => {
-                let mut chain = self.storage.load_chain(sender).await?;
+                let mut chain = self.load_chain(sender).await?;
                 let mut height_with_fully_delivered_messages = BlockHeight::ZERO;
 
                 for (medium, height) in latest_heights {
@@ -1446,6 +2317,9 @@ where
 
                 // Save the chain state.
                 chain.save().await?;
+                if chain.outbox_counters.get().is_empty() {
+                    self.update_wal_entry(&chain, None).await?;
+                }
 
                 // Handle delivery notifiers for this chain, if any.
                 if let hash_map::Entry::Occupied(mut map) =
@@ -0,0 +1,136 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Counters and gauges reported by user applications.
+//!
+//! Validators expose them in their metrics endpoint, labeled with the application ID. To keep
+//! the cardinality bounded, only a limited number of applications and of metric names per
+//! application are exposed; further updates are dropped.
+
+#[cfg(with_metrics)]
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+#[cfg(with_metrics)]
+use linera_base::{prometheus_util, sync::Lazy};
+#[cfg(with_metrics)]
+use prometheus::{IntCounterVec, IntGaugeVec};
+
+use crate::ExecutionError;
+#[cfg(with_metrics)]
+use crate::UserApplicationId;
+
+#[cfg(test)]
+#[path = "unit_tests/application_metrics_tests.rs"]
+mod tests;
+
+/// The maximal length of an application metric name, in bytes.
+pub const MAX_METRIC_NAME_LENGTH: usize = 64;
+
+/// The maximal number of metric names exposed for each application.
+pub const MAX_METRICS_PER_APPLICATION: usize = 16;
+
+/// The maximal number of applications whose metrics are exposed.
+pub const MAX_APPLICATIONS_WITH_METRICS: usize = 256;
+
+#[cfg(with_metrics)]
+static APPLICATION_COUNTER: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "application_counter",
+        "Counters reported by applications",
+        &["application_id", "name"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static APPLICATION_GAUGE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    prometheus_util::register_int_gauge_vec(
+        "application_gauge",
+        "Gauges reported by applications",
+        &["application_id", "name"],
+    )
+    .expect("Gauge creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static DROPPED_APPLICATION_METRIC_UPDATES: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "dropped_application_metric_updates",
+        "Number of application metric updates dropped because of the cardinality limits",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
+/// The metric names exposed so far, for each application.
+#[cfg(with_metrics)]
+static EXPOSED_METRICS: Lazy<Mutex<HashMap<UserApplicationId, HashSet<String>>>> =
+    Lazy::new(Mutex::default);
+
+/// Checks that `name` is a valid application metric name.
+///
+/// Valid names are non-empty, at most [`MAX_METRIC_NAME_LENGTH`] bytes long, and only contain
+/// ASCII alphanumeric characters and underscores.
+pub fn check_metric_name(name: &str) -> Result<(), ExecutionError> {
+    let is_valid = !name.is_empty()
+        && name.len() <= MAX_METRIC_NAME_LENGTH
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_');
+    if is_valid {
+        Ok(())
+    } else {
+        Err(ExecutionError::InvalidMetricName(name.to_owned()))
+    }
+}
+
+/// Increments the counter `name` of the application by `value`.
+#[cfg(with_metrics)]
+pub(crate) fn increment_counter(application_id: UserApplicationId, name: &str, value: u64) {
+    if let Some(application_id) = exposed_label(application_id, name) {
+        APPLICATION_COUNTER
+            .with_label_values(&[&application_id, name])
+            .inc_by(value);
+    }
+}
+
+/// Sets the gauge `name` of the application to `value`.
+#[cfg(with_metrics)]
+pub(crate) fn set_gauge(application_id: UserApplicationId, name: &str, value: i64) {
+    if let Some(application_id) = exposed_label(application_id, name) {
+        APPLICATION_GAUGE
+            .with_label_values(&[&application_id, name])
+            .set(value);
+    }
+}
+
+/// Returns the application ID label to use, or `None` if the metric is not exposed because of
+/// the cardinality limits.
+#[cfg(with_metrics)]
+fn exposed_label(application_id: UserApplicationId, name: &str) -> Option<String> {
+    let mut exposed_metrics = EXPOSED_METRICS
+        .lock()
+        .expect("application metrics should not be poisoned");
+    if !exposed_metrics.contains_key(&application_id)
+        && exposed_metrics.len() >= MAX_APPLICATIONS_WITH_METRICS
+    {
+        DROPPED_APPLICATION_METRIC_UPDATES
+            .with_label_values(&[])
+            .inc();
+        return None;
+    }
+    let names = exposed_metrics.entry(application_id).or_default();
+    if !names.contains(name) {
+        if names.len() >= MAX_METRICS_PER_APPLICATION {
+            DROPPED_APPLICATION_METRIC_UPDATES
+                .with_label_values(&[])
+                .inc();
+            return None;
+        }
+        names.insert(name.to_owned());
+    }
+    Some(application_id.to_string())
+}
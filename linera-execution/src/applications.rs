@@ -1,12 +1,16 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    iter,
+};
 
 use custom_debug_derive::Debug;
 use linera_base::{
     crypto::CryptoHash,
-    hex_debug,
+    data_types::ArithmeticError,
+    ensure, hex_debug,
     identifiers::{BytecodeId, MessageId},
 };
 use linera_views::{
@@ -48,6 +52,51 @@ pub struct UserApplicationDescription {
     pub parameters: Vec<u8>,
     /// Required dependencies.
     pub required_application_ids: Vec<UserApplicationId>,
+    /// The upgrades of the application's bytecode, in the order they were made. Version `n` of
+    /// the application runs the bytecode of the `n`-th upgrade, and version 0 the original one.
+    pub upgrades: Vec<ApplicationUpgrade>,
+}
+
+impl UserApplicationDescription {
+    /// Returns the latest version of the application, i.e. its number of upgrades.
+    pub fn latest_version(&self) -> u32 {
+        self.upgrades.len() as u32
+    }
+
+    /// Returns the ID and the location of the bytecode that the given version of the
+    /// application runs, if that version exists.
+    pub fn bytecode_at(&self, version: u32) -> Option<(BytecodeId, BytecodeLocation)> {
+        match version.checked_sub(1) {
+            None => Some((self.bytecode_id, self.bytecode_location)),
+            Some(index) => self
+                .upgrades
+                .get(index as usize)
+                .map(|upgrade| (upgrade.bytecode_id, upgrade.bytecode_location)),
+        }
+    }
+
+    /// Returns the locations of the bytecodes of all the versions of the application.
+    pub fn bytecode_locations(&self) -> impl Iterator<Item = BytecodeLocation> + '_ {
+        iter::once(self.bytecode_location).chain(
+            self.upgrades
+                .iter()
+                .map(|upgrade| upgrade.bytecode_location),
+        )
+    }
+}
+
+/// An upgrade of the bytecode of a user application.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Hash, Serialize)]
+pub struct ApplicationUpgrade {
+    /// The unique ID of the new bytecode.
+    pub bytecode_id: BytecodeId,
+    /// The location of the new bytecode.
+    pub bytecode_location: BytecodeLocation,
+    /// The argument passed to the `migrate` entrypoint of the new contract, on every chain
+    /// where the application's state is migrated to this version.
+    #[serde(with = "serde_bytes")]
+    #[debug(with = "hex_debug")]
+    pub migration_argument: Vec<u8>,
 }
 
 impl From<&UserApplicationDescription> for UserApplicationId {
@@ -74,6 +123,12 @@ pub struct ApplicationRegistryView<C> {
     pub published_bytecodes: HashedMapView<C, BytecodeId, BytecodeLocation>,
     /// The applications that are known by the chain.
     pub known_applications: HashedMapView<C, UserApplicationId, UserApplicationDescription>,
+    /// The versions of the applications whose state on this chain was migrated to an upgrade.
+    /// The other applications run their original bytecode.
+    pub versions: HashedMapView<C, UserApplicationId, u32>,
+    /// The versions of the applications that this chain keeps running instead of migrating to
+    /// their latest upgrade.
+    pub pinned_versions: HashedMapView<C, UserApplicationId, u32>,
 }
 
 #[cfg(with_testing)]
@@ -145,7 +200,20 @@ where
             self.describe_application(*required_id).await?;
         }
         let id = UserApplicationId::from(&application);
-        self.known_applications.insert(&id, application)?;
+        match self.known_applications.get(&id).await? {
+            // Descriptions from other chains may not know about the latest upgrades yet.
+            Some(known) if known.upgrades.len() >= application.upgrades.len() => {}
+            Some(_) => self.known_applications.insert(&id, application)?,
+            None => {
+                // The application has no state on this chain yet, so there is nothing to
+                // migrate: it starts with its latest version.
+                let version = application.latest_version();
+                if version > 0 {
+                    self.versions.insert(&id, version)?;
+                }
+                self.known_applications.insert(&id, application)?;
+            }
+        }
         Ok(id)
     }
 
@@ -176,12 +244,116 @@ where
             parameters,
             creation,
             required_application_ids,
+            upgrades: Vec::new(),
         };
         self.known_applications
             .insert(&application_id, description)?;
         Ok(())
     }
 
+    /// Upgrades an application to a published bytecode, and returns the new version.
+    ///
+    /// The state of the application is migrated on each chain the next time the application
+    /// is executed there, unless the chain pinned an earlier version.
+    pub async fn upgrade_application(
+        &mut self,
+        id: UserApplicationId,
+        bytecode_id: BytecodeId,
+        migration_argument: Vec<u8>,
+    ) -> Result<u32, SystemExecutionError> {
+        let mut description = self.describe_application(id).await?;
+        let bytecode_location = self
+            .published_bytecodes
+            .get(&bytecode_id)
+            .await?
+            .ok_or(SystemExecutionError::UnknownBytecodeId(bytecode_id))?;
+        let version = description
+            .latest_version()
+            .checked_add(1)
+            .ok_or(ArithmeticError::Overflow)?;
+        description.upgrades.push(ApplicationUpgrade {
+            bytecode_id,
+            bytecode_location,
+            migration_argument,
+        });
+        self.known_applications.insert(&id, description)?;
+        Ok(version)
+    }
+
+    /// Pins the version of an application that this chain runs, or lets the chain follow the
+    /// upgrades of the application again if `version` is `None`.
+    ///
+    /// The state of the application can't be migrated back, so the version can't be older
+    /// than the one the chain already runs.
+    pub async fn pin_version(
+        &mut self,
+        id: UserApplicationId,
+        version: Option<u32>,
+    ) -> Result<(), SystemExecutionError> {
+        let description = self.describe_application(id).await?;
+        let Some(version) = version else {
+            self.pinned_versions.remove(&id)?;
+            return Ok(());
+        };
+        ensure!(
+            version <= description.latest_version(),
+            SystemExecutionError::UnknownApplicationVersion {
+                application_id: Box::new(id),
+                version,
+            }
+        );
+        let current_version = self.version(&id).await?;
+        ensure!(
+            version >= current_version,
+            SystemExecutionError::ApplicationVersionAlreadyMigrated {
+                application_id: Box::new(id),
+                version,
+                current_version,
+            }
+        );
+        self.pinned_versions.insert(&id, version)?;
+        Ok(())
+    }
+
+    /// Returns the version of the application that runs on this chain.
+    pub async fn version(&self, id: &UserApplicationId) -> Result<u32, SystemExecutionError> {
+        Ok(self.versions.get(id).await?.unwrap_or_default())
+    }
+
+    /// Returns the versions that the state of the application must be migrated to, in order,
+    /// with their migration arguments, before the application is executed on this chain.
+    pub async fn pending_migrations(
+        &self,
+        id: UserApplicationId,
+    ) -> Result<Vec<(u32, Vec<u8>)>, SystemExecutionError> {
+        let description = self.describe_application(id).await?;
+        let latest_version = description.latest_version();
+        let target_version = self
+            .pinned_versions
+            .get(&id)
+            .await?
+            .map_or(latest_version, |version| version.min(latest_version));
+        let current_version = self.version(&id).await?;
+        Ok(description
+            .upgrades
+            .into_iter()
+            .zip(1..)
+            .filter(|(_, version)| *version > current_version && *version <= target_version)
+            .map(|(upgrade, version)| (version, upgrade.migration_argument))
+            .collect())
+    }
+
+    /// Records that the state of the application on this chain was migrated to the given
+    /// version.
+    pub fn set_version(
+        &mut self,
+        id: &UserApplicationId,
+        version: u32,
+    ) -> Result<(), SystemExecutionError> {
+        self.versions.insert(id, version)?;
+        Ok(())
+    }
+
     /// Retrieves an application's description.
     pub async fn describe_application(
         &self,
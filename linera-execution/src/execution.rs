@@ -1,35 +1,43 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    iter,
+};
 
 use futures::{stream::FuturesUnordered, StreamExt, TryStreamExt};
 use linera_base::{
+    crypto::CryptoHash,
     data_types::{Amount, BlockHeight, OracleRecord},
+    ensure,
     identifiers::{Account, ChainId, Destination, Owner},
 };
 use linera_views::{
+    batch::Batch,
     common::Context,
     key_value_store_view::KeyValueStoreView,
+    map_view::HashedMapView,
+    merkle::{FieldsProof, KeyInclusionProof},
     reentrant_collection_view::HashedReentrantCollectionView,
     views::{View, ViewError},
 };
 use linera_views_derive::CryptoHashView;
 #[cfg(with_testing)]
 use {
-    crate::{
-        ResourceControlPolicy, ResourceTracker, TestExecutionRuntimeContext, UserContractCode,
-    },
+    crate::{ResourceTracker, TestExecutionRuntimeContext, UserContractCode},
     linera_views::memory::MemoryContext,
     std::sync::Arc,
 };
 
 use crate::{
-    resources::ResourceController, system::SystemExecutionStateView, ContractSyncRuntime,
-    ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig, ExecutionRuntimeContext, Message,
-    MessageContext, MessageKind, Operation, OperationContext, Query, QueryContext,
-    RawExecutionOutcome, RawOutgoingMessage, Response, ServiceSyncRuntime, SystemMessage,
-    UserApplicationDescription, UserApplicationId,
+    resources::ResourceController,
+    system::{SystemExecutionError, SystemExecutionStateView},
+    Bytecode, ContractSyncRuntime, ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig,
+    ExecutionRuntimeContext, Message, MessageContext, MessageKind, Operation, OperationContext,
+    Query, QueryContext, RawExecutionOutcome, RawOutgoingMessage, ResourceControlPolicy, Response,
+    ServiceSyncRuntime, SystemMessage, SystemOperation, UserApplicationDescription,
+    UserApplicationId,
 };
 
 /// A view accessing the execution state of a chain.
@@ -39,6 +47,8 @@ pub struct ExecutionStateView<C> {
     pub system: SystemExecutionStateView<C>,
     /// User applications.
     pub users: HashedReentrantCollectionView<C, UserApplicationId, KeyValueStoreView<C>>,
+    /// The keys of the user applications to delete before executing the block at each height.
+    pub expiring_keys: HashedMapView<C, BlockHeight, Vec<(UserApplicationId, Vec<u8>)>>,
 }
 
 #[cfg(with_testing)]
@@ -48,19 +58,20 @@ where
     ViewError:
         From<<MemoryContext<TestExecutionRuntimeContext> as linera_views::common::Context>::Error>,
 {
-    /// Simulates the instantiation of an application.
+    /// Simulates the instantiation of an application, and returns the resources it used.
     pub async fn simulate_instantiation(
         &mut self,
         contract: UserContractCode,
         application_description: UserApplicationDescription,
         instantiation_argument: Vec<u8>,
-    ) -> Result<(), ExecutionError> {
+    ) -> Result<ResourceTracker, ExecutionError> {
         let chain_id = application_description.creation.chain_id;
         let context = OperationContext {
             chain_id,
             authenticated_signer: None,
             authenticated_caller_id: None,
             height: application_description.creation.height,
+            previous_block_hash: None,
             index: Some(application_description.creation.index),
             next_message_index: 0,
         };
@@ -96,7 +107,7 @@ where
         )
         .await?;
 
-        Ok(())
+        Ok(resource_controller.tracker)
     }
 }
 
@@ -104,6 +115,7 @@ pub enum UserAction {
     Instantiate(OperationContext, Vec<u8>),
     Operation(OperationContext, Vec<u8>),
     Message(MessageContext, Vec<u8>),
+    Migrate(OperationContext, Vec<u8>),
 }
 
 impl UserAction {
@@ -113,6 +125,7 @@ impl UserAction {
             Instantiate(context, _) => context.authenticated_signer,
             Operation(context, _) => context.authenticated_signer,
             Message(context, _) => context.authenticated_signer,
+            Migrate(context, _) => context.authenticated_signer,
         }
     }
 
@@ -121,6 +134,18 @@ impl UserAction {
             UserAction::Instantiate(context, _) => context.height,
             UserAction::Operation(context, _) => context.height,
             UserAction::Message(context, _) => context.height,
+            UserAction::Migrate(context, _) => context.height,
+        }
+    }
+
+    /// Returns the hash of the certificate of the previous block of the chain, if this is
+    /// not a message, for which the hash of the sending certificate is used instead.
+    pub(crate) fn previous_block_hash(&self) -> Option<CryptoHash> {
+        match self {
+            UserAction::Instantiate(context, _) => context.previous_block_hash,
+            UserAction::Operation(context, _) => context.previous_block_hash,
+            UserAction::Message(_, _) => None,
+            UserAction::Migrate(context, _) => context.previous_block_hash,
         }
     }
 
@@ -129,6 +154,7 @@ impl UserAction {
             UserAction::Instantiate(context, _) => context.next_message_index,
             UserAction::Operation(context, _) => context.next_message_index,
             UserAction::Message(context, _) => context.next_message_index,
+            UserAction::Migrate(context, _) => context.next_message_index,
         }
     }
 }
@@ -150,6 +176,8 @@ where
         oracle_record: Option<OracleRecord>,
         resource_controller: &mut ResourceController<Option<Owner>>,
     ) -> Result<(Vec<ExecutionOutcome>, OracleRecord), ExecutionError> {
+        self.migrate_application(application_id, chain_id, &action, resource_controller)
+            .await?;
         let (execution_outcomes, oracle_record) =
             match self.context().extra().execution_runtime_config() {
                 ExecutionRuntimeConfig::Synchronous => {
@@ -171,6 +199,62 @@ where
         Ok((execution_outcomes, oracle_record))
     }
 
+    /// Migrates the state of an application on this chain to the version it must run, by
+    /// calling the `migrate` entrypoint of each upgrade that wasn't applied yet, before the
+    /// `action` is executed.
+    ///
+    /// Migrations can't use oracles, so that the block is executed the same way by everyone,
+    /// and they can't send messages, so that the indices of the messages of the `action` don't
+    /// change.
+    async fn migrate_application(
+        &mut self,
+        application_id: UserApplicationId,
+        chain_id: ChainId,
+        action: &UserAction,
+        resource_controller: &mut ResourceController<Option<Owner>>,
+    ) -> Result<(), ExecutionError> {
+        let migrations = self
+            .system
+            .registry
+            .pending_migrations(application_id)
+            .await?;
+        for (version, argument) in migrations {
+            self.system.registry.set_version(&application_id, version)?;
+            let context = OperationContext {
+                chain_id,
+                authenticated_signer: None,
+                authenticated_caller_id: None,
+                height: action.height(),
+                previous_block_hash: action.previous_block_hash(),
+                index: None,
+                next_message_index: action.next_message_index(),
+            };
+            let (outcomes, _) = match self.context().extra().execution_runtime_config() {
+                ExecutionRuntimeConfig::Synchronous => {
+                    self.run_user_action_with_synchronous_runtime(
+                        application_id,
+                        chain_id,
+                        UserAction::Migrate(context, argument),
+                        None,
+                        None,
+                        Some(OracleRecord::default()),
+                        resource_controller,
+                    )
+                    .await?
+                }
+            };
+            let sends_messages = outcomes.iter().any(|outcome| match outcome {
+                ExecutionOutcome::System(outcome) => !outcome.messages.is_empty(),
+                ExecutionOutcome::User(_, outcome) => !outcome.messages.is_empty(),
+            });
+            ensure!(
+                !sends_messages,
+                ExecutionError::MigrationSentMessages(Box::new(application_id))
+            );
+        }
+        Ok(())
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn run_user_action_with_synchronous_runtime(
         &mut self,
@@ -192,6 +276,13 @@ where
             tracker: resource_controller.tracker,
             account: initial_balance,
         };
+        // Blocks with recorded oracle responses were already executed by the validators that
+        // signed them, so they are replayed however long they take.
+        let timeout = if cfg!(target_arch = "wasm32") || oracle_record.is_some() {
+            None
+        } else {
+            self.context().extra().contract_limits().timeout
+        };
         let (execution_state_sender, mut execution_state_receiver) =
             futures::channel::mpsc::unbounded();
         let execution_outcomes_future = tokio::task::spawn_blocking(move || {
@@ -203,18 +294,22 @@ where
                 controller,
                 action,
                 oracle_record,
+                timeout,
             )
         });
         while let Some(request) = execution_state_receiver.next().await {
             self.handle_request(request).await?;
         }
-        let (execution_outcomes, oracle_record, controller) = execution_outcomes_future.await??;
-        resource_controller
-            .with_state_and_grant(self, grant)
-            .await?
-            .merge_balance(initial_balance, controller.balance()?)?;
-        resource_controller.tracker = controller.tracker;
-        Ok((execution_outcomes, oracle_record))
+        let (result, controller) = execution_outcomes_future.await?;
+        // A contract that exhausts its sandbox still pays for the resources it used.
+        if matches!(result, Ok(_) | Err(ExecutionError::ResourceExceeded { .. })) {
+            resource_controller
+                .with_state_and_grant(self, grant)
+                .await?
+                .merge_balance(initial_balance, controller.balance()?)?;
+            resource_controller.tracker = controller.tracker;
+        }
+        result
     }
 
     /// Schedules application registration messages when needed.
@@ -262,6 +357,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Simple,
+                    expiration: None,
                     message: SystemMessage::RegisterApplications { applications },
                 })
             })
@@ -279,6 +375,33 @@ where
         Ok(results)
     }
 
+    /// Rejects the bytecodes of a `PublishBytecode` or `PublishBytecodeBlobs` operation if they
+    /// are too large or can't be executed, and compiles them so that they are ready for their
+    /// first use.
+    async fn check_published_bytecodes(
+        &self,
+        contract: &Bytecode,
+        service: &Bytecode,
+        policy: &ResourceControlPolicy,
+    ) -> Result<(), ExecutionError> {
+        for bytecode in [contract, service] {
+            let size = bytecode.as_ref().len() as u64;
+            ensure!(
+                size <= policy.maximum_bytecode_size,
+                SystemExecutionError::BytecodeTooLarge {
+                    size,
+                    maximum: policy.maximum_bytecode_size,
+                }
+            );
+        }
+        #[cfg(with_wasm_runtime)]
+        crate::wasm::validate_bytecodes(contract, service)?;
+        self.context()
+            .extra()
+            .prepare_bytecodes(contract, service)
+            .await
+    }
+
     pub async fn execute_operation(
         &mut self,
         context: OperationContext,
@@ -289,6 +412,28 @@ where
         assert_eq!(context.chain_id, self.context().extra().chain_id());
         match operation {
             Operation::System(op) => {
+                match &op {
+                    SystemOperation::PublishBytecode { contract, service } => {
+                        self.check_published_bytecodes(
+                            contract,
+                            service,
+                            &resource_controller.policy,
+                        )
+                        .await?;
+                    }
+                    SystemOperation::PublishBytecodeBlobs { contract, service } => {
+                        let extra = self.context().extra();
+                        let contract = Bytecode::from(extra.get_blob(*contract).await?);
+                        let service = Bytecode::from(extra.get_blob(*service).await?);
+                        self.check_published_bytecodes(
+                            &contract,
+                            &service,
+                            &resource_controller.policy,
+                        )
+                        .await?;
+                    }
+                    _ => {}
+                }
                 let (mut result, new_application) =
                     self.system.execute_operation(context, op).await?;
                 result.authenticated_signer = context.authenticated_signer;
@@ -341,11 +486,12 @@ where
         assert_eq!(context.chain_id, self.context().extra().chain_id());
         match message {
             Message::System(message) => {
+                let topic_outcome = message.topic_subscription_outcome();
                 let outcome = self.system.execute_message(context, message).await?;
-                Ok((
-                    vec![ExecutionOutcome::System(outcome)],
-                    OracleRecord::default(),
-                ))
+                let outcomes = iter::once(ExecutionOutcome::System(outcome))
+                    .chain(topic_outcome)
+                    .collect();
+                Ok((outcomes, OracleRecord::default()))
             }
             Message::User {
                 application_id,
@@ -385,6 +531,7 @@ where
                     authenticated: true,
                     grant,
                     kind: MessageKind::Bouncing,
+                    expiration: None,
                     message,
                 });
                 Ok(vec![ExecutionOutcome::System(outcome)])
@@ -403,6 +550,7 @@ where
                     authenticated: true,
                     grant,
                     kind: MessageKind::Bouncing,
+                    expiration: None,
                     message: bytes,
                 });
                 Ok(vec![ExecutionOutcome::User(application_id, outcome)])
@@ -423,6 +571,7 @@ where
             authenticated: false,
             grant: Amount::ZERO,
             kind: MessageKind::Tracked,
+            expiration: None,
             message: SystemMessage::Credit {
                 amount,
                 source: context.authenticated_signer,
@@ -465,15 +614,33 @@ where
         context: QueryContext,
         query: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
+        let limits = self.context().extra().query_limits();
         let (execution_state_sender, mut execution_state_receiver) =
             futures::channel::mpsc::unbounded();
         let query_result_future = tokio::task::spawn_blocking(move || {
-            ServiceSyncRuntime::run_query(execution_state_sender, application_id, context, query)
+            ServiceSyncRuntime::run_query(
+                execution_state_sender,
+                application_id,
+                context,
+                query,
+                limits.maximum_fuel,
+            )
         });
-        while let Some(request) = execution_state_receiver.next().await {
-            self.handle_request(request).await?;
+        let query = async {
+            while let Some(request) = execution_state_receiver.next().await {
+                self.handle_request(request).await?;
+            }
+            query_result_future.await?
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = limits.timeout {
+            // If the query times out, the receiver is dropped, so the service's next request to
+            // the runtime fails and the query thread stops.
+            return tokio::time::timeout(timeout, query)
+                .await
+                .map_err(|_| ExecutionError::QueryTimeout(timeout))?;
         }
-        query_result_future.await?
+        query.await
     }
 
     pub async fn list_applications(
@@ -490,4 +657,59 @@ where
         }
         Ok(applications)
     }
+
+    /// Returns the number of bytes stored by each application that has written to this chain.
+    pub async fn application_storage(&self) -> Result<BTreeMap<UserApplicationId, u64>, ViewError> {
+        let ids = self.users.indices().await?;
+        let views = self.users.try_load_entries(&ids).await?;
+        Ok(ids
+            .into_iter()
+            .zip(views)
+            .map(|(id, view)| (id, stored_bytes(&*view)))
+            .collect())
+    }
+
+    /// Deletes the keys of the user applications that expire at `height`. This must be
+    /// called before executing the block at that height, so that all validators delete the
+    /// same keys at the same point.
+    pub async fn delete_expired_keys(&mut self, height: BlockHeight) -> Result<(), ViewError> {
+        let Some(expired_keys) = self.expiring_keys.get(&height).await? else {
+            return Ok(());
+        };
+        self.expiring_keys.remove(&height)?;
+        let mut batches = BTreeMap::<UserApplicationId, Batch>::new();
+        for (id, key) in expired_keys {
+            batches.entry(id).or_default().delete_key(key);
+        }
+        for (id, batch) in batches {
+            let mut view = self.users.try_load_entry_mut(&id).await?;
+            view.write_batch(batch).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns a proof of the balance of `owner`, to be checked against the state hash of a
+    /// block with [`KeyInclusionProof::verify_crypto_hash`] and the type name
+    /// `ExecutionStateView`, or `None` if the owner has no balance.
+    pub async fn balance_proof(
+        &self,
+        owner: &Owner,
+    ) -> Result<Option<KeyInclusionProof>, ViewError> {
+        let path = vec![
+            FieldsProof::new(&self.system, "balances").await?,
+            FieldsProof::new(self, "system").await?,
+        ];
+        self.system.balances.inclusion_proof(owner, path).await
+    }
+}
+
+/// Returns the number of bytes stored in an application's key-value store, counting both keys
+/// and values.
+pub(crate) fn stored_bytes<C>(view: &KeyValueStoreView<C>) -> u64
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+{
+    let size = view.total_size();
+    u64::from(size.key) + u64::from(size.value)
 }
@@ -7,8 +7,8 @@ use std::fmt::{self, Debug, Formatter};
 
 use futures::channel::mpsc;
 use linera_base::{
-    data_types::{Amount, ApplicationPermissions, Timestamp},
-    identifiers::{Account, MessageId, Owner},
+    data_types::{Amount, ApplicationPermissions, BlockHeight, Timestamp},
+    identifiers::{Account, Blob, BlobId, MessageId, Owner},
     ownership::ChainOwnership,
 };
 #[cfg(with_metrics)]
@@ -26,6 +26,7 @@ use oneshot::Sender;
 use prometheus::HistogramVec;
 
 use crate::{
+    execution,
     system::{OpenChainConfig, Recipient, UserData},
     util::RespondExt,
     ExecutionError, ExecutionRuntimeContext, ExecutionStateView, RawExecutionOutcome,
@@ -79,10 +80,11 @@ where
                 #[cfg(with_metrics)]
                 let _latency = LOAD_CONTRACT_LATENCY.measure_latency();
                 let description = self.system.registry.describe_application(id).await?;
+                let version = self.system.registry.version(&id).await?;
                 let code = self
                     .context()
                     .extra()
-                    .get_user_contract(&description)
+                    .get_user_contract(&description, version)
                     .await?;
                 callback.respond((code, description));
             }
@@ -91,10 +93,11 @@ where
                 #[cfg(with_metrics)]
                 let _latency = LOAD_SERVICE_LATENCY.measure_latency();
                 let description = self.system.registry.describe_application(id).await?;
+                let version = self.system.registry.version(&id).await?;
                 let code = self
                     .context()
                     .extra()
-                    .get_user_service(&description)
+                    .get_user_service(&description, version)
                     .await?;
                 callback.respond((code, description));
             }
@@ -225,6 +228,17 @@ where
             } => {
                 let mut view = self.users.try_load_entry_mut(&id).await?;
                 view.write_batch(batch).await?;
+                callback.respond(execution::stored_bytes(&*view));
+            }
+
+            ExpireKey {
+                id,
+                key,
+                height,
+                callback,
+            } => {
+                let keys = self.expiring_keys.get_mut_or_default(&height).await?;
+                keys.push((id, key));
                 callback.respond(());
             }
 
@@ -262,6 +276,11 @@ where
                 }
             }
 
+            ReadBlob { blob_id, callback } => {
+                let blob = self.context().extra().get_blob(blob_id).await?;
+                callback.respond(blob);
+            }
+
             #[cfg(not(target_arch = "wasm32"))]
             FetchUrl { url, callback } => {
                 let bytes = reqwest::get(url).await?.bytes().await?.to_vec();
@@ -366,6 +385,13 @@ pub enum Request {
     WriteBatch {
         id: UserApplicationId,
         batch: Batch,
+        callback: Sender<u64>,
+    },
+
+    ExpireKey {
+        id: UserApplicationId,
+        key: Vec<u8>,
+        height: BlockHeight,
         callback: Sender<()>,
     },
 
@@ -382,6 +408,11 @@ pub enum Request {
         callback: oneshot::Sender<Result<(), ExecutionError>>,
     },
 
+    ReadBlob {
+        blob_id: BlobId,
+        callback: Sender<Blob>,
+    },
+
     #[cfg(not(target_arch = "wasm32"))]
     FetchUrl {
         url: String,
@@ -497,6 +528,15 @@ impl Debug for Request {
                 .field("batch", batch)
                 .finish_non_exhaustive(),
 
+            Request::ExpireKey {
+                id, key, height, ..
+            } => formatter
+                .debug_struct("Request::ExpireKey")
+                .field("id", id)
+                .field("key", key)
+                .field("height", height)
+                .finish_non_exhaustive(),
+
             Request::OpenChain { balance, .. } => formatter
                 .debug_struct("Request::OpenChain")
                 .field("balance", balance)
@@ -507,6 +547,11 @@ impl Debug for Request {
                 .field("application_id", application_id)
                 .finish_non_exhaustive(),
 
+            Request::ReadBlob { blob_id, .. } => formatter
+                .debug_struct("Request::ReadBlob")
+                .field("blob_id", blob_id)
+                .finish_non_exhaustive(),
+
             #[cfg(not(target_arch = "wasm32"))]
             Request::FetchUrl { url, .. } => formatter
                 .debug_struct("Request::FetchUrl")
@@ -3,6 +3,7 @@
 
 //! This module manages the execution of the system application and the user applications in a Linera chain.
 
+pub mod application_metrics;
 mod applications;
 pub mod committee;
 mod execution;
@@ -17,7 +18,7 @@ pub mod test_utils;
 mod util;
 mod wasm;
 
-use std::{fmt, str::FromStr, sync::Arc};
+use std::{fmt, str::FromStr, sync::Arc, time::Duration};
 
 use async_graphql::SimpleObject;
 use async_trait::async_trait;
@@ -26,11 +27,11 @@ use dashmap::DashMap;
 use derive_more::Display;
 use linera_base::{
     abi::Abi,
-    crypto::CryptoHash,
+    crypto::{BcsHashable, CryptoHash},
     data_types::{Amount, ArithmeticError, BlockHeight, Resources, SendMessageRequest, Timestamp},
     doc_scalar, hex_debug,
     identifiers::{
-        Account, ApplicationId, BytecodeId, ChainId, ChannelName, Destination,
+        Account, ApplicationId, Blob, BlobId, BytecodeId, ChainId, ChannelName, Destination,
         GenericApplicationId, MessageId, Owner,
     },
     ownership::ChainOwnership,
@@ -45,15 +46,17 @@ pub use crate::applications::ApplicationRegistry;
 pub use crate::wasm::test as wasm_test;
 #[cfg(with_wasm_runtime)]
 pub use crate::wasm::{
-    ContractEntrypoints, ContractSystemApi, ServiceEntrypoints, ServiceSystemApi, SystemApiData,
-    ViewSystemApi, WasmContractModule, WasmExecutionError, WasmServiceModule,
+    set_wasm_artifact_directory, BytecodeKind, BytecodeValidationError, ContractEntrypoints,
+    ContractSystemApi, ServiceEntrypoints, ServiceSystemApi, SystemApiData, ViewSystemApi,
+    WasmContractModule, WasmExecutionError, WasmServiceModule,
 };
 pub use crate::{
     applications::{
-        ApplicationRegistryView, BytecodeLocation, UserApplicationDescription, UserApplicationId,
+        ApplicationRegistryView, ApplicationUpgrade, BytecodeLocation, UserApplicationDescription,
+        UserApplicationId,
     },
     execution::ExecutionStateView,
-    policy::ResourceControlPolicy,
+    policy::{BlockLimits, ResourceControlPolicy},
     resources::{ResourceController, ResourceTracker},
     runtime::{ContractSyncRuntime, ServiceSyncRuntime},
     system::{
@@ -104,6 +107,9 @@ pub enum ExecutionError {
     #[cfg(any(with_wasmer, with_wasmtime))]
     #[error(transparent)]
     WasmError(#[from] WasmExecutionError),
+    #[cfg(any(with_wasmer, with_wasmtime))]
+    #[error(transparent)]
+    InvalidBytecode(#[from] BytecodeValidationError),
     #[error(transparent)]
     JoinError(#[from] tokio::task::JoinError),
     #[error("The given promise is invalid or was polled once already")]
@@ -111,6 +117,20 @@ pub enum ExecutionError {
 
     #[error("Attempted to perform a reentrant call to application {0}")]
     ReentrantCall(UserApplicationId),
+    #[error(
+        "Application {application_id} would store {stored_bytes} bytes, exceeding its quota of \
+        {maximum} bytes"
+    )]
+    StorageQuotaExceeded {
+        application_id: Box<UserApplicationId>,
+        stored_bytes: u64,
+        maximum: u64,
+    },
+    #[error("Call to application {callee_id} exceeds the maximum call depth of {maximum}")]
+    MaximumCallDepthExceeded {
+        callee_id: Box<UserApplicationId>,
+        maximum: u32,
+    },
     #[error(
         "Application {caller_id} attempted to perform a cross-application to {callee_id} call \
         from `finalize`"
@@ -123,7 +143,20 @@ pub enum ExecutionError {
     ServiceWriteAttempt,
     #[error("Failed to load bytecode from storage {0:?}")]
     ApplicationBytecodeNotFound(Box<UserApplicationDescription>),
-
+    #[error("Blob {0} is not in the storage")]
+    BlobNotFound(BlobId),
+
+    #[error("Maximum fuel per block exceeded")]
+    MaximumFuelExceeded,
+    #[error("Maximum fuel per query exceeded")]
+    MaximumQueryFuelExceeded,
+    #[error("Query exceeded the time limit of {0:?}")]
+    QueryTimeout(Duration),
+    #[error("Contract execution exceeded the limit of {limit} {resource}")]
+    ResourceExceeded {
+        resource: SandboxResource,
+        limit: u64,
+    },
     #[error("Excessive number of bytes read from storage")]
     ExcessiveRead,
     #[error("Excessive number of bytes written to storage")]
@@ -136,6 +169,7 @@ pub enum ExecutionError {
     OwnerIsNone,
     #[error("Application is not authorized to perform system operations on this chain: {0:}")]
     UnauthorizedApplication(UserApplicationId),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("Failed to make network reqwest")]
     ReqwestError(#[from] reqwest::Error),
     #[error("Encountered IO error")]
@@ -146,6 +180,15 @@ pub enum ExecutionError {
     Json(#[from] serde_json::Error),
     #[error("Recorded response for oracle query has the wrong type")]
     OracleResponseMismatch,
+    #[error("Invalid application metric name: {0:?}")]
+    InvalidMetricName(String),
+    #[error("Keys can only expire after the current block height {block_height}, not at {height}")]
+    InvalidKeyExpiry {
+        height: BlockHeight,
+        block_height: BlockHeight,
+    },
+    #[error("The migration of application {0} to a new version attempted to send messages")]
+    MigrationSentMessages(Box<UserApplicationId>),
 }
 
 /// The public entry points provided by the contract part of an application.
@@ -171,6 +214,14 @@ pub trait UserContract {
         message: Vec<u8>,
     ) -> Result<(), ExecutionError>;
 
+    /// Migrates the application state on the current chain after an upgrade of the
+    /// application to this bytecode.
+    fn migrate(
+        &mut self,
+        context: OperationContext,
+        argument: Vec<u8>,
+    ) -> Result<(), ExecutionError>;
+
     /// Finishes execution of the current transaction.
     fn finalize(&mut self, context: FinalizeContext) -> Result<(), ExecutionError>;
 }
@@ -209,6 +260,56 @@ pub enum ExecutionRuntimeConfig {
     Synchronous,
 }
 
+/// Limits on the resources used by a query to an application service, so that expensive queries
+/// can't exhaust the node answering them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct QueryLimits {
+    /// The maximum fuel used by a query, including the queries it makes to other applications.
+    pub maximum_fuel: u64,
+    /// How long a query may run before it is aborted, if limited.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        QueryLimits {
+            maximum_fuel: 10_000_000_000,
+            timeout: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+/// Limits on the resources used by the execution of a transaction by application contracts,
+/// enforced when a block is proposed or validated but not when a confirmed block is replayed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ContractLimits {
+    /// How long a transaction may run before it is aborted, if limited.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for ContractLimits {
+    fn default() -> Self {
+        ContractLimits {
+            timeout: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+/// A resource of the sandbox of application contracts that can be exhausted, making the
+/// execution fail with [`ExecutionError::ResourceExceeded`].
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum SandboxResource {
+    /// The linear memory of a contract, in Wasm pages of 64 KiB.
+    #[display(fmt = "memory pages")]
+    Memory,
+    /// The stack used by the nested Wasm function calls of a contract, in bytes.
+    #[display(fmt = "bytes of stack")]
+    Stack,
+    /// The wall-clock time of the transaction, in milliseconds.
+    #[display(fmt = "milliseconds")]
+    Time,
+}
+
 /// Requirements for the `extra` field in our state views (and notably the
 /// [`ExecutionStateView`]).
 #[async_trait]
@@ -217,19 +318,38 @@ pub trait ExecutionRuntimeContext {
 
     fn execution_runtime_config(&self) -> ExecutionRuntimeConfig;
 
+    fn query_limits(&self) -> QueryLimits;
+
+    fn contract_limits(&self) -> ContractLimits;
+
     fn user_contracts(&self) -> &Arc<DashMap<UserApplicationId, UserContractCode>>;
 
     fn user_services(&self) -> &Arc<DashMap<UserApplicationId, UserServiceCode>>;
 
+    /// Returns the code of the contract of the given `version` of an application.
     async fn get_user_contract(
         &self,
         description: &UserApplicationDescription,
+        version: u32,
     ) -> Result<UserContractCode, ExecutionError>;
 
+    /// Returns the code of the service of the given `version` of an application.
     async fn get_user_service(
         &self,
         description: &UserApplicationDescription,
+        version: u32,
     ) -> Result<UserServiceCode, ExecutionError>;
+
+    /// Compiles the bytecodes of a newly published application ahead of their first use.
+    async fn prepare_bytecodes(
+        &self,
+        contract: &Bytecode,
+        service: &Bytecode,
+    ) -> Result<(), ExecutionError>;
+
+    /// Returns the blob with the given ID, or [`ExecutionError::BlobNotFound`] if it is not
+    /// in the storage.
+    async fn get_blob(&self, blob_id: BlobId) -> Result<Blob, ExecutionError>;
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -243,6 +363,8 @@ pub struct OperationContext {
     pub authenticated_caller_id: Option<UserApplicationId>,
     /// The current block height.
     pub height: BlockHeight,
+    /// The hash of the certificate of the previous block of the chain, if any.
+    pub previous_block_hash: Option<CryptoHash>,
     /// The current index of the operation.
     pub index: Option<u32>,
     /// The index of the next message to be created.
@@ -428,6 +550,9 @@ pub trait BaseRuntime {
     /// Makes a GET request to the given URL and returns the JSON part, if any.
     #[cfg(not(target_arch = "wasm32"))]
     fn fetch_json(&mut self, url: &str) -> Result<String, ExecutionError>;
+
+    /// Reads the content of the blob with the given ID.
+    fn read_blob(&mut self, blob_id: BlobId) -> Result<Vec<u8>, ExecutionError>;
 }
 
 pub trait ServiceRuntime: BaseRuntime {
@@ -441,6 +566,12 @@ pub trait ServiceRuntime: BaseRuntime {
     /// Fetches blob of bytes from an arbitrary URL.
     #[cfg(not(target_arch = "wasm32"))]
     fn fetch_url(&mut self, url: &str) -> Result<Vec<u8>, ExecutionError>;
+
+    /// Returns the amount of execution fuel remaining before the query is aborted.
+    fn remaining_fuel(&mut self) -> Result<u64, ExecutionError>;
+
+    /// Consumes some of the execution fuel of the query.
+    fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError>;
 }
 
 pub trait ContractRuntime: BaseRuntime {
@@ -464,6 +595,23 @@ pub trait ContractRuntime: BaseRuntime {
     /// Consumes some of the execution fuel.
     fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError>;
 
+    /// Consumes the execution fuel charged for a call to the system API.
+    fn track_host_call(&mut self) -> Result<(), ExecutionError>;
+
+    /// Returns how long the transaction may still run before it is aborted, if limited.
+    fn remaining_time(&mut self) -> Result<Option<Duration>, ExecutionError>;
+
+    /// Returns a new pseudo-random value. The values are derived deterministically from the
+    /// current transaction and application, so that all the validators agree on them, but
+    /// they are not secret: anyone can compute them before the transaction is executed.
+    fn random_value(&mut self) -> Result<CryptoHash, ExecutionError>;
+
+    /// Increments the application's counter `name` by `value`.
+    fn increment_counter(&mut self, name: String, value: u64) -> Result<(), ExecutionError>;
+
+    /// Sets the application's gauge `name` to `value`.
+    fn set_gauge(&mut self, name: String, value: i64) -> Result<(), ExecutionError>;
+
     /// Schedules a message to be sent.
     fn send_message(&mut self, message: SendMessageRequest<Vec<u8>>) -> Result<(), ExecutionError>;
 
@@ -473,6 +621,29 @@ pub trait ContractRuntime: BaseRuntime {
     /// Schedules to unsubscribe to some `channel` on a `chain`.
     fn unsubscribe(&mut self, chain: ChainId, channel: ChannelName) -> Result<(), ExecutionError>;
 
+    /// Emits an event with the given `key` and `value`, to be recorded in the block for
+    /// indexers.
+    fn emit(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<(), ExecutionError>;
+
+    /// Publishes a `payload` to a `topic` of the current application, to be delivered as a
+    /// message to the same application on the chains subscribed to it.
+    fn publish_to_topic(&mut self, topic: String, payload: Vec<u8>) -> Result<(), ExecutionError>;
+
+    /// Schedules to subscribe to a `topic` of the current application on a `chain`.
+    fn subscribe_to_topic(&mut self, chain: ChainId, topic: String) -> Result<(), ExecutionError>;
+
+    /// Schedules to unsubscribe from a `topic` of the current application on a `chain`.
+    fn unsubscribe_from_topic(
+        &mut self,
+        chain: ChainId,
+        topic: String,
+    ) -> Result<(), ExecutionError>;
+
+    /// Marks the `key` of the application's state as ephemeral: it is deleted before
+    /// executing the block of this chain at `height`, which must be later than the current
+    /// block.
+    fn expire_key(&mut self, key: Vec<u8>, height: BlockHeight) -> Result<(), ExecutionError>;
+
     /// Transfers amount from source to destination.
     fn transfer(
         &mut self,
@@ -579,6 +750,8 @@ pub struct RawOutgoingMessage<Message, Grant = Resources> {
     pub grant: Grant,
     /// The kind of outgoing message being sent.
     pub kind: MessageKind,
+    /// The time after which the message can no longer be executed, if any.
+    pub expiration: Option<Timestamp>,
     /// The message itself.
     pub message: Message,
 }
@@ -590,6 +763,7 @@ impl<Message> From<SendMessageRequest<Message>> for RawOutgoingMessage<Message,
             authenticated,
             grant,
             is_tracked,
+            expiration,
             message,
         } = request;
 
@@ -604,6 +778,7 @@ impl<Message> From<SendMessageRequest<Message>> for RawOutgoingMessage<Message,
             authenticated,
             grant,
             kind,
+            expiration,
             message,
         }
     }
@@ -640,6 +815,8 @@ pub struct RawExecutionOutcome<Message, Grant = Resources> {
     pub subscribe: Vec<(ChannelName, ChainId)>,
     /// Unsubscribe chains to channels.
     pub unsubscribe: Vec<(ChannelName, ChainId)>,
+    /// Events emitted for indexers, as keys and values.
+    pub events: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 /// The identifier of a channel, relative to a particular application.
@@ -687,6 +864,12 @@ impl<Message, Grant> RawExecutionOutcome<Message, Grant> {
         self.messages.push(message);
         self
     }
+
+    /// Adds an event with the given `key` and `value` to this [`RawExecutionOutcome`].
+    pub fn with_event(mut self, key: Vec<u8>, value: Vec<u8>) -> Self {
+        self.events.push((key, value));
+        self
+    }
 }
 
 impl<Message, Grant> Default for RawExecutionOutcome<Message, Grant> {
@@ -697,6 +880,7 @@ impl<Message, Grant> Default for RawExecutionOutcome<Message, Grant> {
             messages: Vec::new(),
             subscribe: Vec::new(),
             unsubscribe: Vec::new(),
+            events: Vec::new(),
         }
     }
 }
@@ -711,6 +895,7 @@ impl<Message> RawOutgoingMessage<Message, Resources> {
             authenticated,
             grant,
             kind,
+            expiration,
             message,
         } = self;
         Ok(RawOutgoingMessage {
@@ -718,6 +903,7 @@ impl<Message> RawOutgoingMessage<Message, Resources> {
             authenticated,
             grant: policy.total_price(&grant)?,
             kind,
+            expiration,
             message,
         })
     }
@@ -734,6 +920,7 @@ impl<Message> RawExecutionOutcome<Message, Resources> {
             messages,
             subscribe,
             unsubscribe,
+            events,
         } = self;
         let messages = messages
             .into_iter()
@@ -745,6 +932,7 @@ impl<Message> RawExecutionOutcome<Message, Resources> {
             messages,
             subscribe,
             unsubscribe,
+            events,
         })
     }
 }
@@ -771,8 +959,11 @@ impl OperationContext {
 pub struct TestExecutionRuntimeContext {
     chain_id: ChainId,
     execution_runtime_config: ExecutionRuntimeConfig,
+    query_limits: QueryLimits,
+    contract_limits: ContractLimits,
     user_contracts: Arc<DashMap<UserApplicationId, UserContractCode>>,
     user_services: Arc<DashMap<UserApplicationId, UserServiceCode>>,
+    blobs: Arc<DashMap<BlobId, Blob>>,
 }
 
 #[cfg(with_testing)]
@@ -781,10 +972,30 @@ impl TestExecutionRuntimeContext {
         Self {
             chain_id,
             execution_runtime_config,
+            query_limits: QueryLimits::default(),
+            contract_limits: ContractLimits::default(),
             user_contracts: Arc::default(),
             user_services: Arc::default(),
+            blobs: Arc::default(),
         }
     }
+
+    /// Sets the limits on the queries to application services.
+    pub fn with_query_limits(mut self, query_limits: QueryLimits) -> Self {
+        self.query_limits = query_limits;
+        self
+    }
+
+    /// Sets the limits on the transactions executed by application contracts.
+    pub fn with_contract_limits(mut self, contract_limits: ContractLimits) -> Self {
+        self.contract_limits = contract_limits;
+        self
+    }
+
+    /// Makes a blob available to the applications.
+    pub fn add_blob(&self, blob: Blob) {
+        self.blobs.insert(blob.id(), blob);
+    }
 }
 
 #[cfg(with_testing)]
@@ -798,6 +1009,14 @@ impl ExecutionRuntimeContext for TestExecutionRuntimeContext {
         self.execution_runtime_config
     }
 
+    fn query_limits(&self) -> QueryLimits {
+        self.query_limits
+    }
+
+    fn contract_limits(&self) -> ContractLimits {
+        self.contract_limits
+    }
+
     fn user_contracts(&self) -> &Arc<DashMap<UserApplicationId, UserContractCode>> {
         &self.user_contracts
     }
@@ -809,6 +1028,7 @@ impl ExecutionRuntimeContext for TestExecutionRuntimeContext {
     async fn get_user_contract(
         &self,
         description: &UserApplicationDescription,
+        _version: u32,
     ) -> Result<UserContractCode, ExecutionError> {
         let application_id = description.into();
         Ok(self
@@ -823,6 +1043,7 @@ impl ExecutionRuntimeContext for TestExecutionRuntimeContext {
     async fn get_user_service(
         &self,
         description: &UserApplicationDescription,
+        _version: u32,
     ) -> Result<UserServiceCode, ExecutionError> {
         let application_id = description.into();
         Ok(self
@@ -833,6 +1054,22 @@ impl ExecutionRuntimeContext for TestExecutionRuntimeContext {
             })?
             .clone())
     }
+
+    async fn prepare_bytecodes(
+        &self,
+        _contract: &Bytecode,
+        _service: &Bytecode,
+    ) -> Result<(), ExecutionError> {
+        Ok(())
+    }
+
+    async fn get_blob(&self, blob_id: BlobId) -> Result<Blob, ExecutionError> {
+        Ok(self
+            .blobs
+            .get(&blob_id)
+            .ok_or(ExecutionError::BlobNotFound(blob_id))?
+            .clone())
+    }
 }
 
 impl From<SystemOperation> for Operation {
@@ -949,8 +1186,7 @@ pub struct Bytecode {
 
 impl Bytecode {
     /// Creates a new [`Bytecode`] instance using the provided `bytes`.
-    #[allow(dead_code)]
-    pub(crate) fn new(bytes: Vec<u8>) -> Self {
+    pub fn new(bytes: Vec<u8>) -> Self {
         Bytecode { bytes }
     }
 
@@ -962,6 +1198,20 @@ impl Bytecode {
     }
 }
 
+impl BcsHashable for Bytecode {}
+
+impl From<Blob> for Bytecode {
+    fn from(blob: Blob) -> Self {
+        Bytecode::new(blob.into_bytes())
+    }
+}
+
+impl From<Bytecode> for Blob {
+    fn from(bytecode: Bytecode) -> Self {
+        Blob::new(bytecode.bytes)
+    }
+}
+
 impl AsRef<[u8]> for Bytecode {
     fn as_ref(&self) -> &[u8] {
         self.bytes.as_ref()
@@ -975,6 +1225,9 @@ impl std::fmt::Debug for Bytecode {
 }
 
 /// The runtime to use for running the application.
+///
+/// Applications consume the same amount of fuel in all the runtimes with a sanitizer, so
+/// validators using different runtimes must use these variants to agree on the fees.
 #[derive(Clone, Copy, Display)]
 #[cfg_attr(with_wasm_runtime, derive(Debug, Default))]
 pub enum WasmRuntime {
@@ -987,8 +1240,10 @@ pub enum WasmRuntime {
     #[display(fmt = "wasmtime")]
     Wasmtime,
     #[cfg(with_wasmer)]
+    #[display(fmt = "wasmer-with-sanitizer")]
     WasmerWithSanitizer,
     #[cfg(with_wasmtime)]
+    #[display(fmt = "wasmtime-with-sanitizer")]
     WasmtimeWithSanitizer,
 }
 
@@ -1045,6 +1300,10 @@ impl FromStr for WasmRuntime {
             "wasmer" => Ok(WasmRuntime::Wasmer),
             #[cfg(with_wasmtime)]
             "wasmtime" => Ok(WasmRuntime::Wasmtime),
+            #[cfg(with_wasmer)]
+            "wasmer-with-sanitizer" => Ok(WasmRuntime::WasmerWithSanitizer),
+            #[cfg(with_wasmtime)]
+            "wasmtime-with-sanitizer" => Ok(WasmRuntime::WasmtimeWithSanitizer),
             unknown => Err(InvalidWasmRuntime(unknown.to_owned())),
         }
     }
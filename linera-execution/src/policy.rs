@@ -33,13 +33,28 @@ pub struct ResourceControlPolicy {
     pub message: Amount,
     /// The additional price for each byte in the argument of a user message.
     pub message_byte: Amount,
+    /// The fuel charged for each call from an application to the system API, on top of the
+    /// fuel of its instructions.
+    pub host_call_fuel: u64,
 
-    // TODO(#1538): Cap the number of transactions per block and the total size of their
-    // arguments.
+    /// The maximum fuel used per block
+    pub maximum_fuel_per_block: u64,
     /// The maximum data to read per block
     pub maximum_bytes_read_per_block: u64,
     /// The maximum data to write per block
     pub maximum_bytes_written_per_block: u64,
+    /// The maximum size of a published contract or service bytecode
+    pub maximum_bytecode_size: u64,
+    /// The maximum depth of nested calls between applications
+    pub maximum_call_depth: u32,
+    /// The maximum number of bytes each application can store on a chain
+    pub maximum_bytes_stored_per_application: u64,
+    /// The maximum number of operations in a block
+    pub maximum_operations_per_block: u64,
+    /// The maximum number of incoming messages in a block
+    pub maximum_incoming_messages_per_block: u64,
+    /// The maximum size of a block, in bytes
+    pub maximum_block_size: u64,
 }
 
 impl Default for ResourceControlPolicy {
@@ -56,17 +71,51 @@ impl Default for ResourceControlPolicy {
             operation_byte: Amount::default(),
             message: Amount::default(),
             message_byte: Amount::default(),
+            host_call_fuel: 0,
+            maximum_fuel_per_block: u64::MAX,
             maximum_bytes_read_per_block: u64::MAX,
             maximum_bytes_written_per_block: u64::MAX,
+            maximum_bytecode_size: u64::MAX,
+            maximum_call_depth: u32::MAX,
+            maximum_bytes_stored_per_application: u64::MAX,
+            maximum_operations_per_block: u64::MAX,
+            maximum_incoming_messages_per_block: u64::MAX,
+            maximum_block_size: u64::MAX,
         }
     }
 }
 
+/// The limits on the contents of a block, which clients must respect when proposing one.
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BlockLimits {
+    /// The maximum number of operations in a block
+    pub maximum_operations: u64,
+    /// The maximum number of incoming messages in a block
+    pub maximum_incoming_messages: u64,
+    /// The maximum size of a block, in bytes
+    pub maximum_size: u64,
+}
+
+impl Default for BlockLimits {
+    fn default() -> Self {
+        ResourceControlPolicy::default().block_limits()
+    }
+}
+
 impl ResourceControlPolicy {
     pub fn block_price(&self) -> Amount {
         self.block
     }
 
+    /// Returns the limits on the contents of a block.
+    pub fn block_limits(&self) -> BlockLimits {
+        BlockLimits {
+            maximum_operations: self.maximum_operations_per_block,
+            maximum_incoming_messages: self.maximum_incoming_messages_per_block,
+            maximum_size: self.maximum_block_size,
+        }
+    }
+
     pub fn total_price(&self, resources: &Resources) -> Result<Amount, ArithmeticError> {
         let mut amount = Amount::ZERO;
         amount.try_add_assign(self.fuel_price(resources.fuel)?)?;
@@ -114,9 +163,11 @@ impl ResourceControlPolicy {
         self.fuel_unit.try_mul(u128::from(fuel))
     }
 
-    /// Returns how much fuel can be paid with the given balance.
-    pub(crate) fn remaining_fuel(&self, balance: Amount) -> u64 {
-        u64::try_from(balance.saturating_div(self.fuel_unit)).unwrap_or(u64::MAX)
+    /// Returns how much fuel can be paid with the given balance, if `fuel_used` was already
+    /// used in the block.
+    pub(crate) fn remaining_fuel(&self, balance: Amount, fuel_used: u64) -> u64 {
+        let affordable = u64::try_from(balance.saturating_div(self.fuel_unit)).unwrap_or(u64::MAX);
+        affordable.min(self.maximum_fuel_per_block.saturating_sub(fuel_used))
     }
 }
 
@@ -174,8 +225,16 @@ impl ResourceControlPolicy {
             operation_byte: Amount::from_nanos(10),
             operation: Amount::from_micros(10),
             message: Amount::from_micros(10),
+            host_call_fuel: 0,
+            maximum_fuel_per_block: 10_000_000_000,
             maximum_bytes_read_per_block: 100_000_000,
             maximum_bytes_written_per_block: 10_000_000,
+            maximum_bytecode_size: 10_000_000,
+            maximum_call_depth: 64,
+            maximum_bytes_stored_per_application: 100_000_000,
+            maximum_operations_per_block: 1_000,
+            maximum_incoming_messages_per_block: 1_000,
+            maximum_block_size: 10_000_000,
         }
     }
 }
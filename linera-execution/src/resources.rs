@@ -13,8 +13,8 @@ use linera_base::{
 use linera_views::{common::Context, views::ViewError};
 
 use crate::{
-    system::SystemExecutionError, ExecutionError, ExecutionStateView, Message, Operation,
-    ResourceControlPolicy,
+    system::{FeeSource, SystemExecutionError},
+    ExecutionError, ExecutionStateView, Message, Operation, ResourceControlPolicy,
 };
 
 #[derive(Clone, Debug, Default)]
@@ -54,6 +54,8 @@ pub struct ResourceTracker {
     pub message_bytes: u64,
     /// The amount allocated to message grants.
     pub grants: Amount,
+    /// The fees paid so far, not counting the grants.
+    pub fees: Amount,
 }
 
 /// How to access the balance of an account.
@@ -103,10 +105,19 @@ where
         Ok(())
     }
 
-    /// Obtains the amount of fuel that could be spent by consuming the entire balance.
-    pub(crate) fn remaining_fuel(&self) -> u64 {
+    /// Charges fees to the account and counts them as fees paid.
+    fn charge(&mut self, fees: Amount) -> Result<(), ExecutionError> {
+        self.update_balance(fees)?;
+        self.tracker.as_mut().fees.try_add_assign(fees)?;
+        Ok(())
+    }
+
+    /// Obtains the amount of fuel that could be spent by consuming the entire balance,
+    /// without exceeding the block's fuel limit.
+    pub(crate) fn remaining_fuel(&mut self) -> u64 {
+        let fuel_used = self.tracker.as_mut().fuel;
         self.policy
-            .remaining_fuel(self.balance().unwrap_or(Amount::MAX))
+            .remaining_fuel(self.balance().unwrap_or(Amount::MAX), fuel_used)
     }
 
     /// Tracks the allocation of a grant.
@@ -123,7 +134,7 @@ where
             .blocks
             .checked_add(1)
             .ok_or(ArithmeticError::Overflow)?;
-        self.update_balance(self.policy.block)
+        self.charge(self.policy.block)
     }
 
     /// Tracks the execution of an operation in block.
@@ -134,7 +145,7 @@ where
             .operations
             .checked_add(1)
             .ok_or(ArithmeticError::Overflow)?;
-        self.update_balance(self.policy.operation)?;
+        self.charge(self.policy.operation)?;
         match operation {
             Operation::System(_) => Ok(()),
             Operation::User { bytes, .. } => {
@@ -145,7 +156,7 @@ where
                     .operation_bytes
                     .checked_add(size as u64)
                     .ok_or(ArithmeticError::Overflow)?;
-                self.update_balance(self.policy.operation_bytes_price(size as u64)?)?;
+                self.charge(self.policy.operation_bytes_price(size as u64)?)?;
                 Ok(())
             }
         }
@@ -159,7 +170,7 @@ where
             .messages
             .checked_add(1)
             .ok_or(ArithmeticError::Overflow)?;
-        self.update_balance(self.policy.message)?;
+        self.charge(self.policy.message)?;
         match message {
             Message::System(_) => Ok(()),
             Message::User { bytes, .. } => {
@@ -170,7 +181,7 @@ where
                     .message_bytes
                     .checked_add(size as u64)
                     .ok_or(ArithmeticError::Overflow)?;
-                self.update_balance(self.policy.message_bytes_price(size as u64)?)?;
+                self.charge(self.policy.message_bytes_price(size as u64)?)?;
                 Ok(())
             }
         }
@@ -184,7 +195,18 @@ where
             .fuel
             .checked_add(fuel)
             .ok_or(ArithmeticError::Overflow)?;
-        self.update_balance(self.policy.fuel_price(fuel)?)
+        if self.tracker.as_mut().fuel > self.policy.maximum_fuel_per_block {
+            return Err(ExecutionError::MaximumFuelExceeded);
+        }
+        self.charge(self.policy.fuel_price(fuel)?)
+    }
+
+    /// Tracks a call from an application to the system API.
+    pub(crate) fn track_host_call(&mut self) -> Result<(), ExecutionError> {
+        if self.policy.host_call_fuel == 0 {
+            return Ok(());
+        }
+        self.track_fuel(self.policy.host_call_fuel)
     }
 
     /// Tracks a read operation.
@@ -195,7 +217,7 @@ where
             .read_operations
             .checked_add(count)
             .ok_or(ArithmeticError::Overflow)?;
-        self.update_balance(self.policy.read_operations_price(count)?)
+        self.charge(self.policy.read_operations_price(count)?)
     }
 
     /// Tracks a write operation.
@@ -206,7 +228,7 @@ where
             .write_operations
             .checked_add(count)
             .ok_or(ArithmeticError::Overflow)?;
-        self.update_balance(self.policy.write_operations_price(count)?)
+        self.charge(self.policy.write_operations_price(count)?)
     }
 
     /// Tracks a number of bytes read.
@@ -220,7 +242,7 @@ where
         if self.tracker.as_mut().bytes_read >= self.policy.maximum_bytes_read_per_block {
             return Err(ExecutionError::ExcessiveRead);
         }
-        self.update_balance(self.policy.bytes_read_price(count)?)?;
+        self.charge(self.policy.bytes_read_price(count)?)?;
         Ok(())
     }
 
@@ -235,7 +257,7 @@ where
         if self.tracker.as_mut().bytes_written >= self.policy.maximum_bytes_written_per_block {
             return Err(ExecutionError::ExcessiveWrite);
         }
-        self.update_balance(self.policy.bytes_written_price(count)?)?;
+        self.charge(self.policy.bytes_written_price(count)?)?;
         Ok(())
     }
 
@@ -340,17 +362,22 @@ impl ResourceController<Option<Owner>, ResourceTracker> {
         ViewError: From<C::Error>,
     {
         let mut sources = Vec::new();
+        // If the chain is configured so, the signer pays instead of the chain account.
+        let signer_pays =
+            *view.system.fee_source.get() == FeeSource::Proposer && self.account.is_some();
         // First, use the grant (e.g. for messages) and otherwise use the chain account
         // (e.g. for blocks and operations).
         if let Some(grant) = grant {
             sources.push(grant);
-        } else {
+        } else if !signer_pays {
             sources.push(view.system.balance.get_mut());
         }
         // Then the local account, if any. Currently, any negative fee (e.g. storage
         // refund) goes preferably to this account.
         if let Some(owner) = &self.account {
-            if let Some(balance) = view.system.balances.get_mut(owner).await? {
+            if signer_pays {
+                sources.push(view.system.balances.get_mut_or_default(owner).await?);
+            } else if let Some(balance) = view.system.balances.get_mut(owner).await? {
                 sources.push(balance);
             }
         }
@@ -5,29 +5,35 @@ use std::{
     collections::{hash_map, BTreeMap, HashMap, HashSet},
     mem,
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
     vec,
 };
 
 use custom_debug_derive::Debug;
 use linera_base::{
+    crypto::{BcsHashable, CryptoHash},
     data_types::{
         Amount, ApplicationPermissions, ArithmeticError, BlockHeight, OracleRecord, OracleResponse,
         Resources, SendMessageRequest, Timestamp,
     },
     ensure,
-    identifiers::{Account, ApplicationId, ChainId, ChannelName, MessageId, Owner},
+    identifiers::{Account, ApplicationId, BlobId, ChainId, ChannelName, MessageId, Owner},
     ownership::ChainOwnership,
 };
 use linera_views::batch::Batch;
 use oneshot::Receiver;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    application_metrics,
     execution::UserAction,
     execution_state_actor::{ExecutionStateSender, Request},
     resources::ResourceController,
+    system,
     util::{ReceiverExt, UnboundedSenderExt},
-    BaseRuntime, ContractRuntime, ExecutionError, ExecutionOutcome, FinalizeContext,
-    MessageContext, OperationContext, RawExecutionOutcome, ServiceRuntime,
+    BaseRuntime, ContractRuntime, Destination, ExecutionError, ExecutionOutcome, FinalizeContext,
+    MessageContext, MessageKind, OperationContext, QueryLimits, RawExecutionOutcome,
+    RawOutgoingMessage, ResourceControlPolicy, SandboxResource, ServiceRuntime,
     UserApplicationDescription, UserApplicationId, UserContractInstance, UserServiceInstance,
 };
 
@@ -60,6 +66,8 @@ pub struct SyncRuntimeInternal<UserInstance> {
     /// The height of the next block that will be added to this chain. During operations
     /// and messages, this is the current block height.
     height: BlockHeight,
+    /// The hash of the certificate of the previous block of this chain, if any and if known.
+    previous_block_hash: Option<CryptoHash>,
     /// The authenticated signer of the operation or message, if any.
     authenticated_signer: Option<Owner>,
     /// The current message being executed, if there is one.
@@ -95,6 +103,16 @@ pub struct SyncRuntimeInternal<UserInstance> {
     refund_grant_to: Option<Account>,
     /// Controller to track fuel and storage consumption.
     resource_controller: ResourceController,
+    /// When the transaction must be aborted, with the time limit it was computed from, if
+    /// limited.
+    deadline: Option<(Instant, Duration)>,
+    /// The first resource of the sandbox that a contract exhausted, with its limit, if any.
+    exceeded_resource: Option<(SandboxResource, u64)>,
+    /// The hash from which the pseudo-random values of the transaction are derived, if it is
+    /// a transaction.
+    random_seed: Option<CryptoHash>,
+    /// The number of pseudo-random values returned so far in the transaction.
+    random_value_count: u64,
 }
 
 impl<UserInstance> SyncRuntimeInternal<UserInstance> {
@@ -279,6 +297,7 @@ impl<UserInstance> SyncRuntimeInternal<UserInstance> {
         Self {
             chain_id,
             height,
+            previous_block_hash: None,
             authenticated_signer,
             next_message_index,
             executing_message,
@@ -293,6 +312,10 @@ impl<UserInstance> SyncRuntimeInternal<UserInstance> {
             refund_grant_to,
             resource_controller,
             oracle_responses,
+            deadline: None,
+            exceeded_resource: None,
+            random_seed: None,
+            random_value_count: 0,
         }
     }
 
@@ -359,6 +382,55 @@ impl<UserInstance> SyncRuntimeInternal<UserInstance> {
         );
         Ok(())
     }
+
+    /// Ensures that a call to `application_id` doesn't nest deeper than the policy allows.
+    ///
+    /// The application that is executing the operation or message counts as the first level.
+    fn check_call_depth(
+        &mut self,
+        application_id: UserApplicationId,
+    ) -> Result<(), ExecutionError> {
+        let maximum = self.resource_controller.policy.maximum_call_depth;
+        ensure!(
+            self.call_stack.len() < maximum as usize,
+            ExecutionError::MaximumCallDepthExceeded {
+                callee_id: Box::new(application_id),
+                maximum,
+            }
+        );
+        Ok(())
+    }
+
+    /// Ensures that the transaction didn't run for longer than its time limit.
+    fn check_deadline(&mut self) -> Result<(), ExecutionError> {
+        if let Some((deadline, timeout)) = self.deadline {
+            if Instant::now() >= deadline {
+                let limit = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+                return Err(
+                    self.record_exceeded_resource(ExecutionError::ResourceExceeded {
+                        resource: SandboxResource::Time,
+                        limit,
+                    }),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Remembers the first resource of the sandbox that a contract exhausted, and reports it
+    /// instead of the `error` of any call that failed after it.
+    ///
+    /// The applications up the call stack then fail with the same error, whatever their Wasm
+    /// runtime reports for their failed call to the system API.
+    fn record_exceeded_resource(&mut self, error: ExecutionError) -> ExecutionError {
+        if let ExecutionError::ResourceExceeded { resource, limit } = error {
+            self.exceeded_resource.get_or_insert((resource, limit));
+        }
+        match self.exceeded_resource {
+            Some((resource, limit)) => ExecutionError::ResourceExceeded { resource, limit },
+            None => error,
+        }
+    }
 }
 
 impl SyncRuntimeInternal<UserContractInstance> {
@@ -394,6 +466,7 @@ impl SyncRuntimeInternal<UserContractInstance> {
         callee_id: UserApplicationId,
     ) -> Result<(Arc<Mutex<UserContractInstance>>, OperationContext), ExecutionError> {
         self.check_for_reentrancy(callee_id)?;
+        self.check_call_depth(callee_id)?;
 
         ensure!(
             !self.is_finalizing,
@@ -420,6 +493,7 @@ impl SyncRuntimeInternal<UserContractInstance> {
             authenticated_signer,
             authenticated_caller_id,
             height: self.height,
+            previous_block_hash: self.previous_block_hash,
             index: None,
             next_message_index: self.next_message_index,
         };
@@ -643,6 +717,10 @@ impl<UserInstance> BaseRuntime for SyncRuntime<UserInstance> {
     fn fetch_json(&mut self, url: &str) -> Result<String, ExecutionError> {
         self.inner().fetch_json(url)
     }
+
+    fn read_blob(&mut self, blob_id: BlobId) -> Result<Vec<u8>, ExecutionError> {
+        self.inner().read_blob(blob_id)
+    }
 }
 
 impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
@@ -861,7 +939,9 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
             next_block_height: self.height,
         };
         let sender = self.execution_state_sender.clone();
-        let response = ServiceSyncRuntime::run_query(sender, application_id, context, query)?;
+        let maximum_fuel = QueryLimits::default().maximum_fuel;
+        let response =
+            ServiceSyncRuntime::run_query(sender, application_id, context, query, maximum_fuel)?;
         if let OracleResponses::Record(responses) = &mut self.oracle_responses {
             responses.push(OracleResponse::Service(response.clone()));
         }
@@ -887,6 +967,29 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         }
         Ok(json)
     }
+
+    fn read_blob(&mut self, blob_id: BlobId) -> Result<Vec<u8>, ExecutionError> {
+        // Only the ID of the blob is recorded: its content is deterministic, but validators
+        // must know which blobs a block needs, to fetch them and to keep them in storage.
+        if let OracleResponses::Replay(responses) = &mut self.oracle_responses {
+            match responses.next() {
+                Some(OracleResponse::Blob(recorded_id)) if recorded_id == blob_id => {}
+                Some(_) => return Err(ExecutionError::OracleResponseMismatch),
+                None => return Err(ExecutionError::MissingOracleResponse),
+            }
+        }
+        self.resource_controller.track_read_operations(1)?;
+        let blob = self
+            .execution_state_sender
+            .send_request(|callback| Request::ReadBlob { blob_id, callback })?
+            .recv_response()?;
+        self.resource_controller
+            .track_bytes_read(blob.bytes().len() as u64)?;
+        if let OracleResponses::Record(responses) = &mut self.oracle_responses {
+            responses.push(OracleResponse::Blob(blob_id));
+        }
+        Ok(blob.into_bytes())
+    }
 }
 
 impl<UserInstance> Clone for SyncRuntime<UserInstance> {
@@ -896,8 +999,11 @@ impl<UserInstance> Clone for SyncRuntime<UserInstance> {
 }
 
 impl ContractSyncRuntime {
-    /// Main entry point to start executing a user action.
-    #[allow(clippy::type_complexity)]
+    /// Main entry point to start executing a user action, aborting it if it runs for longer
+    /// than `timeout`.
+    ///
+    /// Returns the resources used by the action even if it failed.
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     pub(crate) fn run_action(
         execution_state_sender: ExecutionStateSender,
         application_id: UserApplicationId,
@@ -906,7 +1012,11 @@ impl ContractSyncRuntime {
         resource_controller: ResourceController,
         action: UserAction,
         oracle_record: Option<OracleRecord>,
-    ) -> Result<(Vec<ExecutionOutcome>, OracleRecord, ResourceController), ExecutionError> {
+        timeout: Option<Duration>,
+    ) -> (
+        Result<(Vec<ExecutionOutcome>, OracleRecord), ExecutionError>,
+        ResourceController,
+    ) {
         let executing_message = match &action {
             UserAction::Message(context, _) => Some(context.into()),
             _ => None,
@@ -914,12 +1024,13 @@ impl ContractSyncRuntime {
         let signer = action.signer();
         let height = action.height();
         let next_message_index = action.next_message_index();
+        let random_seed = CryptoHash::new(&RandomSeed::new(chain_id, &action));
         let oracle_record = if let Some(responses) = oracle_record {
             OracleResponses::Replay(responses.responses.into_iter())
         } else {
             OracleResponses::Record(Vec::new())
         };
-        let mut runtime = ContractSyncRuntime::new(SyncRuntimeInternal::new(
+        let mut runtime_internal = SyncRuntimeInternal::new(
             chain_id,
             height,
             signer,
@@ -929,21 +1040,31 @@ impl ContractSyncRuntime {
             refund_grant_to,
             resource_controller,
             oracle_record,
-        ));
+        );
+        runtime_internal.deadline = timeout.map(|timeout| (Instant::now() + timeout, timeout));
+        runtime_internal.previous_block_hash = action.previous_block_hash();
+        runtime_internal.random_seed = Some(random_seed);
+        let mut runtime = ContractSyncRuntime::new(runtime_internal);
         let finalize_context = FinalizeContext {
             authenticated_signer: signer,
             chain_id,
             height,
             next_message_index,
         };
-        runtime.execute(application_id, signer, move |code| match action {
-            UserAction::Instantiate(context, argument) => code.instantiate(context, argument),
-            UserAction::Operation(context, operation) => {
-                code.execute_operation(context, operation).map(|_| ())
-            }
-            UserAction::Message(context, message) => code.execute_message(context, message),
-        })?;
-        runtime.finalize(finalize_context)?;
+        let result = runtime
+            .execute(application_id, signer, move |code| match action {
+                UserAction::Instantiate(context, argument) => code.instantiate(context, argument),
+                UserAction::Operation(context, operation) => {
+                    code.execute_operation(context, operation).map(|_| ())
+                }
+                UserAction::Message(context, message) => code.execute_message(context, message),
+                UserAction::Migrate(context, argument) => code.migrate(context, argument),
+            })
+            .and_then(|()| runtime.finalize(finalize_context));
+        if let Err(error) = result {
+            let resource_controller = runtime.inner().resource_controller.clone();
+            return (Err(error), resource_controller);
+        }
         let runtime = runtime
             .into_inner()
             .expect("Runtime clones should have been freed by now");
@@ -952,11 +1073,10 @@ impl ContractSyncRuntime {
         } else {
             OracleRecord::default()
         };
-        Ok((
-            runtime.execution_outcomes,
-            oracle_record,
+        (
+            Ok((runtime.execution_outcomes, oracle_record)),
             runtime.resource_controller,
-        ))
+        )
     }
 
     /// Notifies all loaded applications that execution is finalizing.
@@ -1027,12 +1147,13 @@ impl ContractSyncRuntime {
             application
         };
 
-        closure(
+        let result = closure(
             &mut contract
                 .instance
                 .try_lock()
                 .expect("Application should not be already executing"),
-        )?;
+        );
+        result.map_err(|error| self.inner().record_exceeded_resource(error))?;
 
         let mut runtime = self.inner();
         let application_status = runtime.pop_application();
@@ -1078,7 +1199,66 @@ impl ContractRuntime for ContractSyncRuntime {
 
     fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError> {
         let mut this = self.inner();
-        this.resource_controller.track_fuel(fuel)
+        this.resource_controller.track_fuel(fuel)?;
+        this.check_deadline()
+    }
+
+    fn track_host_call(&mut self) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        this.resource_controller.track_host_call()?;
+        this.check_deadline()
+    }
+
+    fn random_value(&mut self) -> Result<CryptoHash, ExecutionError> {
+        let mut this = self.inner();
+        let application_id = this.application_id()?;
+        let seed = this
+            .random_seed
+            .expect("Contracts are only executed in transactions, which have a random seed");
+        let index = this.random_value_count;
+        this.random_value_count += 1;
+        Ok(CryptoHash::new(&RandomValue {
+            seed,
+            application_id,
+            index,
+        }))
+    }
+
+    fn remaining_time(&mut self) -> Result<Option<Duration>, ExecutionError> {
+        let mut this = self.inner();
+        this.check_deadline()?;
+        Ok(this
+            .deadline
+            .map(|(deadline, _)| deadline.saturating_duration_since(Instant::now())))
+    }
+
+    #[cfg_attr(not(with_metrics), allow(unused_variables))]
+    fn increment_counter(&mut self, name: String, value: u64) -> Result<(), ExecutionError> {
+        application_metrics::check_metric_name(&name)?;
+        #[cfg(with_metrics)]
+        {
+            let mut this = self.inner();
+            // Only confirmed blocks are replayed, so that each update is counted once.
+            if matches!(this.oracle_responses, OracleResponses::Replay(_)) {
+                let application_id = this.current_application().id;
+                application_metrics::increment_counter(application_id, &name, value);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(not(with_metrics), allow(unused_variables))]
+    fn set_gauge(&mut self, name: String, value: i64) -> Result<(), ExecutionError> {
+        application_metrics::check_metric_name(&name)?;
+        #[cfg(with_metrics)]
+        {
+            let mut this = self.inner();
+            if matches!(this.oracle_responses, OracleResponses::Replay(_)) {
+                let application_id = this.current_application().id;
+                application_metrics::set_gauge(application_id, &name, value);
+            }
+        }
+        Ok(())
     }
 
     fn send_message(&mut self, message: SendMessageRequest<Vec<u8>>) -> Result<(), ExecutionError> {
@@ -1108,6 +1288,80 @@ impl ContractRuntime for ContractSyncRuntime {
         Ok(())
     }
 
+    fn emit(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        let application = this.current_application_mut();
+
+        application.outcome.events.push((key, value));
+
+        Ok(())
+    }
+
+    fn publish_to_topic(&mut self, topic: String, payload: Vec<u8>) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        let application = this.current_application_mut();
+
+        application.outcome.messages.push(RawOutgoingMessage {
+            destination: Destination::Subscribers(system::topic_channel_name(&topic)),
+            authenticated: false,
+            grant: Resources::default(),
+            kind: MessageKind::Simple,
+            expiration: None,
+            message: payload,
+        });
+
+        Ok(())
+    }
+
+    fn subscribe_to_topic(&mut self, chain: ChainId, topic: String) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        let application_id = this.current_application().id;
+        let message =
+            system::subscribe_to_topic_message(this.chain_id, chain, application_id, topic)?;
+        let outcome = RawExecutionOutcome::default().with_message(message);
+        this.execution_outcomes
+            .push(ExecutionOutcome::System(outcome));
+        Ok(())
+    }
+
+    fn unsubscribe_from_topic(
+        &mut self,
+        chain: ChainId,
+        topic: String,
+    ) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        let application_id = this.current_application().id;
+        let message =
+            system::unsubscribe_from_topic_message(this.chain_id, chain, application_id, topic);
+        let outcome = RawExecutionOutcome::default().with_message(message);
+        this.execution_outcomes
+            .push(ExecutionOutcome::System(outcome));
+        Ok(())
+    }
+
+    fn expire_key(&mut self, key: Vec<u8>, height: BlockHeight) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        ensure!(
+            height > this.height,
+            ExecutionError::InvalidKeyExpiry {
+                height,
+                block_height: this.height,
+            }
+        );
+        let id = this.application_id()?;
+        this.resource_controller.track_write_operations(1)?;
+        this.resource_controller
+            .track_bytes_written(key.len() as u64)?;
+        this.execution_state_sender
+            .send_request(|callback| Request::ExpireKey {
+                id,
+                key,
+                height,
+                callback,
+            })?
+            .recv_response()
+    }
+
     fn transfer(
         &mut self,
         source: Option<Owner>,
@@ -1168,10 +1422,11 @@ impl ContractRuntime for ContractSyncRuntime {
             self.inner()
                 .prepare_for_call(cloned_self, authenticated, callee_id)?;
 
-        let value = contract
+        let result = contract
             .try_lock()
             .expect("Applications should not have reentrant calls")
-            .execute_operation(context, argument)?;
+            .execute_operation(context, argument);
+        let value = result.map_err(|error| self.inner().record_exceeded_resource(error))?;
 
         self.inner().finish_call()?;
 
@@ -1234,24 +1489,48 @@ impl ContractRuntime for ContractSyncRuntime {
         )?;
         this.resource_controller
             .track_bytes_written(batch.size() as u64)?;
-        this.execution_state_sender
+        let stored_bytes = this
+            .execution_state_sender
             .send_request(|callback| Request::WriteBatch {
                 id,
                 batch,
                 callback,
             })?
             .recv_response()?;
+        let maximum = this
+            .resource_controller
+            .policy
+            .maximum_bytes_stored_per_application;
+        ensure!(
+            stored_bytes <= maximum,
+            ExecutionError::StorageQuotaExceeded {
+                application_id: Box::new(id),
+                stored_bytes,
+                maximum,
+            }
+        );
         Ok(())
     }
 }
 
 impl ServiceSyncRuntime {
+    /// Runs a query to an application service, aborting it if it uses more than
+    /// `maximum_fuel`.
     pub(crate) fn run_query(
         execution_state_sender: ExecutionStateSender,
         application_id: UserApplicationId,
         context: crate::QueryContext,
         query: Vec<u8>,
+        maximum_fuel: u64,
     ) -> Result<Vec<u8>, ExecutionError> {
+        // Queries are free: only their fuel is limited.
+        let resource_controller = ResourceController {
+            policy: Arc::new(ResourceControlPolicy {
+                maximum_fuel_per_block: maximum_fuel,
+                ..ResourceControlPolicy::default()
+            }),
+            ..ResourceController::default()
+        };
         let runtime_internal = SyncRuntimeInternal::new(
             context.chain_id,
             context.next_block_height,
@@ -1260,7 +1539,7 @@ impl ServiceSyncRuntime {
             None,
             execution_state_sender,
             None,
-            ResourceController::default(),
+            resource_controller,
             OracleResponses::Forget,
         );
         let mut runtime = ServiceSyncRuntime::new(runtime_internal);
@@ -1276,6 +1555,9 @@ impl ServiceSyncRuntime {
 
 impl ServiceRuntime for ServiceSyncRuntime {
     /// Note that queries are not available from writable contexts.
+    ///
+    /// Services can query each other, but not in a cycle: querying an application that is
+    /// already handling a query fails.
     fn try_query_application(
         &mut self,
         queried_id: UserApplicationId,
@@ -1284,6 +1566,7 @@ impl ServiceRuntime for ServiceSyncRuntime {
         let (query_context, service) = {
             let cloned_self = self.clone().0;
             let mut this = self.inner();
+            this.check_for_reentrancy(queried_id)?;
 
             // Load the application.
             let application = this.load_service_instance(cloned_self, queried_id)?;
@@ -1304,9 +1587,10 @@ impl ServiceRuntime for ServiceSyncRuntime {
         let response = service
             .try_lock()
             .expect("Applications should not have reentrant calls")
-            .handle_query(query_context, argument)?;
+            .handle_query(query_context, argument);
+        // The caller may recover from the error, so the call stack must be restored.
         self.inner().pop_application();
-        Ok(response)
+        response
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -1318,8 +1602,77 @@ impl ServiceRuntime for ServiceSyncRuntime {
             .send_request(|callback| Request::FetchUrl { url, callback })?
             .recv_response()
     }
+
+    fn remaining_fuel(&mut self) -> Result<u64, ExecutionError> {
+        Ok(self.inner().resource_controller.remaining_fuel())
+    }
+
+    fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        this.resource_controller
+            .track_fuel(fuel)
+            .map_err(|error| match error {
+                ExecutionError::MaximumFuelExceeded => ExecutionError::MaximumQueryFuelExceeded,
+                error => error,
+            })
+    }
 }
 
+/// What the pseudo-random values of a transaction are derived from: the hash of a
+/// certificate, i.e. of the previous block of the chain for operations and of the sending
+/// block for messages, and everything that identifies the transaction.
+#[derive(Debug, Serialize, Deserialize)]
+enum RandomSeed {
+    /// An operation, or the instantiation or migration of an application.
+    Operation {
+        chain_id: ChainId,
+        height: BlockHeight,
+        previous_block_hash: Option<CryptoHash>,
+        index: Option<u32>,
+    },
+    /// An incoming message.
+    Message {
+        chain_id: ChainId,
+        height: BlockHeight,
+        certificate_hash: CryptoHash,
+        message_id: MessageId,
+    },
+}
+
+impl BcsHashable for RandomSeed {}
+
+impl RandomSeed {
+    fn new(chain_id: ChainId, action: &UserAction) -> Self {
+        match action {
+            UserAction::Instantiate(context, _)
+            | UserAction::Operation(context, _)
+            | UserAction::Migrate(context, _) => RandomSeed::Operation {
+                chain_id,
+                height: context.height,
+                previous_block_hash: context.previous_block_hash,
+                index: context.index,
+            },
+            UserAction::Message(context, _) => RandomSeed::Message {
+                chain_id,
+                height: context.height,
+                certificate_hash: context.certificate_hash,
+                message_id: context.message_id,
+            },
+        }
+    }
+}
+
+/// The input of the hash returned as the `index`-th pseudo-random value of a transaction to
+/// an application.
+#[derive(Debug, Serialize, Deserialize)]
+struct RandomValue {
+    seed: CryptoHash,
+    application_id: UserApplicationId,
+    index: u64,
+}
+
+impl BcsHashable for RandomValue {}
+
 /// The origin of the execution.
 #[derive(Clone, Copy, Debug)]
 struct ExecutingMessage {
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Display, Formatter},
     iter,
 };
@@ -12,14 +12,15 @@ use async_graphql::Enum;
 use custom_debug_derive::Debug;
 use linera_base::{
     crypto::{CryptoHash, PublicKey},
-    data_types::{Amount, ApplicationPermissions, ArithmeticError, Timestamp},
+    data_types::{Amount, ApplicationPermissions, ArithmeticError, SoftwareVersion, Timestamp},
     ensure, hex_debug,
-    identifiers::{Account, BytecodeId, ChainDescription, ChainId, MessageId, Owner},
+    identifiers::{Account, BlobId, BytecodeId, ChainDescription, ChainId, MessageId, Owner},
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_views::{
     common::Context,
     map_view::HashedMapView,
+    merkle::HashedMerkleMapView,
     register_view::HashedRegisterView,
     set_view::HashedSetView,
     views::{HashableView, View, ViewError},
@@ -35,10 +36,10 @@ use {
 #[cfg(test)]
 use crate::test_utils::SystemExecutionState;
 use crate::{
-    committee::{Committee, Epoch},
+    committee::{Committee, Epoch, ValidatorName, ValidatorState},
     ApplicationRegistryView, Bytecode, BytecodeLocation, ChannelName, ChannelSubscription,
-    Destination, MessageContext, MessageKind, OperationContext, QueryContext, RawExecutionOutcome,
-    RawOutgoingMessage, UserApplicationDescription, UserApplicationId,
+    Destination, ExecutionOutcome, MessageContext, MessageKind, OperationContext, QueryContext,
+    RawExecutionOutcome, RawOutgoingMessage, UserApplicationDescription, UserApplicationId,
 };
 
 /// The relative index of the `OpenChain` message created by the `OpenChain` operation.
@@ -79,10 +80,23 @@ pub struct SystemExecutionStateView<C> {
     pub committees: HashedRegisterView<C, BTreeMap<Epoch, Committee>>,
     /// Ownership of the chain.
     pub ownership: HashedRegisterView<C, ChainOwnership>,
+    /// The key that the chain is being transferred to, if it has not accepted yet.
+    pub pending_ownership_transfer: HashedRegisterView<C, Option<PublicKey>>,
     /// Balance of the chain. (Available to any user able to create blocks in the chain.)
     pub balance: HashedRegisterView<C, Amount>,
-    /// Balances attributed to a given owner.
-    pub balances: HashedMapView<C, Owner, Amount>,
+    /// Balances attributed to a given owner. Their hash is a Merkle root, so that a single
+    /// balance can be proven against the state hash.
+    pub balances: HashedMerkleMapView<C, Owner, Amount>,
+    /// The amounts that owners allow other owners to transfer from their balances, indexed by
+    /// owner and spender.
+    pub allowances: HashedMapView<C, (Owner, Owner), Amount>,
+    /// The account from which the fees of the blocks are paid.
+    pub fee_source: HashedRegisterView<C, FeeSource>,
+    /// The fees paid by the blocks of the chain in each epoch, i.e. the rewards of the
+    /// validators of that epoch.
+    pub collected_fees: HashedMapView<C, Epoch, Amount>,
+    /// On the admin chain, the first evidence reported of each validator's equivocation.
+    pub equivocations: HashedMapView<C, ValidatorName, Vec<u8>>,
     /// The timestamp of the most recent block.
     pub timestamp: HashedRegisterView<C, Timestamp>,
     /// Track the locations of known bytecodes as well as the descriptions of known applications.
@@ -91,6 +105,13 @@ pub struct SystemExecutionStateView<C> {
     pub closed: HashedRegisterView<C, bool>,
     /// Permissions for applications on this chain.
     pub application_permissions: HashedRegisterView<C, ApplicationPermissions>,
+    /// On the admin chain, the pending proposals to change the committee, by ID.
+    pub committee_proposals: HashedMapView<C, u32, CommitteeProposal>,
+    /// On the admin chain, the ID of the next proposal to change the committee.
+    pub next_committee_proposal_id: HashedRegisterView<C, u32>,
+    /// The minimum version of the software that the validators must run, as last set by the
+    /// admin chain. Chains opened since then don't know it until it changes again.
+    pub minimum_version: HashedRegisterView<C, Option<SoftwareVersion>>,
 }
 
 /// The configuration for a new chain.
@@ -176,6 +197,101 @@ pub enum SystemOperation {
     },
     /// Operations that are only allowed on the admin chain.
     Admin(AdminOperation),
+    /// Allows `spender` to transfer up to `amount` units of value from the given owner's
+    /// account, replacing any previous allowance. A zero amount revokes the allowance.
+    Approve {
+        owner: Owner,
+        spender: Owner,
+        amount: Amount,
+    },
+    /// Transfers `amount` units of value from the given owner's account to the recipient, on
+    /// behalf of the authenticated signer. The amount is deducted from the allowance given to
+    /// the signer by the owner.
+    TransferFrom {
+        owner: Owner,
+        recipient: Recipient,
+        amount: Amount,
+        user_data: UserData,
+    },
+    /// Transfers units of value from the given owner's account to several recipients.
+    /// If no owner is given, try to take the units out of the unattributed account.
+    TransferMany {
+        owner: Option<Owner>,
+        transfers: Vec<(Recipient, Amount)>,
+        user_data: UserData,
+    },
+    /// Changes the account from which the fees of this chain's blocks are paid.
+    ChangeFeeSource(FeeSource),
+    /// Transfers the whole unattributed balance of the chain to the `recipient`, then closes
+    /// the chain. The fees of the block must be paid from the signer's account.
+    ReclaimAndCloseChain { recipient: Account },
+    /// Reports to the admin chain that a validator signed two conflicting blocks. The
+    /// `evidence` is a serialized `EquivocationEvidence`, which is verified before the operation
+    /// is executed.
+    ReportEquivocation {
+        validator: ValidatorName,
+        #[serde(with = "serde_bytes")]
+        #[debug(with = "hex_debug")]
+        evidence: Vec<u8>,
+    },
+    /// Starts transferring the chain to a new owner. The new key is added as a super owner,
+    /// and the current owners keep theirs until the new owner accepts the transfer. Changing
+    /// the ownership in the meantime cancels the transfer.
+    TransferOwnership { new_public_key: PublicKey },
+    /// Completes the pending transfer of the chain: the new key becomes its only owner. This
+    /// must be signed by the new key.
+    AcceptOwnership,
+    /// Publishes a blob, so that validators keep it in their storage and applications can read
+    /// it. The content of the blob is not part of the block: it is sent along with the block
+    /// proposal.
+    PublishBlob { blob_id: BlobId },
+    /// Publishes a new application bytecode, whose contract and service are in blobs sent
+    /// along with the block proposal. Unlike `PublishBytecode`, the certificate of the block
+    /// only contains the IDs of the blobs, and the nodes missing the bytecode fetch it on
+    /// demand.
+    PublishBytecodeBlobs { contract: BlobId, service: BlobId },
+    /// Subscribes to a topic of an application on another chain. The payloads it publishes
+    /// to the topic are then delivered as messages to the same application on this chain.
+    SubscribeToTopic {
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        topic: String,
+    },
+    /// Unsubscribes from a topic of an application on another chain.
+    UnsubscribeFromTopic {
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        topic: String,
+    },
+    /// Upgrades an application created by this chain to a new bytecode. Each chain running
+    /// the application migrates its state by calling the `migrate` entrypoint of the new
+    /// contract with the `migration_argument`, unless it pinned an earlier version.
+    UpgradeApplication {
+        application_id: UserApplicationId,
+        bytecode_id: BytecodeId,
+        #[serde(with = "serde_bytes")]
+        #[debug(with = "hex_debug")]
+        migration_argument: Vec<u8>,
+    },
+    /// Pins the version of an application that this chain runs, so that its state is not
+    /// migrated to later upgrades. `None` lets the chain follow the upgrades again.
+    PinApplicationVersion {
+        application_id: UserApplicationId,
+        version: Option<u32>,
+    },
+}
+
+/// The account from which the fees of a block are paid.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize, clap::ValueEnum,
+)]
+pub enum FeeSource {
+    /// The chain's unattributed balance, then the balance of the block's signer.
+    #[default]
+    Chain,
+    /// Only the balance of the block's signer. Blocks without a signer still pay with the
+    /// chain's balance.
+    Proposer,
 }
 
 /// Operations that are only allowed on the admin chain.
@@ -189,6 +305,75 @@ pub enum AdminOperation {
     /// blocks from the retired epoch will not be accepted until they are followed (hence
     /// re-certified) by a block certified by a recent committee.
     RemoveCommittee { epoch: Epoch },
+    /// Proposes a change of the validator set, voted for by the signer. The change is
+    /// applied by `FinalizeCommitteeChange` once a majority of the chain owners voted for it.
+    ProposeCommitteeChange { change: CommitteeChange },
+    /// Votes for a pending proposal to change the validator set.
+    VoteCommitteeChange { proposal_id: u32 },
+    /// Creates a new committee, with the change of a proposal that a majority of the chain
+    /// owners voted for, and starts using it.
+    FinalizeCommitteeChange { proposal_id: u32 },
+    /// Sets the minimum version of the software that the validators must run, to coordinate
+    /// an upgrade of the network. This will notify the subscribers of the admin chain with a
+    /// `SetMinimumVersion` message.
+    SetMinimumVersion { version: SoftwareVersion },
+}
+
+/// A change of the validator set, to be voted on by the owners of the admin chain.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum CommitteeChange {
+    /// Adds a validator, or changes its network address and voting power.
+    SetValidator {
+        name: ValidatorName,
+        state: ValidatorState,
+    },
+    /// Removes a validator.
+    RemoveValidator { name: ValidatorName },
+    /// Replaces the key of a validator, keeping its network address and voting power.
+    RotateValidatorKey {
+        name: ValidatorName,
+        new_name: ValidatorName,
+    },
+}
+
+impl CommitteeChange {
+    /// Returns the committee resulting from applying this change to `committee`.
+    pub fn apply(&self, committee: &Committee) -> Result<Committee, SystemExecutionError> {
+        let mut validators = committee.validators().clone();
+        match self {
+            CommitteeChange::SetValidator { name, state } => {
+                validators.insert(*name, state.clone());
+            }
+            CommitteeChange::RemoveValidator { name } => {
+                ensure!(
+                    validators.remove(name).is_some(),
+                    SystemExecutionError::InvalidCommitteeChange
+                );
+            }
+            CommitteeChange::RotateValidatorKey { name, new_name } => {
+                ensure!(
+                    !validators.contains_key(new_name),
+                    SystemExecutionError::InvalidCommitteeChange
+                );
+                let state = validators
+                    .remove(name)
+                    .ok_or(SystemExecutionError::InvalidCommitteeChange)?;
+                validators.insert(*new_name, state);
+            }
+        }
+        ensure!(
+            !validators.is_empty(),
+            SystemExecutionError::InvalidCommitteeChange
+        );
+        Ok(Committee::new(validators, committee.policy().clone()))
+    }
+}
+
+/// A pending change of the validator set, and the owners of the admin chain who voted for it.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CommitteeProposal {
+    pub change: CommitteeChange,
+    pub votes: BTreeSet<Owner>,
 }
 
 /// A system message meant to be executed on a remote chain.
@@ -245,6 +430,27 @@ pub enum SystemMessage {
     /// Requests a `RegisterApplication` message from the target chain to register the specified
     /// application on the sender chain.
     RequestApplication(UserApplicationId),
+    /// Notifies the admin chain that a validator signed two conflicting blocks.
+    EquivocationReported {
+        validator: ValidatorName,
+        #[serde(with = "serde_bytes")]
+        #[debug(with = "hex_debug")]
+        evidence: Vec<u8>,
+    },
+    /// Subscribes the chain `id` to a topic of an application on the receiving chain.
+    SubscribeToTopic {
+        id: ChainId,
+        application_id: UserApplicationId,
+        topic: String,
+    },
+    /// Unsubscribes the chain `id` from a topic of an application on the receiving chain.
+    UnsubscribeFromTopic {
+        id: ChainId,
+        application_id: UserApplicationId,
+        topic: String,
+    },
+    /// Sets the minimum version of the software that the validators must run.
+    SetMinimumVersion { version: SoftwareVersion },
 }
 
 impl SystemMessage {
@@ -265,7 +471,7 @@ impl SystemMessage {
                 locations: new_locations,
             } => Box::new(new_locations.iter().map(|(_id, location)| *location)),
             SystemMessage::RegisterApplications { applications } => {
-                Box::new(applications.iter().map(|app| app.bytecode_location))
+                Box::new(applications.iter().flat_map(|app| app.bytecode_locations()))
             }
             SystemMessage::Credit { .. }
             | SystemMessage::Withdraw { .. }
@@ -275,9 +481,41 @@ impl SystemMessage {
             | SystemMessage::Unsubscribe { .. }
             | SystemMessage::ApplicationCreated
             | SystemMessage::Notify { .. }
-            | SystemMessage::RequestApplication(_) => Box::new(iter::empty()),
+            | SystemMessage::RequestApplication(_)
+            | SystemMessage::EquivocationReported { .. }
+            | SystemMessage::SubscribeToTopic { .. }
+            | SystemMessage::UnsubscribeFromTopic { .. }
+            | SystemMessage::SetMinimumVersion { .. } => Box::new(iter::empty()),
         }
     }
+
+    /// Returns the outcome of the application whose topic this message subscribes to or
+    /// unsubscribes from, which adds the subscriber to the channel of the topic or removes
+    /// it. The payloads published to the topic are then sent to the subscriber as messages
+    /// of that application.
+    pub fn topic_subscription_outcome(&self) -> Option<ExecutionOutcome> {
+        let mut outcome = RawExecutionOutcome::default();
+        let application_id = match self {
+            SystemMessage::SubscribeToTopic {
+                id,
+                application_id,
+                topic,
+            } => {
+                outcome.subscribe.push((topic_channel_name(topic), *id));
+                *application_id
+            }
+            SystemMessage::UnsubscribeFromTopic {
+                id,
+                application_id,
+                topic,
+            } => {
+                outcome.unsubscribe.push((topic_channel_name(topic), *id));
+                *application_id
+            }
+            _ => return None,
+        };
+        Some(ExecutionOutcome::User(application_id, outcome))
+    }
 }
 
 /// A query to the system state.
@@ -311,6 +549,64 @@ impl SystemChannel {
     }
 }
 
+/// The prefix of the names of the channels of topics, which sets them apart from the
+/// [`SystemChannel`]s.
+const TOPIC_CHANNEL_PREFIX: &[u8] = b"topic:";
+
+/// Returns the [`ChannelName`] of the channel of an application on which the payloads it
+/// publishes to `topic` are sent.
+pub fn topic_channel_name(topic: &str) -> ChannelName {
+    [TOPIC_CHANNEL_PREFIX, topic.as_bytes()].concat().into()
+}
+
+/// Returns the message that subscribes the chain `id` to `topic` of the application
+/// `application_id` on the chain `chain_id`.
+pub fn subscribe_to_topic_message(
+    id: ChainId,
+    chain_id: ChainId,
+    application_id: UserApplicationId,
+    topic: String,
+) -> Result<RawOutgoingMessage<SystemMessage, Amount>, SystemExecutionError> {
+    ensure!(
+        id != chain_id,
+        SystemExecutionError::SelfTopicSubscription(id, topic)
+    );
+    Ok(RawOutgoingMessage {
+        destination: Destination::Recipient(chain_id),
+        authenticated: false,
+        grant: Amount::ZERO,
+        kind: MessageKind::Protected,
+        expiration: None,
+        message: SystemMessage::SubscribeToTopic {
+            id,
+            application_id,
+            topic,
+        },
+    })
+}
+
+/// Returns the message that unsubscribes the chain `id` from `topic` of the application
+/// `application_id` on the chain `chain_id`.
+pub fn unsubscribe_from_topic_message(
+    id: ChainId,
+    chain_id: ChainId,
+    application_id: UserApplicationId,
+    topic: String,
+) -> RawOutgoingMessage<SystemMessage, Amount> {
+    RawOutgoingMessage {
+        destination: Destination::Recipient(chain_id),
+        authenticated: false,
+        grant: Amount::ZERO,
+        kind: MessageKind::Protected,
+        expiration: None,
+        message: SystemMessage::UnsubscribeFromTopic {
+            id,
+            application_id,
+            topic,
+        },
+    }
+}
+
 impl Display for SystemChannel {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         let display_name = match self {
@@ -371,6 +667,14 @@ pub enum SystemExecutionError {
     InsufficientFunding { balance: Amount },
     #[error("Required execution fees exceeded the total funding available: {balance}")]
     InsufficientFundingForFees { balance: Amount },
+    #[error("Transfer must have at least one recipient")]
+    NoTransferRecipients,
+    #[error("Approval must be authenticated by the owner of the account")]
+    UnauthenticatedApprovalOwner,
+    #[error("Transfer on behalf of another owner must be authenticated by the spender")]
+    UnauthenticatedTransferSpender,
+    #[error("The transferred amount must not exceed the spender's allowance: {allowance}")]
+    InsufficientAllowance { allowance: Amount },
     #[error("Claim must have positive amount")]
     IncorrectClaimAmount,
     #[error("Claim must be authenticated by the right signer")]
@@ -381,6 +685,14 @@ pub enum SystemExecutionError {
     InvalidCommitteeCreation,
     #[error("Failed to remove committee")]
     InvalidCommitteeRemoval,
+    #[error("Committee changes must be signed by an owner of the admin chain")]
+    UnauthorizedCommitteeVote,
+    #[error("Unknown proposal {0} to change the committee")]
+    UnknownCommitteeProposal(u32),
+    #[error("Proposal {proposal_id} to change the committee has only {votes} votes")]
+    InsufficientCommitteeVotes { proposal_id: u32, votes: usize },
+    #[error("Invalid change of the committee")]
+    InvalidCommitteeChange,
     #[error(
         "Chain {0} tried to subscribe to the admin channel ({1}) of a chain that is not the admin chain"
     )]
@@ -391,6 +703,8 @@ pub enum SystemExecutionError {
     AlreadySubscribedToChannel(ChainId, SystemChannel),
     #[error("Invalid unsubscription request to channel {1} on chain {0}")]
     InvalidUnsubscription(ChainId, SystemChannel),
+    #[error("Cannot subscribe to a topic ({1:?}) on the same chain ({0})")]
+    SelfTopicSubscription(ChainId, String),
     #[error("Amount overflow")]
     AmountOverflow,
     #[error("Amount underflow")]
@@ -403,12 +717,38 @@ pub enum SystemExecutionError {
     CannotRewindEpoch,
     #[error("Cannot decrease the chain's timestamp")]
     TicksOutOfOrder,
+    #[error("Published bytecode of {size} bytes exceeds the maximum of {maximum} bytes")]
+    BytecodeTooLarge { size: u64, maximum: u64 },
     #[error("Attempt to create an application using unregistered bytecode identifier {0:?}")]
     UnknownBytecodeId(BytecodeId),
     #[error("Application {0:?} is not registered by the chain")]
     UnknownApplicationId(Box<UserApplicationId>),
     #[error("Chain is not active yet.")]
     InactiveChain,
+    #[error("The chain is already being transferred to {0}")]
+    OwnershipTransferPending(PublicKey),
+    #[error("The chain is not being transferred to a new owner")]
+    NoPendingOwnershipTransfer,
+    #[error("The transfer of the chain must be accepted by the new owner {0}")]
+    UnauthenticatedOwnershipTransfer(PublicKey),
+    #[error("The signer of the block is not allowed to register applications on this chain")]
+    UnauthorizedApplicationRegistration,
+    #[error("Application {0:?} can only be upgraded by the chain that created it")]
+    UnauthorizedApplicationUpgrade(Box<UserApplicationId>),
+    #[error("Application {application_id:?} has no version {version}")]
+    UnknownApplicationVersion {
+        application_id: Box<UserApplicationId>,
+        version: u32,
+    },
+    #[error(
+        "Cannot pin application {application_id:?} to version {version}: its state on this \
+        chain was already migrated to version {current_version}"
+    )]
+    ApplicationVersionAlreadyMigrated {
+        application_id: Box<UserApplicationId>,
+        version: u32,
+        current_version: u32,
+    },
 }
 
 impl<C> SystemExecutionStateView<C>
@@ -431,6 +771,33 @@ where
         Some((*epoch, committee))
     }
 
+    /// Returns the fees paid by the blocks of the chain in each epoch.
+    pub async fn collected_fees(&self) -> Result<BTreeMap<Epoch, Amount>, ViewError> {
+        let mut fees = BTreeMap::new();
+        self.collected_fees
+            .for_each_index_value(|epoch, amount| {
+                fees.insert(epoch, amount);
+                Ok(())
+            })
+            .await?;
+        Ok(fees)
+    }
+
+    /// Returns an error if the application permissions of the chain don't allow the signer
+    /// of the block to create or request applications.
+    fn check_can_register_applications(
+        &self,
+        signer: Option<Owner>,
+    ) -> Result<(), SystemExecutionError> {
+        ensure!(
+            self.application_permissions
+                .get()
+                .can_register_applications(signer.as_ref()),
+            SystemExecutionError::UnauthorizedApplicationRegistration
+        );
+        Ok(())
+    }
+
     /// Executes the sender's side of an operation and returns a list of actions to be
     /// taken.
     pub async fn execute_operation(
@@ -473,6 +840,33 @@ where
                     multi_leader_rounds,
                     timeout_config,
                 });
+                self.pending_ownership_transfer.set(None);
+            }
+            TransferOwnership { new_public_key } => {
+                if let Some(public_key) = *self.pending_ownership_transfer.get() {
+                    return Err(SystemExecutionError::OwnershipTransferPending(public_key));
+                }
+                self.ownership
+                    .get_mut()
+                    .super_owners
+                    .insert(Owner::from(new_public_key), new_public_key);
+                self.pending_ownership_transfer.set(Some(new_public_key));
+            }
+            AcceptOwnership => {
+                let new_public_key = (*self.pending_ownership_transfer.get())
+                    .ok_or(SystemExecutionError::NoPendingOwnershipTransfer)?;
+                ensure!(
+                    context.authenticated_signer == Some(Owner::from(new_public_key)),
+                    SystemExecutionError::UnauthenticatedOwnershipTransfer(new_public_key)
+                );
+                let ownership = self.ownership.get_mut();
+                ownership.super_owners =
+                    iter::once((Owner::from(new_public_key), new_public_key)).collect();
+                ownership.owners.clear();
+                self.pending_ownership_transfer.set(None);
+            }
+            PublishBlob { .. } => {
+                // The worker checks that the blob is available before executing the block.
             }
             ChangeApplicationPermissions(application_permissions) => {
                 self.application_permissions.set(application_permissions);
@@ -481,6 +875,22 @@ where
                 let messages = self.close_chain(context.chain_id).await?;
                 outcome.messages.extend(messages);
             }
+            ReclaimAndCloseChain { recipient } => {
+                let balance = *self.balance.get();
+                if balance > Amount::ZERO {
+                    let message = self
+                        .transfer(
+                            context.authenticated_signer,
+                            None,
+                            Recipient::Account(recipient),
+                            balance,
+                        )
+                        .await?;
+                    outcome.messages.extend(message);
+                }
+                let messages = self.close_chain(context.chain_id).await?;
+                outcome.messages.extend(messages);
+            }
             Transfer {
                 owner,
                 amount,
@@ -495,6 +905,52 @@ where
                     outcome.messages.push(message)
                 }
             }
+            TransferFrom {
+                owner,
+                recipient,
+                amount,
+                ..
+            } => {
+                let message = self
+                    .transfer_from(context.authenticated_signer, owner, recipient, amount)
+                    .await?;
+
+                if let Some(message) = message {
+                    outcome.messages.push(message)
+                }
+            }
+            TransferMany {
+                owner, transfers, ..
+            } => {
+                ensure!(
+                    !transfers.is_empty(),
+                    SystemExecutionError::NoTransferRecipients
+                );
+                for (recipient, amount) in transfers {
+                    let message = self
+                        .transfer(context.authenticated_signer, owner, recipient, amount)
+                        .await?;
+                    outcome.messages.extend(message);
+                }
+            }
+            Approve {
+                owner,
+                spender,
+                amount,
+            } => {
+                ensure!(
+                    context.authenticated_signer == Some(owner),
+                    SystemExecutionError::UnauthenticatedApprovalOwner
+                );
+                if amount == Amount::ZERO {
+                    self.allowances.remove(&(owner, spender))?;
+                } else {
+                    self.allowances.insert(&(owner, spender), amount)?;
+                }
+            }
+            ChangeFeeSource(fee_source) => {
+                self.fee_source.set(fee_source);
+            }
             Claim {
                 owner,
                 target_id,
@@ -528,34 +984,75 @@ where
                         );
                         self.committees.get_mut().insert(epoch, committee);
                         self.epoch.set(Some(epoch));
-                        let message = RawOutgoingMessage {
-                            destination: Destination::Subscribers(SystemChannel::Admin.name()),
-                            authenticated: false,
-                            grant: Amount::ZERO,
-                            kind: MessageKind::Protected,
-                            message: SystemMessage::SetCommittees {
-                                epoch,
-                                committees: self.committees.get().clone(),
-                            },
-                        };
-                        outcome.messages.push(message);
+                        outcome.messages.push(self.set_committees_message());
                     }
                     AdminOperation::RemoveCommittee { epoch } => {
                         ensure!(
                             self.committees.get_mut().remove(&epoch).is_some(),
                             SystemExecutionError::InvalidCommitteeRemoval
                         );
-                        let message = RawOutgoingMessage {
+                        outcome.messages.push(self.set_committees_message());
+                    }
+                    AdminOperation::ProposeCommitteeChange { change } => {
+                        let owner = self.committee_voter(context.authenticated_signer)?;
+                        let proposal_id = *self.next_committee_proposal_id.get();
+                        self.next_committee_proposal_id.set(
+                            proposal_id
+                                .checked_add(1)
+                                .ok_or(ArithmeticError::Overflow)?,
+                        );
+                        let proposal = CommitteeProposal {
+                            change,
+                            votes: iter::once(owner).collect(),
+                        };
+                        self.committee_proposals.insert(&proposal_id, proposal)?;
+                    }
+                    AdminOperation::VoteCommitteeChange { proposal_id } => {
+                        let owner = self.committee_voter(context.authenticated_signer)?;
+                        let mut proposal =
+                            self.committee_proposals.get(&proposal_id).await?.ok_or(
+                                SystemExecutionError::UnknownCommitteeProposal(proposal_id),
+                            )?;
+                        proposal.votes.insert(owner);
+                        self.committee_proposals.insert(&proposal_id, proposal)?;
+                    }
+                    AdminOperation::FinalizeCommitteeChange { proposal_id } => {
+                        self.committee_voter(context.authenticated_signer)?;
+                        let proposal =
+                            self.committee_proposals.get(&proposal_id).await?.ok_or(
+                                SystemExecutionError::UnknownCommitteeProposal(proposal_id),
+                            )?;
+                        // Only the votes of the current owners count.
+                        let owners = self.ownership.get().all_owners().collect::<BTreeSet<_>>();
+                        let votes = proposal
+                            .votes
+                            .iter()
+                            .filter(|owner| owners.contains(owner))
+                            .count();
+                        ensure!(
+                            2 * votes > owners.len(),
+                            SystemExecutionError::InsufficientCommitteeVotes { proposal_id, votes }
+                        );
+                        let (epoch, committee) = self
+                            .current_committee()
+                            .ok_or(SystemExecutionError::InactiveChain)?;
+                        let committee = proposal.change.apply(committee)?;
+                        let epoch = epoch.try_add_one()?;
+                        self.committee_proposals.remove(&proposal_id)?;
+                        self.committees.get_mut().insert(epoch, committee);
+                        self.epoch.set(Some(epoch));
+                        outcome.messages.push(self.set_committees_message());
+                    }
+                    AdminOperation::SetMinimumVersion { version } => {
+                        self.minimum_version.set(Some(version));
+                        outcome.messages.push(RawOutgoingMessage {
                             destination: Destination::Subscribers(SystemChannel::Admin.name()),
                             authenticated: false,
                             grant: Amount::ZERO,
                             kind: MessageKind::Protected,
-                            message: SystemMessage::SetCommittees {
-                                epoch: self.epoch.get().expect("chain is active"),
-                                committees: self.committees.get().clone(),
-                            },
-                        };
-                        outcome.messages.push(message);
+                            expiration: None,
+                            message: SystemMessage::SetMinimumVersion { version },
+                        });
                     }
                 }
             }
@@ -584,6 +1081,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Protected,
+                    expiration: None,
                     message: SystemMessage::Subscribe {
                         id: context.chain_id,
                         subscription,
@@ -606,6 +1104,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Protected,
+                    expiration: None,
                     message: SystemMessage::Unsubscribe {
                         id: context.chain_id,
                         subscription,
@@ -613,7 +1112,7 @@ where
                 };
                 outcome.messages.push(message);
             }
-            PublishBytecode { .. } => {
+            PublishBytecode { .. } | PublishBytecodeBlobs { .. } => {
                 // Send a `BytecodePublished` message to ourself so that we can broadcast
                 // the bytecode-id next.
                 let message = RawOutgoingMessage {
@@ -621,6 +1120,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Protected,
+                    expiration: None,
                     message: SystemMessage::BytecodePublished {
                         operation_index: context
                             .index
@@ -635,6 +1135,7 @@ where
                 instantiation_argument,
                 required_application_ids,
             } => {
+                self.check_can_register_applications(context.authenticated_signer)?;
                 let id = UserApplicationId {
                     bytecode_id,
                     creation: context.next_message_id(),
@@ -652,24 +1153,90 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Protected,
+                    expiration: None,
                     message: SystemMessage::ApplicationCreated,
                 };
                 outcome.messages.push(message);
                 new_application = Some((id, instantiation_argument.clone()));
             }
+            UpgradeApplication {
+                application_id,
+                bytecode_id,
+                migration_argument,
+            } => {
+                self.check_can_register_applications(context.authenticated_signer)?;
+                ensure!(
+                    application_id.creation.chain_id == context.chain_id,
+                    SystemExecutionError::UnauthorizedApplicationUpgrade(Box::new(application_id))
+                );
+                self.registry
+                    .upgrade_application(application_id, bytecode_id, migration_argument)
+                    .await?;
+            }
+            PinApplicationVersion {
+                application_id,
+                version,
+            } => {
+                self.registry.pin_version(application_id, version).await?;
+            }
             RequestApplication {
                 chain_id,
                 application_id,
             } => {
+                self.check_can_register_applications(context.authenticated_signer)?;
                 let message = RawOutgoingMessage {
                     destination: Destination::Recipient(chain_id),
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Simple,
+                    expiration: None,
                     message: SystemMessage::RequestApplication(application_id),
                 };
                 outcome.messages.push(message);
             }
+            SubscribeToTopic {
+                chain_id,
+                application_id,
+                topic,
+            } => {
+                let message =
+                    subscribe_to_topic_message(context.chain_id, chain_id, application_id, topic)?;
+                outcome.messages.push(message);
+            }
+            UnsubscribeFromTopic {
+                chain_id,
+                application_id,
+                topic,
+            } => {
+                let message = unsubscribe_from_topic_message(
+                    context.chain_id,
+                    chain_id,
+                    application_id,
+                    topic,
+                );
+                outcome.messages.push(message);
+            }
+            ReportEquivocation {
+                validator,
+                evidence,
+            } => {
+                let admin_id = self
+                    .admin_id
+                    .get()
+                    .ok_or(SystemExecutionError::InactiveChain)?;
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(admin_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Simple,
+                    expiration: None,
+                    message: SystemMessage::EquivocationReported {
+                        validator,
+                        evidence,
+                    },
+                };
+                outcome.messages.push(message);
+            }
         }
 
         Ok((outcome, new_application))
@@ -699,23 +1266,66 @@ where
         balance
             .try_sub_assign(amount)
             .map_err(|_| SystemExecutionError::InsufficientFunding { balance: *balance })?;
-        match recipient {
-            Recipient::Account(account) => {
-                let message = RawOutgoingMessage {
-                    destination: Destination::Recipient(account.chain_id),
-                    authenticated: false,
-                    grant: Amount::ZERO,
-                    kind: MessageKind::Tracked,
-                    message: SystemMessage::Credit {
-                        amount,
-                        source: owner,
-                        target: account.owner,
-                    },
-                };
+        Ok(Self::credit_message(owner, recipient, amount))
+    }
 
-                Ok(Some(message))
-            }
-            Recipient::Burn => Ok(None),
+    /// Transfers `amount` from `owner`'s account on behalf of the `authenticated_signer`, using
+    /// the allowance the owner gave them.
+    pub async fn transfer_from(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        owner: Owner,
+        recipient: Recipient,
+        amount: Amount,
+    ) -> Result<Option<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let spender =
+            authenticated_signer.ok_or(SystemExecutionError::UnauthenticatedTransferSpender)?;
+        ensure!(
+            amount > Amount::ZERO,
+            SystemExecutionError::IncorrectTransferAmount
+        );
+        let allowance = self
+            .allowances
+            .get(&(owner, spender))
+            .await?
+            .unwrap_or_default();
+        let remaining_allowance = allowance
+            .try_sub(amount)
+            .map_err(|_| SystemExecutionError::InsufficientAllowance { allowance })?;
+        let balance = self.balances.get_mut_or_default(&owner).await?;
+        balance
+            .try_sub_assign(amount)
+            .map_err(|_| SystemExecutionError::InsufficientFunding { balance: *balance })?;
+        if remaining_allowance == Amount::ZERO {
+            self.allowances.remove(&(owner, spender))?;
+        } else {
+            self.allowances
+                .insert(&(owner, spender), remaining_allowance)?;
+        }
+        Ok(Self::credit_message(Some(owner), recipient, amount))
+    }
+
+    /// Returns the message crediting `amount` to the `recipient`, or `None` if it is burnt.
+    /// The `source` is credited instead if the message bounces.
+    fn credit_message(
+        source: Option<Owner>,
+        recipient: Recipient,
+        amount: Amount,
+    ) -> Option<RawOutgoingMessage<SystemMessage, Amount>> {
+        match recipient {
+            Recipient::Account(account) => Some(RawOutgoingMessage {
+                destination: Destination::Recipient(account.chain_id),
+                authenticated: false,
+                grant: Amount::ZERO,
+                kind: MessageKind::Tracked,
+                expiration: None,
+                message: SystemMessage::Credit {
+                    amount,
+                    source,
+                    target: account.owner,
+                },
+            }),
+            Recipient::Burn => None,
         }
     }
 
@@ -742,6 +1352,7 @@ where
             authenticated: true,
             grant: Amount::ZERO,
             kind: MessageKind::Simple,
+            expiration: None,
             message: SystemMessage::Withdraw {
                 amount,
                 owner,
@@ -799,6 +1410,7 @@ where
                             authenticated: false,
                             grant: Amount::ZERO,
                             kind: MessageKind::Tracked,
+                            expiration: None,
                             message: SystemMessage::Credit {
                                 amount,
                                 source: Some(owner),
@@ -818,6 +1430,7 @@ where
                 self.epoch.set(Some(epoch));
                 self.committees.set(committees);
             }
+            SetMinimumVersion { version } => self.minimum_version.set(Some(version)),
             Subscribe { id, subscription } => {
                 ensure!(
                     subscription.chain_id == context.chain_id,
@@ -830,6 +1443,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Protected,
+                    expiration: None,
                     message: SystemMessage::Notify { id },
                 };
                 outcome.messages.push(message);
@@ -845,6 +1459,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Protected,
+                    expiration: None,
                     message: SystemMessage::Notify { id },
                 };
                 outcome.messages.push(message);
@@ -864,6 +1479,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Simple,
+                    expiration: None,
                     message: SystemMessage::BytecodeLocations { locations },
                 };
                 outcome.messages.push(message);
@@ -893,6 +1509,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Simple,
+                    expiration: None,
                     message: SystemMessage::RegisterApplications { applications },
                 };
                 outcome.messages.push(message);
@@ -900,11 +1517,64 @@ where
             OpenChain(_) => {
                 // This special message is executed immediately when cross-chain requests are received.
             }
+            EquivocationReported {
+                validator,
+                evidence,
+            } => {
+                if *self.admin_id.get() == Some(context.chain_id)
+                    && self.equivocations.get(&validator).await?.is_none()
+                {
+                    self.equivocations.insert(&validator, evidence)?;
+                }
+            }
+            SubscribeToTopic { id, .. } | UnsubscribeFromTopic { id, .. } => {
+                // The subscriber is added to or removed from the channel of the application,
+                // in the outcome returned by `SystemMessage::topic_subscription_outcome`.
+                // Notify it about this block, as for the other channels.
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Protected,
+                    expiration: None,
+                    message: SystemMessage::Notify { id },
+                };
+                outcome.messages.push(message);
+            }
             ApplicationCreated | Notify { .. } => (),
         }
         Ok(outcome)
     }
 
+    /// Returns the signer of a committee change if they are an owner of the chain.
+    fn committee_voter(&self, signer: Option<Owner>) -> Result<Owner, SystemExecutionError> {
+        let owner = signer.ok_or(SystemExecutionError::UnauthorizedCommitteeVote)?;
+        ensure!(
+            self.ownership
+                .get()
+                .all_owners()
+                .any(|other| *other == owner),
+            SystemExecutionError::UnauthorizedCommitteeVote
+        );
+        Ok(owner)
+    }
+
+    /// Returns the message notifying the subscribers of the admin chain of the current epoch
+    /// and committees.
+    fn set_committees_message(&self) -> RawOutgoingMessage<SystemMessage, Amount> {
+        RawOutgoingMessage {
+            destination: Destination::Subscribers(SystemChannel::Admin.name()),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Protected,
+            expiration: None,
+            message: SystemMessage::SetCommittees {
+                epoch: self.epoch.get().expect("chain is active"),
+                committees: self.committees.get().clone(),
+            },
+        }
+    }
+
     /// Initializes the system application state on a newly opened chain.
     pub fn initialize_chain(
         &mut self,
@@ -986,6 +1656,7 @@ where
             authenticated: false,
             grant: Amount::ZERO,
             kind: MessageKind::Protected,
+            expiration: None,
             message: SystemMessage::OpenChain(config),
         };
         let subscription = ChannelSubscription {
@@ -997,6 +1668,7 @@ where
             authenticated: false,
             grant: Amount::ZERO,
             kind: MessageKind::Protected,
+            expiration: None,
             message: SystemMessage::Subscribe {
                 id: child_id,
                 subscription,
@@ -1018,6 +1690,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Protected,
+                    expiration: None,
                     message: SystemMessage::Unsubscribe { id, subscription },
                 };
                 messages.push(message);
@@ -1032,6 +1705,7 @@ where
 
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
     use linera_base::{data_types::BlockHeight, identifiers::ApplicationId};
     use linera_views::memory::MemoryContext;
 
@@ -1050,6 +1724,7 @@ mod tests {
             authenticated_signer: None,
             authenticated_caller_id: None,
             height: BlockHeight::from(7),
+            previous_block_hash: None,
             index: Some(2),
             next_message_index: 3,
         };
@@ -1157,4 +1832,398 @@ mod tests {
             SystemMessage::OpenChain(config)
         );
     }
+
+    #[tokio::test]
+    async fn transfer_from_uses_allowance() {
+        let (mut view, context) = new_view_and_context().await;
+        let owner = Owner::from(PublicKey::test_key(1));
+        let spender = Owner::from(PublicKey::test_key(2));
+        view.system
+            .balances
+            .insert(&owner, Amount::from_tokens(10))
+            .unwrap();
+        let recipient = Recipient::chain(ChainId::root(1));
+        let transfer_from = |amount| SystemOperation::TransferFrom {
+            owner,
+            recipient,
+            amount,
+            user_data: UserData::default(),
+        };
+        let owner_context = OperationContext {
+            authenticated_signer: Some(owner),
+            ..context
+        };
+        let spender_context = OperationContext {
+            authenticated_signer: Some(spender),
+            ..context
+        };
+
+        let result = view
+            .system
+            .execute_operation(spender_context, transfer_from(Amount::ONE))
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::InsufficientAllowance { allowance }) if allowance == Amount::ZERO
+        );
+
+        let approve = SystemOperation::Approve {
+            owner,
+            spender,
+            amount: Amount::from_tokens(3),
+        };
+        let result = view
+            .system
+            .execute_operation(spender_context, approve.clone())
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::UnauthenticatedApprovalOwner)
+        );
+        view.system
+            .execute_operation(owner_context, approve)
+            .await
+            .unwrap();
+
+        let (outcome, _) = view
+            .system
+            .execute_operation(spender_context, transfer_from(Amount::from_tokens(2)))
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome.messages[0].message,
+            SystemMessage::Credit {
+                target: None,
+                amount: Amount::from_tokens(2),
+                source: Some(owner),
+            }
+        );
+        assert_eq!(
+            view.system.balances.get(&owner).await.unwrap(),
+            Some(Amount::from_tokens(8))
+        );
+        assert_eq!(
+            view.system.allowances.get(&(owner, spender)).await.unwrap(),
+            Some(Amount::ONE)
+        );
+
+        let result = view
+            .system
+            .execute_operation(spender_context, transfer_from(Amount::from_tokens(2)))
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::InsufficientAllowance { allowance }) if allowance == Amount::ONE
+        );
+    }
+
+    #[tokio::test]
+    async fn transfer_many_credits_each_recipient() {
+        let (mut view, context) = new_view_and_context().await;
+        view.system.balance.set(Amount::from_tokens(5));
+        let transfers = vec![
+            (Recipient::chain(ChainId::root(1)), Amount::from_tokens(1)),
+            (Recipient::Burn, Amount::from_tokens(1)),
+            (Recipient::chain(ChainId::root(2)), Amount::from_tokens(2)),
+        ];
+        let operation = SystemOperation::TransferMany {
+            owner: None,
+            transfers,
+            user_data: UserData::default(),
+        };
+        let (outcome, _) = view
+            .system
+            .execute_operation(context, operation)
+            .await
+            .unwrap();
+        let destinations = outcome
+            .messages
+            .iter()
+            .map(|message| message.destination.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            destinations,
+            vec![
+                Destination::Recipient(ChainId::root(1)),
+                Destination::Recipient(ChainId::root(2)),
+            ]
+        );
+        assert_eq!(*view.system.balance.get(), Amount::ONE);
+    }
+
+    #[tokio::test]
+    async fn reclaim_and_close_chain_transfers_balance() {
+        let (mut view, context) = new_view_and_context().await;
+        view.system.balance.set(Amount::from_tokens(5));
+        let recipient = Account::chain(ChainId::root(1));
+        let operation = SystemOperation::ReclaimAndCloseChain { recipient };
+        let (outcome, _) = view
+            .system
+            .execute_operation(context, operation)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome.messages[0].message,
+            SystemMessage::Credit {
+                target: None,
+                amount: Amount::from_tokens(5),
+                source: None,
+            }
+        );
+        assert_eq!(*view.system.balance.get(), Amount::ZERO);
+        assert!(*view.system.closed.get());
+    }
+
+    #[tokio::test]
+    async fn committee_change_needs_majority_of_owners() {
+        let (mut view, context) = new_view_and_context().await;
+        let owners = (0..3)
+            .map(|index| Owner::from(PublicKey::test_key(index)))
+            .collect::<Vec<_>>();
+        view.system.admin_id.set(Some(context.chain_id));
+        view.system.ownership.set(ChainOwnership::multiple(
+            (0..3).map(|index| (PublicKey::test_key(index), 100)),
+            10,
+            TimeoutConfig::default(),
+        ));
+        let validator = ValidatorName(PublicKey::test_key(10));
+        view.system
+            .committees
+            .set(iter::once((Epoch(1), Committee::make_simple(vec![validator]))).collect());
+        let signed_by = |index: usize| OperationContext {
+            authenticated_signer: Some(owners[index]),
+            ..context
+        };
+        let new_validator = ValidatorName(PublicKey::test_key(11));
+        let change = CommitteeChange::SetValidator {
+            name: new_validator,
+            state: ValidatorState {
+                network_address: "validator-11".to_string(),
+                votes: 1,
+            },
+        };
+
+        let propose = SystemOperation::Admin(AdminOperation::ProposeCommitteeChange {
+            change: change.clone(),
+        });
+        let result = view
+            .system
+            .execute_operation(context, propose.clone())
+            .await;
+        assert_matches!(result, Err(SystemExecutionError::UnauthorizedCommitteeVote));
+        view.system
+            .execute_operation(signed_by(0), propose)
+            .await
+            .unwrap();
+
+        let finalize =
+            SystemOperation::Admin(AdminOperation::FinalizeCommitteeChange { proposal_id: 0 });
+        let result = view
+            .system
+            .execute_operation(signed_by(0), finalize.clone())
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::InsufficientCommitteeVotes {
+                proposal_id: 0,
+                votes: 1
+            })
+        );
+
+        let vote = SystemOperation::Admin(AdminOperation::VoteCommitteeChange { proposal_id: 0 });
+        view.system
+            .execute_operation(signed_by(2), vote)
+            .await
+            .unwrap();
+        let (outcome, _) = view
+            .system
+            .execute_operation(signed_by(1), finalize.clone())
+            .await
+            .unwrap();
+        let expected = change
+            .apply(&Committee::make_simple(vec![validator]))
+            .unwrap();
+        assert_eq!(
+            outcome.messages[0].message,
+            SystemMessage::SetCommittees {
+                epoch: Epoch(2),
+                committees: [
+                    (Epoch(1), Committee::make_simple(vec![validator])),
+                    (Epoch(2), expected),
+                ]
+                .into_iter()
+                .collect(),
+            }
+        );
+        assert_eq!(view.system.committee_proposals.get(&0).await.unwrap(), None);
+
+        let result = view.system.execute_operation(signed_by(1), finalize).await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::UnknownCommitteeProposal(0))
+        );
+    }
+
+    #[tokio::test]
+    async fn minimum_version_is_set_by_admin_chain() {
+        let (mut view, context) = new_view_and_context().await;
+        let version = SoftwareVersion {
+            major: 1,
+            minor: 2,
+            patch: 3,
+        };
+        let operation = SystemOperation::Admin(AdminOperation::SetMinimumVersion { version });
+        let result = view
+            .system
+            .execute_operation(context, operation.clone())
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::AdminOperationOnNonAdminChain)
+        );
+        assert_eq!(*view.system.minimum_version.get(), None);
+
+        view.system.admin_id.set(Some(context.chain_id));
+        let (outcome, _) = view
+            .system
+            .execute_operation(context, operation)
+            .await
+            .unwrap();
+        assert_eq!(*view.system.minimum_version.get(), Some(version));
+        let message = &outcome.messages[0];
+        assert_eq!(
+            message.destination,
+            Destination::Subscribers(SystemChannel::Admin.name())
+        );
+        assert_eq!(
+            message.message,
+            SystemMessage::SetMinimumVersion { version }
+        );
+    }
+
+    #[tokio::test]
+    async fn ownership_transfer_needs_acceptance() {
+        let (mut view, context) = new_view_and_context().await;
+        let old_key = PublicKey::test_key(0);
+        let new_key = PublicKey::test_key(1);
+        view.system.ownership.set(ChainOwnership::single(old_key));
+        let signed_by = |public_key: PublicKey| OperationContext {
+            authenticated_signer: Some(Owner::from(public_key)),
+            ..context
+        };
+
+        let result = view
+            .system
+            .execute_operation(signed_by(new_key), SystemOperation::AcceptOwnership)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::NoPendingOwnershipTransfer)
+        );
+
+        let transfer = SystemOperation::TransferOwnership {
+            new_public_key: new_key,
+        };
+        view.system
+            .execute_operation(signed_by(old_key), transfer.clone())
+            .await
+            .unwrap();
+        let ownership = view.system.ownership.get();
+        assert_eq!(ownership.verify_owner(&Owner::from(old_key)), Some(old_key));
+        assert_eq!(ownership.verify_owner(&Owner::from(new_key)), Some(new_key));
+        let result = view
+            .system
+            .execute_operation(signed_by(old_key), transfer)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::OwnershipTransferPending(key)) if key == new_key
+        );
+
+        let result = view
+            .system
+            .execute_operation(signed_by(old_key), SystemOperation::AcceptOwnership)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::UnauthenticatedOwnershipTransfer(key)) if key == new_key
+        );
+        view.system
+            .execute_operation(signed_by(new_key), SystemOperation::AcceptOwnership)
+            .await
+            .unwrap();
+        let ownership = view.system.ownership.get();
+        assert_eq!(ownership.verify_owner(&Owner::from(old_key)), None);
+        assert_eq!(ownership.verify_owner(&Owner::from(new_key)), Some(new_key));
+        assert_eq!(*view.system.pending_ownership_transfer.get(), None);
+    }
+
+    #[tokio::test]
+    async fn application_registration_needs_permission() {
+        let (mut view, context) = new_view_and_context().await;
+        let owner = Owner::from(PublicKey::test_key(0));
+        view.system
+            .application_permissions
+            .set(ApplicationPermissions {
+                register_applications: Some(vec![owner]),
+                ..ApplicationPermissions::default()
+            });
+        let bytecode_id = BytecodeId::new(MessageId {
+            chain_id: context.chain_id,
+            height: BlockHeight::from(5),
+            index: 0,
+        });
+        let location = BytecodeLocation {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            operation_index: 1,
+        };
+        view.system
+            .registry
+            .register_published_bytecode(bytecode_id, location)
+            .unwrap();
+        let operation = SystemOperation::CreateApplication {
+            bytecode_id,
+            parameters: vec![],
+            instantiation_argument: vec![],
+            required_application_ids: vec![],
+        };
+        let request = SystemOperation::RequestApplication {
+            chain_id: ChainId::root(0),
+            application_id: ApplicationId {
+                bytecode_id,
+                creation: MessageId {
+                    chain_id: ChainId::root(0),
+                    height: BlockHeight::from(1),
+                    index: 0,
+                },
+            },
+        };
+        let signed_by = |signer: Option<Owner>| OperationContext {
+            authenticated_signer: signer,
+            ..context
+        };
+
+        for signer in [None, Some(Owner::from(PublicKey::test_key(1)))] {
+            for operation in [operation.clone(), request.clone()] {
+                let result = view
+                    .system
+                    .execute_operation(signed_by(signer), operation)
+                    .await;
+                assert_matches!(
+                    result,
+                    Err(SystemExecutionError::UnauthorizedApplicationRegistration)
+                );
+            }
+        }
+        let (_, new_application) = view
+            .system
+            .execute_operation(signed_by(Some(owner)), operation)
+            .await
+            .unwrap();
+        assert!(new_application.is_some());
+        view.system
+            .execute_operation(signed_by(Some(owner)), request)
+            .await
+            .unwrap();
+    }
 }
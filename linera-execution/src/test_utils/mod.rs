@@ -50,6 +50,7 @@ pub fn create_dummy_user_application_description(index: u64) -> UserApplicationD
         },
         required_application_ids: vec![],
         parameters: vec![],
+        upgrades: vec![],
     }
 }
 
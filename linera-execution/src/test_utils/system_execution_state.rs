@@ -7,7 +7,7 @@ use std::{
 };
 
 use linera_base::{
-    crypto::CryptoHash,
+    crypto::{CryptoHash, PublicKey},
     data_types::{Amount, ApplicationPermissions, Timestamp},
     identifiers::{ApplicationId, ChainDescription, ChainId, Owner},
     ownership::ChainOwnership,
@@ -20,9 +20,9 @@ use linera_views::{
 
 use crate::{
     applications::ApplicationRegistry,
-    committee::{Committee, Epoch},
+    committee::{Committee, Epoch, ValidatorName},
     execution::UserAction,
-    system::SystemChannel,
+    system::{FeeSource, SystemChannel},
     ChannelSubscription, ExecutionError, ExecutionRuntimeConfig, ExecutionRuntimeContext,
     ExecutionStateView, OperationContext, ResourceControlPolicy, ResourceController,
     ResourceTracker, TestExecutionRuntimeContext, UserApplicationDescription, UserContractCode,
@@ -37,8 +37,12 @@ pub struct SystemExecutionState {
     pub subscriptions: BTreeSet<ChannelSubscription>,
     pub committees: BTreeMap<Epoch, Committee>,
     pub ownership: ChainOwnership,
+    pub pending_ownership_transfer: Option<PublicKey>,
     pub balance: Amount,
     pub balances: BTreeMap<Owner, Amount>,
+    pub allowances: BTreeMap<(Owner, Owner), Amount>,
+    pub fee_source: FeeSource,
+    pub equivocations: BTreeMap<ValidatorName, Vec<u8>>,
     pub timestamp: Timestamp,
     pub registry: ApplicationRegistry,
     pub closed: bool,
@@ -94,8 +98,12 @@ impl SystemExecutionState {
             subscriptions,
             committees,
             ownership,
+            pending_ownership_transfer,
             balance,
             balances,
+            allowances,
+            fee_source,
+            equivocations,
             timestamp,
             registry,
             closed,
@@ -117,6 +125,9 @@ impl SystemExecutionState {
         }
         view.system.committees.set(committees);
         view.system.ownership.set(ownership);
+        view.system
+            .pending_ownership_transfer
+            .set(pending_ownership_transfer);
         view.system.balance.set(balance);
         for (owner, balance) in balances {
             view.system
@@ -124,6 +135,19 @@ impl SystemExecutionState {
                 .insert(&owner, balance)
                 .expect("insertion of balances should not fail");
         }
+        for (owner_and_spender, allowance) in allowances {
+            view.system
+                .allowances
+                .insert(&owner_and_spender, allowance)
+                .expect("insertion of allowances should not fail");
+        }
+        view.system.fee_source.set(fee_source);
+        for (validator, evidence) in equivocations {
+            view.system
+                .equivocations
+                .insert(&validator, evidence)
+                .expect("insertion of equivocations should not fail");
+        }
         view.system.timestamp.set(timestamp);
         view.system
             .registry
@@ -0,0 +1,34 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{check_metric_name, MAX_METRIC_NAME_LENGTH};
+use crate::ExecutionError;
+
+#[test]
+fn test_valid_metric_names() {
+    for name in ["transfers", "Total_Volume_2", "_"] {
+        assert!(check_metric_name(name).is_ok(), "{name:?} should be valid");
+    }
+    assert!(check_metric_name(&"a".repeat(MAX_METRIC_NAME_LENGTH)).is_ok());
+}
+
+#[test]
+fn test_invalid_metric_names() {
+    let too_long = "a".repeat(MAX_METRIC_NAME_LENGTH + 1);
+    for name in [
+        "",
+        "with space",
+        "dashed-name",
+        "näme",
+        "quote\"",
+        too_long.as_str(),
+    ] {
+        assert!(
+            matches!(
+                check_metric_name(name),
+                Err(ExecutionError::InvalidMetricName(invalid)) if invalid == name
+            ),
+            "{name:?} should be invalid"
+        );
+    }
+}
@@ -38,6 +38,7 @@ fn app_description(index: u32, deps: Vec<u32>) -> UserApplicationDescription {
         creation: message_id(index),
         parameters: vec![],
         required_application_ids: deps.into_iter().map(app_id).collect(),
+        upgrades: vec![],
     }
 }
 
@@ -124,3 +125,66 @@ async fn test_topological_sort_with_loop() {
         Vec::from_iter([2, 3, 1, 0].into_iter().map(app_id))
     );
 }
+
+#[tokio::test]
+async fn test_upgrades_and_pinned_versions() {
+    let mut view = ApplicationRegistryView::new().await;
+    view.register_published_bytecode(bytecode_id(1), location(1))
+        .unwrap();
+    view.register_published_bytecode(bytecode_id(2), location(2))
+        .unwrap();
+    view.register_application(app_description(0, vec![]))
+        .await
+        .unwrap();
+    let id = app_id(0);
+
+    let version = view
+        .upgrade_application(id, bytecode_id(1), b"first".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(version, 1);
+    let version = view
+        .upgrade_application(id, bytecode_id(2), b"second".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(version, 2);
+    let description = view.describe_application(id).await.unwrap();
+    assert_eq!(
+        description.bytecode_at(2),
+        Some((bytecode_id(2), location(2)))
+    );
+    assert_eq!(description.bytecode_at(3), None);
+
+    view.pin_version(id, Some(1)).await.unwrap();
+    assert_eq!(
+        view.pending_migrations(id).await.unwrap(),
+        vec![(1, b"first".to_vec())]
+    );
+    view.set_version(&id, 1).unwrap();
+    assert!(view.pending_migrations(id).await.unwrap().is_empty());
+    // The state was migrated, so the chain can't go back to the original bytecode.
+    assert!(view.pin_version(id, Some(0)).await.is_err());
+    assert!(view.pin_version(id, Some(3)).await.is_err());
+    view.pin_version(id, None).await.unwrap();
+    assert_eq!(
+        view.pending_migrations(id).await.unwrap(),
+        vec![(2, b"second".to_vec())]
+    );
+
+    // A chain that learns about the application has no state to migrate.
+    let mut other_view = ApplicationRegistryView::new().await;
+    other_view
+        .register_application(description.clone())
+        .await
+        .unwrap();
+    assert_eq!(other_view.version(&id).await.unwrap(), 2);
+    // Outdated descriptions don't undo the upgrades.
+    other_view
+        .register_application(app_description(0, vec![]))
+        .await
+        .unwrap();
+    assert_eq!(
+        other_view.describe_application(id).await.unwrap(),
+        description
+    );
+}
@@ -5,6 +5,9 @@
 
 #![cfg(with_tokio_multi_thread)]
 
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
 use futures::{channel::mpsc, StreamExt};
 use linera_base::{
     data_types::BlockHeight,
@@ -14,8 +17,8 @@ use linera_views::batch::Batch;
 
 use super::{ApplicationStatus, SyncRuntime, SyncRuntimeInternal};
 use crate::{
-    execution_state_actor::Request, runtime::ResourceController, ContractRuntime,
-    RawExecutionOutcome, UserContractInstance,
+    execution_state_actor::Request, runtime::ResourceController, ContractRuntime, ExecutionError,
+    RawExecutionOutcome, ResourceControlPolicy, UserContractInstance,
 };
 
 /// Test writing a batch of changes.
@@ -62,7 +65,7 @@ async fn test_write_batch() {
         assert_eq!(batch, expected_batch);
 
         callback
-            .send(())
+            .send(expected_bytes_count as u64)
             .expect("Failed to notify that writing the batch finished");
     });
 
@@ -80,6 +83,44 @@ async fn test_write_batch() {
     );
 }
 
+/// Test writing a batch of changes that makes the application exceed its storage quota.
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_write_batch_above_storage_quota() {
+    let (mut runtime, mut execution_state_receiver) = create_contract_runtime();
+    runtime.resource_controller.policy = Arc::new(ResourceControlPolicy {
+        maximum_bytes_stored_per_application: 10,
+        ..ResourceControlPolicy::default()
+    });
+    let mut runtime = SyncRuntime::new(runtime);
+    let expected_application_id = runtime.inner().current_application().id;
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 2, 3], vec![0; 8]);
+
+    tokio::spawn(async move {
+        let request = execution_state_receiver
+            .next()
+            .await
+            .expect("Missing expected request to write a batch");
+        let Request::WriteBatch { callback, .. } = request else {
+            panic!("Expected a `Request::WriteBatch` but got {request:?} instead");
+        };
+        callback
+            .send(11)
+            .expect("Failed to notify that writing the batch finished");
+    });
+
+    let result = runtime.write_batch(batch);
+
+    assert_matches!(
+        result,
+        Err(ExecutionError::StorageQuotaExceeded {
+            application_id,
+            stored_bytes: 11,
+            maximum: 10,
+        }) if *application_id == expected_application_id
+    );
+}
+
 /// Creates a [`SyncRuntimeInternal`] instance for contracts, and returns it and the receiver
 /// endpoint for the requests the runtime sends to the [`ExecutionStateView`] actor.
 fn create_contract_runtime() -> (
@@ -0,0 +1,147 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An optional on-disk cache of compiled WebAssembly modules.
+//!
+//! When a directory is configured with [`set_wasm_artifact_directory`], the modules compiled by
+//! the runtimes are saved in it, so that a restarted process loads them instead of compiling
+//! them again. Artifacts are addressed by the hash of the bytecode they were compiled from, and
+//! grouped by the version of this crate, since the way bytecodes are compiled and instrumented
+//! may change between versions.
+//!
+//! Compiled modules are native code that is loaded without any verification, so the directory
+//! must only be writable by the process using it. An artifact that can't be loaded, e.g. because
+//! it was written by another version of the runtime, is compiled again and replaced.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use linera_base::crypto::CryptoHash;
+use once_cell::sync::OnceCell;
+use tracing::warn;
+
+/// The directory where compiled modules are saved, if enabled.
+static ARTIFACT_DIRECTORY: OnceCell<PathBuf> = OnceCell::new();
+
+/// Enables saving the compiled WebAssembly modules in `directory`, and loading them from it.
+///
+/// This can only be set once per process, before any application is loaded.
+pub fn set_wasm_artifact_directory(directory: impl AsRef<Path>) -> io::Result<()> {
+    let directory = directory
+        .as_ref()
+        .join(concat!("v", env!("CARGO_PKG_VERSION")));
+    fs::create_dir_all(&directory)?;
+    ARTIFACT_DIRECTORY.set(directory).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "the Wasm artifact directory is already set",
+        )
+    })
+}
+
+/// Returns the module compiled from the bytecode with the given `hash`, loading it from the
+/// artifact directory if possible and otherwise compiling it with `compile` and saving it.
+///
+/// The `kind` distinguishes the modules compiled differently from the same bytecode, e.g. by
+/// different runtimes or engines. `deserialize` must only accept bytes returned by `serialize`
+/// for the same `kind`.
+pub(crate) fn load_or_compile<Module>(
+    kind: &str,
+    hash: CryptoHash,
+    compile: impl FnOnce() -> anyhow::Result<Module>,
+    serialize: impl FnOnce(&Module) -> anyhow::Result<Vec<u8>>,
+    deserialize: impl FnOnce(&[u8]) -> anyhow::Result<Module>,
+) -> anyhow::Result<Module> {
+    load_or_compile_in(
+        ARTIFACT_DIRECTORY.get().map(PathBuf::as_path),
+        kind,
+        hash,
+        compile,
+        serialize,
+        deserialize,
+    )
+}
+
+fn load_or_compile_in<Module>(
+    directory: Option<&Path>,
+    kind: &str,
+    hash: CryptoHash,
+    compile: impl FnOnce() -> anyhow::Result<Module>,
+    serialize: impl FnOnce(&Module) -> anyhow::Result<Vec<u8>>,
+    deserialize: impl FnOnce(&[u8]) -> anyhow::Result<Module>,
+) -> anyhow::Result<Module> {
+    let Some(directory) = directory else {
+        return compile();
+    };
+    let path = directory.join(format!("{kind}-{hash}"));
+    if let Ok(bytes) = fs::read(&path) {
+        match deserialize(&bytes) {
+            Ok(module) => return Ok(module),
+            Err(error) => warn!(
+                "Recompiling invalid Wasm artifact {}: {error}",
+                path.display()
+            ),
+        }
+    }
+    let module = compile()?;
+    if let Err(error) = serialize(&module).and_then(|bytes| save(&path, &bytes)) {
+        warn!("Failed to save Wasm artifact {}: {error}", path.display());
+    }
+    Ok(module)
+}
+
+/// Writes an artifact so that concurrent readers never see a partial file.
+fn save(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let temporary_path = path.with_extension(format!("tmp-{}", std::process::id()));
+    fs::write(&temporary_path, bytes)?;
+    fs::rename(temporary_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::crypto::CryptoHash;
+
+    use super::load_or_compile_in;
+
+    fn load(directory: &std::path::Path, expected_compilation: bool) -> Vec<u8> {
+        let mut compiled = false;
+        let module = load_or_compile_in(
+            Some(directory),
+            "test",
+            CryptoHash::test_hash("bytecode"),
+            || {
+                compiled = true;
+                Ok(b"module".to_vec())
+            },
+            |module| Ok(module.clone()),
+            |bytes| {
+                anyhow::ensure!(bytes == b"module", "corrupted artifact");
+                Ok(bytes.to_vec())
+            },
+        )
+        .unwrap();
+        assert_eq!(compiled, expected_compilation);
+        module
+    }
+
+    /// Tests that a module is only compiled if its artifact is missing or invalid.
+    #[test]
+    fn compiled_modules_are_reused() {
+        let directory = tempfile::tempdir().unwrap();
+        assert_eq!(load(directory.path(), true), b"module");
+        assert_eq!(load(directory.path(), false), b"module");
+
+        let artifact = std::fs::read_dir(directory.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        std::fs::write(artifact, b"garbage").unwrap();
+        assert_eq!(load(directory.path(), true), b"module");
+        assert_eq!(load(directory.path(), false), b"module");
+    }
+}
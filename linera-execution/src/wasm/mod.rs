@@ -10,11 +10,14 @@
 
 #![cfg(with_wasm_runtime)]
 
+mod artifact_cache;
 mod entrypoints;
 mod module_cache;
+mod sandbox;
 mod sanitizer;
 #[macro_use]
 mod system_api;
+mod validation;
 #[cfg(with_wasmer)]
 mod wasmer;
 #[cfg(with_wasmtime)]
@@ -35,11 +38,13 @@ use wasmer::{WasmerContractInstance, WasmerServiceInstance};
 #[cfg(with_wasmtime)]
 use wasmtime::{WasmtimeContractInstance, WasmtimeServiceInstance};
 
-use self::sanitizer::sanitize;
 pub use self::{
+    artifact_cache::set_wasm_artifact_directory,
     entrypoints::{ContractEntrypoints, ServiceEntrypoints},
     system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi},
+    validation::{validate_bytecodes, BytecodeKind, BytecodeValidationError},
 };
+use self::{sandbox::limit_memory, sanitizer::sanitize};
 use crate::{
     Bytecode, ContractSyncRuntime, ExecutionError, ServiceSyncRuntime, UserContractInstance,
     UserContractModule, UserServiceInstance, UserServiceModule, WasmRuntime,
@@ -96,6 +101,8 @@ impl WasmContractModule {
         } else {
             contract_bytecode
         };
+        let contract_bytecode =
+            limit_memory(contract_bytecode).map_err(WasmExecutionError::LoadContractModule)?;
         match runtime {
             #[cfg(with_wasmer)]
             WasmRuntime::Wasmer | WasmRuntime::WasmerWithSanitizer => {
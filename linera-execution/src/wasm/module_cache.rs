@@ -3,12 +3,13 @@
 
 //! A cache of compiled WebAssembly modules.
 //!
-//! The cache is limited by the total size of cached bytecodes. Note that this is a heuristic to
-//! estimate the total memory usage by the cache, since it's currently not possible to determine
-//! the size of a generic `Module`.
+//! Modules are addressed by the hash of their bytecode. The cache is limited by the total size
+//! of cached bytecodes. Note that this is a heuristic to estimate the total memory usage by the
+//! cache, since it's currently not possible to determine the size of a generic `Module`.
 
 use std::sync::Arc;
 
+use linera_base::crypto::CryptoHash;
 use lru::LruCache;
 
 use crate::Bytecode;
@@ -18,13 +19,19 @@ const DEFAULT_MAX_CACHE_SIZE: u64 = 512 /* MiB */ * 1024 /* KiB */ * 1024 /* byt
 
 /// A cache of compiled WebAssembly modules.
 ///
-/// The cache prioritizes entries based on their [`Metadata`].
+/// The least recently used modules are evicted first.
 pub struct ModuleCache<Module> {
-    modules: LruCache<Bytecode, Arc<Module>>,
+    modules: LruCache<CryptoHash, CachedModule<Module>>,
     total_size: u64,
     max_size: u64,
 }
 
+/// A compiled module, with the size of the bytecode it was compiled from.
+struct CachedModule<Module> {
+    module: Arc<Module>,
+    bytecode_size: u64,
+}
+
 impl<Module> Default for ModuleCache<Module> {
     fn default() -> Self {
         ModuleCache {
@@ -38,47 +45,58 @@ impl<Module> Default for ModuleCache<Module> {
 impl<Module> ModuleCache<Module> {
     /// Returns a `Module` for the requested `bytecode`, creating it with `module_builder` and
     /// adding it to the cache if it doesn't already exist in the cache.
+    ///
+    /// The `module_builder` also receives the hash addressing the bytecode.
     pub fn get_or_insert_with<E>(
         &mut self,
         bytecode: Bytecode,
-        module_builder: impl FnOnce(Bytecode) -> Result<Module, E>,
+        module_builder: impl FnOnce(Bytecode, CryptoHash) -> Result<Module, E>,
     ) -> Result<Arc<Module>, E> {
-        if let Some(module) = self.get(&bytecode) {
+        let hash = CryptoHash::new(&bytecode);
+        if let Some(module) = self.get(&hash) {
             Ok(module)
         } else {
-            let module = Arc::new(module_builder(bytecode.clone())?);
-            self.insert(bytecode, module.clone());
+            let bytecode_size = bytecode.as_ref().len() as u64;
+            let module = Arc::new(module_builder(bytecode, hash)?);
+            self.insert(hash, bytecode_size, module.clone());
             Ok(module)
         }
     }
 
-    /// Returns a `Module` for the requested `bytecode` if it's in the cache.
-    pub fn get(&mut self, bytecode: &Bytecode) -> Option<Arc<Module>> {
-        self.modules.get(bytecode).cloned()
+    /// Returns the `Module` compiled from the bytecode with the given `hash` if it's in the
+    /// cache.
+    pub fn get(&mut self, hash: &CryptoHash) -> Option<Arc<Module>> {
+        self.modules
+            .get(hash)
+            .map(|cached_module| cached_module.module.clone())
     }
 
-    /// Inserts a `bytecode` and its compiled `module` in the cache.
-    pub fn insert(&mut self, bytecode: Bytecode, module: Arc<Module>) {
-        let bytecode_size = bytecode.as_ref().len() as u64;
-
+    /// Inserts the `module` compiled from a bytecode with the given `hash` and size in the
+    /// cache.
+    pub fn insert(&mut self, hash: CryptoHash, bytecode_size: u64, module: Arc<Module>) {
         if self.total_size + bytecode_size > self.max_size {
-            self.reduce_size_to(self.max_size - bytecode_size);
+            self.reduce_size_to(self.max_size.saturating_sub(bytecode_size));
         }
 
-        self.modules.put(bytecode, module);
+        let cached_module = CachedModule {
+            module,
+            bytecode_size,
+        };
+        if let Some(replaced) = self.modules.put(hash, cached_module) {
+            self.total_size -= replaced.bytecode_size;
+        }
+        self.total_size += bytecode_size;
     }
 
     /// Evicts entries from the cache so that the total size of cached bytecodes is less than
     /// `new_size`.
     fn reduce_size_to(&mut self, new_size: u64) {
         while self.total_size > new_size {
-            let (bytecode, _module) = self
+            let (_hash, cached_module) = self
                 .modules
                 .pop_lru()
                 .expect("Empty cache should have a `total_size` of zero");
-            let bytecode_size = bytecode.as_ref().len() as u64;
-
-            self.total_size -= bytecode_size;
+            self.total_size -= cached_module.bytecode_size;
         }
     }
 }
@@ -0,0 +1,179 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Limits of the sandbox in which application contracts are executed, so that malicious bytecode
+//! can't exhaust the memory or the stack of the validators.
+//!
+//! The linear memory is limited by rewriting the maximum size declared by the module before it is
+//! compiled, so that growing the memory further fails in the same way in both runtimes. The stack
+//! is limited by the configuration of the runtime engines. The time of a transaction is limited
+//! by the runtime, which checks its deadline at each call to the system API, including the calls
+//! consuming fuel, and by the epoch interruptions of Wasmtime.
+
+use anyhow::ensure;
+use wasm_encoder::Encode;
+use wasmparser::{Parser, Payload};
+
+use super::WasmExecutionError;
+use crate::{Bytecode, ContractRuntime, ExecutionError, SandboxResource};
+
+/// The maximum number of 64 KiB pages of the linear memory of a contract, i.e. 256 MiB.
+pub const MAXIMUM_CONTRACT_MEMORY_PAGES: u32 = 4_096;
+
+/// The maximum size in bytes of the stack used by the nested Wasm function calls of a contract.
+///
+/// This is also the size of the stacks that Wasmer runs its calls on.
+pub const MAXIMUM_CONTRACT_STACK_SIZE: usize = 1 << 20;
+
+/// Limits the linear memories defined by a contract `bytecode` to
+/// [`MAXIMUM_CONTRACT_MEMORY_PAGES`].
+///
+/// Fails if the contract needs more memory than that to start.
+pub fn limit_memory(bytecode: Bytecode) -> Result<Bytecode, anyhow::Error> {
+    let maximum_pages = u64::from(MAXIMUM_CONTRACT_MEMORY_PAGES);
+    let bytes = bytecode.as_ref();
+
+    for payload in Parser::new(0).parse_all(bytes) {
+        let Payload::MemorySection(memories) = payload? else {
+            continue;
+        };
+        let range = memories.range();
+        let mut section = wasm_encoder::MemorySection::new();
+
+        for memory in memories {
+            let memory = memory?;
+            ensure!(
+                memory.initial <= maximum_pages,
+                "Contract needs {} pages of memory, more than the maximum of {maximum_pages}",
+                memory.initial
+            );
+            section.memory(wasm_encoder::MemoryType {
+                minimum: memory.initial,
+                maximum: Some(
+                    memory
+                        .maximum
+                        .map_or(maximum_pages, |maximum| maximum.min(maximum_pages)),
+                ),
+                memory64: memory.memory64,
+                shared: memory.shared,
+            });
+        }
+
+        let size_prefix_length = match range.len() {
+            0x00..=0x7f => 1,
+            0x80..=0x3fff => 2,
+            0x4000..=0x1f_ffff => 3,
+            0x20_0000..=0x0fff_ffff => 4,
+            0x1000_0000.. => 5,
+        };
+        let mut limited_bytes = bytes[..range.start - size_prefix_length].to_vec();
+        section.encode(&mut limited_bytes);
+        limited_bytes.extend(&bytes[range.end..]);
+
+        return Ok(Bytecode::new(limited_bytes));
+    }
+
+    Ok(bytecode)
+}
+
+/// Converts the `error` of a failed call to a contract into an [`ExecutionError`], reporting if
+/// the contract exhausted its sandbox.
+///
+/// A call that fails while all the linear memory is in use is assumed to have failed to
+/// allocate more.
+pub fn call_error(
+    runtime: &mut impl ContractRuntime,
+    error: WasmExecutionError,
+    is_stack_overflow: bool,
+    memory_pages: Option<u64>,
+) -> ExecutionError {
+    if let Err(error) = runtime.remaining_time() {
+        return error;
+    }
+    if is_stack_overflow {
+        return ExecutionError::ResourceExceeded {
+            resource: SandboxResource::Stack,
+            limit: MAXIMUM_CONTRACT_STACK_SIZE as u64,
+        };
+    }
+    if memory_pages >= Some(u64::from(MAXIMUM_CONTRACT_MEMORY_PAGES)) {
+        return ExecutionError::ResourceExceeded {
+            resource: SandboxResource::Memory,
+            limit: u64::from(MAXIMUM_CONTRACT_MEMORY_PAGES),
+        };
+    }
+    error.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmparser::{Parser, Payload};
+
+    use super::{limit_memory, MAXIMUM_CONTRACT_MEMORY_PAGES};
+    use crate::Bytecode;
+
+    /// Returns a module with a single memory of the given `minimum` and `maximum` sizes.
+    fn module_with_memory(minimum: u64, maximum: Option<u64>) -> Bytecode {
+        let mut memories = wasm_encoder::MemorySection::new();
+        memories.memory(wasm_encoder::MemoryType {
+            minimum,
+            maximum,
+            memory64: false,
+            shared: false,
+        });
+        let mut module = wasm_encoder::Module::new();
+        module.section(&memories);
+        Bytecode::new(module.finish())
+    }
+
+    /// Returns the minimum and maximum sizes of the memories of a module.
+    fn memory_limits(bytecode: &Bytecode) -> Vec<(u64, Option<u64>)> {
+        let mut limits = Vec::new();
+        for payload in Parser::new(0).parse_all(bytecode.as_ref()) {
+            if let Payload::MemorySection(memories) = payload.unwrap() {
+                for memory in memories {
+                    let memory = memory.unwrap();
+                    limits.push((memory.initial, memory.maximum));
+                }
+            }
+        }
+        limits
+    }
+
+    /// Tests that the memories of a module get the maximum size of the sandbox, unless they
+    /// already declare a smaller one.
+    #[test]
+    fn limits_the_maximum_memory() {
+        let maximum = u64::from(MAXIMUM_CONTRACT_MEMORY_PAGES);
+
+        let bytecode = limit_memory(module_with_memory(17, None)).unwrap();
+        assert_eq!(memory_limits(&bytecode), vec![(17, Some(maximum))]);
+
+        let bytecode = limit_memory(module_with_memory(17, Some(maximum * 2))).unwrap();
+        assert_eq!(memory_limits(&bytecode), vec![(17, Some(maximum))]);
+
+        let bytecode = limit_memory(module_with_memory(17, Some(20))).unwrap();
+        assert_eq!(memory_limits(&bytecode), vec![(17, Some(20))]);
+    }
+
+    /// Tests that the example application is still a valid module after its memory is limited.
+    #[test]
+    fn keeps_the_example_application_valid() {
+        let contract =
+            Bytecode::new(include_bytes!("../../tests/fixtures/counter_contract.wasm").to_vec());
+        let bytecode = limit_memory(contract).unwrap();
+        wasmparser::Validator::new()
+            .validate_all(bytecode.as_ref())
+            .unwrap();
+        assert!(memory_limits(&bytecode)
+            .iter()
+            .all(|(_, maximum)| *maximum <= Some(u64::from(MAXIMUM_CONTRACT_MEMORY_PAGES))));
+    }
+
+    /// Tests that a module needing more memory than the sandbox allows is rejected.
+    #[test]
+    fn rejects_too_large_initial_memory() {
+        let pages = u64::from(MAXIMUM_CONTRACT_MEMORY_PAGES) + 1;
+        assert!(limit_memory(module_with_memory(pages, None)).is_err());
+    }
+}
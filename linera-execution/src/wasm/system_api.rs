@@ -4,8 +4,9 @@
 use std::{any::Any, collections::HashMap, marker::PhantomData};
 
 use linera_base::{
+    crypto::CryptoHash,
     data_types::{Amount, BlockHeight, SendMessageRequest, Timestamp},
-    identifiers::{Account, ApplicationId, ChainId, ChannelName, MessageId, Owner},
+    identifiers::{Account, ApplicationId, BlobId, ChainId, ChannelName, MessageId, Owner},
     ownership::{ChainOwnership, CloseChainError},
 };
 use linera_views::batch::{Batch, WriteOperation};
@@ -71,6 +72,32 @@ impl<Runtime> SystemApiData<Runtime> {
     }
 }
 
+/// Charges the fuel of a call from a contract to the system API.
+fn charge_contract_call<Caller, Runtime>(caller: &mut Caller) -> Result<(), RuntimeError>
+where
+    Caller: Instance<UserData = SystemApiData<Runtime>>,
+    Runtime: ContractRuntime,
+{
+    caller
+        .user_data_mut()
+        .runtime
+        .track_host_call()
+        .map_err(|error| RuntimeError::Custom(error.into()))
+}
+
+/// Charges the fuel of a call to the view storage API, if it comes from a contract.
+fn charge_view_call<Caller, Runtime>(caller: &mut Caller) -> Result<(), RuntimeError>
+where
+    Caller: Instance<UserData = SystemApiData<Runtime>>,
+    Runtime: WriteBatch,
+{
+    caller
+        .user_data_mut()
+        .runtime
+        .track_host_call()
+        .map_err(|error| RuntimeError::Custom(error.into()))
+}
+
 /// An implementation of the system API made available to contracts.
 #[derive(Default)]
 pub struct ContractSystemApi<Caller>(PhantomData<Caller>);
@@ -83,6 +110,7 @@ where
 {
     /// Returns the ID of the current chain.
     fn get_chain_id(caller: &mut Caller) -> Result<ChainId, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -92,6 +120,7 @@ where
 
     /// Returns the height of the current block that is executing.
     fn get_block_height(caller: &mut Caller) -> Result<BlockHeight, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -101,6 +130,7 @@ where
 
     /// Returns the ID of the current application.
     fn get_application_id(caller: &mut Caller) -> Result<ApplicationId, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -110,6 +140,7 @@ where
 
     /// Returns the application parameters provided when the application was created.
     fn application_parameters(caller: &mut Caller) -> Result<Vec<u8>, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -119,6 +150,7 @@ where
 
     /// Returns the authenticated signer for this execution, if there is one.
     fn authenticated_signer(caller: &mut Caller) -> Result<Option<Owner>, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -128,6 +160,7 @@ where
 
     /// Retrieves the current system time, i.e. the timestamp of the block in which this is called.
     fn read_system_timestamp(caller: &mut Caller) -> Result<Timestamp, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -138,6 +171,7 @@ where
     /// Returns the ID of the incoming message that is being handled, or [`None`] if not executing
     /// an incoming message.
     fn get_message_id(caller: &mut Caller) -> Result<Option<MessageId>, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -149,6 +183,7 @@ where
     /// is now bouncing back, `Some(false)` if the message is being currently being delivered to
     /// its original destination, or [`None`] if not executing an incoming message.
     fn message_is_bouncing(caller: &mut Caller) -> Result<Option<bool>, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -158,6 +193,7 @@ where
 
     /// Returns the authenticated caller ID, if the caller configured it and if the current context.
     fn authenticated_caller_id(caller: &mut Caller) -> Result<Option<ApplicationId>, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -167,6 +203,7 @@ where
 
     /// Returns the current chain balance.
     fn read_chain_balance(caller: &mut Caller) -> Result<Amount, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -176,6 +213,7 @@ where
 
     /// Returns the balance of one of the accounts on this chain.
     fn read_owner_balance(caller: &mut Caller, owner: Owner) -> Result<Amount, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -188,6 +226,7 @@ where
         caller: &mut Caller,
         message: SendMessageRequest<Vec<u8>>,
     ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -201,6 +240,7 @@ where
         chain: ChainId,
         channel: ChannelName,
     ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -214,6 +254,7 @@ where
         chain: ChainId,
         channel: ChannelName,
     ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -229,6 +270,7 @@ where
         destination: Account,
         amount: Amount,
     ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -243,6 +285,7 @@ where
         destination: Account,
         amount: Amount,
     ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -252,6 +295,7 @@ where
 
     /// Retrieves the owner configuration for the current chain.
     fn get_chain_ownership(caller: &mut Caller) -> Result<ChainOwnership, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -266,6 +310,7 @@ where
         chain_ownership: ChainOwnership,
         balance: Amount,
     ) -> Result<ChainId, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -276,6 +321,7 @@ where
     /// Closes the current chain. Returns an error if the application doesn't have
     /// permission to do so.
     fn close_chain(caller: &mut Caller) -> Result<Result<(), CloseChainError>, RuntimeError> {
+        charge_contract_call(caller)?;
         match caller.user_data_mut().runtime.close_chain() {
             Ok(()) => Ok(Ok(())),
             Err(ExecutionError::UnauthorizedApplication(_)) => {
@@ -292,6 +338,7 @@ where
         callee_id: ApplicationId,
         argument: Vec<u8>,
     ) -> Result<Vec<u8>, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -305,6 +352,7 @@ where
         application_id: ApplicationId,
         query: Vec<u8>,
     ) -> Result<Vec<u8>, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -314,6 +362,7 @@ where
 
     /// Makes a GET request to the given URL and returns the JSON part, if any.
     fn fetch_json(caller: &mut Caller, query: String) -> Result<String, RuntimeError> {
+        charge_contract_call(caller)?;
         caller
             .user_data_mut()
             .runtime
@@ -321,8 +370,19 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Reads the content of the blob with the given ID.
+    fn read_blob(caller: &mut Caller, blob_id: BlobId) -> Result<Vec<u8>, RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime
+            .read_blob(blob_id)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Logs a `message` with the provided information `level`.
-    fn log(_caller: &mut Caller, message: String, level: log::Level) -> Result<(), RuntimeError> {
+    fn log(caller: &mut Caller, message: String, level: log::Level) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
         match level {
             log::Level::Trace => tracing::trace!("{message}"),
             log::Level::Debug => tracing::debug!("{message}"),
@@ -333,6 +393,108 @@ where
         Ok(())
     }
 
+    /// Increments the application's counter `name` by `value`.
+    fn increment_counter(
+        caller: &mut Caller,
+        name: String,
+        value: u64,
+    ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .increment_counter(name, value)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Sets the application's gauge `name` to `value`.
+    fn set_gauge(caller: &mut Caller, name: String, value: i64) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .set_gauge(name, value)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Emits an event with the given `key` and `value`, to be recorded in the block.
+    fn emit(caller: &mut Caller, key: Vec<u8>, value: Vec<u8>) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .emit(key, value)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Publishes a `payload` to a `topic` of the current application.
+    fn publish_to_topic(
+        caller: &mut Caller,
+        topic: String,
+        payload: Vec<u8>,
+    ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .publish_to_topic(topic, payload)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Subscribes to a `topic` of the current application on another chain.
+    fn subscribe_to_topic(
+        caller: &mut Caller,
+        chain: ChainId,
+        topic: String,
+    ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .subscribe_to_topic(chain, topic)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Unsubscribes from a `topic` of the current application on another chain.
+    fn unsubscribe_from_topic(
+        caller: &mut Caller,
+        chain: ChainId,
+        topic: String,
+    ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .unsubscribe_from_topic(chain, topic)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Marks the `key` of the application's state as ephemeral, to be deleted before the
+    /// block at `height` is executed.
+    fn expire_key(
+        caller: &mut Caller,
+        key: Vec<u8>,
+        height: BlockHeight,
+    ) -> Result<(), RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .expire_key(key, height)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Returns a new pseudo-random value, derived deterministically from the current
+    /// transaction. It is not secret.
+    fn random_value(caller: &mut Caller) -> Result<CryptoHash, RuntimeError> {
+        charge_contract_call(caller)?;
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .random_value()
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Consume some fuel.
     ///
     /// This is intended for the metering instrumentation, but if the user wants to donate
@@ -481,6 +643,15 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Reads the content of the blob with the given ID.
+    fn read_blob(caller: &mut Caller, blob_id: BlobId) -> Result<Vec<u8>, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .read_blob(blob_id)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Logs a `message` with the provided information `level`.
     fn log(_caller: &mut Caller, message: String, level: log::Level) -> Result<(), RuntimeError> {
         match level {
@@ -492,6 +663,18 @@ where
         }
         Ok(())
     }
+
+    /// Consume some fuel.
+    ///
+    /// This is intended for the metering instrumentation, which limits the fuel used by a
+    /// query.
+    fn consume_fuel(caller: &mut Caller, fuel: u64) -> Result<(), RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .consume_fuel(fuel)
+            .map_err(|e| RuntimeError::Custom(e.into()))
+    }
 }
 
 /// An implementation of the system API used to access the view storage for both contracts and
@@ -507,6 +690,7 @@ where
 {
     /// Creates a new promise to check if the `key` is in storage.
     fn contains_key_new(caller: &mut Caller, key: Vec<u8>) -> Result<u32, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data
             .runtime
@@ -518,6 +702,7 @@ where
 
     /// Waits for the promise to check if the `key` is in storage.
     fn contains_key_wait(caller: &mut Caller, promise_id: u32) -> Result<bool, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
@@ -531,6 +716,7 @@ where
         caller: &mut Caller,
         keys: Vec<Vec<u8>>,
     ) -> Result<u32, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data
             .runtime
@@ -545,6 +731,7 @@ where
         caller: &mut Caller,
         promise_id: u32,
     ) -> Result<Vec<Option<Vec<u8>>>, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
@@ -555,6 +742,7 @@ where
 
     /// Creates a new promise to read a single entry from storage.
     fn read_value_bytes_new(caller: &mut Caller, key: Vec<u8>) -> Result<u32, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data
             .runtime
@@ -569,6 +757,7 @@ where
         caller: &mut Caller,
         promise_id: u32,
     ) -> Result<Option<Vec<u8>>, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
@@ -579,6 +768,7 @@ where
 
     /// Creates a new promise to search for keys that start with the `key_prefix`.
     fn find_keys_new(caller: &mut Caller, key_prefix: Vec<u8>) -> Result<u32, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data
             .runtime
@@ -590,6 +780,7 @@ where
 
     /// Waits for the promise to search for keys that start with the `key_prefix`.
     fn find_keys_wait(caller: &mut Caller, promise_id: u32) -> Result<Vec<Vec<u8>>, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
@@ -600,6 +791,7 @@ where
 
     /// Creates a new promise to search for entries whose keys that start with the `key_prefix`.
     fn find_key_values_new(caller: &mut Caller, key_prefix: Vec<u8>) -> Result<u32, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data
             .runtime
@@ -615,6 +807,7 @@ where
         caller: &mut Caller,
         promise_id: u32,
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RuntimeError> {
+        charge_view_call(caller)?;
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
@@ -628,6 +821,7 @@ where
         caller: &mut Caller,
         operations: Vec<WriteOperation>,
     ) -> Result<(), RuntimeError> {
+        charge_view_call(caller)?;
         WriteBatch::write_batch(&mut caller.user_data_mut().runtime, Batch { operations })
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -640,16 +834,28 @@ where
 pub trait WriteBatch {
     /// Writes a [`Batch`] of operations to storage.
     fn write_batch(&mut self, batch: Batch) -> Result<(), ExecutionError>;
+
+    /// Charges the fuel of a call to the view storage API.
+    fn track_host_call(&mut self) -> Result<(), ExecutionError>;
 }
 
 impl WriteBatch for ContractSyncRuntime {
     fn write_batch(&mut self, batch: Batch) -> Result<(), ExecutionError> {
         ContractRuntime::write_batch(self, batch)
     }
+
+    fn track_host_call(&mut self) -> Result<(), ExecutionError> {
+        ContractRuntime::track_host_call(self)
+    }
 }
 
 impl WriteBatch for ServiceSyncRuntime {
     fn write_batch(&mut self, _: Batch) -> Result<(), ExecutionError> {
         Err(ExecutionError::ServiceWriteAttempt)
     }
+
+    fn track_host_call(&mut self) -> Result<(), ExecutionError> {
+        // Queries are not charged.
+        Ok(())
+    }
 }
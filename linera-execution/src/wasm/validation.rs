@@ -0,0 +1,195 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Validation of application bytecodes when they are published.
+//!
+//! The checks run when a `PublishBytecode` operation is executed, so that a module that can't be
+//! used is rejected with a [`BytecodeValidationError`] instead of failing the first time the
+//! application is executed. Since the outcome decides whether a block is valid, the checks only
+//! depend on the bytecode itself:
+//!
+//! - the module must be valid WebAssembly without the proposals that allow non-deterministic
+//!   execution, i.e. threads (shared memories and atomics) and relaxed SIMD;
+//! - the module must export the functions of all the entrypoints used by the runtime.
+//!
+//! Floating-point instructions are accepted: the only non-determinism they have is in the bit
+//! patterns of NaNs, and both runtimes are configured to canonicalize them. They are also
+//! emitted by the compiler for most applications, for instance through `serde_json`.
+
+use std::{collections::BTreeSet, fmt};
+
+use thiserror::Error;
+use wasmparser::{BinaryReaderError, ExternalKind, Parser, Payload, Validator, WasmFeatures};
+
+use crate::Bytecode;
+
+/// The functions a contract module must export. The `migrate` entrypoint is optional, since it
+/// is only called on the bytecodes that applications are upgraded to.
+const CONTRACT_ENTRYPOINTS: &[&str] = &[
+    "linera:app/contract-entrypoints#instantiate",
+    "linera:app/contract-entrypoints#execute-operation",
+    "linera:app/contract-entrypoints#execute-message",
+    "linera:app/contract-entrypoints#finalize",
+];
+
+/// The functions a service module must export.
+const SERVICE_ENTRYPOINTS: &[&str] = &["linera:app/service-entrypoints#handle-query"];
+
+/// Checks that the `contract` and `service` bytecodes of an application can be executed.
+pub fn validate_bytecodes(
+    contract: &Bytecode,
+    service: &Bytecode,
+) -> Result<(), BytecodeValidationError> {
+    validate(contract, BytecodeKind::Contract)?;
+    validate(service, BytecodeKind::Service)
+}
+
+/// Checks a single bytecode of the given `kind`.
+fn validate(bytecode: &Bytecode, kind: BytecodeKind) -> Result<(), BytecodeValidationError> {
+    let invalid_module = |error: BinaryReaderError| BytecodeValidationError::InvalidModule {
+        kind,
+        reason: error.to_string(),
+    };
+    let bytes = bytecode.as_ref();
+    Validator::new_with_features(deterministic_features())
+        .validate_all(bytes)
+        .map_err(invalid_module)?;
+
+    let mut exported_functions = BTreeSet::new();
+    for payload in Parser::new(0).parse_all(bytes) {
+        if let Payload::ExportSection(exports) = payload.map_err(invalid_module)? {
+            for export in exports {
+                let export = export.map_err(invalid_module)?;
+                if export.kind == ExternalKind::Func {
+                    exported_functions.insert(export.name);
+                }
+            }
+        }
+    }
+
+    for entrypoint in kind.entrypoints() {
+        if !exported_functions.contains(entrypoint) {
+            return Err(BytecodeValidationError::MissingEntrypoint {
+                kind,
+                entrypoint: (*entrypoint).to_owned(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The WebAssembly features accepted in application bytecodes.
+fn deterministic_features() -> WasmFeatures {
+    WasmFeatures {
+        threads: false,
+        relaxed_simd: false,
+        ..WasmFeatures::default()
+    }
+}
+
+/// Which part of an application a bytecode implements.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BytecodeKind {
+    Contract,
+    Service,
+}
+
+impl BytecodeKind {
+    fn entrypoints(self) -> &'static [&'static str] {
+        match self {
+            BytecodeKind::Contract => CONTRACT_ENTRYPOINTS,
+            BytecodeKind::Service => SERVICE_ENTRYPOINTS,
+        }
+    }
+}
+
+impl fmt::Display for BytecodeKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BytecodeKind::Contract => write!(formatter, "contract"),
+            BytecodeKind::Service => write!(formatter, "service"),
+        }
+    }
+}
+
+/// Reasons for rejecting a published bytecode.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum BytecodeValidationError {
+    #[error("Invalid {kind} bytecode: {reason}")]
+    InvalidModule { kind: BytecodeKind, reason: String },
+    #[error("The {kind} bytecode does not export the entrypoint {entrypoint:?}")]
+    MissingEntrypoint {
+        kind: BytecodeKind,
+        entrypoint: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_bytecodes, BytecodeKind, BytecodeValidationError};
+    use crate::Bytecode;
+
+    fn counter_bytecodes() -> (Bytecode, Bytecode) {
+        (
+            Bytecode::new(include_bytes!("../../tests/fixtures/counter_contract.wasm").to_vec()),
+            Bytecode::new(include_bytes!("../../tests/fixtures/counter_service.wasm").to_vec()),
+        )
+    }
+
+    /// Tests that the bytecodes of an example application are accepted.
+    #[test]
+    fn accepts_example_application() {
+        let (contract, service) = counter_bytecodes();
+        assert_eq!(validate_bytecodes(&contract, &service), Ok(()));
+    }
+
+    /// Tests that a bytecode published with the wrong kind is missing entrypoints.
+    #[test]
+    fn rejects_missing_entrypoints() {
+        let (contract, service) = counter_bytecodes();
+        assert_eq!(
+            validate_bytecodes(&service, &service),
+            Err(BytecodeValidationError::MissingEntrypoint {
+                kind: BytecodeKind::Contract,
+                entrypoint: "linera:app/contract-entrypoints#instantiate".to_owned(),
+            })
+        );
+        assert_eq!(
+            validate_bytecodes(&contract, &contract),
+            Err(BytecodeValidationError::MissingEntrypoint {
+                kind: BytecodeKind::Service,
+                entrypoint: "linera:app/service-entrypoints#handle-query".to_owned(),
+            })
+        );
+    }
+
+    /// Tests that bytes that aren't a WebAssembly module are rejected.
+    #[test]
+    fn rejects_malformed_module() {
+        let (_, service) = counter_bytecodes();
+        let garbage = Bytecode::new(b"not a module".to_vec());
+        assert!(matches!(
+            validate_bytecodes(&garbage, &service),
+            Err(BytecodeValidationError::InvalidModule {
+                kind: BytecodeKind::Contract,
+                ..
+            })
+        ));
+    }
+
+    /// Tests that modules using shared memory are rejected.
+    #[cfg(with_wasmer)]
+    #[test]
+    fn rejects_threads() {
+        let (contract, _) = counter_bytecodes();
+        let wat = r#"(module (memory 1 1 shared))"#;
+        let service = Bytecode::new(wasmer::wat2wasm(wat.as_bytes()).unwrap().into_owned());
+        assert!(matches!(
+            validate_bytecodes(&contract, &service),
+            Err(BytecodeValidationError::InvalidModule {
+                kind: BytecodeKind::Service,
+                ..
+            })
+        ));
+    }
+}
@@ -9,14 +9,17 @@ use bytes::Bytes;
 use linera_base::sync::Lazy;
 use linera_witty::{
     wasmer::{EntrypointInstance, InstanceBuilder},
-    ExportTo,
+    ExportTo, Instance, RuntimeError,
 };
 use tokio::sync::Mutex;
 use wasm_instrument::{gas_metering, parity_wasm};
-use wasmer::{sys::EngineBuilder, Cranelift, Engine, Module, Singlepass, Store};
+use wasmer::{sys::EngineBuilder, Cranelift, Engine, Extern, Module, Singlepass, Store};
+use wasmer_types::TrapCode;
 
 use super::{
+    artifact_cache,
     module_cache::ModuleCache,
+    sandbox,
     system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi, WriteBatch},
     ContractEntrypoints, ServiceEntrypoints, WasmExecutionError,
 };
@@ -55,7 +58,19 @@ impl WasmContractModule {
     pub async fn from_wasmer(contract_bytecode: Bytecode) -> Result<Self, WasmExecutionError> {
         let mut contract_cache = CONTRACT_CACHE.lock().await;
         let (engine, module) = contract_cache
-            .get_or_insert_with(contract_bytecode, CachedContractModule::new)
+            .get_or_insert_with(contract_bytecode, |bytecode, hash| {
+                artifact_cache::load_or_compile(
+                    "wasmer-contract",
+                    hash,
+                    || CachedContractModule::new(bytecode),
+                    |module| Ok(module.compiled_bytecode.to_vec()),
+                    |bytes| {
+                        Ok(CachedContractModule {
+                            compiled_bytecode: Bytes::copy_from_slice(bytes),
+                        })
+                    },
+                )
+            })
             .map_err(WasmExecutionError::LoadContractModule)?
             .create_execution_instance()
             .map_err(WasmExecutionError::LoadContractModule)?;
@@ -85,13 +100,48 @@ where
     }
 }
 
+impl<Runtime> WasmerContractInstance<Runtime>
+where
+    Runtime: ContractRuntime + Send + Unpin + 'static,
+{
+    /// Converts the `error` of a failed call into an [`ExecutionError`], reporting if the
+    /// contract exhausted its sandbox.
+    fn call_error(&mut self, error: RuntimeError) -> ExecutionError {
+        let is_stack_overflow = matches!(
+            &error,
+            RuntimeError::Wasmer(error) if error.clone().to_trap() == Some(TrapCode::StackOverflow)
+        );
+        let memory_pages = match self.instance.load_export("memory") {
+            Some(Extern::Memory(memory)) => Some(u64::from(memory.view(&self.instance).size().0)),
+            _ => None,
+        };
+        sandbox::call_error(
+            self.instance.user_data_mut().runtime_mut(),
+            error.into(),
+            is_stack_overflow,
+            memory_pages,
+        )
+    }
+}
+
 impl WasmServiceModule {
     /// Creates a new [`WasmServiceModule`] using Wasmer with the provided bytecodes.
     pub async fn from_wasmer(service_bytecode: Bytecode) -> Result<Self, WasmExecutionError> {
         let mut service_cache = SERVICE_CACHE.lock().await;
         let module = service_cache
-            .get_or_insert_with(service_bytecode, |bytecode| {
-                Module::new(&*SERVICE_ENGINE, bytecode).map_err(anyhow::Error::from)
+            .get_or_insert_with(service_bytecode, |bytecode, hash| {
+                artifact_cache::load_or_compile(
+                    "wasmer-metered-service",
+                    hash,
+                    || {
+                        let bytecode = add_metering(bytecode, "linera:app/service-system-api")?;
+                        Ok(Module::new(&*SERVICE_ENGINE, bytecode)?)
+                    },
+                    |module| Ok(module.serialize()?.to_vec()),
+                    // SAFETY: Artifacts are only written by `Module::serialize` with the same
+                    // engine.
+                    |bytes| Ok(unsafe { Module::deserialize(&*SERVICE_ENGINE, bytes) }?),
+                )
             })
             .map_err(WasmExecutionError::LoadServiceModule)?;
         Ok(WasmServiceModule::Wasmer { module })
@@ -125,10 +175,8 @@ where
         _context: OperationContext,
         argument: Vec<u8>,
     ) -> Result<(), ExecutionError> {
-        ContractEntrypoints::new(&mut self.instance)
-            .instantiate(argument)
-            .map_err(WasmExecutionError::from)?;
-        Ok(())
+        let result = ContractEntrypoints::new(&mut self.instance).instantiate(argument);
+        result.map_err(|error| self.call_error(error))
     }
 
     fn execute_operation(
@@ -136,9 +184,8 @@ where
         _context: OperationContext,
         operation: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        Ok(ContractEntrypoints::new(&mut self.instance)
-            .execute_operation(operation)
-            .map_err(WasmExecutionError::from)?)
+        let result = ContractEntrypoints::new(&mut self.instance).execute_operation(operation);
+        result.map_err(|error| self.call_error(error))
     }
 
     fn execute_message(
@@ -146,17 +193,22 @@ where
         _context: MessageContext,
         message: Vec<u8>,
     ) -> Result<(), ExecutionError> {
-        ContractEntrypoints::new(&mut self.instance)
-            .execute_message(message)
-            .map_err(WasmExecutionError::from)?;
-        Ok(())
+        let result = ContractEntrypoints::new(&mut self.instance).execute_message(message);
+        result.map_err(|error| self.call_error(error))
+    }
+
+    fn migrate(
+        &mut self,
+        _context: OperationContext,
+        argument: Vec<u8>,
+    ) -> Result<(), ExecutionError> {
+        let result = ContractEntrypoints::new(&mut self.instance).migrate(argument);
+        result.map_err(|error| self.call_error(error))
     }
 
     fn finalize(&mut self, _context: FinalizeContext) -> Result<(), ExecutionError> {
-        ContractEntrypoints::new(&mut self.instance)
-            .finalize()
-            .map_err(WasmExecutionError::from)?;
-        Ok(())
+        let result = ContractEntrypoints::new(&mut self.instance).finalize();
+        result.map_err(|error| self.call_error(error))
     }
 }
 
@@ -196,7 +248,9 @@ pub struct CachedContractModule {
     compiled_bytecode: Bytes,
 }
 
-pub fn add_metering(bytecode: Bytecode) -> anyhow::Result<Bytecode> {
+/// Instruments a `bytecode` to consume fuel, by calling the `consume-fuel` function of the
+/// `system_api` interface.
+pub fn add_metering(bytecode: Bytecode, system_api: &'static str) -> anyhow::Result<Bytecode> {
     struct WasmtimeRules;
 
     impl gas_metering::Rules for WasmtimeRules {
@@ -227,10 +281,7 @@ pub fn add_metering(bytecode: Bytecode) -> anyhow::Result<Bytecode> {
 
     let instrumented_module = gas_metering::inject(
         parity_wasm::deserialize_buffer(&bytecode.bytes)?,
-        gas_metering::host_function::Injector::new(
-            "linera:app/contract-system-api",
-            "consume-fuel",
-        ),
+        gas_metering::host_function::Injector::new(system_api, "consume-fuel"),
         &WasmtimeRules,
     )
     .map_err(|_| anyhow::anyhow!("failed to instrument module"))?;
@@ -245,7 +296,7 @@ impl CachedContractModule {
     pub fn new(contract_bytecode: Bytecode) -> Result<Self, anyhow::Error> {
         let module = Module::new(
             &Self::create_compilation_engine(),
-            add_metering(contract_bytecode)?,
+            add_metering(contract_bytecode, "linera:app/contract-system-api")?,
         )?;
         let compiled_bytecode = module.serialize()?;
         Ok(CachedContractModule { compiled_bytecode })
@@ -3,15 +3,17 @@
 
 //! Code specific to the usage of the [Wasmtime](https://wasmtime.dev/) runtime.
 
-use std::error::Error;
+use std::{error::Error, thread, time::Duration};
 
-use linera_witty::{wasmtime::EntrypointInstance, ExportTo, Instance};
+use linera_witty::{wasmtime::EntrypointInstance, ExportTo, Instance, RuntimeError};
 use once_cell::sync::Lazy;
 use tokio::sync::Mutex;
-use wasmtime::{AsContextMut, Config, Engine, Linker, Module, Store};
+use wasmtime::{AsContextMut, Config, Engine, Extern, Linker, Module, Store, TrapCode};
 
 use super::{
+    artifact_cache,
     module_cache::ModuleCache,
+    sandbox::{self, MAXIMUM_CONTRACT_STACK_SIZE},
     system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi, WriteBatch},
     ContractEntrypoints, ServiceEntrypoints, WasmExecutionError,
 };
@@ -21,18 +23,38 @@ use crate::{
     QueryContext, ServiceRuntime,
 };
 
+/// How often the epoch of the contract [`Engine`] is incremented, interrupting the contracts
+/// that reached their deadline.
+const EPOCH_PERIOD: Duration = Duration::from_millis(10);
+
+/// The epoch deadline of the contracts without a time limit, far enough to never be reached.
+const NO_DEADLINE: u64 = u64::MAX / 2;
+
 /// An [`Engine`] instance configured to run application contracts.
 static CONTRACT_ENGINE: Lazy<Engine> = Lazy::new(|| {
     let mut config = Config::default();
     config
         .consume_fuel(true)
-        .cranelift_nan_canonicalization(true);
-
-    Engine::new(&config).expect("Failed to create Wasmtime `Engine` for contracts")
+        .cranelift_nan_canonicalization(true)
+        .epoch_interruption(true)
+        .max_wasm_stack(MAXIMUM_CONTRACT_STACK_SIZE);
+
+    let engine = Engine::new(&config).expect("Failed to create Wasmtime `Engine` for contracts");
+    let ticking_engine = engine.clone();
+    thread::spawn(move || loop {
+        thread::sleep(EPOCH_PERIOD);
+        ticking_engine.increment_epoch();
+    });
+    engine
 });
 
 /// An [`Engine`] instance configured to run application services.
-static SERVICE_ENGINE: Lazy<Engine> = Lazy::new(Engine::default);
+static SERVICE_ENGINE: Lazy<Engine> = Lazy::new(|| {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+
+    Engine::new(&config).expect("Failed to create Wasmtime `Engine` for services")
+});
 
 /// A cache of compiled contract modules.
 static CONTRACT_CACHE: Lazy<Mutex<ModuleCache<Module>>> = Lazy::new(Mutex::default);
@@ -57,6 +79,37 @@ where
 
 // TODO(#1785): Simplify by using proper fuel getter and setter methods from Wasmtime once the
 // dependency is updated
+/// Sets the fuel available to an `instance`.
+fn set_fuel<UserData>(instance: &mut EntrypointInstance<UserData>, fuel: u64) {
+    let mut context = instance.as_context_mut();
+
+    context
+        .add_fuel(1)
+        .expect("Fuel consumption should be enabled");
+
+    let existing_fuel = context
+        .consume_fuel(0)
+        .expect("Fuel consumption should be enabled");
+
+    if existing_fuel > fuel {
+        context
+            .consume_fuel(existing_fuel - fuel)
+            .expect("Existing fuel was incorrectly calculated");
+    } else {
+        context
+            .add_fuel(fuel - existing_fuel)
+            .expect("Fuel consumption wasn't properly enabled");
+    }
+}
+
+/// Returns the fuel still available to an `instance`.
+fn remaining_fuel<UserData>(instance: &mut EntrypointInstance<UserData>) -> u64 {
+    instance
+        .as_context_mut()
+        .consume_fuel(0)
+        .expect("Failed to read remaining fuel")
+}
+
 impl<Runtime> WasmtimeContractInstance<Runtime>
 where
     Runtime: ContractRuntime + Send + Sync,
@@ -64,49 +117,90 @@ where
     fn configure_initial_fuel(&mut self) -> Result<(), ExecutionError> {
         let runtime = &mut self.instance.user_data_mut().runtime_mut();
         let fuel = runtime.remaining_fuel()?;
-        let mut context = self.instance.as_context_mut();
 
         self.initial_fuel = fuel;
-
-        context
-            .add_fuel(1)
-            .expect("Fuel consumption should be enabled");
-
-        let existing_fuel = context
-            .consume_fuel(0)
-            .expect("Fuel consumption should be enabled");
-
-        if existing_fuel > fuel {
-            context
-                .consume_fuel(existing_fuel - fuel)
-                .expect("Existing fuel was incorrectly calculated");
-        } else {
-            context
-                .add_fuel(fuel - existing_fuel)
-                .expect("Fuel consumption wasn't properly enabled");
-        }
+        set_fuel(&mut self.instance, fuel);
 
         Ok(())
     }
 
     fn persist_remaining_fuel(&mut self) -> Result<(), ExecutionError> {
-        let remaining_fuel = self
-            .instance
-            .as_context_mut()
-            .consume_fuel(0)
-            .expect("Failed to read remaining fuel");
+        let remaining_fuel = remaining_fuel(&mut self.instance);
         let runtime = &mut self.instance.user_data_mut().runtime_mut();
 
         assert!(self.initial_fuel >= remaining_fuel);
 
         runtime.consume_fuel(self.initial_fuel - remaining_fuel)
     }
+
+    /// Interrupts the contract when the transaction reaches its deadline, if any.
+    fn configure_deadline(&mut self) -> Result<(), ExecutionError> {
+        let remaining_time = self
+            .instance
+            .user_data_mut()
+            .runtime_mut()
+            .remaining_time()?;
+        let ticks = match remaining_time {
+            Some(time) => u64::try_from(time.as_nanos() / EPOCH_PERIOD.as_nanos())
+                .map_or(NO_DEADLINE, |ticks| ticks.min(NO_DEADLINE) + 1),
+            None => NO_DEADLINE,
+        };
+        self.instance.as_context_mut().set_epoch_deadline(ticks);
+
+        Ok(())
+    }
+
+    /// Converts the `error` of a failed call into an [`ExecutionError`], reporting if the
+    /// contract exhausted its sandbox.
+    fn call_error(&mut self, error: RuntimeError) -> ExecutionError {
+        let is_stack_overflow = matches!(
+            &error,
+            RuntimeError::WasmtimeTrap(trap) if trap.trap_code() == Some(TrapCode::StackOverflow)
+        );
+        let memory_pages = match self.instance.load_export("memory") {
+            Some(Extern::Memory(memory)) => Some(memory.size(&self.instance)),
+            _ => None,
+        };
+        sandbox::call_error(
+            self.instance.user_data_mut().runtime_mut(),
+            error.into(),
+            is_stack_overflow,
+            memory_pages,
+        )
+    }
 }
 
 /// Type representing a running [Wasmtime](https://wasmtime.dev/) service.
 pub struct WasmtimeServiceInstance<Runtime> {
     /// The Wasm module instance.
     instance: EntrypointInstance<SystemApiData<Runtime>>,
+
+    /// The starting amount of fuel.
+    initial_fuel: u64,
+}
+
+impl<Runtime> WasmtimeServiceInstance<Runtime>
+where
+    Runtime: ServiceRuntime + Send + Sync,
+{
+    fn configure_initial_fuel(&mut self) -> Result<(), ExecutionError> {
+        let runtime = &mut self.instance.user_data_mut().runtime_mut();
+        let fuel = runtime.remaining_fuel()?;
+
+        self.initial_fuel = fuel;
+        set_fuel(&mut self.instance, fuel);
+
+        Ok(())
+    }
+
+    fn persist_remaining_fuel(&mut self) -> Result<(), ExecutionError> {
+        let remaining_fuel = remaining_fuel(&mut self.instance);
+        let runtime = &mut self.instance.user_data_mut().runtime_mut();
+
+        assert!(self.initial_fuel >= remaining_fuel);
+
+        runtime.consume_fuel(self.initial_fuel - remaining_fuel)
+    }
 }
 
 impl WasmContractModule {
@@ -114,8 +208,16 @@ impl WasmContractModule {
     pub async fn from_wasmtime(contract_bytecode: Bytecode) -> Result<Self, WasmExecutionError> {
         let mut contract_cache = CONTRACT_CACHE.lock().await;
         let module = contract_cache
-            .get_or_insert_with(contract_bytecode, |bytecode| {
-                Module::new(&CONTRACT_ENGINE, bytecode)
+            .get_or_insert_with(contract_bytecode, |bytecode, hash| {
+                artifact_cache::load_or_compile(
+                    "wasmtime-contract",
+                    hash,
+                    || Module::new(&CONTRACT_ENGINE, bytecode),
+                    |module| module.serialize(),
+                    // SAFETY: Artifacts are only written by `Module::serialize` with the same
+                    // engine.
+                    |bytes| unsafe { Module::deserialize(&CONTRACT_ENGINE, bytes) },
+                )
             })
             .map_err(WasmExecutionError::LoadContractModule)?;
         Ok(WasmContractModule::Wasmtime { module })
@@ -135,6 +237,7 @@ where
 
         let user_data = SystemApiData::new(runtime);
         let mut store = Store::new(&CONTRACT_ENGINE, user_data);
+        store.set_epoch_deadline(NO_DEADLINE);
         let instance = linker
             .instantiate(&mut store, contract_module)
             .map_err(WasmExecutionError::LoadContractModule)?;
@@ -151,8 +254,16 @@ impl WasmServiceModule {
     pub async fn from_wasmtime(service_bytecode: Bytecode) -> Result<Self, WasmExecutionError> {
         let mut service_cache = SERVICE_CACHE.lock().await;
         let module = service_cache
-            .get_or_insert_with(service_bytecode, |bytecode| {
-                Module::new(&SERVICE_ENGINE, bytecode)
+            .get_or_insert_with(service_bytecode, |bytecode, hash| {
+                artifact_cache::load_or_compile(
+                    "wasmtime-service",
+                    hash,
+                    || Module::new(&SERVICE_ENGINE, bytecode),
+                    |module| module.serialize(),
+                    // SAFETY: Artifacts are only written by `Module::serialize` with the same
+                    // engine.
+                    |bytes| unsafe { Module::deserialize(&SERVICE_ENGINE, bytes) },
+                )
             })
             .map_err(WasmExecutionError::LoadServiceModule)?;
         Ok(WasmServiceModule::Wasmtime { module })
@@ -178,6 +289,7 @@ where
 
         Ok(Self {
             instance: EntrypointInstance::new(instance, store),
+            initial_fuel: 0,
         })
     }
 }
@@ -192,10 +304,10 @@ where
         argument: Vec<u8>,
     ) -> Result<(), ExecutionError> {
         self.configure_initial_fuel()?;
+        self.configure_deadline()?;
         let result = ContractEntrypoints::new(&mut self.instance).instantiate(argument);
         self.persist_remaining_fuel()?;
-        result.map_err(WasmExecutionError::from)?;
-        Ok(())
+        result.map_err(|error| self.call_error(error))
     }
 
     fn execute_operation(
@@ -204,9 +316,10 @@ where
         operation: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
         self.configure_initial_fuel()?;
+        self.configure_deadline()?;
         let result = ContractEntrypoints::new(&mut self.instance).execute_operation(operation);
         self.persist_remaining_fuel()?;
-        Ok(result.map_err(WasmExecutionError::from)?)
+        result.map_err(|error| self.call_error(error))
     }
 
     fn execute_message(
@@ -215,18 +328,30 @@ where
         message: Vec<u8>,
     ) -> Result<(), ExecutionError> {
         self.configure_initial_fuel()?;
+        self.configure_deadline()?;
         let result = ContractEntrypoints::new(&mut self.instance).execute_message(message);
         self.persist_remaining_fuel()?;
-        result.map_err(WasmExecutionError::from)?;
-        Ok(())
+        result.map_err(|error| self.call_error(error))
+    }
+
+    fn migrate(
+        &mut self,
+        _context: OperationContext,
+        argument: Vec<u8>,
+    ) -> Result<(), ExecutionError> {
+        self.configure_initial_fuel()?;
+        self.configure_deadline()?;
+        let result = ContractEntrypoints::new(&mut self.instance).migrate(argument);
+        self.persist_remaining_fuel()?;
+        result.map_err(|error| self.call_error(error))
     }
 
     fn finalize(&mut self, _context: FinalizeContext) -> Result<(), ExecutionError> {
         self.configure_initial_fuel()?;
+        self.configure_deadline()?;
         let result = ContractEntrypoints::new(&mut self.instance).finalize();
         self.persist_remaining_fuel()?;
-        result.map_err(WasmExecutionError::from)?;
-        Ok(())
+        result.map_err(|error| self.call_error(error))
     }
 }
 
@@ -239,9 +364,10 @@ where
         _context: QueryContext,
         argument: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        Ok(ServiceEntrypoints::new(&mut self.instance)
-            .handle_query(argument)
-            .map_err(WasmExecutionError::from)?)
+        self.configure_initial_fuel()?;
+        let result = ServiceEntrypoints::new(&mut self.instance).handle_query(argument);
+        self.persist_remaining_fuel()?;
+        Ok(result.map_err(WasmExecutionError::from)?)
     }
 }
 
@@ -13,6 +13,7 @@ use linera_base::{
     identifiers::{Account, ChainDescription, ChainId, MessageId, Owner},
 };
 use linera_execution::{
+    system::FeeSource,
     test_utils::{register_mock_applications, ExpectedCall, SystemExecutionState},
     ContractRuntime, ExecutionError, ExecutionOutcome, Message, MessageContext,
     RawExecutionOutcome, ResourceControlPolicy, ResourceController,
@@ -146,8 +147,16 @@ async fn test_fee_consumption(
         operation_byte: Amount::from_tokens(23),
         message: Amount::from_tokens(29),
         message_byte: Amount::from_tokens(31),
+        host_call_fuel: 0,
+        maximum_fuel_per_block: u64::MAX,
         maximum_bytes_read_per_block: 37,
         maximum_bytes_written_per_block: 41,
+        maximum_bytecode_size: 43,
+        maximum_call_depth: 47,
+        maximum_bytes_stored_per_application: 53,
+        maximum_operations_per_block: u64::MAX,
+        maximum_incoming_messages_per_block: u64::MAX,
+        maximum_block_size: u64::MAX,
     };
 
     let consumed_fees = spends
@@ -284,6 +293,53 @@ async fn test_fee_consumption(
     }
 }
 
+/// Tests that the block fees are only deducted from the proposer's balance if the chain is
+/// configured so.
+#[tokio::test]
+async fn test_block_fee_paid_by_proposer() {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        balance: Amount::from_tokens(10),
+        fee_source: FeeSource::Proposer,
+        ..SystemExecutionState::default()
+    };
+    let mut view = state.into_view().await;
+    let owner = Owner::from(PublicKey::test_key(0));
+    view.system
+        .balances
+        .insert(&owner, Amount::from_tokens(3))
+        .unwrap();
+
+    let policy = ResourceControlPolicy {
+        block: Amount::from_tokens(2),
+        ..ResourceControlPolicy::default()
+    };
+    let mut controller = ResourceController {
+        policy: Arc::new(policy),
+        account: Some(owner),
+        ..ResourceController::default()
+    };
+    controller
+        .with_state(&mut view)
+        .await
+        .unwrap()
+        .track_block()
+        .unwrap();
+    assert_eq!(*view.system.balance.get(), Amount::from_tokens(10));
+    assert_eq!(
+        view.system.balances.get(&owner).await.unwrap(),
+        Some(Amount::ONE)
+    );
+
+    let result = controller
+        .with_state(&mut view)
+        .await
+        .unwrap()
+        .track_block();
+    assert!(result.is_err());
+    assert_eq!(*view.system.balance.get(), Amount::from_tokens(10));
+}
+
 /// A runtime operation that costs some amount of fees.
 pub enum FeeSpend {
     /// Consume some execution fuel.
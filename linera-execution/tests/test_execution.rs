@@ -3,17 +3,19 @@
 
 #![allow(clippy::field_reassign_with_default)]
 
-use std::{collections::BTreeMap, vec};
+use std::{collections::BTreeMap, sync::Arc, time::Duration, vec};
 
 use assert_matches::assert_matches;
 use futures::{stream, StreamExt, TryStreamExt};
 use linera_base::{
-    crypto::PublicKey,
+    crypto::{CryptoHash, PublicKey},
     data_types::{
-        Amount, ApplicationPermissions, BlockHeight, OracleRecord, Resources, SendMessageRequest,
-        Timestamp,
+        Amount, ApplicationPermissions, BlockHeight, OracleRecord, OracleResponse, Resources,
+        SendMessageRequest, Timestamp,
+    },
+    identifiers::{
+        Account, Blob, BytecodeId, ChainDescription, ChainId, Destination, MessageId, Owner,
     },
-    identifiers::{Account, ChainDescription, ChainId, Destination, MessageId, Owner},
     ownership::ChainOwnership,
 };
 use linera_execution::{
@@ -23,16 +25,23 @@ use linera_execution::{
         create_dummy_user_application_registrations, register_mock_applications, ExpectedCall,
         SystemExecutionState,
     },
-    BaseRuntime, ContractRuntime, ExecutionError, ExecutionOutcome, MessageKind, Operation,
-    OperationContext, Query, QueryContext, RawExecutionOutcome, RawOutgoingMessage,
-    ResourceControlPolicy, ResourceController, Response, SystemOperation,
+    BaseRuntime, BytecodeLocation, ContractLimits, ContractRuntime, ExecutionError,
+    ExecutionOutcome, ExecutionRuntimeConfig, ExecutionStateView, MessageKind, Operation,
+    OperationContext, Query, QueryContext, QueryLimits, RawExecutionOutcome, RawOutgoingMessage,
+    ResourceControlPolicy, ResourceController, ResourceTracker, Response, SandboxResource,
+    ServiceRuntime, SystemOperation, TestExecutionRuntimeContext,
+};
+use linera_views::{
+    batch::Batch,
+    memory::{MemoryContext, TEST_MEMORY_MAX_STREAM_QUERIES},
+    views::View,
 };
-use linera_views::batch::Batch;
 
 fn make_operation_context() -> OperationContext {
     OperationContext {
         chain_id: ChainId::root(0),
         height: BlockHeight(0),
+        previous_block_hash: None,
         index: Some(0),
         authenticated_signer: None,
         authenticated_caller_id: None,
@@ -524,6 +533,394 @@ async fn test_rejecting_block_from_called_applications_finalize() -> anyhow::Res
     Ok(())
 }
 
+/// Tests that contracts read the chain ID, block height, timestamp and signer of the block
+/// being executed.
+#[tokio::test]
+async fn test_contract_reads_block_context() -> anyhow::Result<()> {
+    let timestamp = Timestamp::from(1_000_000);
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    state.timestamp = timestamp;
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 1).await?;
+    let (application_id, application) = applications
+        .next()
+        .expect("Mock application should be registered");
+
+    let owner = Owner::from(PublicKey::test_key(0));
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            assert_eq!(runtime.chain_id()?, ChainId::root(0));
+            assert_eq!(runtime.block_height()?, BlockHeight(5));
+            assert_eq!(runtime.read_system_timestamp()?, timestamp);
+            assert_eq!(runtime.authenticated_signer()?, Some(owner));
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let context = OperationContext {
+        height: BlockHeight(5),
+        authenticated_signer: Some(owner),
+        ..make_operation_context()
+    };
+    let mut controller = ResourceController::default();
+    view.execute_operation(
+        context,
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        Some(OracleRecord::default()),
+        &mut controller,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Tests that a contract can read a blob, and that the read is recorded as an oracle response
+/// with the ID of the blob, so that the block can only be replayed where the blob is available.
+#[tokio::test]
+async fn test_contract_reads_blob() -> anyhow::Result<()> {
+    let blob = Blob::new(b"media".to_vec());
+    let blob_id = blob.id();
+    let extra =
+        TestExecutionRuntimeContext::new(ChainId::root(0), ExecutionRuntimeConfig::default());
+    extra.add_blob(blob);
+    let context = MemoryContext::new(TEST_MEMORY_MAX_STREAM_QUERIES, extra);
+    let mut view = ExecutionStateView::load(context).await?;
+
+    let mut applications = register_mock_applications(&mut view, 1).await?;
+    let (application_id, application) = applications
+        .next()
+        .expect("Mock application should be registered");
+
+    // Each execution instantiates the application again, with the calls expected so far.
+    let expect_calls = || {
+        application.expect_call(ExpectedCall::execute_operation(
+            move |runtime, _context, _operation| {
+                assert_eq!(runtime.read_blob(blob_id)?, b"media".to_vec());
+                Ok(vec![])
+            },
+        ));
+        application.expect_call(ExpectedCall::default_finalize());
+    };
+
+    let operation = Operation::User {
+        application_id,
+        bytes: vec![],
+    };
+    expect_calls();
+    let (_, record) = view
+        .execute_operation(
+            make_operation_context(),
+            operation.clone(),
+            None,
+            &mut ResourceController::default(),
+        )
+        .await?;
+    assert_eq!(record.responses, vec![OracleResponse::Blob(blob_id)]);
+
+    // Replaying the transaction reads the blob again.
+    expect_calls();
+    view.execute_operation(
+        make_operation_context(),
+        operation,
+        Some(record),
+        &mut ResourceController::default(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Tests that the events emitted by a contract are recorded in its execution outcome.
+#[tokio::test]
+async fn test_contract_emits_events() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 1).await?;
+    let (application_id, application) = applications
+        .next()
+        .expect("Mock application should be registered");
+
+    application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _operation| {
+            runtime.emit(b"transfers".to_vec(), vec![1, 2, 3])?;
+            runtime.emit(b"transfers".to_vec(), vec![4])?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut controller = ResourceController::default();
+    let (outcomes, _) = view
+        .execute_operation(
+            make_operation_context(),
+            Operation::User {
+                application_id,
+                bytes: vec![],
+            },
+            Some(OracleRecord::default()),
+            &mut controller,
+        )
+        .await?;
+    let events = outcomes
+        .into_iter()
+        .flat_map(|outcome| match outcome {
+            ExecutionOutcome::User(id, outcome) => {
+                assert_eq!(id, application_id);
+                outcome.events
+            }
+            ExecutionOutcome::System(outcome) => {
+                assert!(outcome.events.is_empty());
+                Vec::new()
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        vec![
+            (b"transfers".to_vec(), vec![1, 2, 3]),
+            (b"transfers".to_vec(), vec![4]),
+        ]
+    );
+    Ok(())
+}
+
+/// Returns the two pseudo-random values that a contract gets in the operation with the given
+/// index, in a block following the given one.
+async fn random_values_in_operation(
+    previous_block_hash: Option<CryptoHash>,
+    index: u32,
+) -> anyhow::Result<(CryptoHash, CryptoHash)> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 1).await?;
+    let (application_id, application) = applications
+        .next()
+        .expect("Mock application should be registered");
+
+    let values = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded_values = values.clone();
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            let mut values = recorded_values.lock().unwrap();
+            values.push(runtime.random_value()?);
+            values.push(runtime.random_value()?);
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let context = OperationContext {
+        previous_block_hash,
+        index: Some(index),
+        ..make_operation_context()
+    };
+    let mut controller = ResourceController::default();
+    view.execute_operation(
+        context,
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        Some(OracleRecord::default()),
+        &mut controller,
+    )
+    .await?;
+
+    let values = values.lock().unwrap();
+    Ok((values[0], values[1]))
+}
+
+/// Tests that the pseudo-random values are the same whenever a transaction is executed, but
+/// differ between calls, between transactions and between blocks.
+#[tokio::test]
+async fn test_random_values_are_deterministic() -> anyhow::Result<()> {
+    let previous_block_hash = Some(CryptoHash::test_hash("previous block"));
+    let (first, second) = random_values_in_operation(previous_block_hash, 0).await?;
+    assert_ne!(first, second);
+    assert_eq!(
+        random_values_in_operation(previous_block_hash, 0).await?,
+        (first, second)
+    );
+    let (other_first, other_second) = random_values_in_operation(previous_block_hash, 1).await?;
+    assert_ne!(other_first, first);
+    assert_ne!(other_second, second);
+    let other_block_hash = Some(CryptoHash::test_hash("other block"));
+    let (other_first, other_second) = random_values_in_operation(other_block_hash, 0).await?;
+    assert_ne!(other_first, first);
+    assert_ne!(other_second, second);
+    Ok(())
+}
+
+/// Tests that the keys marked as ephemeral by a contract are deleted at their expiry height,
+/// and only then.
+#[tokio::test]
+async fn test_expiring_keys() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 1).await?;
+    let (application_id, application) = applications
+        .next()
+        .expect("Mock application should be registered");
+
+    application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _operation| {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(b"order".to_vec(), vec![1]);
+            batch.put_key_value_bytes(b"balance".to_vec(), vec![2]);
+            runtime.write_batch(batch)?;
+            runtime.expire_key(b"order".to_vec(), BlockHeight(2))?;
+            assert_matches!(
+                runtime.expire_key(b"balance".to_vec(), BlockHeight(0)),
+                Err(ExecutionError::InvalidKeyExpiry { .. })
+            );
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut controller = ResourceController::default();
+    view.execute_operation(
+        make_operation_context(),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        Some(OracleRecord::default()),
+        &mut controller,
+    )
+    .await?;
+
+    view.delete_expired_keys(BlockHeight(1)).await?;
+    let state = view.users.try_load_entry(&application_id).await?.unwrap();
+    assert_eq!(state.get(b"order").await?, Some(vec![1]));
+    drop(state);
+
+    view.delete_expired_keys(BlockHeight(2)).await?;
+    let state = view.users.try_load_entry(&application_id).await?.unwrap();
+    assert_eq!(state.get(b"order").await?, None);
+    assert_eq!(state.get(b"balance").await?, Some(vec![2]));
+    drop(state);
+    assert!(view.expiring_keys.indices().await?.is_empty());
+    Ok(())
+}
+
+/// Tests that an application can't be called while it is already executing.
+#[tokio::test]
+async fn test_reentrant_call_is_rejected() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 2).await?;
+    let (first_id, first_application) = applications
+        .next()
+        .expect("First mock application should be registered");
+    let (second_id, second_application) = applications
+        .next()
+        .expect("Second mock application should be registered");
+
+    first_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(false, second_id, vec![])?;
+            Ok(vec![])
+        },
+    ));
+    second_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _argument| {
+            runtime.try_call_application(false, first_id, vec![])?;
+            Ok(vec![])
+        },
+    ));
+
+    let context = make_operation_context();
+    let mut controller = ResourceController::default();
+    let result = view
+        .execute_operation(
+            context,
+            Operation::User {
+                application_id: first_id,
+                bytes: vec![],
+            },
+            Some(OracleRecord::default()),
+            &mut controller,
+        )
+        .await;
+
+    assert_matches!(result, Err(ExecutionError::ReentrantCall(id)) if id == first_id);
+    Ok(())
+}
+
+/// Tests that nested calls between applications are limited by the policy's maximum call depth.
+#[tokio::test]
+async fn test_maximum_call_depth() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 3).await?;
+    let (first_id, first_application) = applications
+        .next()
+        .expect("First mock application should be registered");
+    let (second_id, second_application) = applications
+        .next()
+        .expect("Second mock application should be registered");
+    let (third_id, _third_application) = applications
+        .next()
+        .expect("Third mock application should be registered");
+
+    first_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(false, second_id, vec![])?;
+            Ok(vec![])
+        },
+    ));
+    second_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _argument| {
+            runtime.try_call_application(false, third_id, vec![])?;
+            Ok(vec![])
+        },
+    ));
+
+    let context = make_operation_context();
+    let policy = ResourceControlPolicy {
+        maximum_call_depth: 2,
+        ..ResourceControlPolicy::default()
+    };
+    let mut controller = ResourceController {
+        policy: Arc::new(policy),
+        tracker: ResourceTracker::default(),
+        account: None,
+    };
+    let result = view
+        .execute_operation(
+            context,
+            Operation::User {
+                application_id: first_id,
+                bytes: vec![],
+            },
+            Some(OracleRecord::default()),
+            &mut controller,
+        )
+        .await;
+
+    assert_matches!(
+        result,
+        Err(ExecutionError::MaximumCallDepthExceeded { callee_id, maximum: 2 })
+            if *callee_id == third_id
+    );
+    Ok(())
+}
+
 /// Tests if `finalize` can send messages.
 #[tokio::test]
 async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
@@ -551,6 +948,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"first".to_vec(),
     };
 
@@ -586,6 +984,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"second".to_vec(),
     };
     let third_message = SendMessageRequest {
@@ -593,6 +992,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"third".to_vec(),
     };
     let fourth_message = SendMessageRequest {
@@ -600,6 +1000,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"fourth".to_vec(),
     };
 
@@ -645,6 +1046,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiration: None,
         message: SystemMessage::RegisterApplications { applications },
     };
     let account = Account {
@@ -939,6 +1341,7 @@ async fn test_simple_message() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"msg".to_vec(),
     };
 
@@ -978,6 +1381,7 @@ async fn test_simple_message() -> anyhow::Result<()> {
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiration: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![application_description],
         },
@@ -1038,6 +1442,7 @@ async fn test_message_from_cross_application_call() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"msg".to_vec(),
     };
 
@@ -1075,6 +1480,7 @@ async fn test_message_from_cross_application_call() -> anyhow::Result<()> {
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiration: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![target_description],
         },
@@ -1152,6 +1558,7 @@ async fn test_message_from_deeper_call() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"msg".to_vec(),
     };
 
@@ -1190,6 +1597,7 @@ async fn test_message_from_deeper_call() -> anyhow::Result<()> {
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiration: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![target_description],
         },
@@ -1272,6 +1680,7 @@ async fn test_multiple_messages_from_different_applications() -> anyhow::Result<
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"first".to_vec(),
     };
 
@@ -1310,6 +1719,7 @@ async fn test_multiple_messages_from_different_applications() -> anyhow::Result<
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiration: None,
         message: b"second".to_vec(),
     };
 
@@ -1359,6 +1769,7 @@ async fn test_multiple_messages_from_different_applications() -> anyhow::Result<
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiration: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![sending_target_description.clone(), caller_description],
         },
@@ -1369,6 +1780,7 @@ async fn test_multiple_messages_from_different_applications() -> anyhow::Result<
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiration: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![sending_target_description],
         },
@@ -1597,3 +2009,345 @@ async fn test_close_chain() {
     .unwrap();
     assert!(view.system.closed.get());
 }
+
+/// Queries a mock application handling the query with `expected_call`, on a node with the given
+/// query `limits`.
+async fn query_with_limits(
+    limits: QueryLimits,
+    expected_call: ExpectedCall,
+) -> Result<Response, ExecutionError> {
+    let chain_id = ChainId::root(0);
+    let extra = TestExecutionRuntimeContext::new(chain_id, ExecutionRuntimeConfig::default())
+        .with_query_limits(limits);
+    let context = MemoryContext::new(TEST_MEMORY_MAX_STREAM_QUERIES, extra);
+    let mut view = ExecutionStateView::load(context).await?;
+
+    let (application_id, application) = register_mock_applications(&mut view, 1)
+        .await
+        .expect("Mock application should be registered")
+        .next()
+        .expect("Mock application should be registered");
+    application.expect_call(expected_call);
+
+    let context = QueryContext {
+        chain_id,
+        next_block_height: BlockHeight(0),
+    };
+    let query = Query::User {
+        application_id,
+        bytes: vec![],
+    };
+    view.query_application(context, query).await
+}
+
+/// Tests that a query using more than the maximum fuel per query is aborted.
+#[tokio::test]
+async fn test_query_exceeding_fuel_limit() {
+    let limits = QueryLimits {
+        maximum_fuel: 1_000,
+        timeout: None,
+    };
+
+    let response = query_with_limits(
+        limits,
+        ExpectedCall::handle_query(|runtime, _context, _query| {
+            runtime.consume_fuel(600)?;
+            assert_eq!(runtime.remaining_fuel()?, 400);
+            Ok(vec![1])
+        }),
+    )
+    .await;
+    assert_matches!(response, Ok(Response::User(bytes)) if bytes == vec![1]);
+
+    let response = query_with_limits(
+        limits,
+        ExpectedCall::handle_query(|runtime, _context, _query| {
+            runtime.consume_fuel(600)?;
+            runtime.consume_fuel(600)?;
+            Ok(vec![])
+        }),
+    )
+    .await;
+    assert_matches!(response, Err(ExecutionError::MaximumQueryFuelExceeded));
+}
+
+/// Tests that a query running for longer than the query timeout is aborted.
+#[tokio::test]
+async fn test_query_exceeding_timeout() {
+    let timeout = Duration::from_millis(50);
+    let limits = QueryLimits {
+        timeout: Some(timeout),
+        ..QueryLimits::default()
+    };
+
+    let response = query_with_limits(
+        limits,
+        ExpectedCall::handle_query(|runtime, _context, _query| {
+            std::thread::sleep(Duration::from_millis(500));
+            runtime.read_chain_balance()?;
+            Ok(vec![])
+        }),
+    )
+    .await;
+    assert_matches!(response, Err(ExecutionError::QueryTimeout(limit)) if limit == timeout);
+}
+
+/// Tests that a service can query the service of another application on the same chain.
+#[tokio::test]
+async fn test_cross_application_query() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 2).await?;
+    let (first_id, first_application) = applications
+        .next()
+        .expect("First mock application should be registered");
+    let (second_id, second_application) = applications
+        .next()
+        .expect("Second mock application should be registered");
+
+    first_application.expect_call(ExpectedCall::handle_query(
+        move |runtime, _context, query| {
+            let mut response = runtime.try_query_application(second_id, query)?;
+            response.push(1);
+            Ok(response)
+        },
+    ));
+    second_application.expect_call(ExpectedCall::handle_query(|_runtime, _context, query| {
+        assert_eq!(query, vec![0]);
+        Ok(vec![2])
+    }));
+
+    let context = QueryContext {
+        chain_id: ChainId::root(0),
+        next_block_height: BlockHeight(0),
+    };
+    let query = Query::User {
+        application_id: first_id,
+        bytes: vec![0],
+    };
+    assert_eq!(
+        view.query_application(context, query).await?,
+        Response::User(vec![2, 1])
+    );
+    Ok(())
+}
+
+/// Tests that a cycle of queries between services is rejected, without preventing the
+/// services from handling the error.
+#[tokio::test]
+async fn test_cyclic_query_is_rejected() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 2).await?;
+    let (first_id, first_application) = applications
+        .next()
+        .expect("First mock application should be registered");
+    let (second_id, second_application) = applications
+        .next()
+        .expect("Second mock application should be registered");
+
+    first_application.expect_call(ExpectedCall::handle_query(
+        move |runtime, _context, _query| {
+            let response = runtime.try_query_application(second_id, vec![])?;
+            // The second application is not handling a query anymore.
+            let result = runtime.try_query_application(second_id, vec![]);
+            assert_matches!(result, Ok(bytes) if bytes.is_empty());
+            Ok(response)
+        },
+    ));
+    second_application.expect_call(ExpectedCall::handle_query(
+        move |runtime, _context, _query| {
+            let result = runtime.try_query_application(first_id, vec![]);
+            assert_matches!(result, Err(ExecutionError::ReentrantCall(id)) if id == first_id);
+            Ok(vec![1])
+        },
+    ));
+    second_application.expect_call(ExpectedCall::handle_query(|_runtime, _context, _query| {
+        Ok(vec![])
+    }));
+
+    let context = QueryContext {
+        chain_id: ChainId::root(0),
+        next_block_height: BlockHeight(0),
+    };
+    let query = Query::User {
+        application_id: first_id,
+        bytes: vec![],
+    };
+    assert_eq!(
+        view.query_application(context, query).await?,
+        Response::User(vec![1])
+    );
+    Ok(())
+}
+
+/// Tests that a transaction running for longer than the contract timeout is aborted, even if
+/// the application up the call stack reports another error, and that the fuel used until then
+/// is still charged.
+#[tokio::test]
+async fn test_contract_exceeding_timeout() -> anyhow::Result<()> {
+    let chain_id = ChainId::root(0);
+    let timeout = Duration::from_millis(50);
+    let extra = TestExecutionRuntimeContext::new(chain_id, ExecutionRuntimeConfig::default())
+        .with_contract_limits(ContractLimits {
+            timeout: Some(timeout),
+        });
+    let context = MemoryContext::new(TEST_MEMORY_MAX_STREAM_QUERIES, extra);
+    let mut view = ExecutionStateView::load(context).await?;
+
+    let mut applications = register_mock_applications(&mut view, 2).await?;
+    let (caller_id, caller_application) = applications
+        .next()
+        .expect("Caller mock application should be registered");
+    let (callee_id, callee_application) = applications
+        .next()
+        .expect("Callee mock application should be registered");
+
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime
+                .try_call_application(false, callee_id, vec![])
+                .map_err(|error| ExecutionError::UserError(error.to_string()))?;
+            Ok(vec![])
+        },
+    ));
+    callee_application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _argument| {
+            runtime.consume_fuel(100)?;
+            std::thread::sleep(Duration::from_millis(500));
+            runtime.consume_fuel(10)?;
+            Ok(vec![])
+        },
+    ));
+
+    let mut controller = ResourceController::default();
+    let result = view
+        .execute_operation(
+            make_operation_context(),
+            Operation::User {
+                application_id: caller_id,
+                bytes: vec![],
+            },
+            None,
+            &mut controller,
+        )
+        .await;
+
+    assert_matches!(
+        result,
+        Err(ExecutionError::ResourceExceeded {
+            resource: SandboxResource::Time,
+            limit: 50,
+        })
+    );
+    assert_eq!(controller.tracker.fuel, 110);
+
+    // Replaying the transaction with its recorded oracle responses ignores the timeout.
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(false, callee_id, vec![])?;
+            Ok(vec![])
+        },
+    ));
+    callee_application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _argument| {
+            std::thread::sleep(Duration::from_millis(100));
+            runtime.consume_fuel(10)?;
+            Ok(vec![])
+        },
+    ));
+    callee_application.expect_call(ExpectedCall::default_finalize());
+    caller_application.expect_call(ExpectedCall::default_finalize());
+    view.execute_operation(
+        make_operation_context(),
+        Operation::User {
+            application_id: caller_id,
+            bytes: vec![],
+        },
+        Some(OracleRecord::default()),
+        &mut ResourceController::default(),
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upgraded_application_is_migrated_before_execution() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = register_mock_applications(&mut view, 1).await?;
+    let (application_id, application) = applications
+        .next()
+        .expect("Mock application should be registered");
+
+    let bytecode_id = BytecodeId::new(MessageId {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(1),
+        index: 0,
+    });
+    let location = BytecodeLocation {
+        certificate_hash: CryptoHash::test_hash("upgraded bytecode"),
+        operation_index: 0,
+    };
+    let registry = &mut view.system.registry;
+    registry.register_published_bytecode(bytecode_id, location)?;
+    registry
+        .upgrade_application(application_id, bytecode_id, b"argument".to_vec())
+        .await?;
+
+    application.expect_call({
+        let application = application.clone();
+        ExpectedCall::migrate(move |runtime, _context, argument| {
+            assert_eq!(argument, b"argument");
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![], b"migrated".to_vec());
+            runtime.write_batch(batch)?;
+            // The operation is executed by a new instance, after the migration is finalized.
+            application.expect_call(ExpectedCall::execute_operation(
+                |runtime, _context, _operation| {
+                    assert_eq!(
+                        runtime.read_value_bytes(vec![])?,
+                        Some(b"migrated".to_vec())
+                    );
+                    Ok(vec![])
+                },
+            ));
+            application.expect_call(ExpectedCall::default_finalize());
+            Ok(())
+        })
+    });
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let operation = Operation::User {
+        application_id,
+        bytes: vec![],
+    };
+    view.execute_operation(
+        make_operation_context(),
+        operation.clone(),
+        None,
+        &mut ResourceController::default(),
+    )
+    .await?;
+    assert_eq!(view.system.registry.version(&application_id).await?, 1);
+
+    // The state is only migrated once.
+    application.expect_call(ExpectedCall::execute_operation(
+        |_runtime, _context, _operation| Ok(vec![]),
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+    view.execute_operation(
+        make_operation_context(),
+        operation,
+        None,
+        &mut ResourceController::default(),
+    )
+    .await?;
+    Ok(())
+}
@@ -3,17 +3,29 @@
 
 #![allow(clippy::field_reassign_with_default)]
 
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
 use linera_base::{
-    crypto::CryptoHash,
+    crypto::{CryptoHash, PublicKey},
     data_types::{Amount, BlockHeight, OracleRecord},
-    identifiers::{Account, ChainDescription, ChainId, MessageId},
+    identifiers::{
+        Account, ApplicationId, Blob, ChainDescription, ChainId, Destination, MessageId, Owner,
+    },
 };
 use linera_execution::{
-    system::{Recipient, UserData},
+    system::{topic_channel_name, Recipient, SystemChannel, UserData},
     test_utils::SystemExecutionState,
-    ExecutionOutcome, Message, MessageContext, Operation, OperationContext, Query, QueryContext,
-    RawExecutionOutcome, ResourceController, Response, SystemMessage, SystemOperation, SystemQuery,
-    SystemResponse,
+    Bytecode, ExecutionError, ExecutionOutcome, ExecutionStateView, Message, MessageContext,
+    MessageKind, Operation, OperationContext, Query, QueryContext, RawExecutionOutcome,
+    RawOutgoingMessage, ResourceControlPolicy, ResourceController, ResourceTracker, Response,
+    SystemExecutionError, SystemMessage, SystemOperation, SystemQuery, SystemResponse,
+    TestExecutionRuntimeContext,
+};
+use linera_views::{
+    common::Context as _,
+    memory::MemoryContext,
+    views::{CryptoHashView, View},
 };
 
 #[tokio::test]
@@ -31,6 +43,7 @@ async fn test_simple_system_operation() -> anyhow::Result<()> {
     let context = OperationContext {
         chain_id: ChainId::root(0),
         height: BlockHeight(0),
+        previous_block_hash: None,
         index: Some(0),
         authenticated_signer: None,
         authenticated_caller_id: None,
@@ -126,3 +139,293 @@ async fn test_simple_system_query() -> anyhow::Result<()> {
     );
     Ok(())
 }
+
+#[tokio::test]
+async fn test_publish_bytecode_above_size_limit() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+    let operation = SystemOperation::PublishBytecode {
+        contract: Bytecode::new(vec![0; 4]),
+        service: Bytecode::new(vec![0; 5]),
+    };
+    let context = OperationContext {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(0),
+        previous_block_hash: None,
+        index: Some(0),
+        authenticated_signer: None,
+        authenticated_caller_id: None,
+        next_message_index: 0,
+    };
+    let policy = ResourceControlPolicy {
+        maximum_bytecode_size: 4,
+        ..ResourceControlPolicy::default()
+    };
+    let mut controller = ResourceController {
+        policy: Arc::new(policy),
+        tracker: ResourceTracker::default(),
+        account: None,
+    };
+    let result = view
+        .execute_operation(
+            context,
+            Operation::System(operation),
+            Some(OracleRecord::default()),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::SystemError(
+            SystemExecutionError::BytecodeTooLarge {
+                size: 5,
+                maximum: 4
+            }
+        ))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_publish_bytecode_blobs_above_size_limit() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+    let contract = Blob::new(vec![0; 4]);
+    let service = Blob::new(vec![0; 5]);
+    let operation = SystemOperation::PublishBytecodeBlobs {
+        contract: contract.id(),
+        service: service.id(),
+    };
+    let context = OperationContext {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(0),
+        previous_block_hash: None,
+        index: Some(0),
+        authenticated_signer: None,
+        authenticated_caller_id: None,
+        next_message_index: 0,
+    };
+    let policy = ResourceControlPolicy {
+        maximum_bytecode_size: 4,
+        ..ResourceControlPolicy::default()
+    };
+    let mut controller = ResourceController {
+        policy: Arc::new(policy),
+        tracker: ResourceTracker::default(),
+        account: None,
+    };
+
+    // The bytecodes can only be checked once the blobs are available.
+    let result = view
+        .execute_operation(
+            context,
+            Operation::System(operation.clone()),
+            Some(OracleRecord::default()),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(result, Err(ExecutionError::BlobNotFound(blob_id)) if blob_id == contract.id());
+
+    view.context().extra().add_blob(contract);
+    view.context().extra().add_blob(service);
+    let result = view
+        .execute_operation(
+            context,
+            Operation::System(operation),
+            Some(OracleRecord::default()),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::SystemError(
+            SystemExecutionError::BytecodeTooLarge {
+                size: 5,
+                maximum: 4
+            }
+        ))
+    );
+    Ok(())
+}
+
+/// Executes a system operation on chain 0, and returns the messages it sends.
+async fn execute_system_operation(
+    view: &mut ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>,
+    operation: SystemOperation,
+) -> Result<Vec<RawOutgoingMessage<SystemMessage, Amount>>, ExecutionError> {
+    let context = OperationContext {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(0),
+        previous_block_hash: None,
+        index: Some(0),
+        authenticated_signer: None,
+        authenticated_caller_id: None,
+        next_message_index: 0,
+    };
+    let mut controller = ResourceController::default();
+    let (outcomes, _) = view
+        .execute_operation(
+            context,
+            Operation::System(operation),
+            Some(OracleRecord::default()),
+            &mut controller,
+        )
+        .await?;
+    Ok(outcomes
+        .into_iter()
+        .flat_map(|outcome| match outcome {
+            ExecutionOutcome::System(outcome) => outcome.messages,
+            ExecutionOutcome::User(..) => Vec::new(),
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn test_topic_subscriptions() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+    let publisher = ChainId::root(1);
+    let application_id = ApplicationId::default();
+    let topic = "prices".to_string();
+
+    let subscribe = SystemOperation::SubscribeToTopic {
+        chain_id: publisher,
+        application_id,
+        topic: topic.clone(),
+    };
+    let messages = execute_system_operation(&mut view, subscribe).await?;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].destination, Destination::Recipient(publisher));
+    assert_eq!(
+        messages[0].message,
+        SystemMessage::SubscribeToTopic {
+            id: ChainId::root(0),
+            application_id,
+            topic: topic.clone(),
+        }
+    );
+
+    let unsubscribe = SystemOperation::UnsubscribeFromTopic {
+        chain_id: publisher,
+        application_id,
+        topic: topic.clone(),
+    };
+    let messages = execute_system_operation(&mut view, unsubscribe).await?;
+    assert_eq!(
+        messages[0].message,
+        SystemMessage::UnsubscribeFromTopic {
+            id: ChainId::root(0),
+            application_id,
+            topic: topic.clone(),
+        }
+    );
+
+    // Chains can't subscribe to their own topics.
+    let self_subscription = SystemOperation::SubscribeToTopic {
+        chain_id: ChainId::root(0),
+        application_id,
+        topic,
+    };
+    assert_matches!(
+        execute_system_operation(&mut view, self_subscription).await,
+        Err(ExecutionError::SystemError(
+            SystemExecutionError::SelfTopicSubscription(..)
+        ))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_topic_subscription_message() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+    let subscriber = ChainId::root(1);
+    let application_id = ApplicationId::default();
+    let message = SystemMessage::SubscribeToTopic {
+        id: subscriber,
+        application_id,
+        topic: "prices".to_string(),
+    };
+    let context = MessageContext {
+        chain_id: ChainId::root(0),
+        is_bouncing: false,
+        height: BlockHeight(0),
+        certificate_hash: CryptoHash::test_hash("certificate"),
+        message_id: MessageId {
+            chain_id: subscriber,
+            height: BlockHeight(0),
+            index: 0,
+        },
+        authenticated_signer: None,
+        refund_grant_to: None,
+        next_message_index: 0,
+    };
+    let mut controller = ResourceController::default();
+    let (outcomes, _) = view
+        .execute_message(
+            context,
+            Message::System(message),
+            None,
+            Some(OracleRecord::default()),
+            &mut controller,
+        )
+        .await?;
+
+    // The subscriber is notified, and added to the channel of the topic of the application,
+    // so that the payloads it publishes are delivered as messages of that application.
+    let notification = RawOutgoingMessage {
+        destination: Destination::Recipient(subscriber),
+        authenticated: false,
+        grant: Amount::ZERO,
+        kind: MessageKind::Protected,
+        expiration: None,
+        message: SystemMessage::Notify { id: subscriber },
+    };
+    let mut subscription = RawExecutionOutcome::default();
+    subscription
+        .subscribe
+        .push((topic_channel_name("prices"), subscriber));
+    assert_eq!(
+        outcomes,
+        vec![
+            ExecutionOutcome::System(RawExecutionOutcome::default().with_message(notification)),
+            ExecutionOutcome::User(application_id, subscription),
+        ]
+    );
+    // Topics don't share the names of the system channels.
+    assert_ne!(topic_channel_name("prices"), SystemChannel::Admin.name());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_balance_proof() -> anyhow::Result<()> {
+    let owner = Owner::from(PublicKey::test_key(1));
+    let other_owner = Owner::from(PublicKey::test_key(2));
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    state.balances.insert(owner, Amount::from_tokens(3));
+    let mut view = state.into_view().await;
+    let state_hash = view.crypto_hash().await?;
+
+    let proof = view.balance_proof(&owner).await?.unwrap();
+    assert_eq!(
+        bcs::from_bytes::<Amount>(&proof.value)?,
+        Amount::from_tokens(3)
+    );
+    assert!(proof.verify_crypto_hash("ExecutionStateView", &state_hash));
+    assert!(view.balance_proof(&other_owner).await?.is_none());
+
+    // The proof does not hold anymore once the balances change.
+    view.system
+        .balances
+        .insert(&other_owner, Amount::from_tokens(5))?;
+    let new_state_hash = view.crypto_hash().await?;
+    assert!(!proof.verify_crypto_hash("ExecutionStateView", &new_state_hash));
+    let proof = view.balance_proof(&owner).await?.unwrap();
+    assert!(proof.verify_crypto_hash("ExecutionStateView", &new_state_hash));
+    Ok(())
+}
@@ -67,6 +67,7 @@ async fn test_fuel_for_counter_wasm_application(
     let context = OperationContext {
         chain_id: ChainId::root(0),
         height: BlockHeight(0),
+        previous_block_hash: None,
         index: Some(0),
         authenticated_signer: None,
         authenticated_caller_id: None,
@@ -140,3 +141,78 @@ async fn test_fuel_for_counter_wasm_application(
     );
     Ok(())
 }
+
+/// Test that the calls of the "counter" application to the system API are charged, and that
+/// it cannot use more fuel than allowed in a block.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_fuel_limits_for_counter_wasm_application(
+    wasm_runtime: WasmRuntime,
+) -> anyhow::Result<()> {
+    let instruction_fuel = increment_counter(wasm_runtime, ResourceControlPolicy::default())
+        .await?
+        .fuel;
+
+    let policy = ResourceControlPolicy {
+        host_call_fuel: 1_000,
+        ..ResourceControlPolicy::default()
+    };
+    let fuel = increment_counter(wasm_runtime, policy).await?.fuel;
+    assert!(fuel > instruction_fuel);
+    assert_eq!((fuel - instruction_fuel) % 1_000, 0);
+
+    let policy = ResourceControlPolicy {
+        maximum_fuel_per_block: instruction_fuel - 1,
+        ..ResourceControlPolicy::default()
+    };
+    assert!(increment_counter(wasm_runtime, policy).await.is_err());
+    Ok(())
+}
+
+/// Executes one increment of a new "counter" application with the given policy, and returns
+/// the resources it used.
+async fn increment_counter(
+    wasm_runtime: WasmRuntime,
+    policy: ResourceControlPolicy,
+) -> anyhow::Result<ResourceTracker> {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let mut view = state
+        .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::Synchronous)
+        .await;
+    let app_desc = create_dummy_user_application_description(1);
+    let app_id = view.system.registry.register_application(app_desc).await?;
+    let contract =
+        WasmContractModule::from_file("tests/fixtures/counter_contract.wasm", wasm_runtime).await?;
+    view.context()
+        .extra
+        .user_contracts()
+        .insert(app_id, Arc::new(contract));
+    let app_id = app_id.with_abi::<CounterAbi>();
+
+    let context = OperationContext {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(0),
+        previous_block_hash: None,
+        index: Some(0),
+        authenticated_signer: None,
+        authenticated_caller_id: None,
+        next_message_index: 0,
+    };
+    let mut controller = ResourceController {
+        policy: Arc::new(policy),
+        tracker: ResourceTracker::default(),
+        account: None,
+    };
+    view.execute_operation(
+        context,
+        Operation::user(app_id, &1_u64).unwrap(),
+        Some(OracleRecord::default()),
+        &mut controller,
+    )
+    .await?;
+    Ok(controller.tracker)
+}
@@ -1,10 +1,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-//! An example of an indexer with the operations plugin.
+//! An example of an indexer with the blocks and operations plugins.
 
 use linera_indexer::{common::IndexerError, plugin::Plugin, rocks_db::RocksDbRunner};
-use linera_indexer_plugins::operations::OperationsPlugin;
+use linera_indexer_plugins::{blocks::BlocksPlugin, operations::OperationsPlugin};
 
 #[tokio::main]
 async fn main() -> Result<(), IndexerError> {
@@ -17,6 +17,9 @@ async fn main() -> Result<(), IndexerError> {
         .init();
 
     let mut runner = RocksDbRunner::load().await?;
+    runner
+        .add_plugin(BlocksPlugin::load(runner.store.clone()).await?)
+        .await?;
     runner
         .add_plugin(OperationsPlugin::load(runner.store.clone()).await?)
         .await?;
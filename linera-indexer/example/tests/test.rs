@@ -7,6 +7,7 @@ use std::{str::FromStr, time::Duration};
 
 use linera_base::{command::resolve_binary, data_types::Amount, identifiers::ChainId, sync::Lazy};
 use linera_indexer_graphql_client::{
+    blocks::{get_block, GetBlock},
     indexer::{plugins, state, Plugins, State},
     operations::{get_operation, GetOperation, OperationKey},
 };
@@ -102,8 +103,8 @@ async fn test_end_to_end_operations_indexer(config: impl LineraNetConfig) {
         .plugins;
     assert_eq!(
         plugins,
-        vec!["operations"],
-        "Indexer plugin 'operations' not loaded",
+        vec!["blocks", "operations"],
+        "Indexer plugins 'blocks' and 'operations' not loaded",
     );
 
     // making a few transfers
@@ -141,10 +142,32 @@ async fn test_end_to_end_operations_indexer(config: impl LineraNetConfig) {
         "Different states between service and indexer"
     );
 
-    // checking indexer operation
+    // checking indexer block
     let Some(executed_block) = last_block.value.executed_block else {
         panic!("last block is a new round")
     };
+    let variables = get_block::Variables { hash: last_hash };
+    let indexer_block =
+        request::<GetBlock, _>(&req_client, "http://localhost:8081/blocks", variables)
+            .await
+            .unwrap()
+            .block
+            .unwrap_or_else(|| panic!("no block found in the indexer"));
+    assert_eq!(
+        (
+            indexer_block.chain_id,
+            indexer_block.height,
+            indexer_block.operation_count,
+        ),
+        (
+            chain0,
+            executed_block.block.height,
+            executed_block.block.operations.len() as i64,
+        ),
+        "service and indexer blocks are different"
+    );
+
+    // checking indexer operation
     let last_operation = executed_block.block.operations[0].clone();
     let variables = get_operation::Variables {
         key: get_operation::OperationKeyKind::Last(chain0),
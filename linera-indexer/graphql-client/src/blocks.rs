@@ -0,0 +1,29 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use graphql_client::GraphQLQuery;
+use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/blocks_schema.graphql",
+    query_path = "gql/blocks_requests.graphql",
+    response_derives = "Debug, Serialize, Clone"
+)]
+pub struct GetBlock;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/blocks_schema.graphql",
+    query_path = "gql/blocks_requests.graphql",
+    response_derives = "Debug, Serialize, Clone"
+)]
+pub struct Blocks;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "gql/blocks_schema.graphql",
+    query_path = "gql/blocks_requests.graphql",
+    response_derives = "Debug, Serialize, Clone"
+)]
+pub struct LastBlock;
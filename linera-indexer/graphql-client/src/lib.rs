@@ -3,5 +3,6 @@
 
 //! A GraphQL client for the indexer.
 
+pub mod blocks;
 pub mod indexer;
 pub mod operations;
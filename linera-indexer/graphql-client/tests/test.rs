@@ -54,3 +54,27 @@ async fn test_check_indexer_operations_schema() {
          regenerate schema following steps in linera-indexer-graphql-client/README.md\n"
     )
 }
+
+#[test_log::test(tokio::test)]
+async fn test_check_indexer_blocks_schema() {
+    let tmp_dir = tempdir().unwrap();
+    let path = resolve_binary("linera-indexer", "linera-indexer-example")
+        .await
+        .unwrap();
+    let mut command = Command::new(path);
+    let output = command
+        .current_dir(tmp_dir.path())
+        .args(["schema", "blocks"])
+        .output()
+        .await
+        .unwrap();
+    let service_schema = String::from_utf8(output.stdout).unwrap();
+    let mut file_base = std::fs::File::open("gql/blocks_schema.graphql").unwrap();
+    let mut graphql_schema = String::new();
+    file_base.read_to_string(&mut graphql_schema).unwrap();
+    assert_eq!(
+        graphql_schema, service_schema,
+        "\nGraphQL indexer blocks schema has changed -> \
+         regenerate schema following steps in linera-indexer-graphql-client/README.md\n"
+    )
+}
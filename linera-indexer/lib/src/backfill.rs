@@ -0,0 +1,150 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the sources of certificates used to backfill the indexer.
+//!
+//! Instead of following the notifications of a node service, a backfill reads the confirmed
+//! blocks of a chain directly from a copy of a validator's storage, or from an archive of
+//! exported certificates.
+
+use std::{collections::BTreeSet, ops::Range, path::PathBuf};
+
+use async_trait::async_trait;
+use linera_base::identifiers::ChainId;
+use linera_chain::data_types::{Certificate, HashedCertificateValue};
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+
+use crate::common::IndexerError;
+
+/// The origin of the certificates to backfill the indexer with.
+#[derive(clap::Args, Debug, Clone)]
+#[group(required = true, multiple = false)]
+pub struct BackfillSource {
+    /// A directory of archived certificates, e.g. an S3 export synchronized locally. The
+    /// certificate of the block at height `h` of a chain must be BCS-encoded in the file
+    /// `<chain-id>/<h>.bcs`
+    #[arg(long)]
+    pub archive: Option<PathBuf>,
+    /// The namespace of a validator's storage, in the same database as the indexer, e.g. a
+    /// copy of the validator's database
+    #[arg(long)]
+    pub validator_namespace: Option<String>,
+}
+
+/// A source of confirmed blocks.
+#[async_trait]
+pub trait CertificateSource: Send + Sync {
+    /// Returns the number of consecutive blocks of the chain available from the source,
+    /// starting at height zero.
+    async fn chain_length(&self, chain_id: ChainId) -> Result<u64, IndexerError>;
+
+    /// Reads the confirmed blocks of the chain at the given heights, in order.
+    async fn read_values(
+        &self,
+        chain_id: ChainId,
+        heights: Range<u64>,
+    ) -> Result<Vec<HashedCertificateValue>, IndexerError>;
+}
+
+/// Reads the certificates from a validator's storage.
+pub struct StorageSource<St> {
+    storage: St,
+}
+
+impl<St> StorageSource<St> {
+    pub fn new(storage: St) -> Self {
+        Self { storage }
+    }
+}
+
+#[async_trait]
+impl<St> CertificateSource for StorageSource<St>
+where
+    St: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<St::ContextError>,
+{
+    async fn chain_length(&self, chain_id: ChainId) -> Result<u64, IndexerError> {
+        let chain = self.storage.load_chain(chain_id).await?;
+        Ok(chain.confirmed_log.count() as u64)
+    }
+
+    async fn read_values(
+        &self,
+        chain_id: ChainId,
+        heights: Range<u64>,
+    ) -> Result<Vec<HashedCertificateValue>, IndexerError> {
+        let chain = self.storage.load_chain(chain_id).await?;
+        let hashes = chain
+            .confirmed_log
+            .read(heights.start as usize..heights.end as usize)
+            .await?;
+        let certificates = self.storage.read_certificates(hashes).await?;
+        Ok(certificates
+            .into_iter()
+            .map(|certificate| certificate.value)
+            .collect())
+    }
+}
+
+/// Reads the certificates from a directory of exported certificates.
+pub struct ArchiveSource {
+    path: PathBuf,
+}
+
+impl ArchiveSource {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn chain_path(&self, chain_id: ChainId) -> PathBuf {
+        self.path.join(chain_id.to_string())
+    }
+}
+
+#[async_trait]
+impl CertificateSource for ArchiveSource {
+    async fn chain_length(&self, chain_id: ChainId) -> Result<u64, IndexerError> {
+        let mut heights = BTreeSet::new();
+        let mut entries = match tokio::fs::read_dir(self.chain_path(chain_id)).await {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(error) => return Err(error.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path
+                .extension()
+                .map_or(false, |extension| extension == "bcs")
+            {
+                if let Some(height) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<u64>().ok())
+                {
+                    heights.insert(height);
+                }
+            }
+        }
+        Ok(heights
+            .into_iter()
+            .enumerate()
+            .take_while(|(index, height)| *index as u64 == *height)
+            .count() as u64)
+    }
+
+    async fn read_values(
+        &self,
+        chain_id: ChainId,
+        heights: Range<u64>,
+    ) -> Result<Vec<HashedCertificateValue>, IndexerError> {
+        let chain_path = self.chain_path(chain_id);
+        let mut values = Vec::new();
+        for height in heights {
+            let bytes = tokio::fs::read(chain_path.join(format!("{height}.bcs"))).await?;
+            let certificate = bcs::from_bytes::<Certificate>(&bytes)?;
+            values.push(certificate.value);
+        }
+        Ok(values)
+    }
+}
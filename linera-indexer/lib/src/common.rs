@@ -30,6 +30,8 @@ pub enum IndexerError {
     ParserError(#[from] AddrParseError),
     #[error(transparent)]
     ServerError(#[from] hyper::Error),
+    #[error(transparent)]
+    BcsError(#[from] bcs::Error),
     #[error("Null GraphQL data: {0:?}")]
     NullData(Option<Vec<graphql_client::Error>>),
     #[error("Block not found: {0:?}")]
@@ -46,6 +48,8 @@ pub enum IndexerError {
     PluginAlreadyRegistered,
     #[error("Invalid certificate content: {0:?}")]
     InvalidCertificateValue(CryptoHash),
+    #[error("Backfill sources are not available for this storage backend")]
+    BackfillNotSupported,
 
     #[cfg(feature = "rocksdb")]
     #[error(transparent)]
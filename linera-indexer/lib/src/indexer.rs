@@ -8,6 +8,7 @@ use std::{collections::BTreeMap, sync::Arc};
 use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
 use axum::{extract::Extension, routing::get, Router};
+use futures::{stream, StreamExt as _, TryStreamExt as _};
 use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
 use linera_chain::data_types::HashedCertificateValue;
 use linera_views::{
@@ -23,6 +24,7 @@ use tower_http::cors::CorsLayer;
 use tracing::info;
 
 use crate::{
+    backfill::CertificateSource,
     common::{graphiql, IndexerError},
     plugin::Plugin,
     service::Listener,
@@ -164,6 +166,74 @@ where
         }
     }
 
+    /// Backfills a chain with the blocks read from the source, starting after the latest
+    /// block already indexed. Returns the number of blocks processed.
+    pub async fn backfill_chain(
+        &self,
+        source: &dyn CertificateSource,
+        chain_id: ChainId,
+        batch_size: usize,
+    ) -> Result<u64, IndexerError> {
+        let length = source.chain_length(chain_id).await?;
+        let (mut next_height, mut last_hash) =
+            match self.state.0.lock().await.chains.get(&chain_id).await? {
+                None => (0, None),
+                Some((hash, height)) => (height.0 + 1, Some(hash)),
+            };
+        info!(
+            "backfill {:?}: from {} to {}",
+            chain_id, next_height, length
+        );
+        let start_height = next_height;
+        while next_height < length {
+            let end = length.min(next_height.saturating_add(batch_size.max(1) as u64));
+            let values = source.read_values(chain_id, next_height..end).await?;
+            if values.is_empty() {
+                break;
+            }
+            let state = &mut self.state.0.lock().await;
+            for value in values {
+                let Some(block) = value.inner().block() else {
+                    return Err(IndexerError::InvalidCertificateValue(value.hash()));
+                };
+                if !value.inner().is_confirmed()
+                    || block.chain_id != chain_id
+                    || block.height != BlockHeight(next_height)
+                    || block.previous_block_hash != last_hash
+                {
+                    return Err(IndexerError::InvalidCertificateValue(value.hash()));
+                }
+                self.process_value(state, &value).await?;
+                last_hash = Some(value.hash());
+                next_height += 1;
+            }
+        }
+        Ok(next_height - start_height)
+    }
+
+    /// Backfills the chains with the blocks read from the source, processing up to
+    /// `workers` chains in parallel.
+    pub async fn backfill(
+        &self,
+        source: &dyn CertificateSource,
+        chains: Vec<ChainId>,
+        workers: usize,
+        batch_size: usize,
+    ) -> Result<(), IndexerError> {
+        let counts = stream::iter(chains)
+            .map(|chain_id| async move {
+                let count = self.backfill_chain(source, chain_id, batch_size).await?;
+                Ok::<_, IndexerError>((chain_id, count))
+            })
+            .buffer_unordered(workers.max(1))
+            .try_collect::<Vec<_>>()
+            .await?;
+        for (chain_id, count) in counts {
+            info!("backfilled {:?}: {} blocks", chain_id, count);
+        }
+        Ok(())
+    }
+
     /// Produces the GraphQL schema for the indexer or for a certain plugin
     pub fn sdl(&self, plugin: Option<String>) -> Result<String, IndexerError> {
         match plugin {
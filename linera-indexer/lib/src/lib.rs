@@ -6,7 +6,9 @@
 //! - the block processing (indexer.rs)
 //! - the generic plugin trait (plugin.rs)
 //! - the runner struct (runner.rs)
+//! - the sources of certificates to backfill the indexer (backfill.rs)
 
+pub mod backfill;
 pub mod common;
 pub mod indexer;
 pub mod plugin;
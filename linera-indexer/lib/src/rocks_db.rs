@@ -4,12 +4,14 @@
 use std::path::PathBuf;
 
 use clap::Parser as _;
+use linera_storage::{RocksDbStorage, WallClock};
 use linera_views::{
     common::{AdminKeyValueStore, CommonStoreConfig},
-    rocks_db::{RocksDbStore, RocksDbStoreConfig},
+    rocks_db::{RocksDbStore, RocksDbStoreConfig, RocksDbTuningConfig},
 };
 
 use crate::{
+    backfill::StorageSource,
     common::IndexerError,
     runner::{IndexerConfig, Runner},
 };
@@ -46,9 +48,15 @@ impl RocksDbRunner {
         let store_config = RocksDbStoreConfig {
             path_buf: config.client.storage.as_path().to_path_buf(),
             common_config,
+            tuning: RocksDbTuningConfig::default(),
         };
         let namespace = config.client.table.clone();
         let store = RocksDbStore::maybe_create_and_connect(&store_config, &namespace).await?;
-        Self::new(config, store).await
+        let mut runner = Self::new(config, store).await?;
+        if let Some(namespace) = runner.config.command.validator_namespace() {
+            let storage = RocksDbStorage::<WallClock>::new(store_config, namespace, None).await?;
+            runner.validator_source = Some(Box::new(StorageSource::new(storage)));
+        }
+        Ok(runner)
     }
 }
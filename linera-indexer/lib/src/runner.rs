@@ -10,7 +10,13 @@ use linera_views::{
 use tokio::select;
 use tracing::{info, warn};
 
-use crate::{common::IndexerError, indexer::Indexer, plugin::Plugin, service::Listener};
+use crate::{
+    backfill::{ArchiveSource, BackfillSource, CertificateSource},
+    common::IndexerError,
+    indexer::Indexer,
+    plugin::Plugin,
+    service::Listener,
+};
 
 #[derive(clap::Parser, Debug, Clone)]
 #[command(version = linera_version::VersionInfo::default_clap_str())]
@@ -27,6 +33,31 @@ pub enum IndexerCommand {
         /// Chains to index (default: the ones on the service wallet)
         chains: Vec<ChainId>,
     },
+    /// Indexes the history of chains from cold storage, resuming after the latest block
+    /// already indexed for each chain
+    Backfill {
+        #[command(flatten)]
+        source: BackfillSource,
+        /// The number of chains backfilled in parallel
+        #[arg(long, default_value = "4")]
+        workers: usize,
+        /// The number of certificates read at once from the source
+        #[arg(long, default_value = "100")]
+        batch_size: usize,
+        /// Chains to backfill
+        #[arg(required = true)]
+        chains: Vec<ChainId>,
+    },
+}
+
+impl IndexerCommand {
+    /// Returns the namespace of the validator's storage to backfill from, if any.
+    pub fn validator_namespace(&self) -> Option<&str> {
+        match self {
+            IndexerCommand::Backfill { source, .. } => source.validator_namespace.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(clap::Parser, Debug, Clone)]
@@ -41,6 +72,8 @@ pub struct Runner<DB, Config: clap::Args> {
     pub store: DB,
     pub config: IndexerConfig<Config>,
     pub indexer: Indexer<DB>,
+    /// The validator's storage to backfill from, opened by the storage backend
+    pub validator_source: Option<Box<dyn CertificateSource>>,
 }
 
 impl<DB, Config> Runner<DB, Config>
@@ -66,6 +99,7 @@ where
             store,
             config,
             indexer,
+            validator_source: None,
         })
     }
 
@@ -130,6 +164,24 @@ where
                     }
                 }
             }
+            IndexerCommand::Backfill {
+                source,
+                workers,
+                batch_size,
+                chains,
+            } => {
+                info!("config: {:?}", config);
+                let source: Box<dyn CertificateSource> = match source.archive {
+                    Some(path) => Box::new(ArchiveSource::new(path)),
+                    None => self
+                        .validator_source
+                        .take()
+                        .ok_or(IndexerError::BackfillNotSupported)?,
+                };
+                self.indexer
+                    .backfill(&*source, chains, workers, batch_size)
+                    .await
+            }
         }
     }
 }
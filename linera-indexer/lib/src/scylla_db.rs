@@ -1,12 +1,14 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use linera_storage::{ScyllaDbStorage, WallClock};
 use linera_views::{
     common::{AdminKeyValueStore, CommonStoreConfig},
     scylla_db::{ScyllaDbStore, ScyllaDbStoreConfig},
 };
 
 use crate::{
+    backfill::StorageSource,
     common::IndexerError,
     runner::{IndexerConfig, Runner},
 };
@@ -46,6 +48,11 @@ impl ScyllaDbRunner {
             common_config,
         };
         let store = ScyllaDbStore::connect(&store_config, &namespace).await?;
-        Self::new(config, store).await
+        let mut runner = Self::new(config, store).await?;
+        if let Some(namespace) = runner.config.command.validator_namespace() {
+            let storage = ScyllaDbStorage::<WallClock>::new(store_config, namespace, None).await?;
+            runner.validator_source = Some(Box::new(StorageSource::new(storage)));
+        }
+        Ok(runner)
     }
 }
@@ -0,0 +1,262 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A plugin registering a summary of every block and the messages it sends, for block
+//! explorers.
+
+use std::sync::Arc;
+
+use async_graphql::{Object, SimpleObject};
+use axum::Router;
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, BlockHeight, Timestamp},
+    identifiers::{ChainId, Destination, Owner},
+};
+use linera_chain::data_types::HashedCertificateValue;
+use linera_execution::{committee::Epoch, Message, MessageKind};
+use linera_indexer::{
+    common::IndexerError,
+    plugin::{load, route, sdl, Plugin},
+};
+use linera_views::{
+    common::{Context, ContextFromStore, KeyValueStore},
+    map_view::MapView,
+    views::{RootView, ViewError},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// A block, as registered by the indexer.
+#[derive(Deserialize, Serialize, Clone, SimpleObject, Debug)]
+pub struct IndexedBlock {
+    /// The hash of the block.
+    hash: CryptoHash,
+    /// The chain of the block.
+    chain_id: ChainId,
+    /// The height of the block in its chain.
+    height: BlockHeight,
+    /// The hash of the previous block of the chain, if any.
+    previous_block_hash: Option<CryptoHash>,
+    /// The epoch of the block.
+    epoch: Epoch,
+    /// The timestamp of the block.
+    timestamp: Timestamp,
+    /// The signer of the block, if any.
+    authenticated_signer: Option<Owner>,
+    /// The number of incoming messages executed by the block.
+    incoming_message_count: u32,
+    /// The number of operations executed by the block.
+    operation_count: u32,
+    /// The number of messages sent by the block.
+    outgoing_message_count: u32,
+    /// The hash of the chain's execution state after the block.
+    state_hash: CryptoHash,
+}
+
+/// A message sent by a block, as registered by the indexer.
+#[derive(Deserialize, Serialize, Clone, SimpleObject, Debug)]
+pub struct IndexedMessage {
+    /// The hash of the block sending the message.
+    block: CryptoHash,
+    /// The index of the message in the block.
+    index: u32,
+    /// The destination of the message.
+    destination: Destination,
+    /// The user authentication carried by the message, if any.
+    authenticated_signer: Option<Owner>,
+    /// A grant to pay for the message execution.
+    grant: Amount,
+    /// The kind of the message.
+    kind: MessageKind,
+    /// The message itself.
+    content: Message,
+}
+
+#[derive(RootView)]
+pub struct Blocks<C> {
+    /// The registered blocks, indexed by their hash
+    blocks: MapView<C, CryptoHash, IndexedBlock>,
+    /// The hash of the registered block at each height of each chain
+    heights: MapView<C, (ChainId, BlockHeight), CryptoHash>,
+    /// The messages sent by each registered block
+    messages: MapView<C, CryptoHash, Vec<IndexedMessage>>,
+    /// The latest registered block of each chain
+    last: MapView<C, ChainId, CryptoHash>,
+}
+
+/// Implements helper functions on the `RootView`
+impl<C> Blocks<C>
+where
+    C: Context + Send + Sync + 'static + Clone,
+    ViewError: From<C::Error>,
+{
+    /// Registers a block and the messages it sends, unless it is already registered
+    fn register_block(
+        &mut self,
+        block: IndexedBlock,
+        messages: Vec<IndexedMessage>,
+    ) -> Result<(), IndexerError> {
+        info!(
+            "register block {:?} of {:?} ({})",
+            block.hash, block.chain_id, block.height
+        );
+        self.heights
+            .insert(&(block.chain_id, block.height), block.hash)?;
+        self.last.insert(&block.chain_id, block.hash)?;
+        self.messages.insert(&block.hash, messages)?;
+        let hash = block.hash;
+        Ok(self.blocks.insert(&hash, block)?)
+    }
+
+    /// Gets the hash of the block at the given height of a chain, or of its latest block
+    async fn block_hash(
+        &self,
+        chain_id: ChainId,
+        height: Option<BlockHeight>,
+    ) -> Result<Option<CryptoHash>, IndexerError> {
+        Ok(match height {
+            None => self.last.get(&chain_id).await?,
+            Some(height) => self.heights.get(&(chain_id, height)).await?,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct BlocksPlugin<C>(Arc<Mutex<Blocks<C>>>);
+
+static NAME: &str = "blocks";
+
+/// Implements `Plugin`
+#[async_trait::async_trait]
+impl<S> Plugin<S> for BlocksPlugin<ContextFromStore<(), S>>
+where
+    S: KeyValueStore + Clone + Send + Sync + 'static,
+    S::Error: From<bcs::Error> + Send + Sync + std::error::Error + 'static,
+    ViewError: From<S::Error>,
+{
+    fn name(&self) -> String {
+        NAME.to_string()
+    }
+
+    async fn load(store: S) -> Result<Self, IndexerError>
+    where
+        Self: Sized,
+    {
+        Ok(Self(load(store, NAME).await?))
+    }
+
+    async fn register(&self, value: &HashedCertificateValue) -> Result<(), IndexerError> {
+        let mut plugin = self.0.lock().await;
+        let Some(executed_block) = value.inner().executed_block() else {
+            return Ok(());
+        };
+        let hash = value.hash();
+        if plugin.blocks.contains_key(&hash).await? {
+            return Ok(());
+        }
+        let block = &executed_block.block;
+        let outcome = &executed_block.outcome;
+        let messages = outcome
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| IndexedMessage {
+                block: hash,
+                index: index as u32,
+                destination: message.destination.clone(),
+                authenticated_signer: message.authenticated_signer,
+                grant: message.grant,
+                kind: message.kind,
+                content: message.message.clone(),
+            })
+            .collect();
+        let indexed_block = IndexedBlock {
+            hash,
+            chain_id: block.chain_id,
+            height: block.height,
+            previous_block_hash: block.previous_block_hash,
+            epoch: block.epoch,
+            timestamp: block.timestamp,
+            authenticated_signer: block.authenticated_signer,
+            incoming_message_count: block.incoming_messages.len() as u32,
+            operation_count: block.operations.len() as u32,
+            outgoing_message_count: outcome.messages.len() as u32,
+            state_hash: outcome.state_hash,
+        };
+        plugin.register_block(indexed_block, messages)?;
+        Ok(plugin.save().await?)
+    }
+
+    fn sdl(&self) -> String {
+        sdl(self.clone())
+    }
+
+    fn route(&self, app: Router) -> Router {
+        route(&self.name(), self.clone(), app)
+    }
+}
+
+/// Implements `ObjectType`
+#[Object]
+impl<C> BlocksPlugin<C>
+where
+    C: Context + Send + Sync + 'static + Clone,
+    ViewError: From<C::Error>,
+{
+    /// Gets the block with the given hash
+    pub async fn block(&self, hash: CryptoHash) -> Result<Option<IndexedBlock>, IndexerError> {
+        let plugin = self.0.lock().await;
+        Ok(plugin.blocks.get(&hash).await?)
+    }
+
+    /// Gets the block at the given height of a chain, or its latest block
+    pub async fn block_at(
+        &self,
+        chain_id: ChainId,
+        height: Option<BlockHeight>,
+    ) -> Result<Option<IndexedBlock>, IndexerError> {
+        let plugin = self.0.lock().await;
+        let Some(hash) = plugin.block_hash(chain_id, height).await? else {
+            return Ok(None);
+        };
+        Ok(plugin.blocks.get(&hash).await?)
+    }
+
+    /// Gets the blocks of a chain in downward order from the given height or from its latest block
+    pub async fn blocks(
+        &self,
+        chain_id: ChainId,
+        from: Option<BlockHeight>,
+        limit: Option<u32>,
+    ) -> Result<Vec<IndexedBlock>, IndexerError> {
+        let plugin = self.0.lock().await;
+        let mut hash = plugin.block_hash(chain_id, from).await?;
+        let mut result = Vec::new();
+        let limit = limit.unwrap_or(20);
+        for _ in 0..limit {
+            let Some(next_hash) = hash else { break };
+            match plugin.blocks.get(&next_hash).await? {
+                None => break,
+                Some(block) => {
+                    hash = block.previous_block_hash;
+                    result.push(block)
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Gets the messages sent by the block with the given hash
+    pub async fn messages(&self, hash: CryptoHash) -> Result<Vec<IndexedMessage>, IndexerError> {
+        let plugin = self.0.lock().await;
+        Ok(plugin.messages.get(&hash).await?.unwrap_or_default())
+    }
+
+    /// Gets the hash of the latest block registered for a chain
+    pub async fn last(&self, chain_id: ChainId) -> Result<Option<CryptoHash>, IndexerError> {
+        let plugin = self.0.lock().await;
+        Ok(plugin.last.get(&chain_id).await?)
+    }
+}
@@ -3,4 +3,5 @@
 
 //! Plugins for Linera indexer.
 
+pub mod blocks;
 pub mod operations;
@@ -0,0 +1,185 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module verifies Linera certificates without the storage and network stack of a node.
+//!
+//! Starting from a trusted committee, a [`LightClient`] checks the signatures of confirmed
+//! blocks, follows the chains it tracks block by block, and learns the committees of new
+//! epochs from the blocks of the admin chain. Wallets and bridges can use it to verify the
+//! certificates they receive from untrusted sources.
+
+use std::collections::BTreeMap;
+
+use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
+use linera_chain::{
+    data_types::{Certificate, ExecutedBlock},
+    ChainError,
+};
+use linera_execution::{
+    committee::{Committee, Epoch},
+    Message, SystemMessage,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(test)]
+#[path = "unit_tests/light_client_tests.rs"]
+mod tests;
+
+/// An error while verifying a certificate.
+#[derive(Debug, Error)]
+pub enum LightClientError {
+    #[error("The certificate is not for a confirmed block")]
+    NotConfirmedBlock,
+    #[error("The committee of {0:?} is unknown or was removed")]
+    UntrustedEpoch(Epoch),
+    #[error("Invalid certificate: {0}")]
+    InvalidCertificate(#[from] ChainError),
+    #[error("Chain {0} is not tracked")]
+    UntrackedChain(ChainId),
+    #[error("Expected block height {expected} of chain {chain_id}, found {found}")]
+    UnexpectedBlockHeight {
+        chain_id: ChainId,
+        expected: BlockHeight,
+        found: BlockHeight,
+    },
+    #[error("The block does not extend the latest verified block of chain {0}")]
+    InvalidPreviousBlockHash(ChainId),
+}
+
+/// The latest verified block of a chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainTip {
+    /// The height of the block.
+    pub height: BlockHeight,
+    /// The hash of the block.
+    pub hash: CryptoHash,
+}
+
+/// The state of a light client: the trusted committees and the tracked chains.
+///
+/// The state is serializable, so that it can be persisted between sessions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LightClient {
+    /// The admin chain, whose blocks create and remove committees.
+    admin_id: ChainId,
+    /// The committees of the epochs that are trusted.
+    committees: BTreeMap<Epoch, Committee>,
+    /// The latest verified block of each tracked chain, or `None` if no block was verified yet.
+    chains: BTreeMap<ChainId, Option<ChainTip>>,
+}
+
+impl LightClient {
+    /// Creates a light client trusting the given committee of the given epoch. The admin
+    /// chain is tracked from its first block.
+    pub fn new(admin_id: ChainId, epoch: Epoch, committee: Committee) -> Self {
+        LightClient {
+            admin_id,
+            committees: BTreeMap::from([(epoch, committee)]),
+            chains: BTreeMap::from([(admin_id, None)]),
+        }
+    }
+
+    /// Returns the admin chain.
+    pub fn admin_id(&self) -> ChainId {
+        self.admin_id
+    }
+
+    /// Returns the trusted committees.
+    pub fn committees(&self) -> &BTreeMap<Epoch, Committee> {
+        &self.committees
+    }
+
+    /// Tracks the given chain from its first block.
+    pub fn track_chain(&mut self, chain_id: ChainId) {
+        self.chains.entry(chain_id).or_insert(None);
+    }
+
+    /// Tracks the given chain from a trusted checkpoint, e.g. to start following the admin
+    /// chain at a later epoch. Only the blocks after `tip` are verified.
+    pub fn track_chain_from(&mut self, chain_id: ChainId, tip: ChainTip) {
+        self.chains.insert(chain_id, Some(tip));
+    }
+
+    /// Returns the latest verified block of the given chain, if any.
+    pub fn chain_tip(&self, chain_id: ChainId) -> Option<ChainTip> {
+        self.chains.get(&chain_id).copied().flatten()
+    }
+
+    /// Checks that the certificate is for a confirmed block, signed by a quorum of the
+    /// committee of the block's epoch.
+    ///
+    /// Unlike [`LightClient::follow`], this does not check that the block extends its chain.
+    pub fn check_certificate<'a>(
+        &self,
+        certificate: &'a Certificate,
+    ) -> Result<&'a ExecutedBlock, LightClientError> {
+        let value = certificate.value();
+        if !value.is_confirmed() {
+            return Err(LightClientError::NotConfirmedBlock);
+        }
+        let executed_block = value
+            .executed_block()
+            .ok_or(LightClientError::NotConfirmedBlock)?;
+        let epoch = executed_block.block.epoch;
+        let committee = self
+            .committees
+            .get(&epoch)
+            .ok_or(LightClientError::UntrustedEpoch(epoch))?;
+        certificate.check(committee)?;
+        Ok(executed_block)
+    }
+
+    /// Verifies the certificate of the next block of a tracked chain, and makes it the
+    /// chain's latest verified block.
+    ///
+    /// The committees created and removed by the blocks of the admin chain are applied, so
+    /// the admin chain must be followed without gaps to keep the committees up to date.
+    pub fn follow(&mut self, certificate: &Certificate) -> Result<ChainTip, LightClientError> {
+        let executed_block = self.check_certificate(certificate)?;
+        let block = &executed_block.block;
+        let chain_id = block.chain_id;
+        let tip = self
+            .chains
+            .get(&chain_id)
+            .ok_or(LightClientError::UntrackedChain(chain_id))?;
+        let (expected_height, expected_previous_hash) = match tip {
+            None => (BlockHeight::ZERO, None),
+            Some(tip) => (
+                tip.height.try_add_one().map_err(ChainError::from)?,
+                Some(tip.hash),
+            ),
+        };
+        if block.height != expected_height {
+            return Err(LightClientError::UnexpectedBlockHeight {
+                chain_id,
+                expected: expected_height,
+                found: block.height,
+            });
+        }
+        if block.previous_block_hash != expected_previous_hash {
+            return Err(LightClientError::InvalidPreviousBlockHash(chain_id));
+        }
+        if chain_id == self.admin_id {
+            self.apply_reconfigurations(executed_block);
+        }
+        let tip = ChainTip {
+            height: block.height,
+            hash: certificate.hash(),
+        };
+        self.chains.insert(chain_id, Some(tip));
+        Ok(tip)
+    }
+
+    /// Applies the committee changes announced by a block of the admin chain: each update
+    /// sends the full set of current committees to the admin channel.
+    fn apply_reconfigurations(&mut self, executed_block: &ExecutedBlock) {
+        for outgoing_message in &executed_block.outcome.messages {
+            if let Message::System(SystemMessage::SetCommittees { committees, .. }) =
+                &outgoing_message.message
+            {
+                self.committees = committees.clone();
+            }
+        }
+    }
+}
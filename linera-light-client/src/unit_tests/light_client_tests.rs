@@ -0,0 +1,195 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use assert_matches::assert_matches;
+use linera_base::{
+    crypto::{CryptoHash, KeyPair},
+    data_types::{Amount, Round},
+    identifiers::Destination,
+};
+use linera_chain::{
+    data_types::{
+        Block, BlockExecutionOutcome, ExecutedBlock, HashedCertificateValue, LiteVote,
+        OutgoingMessage,
+    },
+    test::{make_child_block, make_first_block, BlockTestExt},
+};
+use linera_execution::{committee::ValidatorName, system::SystemChannel, MessageKind};
+
+use super::*;
+
+/// A committee of four validators, with equal votes.
+struct TestCommittee {
+    key_pairs: Vec<KeyPair>,
+    committee: Committee,
+}
+
+impl TestCommittee {
+    fn new() -> Self {
+        let key_pairs = (0..4).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+        let names = key_pairs
+            .iter()
+            .map(|key_pair| ValidatorName(key_pair.public()))
+            .collect();
+        let committee = Committee::make_simple(names);
+        TestCommittee {
+            key_pairs,
+            committee,
+        }
+    }
+
+    /// Returns a certificate for the value, signed by the first `count` validators.
+    fn certify(&self, value: HashedCertificateValue, count: usize) -> Certificate {
+        let signatures = self.key_pairs[..count]
+            .iter()
+            .map(|key_pair| {
+                let vote = LiteVote::new(value.lite(), Round::Fast, key_pair);
+                (vote.validator, vote.signature)
+            })
+            .collect();
+        Certificate::new(value, Round::Fast, signatures)
+    }
+}
+
+fn execute(block: Block, messages: Vec<OutgoingMessage>) -> ExecutedBlock {
+    BlockExecutionOutcome {
+        messages,
+        message_counts: vec![],
+        state_hash: CryptoHash::test_hash("state"),
+        oracle_records: vec![],
+        fuel_used: 0,
+        events: Vec::new(),
+    }
+    .with(block)
+}
+
+fn confirmed(block: Block, messages: Vec<OutgoingMessage>) -> HashedCertificateValue {
+    HashedCertificateValue::new_confirmed(execute(block, messages))
+}
+
+fn set_committees(epoch: Epoch, committees: BTreeMap<Epoch, Committee>) -> OutgoingMessage {
+    OutgoingMessage {
+        destination: Destination::Subscribers(SystemChannel::Admin.name()),
+        authenticated_signer: None,
+        grant: Amount::ZERO,
+        refund_grant_to: None,
+        kind: MessageKind::Protected,
+        expiration: None,
+        message: Message::System(SystemMessage::SetCommittees { epoch, committees }),
+    }
+}
+
+#[test]
+fn test_follow_chain() {
+    let validators = TestCommittee::new();
+    let admin_id = ChainId::root(0);
+    let chain_id = ChainId::root(1);
+    let mut client = LightClient::new(admin_id, Epoch::ZERO, validators.committee.clone());
+
+    let value0 = confirmed(make_first_block(chain_id), vec![]);
+    let certificate0 = validators.certify(value0.clone(), 3);
+    assert_matches!(
+        client.follow(&certificate0),
+        Err(LightClientError::UntrackedChain(id)) if id == chain_id
+    );
+    assert!(client.check_certificate(&certificate0).is_ok());
+
+    client.track_chain(chain_id);
+    let tip0 = client.follow(&certificate0).unwrap();
+    assert_eq!(tip0.height, BlockHeight::ZERO);
+    assert_eq!(tip0.hash, value0.hash());
+
+    let value1 = confirmed(make_child_block(&value0), vec![]);
+    let certificate1 = validators.certify(value1.clone(), 3);
+    let tip1 = client.follow(&certificate1).unwrap();
+    assert_eq!(client.chain_tip(chain_id), Some(tip1));
+    assert_matches!(
+        client.follow(&certificate1),
+        Err(LightClientError::UnexpectedBlockHeight { .. })
+    );
+
+    let mut fork = make_child_block(&value1);
+    fork.previous_block_hash = Some(value0.hash());
+    let certificate = validators.certify(confirmed(fork, vec![]), 3);
+    assert_matches!(
+        client.follow(&certificate),
+        Err(LightClientError::InvalidPreviousBlockHash(id)) if id == chain_id
+    );
+    assert_eq!(client.chain_tip(chain_id), Some(tip1));
+}
+
+#[test]
+fn test_certificate_requires_confirmed_block_and_quorum() {
+    let validators = TestCommittee::new();
+    let admin_id = ChainId::root(0);
+    let client = LightClient::new(admin_id, Epoch::ZERO, validators.committee.clone());
+
+    let value = confirmed(make_first_block(admin_id), vec![]);
+    let certificate = validators.certify(value.clone(), 2);
+    assert_matches!(
+        client.check_certificate(&certificate),
+        Err(LightClientError::InvalidCertificate(
+            ChainError::CertificateRequiresQuorum
+        ))
+    );
+
+    let other_validators = TestCommittee::new();
+    let certificate = other_validators.certify(value, 3);
+    assert_matches!(
+        client.check_certificate(&certificate),
+        Err(LightClientError::InvalidCertificate(_))
+    );
+
+    let executed_block = execute(make_first_block(admin_id), vec![]);
+    let value = HashedCertificateValue::new_validated(executed_block);
+    let certificate = validators.certify(value, 4);
+    assert_matches!(
+        client.check_certificate(&certificate),
+        Err(LightClientError::NotConfirmedBlock)
+    );
+}
+
+#[test]
+fn test_reconfiguration() {
+    let validators0 = TestCommittee::new();
+    let validators1 = TestCommittee::new();
+    let admin_id = ChainId::root(0);
+    let chain_id = ChainId::root(1);
+    let mut client = LightClient::new(admin_id, Epoch::ZERO, validators0.committee.clone());
+    client.track_chain(chain_id);
+
+    let block = make_first_block(chain_id)
+        .with_epoch(1)
+        .with_simple_transfer(admin_id, Amount::ONE);
+    let user_certificate = validators1.certify(confirmed(block, vec![]), 3);
+    assert_matches!(
+        client.check_certificate(&user_certificate),
+        Err(LightClientError::UntrustedEpoch(Epoch(1)))
+    );
+
+    // The admin chain creates the committee of epoch 1.
+    let committees = BTreeMap::from([
+        (Epoch::ZERO, validators0.committee.clone()),
+        (Epoch(1), validators1.committee.clone()),
+    ]);
+    let message = set_committees(Epoch(1), committees);
+    let admin_value0 = confirmed(make_first_block(admin_id), vec![message]);
+    client
+        .follow(&validators0.certify(admin_value0.clone(), 3))
+        .unwrap();
+    assert_eq!(client.committees().len(), 2);
+    client.follow(&user_certificate).unwrap();
+
+    // The admin chain removes the committee of epoch 0.
+    let committees = BTreeMap::from([(Epoch(1), validators1.committee.clone())]);
+    let message = set_committees(Epoch(1), committees);
+    let admin_value1 = confirmed(make_child_block(&admin_value0).with_epoch(1), vec![message]);
+    client
+        .follow(&validators1.certify(admin_value1, 3))
+        .unwrap();
+    let value = confirmed(make_first_block(ChainId::root(2)), vec![]);
+    assert_matches!(
+        client.check_certificate(&validators0.certify(value, 3)),
+        Err(LightClientError::UntrustedEpoch(Epoch::ZERO))
+    );
+}
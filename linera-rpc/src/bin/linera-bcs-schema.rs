@@ -0,0 +1,35 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prints the BCS formats of the types exchanged with Linera validators, for SDKs written in
+//! other languages to generate their serializers.
+
+use std::path::PathBuf;
+
+use linera_rpc::schema::{self, SchemaFormat};
+
+#[derive(clap::Parser)]
+#[command(
+    name = "linera-bcs-schema",
+    version = linera_version::VersionInfo::default_clap_str(),
+    about = "Prints the BCS formats of the Linera wire protocol, as a serde-reflection registry",
+)]
+struct Options {
+    /// The format of the registry.
+    #[arg(long, value_enum, default_value_t)]
+    format: SchemaFormat,
+
+    /// Writes the registry to this file instead of the standard output.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let options = <Options as clap::Parser>::parse();
+    let schema = schema::export(options.format)?;
+    match options.output {
+        Some(path) => std::fs::write(path, schema)?,
+        None => print!("{schema}"),
+    }
+    Ok(())
+}
@@ -1,9 +1,12 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_base::identifiers::ChainId;
+use linera_base::{
+    crypto::CryptoHash,
+    identifiers::{Blob, BlobId, ChainId},
+};
 use linera_chain::data_types::{
-    BlockProposal, Certificate, HashedCertificateValue, LiteCertificate,
+    Block, BlockProposal, Certificate, ExecutedBlock, HashedCertificateValue, LiteCertificate,
 };
 #[cfg(web)]
 use linera_core::node::{
@@ -110,6 +113,28 @@ impl ValidatorNode for Client {
         }
     }
 
+    async fn simulate_block_proposal(&mut self, block: Block) -> Result<ExecutedBlock, NodeError> {
+        match self {
+            Client::Grpc(grpc_client) => grpc_client.simulate_block_proposal(block).await,
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => simple_client.simulate_block_proposal(block).await,
+        }
+    }
+
+    async fn download_blob(
+        &mut self,
+        chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Result<Blob, NodeError> {
+        match self {
+            Client::Grpc(grpc_client) => grpc_client.download_blob(chain_id, blob_id).await,
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => simple_client.download_blob(chain_id, blob_id).await,
+        }
+    }
+
     async fn subscribe(
         &mut self,
         chains: Vec<ChainId>,
@@ -130,4 +155,13 @@ impl ValidatorNode for Client {
             Client::Simple(simple_client) => simple_client.get_version_info().await?,
         })
     }
+
+    async fn get_genesis_config_hash(&mut self) -> Result<CryptoHash, NodeError> {
+        Ok(match self {
+            Client::Grpc(grpc_client) => grpc_client.get_genesis_config_hash().await?,
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => simple_client.get_genesis_config_hash().await?,
+        })
+    }
 }
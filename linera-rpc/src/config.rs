@@ -1,6 +1,13 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
 use linera_base::identifiers::ChainId;
 use serde::{Deserialize, Serialize};
 
@@ -13,13 +20,15 @@ pub struct CrossChainConfig {
     #[arg(long = "cross-chain-queue-size", default_value = "1000")]
     pub(crate) queue_size: usize,
 
-    /// Maximum number of retries for a cross-chain message.
-    #[arg(long = "cross-chain-max-retries", default_value = "10")]
-    pub(crate) max_retries: u32,
+    /// Maximum number of retries for a cross-chain message. Defaults to the cross-chain
+    /// retry policy of the server configuration, or to 10.
+    #[arg(long = "cross-chain-max-retries")]
+    pub(crate) max_retries: Option<u32>,
 
-    /// Delay before retrying of cross-chain message.
-    #[arg(long = "cross-chain-retry-delay-ms", default_value = "2000")]
-    pub(crate) retry_delay_ms: u64,
+    /// Delay before retrying of cross-chain message. Defaults to the cross-chain retry
+    /// policy of the server configuration, or to 2000.
+    #[arg(long = "cross-chain-retry-delay-ms")]
+    pub(crate) retry_delay_ms: Option<u64>,
 
     /// Introduce a delay before sending every cross-chain message (e.g. for testing purpose).
     #[arg(long = "cross-chain-sender-delay-ms", default_value = "0")]
@@ -29,9 +38,148 @@ pub struct CrossChainConfig {
     #[arg(long = "cross-chain-sender-failure-rate", default_value = "0.0")]
     pub(crate) sender_failure_rate: f32,
 
-    /// How many concurrent tasks to spawn for cross-chain message handling RPCs.
+    /// Send cross-chain messages twice randomly at the given rate (0 <= rate <= 1) (meant for
+    /// testing).
+    #[arg(long = "cross-chain-sender-duplicate-rate", default_value = "0.0")]
+    pub(crate) sender_duplicate_rate: f32,
+
+    /// How many concurrent tasks to spawn for cross-chain message handling RPCs. The
+    /// messages to different shards are sent in parallel, and the messages to the same
+    /// shard in order.
     #[arg(long = "cross-chain-max-tasks", default_value = "10")]
     pub(crate) max_concurrent_tasks: usize,
+
+    /// The maximum number of queued cross-chain messages sent to the same shard in one RPC
+    /// (gRPC only). Batching is disabled with 1, e.g. while upgrading from shards that don't
+    /// support it.
+    #[arg(long = "cross-chain-max-batch-size", default_value = "32")]
+    pub(crate) max_batch_size: usize,
+
+    /// How long to wait for more cross-chain messages to the same shard after the first one
+    /// of a batch, in milliseconds (gRPC only). By default, only the messages that are
+    /// already queued are batched.
+    #[arg(long = "cross-chain-batch-delay-ms", default_value = "0")]
+    pub(crate) batch_delay_ms: u64,
+}
+
+impl CrossChainConfig {
+    /// Uses the given retry `policy`, e.g. from a configuration file, for the retry options
+    /// that are not set.
+    pub fn with_retry_policy(mut self, policy: Option<RetryPolicy>) -> Self {
+        if let Some(policy) = policy {
+            self.max_retries = self.max_retries.or(Some(policy.max_retries));
+            self.retry_delay_ms = self.retry_delay_ms.or(Some(policy.retry_delay_ms));
+        }
+        self
+    }
+
+    /// Returns the policy for retrying to send cross-chain messages.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::CROSS_CHAIN.with_overrides(self.max_retries, self.retry_delay_ms)
+    }
+}
+
+/// How long to wait for a request to be sent and for its response to be received.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeoutPolicy {
+    /// Timeout for sending a request, in milliseconds.
+    pub send_timeout_ms: u64,
+    /// Timeout for receiving a response, in milliseconds.
+    pub recv_timeout_ms: u64,
+}
+
+impl Default for TimeoutPolicy {
+    fn default() -> Self {
+        TimeoutPolicy {
+            send_timeout_ms: 4000,
+            recv_timeout_ms: 4000,
+        }
+    }
+}
+
+impl TimeoutPolicy {
+    /// Returns the policy with the timeouts that are set, e.g. from the command line, instead
+    /// of its own.
+    pub fn with_overrides(
+        self,
+        send_timeout_ms: Option<u64>,
+        recv_timeout_ms: Option<u64>,
+    ) -> Self {
+        TimeoutPolicy {
+            send_timeout_ms: send_timeout_ms.unwrap_or(self.send_timeout_ms),
+            recv_timeout_ms: recv_timeout_ms.unwrap_or(self.recv_timeout_ms),
+        }
+    }
+
+    pub fn send_timeout(&self) -> Duration {
+        Duration::from_millis(self.send_timeout_ms)
+    }
+
+    pub fn recv_timeout(&self) -> Duration {
+        Duration::from_millis(self.recv_timeout_ms)
+    }
+}
+
+/// How many times to retry a failed request, and how long to wait before each retry. The
+/// delay increases linearly: the `n`-th retry waits `n` times `retry_delay_ms`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts after the first one.
+    pub max_retries: u32,
+    /// The delay increment between two attempts, in milliseconds.
+    pub retry_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    /// The default policy for reconnecting to a validator to receive notifications.
+    pub const NOTIFICATIONS: RetryPolicy = RetryPolicy {
+        max_retries: 10,
+        retry_delay_ms: 1000,
+    };
+
+    /// The default policy for sending cross-chain messages to other shards.
+    pub const CROSS_CHAIN: RetryPolicy = RetryPolicy {
+        max_retries: 10,
+        retry_delay_ms: 2000,
+    };
+
+    /// Returns the policy with the options that are set, e.g. from the command line, instead
+    /// of its own.
+    pub fn with_overrides(self, max_retries: Option<u32>, retry_delay_ms: Option<u64>) -> Self {
+        RetryPolicy {
+            max_retries: max_retries.unwrap_or(self.max_retries),
+            retry_delay_ms: retry_delay_ms.unwrap_or(self.retry_delay_ms),
+        }
+    }
+
+    /// Returns how long to wait before the given retry, starting from 0 for the first
+    /// attempt.
+    pub fn delay(&self, retry: u32) -> Duration {
+        Duration::from_millis(self.retry_delay_ms).saturating_mul(retry)
+    }
+}
+
+/// The timeout and retry policies of the RPC clients, as set in the wallet or in the
+/// server configuration. The command-line options take precedence, and the defaults are
+/// used for the policies that are not set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcPolicies {
+    /// The timeouts of the requests to the validators, or from a proxy to its shards.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<TimeoutPolicy>,
+    /// How to retry connecting to a validator to receive notifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_retry: Option<RetryPolicy>,
+    /// How to retry sending cross-chain messages to the other shards of a validator.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cross_chain_retry: Option<RetryPolicy>,
+}
+
+impl RpcPolicies {
+    /// Returns whether no policy is set.
+    pub fn is_empty(&self) -> bool {
+        *self == RpcPolicies::default()
+    }
 }
 
 #[derive(Clone, Debug, clap::Parser)]
@@ -43,6 +191,14 @@ pub struct NotificationConfig {
 
 pub type ShardId = usize;
 
+/// Returns the shard handling `chain_id`, for a validator with `num_shards` shards.
+pub fn shard_id(chain_id: ChainId, num_shards: usize) -> ShardId {
+    use std::hash::{Hash, Hasher};
+    let mut s = std::collections::hash_map::DefaultHasher::new();
+    chain_id.hash(&mut s);
+    (s.finish() as ShardId) % num_shards
+}
+
 /// The network configuration of a shard.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ShardConfig {
@@ -54,6 +210,36 @@ pub struct ShardConfig {
     pub metrics_host: String,
     /// The port on which metrics are served.
     pub metrics_port: Option<u16>,
+    /// The region the shard is deployed in, if known.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// The availability zone the shard is deployed in, if known.
+    #[serde(default)]
+    pub zone: Option<String>,
+    /// Other servers providing the same shard, e.g. in other availability zones. They only
+    /// receive the read-only requests: the requests changing the chains always go to the
+    /// shard itself, so that a single server writes to their storage.
+    #[serde(default)]
+    pub replicas: Vec<ShardReplicaConfig>,
+    /// Read-only servers following the storage of this shard. The proxy sends them the
+    /// chain information queries that don't vote, so that heavy query load doesn't slow
+    /// down the handling of block proposals and certificates.
+    #[serde(default)]
+    pub read_replicas: Vec<ShardReplicaConfig>,
+    /// A server receiving a copy of a sample of the requests for this shard, e.g. to test a
+    /// new version of the worker against production traffic. Its responses are discarded.
+    #[serde(default)]
+    pub shadow: Option<ShadowShardConfig>,
+    /// A server receiving some of the requests for this shard instead of it, e.g. to validate
+    /// a new version of the worker with production traffic before rolling it out to all the
+    /// shards. Its responses are returned to the clients.
+    #[serde(default)]
+    pub canary: Option<CanaryShardConfig>,
+    /// A Unix domain socket on which the shard listens instead of its port, to avoid the
+    /// overhead of TCP when the proxy and the other shards run on the same host. Only
+    /// supported with gRPC.
+    #[serde(default)]
+    pub unix_socket: Option<PathBuf>,
 }
 
 impl ShardConfig {
@@ -64,6 +250,228 @@ impl ShardConfig {
     pub fn http_address(&self) -> String {
         format!("http://{}:{}", self.host, self.port)
     }
+
+    /// Returns the canary of this shard if the requests for the given chain are sent to it.
+    pub fn canary_for(&self, chain_id: ChainId) -> Option<&CanaryShardConfig> {
+        self.canary.as_ref().filter(|canary| canary.routes(chain_id))
+    }
+
+    /// Returns the endpoint of the shard itself, as seen from the given location.
+    pub fn primary_endpoint(&self, region: Option<&str>, zone: Option<&str>) -> ShardEndpoint<'_> {
+        ShardEndpoint {
+            host: &self.host,
+            port: self.port,
+            unix_socket: self.unix_socket.as_deref(),
+            locality: Locality::between(
+                (region, zone),
+                (self.region.as_deref(), self.zone.as_deref()),
+            ),
+        }
+    }
+
+    /// Returns the endpoint of this shard closest to the given location: the shard itself or
+    /// one of its replicas. Ties are resolved in favor of the shard itself, then of the first
+    /// replicas listed. Only read-only requests may be sent to the replicas.
+    pub fn closest_endpoint(&self, region: Option<&str>, zone: Option<&str>) -> ShardEndpoint<'_> {
+        let primary = self.primary_endpoint(region, zone);
+        let replicas = self.replicas.iter().map(|replica| ShardEndpoint {
+            host: &replica.host,
+            port: replica.port,
+            unix_socket: None,
+            locality: Locality::between(
+                (region, zone),
+                (replica.region.as_deref(), replica.zone.as_deref()),
+            ),
+        });
+        std::iter::once(primary)
+            .chain(replicas)
+            .min_by_key(|endpoint| endpoint.locality)
+            .expect("there is at least one endpoint")
+    }
+
+    /// Returns one of the read replicas of this shard closest to the given location, or
+    /// `None` if it has none. Ties are resolved by the `sample`, so that random samples
+    /// spread the queries evenly over the equally close replicas.
+    pub fn closest_read_endpoint(
+        &self,
+        region: Option<&str>,
+        zone: Option<&str>,
+        sample: usize,
+    ) -> Option<ShardEndpoint<'_>> {
+        let endpoints = self
+            .read_replicas
+            .iter()
+            .map(|replica| ShardEndpoint {
+                host: &replica.host,
+                port: replica.port,
+                unix_socket: None,
+                locality: Locality::between(
+                    (region, zone),
+                    (replica.region.as_deref(), replica.zone.as_deref()),
+                ),
+            })
+            .collect::<Vec<_>>();
+        let best = endpoints.iter().map(|endpoint| endpoint.locality).min()?;
+        let closest = endpoints
+            .into_iter()
+            .filter(|endpoint| endpoint.locality == best)
+            .collect::<Vec<_>>();
+        Some(closest[sample % closest.len()])
+    }
+}
+
+/// Another server providing the same shard as a [`ShardConfig`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardReplicaConfig {
+    /// The host name (e.g an IP address).
+    pub host: String,
+    /// The port.
+    pub port: u16,
+    /// The region the replica is deployed in, if known.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// The availability zone the replica is deployed in, if known.
+    #[serde(default)]
+    pub zone: Option<String>,
+}
+
+/// A server to which the proxy mirrors some of the requests for a [`ShardConfig`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShadowShardConfig {
+    /// The host name (e.g an IP address).
+    pub host: String,
+    /// The port.
+    pub port: u16,
+    /// The percentage of the requests to mirror, between 0 and 100.
+    pub percentage: u8,
+}
+
+impl ShadowShardConfig {
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    pub fn http_address(&self) -> String {
+        format!("http://{}:{}", self.host, self.port)
+    }
+
+    /// Returns whether a request should be mirrored, given a sample drawn uniformly in
+    /// `0..100`.
+    pub fn mirrors(&self, sample: u8) -> bool {
+        sample < self.percentage
+    }
+}
+
+/// A server to which the proxy sends some of the requests for a [`ShardConfig`] instead of
+/// the shard itself. It must use the same storage as the shard.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanaryShardConfig {
+    /// The host name (e.g an IP address).
+    pub host: String,
+    /// The port.
+    pub port: u16,
+    /// The percentage of the chains whose requests are all sent to the canary, between 0 and
+    /// 100.
+    #[serde(default)]
+    pub percentage: u8,
+    /// The prefixes of the hexadecimal chain IDs whose requests are all sent to the canary.
+    #[serde(default)]
+    pub chain_id_prefixes: Vec<String>,
+}
+
+impl CanaryShardConfig {
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    pub fn http_address(&self) -> String {
+        format!("http://{}:{}", self.host, self.port)
+    }
+
+    /// Returns whether the requests for the given chain should be sent to the canary. This
+    /// only depends on the chain, so that the shard and its canary never handle requests for
+    /// the same chain.
+    pub fn routes(&self, chain_id: ChainId) -> bool {
+        // Unlike the hash assigning the chains to the shards, the chain IDs themselves are
+        // uniformly distributed among the chains of a shard.
+        let sample = <[u64; 4]>::from(chain_id.0)[0] % 100;
+        if sample < u64::from(self.percentage) {
+            return true;
+        }
+        if self.chain_id_prefixes.is_empty() {
+            return false;
+        }
+        let chain_id = chain_id.to_string();
+        self.chain_id_prefixes
+            .iter()
+            .any(|prefix| chain_id.starts_with(&prefix.to_lowercase()))
+    }
+}
+
+/// A server to send the requests for a shard to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShardEndpoint<'a> {
+    /// The host name (e.g an IP address).
+    pub host: &'a str,
+    /// The port.
+    pub port: u16,
+    /// The Unix domain socket the server listens on instead of its port, if any.
+    pub unix_socket: Option<&'a Path>,
+    /// Where the server is, relative to the sender of the requests.
+    pub locality: Locality,
+}
+
+impl ShardEndpoint<'_> {
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    pub fn http_address(&self) -> String {
+        format!("http://{}:{}", self.host, self.port)
+    }
+}
+
+/// Where a server is relative to another one. Variants are ordered by preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Locality {
+    /// Both servers are in the same availability zone.
+    SameZone,
+    /// The servers are in different availability zones of the same region.
+    CrossZone,
+    /// The location of one of the servers is not configured.
+    Unknown,
+    /// The servers are in different regions.
+    CrossRegion,
+}
+
+impl Locality {
+    /// Compares two `(region, zone)` locations.
+    pub fn between(
+        (region, zone): (Option<&str>, Option<&str>),
+        (other_region, other_zone): (Option<&str>, Option<&str>),
+    ) -> Self {
+        match (region, other_region) {
+            (Some(region), Some(other_region)) if region != other_region => {
+                return Locality::CrossRegion
+            }
+            _ => {}
+        }
+        match (zone, other_zone) {
+            (Some(zone), Some(other_zone)) if zone == other_zone => Locality::SameZone,
+            (Some(_), Some(_)) => Locality::CrossZone,
+            _ => Locality::Unknown,
+        }
+    }
+
+    /// A short name for the locality, to label metrics with.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locality::SameZone => "same_zone",
+            Locality::CrossZone => "cross_zone",
+            Locality::Unknown => "unknown",
+            Locality::CrossRegion => "cross_region",
+        }
+    }
 }
 
 /// The network protocol.
@@ -115,6 +523,34 @@ pub struct ValidatorInternalNetworkPreConfig<P> {
     pub metrics_host: String,
     /// The port of the proxy's metrics endpoint.
     pub metrics_port: u16,
+    /// The region the proxy is deployed in, if known.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// The availability zone the proxy is deployed in, if known.
+    #[serde(default)]
+    pub zone: Option<String>,
+    /// The certificates with which the proxy and the shards authenticate each other, if the
+    /// internal network uses mutual TLS. Only supported with gRPC.
+    #[serde(default)]
+    pub mutual_tls: Option<InternalTlsConfig>,
+}
+
+/// The certificates with which the proxy and the shards of a validator authenticate each
+/// other on the internal network.
+///
+/// They all present the same certificate, issued for `domain_name` by the certificate
+/// authority, and only accept connections from peers presenting a certificate issued by it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InternalTlsConfig {
+    /// The PEM file with the certificate of the certificate authority.
+    pub ca_certificate: PathBuf,
+    /// The PEM file with the certificate presented to the peers.
+    pub certificate: PathBuf,
+    /// The PEM file with the private key of the certificate.
+    pub private_key: PathBuf,
+    /// The name the certificate is issued for. It is checked instead of the host names of
+    /// the peers, which may not be stable.
+    pub domain_name: String,
 }
 
 impl<P> ValidatorInternalNetworkPreConfig<P> {
@@ -126,13 +562,55 @@ impl<P> ValidatorInternalNetworkPreConfig<P> {
             port: self.port,
             metrics_host: self.metrics_host.clone(),
             metrics_port: self.metrics_port,
+            region: self.region.clone(),
+            zone: self.zone.clone(),
+            mutual_tls: self.mutual_tls.clone(),
+        }
+    }
+
+    /// Returns the URI of the gRPC server at `address` on the internal network.
+    pub fn grpc_uri(&self, address: impl Display) -> String {
+        let scheme = if self.mutual_tls.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        format!("{scheme}://{address}")
+    }
+
+    /// Returns the URI of the gRPC server of a shard on the internal network.
+    pub fn shard_grpc_uri(&self, shard: &ShardConfig) -> String {
+        match &shard.unix_socket {
+            Some(path) => unix_socket_uri(path),
+            None => self.grpc_uri(shard.address()),
         }
     }
+
+    /// Returns the URI of the gRPC server of a shard endpoint on the internal network.
+    pub fn endpoint_grpc_uri(&self, endpoint: &ShardEndpoint<'_>) -> String {
+        match endpoint.unix_socket {
+            Some(path) => unix_socket_uri(path),
+            None => self.grpc_uri(endpoint.address()),
+        }
+    }
+}
+
+/// The prefix of the URIs of gRPC servers listening on a Unix domain socket.
+pub const UNIX_SOCKET_URI_PREFIX: &str = "unix:";
+
+/// Returns the URI of the gRPC server listening on the Unix domain socket at `path`.
+pub fn unix_socket_uri(path: &Path) -> String {
+    format!("{UNIX_SOCKET_URI_PREFIX}{}", path.display())
 }
 
 impl ValidatorInternalNetworkConfig {
     pub fn proxy_address(&self) -> String {
-        format!("{}://{}:{}", self.protocol.scheme(), self.host, self.port)
+        let scheme = if self.mutual_tls.is_some() {
+            "https"
+        } else {
+            self.protocol.scheme()
+        };
+        format!("{}://{}:{}", scheme, self.host, self.port)
     }
 }
 
@@ -225,13 +703,41 @@ impl std::str::FromStr for NetworkProtocol {
     }
 }
 
+/// A chain that a new configuration of the internal network assigns to another shard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ShardMove {
+    /// The chain being moved.
+    pub chain_id: ChainId,
+    /// The shard of the chain in the old configuration.
+    pub old_shard: ShardId,
+    /// The shard of the chain in the new configuration.
+    pub new_shard: ShardId,
+}
+
 impl<P> ValidatorInternalNetworkPreConfig<P> {
     /// Static shard assignment
     pub fn get_shard_id(&self, chain_id: ChainId) -> ShardId {
-        use std::hash::{Hash, Hasher};
-        let mut s = std::collections::hash_map::DefaultHasher::new();
-        chain_id.hash(&mut s);
-        (s.finish() as ShardId) % self.shards.len()
+        shard_id(chain_id, self.shards.len())
+    }
+
+    /// Returns the given chains that are assigned to a different shard in `new_config`.
+    pub fn shard_moves<Q>(
+        &self,
+        new_config: &ValidatorInternalNetworkPreConfig<Q>,
+        chains: impl IntoIterator<Item = ChainId>,
+    ) -> Vec<ShardMove> {
+        chains
+            .into_iter()
+            .filter_map(|chain_id| {
+                let old_shard = self.get_shard_id(chain_id);
+                let new_shard = new_config.get_shard_id(chain_id);
+                (old_shard != new_shard).then_some(ShardMove {
+                    chain_id,
+                    old_shard,
+                    new_shard,
+                })
+            })
+            .collect()
     }
 
     pub fn shard(&self, shard_id: ShardId) -> &ShardConfig {
@@ -242,4 +748,98 @@ impl<P> ValidatorInternalNetworkPreConfig<P> {
     pub fn get_shard_for(&self, chain_id: ChainId) -> &ShardConfig {
         self.shard(self.get_shard_id(chain_id))
     }
+
+    /// Gets the endpoint of the shard assigned to the `chain_id`, which handles all the
+    /// requests for it.
+    pub fn get_shard_endpoint_for(&self, chain_id: ChainId) -> ShardEndpoint<'_> {
+        self.get_shard_endpoint(self.get_shard_id(chain_id))
+    }
+
+    /// Gets the endpoint of the given shard itself, which handles all the requests for it.
+    pub fn get_shard_endpoint(&self, shard_id: ShardId) -> ShardEndpoint<'_> {
+        self.shard(shard_id)
+            .primary_endpoint(self.region.as_deref(), self.zone.as_deref())
+    }
+
+    /// Gets the endpoint to send a request for the given shard to. Read-only requests go to
+    /// one of the closest read replicas of the shard, chosen by the `sample`, or else to the
+    /// closest of the shard and its replicas. All the other requests go to the shard itself.
+    pub fn get_request_endpoint(
+        &self,
+        shard_id: ShardId,
+        read_only: bool,
+        sample: usize,
+    ) -> ShardEndpoint<'_> {
+        if read_only {
+            let shard = self.shard(shard_id);
+            let region = self.region.as_deref();
+            let zone = self.zone.as_deref();
+            return shard
+                .closest_read_endpoint(region, zone, sample)
+                .unwrap_or_else(|| shard.closest_endpoint(region, zone));
+        }
+        self.get_shard_endpoint(shard_id)
+    }
+
+    /// Returns the metrics endpoints of the proxy and of the shards serving metrics, labeled
+    /// with the name of the `validator`, their role, their shard and their location.
+    pub fn metrics_targets(&self, validator: &str) -> Vec<MetricsTarget> {
+        let proxy = MetricsTarget::new(
+            format!("{}:{}", self.metrics_host, self.metrics_port),
+            [("validator", validator), ("role", "proxy")],
+            self.region.as_deref(),
+            self.zone.as_deref(),
+        );
+        let shards = self
+            .shards
+            .iter()
+            .enumerate()
+            .filter_map(|(shard_id, shard)| {
+                let port = shard.metrics_port?;
+                let shard_id = shard_id.to_string();
+                Some(MetricsTarget::new(
+                    format!("{}:{}", shard.metrics_host, port),
+                    [
+                        ("validator", validator),
+                        ("role", "shard"),
+                        ("shard", &shard_id),
+                    ],
+                    shard.region.as_deref(),
+                    shard.zone.as_deref(),
+                ))
+            });
+        std::iter::once(proxy).chain(shards).collect()
+    }
+}
+
+/// A metrics endpoint with its labels, in the JSON format of the file-based and HTTP service
+/// discovery of Prometheus.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsTarget {
+    /// The `host:port` addresses to scrape.
+    pub targets: Vec<String>,
+    /// The labels attached to the scraped metrics.
+    pub labels: BTreeMap<String, String>,
+}
+
+impl MetricsTarget {
+    fn new<'a>(
+        address: String,
+        labels: impl IntoIterator<Item = (&'a str, &'a str)>,
+        region: Option<&'a str>,
+        zone: Option<&'a str>,
+    ) -> Self {
+        let location = [("region", region), ("zone", zone)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)));
+        let labels = labels
+            .into_iter()
+            .chain(location)
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        MetricsTarget {
+            targets: vec![address],
+            labels,
+        }
+    }
 }
@@ -1,10 +1,21 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{iter, time::Duration};
+use std::{
+    collections::HashMap,
+    iter,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
 use futures::{future, stream, StreamExt};
-use linera_base::identifiers::ChainId;
+use linera_base::{
+    crypto::CryptoHash,
+    identifiers::{Blob, BlobId, ChainId},
+};
 use linera_chain::data_types;
 #[cfg(web)]
 use linera_core::node::{
@@ -26,21 +37,55 @@ use {
 
 use super::{
     api::{
-        chain_info_result::Inner, validator_node_client::ValidatorNodeClient, SubscriptionRequest,
+        self, blob_result, block_simulation_result, chain_info_result::Inner,
+        validator_node_client::ValidatorNodeClient, validator_worker_client::ValidatorWorkerClient,
+        SubscriptionRequest,
     },
-    transport, GrpcError, GRPC_MAX_MESSAGE_SIZE,
+    parse_protocol_version, request_id, set_protocol_version, transport, ContentEncoding,
+    GrpcError, GRPC_MAX_MESSAGE_SIZE,
 };
 use crate::{
-    config::ValidatorPublicNetworkConfig, node_provider::NodeOptions, HandleCertificateRequest,
-    HandleLiteCertRequest,
+    config::{RetryPolicy, ValidatorPublicNetworkConfig},
+    limits,
+    node_provider::NodeOptions,
+    version::{negotiate_protocol_version, PROTOCOL_VERSION, PROTOCOL_VERSION_HEADER},
+    DownloadBlobRequest, HandleCertificateRequest, HandleLiteCertRequest,
 };
 
 #[derive(Clone)]
 pub struct GrpcClient {
     address: String,
     client: ValidatorNodeClient<transport::Channel>,
-    notification_retry_delay: Duration,
-    notification_retries: u32,
+    notification_retry: RetryPolicy,
+    /// The deadline sent with each request, so that the validator stops working on a
+    /// request once we have given up on it.
+    timeout: Duration,
+    /// The version of the wire format used in requests: ours until the validator answers
+    /// with an older one.
+    protocol_version: u16,
+    /// The encoding of the content of proposals and certificates: bincode until the validator
+    /// answers with a version decoding protobuf messages.
+    content_encoding: ContentEncoding,
+    /// The connections to the shards, if the requests about a chain are sent directly to
+    /// the validator's shard of the chain instead of its proxy.
+    direct_shards: Option<Arc<DirectShards>>,
+}
+
+/// The maximum number of chains whose shard address is cached. The cache is cleared when it
+/// is full.
+const MAX_CACHED_CHAINS: usize = 100_000;
+
+/// The shards of a validator that a trusted client sends its requests to directly, skipping
+/// the proxy. The proxy tells the address of the shard of each chain.
+struct DirectShards {
+    transport_options: transport::Options,
+    /// The address of the shard of each chain.
+    addresses: Mutex<HashMap<ChainId, String>>,
+    /// The client of each shard, by address.
+    clients: Mutex<HashMap<String, ValidatorWorkerClient<transport::Channel>>>,
+    /// Whether the proxy refused to give the shard addresses, e.g. because we are not one of
+    /// its trusted peers. The requests then all go through the proxy.
+    refused: AtomicBool,
 }
 
 impl GrpcClient {
@@ -50,8 +95,8 @@ impl GrpcClient {
     ) -> Result<Self, GrpcError> {
         let address = network.http_address();
 
-        let channel =
-            transport::create_channel(address.clone(), &transport::Options::from(&options))?;
+        let transport_options = transport::Options::from(&options);
+        let channel = transport::create_channel(address.clone(), &transport_options)?;
         let client = ValidatorNodeClient::new(channel)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
             .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
@@ -59,11 +104,129 @@ impl GrpcClient {
         Ok(Self {
             address,
             client,
-            notification_retry_delay: options.notification_retry_delay,
-            notification_retries: options.notification_retries,
+            notification_retry: options.notification_retry,
+            timeout: options.timeout.recv_timeout(),
+            protocol_version: PROTOCOL_VERSION,
+            content_encoding: ContentEncoding::Bincode,
+            direct_shards: options.direct_to_shards.then(|| {
+                Arc::new(DirectShards {
+                    transport_options,
+                    addresses: Mutex::default(),
+                    clients: Mutex::default(),
+                    refused: AtomicBool::new(false),
+                })
+            }),
         })
     }
 
+    /// Returns a request with the deadline and the protocol version to send.
+    fn request<T>(&self, inner: T) -> Request<T> {
+        let mut request = Request::new(inner);
+        request.set_timeout(self.timeout);
+        set_protocol_version(&mut request, self.protocol_version);
+        request
+    }
+
+    /// Returns the client of the shard of `chain_id`, if requests are sent directly to the
+    /// shards and the proxy tells us its address.
+    async fn shard_client(
+        &mut self,
+        chain_id: ChainId,
+    ) -> Option<ValidatorWorkerClient<transport::Channel>> {
+        let shards = self.direct_shards.clone()?;
+        if shards.refused.load(Ordering::Relaxed) {
+            return None;
+        }
+        let cached = shards.addresses.lock().unwrap().get(&chain_id).cloned();
+        let address = match cached {
+            Some(address) => address,
+            None => {
+                let request = self.request(api::ChainId::from(chain_id));
+                match self.client.get_shard_address(request).await {
+                    Ok(response) => {
+                        let address = response.into_inner().address;
+                        let mut addresses = shards.addresses.lock().unwrap();
+                        if addresses.len() >= MAX_CACHED_CHAINS {
+                            addresses.clear();
+                        }
+                        addresses.insert(chain_id, address.clone());
+                        address
+                    }
+                    Err(status)
+                        if matches!(
+                            status.code(),
+                            Code::PermissionDenied | Code::Unimplemented
+                        ) =>
+                    {
+                        info!(
+                            address = %self.address,
+                            %status,
+                            "The validator does not give shard addresses; using its proxy",
+                        );
+                        shards.refused.store(true, Ordering::Relaxed);
+                        return None;
+                    }
+                    Err(status) => {
+                        debug!(%status, "Could not get the address of the shard of {chain_id}");
+                        return None;
+                    }
+                }
+            }
+        };
+        let mut clients = shards.clients.lock().unwrap();
+        if let Some(client) = clients.get(&address) {
+            return Some(client.clone());
+        }
+        let channel = match transport::create_channel(address.clone(), &shards.transport_options) {
+            Ok(channel) => channel,
+            Err(error) => {
+                debug!(%error, address, "Could not connect to a shard");
+                return None;
+            }
+        };
+        let client = ValidatorWorkerClient::new(channel)
+            .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
+            .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
+        clients.insert(address, client.clone());
+        Some(client)
+    }
+
+    /// Forgets the shard address of `chain_id`, e.g. because the shard didn't answer.
+    fn forget_shard(&self, chain_id: ChainId) {
+        if let Some(shards) = &self.direct_shards {
+            shards.addresses.lock().unwrap().remove(&chain_id);
+        }
+    }
+
+    /// Updates the version of the wire format to use in the next requests, given a response
+    /// of the validator.
+    #[allow(clippy::result_large_err)]
+    fn update_protocol_version<T>(
+        &mut self,
+        response: &tonic::Response<T>,
+    ) -> Result<(), NodeError> {
+        self.protocol_version = Self::negotiated_protocol_version(response)?;
+        self.content_encoding = ContentEncoding::for_version(self.protocol_version);
+        Ok(())
+    }
+
+    /// Returns the version of the wire format to use in the next requests, given a response
+    /// of the validator.
+    #[allow(clippy::result_large_err)]
+    fn negotiated_protocol_version<T>(response: &tonic::Response<T>) -> Result<u16, NodeError> {
+        let value = response
+            .metadata()
+            .get(PROTOCOL_VERSION_HEADER)
+            .map(|value| value.as_bytes());
+        let version = parse_protocol_version(value).map_err(|status| NodeError::GrpcError {
+            error: format!(
+                "validator uses an unsupported protocol: {}",
+                status.message()
+            ),
+        })?;
+        Ok(negotiate_protocol_version(version))
+    }
+
     /// Returns whether this gRPC status means the server stream should be reconnected to, or not.
     /// Logs a warning on unexpected status codes.
     fn is_retryable(status: &Status) -> bool {
@@ -95,24 +258,30 @@ impl GrpcClient {
     }
 }
 
+/// Sends a request about the chain `$chain_id`, directly to its shard if possible, falling
+/// back to the proxy if the shard fails.
 macro_rules! client_delegate {
-    ($self:ident, $handler:ident, $req:ident) => {{
+    ($self:ident, $handler:ident, $req:ident: $proto:ty, $chain_id:expr) => {{
         debug!(request = ?$req, "sending gRPC request");
-        let request_inner = $req.try_into().map_err(|_| NodeError::GrpcError {
+        let chain_id = $chain_id;
+        let request_inner: $proto = $req.try_into().map_err(|_| NodeError::GrpcError {
             error: "could not convert request to proto".to_string(),
         })?;
-        let request = Request::new(request_inner);
-        match $self
-            .client
-            .$handler(request)
-            .await
-            .map_err(|s| NodeError::GrpcError {
-                error: format!(
-                    "remote request [{}] failed with status: {:?}",
-                    stringify!($handler),
-                    s
-                ),
-            })?
+        let result = match $self.shard_client(chain_id).await {
+            Some(mut shard) => match shard.$handler($self.request(request_inner.clone())).await {
+                Ok(response) => Ok(response),
+                Err(status) => {
+                    debug!(%status, "request to the shard failed; retrying through the proxy");
+                    $self.forget_shard(chain_id);
+                    $self.client.$handler($self.request(request_inner)).await
+                }
+            },
+            None => $self.client.$handler($self.request(request_inner)).await,
+        };
+        let response = result.map_err(|status| request_failed(stringify!($handler), status))?;
+        $self.update_protocol_version(&response)?;
+        let request_id = request_id(response.metadata()).map(str::to_owned);
+        match response
             .into_inner()
             .inner
             .ok_or(NodeError::GrpcError {
@@ -124,14 +293,35 @@ macro_rules! client_delegate {
                 })?)
             }
             Inner::Error(error) => {
-                Err(bincode::deserialize(&error).map_err(|err| NodeError::GrpcError {
-                    error: format!("failed to marshal error message: {}", err),
-                })?)
+                let error: NodeError =
+                    limits::deserialize(&error).map_err(|err| NodeError::GrpcError {
+                        error: format!("failed to marshal error message: {}", err),
+                    })?;
+                debug!(?request_id, %error, "request [{}] rejected", stringify!($handler));
+                Err(error)
             }
         }
     }};
 }
 
+/// Returns the error of a request that could not be converted to protobuf.
+fn encoding_failed(error: impl std::fmt::Display) -> NodeError {
+    NodeError::GrpcError {
+        error: format!("could not convert request to proto: {error}"),
+    }
+}
+
+/// Returns the error of a request that failed with `status`, including the ID of the request
+/// so that it can be found in the validator's logs.
+fn request_failed(handler: &str, status: Status) -> NodeError {
+    let request_id = request_id(status.metadata())
+        .map(|request_id| format!(" (request ID: {request_id})"))
+        .unwrap_or_default();
+    NodeError::GrpcError {
+        error: format!("remote request [{handler}] failed with status: {status:?}{request_id}"),
+    }
+}
+
 impl ValidatorNode for GrpcClient {
     type NotificationStream = NotificationStream;
 
@@ -140,7 +330,10 @@ impl ValidatorNode for GrpcClient {
         &mut self,
         proposal: data_types::BlockProposal,
     ) -> Result<linera_core::data_types::ChainInfoResponse, NodeError> {
-        client_delegate!(self, handle_block_proposal, proposal)
+        let chain_id = proposal.content.block.chain_id;
+        let proposal =
+            api::BlockProposal::encode(proposal, self.content_encoding).map_err(encoding_failed)?;
+        client_delegate!(self, handle_block_proposal, proposal: api::BlockProposal, chain_id)
     }
 
     #[instrument(target = "grpc_client", skip_all, fields(address = self.address))]
@@ -150,11 +343,14 @@ impl ValidatorNode for GrpcClient {
         delivery: CrossChainMessageDelivery,
     ) -> Result<linera_core::data_types::ChainInfoResponse, NodeError> {
         let wait_for_outgoing_messages = delivery.wait_for_outgoing_messages();
+        let chain_id = certificate.value.chain_id;
         let request = HandleLiteCertRequest {
             certificate,
             wait_for_outgoing_messages,
         };
-        client_delegate!(self, handle_lite_certificate, request)
+        let request = api::LiteCertificate::encode(request, self.content_encoding)
+            .map_err(encoding_failed)?;
+        client_delegate!(self, handle_lite_certificate, request: api::LiteCertificate, chain_id)
     }
 
     #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
@@ -165,12 +361,15 @@ impl ValidatorNode for GrpcClient {
         delivery: CrossChainMessageDelivery,
     ) -> Result<linera_core::data_types::ChainInfoResponse, NodeError> {
         let wait_for_outgoing_messages = delivery.wait_for_outgoing_messages();
+        let chain_id = certificate.value().chain_id();
         let request = HandleCertificateRequest {
             certificate,
             hashed_certificate_values,
             wait_for_outgoing_messages,
         };
-        client_delegate!(self, handle_certificate, request)
+        let request =
+            api::Certificate::encode(request, self.content_encoding).map_err(encoding_failed)?;
+        client_delegate!(self, handle_certificate, request: api::Certificate, chain_id)
     }
 
     #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
@@ -178,7 +377,75 @@ impl ValidatorNode for GrpcClient {
         &mut self,
         query: linera_core::data_types::ChainInfoQuery,
     ) -> Result<linera_core::data_types::ChainInfoResponse, NodeError> {
-        client_delegate!(self, handle_chain_info_query, query)
+        let chain_id = query.chain_id;
+        client_delegate!(self, handle_chain_info_query, query: api::ChainInfoQuery, chain_id)
+    }
+
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn simulate_block_proposal(
+        &mut self,
+        block: data_types::Block,
+    ) -> Result<data_types::ExecutedBlock, NodeError> {
+        debug!(request = ?block, "sending gRPC request");
+        let request_inner: api::BlockSimulationRequest =
+            block.try_into().map_err(|_| NodeError::GrpcError {
+                error: "could not convert request to proto".to_string(),
+            })?;
+        let mut request = Request::new(request_inner);
+        request.set_timeout(self.timeout);
+        set_protocol_version(&mut request, self.protocol_version);
+        let response = self
+            .client
+            .simulate_block_proposal(request)
+            .await
+            .map_err(|status| request_failed("simulate_block_proposal", status))?;
+        self.update_protocol_version(&response)?;
+        let result = response.into_inner().inner.ok_or(NodeError::GrpcError {
+            error: "missing body from response".to_string(),
+        })?;
+        match result {
+            block_simulation_result::Inner::ExecutedBlock(executed_block) => {
+                limits::deserialize(&executed_block).map_err(|err| NodeError::GrpcError {
+                    error: format!("failed to marshal response: {}", err),
+                })
+            }
+            block_simulation_result::Inner::Error(error) => Err(limits::deserialize(&error)
+                .map_err(|err| NodeError::GrpcError {
+                    error: format!("failed to marshal error message: {}", err),
+                })?),
+        }
+    }
+
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn download_blob(
+        &mut self,
+        chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Result<Blob, NodeError> {
+        debug!(?chain_id, ?blob_id, "sending gRPC request");
+        let mut request = Request::new(api::BlobRequest::from(DownloadBlobRequest {
+            chain_id,
+            blob_id,
+        }));
+        request.set_timeout(self.timeout);
+        set_protocol_version(&mut request, self.protocol_version);
+        let response = self
+            .client
+            .download_blob(request)
+            .await
+            .map_err(|status| request_failed("download_blob", status))?;
+        self.update_protocol_version(&response)?;
+        let result = response.into_inner().inner.ok_or(NodeError::GrpcError {
+            error: "missing body from response".to_string(),
+        })?;
+        match result {
+            blob_result::Inner::Blob(bytes) => Ok(Blob::new(bytes)),
+            blob_result::Inner::Error(error) => Err(limits::deserialize(&error).map_err(
+                |err| NodeError::GrpcError {
+                    error: format!("failed to marshal error message: {}", err),
+                },
+            )?),
+        }
     }
 
     #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
@@ -186,8 +453,7 @@ impl ValidatorNode for GrpcClient {
         &mut self,
         chains: Vec<ChainId>,
     ) -> Result<Self::NotificationStream, NodeError> {
-        let notification_retry_delay = self.notification_retry_delay;
-        let notification_retries = self.notification_retries;
+        let notification_retry = self.notification_retry;
         let mut retry_count = 0;
         let subscription_request = SubscriptionRequest {
             chain_ids: chains.into_iter().map(|chain| chain.into()).collect(),
@@ -239,10 +505,10 @@ impl ValidatorNode for GrpcClient {
                     retry_count = 0;
                     return future::Either::Left(future::ready(true));
                 };
-                if !Self::is_retryable(status) || retry_count >= notification_retries {
+                if !Self::is_retryable(status) || retry_count >= notification_retry.max_retries {
                     return future::Either::Left(future::ready(false));
                 }
-                let delay = notification_retry_delay.saturating_mul(retry_count);
+                let delay = notification_retry.delay(retry_count);
                 retry_count += 1;
                 future::Either::Right(async move {
                     tokio::time::sleep(delay).await;
@@ -258,6 +524,14 @@ impl ValidatorNode for GrpcClient {
     async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError> {
         Ok(self.client.get_version_info(()).await?.into_inner().into())
     }
+
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn get_genesis_config_hash(&mut self) -> Result<CryptoHash, NodeError> {
+        let hash = self.client.get_genesis_config_hash(()).await?.into_inner();
+        hash.try_into().map_err(|err| NodeError::GrpcError {
+            error: format!("failed to marshal response: {}", err),
+        })
+    }
 }
 
 #[cfg(not(web))]
@@ -296,8 +570,8 @@ impl mass_client::MassClient for GrpcClient {
                             ))))
                         }
                         Inner::Error(error) => {
-                            let error = bincode::deserialize::<NodeError>(&error)
-                                .map_err(GrpcProtoConversionError::BincodeError)?;
+                            let error = limits::deserialize::<NodeError>(&error)
+                                .map_err(GrpcProtoConversionError::from)?;
                             tracing::error!(?error, "received error response");
                             Ok(None)
                         }
@@ -317,3 +591,159 @@ impl mass_client::MassClient for GrpcClient {
         Ok(responses)
     }
 }
+
+/// Sends a request received over the simple transport to the gRPC endpoint of a shard, and
+/// returns the message to answer with, if any. This lets a proxy serve the clients of the
+/// simple transport in front of shards using gRPC.
+///
+/// The errors returned by the shard are answered with [`RpcMessage::Error`], like the simple
+/// shards do.
+#[cfg(not(web))]
+pub async fn forward_to_worker(
+    client: &mut ValidatorWorkerClient<transport::Channel>,
+    message: RpcMessage,
+    timeout: Duration,
+) -> Result<Option<RpcMessage>, NodeError> {
+    let response = match message {
+        RpcMessage::BlockProposal(proposal) => {
+            let request = worker_request(api::BlockProposal::try_from(*proposal), timeout)?;
+            let response = client
+                .handle_block_proposal(request)
+                .await
+                .map_err(|status| request_failed("handle_block_proposal", status))?;
+            chain_info_message(response.into_inner())?
+        }
+        RpcMessage::LiteCertificate(request) => {
+            let request = worker_request(api::LiteCertificate::try_from(*request), timeout)?;
+            let response = client
+                .handle_lite_certificate(request)
+                .await
+                .map_err(|status| request_failed("handle_lite_certificate", status))?;
+            chain_info_message(response.into_inner())?
+        }
+        RpcMessage::Certificate(request) => {
+            let request = worker_request(api::Certificate::try_from(*request), timeout)?;
+            let response = client
+                .handle_certificate(request)
+                .await
+                .map_err(|status| request_failed("handle_certificate", status))?;
+            chain_info_message(response.into_inner())?
+        }
+        RpcMessage::ChainInfoQuery(query) => {
+            let request = worker_request(api::ChainInfoQuery::try_from(*query), timeout)?;
+            let response = client
+                .handle_chain_info_query(request)
+                .await
+                .map_err(|status| request_failed("handle_chain_info_query", status))?;
+            chain_info_message(response.into_inner())?
+        }
+        RpcMessage::BlockSimulation(block) => {
+            let request = worker_request(api::BlockSimulationRequest::try_from(*block), timeout)?;
+            let response = client
+                .simulate_block_proposal(request)
+                .await
+                .map_err(|status| request_failed("simulate_block_proposal", status))?;
+            match response.into_inner().inner.ok_or_else(missing_body)? {
+                block_simulation_result::Inner::ExecutedBlock(executed_block) => {
+                    let executed_block = limits::deserialize(&executed_block).map_err(|err| {
+                        NodeError::GrpcError {
+                            error: format!("failed to marshal response: {}", err),
+                        }
+                    })?;
+                    RpcMessage::BlockSimulationResponse(Box::new(executed_block))
+                }
+                block_simulation_result::Inner::Error(error) => error_message(&error)?,
+            }
+        }
+        RpcMessage::DownloadBlob(request) => {
+            let request = worker_request(Ok(api::BlobRequest::from(*request)), timeout)?;
+            let response = client
+                .download_blob(request)
+                .await
+                .map_err(|status| request_failed("download_blob", status))?;
+            match response.into_inner().inner.ok_or_else(missing_body)? {
+                blob_result::Inner::Blob(bytes) => {
+                    RpcMessage::DownloadBlobResponse(Box::new(Blob::new(bytes)))
+                }
+                blob_result::Inner::Error(error) => error_message(&error)?,
+            }
+        }
+        RpcMessage::CrossChainRequest(request) => {
+            let request = worker_request(api::CrossChainRequest::try_from(*request), timeout)?;
+            client
+                .handle_cross_chain_request(request)
+                .await
+                .map_err(|status| request_failed("handle_cross_chain_request", status))?;
+            return Ok(None);
+        }
+        RpcMessage::VersionInfoQuery => {
+            let request = worker_request(Ok(()), timeout)?;
+            let response = client
+                .get_version_info(request)
+                .await
+                .map_err(|status| request_failed("get_version_info", status))?;
+            RpcMessage::VersionInfoResponse(Box::new(response.into_inner().into()))
+        }
+        message @ (RpcMessage::Vote(_)
+        | RpcMessage::ChainInfoResponse(_)
+        | RpcMessage::Error(_)
+        | RpcMessage::VersionInfoResponse(_)
+        | RpcMessage::BlockSimulationResponse(_)
+        | RpcMessage::GenesisConfigHashQuery
+        | RpcMessage::GenesisConfigHashResponse(_)
+        | RpcMessage::DownloadBlobResponse(_)) => {
+            return Err(NodeError::GrpcError {
+                error: format!("cannot forward {message:?} to a shard"),
+            });
+        }
+    };
+    Ok(Some(response))
+}
+
+/// Returns the request to send to a shard, with the deadline and our protocol version, given
+/// the result of its conversion to protobuf.
+#[cfg(not(web))]
+#[allow(clippy::result_large_err)]
+fn worker_request<T>(
+    inner: Result<T, GrpcProtoConversionError>,
+    timeout: Duration,
+) -> Result<Request<T>, NodeError> {
+    let mut request = Request::new(inner.map_err(encoding_failed)?);
+    request.set_timeout(timeout);
+    set_protocol_version(&mut request, PROTOCOL_VERSION);
+    Ok(request)
+}
+
+/// Returns the message to answer with, given the result of a request to a shard returning
+/// chain information.
+#[cfg(not(web))]
+#[allow(clippy::result_large_err)]
+fn chain_info_message(result: api::ChainInfoResult) -> Result<RpcMessage, NodeError> {
+    match result.inner.ok_or_else(missing_body)? {
+        Inner::ChainInfoResponse(response) => {
+            let response = response.try_into().map_err(|err| NodeError::GrpcError {
+                error: format!("failed to marshal response: {}", err),
+            })?;
+            Ok(RpcMessage::ChainInfoResponse(Box::new(response)))
+        }
+        Inner::Error(error) => error_message(&error),
+    }
+}
+
+/// Returns the message to answer with, given the bincode-encoded error returned by a shard.
+#[cfg(not(web))]
+#[allow(clippy::result_large_err)]
+fn error_message(error: &[u8]) -> Result<RpcMessage, NodeError> {
+    let error: NodeError = limits::deserialize(error).map_err(|err| NodeError::GrpcError {
+        error: format!("failed to marshal error message: {}", err),
+    })?;
+    Ok(RpcMessage::Error(Box::new(error)))
+}
+
+/// Returns the error of a response without a body.
+#[cfg(not(web))]
+fn missing_body() -> NodeError {
+    NodeError::GrpcError {
+        error: "missing body from response".to_string(),
+    }
+}
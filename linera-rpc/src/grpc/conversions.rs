@@ -3,29 +3,44 @@
 
 use linera_base::{
     crypto::{CryptoError, CryptoHash, PublicKey, Signature},
-    data_types::BlockHeight,
+    data_types::{Amount, BlockHeight, OracleRecord, OracleResponse, Round, Timestamp},
     ensure,
-    identifiers::{ChainId, Owner},
+    identifiers::{
+        Account, ApplicationId, Blob, BlobId, BytecodeId, ChainId, ChannelName, Destination,
+        GenericApplicationId, MessageId, Owner,
+    },
 };
 use linera_chain::data_types::{
-    BlockAndRound, BlockProposal, Certificate, HashedCertificateValue, LiteCertificate, LiteValue,
+    ApplicationEvent, Block, BlockAndRound, BlockExecutionOutcome, BlockProposal, Certificate,
+    CertificateValue, ChannelFullName, Event, ExecutedBlock, HashedCertificateValue,
+    IncomingMessage, LiteCertificate, LiteValue, Medium, MessageAction, Origin, OutgoingMessage,
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     node::NodeError,
     worker::Notification,
 };
-use linera_execution::committee::ValidatorName;
+use linera_execution::{
+    committee::{Epoch, ValidatorName},
+    Message, MessageKind, Operation,
+};
+use serde::de::DeserializeOwned;
 use thiserror::Error;
 use tonic::{Code, Status};
 
 use super::api;
-use crate::{HandleCertificateRequest, HandleLiteCertRequest};
+use crate::{
+    limits::{self, DeserializationError, LimitExceeded},
+    version::NATIVE_CONTENT_VERSION,
+    DownloadBlobRequest, HandleCertificateRequest, HandleLiteCertRequest,
+};
 
 #[derive(Error, Debug)]
 pub enum GrpcProtoConversionError {
     #[error(transparent)]
     BincodeError(#[from] bincode::Error),
+    #[error("Deserialization limits exceeded: {0}")]
+    LimitExceeded(#[from] LimitExceeded),
     #[error("Conversion failed due to missing field")]
     MissingField,
     #[error("Signature error: {0}")]
@@ -34,6 +49,19 @@ pub enum GrpcProtoConversionError {
     CryptoError(#[from] CryptoError),
     #[error("Inconsistent outer/inner chain ids")]
     InconsistentChainId,
+    #[error("Invalid enum value: {0}")]
+    InvalidEnumValue(i32),
+}
+
+impl From<DeserializationError> for GrpcProtoConversionError {
+    fn from(error: DeserializationError) -> Self {
+        match error {
+            DeserializationError::Bincode(error) => GrpcProtoConversionError::BincodeError(error),
+            DeserializationError::LimitExceeded(exceeded) => {
+                GrpcProtoConversionError::LimitExceeded(exceeded)
+            }
+        }
+    }
 }
 
 /// Extracts an optional field from a Proto type and tries to map it.
@@ -91,6 +119,12 @@ impl From<linera_version::VersionInfo> for api::VersionInfo {
             rpc_hash: version_info.rpc_hash.into(),
             graphql_hash: version_info.graphql_hash.into(),
             wit_hash: version_info.wit_hash.into(),
+            build_timestamp: version_info.build_timestamp,
+            features: version_info
+                .features
+                .iter()
+                .map(|feature| feature.to_string())
+                .collect(),
         }
     }
 }
@@ -113,6 +147,8 @@ impl From<api::VersionInfo> for linera_version::VersionInfo {
             rpc_hash: version_info.rpc_hash.into(),
             graphql_hash: version_info.graphql_hash.into(),
             wit_hash: version_info.wit_hash.into(),
+            build_timestamp: version_info.build_timestamp,
+            features: version_info.features.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -134,7 +170,7 @@ impl TryFrom<api::Notification> for Notification {
     fn try_from(notification: api::Notification) -> Result<Self, Self::Error> {
         Ok(Self {
             chain_id: try_proto_convert(notification.chain_id)?,
-            reason: bincode::deserialize(&notification.reason)?,
+            reason: limits::deserialize(&notification.reason)?,
         })
     }
 }
@@ -161,46 +197,175 @@ impl TryFrom<NodeError> for api::ChainInfoResult {
     }
 }
 
-impl TryFrom<BlockProposal> for api::BlockProposal {
+impl TryFrom<Block> for api::BlockSimulationRequest {
     type Error = GrpcProtoConversionError;
 
-    fn try_from(block_proposal: BlockProposal) -> Result<Self, Self::Error> {
+    fn try_from(block: Block) -> Result<Self, Self::Error> {
         Ok(Self {
-            chain_id: Some(block_proposal.content.block.chain_id.into()),
-            content: bincode::serialize(&block_proposal.content)?,
-            owner: Some(block_proposal.owner.into()),
-            signature: Some(block_proposal.signature.into()),
-            hashed_certificate_values: bincode::serialize(
-                &block_proposal.hashed_certificate_values,
-            )?,
-            validated: block_proposal
-                .validated
-                .map(|cert| bincode::serialize(&cert))
-                .transpose()?,
+            chain_id: Some(block.chain_id.into()),
+            block: bincode::serialize(&block)?,
+        })
+    }
+}
+
+impl TryFrom<api::BlockSimulationRequest> for Block {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(request: api::BlockSimulationRequest) -> Result<Self, Self::Error> {
+        let block: Block = limits::deserialize(&request.block)?;
+        ensure!(
+            Some(block.chain_id.into()) == request.chain_id,
+            GrpcProtoConversionError::InconsistentChainId
+        );
+        Ok(block)
+    }
+}
+
+impl TryFrom<ExecutedBlock> for api::BlockSimulationResult {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(executed_block: ExecutedBlock) -> Result<Self, Self::Error> {
+        let executed_block = bincode::serialize(&executed_block)?;
+        Ok(api::BlockSimulationResult {
+            inner: Some(api::block_simulation_result::Inner::ExecutedBlock(
+                executed_block,
+            )),
+        })
+    }
+}
+
+impl TryFrom<NodeError> for api::BlockSimulationResult {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(node_error: NodeError) -> Result<Self, Self::Error> {
+        let error = bincode::serialize(&node_error)?;
+        Ok(api::BlockSimulationResult {
+            inner: Some(api::block_simulation_result::Inner::Error(error)),
+        })
+    }
+}
+
+impl From<DownloadBlobRequest> for api::BlobRequest {
+    fn from(request: DownloadBlobRequest) -> Self {
+        Self {
+            chain_id: Some(request.chain_id.into()),
+            blob_id: Some(request.blob_id.0.into()),
+        }
+    }
+}
+
+impl TryFrom<api::BlobRequest> for DownloadBlobRequest {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(request: api::BlobRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: try_proto_convert(request.chain_id)?,
+            blob_id: BlobId(try_proto_convert(request.blob_id)?),
+        })
+    }
+}
+
+impl From<Blob> for api::BlobResult {
+    fn from(blob: Blob) -> Self {
+        api::BlobResult {
+            inner: Some(api::blob_result::Inner::Blob(blob.into_bytes())),
+        }
+    }
+}
+
+impl TryFrom<NodeError> for api::BlobResult {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(node_error: NodeError) -> Result<Self, Self::Error> {
+        let error = bincode::serialize(&node_error)?;
+        Ok(api::BlobResult {
+            inner: Some(api::blob_result::Inner::Error(error)),
+        })
+    }
+}
+
+impl api::BlockProposal {
+    /// Converts the `block_proposal`, with its content in the given `encoding`.
+    pub fn encode(
+        block_proposal: BlockProposal,
+        encoding: ContentEncoding,
+    ) -> Result<Self, GrpcProtoConversionError> {
+        let chain_id = Some(block_proposal.content.block.chain_id.into());
+        let owner = Some(block_proposal.owner.into());
+        let signature = Some(block_proposal.signature.into());
+        let blobs = block_proposal
+            .blobs
+            .into_iter()
+            .map(Blob::into_bytes)
+            .collect();
+        Ok(match encoding {
+            ContentEncoding::Bincode => Self {
+                chain_id,
+                content: bincode::serialize(&block_proposal.content)?,
+                owner,
+                signature,
+                hashed_certificate_values: bincode::serialize(
+                    &block_proposal.hashed_certificate_values,
+                )?,
+                validated: block_proposal
+                    .validated
+                    .map(|cert| bincode::serialize(&cert))
+                    .transpose()?,
+                blobs,
+                ..Self::default()
+            },
+            ContentEncoding::Native => Self {
+                chain_id,
+                owner,
+                signature,
+                native_content: Some(block_proposal.content.try_into()?),
+                native_hashed_certificate_values: certificate_values_to_proto(
+                    block_proposal.hashed_certificate_values,
+                )?,
+                native_validated: block_proposal
+                    .validated
+                    .map(TryInto::try_into)
+                    .transpose()?,
+                blobs,
+                ..Self::default()
+            },
         })
     }
 }
 
+impl TryFrom<BlockProposal> for api::BlockProposal {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(block_proposal: BlockProposal) -> Result<Self, Self::Error> {
+        Self::encode(block_proposal, ContentEncoding::Bincode)
+    }
+}
+
 impl TryFrom<api::BlockProposal> for BlockProposal {
     type Error = GrpcProtoConversionError;
 
     fn try_from(block_proposal: api::BlockProposal) -> Result<Self, Self::Error> {
-        let content: BlockAndRound = bincode::deserialize(&block_proposal.content)?;
+        let content: BlockAndRound = bincode_or_native(&block_proposal.content, || {
+            try_proto_convert(block_proposal.native_content)
+        })?;
         ensure!(
             Some(content.block.chain_id.into()) == block_proposal.chain_id,
             GrpcProtoConversionError::InconsistentChainId
         );
+        let validated = match (block_proposal.validated, block_proposal.native_validated) {
+            (Some(bytes), _) => Some(limits::deserialize(&bytes)?),
+            (None, native) => native.map(TryInto::try_into).transpose()?,
+        };
         Ok(Self {
             content,
             owner: try_proto_convert(block_proposal.owner)?,
             signature: try_proto_convert(block_proposal.signature)?,
-            hashed_certificate_values: bincode::deserialize(
+            hashed_certificate_values: bincode_or_native(
                 &block_proposal.hashed_certificate_values,
+                || certificate_values_from_proto(block_proposal.native_hashed_certificate_values),
             )?,
-            validated: block_proposal
-                .validated
-                .map(|bytes| bincode::deserialize(&bytes))
-                .transpose()?,
+            blobs: block_proposal.blobs.into_iter().map(Blob::new).collect(),
+            validated,
         })
     }
 }
@@ -222,7 +387,7 @@ impl TryFrom<api::CrossChainRequest> for CrossChainRequest {
             }) => CrossChainRequest::UpdateRecipient {
                 sender: try_proto_convert(sender)?,
                 recipient: try_proto_convert(recipient)?,
-                bundle_vecs: bincode::deserialize(&bundle_vecs)?,
+                bundle_vecs: limits::deserialize(&bundle_vecs)?,
             },
             Inner::ConfirmUpdatedRecipient(api::ConfirmUpdatedRecipient {
                 sender,
@@ -231,7 +396,7 @@ impl TryFrom<api::CrossChainRequest> for CrossChainRequest {
             }) => CrossChainRequest::ConfirmUpdatedRecipient {
                 sender: try_proto_convert(sender)?,
                 recipient: try_proto_convert(recipient)?,
-                latest_heights: bincode::deserialize(&latest_heights)?,
+                latest_heights: limits::deserialize(&latest_heights)?,
             },
         };
         Ok(ccr)
@@ -276,8 +441,12 @@ impl<'a> TryFrom<api::LiteCertificate> for HandleLiteCertRequest<'a> {
             value_hash: CryptoHash::try_from(certificate.hash.as_slice())?,
             chain_id: try_proto_convert(certificate.chain_id)?,
         };
-        let signatures = bincode::deserialize(&certificate.signatures)?;
-        let round = bincode::deserialize(&certificate.round)?;
+        let signatures = bincode_or_native(&certificate.signatures, || {
+            signatures_from_proto(certificate.native_signatures)
+        })?;
+        let round = bincode_or_native(&certificate.round, || {
+            try_proto_convert(certificate.native_round)
+        })?;
         Ok(Self {
             certificate: LiteCertificate::new(value, round, signatures),
             wait_for_outgoing_messages: certificate.wait_for_outgoing_messages,
@@ -285,17 +454,43 @@ impl<'a> TryFrom<api::LiteCertificate> for HandleLiteCertRequest<'a> {
     }
 }
 
+impl api::LiteCertificate {
+    /// Converts the `request`, with the round and signatures in the given `encoding`.
+    pub fn encode(
+        request: HandleLiteCertRequest,
+        encoding: ContentEncoding,
+    ) -> Result<Self, GrpcProtoConversionError> {
+        let certificate = request.certificate;
+        let mut message = Self {
+            hash: certificate.value.value_hash.as_bytes().to_vec(),
+            chain_id: Some(certificate.value.chain_id.into()),
+            wait_for_outgoing_messages: request.wait_for_outgoing_messages,
+            ..Self::default()
+        };
+        match encoding {
+            ContentEncoding::Bincode => {
+                message.round = bincode::serialize(&certificate.round)?;
+                message.signatures = bincode::serialize(&certificate.signatures)?;
+            }
+            ContentEncoding::Native => {
+                message.native_round = Some(certificate.round.into());
+                message.native_signatures = certificate
+                    .signatures
+                    .iter()
+                    .copied()
+                    .map(Into::into)
+                    .collect();
+            }
+        }
+        Ok(message)
+    }
+}
+
 impl<'a> TryFrom<HandleLiteCertRequest<'a>> for api::LiteCertificate {
     type Error = GrpcProtoConversionError;
 
     fn try_from(request: HandleLiteCertRequest) -> Result<Self, Self::Error> {
-        Ok(Self {
-            hash: request.certificate.value.value_hash.as_bytes().to_vec(),
-            round: bincode::serialize(&request.certificate.round)?,
-            chain_id: Some(request.certificate.value.chain_id.into()),
-            signatures: bincode::serialize(&request.certificate.signatures)?,
-            wait_for_outgoing_messages: request.wait_for_outgoing_messages,
-        })
+        Self::encode(request, ContentEncoding::Bincode)
     }
 }
 
@@ -303,14 +498,23 @@ impl TryFrom<api::Certificate> for HandleCertificateRequest {
     type Error = GrpcProtoConversionError;
 
     fn try_from(cert_request: api::Certificate) -> Result<Self, Self::Error> {
-        let value: HashedCertificateValue = bincode::deserialize(&cert_request.value)?;
+        let value: HashedCertificateValue = bincode_or_native(&cert_request.value, || {
+            let value: CertificateValue = try_proto_convert(cert_request.native_value)?;
+            Ok(value.into())
+        })?;
         ensure!(
             Some(value.inner().chain_id().into()) == cert_request.chain_id,
             GrpcProtoConversionError::InconsistentChainId
         );
-        let signatures = bincode::deserialize(&cert_request.signatures)?;
-        let values = bincode::deserialize(&cert_request.hashed_certificate_values)?;
-        let round = bincode::deserialize(&cert_request.round)?;
+        let signatures = bincode_or_native(&cert_request.signatures, || {
+            signatures_from_proto(cert_request.native_signatures)
+        })?;
+        let values = bincode_or_native(&cert_request.hashed_certificate_values, || {
+            certificate_values_from_proto(cert_request.native_hashed_certificate_values)
+        })?;
+        let round = bincode_or_native(&cert_request.round, || {
+            try_proto_convert(cert_request.native_round)
+        })?;
         Ok(HandleCertificateRequest {
             certificate: Certificate::new(value, round, signatures),
             wait_for_outgoing_messages: cert_request.wait_for_outgoing_messages,
@@ -319,18 +523,51 @@ impl TryFrom<api::Certificate> for HandleCertificateRequest {
     }
 }
 
+impl api::Certificate {
+    /// Converts the `request`, with the certificate and the values in the given `encoding`.
+    pub fn encode(
+        request: HandleCertificateRequest,
+        encoding: ContentEncoding,
+    ) -> Result<Self, GrpcProtoConversionError> {
+        let chain_id = Some(request.certificate.value().chain_id().into());
+        let wait_for_outgoing_messages = request.wait_for_outgoing_messages;
+        Ok(match encoding {
+            ContentEncoding::Bincode => Self {
+                chain_id,
+                value: bincode::serialize(&request.certificate.value)?,
+                round: bincode::serialize(&request.certificate.round)?,
+                signatures: bincode::serialize(request.certificate.signatures())?,
+                hashed_certificate_values: bincode::serialize(&request.hashed_certificate_values)?,
+                wait_for_outgoing_messages,
+                ..Self::default()
+            },
+            ContentEncoding::Native => {
+                let api::CertifiedValue {
+                    value,
+                    round,
+                    signatures,
+                } = request.certificate.try_into()?;
+                Self {
+                    chain_id,
+                    native_value: value,
+                    native_round: round,
+                    native_signatures: signatures,
+                    native_hashed_certificate_values: certificate_values_to_proto(
+                        request.hashed_certificate_values,
+                    )?,
+                    wait_for_outgoing_messages,
+                    ..Self::default()
+                }
+            }
+        })
+    }
+}
+
 impl TryFrom<HandleCertificateRequest> for api::Certificate {
     type Error = GrpcProtoConversionError;
 
     fn try_from(request: HandleCertificateRequest) -> Result<Self, Self::Error> {
-        Ok(Self {
-            chain_id: Some(request.certificate.value().chain_id().into()),
-            value: bincode::serialize(&request.certificate.value)?,
-            round: bincode::serialize(&request.certificate.round)?,
-            signatures: bincode::serialize(request.certificate.signatures())?,
-            hashed_certificate_values: bincode::serialize(&request.hashed_certificate_values)?,
-            wait_for_outgoing_messages: request.wait_for_outgoing_messages,
-        })
+        Self::encode(request, ContentEncoding::Bincode)
     }
 }
 
@@ -340,11 +577,11 @@ impl TryFrom<api::ChainInfoQuery> for ChainInfoQuery {
     fn try_from(chain_info_query: api::ChainInfoQuery) -> Result<Self, Self::Error> {
         let request_sent_certificates_in_range = chain_info_query
             .request_sent_certificates_in_range
-            .map(|range| bincode::deserialize(&range))
+            .map(|range| limits::deserialize(&range))
             .transpose()?;
         let request_hashed_certificate_value = chain_info_query
             .request_hashed_certificate_value
-            .map(|bytes| bincode::deserialize(&bytes))
+            .map(|bytes| limits::deserialize(&bytes))
             .transpose()?;
 
         Ok(Self {
@@ -360,9 +597,19 @@ impl TryFrom<api::ChainInfoQuery> for ChainInfoQuery {
                 .request_received_log_excluding_first_nth,
             test_next_block_height: chain_info_query.test_next_block_height.map(Into::into),
             request_manager_values: chain_info_query.request_manager_values,
+            request_manager_status: chain_info_query.request_manager_status,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
             request_hashed_certificate_value,
+            request_application_storage: chain_info_query.request_application_storage,
+            request_received_certificates_after: chain_info_query
+                .request_received_certificates_after,
+            request_collected_fees: chain_info_query.request_collected_fees,
+            request_events_from: chain_info_query.request_events_from,
+            request_owner_balance_proof: chain_info_query
+                .request_owner_balance_proof
+                .map(TryInto::try_into)
+                .transpose()?,
         })
     }
 }
@@ -390,9 +637,18 @@ impl TryFrom<ChainInfoQuery> for api::ChainInfoQuery {
             request_received_log_excluding_first_nth: chain_info_query
                 .request_received_log_excluding_first_nth,
             request_manager_values: chain_info_query.request_manager_values,
+            request_manager_status: chain_info_query.request_manager_status,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
             request_hashed_certificate_value,
+            request_application_storage: chain_info_query.request_application_storage,
+            request_received_certificates_after: chain_info_query
+                .request_received_certificates_after,
+            request_collected_fees: chain_info_query.request_collected_fees,
+            request_events_from: chain_info_query.request_events_from,
+            request_owner_balance_proof: chain_info_query
+                .request_owner_balance_proof
+                .map(Into::into),
         })
     }
 }
@@ -480,7 +736,7 @@ impl TryFrom<api::ChainInfoResponse> for ChainInfoResponse {
             .signature
             .map(TryInto::try_into)
             .transpose()?;
-        let info = bincode::deserialize(chain_info_response.chain_info.as_slice())?;
+        let info = limits::deserialize(chain_info_response.chain_info.as_slice())?;
         Ok(Self { info, signature })
     }
 }
@@ -515,20 +771,791 @@ impl TryFrom<api::Owner> for Owner {
     }
 }
 
+impl From<CryptoHash> for api::CryptoHash {
+    fn from(hash: CryptoHash) -> Self {
+        Self {
+            bytes: hash.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<api::CryptoHash> for CryptoHash {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(hash: api::CryptoHash) -> Result<Self, Self::Error> {
+        Ok(CryptoHash::try_from(hash.bytes.as_slice())?)
+    }
+}
+
+/// How the contents of proposals and certificates are encoded in protobuf messages.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ContentEncoding {
+    /// As bincode-encoded bytes, as decoded by all versions of the protocol.
+    #[default]
+    Bincode,
+    /// As protobuf messages, as decoded since [`NATIVE_CONTENT_VERSION`].
+    Native,
+}
+
+impl ContentEncoding {
+    /// Returns the encoding to use with a peer known to decode the given protocol `version`.
+    pub fn for_version(version: u16) -> Self {
+        if version >= NATIVE_CONTENT_VERSION {
+            ContentEncoding::Native
+        } else {
+            ContentEncoding::Bincode
+        }
+    }
+}
+
+/// Decodes a field given as bincode-encoded bytes or, if they are empty, with `native` from
+/// the protobuf messages of the field.
+fn bincode_or_native<T, F>(bytes: &[u8], native: F) -> Result<T, GrpcProtoConversionError>
+where
+    T: DeserializeOwned,
+    F: FnOnce() -> Result<T, GrpcProtoConversionError>,
+{
+    if bytes.is_empty() {
+        native()
+    } else {
+        Ok(limits::deserialize(bytes)?)
+    }
+}
+
+fn block_height_from_proto(
+    height: Option<api::BlockHeight>,
+) -> Result<BlockHeight, GrpcProtoConversionError> {
+    Ok(height.ok_or(GrpcProtoConversionError::MissingField)?.into())
+}
+
+fn amount_from_proto(amount: Option<api::Amount>) -> Result<Amount, GrpcProtoConversionError> {
+    let amount = amount.ok_or(GrpcProtoConversionError::MissingField)?;
+    Ok(Amount::from_attos(
+        (u128::from(amount.high) << 64) | u128::from(amount.low),
+    ))
+}
+
+fn signatures_from_proto(
+    signatures: Vec<api::ValidatorSignature>,
+) -> Result<Vec<(ValidatorName, Signature)>, GrpcProtoConversionError> {
+    signatures.into_iter().map(TryInto::try_into).collect()
+}
+
+fn certificate_values_from_proto(
+    values: Vec<api::CertificateValue>,
+) -> Result<Vec<HashedCertificateValue>, GrpcProtoConversionError> {
+    values
+        .into_iter()
+        .map(|value| Ok(CertificateValue::try_from(value)?.into()))
+        .collect()
+}
+
+fn certificate_values_to_proto(
+    values: Vec<HashedCertificateValue>,
+) -> Result<Vec<api::CertificateValue>, GrpcProtoConversionError> {
+    values
+        .into_iter()
+        .map(|value| CertificateValue::from(value).try_into())
+        .collect()
+}
+
+impl From<Amount> for api::Amount {
+    fn from(amount: Amount) -> Self {
+        let attos = u128::from(amount);
+        Self {
+            high: (attos >> 64) as u64,
+            low: attos as u64,
+        }
+    }
+}
+
+impl From<Round> for api::Round {
+    fn from(round: Round) -> Self {
+        use api::round::Inner;
+
+        let inner = match round {
+            Round::Fast => Inner::Fast(()),
+            Round::MultiLeader(number) => Inner::MultiLeader(number),
+            Round::SingleLeader(number) => Inner::SingleLeader(number),
+            Round::Validator(number) => Inner::Validator(number),
+        };
+        Self { inner: Some(inner) }
+    }
+}
+
+impl TryFrom<api::Round> for Round {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(round: api::Round) -> Result<Self, Self::Error> {
+        use api::round::Inner;
+
+        Ok(
+            match round.inner.ok_or(GrpcProtoConversionError::MissingField)? {
+                Inner::Fast(()) => Round::Fast,
+                Inner::MultiLeader(number) => Round::MultiLeader(number),
+                Inner::SingleLeader(number) => Round::SingleLeader(number),
+                Inner::Validator(number) => Round::Validator(number),
+            },
+        )
+    }
+}
+
+impl From<(ValidatorName, Signature)> for api::ValidatorSignature {
+    fn from((validator, signature): (ValidatorName, Signature)) -> Self {
+        Self {
+            validator: Some(validator.into()),
+            signature: Some(signature.into()),
+        }
+    }
+}
+
+impl TryFrom<api::ValidatorSignature> for (ValidatorName, Signature) {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(signature: api::ValidatorSignature) -> Result<Self, Self::Error> {
+        Ok((
+            try_proto_convert(signature.validator)?,
+            try_proto_convert(signature.signature)?,
+        ))
+    }
+}
+
+impl From<MessageId> for api::MessageId {
+    fn from(message_id: MessageId) -> Self {
+        Self {
+            chain_id: Some(message_id.chain_id.into()),
+            height: Some(message_id.height.into()),
+            index: message_id.index,
+        }
+    }
+}
+
+impl TryFrom<api::MessageId> for MessageId {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(message_id: api::MessageId) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: try_proto_convert(message_id.chain_id)?,
+            height: block_height_from_proto(message_id.height)?,
+            index: message_id.index,
+        })
+    }
+}
+
+impl From<ApplicationId> for api::ApplicationId {
+    fn from(application_id: ApplicationId) -> Self {
+        Self {
+            bytecode_id: Some(application_id.bytecode_id.message_id.into()),
+            creation: Some(application_id.creation.into()),
+        }
+    }
+}
+
+impl TryFrom<api::ApplicationId> for ApplicationId {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(application_id: api::ApplicationId) -> Result<Self, Self::Error> {
+        Ok(Self {
+            bytecode_id: BytecodeId::new(try_proto_convert(application_id.bytecode_id)?),
+            creation: try_proto_convert(application_id.creation)?,
+        })
+    }
+}
+
+impl From<Account> for api::Account {
+    fn from(account: Account) -> Self {
+        Self {
+            chain_id: Some(account.chain_id.into()),
+            owner: account.owner.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<api::Account> for Account {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(account: api::Account) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: try_proto_convert(account.chain_id)?,
+            owner: account.owner.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl From<MessageKind> for api::MessageKind {
+    fn from(kind: MessageKind) -> Self {
+        match kind {
+            MessageKind::Simple => api::MessageKind::Simple,
+            MessageKind::Protected => api::MessageKind::Protected,
+            MessageKind::Tracked => api::MessageKind::Tracked,
+            MessageKind::Bouncing => api::MessageKind::Bouncing,
+        }
+    }
+}
+
+/// Converts the value of a Proto `MessageKind` enum field.
+fn message_kind_from_proto(kind: i32) -> Result<MessageKind, GrpcProtoConversionError> {
+    let kind = api::MessageKind::try_from(kind)
+        .map_err(|_| GrpcProtoConversionError::InvalidEnumValue(kind))?;
+    Ok(match kind {
+        api::MessageKind::Simple => MessageKind::Simple,
+        api::MessageKind::Protected => MessageKind::Protected,
+        api::MessageKind::Tracked => MessageKind::Tracked,
+        api::MessageKind::Bouncing => MessageKind::Bouncing,
+    })
+}
+
+impl TryFrom<Operation> for api::Operation {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(operation: Operation) -> Result<Self, Self::Error> {
+        use api::operation::Inner;
+
+        let inner = match operation {
+            Operation::System(operation) => Inner::System(bincode::serialize(&operation)?),
+            Operation::User {
+                application_id,
+                bytes,
+            } => Inner::User(api::ApplicationPayload {
+                application_id: Some(application_id.into()),
+                bytes,
+            }),
+        };
+        Ok(Self { inner: Some(inner) })
+    }
+}
+
+impl TryFrom<api::Operation> for Operation {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(operation: api::Operation) -> Result<Self, Self::Error> {
+        use api::operation::Inner;
+
+        Ok(
+            match operation
+                .inner
+                .ok_or(GrpcProtoConversionError::MissingField)?
+            {
+                Inner::System(bytes) => Operation::System(limits::deserialize(&bytes)?),
+                Inner::User(payload) => Operation::User {
+                    application_id: try_proto_convert(payload.application_id)?,
+                    bytes: payload.bytes,
+                },
+            },
+        )
+    }
+}
+
+impl TryFrom<Message> for api::Message {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(message: Message) -> Result<Self, Self::Error> {
+        use api::message::Inner;
+
+        let inner = match message {
+            Message::System(message) => Inner::System(bincode::serialize(&message)?),
+            Message::User {
+                application_id,
+                bytes,
+            } => Inner::User(api::ApplicationPayload {
+                application_id: Some(application_id.into()),
+                bytes,
+            }),
+        };
+        Ok(Self { inner: Some(inner) })
+    }
+}
+
+impl TryFrom<api::Message> for Message {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(message: api::Message) -> Result<Self, Self::Error> {
+        use api::message::Inner;
+
+        Ok(
+            match message
+                .inner
+                .ok_or(GrpcProtoConversionError::MissingField)?
+            {
+                Inner::System(bytes) => Message::System(limits::deserialize(&bytes)?),
+                Inner::User(payload) => Message::User {
+                    application_id: try_proto_convert(payload.application_id)?,
+                    bytes: payload.bytes,
+                },
+            },
+        )
+    }
+}
+
+impl From<Origin> for api::Origin {
+    fn from(origin: Origin) -> Self {
+        let channel = match origin.medium {
+            Medium::Direct => None,
+            Medium::Channel(ChannelFullName {
+                application_id,
+                name,
+            }) => Some(api::ChannelFullName {
+                application_id: application_id
+                    .user_application_id()
+                    .copied()
+                    .map(Into::into),
+                name: name.as_ref().to_vec(),
+            }),
+        };
+        Self {
+            sender: Some(origin.sender.into()),
+            channel,
+        }
+    }
+}
+
+impl TryFrom<api::Origin> for Origin {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(origin: api::Origin) -> Result<Self, Self::Error> {
+        let medium = match origin.channel {
+            None => Medium::Direct,
+            Some(channel) => Medium::Channel(ChannelFullName {
+                application_id: match channel.application_id {
+                    None => GenericApplicationId::System,
+                    Some(application_id) => GenericApplicationId::User(application_id.try_into()?),
+                },
+                name: ChannelName::from(channel.name),
+            }),
+        };
+        Ok(Self {
+            sender: try_proto_convert(origin.sender)?,
+            medium,
+        })
+    }
+}
+
+impl TryFrom<Event> for api::Event {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        Ok(Self {
+            certificate_hash: Some(event.certificate_hash.into()),
+            height: Some(event.height.into()),
+            index: event.index,
+            authenticated_signer: event.authenticated_signer.map(Into::into),
+            grant: Some(event.grant.into()),
+            refund_grant_to: event.refund_grant_to.map(Into::into),
+            kind: api::MessageKind::from(event.kind).into(),
+            expiration: event.expiration.map(|expiration| expiration.micros()),
+            timestamp: event.timestamp.micros(),
+            message: Some(event.message.try_into()?),
+        })
+    }
+}
+
+impl TryFrom<api::Event> for Event {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(event: api::Event) -> Result<Self, Self::Error> {
+        Ok(Self {
+            certificate_hash: try_proto_convert(event.certificate_hash)?,
+            height: block_height_from_proto(event.height)?,
+            index: event.index,
+            authenticated_signer: event
+                .authenticated_signer
+                .map(TryInto::try_into)
+                .transpose()?,
+            grant: amount_from_proto(event.grant)?,
+            refund_grant_to: event.refund_grant_to.map(TryInto::try_into).transpose()?,
+            kind: message_kind_from_proto(event.kind)?,
+            expiration: event.expiration.map(Timestamp::from),
+            timestamp: event.timestamp.into(),
+            message: try_proto_convert(event.message)?,
+        })
+    }
+}
+
+impl TryFrom<IncomingMessage> for api::IncomingMessage {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(message: IncomingMessage) -> Result<Self, Self::Error> {
+        let action = match message.action {
+            MessageAction::Accept => api::MessageAction::Accept,
+            MessageAction::Reject => api::MessageAction::Reject,
+        };
+        Ok(Self {
+            origin: Some(message.origin.into()),
+            event: Some(message.event.try_into()?),
+            action: action.into(),
+        })
+    }
+}
+
+impl TryFrom<api::IncomingMessage> for IncomingMessage {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(message: api::IncomingMessage) -> Result<Self, Self::Error> {
+        let action = match api::MessageAction::try_from(message.action)
+            .map_err(|_| GrpcProtoConversionError::InvalidEnumValue(message.action))?
+        {
+            api::MessageAction::Accept => MessageAction::Accept,
+            api::MessageAction::Reject => MessageAction::Reject,
+        };
+        Ok(Self {
+            origin: try_proto_convert(message.origin)?,
+            event: try_proto_convert(message.event)?,
+            action,
+        })
+    }
+}
+
+impl TryFrom<Block> for api::Block {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(block: Block) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: Some(block.chain_id.into()),
+            epoch: block.epoch.0,
+            incoming_messages: block
+                .incoming_messages
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            operations: block
+                .operations
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            height: Some(block.height.into()),
+            timestamp: block.timestamp.micros(),
+            authenticated_signer: block.authenticated_signer.map(Into::into),
+            previous_block_hash: block.previous_block_hash.map(Into::into),
+        })
+    }
+}
+
+impl TryFrom<api::Block> for Block {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(block: api::Block) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: try_proto_convert(block.chain_id)?,
+            epoch: Epoch(block.epoch),
+            incoming_messages: block
+                .incoming_messages
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            operations: block
+                .operations
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            height: block_height_from_proto(block.height)?,
+            timestamp: block.timestamp.into(),
+            authenticated_signer: block
+                .authenticated_signer
+                .map(TryInto::try_into)
+                .transpose()?,
+            previous_block_hash: block
+                .previous_block_hash
+                .map(TryInto::try_into)
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<BlockAndRound> for api::BlockAndRound {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(content: BlockAndRound) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block: Some(content.block.try_into()?),
+            round: Some(content.round.into()),
+        })
+    }
+}
+
+impl TryFrom<api::BlockAndRound> for BlockAndRound {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(content: api::BlockAndRound) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block: try_proto_convert(content.block)?,
+            round: try_proto_convert(content.round)?,
+        })
+    }
+}
+
+impl TryFrom<OutgoingMessage> for api::OutgoingMessage {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(message: OutgoingMessage) -> Result<Self, Self::Error> {
+        use api::outgoing_message::Destination as ApiDestination;
+
+        let destination = match message.destination {
+            Destination::Recipient(chain_id) => ApiDestination::Recipient(chain_id.into()),
+            Destination::Subscribers(name) => ApiDestination::Subscribers(name.as_ref().to_vec()),
+        };
+        Ok(Self {
+            destination: Some(destination),
+            authenticated_signer: message.authenticated_signer.map(Into::into),
+            grant: Some(message.grant.into()),
+            refund_grant_to: message.refund_grant_to.map(Into::into),
+            kind: api::MessageKind::from(message.kind).into(),
+            expiration: message.expiration.map(|expiration| expiration.micros()),
+            message: Some(message.message.try_into()?),
+        })
+    }
+}
+
+impl TryFrom<api::OutgoingMessage> for OutgoingMessage {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(message: api::OutgoingMessage) -> Result<Self, Self::Error> {
+        use api::outgoing_message::Destination as ApiDestination;
+
+        let destination = match message
+            .destination
+            .ok_or(GrpcProtoConversionError::MissingField)?
+        {
+            ApiDestination::Recipient(chain_id) => Destination::Recipient(chain_id.try_into()?),
+            ApiDestination::Subscribers(name) => Destination::Subscribers(name.into()),
+        };
+        Ok(Self {
+            destination,
+            authenticated_signer: message
+                .authenticated_signer
+                .map(TryInto::try_into)
+                .transpose()?,
+            grant: amount_from_proto(message.grant)?,
+            refund_grant_to: message.refund_grant_to.map(TryInto::try_into).transpose()?,
+            kind: message_kind_from_proto(message.kind)?,
+            expiration: message.expiration.map(Timestamp::from),
+            message: try_proto_convert(message.message)?,
+        })
+    }
+}
+
+impl From<OracleRecord> for api::OracleRecord {
+    fn from(record: OracleRecord) -> Self {
+        use api::oracle_response::Inner;
+
+        let responses = record
+            .responses
+            .into_iter()
+            .map(|response| {
+                let inner = match response {
+                    OracleResponse::Service(bytes) => Inner::Service(bytes),
+                    OracleResponse::Json(json) => Inner::Json(json),
+                    OracleResponse::Blob(blob_id) => Inner::Blob(blob_id.0.into()),
+                };
+                api::OracleResponse { inner: Some(inner) }
+            })
+            .collect();
+        Self { responses }
+    }
+}
+
+impl TryFrom<api::OracleRecord> for OracleRecord {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(record: api::OracleRecord) -> Result<Self, Self::Error> {
+        use api::oracle_response::Inner;
+
+        let responses = record
+            .responses
+            .into_iter()
+            .map(|response| {
+                match response
+                    .inner
+                    .ok_or(GrpcProtoConversionError::MissingField)?
+                {
+                    Inner::Service(bytes) => Ok(OracleResponse::Service(bytes)),
+                    Inner::Json(json) => Ok(OracleResponse::Json(json)),
+                    Inner::Blob(hash) => Ok(OracleResponse::Blob(BlobId(hash.try_into()?))),
+                }
+            })
+            .collect::<Result<_, GrpcProtoConversionError>>()?;
+        Ok(Self { responses })
+    }
+}
+
+impl From<ApplicationEvent> for api::ApplicationEvent {
+    fn from(event: ApplicationEvent) -> Self {
+        Self {
+            transaction_index: event.transaction_index,
+            application_id: Some(event.application_id.into()),
+            key: event.key,
+            value: event.value,
+        }
+    }
+}
+
+impl TryFrom<api::ApplicationEvent> for ApplicationEvent {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(event: api::ApplicationEvent) -> Result<Self, Self::Error> {
+        Ok(Self {
+            transaction_index: event.transaction_index,
+            application_id: try_proto_convert(event.application_id)?,
+            key: event.key,
+            value: event.value,
+        })
+    }
+}
+
+impl TryFrom<ExecutedBlock> for api::ExecutedBlock {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(executed_block: ExecutedBlock) -> Result<Self, Self::Error> {
+        let outcome = executed_block.outcome;
+        Ok(Self {
+            block: Some(executed_block.block.try_into()?),
+            outcome: Some(api::BlockExecutionOutcome {
+                messages: outcome
+                    .messages
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+                message_counts: outcome.message_counts,
+                state_hash: Some(outcome.state_hash.into()),
+                oracle_records: outcome.oracle_records.into_iter().map(Into::into).collect(),
+                fuel_used: outcome.fuel_used,
+                events: outcome.events.into_iter().map(Into::into).collect(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<api::ExecutedBlock> for ExecutedBlock {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(executed_block: api::ExecutedBlock) -> Result<Self, Self::Error> {
+        let outcome = executed_block
+            .outcome
+            .ok_or(GrpcProtoConversionError::MissingField)?;
+        Ok(Self {
+            block: try_proto_convert(executed_block.block)?,
+            outcome: BlockExecutionOutcome {
+                messages: outcome
+                    .messages
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+                message_counts: outcome.message_counts,
+                state_hash: try_proto_convert(outcome.state_hash)?,
+                oracle_records: outcome
+                    .oracle_records
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+                fuel_used: outcome.fuel_used,
+                events: outcome
+                    .events
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+            },
+        })
+    }
+}
+
+impl TryFrom<CertificateValue> for api::CertificateValue {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(value: CertificateValue) -> Result<Self, Self::Error> {
+        use api::certificate_value::Inner;
+
+        let inner = match value {
+            CertificateValue::ValidatedBlock { executed_block } => {
+                Inner::ValidatedBlock(executed_block.try_into()?)
+            }
+            CertificateValue::ConfirmedBlock { executed_block } => {
+                Inner::ConfirmedBlock(executed_block.try_into()?)
+            }
+            CertificateValue::Timeout {
+                chain_id,
+                height,
+                epoch,
+            } => Inner::Timeout(api::Timeout {
+                chain_id: Some(chain_id.into()),
+                height: Some(height.into()),
+                epoch: epoch.0,
+            }),
+        };
+        Ok(Self { inner: Some(inner) })
+    }
+}
+
+impl TryFrom<api::CertificateValue> for CertificateValue {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(value: api::CertificateValue) -> Result<Self, Self::Error> {
+        use api::certificate_value::Inner;
+
+        Ok(
+            match value.inner.ok_or(GrpcProtoConversionError::MissingField)? {
+                Inner::ValidatedBlock(executed_block) => CertificateValue::ValidatedBlock {
+                    executed_block: executed_block.try_into()?,
+                },
+                Inner::ConfirmedBlock(executed_block) => CertificateValue::ConfirmedBlock {
+                    executed_block: executed_block.try_into()?,
+                },
+                Inner::Timeout(timeout) => CertificateValue::Timeout {
+                    chain_id: try_proto_convert(timeout.chain_id)?,
+                    height: block_height_from_proto(timeout.height)?,
+                    epoch: Epoch(timeout.epoch),
+                },
+            },
+        )
+    }
+}
+
+impl TryFrom<Certificate> for api::CertifiedValue {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(certificate: Certificate) -> Result<Self, Self::Error> {
+        let signatures = certificate
+            .signatures()
+            .iter()
+            .copied()
+            .map(Into::into)
+            .collect();
+        Ok(Self {
+            value: Some(certificate.value.into_inner().try_into()?),
+            round: Some(certificate.round.into()),
+            signatures,
+        })
+    }
+}
+
+impl TryFrom<api::CertifiedValue> for Certificate {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(certificate: api::CertifiedValue) -> Result<Self, Self::Error> {
+        let value: CertificateValue = try_proto_convert(certificate.value)?;
+        Ok(Certificate::new(
+            value.into(),
+            try_proto_convert(certificate.round)?,
+            signatures_from_proto(certificate.signatures)?,
+        ))
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
-    use std::{borrow::Cow, fmt::Debug};
+    use std::{borrow::Cow, collections::BTreeMap, fmt::Debug};
 
     use linera_base::{
         crypto::{BcsSignable, CryptoHash, KeyPair},
-        data_types::{Amount, Round, Timestamp},
+        data_types::{Amount, Round, SoftwareVersion, Timestamp},
     };
     use linera_chain::{
         data_types::{Block, BlockAndRound, BlockExecutionOutcome, HashedCertificateValue},
-        test::make_first_block,
+        test::{make_first_block, strategies, BlockTestExt as _},
     };
     use linera_core::data_types::ChainInfo;
+    use linera_execution::BlockLimits;
+    use proptest::collection::vec;
     use serde::{Deserialize, Serialize};
+    use test_strategy::proptest;
 
     use super::*;
 
@@ -554,6 +1581,40 @@ pub mod tests {
         assert_eq!(value, message.try_into().unwrap());
     }
 
+    /// Checks that a value is unchanged after serializing it with BCS and deserializing it.
+    fn bcs_round_trip_check<T>(value: T)
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Debug + Eq,
+    {
+        let bytes = bcs::to_bytes(&value).unwrap();
+        assert_eq!(value, bcs::from_bytes(&bytes).unwrap());
+    }
+
+    /// Decodes `bytes` as a protobuf message and tries to convert it, ignoring errors.
+    fn decode_check<T, M>(bytes: &[u8])
+    where
+        T: TryFrom<M>,
+        M: prost::Message + Default,
+    {
+        if let Ok(message) = M::decode(bytes) {
+            let _ = T::try_from(message);
+        }
+    }
+
+    /// Encodes `value` as a protobuf message, and checks that decoding and converting every
+    /// truncation of the encoding fails gracefully.
+    fn truncation_check<T, M>(value: T)
+    where
+        T: TryFrom<M>,
+        M: TryFrom<T> + prost::Message + Default,
+        M::Error: Debug,
+    {
+        let bytes = M::try_from(value).unwrap().encode_to_vec();
+        for length in 0..bytes.len() {
+            decode_check::<T, M>(&bytes[..length]);
+        }
+    }
+
     #[test]
     pub fn test_public_key() {
         let public_key = KeyPair::generate().public();
@@ -574,6 +1635,12 @@ pub mod tests {
         round_trip_check::<_, api::Owner>(owner);
     }
 
+    #[test]
+    pub fn test_crypto_hash() {
+        let hash = CryptoHash::new(&Foo("genesis".into()));
+        round_trip_check::<_, api::CryptoHash>(hash);
+    }
+
     #[test]
     pub fn test_block_height() {
         let block_height = BlockHeight::from(10);
@@ -610,9 +1677,20 @@ pub mod tests {
             requested_owner_balance: None,
             requested_pending_messages: vec![],
             requested_sent_certificates: vec![],
+            requested_sent_certificates_next_range: None,
             count_received_log: 0,
             requested_received_log: vec![],
+            requested_received_certificates: vec![],
+            received_certificates_cursor: None,
             requested_hashed_certificate_value: None,
+            requested_application_storage: BTreeMap::new(),
+            requested_collected_fees: BTreeMap::new(),
+            requested_events: vec![],
+            events_cursor: None,
+            requested_owner_balance_proof: None,
+            block_limits: BlockLimits::default(),
+            minimum_version: None,
+            software_version: SoftwareVersion::current(),
         });
 
         let chain_info_response_none = ChainInfoResponse {
@@ -647,13 +1725,28 @@ pub mod tests {
             }),
             request_received_log_excluding_first_nth: None,
             request_manager_values: false,
+            request_manager_status: true,
             request_leader_timeout: false,
             request_fallback: true,
             request_hashed_certificate_value: None,
+            request_application_storage: true,
+            request_received_certificates_after: Some(7),
+            request_collected_fees: true,
+            request_events_from: Some(3),
+            request_owner_balance_proof: Some(Owner::from(PublicKey::test_key(1))),
         };
         round_trip_check::<_, api::ChainInfoQuery>(chain_info_query_some);
     }
 
+    #[test]
+    pub fn test_download_blob_request() {
+        let request = DownloadBlobRequest {
+            chain_id: ChainId::root(0),
+            blob_id: Blob::new(b"blob".to_vec()).id(),
+        };
+        round_trip_check::<_, api::BlobRequest>(request);
+    }
+
     #[test]
     pub fn test_lite_certificate() {
         let key_pair = KeyPair::generate();
@@ -749,6 +1842,7 @@ pub mod tests {
                 }
                 .with(get_block()),
             )],
+            blobs: vec![Blob::new(b"blob".to_vec())],
             validated: Some(Certificate::new(
                 HashedCertificateValue::new_validated(
                     BlockExecutionOutcome {
@@ -779,4 +1873,243 @@ pub mod tests {
         };
         round_trip_check::<_, api::Notification>(notification);
     }
+
+    /// Tests that block proposals survive a round trip through their protobuf and BCS
+    /// representations.
+    #[proptest]
+    fn round_trips_arbitrary_block_proposal(
+        #[strategy(strategies::block_proposal())] proposal: BlockProposal,
+    ) {
+        round_trip_check::<_, api::BlockProposal>(proposal.clone());
+        bcs_round_trip_check(proposal);
+    }
+
+    /// Tests that certificates survive a round trip through their protobuf and BCS
+    /// representations.
+    #[proptest]
+    fn round_trips_arbitrary_certificate(
+        #[strategy(strategies::certificate())] certificate: Certificate,
+        #[strategy(vec(strategies::certificate_value(), 0..strategies::MAX_LENGTH))]
+        hashed_certificate_values: Vec<HashedCertificateValue>,
+        wait_for_outgoing_messages: bool,
+    ) {
+        bcs_round_trip_check(certificate.clone());
+        let request = HandleCertificateRequest {
+            certificate,
+            hashed_certificate_values,
+            wait_for_outgoing_messages,
+        };
+        round_trip_check::<_, api::Certificate>(request);
+    }
+
+    /// Tests that lite certificates survive a round trip through their protobuf and BCS
+    /// representations.
+    #[proptest]
+    fn round_trips_arbitrary_lite_certificate(
+        #[strategy(strategies::lite_certificate())] certificate: LiteCertificate<'static>,
+        wait_for_outgoing_messages: bool,
+    ) {
+        bcs_round_trip_check(certificate.clone());
+        let request = HandleLiteCertRequest {
+            certificate,
+            wait_for_outgoing_messages,
+        };
+        round_trip_check::<_, api::LiteCertificate>(request);
+    }
+
+    /// Tests that block proposals survive a round trip through their protobuf-native
+    /// representation, without any bincode-encoded content.
+    #[proptest]
+    fn round_trips_arbitrary_block_proposal_natively(
+        #[strategy(strategies::block_proposal())] proposal: BlockProposal,
+    ) {
+        let message =
+            api::BlockProposal::encode(proposal.clone(), ContentEncoding::Native).unwrap();
+        assert!(message.content.is_empty());
+        assert!(message.hashed_certificate_values.is_empty());
+        assert!(message.validated.is_none());
+        assert_eq!(proposal, BlockProposal::try_from(message).unwrap());
+    }
+
+    /// Tests that certificates survive a round trip through their protobuf-native
+    /// representation, without any bincode-encoded content.
+    #[proptest]
+    fn round_trips_arbitrary_certificate_natively(
+        #[strategy(strategies::certificate())] certificate: Certificate,
+        #[strategy(vec(strategies::certificate_value(), 0..strategies::MAX_LENGTH))]
+        hashed_certificate_values: Vec<HashedCertificateValue>,
+        wait_for_outgoing_messages: bool,
+    ) {
+        let request = HandleCertificateRequest {
+            certificate,
+            hashed_certificate_values,
+            wait_for_outgoing_messages,
+        };
+        let message = api::Certificate::encode(request.clone(), ContentEncoding::Native).unwrap();
+        assert!(message.value.is_empty());
+        assert!(message.round.is_empty());
+        assert!(message.signatures.is_empty());
+        assert!(message.hashed_certificate_values.is_empty());
+        assert_eq!(
+            request,
+            HandleCertificateRequest::try_from(message).unwrap()
+        );
+    }
+
+    /// Tests that lite certificates survive a round trip through their protobuf-native
+    /// representation, without any bincode-encoded content.
+    #[proptest]
+    fn round_trips_arbitrary_lite_certificate_natively(
+        #[strategy(strategies::lite_certificate())] certificate: LiteCertificate<'static>,
+        wait_for_outgoing_messages: bool,
+    ) {
+        let request = HandleLiteCertRequest {
+            certificate,
+            wait_for_outgoing_messages,
+        };
+        let message =
+            api::LiteCertificate::encode(request.clone(), ContentEncoding::Native).unwrap();
+        assert!(message.round.is_empty());
+        assert!(message.signatures.is_empty());
+        assert_eq!(request, HandleLiteCertRequest::try_from(message).unwrap());
+    }
+
+    /// Tests that cross-chain requests survive a round trip through their protobuf and BCS
+    /// representations.
+    #[proptest]
+    fn round_trips_arbitrary_cross_chain_request(request: CrossChainRequest) {
+        round_trip_check::<_, api::CrossChainRequest>(request.clone());
+        bcs_round_trip_check(request);
+    }
+
+    /// Tests that chain info queries survive a round trip through their protobuf and BCS
+    /// representations.
+    #[proptest]
+    fn round_trips_arbitrary_chain_info_query(query: ChainInfoQuery) {
+        round_trip_check::<_, api::ChainInfoQuery>(query.clone());
+        bcs_round_trip_check(query);
+    }
+
+    /// Tests that decoding arbitrary bytes as one of the requests handled by the proxy, and
+    /// converting the result, fails gracefully.
+    #[proptest]
+    fn decodes_arbitrary_requests_without_panicking(bytes: Vec<u8>) {
+        decode_check::<BlockProposal, api::BlockProposal>(&bytes);
+        decode_check::<HandleCertificateRequest, api::Certificate>(&bytes);
+        decode_check::<HandleLiteCertRequest, api::LiteCertificate>(&bytes);
+        decode_check::<CrossChainRequest, api::CrossChainRequest>(&bytes);
+        decode_check::<ChainInfoQuery, api::ChainInfoQuery>(&bytes);
+        decode_check::<Block, api::BlockSimulationRequest>(&bytes);
+        decode_check::<DownloadBlobRequest, api::BlobRequest>(&bytes);
+        let _ = bcs::from_bytes::<Certificate>(&bytes);
+        let _ = bcs::from_bytes::<BlockProposal>(&bytes);
+    }
+
+    /// Tests that truncated requests, as a peer closing the connection early could send, fail
+    /// gracefully.
+    #[proptest]
+    fn decodes_truncated_requests_without_panicking(
+        #[strategy(strategies::block_proposal())] proposal: BlockProposal,
+        #[strategy(strategies::lite_certificate())] certificate: LiteCertificate<'static>,
+        request: CrossChainRequest,
+        query: ChainInfoQuery,
+    ) {
+        truncation_check::<_, api::BlockProposal>(proposal);
+        truncation_check::<_, api::LiteCertificate>(HandleLiteCertRequest {
+            certificate,
+            wait_for_outgoing_messages: false,
+        });
+        truncation_check::<_, api::CrossChainRequest>(request);
+        truncation_check::<_, api::ChainInfoQuery>(query);
+    }
+
+    /// Tests that requests with invalid bytes in their serialized fields are rejected.
+    #[test]
+    pub fn test_requests_with_invalid_bytes() {
+        let invalid_bytes = vec![0xff; 8];
+
+        let proposal = get_block().into_fast_proposal(&KeyPair::generate());
+        let mut message = api::BlockProposal::try_from(proposal).unwrap();
+        message.content = invalid_bytes.clone();
+        assert!(matches!(
+            BlockProposal::try_from(message),
+            Err(GrpcProtoConversionError::BincodeError(_))
+        ));
+
+        let request = HandleCertificateRequest {
+            certificate: Certificate::new(
+                HashedCertificateValue::new_confirmed(
+                    BlockExecutionOutcome::default().with(get_block()),
+                ),
+                Round::Fast,
+                vec![],
+            ),
+            hashed_certificate_values: vec![],
+            wait_for_outgoing_messages: false,
+        };
+        let mut message = api::Certificate::try_from(request.clone()).unwrap();
+        message.signatures = invalid_bytes.clone();
+        // The bytes start with the length of the list of signatures, which is too large.
+        assert!(matches!(
+            HandleCertificateRequest::try_from(message),
+            Err(GrpcProtoConversionError::LimitExceeded(_))
+        ));
+
+        let mut message = api::LiteCertificate::try_from(HandleLiteCertRequest {
+            certificate: request.certificate.lite_certificate().cloned(),
+            wait_for_outgoing_messages: false,
+        })
+        .unwrap();
+        message.round = invalid_bytes.clone();
+        assert!(matches!(
+            HandleLiteCertRequest::try_from(message),
+            Err(GrpcProtoConversionError::BincodeError(_))
+        ));
+
+        let message = api::BlockSimulationRequest {
+            chain_id: Some(ChainId::root(0).into()),
+            block: invalid_bytes,
+        };
+        assert!(matches!(
+            Block::try_from(message),
+            Err(GrpcProtoConversionError::BincodeError(_))
+        ));
+    }
+
+    /// Tests that the chain ID of a request, as extracted by the proxy, is the one of the
+    /// converted request.
+    #[cfg(with_server)]
+    #[proptest]
+    fn proxy_finds_chain_id_of_arbitrary_requests(
+        #[strategy(strategies::block_proposal())] proposal: BlockProposal,
+        #[strategy(strategies::lite_certificate())] certificate: LiteCertificate<'static>,
+        request: CrossChainRequest,
+        query: ChainInfoQuery,
+    ) {
+        use crate::grpc::GrpcProxyable as _;
+
+        let chain_id = proposal.content.block.chain_id;
+        let message = api::BlockProposal::try_from(proposal).unwrap();
+        assert_eq!(message.chain_id(), Some(chain_id));
+
+        let chain_id = certificate.value.chain_id;
+        let message = api::LiteCertificate::try_from(HandleLiteCertRequest {
+            certificate,
+            wait_for_outgoing_messages: false,
+        })
+        .unwrap();
+        assert_eq!(message.chain_id(), Some(chain_id));
+
+        // Cross-chain requests are routed by their recipient, whatever their kind.
+        let chain_id = match &request {
+            CrossChainRequest::UpdateRecipient { recipient, .. }
+            | CrossChainRequest::ConfirmUpdatedRecipient { recipient, .. } => *recipient,
+        };
+        let message = api::CrossChainRequest::try_from(request).unwrap();
+        assert_eq!(message.chain_id(), Some(chain_id));
+
+        let chain_id = query.chain_id;
+        let message = api::ChainInfoQuery::try_from(query).unwrap();
+        assert_eq!(message.chain_id(), Some(chain_id));
+    }
 }
@@ -0,0 +1,454 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Filtering of the peers allowed to send requests to the gRPC servers of a validator.
+//!
+//! Requests are rejected if their peer's IP address is in one of the denied ranges, or if it
+//! is in none of the allowed ranges while there are some. The ranges are read from a file
+//! that is reloaded when it changes, so that they can be updated without restarting the
+//! servers.
+//!
+//! Peers that repeatedly send requests that can't be converted, e.g. because they are
+//! truncated or inconsistent, can also be banned for a while. This should only be enabled
+//! where the peers are the clients: the shards would otherwise ban the proxy for the
+//! requests it forwards.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex, RwLock},
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use ipnet::IpNet;
+use thiserror::Error;
+use tonic::{
+    body::BoxBody,
+    codegen::http,
+    transport::{
+        server::{TcpConnectInfo, TlsConnectInfo},
+        Body,
+    },
+    Code, Status,
+};
+use tower::{Layer, Service};
+use tracing::{debug, info, warn};
+
+/// The number of peers above which the records of those that are not banned anymore are
+/// cleaned up.
+const MAX_PEER_RECORDS: usize = 10_000;
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct IpFilterConfig {
+    /// A file with the IP ranges allowed or denied to send requests, reloaded when it
+    /// changes. Each line is either `allow <CIDR>` or `deny <CIDR>`; empty lines and lines
+    /// starting with `#` are ignored.
+    #[arg(long = "ip-filter-file")]
+    pub file: Option<PathBuf>,
+
+    /// How often to check whether the IP filter file changed.
+    #[arg(long = "ip-filter-reload-interval-ms", default_value = "5000")]
+    pub reload_interval_ms: u64,
+
+    /// The number of malformed requests after which a peer is banned. Zero disables banning.
+    #[arg(long = "ban-after-malformed-requests", default_value = "0")]
+    pub ban_threshold: u32,
+
+    /// The period over which the malformed requests of a peer are counted.
+    #[arg(long = "ban-window-ms", default_value = "60000")]
+    pub ban_window_ms: u64,
+
+    /// How long a peer is banned for.
+    #[arg(long = "ban-duration-ms", default_value = "600000")]
+    pub ban_duration_ms: u64,
+}
+
+impl Default for IpFilterConfig {
+    fn default() -> Self {
+        IpFilterConfig {
+            file: None,
+            reload_interval_ms: 5_000,
+            ban_threshold: 0,
+            ban_window_ms: 60_000,
+            ban_duration_ms: 600_000,
+        }
+    }
+}
+
+impl IpFilterConfig {
+    /// Creates the filter and, if there is an IP filter file, starts reloading it whenever it
+    /// changes, until the filter is dropped.
+    pub fn start(&self) -> Result<Arc<IpFilter>, IpFilterError> {
+        let policy = BanPolicy {
+            threshold: self.ban_threshold,
+            window: Duration::from_millis(self.ban_window_ms),
+            duration: Duration::from_millis(self.ban_duration_ms),
+        };
+        let Some(path) = self.file.clone() else {
+            return Ok(Arc::new(IpFilter::new(IpFilterRules::default(), policy)));
+        };
+        let mut modified = fs::metadata(&path)?.modified()?;
+        let filter = Arc::new(IpFilter::new(IpFilterRules::load(&path)?, policy));
+        let weak_filter = Arc::downgrade(&filter);
+        let interval = Duration::from_millis(self.reload_interval_ms);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(filter) = weak_filter.upgrade() else {
+                    return;
+                };
+                match reload_if_modified(&filter, &path, modified) {
+                    Ok(time) => modified = time,
+                    Err(error) => warn!(%error, "Failed to reload the IP filter file"),
+                }
+            }
+        });
+        Ok(filter)
+    }
+}
+
+/// Reloads the rules if the file was modified after `modified`, and returns the time it was
+/// last modified.
+fn reload_if_modified(
+    filter: &IpFilter,
+    path: &Path,
+    modified: SystemTime,
+) -> Result<SystemTime, IpFilterError> {
+    let last_modified = fs::metadata(path)?.modified()?;
+    if last_modified != modified {
+        filter.set_rules(IpFilterRules::load(path)?);
+        info!(path = %path.display(), "Reloaded the IP filter file");
+    }
+    Ok(last_modified)
+}
+
+/// An error loading the rules of an [`IpFilter`].
+#[derive(Debug, Error)]
+pub enum IpFilterError {
+    #[error("failed to read the IP filter file: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid rule on line {line} of the IP filter file: {content:?}")]
+    InvalidRule { line: usize, content: String },
+}
+
+/// The IP ranges allowed and denied to send requests.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IpFilterRules {
+    /// If not empty, only the peers in these ranges are allowed.
+    pub allowed: Vec<IpNet>,
+    /// The peers in these ranges are never allowed.
+    pub denied: Vec<IpNet>,
+}
+
+impl IpFilterRules {
+    /// Reads the rules from a file.
+    pub fn load(path: &Path) -> Result<Self, IpFilterError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Returns whether the rules allow requests from `ip`.
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        !self.denied.iter().any(|range| range.contains(&ip))
+            && (self.allowed.is_empty() || self.allowed.iter().any(|range| range.contains(&ip)))
+    }
+}
+
+impl FromStr for IpFilterRules {
+    type Err = IpFilterError;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut rules = IpFilterRules::default();
+        for (index, line) in contents.lines().enumerate() {
+            let content = line.trim();
+            if content.is_empty() || content.starts_with('#') {
+                continue;
+            }
+            let invalid_rule = || IpFilterError::InvalidRule {
+                line: index + 1,
+                content: content.to_string(),
+            };
+            let (action, range) = content
+                .split_once(char::is_whitespace)
+                .ok_or_else(invalid_rule)?;
+            let range = parse_range(range.trim()).ok_or_else(invalid_rule)?;
+            match action {
+                "allow" => rules.allowed.push(range),
+                "deny" => rules.denied.push(range),
+                _ => return Err(invalid_rule()),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+/// Parses a CIDR range, or a single IP address.
+fn parse_range(range: &str) -> Option<IpNet> {
+    range
+        .parse()
+        .ok()
+        .or_else(|| range.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+/// When to ban a peer that sends malformed requests.
+#[derive(Clone, Copy, Debug)]
+struct BanPolicy {
+    /// The number of malformed requests after which a peer is banned, or zero to never ban.
+    threshold: u32,
+    window: Duration,
+    duration: Duration,
+}
+
+/// The malformed requests sent by a peer.
+struct PeerRecord {
+    window_start: Instant,
+    malformed_requests: u32,
+    banned_until: Option<Instant>,
+}
+
+/// Decides which peers are allowed to send requests to a server.
+pub struct IpFilter {
+    rules: RwLock<IpFilterRules>,
+    policy: BanPolicy,
+    peers: Mutex<HashMap<IpAddr, PeerRecord>>,
+}
+
+impl Default for IpFilter {
+    fn default() -> Self {
+        IpFilter::new(
+            IpFilterRules::default(),
+            BanPolicy {
+                threshold: 0,
+                window: Duration::ZERO,
+                duration: Duration::ZERO,
+            },
+        )
+    }
+}
+
+impl IpFilter {
+    fn new(rules: IpFilterRules, policy: BanPolicy) -> Self {
+        IpFilter {
+            rules: RwLock::new(rules),
+            policy,
+            peers: Mutex::default(),
+        }
+    }
+
+    /// Replaces the rules of the filter.
+    pub fn set_rules(&self, rules: IpFilterRules) {
+        *self.rules.write().unwrap() = rules;
+    }
+
+    /// Returns the current rules of the filter.
+    pub fn rules(&self) -> IpFilterRules {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// Returns whether requests from `ip` are accepted.
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        self.rules.read().unwrap().allows(ip) && !self.is_banned(ip, Instant::now())
+    }
+
+    fn is_banned(&self, ip: IpAddr, now: Instant) -> bool {
+        self.peers
+            .lock()
+            .unwrap()
+            .get(&ip)
+            .and_then(|record| record.banned_until)
+            .is_some_and(|banned_until| now < banned_until)
+    }
+
+    /// Records that `ip` sent a malformed request, and bans it if it sent too many of them.
+    pub fn record_malformed_request(&self, ip: IpAddr) {
+        self.record_malformed_request_at(ip, Instant::now());
+    }
+
+    fn record_malformed_request_at(&self, ip: IpAddr, now: Instant) {
+        if self.policy.threshold == 0 {
+            return;
+        }
+        let mut peers = self.peers.lock().unwrap();
+        if peers.len() >= MAX_PEER_RECORDS {
+            let window = self.policy.window;
+            peers.retain(|_, record| {
+                record.banned_until.is_some_and(|until| now < until)
+                    || now.duration_since(record.window_start) < window
+            });
+        }
+        let record = peers.entry(ip).or_insert(PeerRecord {
+            window_start: now,
+            malformed_requests: 0,
+            banned_until: None,
+        });
+        if now.duration_since(record.window_start) >= self.policy.window {
+            record.window_start = now;
+            record.malformed_requests = 0;
+        }
+        record.malformed_requests += 1;
+        if record.malformed_requests >= self.policy.threshold {
+            warn!(%ip, "Banning a peer after {} malformed requests", record.malformed_requests);
+            record.banned_until = Some(now + self.policy.duration);
+            record.window_start = now;
+            record.malformed_requests = 0;
+        }
+    }
+}
+
+/// A layer rejecting the requests from the peers that an [`IpFilter`] doesn't allow, and
+/// reporting the malformed requests to it.
+#[derive(Clone)]
+pub struct IpFilterLayer {
+    filter: Arc<IpFilter>,
+}
+
+impl IpFilterLayer {
+    pub fn new(filter: Arc<IpFilter>) -> Self {
+        IpFilterLayer { filter }
+    }
+}
+
+#[derive(Clone)]
+pub struct IpFilterService<T> {
+    service: T,
+    filter: Arc<IpFilter>,
+}
+
+impl<S> Layer<S> for IpFilterLayer {
+    type Service = IpFilterService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        IpFilterService {
+            service,
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<S> Service<http::Request<Body>> for IpFilterService<S>
+where
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>> + std::marker::Send,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let Some(ip) = remote_ip(&request) else {
+            return self.service.call(request).boxed();
+        };
+        if !self.filter.is_allowed(ip) {
+            debug!(%ip, "Rejecting a request from a filtered peer");
+            let response =
+                Status::permission_denied("requests from this address are not allowed").to_http();
+            return futures::future::ready(Ok(response)).boxed();
+        }
+        let future = self.service.call(request);
+        let filter = self.filter.clone();
+        async move {
+            let response = future.await?;
+            if Status::from_header_map(response.headers())
+                .is_some_and(|status| status.code() == Code::InvalidArgument)
+            {
+                filter.record_malformed_request(ip);
+            }
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+/// Returns the IP address of the peer that sent a request.
+pub(crate) fn remote_ip<B>(request: &http::Request<B>) -> Option<IpAddr> {
+    let extensions = request.extensions();
+    extensions
+        .get::<TcpConnectInfo>()
+        .and_then(TcpConnectInfo::remote_addr)
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .and_then(|info| info.get_ref().remote_addr())
+        })
+        .map(|address| address.ip().to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn test_rules_from_file_contents() {
+        let rules: IpFilterRules = "
+            # The internal network, except for a compromised pod.
+            allow 10.0.0.0/8
+            deny 10.1.2.3
+            allow ::1
+        "
+        .parse()
+        .unwrap();
+        assert_eq!(rules.allowed.len(), 2);
+        assert_eq!(rules.denied, vec!["10.1.2.3/32".parse().unwrap()]);
+        assert!(rules.allows(ip("10.4.5.6")));
+        assert!(!rules.allows(ip("10.1.2.3")));
+        assert!(!rules.allows(ip("192.168.0.1")));
+        assert!(rules.allows(ip("::1")));
+        // IPv4 addresses mapped to IPv6 by dual-stack sockets are matched as IPv4.
+        assert!(rules.allows(ip("::ffff:10.4.5.6")));
+
+        assert!(matches!(
+            "allow 10.0.0.0/8\nreject 1.2.3.4".parse::<IpFilterRules>(),
+            Err(IpFilterError::InvalidRule { line: 2, .. })
+        ));
+        assert!(matches!(
+            "allow 10.0.0.0/33".parse::<IpFilterRules>(),
+            Err(IpFilterError::InvalidRule { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_denied_ranges_without_allowed_ranges() {
+        let rules: IpFilterRules = "deny 192.168.0.0/16".parse().unwrap();
+        assert!(rules.allows(ip("10.0.0.1")));
+        assert!(!rules.allows(ip("192.168.3.4")));
+    }
+
+    #[test]
+    fn test_peers_are_banned_after_malformed_requests() {
+        let filter = IpFilter::new(
+            IpFilterRules::default(),
+            BanPolicy {
+                threshold: 3,
+                window: Duration::from_secs(10),
+                duration: Duration::from_secs(60),
+            },
+        );
+        let peer = ip("1.2.3.4");
+        let start = Instant::now();
+        filter.record_malformed_request_at(peer, start);
+        filter.record_malformed_request_at(peer, start + Duration::from_secs(1));
+        // The first request is too old to count anymore.
+        filter.record_malformed_request_at(peer, start + Duration::from_secs(11));
+        assert!(!filter.is_banned(peer, start + Duration::from_secs(11)));
+
+        filter.record_malformed_request_at(peer, start + Duration::from_secs(12));
+        filter.record_malformed_request_at(peer, start + Duration::from_secs(13));
+        assert!(filter.is_banned(peer, start + Duration::from_secs(13)));
+        assert!(!filter.is_banned(ip("1.2.3.5"), start + Duration::from_secs(13)));
+        assert!(!filter.is_banned(peer, start + Duration::from_secs(73)));
+    }
+}
@@ -3,9 +3,19 @@
 
 mod client;
 mod conversions;
+#[cfg(with_server)]
+pub mod ip_filter;
 mod node_provider;
+#[cfg(with_server)]
+pub mod peer_limits;
 pub mod pool;
 #[cfg(with_server)]
+pub mod protocol_version;
+#[cfg(with_server)]
+pub mod public_queries;
+#[cfg(with_server)]
+pub mod request_id;
+#[cfg(with_server)]
 mod server;
 pub mod transport;
 
@@ -38,10 +48,172 @@ pub enum GrpcError {
     #[error(transparent)]
     InvalidUri(#[from] tonic::codegen::http::uri::InvalidUri),
 
+    #[error("failed to load TLS certificates: {0}")]
+    Tls(#[from] std::io::Error),
+
+    #[error("failed to listen on the Unix domain socket {path}: {error}")]
+    UnixSocket {
+        path: std::path::PathBuf,
+        error: std::io::Error,
+    },
+
     #[cfg(with_server)]
     #[error(transparent)]
     Reflection(#[from] tonic_reflection::server::Error),
 }
 
+use crate::{
+    limits,
+    version::{check_protocol_version, PROTOCOL_VERSION_HEADER},
+};
+
 const MEBIBYTE: usize = 1024 * 1024;
 pub const GRPC_MAX_MESSAGE_SIZE: usize = 16 * MEBIBYTE;
+
+/// The header in which gRPC clients send the time they are willing to wait for a response.
+const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+/// The header in which an administrator sends the storage trace token of a validator, to
+/// receive the storage keys accessed while handling a request.
+pub const STORAGE_TRACE_TOKEN_HEADER: &str = "linera-storage-trace-token";
+
+/// The binary header in which a validator returns the storage trace of a request.
+pub const STORAGE_TRACE_HEADER: &str = "linera-storage-trace-bin";
+
+/// The header identifying a request in the logs of the proxy and the shards. Clients may set
+/// it; otherwise the proxy generates one. It is returned in the response, including when the
+/// request failed.
+pub const REQUEST_ID_HEADER: &str = "linera-request-id";
+
+/// The maximum length of the request IDs chosen by clients.
+pub const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+/// Returns the ID of a request given the metadata of its response or error status, if any.
+pub fn request_id(metadata: &tonic::metadata::MetadataMap) -> Option<&str> {
+    metadata.get(REQUEST_ID_HEADER)?.to_str().ok()
+}
+
+/// Returns the time the client of a request is willing to wait for the response, if it has
+/// set a deadline.
+pub fn grpc_timeout<T>(request: &tonic::Request<T>) -> Option<std::time::Duration> {
+    let value = request.metadata().get(GRPC_TIMEOUT_HEADER)?.to_str().ok()?;
+    parse_grpc_timeout(value)
+}
+
+/// Asks the validator to return the storage keys accessed while handling the request. The
+/// token must be the one the validator's shards were started with.
+pub fn request_storage_trace<T>(
+    request: &mut tonic::Request<T>,
+    token: &str,
+) -> Result<(), tonic::metadata::errors::InvalidMetadataValue> {
+    request
+        .metadata_mut()
+        .insert(STORAGE_TRACE_TOKEN_HEADER, token.parse()?);
+    Ok(())
+}
+
+/// Returns the storage trace of the request that was answered with `response`, if it was
+/// requested and recorded.
+pub fn storage_trace<T>(
+    response: &tonic::Response<T>,
+) -> Option<linera_views::trace::StorageTrace> {
+    let bytes = response
+        .metadata()
+        .get_bin(STORAGE_TRACE_HEADER)?
+        .to_bytes()
+        .ok()?;
+    limits::deserialize(&bytes).ok()
+}
+
+/// Sets the version of the wire format used by the sender of a request.
+pub fn set_protocol_version<T>(request: &mut tonic::Request<T>, version: u16) {
+    request
+        .metadata_mut()
+        .insert(PROTOCOL_VERSION_HEADER, version.into());
+}
+
+/// Returns the version of the wire format used by the sender of a request or response, given
+/// the value of its [`PROTOCOL_VERSION_HEADER`], if any. Peers of version 0 don't send the
+/// header.
+pub fn parse_protocol_version(value: Option<&[u8]>) -> Result<u16, tonic::Status> {
+    let Some(value) = value else {
+        return Ok(0);
+    };
+    let version = std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| tonic::Status::invalid_argument("invalid protocol version header"))?;
+    check_protocol_version(version)
+        .map_err(|error| tonic::Status::failed_precondition(error.to_string()))?;
+    Ok(version)
+}
+
+/// Parses the value of a `grpc-timeout` header: at most eight digits followed by a unit.
+fn parse_grpc_timeout(value: &str) -> Option<std::time::Duration> {
+    use std::time::Duration;
+
+    if value.len() < 2 || value.len() > 9 || !value.is_ascii() {
+        return None;
+    }
+    let (digits, unit) = value.split_at(value.len() - 1);
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let amount = digits.parse::<u64>().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+/// Named so as not to shadow the `tests` module re-exported from `conversions`.
+#[cfg(test)]
+mod header_tests {
+    use std::time::Duration;
+
+    use super::{grpc_timeout, parse_grpc_timeout, parse_protocol_version, set_protocol_version};
+    use crate::version::{PROTOCOL_VERSION, PROTOCOL_VERSION_HEADER};
+
+    #[test]
+    fn test_parse_grpc_timeout() {
+        assert_eq!(parse_grpc_timeout("2H"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_grpc_timeout("3M"), Some(Duration::from_secs(180)));
+        assert_eq!(parse_grpc_timeout("10S"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_grpc_timeout("250m"), Some(Duration::from_millis(250)));
+        assert_eq!(
+            parse_grpc_timeout("99999999u"),
+            Some(Duration::from_micros(99999999))
+        );
+        assert_eq!(parse_grpc_timeout("5n"), Some(Duration::from_nanos(5)));
+        assert_eq!(parse_grpc_timeout("S"), None);
+        assert_eq!(parse_grpc_timeout("+5S"), None);
+        assert_eq!(parse_grpc_timeout("5s"), None);
+        assert_eq!(parse_grpc_timeout("123456789S"), None);
+    }
+
+    #[test]
+    fn test_grpc_timeout_round_trip() {
+        let mut request = tonic::Request::new(());
+        assert_eq!(grpc_timeout(&request), None);
+        request.set_timeout(Duration::from_millis(1500));
+        assert_eq!(grpc_timeout(&request), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_protocol_version_header() {
+        let mut request = tonic::Request::new(());
+        let version = |request: &tonic::Request<()>| {
+            let value = request.metadata().get(PROTOCOL_VERSION_HEADER);
+            parse_protocol_version(value.map(|value| value.as_bytes()))
+        };
+        assert_eq!(version(&request).unwrap(), 0);
+        set_protocol_version(&mut request, PROTOCOL_VERSION);
+        assert_eq!(version(&request).unwrap(), PROTOCOL_VERSION);
+        assert!(parse_protocol_version(Some(b"one")).is_err());
+    }
+}
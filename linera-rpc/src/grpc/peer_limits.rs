@@ -0,0 +1,282 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Limits on the resources a single peer can use on a gRPC server.
+//!
+//! Each peer, identified by its IP address, can only have a limited number of requests being
+//! handled at the same time, and must send the body of each request within a timeout. This
+//! prevents a peer that opens many streams, or sends its requests very slowly, from holding
+//! all the tasks of the server.
+//!
+//! The request bodies are read completely before the requests are handled, which is fine for
+//! the unary requests of the validator API.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use futures::{future::BoxFuture, FutureExt};
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body as _},
+    transport::Body,
+    Status,
+};
+use tower::{Layer, Service};
+use tracing::debug;
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::{IntCounterVec, IntGaugeVec},
+};
+
+use super::{ip_filter::remote_ip, GRPC_MAX_MESSAGE_SIZE};
+
+/// The maximum size of a request body. Bodies are larger than the messages they contain,
+/// e.g. because gRPC-web can encode them in base64.
+const MAX_BODY_SIZE: usize = 2 * GRPC_MAX_MESSAGE_SIZE;
+
+#[cfg(with_metrics)]
+static PEER_LIMIT_REJECTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "peer_limit_rejections",
+        "Requests rejected because their peer exceeded a limit",
+        &["reason"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static PEERS_WITH_REQUESTS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    prometheus_util::register_int_gauge_vec(
+        "peers_with_requests",
+        "Number of peers with requests being handled",
+        &[],
+    )
+    .expect("Gauge creation should not fail")
+});
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct PeerLimitsConfig {
+    /// The maximum number of requests from the same IP address handled at the same time.
+    #[arg(long = "max-concurrent-requests-per-peer", default_value = "64")]
+    pub max_concurrent_requests: usize,
+
+    /// The time a peer has to send the body of a request.
+    #[arg(long = "request-body-timeout-ms", default_value = "10000")]
+    pub body_timeout_ms: u64,
+}
+
+impl Default for PeerLimitsConfig {
+    fn default() -> Self {
+        PeerLimitsConfig {
+            max_concurrent_requests: 64,
+            body_timeout_ms: 10_000,
+        }
+    }
+}
+
+/// The requests being handled for each peer.
+pub struct PeerLimits {
+    max_concurrent_requests: usize,
+    body_timeout: Duration,
+    in_flight: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl PeerLimits {
+    pub fn new(config: &PeerLimitsConfig) -> Self {
+        PeerLimits {
+            max_concurrent_requests: config.max_concurrent_requests,
+            body_timeout: Duration::from_millis(config.body_timeout_ms),
+            in_flight: Mutex::default(),
+        }
+    }
+
+    /// Registers a new request from `ip`, unless it already has too many requests being
+    /// handled. The request is unregistered when the guard is dropped.
+    fn acquire(self: &Arc<Self>, ip: IpAddr) -> Option<InFlightRequest> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let count = in_flight.entry(ip).or_default();
+        if *count >= self.max_concurrent_requests {
+            if *count == 0 {
+                in_flight.remove(&ip);
+            }
+            return None;
+        }
+        *count += 1;
+        #[cfg(with_metrics)]
+        PEERS_WITH_REQUESTS
+            .with_label_values(&[])
+            .set(in_flight.len() as i64);
+        Some(InFlightRequest {
+            limits: self.clone(),
+            ip,
+        })
+    }
+
+    /// Returns the number of requests from `ip` being handled.
+    pub fn in_flight_requests(&self, ip: IpAddr) -> usize {
+        self.in_flight
+            .lock()
+            .unwrap()
+            .get(&ip)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// A request being handled, counted in the limits of its peer until it is dropped.
+struct InFlightRequest {
+    limits: Arc<PeerLimits>,
+    ip: IpAddr,
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        let mut in_flight = self.limits.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&self.ip);
+            }
+        }
+        #[cfg(with_metrics)]
+        PEERS_WITH_REQUESTS
+            .with_label_values(&[])
+            .set(in_flight.len() as i64);
+    }
+}
+
+/// A layer enforcing [`PeerLimits`].
+#[derive(Clone)]
+pub struct PeerLimitsLayer {
+    limits: Arc<PeerLimits>,
+}
+
+impl PeerLimitsLayer {
+    pub fn new(limits: Arc<PeerLimits>) -> Self {
+        PeerLimitsLayer { limits }
+    }
+}
+
+#[derive(Clone)]
+pub struct PeerLimitsService<T> {
+    service: T,
+    limits: Arc<PeerLimits>,
+}
+
+impl<S> Layer<S> for PeerLimitsLayer {
+    type Service = PeerLimitsService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        PeerLimitsService {
+            service,
+            limits: self.limits.clone(),
+        }
+    }
+}
+
+impl<S> Service<http::Request<Body>> for PeerLimitsService<S>
+where
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>>
+        + Clone
+        + std::marker::Send
+        + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let Some(ip) = remote_ip(&request) else {
+            return self.service.call(request).boxed();
+        };
+        let Some(in_flight_request) = self.limits.acquire(ip) else {
+            debug!(%ip, "Rejecting a request from a peer with too many requests");
+            record_rejection("concurrency");
+            let status = Status::resource_exhausted("too many concurrent requests");
+            return futures::future::ready(Ok(status.to_http())).boxed();
+        };
+        // The service that was polled to be ready is the one that must handle the request.
+        let clone = self.service.clone();
+        let mut service = std::mem::replace(&mut self.service, clone);
+        let body_timeout = self.limits.body_timeout;
+        async move {
+            let (parts, body) = request.into_parts();
+            let body = match tokio::time::timeout(body_timeout, read_body(body)).await {
+                Ok(Ok(body)) => body,
+                Ok(Err(status)) => {
+                    record_rejection("body_size");
+                    return Ok(status.to_http());
+                }
+                Err(_) => {
+                    debug!(%ip, "Rejecting a request whose body was not received in time");
+                    record_rejection("body_timeout");
+                    let status = Status::deadline_exceeded("timed out reading the request body");
+                    return Ok(status.to_http());
+                }
+            };
+            let response = service
+                .call(http::Request::from_parts(parts, Body::from(body)))
+                .await;
+            drop(in_flight_request);
+            response
+        }
+        .boxed()
+    }
+}
+
+/// Reads a request body, up to [`MAX_BODY_SIZE`] bytes.
+async fn read_body(mut body: Body) -> Result<Bytes, Status> {
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|error| Status::cancelled(error.to_string()))?;
+        if bytes.len() + chunk.len() > MAX_BODY_SIZE {
+            return Err(Status::resource_exhausted("request body is too large"));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes.freeze())
+}
+
+fn record_rejection(reason: &str) {
+    #![allow(unused_variables)]
+    #[cfg(with_metrics)]
+    PEER_LIMIT_REJECTIONS.with_label_values(&[reason]).inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_requests_are_limited_per_peer() {
+        let limits = Arc::new(PeerLimits::new(&PeerLimitsConfig {
+            max_concurrent_requests: 2,
+            ..PeerLimitsConfig::default()
+        }));
+        let peer = "1.2.3.4".parse().unwrap();
+        let other_peer = "1.2.3.5".parse().unwrap();
+
+        let first = limits.acquire(peer).unwrap();
+        let _second = limits.acquire(peer).unwrap();
+        assert!(limits.acquire(peer).is_none());
+        let _other = limits.acquire(other_peer).unwrap();
+        assert_eq!(limits.in_flight_requests(peer), 2);
+
+        drop(first);
+        assert_eq!(limits.in_flight_requests(peer), 1);
+        assert!(limits.acquire(peer).is_some());
+    }
+}
@@ -25,6 +25,13 @@ impl GrpcConnectionPool {
         self
     }
 
+    /// Uses TLS with the given configuration for the new channels.
+    #[cfg(not(web))]
+    pub fn with_tls(mut self, tls: impl Into<Option<tonic::transport::ClientTlsConfig>>) -> Self {
+        self.options.tls = tls.into();
+        self
+    }
+
     /// Obtains a channel for the current address. Either clones an existing one (thereby
     /// reusing the connection), or creates one if needed. New channels do not create a
     /// connection immediately.
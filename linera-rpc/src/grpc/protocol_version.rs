@@ -0,0 +1,78 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the version of the wire format used by the senders of gRPC requests.
+//!
+//! Requests from peers whose version can't be decoded anymore are rejected. The responses
+//! carry the version the peer should use to talk to this server, i.e. the lowest of the
+//! server's version and the peer's.
+
+use std::task::{Context, Poll};
+
+use futures::{future::BoxFuture, FutureExt};
+use tonic::{
+    body::BoxBody,
+    codegen::http::{self, HeaderValue},
+    transport::Body,
+};
+use tower::{Layer, Service};
+use tracing::debug;
+
+use super::parse_protocol_version;
+use crate::version::{negotiate_protocol_version, PROTOCOL_VERSION_HEADER};
+
+/// A layer checking the protocol version of the requests.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProtocolVersionLayer;
+
+#[derive(Clone)]
+pub struct ProtocolVersionService<S> {
+    service: S,
+}
+
+impl<S> Layer<S> for ProtocolVersionLayer {
+    type Service = ProtocolVersionService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        ProtocolVersionService { service }
+    }
+}
+
+impl<S> Service<http::Request<Body>> for ProtocolVersionService<S>
+where
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>> + std::marker::Send,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let value = request
+            .headers()
+            .get(PROTOCOL_VERSION_HEADER)
+            .map(HeaderValue::as_bytes);
+        let version = match parse_protocol_version(value) {
+            Ok(version) => version,
+            Err(status) => {
+                debug!(%status, "Rejecting a request with an unsupported protocol version");
+                return futures::future::ready(Ok(status.to_http())).boxed();
+            }
+        };
+        let future = self.service.call(request);
+        async move {
+            let mut response = future.await?;
+            response.headers_mut().insert(
+                PROTOCOL_VERSION_HEADER,
+                HeaderValue::from(negotiate_protocol_version(version)),
+            );
+            Ok(response)
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A read-only endpoint for anonymous clients, e.g. explorers and light clients.
+//!
+//! Only the methods in [`PUBLIC_QUERY_METHODS`] are answered: requests to submit proposals
+//! or certificates are rejected before reaching the service. Each peer, identified by its IP
+//! address, can only send a limited number of requests per second, with bursts of up to
+//! [`PublicQueryConfig::burst`] requests.
+//!
+//! The chain information returned by the validator node is signed by the validator, so
+//! clients can check the answers they receive through this endpoint.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use tonic::{body::BoxBody, codegen::http, transport::Body, Status};
+use tower::{Layer, Service};
+use tracing::debug;
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::IntCounterVec,
+};
+
+use super::ip_filter::remote_ip;
+
+/// The methods of the gRPC services answered on the public query endpoint.
+pub const PUBLIC_QUERY_METHODS: &[&str] = &[
+    "/rpc.v1.ValidatorNode/HandleChainInfoQuery",
+    "/rpc.v1.ValidatorNode/GetVersionInfo",
+    "/rpc.v1.ValidatorNode/GetGenesisConfigHash",
+    "/rpc.v1.ValidatorNode/DownloadBlob",
+    "/grpc.health.v1.Health/Check",
+];
+
+/// The maximum number of peers whose request rates are tracked. When there are more, the
+/// peers that could send a full burst again are forgotten.
+const MAX_TRACKED_PEERS: usize = 100_000;
+
+#[cfg(with_metrics)]
+static PUBLIC_QUERY_REJECTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "public_query_rejections",
+        "Requests rejected by the public query endpoint",
+        &["reason"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct PublicQueryConfig {
+    /// The port on which to serve the read-only public query API. It is disabled if no port
+    /// is given.
+    #[arg(long = "public-query-port")]
+    pub port: Option<u16>,
+
+    /// The number of requests per second a single IP address can send to the public query
+    /// API.
+    #[arg(long = "public-query-requests-per-second", default_value = "20")]
+    pub requests_per_second: u32,
+
+    /// The number of requests a single IP address can send at once to the public query API.
+    #[arg(long = "public-query-burst", default_value = "50")]
+    pub burst: u32,
+
+    /// The maximum number of requests to the public query API from the same IP address
+    /// handled at the same time.
+    #[arg(long = "public-query-max-concurrent-requests", default_value = "8")]
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for PublicQueryConfig {
+    fn default() -> Self {
+        PublicQueryConfig {
+            port: None,
+            requests_per_second: 20,
+            burst: 50,
+            max_concurrent_requests: 8,
+        }
+    }
+}
+
+/// The number of requests each peer can still send, as token buckets.
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(config: &PublicQueryConfig) -> Self {
+        RateLimiter {
+            requests_per_second: f64::from(config.requests_per_second),
+            burst: f64::from(config.burst.max(1)),
+            buckets: Mutex::default(),
+        }
+    }
+
+    /// Registers a request from `ip` at time `now`, and returns whether it is within the
+    /// rate limit.
+    fn try_acquire(&self, ip: IpAddr, now: Instant) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_PEERS && !buckets.contains_key(&ip) {
+            buckets.retain(|_, bucket| self.tokens(bucket, now) < self.burst);
+        }
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: self.burst,
+            updated_at: now,
+        });
+        bucket.tokens = self.tokens(bucket, now);
+        bucket.updated_at = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Returns the number of requests the peer of `bucket` can send at time `now`.
+    fn tokens(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        (bucket.tokens + elapsed.as_secs_f64() * self.requests_per_second).min(self.burst)
+    }
+}
+
+/// A layer only letting through the [`PUBLIC_QUERY_METHODS`], within the rate limit of
+/// each peer.
+#[derive(Clone)]
+pub struct PublicQueryLayer {
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl PublicQueryLayer {
+    pub fn new(rate_limiter: Arc<RateLimiter>) -> Self {
+        PublicQueryLayer { rate_limiter }
+    }
+}
+
+#[derive(Clone)]
+pub struct PublicQueryService<S> {
+    service: S,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl<S> Layer<S> for PublicQueryLayer {
+    type Service = PublicQueryService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        PublicQueryService {
+            service,
+            rate_limiter: self.rate_limiter.clone(),
+        }
+    }
+}
+
+impl<S> Service<http::Request<Body>> for PublicQueryService<S>
+where
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>> + std::marker::Send,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let path = request.uri().path();
+        if !is_public_query(path) {
+            debug!(path, "Rejecting a request to a method that is not public");
+            record_rejection("method");
+            let status = Status::permission_denied("only queries are served on this endpoint");
+            return futures::future::ready(Ok(status.to_http())).boxed();
+        }
+        if let Some(ip) = remote_ip(&request) {
+            if !self.rate_limiter.try_acquire(ip, Instant::now()) {
+                debug!(%ip, "Rejecting a query from a peer over its rate limit");
+                record_rejection("rate");
+                let status = Status::resource_exhausted("too many requests");
+                return futures::future::ready(Ok(status.to_http())).boxed();
+            }
+        }
+        self.service.call(request).boxed()
+    }
+}
+
+/// Returns whether the gRPC method at `path` is answered on the public query endpoint.
+fn is_public_query(path: &str) -> bool {
+    PUBLIC_QUERY_METHODS.contains(&path)
+}
+
+#[cfg_attr(not(with_metrics), allow(unused_variables))]
+fn record_rejection(reason: &str) {
+    #[cfg(with_metrics)]
+    PUBLIC_QUERY_REJECTIONS.with_label_values(&[reason]).inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_public_query_methods() {
+        assert!(is_public_query(
+            "/rpc.v1.ValidatorNode/HandleChainInfoQuery"
+        ));
+        assert!(!is_public_query(
+            "/rpc.v1.ValidatorNode/HandleBlockProposal"
+        ));
+        assert!(!is_public_query(
+            "/rpc.v1.ValidatorNode/HandleConfirmedCertificate"
+        ));
+        assert!(!is_public_query("/rpc.v1.ValidatorNode/Subscribe"));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let config = PublicQueryConfig {
+            requests_per_second: 2,
+            burst: 3,
+            ..PublicQueryConfig::default()
+        };
+        let limiter = RateLimiter::new(&config);
+        let alice = IpAddr::from([10, 0, 0, 1]);
+        let bob = IpAddr::from([10, 0, 0, 2]);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(alice, start));
+        }
+        assert!(!limiter.try_acquire(alice, start));
+        assert!(limiter.try_acquire(bob, start));
+        let later = start + Duration::from_millis(500);
+        assert!(limiter.try_acquire(alice, later));
+        assert!(!limiter.try_acquire(alice, later));
+        let much_later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(alice, much_later));
+        }
+        assert!(!limiter.try_acquire(alice, much_later));
+    }
+}
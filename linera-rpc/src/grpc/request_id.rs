@@ -0,0 +1,117 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Identifiers of gRPC requests, to correlate the failures reported by users with the logs
+//! of the proxy and the shards.
+//!
+//! Requests carry their ID in the [`REQUEST_ID_HEADER`]. A request without a valid one is
+//! given a random ID. The log lines written while handling the request include it, and the
+//! response returns it to the client.
+
+use std::task::{Context, Poll};
+
+use futures::{future::BoxFuture, FutureExt};
+use tonic::{
+    body::BoxBody,
+    codegen::http::{self, HeaderValue},
+    transport::Body,
+};
+use tower::{Layer, Service};
+use tracing::{info_span, Instrument};
+
+use super::{MAX_REQUEST_ID_LENGTH, REQUEST_ID_HEADER};
+
+/// A layer identifying the requests.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequestIdLayer;
+
+#[derive(Clone)]
+pub struct RequestIdService<S> {
+    service: S,
+}
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RequestIdService { service }
+    }
+}
+
+impl<S> Service<http::Request<Body>> for RequestIdService<S>
+where
+    S::Future: Send + 'static,
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>> + std::marker::Send,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<Body>) -> Self::Future {
+        let request_id = match request.headers().get(REQUEST_ID_HEADER) {
+            Some(value) if is_valid_request_id(value) => value.clone(),
+            _ => {
+                let value = HeaderValue::from_str(&generate_request_id())
+                    .expect("hexadecimal digits are valid header values");
+                request
+                    .headers_mut()
+                    .insert(REQUEST_ID_HEADER, value.clone());
+                value
+            }
+        };
+        let span = info_span!(
+            "request",
+            request_id = request_id.to_str().unwrap_or_default()
+        );
+        let future = span.in_scope(|| self.service.call(request));
+        async move {
+            let mut response = future.await?;
+            response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+            Ok(response)
+        }
+        .instrument(span)
+        .boxed()
+    }
+}
+
+/// Returns whether a request ID chosen by a client can be used in the logs.
+fn is_valid_request_id(value: &HeaderValue) -> bool {
+    let bytes = value.as_bytes();
+    !bytes.is_empty()
+        && bytes.len() <= MAX_REQUEST_ID_LENGTH
+        && bytes.iter().all(|byte| byte.is_ascii_graphic())
+}
+
+/// Returns a new random request ID.
+fn generate_request_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_ids() {
+        let generated = generate_request_id();
+        assert_eq!(generated.len(), 16);
+        assert!(is_valid_request_id(
+            &HeaderValue::from_str(&generated).unwrap()
+        ));
+        assert!(is_valid_request_id(&HeaderValue::from_static(
+            "client-42/proposal"
+        )));
+        assert!(!is_valid_request_id(&HeaderValue::from_static("")));
+        assert!(!is_valid_request_id(&HeaderValue::from_static(
+            "with space"
+        )));
+        let too_long = "x".repeat(MAX_REQUEST_ID_LENGTH + 1);
+        assert!(!is_valid_request_id(
+            &HeaderValue::from_str(&too_long).unwrap()
+        ));
+    }
+}
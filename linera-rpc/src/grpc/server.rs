@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+    collections::BTreeMap,
+    io, iter,
     net::SocketAddr,
+    path::Path,
     str::FromStr,
+    sync::Arc,
     task::{Context, Poll},
     time::{Duration, Instant},
 };
@@ -11,18 +15,26 @@ use std::{
 use futures::{
     channel::{mpsc, mpsc::Receiver, oneshot::Sender},
     future::BoxFuture,
-    FutureExt, StreamExt,
+    stream, FutureExt, StreamExt,
 };
 use linera_base::identifiers::ChainId;
 use linera_core::{
     node::NodeError,
-    worker::{NetworkActions, Notification, ValidatorWorker, WorkerError, WorkerState},
+    worker::{
+        AdminCommand, NetworkActions, Notification, ValidatorWorker, WorkerError, WorkerState,
+    },
 };
 use linera_storage::Storage;
 use linera_views::views::ViewError;
+use prost::Message as _;
 use rand::Rng;
-use tokio::{sync::oneshot, task::JoinHandle};
-use tonic::{Request, Response, Status};
+use tokio::{net::UnixListener, sync::oneshot, task::JoinHandle};
+use tokio_stream::wrappers::UnixListenerStream;
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    transport::{ClientTlsConfig, Server},
+    Request, Response, Status,
+};
 use tower::{builder::ServiceBuilder, Layer, Service};
 use tracing::{debug, error, info, instrument, warn};
 #[cfg(with_metrics)]
@@ -37,17 +49,27 @@ use super::{
         notifier_service_client::NotifierServiceClient,
         validator_worker_client::ValidatorWorkerClient,
         validator_worker_server::{ValidatorWorker as ValidatorWorkerRpc, ValidatorWorkerServer},
-        BlockProposal, Certificate, ChainInfoQuery, ChainInfoResult, CrossChainRequest,
-        LiteCertificate,
+        BlobRequest, BlobResult, BlockProposal, BlockSimulationRequest, BlockSimulationResult,
+        Certificate, ChainInfoQuery, ChainInfoResult, CrossChainRequest, CrossChainRequestAcks,
+        CrossChainRequestBatch, LiteCertificate,
     },
+    ip_filter::{IpFilter, IpFilterLayer},
     pool::GrpcConnectionPool,
-    GrpcError, GRPC_MAX_MESSAGE_SIZE,
+    protocol_version::ProtocolVersionLayer,
+    request_id::RequestIdLayer,
+    transport, GrpcError, GRPC_MAX_MESSAGE_SIZE, STORAGE_TRACE_HEADER, STORAGE_TRACE_TOKEN_HEADER,
 };
 use crate::{
-    config::{CrossChainConfig, NotificationConfig, ShardId, ValidatorInternalNetworkConfig},
-    HandleCertificateRequest, HandleLiteCertRequest,
+    config::{
+        CrossChainConfig, NotificationConfig, RetryPolicy, ShardId, ValidatorInternalNetworkConfig,
+    },
+    DownloadBlobRequest, HandleCertificateRequest, HandleLiteCertRequest,
 };
 
+/// The maximum encoded size of the cross-chain requests sent in one batch, leaving room for
+/// the rest of the message.
+const MAX_CROSS_CHAIN_BATCH_BYTES: usize = GRPC_MAX_MESSAGE_SIZE / 2;
+
 type CrossChainSender = mpsc::Sender<(linera_core::data_types::CrossChainRequest, ShardId)>;
 type NotificationSender = mpsc::Sender<Notification>;
 
@@ -171,11 +193,105 @@ where
     }
 }
 
+/// A layer recording the storage keys accessed while handling the requests that carry the
+/// storage trace token, and returning them in the response headers.
+#[derive(Clone)]
+pub struct StorageTraceLayer {
+    token: Option<Arc<str>>,
+}
+
+#[derive(Clone)]
+pub struct StorageTraceService<T> {
+    service: T,
+    token: Option<Arc<str>>,
+}
+
+impl<S> Layer<S> for StorageTraceLayer {
+    type Service = StorageTraceService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        StorageTraceService {
+            service,
+            token: self.token.clone(),
+        }
+    }
+}
+
+impl<S, B> Service<tonic::codegen::http::Request<tonic::transport::Body>> for StorageTraceService<S>
+where
+    S::Future: Send + 'static,
+    S: Service<
+            tonic::codegen::http::Request<tonic::transport::Body>,
+            Response = tonic::codegen::http::Response<B>,
+        > + std::marker::Send,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(
+        &mut self,
+        request: tonic::codegen::http::Request<tonic::transport::Body>,
+    ) -> Self::Future {
+        let traced = self.token.as_deref().is_some_and(|token| {
+            request
+                .headers()
+                .get(STORAGE_TRACE_TOKEN_HEADER)
+                .is_some_and(|value| value.as_bytes() == token.as_bytes())
+        });
+        let future = self.service.call(request);
+        if !traced {
+            return future.boxed();
+        }
+        async move {
+            let (result, trace) = linera_views::trace::record(future).await;
+            let mut response = result?;
+            info!(
+                reads = trace.reads(),
+                writes = trace.writes(),
+                omitted = trace.omitted,
+                "Recorded the storage trace of a request"
+            );
+            debug!(?trace, "Storage trace");
+            match bincode::serialize(&trace) {
+                Ok(bytes) => {
+                    let mut metadata = MetadataMap::new();
+                    metadata.insert_bin(STORAGE_TRACE_HEADER, MetadataValue::from_bytes(&bytes));
+                    response.headers_mut().extend(metadata.into_headers());
+                }
+                Err(error) => warn!(%error, "Failed to serialize the storage trace"),
+            }
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
 impl<S> GrpcServer<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
     ViewError: From<S::ContextError>,
 {
+    /// Spawns a gRPC server for a shard. If `storage_trace_token` is set, the requests
+    /// carrying it in the [`STORAGE_TRACE_TOKEN_HEADER`] are answered with the storage keys
+    /// they accessed.
+    ///
+    /// If the internal network uses mutual TLS, the server only accepts connections from the
+    /// proxy and the other shards, and presents its own certificate when connecting to them.
+    /// The requests from the peers that `ip_filter` doesn't allow are rejected.
+    ///
+    /// If the shard is configured with a Unix domain socket, the server listens on it instead
+    /// of its port, without TLS: the permissions of the socket control who can connect.
+    ///
+    /// The commands of the operators received from `admin_commands`, if any, are executed
+    /// by the worker, and the resulting cross-chain requests are sent.
+    ///
+    /// If `grpc_web` is set, the server also accepts grpc-web requests over HTTP/1.1, so
+    /// that browser clients can talk to it directly.
     #[allow(clippy::too_many_arguments)]
     pub async fn spawn(
         host: String,
@@ -185,6 +301,10 @@ where
         internal_network: ValidatorInternalNetworkConfig,
         cross_chain_config: CrossChainConfig,
         notification_config: NotificationConfig,
+        storage_trace_token: Option<String>,
+        grpc_web: bool,
+        ip_filter: Arc<IpFilter>,
+        admin_commands: Option<mpsc::UnboundedReceiver<AdminCommand>>,
     ) -> Result<GrpcServerHandle, GrpcError> {
         info!(
             "spawning gRPC server on {}:{} for shard {}",
@@ -192,6 +312,16 @@ where
         );
 
         let server_address = SocketAddr::from_str(&format!("{}:{}", host, port))?;
+        let unix_socket = internal_network.shard(shard_id).unix_socket.clone();
+
+        let mut server = Server::builder().accept_http1(grpc_web);
+        let mut client_tls = None;
+        if let Some(tls) = &internal_network.mutual_tls {
+            if unix_socket.is_none() {
+                server = server.tls_config(tls.server_config()?)?;
+            }
+            client_tls = Some(tls.client_config()?);
+        }
 
         let (cross_chain_sender, cross_chain_receiver) =
             mpsc::channel(cross_chain_config.queue_size);
@@ -207,12 +337,15 @@ where
             Self::forward_cross_chain_queries(
                 state.nickname().to_string(),
                 internal_network.clone(),
-                cross_chain_config.max_retries,
-                Duration::from_millis(cross_chain_config.retry_delay_ms),
+                cross_chain_config.retry_policy(),
                 Duration::from_millis(cross_chain_config.sender_delay_ms),
                 cross_chain_config.sender_failure_rate,
+                cross_chain_config.sender_duplicate_rate,
                 cross_chain_config.max_concurrent_tasks,
+                cross_chain_config.max_batch_size,
+                Duration::from_millis(cross_chain_config.batch_delay_ms),
                 shard_id,
+                client_tls.clone(),
                 cross_chain_receiver,
             )
         });
@@ -225,6 +358,7 @@ where
             Self::forward_notifications(
                 state.nickname().to_string(),
                 internal_network.proxy_address(),
+                client_tls,
                 notification_receiver,
             )
         });
@@ -243,6 +377,10 @@ where
             cross_chain_sender,
             notification_sender,
         };
+        grpc_server.replay_write_ahead_log().await;
+        if let Some(commands) = admin_commands {
+            tokio::spawn(grpc_server.clone().handle_admin_commands(commands));
+        }
 
         let worker_node = ValidatorWorkerServer::new(grpc_server)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
@@ -252,18 +390,41 @@ where
             .register_encoded_file_descriptor_set(crate::FILE_DESCRIPTOR_SET)
             .build()?;
 
-        let handle = tokio::spawn(
-            tonic::transport::Server::builder()
-                .layer(
-                    ServiceBuilder::new()
-                        .layer(GrpcPrometheusMetricsMiddlewareLayer)
-                        .into_inner(),
-                )
-                .add_service(health_service)
+        let router = server
+            .layer(
+                ServiceBuilder::new()
+                    .layer(RequestIdLayer)
+                    .layer(IpFilterLayer::new(ip_filter))
+                    .layer(ProtocolVersionLayer)
+                    .layer(GrpcPrometheusMetricsMiddlewareLayer)
+                    .layer(StorageTraceLayer {
+                        token: storage_trace_token.map(Arc::from),
+                    })
+                    .into_inner(),
+            )
+            .add_service(health_service);
+        let router = if grpc_web {
+            router
+                .add_service(tonic_web::enable(reflection_service))
+                .add_service(tonic_web::enable(worker_node))
+        } else {
+            router
                 .add_service(reflection_service)
                 .add_service(worker_node)
-                .serve_with_shutdown(server_address, receiver.map(|_| ())),
-        );
+        };
+        let shutdown = receiver.map(|_| ());
+        let handle = match unix_socket {
+            Some(path) => {
+                info!(path = %path.display(), "listening on a Unix domain socket for shard {}", shard_id);
+                let listener = bind_unix_socket(&path)
+                    .map_err(|error| GrpcError::UnixSocket { path, error })?;
+                tokio::spawn(
+                    router
+                        .serve_with_incoming_shutdown(UnixListenerStream::new(listener), shutdown),
+                )
+            }
+            None => tokio::spawn(router.serve_with_shutdown(server_address, shutdown)),
+        };
 
         Ok(GrpcServerHandle {
             _complete: complete,
@@ -273,15 +434,19 @@ where
 
     /// Continuously waits for receiver to receive a notification which is then sent to
     /// the proxy.
-    #[instrument(skip(receiver))]
+    #[instrument(skip(tls, receiver))]
     async fn forward_notifications(
         nickname: String,
         proxy_address: String,
+        tls: Option<ClientTlsConfig>,
         mut receiver: Receiver<Notification>,
     ) {
-        let channel = tonic::transport::Channel::from_shared(proxy_address.clone())
-            .expect("Proxy URI should be valid")
-            .connect_lazy();
+        let options = transport::Options {
+            tls,
+            ..transport::Options::default()
+        };
+        let channel = transport::create_channel(proxy_address.clone(), &options)
+            .expect("Proxy URI should be valid");
         let mut client = NotifierServiceClient::new(channel)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
             .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
@@ -306,6 +471,46 @@ where
         }
     }
 
+    /// Resumes the work that the write-ahead log records for the chains of this shard, e.g.
+    /// after a crash, and sends the pending cross-chain requests.
+    async fn replay_write_ahead_log(&self) {
+        let network = &self.network;
+        let shard_id = self.shard_id;
+        let result = self
+            .state
+            .clone()
+            .replay_write_ahead_log(|chain_id| network.get_shard_id(chain_id) == shard_id)
+            .await;
+        match result {
+            Ok(actions) => self.handle_network_actions(actions),
+            Err(error) => error!(
+                nickname = self.state.nickname(),
+                %error,
+                "Failed to replay the write-ahead log"
+            ),
+        }
+    }
+
+    /// Executes the commands of the operators, and sends the resulting cross-chain requests.
+    async fn handle_admin_commands(self, mut commands: mpsc::UnboundedReceiver<AdminCommand>) {
+        while let Some(command) = commands.next().await {
+            info!(
+                nickname = self.state.nickname(),
+                ?command,
+                "Executing a command of the operators"
+            );
+            match self.state.handle_admin_command(command).await {
+                Ok(actions) => self.handle_network_actions(actions),
+                Err(error) => error!(
+                    nickname = self.state.nickname(),
+                    %error,
+                    ?command,
+                    "Failed to execute a command of the operators"
+                ),
+            }
+        }
+    }
+
     fn handle_network_actions(&self, actions: NetworkActions) {
         let mut cross_chain_sender = self.cross_chain_sender.clone();
         let mut notification_sender = self.notification_sender.clone();
@@ -338,50 +543,84 @@ where
     async fn forward_cross_chain_queries(
         nickname: String,
         network: ValidatorInternalNetworkConfig,
-        cross_chain_max_retries: u32,
-        cross_chain_retry_delay: Duration,
+        cross_chain_retry: RetryPolicy,
         cross_chain_sender_delay: Duration,
         cross_chain_sender_failure_rate: f32,
+        cross_chain_sender_duplicate_rate: f32,
         cross_chain_max_concurrent_tasks: usize,
+        cross_chain_max_batch_size: usize,
+        cross_chain_batch_delay: Duration,
         this_shard: ShardId,
+        tls: Option<ClientTlsConfig>,
         receiver: mpsc::Receiver<(linera_core::data_types::CrossChainRequest, ShardId)>,
     ) {
-        let pool = GrpcConnectionPool::default();
+        let pool = GrpcConnectionPool::default().with_tls(tls);
         let max_concurrent_tasks = Some(cross_chain_max_concurrent_tasks);
 
-        receiver
-            .for_each_concurrent(max_concurrent_tasks, |(cross_chain_request, shard_id)| {
+        let requests = receiver.flat_map(|(cross_chain_request, shard_id)| {
+            let copies = if cross_chain_sender_failure_rate > 0.0
+                && rand::thread_rng().gen::<f32>() < cross_chain_sender_failure_rate
+            {
+                warn!("Dropped 1 cross-chain message intentionally.");
+                0
+            } else if cross_chain_sender_duplicate_rate > 0.0
+                && rand::thread_rng().gen::<f32>() < cross_chain_sender_duplicate_rate
+            {
+                warn!("Duplicated 1 cross-chain message intentionally.");
+                2
+            } else {
+                1
+            };
+            // The requests for the chains of the canary of a shard are sent to the canary.
+            let to_canary = network
+                .shard(shard_id)
+                .canary_for(cross_chain_request.target_chain_id())
+                .is_some();
+            let request = match CrossChainRequest::try_from(cross_chain_request) {
+                Ok(request) => Some((request, (shard_id, to_canary))),
+                Err(error) => {
+                    error!(%error, "Dropping a cross-chain query that can't be converted");
+                    None
+                }
+            };
+            stream::iter(iter::repeat(request).take(copies).flatten())
+        });
+        // During bursts, the requests that are already queued are sent together. With a
+        // batch delay, the requests scheduled shortly after the first one of a batch join it.
+        let max_batch_size = cross_chain_max_batch_size.max(1);
+        let batches = if cross_chain_batch_delay.is_zero() {
+            requests.ready_chunks(max_batch_size).boxed()
+        } else {
+            tokio_stream::StreamExt::chunks_timeout(
+                requests,
+                max_batch_size,
+                cross_chain_batch_delay,
+            )
+            .boxed()
+        };
+
+        batches
+            .flat_map(|requests| stream::iter(batches_by_shard(requests)))
+            .for_each_concurrent(max_concurrent_tasks, |(destination, mut requests)| {
+                let (shard_id, to_canary) = destination;
                 let shard = network.shard(shard_id);
-                let remote_address = format!("http://{}", shard.address());
+                let remote_address = match shard.canary.as_ref().filter(|_| to_canary) {
+                    Some(canary) => network.grpc_uri(canary.address()),
+                    None => network.shard_grpc_uri(shard),
+                };
 
                 let pool = pool.clone();
                 let nickname = nickname.clone();
 
-                // Send the cross-chain query and retry if needed.
+                // Send the cross-chain queries and retry the ones that were not handled.
                 async move {
-                    if cross_chain_sender_failure_rate > 0.0
-                        && rand::thread_rng().gen::<f32>() < cross_chain_sender_failure_rate
-                    {
-                        warn!("Dropped 1 cross-chain message intentionally.");
-                        return;
-                    }
-
-                    for i in 0..cross_chain_max_retries {
-                        // Delay increases linearly with the attempt number.
-                        tokio::time::sleep(cross_chain_sender_delay + cross_chain_retry_delay * i)
+                    #[cfg(with_metrics)]
+                    let start = Instant::now();
+                    for i in 0..cross_chain_retry.max_retries {
+                        tokio::time::sleep(cross_chain_sender_delay + cross_chain_retry.delay(i))
                             .await;
 
-                        let result = || async {
-                            let cross_chain_request = cross_chain_request.clone().try_into()?;
-                            let request = Request::new(cross_chain_request);
-                            let mut client =
-                                ValidatorWorkerClient::new(pool.channel(remote_address.clone())?)
-                                    .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
-                                    .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
-                            let response = client.handle_cross_chain_request(request).await?;
-                            Ok::<_, anyhow::Error>(response)
-                        };
-                        match result().await {
+                        match send_cross_chain_requests(&pool, &remote_address, &requests).await {
                             Err(error) => {
                                 warn!(
                                     nickname,
@@ -389,30 +628,65 @@ where
                                     i,
                                     from_shard = this_shard,
                                     to_shard = shard_id,
-                                    "Failed to send cross-chain query",
+                                    count = requests.len(),
+                                    "Failed to send cross-chain queries",
                                 );
                             }
-                            _ => {
+                            Ok(handled) => {
                                 debug!(
                                     from_shard = this_shard,
                                     to_shard = shard_id,
-                                    "Sent cross-chain query",
+                                    count = requests.len(),
+                                    "Sent cross-chain queries",
                                 );
-                                break;
+                                let mut handled = handled.into_iter();
+                                requests.retain(|_| !handled.next().unwrap_or(false));
+                                if requests.is_empty() {
+                                    #[cfg(with_metrics)]
+                                    crate::CROSS_CHAIN_DELIVERY_LATENCY
+                                        .with_label_values(&[&shard_id.to_string()])
+                                        .observe(start.elapsed().as_secs_f64() * 1000.0);
+                                    return;
+                                }
                             }
                         }
-                        error!(
-                            nickname,
-                            from_shard = this_shard,
-                            to_shard = shard_id,
-                            "Dropping cross-chain query",
-                        );
                     }
+                    error!(
+                        nickname,
+                        from_shard = this_shard,
+                        to_shard = shard_id,
+                        count = requests.len(),
+                        "Dropping cross-chain queries",
+                    );
                 }
             })
             .await;
     }
 
+    /// Handles a cross-chain request, and returns whether it succeeded.
+    async fn process_cross_chain_request(
+        &self,
+        request: linera_core::data_types::CrossChainRequest,
+    ) -> bool {
+        debug!(?request, "Handling cross-chain request");
+        match self.state.clone().handle_cross_chain_request(request).await {
+            Ok(actions) => {
+                self.handle_network_actions(actions);
+                true
+            }
+            Err(error) => {
+                #[cfg(with_metrics)]
+                {
+                    SERVER_REQUEST_ERROR
+                        .with_label_values(&["handle_cross_chain_request"])
+                        .inc();
+                }
+                error!(nickname = self.state.nickname(), %error, "Failed to handle cross-chain request");
+                false
+            }
+        }
+    }
+
     fn log_request_success_and_latency(start: Instant, method_name: &str) {
         #![allow(unused_variables)]
         #[cfg(with_metrics)]
@@ -576,37 +850,166 @@ where
         }
     }
 
-    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname(), chain_id= ?request.get_ref().chain_id()))]
-    async fn handle_cross_chain_request(
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname(), chain_id = ?request.get_ref().chain_id()))]
+    async fn simulate_block_proposal(
         &self,
-        request: Request<CrossChainRequest>,
-    ) -> Result<Response<()>, Status> {
+        request: Request<BlockSimulationRequest>,
+    ) -> Result<Response<BlockSimulationResult>, Status> {
         let start = Instant::now();
-        let request = request.into_inner().try_into()?;
-        debug!(?request, "Handling cross-chain request");
-        match self.state.clone().handle_cross_chain_request(request).await {
-            Ok(actions) => {
-                Self::log_request_success_and_latency(start, "handle_cross_chain_request");
-                self.handle_network_actions(actions)
+        let block = request.into_inner().try_into()?;
+        debug!(?block, "Simulating block proposal");
+        match self.state.clone().simulate_block_proposal(block).await {
+            Ok(executed_block) => {
+                Self::log_request_success_and_latency(start, "simulate_block_proposal");
+                Ok(Response::new(executed_block.try_into()?))
             }
             Err(error) => {
                 #[cfg(with_metrics)]
                 {
                     SERVER_REQUEST_ERROR
-                        .with_label_values(&["handle_cross_chain_request"])
+                        .with_label_values(&["simulate_block_proposal"])
                         .inc();
                 }
-                error!(nickname = self.state.nickname(), %error, "Failed to handle cross-chain request");
+                debug!(nickname = self.state.nickname(), %error, "Failed to simulate block proposal");
+                Ok(Response::new(NodeError::from(error).try_into()?))
             }
         }
+    }
+
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname(), chain_id = ?request.get_ref().chain_id()))]
+    async fn download_blob(
+        &self,
+        request: Request<BlobRequest>,
+    ) -> Result<Response<BlobResult>, Status> {
+        let start = Instant::now();
+        let DownloadBlobRequest { blob_id, .. } = request.into_inner().try_into()?;
+        debug!(?blob_id, "Downloading blob");
+        match self.state.download_blob(blob_id).await {
+            Ok(blob) => {
+                Self::log_request_success_and_latency(start, "download_blob");
+                Ok(Response::new(blob.into()))
+            }
+            Err(error) => {
+                #[cfg(with_metrics)]
+                {
+                    SERVER_REQUEST_ERROR
+                        .with_label_values(&["download_blob"])
+                        .inc();
+                }
+                debug!(nickname = self.state.nickname(), %error, "Failed to download blob");
+                Ok(Response::new(NodeError::from(error).try_into()?))
+            }
+        }
+    }
+
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname(), chain_id= ?request.get_ref().chain_id()))]
+    async fn handle_cross_chain_request(
+        &self,
+        request: Request<CrossChainRequest>,
+    ) -> Result<Response<()>, Status> {
+        let start = Instant::now();
+        let request = request.into_inner().try_into()?;
+        if self.process_cross_chain_request(request).await {
+            Self::log_request_success_and_latency(start, "handle_cross_chain_request");
+        }
         Ok(Response::new(()))
     }
+
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname(), count = request.get_ref().requests.len()))]
+    async fn handle_cross_chain_requests(
+        &self,
+        request: Request<CrossChainRequestBatch>,
+    ) -> Result<Response<CrossChainRequestAcks>, Status> {
+        let start = Instant::now();
+        let mut handled = Vec::new();
+        for request in request.into_inner().requests {
+            let is_handled = match request.try_into() {
+                Ok(request) => self.process_cross_chain_request(request).await,
+                Err(error) => {
+                    error!(%error, "Invalid cross-chain request in a batch");
+                    false
+                }
+            };
+            handled.push(is_handled);
+        }
+        Self::log_request_success_and_latency(start, "handle_cross_chain_requests");
+        Ok(Response::new(CrossChainRequestAcks { handled }))
+    }
+
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname()))]
+    async fn get_version_info(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<api::VersionInfo>, Status> {
+        Ok(Response::new(linera_version::VersionInfo::default().into()))
+    }
+}
+
+/// Groups the cross-chain `requests` by target shard, in batches that are small enough to be
+/// sent in one message. The requests to each shard stay in order.
+fn batches_by_shard<K: Ord + Copy>(
+    requests: Vec<(CrossChainRequest, K)>,
+) -> Vec<(K, Vec<CrossChainRequest>)> {
+    let mut batches = Vec::new();
+    let mut by_shard = BTreeMap::<K, Vec<CrossChainRequest>>::new();
+    for (request, shard_id) in requests {
+        by_shard.entry(shard_id).or_default().push(request);
+    }
+    for (shard_id, requests) in by_shard {
+        let mut batch = Vec::new();
+        let mut batch_size = 0;
+        for request in requests {
+            let size = request.encoded_len();
+            if !batch.is_empty() && batch_size + size > MAX_CROSS_CHAIN_BATCH_BYTES {
+                batches.push((shard_id, std::mem::take(&mut batch)));
+                batch_size = 0;
+            }
+            batch_size += size;
+            batch.push(request);
+        }
+        batches.push((shard_id, batch));
+    }
+    batches
+}
+
+/// Sends the cross-chain `requests` to the shard at `address`, and returns whether each one
+/// was handled. A single request is sent on its own.
+async fn send_cross_chain_requests(
+    pool: &GrpcConnectionPool,
+    address: &str,
+    requests: &[CrossChainRequest],
+) -> Result<Vec<bool>, anyhow::Error> {
+    let mut client = ValidatorWorkerClient::new(pool.channel(address.to_owned())?)
+        .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
+        .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
+    if let [request] = requests {
+        client
+            .handle_cross_chain_request(Request::new(request.clone()))
+            .await?;
+        return Ok(vec![true]);
+    }
+    let batch = CrossChainRequestBatch {
+        requests: requests.to_vec(),
+    };
+    let acks = client
+        .handle_cross_chain_requests(Request::new(batch))
+        .await?;
+    Ok(acks.into_inner().handled)
 }
 
 /// Types which are proxyable and expose the appropriate methods to be handled
 /// by the `GrpcProxy`
+///
+/// The chain ID is read from the dedicated routing field of the message, so that the proxy
+/// doesn't have to deserialize the bincode payloads sent by untrusted clients. The shard checks
+/// that it is consistent with the payload when converting the message.
 pub trait GrpcProxyable {
     fn chain_id(&self) -> Option<ChainId>;
+
+    /// Whether the request can be answered by a read replica of its shard.
+    fn is_read_only(&self) -> bool {
+        false
+    }
 }
 
 impl GrpcProxyable for BlockProposal {
@@ -631,6 +1034,26 @@ impl GrpcProxyable for ChainInfoQuery {
     fn chain_id(&self) -> Option<ChainId> {
         self.chain_id.clone()?.try_into().ok()
     }
+
+    fn is_read_only(&self) -> bool {
+        !self.request_leader_timeout && !self.request_fallback
+    }
+}
+
+impl GrpcProxyable for BlockSimulationRequest {
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id.clone()?.try_into().ok()
+    }
+}
+
+impl GrpcProxyable for BlobRequest {
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id.clone()?.try_into().ok()
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
 }
 
 impl GrpcProxyable for CrossChainRequest {
@@ -645,3 +1068,56 @@ impl GrpcProxyable for CrossChainRequest {
         }
     }
 }
+
+/// Binds a listener to the Unix domain socket at `path`, replacing the socket left by a
+/// previous run of the server, if any.
+fn bind_unix_socket(path: &Path) -> Result<UnixListener, io::Error> {
+    match std::fs::remove_file(path) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+        Err(error) => return Err(error),
+    }
+    UnixListener::bind(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        api::{cross_chain_request::Inner, ConfirmUpdatedRecipient},
+        *,
+    };
+
+    fn cross_chain_request(size: usize) -> CrossChainRequest {
+        CrossChainRequest {
+            inner: Some(Inner::ConfirmUpdatedRecipient(ConfirmUpdatedRecipient {
+                sender: None,
+                recipient: None,
+                latest_heights: vec![0; size],
+            })),
+        }
+    }
+
+    #[test]
+    fn test_cross_chain_batches() {
+        let requests = vec![
+            (cross_chain_request(1), 1),
+            (cross_chain_request(2), 0),
+            (cross_chain_request(3), 1),
+        ];
+        let batches = batches_by_shard(requests);
+        assert_eq!(
+            batches,
+            vec![
+                (0, vec![cross_chain_request(2)]),
+                (1, vec![cross_chain_request(1), cross_chain_request(3)]),
+            ]
+        );
+
+        let large = MAX_CROSS_CHAIN_BATCH_BYTES / 3;
+        let requests = (0..3).map(|_| (cross_chain_request(large), 0)).collect();
+        let batches = batches_by_shard(requests);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].1.len(), 2);
+        assert_eq!(batches[1].1.len(), 1);
+    }
+}
@@ -7,13 +7,18 @@ use crate::NodeOptions;
 pub struct Options {
     pub connect_timeout: Option<std::time::Duration>,
     pub timeout: Option<std::time::Duration>,
+    /// The certificates to authenticate the server and ourselves with, if any.
+    #[cfg(not(web))]
+    pub tls: Option<tonic::transport::ClientTlsConfig>,
 }
 
 impl From<&'_ NodeOptions> for Options {
     fn from(node_options: &NodeOptions) -> Self {
         Self {
-            connect_timeout: Some(node_options.send_timeout),
-            timeout: Some(node_options.recv_timeout),
+            connect_timeout: Some(node_options.timeout.send_timeout()),
+            timeout: Some(node_options.timeout.recv_timeout()),
+            #[cfg(not(web))]
+            tls: None,
         }
     }
 }
@@ -27,12 +32,50 @@ cfg_if::cfg_if! {
             Ok(tonic_web_wasm_client::Client::new(address))
         }
     } else {
+        use std::{fs, io};
+
         pub use tonic::transport::{Channel, Error};
+        use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+        use crate::config::InternalTlsConfig;
+
+        impl InternalTlsConfig {
+            /// Loads the TLS configuration of a client on the internal network.
+            pub fn client_config(&self) -> Result<ClientTlsConfig, io::Error> {
+                Ok(ClientTlsConfig::new()
+                    .ca_certificate(self.load_ca_certificate()?)
+                    .identity(self.load_identity()?)
+                    .domain_name(&self.domain_name))
+            }
+
+            /// Loads the TLS configuration of a server on the internal network, which rejects
+            /// the clients without a certificate issued by the certificate authority.
+            pub fn server_config(&self) -> Result<ServerTlsConfig, io::Error> {
+                Ok(ServerTlsConfig::new()
+                    .identity(self.load_identity()?)
+                    .client_ca_root(self.load_ca_certificate()?))
+            }
+
+            fn load_ca_certificate(&self) -> Result<Certificate, io::Error> {
+                Ok(Certificate::from_pem(fs::read(&self.ca_certificate)?))
+            }
+
+            fn load_identity(&self) -> Result<Identity, io::Error> {
+                Ok(Identity::from_pem(
+                    fs::read(&self.certificate)?,
+                    fs::read(&self.private_key)?,
+                ))
+            }
+        }
 
         pub fn create_channel(
             address: String,
             options: &Options,
         ) -> Result<Channel, Error> {
+            #[cfg(unix)]
+            if let Some(path) = address.strip_prefix(crate::config::UNIX_SOCKET_URI_PREFIX) {
+                return Ok(create_unix_channel(path.into(), options));
+            }
             let mut endpoint = tonic::transport::Endpoint::from_shared(address)?;
             if let Some(timeout) = options.connect_timeout {
                 endpoint = endpoint.connect_timeout(timeout);
@@ -40,7 +83,29 @@ cfg_if::cfg_if! {
             if let Some(timeout) = options.timeout {
                 endpoint = endpoint.timeout(timeout);
             }
+            if let Some(tls) = &options.tls {
+                endpoint = endpoint.tls_config(tls.clone())?;
+            }
             Ok(endpoint.connect_lazy())
         }
+
+        /// Creates a channel to a server listening on the Unix domain socket at `path`.
+        ///
+        /// TLS is not used: only the processes allowed by the permissions of the socket can
+        /// connect to it.
+        #[cfg(unix)]
+        fn create_unix_channel(path: std::path::PathBuf, options: &Options) -> Channel {
+            // The URI is only used for the requests' headers: the connector ignores it.
+            let mut endpoint = tonic::transport::Endpoint::from_static("http://localhost");
+            if let Some(timeout) = options.connect_timeout {
+                endpoint = endpoint.connect_timeout(timeout);
+            }
+            if let Some(timeout) = options.timeout {
+                endpoint = endpoint.timeout(timeout);
+            }
+            endpoint.connect_with_connector_lazy(tower::service_fn(
+                move |_: tonic::transport::Uri| tokio::net::UnixStream::connect(path.clone()),
+            ))
+        }
     }
 }
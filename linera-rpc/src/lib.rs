@@ -14,14 +14,38 @@ pub mod node_provider;
 
 pub mod client;
 
+pub mod limits;
 mod message;
 #[cfg(with_simple_network)]
 pub mod simple;
 
 pub mod grpc;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod version;
 
 pub use message::RpcMessage;
 pub use node_provider::NodeOptions;
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::HistogramVec,
+};
+
+/// The time to deliver the cross-chain requests to each target shard, in milliseconds,
+/// including the retries.
+#[cfg(with_metrics)]
+pub(crate) static CROSS_CHAIN_DELIVERY_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "cross_chain_delivery_latency",
+        "Cross-chain delivery latency",
+        &["target_shard"],
+        Some(vec![
+            0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
@@ -38,4 +62,12 @@ pub struct HandleCertificateRequest {
     pub hashed_certificate_values: Vec<linera_chain::data_types::HashedCertificateValue>,
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct DownloadBlobRequest {
+    /// The chain that published the blob, to route the request to its shard.
+    pub chain_id: linera_base::identifiers::ChainId,
+    pub blob_id: linera_base::identifiers::BlobId,
+}
+
 pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("file_descriptor_set");
@@ -0,0 +1,636 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Limits on the values deserialized from the messages of untrusted peers.
+//!
+//! The bincode payloads received over the network announce the lengths of their sequences,
+//! maps, strings and byte arrays in prefixes. A crafted payload could announce huge lengths,
+//! e.g. of a sequence of zero-sized elements, or nest values deeply enough to overflow the
+//! stack. The payloads are therefore deserialized with [`DeserializationLimits`], and the
+//! lengths they announce can't be larger than the payload itself.
+
+use std::{cell::Cell, fmt, io};
+
+use bincode::Options as _;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use thiserror::Error;
+
+/// The default maximum number of elements in a sequence or entries in a map.
+pub const DEFAULT_MAX_SEQUENCE_LENGTH: usize = 16 * 1024 * 1024;
+
+/// The default maximum size of a string or a byte array, in bytes.
+pub const DEFAULT_MAX_STRING_SIZE: usize = 16 * 1024 * 1024;
+
+/// The default maximum nesting depth of the deserialized values.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// The limits on the values deserialized from a message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DeserializationLimits {
+    /// The maximum number of elements in a sequence or entries in a map. Byte arrays that
+    /// are not serialized as bytes count as sequences.
+    pub max_sequence_length: usize,
+    /// The maximum size of a string or a byte array, in bytes.
+    pub max_string_size: usize,
+    /// The maximum nesting depth of structs, enums, tuples, options, sequences and maps.
+    pub max_depth: usize,
+}
+
+impl Default for DeserializationLimits {
+    fn default() -> Self {
+        DeserializationLimits {
+            max_sequence_length: DEFAULT_MAX_SEQUENCE_LENGTH,
+            max_string_size: DEFAULT_MAX_STRING_SIZE,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+/// A limit exceeded by a deserialized value.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum LimitExceeded {
+    #[error("Sequence of {length} elements exceeds the maximum length of {max}")]
+    SequenceTooLong { length: usize, max: usize },
+    #[error("String or byte array of {size} bytes exceeds the maximum size of {max}")]
+    StringTooLarge { size: usize, max: usize },
+    #[error("Value is nested deeper than the maximum depth of {max}")]
+    TooDeep { max: usize },
+}
+
+/// An error when deserializing a message with limits.
+#[derive(Debug, Error)]
+pub enum DeserializationError {
+    #[error(transparent)]
+    Bincode(#[from] bincode::Error),
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
+}
+
+/// Deserializes a bincode payload, in the format of [`bincode::deserialize`], with the
+/// default limits.
+pub fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DeserializationError> {
+    DeserializationLimits::default().deserialize(bytes)
+}
+
+impl DeserializationLimits {
+    /// Deserializes a bincode payload, in the format of [`bincode::deserialize`].
+    pub fn deserialize<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, DeserializationError> {
+        let options = bincode_options(bytes.len() as u64);
+        self.deserialize_with(&mut bincode::Deserializer::from_slice(bytes, options))
+    }
+
+    /// Deserializes a bincode payload of at most `size` bytes from the `reader`, in the format
+    /// of [`bincode::deserialize_from`]. The bytes after the value are left in the `reader`.
+    pub fn deserialize_from<T: DeserializeOwned>(
+        &self,
+        reader: impl io::Read,
+        size: u64,
+    ) -> Result<T, DeserializationError> {
+        let options = bincode_options(size);
+        self.deserialize_with(&mut bincode::Deserializer::with_reader(reader, options))
+    }
+
+    /// Deserializes a value with the `deserializer`, failing if it exceeds the limits.
+    pub fn deserialize_with<'de, T, D>(&self, deserializer: D) -> Result<T, DeserializationError>
+    where
+        T: de::Deserialize<'de>,
+        D: Deserializer<'de, Error = bincode::Error>,
+    {
+        let tracker = Tracker {
+            limits: *self,
+            depth: Cell::new(0),
+            exceeded: Cell::new(None),
+        };
+        let deserializer = Limited {
+            inner: deserializer,
+            tracker: &tracker,
+        };
+        T::deserialize(deserializer).map_err(|error| match tracker.exceeded.get() {
+            Some(exceeded) => DeserializationError::LimitExceeded(exceeded),
+            None => DeserializationError::Bincode(error),
+        })
+    }
+}
+
+/// Returns the options of [`bincode::deserialize`], rejecting the lengths that are larger than
+/// the `size` of the payload.
+fn bincode_options(size: u64) -> impl bincode::Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(size)
+}
+
+/// The state of a deserialization with limits.
+struct Tracker {
+    limits: DeserializationLimits,
+    /// The nesting depth of the value being deserialized.
+    depth: Cell<usize>,
+    /// The limit that made the deserialization fail, if any.
+    exceeded: Cell<Option<LimitExceeded>>,
+}
+
+impl Tracker {
+    fn fail<E: de::Error>(&self, exceeded: LimitExceeded) -> E {
+        self.exceeded.set(Some(exceeded));
+        E::custom(exceeded)
+    }
+
+    fn check_length<E: de::Error>(&self, length: usize) -> Result<(), E> {
+        let max = self.limits.max_sequence_length;
+        if length > max {
+            return Err(self.fail(LimitExceeded::SequenceTooLong { length, max }));
+        }
+        Ok(())
+    }
+
+    fn check_size<E: de::Error>(&self, size: usize) -> Result<(), E> {
+        let max = self.limits.max_string_size;
+        if size > max {
+            return Err(self.fail(LimitExceeded::StringTooLarge { size, max }));
+        }
+        Ok(())
+    }
+
+    fn enter<E: de::Error>(&self) -> Result<(), E> {
+        let depth = self.depth.get() + 1;
+        let max = self.limits.max_depth;
+        if depth > max {
+            return Err(self.fail(LimitExceeded::TooDeep { max }));
+        }
+        self.depth.set(depth);
+        Ok(())
+    }
+
+    fn leave(&self) {
+        self.depth.set(self.depth.get() - 1);
+    }
+}
+
+/// A deserializer enforcing the limits of the `tracker` on the values of the `inner` one.
+struct Limited<'t, D> {
+    inner: D,
+    tracker: &'t Tracker,
+}
+
+impl<'t, D> Limited<'t, D> {
+    fn visitor<V>(&self, visitor: V, counted: bool) -> LimitedVisitor<'t, V> {
+        LimitedVisitor {
+            inner: visitor,
+            tracker: self.tracker,
+            counted,
+        }
+    }
+}
+
+/// Forwards the methods of a [`Deserializer`] to the inner one, with a limited visitor.
+macro_rules! forward_deserialize {
+    ($($method:ident($($argument:ident: $type:ty),*);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(
+                self,
+                $($argument: $type,)*
+                visitor: V,
+            ) -> Result<V::Value, D::Error> {
+                let visitor = self.visitor(visitor, false);
+                self.inner.$method($($argument,)* visitor)
+            }
+        )*
+    };
+}
+
+/// Forwards the methods of a [`Deserializer`] for nested values to the inner one, with a
+/// limited visitor, one level deeper. The elements are counted if the length of the value is
+/// read from the payload rather than known from its type.
+macro_rules! forward_deserialize_nested {
+    ($($method:ident($($argument:ident: $type:ty),*) counted: $counted:literal;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(
+                self,
+                $($argument: $type,)*
+                visitor: V,
+            ) -> Result<V::Value, D::Error> {
+                self.tracker.enter::<D::Error>()?;
+                let tracker = self.tracker;
+                let visitor = self.visitor(visitor, $counted);
+                let result = self.inner.$method($($argument,)* visitor);
+                tracker.leave();
+                result
+            }
+        )*
+    };
+}
+
+impl<'de, 't, D: Deserializer<'de>> Deserializer<'de> for Limited<'t, D> {
+    type Error = D::Error;
+
+    forward_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    forward_deserialize_nested! {
+        deserialize_option() counted: false;
+        deserialize_newtype_struct(name: &'static str) counted: false;
+        deserialize_seq() counted: true;
+        deserialize_tuple(length: usize) counted: false;
+        deserialize_tuple_struct(name: &'static str, length: usize) counted: false;
+        deserialize_map() counted: true;
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]) counted: false;
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]) counted: false;
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// A visitor passing limited deserializers and accessors to the `inner` one.
+struct LimitedVisitor<'t, V> {
+    inner: V,
+    tracker: &'t Tracker,
+    /// Whether the elements of a sequence or a map are counted against the limit.
+    counted: bool,
+}
+
+/// Forwards the methods of a [`Visitor`] for scalar values to the inner one.
+macro_rules! forward_visit {
+    ($($method:ident($type:ty);)*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $type) -> Result<V::Value, E> {
+                self.inner.$method(value)
+            }
+        )*
+    };
+}
+
+/// Forwards the methods of a [`Visitor`] for strings and byte arrays to the inner one, after
+/// checking their size.
+macro_rules! forward_visit_sized {
+    ($($method:ident($type:ty);)*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $type) -> Result<V::Value, E> {
+                self.tracker.check_size::<E>(value.len())?;
+                self.inner.$method(value)
+            }
+        )*
+    };
+}
+
+impl<'de, 't, V: Visitor<'de>> Visitor<'de> for LimitedVisitor<'t, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    forward_visit! {
+        visit_bool(bool);
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f32(f32);
+        visit_f64(f64);
+        visit_char(char);
+    }
+
+    forward_visit_sized! {
+        visit_str(&str);
+        visit_borrowed_str(&'de str);
+        visit_string(String);
+        visit_bytes(&[u8]);
+        visit_borrowed_bytes(&'de [u8]);
+        visit_byte_buf(Vec<u8>);
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        self.inner.visit_some(Limited {
+            inner: deserializer,
+            tracker: self.tracker,
+        })
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<V::Value, D::Error> {
+        self.inner.visit_newtype_struct(Limited {
+            inner: deserializer,
+            tracker: self.tracker,
+        })
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, sequence: A) -> Result<V::Value, A::Error> {
+        if let (true, Some(length)) = (self.counted, sequence.size_hint()) {
+            self.tracker.check_length::<A::Error>(length)?;
+        }
+        let length = self.counted.then_some(0);
+        self.inner.visit_seq(LimitedAccess {
+            inner: sequence,
+            tracker: self.tracker,
+            length,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        if let (true, Some(length)) = (self.counted, map.size_hint()) {
+            self.tracker.check_length::<A::Error>(length)?;
+        }
+        let length = self.counted.then_some(0);
+        self.inner.visit_map(LimitedAccess {
+            inner: map,
+            tracker: self.tracker,
+            length,
+        })
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.inner.visit_enum(LimitedAccess {
+            inner: data,
+            tracker: self.tracker,
+            length: None,
+        })
+    }
+}
+
+/// A seed deserializing the value of the `inner` one with a limited deserializer.
+struct LimitedSeed<'t, S> {
+    inner: S,
+    tracker: &'t Tracker,
+}
+
+impl<'de, 't, S: DeserializeSeed<'de>> DeserializeSeed<'de> for LimitedSeed<'t, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.inner.deserialize(Limited {
+            inner: deserializer,
+            tracker: self.tracker,
+        })
+    }
+}
+
+/// An accessor to the elements of a sequence or a map, or to the variant of an enum, that
+/// deserializes them with limits.
+struct LimitedAccess<'t, A> {
+    inner: A,
+    tracker: &'t Tracker,
+    /// The number of elements or entries deserialized so far, if they are counted.
+    length: Option<usize>,
+}
+
+impl<'t, A> LimitedAccess<'t, A> {
+    fn seed<S>(&self, seed: S) -> LimitedSeed<'t, S> {
+        LimitedSeed {
+            inner: seed,
+            tracker: self.tracker,
+        }
+    }
+
+    /// Counts an element, in case the length of the sequence was not announced.
+    fn count<T, E: de::Error>(&mut self, element: &Option<T>) -> Result<(), E> {
+        if let (Some(length), Some(_)) = (&mut self.length, element) {
+            *length += 1;
+            self.tracker.check_length::<E>(*length)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de, 't, A: SeqAccess<'de>> SeqAccess<'de> for LimitedAccess<'t, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        let seed = self.seed(seed);
+        let element = self.inner.next_element_seed(seed)?;
+        self.count::<_, A::Error>(&element)?;
+        Ok(element)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, 't, A: MapAccess<'de>> MapAccess<'de> for LimitedAccess<'t, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        let seed = self.seed(seed);
+        let key = self.inner.next_key_seed(seed)?;
+        self.count::<_, A::Error>(&key)?;
+        Ok(key)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, 't, A: EnumAccess<'de>> EnumAccess<'de> for LimitedAccess<'t, A> {
+    type Error = A::Error;
+    type Variant = LimitedAccess<'t, A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), A::Error> {
+        let seed = self.seed(seed);
+        let (value, variant) = self.inner.variant_seed(seed)?;
+        let variant = LimitedAccess {
+            inner: variant,
+            tracker: self.tracker,
+            length: None,
+        };
+        Ok((value, variant))
+    }
+}
+
+impl<'de, 't, A: VariantAccess<'de>> VariantAccess<'de> for LimitedAccess<'t, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        length: usize,
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        let visitor = LimitedVisitor {
+            inner: visitor,
+            tracker: self.tracker,
+            counted: false,
+        };
+        self.inner.tuple_variant(length, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        let visitor = LimitedVisitor {
+            inner: visitor,
+            tracker: self.tracker,
+            counted: false,
+        };
+        self.inner.struct_variant(fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::identifiers::ChainId;
+    use linera_core::{data_types::ChainInfoQuery, node::NodeError};
+    use serde::{Deserialize, Serialize};
+
+    use super::{DeserializationError, DeserializationLimits, LimitExceeded};
+    use crate::RpcMessage;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    enum Tree {
+        Leaf,
+        Node(Box<Tree>),
+    }
+
+    fn limits() -> DeserializationLimits {
+        DeserializationLimits {
+            max_sequence_length: 4,
+            max_string_size: 4,
+            max_depth: 8,
+        }
+    }
+
+    /// Test that the values within the limits are deserialized like with
+    /// [`bincode::deserialize`].
+    #[test]
+    fn deserializes_values_within_the_limits() {
+        let message = RpcMessage::from(ChainInfoQuery::new(ChainId::root(1)));
+        let bytes = bincode::serialize(&message).unwrap();
+        let deserialized: RpcMessage = DeserializationLimits::default()
+            .deserialize(&bytes)
+            .unwrap();
+        assert_eq!(bincode::serialize(&deserialized).unwrap(), bytes);
+
+        let value = (
+            vec![1u8, 2, 3, 4],
+            "abcd".to_string(),
+            Some(Tree::Node(Box::new(Tree::Leaf))),
+        );
+        let bytes = bincode::serialize(&value).unwrap();
+        assert_eq!(
+            limits()
+                .deserialize::<(Vec<u8>, String, Option<Tree>)>(&bytes)
+                .unwrap(),
+            value
+        );
+    }
+
+    /// Test that each limit is enforced with a typed error.
+    #[test]
+    fn rejects_values_exceeding_the_limits() {
+        let bytes = bincode::serialize(&vec![0u16; 5]).unwrap();
+        assert!(matches!(
+            limits().deserialize::<Vec<u16>>(&bytes),
+            Err(DeserializationError::LimitExceeded(
+                LimitExceeded::SequenceTooLong { length: 5, max: 4 }
+            ))
+        ));
+
+        let bytes = bincode::serialize(&NodeError::ClientIoError {
+            error: "too long".to_string(),
+        })
+        .unwrap();
+        assert!(matches!(
+            limits().deserialize::<NodeError>(&bytes),
+            Err(DeserializationError::LimitExceeded(
+                LimitExceeded::StringTooLarge { size: 8, max: 4 }
+            ))
+        ));
+
+        let mut tree = Tree::Leaf;
+        for _ in 0..8 {
+            tree = Tree::Node(Box::new(tree));
+        }
+        let bytes = bincode::serialize(&tree).unwrap();
+        assert!(matches!(
+            limits().deserialize::<Tree>(&bytes),
+            Err(DeserializationError::LimitExceeded(
+                LimitExceeded::TooDeep { max: 8 }
+            ))
+        ));
+    }
+
+    /// Test that a short payload announcing a huge sequence of zero-sized elements is
+    /// rejected without iterating over them.
+    #[test]
+    fn rejects_huge_sequences_of_zero_sized_elements() {
+        let bytes = u64::MAX.to_le_bytes();
+        assert!(matches!(
+            DeserializationLimits::default().deserialize::<Vec<()>>(&bytes),
+            Err(DeserializationError::LimitExceeded(
+                LimitExceeded::SequenceTooLong { .. }
+            ))
+        ));
+    }
+}
@@ -2,8 +2,11 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_base::identifiers::ChainId;
-use linera_chain::data_types::{BlockProposal, LiteVote};
+use linera_base::{
+    crypto::CryptoHash,
+    identifiers::{Blob, ChainId},
+};
+use linera_chain::data_types::{Block, BlockProposal, ExecutedBlock, LiteVote};
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     node::NodeError,
@@ -11,7 +14,7 @@ use linera_core::{
 use linera_version::VersionInfo;
 use serde::{Deserialize, Serialize};
 
-use crate::{HandleCertificateRequest, HandleLiteCertRequest};
+use crate::{DownloadBlobRequest, HandleCertificateRequest, HandleLiteCertRequest};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
@@ -22,15 +25,25 @@ pub enum RpcMessage {
     LiteCertificate(Box<HandleLiteCertRequest<'static>>),
     ChainInfoQuery(Box<ChainInfoQuery>),
     VersionInfoQuery,
+    BlockSimulation(Box<Block>),
 
     // Outbound
     Vote(Box<LiteVote>),
     ChainInfoResponse(Box<ChainInfoResponse>),
     Error(Box<NodeError>),
     VersionInfoResponse(Box<VersionInfo>),
+    BlockSimulationResponse(Box<ExecutedBlock>),
 
     // Internal to a validator
     CrossChainRequest(Box<CrossChainRequest>),
+
+    // Network identification, answered by the proxy
+    GenesisConfigHashQuery,
+    GenesisConfigHashResponse(Box<CryptoHash>),
+
+    // Blobs, fetched on demand by the nodes missing them
+    DownloadBlob(Box<DownloadBlobRequest>),
+    DownloadBlobResponse(Box<Blob>),
 }
 
 impl RpcMessage {
@@ -45,18 +58,33 @@ impl RpcMessage {
             LiteCertificate(request) => request.certificate.value.chain_id,
             Certificate(request) => request.certificate.value().chain_id(),
             ChainInfoQuery(query) => query.chain_id,
+            BlockSimulation(block) => block.chain_id,
+            DownloadBlob(request) => request.chain_id,
             CrossChainRequest(request) => request.target_chain_id(),
             Vote(_)
             | Error(_)
             | ChainInfoResponse(_)
             | VersionInfoQuery
-            | VersionInfoResponse(_) => {
+            | VersionInfoResponse(_)
+            | BlockSimulationResponse(_)
+            | GenesisConfigHashQuery
+            | GenesisConfigHashResponse(_)
+            | DownloadBlobResponse(_) => {
                 return None;
             }
         };
 
         Some(chain_id)
     }
+
+    /// Whether this message can be answered by a read replica of the shard of its chain.
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            RpcMessage::ChainInfoQuery(query) => query.is_read_only(),
+            RpcMessage::DownloadBlob(_) => true,
+            _ => false,
+        }
+    }
 }
 
 impl TryFrom<RpcMessage> for ChainInfoResponse {
@@ -83,6 +111,42 @@ impl TryFrom<RpcMessage> for VersionInfo {
     }
 }
 
+impl TryFrom<RpcMessage> for CryptoHash {
+    type Error = NodeError;
+    fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
+        use RpcMessage::*;
+        match message {
+            GenesisConfigHashResponse(hash) => Ok(*hash),
+            Error(error) => Err(*error),
+            _ => Err(NodeError::UnexpectedMessage),
+        }
+    }
+}
+
+impl TryFrom<RpcMessage> for ExecutedBlock {
+    type Error = NodeError;
+    fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
+        use RpcMessage::*;
+        match message {
+            BlockSimulationResponse(executed_block) => Ok(*executed_block),
+            Error(error) => Err(*error),
+            _ => Err(NodeError::UnexpectedMessage),
+        }
+    }
+}
+
+impl TryFrom<RpcMessage> for Blob {
+    type Error = NodeError;
+    fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
+        use RpcMessage::*;
+        match message {
+            DownloadBlobResponse(blob) => Ok(*blob),
+            Error(error) => Err(*error),
+            _ => Err(NodeError::UnexpectedMessage),
+        }
+    }
+}
+
 impl From<BlockProposal> for RpcMessage {
     fn from(block_proposal: BlockProposal) -> Self {
         RpcMessage::BlockProposal(Box::new(block_proposal))
@@ -107,6 +171,30 @@ impl From<ChainInfoQuery> for RpcMessage {
     }
 }
 
+impl From<Block> for RpcMessage {
+    fn from(block: Block) -> Self {
+        RpcMessage::BlockSimulation(Box::new(block))
+    }
+}
+
+impl From<ExecutedBlock> for RpcMessage {
+    fn from(executed_block: ExecutedBlock) -> Self {
+        RpcMessage::BlockSimulationResponse(Box::new(executed_block))
+    }
+}
+
+impl From<DownloadBlobRequest> for RpcMessage {
+    fn from(request: DownloadBlobRequest) -> Self {
+        RpcMessage::DownloadBlob(Box::new(request))
+    }
+}
+
+impl From<Blob> for RpcMessage {
+    fn from(blob: Blob) -> Self {
+        RpcMessage::DownloadBlobResponse(Box::new(blob))
+    }
+}
+
 impl From<LiteVote> for RpcMessage {
     fn from(vote: LiteVote) -> Self {
         RpcMessage::Vote(Box::new(vote))
@@ -131,6 +219,12 @@ impl From<CrossChainRequest> for RpcMessage {
     }
 }
 
+impl From<CryptoHash> for RpcMessage {
+    fn from(hash: CryptoHash) -> Self {
+        RpcMessage::GenesisConfigHashResponse(Box::new(hash))
+    }
+}
+
 impl From<VersionInfo> for RpcMessage {
     fn from(version_info: VersionInfo) -> Self {
         RpcMessage::VersionInfoResponse(Box::new(version_info))
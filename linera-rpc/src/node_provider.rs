@@ -1,13 +1,15 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::time::Duration;
-
 use linera_core::node::{LocalValidatorNodeProvider, NodeError};
 
 #[cfg(with_simple_network)]
 use crate::simple::SimpleNodeProvider;
-use crate::{client::Client, grpc::GrpcNodeProvider};
+use crate::{
+    client::Client,
+    config::{RetryPolicy, TimeoutPolicy},
+    grpc::GrpcNodeProvider,
+};
 
 /// A general node provider which delegates node provision to the underlying
 /// node provider according to the `ValidatorPublicNetworkConfig`.
@@ -47,10 +49,12 @@ impl LocalValidatorNodeProvider for NodeProvider {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct NodeOptions {
-    pub send_timeout: Duration,
-    pub recv_timeout: Duration,
-    pub notification_retry_delay: Duration,
-    pub notification_retries: u32,
+    pub timeout: TimeoutPolicy,
+    /// How to retry connecting to a validator to receive notifications.
+    pub notification_retry: RetryPolicy,
+    /// Whether to send the requests about a chain directly to the validator's shard of the
+    /// chain, if the proxy gives its address (gRPC only).
+    pub direct_to_shards: bool,
 }
@@ -0,0 +1,88 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The BCS formats of the types exchanged with validators, traced with `serde-reflection`.
+//!
+//! The resulting [`Registry`] describes every type of the wire protocol, e.g. [`RpcMessage`],
+//! so that SDKs in other languages can generate their serializers with `serde-generate`.
+//! The `linera-rpc` format test compares it with a snapshot, so that changes of the wire
+//! format are always deliberate.
+
+use linera_base::{
+    data_types::{OracleResponse, Round},
+    identifiers::{ChainDescription, Destination, GenericApplicationId},
+    ownership::ChainOwnership,
+};
+use linera_chain::{
+    data_types::{CertificateValue, HashedCertificateValue, Medium, MessageAction, Transaction},
+    manager::ChainManagerInfo,
+};
+use linera_core::{data_types::CrossChainRequest, node::NodeError};
+use linera_execution::{
+    system::{
+        AdminOperation, CommitteeChange, FeeSource, Recipient, SystemChannel, SystemMessage,
+        SystemOperation,
+    },
+    Message, MessageKind, Operation,
+};
+use serde_reflection::{Registry, Samples, Tracer, TracerConfig};
+
+use crate::RpcMessage;
+
+/// The formats in which the registry can be written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum SchemaFormat {
+    /// The YAML format read by `serdegen`, the command-line tool of `serde-generate`.
+    #[default]
+    Yaml,
+    /// The same registry as JSON.
+    Json,
+}
+
+/// Traces the formats of all the types of the wire protocol.
+pub fn registry() -> serde_reflection::Result<Registry> {
+    let mut tracer = Tracer::new(
+        TracerConfig::default()
+            .record_samples_for_newtype_structs(true)
+            .record_samples_for_tuple_structs(true),
+    );
+    let samples = Samples::new();
+    // 1. Record samples for types with custom deserializers.
+    // 2. Trace the main entry point(s) + every enum separately.
+    tracer.trace_type::<Round>(&samples)?;
+    tracer.trace_type::<OracleResponse>(&samples)?;
+    tracer.trace_type::<Recipient>(&samples)?;
+    tracer.trace_type::<SystemChannel>(&samples)?;
+    tracer.trace_type::<FeeSource>(&samples)?;
+    tracer.trace_type::<SystemOperation>(&samples)?;
+    tracer.trace_type::<AdminOperation>(&samples)?;
+    tracer.trace_type::<CommitteeChange>(&samples)?;
+    tracer.trace_type::<SystemMessage>(&samples)?;
+    tracer.trace_type::<Operation>(&samples)?;
+    tracer.trace_type::<Message>(&samples)?;
+    tracer.trace_type::<MessageAction>(&samples)?;
+    tracer.trace_type::<Transaction>(&samples)?;
+    tracer.trace_type::<MessageKind>(&samples)?;
+    tracer.trace_type::<HashedCertificateValue>(&samples)?;
+    tracer.trace_type::<CertificateValue>(&samples)?;
+    tracer.trace_type::<Medium>(&samples)?;
+    tracer.trace_type::<Destination>(&samples)?;
+    tracer.trace_type::<ChainDescription>(&samples)?;
+    tracer.trace_type::<ChainOwnership>(&samples)?;
+    tracer.trace_type::<GenericApplicationId>(&samples)?;
+    tracer.trace_type::<ChainManagerInfo>(&samples)?;
+    tracer.trace_type::<CrossChainRequest>(&samples)?;
+    tracer.trace_type::<NodeError>(&samples)?;
+    tracer.trace_type::<RpcMessage>(&samples)?;
+    tracer.registry()
+}
+
+/// Traces the formats of the wire protocol and writes them in the given format.
+pub fn export(format: SchemaFormat) -> anyhow::Result<String> {
+    // The errors of `serde_reflection` hold formats that can't be sent between threads.
+    let registry = registry().map_err(|error| anyhow::anyhow!("{error}"))?;
+    Ok(match format {
+        SchemaFormat::Yaml => serde_yaml::to_string(&registry)?,
+        SchemaFormat::Json => serde_json::to_string_pretty(&registry)?,
+    })
+}
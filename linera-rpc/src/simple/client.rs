@@ -6,9 +6,12 @@ use std::{future::Future, time::Duration};
 
 use async_trait::async_trait;
 use futures::{sink::SinkExt, stream::StreamExt};
-use linera_base::identifiers::ChainId;
+use linera_base::{
+    crypto::CryptoHash,
+    identifiers::{Blob, BlobId, ChainId},
+};
 use linera_chain::data_types::{
-    BlockProposal, Certificate, HashedCertificateValue, LiteCertificate,
+    Block, BlockProposal, Certificate, ExecutedBlock, HashedCertificateValue, LiteCertificate,
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
@@ -16,13 +19,33 @@ use linera_core::{
 };
 use linera_version::VersionInfo;
 use tokio::time;
+use tracing::debug;
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::IntCounterVec,
+};
 
 use super::{codec, transport::TransportProtocol};
 use crate::{
-    config::ValidatorPublicNetworkPreConfig, mass_client, HandleCertificateRequest,
-    HandleLiteCertRequest, RpcMessage,
+    config::ValidatorPublicNetworkPreConfig, mass_client, DownloadBlobRequest,
+    HandleCertificateRequest, HandleLiteCertRequest, RpcMessage,
 };
 
+/// The number of times a request is sent over UDP before giving up on a response. The receive
+/// timeout is shared between the attempts.
+const UDP_REQUEST_ATTEMPTS: u32 = 3;
+
+#[cfg(with_metrics)]
+static UDP_RETRANSMISSIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "udp_retransmissions",
+        "Requests sent again over UDP because no response arrived in time",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
 #[derive(Clone)]
 pub struct SimpleClient {
     network: ValidatorPublicNetworkPreConfig<TransportProtocol>,
@@ -49,16 +72,37 @@ impl SimpleClient {
     ) -> Result<RpcMessage, codec::Error> {
         let address = format!("{}:{}", self.network.host, self.network.port);
         let mut stream = self.network.protocol.connect(address).await?;
-        // Send message
-        time::timeout(self.send_timeout, stream.send(message))
-            .await
-            .map_err(|timeout| codec::Error::Io(timeout.into()))??;
-        // Wait for reply
-        time::timeout(self.recv_timeout, stream.next())
-            .await
-            .map_err(|timeout| codec::Error::Io(timeout.into()))?
-            .transpose()?
-            .ok_or_else(|| codec::Error::Io(std::io::ErrorKind::UnexpectedEof.into()))
+        // UDP may lose the request or the response, so the request is sent again if the
+        // response takes too long. Requests can safely be handled several times.
+        let attempts = match self.network.protocol {
+            TransportProtocol::Udp => UDP_REQUEST_ATTEMPTS,
+            TransportProtocol::Tcp => 1,
+        };
+        let recv_timeout = self.recv_timeout / attempts;
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                debug!(attempt, "Sending a request again over UDP");
+                #[cfg(with_metrics)]
+                UDP_RETRANSMISSIONS.with_label_values(&[]).inc();
+            }
+            // Send message
+            time::timeout(self.send_timeout, stream.send(message.clone()))
+                .await
+                .map_err(|timeout| codec::Error::Io(timeout.into()))??;
+            // Wait for reply
+            match time::timeout(recv_timeout, stream.next()).await {
+                Ok(reply) => {
+                    return reply
+                        .transpose()?
+                        .ok_or_else(|| codec::Error::Io(std::io::ErrorKind::UnexpectedEof.into()))
+                }
+                Err(timeout) if attempt == attempts => {
+                    return Err(codec::Error::Io(timeout.into()))
+                }
+                Err(_) => {}
+            }
+        }
+        unreachable!("the last attempt should return")
     }
 
     async fn query<Response>(&mut self, query: RpcMessage) -> Result<Response, Response::Error>
@@ -119,6 +163,21 @@ impl ValidatorNode for SimpleClient {
         self.query(query.into()).await
     }
 
+    /// Executes a block without voting on it or saving the result.
+    async fn simulate_block_proposal(&mut self, block: Block) -> Result<ExecutedBlock, NodeError> {
+        self.query(block.into()).await
+    }
+
+    /// Downloads a blob written or published by this validator.
+    async fn download_blob(
+        &mut self,
+        chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Result<Blob, NodeError> {
+        self.query(DownloadBlobRequest { chain_id, blob_id }.into())
+            .await
+    }
+
     fn subscribe(
         &mut self,
         _chains: Vec<ChainId>,
@@ -130,6 +189,10 @@ impl ValidatorNode for SimpleClient {
     async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError> {
         self.query(RpcMessage::VersionInfoQuery).await
     }
+
+    async fn get_genesis_config_hash(&mut self) -> Result<CryptoHash, NodeError> {
+        self.query(RpcMessage::GenesisConfigHashQuery).await
+    }
 }
 
 #[derive(Clone)]
@@ -8,17 +8,73 @@ use linera_core::node::NodeError;
 use thiserror::Error;
 use tokio_util::codec::{Decoder, Encoder};
 
-use crate::RpcMessage;
+use crate::{
+    limits::{DeserializationError, DeserializationLimits, LimitExceeded},
+    version::{
+        check_protocol_version, negotiate_protocol_version, UnsupportedProtocolVersion,
+        PROTOCOL_VERSION,
+    },
+    RpcMessage,
+};
 
 /// The size of the frame prefix that contains the payload size.
 const PREFIX_SIZE: u8 = mem::size_of::<u32>() as u8;
 
+/// The bytes starting the trailer with the version of the sender, after the message.
+const VERSION_TRAILER_MAGIC: [u8; 2] = *b"LV";
+
+/// The maximum size of a frame's payload, in bytes.
+///
+/// Larger frames are rejected as soon as their prefix is read, so that a peer can't make us
+/// buffer and deserialize arbitrarily large messages.
+pub const MAX_PAYLOAD_SIZE: usize = 16 * 1024 * 1024;
+
 /// An encoder/decoder of [`RpcMessage`]s for the RPC protocol.
 ///
 /// The frames are length-delimited by a [`u32`] prefix, and the payload is deserialized by
-/// [`bincode`].
+/// [`bincode`]. From version 1 of the wire format, the payload is followed by a trailer with
+/// the version of the sender: [`VERSION_TRAILER_MAGIC`] and the version as a little-endian
+/// [`u16`]. Frames of version 0 have no trailer.
+///
+/// Once a frame has been received, the following frames are sent in the lowest of our version
+/// and the sender's. A UDP socket shares its codec between all its peers, so its frames are
+/// sent in the version of the last frame received.
+///
+/// The maximum size of a frame's payload is [`MAX_PAYLOAD_SIZE`]. Over UDP, frames larger than
+/// a datagram are fragmented by [`UdpCodec`](super::UdpCodec). The messages received are
+/// deserialized with [`DeserializationLimits`].
 #[derive(Clone, Copy, Debug)]
-pub struct Codec;
+pub struct Codec {
+    version: u16,
+    limits: DeserializationLimits,
+}
+
+impl Default for Codec {
+    fn default() -> Self {
+        Codec::with_version(PROTOCOL_VERSION)
+    }
+}
+
+impl Codec {
+    /// Creates a codec sending frames of the given version.
+    pub fn with_version(version: u16) -> Self {
+        Codec {
+            version,
+            limits: DeserializationLimits::default(),
+        }
+    }
+
+    /// Returns this codec, deserializing the messages received with the given limits.
+    pub fn with_deserialization_limits(mut self, limits: DeserializationLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the version of the wire format of the frames sent.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+}
 
 impl Encoder<RpcMessage> for Codec {
     type Error = Error;
@@ -34,15 +90,23 @@ impl Encoder<RpcMessage> for Codec {
             .map_err(|error| Error::Serialization(*error))?;
 
         let mut frame_buffer = frame_writer.into_inner();
+        if self.version > 0 {
+            frame_buffer.put_slice(&VERSION_TRAILER_MAGIC);
+            frame_buffer.put_u16_le(self.version);
+        }
         let frame_size = frame_buffer.len();
         let payload_size = frame_size - PREFIX_SIZE as usize;
 
+        if payload_size > MAX_PAYLOAD_SIZE {
+            return Err(Error::MessageTooBig { size: payload_size });
+        }
+
         let mut start_of_frame = frame_buffer.deref_mut();
 
         start_of_frame.put_u32_le(
             payload_size
                 .try_into()
-                .map_err(|_| Error::MessageTooBig { size: payload_size })?,
+                .expect("MAX_PAYLOAD_SIZE should fit in a u32"),
         );
 
         buffer.unsplit(frame_buffer);
@@ -66,6 +130,10 @@ impl Decoder for Codec {
             .try_into()
             .expect("u32 should fit in a usize");
 
+        if payload_size > MAX_PAYLOAD_SIZE {
+            return Err(Error::MessageTooBig { size: payload_size });
+        }
+
         let frame_size = PREFIX_SIZE as usize + payload_size;
 
         if buffer.len() < frame_size {
@@ -76,8 +144,24 @@ impl Decoder for Codec {
         let _prefix = buffer.split_to(PREFIX_SIZE.into());
         let payload = buffer.split_to(payload_size);
 
-        let message =
-            bincode::deserialize(&payload).map_err(|error| Error::Deserialization(*error))?;
+        let mut reader = &payload[..];
+        // The lengths of the sequences in the payload can't be larger than the payload itself,
+        // so that a peer can't make us allocate more memory than it sent.
+        let message = self
+            .limits
+            .deserialize_from(&mut reader, payload_size as u64)?;
+        let version = match reader {
+            [] => 0,
+            // Newer versions may extend the trailer.
+            [magic_0, magic_1, version_0, version_1, ..]
+                if [*magic_0, *magic_1] == VERSION_TRAILER_MAGIC =>
+            {
+                u16::from_le_bytes([*version_0, *version_1])
+            }
+            _ => return Err(Error::InvalidTrailer),
+        };
+        check_protocol_version(version)?;
+        self.version = negotiate_protocol_version(version);
 
         Ok(Some(message))
     }
@@ -92,15 +176,36 @@ pub enum Error {
     #[error("Failed to deserialize an incoming message")]
     Deserialization(#[source] bincode::ErrorKind),
 
+    #[error("Incoming message exceeds the deserialization limits: {0}")]
+    LimitExceeded(#[from] LimitExceeded),
+
     #[error("Failed to serialize outgoing message")]
     Serialization(#[source] bincode::ErrorKind),
 
+    #[error("Unexpected bytes after the message in a frame")]
+    InvalidTrailer,
+
+    #[error("Invalid fragment of a frame received over UDP")]
+    InvalidFragment,
+
+    #[error(transparent)]
+    UnsupportedVersion(#[from] UnsupportedProtocolVersion),
+
     #[error("RpcMessage is too big to fit in a protocol frame: \
         message is {size} bytes but can't be larger than {max} bytes.",
-        max = u32::MAX)]
+        max = MAX_PAYLOAD_SIZE)]
     MessageTooBig { size: usize },
 }
 
+impl From<DeserializationError> for Error {
+    fn from(error: DeserializationError) -> Error {
+        match error {
+            DeserializationError::Bincode(error) => Error::Deserialization(*error),
+            DeserializationError::LimitExceeded(exceeded) => Error::LimitExceeded(exceeded),
+        }
+    }
+}
+
 impl From<Error> for NodeError {
     fn from(error: Error) -> NodeError {
         match error {
@@ -118,11 +223,15 @@ impl From<Error> for NodeError {
 #[cfg(test)]
 mod tests {
     use bytes::{BufMut, BytesMut};
-    use linera_core::data_types::ChainInfoQuery;
+    use linera_core::{data_types::ChainInfoQuery, node::NodeError};
     use test_strategy::proptest;
     use tokio_util::codec::{Decoder, Encoder};
 
-    use super::{Codec, RpcMessage, PREFIX_SIZE};
+    use super::{
+        Codec, Error, RpcMessage, MAX_PAYLOAD_SIZE, PREFIX_SIZE, PROTOCOL_VERSION,
+        VERSION_TRAILER_MAGIC,
+    };
+    use crate::limits::{DeserializationLimits, LimitExceeded};
 
     /// Test decoding of a frame from a buffer.
     ///
@@ -152,13 +261,17 @@ mod tests {
         buffer.extend_from_slice(&payload);
         buffer.extend_from_slice(&trailing_bytes);
 
-        let result = Codec.decode(&mut buffer);
+        let mut codec = Codec::default();
+        let result = codec.decode(&mut buffer);
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), Some(message));
 
         assert_eq!(&start_of_buffer, &leading_bytes);
         assert_eq!(&buffer, &trailing_bytes);
+
+        // The frame has no version trailer, so it was sent by a peer of version 0.
+        assert_eq!(codec.version(), 0);
     }
 
     /// Test encoding a message to buffer.
@@ -166,9 +279,9 @@ mod tests {
     /// The buffer may already contain some leading bytes, but the cursor is set to where the frame
     /// should start.
     ///
-    /// The encoder should write a prefix with the size of the serialized message, followed by the
-    /// serialized message bytes. It should not touch the leading bytes nor append any trailing
-    /// bytes.
+    /// The encoder should write a prefix with the size of the serialized message and version
+    /// trailer, followed by the serialized message bytes and the trailer. It should not touch
+    /// the leading bytes nor append any other trailing bytes.
     #[proptest]
     fn encodes_at_the_correct_buffer_offset(
         leading_bytes: Vec<u8>,
@@ -185,7 +298,7 @@ mod tests {
         let frame_start = buffer.len();
         let prefix_end = frame_start + PREFIX_SIZE as usize;
 
-        let result = Codec.encode(message, &mut buffer);
+        let result = Codec::default().encode(message, &mut buffer);
 
         assert!(matches!(result, Ok(())));
         assert_eq!(&buffer[..frame_start], &leading_bytes);
@@ -196,12 +309,131 @@ mod tests {
                 .expect("Incorrect prefix slice indices"),
         );
 
-        assert_eq!(prefix as usize, serialized_message.len());
+        let mut expected_payload = serialized_message;
+        expected_payload.extend_from_slice(&VERSION_TRAILER_MAGIC);
+        expected_payload.extend_from_slice(&PROTOCOL_VERSION.to_le_bytes());
+
+        assert_eq!(prefix as usize, expected_payload.len());
         assert_eq!(
             buffer.len(),
             leading_bytes.len() + PREFIX_SIZE as usize + prefix as usize
         );
 
-        assert_eq!(&buffer[prefix_end..], &serialized_message);
+        assert_eq!(&buffer[prefix_end..], &expected_payload);
+    }
+
+    /// Test that the payload of a versioned frame can still be deserialized by peers of
+    /// version 0, which ignore the trailer, and that the version of the sender is used to
+    /// answer it.
+    #[test]
+    fn versioned_frames_are_compatible() {
+        let message = RpcMessage::VersionInfoQuery;
+        let mut buffer = BytesMut::new();
+        Codec::default()
+            .encode(message.clone(), &mut buffer)
+            .unwrap();
+
+        let payload = &buffer[PREFIX_SIZE as usize..];
+        assert_eq!(
+            bincode::deserialize::<RpcMessage>(payload).unwrap(),
+            message
+        );
+
+        let mut newer_buffer = buffer.clone();
+        let version_offset = newer_buffer.len() - 2;
+        newer_buffer[version_offset..].copy_from_slice(&(PROTOCOL_VERSION + 1).to_le_bytes());
+        let mut codec = Codec::default();
+        assert_eq!(
+            codec.decode(&mut newer_buffer).unwrap(),
+            Some(message.clone())
+        );
+        assert_eq!(codec.version(), PROTOCOL_VERSION);
+
+        let mut invalid_buffer = BytesMut::new();
+        let payload = bincode::serialize(&message).unwrap();
+        invalid_buffer.put_u32_le(payload.len() as u32 + 1);
+        invalid_buffer.extend_from_slice(&payload);
+        invalid_buffer.put_u8(0);
+        assert!(matches!(
+            Codec::default().decode(&mut invalid_buffer),
+            Err(Error::InvalidTrailer)
+        ));
+    }
+
+    /// Test that a frame announcing a payload larger than [`MAX_PAYLOAD_SIZE`] is rejected as
+    /// soon as its prefix is read, without waiting for the payload.
+    #[test]
+    fn rejects_oversized_frame_before_reading_payload() {
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(MAX_PAYLOAD_SIZE as u32 + 1);
+        buffer.extend_from_slice(&[0; 16]);
+
+        let result = Codec::default().decode(&mut buffer);
+
+        assert!(
+            matches!(result, Err(Error::MessageTooBig { size }) if size == MAX_PAYLOAD_SIZE + 1)
+        );
+        assert!(buffer.capacity() < MAX_PAYLOAD_SIZE);
+    }
+
+    /// Test that decoding frames with arbitrary payloads, as a Byzantine peer could send, never
+    /// panics.
+    #[proptest]
+    fn decodes_arbitrary_payloads_without_panicking(payload: Vec<u8>) {
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(payload.len() as u32);
+        buffer.extend_from_slice(&payload);
+
+        let _ = Codec::default().decode(&mut buffer);
+    }
+
+    /// Test that a small frame whose payload claims to contain a huge string is rejected,
+    /// without allocating memory for it.
+    #[test]
+    fn rejects_frame_with_oversized_length_in_payload() {
+        let message = RpcMessage::Error(Box::new(NodeError::ClientIoError {
+            error: "x".to_string(),
+        }));
+        let mut payload = bincode::serialize(&message).unwrap();
+        let string_start = payload
+            .windows(9)
+            .position(|window| window == [1, 0, 0, 0, 0, 0, 0, 0, b'x'])
+            .expect("the string should be serialized with its length");
+        payload[string_start..string_start + 8].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(payload.len() as u32);
+        buffer.extend_from_slice(&payload);
+
+        assert!(matches!(
+            Codec::default().decode(&mut buffer),
+            Err(Error::Deserialization(_))
+        ));
+    }
+
+    /// Test that the messages exceeding the deserialization limits of the codec are rejected.
+    #[test]
+    fn rejects_message_exceeding_the_deserialization_limits() {
+        let message = RpcMessage::Error(Box::new(NodeError::ClientIoError {
+            error: "too long".to_string(),
+        }));
+        let mut buffer = BytesMut::new();
+        Codec::default()
+            .encode(message, &mut buffer)
+            .expect("Encoding should succeed");
+
+        let limits = DeserializationLimits {
+            max_string_size: 4,
+            ..DeserializationLimits::default()
+        };
+        assert!(matches!(
+            Codec::default()
+                .with_deserialization_limits(limits)
+                .decode(&mut buffer),
+            Err(Error::LimitExceeded(LimitExceeded::StringTooLarge {
+                size: 8,
+                max: 4
+            }))
+        ));
     }
 }
@@ -7,6 +7,7 @@ mod node_provider;
 #[cfg(with_server)]
 mod server;
 mod transport;
+mod udp;
 
 pub use client::*;
 pub use codec::*;
@@ -14,3 +15,4 @@ pub use node_provider::*;
 #[cfg(with_server)]
 pub use server::*;
 pub use transport::*;
+pub use udp::*;
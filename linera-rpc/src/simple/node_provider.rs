@@ -28,7 +28,11 @@ impl LocalValidatorNodeProvider for SimpleNodeProvider {
             }
         })?;
 
-        let client = SimpleClient::new(network, self.0.send_timeout, self.0.recv_timeout);
+        let client = SimpleClient::new(
+            network,
+            self.0.timeout.send_timeout(),
+            self.0.timeout.recv_timeout(),
+        );
 
         Ok(client)
     }
@@ -1,23 +1,26 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{io, time::Duration};
+use std::{io, iter, time::Duration};
 
 use async_trait::async_trait;
-use futures::{channel::mpsc, stream::StreamExt};
+use futures::{
+    channel::mpsc,
+    stream::{self, StreamExt},
+};
 use linera_core::{
     node::NodeError,
-    worker::{NetworkActions, ValidatorWorker, WorkerError, WorkerState},
+    worker::{AdminCommand, NetworkActions, ValidatorWorker, WorkerError, WorkerState},
 };
 use linera_storage::Storage;
 use linera_views::views::ViewError;
 use rand::Rng;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, Mutex};
 use tracing::{debug, error, info, instrument, warn};
 
 use super::transport::{MessageHandler, ServerHandle, TransportProtocol};
 use crate::{
-    config::{CrossChainConfig, ShardId, ValidatorInternalNetworkPreConfig},
+    config::{CrossChainConfig, RetryPolicy, ShardId, ValidatorInternalNetworkPreConfig},
     RpcMessage,
 };
 
@@ -70,71 +73,118 @@ where
     S: Storage + Clone + Send + Sync + 'static,
     ViewError: From<S::ContextError>,
 {
+    /// Sends the queued cross-chain queries, to several shards in parallel and to each shard
+    /// in order.
     #[allow(clippy::too_many_arguments)]
     async fn forward_cross_chain_queries(
         nickname: String,
         network: ValidatorInternalNetworkPreConfig<TransportProtocol>,
-        cross_chain_max_retries: u32,
-        cross_chain_retry_delay: Duration,
+        cross_chain_retry: RetryPolicy,
         cross_chain_sender_delay: Duration,
         cross_chain_sender_failure_rate: f32,
+        cross_chain_sender_duplicate_rate: f32,
+        cross_chain_max_concurrent_tasks: usize,
         this_shard: ShardId,
-        mut receiver: mpsc::Receiver<(RpcMessage, ShardId)>,
+        receiver: mpsc::Receiver<(RpcMessage, ShardId)>,
     ) {
-        let mut pool = network
-            .protocol
-            .make_outgoing_connection_pool()
-            .await
-            .expect("Initialization should not fail");
-
-        while let Some((message, shard_id)) = receiver.next().await {
-            if cross_chain_sender_failure_rate > 0.0
-                && rand::thread_rng().gen::<f32>() < cross_chain_sender_failure_rate
-            {
-                warn!("Dropped 1 cross-message intentionally.");
-                continue;
-            }
+        // One connection pool per shard, so that a slow shard doesn't delay the others.
+        let mut pools = Vec::new();
+        for _ in &network.shards {
+            let pool = network
+                .protocol
+                .make_outgoing_connection_pool()
+                .await
+                .expect("Initialization should not fail");
+            pools.push(Mutex::new(pool));
+        }
+        let pools = &pools;
+        let network = &network;
+        let nickname = &nickname;
 
-            let shard = network.shard(shard_id);
-            let remote_address = format!("{}:{}", shard.host, shard.port);
+        receiver
+            .flat_map(|(message, shard_id)| {
+                let copies = if cross_chain_sender_failure_rate > 0.0
+                    && rand::thread_rng().gen::<f32>() < cross_chain_sender_failure_rate
+                {
+                    warn!("Dropped 1 cross-message intentionally.");
+                    0
+                } else if cross_chain_sender_duplicate_rate > 0.0
+                    && rand::thread_rng().gen::<f32>() < cross_chain_sender_duplicate_rate
+                {
+                    warn!("Duplicated 1 cross-message intentionally.");
+                    2
+                } else {
+                    1
+                };
+                stream::iter(iter::repeat((message, shard_id)).take(copies))
+            })
+            .for_each_concurrent(
+                Some(cross_chain_max_concurrent_tasks),
+                |(message, shard_id)| async move {
+                    let shard = network.shard(shard_id);
+                    // The requests for the chains of the canary of a shard are sent to the
+                    // canary.
+                    let remote_address = match message
+                        .target_chain_id()
+                        .and_then(|chain_id| shard.canary_for(chain_id))
+                    {
+                        Some(canary) => canary.address(),
+                        None => format!("{}:{}", shard.host, shard.port),
+                    };
+                    // The lock is fair, so the queries to the same shard are sent in order.
+                    let mut pool = pools[shard_id].lock().await;
+                    #[cfg(with_metrics)]
+                    let start = std::time::Instant::now();
 
-            // Send the cross-chain query and retry if needed.
-            for i in 0..cross_chain_max_retries {
-                // Delay increases linearly with the attempt number.
-                tokio::time::sleep(cross_chain_sender_delay + cross_chain_retry_delay * i).await;
+                    // Send the cross-chain query and retry if needed.
+                    for i in 0..cross_chain_retry.max_retries {
+                        tokio::time::sleep(cross_chain_sender_delay + cross_chain_retry.delay(i))
+                            .await;
 
-                let status = pool.send_message_to(message.clone(), &remote_address).await;
-                match status {
-                    Err(error) => {
-                        warn!(
-                            nickname,
-                            %error,
-                            i,
-                            from_shard = this_shard,
-                            to_shard = shard_id,
-                            "Failed to send cross-chain query",
-                        );
-                    }
-                    _ => {
-                        debug!(
-                            from_shard = this_shard,
-                            to_shard = shard_id,
-                            "Sent cross-chain query",
-                        );
-                        break;
+                        let status = pool.send_message_to(message.clone(), &remote_address).await;
+                        match status {
+                            Err(error) => {
+                                warn!(
+                                    nickname,
+                                    %error,
+                                    i,
+                                    from_shard = this_shard,
+                                    to_shard = shard_id,
+                                    "Failed to send cross-chain query",
+                                );
+                            }
+                            _ => {
+                                debug!(
+                                    from_shard = this_shard,
+                                    to_shard = shard_id,
+                                    "Sent cross-chain query",
+                                );
+                                #[cfg(with_metrics)]
+                                crate::CROSS_CHAIN_DELIVERY_LATENCY
+                                    .with_label_values(&[&shard_id.to_string()])
+                                    .observe(start.elapsed().as_secs_f64() * 1000.0);
+                                return;
+                            }
+                        }
                     }
-                }
-                error!(
-                    nickname,
-                    from_shard = this_shard,
-                    to_shard = shard_id,
-                    "Dropping cross-chain query",
-                );
-            }
-        }
+                    error!(
+                        nickname,
+                        from_shard = this_shard,
+                        to_shard = shard_id,
+                        "Dropping cross-chain query",
+                    );
+                },
+            )
+            .await;
     }
 
-    pub async fn spawn(self) -> Result<ServerHandle, io::Error> {
+    /// Starts listening for requests. The commands of the operators received from
+    /// `admin_commands`, if any, are executed by the worker, and the resulting cross-chain
+    /// requests are sent.
+    pub async fn spawn(
+        self,
+        admin_commands: Option<mpsc::UnboundedReceiver<AdminCommand>>,
+    ) -> Result<ServerHandle, io::Error> {
         info!(
             "Listening to {:?} traffic on {}:{}",
             self.network.protocol, self.host, self.port
@@ -147,19 +197,24 @@ where
         tokio::spawn(Self::forward_cross_chain_queries(
             self.state.nickname().to_string(),
             self.network.clone(),
-            self.cross_chain_config.max_retries,
-            Duration::from_millis(self.cross_chain_config.retry_delay_ms),
+            self.cross_chain_config.retry_policy(),
             Duration::from_millis(self.cross_chain_config.sender_delay_ms),
             self.cross_chain_config.sender_failure_rate,
+            self.cross_chain_config.sender_duplicate_rate,
+            self.cross_chain_config.max_concurrent_tasks,
             self.shard_id,
             cross_chain_receiver,
         ));
 
         let protocol = self.network.protocol;
-        let state = RunningServerState {
+        let mut state = RunningServerState {
             server: self,
             cross_chain_sender,
         };
+        state.replay_write_ahead_log().await;
+        if let Some(commands) = admin_commands {
+            tokio::spawn(state.clone().handle_admin_commands(commands));
+        }
         // Launch server for the appropriate protocol.
         protocol.spawn_server(&address, state).await
     }
@@ -287,10 +342,34 @@ where
 
             RpcMessage::VersionInfoQuery => Ok(Some(linera_version::VersionInfo::default().into())),
 
+            RpcMessage::BlockSimulation(block) => {
+                match self.server.state.simulate_block_proposal(*block).await {
+                    Ok(executed_block) => Ok(Some(executed_block.into())),
+                    Err(error) => {
+                        debug!(nickname = self.server.state.nickname(), %error, "Failed to simulate block proposal");
+                        Err(error.into())
+                    }
+                }
+            }
+
+            RpcMessage::DownloadBlob(request) => {
+                match self.server.state.download_blob(request.blob_id).await {
+                    Ok(blob) => Ok(Some(blob.into())),
+                    Err(error) => {
+                        debug!(nickname = self.server.state.nickname(), %error, "Failed to download blob");
+                        Err(error.into())
+                    }
+                }
+            }
+
             RpcMessage::Vote(_)
             | RpcMessage::Error(_)
             | RpcMessage::ChainInfoResponse(_)
-            | RpcMessage::VersionInfoResponse(_) => Err(NodeError::UnexpectedMessage),
+            | RpcMessage::VersionInfoResponse(_)
+            | RpcMessage::BlockSimulationResponse(_)
+            | RpcMessage::GenesisConfigHashQuery
+            | RpcMessage::GenesisConfigHashResponse(_)
+            | RpcMessage::DownloadBlobResponse(_) => Err(NodeError::UnexpectedMessage),
         };
 
         self.server.packets_processed += 1;
@@ -323,8 +402,50 @@ where
 
 impl<S> RunningServerState<S>
 where
-    S: Send,
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
 {
+    /// Resumes the work that the write-ahead log records for the chains of this shard, e.g.
+    /// after a crash, and sends the pending cross-chain requests.
+    async fn replay_write_ahead_log(&mut self) {
+        let network = &self.server.network;
+        let shard_id = self.server.shard_id;
+        let result = self
+            .server
+            .state
+            .clone()
+            .replay_write_ahead_log(|chain_id| network.get_shard_id(chain_id) == shard_id)
+            .await;
+        match result {
+            Ok(actions) => self.handle_network_actions(actions),
+            Err(error) => error!(
+                nickname = self.server.state.nickname(),
+                %error,
+                "Failed to replay the write-ahead log"
+            ),
+        }
+    }
+
+    /// Executes the commands of the operators, and sends the resulting cross-chain requests.
+    async fn handle_admin_commands(mut self, mut commands: mpsc::UnboundedReceiver<AdminCommand>) {
+        while let Some(command) = commands.next().await {
+            info!(
+                nickname = self.server.state.nickname(),
+                ?command,
+                "Executing a command of the operators"
+            );
+            match self.server.state.handle_admin_command(command).await {
+                Ok(actions) => self.handle_network_actions(actions),
+                Err(error) => error!(
+                    nickname = self.server.state.nickname(),
+                    %error,
+                    ?command,
+                    "Failed to execute a command of the operators"
+                ),
+            }
+        }
+    }
+
     fn handle_network_actions(&mut self, actions: NetworkActions) {
         for request in actions.cross_chain_requests {
             let shard_id = self.server.network.get_shard_id(request.target_chain_id());
@@ -19,7 +19,11 @@ use tokio_util::{codec::Framed, udp::UdpFramed};
 use tracing::{error, warn};
 
 use crate::{
-    simple::{codec, codec::Codec},
+    simple::{
+        codec,
+        codec::Codec,
+        udp::{Fragmenter, UdpCodec, MAX_DATAGRAM_SIZE},
+    },
     RpcMessage,
 };
 
@@ -122,16 +126,26 @@ impl TransportProtocol {
         let stream: futures::future::Either<_, _> = match self {
             TransportProtocol::Udp => {
                 let socket = UdpSocket::bind(&"0.0.0.0:0").await?;
-
-                UdpFramed::new(socket, Codec)
-                    .with(move |message| future::ready(Ok((message, address))))
+                let (codec, fragmenter) = UdpCodec::new(MAX_DATAGRAM_SIZE);
+
+                UdpFramed::new(socket, codec)
+                    .with_flat_map(move |message| {
+                        let datagrams = match fragmenter.datagrams(message) {
+                            Ok(datagrams) => datagrams
+                                .into_iter()
+                                .map(|datagram| Ok((datagram, address)))
+                                .collect(),
+                            Err(error) => vec![Err(error)],
+                        };
+                        stream::iter(datagrams)
+                    })
                     .map_ok(|(message, _address)| message)
                     .left_stream()
             }
             TransportProtocol::Tcp => {
                 let stream = TcpStream::connect(address).await?;
 
-                Framed::new(stream, Codec).right_stream()
+                Framed::new(stream, Codec::default()).right_stream()
             }
         };
 
@@ -175,14 +189,19 @@ impl TransportProtocol {
 
 /// An implementation of [`ConnectionPool`] based on UDP.
 struct UdpConnectionPool {
-    transport: UdpFramed<Codec>,
+    transport: UdpFramed<UdpCodec>,
+    fragmenter: Fragmenter,
 }
 
 impl UdpConnectionPool {
     async fn new() -> Result<Self, std::io::Error> {
         let socket = UdpSocket::bind(&"0.0.0.0:0").await?;
-        let transport = UdpFramed::new(socket, Codec);
-        Ok(Self { transport })
+        let (codec, fragmenter) = UdpCodec::new(MAX_DATAGRAM_SIZE);
+        let transport = UdpFramed::new(socket, codec);
+        Ok(Self {
+            transport,
+            fragmenter,
+        })
     }
 }
 
@@ -196,7 +215,9 @@ impl ConnectionPool for UdpConnectionPool {
             let address = address
                 .parse()
                 .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))?;
-            self.transport.send((message, address)).await
+            self.fragmenter
+                .send(&mut self.transport, message, address)
+                .await
         })
     }
 }
@@ -211,7 +232,8 @@ impl TransportProtocol {
     where
         S: MessageHandler + Send + 'static,
     {
-        let (udp_sink, udp_stream) = UdpFramed::new(socket, Codec).split();
+        let (codec, fragmenter) = UdpCodec::new(MAX_DATAGRAM_SIZE);
+        let (udp_sink, udp_stream) = UdpFramed::new(socket, codec).split();
         let mut udp_stream = Abortable::new(udp_stream, registration);
         let udp_sink = Arc::new(Mutex::new(udp_sink));
         // Track the latest tasks for a given peer. This is used to return answers in the
@@ -231,6 +253,7 @@ impl TransportProtocol {
             let previous_task = previous_tasks.remove(&peer);
             let mut state = state.clone();
             let udp_sink = udp_sink.clone();
+            let fragmenter = fragmenter.clone();
             let new_task = tokio::spawn(async move {
                 if let Some(reply) = state.handle_message(message).await {
                     if let Some(task) = previous_task {
@@ -238,7 +261,8 @@ impl TransportProtocol {
                             warn!("Previous task cannot be joined: {}", error);
                         }
                     }
-                    let status = udp_sink.lock().await.send((reply, peer)).await;
+                    let mut udp_sink = udp_sink.lock().await;
+                    let status = fragmenter.send(&mut *udp_sink, reply, peer).await;
                     if let Err(error) = status {
                         error!("Failed to send query response: {}", error);
                     }
@@ -273,7 +297,7 @@ impl TcpConnectionPool {
             match TcpStream::connect(address).await {
                 Ok(s) => {
                     self.streams
-                        .insert(address.to_string(), Framed::new(s, Codec));
+                        .insert(address.to_string(), Framed::new(s, Codec::default()));
                 }
                 Err(error) => {
                     error!("Failed to open connection to {}: {}", address, error);
@@ -321,7 +345,7 @@ impl TransportProtocol {
             let socket = value?;
             let mut handler = state.clone();
             tokio::spawn(async move {
-                let mut transport = Framed::new(socket, Codec);
+                let mut transport = Framed::new(socket, Codec::default());
                 while let Some(maybe_message) = transport.next().await {
                     let message = match maybe_message {
                         Ok(message) => message,
@@ -0,0 +1,377 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fragmentation of the frames sent over UDP.
+//!
+//! A frame that doesn't fit in a datagram is split into fragments, each sent in its own
+//! datagram after a header: [`FRAGMENT_MARKER`], the random ID of the frame, the index of the
+//! fragment and the number of fragments, all little-endian. Frames that fit in a datagram are
+//! sent unchanged, so that peers not supporting fragmentation still receive them. The marker is
+//! larger than any frame size, so these peers reject fragments instead of misreading them.
+//!
+//! The receiver reassembles the fragments, and drops the frames that are still incomplete
+//! after [`REASSEMBLY_TIMEOUT`]. Datagrams are not acknowledged: UDP clients retransmit their
+//! requests when the response doesn't arrive in time.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::{Sink, SinkExt};
+use tokio_util::codec::{Decoder, Encoder};
+use tracing::debug;
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::IntCounterVec,
+};
+
+use super::codec::{Codec, Error, MAX_PAYLOAD_SIZE};
+use crate::{version::PROTOCOL_VERSION, RpcMessage};
+
+/// The largest payload of a UDP datagram over IPv4.
+pub const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// The first four bytes of the datagrams carrying a fragment.
+const FRAGMENT_MARKER: u32 = u32::MAX;
+
+/// The size of the header of the datagrams carrying a fragment.
+const FRAGMENT_HEADER_SIZE: usize = 4 + 8 + 2 + 2;
+
+/// How long the fragments of an incomplete frame are kept.
+const REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The maximum number of frames being reassembled at the same time.
+const MAX_INCOMPLETE_FRAMES: usize = 64;
+
+/// The maximum number of bytes kept for the frames being reassembled.
+const MAX_INCOMPLETE_BYTES: usize = 4 * MAX_PAYLOAD_SIZE;
+
+#[cfg(with_metrics)]
+static UDP_DROPPED_FRAMES: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "udp_dropped_frames",
+        "Frames received over UDP that were dropped before all their fragments arrived",
+        &["reason"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+fn record_dropped_frame(reason: &str) {
+    #![allow(unused_variables)]
+    #[cfg(with_metrics)]
+    UDP_DROPPED_FRAMES.with_label_values(&[reason]).inc();
+}
+
+/// Splits the frames to send over UDP into datagrams.
+#[derive(Clone, Debug)]
+pub struct Fragmenter {
+    max_datagram_size: usize,
+    /// The version of the frames sent, negotiated by the [`UdpCodec`] receiving the answers.
+    version: Arc<AtomicU16>,
+}
+
+impl Fragmenter {
+    /// Returns the datagrams to send the message in.
+    pub fn datagrams(&self, message: RpcMessage) -> Result<Vec<Bytes>, Error> {
+        let mut frame = BytesMut::new();
+        Codec::with_version(self.version.load(Ordering::Relaxed)).encode(message, &mut frame)?;
+        if frame.len() <= self.max_datagram_size {
+            return Ok(vec![frame.freeze()]);
+        }
+        let chunk_size = self.max_datagram_size - FRAGMENT_HEADER_SIZE;
+        let count = u16::try_from(frame.len().div_ceil(chunk_size))
+            .map_err(|_| Error::MessageTooBig { size: frame.len() })?;
+        let id = rand::random::<u64>();
+        let datagrams = frame
+            .chunks(chunk_size)
+            .zip(0..count)
+            .map(|(chunk, index)| {
+                let mut datagram = BytesMut::with_capacity(FRAGMENT_HEADER_SIZE + chunk.len());
+                datagram.put_u32_le(FRAGMENT_MARKER);
+                datagram.put_u64_le(id);
+                datagram.put_u16_le(index);
+                datagram.put_u16_le(count);
+                datagram.put_slice(chunk);
+                datagram.freeze()
+            })
+            .collect();
+        Ok(datagrams)
+    }
+
+    /// Sends the message to `address` through a sink of datagrams.
+    pub async fn send<S>(
+        &self,
+        sink: &mut S,
+        message: RpcMessage,
+        address: SocketAddr,
+    ) -> Result<(), Error>
+    where
+        S: Sink<(Bytes, SocketAddr), Error = Error> + Unpin,
+    {
+        for datagram in self.datagrams(message)? {
+            sink.feed((datagram, address)).await?;
+        }
+        sink.flush().await
+    }
+}
+
+/// The fragments received so far of a frame.
+struct IncompleteFrame {
+    count: u16,
+    fragments: BTreeMap<u16, Bytes>,
+    size: usize,
+    started: Instant,
+}
+
+/// A codec for UDP datagrams, reassembling the frames received in several fragments. The
+/// datagrams to send are produced by its [`Fragmenter`].
+pub struct UdpCodec {
+    codec: Codec,
+    version: Arc<AtomicU16>,
+    incomplete: HashMap<u64, IncompleteFrame>,
+    incomplete_bytes: usize,
+}
+
+impl UdpCodec {
+    /// Creates a codec and the fragmenter for the datagrams to send, splitting the frames
+    /// larger than `max_datagram_size`.
+    pub fn new(max_datagram_size: usize) -> (Self, Fragmenter) {
+        assert!(max_datagram_size > FRAGMENT_HEADER_SIZE);
+        let version = Arc::new(AtomicU16::new(PROTOCOL_VERSION));
+        let codec = UdpCodec {
+            codec: Codec::default(),
+            version: version.clone(),
+            incomplete: HashMap::new(),
+            incomplete_bytes: 0,
+        };
+        let fragmenter = Fragmenter {
+            max_datagram_size,
+            version,
+        };
+        (codec, fragmenter)
+    }
+
+    /// Decodes a complete frame, and records the version of its sender.
+    fn decode_frame(&mut self, frame: &mut BytesMut) -> Result<Option<RpcMessage>, Error> {
+        let message = self.codec.decode(frame)?;
+        self.version.store(self.codec.version(), Ordering::Relaxed);
+        Ok(message)
+    }
+
+    /// Adds a fragment, and returns the frame if it is now complete.
+    fn add_fragment(
+        &mut self,
+        id: u64,
+        index: u16,
+        count: u16,
+        fragment: Bytes,
+    ) -> Result<Option<BytesMut>, Error> {
+        if count < 2 || index >= count {
+            return Err(Error::InvalidFragment);
+        }
+        self.remove_expired_frames();
+        let frame = self
+            .incomplete
+            .entry(id)
+            .or_insert_with(|| IncompleteFrame {
+                count,
+                fragments: BTreeMap::new(),
+                size: 0,
+                started: Instant::now(),
+            });
+        if frame.count != count {
+            return Err(Error::InvalidFragment);
+        }
+        if frame.fragments.contains_key(&index) {
+            // A duplicate, e.g. of a retransmitted request.
+            return Ok(None);
+        }
+        frame.size += fragment.len();
+        self.incomplete_bytes += fragment.len();
+        frame.fragments.insert(index, fragment);
+        // The frame also contains the prefix with its size.
+        if frame.size > MAX_PAYLOAD_SIZE + mem::size_of::<u32>() {
+            let size = frame.size;
+            self.remove_frame(id);
+            return Err(Error::MessageTooBig { size });
+        }
+        if frame.fragments.len() < usize::from(count) {
+            self.evict_oldest_frames();
+            return Ok(None);
+        }
+        let frame = self
+            .remove_frame(id)
+            .expect("the frame should be incomplete");
+        let mut bytes = BytesMut::with_capacity(frame.size);
+        for fragment in frame.fragments.into_values() {
+            bytes.put(fragment);
+        }
+        Ok(Some(bytes))
+    }
+
+    fn remove_frame(&mut self, id: u64) -> Option<IncompleteFrame> {
+        let frame = self.incomplete.remove(&id)?;
+        self.incomplete_bytes -= frame.size;
+        Some(frame)
+    }
+
+    fn remove_expired_frames(&mut self) {
+        let now = Instant::now();
+        let expired = self
+            .incomplete
+            .iter()
+            .filter(|(_, frame)| now.duration_since(frame.started) > REASSEMBLY_TIMEOUT)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in expired {
+            debug!(
+                id,
+                "Dropping a frame whose fragments didn't all arrive in time"
+            );
+            record_dropped_frame("expired");
+            self.remove_frame(id);
+        }
+    }
+
+    fn evict_oldest_frames(&mut self) {
+        while self.incomplete.len() > MAX_INCOMPLETE_FRAMES
+            || self.incomplete_bytes > MAX_INCOMPLETE_BYTES
+        {
+            let Some(id) = self
+                .incomplete
+                .iter()
+                .min_by_key(|(_, frame)| frame.started)
+                .map(|(id, _)| *id)
+            else {
+                break;
+            };
+            debug!(id, "Dropping an incomplete frame to make room for others");
+            record_dropped_frame("evicted");
+            self.remove_frame(id);
+        }
+    }
+}
+
+impl Encoder<Bytes> for UdpCodec {
+    type Error = Error;
+
+    fn encode(&mut self, datagram: Bytes, buffer: &mut BytesMut) -> Result<(), Self::Error> {
+        buffer.extend_from_slice(&datagram);
+        Ok(())
+    }
+}
+
+impl Decoder for UdpCodec {
+    type Item = RpcMessage;
+    type Error = Error;
+
+    /// Decodes a datagram, which is either a whole frame or a fragment of one.
+    fn decode(&mut self, buffer: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if buffer.len() < FRAGMENT_HEADER_SIZE || buffer[..4] != FRAGMENT_MARKER.to_le_bytes() {
+            return self.decode_frame(buffer);
+        }
+        let mut datagram = buffer.split();
+        datagram.advance(4);
+        let id = datagram.get_u64_le();
+        let index = datagram.get_u16_le();
+        let count = datagram.get_u16_le();
+        match self.add_fragment(id, index, count, datagram.freeze())? {
+            Some(mut frame) => self.decode_frame(&mut frame),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use linera_base::identifiers::ChainId;
+    use linera_core::data_types::ChainInfoQuery;
+    use test_strategy::proptest;
+    use tokio_util::codec::Decoder;
+
+    use super::{UdpCodec, FRAGMENT_HEADER_SIZE};
+    use crate::{simple::codec::Error, RpcMessage};
+
+    fn decode_datagrams(
+        codec: &mut UdpCodec,
+        datagrams: impl IntoIterator<Item = bytes::Bytes>,
+    ) -> Vec<RpcMessage> {
+        datagrams
+            .into_iter()
+            .filter_map(|datagram| {
+                codec
+                    .decode_eof(&mut BytesMut::from(&datagram[..]))
+                    .expect("datagrams should be valid")
+            })
+            .collect()
+    }
+
+    /// Test that frames are fragmented when they don't fit in a datagram, and reassembled
+    /// regardless of the order and duplicates of their fragments.
+    #[proptest]
+    fn reassembles_fragmented_frames(
+        message_contents: ChainInfoQuery,
+        #[strategy(FRAGMENT_HEADER_SIZE + 1..FRAGMENT_HEADER_SIZE + 16)] max_datagram_size: usize,
+        reverse: bool,
+    ) {
+        let message = RpcMessage::from(message_contents);
+        let (mut codec, fragmenter) = UdpCodec::new(max_datagram_size);
+        let mut datagrams = fragmenter.datagrams(message.clone()).unwrap();
+        assert!(datagrams
+            .iter()
+            .all(|datagram| datagram.len() <= max_datagram_size));
+        if reverse {
+            datagrams.reverse();
+        }
+        let duplicate = datagrams[0].clone();
+        datagrams.insert(1, duplicate);
+
+        assert_eq!(decode_datagrams(&mut codec, datagrams), vec![message]);
+        assert!(codec.incomplete.is_empty());
+    }
+
+    /// Test that frames fitting in a datagram are sent unchanged.
+    #[test]
+    fn small_frames_are_not_fragmented() {
+        let (mut codec, fragmenter) = UdpCodec::new(super::MAX_DATAGRAM_SIZE);
+        let message = RpcMessage::VersionInfoQuery;
+        let datagrams = fragmenter.datagrams(message.clone()).unwrap();
+        assert_eq!(datagrams.len(), 1);
+
+        let mut frame = BytesMut::from(&datagrams[0][..]);
+        assert_eq!(
+            super::Codec::default().decode(&mut frame).unwrap(),
+            Some(message.clone())
+        );
+        assert_eq!(decode_datagrams(&mut codec, datagrams), vec![message]);
+    }
+
+    /// Test that fragments inconsistent with the others of their frame are rejected.
+    #[test]
+    fn rejects_inconsistent_fragments() {
+        let (mut codec, fragmenter) = UdpCodec::new(FRAGMENT_HEADER_SIZE + 4);
+        let message = RpcMessage::from(ChainInfoQuery::new(ChainId::root(0)));
+        let datagrams = fragmenter.datagrams(message).unwrap();
+        assert!(datagrams.len() > 2);
+
+        let mut first = BytesMut::from(&datagrams[0][..]);
+        assert!(matches!(codec.decode(&mut first), Ok(None)));
+        // The same frame ID, with a different number of fragments.
+        let mut other = BytesMut::from(&datagrams[1][..]);
+        other[14] ^= 1;
+        assert!(matches!(
+            codec.decode(&mut other),
+            Err(Error::InvalidFragment)
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Versions of the wire format of the RPC protocols.
+//!
+//! Every gRPC request and response carries the version of its sender in the
+//! [`PROTOCOL_VERSION_HEADER`], and every frame of the simple transport in a trailer after its
+//! payload. Peers answer in the lowest of their version and the version of the request, so
+//! that validators and clients on adjacent releases can talk to each other during a rolling
+//! upgrade.
+//!
+//! Version 0 is the unversioned format of earlier releases: requests without the header and
+//! frames without the trailer are decoded as such. Older peers ignore the trailing bytes of a
+//! frame, so they still decode the frames of version 1.
+//!
+//! Since version 2, the gRPC proposals and certificates can carry their content as protobuf
+//! messages instead of bincode-encoded bytes. Clients only send them so once the validator
+//! answered with version 2 or later.
+
+use thiserror::Error;
+
+/// The version of the wire format used by this release.
+pub const PROTOCOL_VERSION: u16 = 2;
+
+/// The oldest version of the wire format this release can still decode.
+pub const MIN_SUPPORTED_PROTOCOL_VERSION: u16 = 0;
+
+/// The first version of the wire format in which the content of gRPC proposals and
+/// certificates can be protobuf messages.
+pub const NATIVE_CONTENT_VERSION: u16 = 2;
+
+/// The header in which gRPC requests and responses carry the version of their sender.
+pub const PROTOCOL_VERSION_HEADER: &str = "linera-protocol-version";
+
+/// A peer uses a version of the wire format this release can't decode anymore.
+#[derive(Clone, Copy, Debug, Error, Eq, PartialEq)]
+#[error(
+    "protocol version {version} is not supported: the oldest supported version is {min}",
+    min = MIN_SUPPORTED_PROTOCOL_VERSION
+)]
+pub struct UnsupportedProtocolVersion {
+    pub version: u16,
+}
+
+/// Checks that messages of a peer using `version` can be decoded.
+///
+/// Newer versions are accepted: the newer peer is responsible for speaking the version of its
+/// older peers, once it knows it.
+pub fn check_protocol_version(version: u16) -> Result<(), UnsupportedProtocolVersion> {
+    if !(MIN_SUPPORTED_PROTOCOL_VERSION..).contains(&version) {
+        return Err(UnsupportedProtocolVersion { version });
+    }
+    Ok(())
+}
+
+/// Returns the version to use when answering a peer using `version`.
+pub fn negotiate_protocol_version(version: u16) -> u16 {
+    version.min(PROTOCOL_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_protocol_version() {
+        assert_eq!(negotiate_protocol_version(0), 0);
+        assert_eq!(
+            negotiate_protocol_version(PROTOCOL_VERSION),
+            PROTOCOL_VERSION
+        );
+        assert_eq!(
+            negotiate_protocol_version(PROTOCOL_VERSION + 1),
+            PROTOCOL_VERSION
+        );
+        assert!(check_protocol_version(PROTOCOL_VERSION + 1).is_ok());
+        assert!(check_protocol_version(MIN_SUPPORTED_PROTOCOL_VERSION).is_ok());
+    }
+}
@@ -2,58 +2,21 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_base::{
-    data_types::{OracleResponse, Round},
-    identifiers::{ChainDescription, Destination, GenericApplicationId},
-    ownership::ChainOwnership,
-};
-use linera_chain::{
-    data_types::{CertificateValue, HashedCertificateValue, Medium, MessageAction},
-    manager::ChainManagerInfo,
-};
-use linera_core::{data_types::CrossChainRequest, node::NodeError};
-use linera_execution::{
-    system::{AdminOperation, Recipient, SystemChannel, SystemMessage, SystemOperation},
-    Message, MessageKind, Operation,
-};
-use linera_rpc::RpcMessage;
-use serde_reflection::{Registry, Result, Samples, Tracer, TracerConfig};
+use linera_rpc::schema::{self, SchemaFormat};
+use serde_reflection::Registry;
 
-fn get_registry() -> Result<Registry> {
-    let mut tracer = Tracer::new(
-        TracerConfig::default()
-            .record_samples_for_newtype_structs(true)
-            .record_samples_for_tuple_structs(true),
-    );
-    let samples = Samples::new();
-    // 1. Record samples for types with custom deserializers.
-    // 2. Trace the main entry point(s) + every enum separately.
-    tracer.trace_type::<Round>(&samples)?;
-    tracer.trace_type::<OracleResponse>(&samples)?;
-    tracer.trace_type::<Recipient>(&samples)?;
-    tracer.trace_type::<SystemChannel>(&samples)?;
-    tracer.trace_type::<SystemOperation>(&samples)?;
-    tracer.trace_type::<AdminOperation>(&samples)?;
-    tracer.trace_type::<SystemMessage>(&samples)?;
-    tracer.trace_type::<Operation>(&samples)?;
-    tracer.trace_type::<Message>(&samples)?;
-    tracer.trace_type::<MessageAction>(&samples)?;
-    tracer.trace_type::<MessageKind>(&samples)?;
-    tracer.trace_type::<HashedCertificateValue>(&samples)?;
-    tracer.trace_type::<CertificateValue>(&samples)?;
-    tracer.trace_type::<Medium>(&samples)?;
-    tracer.trace_type::<Destination>(&samples)?;
-    tracer.trace_type::<ChainDescription>(&samples)?;
-    tracer.trace_type::<ChainOwnership>(&samples)?;
-    tracer.trace_type::<GenericApplicationId>(&samples)?;
-    tracer.trace_type::<ChainManagerInfo>(&samples)?;
-    tracer.trace_type::<CrossChainRequest>(&samples)?;
-    tracer.trace_type::<NodeError>(&samples)?;
-    tracer.trace_type::<RpcMessage>(&samples)?;
-    tracer.registry()
+/// The snapshot is the published description of the wire format: if this test fails, check
+/// that the change of the format is intended, then update the snapshot with `cargo insta`.
+#[test]
+fn test_format() {
+    insta::assert_yaml_snapshot!("format.yaml", schema::registry().unwrap());
 }
 
 #[test]
-fn test_format() {
-    insta::assert_yaml_snapshot!("format.yaml", get_registry().unwrap());
+fn test_exported_formats_describe_the_same_registry() {
+    let registry = schema::registry().unwrap();
+    let yaml = schema::export(SchemaFormat::Yaml).unwrap();
+    let json = schema::export(SchemaFormat::Json).unwrap();
+    assert_eq!(serde_yaml::from_str::<Registry>(&yaml).unwrap(), registry);
+    assert_eq!(serde_json::from_str::<Registry>(&json).unwrap(), registry);
 }
@@ -9,8 +9,6 @@ wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
 #[ignore]
 // this test currently must be run manually, as it requires a Linera proxy to be running on 127.0.0.1:9000.
 async fn client() {
-    use std::time::Duration;
-
     use linera_core::node::LocalValidatorNode as _;
     use linera_rpc::config::*;
 
@@ -21,10 +19,15 @@ async fn client() {
     };
 
     let node_options = linera_rpc::node_provider::NodeOptions {
-        send_timeout: Duration::from_millis(100),
-        recv_timeout: Duration::from_millis(100),
-        notification_retry_delay: Duration::from_millis(100),
-        notification_retries: 5,
+        timeout: TimeoutPolicy {
+            send_timeout_ms: 100,
+            recv_timeout_ms: 100,
+        },
+        notification_retry: RetryPolicy {
+            max_retries: 5,
+            retry_delay_ms: 100,
+        },
+        direct_to_shards: false,
     };
 
     let _ = linera_rpc::grpc::GrpcClient::new(network_config, node_options)
@@ -0,0 +1,180 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Derivation of the `ApplicationMessage` trait for the message enums of applications.
+
+use proc_macro2::{Ident, Span};
+use syn::{
+    Attribute, Fields, ItemEnum, LitStr,
+    __private::{quote::quote, TokenStream2},
+};
+
+/// The options of how a kind of message is sent, set with `#[message(...)]` attributes.
+#[derive(Clone, Copy, Default)]
+struct MessageOptions {
+    tracked: bool,
+    authenticated: bool,
+    direct: bool,
+}
+
+impl MessageOptions {
+    /// Adds the options set by the `#[message(...)]` attributes in `attributes`.
+    fn parse(mut self, attributes: &[Attribute]) -> syn::Result<Self> {
+        for attribute in attributes {
+            if !attribute.path().is_ident("message") {
+                continue;
+            }
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tracked") {
+                    self.tracked = true;
+                } else if meta.path.is_ident("authenticated") {
+                    self.authenticated = true;
+                } else if meta.path.is_ident("direct") {
+                    self.direct = true;
+                } else {
+                    return Err(meta
+                        .error("expected `tracked`, `authenticated` or `direct` message option"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(self)
+    }
+}
+
+pub fn generate_application_message_code(input: ItemEnum, crate_root: &str) -> TokenStream2 {
+    match try_generate_application_message_code(input, crate_root) {
+        Ok(code) => code,
+        Err(error) => error.to_compile_error(),
+    }
+}
+
+fn try_generate_application_message_code(
+    input: ItemEnum,
+    crate_root: &str,
+) -> syn::Result<TokenStream2> {
+    let crate_root = Ident::new(crate_root, Span::call_site());
+    let enum_name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let defaults = MessageOptions::default().parse(&input.attrs)?;
+
+    let mut kinds = vec![];
+    let mut tracked = vec![];
+    let mut authenticated = vec![];
+    let mut direct = vec![];
+    for variant in &input.variants {
+        let variant_name = &variant.ident;
+        let pattern = match variant.fields {
+            Fields::Named(_) => quote! { #enum_name::#variant_name { .. } },
+            Fields::Unnamed(_) => quote! { #enum_name::#variant_name(..) },
+            Fields::Unit => quote! { #enum_name::#variant_name },
+        };
+        let kind = LitStr::new(&variant_name.to_string(), variant_name.span());
+        let options = defaults.parse(&variant.attrs)?;
+        let (is_tracked, is_authenticated, is_direct) =
+            (options.tracked, options.authenticated, options.direct);
+        kinds.push(quote! { #pattern => #kind });
+        tracked.push(quote! { #pattern => #is_tracked });
+        authenticated.push(quote! { #pattern => #is_authenticated });
+        direct.push(quote! { #pattern => #is_direct });
+    }
+
+    Ok(quote! {
+        impl #impl_generics #crate_root::ApplicationMessage for #enum_name #type_generics
+        #where_clause
+        {
+            fn kind(&self) -> &'static str {
+                match *self { #(#kinds,)* }
+            }
+
+            fn is_tracked(&self) -> bool {
+                match *self { #(#tracked,)* }
+            }
+
+            fn is_authenticated(&self) -> bool {
+                match *self { #(#authenticated,)* }
+            }
+
+            fn is_direct(&self) -> bool {
+                match *self { #(#direct,)* }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, ItemEnum, __private::quote::quote};
+
+    use super::generate_application_message_code;
+    use crate::tests::assert_eq_no_whitespace;
+
+    #[test]
+    fn test_derive_application_message() {
+        let message: ItemEnum = parse_quote! {
+            #[message(authenticated)]
+            enum SomeMessage {
+                #[message(tracked)]
+                Credit { amount: u64 },
+                #[message(direct)]
+                Withdraw(u64),
+                Ping,
+            }
+        };
+
+        let output = generate_application_message_code(message, "linera_sdk");
+
+        let expected = quote! {
+            impl linera_sdk::ApplicationMessage for SomeMessage {
+                fn kind(&self) -> &'static str {
+                    match *self {
+                        SomeMessage::Credit { .. } => "Credit",
+                        SomeMessage::Withdraw(..) => "Withdraw",
+                        SomeMessage::Ping => "Ping",
+                    }
+                }
+
+                fn is_tracked(&self) -> bool {
+                    match *self {
+                        SomeMessage::Credit { .. } => true,
+                        SomeMessage::Withdraw(..) => false,
+                        SomeMessage::Ping => false,
+                    }
+                }
+
+                fn is_authenticated(&self) -> bool {
+                    match *self {
+                        SomeMessage::Credit { .. } => true,
+                        SomeMessage::Withdraw(..) => true,
+                        SomeMessage::Ping => true,
+                    }
+                }
+
+                fn is_direct(&self) -> bool {
+                    match *self {
+                        SomeMessage::Credit { .. } => false,
+                        SomeMessage::Withdraw(..) => true,
+                        SomeMessage::Ping => false,
+                    }
+                }
+            }
+        };
+
+        assert_eq_no_whitespace(output.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_reject_unknown_message_option() {
+        let message: ItemEnum = parse_quote! {
+            enum SomeMessage {
+                #[message(urgent)]
+                Ping,
+            }
+        };
+
+        let output = generate_application_message_code(message, "linera_sdk").to_string();
+
+        assert!(output.contains("compile_error"));
+        assert!(output.contains("expected `tracked`, `authenticated` or `direct` message option"));
+    }
+}
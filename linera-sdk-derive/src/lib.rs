@@ -3,6 +3,7 @@
 
 //! The procedural macros for the crate `linera-sdk`.
 
+mod application_message;
 mod utils;
 
 use proc_macro::TokenStream;
@@ -12,7 +13,10 @@ use syn::{
     __private::{quote::quote, TokenStream2},
 };
 
-use crate::utils::{concat, snakify};
+use crate::{
+    application_message::generate_application_message_code,
+    utils::{concat, snakify},
+};
 
 #[proc_macro_derive(GraphQLMutationRoot)]
 pub fn derive_mutation_root(input: TokenStream) -> TokenStream {
@@ -26,6 +30,15 @@ pub fn derive_mutation_root_in_crate(input: TokenStream) -> TokenStream {
     generate_mutation_root_code(input, "crate").into()
 }
 
+/// Implements `ApplicationMessage` for an enum of messages. The variants are sent with the
+/// options of their `#[message(...)]` attributes, and of the attribute of the enum if any:
+/// `tracked`, `authenticated` and `direct`.
+#[proc_macro_derive(ApplicationMessage, attributes(message))]
+pub fn derive_application_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as ItemEnum);
+    generate_application_message_code(input, "linera_sdk").into()
+}
+
 fn generate_mutation_root_code(input: ItemEnum, crate_root: &str) -> TokenStream2 {
     let crate_root = Ident::new(crate_root, Span::call_site());
     let enum_name = input.ident;
@@ -107,7 +120,7 @@ pub mod tests {
 
     use crate::generate_mutation_root_code;
 
-    fn assert_eq_no_whitespace(mut actual: String, mut expected: String) {
+    pub(crate) fn assert_eq_no_whitespace(mut actual: String, mut expected: String) {
         // Intentionally left here for debugging purposes
         println!("{}", actual);
 
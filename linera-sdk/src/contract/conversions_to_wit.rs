@@ -5,9 +5,10 @@
 
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Amount, BlockHeight, Resources, SendMessageRequest},
+    data_types::{Amount, BlockHeight, Resources, SendMessageRequest, Timestamp},
     identifiers::{
-        Account, ApplicationId, BytecodeId, ChainId, ChannelName, Destination, MessageId, Owner,
+        Account, ApplicationId, BlobId, BytecodeId, ChainId, ChannelName, Destination, MessageId,
+        Owner,
     },
 };
 
@@ -73,6 +74,14 @@ impl From<ChainId> for wit_system_api::ChainId {
     }
 }
 
+impl From<BlobId> for wit_system_api::BlobId {
+    fn from(blob_id: BlobId) -> Self {
+        wit_system_api::BlobId {
+            inner0: blob_id.0.into(),
+        }
+    }
+}
+
 impl From<ApplicationId> for wit_system_api::ApplicationId {
     fn from(application_id: ApplicationId) -> Self {
         wit_system_api::ApplicationId {
@@ -107,11 +116,20 @@ impl From<SendMessageRequest<Vec<u8>>> for wit_system_api::SendMessageRequest {
             authenticated: message.authenticated,
             is_tracked: message.is_tracked,
             grant: message.grant.into(),
+            expiration: message.expiration.map(Into::into),
             message: message.message,
         }
     }
 }
 
+impl From<Timestamp> for wit_system_api::Timestamp {
+    fn from(timestamp: Timestamp) -> Self {
+        wit_system_api::Timestamp {
+            inner0: timestamp.micros(),
+        }
+    }
+}
+
 impl From<Destination> for wit_system_api::Destination {
     fn from(destination: Destination) -> Self {
         match destination {
@@ -86,6 +86,14 @@ macro_rules! contract {
                 )
             }
 
+            fn migrate(argument: Vec<u8>) {
+                use $crate::util::BlockingWait;
+                $crate::contract::run_async_entrypoint::<$contract, _, _>(
+                    unsafe { &mut CONTRACT },
+                    move |contract| contract.migrate(argument).blocking_wait(),
+                )
+            }
+
             fn finalize() {
                 use $crate::util::BlockingWait;
 
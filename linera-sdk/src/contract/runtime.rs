@@ -5,14 +5,17 @@
 
 use linera_base::{
     abi::{ContractAbi, ServiceAbi},
+    crypto::CryptoHash,
     data_types::{Amount, BlockHeight, Resources, SendMessageRequest, Timestamp},
-    identifiers::{Account, ApplicationId, ChainId, ChannelName, Destination, MessageId, Owner},
+    identifiers::{
+        Account, ApplicationId, BlobId, ChainId, ChannelName, Destination, MessageId, Owner,
+    },
     ownership::{ChainOwnership, CloseChainError},
 };
 use serde::Serialize;
 
 use super::wit::contract_system_api as wit;
-use crate::{Contract, KeyValueStore};
+use crate::{ApplicationMessage, Contract, KeyValueStore};
 
 /// The common runtime to interface with the host executing the contract.
 ///
@@ -146,6 +149,37 @@ where
         self.prepare_message(message).send_to(destination)
     }
 
+    /// Schedules a message to be sent to this application on another chain, with the options
+    /// declared by its type, see [`ApplicationMessage`].
+    ///
+    /// # Panics
+    ///
+    /// If the message must be sent to a single chain and the `destination` is a channel.
+    pub fn send_application_message(
+        &mut self,
+        destination: impl Into<Destination>,
+        message: Application::Message,
+    ) where
+        Application::Message: ApplicationMessage,
+    {
+        let destination = destination.into();
+        assert!(
+            !message.is_direct() || matches!(destination, Destination::Recipient(_)),
+            "Message `{}` must be sent to a single chain",
+            message.kind()
+        );
+        let is_tracked = message.is_tracked();
+        let is_authenticated = message.is_authenticated();
+        let mut builder = self.prepare_message(message);
+        if is_tracked {
+            builder = builder.with_tracking();
+        }
+        if is_authenticated {
+            builder = builder.with_authentication();
+        }
+        builder.send_to(destination)
+    }
+
     /// Returns a `MessageBuilder` to prepare a message to be sent.
     pub fn prepare_message(
         &mut self,
@@ -164,6 +198,63 @@ where
         wit::unsubscribe(chain.into(), &channel.into());
     }
 
+    /// Emits an `event` under the given `key`.
+    ///
+    /// Events are recorded in the block, so that indexers can track the activity of this
+    /// application without re-executing it. They are not visible to applications.
+    pub fn emit(&mut self, key: &[u8], event: &impl Serialize) {
+        let value = bcs::to_bytes(event).expect("Failed to serialize event");
+        wit::emit(key, &value)
+    }
+
+    /// Publishes a `payload` to a `topic` of this application. A single message delivers it
+    /// to this application on all the chains subscribed to the topic, where it is executed
+    /// like any other message.
+    pub fn publish_to_topic(&mut self, topic: &str, payload: &Application::Message) {
+        let payload = bcs::to_bytes(payload).expect("Failed to serialize payload");
+        wit::publish_to_topic(topic, &payload)
+    }
+
+    /// Subscribes to a `topic` of this application on another chain.
+    pub fn subscribe_to_topic(&mut self, chain: ChainId, topic: &str) {
+        wit::subscribe_to_topic(chain.into(), topic);
+    }
+
+    /// Unsubscribes from a `topic` of this application on another chain.
+    pub fn unsubscribe_from_topic(&mut self, chain: ChainId, topic: &str) {
+        wit::unsubscribe_from_topic(chain.into(), topic);
+    }
+
+    /// Marks the `key` of the application's state as ephemeral: it is deleted, whatever its
+    /// value is then, before the block of this chain at `height` is executed. The `height`
+    /// must be later than the current block's.
+    ///
+    /// This allows keeping temporary data, such as the orders of an order book or the state
+    /// of a session, without having to delete it with a later operation.
+    pub fn expire_key(&mut self, key: &[u8], height: BlockHeight) {
+        wit::expire_key(key, height.into())
+    }
+
+    /// Returns 32 new pseudo-random bytes.
+    ///
+    /// They are derived deterministically from the current transaction, so that all the
+    /// validators compute the same ones, and each call returns different ones. They are **not
+    /// secret**: anyone can compute them before the transaction is executed, and the owners
+    /// proposing the block can choose its transactions to get the values they want. So they
+    /// must not be used as keys, nor to draw lots whose outcome is worth knowing in advance.
+    pub fn random_bytes(&mut self) -> [u8; 32] {
+        let value = CryptoHash::from(wit::random_value());
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(value.as_bytes());
+        bytes
+    }
+
+    /// Returns a new pseudo-random number. Like [`ContractRuntime::random_bytes`], it is
+    /// deterministic and not secret.
+    pub fn random_u64(&mut self) -> u64 {
+        <[u64; 4]>::from(CryptoHash::from(wit::random_value()))[0]
+    }
+
     /// Transfers an `amount` of native tokens from `source` owner account (or the current chain's
     /// balance) to `destination`.
     pub fn transfer(&mut self, source: Option<Owner>, destination: Account, amount: Amount) {
@@ -190,7 +281,15 @@ where
         wit::close_chain().map_err(|error| error.into())
     }
 
-    /// Calls another application.
+    /// Calls another application on the current chain, and returns its response.
+    ///
+    /// The call is executed synchronously, as part of the current operation or message. If
+    /// `authenticated` is `true`, the callee sees this application as its caller and the
+    /// authenticated signer is forwarded to it.
+    ///
+    /// The transaction fails if the callee is already executing, i.e. if this is a reentrant
+    /// call, or if the nested calls exceed the maximum call depth of the chain's resource
+    /// control policy.
     pub fn call_application<A: ContractAbi + Send>(
         &mut self,
         authenticated: bool,
@@ -234,6 +333,30 @@ where
     pub fn fetch_json(&mut self, url: &str) -> String {
         wit::fetch_json(url)
     }
+
+    /// Reads the content of the blob with the given ID.
+    ///
+    /// The block then references the blob, so validators keep it in their storage. The
+    /// blob must have been published, e.g. with a `PublishBlob` operation.
+    pub fn read_blob(&mut self, blob_id: BlobId) -> Vec<u8> {
+        wit::read_blob(blob_id.into())
+    }
+
+    /// Increments this application's counter `name` by `value`.
+    ///
+    /// Validators expose the counter in their metrics endpoint, labeled with this application's
+    /// ID, once the block is confirmed. Names must be non-empty, at most 64 bytes long and
+    /// consist of ASCII alphanumeric characters and underscores.
+    pub fn increment_counter(&mut self, name: &str, value: u64) {
+        wit::increment_counter(name, value)
+    }
+
+    /// Sets this application's gauge `name` to `value`.
+    ///
+    /// The same rules as for [`Self::increment_counter`] apply.
+    pub fn set_gauge(&mut self, name: &str, value: i64) {
+        wit::set_gauge(name, value)
+    }
 }
 
 /// A helper type that uses the builder pattern to configure how a message is sent, and then
@@ -246,6 +369,7 @@ where
     authenticated: bool,
     is_tracked: bool,
     grant: Resources,
+    expiration: Option<Timestamp>,
     message: Message,
 }
 
@@ -259,6 +383,7 @@ where
             authenticated: false,
             is_tracked: false,
             grant: Resources::default(),
+            expiration: None,
             message,
         }
     }
@@ -282,6 +407,13 @@ where
         self
     }
 
+    /// Lets the message expire at the given time: after that, the receiver can no longer
+    /// execute it, and can reject or skip it instead.
+    pub fn with_expiration(mut self, expiration: Timestamp) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
     /// Schedules this `Message` to be sent to the `destination`.
     pub fn send_to(self, destination: impl Into<Destination>) {
         let serialized_message =
@@ -292,6 +424,7 @@ where
             authenticated: self.authenticated,
             is_tracked: self.is_tracked,
             grant: self.grant,
+            expiration: self.expiration,
             message: serialized_message,
         };
 
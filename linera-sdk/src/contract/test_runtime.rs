@@ -10,13 +10,16 @@ use std::{
 
 use linera_base::{
     abi::{ContractAbi, ServiceAbi},
+    crypto::{BcsHashable, CryptoHash},
     data_types::{Amount, BlockHeight, Resources, SendMessageRequest, Timestamp},
-    identifiers::{Account, ApplicationId, ChainId, ChannelName, Destination, MessageId, Owner},
+    identifiers::{
+        Account, ApplicationId, Blob, BlobId, ChainId, ChannelName, Destination, MessageId, Owner,
+    },
     ownership::{ChainOwnership, CloseChainError},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{Contract, KeyValueStore};
+use crate::{ApplicationMessage, Contract, KeyValueStore};
 
 /// A mock of the common runtime to interface with the host executing the contract.
 pub struct MockContractRuntime<Application>
@@ -37,13 +40,23 @@ where
     chain_ownership: Option<ChainOwnership>,
     can_close_chain: Option<bool>,
     call_application_handler: Option<CallApplicationHandler>,
+    expected_calls: VecDeque<ExpectedCall>,
     send_message_requests: Arc<Mutex<Vec<SendMessageRequest<Application::Message>>>>,
     subscribe_requests: Vec<(ChainId, ChannelName)>,
     unsubscribe_requests: Vec<(ChainId, ChannelName)>,
+    events: Vec<(Vec<u8>, Vec<u8>)>,
+    published_payloads: Vec<(String, Vec<u8>)>,
+    topic_subscribe_requests: Vec<(ChainId, String)>,
+    topic_unsubscribe_requests: Vec<(ChainId, String)>,
+    expiring_keys: Vec<(Vec<u8>, BlockHeight)>,
+    random_value_count: u64,
     outgoing_transfers: HashMap<Account, Amount>,
     claim_requests: Vec<ClaimRequest>,
     expected_service_queries: VecDeque<(ApplicationId, String, String)>,
     expected_json_requests: VecDeque<(String, String)>,
+    blobs: HashMap<BlobId, Vec<u8>>,
+    counters: HashMap<String, u64>,
+    gauges: HashMap<String, i64>,
     key_value_store: KeyValueStore,
 }
 
@@ -77,13 +90,23 @@ where
             chain_ownership: None,
             can_close_chain: None,
             call_application_handler: None,
+            expected_calls: VecDeque::new(),
             send_message_requests: Arc::default(),
             subscribe_requests: Vec::new(),
             unsubscribe_requests: Vec::new(),
+            events: Vec::new(),
+            published_payloads: Vec::new(),
+            topic_subscribe_requests: Vec::new(),
+            topic_unsubscribe_requests: Vec::new(),
+            expiring_keys: Vec::new(),
+            random_value_count: 0,
             outgoing_transfers: HashMap::new(),
             claim_requests: Vec::new(),
             expected_service_queries: VecDeque::new(),
             expected_json_requests: VecDeque::new(),
+            blobs: HashMap::new(),
+            counters: HashMap::new(),
+            gauges: HashMap::new(),
             key_value_store: KeyValueStore::mock().to_mut(),
         }
     }
@@ -392,6 +415,37 @@ where
         self.prepare_message(message).send_to(destination)
     }
 
+    /// Schedules a message to be sent to this application on another chain, with the options
+    /// declared by its type, see [`ApplicationMessage`].
+    ///
+    /// # Panics
+    ///
+    /// If the message must be sent to a single chain and the `destination` is a channel.
+    pub fn send_application_message(
+        &mut self,
+        destination: impl Into<Destination>,
+        message: Application::Message,
+    ) where
+        Application::Message: ApplicationMessage,
+    {
+        let destination = destination.into();
+        assert!(
+            !message.is_direct() || matches!(destination, Destination::Recipient(_)),
+            "Message `{}` must be sent to a single chain",
+            message.kind()
+        );
+        let is_tracked = message.is_tracked();
+        let is_authenticated = message.is_authenticated();
+        let mut builder = self.prepare_message(message);
+        if is_tracked {
+            builder = builder.with_tracking();
+        }
+        if is_authenticated {
+            builder = builder.with_authentication();
+        }
+        builder.send_to(destination)
+    }
+
     /// Returns a `MessageBuilder` to prepare a message to be sent.
     pub fn prepare_message(
         &mut self,
@@ -429,6 +483,79 @@ where
         &self.unsubscribe_requests
     }
 
+    /// Emits an `event` under the given `key`.
+    pub fn emit(&mut self, key: &[u8], event: &impl Serialize) {
+        let value = bcs::to_bytes(event).expect("Failed to serialize event");
+        self.events.push((key.to_vec(), value));
+    }
+
+    /// Returns the keys and serialized values of the events emitted in the test so far.
+    pub fn events(&self) -> &[(Vec<u8>, Vec<u8>)] {
+        &self.events
+    }
+
+    /// Publishes a `payload` to a `topic` of this application.
+    pub fn publish_to_topic(&mut self, topic: &str, payload: &Application::Message) {
+        let payload = bcs::to_bytes(payload).expect("Failed to serialize payload");
+        self.published_payloads.push((topic.to_owned(), payload));
+    }
+
+    /// Returns the topics and serialized payloads published in the test so far.
+    pub fn published_payloads(&self) -> &[(String, Vec<u8>)] {
+        &self.published_payloads
+    }
+
+    /// Subscribes to a `topic` of this application on another chain.
+    pub fn subscribe_to_topic(&mut self, chain: ChainId, topic: &str) {
+        self.topic_subscribe_requests
+            .push((chain, topic.to_owned()));
+    }
+
+    /// Returns the list of requests to subscribe to topics made in the test so far.
+    pub fn topic_subscribe_requests(&self) -> &[(ChainId, String)] {
+        &self.topic_subscribe_requests
+    }
+
+    /// Unsubscribes from a `topic` of this application on another chain.
+    pub fn unsubscribe_from_topic(&mut self, chain: ChainId, topic: &str) {
+        self.topic_unsubscribe_requests
+            .push((chain, topic.to_owned()));
+    }
+
+    /// Returns the list of requests to unsubscribe from topics made in the test so far.
+    pub fn topic_unsubscribe_requests(&self) -> &[(ChainId, String)] {
+        &self.topic_unsubscribe_requests
+    }
+
+    /// Marks the `key` of the application's state as ephemeral, to be deleted at `height`.
+    pub fn expire_key(&mut self, key: &[u8], height: BlockHeight) {
+        self.expiring_keys.push((key.to_vec(), height));
+    }
+
+    /// Returns the keys marked as ephemeral in the test so far, with their expiry heights.
+    pub fn expiring_keys(&self) -> &[(Vec<u8>, BlockHeight)] {
+        &self.expiring_keys
+    }
+
+    /// Returns 32 new pseudo-random bytes. The mock returns the same sequence in every test.
+    pub fn random_bytes(&mut self) -> [u8; 32] {
+        let value = self.random_value();
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(value.as_bytes());
+        bytes
+    }
+
+    /// Returns a new pseudo-random number. The mock returns the same sequence in every test.
+    pub fn random_u64(&mut self) -> u64 {
+        <[u64; 4]>::from(self.random_value())[0]
+    }
+
+    fn random_value(&mut self) -> CryptoHash {
+        let index = self.random_value_count;
+        self.random_value_count += 1;
+        CryptoHash::new(&MockRandomValue(index))
+    }
+
     /// Transfers an `amount` of native tokens from `source` owner account (or the current chain's
     /// balance) to `destination`.
     pub fn transfer(&mut self, source: Option<Owner>, destination: Account, amount: Amount) {
@@ -562,6 +689,27 @@ where
         self
     }
 
+    /// Adds an expected cross-application call, and the response it should return in the test.
+    ///
+    /// Expected calls are checked in order, before falling back to the handler configured with
+    /// [`MockContractRuntime::set_call_application_handler`].
+    pub fn add_expected_call<A: ContractAbi + Send>(
+        &mut self,
+        authenticated: bool,
+        application_id: ApplicationId<A>,
+        call: A::Operation,
+        response: A::Response,
+    ) {
+        let call = bcs::to_bytes(&call).expect("Failed to serialize `Operation` type");
+        let response = bcs::to_bytes(&response).expect("Failed to serialize `Response` type");
+        self.expected_calls.push_back(ExpectedCall {
+            authenticated,
+            application_id: application_id.forget_abi(),
+            call,
+            response,
+        });
+    }
+
     /// Calls another application.
     pub fn call_application<A: ContractAbi + Send>(
         &mut self,
@@ -572,11 +720,25 @@ where
         let call_bytes = bcs::to_bytes(call)
             .expect("Failed to serialize `Operation` type for a cross-application call");
 
-        let handler = self.call_application_handler.as_mut().expect(
-            "Handler for `call_application` has not been mocked, \
-            please call `MockContractRuntime::set_call_application_handler` first",
-        );
-        let response_bytes = handler(authenticated, application.forget_abi(), call_bytes);
+        let response_bytes = if let Some(expected) = self.expected_calls.pop_front() {
+            assert_eq!(
+                (
+                    expected.authenticated,
+                    expected.application_id,
+                    expected.call
+                ),
+                (authenticated, application.forget_abi(), call_bytes),
+                "Unexpected cross-application call"
+            );
+            expected.response
+        } else {
+            let handler = self.call_application_handler.as_mut().expect(
+                "Handler for `call_application` has not been mocked, \
+                please call `MockContractRuntime::set_call_application_handler` \
+                or `MockContractRuntime::add_expected_call` first",
+            );
+            handler(authenticated, application.forget_abi(), call_bytes)
+        };
 
         bcs::from_bytes(&response_bytes)
             .expect("Failed to deserialize `Response` type from cross-application call")
@@ -634,11 +796,59 @@ where
         assert_eq!(*url, expected_url);
         response
     }
+
+    /// Adds a blob with the given content that can be read during the test, and returns its ID.
+    pub fn add_blob(&mut self, bytes: Vec<u8>) -> BlobId {
+        let blob_id = Blob::new(bytes.clone()).id();
+        self.blobs.insert(blob_id, bytes);
+        blob_id
+    }
+
+    /// Reads the content of the blob with the given ID.
+    pub fn read_blob(&mut self, blob_id: BlobId) -> Vec<u8> {
+        self.blobs.get(&blob_id).cloned().unwrap_or_else(|| {
+            panic!(
+                "Blob {blob_id} has not been mocked, \
+                please call `MockContractRuntime::add_blob` first"
+            )
+        })
+    }
+
+    /// Increments this application's counter `name` by `value`.
+    pub fn increment_counter(&mut self, name: &str, value: u64) {
+        let counter = self.counters.entry(name.to_owned()).or_default();
+        *counter = counter
+            .checked_add(value)
+            .expect("Application counter overflow");
+    }
+
+    /// Returns the value of this application's counter `name` in the test so far.
+    pub fn counter(&self, name: &str) -> u64 {
+        self.counters.get(name).copied().unwrap_or_default()
+    }
+
+    /// Sets this application's gauge `name` to `value`.
+    pub fn set_gauge(&mut self, name: &str, value: i64) {
+        self.gauges.insert(name.to_owned(), value);
+    }
+
+    /// Returns the value of this application's gauge `name`, if it was set in the test so far.
+    pub fn gauge(&self, name: &str) -> Option<i64> {
+        self.gauges.get(name).copied()
+    }
 }
 
 /// A type alias for the handler for cross-application calls.
 pub type CallApplicationHandler = Box<dyn FnMut(bool, ApplicationId, Vec<u8>) -> Vec<u8>>;
 
+/// A cross-application call expected during the test, with its serialized response.
+struct ExpectedCall {
+    authenticated: bool,
+    application_id: ApplicationId,
+    call: Vec<u8>,
+    response: Vec<u8>,
+}
+
 /// A helper type that uses the builder pattern to configure how a message is sent, and then
 /// sends the message once it is dropped.
 #[must_use]
@@ -649,6 +859,7 @@ where
     authenticated: bool,
     is_tracked: bool,
     grant: Resources,
+    expiration: Option<Timestamp>,
     message: Message,
     send_message_requests: Arc<Mutex<Vec<SendMessageRequest<Message>>>>,
 }
@@ -666,6 +877,7 @@ where
             authenticated: false,
             is_tracked: false,
             grant: Resources::default(),
+            expiration: None,
             message,
             send_message_requests,
         }
@@ -690,6 +902,13 @@ where
         self
     }
 
+    /// Lets the message expire at the given time: after that, the receiver can no longer
+    /// execute it, and can reject or skip it instead.
+    pub fn with_expiration(mut self, expiration: Timestamp) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
     /// Schedules this `Message` to be sent to the `destination`.
     pub fn send_to(self, destination: impl Into<Destination>) {
         let request = SendMessageRequest {
@@ -697,6 +916,7 @@ where
             authenticated: self.authenticated,
             is_tracked: self.is_tracked,
             grant: self.grant,
+            expiration: self.expiration,
             message: self.message,
         };
 
@@ -714,3 +934,9 @@ pub struct ClaimRequest {
     destination: Account,
     amount: Amount,
 }
+
+/// The input of the hash returned as the pseudo-random value with the given index.
+#[derive(Debug, Serialize, Deserialize)]
+struct MockRandomValue(u64);
+
+impl BcsHashable for MockRandomValue {}
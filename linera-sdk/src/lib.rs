@@ -51,6 +51,8 @@ pub use linera_base::{
     data_types::{Resources, SendMessageRequest},
     ensure,
 };
+/// Implements [`ApplicationMessage`] for an enum of messages, from `#[message(...)]` attributes.
+pub use linera_sdk_derive::ApplicationMessage;
 use serde::{de::DeserializeOwned, Serialize};
 pub use serde_json;
 
@@ -118,6 +120,16 @@ pub trait Contract: WithContractAbi + ContractAbi + Sized {
     /// chain.
     async fn execute_message(&mut self, message: Self::Message);
 
+    /// Migrates the state of the application after it was upgraded to this contract.
+    ///
+    /// This is called on every chain where the application runs, before the first operation or
+    /// message that the new version executes there, with the migration argument of the
+    /// upgrade. Chains that pinned an earlier version of the application only migrate once
+    /// they let it follow the upgrades again.
+    ///
+    /// Migrations can't use oracles or send messages. By default, the state is kept as it is.
+    async fn migrate(&mut self, _argument: Vec<u8>) {}
+
     /// Finishes the execution of the current transaction.
     ///
     /// This is called once at the end of the transaction, to allow all applications that
@@ -128,6 +140,42 @@ pub trait Contract: WithContractAbi + ContractAbi + Sized {
     async fn store(self);
 }
 
+/// How the messages of an application are sent, so that
+/// [`ContractRuntime::send_application_message`] can apply the same options every time a kind of
+/// message is sent.
+///
+/// It is usually derived, with the options of each variant of the message enum declared in a
+/// `#[message(...)]` attribute:
+///
+/// ```ignore
+/// #[derive(Debug, Deserialize, Serialize, ApplicationMessage)]
+/// pub enum Message {
+///     #[message(tracked, authenticated, direct)]
+///     Credit { target: Owner, amount: Amount },
+///     Announcement(String),
+/// }
+/// ```
+pub trait ApplicationMessage: Serialize {
+    /// Returns the name of the kind of message, e.g. the name of its variant.
+    fn kind(&self) -> &'static str;
+
+    /// Whether the message is tracked, i.e. bounces back to the sender if it is rejected.
+    fn is_tracked(&self) -> bool {
+        false
+    }
+
+    /// Whether the authenticated signer of the transaction is forwarded with the message.
+    fn is_authenticated(&self) -> bool {
+        false
+    }
+
+    /// Whether the message must be sent to a single chain, instead of to the subscribers of a
+    /// channel.
+    fn is_direct(&self) -> bool {
+        false
+    }
+}
+
 /// The service interface of a Linera application.
 ///
 /// As opposed to the [`Contract`] interface of an application, service entry points
@@ -8,7 +8,7 @@ use std::cell::Cell;
 use linera_base::{
     abi::ServiceAbi,
     data_types::{Amount, BlockHeight, Timestamp},
-    identifiers::{ApplicationId, ChainId, Owner},
+    identifiers::{ApplicationId, BlobId, ChainId, Owner},
 };
 
 use super::wit::service_system_api as wit;
@@ -114,7 +114,10 @@ where
         })
     }
 
-    /// Queries another application.
+    /// Queries the service of another application on the same chain.
+    ///
+    /// The queried service must not be handling a query already, i.e. queries between
+    /// applications can't form a cycle.
     pub fn query_application<A: ServiceAbi>(
         &self,
         application: ApplicationId<A>,
@@ -135,6 +138,11 @@ where
         wit::fetch_url(url)
     }
 
+    /// Reads the content of the blob with the given ID.
+    pub fn read_blob(&self, blob_id: BlobId) -> Vec<u8> {
+        wit::read_blob(blob_id.into())
+    }
+
     /// Loads a value from the `cell` cache or fetches it and stores it in the cache.
     fn fetch_value_through_cache<T>(cell: &Cell<Option<T>>, fetch: impl FnOnce() -> T) -> T
     where
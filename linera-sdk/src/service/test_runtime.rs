@@ -11,7 +11,7 @@ use std::{
 use linera_base::{
     abi::ServiceAbi,
     data_types::{Amount, BlockHeight, Timestamp},
-    identifiers::{ApplicationId, ChainId, Owner},
+    identifiers::{ApplicationId, Blob, BlobId, ChainId, Owner},
 };
 
 use crate::{KeyValueStore, Service};
@@ -30,6 +30,7 @@ where
     owner_balances: RefCell<Option<HashMap<Owner, Amount>>>,
     query_application_handler: RefCell<Option<QueryApplicationHandler>>,
     url_blobs: RefCell<Option<HashMap<String, Vec<u8>>>>,
+    blobs: RefCell<HashMap<BlobId, Vec<u8>>>,
     key_value_store: KeyValueStore,
 }
 
@@ -58,10 +59,18 @@ where
             owner_balances: RefCell::new(None),
             query_application_handler: RefCell::new(None),
             url_blobs: RefCell::new(None),
+            blobs: RefCell::new(HashMap::new()),
             key_value_store: KeyValueStore::mock(),
         }
     }
 
+    /// Configures the service to read from the given key-value store, e.g. the one written to
+    /// by a [`MockContractRuntime`][crate::contract::MockContractRuntime] in the same test.
+    pub fn with_key_value_store(mut self, key_value_store: KeyValueStore) -> Self {
+        self.key_value_store = key_value_store.to_read_only();
+        self
+    }
+
     /// Returns the key-value store to interface with storage.
     pub fn key_value_store(&self) -> KeyValueStore {
         self.key_value_store.clone()
@@ -357,6 +366,27 @@ where
             })
     }
 
+    /// Adds a blob with the given content that can be read during the test, and returns its ID.
+    pub fn add_blob(&self, bytes: Vec<u8>) -> BlobId {
+        let blob_id = Blob::new(bytes.clone()).id();
+        self.blobs.borrow_mut().insert(blob_id, bytes);
+        blob_id
+    }
+
+    /// Reads the content of the blob with the given ID.
+    pub fn read_blob(&self, blob_id: BlobId) -> Vec<u8> {
+        self.blobs
+            .borrow()
+            .get(&blob_id)
+            .cloned()
+            .unwrap_or_else(|| {
+                panic!(
+                    "Blob {blob_id} has not been mocked, \
+                    please call `MockServiceRuntime::add_blob` first"
+                )
+            })
+    }
+
     /// Loads a mocked value from the `cell` cache or panics with a provided `message`.
     fn fetch_mocked_value<T>(cell: &Cell<Option<T>>, message: &str) -> T
     where
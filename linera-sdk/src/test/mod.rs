@@ -1,7 +1,12 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-//! Helper types for writing integration tests for WebAssembly applications.
+//! Helper types for writing unit tests and integration tests for WebAssembly applications.
+//!
+//! Unit tests run the contract and the service natively, with the [`MockContractRuntime`] and
+//! the [`MockServiceRuntime`] instead of validators: the tests configure the block context,
+//! the storage and the responses of other applications, then check the effects of the
+//! application on the mock runtime.
 //!
 //! Integration tests are usually written in the `tests` directory in the root of the crate's
 //! directory (i.e., beside the `src` directory). Linera application integration tests should be
@@ -22,6 +27,13 @@ mod validator;
 pub use self::mock_stubs::*;
 #[cfg(any(feature = "wasmer", feature = "wasmtime"))]
 pub use self::{block::BlockBuilder, chain::ActiveChain, validator::TestValidator};
+#[cfg(with_testing)]
+pub use crate::{contract::MockContractRuntime, service::MockServiceRuntime};
+#[cfg(with_testing)]
+use crate::{
+    util::BlockingWait,
+    views::{KeyValueStore, View, ViewStorageContext},
+};
 use crate::{Contract, ContractRuntime, Service, ServiceRuntime};
 
 /// Creates a [`ContractRuntime`] to use in tests.
@@ -33,3 +45,15 @@ pub fn test_contract_runtime<Application: Contract>() -> ContractRuntime<Applica
 pub fn test_service_runtime<Application: Service>() -> ServiceRuntime<Application> {
     ServiceRuntime::new()
 }
+
+/// Loads the state of an application from the storage of a mock runtime, e.g. to check the
+/// state saved by a contract, or to create a service reading it.
+#[cfg(with_testing)]
+pub fn load_state<State>(key_value_store: KeyValueStore) -> State
+where
+    State: View<ViewStorageContext>,
+{
+    State::load(ViewStorageContext::from(key_value_store))
+        .blocking_wait()
+        .expect("Failed to load the application state from the mock key-value store")
+}
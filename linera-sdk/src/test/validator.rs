@@ -17,6 +17,7 @@ use linera_base::{
     crypto::KeyPair,
     data_types::Timestamp,
     identifiers::{ApplicationId, BytecodeId, ChainDescription, ChainId},
+    ownership::ChainOwnership,
 };
 use linera_core::worker::WorkerState;
 use linera_execution::{
@@ -165,7 +166,7 @@ impl TestValidator {
                 self.committee.clone(),
                 ChainId::root(0),
                 description,
-                key_pair.public(),
+                ChainOwnership::single(key_pair.public()),
                 0.into(),
                 Timestamp::from(0),
             )
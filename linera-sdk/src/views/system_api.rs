@@ -77,6 +77,22 @@ impl KeyValueStore {
             },
         }
     }
+
+    /// Returns a mocked [`KeyValueStore`] that shares the memory storage with this instance but
+    /// does not allow write operations.
+    #[cfg(with_testing)]
+    pub(crate) fn to_read_only(&self) -> Self {
+        let WitInterface::Mock { store, .. } = &self.wit_api else {
+            panic!("Real `KeyValueStore` should not be used in unit tests");
+        };
+
+        KeyValueStore {
+            wit_api: WitInterface::Mock {
+                store: store.clone(),
+                read_only: true,
+            },
+        }
+    }
 }
 
 impl ReadableKeyValueStore<ViewError> for KeyValueStore {
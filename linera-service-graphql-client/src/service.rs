@@ -131,8 +131,8 @@ pub struct Transfer;
 mod from {
     use linera_base::data_types::OracleRecord;
     use linera_chain::data_types::{
-        BlockExecutionOutcome, ExecutedBlock, HashedCertificateValue, IncomingMessage,
-        OutgoingMessage,
+        ApplicationEvent, BlockExecutionOutcome, ExecutedBlock, HashedCertificateValue,
+        IncomingMessage, OutgoingMessage,
     };
 
     use super::*;
@@ -189,6 +189,7 @@ mod from {
                 grant,
                 refund_grant_to,
                 kind,
+                expiration,
                 message,
             } = val;
             OutgoingMessage {
@@ -197,13 +198,15 @@ mod from {
                 grant,
                 refund_grant_to,
                 kind,
+                expiration,
                 message,
             }
         }
     }
 
-    impl From<block::BlockBlockValueExecutedBlock> for ExecutedBlock {
-        fn from(val: block::BlockBlockValueExecutedBlock) -> Self {
+    impl TryFrom<block::BlockBlockValueExecutedBlock> for ExecutedBlock {
+        type Error = String;
+        fn try_from(val: block::BlockBlockValueExecutedBlock) -> Result<Self, Self::Error> {
             let block::BlockBlockValueExecutedBlock {
                 block,
                 outcome:
@@ -212,21 +215,54 @@ mod from {
                         message_counts,
                         state_hash,
                         oracle_records,
+                        fuel_used,
+                        events,
                     },
             } = val;
             let messages = messages
                 .into_iter()
                 .map(OutgoingMessage::from)
                 .collect::<Vec<_>>();
-            ExecutedBlock {
+            let events = events
+                .into_iter()
+                .map(ApplicationEvent::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(ExecutedBlock {
                 block: block.into(),
                 outcome: BlockExecutionOutcome {
                     messages,
                     message_counts: message_counts.into_iter().map(|c| c as u32).collect(),
                     state_hash,
                     oracle_records: oracle_records.into_iter().map(Into::into).collect(),
+                    fuel_used: fuel_used as u64,
+                    events,
                 },
-            }
+            })
+        }
+    }
+
+    impl TryFrom<block::BlockBlockValueExecutedBlockOutcomeEvents> for ApplicationEvent {
+        type Error = String;
+        fn try_from(
+            val: block::BlockBlockValueExecutedBlockOutcomeEvents,
+        ) -> Result<Self, Self::Error> {
+            let block::BlockBlockValueExecutedBlockOutcomeEvents {
+                transaction_index,
+                application_id,
+                key,
+                value,
+            } = val;
+            let bytes = |ints: Vec<i64>| {
+                ints.into_iter()
+                    .map(|int| u8::try_from(int).map_err(|error| error.to_string()))
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            Ok(ApplicationEvent {
+                transaction_index: transaction_index as u32,
+                application_id: application_id.parse().map_err(|error| format!("{error}"))?,
+                key: bytes(key)?,
+                value: bytes(value)?,
+            })
         }
     }
 
@@ -241,12 +277,12 @@ mod from {
         type Error = String;
         fn try_from(val: block::BlockBlock) -> Result<Self, Self::Error> {
             match (val.value.status.as_str(), val.value.executed_block) {
-                ("validated", Some(executed_block)) => {
-                    Ok(HashedCertificateValue::new_validated(executed_block.into()))
-                }
-                ("confirmed", Some(executed_block)) => {
-                    Ok(HashedCertificateValue::new_confirmed(executed_block.into()))
-                }
+                ("validated", Some(executed_block)) => Ok(HashedCertificateValue::new_validated(
+                    executed_block.try_into()?,
+                )),
+                ("confirmed", Some(executed_block)) => Ok(HashedCertificateValue::new_confirmed(
+                    executed_block.try_into()?,
+                )),
                 _ => Err(val.value.status),
             }
         }
@@ -0,0 +1,429 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An HTTP API for the operators of a validator to inspect and control its shards and
+//! proxies.
+//!
+//! - `GET /proposals` lists the block proposals each shard is handling, with how long ago they
+//!   were received and whether they are executing or waiting for their chain's turn.
+//! - `POST /shards/{shard}/proposals/{id}/cancel` cancels a proposal, which releases the lock on
+//!   its chain without having to restart the shard.
+//! - `GET /equivocations` lists the evidence that validators signed conflicting blocks, found
+//!   in the certificates the shards checked. It can be submitted to the admin chain with
+//!   `linera report-equivocation`.
+//! - `POST /shards/{shard}/chains/{chain_id}/redeliver` makes a shard send the cross-chain
+//!   requests for the outgoing messages of a chain that are not confirmed as delivered yet
+//!   again.
+//! - `POST /caches/flush` clears the caches of the shards and the proxy of this process.
+//! - `GET /log-filter` returns the filter of the logs of this process, and `PUT /log-filter`
+//!   replaces it with the directives in the body, e.g. `info,linera_core=debug`, without
+//!   restarting the process.
+//! - `GET /load` reports the load of each shard over the last few seconds: the requests it
+//!   handles per second, how many are in its queue, the latency of the storage and its busiest
+//!   chains.
+//!
+//! The load report is the hook for orchestration tooling to scale the shards out. A shard is
+//! reported as `overloaded`, and the report recommends to `scale_out`, when its load reaches
+//! one of the [`ScaleOutThresholds`] configured by the operators. The shards are then added
+//! to the server configuration, and `linera-server rebalance`, given the old and the new
+//! configurations and the busiest chains as `--chains`, checks that the chains moving to
+//! another shard are ready to be served by it before the shards are restarted.
+//!
+//! If an admin token is configured, every request must carry it in an
+//! `Authorization: Bearer <token>` header. Otherwise the API is not authenticated, so it must
+//! only be reachable by the operators, e.g. by binding it to a local address.
+
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+};
+
+use axum::{
+    extract::{FromRef, Path, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use futures::channel::mpsc;
+use linera_base::{data_types::BlockHeight, identifiers::ChainId};
+use linera_chain::data_types::EquivocationEvidence;
+use linera_core::{
+    equivocation_detector::EquivocationDetector,
+    load_monitor::{LoadMonitor, LoadReport},
+    proposal_registry::{ProposalRegistry, ProposalStatus},
+    worker::AdminCommand,
+};
+use linera_rpc::config::ShardId;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use tracing_subscriber::{
+    filter::LevelFilter, layer::SubscriberExt as _, reload, util::SubscriberInitExt as _,
+    EnvFilter, Registry,
+};
+
+use crate::routing::RoutingCache;
+
+#[cfg(test)]
+#[path = "unit_tests/admin_server.rs"]
+mod tests;
+
+/// The shards running in this process.
+type Shards = Arc<BTreeMap<ShardId, ShardState>>;
+
+/// The handle to change the filter of the logs, if they were set up with [`init_tracing`].
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// The state of a shard that the admin API can inspect.
+#[derive(Clone, Default)]
+pub struct ShardState {
+    pub proposals: Arc<ProposalRegistry>,
+    pub equivocations: Arc<EquivocationDetector>,
+    pub load: Arc<LoadMonitor>,
+    /// Sends the commands of the operators to the server of the shard, if it runs.
+    pub commands: Option<mpsc::UnboundedSender<AdminCommand>>,
+}
+
+/// The processes of a validator that the admin API inspects and controls.
+#[derive(Clone, Default)]
+pub struct AdminApi {
+    shards: BTreeMap<ShardId, ShardState>,
+    /// The routing cache of the proxy running in this process, if any.
+    routing: Option<Arc<RoutingCache>>,
+    /// The token that every request must carry, if any.
+    token: Option<Arc<str>>,
+    scale_out: ScaleOutThresholds,
+}
+
+/// The state of the handlers of the admin API.
+#[derive(Clone)]
+struct AdminState {
+    shards: Shards,
+    routing: Option<Arc<RoutingCache>>,
+    scale_out: ScaleOutThresholds,
+}
+
+/// The load from which a shard is reported as overloaded, so that more shards should be
+/// added.
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct ScaleOutThresholds {
+    /// The number of requests per second from which a shard is reported as overloaded by the
+    /// admin API.
+    #[arg(long = "scale-out-requests-per-second")]
+    pub requests_per_second: Option<f64>,
+
+    /// The number of requests waiting or being executed from which a shard is reported as
+    /// overloaded by the admin API.
+    #[arg(long = "scale-out-queue-depth")]
+    pub queue_depth: Option<usize>,
+
+    /// The average latency of the storage, in milliseconds, from which a shard is reported
+    /// as overloaded by the admin API.
+    #[arg(long = "scale-out-storage-latency-ms")]
+    pub storage_latency_ms: Option<f64>,
+}
+
+impl ScaleOutThresholds {
+    /// Returns whether the `load` reaches any of the thresholds.
+    pub fn is_reached(&self, load: &LoadReport) -> bool {
+        self.requests_per_second
+            .map_or(false, |max| load.requests_per_second >= max)
+            || self
+                .queue_depth
+                .map_or(false, |max| load.queue_depth >= max)
+            || self
+                .storage_latency_ms
+                .zip(load.storage_latency_ms)
+                .map_or(false, |(max, latency)| latency >= max)
+    }
+}
+
+impl FromRef<AdminState> for Shards {
+    fn from_ref(state: &AdminState) -> Self {
+        state.shards.clone()
+    }
+}
+
+/// A block proposal handled by a shard, as returned by the API.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ShardProposal {
+    pub shard: ShardId,
+    pub id: u64,
+    pub chain_id: ChainId,
+    pub height: BlockHeight,
+    pub age_ms: u64,
+    pub executing: bool,
+}
+
+/// The load of a shard, as returned by the API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShardLoad {
+    pub shard: ShardId,
+    #[serde(flatten)]
+    pub load: LoadReport,
+    /// Whether the load reaches one of the scale-out thresholds.
+    pub overloaded: bool,
+}
+
+/// The load of the shards of this process, as returned by the API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorLoad {
+    pub shards: Vec<ShardLoad>,
+    /// Whether any shard is overloaded, so that more shards should be added.
+    pub scale_out: bool,
+}
+
+impl ShardProposal {
+    fn new(shard: ShardId, status: ProposalStatus) -> Self {
+        ShardProposal {
+            shard,
+            id: status.id,
+            chain_id: status.chain_id,
+            height: status.height,
+            age_ms: u64::try_from(status.age.as_millis()).unwrap_or(u64::MAX),
+            executing: status.executing,
+        }
+    }
+}
+
+impl AdminApi {
+    /// Returns an API for no shards nor proxy, only accepting the requests carrying `token`,
+    /// if it is given.
+    pub fn new(token: Option<String>) -> Self {
+        AdminApi {
+            token: token.map(Arc::from),
+            ..AdminApi::default()
+        }
+    }
+
+    /// Returns this API with the given shards.
+    pub fn with_shards(mut self, shards: impl IntoIterator<Item = (ShardId, ShardState)>) -> Self {
+        self.shards.extend(shards);
+        self
+    }
+
+    /// Returns this API with the routing cache of a proxy, cleared when the caches are
+    /// flushed.
+    pub fn with_proxy_routing(mut self, routing: Arc<RoutingCache>) -> Self {
+        self.routing = Some(routing);
+        self
+    }
+
+    /// Returns this API reporting the shards whose load reaches the `thresholds` as
+    /// overloaded.
+    pub fn with_scale_out_thresholds(mut self, thresholds: ScaleOutThresholds) -> Self {
+        self.scale_out = thresholds;
+        self
+    }
+
+    fn router(self) -> Router {
+        let state = AdminState {
+            shards: Arc::new(self.shards),
+            routing: self.routing,
+            scale_out: self.scale_out,
+        };
+        Router::new()
+            .route("/proposals", get(list_proposals))
+            .route("/shards/:shard/proposals/:id/cancel", post(cancel_proposal))
+            .route("/equivocations", get(list_equivocations))
+            .route(
+                "/shards/:shard/chains/:chain_id/redeliver",
+                post(redeliver_messages),
+            )
+            .route("/caches/flush", post(flush_caches))
+            .route("/log-filter", get(get_log_filter).put(set_log_filter))
+            .route("/load", get(get_load))
+            .layer(middleware::from_fn_with_state(self.token, check_token))
+            .with_state(state)
+    }
+}
+
+/// Prints the logs of this process to the standard error, with the filter of the `RUST_LOG`
+/// environment variable or at the info level, and lets the admin API change the filter.
+pub fn init_tracing() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+    let (filter, handle) = reload::Layer::new(env_filter);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+    LOG_FILTER
+        .set(handle)
+        .expect("tracing should only be initialized once");
+}
+
+/// Starts serving the admin API on `address`.
+pub fn start_admin_server(address: SocketAddr, api: AdminApi) {
+    info!("Starting to serve the admin API on {:?}", address);
+    if api.token.is_none() {
+        warn!("The admin API is not authenticated: only the operators should reach it");
+    }
+    let router = api.router();
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(
+            tokio::net::TcpListener::bind(address).await.unwrap(),
+            router,
+        )
+        .await
+        {
+            panic!("Error serving the admin API: {}", e);
+        }
+    });
+}
+
+/// Rejects the requests that don't carry the admin token, if there is one.
+async fn check_token(
+    State(token): State<Option<Arc<str>>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if let Some(token) = token {
+        let authorized = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map_or(false, |value| {
+                constant_time_eq(value.as_bytes(), token.as_bytes())
+            });
+        if !authorized {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+/// Compares two secrets in a time that doesn't depend on the position of their first
+/// difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn list_proposals(State(shards): State<Shards>) -> Json<Vec<ShardProposal>> {
+    let proposals = shards
+        .iter()
+        .flat_map(|(shard, state)| {
+            state
+                .proposals
+                .list()
+                .into_iter()
+                .map(|status| ShardProposal::new(*shard, status))
+        })
+        .collect();
+    Json(proposals)
+}
+
+async fn cancel_proposal(
+    State(shards): State<Shards>,
+    Path((shard, id)): Path<(ShardId, u64)>,
+) -> Result<Json<ShardProposal>, StatusCode> {
+    let state = shards.get(&shard).ok_or(StatusCode::NOT_FOUND)?;
+    let status = state.proposals.cancel(id).ok_or(StatusCode::NOT_FOUND)?;
+    warn!(
+        shard,
+        chain_id = %status.chain_id,
+        height = %status.height,
+        "Cancelling block proposal at the request of an operator"
+    );
+    Ok(Json(ShardProposal::new(shard, status)))
+}
+
+async fn list_equivocations(State(shards): State<Shards>) -> Json<Vec<EquivocationEvidence>> {
+    let evidence = shards
+        .values()
+        .flat_map(|state| state.equivocations.evidence())
+        .collect();
+    Json(evidence)
+}
+
+async fn redeliver_messages(
+    State(shards): State<Shards>,
+    Path((shard, chain_id)): Path<(ShardId, ChainId)>,
+) -> StatusCode {
+    let Some(state) = shards.get(&shard) else {
+        return StatusCode::NOT_FOUND;
+    };
+    info!(
+        shard,
+        %chain_id,
+        "Redelivering the messages of a chain at the request of an operator"
+    );
+    if !send_command(state, AdminCommand::RedeliverMessages(chain_id)) {
+        return StatusCode::SERVICE_UNAVAILABLE;
+    }
+    StatusCode::ACCEPTED
+}
+
+async fn flush_caches(State(state): State<AdminState>) -> StatusCode {
+    warn!("Flushing the caches at the request of an operator");
+    if let Some(routing) = &state.routing {
+        routing.invalidate();
+    }
+    let mut status = StatusCode::ACCEPTED;
+    for (shard, shard_state) in state.shards.iter() {
+        if !send_command(shard_state, AdminCommand::FlushCaches) {
+            warn!(
+                shard,
+                "Failed to flush the caches of a shard that is not running"
+            );
+            status = StatusCode::SERVICE_UNAVAILABLE;
+        }
+    }
+    status
+}
+
+/// Sends a command to the server of a shard, and returns whether it is running.
+fn send_command(state: &ShardState, command: AdminCommand) -> bool {
+    state
+        .commands
+        .as_ref()
+        .map_or(false, |commands| commands.unbounded_send(command).is_ok())
+}
+
+async fn get_log_filter() -> Result<String, StatusCode> {
+    let handle = LOG_FILTER.get().ok_or(StatusCode::NOT_FOUND)?;
+    handle
+        .with_current(|filter| filter.to_string())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn set_log_filter(directives: String) -> Result<String, (StatusCode, String)> {
+    let handle = LOG_FILTER.get().ok_or_else(|| {
+        let message = "The log filter of this process cannot be changed".to_owned();
+        (StatusCode::NOT_FOUND, message)
+    })?;
+    let filter = EnvFilter::builder()
+        .parse(directives.trim())
+        .map_err(|error| (StatusCode::BAD_REQUEST, error.to_string()))?;
+    let description = filter.to_string();
+    handle
+        .reload(filter)
+        .map_err(|error| (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()))?;
+    warn!(
+        filter = %description,
+        "Changed the log filter at the request of an operator"
+    );
+    Ok(description)
+}
+
+async fn get_load(State(state): State<AdminState>) -> Json<ValidatorLoad> {
+    let shards = state
+        .shards
+        .iter()
+        .map(|(shard, shard_state)| {
+            let load = shard_state.load.report();
+            ShardLoad {
+                shard: *shard,
+                overloaded: state.scale_out.is_reached(&load),
+                load,
+            }
+        })
+        .collect::<Vec<_>>();
+    let scale_out = shards.iter().any(|shard| shard.overloaded);
+    Json(ValidatorLoad { shards, scale_out })
+}
@@ -0,0 +1,111 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Alerts about the chains of the wallet running low on tokens, e.g. to pay for their fees.
+//!
+//! When a threshold is configured, the chain listener checks the balance of each chain of the
+//! wallet when it starts listening to it, after each new block and on each refresh of the
+//! followed chains. When a balance drops below the threshold, an alert is logged and, if a
+//! webhook is configured, posted to it as JSON. There is only one alert until the balance is
+//! back to the threshold or above.
+
+use std::time::Duration;
+
+use linera_base::{data_types::Amount, identifiers::ChainId};
+use serde::Serialize;
+use tracing::warn;
+
+#[cfg(test)]
+#[path = "unit_tests/balance_alert.rs"]
+mod tests;
+
+/// How long to wait for the webhook to answer.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The configuration of the low-balance alerts.
+#[derive(Debug, Default, Clone, clap::Args)]
+pub struct BalanceAlertConfig {
+    /// Alert when the balance of a chain of the wallet drops below this amount.
+    #[arg(long = "low-balance-threshold")]
+    pub threshold: Option<Amount>,
+
+    /// The URL to which the low-balance alerts are posted as JSON, in addition to being logged.
+    #[arg(long = "low-balance-webhook", requires = "threshold")]
+    pub webhook: Option<String>,
+}
+
+/// An alert about a chain whose balance dropped below the threshold, as posted to the
+/// webhook.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct LowBalanceAlert {
+    pub chain_id: ChainId,
+    pub balance: Amount,
+    pub threshold: Amount,
+}
+
+/// Watches the balance of one chain, to alert once each time it drops below the threshold.
+pub struct BalanceWatcher {
+    chain_id: ChainId,
+    config: BalanceAlertConfig,
+    /// Whether the last balance was below the threshold, so that it was already alerted about.
+    is_low: bool,
+}
+
+impl BalanceWatcher {
+    /// Creates a watcher of the balance of `chain_id`.
+    pub fn new(chain_id: ChainId, config: BalanceAlertConfig) -> Self {
+        Self {
+            chain_id,
+            config,
+            is_low: false,
+        }
+    }
+
+    /// Returns whether a threshold is configured, so that the balance needs to be checked.
+    pub fn is_enabled(&self) -> bool {
+        self.config.threshold.is_some()
+    }
+
+    /// Records the current balance, and returns an alert if it just dropped below the
+    /// threshold.
+    pub fn observe(&mut self, balance: Amount) -> Option<LowBalanceAlert> {
+        let threshold = self.config.threshold?;
+        let was_low = std::mem::replace(&mut self.is_low, balance < threshold);
+        (self.is_low && !was_low).then_some(LowBalanceAlert {
+            chain_id: self.chain_id,
+            balance,
+            threshold,
+        })
+    }
+
+    /// Records the current balance, and sends an alert if it just dropped below the threshold.
+    /// The webhook is called in the background, so that the listener is not delayed.
+    pub fn check(&mut self, balance: Amount) {
+        let Some(alert) = self.observe(balance) else {
+            return;
+        };
+        warn!(
+            chain_id = %alert.chain_id,
+            balance = %alert.balance,
+            threshold = %alert.threshold,
+            "The balance of the chain is low."
+        );
+        if let Some(webhook) = self.config.webhook.clone() {
+            tokio::spawn(send_alert(webhook, alert));
+        }
+    }
+}
+
+/// Posts the `alert` to the `webhook`, logging any failure.
+async fn send_alert(webhook: String, alert: LowBalanceAlert) {
+    let result = reqwest::Client::new()
+        .post(&webhook)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&alert)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(error) = result {
+        warn!(%error, chain_id = %alert.chain_id, "Failed to send the low-balance alert.");
+    }
+}
@@ -62,6 +62,7 @@ async fn main() -> Result<()> {
         .with_env_filter(env_filter)
         .init();
 
+    linera_version::VersionInfo::handle_verbose_version_flag();
     let args = Args::parse();
     match args {
         Args::Fungible {
@@ -0,0 +1,156 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Workloads and results of `linera benchmark`.
+//!
+//! The results can be written as JSON, to track the performance of a network across releases.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use linera_base::identifiers::ChainId;
+use linera_rpc::config::{self, ShardId};
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "unit_tests/benchmark_report.rs"]
+mod tests;
+
+/// The kind of operations in the blocks of a benchmark.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Workload {
+    /// Native token transfers to an account on the same chain.
+    Transfer,
+    /// Native token transfers to another chain, i.e. cross-chain messages.
+    CrossChain,
+    /// Transfers of a fungible token application, i.e. application calls.
+    Fungible,
+}
+
+impl Workload {
+    /// Returns the workload of the `index`-th chain when mixing the given workloads.
+    pub fn for_chain(workloads: &[Workload], index: usize) -> Workload {
+        workloads
+            .get(index % workloads.len().max(1))
+            .copied()
+            .unwrap_or(Workload::CrossChain)
+    }
+}
+
+/// Percentiles of the latencies of the requests of a phase, in milliseconds.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LatencyPercentiles {
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyPercentiles {
+    /// Computes the percentiles of the given latencies, or returns `None` if there are none.
+    pub fn new(mut latencies: Vec<Duration>) -> Option<Self> {
+        if latencies.is_empty() {
+            return None;
+        }
+        latencies.sort();
+        // The nearest-rank percentile.
+        let percentile = |p: usize| {
+            let rank = (p * latencies.len()).div_ceil(100).max(1);
+            latencies[rank - 1].as_secs_f64() * 1000.0
+        };
+        Some(LatencyPercentiles {
+            samples: latencies.len(),
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms: percentile(100),
+        })
+    }
+}
+
+/// The results of broadcasting the requests of one phase to all validators.
+#[derive(Clone, Debug, Serialize)]
+pub struct PhaseReport {
+    pub phase: String,
+    /// The number of requests sent to each validator.
+    pub requests: usize,
+    /// The number of responses received from all validators.
+    pub responses: usize,
+    pub elapsed_ms: f64,
+    /// The number of requests per second handled by each validator.
+    pub throughput_per_sec: f64,
+    /// The latencies of the batches of requests, if the requests were sent at a target rate.
+    pub latency: Option<LatencyPercentiles>,
+}
+
+impl PhaseReport {
+    pub fn new(
+        phase: &str,
+        requests: usize,
+        responses: usize,
+        elapsed: Duration,
+        latencies: Vec<Duration>,
+    ) -> Self {
+        PhaseReport {
+            phase: phase.to_string(),
+            requests,
+            responses,
+            elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            throughput_per_sec: per_second(requests, elapsed),
+            latency: LatencyPercentiles::new(latencies),
+        }
+    }
+}
+
+/// The confirmed certificates handled by a shard of each validator.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ShardReport {
+    pub shard: ShardId,
+    pub certificates: usize,
+    pub throughput_per_sec: f64,
+}
+
+impl ShardReport {
+    /// Counts the certificates of each shard, for validators with `num_shards` shards.
+    pub fn for_chains(
+        chain_ids: impl IntoIterator<Item = ChainId>,
+        num_shards: usize,
+        elapsed: Duration,
+    ) -> Vec<ShardReport> {
+        let mut counts = BTreeMap::<ShardId, usize>::new();
+        for chain_id in chain_ids {
+            *counts
+                .entry(config::shard_id(chain_id, num_shards.max(1)))
+                .or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(shard, certificates)| ShardReport {
+                shard,
+                certificates,
+                throughput_per_sec: per_second(certificates, elapsed),
+            })
+            .collect()
+    }
+}
+
+/// The results of a benchmark.
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchmarkReport {
+    pub num_chains: usize,
+    pub transactions_per_block: usize,
+    pub workloads: Vec<Workload>,
+    /// The target number of requests per second sent to each validator, if any.
+    pub target_rate: Option<u32>,
+    pub phases: Vec<PhaseReport>,
+    /// The certificates per shard, if the number of shards was given.
+    pub shards: Vec<ShardReport>,
+}
+
+fn per_second(count: usize, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    count as f64 / elapsed.as_secs_f64()
+}
@@ -0,0 +1,279 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A file format for the certificate history of a chain, used to migrate chains between
+//! validator deployments and to audit or replay them.
+//!
+//! An archive is a sequence of entries, each serialized with BCS and prefixed with its length
+//! as a little-endian `u32`, so that it can be written and read without holding the whole
+//! history in memory. It starts with a header naming the chain, continues with the confirmed
+//! certificates of the chain's blocks in order, and ends with a trailer counting them.
+//!
+//! When reading an archive, each certificate is checked to be for the next block of the chain,
+//! and to refer to the hash of the previous one. The hashes are computed again from the
+//! certificates' values, so an archive that was modified or truncated is rejected. The
+//! signatures are only checked when the certificates are imported, against the committees of
+//! the chain.
+
+use std::io::{self, Read, Write};
+
+use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
+use linera_chain::data_types::{Certificate, CertificateValue};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(test)]
+#[path = "unit_tests/chain_archive.rs"]
+mod tests;
+
+/// The version of the archive format written by this crate.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// The maximal size of an archive entry, to avoid allocating arbitrary amounts of memory when
+/// reading a corrupted archive.
+const MAX_ENTRY_SIZE: u32 = 1 << 30;
+
+/// An entry of an archive.
+#[derive(Debug, Serialize, Deserialize)]
+enum ArchiveEntry {
+    /// The first entry of an archive.
+    Header { version: u32, chain_id: ChainId },
+    /// The certificate of the next block of the chain.
+    Certificate(Certificate),
+    /// The last entry of an archive.
+    Trailer {
+        certificate_count: u64,
+        last_hash: Option<CryptoHash>,
+    },
+}
+
+/// An error reading or writing an archive.
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("BCS (de)serialization error: {0}")]
+    Bcs(#[from] bcs::Error),
+    #[error("Archive entry of {0} bytes is too large")]
+    EntryTooLarge(u64),
+    #[error("Unsupported archive version {0}")]
+    UnsupportedVersion(u32),
+    #[error("The archive does not start with a header")]
+    MissingHeader,
+    #[error("The archive ends without a trailer")]
+    MissingTrailer,
+    #[error("Unexpected entry after the archive trailer")]
+    DataAfterTrailer,
+    #[error("Certificate {hash} is not a confirmed block of chain {chain_id} at height {height}")]
+    UnexpectedCertificate {
+        hash: CryptoHash,
+        chain_id: ChainId,
+        height: BlockHeight,
+    },
+    #[error("The block at height {height} does not refer to the previous block of the archive")]
+    BrokenChain { height: BlockHeight },
+    #[error(
+        "The archive trailer reports {expected} certificates ending with {expected_hash:?}, \
+        but {actual} certificates ending with {actual_hash:?} were read"
+    )]
+    TrailerMismatch {
+        expected: u64,
+        expected_hash: Option<CryptoHash>,
+        actual: u64,
+        actual_hash: Option<CryptoHash>,
+    },
+}
+
+/// Writes the certificates of a chain to an archive.
+pub struct ArchiveWriter<W> {
+    writer: W,
+    chain_id: ChainId,
+    certificate_count: u64,
+    last_hash: Option<CryptoHash>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Starts an archive for the given chain.
+    pub fn new(mut writer: W, chain_id: ChainId) -> Result<Self, ArchiveError> {
+        let header = ArchiveEntry::Header {
+            version: ARCHIVE_VERSION,
+            chain_id,
+        };
+        write_entry(&mut writer, &header)?;
+        Ok(ArchiveWriter {
+            writer,
+            chain_id,
+            certificate_count: 0,
+            last_hash: None,
+        })
+    }
+
+    /// Appends the certificate of the next block of the chain.
+    pub fn append(&mut self, certificate: Certificate) -> Result<(), ArchiveError> {
+        check_next_certificate(
+            &certificate,
+            self.chain_id,
+            self.certificate_count,
+            self.last_hash,
+        )?;
+        let hash = certificate.hash();
+        write_entry(&mut self.writer, &ArchiveEntry::Certificate(certificate))?;
+        self.certificate_count += 1;
+        self.last_hash = Some(hash);
+        Ok(())
+    }
+
+    /// Writes the trailer and returns the number of certificates in the archive.
+    pub fn finish(mut self) -> Result<u64, ArchiveError> {
+        let trailer = ArchiveEntry::Trailer {
+            certificate_count: self.certificate_count,
+            last_hash: self.last_hash,
+        };
+        write_entry(&mut self.writer, &trailer)?;
+        self.writer.flush()?;
+        Ok(self.certificate_count)
+    }
+}
+
+/// Reads the certificates of a chain from an archive, in order.
+///
+/// If the archive is truncated or corrupted, the iterator returns an error after the
+/// certificates read so far.
+pub struct ArchiveReader<R> {
+    reader: R,
+    chain_id: ChainId,
+    certificate_count: u64,
+    last_hash: Option<CryptoHash>,
+    finished: bool,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    /// Reads the header of an archive.
+    pub fn new(mut reader: R) -> Result<Self, ArchiveError> {
+        let chain_id = match read_entry(&mut reader)? {
+            Some(ArchiveEntry::Header {
+                version: ARCHIVE_VERSION,
+                chain_id,
+            }) => chain_id,
+            Some(ArchiveEntry::Header { version, .. }) => {
+                return Err(ArchiveError::UnsupportedVersion(version))
+            }
+            _ => return Err(ArchiveError::MissingHeader),
+        };
+        Ok(ArchiveReader {
+            reader,
+            chain_id,
+            certificate_count: 0,
+            last_hash: None,
+            finished: false,
+        })
+    }
+
+    /// Returns the chain the archive is for.
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_id
+    }
+
+    /// Returns the next certificate, or `None` once the trailer was read and checked.
+    fn read_next(&mut self) -> Result<Option<Certificate>, ArchiveError> {
+        match read_entry(&mut self.reader)? {
+            Some(ArchiveEntry::Certificate(certificate)) => {
+                check_next_certificate(
+                    &certificate,
+                    self.chain_id,
+                    self.certificate_count,
+                    self.last_hash,
+                )?;
+                self.certificate_count += 1;
+                self.last_hash = Some(certificate.hash());
+                Ok(Some(certificate))
+            }
+            Some(ArchiveEntry::Trailer {
+                certificate_count,
+                last_hash,
+            }) => {
+                if certificate_count != self.certificate_count || last_hash != self.last_hash {
+                    return Err(ArchiveError::TrailerMismatch {
+                        expected: certificate_count,
+                        expected_hash: last_hash,
+                        actual: self.certificate_count,
+                        actual_hash: self.last_hash,
+                    });
+                }
+                if read_entry(&mut self.reader)?.is_some() {
+                    return Err(ArchiveError::DataAfterTrailer);
+                }
+                Ok(None)
+            }
+            Some(ArchiveEntry::Header { .. }) | None => Err(ArchiveError::MissingTrailer),
+        }
+    }
+}
+
+impl<R: Read> Iterator for ArchiveReader<R> {
+    type Item = Result<Certificate, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.read_next().transpose();
+        self.finished = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+/// Checks that `certificate` confirms the block at `height` of the chain, after the block with
+/// the hash `previous_hash`.
+fn check_next_certificate(
+    certificate: &Certificate,
+    chain_id: ChainId,
+    height: u64,
+    previous_hash: Option<CryptoHash>,
+) -> Result<(), ArchiveError> {
+    let height = BlockHeight(height);
+    let block = match certificate.value() {
+        CertificateValue::ConfirmedBlock { executed_block } => Some(&executed_block.block),
+        _ => None,
+    };
+    let Some(block) = block.filter(|block| block.chain_id == chain_id && block.height == height)
+    else {
+        return Err(ArchiveError::UnexpectedCertificate {
+            hash: certificate.hash(),
+            chain_id,
+            height,
+        });
+    };
+    if block.previous_block_hash != previous_hash {
+        return Err(ArchiveError::BrokenChain { height });
+    }
+    Ok(())
+}
+
+fn write_entry(writer: &mut impl Write, entry: &ArchiveEntry) -> Result<(), ArchiveError> {
+    let bytes = bcs::to_bytes(entry)?;
+    let length = u32::try_from(bytes.len())
+        .ok()
+        .filter(|length| *length <= MAX_ENTRY_SIZE)
+        .ok_or(ArchiveError::EntryTooLarge(bytes.len() as u64))?;
+    writer.write_all(&length.to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Reads the next entry, or returns `None` at the end of the archive.
+fn read_entry(reader: &mut impl Read) -> Result<Option<ArchiveEntry>, ArchiveError> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => (),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    let length = u32::from_le_bytes(length);
+    if length > MAX_ENTRY_SIZE {
+        return Err(ArchiveError::EntryTooLarge(length.into()));
+    }
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(bcs::from_bytes(&bytes)?))
+}
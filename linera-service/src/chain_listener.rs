@@ -11,7 +11,7 @@ use futures::{
 };
 use linera_base::{
     crypto::KeyPair,
-    data_types::Timestamp,
+    data_types::{TimeDelta, Timestamp},
     identifiers::{ChainId, Destination},
 };
 use linera_chain::data_types::OutgoingMessage;
@@ -25,7 +25,11 @@ use linera_storage::Storage;
 use linera_views::views::ViewError;
 use tracing::{error, info, warn};
 
-use crate::{node_service::ChainClients, wallet::Wallet};
+use crate::{
+    balance_alert::{BalanceAlertConfig, BalanceWatcher},
+    node_service::ChainClients,
+    wallet::Wallet,
+};
 
 #[cfg(test)]
 #[path = "unit_tests/chain_listener.rs"]
@@ -40,6 +44,14 @@ pub struct ChainListenerConfig {
     /// Wait after processing any notification (useful for rate limiting).
     #[arg(long = "listener-delay-after-ms", default_value = "0")]
     pub delay_after_ms: u64,
+
+    /// Periodically synchronize the chains that the wallet follows but doesn't own, and refresh
+    /// their cached heights and balances (0 means never).
+    #[arg(long = "listener-watch-refresh-ms", default_value = "0")]
+    pub watch_refresh_ms: u64,
+
+    #[command(flatten)]
+    pub balance_alerts: BalanceAlertConfig,
 }
 
 #[async_trait]
@@ -140,13 +152,24 @@ where
             client
         };
         let (_listen_handle, mut local_stream) = client.listen().await?;
+        let mut balance_watcher = BalanceWatcher::new(chain_id, config.balance_alerts.clone());
+        Self::check_balance(chain_id, &client, &mut balance_watcher).await;
         let mut timeout = storage.clock().current_time();
+        let mut next_refresh = Self::next_refresh(&storage, &config);
         loop {
-            let sleep = Box::pin(storage.clock().sleep_until(timeout));
+            let sleep = Box::pin(storage.clock().sleep_until(timeout.min(next_refresh)));
             let notification = match future::select(local_stream.next(), sleep).await {
                 Either::Left((Some(notification), _)) => notification,
                 Either::Left((None, _)) => break,
                 Either::Right(((), _)) => {
+                    if storage.clock().current_time() >= next_refresh {
+                        next_refresh = Self::next_refresh(&storage, &config);
+                        Self::refresh_followed_chain(chain_id, &client, &context).await;
+                        Self::check_balance(chain_id, &client, &mut balance_watcher).await;
+                    }
+                    if storage.clock().current_time() < timeout {
+                        continue;
+                    }
                     match client.lock().await.process_inbox_if_owned().await {
                         Err(error) => warn!(%error, "Failed to process inbox."),
                         Ok((_, None)) => timeout = Timestamp::from(u64::MAX),
@@ -177,6 +200,7 @@ where
                 let mut client_guard = client.lock().await;
                 context.lock().await.update_wallet(&mut *client_guard).await;
             }
+            Self::check_balance(chain_id, &client, &mut balance_watcher).await;
             let value = storage.read_hashed_certificate_value(hash).await?;
             let Some(executed_block) = value.inner().executed_block() else {
                 error!("NewBlock notification about value without a block: {hash}");
@@ -225,6 +249,63 @@ where
         Ok(())
     }
 
+    /// Returns the time of the next refresh of followed chains, or the maximum timestamp if
+    /// refreshing is disabled.
+    fn next_refresh(storage: &S, config: &ChainListenerConfig) -> Timestamp {
+        if config.watch_refresh_ms == 0 {
+            return Timestamp::from(u64::MAX);
+        }
+        let delta = TimeDelta::from_millis(config.watch_refresh_ms);
+        storage.clock().current_time().saturating_add(delta)
+    }
+
+    /// Synchronizes a chain that we follow but don't own from the validators, and updates the
+    /// wallet's cached height and balance. If the chain advanced, its new block is notified
+    /// to the subscribers of the chain. Owned chains are kept up to date by the notifications
+    /// from validators and don't need to be refreshed.
+    async fn refresh_followed_chain<C>(
+        chain_id: ChainId,
+        client: &ArcChainClient<P, S>,
+        context: &Arc<Mutex<C>>,
+    ) where
+        C: ClientContext<P> + Send + 'static,
+    {
+        let (is_followed, old_height) = match context.lock().await.wallet().get(chain_id) {
+            Some(chain) => (chain.key_pair.is_none(), chain.next_block_height),
+            None => return,
+        };
+        if !is_followed {
+            return;
+        }
+        let mut client_guard = client.lock().await;
+        if let Err(error) = client_guard.synchronize_from_validators().await {
+            warn!(%error, "Failed to synchronize followed chain {chain_id}.");
+            return;
+        }
+        context.lock().await.update_wallet(&mut *client_guard).await;
+        let new_height = client_guard.next_block_height();
+        if new_height != old_height {
+            info!("Followed chain {chain_id} advanced from height {old_height} to {new_height}.");
+            client_guard.notify_latest_block().await;
+        }
+    }
+
+    /// Reads the balance of a chain and alerts if it dropped below the threshold, if
+    /// low-balance alerts are enabled.
+    async fn check_balance(
+        chain_id: ChainId,
+        client: &ArcChainClient<P, S>,
+        watcher: &mut BalanceWatcher,
+    ) {
+        if !watcher.is_enabled() {
+            return;
+        }
+        match client.lock().await.local_balance().await {
+            Ok(balance) => watcher.check(balance),
+            Err(error) => warn!(%error, "Failed to read the balance of chain {chain_id}."),
+        }
+    }
+
     async fn maybe_sleep(delay_ms: u64) {
         if delay_ms > 0 {
             tokio::time::sleep(Duration::from_millis(delay_ms)).await;
@@ -0,0 +1,142 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The events of a chain, as printed by `linera watch`, either for humans or as JSON lines.
+
+use std::fmt;
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, BlockHeight, Timestamp},
+    identifiers::{ChainId, Owner},
+};
+use linera_chain::data_types::{Certificate, CertificateValue, IncomingMessage, Medium, Origin};
+use linera_execution::Operation;
+use serde::Serialize;
+
+#[cfg(test)]
+#[path = "unit_tests/chain_watch.rs"]
+mod tests;
+
+/// Something that happened to a watched chain.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ChainEvent {
+    /// A block was confirmed.
+    NewBlock(BlockSummary),
+    /// A message was added to the inbox, waiting to be picked up in a block.
+    NewIncomingMessage {
+        chain_id: ChainId,
+        origin: Origin,
+        height: BlockHeight,
+    },
+}
+
+/// The contents of a confirmed block, and the balance of the chain.
+#[derive(Debug, Serialize)]
+pub struct BlockSummary {
+    pub chain_id: ChainId,
+    pub height: BlockHeight,
+    pub hash: CryptoHash,
+    pub timestamp: Timestamp,
+    pub authenticated_signer: Option<Owner>,
+    pub operations: Vec<Operation>,
+    pub incoming_messages: Vec<IncomingMessage>,
+    pub outgoing_message_count: usize,
+    /// The balance of the chain when the block was reported.
+    pub balance: Amount,
+    /// The balance of the chain before the block, if known.
+    pub previous_balance: Option<Amount>,
+}
+
+impl BlockSummary {
+    /// Summarizes a confirmed block. Returns `None` for other certificates.
+    pub fn new(
+        certificate: &Certificate,
+        balance: Amount,
+        previous_balance: Option<Amount>,
+    ) -> Option<Self> {
+        let CertificateValue::ConfirmedBlock { executed_block } = certificate.value() else {
+            return None;
+        };
+        let block = &executed_block.block;
+        Some(BlockSummary {
+            chain_id: block.chain_id,
+            height: block.height,
+            hash: certificate.hash(),
+            timestamp: block.timestamp,
+            authenticated_signer: block.authenticated_signer,
+            operations: block.operations.clone(),
+            incoming_messages: block.incoming_messages.clone(),
+            outgoing_message_count: executed_block.outcome.messages.len(),
+            balance,
+            previous_balance,
+        })
+    }
+}
+
+impl fmt::Display for ChainEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainEvent::NewBlock(summary) => summary.fmt(f),
+            ChainEvent::NewIncomingMessage {
+                chain_id,
+                origin,
+                height,
+            } => write!(
+                f,
+                "New incoming message for chain {} from {}",
+                chain_id,
+                OriginAtHeight(origin, *height)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for BlockSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Block {} of chain {} at {}: {}",
+            self.height, self.chain_id, self.timestamp, self.hash
+        )?;
+        if let Some(signer) = self.authenticated_signer {
+            write!(f, "\n  Signed by {}", signer)?;
+        }
+        for operation in &self.operations {
+            write!(f, "\n  Operation: {:?}", operation)?;
+        }
+        for message in &self.incoming_messages {
+            write!(
+                f,
+                "\n  Incoming message from {}: {:?} ({:?})",
+                OriginAtHeight(&message.origin, message.event.height),
+                message.event.message,
+                message.action
+            )?;
+        }
+        if self.outgoing_message_count > 0 {
+            write!(f, "\n  Sent {} messages", self.outgoing_message_count)?;
+        }
+        match self.previous_balance {
+            Some(previous) if previous != self.balance => {
+                write!(f, "\n  Balance: {} (was {})", self.balance, previous)
+            }
+            _ => write!(f, "\n  Balance: {}", self.balance),
+        }
+    }
+}
+
+/// Displays the sender of a message, its channel if any, and the height of the sending block.
+struct OriginAtHeight<'a>(&'a Origin, BlockHeight);
+
+impl fmt::Display for OriginAtHeight<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let OriginAtHeight(origin, height) = self;
+        write!(f, "chain {} at height {}", origin.sender, height)?;
+        if let Medium::Channel(channel) = &origin.medium {
+            write!(f, " on channel {:?}", channel)?;
+        }
+        Ok(())
+    }
+}
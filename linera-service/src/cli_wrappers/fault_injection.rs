@@ -0,0 +1,136 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scenarios of faults injected into a [`LocalNet`], to test liveness and the retries of
+//! clients.
+//!
+//! A scenario is written in TOML. It may set the size of the validator set, and lists the
+//! faults to inject in order, each after a delay:
+//!
+//! ```toml
+//! validators = 4
+//! shards = 2
+//!
+//! [[steps]]
+//! wait_ms = 1000
+//! action = "kill_shard"
+//! validator = 3
+//! shard = 1
+//!
+//! [[steps]]
+//! action = "cross_chain"
+//! validator = 0
+//! delay_ms = 500
+//! duplicate_rate = 0.5
+//! ```
+
+use std::{path::Path, str::FromStr, time::Duration};
+
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::cli_wrappers::local_net::{CrossChainFaults, LocalNet, LocalNetConfig};
+
+#[cfg(test)]
+#[path = "../unit_tests/fault_injection.rs"]
+mod tests;
+
+/// A fault injected into a running [`LocalNet`].
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Fault {
+    /// Stops the server of a shard.
+    KillShard { validator: usize, shard: usize },
+    /// Restarts the server of a shard stopped by `kill_shard`.
+    RestartShard { validator: usize, shard: usize },
+    /// Makes a validator unreachable by clients, by stopping its proxy.
+    PartitionValidator { validator: usize },
+    /// Makes a validator stopped by `partition_validator` reachable again.
+    HealValidator { validator: usize },
+    /// Delays, drops or duplicates the cross-chain requests sent by the shards of a
+    /// validator. The shards are restarted to apply the change.
+    CrossChain {
+        validator: usize,
+        #[serde(flatten)]
+        faults: CrossChainFaults,
+    },
+}
+
+/// A fault and the delay before injecting it.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ScenarioStep {
+    /// How long to wait after the previous step, in milliseconds.
+    #[serde(default)]
+    pub wait_ms: u64,
+    #[serde(flatten)]
+    pub fault: Fault,
+}
+
+/// A list of faults to inject into a local network.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultScenario {
+    /// The number of validators to start, if not the default.
+    pub validators: Option<usize>,
+    /// The number of shards of each validator, if not the default.
+    pub shards: Option<usize>,
+    #[serde(default)]
+    pub steps: Vec<ScenarioStep>,
+}
+
+impl FromStr for FaultScenario {
+    type Err = toml::de::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        toml::from_str(input)
+    }
+}
+
+impl FaultScenario {
+    /// Reads a scenario from a TOML file.
+    pub fn read(path: &Path) -> Result<Self> {
+        let input = fs_err::read_to_string(path)?;
+        input
+            .parse()
+            .with_context(|| format!("parsing the fault scenario {}", path.display()))
+    }
+
+    /// Applies the size of the validator set of the scenario to a network configuration.
+    pub fn configure(&self, mut config: LocalNetConfig) -> LocalNetConfig {
+        if let Some(validators) = self.validators {
+            config.num_initial_validators = validators;
+        }
+        if let Some(shards) = self.shards {
+            config.num_shards = shards;
+        }
+        config
+    }
+
+    /// Injects the faults of the scenario into the network, in order.
+    pub async fn run(&self, net: &mut LocalNet) -> Result<()> {
+        for (index, step) in self.steps.iter().enumerate() {
+            tokio::time::sleep(Duration::from_millis(step.wait_ms)).await;
+            info!("Injecting fault {index}: {:?}", step.fault);
+            net.inject_fault(&step.fault)
+                .await
+                .with_context(|| format!("injecting fault {index}: {:?}", step.fault))?;
+        }
+        Ok(())
+    }
+}
+
+impl LocalNet {
+    /// Injects a single fault into the network.
+    pub async fn inject_fault(&mut self, fault: &Fault) -> Result<()> {
+        match *fault {
+            Fault::KillShard { validator, shard } => self.terminate_server(validator, shard).await,
+            Fault::RestartShard { validator, shard } => self.start_server(validator, shard).await,
+            Fault::PartitionValidator { validator } => self.terminate_proxy(validator).await,
+            Fault::HealValidator { validator } => self.start_proxy(validator).await,
+            Fault::CrossChain { validator, faults } => {
+                self.set_cross_chain_faults(validator, faults).await
+            }
+        }
+    }
+}
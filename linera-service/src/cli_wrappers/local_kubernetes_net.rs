@@ -1,7 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use anyhow::{anyhow, bail, ensure, Result};
 use async_trait::async_trait;
@@ -47,6 +47,7 @@ pub struct LocalKubernetesNetConfig {
     pub num_shards: usize,
     pub binaries: BuildArg,
     pub policy: ResourceControlPolicy,
+    pub genesis_overrides: Option<PathBuf>,
 }
 
 /// A wrapper of [`LocalKubernetesNetConfig`] to create a shared local Kubernetes network
@@ -97,6 +98,7 @@ impl SharedLocalKubernetesNetTestingConfig {
             num_shards: 4,
             binaries,
             policy: ResourceControlPolicy::devnet(),
+            genesis_overrides: None,
         })
     }
 }
@@ -135,6 +137,7 @@ impl LineraNetConfig for LocalKubernetesNetConfig {
                 self.num_other_initial_chains,
                 self.initial_amount,
                 self.policy,
+                self.genesis_overrides.as_deref(),
             )
             .await
             .unwrap();
@@ -20,6 +20,7 @@ use linera_execution::ResourceControlPolicy;
 use linera_views::rocks_db::create_rocks_db_test_path;
 #[cfg(all(feature = "scylladb", with_testing))]
 use linera_views::scylla_db::create_scylla_db_test_uri;
+use serde::Deserialize;
 use tempfile::{tempdir, TempDir};
 use tokio::process::{Child, Command};
 use tonic_health::pb::{
@@ -241,6 +242,7 @@ pub struct LocalNetConfig {
     pub num_initial_validators: usize,
     pub num_shards: usize,
     pub policy: ResourceControlPolicy,
+    pub genesis_overrides: Option<PathBuf>,
     pub storage_config_builder: StorageConfigBuilder,
     pub path_provider: PathProvider,
 }
@@ -259,6 +261,33 @@ pub struct LocalNet {
     set_init: HashSet<(usize, usize)>,
     storage_config: StorageConfig,
     path_provider: PathProvider,
+    /// The faults injected into the cross-chain requests of each validator.
+    cross_chain_faults: BTreeMap<usize, CrossChainFaults>,
+}
+
+/// Faults injected into the cross-chain requests sent by the servers of a validator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CrossChainFaults {
+    /// A delay before sending each cross-chain request, in milliseconds.
+    pub delay_ms: u64,
+    /// The rate at which cross-chain requests are dropped.
+    pub drop_rate: f32,
+    /// The rate at which cross-chain requests are sent twice.
+    pub duplicate_rate: f32,
+}
+
+impl CrossChainFaults {
+    fn server_args(&self) -> Vec<String> {
+        vec![
+            format!("--cross-chain-sender-delay-ms={}", self.delay_ms),
+            format!("--cross-chain-sender-failure-rate={}", self.drop_rate),
+            format!(
+                "--cross-chain-sender-duplicate-rate={}",
+                self.duplicate_rate
+            ),
+        ]
+    }
 }
 
 /// The name of the environment variable that allows specifying additional arguments to be passed
@@ -276,24 +305,25 @@ pub enum Database {
 
 /// The processes of a running validator.
 struct Validator {
-    proxy: Child,
-    servers: Vec<Child>,
+    /// The proxy, unless it was stopped to make the validator unreachable.
+    proxy: Option<Child>,
+    /// The servers of the running shards, by shard.
+    servers: BTreeMap<usize, Child>,
 }
 
 impl Validator {
     fn new(proxy: Child) -> Self {
         Self {
-            proxy,
-            servers: vec![],
+            proxy: Some(proxy),
+            servers: BTreeMap::new(),
         }
     }
 
     async fn terminate(&mut self) -> Result<()> {
-        self.proxy
-            .kill()
-            .await
-            .context("terminating validator proxy")?;
-        for server in &mut self.servers {
+        if let Some(proxy) = &mut self.proxy {
+            proxy.kill().await.context("terminating validator proxy")?;
+        }
+        for server in self.servers.values_mut() {
             server
                 .kill()
                 .await
@@ -302,13 +332,16 @@ impl Validator {
         Ok(())
     }
 
-    fn add_server(&mut self, server: Child) {
-        self.servers.push(server)
+    fn add_server(&mut self, shard: usize, server: Child) {
+        self.servers.insert(shard, server);
     }
 
     #[cfg(with_testing)]
-    async fn terminate_server(&mut self, index: usize) -> Result<()> {
-        let mut server = self.servers.remove(index);
+    async fn terminate_server(&mut self, shard: usize) -> Result<()> {
+        let mut server = self
+            .servers
+            .remove(&shard)
+            .context("server is not running")?;
         server
             .kill()
             .await
@@ -316,9 +349,18 @@ impl Validator {
         Ok(())
     }
 
+    #[cfg(with_testing)]
+    async fn terminate_proxy(&mut self) -> Result<()> {
+        let mut proxy = self.proxy.take().context("proxy is not running")?;
+        proxy.kill().await.context("terminating validator proxy")?;
+        Ok(())
+    }
+
     fn ensure_is_running(&mut self) -> Result<()> {
-        self.proxy.ensure_is_running()?;
-        for child in &mut self.servers {
+        if let Some(proxy) = &mut self.proxy {
+            proxy.ensure_is_running()?;
+        }
+        for child in self.servers.values_mut() {
             child.ensure_is_running()?;
         }
         Ok(())
@@ -340,6 +382,7 @@ impl LocalNetConfig {
             num_other_initial_chains: 10,
             initial_amount: Amount::from_tokens(1_000_000),
             policy: ResourceControlPolicy::devnet(),
+            genesis_overrides: None,
             testing_prng_seed: Some(37),
             table_name: linera_views::test_utils::generate_test_namespace(),
             num_initial_validators: 4,
@@ -381,6 +424,7 @@ impl LineraNetConfig for LocalNetConfig {
                 self.num_other_initial_chains,
                 self.initial_amount,
                 self.policy,
+                self.genesis_overrides.as_deref(),
             )
             .await
             .unwrap();
@@ -449,6 +493,7 @@ impl LocalNet {
             set_init: HashSet::new(),
             storage_config,
             path_provider,
+            cross_chain_faults: BTreeMap::new(),
         })
     }
 
@@ -554,6 +599,7 @@ impl LocalNet {
             .command_for_binary("linera-proxy")
             .await?
             .arg(format!("server_{}.json", validator))
+            .args(["--genesis", "genesis.json"])
             .spawn_into()?;
 
         match self.network {
@@ -639,6 +685,9 @@ impl LocalNet {
             command.args(var.split_whitespace());
         }
         command.arg("run");
+        if let Some(faults) = self.cross_chain_faults.get(&validator) {
+            command.args(faults.server_args());
+        }
         let child = command
             .args(["--storage", &storage])
             .args(["--server", &format!("server_{}.json", validator)])
@@ -672,7 +721,7 @@ impl LocalNet {
         let mut validator_proxy = Validator::new(proxy);
         for shard in 0..self.num_shards {
             let server = self.run_server(validator, shard).await?;
-            validator_proxy.add_server(server);
+            validator_proxy.add_server(shard, server);
         }
         self.running_validators.insert(validator, validator_proxy);
         Ok(())
@@ -716,11 +765,71 @@ impl LocalNet {
     }
 
     pub async fn start_server(&mut self, validator: usize, shard: usize) -> Result<()> {
+        ensure!(
+            self.running_validators
+                .get(&validator)
+                .context("could not find server")?
+                .servers
+                .get(&shard)
+                .is_none(),
+            "server {validator}:{shard} is already running"
+        );
         let server = self.run_server(validator, shard).await?;
         self.running_validators
             .get_mut(&validator)
             .context("could not find server")?
-            .add_server(server);
+            .add_server(shard, server);
+        Ok(())
+    }
+
+    /// Stops the proxy of a validator, so that it cannot be reached by clients, while its
+    /// shards keep running.
+    pub async fn terminate_proxy(&mut self, validator: usize) -> Result<()> {
+        self.running_validators
+            .get_mut(&validator)
+            .context("validator not found")?
+            .terminate_proxy()
+            .await
+    }
+
+    /// Restarts the proxy of a validator stopped by [`LocalNet::terminate_proxy`].
+    pub async fn start_proxy(&mut self, validator: usize) -> Result<()> {
+        ensure!(
+            self.running_validators
+                .get(&validator)
+                .context("validator not found")?
+                .proxy
+                .is_none(),
+            "the proxy of validator {validator} is already running"
+        );
+        let proxy = self.run_proxy(validator).await?;
+        self.running_validators
+            .get_mut(&validator)
+            .context("validator not found")?
+            .proxy = Some(proxy);
+        Ok(())
+    }
+
+    /// Sets the options of the cross-chain requests sent by the servers of a validator, and
+    /// restarts its running servers to apply them.
+    pub async fn set_cross_chain_faults(
+        &mut self,
+        validator: usize,
+        faults: CrossChainFaults,
+    ) -> Result<()> {
+        let shards = self
+            .running_validators
+            .get(&validator)
+            .context("validator not found")?
+            .servers
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        self.cross_chain_faults.insert(validator, faults);
+        for shard in shards {
+            self.terminate_server(validator, shard).await?;
+            self.start_server(validator, shard).await?;
+        }
         Ok(())
     }
 }
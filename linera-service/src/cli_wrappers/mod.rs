@@ -7,6 +7,9 @@
 #[cfg(feature = "kubernetes")]
 /// How to run docker operations
 pub mod docker;
+#[cfg(with_testing)]
+/// How to inject faults into Linera validators running locally.
+pub mod fault_injection;
 
 #[cfg(feature = "kubernetes")]
 /// How to run helmfile operations
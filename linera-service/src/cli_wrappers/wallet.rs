@@ -160,6 +160,7 @@ impl ClientWrapper {
         num_other_initial_chains: u32,
         initial_funding: Amount,
         policy: ResourceControlPolicy,
+        overrides: Option<&Path>,
     ) -> Result<()> {
         let ResourceControlPolicy {
             block,
@@ -173,8 +174,16 @@ impl ClientWrapper {
             operation_byte,
             message,
             message_byte,
+            host_call_fuel,
+            maximum_fuel_per_block,
             maximum_bytes_read_per_block,
             maximum_bytes_written_per_block,
+            maximum_bytecode_size,
+            maximum_call_depth,
+            maximum_bytes_stored_per_application,
+            maximum_operations_per_block,
+            maximum_incoming_messages_per_block,
+            maximum_block_size,
         } = policy;
         let mut command = self.command().await?;
         command
@@ -196,6 +205,11 @@ impl ClientWrapper {
             .args(["--operation-price", &operation.to_string()])
             .args(["--operation-byte-price", &operation_byte.to_string()])
             .args(["--message-price", &message.to_string()])
+            .args(["--host-call-fuel", &host_call_fuel.to_string()])
+            .args([
+                "--maximum-fuel-per-block",
+                &maximum_fuel_per_block.to_string(),
+            ])
             .args([
                 "--maximum-bytes-read-per-block",
                 &maximum_bytes_read_per_block.to_string(),
@@ -203,10 +217,31 @@ impl ClientWrapper {
             .args([
                 "--maximum-bytes-written-per-block",
                 &maximum_bytes_written_per_block.to_string(),
-            ]);
+            ])
+            .args([
+                "--maximum-bytecode-size",
+                &maximum_bytecode_size.to_string(),
+            ])
+            .args(["--maximum-call-depth", &maximum_call_depth.to_string()])
+            .args([
+                "--maximum-bytes-stored-per-application",
+                &maximum_bytes_stored_per_application.to_string(),
+            ])
+            .args([
+                "--maximum-operations-per-block",
+                &maximum_operations_per_block.to_string(),
+            ])
+            .args([
+                "--maximum-incoming-messages-per-block",
+                &maximum_incoming_messages_per_block.to_string(),
+            ])
+            .args(["--maximum-block-size", &maximum_block_size.to_string()]);
         if let Some(seed) = self.testing_prng_seed {
             command.arg("--testing-prng-seed").arg(seed.to_string());
         }
+        if let Some(overrides) = overrides {
+            command.arg("--overrides").arg(overrides);
+        }
         command.spawn_and_wait_for_stdout().await?;
         Ok(())
     }
@@ -1149,8 +1184,10 @@ impl Faucet {
     }
 
     pub async fn version_info(&self) -> Result<VersionInfo> {
-        let query =
-            "query { version { crateVersion gitCommit gitDirty rpcHash graphqlHash witHash } }";
+        let query = "query { version { \
+                crateVersion gitCommit gitDirty rpcHash graphqlHash witHash \
+                buildTimestamp features \
+            } }";
         let client = reqwest_client();
         let response = client
             .post(&self.url)
@@ -1183,6 +1220,11 @@ impl Faucet {
             .context("could not parse graphql hash")?;
         let wit_hash = serde_json::from_value(value["data"]["version"]["witHash"].take())
             .context("could not parse wit hash")?;
+        let build_timestamp =
+            serde_json::from_value(value["data"]["version"]["buildTimestamp"].take())
+                .context("could not parse build timestamp")?;
+        let features = serde_json::from_value(value["data"]["version"]["features"].take())
+            .context("could not parse features")?;
         Ok(VersionInfo {
             crate_version,
             git_commit,
@@ -1190,6 +1232,8 @@ impl Faucet {
             rpc_hash,
             graphql_hash,
             wit_hash,
+            build_timestamp,
+            features,
         })
     }
 
@@ -1199,6 +1243,25 @@ impl Faucet {
                 messageId chainId certificateHash \
             }} }}"
         );
+        self.do_claim(query).await
+    }
+
+    /// Claims a chain from a faucet that requires invitations.
+    pub async fn claim_with_invitation(
+        &self,
+        public_key: &PublicKey,
+        invitation: String,
+    ) -> Result<ClaimOutcome> {
+        let invitation = Value::String(invitation);
+        let query = format!(
+            "mutation {{ claim(publicKey: \"{public_key}\", invitation: {invitation}) {{ \
+                messageId chainId certificateHash \
+            }} }}"
+        );
+        self.do_claim(query).await
+    }
+
+    async fn do_claim(&self, query: String) -> Result<ClaimOutcome> {
         let client = reqwest_client();
         let response = client
             .post(&self.url)
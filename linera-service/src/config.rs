@@ -3,41 +3,123 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    io::{BufRead, BufReader, BufWriter, Write},
+    collections::BTreeSet,
+    ffi::OsString,
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
 };
 
-use anyhow::{bail, Context as _};
+use anyhow::{bail, ensure, Context as _};
 use fs4::FileExt as _;
 use fs_err::{self, File, OpenOptions};
 use linera_base::{
-    crypto::{BcsSignable, KeyPair, PublicKey},
+    crypto::{BcsSignable, CryptoHash, KeyPair, PublicKey},
     data_types::{Amount, Timestamp},
     identifiers::{ChainDescription, ChainId},
+    ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_execution::{
     committee::{Committee, ValidatorName, ValidatorState},
     ResourceControlPolicy,
 };
-use linera_rpc::config::{ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig};
+use linera_rpc::config::{
+    RpcPolicies, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+};
 use linera_storage::Storage;
 use linera_views::views::ViewError;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 
 use crate::wallet::Wallet;
 
+#[cfg(test)]
+#[path = "unit_tests/config.rs"]
+mod tests;
+
+/// The name of the field recording the format version of a configuration file.
+const VERSION_FIELD: &str = "version";
+
+/// The first bytes of a wallet file. They are followed by the SHA-256 checksum of the
+/// contents of the wallet, then by these contents in JSON. Files without this header are
+/// wallets in the plain JSON format of older versions.
+const WALLET_HEADER: &[u8] = b"LINERA WALLET 1\n";
+
+/// The length of the checksum of a wallet file.
+const WALLET_CHECKSUM_LENGTH: usize = 32;
+
+/// An error while reading a configuration file.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("invalid configuration file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid version field in configuration file {}", .path.display())]
+    InvalidVersion { path: PathBuf },
+    #[error(
+        "configuration file {} has version {found}, but this build only supports versions up \
+         to {supported}: please upgrade Linera",
+        .path.display()
+    )]
+    UnsupportedVersion {
+        path: PathBuf,
+        found: u64,
+        supported: u64,
+    },
+}
+
+/// A configuration file that can be read, upgrading it from older format versions if needed.
+///
+/// The format version is recorded in a top-level `version` field. Files written before the
+/// field was introduced have version 0.
 pub trait Import: DeserializeOwned {
-    fn read(path: &Path) -> Result<Self, std::io::Error> {
+    /// The format version written by this build.
+    const VERSION: u64 = 1;
+
+    /// Converts the JSON contents of a file from format `version` to `version + 1`.
+    fn upgrade(version: u64, value: &mut serde_json::Value) -> Result<(), ConfigError> {
+        // Version 1 only introduced the version field.
+        let _ = (version, value);
+        Ok(())
+    }
+
+    fn read(path: &Path) -> Result<Self, ConfigError> {
         let data = fs_err::read(path)?;
-        Ok(serde_json::from_slice(data.as_slice())?)
+        let mut value: serde_json::Value = serde_json::from_slice(data.as_slice())?;
+        let version = match value
+            .as_object_mut()
+            .and_then(|object| object.remove(VERSION_FIELD))
+        {
+            None => 0,
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| ConfigError::InvalidVersion {
+                    path: path.to_owned(),
+                })?,
+        };
+        if version > Self::VERSION {
+            return Err(ConfigError::UnsupportedVersion {
+                path: path.to_owned(),
+                found: version,
+                supported: Self::VERSION,
+            });
+        }
+        for version in version..Self::VERSION {
+            Self::upgrade(version, &mut value)?;
+        }
+        Ok(serde_json::from_value(value)?)
     }
 }
 
-pub trait Export: Serialize {
+pub trait Export: Import + Serialize {
     fn write(&self, path: &Path) -> Result<(), std::io::Error> {
         let file = OpenOptions::new().create(true).write(true).open(path)?;
         let mut writer = BufWriter::new(file);
-        let data = serde_json::to_string_pretty(self).unwrap();
+        let mut value = serde_json::to_value(self).unwrap();
+        if let Some(object) = value.as_object_mut() {
+            object.insert(VERSION_FIELD.to_owned(), Self::VERSION.into());
+        }
+        let data = serde_json::to_string_pretty(&value).unwrap();
         writer.write_all(data.as_ref())?;
         writer.write_all(b"\n")?;
         Ok(())
@@ -59,6 +141,14 @@ pub struct ValidatorServerConfig {
     pub validator: ValidatorConfig,
     pub key: KeyPair,
     pub internal_network: ValidatorInternalNetworkConfig,
+    /// A new key being registered on the admin chain. It is used instead of `key` for the
+    /// chains whose current committee contains it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_key: Option<KeyPair>,
+    /// The timeouts of the proxy and the retries of the cross-chain messages, unless set on
+    /// the command line.
+    #[serde(default, skip_serializing_if = "RpcPolicies::is_empty")]
+    pub rpc_policies: RpcPolicies,
 }
 
 impl Import for ValidatorServerConfig {}
@@ -90,6 +180,30 @@ impl CommitteeConfig {
             .collect();
         Committee::new(validators, policy)
     }
+
+    /// Returns a description of each problem that would make this committee unusable:
+    /// having no validators, or several validators with the same key or address.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.validators.is_empty() {
+            problems.push("the committee has no validators".to_owned());
+        }
+        let mut names = BTreeSet::new();
+        let mut addresses = BTreeSet::new();
+        for validator in &self.validators {
+            if !names.insert(validator.name) {
+                problems.push(format!("validator {} is listed twice", validator.name));
+            }
+            let address = (validator.network.host.clone(), validator.network.port);
+            if !addresses.insert(address) {
+                problems.push(format!(
+                    "several validators use the address {}:{}",
+                    validator.network.host, validator.network.port
+                ));
+            }
+        }
+        problems
+    }
 }
 
 /// A guard that keeps an exclusive lock on a file.
@@ -98,13 +212,19 @@ pub struct FileLock {
 }
 
 impl FileLock {
-    /// Acquires an exclusive lock on a provided `file`, returning a [`FileLock`] which will
-    /// release the lock when dropped.
-    pub fn new(file: File, path: &Path) -> Result<Self, anyhow::Error> {
+    /// Acquires an exclusive lock on the lock file of the wallet at `path`, creating it if
+    /// needed, and returns a [`FileLock`] which will release the lock when dropped.
+    ///
+    /// The wallet file itself is replaced on each write, so it can't hold the lock.
+    pub fn new(path: &Path) -> Result<Self, anyhow::Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(with_suffix(path, ".lock"))?;
         file.file().try_lock_exclusive().with_context(|| {
             format!(
-                "Error getting write lock to wallet \"{}\". Please make sure the file exists \
-                 and that it is not in use by another process already.",
+                "Error getting write lock to wallet \"{}\". Please make sure that it is not in \
+                 use by another process already.",
                 path.display()
             )
         })?;
@@ -121,12 +241,73 @@ impl Drop for FileLock {
     }
 }
 
+/// Returns the path of a file next to `path`, with the given suffix appended to its name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    name.into()
+}
+
+/// Encodes the contents of a wallet file: the header, the checksum and the wallet in JSON.
+fn encode_wallet(wallet: &Wallet) -> Result<Vec<u8>, anyhow::Error> {
+    let contents = serde_json::to_vec_pretty(wallet).context("failed to serialize the wallet")?;
+    let mut bytes =
+        Vec::with_capacity(WALLET_HEADER.len() + WALLET_CHECKSUM_LENGTH + contents.len());
+    bytes.extend_from_slice(WALLET_HEADER);
+    bytes.extend_from_slice(&Sha256::digest(&contents));
+    bytes.extend_from_slice(&contents);
+    Ok(bytes)
+}
+
+/// Decodes the contents of a wallet file, checking that they are complete and unchanged.
+/// Wallets in the plain JSON format of older versions are accepted too.
+fn decode_wallet(bytes: &[u8]) -> Result<Wallet, anyhow::Error> {
+    let Some(rest) = bytes.strip_prefix(WALLET_HEADER) else {
+        return serde_json::from_slice(bytes).context("invalid wallet in JSON");
+    };
+    ensure!(
+        rest.len() >= WALLET_CHECKSUM_LENGTH,
+        "the wallet file is truncated"
+    );
+    let (checksum, contents) = rest.split_at(WALLET_CHECKSUM_LENGTH);
+    ensure!(
+        Sha256::digest(contents).as_slice() == checksum,
+        "the checksum of the wallet doesn't match its contents"
+    );
+    serde_json::from_slice(contents).context("invalid wallet contents")
+}
+
+/// Reads the wallet at `path`. If it is corrupted, e.g. because an older version was
+/// interrupted while writing it, the backup of its previous version is read instead.
+fn read_wallet(path: &Path) -> Result<Wallet, anyhow::Error> {
+    let error = match decode_wallet(&fs_err::read(path)?) {
+        Ok(wallet) => return Ok(wallet),
+        Err(error) => error,
+    };
+    let backup_path = with_suffix(path, ".bak");
+    if let Ok(wallet) = fs_err::read(&backup_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| decode_wallet(&bytes))
+    {
+        tracing::warn!(
+            "The wallet \"{}\" is corrupted ({error}): using its backup \"{}\" instead. It \
+             is restored the next time the wallet is saved.",
+            path.display(),
+            backup_path.display()
+        );
+        return Ok(wallet);
+    }
+    Err(error.context(format!("failed to read the wallet \"{}\"", path.display())))
+}
+
 /// A wrapper around `Wallet` which owns a [`FileLock`] to prevent
 /// two processes accessing it at the same time.
+///
+/// An ephemeral wallet state is only kept in memory: it holds no lock, and is never written.
 pub struct WalletState {
     inner: Wallet,
     wallet_path: PathBuf,
-    _lock: FileLock,
+    _lock: Option<FileLock>,
 }
 
 impl WalletState {
@@ -143,66 +324,108 @@ impl WalletState {
     }
 
     pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
-        let file = OpenOptions::new().read(true).write(true).open(path)?;
-        let file_lock = FileLock::new(file, path)?;
-        let inner = serde_json::from_reader(BufReader::new(&file_lock.file))?;
+        ensure!(
+            path.exists(),
+            "There is no wallet at \"{}\".",
+            path.display()
+        );
+        let file_lock = FileLock::new(path)?;
+        let inner = read_wallet(path)?;
         Ok(Self {
             inner,
             wallet_path: path.into(),
-            _lock: file_lock,
+            _lock: Some(file_lock),
         })
     }
 
+    /// Reads the wallet without locking the file, for use in memory only. New key pairs are
+    /// derived deterministically from the given seed, and changes are never written back, so
+    /// several processes can use the same wallet file at the same time.
+    pub fn read_ephemeral(path: &Path, seed: u64) -> Result<Self, anyhow::Error> {
+        let mut inner = read_wallet(path)?;
+        inner.reseed_prng(seed);
+        Ok(Self {
+            inner,
+            wallet_path: path.into(),
+            _lock: None,
+        })
+    }
+
+    /// Returns whether this wallet state is only kept in memory.
+    pub fn is_ephemeral(&self) -> bool {
+        self._lock.is_none()
+    }
+
+    /// Reads the wallet at `path`, or creates a new one if there is no wallet there yet. The
+    /// new wallet is only written by [`WalletState::write`].
     pub fn create(
         path: &Path,
         genesis_config: GenesisConfig,
         testing_prng_seed: Option<u64>,
     ) -> Result<Self, anyhow::Error> {
-        let file = Self::open_options().read(true).open(path)?;
-        let file_lock = FileLock::new(file, path)?;
-        let mut reader = BufReader::new(&file_lock.file);
-        if reader.fill_buf()?.is_empty() {
-            Ok(Self {
-                inner: Wallet::new(genesis_config, testing_prng_seed),
-                wallet_path: path.into(),
-                _lock: file_lock,
-            })
-        } else {
-            let inner = serde_json::from_reader(reader)?;
-            Ok(Self {
-                inner,
-                wallet_path: path.into(),
-                _lock: file_lock,
-            })
-        }
+        let file_lock = FileLock::new(path)?;
+        let inner = match fs_err::metadata(path) {
+            Ok(metadata) if metadata.len() > 0 => read_wallet(path)?,
+            // An empty wallet file may have been truncated: its backup is used if it has one.
+            Ok(_) if with_suffix(path, ".bak").exists() => read_wallet(path)?,
+            Ok(_) => Wallet::new(genesis_config, testing_prng_seed),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Wallet::new(genesis_config, testing_prng_seed)
+            }
+            Err(error) => return Err(error.into()),
+        };
+        Ok(Self {
+            inner,
+            wallet_path: path.into(),
+            _lock: Some(file_lock),
+        })
     }
 
     /// Writes the wallet to disk.
     ///
-    /// The contents of the wallet need to be over-written completely, so
-    /// a temporary file is created as a backup in case a crash occurs while
-    /// writing to disk.
+    /// The new contents are written to a temporary file, which is then renamed to the
+    /// wallet file, so that the wallet is never left partially written, even if a crash
+    /// occurs. The previous version of the wallet is kept as a backup, to recover from a
+    /// wallet file corrupted in some other way.
     ///
-    /// The temporary file is then renamed to the original wallet name. If
-    /// serialization or writing to disk fails, the temporary filed is
-    /// deleted.
+    /// Ephemeral wallet states are not written.
     pub fn write(&mut self) -> Result<(), anyhow::Error> {
-        let mut temp_file_path = self.wallet_path.clone();
-        temp_file_path.set_extension("json.bak");
-        let backup_file = Self::open_options().open(&temp_file_path)?;
-        let mut temp_file_writer = BufWriter::new(backup_file);
-        if let Err(e) = serde_json::to_writer_pretty(&mut temp_file_writer, &self.inner) {
-            fs_err::remove_file(&temp_file_path)?;
-            bail!("failed to serialize the wallet state: {}", e)
+        if self.is_ephemeral() {
+            return Ok(());
         }
-        if let Err(e) = temp_file_writer.flush() {
+        let bytes = encode_wallet(&self.inner)?;
+        let temp_file_path = with_suffix(&self.wallet_path, ".tmp");
+        if let Err(error) = Self::write_synced(&temp_file_path, &bytes) {
             fs_err::remove_file(&temp_file_path)?;
-            bail!("failed to write the wallet state: {}", e);
+            bail!("failed to write the wallet state: {}", error);
+        }
+        // Only an intact previous version replaces the backup.
+        if let Ok(previous) = fs_err::read(&self.wallet_path) {
+            if decode_wallet(&previous).is_ok() {
+                Self::write_synced(&with_suffix(&self.wallet_path, ".bak"), &previous)?;
+            }
         }
         fs_err::rename(&temp_file_path, &self.wallet_path)?;
+        #[cfg(target_family = "unix")]
+        if let Some(directory) = self.wallet_path.parent() {
+            // Make the rename itself durable.
+            let directory = if directory.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                directory
+            };
+            File::open(directory)?.sync_all()?;
+        }
         Ok(())
     }
 
+    /// Replaces the contents of the file at `path` and waits until they are on disk.
+    fn write_synced(path: &Path, bytes: &[u8]) -> Result<(), std::io::Error> {
+        let mut file = Self::open_options().truncate(true).open(path)?;
+        file.write_all(bytes)?;
+        file.sync_all()
+    }
+
     /// Returns options for opening and writing to the wallet file, creating it if it doesn't
     /// exist. On Unix, this restricts read and write permissions to the current user.
     // TODO(#1924): Implement better key management.
@@ -223,6 +446,9 @@ pub struct GenesisConfig {
     pub chains: Vec<(PublicKey, Amount)>,
     pub policy: ResourceControlPolicy,
     pub network_name: String,
+    /// The round timeouts of the initial chains.
+    #[serde(default)]
+    pub timeout_config: TimeoutConfig,
 }
 
 impl Import for GenesisConfig {}
@@ -244,6 +470,7 @@ impl GenesisConfig {
             chains: Vec::new(),
             policy,
             network_name,
+            timeout_config: TimeoutConfig::default(),
         }
     }
 
@@ -255,12 +482,16 @@ impl GenesisConfig {
         let committee = self.create_committee();
         for (chain_number, (public_key, balance)) in (0..).zip(&self.chains) {
             let description = ChainDescription::Root(chain_number);
+            let ownership = ChainOwnership {
+                timeout_config: self.timeout_config.clone(),
+                ..ChainOwnership::single(*public_key)
+            };
             storage
                 .create_chain(
                     committee.clone(),
                     self.admin_id,
                     description,
-                    *public_key,
+                    ownership,
                     *balance,
                     self.timestamp,
                 )
@@ -272,4 +503,104 @@ impl GenesisConfig {
     pub fn create_committee(&self) -> Committee {
         self.committee.clone().into_committee(self.policy.clone())
     }
+
+    /// Returns the hash identifying the network created from this configuration.
+    pub fn hash(&self) -> CryptoHash {
+        CryptoHash::new(self)
+    }
+
+    /// Returns a description of each problem that would prevent the network from starting:
+    /// those of the committee, an admin chain that isn't one of the initial chains, and
+    /// initial balances adding up to more than the maximal amount.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = self.committee.problems();
+        let is_admin_chain_created = (0..)
+            .take(self.chains.len())
+            .any(|index| ChainId::root(index) == self.admin_id);
+        if !is_admin_chain_created {
+            problems.push(format!(
+                "the admin chain {} is not one of the {} initial chains",
+                self.admin_id,
+                self.chains.len()
+            ));
+        }
+        let total_balance = self
+            .chains
+            .iter()
+            .try_fold(Amount::ZERO, |total, (_, balance)| total.try_add(*balance));
+        if total_balance.is_err() {
+            problems.push("the initial balances add up to more than the maximal amount".into());
+        }
+        problems
+    }
+}
+
+/// What each validator receives, together with the genesis configuration, to join a new
+/// network.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidatorBootstrap {
+    /// The public configuration of the validator, as listed in the committee.
+    pub validator: ValidatorConfig,
+    /// The name of the network.
+    pub network_name: String,
+    /// The hash of the genesis configuration, to check the copy received.
+    pub genesis_config_hash: CryptoHash,
+}
+
+impl Import for ValidatorBootstrap {}
+impl Export for ValidatorBootstrap {}
+
+/// Overrides of the protocol parameters of a new genesis configuration, e.g. to make a local
+/// test network fast to iterate on.
+///
+/// Each section only lists the fields to change, using the same names and units as in the
+/// genesis configuration, e.g.:
+///
+/// ```json
+/// {
+///   "policy": { "block": "0.000001", "maximum_bytes_read_per_block": 1000000000 },
+///   "timeout_config": { "base_timeout": 1000000, "timeout_increment": 100000 }
+/// }
+/// ```
+///
+/// Amounts are in tokens, and durations in microseconds.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisOverrides {
+    /// The fees and limits to change in the resource control policy.
+    #[serde(default)]
+    pub policy: serde_json::Map<String, serde_json::Value>,
+    /// The round timeouts to change for the initial chains.
+    #[serde(default)]
+    pub timeout_config: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Import for GenesisOverrides {}
+
+impl GenesisOverrides {
+    /// Applies the overrides to the genesis configuration.
+    pub fn apply(&self, genesis_config: &mut GenesisConfig) -> anyhow::Result<()> {
+        genesis_config.policy = Self::merge(&genesis_config.policy, &self.policy)
+            .context("invalid policy overrides")?;
+        genesis_config.timeout_config =
+            Self::merge(&genesis_config.timeout_config, &self.timeout_config)
+                .context("invalid timeout overrides")?;
+        Ok(())
+    }
+
+    /// Returns a copy of `value` with the given fields replaced.
+    fn merge<T: Serialize + DeserializeOwned>(
+        value: &T,
+        overrides: &serde_json::Map<String, serde_json::Value>,
+    ) -> anyhow::Result<T> {
+        let mut value = serde_json::to_value(value)?;
+        let fields = value
+            .as_object_mut()
+            .context("only structures can be overridden")?;
+        for (name, field) in overrides {
+            ensure!(fields.contains_key(name), "unknown field {name}");
+            fields.insert(name.clone(), field.clone());
+        }
+        Ok(serde_json::from_value(value)?)
+    }
 }
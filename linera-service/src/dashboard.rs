@@ -0,0 +1,284 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A web page showing the activity of the validators of a committee.
+//!
+//! The dashboard regularly queries every validator through its public endpoint for the state
+//! of a chosen set of chains, and aggregates the block heights, latencies and errors it
+//! observes. The page is served at `/`, and the same data is available as JSON at `/status`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    net::SocketAddr,
+    num::NonZeroU16,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{response, Extension, Json, Router};
+use futures::{future, lock::Mutex};
+use linera_base::{data_types::BlockHeight, identifiers::ChainId};
+use linera_core::{
+    data_types::ChainInfoQuery,
+    node::{ValidatorNode as _, ValidatorNodeProvider},
+};
+use linera_execution::committee::{Committee, ValidatorName};
+use serde::Serialize;
+use tower_http::cors::CorsLayer;
+use tracing::{info, warn};
+
+#[cfg(test)]
+#[path = "unit_tests/dashboard.rs"]
+mod tests;
+
+/// The activity of a validator, as observed by the dashboard.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ValidatorActivity {
+    /// The network address of the validator.
+    pub address: String,
+    /// The version of the validator's software, if known.
+    pub version: Option<String>,
+    /// The next block height of each chain, as reported by the validator.
+    pub next_block_heights: BTreeMap<ChainId, BlockHeight>,
+    /// The number of queries sent to the validator.
+    pub queries: u64,
+    /// The number of queries that failed.
+    pub errors: u64,
+    /// The latency of the latest successful query, in milliseconds.
+    pub latest_latency_ms: Option<u64>,
+    /// The average latency of the successful queries, in milliseconds.
+    pub average_latency_ms: Option<u64>,
+    /// The latest error returned by the validator, if any.
+    pub latest_error: Option<String>,
+    #[serde(skip)]
+    total_latency: Duration,
+}
+
+impl ValidatorActivity {
+    fn new(address: String) -> Self {
+        Self {
+            address,
+            ..Self::default()
+        }
+    }
+
+    /// Records a successful query for the given chain.
+    fn record_success(&mut self, chain_id: ChainId, height: BlockHeight, latency: Duration) {
+        self.queries += 1;
+        self.total_latency += latency;
+        let successes = self.queries - self.errors;
+        self.latest_latency_ms = Some(latency.as_millis() as u64);
+        self.average_latency_ms = Some((self.total_latency.as_millis() as u64) / successes);
+        self.next_block_heights.insert(chain_id, height);
+    }
+
+    /// Records a failed query.
+    fn record_error(&mut self, error: String) {
+        self.queries += 1;
+        self.errors += 1;
+        self.latest_error = Some(error);
+    }
+
+    /// Returns the proportion of the queries that failed.
+    pub fn error_rate(&self) -> f64 {
+        if self.queries == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.queries as f64
+    }
+}
+
+type Activities = Arc<Mutex<BTreeMap<ValidatorName, ValidatorActivity>>>;
+
+/// A service polling the validators of a committee, and serving a page with their activity.
+pub struct DashboardService<P> {
+    port: NonZeroU16,
+    node_provider: P,
+    chains: Vec<ChainId>,
+    interval: Duration,
+    activities: Activities,
+}
+
+impl<P> DashboardService<P>
+where
+    P: ValidatorNodeProvider + Send + Sync + 'static,
+{
+    /// Creates a dashboard for the validators of the committee, polling them for the given
+    /// chains at the given interval.
+    pub fn new(
+        port: NonZeroU16,
+        node_provider: P,
+        committee: &Committee,
+        chains: Vec<ChainId>,
+        interval: Duration,
+    ) -> Self {
+        let activities = committee
+            .validators()
+            .iter()
+            .map(|(name, state)| {
+                let activity = ValidatorActivity::new(state.network_address.clone());
+                (*name, activity)
+            })
+            .collect();
+        Self {
+            port,
+            node_provider,
+            chains,
+            interval,
+            activities: Arc::new(Mutex::new(activities)),
+        }
+    }
+
+    /// Runs the dashboard: polls the validators in the background and serves the page.
+    pub async fn run(self) -> anyhow::Result<()> {
+        let port = self.port.get();
+        let activities = self.activities.clone();
+        let app = Router::new()
+            .route("/", axum::routing::get(Self::page_handler))
+            .route("/status", axum::routing::get(Self::status_handler))
+            .layer(Extension(activities))
+            .layer(CorsLayer::permissive());
+
+        tokio::spawn(async move {
+            loop {
+                self.poll().await;
+                tokio::time::sleep(self.interval).await;
+            }
+        });
+
+        info!("Validator dashboard: http://localhost:{}", port);
+
+        axum::serve(
+            tokio::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port))).await?,
+            app,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Queries every validator for its version and for the state of each chain.
+    async fn poll(&self) {
+        let addresses = self
+            .activities
+            .lock()
+            .await
+            .iter()
+            .map(|(name, activity)| (*name, activity.address.clone()))
+            .collect::<Vec<_>>();
+        future::join_all(
+            addresses
+                .into_iter()
+                .map(|(name, address)| self.poll_validator(name, address)),
+        )
+        .await;
+    }
+
+    async fn poll_validator(&self, name: ValidatorName, address: String) {
+        let nodes = self
+            .node_provider
+            .make_nodes_from_list::<Vec<_>, _>([(name, &address)]);
+        let mut node = match nodes {
+            Ok(mut nodes) => nodes.pop().expect("one node should be created").1,
+            Err(error) => {
+                warn!("Failed to connect to validator {name} at {address}: {error}");
+                if let Some(activity) = self.activities.lock().await.get_mut(&name) {
+                    activity.record_error(error.to_string());
+                }
+                return;
+            }
+        };
+        let version = node.get_version_info().await.ok();
+        for chain_id in &self.chains {
+            let start = Instant::now();
+            let result = node
+                .handle_chain_info_query(ChainInfoQuery::new(*chain_id))
+                .await;
+            let latency = start.elapsed();
+            let mut activities = self.activities.lock().await;
+            let Some(activity) = activities.get_mut(&name) else {
+                return;
+            };
+            match result {
+                Ok(response) => {
+                    activity.record_success(*chain_id, response.info.next_block_height, latency)
+                }
+                Err(error) => {
+                    warn!("Failed to query validator {name} for chain {chain_id}: {error}");
+                    activity.record_error(error.to_string());
+                }
+            }
+        }
+        if let Some(version) = version {
+            if let Some(activity) = self.activities.lock().await.get_mut(&name) {
+                activity.version = Some(version.crate_version.to_string());
+            }
+        }
+    }
+
+    async fn page_handler(Extension(activities): Extension<Activities>) -> response::Html<String> {
+        response::Html(render_page(&*activities.lock().await))
+    }
+
+    async fn status_handler(
+        Extension(activities): Extension<Activities>,
+    ) -> Json<BTreeMap<ValidatorName, ValidatorActivity>> {
+        Json(activities.lock().await.clone())
+    }
+}
+
+/// Returns the HTML page showing the activity of the validators.
+fn render_page(activities: &BTreeMap<ValidatorName, ValidatorActivity>) -> String {
+    let chains = activities
+        .values()
+        .flat_map(|activity| activity.next_block_heights.keys())
+        .collect::<BTreeSet<_>>();
+    let mut page = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta http-equiv=\"refresh\" content=\"5\">\n<title>Linera validators</title>\n\
+         </head>\n<body>\n<h1>Linera validators</h1>\n<table border=\"1\">\n<tr>\
+         <th>Validator</th><th>Address</th><th>Version</th><th>Latest latency (ms)</th>\
+         <th>Average latency (ms)</th><th>Error rate</th>",
+    );
+    for chain_id in &chains {
+        write!(page, "<th>Height of {chain_id:.8}</th>").unwrap();
+    }
+    page.push_str("<th>Latest error</th></tr>\n");
+    for (name, activity) in activities {
+        write!(
+            page,
+            "<tr><td>{name}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.1}%</td>",
+            escape(&activity.address),
+            escape(activity.version.as_deref().unwrap_or("-")),
+            display_or_dash(activity.latest_latency_ms),
+            display_or_dash(activity.average_latency_ms),
+            activity.error_rate() * 100.0,
+        )
+        .unwrap();
+        for chain_id in &chains {
+            let height = activity.next_block_heights.get(chain_id);
+            write!(page, "<td>{}</td>", display_or_dash(height)).unwrap();
+        }
+        writeln!(
+            page,
+            "<td>{}</td></tr>",
+            escape(activity.latest_error.as_deref().unwrap_or("-"))
+        )
+        .unwrap();
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+fn display_or_dash(value: Option<impl std::fmt::Display>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+/// Escapes the characters with a special meaning in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
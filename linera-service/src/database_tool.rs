@@ -3,6 +3,7 @@
 
 use std::process;
 
+use linera_base::identifiers::ChainId;
 use linera_service::storage::StorageConfigNamespace;
 use linera_views::common::CommonStoreConfig;
 
@@ -42,6 +43,19 @@ enum DatabaseToolCommand {
         storage_config: String,
     },
 
+    /// Delete all the tables of the database whose namespace starts with a prefix, e.g. the
+    /// tables of the validators of a local test network sharing the database
+    #[command(name = "delete_namespaces")]
+    DeleteNamespaces {
+        /// Storage configuration for the blockchain history. Its namespace is ignored.
+        #[arg(long = "storage")]
+        storage_config: String,
+
+        /// The prefix of the namespaces to delete.
+        #[arg(long)]
+        prefix: String,
+    },
+
     /// Check existence of a database
     #[command(name = "check_existence")]
     CheckExistence {
@@ -66,12 +80,45 @@ enum DatabaseToolCommand {
         storage_config: String,
     },
 
+    /// Compress the certificates, certificate values and blobs written to a RocksDB
+    /// database before value compression was enabled. This must be run before the server is
+    /// started with value compression. It can be resumed if interrupted, and does nothing
+    /// once the values are converted
+    #[command(name = "compress_values")]
+    CompressValues {
+        /// Storage configuration for the blockchain history.
+        #[arg(long = "storage")]
+        storage_config: String,
+
+        /// The Zstandard compression level, which should be the one used by the server.
+        #[arg(long, default_value = "3")]
+        level: i32,
+    },
+
+    /// Replace the state of a chain with its latest checkpoint, e.g. after the state was
+    /// lost. This rolls back the chain, so the validator must be stopped, and it must not
+    /// have voted for anything above the height of the checkpoint
+    #[command(name = "restore_chain_checkpoint")]
+    RestoreChainCheckpoint {
+        /// Storage configuration for the blockchain history.
+        #[arg(long = "storage")]
+        storage_config: String,
+
+        /// The chain to restore.
+        #[arg(long)]
+        chain_id: ChainId,
+    },
+
     /// List the tables of the database
     #[command(name = "list_namespaces")]
     ListNamespaces {
         /// Storage configuration for the blockchain history.
         #[arg(long = "storage")]
         storage_config: String,
+
+        /// Only list the namespaces starting with this prefix.
+        #[arg(long)]
+        prefix: Option<String>,
     },
 }
 
@@ -88,6 +135,30 @@ async fn evaluate_options(options: DatabaseToolOptions) -> Result<i32, anyhow::E
             let full_storage_config = storage_config.add_common_config(common_config).await?;
             full_storage_config.delete_namespace().await?;
         }
+        DatabaseToolCommand::DeleteNamespaces {
+            storage_config,
+            prefix,
+        } => {
+            let storage_config = storage_config.parse::<StorageConfigNamespace>()?;
+            let full_storage_config = storage_config
+                .add_common_config(common_config.clone())
+                .await?;
+            let namespaces = full_storage_config.list_all().await?;
+            for namespace in namespaces {
+                if !namespace.starts_with(&prefix) {
+                    continue;
+                }
+                let storage_config = StorageConfigNamespace {
+                    storage_config: storage_config.storage_config.clone(),
+                    namespace: namespace.clone(),
+                };
+                let full_storage_config = storage_config
+                    .add_common_config(common_config.clone())
+                    .await?;
+                full_storage_config.delete_namespace().await?;
+                tracing::info!("Deleted the namespace {namespace}");
+            }
+        }
         DatabaseToolCommand::CheckExistence { storage_config } => {
             let storage_config = storage_config.parse::<StorageConfigNamespace>()?;
             let full_storage_config = storage_config.add_common_config(common_config).await?;
@@ -117,10 +188,44 @@ async fn evaluate_options(options: DatabaseToolOptions) -> Result<i32, anyhow::E
             let full_storage_config = storage_config.add_common_config(common_config).await?;
             full_storage_config.initialize().await?;
         }
-        DatabaseToolCommand::ListNamespaces { storage_config } => {
+        DatabaseToolCommand::CompressValues {
+            storage_config,
+            level,
+        } => {
             let storage_config = storage_config.parse::<StorageConfigNamespace>()?;
             let full_storage_config = storage_config.add_common_config(common_config).await?;
-            let namespaces = full_storage_config.list_all().await?;
+            let count = full_storage_config.compress_values(level).await?;
+            tracing::info!("Rewrote {count} values");
+        }
+        DatabaseToolCommand::RestoreChainCheckpoint {
+            storage_config,
+            chain_id,
+        } => {
+            let storage_config = storage_config.parse::<StorageConfigNamespace>()?;
+            let full_storage_config = storage_config.add_common_config(common_config).await?;
+            match full_storage_config
+                .restore_chain_checkpoint(chain_id)
+                .await?
+            {
+                Some(height) => {
+                    tracing::info!("Restored chain {chain_id} to its checkpoint at height {height}")
+                }
+                None => {
+                    tracing::error!("Chain {chain_id} has no checkpoint");
+                    return Ok(1);
+                }
+            }
+        }
+        DatabaseToolCommand::ListNamespaces {
+            storage_config,
+            prefix,
+        } => {
+            let storage_config = storage_config.parse::<StorageConfigNamespace>()?;
+            let full_storage_config = storage_config.add_common_config(common_config).await?;
+            let mut namespaces = full_storage_config.list_all().await?;
+            if let Some(prefix) = prefix {
+                namespaces.retain(|namespace| namespace.starts_with(&prefix));
+            }
             println!("The list of namespaces is {:?}", namespaces);
         }
     }
@@ -137,6 +242,7 @@ fn main() {
         .with_env_filter(env_filter)
         .init();
 
+    linera_version::VersionInfo::handle_verbose_version_flag();
     let options = <DatabaseToolOptions as clap::Parser>::parse();
 
     let mut runtime = if options.tokio_threads == Some(1) {
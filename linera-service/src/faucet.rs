@@ -1,15 +1,30 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{net::SocketAddr, num::NonZeroU16, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    io::Write as _,
+    iter,
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU16,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use async_graphql::{EmptySubscription, Error, Object, Schema, SimpleObject};
+use async_graphql::{Context, EmptySubscription, Error, Object, Schema, SimpleObject};
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
-use axum::{http::StatusCode, response, response::IntoResponse, Extension, Router};
+use axum::{
+    extract::ConnectInfo,
+    http::{HeaderMap, StatusCode},
+    response,
+    response::IntoResponse,
+    Extension, Router,
+};
 use futures::lock::Mutex;
+use hmac::{Hmac, Mac as _};
 use linera_base::{
     crypto::{CryptoHash, PublicKey},
-    data_types::{Amount, Timestamp},
+    data_types::{Amount, TimeDelta, Timestamp},
     identifiers::{ChainId, MessageId},
     ownership::ChainOwnership,
 };
@@ -23,6 +38,7 @@ use linera_storage::Storage;
 use linera_views::views::ViewError;
 use serde::Deserialize;
 use serde_json::json;
+use sha2::Sha256;
 use thiserror::Error as ThisError;
 use tower_http::cors::CorsLayer;
 use tracing::{error, info};
@@ -33,6 +49,10 @@ use crate::{chain_listener::ClientContext, config::GenesisConfig, util};
 #[path = "unit_tests/faucet.rs"]
 mod tests;
 
+/// The maximum number of chains that can be claimed in a single request. They are all created
+/// in the same block.
+const MAX_CLAIMS_PER_REQUEST: usize = 1000;
+
 /// The root GraphQL query type.
 pub struct QueryRoot<P, S> {
     genesis_config: Arc<GenesisConfig>,
@@ -47,6 +67,222 @@ pub struct MutationRoot<P, S, C> {
     end_timestamp: Timestamp,
     start_timestamp: Timestamp,
     start_balance: Amount,
+    limits: Arc<ClaimLimits>,
+    tracker: Arc<Mutex<ClaimTracker>>,
+}
+
+/// Limits on the chains that each user of the faucet can claim.
+#[derive(Clone, Debug, Default)]
+pub struct ClaimLimits {
+    /// The maximum number of chains that can be claimed with the same public key.
+    pub max_claims_per_key: Option<u32>,
+    /// The maximum number of chains that can be claimed from the same IP address within
+    /// `ip_window`.
+    pub max_claims_per_ip: Option<u32>,
+    /// The duration over which the claims from an IP address are counted.
+    pub ip_window: TimeDelta,
+    /// The HTTP header containing the client's IP address, e.g. `X-Forwarded-For` if the
+    /// faucet runs behind a reverse proxy. Otherwise the address of the connection is used.
+    pub ip_header: Option<String>,
+    /// The number of trusted reverse proxies in front of the faucet, each appending the
+    /// address it received the request from to `ip_header`. The client's address is the one
+    /// appended by the farthest of them, i.e. this many entries from the right: the entries
+    /// before it could be forged by the client. Zero is treated as one.
+    pub trusted_proxies: usize,
+    /// The URL of a service verifying captcha tokens. If set, every claim must include a token,
+    /// which is sent to this URL, together with the client's IP address, in a JSON `POST`
+    /// request. The claim is accepted if the service responds with a success status.
+    pub captcha_url: Option<String>,
+    /// The secret used to sign invitations. If set, every chain must be claimed with a
+    /// distinct invitation created by [`sign_invitation`], and each invitation can only be
+    /// used once.
+    pub invitation_secret: Option<Vec<u8>>,
+    /// The file where the IDs of the used invitations are recorded, one JSON string per line,
+    /// so that they can't be used again after the faucet restarts. Otherwise they are only
+    /// remembered in memory.
+    pub used_invitations_path: Option<PathBuf>,
+}
+
+impl ClaimLimits {
+    /// Returns the IP address of the client: from the configured header if any, otherwise
+    /// from the connection.
+    fn client_ip(&self, address: SocketAddr, headers: &HeaderMap) -> Option<IpAddr> {
+        let Some(header) = &self.ip_header else {
+            return Some(address.ip());
+        };
+        // Each proxy appends the address it received the request from, so only the entries
+        // appended by the trusted proxies, on the right, can be relied on.
+        let value = headers.get(header)?.to_str().ok()?;
+        let entry = value.rsplit(',').nth(self.trusted_proxies.max(1) - 1)?;
+        entry.trim().parse().ok()
+    }
+}
+
+/// Returns an invitation to claim one chain from a faucet configured with the given secret.
+///
+/// The invitation is the ID followed by a colon and the HMAC-SHA256 of the ID, in
+/// hexadecimal. The ID should be unique, e.g. a counter or the name of the invited user.
+pub fn sign_invitation(secret: &[u8], id: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(id.as_bytes());
+    format!("{id}:{}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Returns the ID of the invitation if it was signed with the given secret.
+fn verify_invitation<'a>(secret: &[u8], invitation: &'a str) -> Option<&'a str> {
+    let (id, signature) = invitation.rsplit_once(':')?;
+    let signature = hex::decode(signature).ok()?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(id.as_bytes());
+    mac.verify_slice(&signature).ok()?;
+    Some(id)
+}
+
+/// The IP address of the client sending a GraphQL request, if known.
+#[derive(Clone, Copy, Debug)]
+struct ClientIp(IpAddr);
+
+/// The claims made so far, to enforce the [`ClaimLimits`].
+#[derive(Debug, Default)]
+struct ClaimTracker {
+    claims_per_key: BTreeMap<PublicKey, u32>,
+    claims_per_ip: BTreeMap<IpAddr, VecDeque<Timestamp>>,
+    used_invitations: BTreeSet<String>,
+}
+
+impl ClaimTracker {
+    /// Returns a tracker that remembers the invitations recorded in the file of the used
+    /// invitations, if any.
+    fn load(limits: &ClaimLimits) -> anyhow::Result<Self> {
+        let mut tracker = ClaimTracker::default();
+        let Some(path) = &limits.used_invitations_path else {
+            return Ok(tracker);
+        };
+        if !path.exists() {
+            return Ok(tracker);
+        }
+        for line in fs_err::read_to_string(path)?.lines() {
+            if !line.is_empty() {
+                tracker.used_invitations.insert(serde_json::from_str(line)?);
+            }
+        }
+        Ok(tracker)
+    }
+
+    /// Returns an error if the limits don't allow claiming a chain for each of the public keys.
+    fn check(
+        &mut self,
+        limits: &ClaimLimits,
+        public_keys: &[PublicKey],
+        invitations: &[String],
+        client_ip: Option<IpAddr>,
+        now: Timestamp,
+    ) -> Result<(), Error> {
+        if let Some(secret) = &limits.invitation_secret {
+            if invitations.len() != public_keys.len() {
+                return Err(Error::new("Each chain must be claimed with an invitation."));
+            }
+            let mut ids = BTreeSet::new();
+            for invitation in invitations {
+                let id = verify_invitation(secret, invitation)
+                    .ok_or_else(|| Error::new("Invalid invitation."))?;
+                if self.used_invitations.contains(id) || !ids.insert(id) {
+                    return Err(Error::new("This invitation has already been used."));
+                }
+            }
+        }
+        if let Some(max_claims) = limits.max_claims_per_key {
+            let mut claims = BTreeMap::new();
+            for public_key in public_keys {
+                let count = claims
+                    .entry(public_key)
+                    .or_insert_with(|| self.claims_per_key.get(public_key).copied().unwrap_or(0));
+                *count += 1;
+                if *count > max_claims {
+                    return Err(Error::new(format!(
+                        "At most {max_claims} chains can be claimed with the same public key."
+                    )));
+                }
+            }
+        }
+        if let Some(max_claims) = limits.max_claims_per_ip {
+            let client_ip = client_ip
+                .ok_or_else(|| Error::new("Could not determine the client's IP address."))?;
+            // Forget the addresses whose claims all expired, so that the tracker doesn't grow
+            // with every address that ever claimed a chain.
+            self.claims_per_ip.retain(|_, claims| {
+                claims
+                    .back()
+                    .is_some_and(|timestamp| now.delta_since(*timestamp) < limits.ip_window)
+            });
+            let claims = self.claims_per_ip.entry(client_ip).or_default();
+            while claims
+                .front()
+                .is_some_and(|timestamp| now.delta_since(*timestamp) >= limits.ip_window)
+            {
+                claims.pop_front();
+            }
+            if claims.len().saturating_add(public_keys.len()) > max_claims as usize {
+                return Err(Error::new(
+                    "Too many claims from this IP address; try again later.",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the claims of a chain for each of the public keys.
+    fn record(
+        &mut self,
+        limits: &ClaimLimits,
+        public_keys: &[PublicKey],
+        invitations: &[String],
+        client_ip: Option<IpAddr>,
+        now: Timestamp,
+    ) {
+        if let Some(secret) = &limits.invitation_secret {
+            let ids = invitations
+                .iter()
+                .filter_map(|invitation| verify_invitation(secret, invitation))
+                .collect::<Vec<_>>();
+            if let Some(path) = &limits.used_invitations_path {
+                // The chains were created already, so the claim still succeeds. The
+                // invitations can't be used again until the faucet restarts.
+                if let Err(error) = Self::append_used_invitations(path, &ids) {
+                    error!(%error, path = %path.display(), "Failed to record used invitations");
+                }
+            }
+            self.used_invitations
+                .extend(ids.into_iter().map(str::to_string));
+        }
+        if limits.max_claims_per_key.is_some() {
+            for public_key in public_keys {
+                *self.claims_per_key.entry(*public_key).or_default() += 1;
+            }
+        }
+        if let (Some(_), Some(client_ip)) = (limits.max_claims_per_ip, client_ip) {
+            self.claims_per_ip
+                .entry(client_ip)
+                .or_default()
+                .extend(iter::repeat(now).take(public_keys.len()));
+        }
+    }
+
+    /// Appends the IDs of used invitations to the given file.
+    fn append_used_invitations(path: &Path, ids: &[&str]) -> anyhow::Result<()> {
+        let mut lines = String::new();
+        for id in ids {
+            lines.push_str(&serde_json::to_string(id)?);
+            lines.push('\n');
+        }
+        let mut file = fs_err::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        file.write_all(lines.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, ThisError)]
@@ -120,8 +356,35 @@ where
     ViewError: From<S::ContextError>,
 {
     /// Creates a new chain with the given authentication key, and transfers tokens to it.
-    async fn claim(&self, public_key: PublicKey) -> Result<ClaimOutcome, Error> {
-        self.do_claim(public_key).await
+    async fn claim(
+        &self,
+        ctx: &Context<'_>,
+        public_key: PublicKey,
+        captcha_token: Option<String>,
+        invitation: Option<String>,
+    ) -> Result<ClaimOutcome, Error> {
+        let client_ip = ctx.data_opt::<ClientIp>().map(|ClientIp(ip)| *ip);
+        let invitations = invitation.into_iter().collect();
+        let mut outcomes = self
+            .do_limited_claim(vec![public_key], invitations, client_ip, captcha_token)
+            .await?;
+        Ok(outcomes.pop().expect("one chain should have been created"))
+    }
+
+    /// Creates a new chain for each of the given authentication keys, and transfers tokens to
+    /// each of them. All chains are created in a single block. If the faucet requires
+    /// invitations, one must be given for each key.
+    async fn claim_many(
+        &self,
+        ctx: &Context<'_>,
+        public_keys: Vec<PublicKey>,
+        captcha_token: Option<String>,
+        invitations: Option<Vec<String>>,
+    ) -> Result<Vec<ClaimOutcome>, Error> {
+        let client_ip = ctx.data_opt::<ClientIp>().map(|ClientIp(ip)| *ip);
+        let invitations = invitations.unwrap_or_default();
+        self.do_limited_claim(public_keys, invitations, client_ip, captcha_token)
+            .await
     }
 }
 
@@ -133,7 +396,73 @@ where
     C: ClientContext<P> + Send + 'static,
     ViewError: From<S::ContextError>,
 {
-    async fn do_claim(&self, public_key: PublicKey) -> Result<ClaimOutcome, Error> {
+    /// Claims the chains if the captcha token and invitations are valid and the client is
+    /// within its limits.
+    async fn do_limited_claim(
+        &self,
+        public_keys: Vec<PublicKey>,
+        invitations: Vec<String>,
+        client_ip: Option<IpAddr>,
+        captcha_token: Option<String>,
+    ) -> Result<Vec<ClaimOutcome>, Error> {
+        if let Some(captcha_url) = &self.limits.captcha_url {
+            let captcha_token =
+                captcha_token.ok_or_else(|| Error::new("A captcha token is required."))?;
+            Self::verify_captcha(captcha_url, &captcha_token, client_ip).await?;
+        }
+        // Claims are serialized anyway, since they use the same chain client. Holding the lock
+        // ensures that concurrent claims can't exceed the limits.
+        let mut tracker = self.tracker.lock().await;
+        let now = self
+            .client
+            .lock()
+            .await
+            .storage_client()
+            .await
+            .clock()
+            .current_time();
+        tracker.check(&self.limits, &public_keys, &invitations, client_ip, now)?;
+        let outcomes = self.do_claim_many(public_keys.clone()).await?;
+        tracker.record(&self.limits, &public_keys, &invitations, client_ip, now);
+        Ok(outcomes)
+    }
+
+    /// Asks the captcha service whether the token is valid.
+    async fn verify_captcha(
+        captcha_url: &str,
+        captcha_token: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<(), Error> {
+        let response = reqwest::Client::new()
+            .post(captcha_url)
+            .json(&json!({ "token": captcha_token, "ip": client_ip }))
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => {
+                info!(status = %response.status(), "Captcha verification failed");
+                Err(Error::new("Captcha verification failed."))
+            }
+            Err(error) => {
+                error!(%error, "Failed to reach the captcha service");
+                Err(Error::new(
+                    "Captcha verification is unavailable; try again later.",
+                ))
+            }
+        }
+    }
+
+    async fn do_claim_many(&self, public_keys: Vec<PublicKey>) -> Result<Vec<ClaimOutcome>, Error> {
+        if public_keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        if public_keys.len() > MAX_CLAIMS_PER_REQUEST {
+            return Err(Error::new(format!(
+                "At most {MAX_CLAIMS_PER_REQUEST} chains can be claimed at once."
+            )));
+        }
+        let amount = self.amount.try_mul(public_keys.len() as u128)?;
         let mut client = self.client.lock().await;
 
         if self.start_timestamp < self.end_timestamp {
@@ -145,7 +474,7 @@ where
                     .as_micros();
                 let remaining_duration = self.end_timestamp.delta_since(local_time).as_micros();
                 let balance = client.local_balance().await?;
-                let Ok(remaining_balance) = balance.try_sub(self.amount) else {
+                let Ok(remaining_balance) = balance.try_sub(amount) else {
                     return Err(Error::new("The faucet is empty."));
                 };
                 // The tokens unlock linearly, e.g. if 1/3 of the time is left, then 1/3 of the
@@ -160,10 +489,13 @@ where
             }
         }
 
-        let ownership = ChainOwnership::single(public_key);
-        let result = client.open_chain(ownership, self.amount).await;
+        let chains = public_keys
+            .into_iter()
+            .map(|public_key| (ChainOwnership::single(public_key), self.amount))
+            .collect();
+        let result = client.open_chains(chains).await;
         self.context.lock().await.update_wallet(&mut *client).await;
-        let (message_id, certificate) = match result? {
+        let (message_ids, certificate) = match result? {
             ClientOutcome::Committed(result) => result,
             ClientOutcome::WaitForTimeout(timeout) => {
                 return Err(Error::new(format!(
@@ -173,12 +505,15 @@ where
                 )));
             }
         };
-        let chain_id = ChainId::child(message_id);
-        Ok(ClaimOutcome {
-            message_id,
-            chain_id,
-            certificate_hash: certificate.hash(),
-        })
+        let certificate_hash = certificate.hash();
+        Ok(message_ids
+            .into_iter()
+            .map(|message_id| ClaimOutcome {
+                message_id,
+                chain_id: ChainId::child(message_id),
+                certificate_hash,
+            })
+            .collect())
     }
 }
 
@@ -204,6 +539,8 @@ pub struct FaucetService<P, S, C> {
     end_timestamp: Timestamp,
     start_timestamp: Timestamp,
     start_balance: Amount,
+    limits: Arc<ClaimLimits>,
+    tracker: Arc<Mutex<ClaimTracker>>,
 }
 
 impl<P, S: Clone, C> Clone for FaucetService<P, S, C> {
@@ -217,6 +554,8 @@ impl<P, S: Clone, C> Clone for FaucetService<P, S, C> {
             end_timestamp: self.end_timestamp,
             start_timestamp: self.start_timestamp,
             start_balance: self.start_balance,
+            limits: self.limits.clone(),
+            tracker: self.tracker.clone(),
         }
     }
 }
@@ -236,10 +575,12 @@ where
         amount: Amount,
         end_timestamp: Timestamp,
         genesis_config: Arc<GenesisConfig>,
+        limits: ClaimLimits,
     ) -> anyhow::Result<Self> {
         let start_timestamp = client.storage_client().await.clock().current_time();
         client.process_inbox().await?;
         let start_balance = client.local_balance().await?;
+        let tracker = ClaimTracker::load(&limits)?;
         Ok(Self {
             client: Arc::new(Mutex::new(client)),
             context: Arc::new(Mutex::new(context)),
@@ -249,6 +590,8 @@ where
             end_timestamp,
             start_timestamp,
             start_balance,
+            limits: Arc::new(limits),
+            tracker: Arc::new(Mutex::new(tracker)),
         })
     }
 
@@ -260,6 +603,8 @@ where
             end_timestamp: self.end_timestamp,
             start_timestamp: self.start_timestamp,
             start_balance: self.start_balance,
+            limits: self.limits.clone(),
+            tracker: self.tracker.clone(),
         };
         let query_root = QueryRoot {
             genesis_config: self.genesis_config.clone(),
@@ -284,7 +629,7 @@ where
 
         axum::serve(
             tokio::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port))).await?,
-            app,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
         )
         .await?;
 
@@ -292,8 +637,22 @@ where
     }
 
     /// Executes a GraphQL query and generates a response for our `Schema`.
-    async fn index_handler(service: Extension<Self>, request: GraphQLRequest) -> GraphQLResponse {
+    async fn index_handler(
+        service: Extension<Self>,
+        ConnectInfo(address): ConnectInfo<SocketAddr>,
+        headers: HeaderMap,
+        request: GraphQLRequest,
+    ) -> GraphQLResponse {
+        let mut request = request.into_inner();
+        if let Some(client_ip) = service.0.client_ip(address, &headers) {
+            request = request.data(ClientIp(client_ip));
+        }
         let schema = service.0.schema();
-        schema.execute(request.into_inner()).await.into()
+        schema.execute(request).await.into()
+    }
+
+    /// Returns the IP address of the client.
+    fn client_ip(&self, address: SocketAddr, headers: &HeaderMap) -> Option<IpAddr> {
+        self.limits.client_ip(address, headers)
     }
 }
@@ -7,42 +7,62 @@
 
 use std::{
     fmt::Debug,
+    future::Future,
     net::SocketAddr,
     sync::Arc,
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use async_trait::async_trait;
-use futures::{future::BoxFuture, FutureExt};
-use linera_base::identifiers::ChainId;
+use futures::{future::BoxFuture, FutureExt, SinkExt as _, StreamExt as _};
+use linera_base::{crypto::CryptoHash, data_types::SoftwareVersion, identifiers::ChainId};
 use linera_core::notifier::Notifier;
 use linera_rpc::{
     config::{
-        ShardConfig, TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+        CanaryShardConfig, Locality, NetworkProtocol, ShadowShardConfig, ShardConfig, ShardId,
+        TimeoutPolicy, TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
     },
     grpc::{
         api::{
+            self,
             notifier_service_server::{NotifierService, NotifierServiceServer},
             validator_node_server::{ValidatorNode, ValidatorNodeServer},
             validator_worker_client::ValidatorWorkerClient,
-            BlockProposal, Certificate, ChainInfoQuery, ChainInfoResult, LiteCertificate,
-            Notification, SubscriptionRequest, VersionInfo,
+            BlobRequest, BlobResult, BlockProposal, BlockSimulationRequest, BlockSimulationResult,
+            Certificate, ChainInfoQuery, ChainInfoResult, LiteCertificate, Notification,
+            SubscriptionRequest, VersionInfo,
         },
+        grpc_timeout,
+        ip_filter::{IpFilter, IpFilterLayer, IpFilterRules},
+        peer_limits::{PeerLimits, PeerLimitsConfig, PeerLimitsLayer},
         pool::GrpcConnectionPool,
-        GrpcProxyable, GRPC_MAX_MESSAGE_SIZE,
+        protocol_version::ProtocolVersionLayer,
+        public_queries::{PublicQueryConfig, PublicQueryLayer, RateLimiter},
+        request_id::RequestIdLayer,
+        GrpcProtoConversionError, GrpcProxyable, GRPC_MAX_MESSAGE_SIZE, REQUEST_ID_HEADER,
+        STORAGE_TRACE_TOKEN_HEADER,
     },
+    simple::TransportProtocol,
+    version::PROTOCOL_VERSION_HEADER,
+    RpcMessage,
 };
+use rand::Rng as _;
 use rcgen::generate_simple_self_signed;
 use tokio::select;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tonic::{
+    body::BoxBody,
+    codegen::http,
     transport::{Body, Channel, Identity, Server, ServerTlsConfig},
     Request, Response, Status,
 };
-use tower::{builder::ServiceBuilder, Layer, Service};
-use tracing::{debug, info, instrument};
+use tonic_health::pb::health_server::{Health, HealthServer};
+use tower::{
+    builder::ServiceBuilder, util::BoxCloneService, BoxError, Layer, Service, ServiceExt as _,
+};
+use tracing::{debug, error, info, instrument, warn};
 #[cfg(with_metrics)]
 use {
     linera_base::{prometheus_util, sync::Lazy},
@@ -51,6 +71,14 @@ use {
 
 #[cfg(with_metrics)]
 use crate::prometheus_server;
+use crate::{
+    routing::RoutingCache,
+    traffic_capture::{CapturedMessage, TrafficCapture},
+};
+
+#[cfg(test)]
+#[path = "unit_tests/grpc_proxy.rs"]
+mod tests;
 
 #[cfg(with_metrics)]
 static PROXY_REQUEST_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
@@ -92,6 +120,225 @@ static PROXY_REQUEST_ERROR: Lazy<IntCounterVec> = Lazy::new(|| {
     .expect("Counter creation should not fail")
 });
 
+#[cfg(with_metrics)]
+static PROXY_SHARD_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "proxy_shard_requests",
+        "Number of requests forwarded to shards, by locality of the shard relative to the proxy",
+        &["locality"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static PROXY_MIRRORED_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "proxy_mirrored_requests",
+        "Number of requests mirrored to shadow shards, by outcome",
+        &["outcome"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static PROXY_CANARY_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "proxy_canary_requests",
+        "Number of requests for shards with a canary, by shard, target (shard or canary) and outcome",
+        &["shard", "target", "outcome"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static PROXY_CANARY_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "proxy_canary_latency",
+        "Latency of the requests for shards with a canary, by shard and target (shard or canary)",
+        &["shard", "target"],
+        Some(vec![
+            0.001, 0.002_5, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0,
+            50.0, 100.0, 200.0, 300.0, 400.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+/// Records that a request was forwarded to a shard with the given locality.
+#[cfg_attr(not(with_metrics), allow(unused_variables))]
+pub fn record_shard_request(locality: Locality) {
+    #[cfg(with_metrics)]
+    PROXY_SHARD_REQUESTS
+        .with_label_values(&[locality.as_str()])
+        .inc();
+}
+
+/// Records whether the shadow shard handled a mirrored request successfully.
+#[cfg_attr(not(with_metrics), allow(unused_variables))]
+pub fn record_mirrored_request(success: bool) {
+    #[cfg(with_metrics)]
+    PROXY_MIRRORED_REQUESTS
+        .with_label_values(&[if success { "success" } else { "error" }])
+        .inc();
+}
+
+/// Returns the shadow server of the shard if the current request is sampled to be mirrored
+/// to it.
+pub fn sample_shadow(shard: &ShardConfig) -> Option<&ShadowShardConfig> {
+    let shadow = shard.shadow.as_ref()?;
+    shadow
+        .mirrors(rand::thread_rng().gen_range(0..100))
+        .then_some(shadow)
+}
+
+/// The server a request for a shard is sent to: the shard itself, or its canary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShardTarget {
+    /// The shard the request is assigned to.
+    pub shard_id: ShardId,
+    /// Whether the request is sent to the canary of the shard instead of the shard itself.
+    pub canary: bool,
+    /// Whether the shard has a canary, in which case the requests sent to the shard and to
+    /// the canary are recorded, so that operators can compare them.
+    compared: bool,
+}
+
+impl ShardTarget {
+    /// Returns the target of a request for `chain_id`, assigned to the given shard: its
+    /// canary if the chain is one of the canary's.
+    pub fn choose(shard_id: ShardId, shard: &ShardConfig, chain_id: ChainId) -> Self {
+        ShardTarget {
+            shard_id,
+            canary: shard.canary_for(chain_id).is_some(),
+            compared: shard.canary.is_some(),
+        }
+    }
+
+    /// Returns the canary of the shard if the request is sent to it.
+    pub fn canary<'a>(&self, shard: &'a ShardConfig) -> Option<&'a CanaryShardConfig> {
+        shard.canary.as_ref().filter(|_| self.canary)
+    }
+
+    #[cfg(with_metrics)]
+    fn as_str(&self) -> &'static str {
+        if self.canary {
+            "canary"
+        } else {
+            "shard"
+        }
+    }
+
+    /// Waits for the response of the target, and records its outcome and latency if the
+    /// shard has a canary.
+    pub async fn measure<T, E>(self, response: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+        let start = Instant::now();
+        let result = response.await;
+        if self.compared {
+            self.record(result.is_ok(), start.elapsed());
+        }
+        result
+    }
+
+    #[cfg_attr(not(with_metrics), allow(unused_variables))]
+    fn record(&self, success: bool, latency: Duration) {
+        #[cfg(with_metrics)]
+        {
+            let shard = self.shard_id.to_string();
+            PROXY_CANARY_REQUESTS
+                .with_label_values(&[
+                    shard.as_str(),
+                    self.as_str(),
+                    if success { "success" } else { "error" },
+                ])
+                .inc();
+            PROXY_CANARY_LATENCY
+                .with_label_values(&[shard.as_str(), self.as_str()])
+                .observe(latency.as_secs_f64() * 1000.0);
+        }
+    }
+}
+
+/// Sends a message to the shard server at `address` over the simple transport, and returns
+/// its response, if any.
+pub async fn send_to_simple_shard(
+    protocol: TransportProtocol,
+    message: RpcMessage,
+    address: String,
+    timeout: TimeoutPolicy,
+) -> Result<Option<RpcMessage>> {
+    let mut connection = protocol.connect(address).await?;
+    tokio::time::timeout(timeout.send_timeout(), connection.send(message)).await??;
+    let message = tokio::time::timeout(timeout.recv_timeout(), connection.next())
+        .await?
+        .transpose()?;
+    Ok(message)
+}
+
+/// Logs an error if the admin chain requires a newer version of the software than the
+/// proxy's.
+pub fn log_minimum_version(minimum_version: Option<SoftwareVersion>) {
+    let version = SoftwareVersion::current();
+    match minimum_version {
+        Some(minimum) if version < minimum => error!(
+            %version,
+            %minimum,
+            "The proxy runs an older version than the minimum version required by the admin chain"
+        ),
+        Some(minimum) => debug!(%version, %minimum, "The proxy runs a supported version"),
+        None => {}
+    }
+}
+
+/// Logs whether the shard at `address` runs a different version than the proxy, which is only
+/// expected in the middle of an upgrade, or an older version than the `minimum_version`
+/// required by the admin chain.
+pub fn log_shard_version(
+    address: &str,
+    shard_version: &linera_version::VersionInfo,
+    minimum_version: Option<SoftwareVersion>,
+) {
+    let linera_version::CrateVersion {
+        major,
+        minor,
+        patch,
+    } = shard_version.crate_version.value;
+    let software_version = SoftwareVersion {
+        major,
+        minor,
+        patch,
+    };
+    if let Some(minimum) = minimum_version.filter(|minimum| software_version < *minimum) {
+        error!(
+            address,
+            shard_version = %software_version,
+            %minimum,
+            "Shard runs an older version than the minimum version required by the admin chain"
+        );
+    }
+    let version = &linera_version::VERSION_INFO;
+    if shard_version == version {
+        debug!(address, "Shard runs the same version as the proxy");
+    } else if version.is_compatible_with(shard_version) {
+        warn!(
+            address,
+            proxy_version = %version.crate_version,
+            proxy_commit = %version.git_commit,
+            shard_version = %shard_version.crate_version,
+            shard_commit = %shard_version.git_commit,
+            "Shard runs a different version than the proxy"
+        );
+    } else {
+        error!(
+            address,
+            proxy_version = %version.crate_version,
+            proxy_commit = %version.git_commit,
+            shard_version = %shard_version.crate_version,
+            shard_commit = %shard_version.git_commit,
+            "Shard runs a version that is incompatible with the proxy"
+        );
+    }
+}
+
 #[derive(Clone)]
 pub struct PrometheusMetricsMiddlewareLayer;
 
@@ -140,6 +387,64 @@ where
     }
 }
 
+/// A gRPC service of the public endpoints of the proxy, with its type erased, as seen by the
+/// custom layers.
+pub type ProxyService = BoxCloneService<http::Request<Body>, http::Response<BoxBody>, BoxError>;
+
+/// A custom tower layer of the public endpoints of the proxy, e.g. to authenticate or to log
+/// the requests.
+#[derive(Clone)]
+pub struct ProxyLayer(Arc<dyn Fn(ProxyService) -> ProxyService + Send + Sync>);
+
+impl ProxyLayer {
+    /// Wraps a tower layer whose services handle the HTTP requests of the proxy.
+    pub fn new<L>(layer: L) -> Self
+    where
+        L: Layer<ProxyService> + Send + Sync + 'static,
+        L::Service: Service<http::Request<Body>, Response = http::Response<BoxBody>>
+            + Clone
+            + Send
+            + 'static,
+        <L::Service as Service<http::Request<Body>>>::Error: Into<BoxError>,
+        <L::Service as Service<http::Request<Body>>>::Future: Send + 'static,
+    {
+        ProxyLayer(Arc::new(move |service| {
+            BoxCloneService::new(layer.layer(service).map_err(Into::into))
+        }))
+    }
+}
+
+/// The custom layers of the public endpoints of the proxy. They handle the requests admitted
+/// by the built-in layers, i.e. the request IDs, the IP filter, the rate limits and the
+/// protocol version check, and their latency is included in the proxy metrics.
+#[derive(Clone, Default)]
+pub struct ProxyLayers(Vec<ProxyLayer>);
+
+impl ProxyLayers {
+    /// Adds a layer, handling the requests after the layers added before.
+    pub fn with_layer(mut self, layer: ProxyLayer) -> Self {
+        self.0.push(layer);
+        self
+    }
+}
+
+impl<S> Layer<S> for ProxyLayers
+where
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    type Service = ProxyService;
+
+    fn layer(&self, service: S) -> ProxyService {
+        let service = BoxCloneService::new(service.map_err(Into::into));
+        self.0
+            .iter()
+            .rev()
+            .fold(service, |service, layer| (layer.0)(service))
+    }
+}
+
 #[derive(Clone)]
 pub struct GrpcProxy(Arc<GrpcProxyInner>);
 
@@ -147,27 +452,85 @@ struct GrpcProxyInner {
     public_config: ValidatorPublicNetworkConfig,
     internal_config: ValidatorInternalNetworkConfig,
     worker_connection_pool: GrpcConnectionPool,
+    /// The transport of the shards if they use the simple transport instead of gRPC, in which
+    /// case the requests and their responses are converted by the proxy.
+    simple_shards: Option<TransportProtocol>,
+    timeout: TimeoutPolicy,
+    /// Where to record a sample of the block proposals and certificates, if anywhere.
+    capture: Option<Arc<TrafficCapture>>,
     notifier: Notifier<Result<Notification, Status>>,
     tls: TlsConfig,
+    ip_filter: Arc<IpFilter>,
+    peer_limits: Arc<PeerLimits>,
+    /// The configuration of the read-only public query endpoint.
+    public_queries: PublicQueryConfig,
+    /// The peers trusted to be told the shard addresses, if any.
+    shard_hints: Option<IpFilterRules>,
+    routing: Arc<RoutingCache>,
+    /// The hash of the genesis configuration of the validator's network.
+    genesis_config_hash: CryptoHash,
+    /// The admin chain of the validator's network.
+    admin_id: ChainId,
 }
 
 impl GrpcProxy {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         public_config: ValidatorPublicNetworkConfig,
         internal_config: ValidatorInternalNetworkConfig,
-        connect_timeout: Duration,
-        timeout: Duration,
+        genesis_config_hash: CryptoHash,
+        admin_id: ChainId,
+        timeout: TimeoutPolicy,
         tls: TlsConfig,
-    ) -> Self {
-        Self(Arc::new(GrpcProxyInner {
+        ip_filter: Arc<IpFilter>,
+        peer_limits: PeerLimits,
+        public_queries: PublicQueryConfig,
+        shard_hints: Option<IpFilterRules>,
+        capture: Option<Arc<TrafficCapture>>,
+    ) -> Result<Self> {
+        ensure!(
+            shard_hints.is_none() || internal_config.mutual_tls.is_none(),
+            "Shard addresses cannot be given to clients when the shards only accept \
+             connections from the proxy."
+        );
+        let simple_shards = match internal_config.protocol {
+            NetworkProtocol::Simple(transport) => Some(transport),
+            NetworkProtocol::Grpc(_) => None,
+        };
+        ensure!(
+            simple_shards.is_none() || shard_hints.is_none(),
+            "Shard addresses can only be given to clients when the shards use gRPC."
+        );
+        let shard_tls = internal_config
+            .mutual_tls
+            .as_ref()
+            .map(|tls| tls.client_config())
+            .transpose()?;
+        Ok(Self(Arc::new(GrpcProxyInner {
             public_config,
             internal_config,
             worker_connection_pool: GrpcConnectionPool::default()
-                .with_connect_timeout(connect_timeout)
-                .with_timeout(timeout),
+                .with_connect_timeout(timeout.send_timeout())
+                .with_timeout(timeout.recv_timeout())
+                .with_tls(shard_tls),
+            simple_shards,
+            timeout,
+            capture,
             notifier: Notifier::default(),
             tls,
-        }))
+            ip_filter,
+            peer_limits: Arc::new(peer_limits),
+            public_queries,
+            shard_hints,
+            routing: Arc::default(),
+            genesis_config_hash,
+            admin_id,
+        })))
+    }
+
+    /// Returns the cache of the shards of the chains.
+    pub fn routing(&self) -> Arc<RoutingCache> {
+        self.0.routing.clone()
     }
 
     fn as_validator_node(&self) -> ValidatorNodeServer<Self> {
@@ -192,20 +555,84 @@ impl GrpcProxy {
         SocketAddr::from(([0, 0, 0, 0], self.0.internal_config.port))
     }
 
-    fn shard_for(&self, proxyable: &impl GrpcProxyable) -> Option<ShardConfig> {
-        Some(
-            self.0
-                .internal_config
-                .get_shard_for(proxyable.chain_id()?)
-                .clone(),
-        )
+    /// Returns the address of the server to send the request to, and which one it is: the
+    /// canary of the shard of the request's chain if the request is routed to it, or else a
+    /// server for the shard: one of the closest replicas if the request is read-only, and
+    /// the shard itself otherwise.
+    fn shard_address_for(&self, proxyable: &impl GrpcProxyable) -> Option<(String, ShardTarget)> {
+        let config = &self.0.internal_config;
+        let chain_id = proxyable.chain_id()?;
+        let shard_id = self.0.routing.route(config, chain_id);
+        let shard = config.shard(shard_id);
+        let target = ShardTarget::choose(shard_id, shard, chain_id);
+        if let Some(canary) = target.canary(shard) {
+            let address = match self.0.simple_shards {
+                Some(_) => canary.address(),
+                None => config.grpc_uri(canary.address()),
+            };
+            return Some((address, target));
+        }
+        let endpoint =
+            config.get_request_endpoint(shard_id, proxyable.is_read_only(), rand::random());
+        record_shard_request(endpoint.locality);
+        let address = match self.0.simple_shards {
+            Some(_) => endpoint.address(),
+            None => config.endpoint_grpc_uri(&endpoint),
+        };
+        Some((address, target))
     }
 
-    fn worker_client_for_shard(
+    /// Sends a copy of the request to the shadow server of its shard, if it is sampled to be
+    /// mirrored. The response is discarded: only its outcome is recorded.
+    fn mirror<R, F, Fut>(&self, inner: &R, send: F)
+    where
+        R: GrpcProxyable + Clone + Send + 'static,
+        F: FnOnce(ValidatorWorkerClient<Channel>, R) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Response<ChainInfoResult>, Status>> + Send + 'static,
+    {
+        let Some(chain_id) = inner.chain_id() else {
+            return;
+        };
+        let shard = self.0.internal_config.get_shard_for(chain_id);
+        let Some(shadow) = sample_shadow(shard) else {
+            return;
+        };
+        let address = self.0.internal_config.grpc_uri(shadow.address());
+        let client = match self.worker_client_for_shard(address) {
+            Ok(client) => client,
+            Err(error) => {
+                debug!(%error, "could not connect to shadow shard");
+                record_mirrored_request(false);
+                return;
+            }
+        };
+        let request = inner.clone();
+        tokio::spawn(async move {
+            let result = send(client, request).await;
+            record_mirrored_request(result.is_ok());
+        });
+    }
+
+    /// Records a copy of the request in the traffic capture, if there is one and the request
+    /// is sampled.
+    fn capture<R: Clone>(
         &self,
-        shard: &ShardConfig,
-    ) -> Result<ValidatorWorkerClient<Channel>> {
-        let address = shard.http_address();
+        inner: &R,
+        into_message: impl FnOnce(R) -> Result<CapturedMessage, GrpcProtoConversionError>,
+    ) {
+        let Some(capture) = &self.0.capture else {
+            return;
+        };
+        if !capture.sample() {
+            return;
+        }
+        match into_message(inner.clone()) {
+            Ok(message) => capture.record(message),
+            Err(error) => debug!(%error, "could not capture a request"),
+        }
+    }
+
+    fn worker_client_for_shard(&self, address: String) -> Result<ValidatorWorkerClient<Channel>> {
         let channel = self.0.worker_connection_pool.channel(address)?;
         let client = ValidatorWorkerClient::new(channel)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
@@ -216,20 +643,29 @@ impl GrpcProxy {
 
     /// Runs the proxy. If either the public server or private server dies for whatever
     /// reason we'll kill the proxy.
-    #[instrument(skip_all, fields(public_address = %self.public_address(), internal_address = %self.internal_address(), metrics_address = %self.metrics_address()), err)]
     pub async fn run(self) -> Result<()> {
+        self.run_with_layers(ProxyLayers::default()).await
+    }
+
+    /// Runs the proxy, with custom layers added to the stack of its public endpoints.
+    #[instrument(skip_all, fields(public_address = %self.public_address(), internal_address = %self.internal_address(), metrics_address = %self.metrics_address()), err)]
+    pub async fn run_with_layers(self, layers: ProxyLayers) -> Result<()> {
         info!("Starting gRPC server");
 
         #[cfg(with_metrics)]
         prometheus_server::start_metrics(self.metrics_address());
 
+        tokio::spawn(self.clone().check_shard_versions());
+
         let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
         health_reporter
             .set_serving::<ValidatorNodeServer<GrpcProxy>>()
             .await;
-        let internal_server = Server::builder()
+        let internal_server = self
+            .internal_server()?
             .add_service(self.as_notifier_service())
             .serve(self.internal_address());
+        let public_query_server = self.serve_public_queries(health_service.clone(), &layers);
         let reflection_service = tonic_reflection::server::Builder::configure()
             .register_encoded_file_descriptor_set(linera_rpc::FILE_DESCRIPTOR_SET)
             .build()?;
@@ -237,7 +673,12 @@ impl GrpcProxy {
             .public_server()?
             .layer(
                 ServiceBuilder::new()
+                    .layer(RequestIdLayer)
+                    .layer(IpFilterLayer::new(self.0.ip_filter.clone()))
+                    .layer(PeerLimitsLayer::new(self.0.peer_limits.clone()))
+                    .layer(ProtocolVersionLayer)
                     .layer(PrometheusMetricsMiddlewareLayer)
+                    .layer(layers.clone())
                     .into_inner(),
             )
             .accept_http1(true)
@@ -249,10 +690,132 @@ impl GrpcProxy {
         select! {
             internal_res = internal_server => internal_res?,
             public_res = public_server => public_res?,
+            public_query_res = public_query_server => public_query_res?,
         }
         Ok(())
     }
 
+    /// Serves the read-only public query endpoint, if it is enabled, with its own limits per
+    /// peer. Otherwise, never returns.
+    async fn serve_public_queries(
+        &self,
+        health_service: HealthServer<impl Health>,
+        layers: &ProxyLayers,
+    ) -> Result<()> {
+        let config = &self.0.public_queries;
+        let Some(port) = config.port else {
+            return futures::future::pending().await;
+        };
+        let address = SocketAddr::from(([0, 0, 0, 0], port));
+        info!("Serving public queries on {address}");
+        let peer_limits = PeerLimits::new(&PeerLimitsConfig {
+            max_concurrent_requests: config.max_concurrent_requests,
+            ..PeerLimitsConfig::default()
+        });
+        self.public_server()?
+            .layer(
+                ServiceBuilder::new()
+                    .layer(RequestIdLayer)
+                    .layer(IpFilterLayer::new(self.0.ip_filter.clone()))
+                    .layer(PublicQueryLayer::new(Arc::new(RateLimiter::new(config))))
+                    .layer(PeerLimitsLayer::new(Arc::new(peer_limits)))
+                    .layer(ProtocolVersionLayer)
+                    .layer(PrometheusMetricsMiddlewareLayer)
+                    .layer(layers.clone())
+                    .into_inner(),
+            )
+            .accept_http1(true)
+            .add_service(health_service)
+            .add_service(tonic_web::enable(self.as_validator_node()))
+            .serve(address)
+            .await?;
+        Ok(())
+    }
+
+    /// Queries the shard of the admin chain for the minimum version of the software that the
+    /// validators must run.
+    async fn minimum_version(&self) -> Result<Option<SoftwareVersion>> {
+        let admin_id = self.0.admin_id;
+        let query = linera_core::data_types::ChainInfoQuery::new(admin_id);
+        let shard = self.0.internal_config.get_shard_for(admin_id);
+        let info = match self.0.simple_shards {
+            Some(protocol) => {
+                let message = RpcMessage::ChainInfoQuery(Box::new(query));
+                match send_to_simple_shard(protocol, message, shard.address(), self.0.timeout)
+                    .await?
+                {
+                    Some(RpcMessage::ChainInfoResponse(response)) => response.info,
+                    response => anyhow::bail!("unexpected response: {response:?}"),
+                }
+            }
+            None => {
+                let address = self.0.internal_config.shard_grpc_uri(shard);
+                let mut client = self.worker_client_for_shard(address)?;
+                let result = client
+                    .handle_chain_info_query(ChainInfoQuery::try_from(query)?)
+                    .await?
+                    .into_inner();
+                match result.inner {
+                    Some(api::chain_info_result::Inner::ChainInfoResponse(response)) => {
+                        linera_core::data_types::ChainInfoResponse::try_from(response)?.info
+                    }
+                    inner => anyhow::bail!("unexpected response: {inner:?}"),
+                }
+            }
+        };
+        Ok(info.minimum_version)
+    }
+
+    /// Queries the version of every shard, to log the ones that differ from the proxy's or
+    /// are older than the minimum version required by the admin chain.
+    async fn check_shard_versions(self) {
+        let minimum_version = match self.minimum_version().await {
+            Ok(minimum_version) => minimum_version,
+            Err(error) => {
+                warn!(%error, "Could not get the minimum version required by the admin chain");
+                None
+            }
+        };
+        log_minimum_version(minimum_version);
+        for shard in &self.0.internal_config.shards {
+            let result = match self.0.simple_shards {
+                Some(protocol) => {
+                    let address = shard.address();
+                    let message = RpcMessage::VersionInfoQuery;
+                    match send_to_simple_shard(protocol, message, address.clone(), self.0.timeout)
+                        .await
+                    {
+                        Ok(Some(RpcMessage::VersionInfoResponse(version))) => {
+                            Ok((address, *version))
+                        }
+                        Ok(response) => Err((
+                            address,
+                            anyhow::anyhow!("unexpected response: {response:?}"),
+                        )),
+                        Err(error) => Err((address, error)),
+                    }
+                }
+                None => {
+                    let address = self.0.internal_config.shard_grpc_uri(shard);
+                    let result = match self.worker_client_for_shard(address.clone()) {
+                        Ok(mut client) => client.get_version_info(()).await.map_err(Into::into),
+                        Err(error) => Err(error),
+                    };
+                    match result {
+                        Ok(response) => Ok((address, response.into_inner().into())),
+                        Err(error) => Err((address, error)),
+                    }
+                }
+            };
+            match result {
+                Ok((address, version)) => log_shard_version(&address, &version, minimum_version),
+                Err((address, error)) => {
+                    warn!(address, %error, "Could not get the version of a shard")
+                }
+            }
+        }
+    }
+
     /// Pre-configures the public server with no services attached.
     /// If a certificate and key are defined, creates a TLS server.
     fn public_server(&self) -> Result<Server> {
@@ -268,36 +831,113 @@ impl GrpcProxy {
         }
     }
 
+    /// Pre-configures the internal server with no services attached. If the internal network
+    /// uses mutual TLS, it only accepts connections from the shards.
+    fn internal_server(&self) -> Result<Server> {
+        match &self.0.internal_config.mutual_tls {
+            Some(tls) => Ok(Server::builder().tls_config(tls.server_config()?)?),
+            None => Ok(Server::builder()),
+        }
+    }
+
+    /// Returns a client for the shard of the request, the request to send to it, and which
+    /// server of the shard the client is for. The
+    /// deadline set by the client, if any, is forwarded, so that the shard stops working on
+    /// the request when the client has given up. So are the storage trace token, if any, the
+    /// protocol version of the client and the ID of the request: the shard checks them, and
+    /// its response headers are returned to the client unchanged.
     async fn client_for_proxy_worker<R>(
         &self,
         request: Request<R>,
-    ) -> Result<(ValidatorWorkerClient<Channel>, R), Status>
+    ) -> Result<(ValidatorWorkerClient<Channel>, Request<R>, ShardTarget), Status>
     where
         R: Debug + GrpcProxyable,
     {
         debug!("proxying request from {:?}", request.remote_addr());
+        let timeout = grpc_timeout(&request);
+        let storage_trace_token = request.metadata().get(STORAGE_TRACE_TOKEN_HEADER).cloned();
+        let protocol_version = request.metadata().get(PROTOCOL_VERSION_HEADER).cloned();
+        let request_id = request.metadata().get(REQUEST_ID_HEADER).cloned();
         let inner = request.into_inner();
-        let shard = self
-            .shard_for(&inner)
+        let (address, target) = self
+            .shard_address_for(&inner)
             .ok_or_else(|| Status::not_found("could not find shard for message"))?;
         let client = self
-            .worker_client_for_shard(&shard)
+            .worker_client_for_shard(address)
             .map_err(|_| Status::internal("could not connect to shard"))?;
-        Ok((client, inner))
+        let mut request = Request::new(inner);
+        if let Some(timeout) = timeout {
+            request.set_timeout(timeout);
+        }
+        if let Some(token) = storage_trace_token {
+            request
+                .metadata_mut()
+                .insert(STORAGE_TRACE_TOKEN_HEADER, token);
+        }
+        if let Some(version) = protocol_version {
+            request
+                .metadata_mut()
+                .insert(PROTOCOL_VERSION_HEADER, version);
+        }
+        if let Some(request_id) = request_id {
+            request.metadata_mut().insert(REQUEST_ID_HEADER, request_id);
+        }
+        Ok((client, request, target))
+    }
+
+    /// Forwards a request to the shard of its chain over the simple transport, converting the
+    /// request and the response of the shard, and mirrors it to the shadow server of the
+    /// shard if it is sampled to be. Unlike with gRPC shards, the deadline and the headers of
+    /// the request are not forwarded: the simple transport has no place for them.
+    async fn forward_to_simple_shard<R, T>(
+        &self,
+        protocol: TransportProtocol,
+        request: Request<R>,
+        into_message: impl FnOnce(R) -> Result<RpcMessage, GrpcProtoConversionError>,
+        from_message: impl FnOnce(RpcMessage) -> Result<T, Status>,
+    ) -> Result<Response<T>, Status>
+    where
+        R: GrpcProxyable,
+    {
+        debug!("proxying request from {:?}", request.remote_addr());
+        let inner = request.into_inner();
+        let chain_id = inner.chain_id();
+        let (address, target) = self
+            .shard_address_for(&inner)
+            .ok_or_else(|| Status::not_found("could not find shard for message"))?;
+        let message = into_message(inner)?;
+        let timeout = self.0.timeout;
+        if let Some(shadow) = chain_id
+            .and_then(|chain_id| sample_shadow(self.0.internal_config.get_shard_for(chain_id)))
+        {
+            let mirrored_message = message.clone();
+            let shadow_address = shadow.address();
+            tokio::spawn(async move {
+                let result =
+                    send_to_simple_shard(protocol, mirrored_message, shadow_address, timeout).await;
+                record_mirrored_request(result.is_ok());
+            });
+        }
+        let response = target
+            .measure(send_to_simple_shard(protocol, message, address, timeout))
+            .await
+            .map_err(|error| Status::unavailable(format!("could not proxy the request: {error}")))?
+            .ok_or_else(|| Status::unavailable("the shard did not respond"))?;
+        Ok(Response::new(from_message(response)?))
     }
 
-    fn log_and_return_proxy_request_outcome(
-        result: Result<Response<ChainInfoResult>, Status>,
+    fn log_and_return_proxy_request_outcome<T>(
+        result: Result<Response<T>, Status>,
         method_name: &str,
-    ) -> Result<Response<ChainInfoResult>, Status> {
+    ) -> Result<Response<T>, Status> {
         #![allow(unused_variables)]
         match result {
-            Ok(chain_info_result) => {
+            Ok(response) => {
                 #[cfg(with_metrics)]
                 PROXY_REQUEST_SUCCESS
                     .with_label_values(&[method_name])
                     .inc();
-                Ok(chain_info_result)
+                Ok(response)
             }
             Err(status) => {
                 #[cfg(with_metrics)]
@@ -317,9 +957,29 @@ impl ValidatorNode for GrpcProxy {
         &self,
         request: Request<BlockProposal>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.client_for_proxy_worker(request).await?;
+        self.capture(request.get_ref(), |inner| {
+            Ok(CapturedMessage::BlockProposal(Box::new(inner.try_into()?)))
+        });
+        if let Some(protocol) = self.0.simple_shards {
+            return Self::log_and_return_proxy_request_outcome(
+                self.forward_to_simple_shard(
+                    protocol,
+                    request,
+                    |inner: BlockProposal| {
+                        Ok(RpcMessage::BlockProposal(Box::new(inner.try_into()?)))
+                    },
+                    chain_info_result,
+                )
+                .await,
+                "handle_block_proposal",
+            );
+        }
+        let (mut client, request, target) = self.client_for_proxy_worker(request).await?;
+        self.mirror(request.get_ref(), |mut client, inner| async move {
+            client.handle_block_proposal(inner).await
+        });
         Self::log_and_return_proxy_request_outcome(
-            client.handle_block_proposal(inner).await,
+            target.measure(client.handle_block_proposal(request)).await,
             "handle_block_proposal",
         )
     }
@@ -329,9 +989,33 @@ impl ValidatorNode for GrpcProxy {
         &self,
         request: Request<LiteCertificate>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.client_for_proxy_worker(request).await?;
+        self.capture(request.get_ref(), |inner| {
+            Ok(CapturedMessage::LiteCertificate(Box::new(
+                inner.try_into()?,
+            )))
+        });
+        if let Some(protocol) = self.0.simple_shards {
+            return Self::log_and_return_proxy_request_outcome(
+                self.forward_to_simple_shard(
+                    protocol,
+                    request,
+                    |inner: LiteCertificate| {
+                        Ok(RpcMessage::LiteCertificate(Box::new(inner.try_into()?)))
+                    },
+                    chain_info_result,
+                )
+                .await,
+                "handle_lite_certificate",
+            );
+        }
+        let (mut client, request, target) = self.client_for_proxy_worker(request).await?;
+        self.mirror(request.get_ref(), |mut client, inner| async move {
+            client.handle_lite_certificate(inner).await
+        });
         Self::log_and_return_proxy_request_outcome(
-            client.handle_lite_certificate(inner).await,
+            target
+                .measure(client.handle_lite_certificate(request))
+                .await,
             "handle_lite_certificate",
         )
     }
@@ -341,9 +1025,27 @@ impl ValidatorNode for GrpcProxy {
         &self,
         request: Request<Certificate>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.client_for_proxy_worker(request).await?;
+        self.capture(request.get_ref(), |inner| {
+            Ok(CapturedMessage::Certificate(Box::new(inner.try_into()?)))
+        });
+        if let Some(protocol) = self.0.simple_shards {
+            return Self::log_and_return_proxy_request_outcome(
+                self.forward_to_simple_shard(
+                    protocol,
+                    request,
+                    |inner: Certificate| Ok(RpcMessage::Certificate(Box::new(inner.try_into()?))),
+                    chain_info_result,
+                )
+                .await,
+                "handle_certificate",
+            );
+        }
+        let (mut client, request, target) = self.client_for_proxy_worker(request).await?;
+        self.mirror(request.get_ref(), |mut client, inner| async move {
+            client.handle_certificate(inner).await
+        });
         Self::log_and_return_proxy_request_outcome(
-            client.handle_certificate(inner).await,
+            target.measure(client.handle_certificate(request)).await,
             "handle_certificate",
         )
     }
@@ -353,13 +1055,84 @@ impl ValidatorNode for GrpcProxy {
         &self,
         request: Request<ChainInfoQuery>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.client_for_proxy_worker(request).await?;
+        if let Some(protocol) = self.0.simple_shards {
+            return Self::log_and_return_proxy_request_outcome(
+                self.forward_to_simple_shard(
+                    protocol,
+                    request,
+                    |inner: ChainInfoQuery| {
+                        Ok(RpcMessage::ChainInfoQuery(Box::new(inner.try_into()?)))
+                    },
+                    chain_info_result,
+                )
+                .await,
+                "handle_chain_info_query",
+            );
+        }
+        let (mut client, request, target) = self.client_for_proxy_worker(request).await?;
+        self.mirror(request.get_ref(), |mut client, inner| async move {
+            client.handle_chain_info_query(inner).await
+        });
         Self::log_and_return_proxy_request_outcome(
-            client.handle_chain_info_query(inner).await,
+            target
+                .measure(client.handle_chain_info_query(request))
+                .await,
             "handle_chain_info_query",
         )
     }
 
+    #[instrument(skip_all, err(Display))]
+    async fn simulate_block_proposal(
+        &self,
+        request: Request<BlockSimulationRequest>,
+    ) -> Result<Response<BlockSimulationResult>, Status> {
+        if let Some(protocol) = self.0.simple_shards {
+            return Self::log_and_return_proxy_request_outcome(
+                self.forward_to_simple_shard(
+                    protocol,
+                    request,
+                    |inner: BlockSimulationRequest| {
+                        Ok(RpcMessage::BlockSimulation(Box::new(inner.try_into()?)))
+                    },
+                    block_simulation_result,
+                )
+                .await,
+                "simulate_block_proposal",
+            );
+        }
+        let (mut client, request, target) = self.client_for_proxy_worker(request).await?;
+        Self::log_and_return_proxy_request_outcome(
+            target
+                .measure(client.simulate_block_proposal(request))
+                .await,
+            "simulate_block_proposal",
+        )
+    }
+
+    #[instrument(skip_all, err(Display))]
+    async fn download_blob(
+        &self,
+        request: Request<BlobRequest>,
+    ) -> Result<Response<BlobResult>, Status> {
+        if let Some(protocol) = self.0.simple_shards {
+            return Self::log_and_return_proxy_request_outcome(
+                self.forward_to_simple_shard(
+                    protocol,
+                    request,
+                    |inner: BlobRequest| Ok(RpcMessage::DownloadBlob(Box::new(inner.try_into()?))),
+                    blob_result,
+                )
+                .await,
+                "download_blob",
+            );
+        }
+        let (mut client, request, target) = self.client_for_proxy_worker(request).await?;
+        Self::log_and_return_proxy_request_outcome(
+            target.measure(client.download_blob(request)).await,
+            "download_blob",
+        )
+    }
+
     #[instrument(skip_all, err(Display))]
     async fn subscribe(
         &self,
@@ -380,9 +1153,45 @@ impl ValidatorNode for GrpcProxy {
         &self,
         _request: Request<()>,
     ) -> Result<Response<VersionInfo>, Status> {
-        // We assume each shard is running the same version as the proxy
+        // We assume each shard is running the same version as the proxy. The ones that
+        // don't are logged when the proxy starts.
         Ok(Response::new(linera_version::VersionInfo::default().into()))
     }
+
+    #[instrument(skip_all, err(Display))]
+    async fn get_genesis_config_hash(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<api::CryptoHash>, Status> {
+        Ok(Response::new(self.0.genesis_config_hash.into()))
+    }
+
+    #[instrument(skip_all, err(Display))]
+    async fn get_shard_address(
+        &self,
+        request: Request<api::ChainId>,
+    ) -> Result<Response<api::ShardAddress>, Status> {
+        let Some(trusted_peers) = &self.0.shard_hints else {
+            return Err(Status::unimplemented(
+                "this proxy does not give shard addresses",
+            ));
+        };
+        let is_trusted = request
+            .remote_addr()
+            .is_some_and(|address| trusted_peers.allows(address.ip().to_canonical()));
+        if !is_trusted {
+            return Err(Status::permission_denied(
+                "shard addresses are only given to trusted peers",
+            ));
+        }
+        let chain_id = ChainId::try_from(request.into_inner())
+            .map_err(|_| Status::invalid_argument("invalid chain ID"))?;
+        let config = &self.0.internal_config;
+        let endpoint = config.get_shard_endpoint_for(chain_id);
+        Ok(Response::new(api::ShardAddress {
+            address: config.grpc_uri(endpoint.address()),
+        }))
+    }
 }
 
 #[async_trait]
@@ -399,3 +1208,35 @@ impl NotifierService for GrpcProxy {
         Ok(Response::new(()))
     }
 }
+
+/// Converts the response of a simple shard to a request returning chain information.
+fn chain_info_result(message: RpcMessage) -> Result<ChainInfoResult, Status> {
+    match message {
+        RpcMessage::ChainInfoResponse(response) => Ok((*response).try_into()?),
+        RpcMessage::Error(error) => Ok((*error).try_into()?),
+        message => Err(unexpected_response(message)),
+    }
+}
+
+/// Converts the response of a simple shard to the simulation of a block.
+fn block_simulation_result(message: RpcMessage) -> Result<BlockSimulationResult, Status> {
+    match message {
+        RpcMessage::BlockSimulationResponse(executed_block) => Ok((*executed_block).try_into()?),
+        RpcMessage::Error(error) => Ok((*error).try_into()?),
+        message => Err(unexpected_response(message)),
+    }
+}
+
+/// Converts the response of a simple shard to the download of a blob.
+fn blob_result(message: RpcMessage) -> Result<BlobResult, Status> {
+    match message {
+        RpcMessage::DownloadBlobResponse(blob) => Ok((*blob).into()),
+        RpcMessage::Error(error) => Ok((*error).try_into()?),
+        message => Err(unexpected_response(message)),
+    }
+}
+
+/// Returns the error of a response of a simple shard that doesn't match the request.
+fn unexpected_response(message: RpcMessage) -> Status {
+    Status::internal(format!("unexpected response from the shard: {message:?}"))
+}
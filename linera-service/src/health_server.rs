@@ -0,0 +1,121 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Health endpoints for container orchestrators, e.g. Kubernetes probes.
+//!
+//! - `GET /health/live` succeeds as long as the process is running.
+//! - `GET /health/ready` succeeds once every [`HealthChecks`] check passes, e.g. the servers
+//!   of the process accept connections, the shards of a proxy are reachable and the store
+//!   answers. Otherwise, it fails with `503 Service Unavailable` and lists the failed checks.
+
+use std::{future::Future, net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use futures::future::{join_all, BoxFuture, FutureExt};
+use linera_rpc::{config::NetworkProtocol, simple::TransportProtocol};
+use tokio::net::TcpStream;
+use tracing::info;
+
+#[cfg(test)]
+#[path = "unit_tests/health_server.rs"]
+mod tests;
+
+/// How long to wait for a check to pass.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A check of the readiness endpoint, returning a description of the problem if it fails.
+type Check = Arc<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+/// The checks that must pass for the process to be ready to handle requests.
+#[derive(Clone, Default)]
+pub struct HealthChecks {
+    checks: Vec<(String, Check)>,
+}
+
+impl HealthChecks {
+    /// Adds a check that the server `name` accepts connections at `address`, given as
+    /// `host:port`.
+    pub fn with_server(self, name: impl Into<String>, address: String) -> Self {
+        self.with_check(name, move || {
+            let address = address.clone();
+            async move {
+                TcpStream::connect(&address)
+                    .await
+                    .map(drop)
+                    .map_err(|error| format!("cannot connect to {address}: {error}"))
+            }
+        })
+    }
+
+    /// Adds a check named `name`.
+    pub fn with_check<F>(
+        mut self,
+        name: impl Into<String>,
+        check: impl Fn() -> F + Send + Sync + 'static,
+    ) -> Self
+    where
+        F: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.checks
+            .push((name.into(), Arc::new(move || check().boxed())));
+        self
+    }
+
+    /// Runs the checks, and returns a description of each one that failed.
+    async fn failures(&self) -> Vec<String> {
+        let results = join_all(self.checks.iter().map(|(name, check)| async move {
+            match tokio::time::timeout(CHECK_TIMEOUT, check()).await {
+                Ok(Ok(())) => None,
+                Ok(Err(problem)) => Some(format!("{name}: {problem}")),
+                Err(_) => Some(format!("{name}: timed out")),
+            }
+        }))
+        .await;
+        results.into_iter().flatten().collect()
+    }
+}
+
+/// Returns whether servers using `protocol` accept TCP connections, as needed to check them
+/// with [`HealthChecks::with_server`].
+pub fn accepts_tcp(protocol: NetworkProtocol) -> bool {
+    !matches!(protocol, NetworkProtocol::Simple(TransportProtocol::Udp))
+}
+
+/// Starts serving the health endpoints on `address`.
+pub fn start_health_server(address: SocketAddr, checks: HealthChecks) {
+    info!("Starting to serve the health endpoints on {:?}", address);
+    let router = router(checks);
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(
+            tokio::net::TcpListener::bind(address).await.unwrap(),
+            router,
+        )
+        .await
+        {
+            panic!("Error serving the health endpoints: {}", e);
+        }
+    });
+}
+
+fn router(checks: HealthChecks) -> Router {
+    Router::new()
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+        .with_state(Arc::new(checks))
+}
+
+async fn live() -> StatusCode {
+    StatusCode::OK
+}
+
+async fn ready(
+    State(checks): State<Arc<HealthChecks>>,
+) -> Result<StatusCode, (StatusCode, Json<Vec<String>>)> {
+    let failures = checks.failures().await;
+    if failures.is_empty() {
+        Ok(StatusCode::OK)
+    } else {
+        Err((StatusCode::SERVICE_UNAVAILABLE, Json(failures)))
+    }
+}
@@ -4,15 +4,32 @@
 
 //! This module provides the executables needed to operate a Linera service, including a placeholder wallet acting as a GraphQL service for user interfaces.
 
+pub mod admin_server;
+pub mod balance_alert;
+#[cfg(feature = "benchmark")]
+pub mod benchmark_report;
+pub mod chain_archive;
 pub mod chain_listener;
+pub mod chain_watch;
 pub mod cli_wrappers;
 pub mod config;
+pub mod dashboard;
 pub mod faucet;
 pub mod grpc_proxy;
+pub mod health_server;
+pub mod mempool;
 pub mod node_service;
 pub mod project;
 #[cfg(with_metrics)]
 pub mod prometheus_server;
+pub mod replay;
+pub mod rest_api;
+pub mod routing;
+#[cfg(unix)]
+pub mod socket_api;
 pub mod storage;
+pub mod traffic_capture;
+pub mod transfer_batch;
 pub mod util;
+pub mod validator_proxy;
 pub mod wallet;
@@ -1,10 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{
-    path::PathBuf,
-    time::{Duration, Instant},
-};
+use std::{path::PathBuf, time::Instant};
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -12,7 +9,7 @@ use colored::Colorize;
 use futures::{lock::OwnedMutexGuard, Future};
 use linera_base::{
     crypto::{CryptoRng, KeyPair},
-    data_types::{BlockHeight, Timestamp},
+    data_types::{BlockHeight, TimeDelta, Timestamp},
     identifiers::{Account, BytecodeId, ChainId},
     ownership::ChainOwnership,
 };
@@ -23,7 +20,10 @@ use linera_core::{
     node::{CrossChainMessageDelivery, ValidatorNodeProvider},
 };
 use linera_execution::Bytecode;
-use linera_rpc::node_provider::{NodeOptions, NodeProvider};
+use linera_rpc::{
+    config::RetryPolicy,
+    node_provider::{NodeOptions, NodeProvider},
+};
 use linera_service::{
     chain_listener,
     config::{GenesisConfig, WalletState},
@@ -57,10 +57,12 @@ use {
         simple::SimpleMassClient, RpcMessage,
     },
     linera_sdk::abis::fungible,
+    linera_service::benchmark_report::{PhaseReport, Workload},
     std::{
         collections::{HashMap, HashSet},
         iter,
         sync::Arc,
+        time::Duration,
     },
     tracing::{error, trace},
 };
@@ -70,10 +72,7 @@ use crate::{client_options::ChainOwnershipConfig, ClientOptions};
 pub struct ClientContext {
     wallet_state: WalletState,
     chain_client_builder: ChainClientBuilder<NodeProvider>,
-    send_timeout: Duration,
-    recv_timeout: Duration,
-    notification_retry_delay: Duration,
-    notification_retries: u32,
+    node_options: NodeOptions,
     prng: Box<dyn CryptoRng>,
 }
 
@@ -115,7 +114,7 @@ impl ClientContext {
     ) -> Result<Self, anyhow::Error> {
         let wallet_state_path = match &options.wallet_state_path {
             Some(path) => path.clone(),
-            None => Self::create_default_wallet_path()?,
+            None => Self::create_default_wallet_path(options)?,
         };
         anyhow::ensure!(
             !wallet_state_path.exists(),
@@ -134,9 +133,12 @@ impl ClientContext {
     pub fn from_options(options: &ClientOptions) -> Result<Self, anyhow::Error> {
         let wallet_state_path = match &options.wallet_state_path {
             Some(path) => path.clone(),
-            None => Self::create_default_wallet_path()?,
+            None => Self::create_default_wallet_path(options)?,
+        };
+        let wallet_state = match options.ephemeral_seed {
+            Some(seed) => WalletState::read_ephemeral(&wallet_state_path, seed)?,
+            None => WalletState::from_file(&wallet_state_path)?,
         };
-        let wallet_state = WalletState::from_file(&wallet_state_path)?;
         Ok(Self::configure(options, wallet_state))
     }
 
@@ -153,41 +155,113 @@ impl ClientContext {
     fn configure(options: &ClientOptions, wallet_state: WalletState) -> Self {
         let prng = wallet_state.inner().make_prng();
 
+        let policies = wallet_state.inner().rpc_policies();
         let node_options = NodeOptions {
-            send_timeout: options.send_timeout,
-            recv_timeout: options.recv_timeout,
-            notification_retry_delay: options.notification_retry_delay,
-            notification_retries: options.notification_retries,
+            timeout: policies
+                .timeout
+                .unwrap_or_default()
+                .with_overrides(options.send_timeout_ms, options.recv_timeout_ms),
+            notification_retry: policies
+                .notification_retry
+                .unwrap_or(RetryPolicy::NOTIFICATIONS)
+                .with_overrides(
+                    options.notification_retries,
+                    options.notification_retry_delay_ms,
+                ),
+            direct_to_shards: options.direct_to_shards,
         };
         let node_provider = NodeProvider::new(node_options);
         let delivery = CrossChainMessageDelivery::new(options.wait_for_outgoing_messages);
         let chain_client_builder =
-            ChainClientBuilder::new(node_provider, options.max_pending_messages, delivery);
+            ChainClientBuilder::new(node_provider, options.max_pending_messages, delivery)
+                .with_proposal_ttl(options.proposal_ttl.map(TimeDelta::from_duration))
+                .with_max_conflict_retries(options.max_conflict_retries)
+                .with_validator_reputations(wallet_state.inner().validator_reputations().clone());
         ClientContext {
             chain_client_builder,
             wallet_state,
-            send_timeout: options.send_timeout,
-            recv_timeout: options.recv_timeout,
-            notification_retry_delay: options.notification_retry_delay,
-            notification_retries: options.notification_retries,
+            node_options,
             prng,
         }
     }
 
-    fn create_default_config_path() -> Result<PathBuf, anyhow::Error> {
+    /// Returns the directory of the configuration of the client, without creating it.
+    fn config_root_path() -> Result<PathBuf, anyhow::Error> {
         let mut config_dir = dirs::config_dir()
             .context("Default configuration directory not supported. Please specify a path.")?;
         config_dir.push("linera");
+        Ok(config_dir)
+    }
+
+    /// Returns the directory of the configuration of the given profile.
+    fn profile_path(name: &str) -> Result<PathBuf, anyhow::Error> {
+        Ok(Self::config_root_path()?.join("profiles").join(name))
+    }
+
+    /// Returns the file storing the name of the current profile.
+    fn current_profile_path() -> Result<PathBuf, anyhow::Error> {
+        Ok(Self::config_root_path()?.join("current_profile"))
+    }
+
+    /// Returns the profile selected with `--profile`, or else with `linera profile switch`.
+    pub fn current_profile(options: &ClientOptions) -> Result<Option<String>, anyhow::Error> {
+        if let Some(profile) = &options.profile {
+            return Ok(Some(profile.clone()));
+        }
+        let path = Self::current_profile_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let name = fs_err::read_to_string(&path)?.trim().to_string();
+        Ok((!name.is_empty()).then_some(name))
+    }
+
+    /// Returns the names of the existing profiles, in alphabetical order.
+    pub fn profiles() -> Result<Vec<String>, anyhow::Error> {
+        let path = Self::config_root_path()?.join("profiles");
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in fs_err::read_dir(&path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                names.extend(entry.file_name().to_str().map(str::to_string));
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Makes the given profile the default one. Its wallet must already exist.
+    pub fn switch_profile(name: &str) -> Result<(), anyhow::Error> {
+        let wallet_path = Self::profile_path(name)?.join("wallet.json");
+        anyhow::ensure!(
+            wallet_path.exists(),
+            "Profile {name} has no wallet at {}. Create one with `linera --profile {name} wallet init`",
+            wallet_path.display()
+        );
+        fs_err::write(Self::current_profile_path()?, name)?;
+        Ok(())
+    }
+
+    /// Returns the directory of the configuration of the current profile, if any, or else
+    /// of the client, creating it if needed.
+    fn create_default_config_path(options: &ClientOptions) -> Result<PathBuf, anyhow::Error> {
+        let config_dir = match Self::current_profile(options)? {
+            Some(profile) => Self::profile_path(&profile)?,
+            None => Self::config_root_path()?,
+        };
         if !config_dir.exists() {
             debug!("{} does not exist, creating", config_dir.display());
-            fs_err::create_dir(&config_dir)?;
+            fs_err::create_dir_all(&config_dir)?;
             debug!("{} created.", config_dir.display());
         }
         Ok(config_dir)
     }
 
-    fn create_default_wallet_path() -> Result<PathBuf, anyhow::Error> {
-        Ok(Self::create_default_config_path()?.join("wallet.json"))
+    fn create_default_wallet_path(options: &ClientOptions) -> Result<PathBuf, anyhow::Error> {
+        Ok(Self::create_default_config_path(options)?.join("wallet.json"))
     }
 
     pub fn storage_config(
@@ -198,7 +272,7 @@ impl ClientContext {
             #[cfg(feature = "rocksdb")]
             None => {
                 let storage_config = linera_service::storage::StorageConfig::RocksDb {
-                    path: Self::create_default_config_path()?.join("wallet.db"),
+                    path: Self::create_default_config_path(options)?.join("wallet.db"),
                 };
                 let namespace = "default".to_string();
                 Ok(StorageConfigNamespace {
@@ -252,18 +326,17 @@ impl ClientContext {
     }
 
     fn make_node_options(&self) -> NodeOptions {
-        NodeOptions {
-            send_timeout: self.send_timeout,
-            recv_timeout: self.recv_timeout,
-            notification_retry_delay: self.notification_retry_delay,
-            notification_retries: self.notification_retries,
-        }
+        self.node_options
     }
 
     pub fn save_wallet(&mut self) {
-        self.wallet_state
-            .inner_mut()
-            .refresh_prng_seed(&mut self.prng);
+        let reputations = self.chain_client_builder.validator_reputations();
+        let wallet = self.wallet_state.inner_mut();
+        wallet.refresh_prng_seed(&mut self.prng);
+        wallet.set_validator_reputations(reputations);
+        if self.wallet_state.is_ephemeral() {
+            return;
+        }
         self.wallet_state
             .write()
             .expect("Unable to write user chains");
@@ -304,6 +377,7 @@ impl ClientContext {
                 timestamp,
                 next_block_height: BlockHeight::ZERO,
                 pending_block: None,
+                balance: None,
             });
         }
     }
@@ -658,32 +732,44 @@ impl ClientContext {
         Ok(())
     }
 
-    /// Makes one block proposal per chain, up to `num_chains` blocks.
+    /// Makes one block proposal per chain, up to `num_chains` blocks. The workloads are
+    /// assigned to the chains in turn.
     pub fn make_benchmark_block_proposals(
         &mut self,
         key_pairs: &HashMap<ChainId, KeyPair>,
         transactions_per_block: usize,
+        workloads: &[Workload],
         fungible_application_id: Option<ApplicationId>,
-    ) -> Vec<RpcMessage> {
+    ) -> anyhow::Result<Vec<RpcMessage>> {
         let mut proposals = Vec::new();
         let mut next_recipient = self.wallet_mut().last_chain().unwrap().chain_id;
         let amount = Amount::from(1);
-        for (&chain_id, key_pair) in key_pairs {
+        for (index, (&chain_id, key_pair)) in key_pairs.iter().enumerate() {
             let public_key = key_pair.public();
-            let operation = match fungible_application_id {
-                Some(application_id) => Self::fungible_transfer(
-                    application_id,
+            let operation = match Workload::for_chain(workloads, index) {
+                Workload::Fungible => Self::fungible_transfer(
+                    fungible_application_id
+                        .context("the fungible workload requires a fungible application ID")?,
                     next_recipient,
                     public_key,
                     public_key,
                     amount,
                 ),
-                None => Operation::System(SystemOperation::Transfer {
+                Workload::CrossChain => Operation::System(SystemOperation::Transfer {
                     owner: None,
                     recipient: Recipient::chain(next_recipient),
                     amount,
                     user_data: UserData::default(),
                 }),
+                Workload::Transfer => Operation::System(SystemOperation::Transfer {
+                    owner: None,
+                    recipient: Recipient::Account(Account::owner(
+                        chain_id,
+                        Owner::from(public_key),
+                    )),
+                    amount,
+                    user_data: UserData::default(),
+                }),
             };
             let operations = iter::repeat(operation)
                 .take(transactions_per_block)
@@ -707,12 +793,13 @@ impl ClientContext {
                 },
                 key_pair,
                 vec![],
+                vec![],
                 None,
             );
             proposals.push(proposal.into());
             next_recipient = chain.chain_id;
         }
-        proposals
+        Ok(proposals)
     }
 
     /// Tries to aggregate votes into certificates.
@@ -756,13 +843,16 @@ impl ClientContext {
         certificates
     }
 
-    /// Broadcasts a bulk of blocks to each validator.
+    /// Broadcasts a bulk of blocks to each validator. If a `target_rate` is given, the
+    /// requests are sent to each validator in batches, at that many requests per second, and
+    /// the latency of each batch is reported.
     pub async fn mass_broadcast(
         &self,
         phase: &'static str,
         max_in_flight: usize,
+        target_rate: Option<u32>,
         proposals: Vec<RpcMessage>,
-    ) -> Vec<RpcMessage> {
+    ) -> (Vec<RpcMessage>, PhaseReport) {
         let time_start = Instant::now();
         info!("Broadcasting {} {}", proposals.len(), phase);
         let mut handles = Vec::new();
@@ -770,32 +860,68 @@ impl ClientContext {
             let proposals = proposals.clone();
             handles.push(tokio::spawn(async move {
                 debug!("Sending {} requests", proposals.len());
-                let responses = client
-                    .send(proposals, max_in_flight)
-                    .await
-                    .unwrap_or_default();
+                let Some(rate) = target_rate else {
+                    let responses = client
+                        .send(proposals, max_in_flight)
+                        .await
+                        .unwrap_or_default();
+                    debug!("Done sending requests");
+                    return (responses, Vec::new());
+                };
+                // Send a tenth of the requests of each second, every 100 ms.
+                let batch_size = usize::try_from(rate / 10).unwrap_or(usize::MAX).max(1);
+                let mut interval = tokio::time::interval(Duration::from_millis(100));
+                let mut responses = Vec::new();
+                let mut latencies = Vec::new();
+                for batch in proposals.chunks(batch_size) {
+                    interval.tick().await;
+                    let batch_start = Instant::now();
+                    responses.extend(
+                        client
+                            .send(batch.to_vec(), max_in_flight)
+                            .await
+                            .unwrap_or_default(),
+                    );
+                    latencies.push(batch_start.elapsed());
+                }
                 debug!("Done sending requests");
-                responses
+                (responses, latencies)
             }));
         }
-        let responses = futures::future::join_all(handles)
+        let mut responses = Vec::new();
+        let mut latencies = Vec::new();
+        for (validator_responses, validator_latencies) in futures::future::join_all(handles)
             .await
             .into_iter()
             .flatten()
-            .flatten()
-            .collect::<Vec<RpcMessage>>();
+        {
+            responses.extend(validator_responses);
+            latencies.extend(validator_latencies);
+        }
         let time_elapsed = time_start.elapsed();
+        let report = PhaseReport::new(
+            phase,
+            proposals.len(),
+            responses.len(),
+            time_elapsed,
+            latencies,
+        );
         info!(
             "Received {} responses in {} ms.",
             responses.len(),
             time_elapsed.as_millis()
         );
         info!(
-            "Estimated server throughput: {} {} per sec",
-            (proposals.len() as u128) * 1_000_000 / time_elapsed.as_micros(),
-            phase
+            "Estimated server throughput: {:.0} {} per sec",
+            report.throughput_per_sec, phase
         );
-        responses
+        if let Some(latency) = &report.latency {
+            info!(
+                "Latency of {} batches: p50 {:.1} ms, p90 {:.1} ms, p99 {:.1} ms, max {:.1} ms",
+                latency.samples, latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.max_ms
+            );
+        }
+        (responses, report)
     }
 
     fn make_validator_mass_clients(&self) -> Vec<Box<dyn MassClient + Send>> {
@@ -806,8 +932,8 @@ impl ClientContext {
                     let network = config.network.clone_with_protocol(protocol);
                     Box::new(SimpleMassClient::new(
                         network,
-                        self.send_timeout,
-                        self.recv_timeout,
+                        self.node_options.timeout.send_timeout(),
+                        self.node_options.timeout.recv_timeout(),
                     ))
                 }
                 NetworkProtocol::Grpc { .. } => Box::new(
@@ -7,17 +7,20 @@ use anyhow::Error;
 use chrono::{DateTime, Utc};
 use linera_base::{
     crypto::PublicKey,
-    data_types::{Amount, TimeDelta},
+    data_types::{Amount, SoftwareVersion, TimeDelta},
     identifiers::{Account, ApplicationId, BytecodeId, ChainId, MessageId, Owner},
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_core::client::MessagePolicy;
 use linera_execution::{
-    committee::ValidatorName, system::SystemChannel, UserApplicationId, WasmRuntime,
-    WithWasmDefault,
+    committee::ValidatorName,
+    set_wasm_artifact_directory,
+    system::{FeeSource, SystemChannel},
+    UserApplicationId, WasmRuntime, WithWasmDefault,
 };
 use linera_service::{
     chain_listener::{ChainListenerConfig, ClientContext as _},
+    mempool::MempoolConfig,
     storage::{full_initialize_storage, run_with_storage},
     util,
 };
@@ -36,31 +39,54 @@ pub struct ClientOptions {
     #[arg(long = "wallet")]
     pub wallet_state_path: Option<PathBuf>,
 
-    /// Storage configuration for the blockchain history.
+    /// Storage configuration for the blockchain history. The verified certificates and chain
+    /// states are kept there, so that they are not downloaded and checked again after a
+    /// restart. Defaults to a RocksDB database in the configuration directory, if supported.
     #[arg(long = "storage")]
     pub storage_config: Option<String>,
 
+    /// The profile to use, e.g. `localnet`, `devnet` or `testnet`. Each profile has its own
+    /// wallet and storage in the configuration directory. Defaults to the profile selected
+    /// with `linera profile switch`, if any. Ignored for the wallet or the storage if they
+    /// are set explicitly.
+    #[arg(long, env = "LINERA_PROFILE", value_parser = parse_profile_name)]
+    pub profile: Option<String>,
+
     /// Given an integer value N, read the wallet state and the wallet storage config from the
     /// environment variables LINERA_WALLET_{N} and LINERA_STORAGE_{N} instead of
     /// LINERA_WALLET and LINERA_STORAGE.
     #[arg(long, short = 'w')]
     pub with_wallet: Option<u32>,
 
-    /// Timeout for sending queries (milliseconds)
-    #[arg(long = "send-timeout-ms", default_value = "4000", value_parser = util::parse_millis)]
-    pub send_timeout: Duration,
+    /// Use the wallet in memory only, deriving new key pairs from this seed. The wallet file
+    /// is neither locked nor written, so that parallel tests or benchmark workers can share it.
+    #[arg(long, env = "LINERA_EPHEMERAL_SEED")]
+    pub ephemeral_seed: Option<u64>,
 
-    /// Timeout for receiving responses (milliseconds)
-    #[arg(long = "recv-timeout-ms", default_value = "4000", value_parser = util::parse_millis)]
-    pub recv_timeout: Duration,
+    /// Timeout for sending queries (milliseconds). Defaults to the timeout policy of the
+    /// wallet, or to 4000
+    #[arg(long)]
+    pub send_timeout_ms: Option<u64>,
+
+    /// Timeout for receiving responses (milliseconds). Defaults to the timeout policy of the
+    /// wallet, or to 4000
+    #[arg(long)]
+    pub recv_timeout_ms: Option<u64>,
 
     #[arg(long, default_value = "10")]
     pub max_pending_messages: usize,
 
-    /// The WebAssembly runtime to use.
+    /// The WebAssembly runtime to use: `wasmer` or `wasmtime`, depending on the features
+    /// the binary was built with, optionally followed by `-with-sanitizer`. Validators mixing
+    /// runtimes must all use a sanitizer, so that applications consume the same fuel
     #[arg(long)]
     pub wasm_runtime: Option<WasmRuntime>,
 
+    /// A directory where compiled WebAssembly modules are saved, so that they don't have to be
+    /// compiled again after a restart
+    #[arg(long)]
+    pub wasm_artifact_cache: Option<PathBuf>,
+
     /// The maximal number of simultaneous queries to the database
     #[arg(long)]
     pub max_concurrent_queries: Option<usize>,
@@ -77,17 +103,21 @@ pub struct ClientOptions {
     #[command(subcommand)]
     pub command: ClientCommand,
 
-    /// Delay increment for retrying to connect to a validator for notifications.
-    #[arg(
-        long = "notification-retry-delay-ms",
-        default_value = "1000",
-        value_parser = util::parse_millis
-    )]
-    pub notification_retry_delay: Duration,
+    /// Delay increment for retrying to connect to a validator for notifications
+    /// (milliseconds). Defaults to the notification retry policy of the wallet, or to 1000
+    #[arg(long)]
+    pub notification_retry_delay_ms: Option<u64>,
 
-    /// Number of times to retry connecting to a validator for notifications.
-    #[arg(long, default_value = "10")]
-    pub notification_retries: u32,
+    /// Number of times to retry connecting to a validator for notifications. Defaults to the
+    /// notification retry policy of the wallet, or to 10
+    #[arg(long)]
+    pub notification_retries: Option<u32>,
+
+    /// Whether to send the requests about a chain directly to the validators' shards of the
+    /// chain, if their proxies trust us to tell us their addresses, e.g. because we are
+    /// another validator.
+    #[arg(long)]
+    pub direct_to_shards: bool,
 
     /// Whether to wait until a quorum of validators has confirmed that all sent cross-chain
     /// messages have been delivered.
@@ -101,10 +131,21 @@ pub struct ClientOptions {
     /// The policy for handling incoming messages.
     #[arg(long, default_value = "accept")]
     pub message_policy: MessagePolicy,
+
+    /// Abandon a pending block if it has not been confirmed after this many milliseconds, and
+    /// propose a new one at the same height if that is safe.
+    #[arg(long = "proposal-ttl-ms", value_parser = util::parse_millis)]
+    pub proposal_ttl: Option<Duration>,
+
+    /// How many times to rebuild and propose a block again if other clients commit blocks to
+    /// the same chain first.
+    #[arg(long, default_value = "5")]
+    pub max_conflict_retries: usize,
 }
 
 impl ClientOptions {
     pub fn init() -> Result<Self, anyhow::Error> {
+        linera_version::VersionInfo::handle_verbose_version_flag();
         let mut options = <ClientOptions as clap::Parser>::parse();
         let suffix = match options.with_wallet {
             None => String::new(),
@@ -125,6 +166,9 @@ impl ClientOptions {
         let context = ClientContext::from_options(&self)?;
         let genesis_config = context.wallet().genesis_config().clone();
         let wasm_runtime = self.wasm_runtime.with_wasm_default();
+        if let Some(directory) = &self.wasm_artifact_cache {
+            set_wasm_artifact_directory(directory)?;
+        }
         let max_concurrent_queries = self.max_concurrent_queries;
         let max_stream_queries = self.max_stream_queries;
         let cache_size = self.cache_size;
@@ -183,6 +227,67 @@ pub enum ClientCommand {
         amount: Amount,
     },
 
+    /// Transfer funds to several recipients in a single operation
+    TransferMany {
+        /// Sending chain ID (must be one of our chains)
+        #[arg(long = "from")]
+        sender: Account,
+
+        /// Recipient accounts
+        #[arg(long = "to", required = true)]
+        recipients: Vec<Account>,
+
+        /// Amounts to transfer, one for each recipient, in the same order
+        #[arg(long = "amount", required = true)]
+        amounts: Vec<Amount>,
+    },
+
+    /// Transfer funds to the recipients listed in a CSV file, in as few blocks as possible
+    ///
+    /// Each line of the file is `RECIPIENT,AMOUNT`. If a block fails, the following ones are
+    /// not submitted. The outcome of each transfer is written to a CSV report.
+    TransferBatch {
+        /// Sending chain ID (must be one of our chains)
+        #[arg(long = "from")]
+        sender: Account,
+
+        /// The CSV file listing the transfers
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Where to write the report, instead of the standard output
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// Allow another owner to transfer funds from an account, up to the given amount
+    Approve {
+        /// The account, as `CHAIN-ID:OWNER`, on one of our chains
+        #[arg(long = "from")]
+        owner: Account,
+
+        /// The owner allowed to transfer the funds
+        #[arg(long)]
+        spender: Owner,
+
+        /// The maximum amount to transfer; zero revokes the allowance
+        amount: Amount,
+    },
+
+    /// Transfer funds from another owner's account, using the allowance they gave us
+    TransferFrom {
+        /// The account, as `CHAIN-ID:OWNER`, on one of our chains
+        #[arg(long = "from")]
+        owner: Account,
+
+        /// Recipient account
+        #[arg(long = "to")]
+        recipient: Account,
+
+        /// Amount to transfer
+        amount: Amount,
+    },
+
     /// Open (i.e. activate) a new chain deriving the UID from an existing one.
     OpenChain {
         /// Chain ID (must be one of our chains).
@@ -257,6 +362,35 @@ pub enum ClientCommand {
         ownership_config: ChainOwnershipConfig,
     },
 
+    /// Start transferring the chain to a new owner key, e.g. one of another wallet.
+    ///
+    /// The new key is added to the owners of the chain, and the current owners keep their keys
+    /// until the new owner runs `accept-ownership`. Changing the ownership in the meantime
+    /// cancels the transfer.
+    TransferOwnership {
+        /// The ID of the chain to transfer.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+
+        /// The public key of the new owner.
+        #[arg(long)]
+        new_public_key: PublicKey,
+    },
+
+    /// Accept the transfer of a chain to a key of this wallet, created with `linera keygen`.
+    ///
+    /// Once accepted, the key is the only owner of the chain, and the keys of the previous
+    /// owners can no longer propose blocks.
+    AcceptOwnership {
+        /// The ID of the chain being transferred.
+        #[arg(long)]
+        chain_id: ChainId,
+
+        /// The public key that the chain is transferred to.
+        #[arg(long)]
+        key: PublicKey,
+    },
+
     /// Changes the application permissions configuration.
     ChangeApplicationPermissions {
         /// The ID of the chain to which the new permissions will be applied.
@@ -267,9 +401,29 @@ pub enum ClientCommand {
         /// no system operations.
         #[arg(long)]
         execute_operations: Option<Vec<ApplicationId>>,
+        /// At least one operation or incoming message from each of these applications must occur in
+        /// every block.
+        #[arg(long)]
+        mandatory_applications: Vec<ApplicationId>,
         /// These applications are allowed to close the current chain using the system API.
         #[arg(long)]
         close_chain: Vec<ApplicationId>,
+        /// If this is not set, any signer of a block can create applications on the chain
+        /// and request applications from other chains. If it is set, only the specified
+        /// owners can.
+        #[arg(long)]
+        register_applications: Option<Vec<Owner>>,
+    },
+
+    /// Change which account pays the fees of the chain's blocks.
+    ChangeFeeSource {
+        /// The ID of the chain whose fee source is changed.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+        /// The account paying the fees: the chain's balance, or the balance of the owner
+        /// proposing each block.
+        #[arg(value_enum)]
+        fee_source: FeeSource,
     },
 
     /// Close an existing chain.
@@ -280,6 +434,35 @@ pub enum ClientCommand {
         /// Chain ID (must be one of our chains)
         #[arg(long = "from")]
         chain_id: ChainId,
+
+        /// Transfer the chain's balance to this account before closing the chain. The fees
+        /// of the block are then paid from the signer's account
+        #[arg(long = "reclaim-to")]
+        reclaim_to: Option<Account>,
+    },
+
+    /// Re-execute the blocks of exported chains against a fresh in-memory storage, and
+    /// report the first block whose outcome differs from the one recorded in its certificate.
+    ///
+    /// The archives are written by `linera-server export-chain`. They are replayed in order,
+    /// so the chains publishing bytecodes must come before the chains using them.
+    Replay {
+        /// The archives to replay.
+        #[arg(required = true)]
+        archives: Vec<PathBuf>,
+    },
+
+    /// Report to the admin chain that validators signed conflicting blocks.
+    ///
+    /// The evidence is found by the validators' shards, and can be downloaded from the
+    /// `/equivocations` endpoint of their admin API.
+    ReportEquivocation {
+        /// The chain proposing the block with the reports. If omitted, uses the default chain
+        /// of the wallet.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+        /// A JSON file with the list of evidence, as returned by the admin API.
+        evidence: PathBuf,
     },
 
     /// Read the current native-token balance of the given account directly from the local
@@ -332,6 +515,16 @@ pub enum ClientCommand {
     ProcessInbox {
         /// The chain to process. If omitted, uses the default chain of the wallet.
         chain_id: Option<ChainId>,
+
+        /// The IDs of incoming messages to reject without executing them, e.g. unwanted
+        /// transfers. Tracked messages bounce back to their sender.
+        #[arg(long = "reject")]
+        reject: Vec<MessageId>,
+
+        /// The IDs of incoming messages to leave in the inbox. Only messages that are
+        /// neither tracked nor protected, and don't carry a grant, can be skipped.
+        #[arg(long = "skip")]
+        skip: Vec<MessageId>,
     },
 
     /// Show the current set of validators for a chain.
@@ -340,6 +533,18 @@ pub enum ClientCommand {
         chain_id: Option<ChainId>,
     },
 
+    /// Upload to a validator the certificates of a chain that it is missing, and those of
+    /// the sender chains that its inboxes are waiting for, so that it catches up with the
+    /// other validators.
+    SyncValidator {
+        /// The public key of the validator to update.
+        name: ValidatorName,
+
+        /// The chain to upload. If omitted, uses the default chain of the wallet.
+        #[arg(long = "chain")]
+        chain_id: Option<ChainId>,
+    },
+
     /// Add or modify a validator (admin only)
     SetValidator {
         /// The public key of the validator.
@@ -362,6 +567,75 @@ pub enum ClientCommand {
         name: ValidatorName,
     },
 
+    /// Replace the key of a validator with the one generated by `linera-server
+    /// generate-next-key`, keeping its address and voting power (admin only)
+    RotateValidatorKey {
+        /// The current public key of the validator.
+        #[arg(long)]
+        name: ValidatorName,
+
+        /// The new public key of the validator.
+        #[arg(long)]
+        new_name: ValidatorName,
+    },
+
+    /// Propose to add or modify a validator, to be voted on by the owners of the admin chain
+    ProposeValidator {
+        /// The public key of the validator.
+        #[arg(long)]
+        name: ValidatorName,
+
+        /// Network address
+        #[arg(long)]
+        address: String,
+
+        /// Voting power
+        #[arg(long, default_value = "1")]
+        votes: u64,
+    },
+
+    /// Propose to remove a validator, to be voted on by the owners of the admin chain
+    ProposeValidatorRemoval {
+        /// The public key of the validator.
+        #[arg(long)]
+        name: ValidatorName,
+    },
+
+    /// Propose to replace the key of a validator, to be voted on by the owners of the admin
+    /// chain
+    ProposeValidatorKeyRotation {
+        /// The current public key of the validator.
+        #[arg(long)]
+        name: ValidatorName,
+
+        /// The new public key of the validator.
+        #[arg(long)]
+        new_name: ValidatorName,
+    },
+
+    /// Vote for a pending change of the validator set (admin chain owners only)
+    VoteCommitteeChange {
+        /// The ID of the proposal, as shown by `committee-proposals`.
+        proposal_id: u32,
+    },
+
+    /// Apply a change of the validator set that a majority of the owners of the admin chain
+    /// voted for, and remove the previous committee
+    FinalizeCommitteeChange {
+        /// The ID of the proposal, as shown by `committee-proposals`.
+        proposal_id: u32,
+    },
+
+    /// Show the pending changes of the validator set, and the owners who voted for them
+    CommitteeProposals,
+
+    /// Set the minimum version of the software that the validators must run, to coordinate an
+    /// upgrade of the network (admin chain owners only)
+    SetMinimumVersion {
+        /// The minimum version, e.g. `0.12.0`.
+        version: SoftwareVersion,
+    },
+
     /// View or update the resource control policy
     ResourceControlPolicy {
         /// Set the base price for creating a block.
@@ -408,6 +682,14 @@ pub enum ClientCommand {
         #[arg(long)]
         message_byte: Option<Amount>,
 
+        /// Set the fuel charged for each call from an application to the system API.
+        #[arg(long)]
+        host_call_fuel: Option<u64>,
+
+        /// Set the maximum fuel used per block.
+        #[arg(long)]
+        maximum_fuel_per_block: Option<u64>,
+
         /// Set the maximum read data per block.
         #[arg(long)]
         maximum_bytes_read_per_block: Option<u64>,
@@ -415,6 +697,30 @@ pub enum ClientCommand {
         /// Set the maximum write data per block.
         #[arg(long)]
         maximum_bytes_written_per_block: Option<u64>,
+
+        /// Set the maximum size of a published contract or service bytecode.
+        #[arg(long)]
+        maximum_bytecode_size: Option<u64>,
+
+        /// Set the maximum depth of nested calls between applications.
+        #[arg(long)]
+        maximum_call_depth: Option<u32>,
+
+        /// Set the maximum number of bytes each application can store on a chain.
+        #[arg(long)]
+        maximum_bytes_stored_per_application: Option<u64>,
+
+        /// Set the maximum number of operations in a block.
+        #[arg(long)]
+        maximum_operations_per_block: Option<u64>,
+
+        /// Set the maximum number of incoming messages in a block.
+        #[arg(long)]
+        maximum_incoming_messages_per_block: Option<u64>,
+
+        /// Set the maximum size of a block, in bytes.
+        #[arg(long)]
+        maximum_block_size: Option<u64>,
     },
 
     /// Send one transfer per chain in bulk mode
@@ -438,9 +744,28 @@ pub enum ClientCommand {
         transactions_per_block: usize,
 
         /// The application ID of a fungible token on the wallet's default chain.
-        /// If none is specified, the benchmark uses the native token.
+        /// It is required by the `fungible` workload.
         #[arg(long)]
         fungible_application_id: Option<linera_base::identifiers::ApplicationId>,
+
+        /// The kinds of operations in the blocks, as a comma-separated list. The workloads
+        /// are assigned to the chains in turn, to benchmark a mix of them.
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "cross-chain")]
+        workload: Vec<linera_service::benchmark_report::Workload>,
+
+        /// The number of requests per second to send to each validator. By default, requests
+        /// are sent as fast as possible and latencies are not measured.
+        #[arg(long)]
+        target_rate: Option<u32>,
+
+        /// The number of shards of each validator, to report the certificates handled by each
+        /// shard.
+        #[arg(long)]
+        num_shards: Option<usize>,
+
+        /// Write the results of the benchmark to this file, as JSON.
+        #[arg(long)]
+        output: Option<PathBuf>,
     },
 
     /// Create genesis configuration for a Linera deployment.
@@ -514,6 +839,14 @@ pub enum ClientCommand {
         #[arg(long, default_value = "0")]
         message_byte_price: Amount,
 
+        /// Set the fuel charged for each call from an application to the system API.
+        #[arg(long, default_value = "0")]
+        host_call_fuel: u64,
+
+        /// Set the maximum fuel used per block.
+        #[arg(long)]
+        maximum_fuel_per_block: Option<u64>,
+
         /// Set the maximum read data per block.
         #[arg(long)]
         maximum_bytes_read_per_block: Option<u64>,
@@ -522,6 +855,30 @@ pub enum ClientCommand {
         #[arg(long)]
         maximum_bytes_written_per_block: Option<u64>,
 
+        /// Set the maximum size of a published contract or service bytecode.
+        #[arg(long)]
+        maximum_bytecode_size: Option<u64>,
+
+        /// Set the maximum depth of nested calls between applications.
+        #[arg(long)]
+        maximum_call_depth: Option<u32>,
+
+        /// Set the maximum number of bytes each application can store on a chain.
+        #[arg(long)]
+        maximum_bytes_stored_per_application: Option<u64>,
+
+        /// Set the maximum number of operations in a block.
+        #[arg(long)]
+        maximum_operations_per_block: Option<u64>,
+
+        /// Set the maximum number of incoming messages in a block.
+        #[arg(long)]
+        maximum_incoming_messages_per_block: Option<u64>,
+
+        /// Set the maximum size of a block, in bytes.
+        #[arg(long)]
+        maximum_block_size: Option<u64>,
+
         /// Force this wallet to generate keys using a PRNG and a given seed. USE FOR
         /// TESTING ONLY.
         #[arg(long)]
@@ -530,9 +887,15 @@ pub enum ClientCommand {
         /// A unique name to identify this network.
         #[arg(long)]
         network_name: Option<String>,
+
+        /// A JSON file overriding some of the fees, limits and round timeouts of the genesis
+        /// configuration, e.g. to make a local test network faster.
+        #[arg(long)]
+        overrides: Option<PathBuf>,
     },
 
-    /// Watch the network for notifications.
+    /// Watch the network for notifications, and print the new blocks and incoming messages of
+    /// the chain.
     Watch {
         /// The chain ID to watch.
         chain_id: Option<ChainId>,
@@ -540,6 +903,10 @@ pub enum ClientCommand {
         /// Show all notifications from all validators.
         #[arg(long)]
         raw: bool,
+
+        /// Print the new blocks and incoming messages as JSON, one per line.
+        #[arg(long)]
+        json: bool,
     },
 
     /// Run a GraphQL service to explore and extend the chains of the wallet.
@@ -547,9 +914,25 @@ pub enum ClientCommand {
         #[command(flatten)]
         config: ChainListenerConfig,
 
+        #[command(flatten)]
+        mempool_config: MempoolConfig,
+
         /// The port on which to run the server
         #[arg(long = "port", default_value = "8080")]
         port: NonZeroU16,
+
+        /// Also serve a JSON-RPC interface for wallet frontends on a Unix socket at this path
+        #[arg(long = "socket")]
+        socket: Option<PathBuf>,
+
+        /// The token that clients of the socket must authenticate with. If none is given, a
+        /// random token is written to the file `<SOCKET>.token`
+        #[arg(
+            long = "socket-token",
+            env = "LINERA_SERVICE_SOCKET_TOKEN",
+            requires = "socket"
+        )]
+        socket_token: Option<String>,
     },
 
     /// Run a GraphQL service that exposes a faucet where users can claim tokens.
@@ -570,6 +953,75 @@ pub enum ClientCommand {
         /// no earlier than this.
         #[arg(long)]
         limit_rate_until: Option<DateTime<Utc>>,
+
+        /// The maximum number of chains that can be claimed with the same public key.
+        #[arg(long)]
+        max_claims_per_key: Option<u32>,
+
+        /// The maximum number of chains that can be claimed from the same IP address within
+        /// `--ip-window-secs`.
+        #[arg(long)]
+        max_claims_per_ip: Option<u32>,
+
+        /// The duration, in seconds, over which the claims from an IP address are counted.
+        #[arg(long, default_value = "3600")]
+        ip_window_secs: u64,
+
+        /// The HTTP header containing the client's IP address, e.g. `X-Forwarded-For` if the
+        /// faucet runs behind a reverse proxy.
+        #[arg(long)]
+        ip_header: Option<String>,
+
+        /// The number of trusted reverse proxies in front of the faucet that append to
+        /// `--ip-header`. The client's address is this many entries from the right.
+        #[arg(long, default_value = "1")]
+        trusted_proxies: usize,
+
+        /// The URL of a service verifying captcha tokens. If set, every claim must include a
+        /// token, which is sent to this URL in a JSON `POST` request.
+        #[arg(long)]
+        captcha_url: Option<String>,
+
+        /// The secret used to sign invitations. If set, every chain must be claimed with an
+        /// invitation created by `linera faucet-invitation`, which can only be used once.
+        #[arg(
+            long,
+            env = "LINERA_FAUCET_INVITATION_SECRET",
+            requires = "used_invitations_path"
+        )]
+        invitation_secret: Option<String>,
+
+        /// The file where the used invitations are recorded, so that they can't be used again
+        /// after the faucet restarts.
+        #[arg(long)]
+        used_invitations_path: Option<PathBuf>,
+    },
+
+    /// Create invitations to claim a chain from a faucet that requires them, and print them.
+    FaucetInvitation {
+        /// The unique IDs of the invitations, e.g. the names of the invited users.
+        #[arg(required = true)]
+        ids: Vec<String>,
+
+        /// The secret used by the faucet to verify invitations.
+        #[arg(long, env = "LINERA_FAUCET_INVITATION_SECRET")]
+        invitation_secret: String,
+    },
+
+    /// Run a web page showing the block heights, latencies and error rates of the validators
+    /// of the current committee.
+    Dashboard {
+        /// The chains whose block heights are shown. If omitted, the default chain of the
+        /// wallet is used.
+        chains: Vec<ChainId>,
+
+        /// The port on which to run the server
+        #[arg(long = "port", default_value = "8082")]
+        port: NonZeroU16,
+
+        /// The interval between two rounds of queries to the validators, in seconds.
+        #[arg(long, default_value = "5")]
+        interval_secs: u64,
     },
 
     /// Publish bytecode.
@@ -585,6 +1037,17 @@ pub enum ClientCommand {
         publisher: Option<ChainId>,
     },
 
+    /// Publish a blob of data, e.g. the media of an NFT, that applications can read, and
+    /// print its ID.
+    PublishBlob {
+        /// Path to the file with the content of the blob.
+        blob_path: PathBuf,
+
+        /// An optional chain ID to publish the blob. The default chain of the wallet is used
+        /// otherwise.
+        publisher: Option<ChainId>,
+    },
+
     /// Create an application.
     CreateApplication {
         /// The bytecode ID of the application to create.
@@ -663,6 +1126,42 @@ pub enum ClientCommand {
         requester_chain_id: Option<ChainId>,
     },
 
+    /// Upgrade an application to a published bytecode, from the chain that created it. The
+    /// chains running the application migrate its state the next time they execute it, unless
+    /// they pinned an earlier version.
+    UpgradeApplication {
+        /// The ID of the application to upgrade.
+        application_id: UserApplicationId,
+
+        /// The bytecode ID of the new version of the application.
+        bytecode_id: BytecodeId,
+
+        /// The migration argument as a JSON string.
+        #[arg(long)]
+        json_argument: Option<String>,
+
+        /// Path to a JSON file containing the migration argument.
+        #[arg(long)]
+        json_argument_path: Option<PathBuf>,
+    },
+
+    /// Pin the version of an application that a chain runs, so that its state is not migrated
+    /// to the later upgrades of the application.
+    PinApplicationVersion {
+        /// The ID of the application.
+        application_id: UserApplicationId,
+
+        /// The version to pin. If not specified, the chain follows the upgrades of the
+        /// application again.
+        #[arg(long)]
+        version: Option<u32>,
+
+        /// The chain that runs the application. The default chain of the wallet is used
+        /// otherwise.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
+
     /// Create an unassigned key-pair.
     Keygen,
 
@@ -687,10 +1186,34 @@ pub enum ClientCommand {
         chain_id: Option<ChainId>,
     },
 
+    /// Show the size and the resources of the next block of a chain, and whether it fits the
+    /// limits of the current committee, by executing it locally without proposing it.
+    ///
+    /// The block is the pending block, if any, or a block with the pending incoming messages.
+    PreviewBlock {
+        /// The chain to propose the block to. If not specified, the wallet's default chain is
+        /// used.
+        chain_id: Option<ChainId>,
+
+        /// A JSON file with a list of operations to add to a block with the pending incoming
+        /// messages, instead of previewing the pending block.
+        #[arg(long)]
+        operations: Option<PathBuf>,
+    },
+
     /// Show the contents of the wallet.
     #[command(subcommand)]
     Wallet(WalletCommand),
 
+    /// Manage the profiles of the client, i.e. the wallets and storages of different
+    /// networks.
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+
+    /// Show how the validators answered the requests of the client.
+    #[command(subcommand)]
+    Validators(ValidatorsCommand),
+
     /// Manage Linera projects.
     #[command(subcommand)]
     Project(ProjectCommand),
@@ -736,6 +1259,11 @@ pub enum NetCommand {
         #[arg(long, default_value = "table_default")]
         table_name: String,
 
+        /// A JSON file overriding some of the fees, limits and round timeouts of the genesis
+        /// configuration, e.g. `configuration/local/dev_overrides.json` for faster iteration.
+        #[arg(long)]
+        overrides: Option<PathBuf>,
+
         /// Start the local network on a local Kubernetes deployment.
         #[cfg(feature = "kubernetes")]
         #[arg(long)]
@@ -752,6 +1280,55 @@ pub enum NetCommand {
     /// Print a bash helper script to make `linera net up` easier to use. The script is
     /// meant to be installed in `~/.bash_profile` or sourced when needed.
     Helper,
+
+    /// Generate the configuration of a test network and a Docker Compose file to run it,
+    /// with ScyllaDB storage and a faucet.
+    GenerateCompose {
+        /// The directory where the configuration files and `docker-compose.yml` are written.
+        #[arg(long)]
+        output_dir: PathBuf,
+
+        /// The number of initial "root" chains created in the genesis config on top of
+        /// the default "admin" chain. All initial chains belong to the faucet's wallet.
+        #[arg(long, default_value = "10")]
+        other_initial_chains: u32,
+
+        /// The initial amount of native tokens credited in the initial "root" chains,
+        /// including the default "admin" chain.
+        #[arg(long, default_value = "1000000")]
+        initial_amount: u128,
+
+        /// The number of validators in the test network. Default is 1.
+        #[arg(long, default_value = "1")]
+        validators: usize,
+
+        /// The number of shards per validator in the test network. Default is 1.
+        #[arg(long, default_value = "1")]
+        shards: usize,
+
+        /// The number of tokens the faucet sends to each new chain.
+        #[arg(long, default_value = "1")]
+        faucet_amount: Amount,
+
+        /// The host name under which the validators are reachable by clients. By default,
+        /// the name of each proxy's service in the Docker Compose network is used.
+        #[arg(long)]
+        public_host: Option<String>,
+
+        /// The name of the Docker image running the network.
+        #[arg(long, default_value = "linera")]
+        image: String,
+
+        /// The root of a Linera repository. If set, the image is built from its
+        /// `docker/Dockerfile` when the network starts.
+        #[arg(long)]
+        build_context: Option<PathBuf>,
+
+        /// Force the configuration to generate keys using a PRNG and a given seed. USE FOR
+        /// TESTING ONLY.
+        #[arg(long)]
+        testing_prng_seed: Option<u64>,
+    },
 }
 
 #[derive(Clone, clap::Subcommand)]
@@ -777,6 +1354,10 @@ pub enum WalletCommand {
         #[arg(long)]
         with_new_chain: bool,
 
+        /// An invitation to present to the faucet, if it requires one.
+        #[arg(long, requires = "with_new_chain")]
+        faucet_invitation: Option<String>,
+
         /// Other chains to follow.
         #[arg(long, num_args(0..))]
         with_other_chains: Vec<ChainId>,
@@ -792,6 +1373,67 @@ pub enum WalletCommand {
 
     /// Forgets the specified chain, including the associated key pair.
     ForgetChain { chain_id: ChainId },
+
+    /// Follows the specified chain without owning it.
+    FollowChain { chain_id: ChainId },
+
+    /// Exports a chain of the wallet to a file, with its latest known certificates, to import
+    /// it into another wallet.
+    ExportChain {
+        /// The chain to export.
+        chain_id: ChainId,
+
+        /// The file to write.
+        path: PathBuf,
+
+        /// Leave out the key of the chain, e.g. to share a multi-owner chain with its other
+        /// owners.
+        #[arg(long)]
+        without_key: bool,
+
+        /// The number of latest certificates of the chain to include.
+        #[arg(long, default_value = "1")]
+        certificates: usize,
+    },
+
+    /// Imports a chain exported from another wallet. If the chain comes without a key, a key
+    /// created with `linera keygen` for one of its owners is used, if any.
+    ImportChain {
+        /// The file written by `linera wallet export-chain`.
+        path: PathBuf,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+pub enum ProfileCommand {
+    /// List the profiles, marking the current one.
+    List,
+
+    /// Make a profile the default one. Its wallet must already exist.
+    Switch {
+        /// The name of the profile.
+        #[arg(value_parser = parse_profile_name)]
+        name: String,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+pub enum ValidatorsCommand {
+    /// Show the average latency and the number of successful and failed requests of each
+    /// validator, as recorded in the wallet over the previous runs.
+    Status,
+}
+
+/// Checks that a profile name can be used as a directory name.
+fn parse_profile_name(name: &str) -> Result<String, Error> {
+    anyhow::ensure!(
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        "Invalid profile name {name:?}: only letters, digits, '-' and '_' are allowed"
+    );
+    Ok(name.to_string())
 }
 
 #[derive(Clone, clap::Parser)]
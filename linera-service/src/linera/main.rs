@@ -2,7 +2,13 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::HashMap, env, path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    env,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, bail, ensure, Context};
 use async_trait::async_trait;
@@ -12,14 +18,14 @@ use client_options::ClientOptions;
 use colored::Colorize;
 use futures::{lock::Mutex, StreamExt};
 use linera_base::{
-    crypto::{CryptoHash, CryptoRng, PublicKey},
-    data_types::{ApplicationPermissions, Timestamp},
+    crypto::{CryptoRng, PublicKey},
+    data_types::{Amount, ApplicationPermissions, TimeDelta, Timestamp},
     identifiers::{ChainDescription, ChainId, MessageId, Owner},
     ownership::ChainOwnership,
 };
-use linera_chain::data_types::{CertificateValue, ExecutedBlock};
+use linera_chain::data_types::{CertificateValue, EquivocationEvidence, ExecutedBlock};
 use linera_core::{
-    client::ChainClientError,
+    client::{ChainClientError, MessageDecision},
     data_types::{ChainInfoQuery, ClientOutcome},
     local_node::LocalNodeClient,
     node::LocalValidatorNodeProvider,
@@ -28,27 +34,33 @@ use linera_core::{
 };
 use linera_execution::{
     committee::{Committee, ValidatorName, ValidatorState},
-    system::{SystemChannel, UserData},
-    Message, ResourceControlPolicy, SystemMessage,
+    system::{CommitteeChange, Recipient, SystemChannel, UserData},
+    Message, ResourceControlPolicy, SystemMessage, WithWasmDefault,
 };
 use linera_service::{
+    chain_archive::ArchiveReader,
     chain_listener::ClientContext as _,
+    chain_watch::{BlockSummary, ChainEvent},
     cli_wrappers,
-    config::{CommitteeConfig, Export, GenesisConfig, Import},
-    faucet::FaucetService,
+    config::{CommitteeConfig, Export, GenesisConfig, GenesisOverrides, Import},
+    dashboard::DashboardService,
+    faucet::{self, ClaimLimits, FaucetService},
     node_service::NodeService,
     project::{self, Project},
-    storage::Runnable,
-    wallet::UserChain,
+    replay::{replay_archive, ReplayReport},
+    storage::{run_with_storage, Runnable, StorageConfig, StorageConfigNamespace},
+    transfer_batch::{self, TransferStatus},
+    wallet::{ChainBundle, UserChain},
 };
 use linera_storage::Storage;
-use linera_views::views::ViewError;
+use linera_views::{common::CommonStoreConfig, views::ViewError};
 use rand::Rng as _;
 use serde_json::Value;
 use tracing::{debug, info, warn};
 
 mod client_context;
 mod client_options;
+mod net_compose;
 #[cfg(any(feature = "kubernetes", feature = "rocksdb"))]
 mod net_up_utils;
 
@@ -57,11 +69,14 @@ use {
     linera_chain::data_types::HashedCertificateValue,
     linera_core::data_types::ChainInfoResponse,
     linera_rpc::{HandleCertificateRequest, RpcMessage},
+    linera_service::benchmark_report::{BenchmarkReport, ShardReport, Workload},
     std::collections::HashSet,
     tracing::error,
 };
 
-use crate::client_options::{ClientCommand, NetCommand, ProjectCommand, WalletCommand};
+use crate::client_options::{
+    ClientCommand, NetCommand, ProfileCommand, ProjectCommand, ValidatorsCommand, WalletCommand,
+};
 
 #[cfg(feature = "benchmark")]
 fn deserialize_response(response: RpcMessage) -> Option<ChainInfoResponse> {
@@ -80,6 +95,34 @@ fn deserialize_response(response: RpcMessage) -> Option<ChainInfoResponse> {
 
 struct Job(ClientContext, ClientCommand);
 
+/// Replays exported chains, in order.
+struct ReplayJob(Vec<PathBuf>);
+
+#[async_trait]
+impl Runnable for ReplayJob {
+    type Output = Vec<ReplayReport>;
+
+    async fn run<S>(self, storage: S) -> anyhow::Result<Vec<ReplayReport>>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+        ViewError: From<S::ContextError>,
+    {
+        let mut reports = Vec::new();
+        for path in self.0 {
+            let file = std::io::BufReader::new(fs_err::File::open(&path)?);
+            let archive = ArchiveReader::new(file)
+                .with_context(|| format!("Failed to read archive {}", path.display()))?;
+            info!(
+                "Replaying chain {} from {}",
+                archive.chain_id(),
+                path.display()
+            );
+            reports.push(replay_archive(&storage, archive).await?);
+        }
+        Ok(reports)
+    }
+}
+
 fn read_json(string: Option<String>, path: Option<PathBuf>) -> anyhow::Result<Vec<u8>> {
     let value = match (string, path) {
         (Some(_), Some(_)) => bail!("cannot have both a json string and file"),
@@ -93,6 +136,32 @@ fn read_json(string: Option<String>, path: Option<PathBuf>) -> anyhow::Result<Ve
     Ok(serde_json::to_vec(&value)?)
 }
 
+/// Spawns a task serving the JSON-RPC interface of the node service on a Unix socket.
+#[cfg(unix)]
+fn serve_socket_api<E: async_graphql::Executor>(
+    socket: PathBuf,
+    token: Option<String>,
+    executor: E,
+) -> anyhow::Result<()> {
+    use linera_service::socket_api::{self, SocketApi};
+
+    let token = match token {
+        Some(token) => token,
+        None => socket_api::create_token_file(&socket)?,
+    };
+    tokio::spawn(async move {
+        if let Err(error) = SocketApi::new(socket, token, executor).run().await {
+            warn!("JSON-RPC socket failed: {error}");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_socket_api<E>(_: PathBuf, _: Option<String>, _: E) -> anyhow::Result<()> {
+    bail!("the JSON-RPC socket is only supported on Unix platforms")
+}
+
 #[async_trait]
 impl Runnable for Job {
     type Output = ();
@@ -132,6 +201,165 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
+            TransferMany {
+                sender,
+                recipients,
+                amounts,
+            } => {
+                ensure!(
+                    recipients.len() == amounts.len(),
+                    "There must be exactly one amount for each recipient"
+                );
+                let chain_client = context
+                    .make_chain_client(storage, sender.chain_id)
+                    .into_arc();
+                info!(
+                    "Starting transfer of native tokens from {} to {} recipients",
+                    sender,
+                    recipients.len()
+                );
+                let transfers = recipients
+                    .into_iter()
+                    .map(Recipient::Account)
+                    .zip(amounts)
+                    .collect::<Vec<_>>();
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| {
+                        let transfers = transfers.clone();
+                        async move {
+                            let data = UserData::default();
+                            chain_client
+                                .transfer_many(sender.owner, transfers, data)
+                                .await
+                        }
+                    })
+                    .await
+                    .context("Failed to make transfers")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            TransferBatch {
+                sender,
+                file,
+                report,
+            } => {
+                let transfers = transfer_batch::parse_transfers(&fs_err::read_to_string(file)?)?;
+                let operations = transfer_batch::transfer_operations(sender.owner, &transfers);
+                let chain_client = context
+                    .make_chain_client(storage, sender.chain_id)
+                    .into_arc();
+                let block_limits = chain_client.lock().await.chain_info().await?.block_limits;
+                let ranges = transfer_batch::block_ranges(&operations, &block_limits)?;
+                info!(
+                    "Starting {} transfers of native tokens from {} in {} blocks",
+                    transfers.len(),
+                    sender,
+                    ranges.len()
+                );
+                let time_start = Instant::now();
+                let mut statuses = vec![TransferStatus::NotSubmitted; transfers.len()];
+                for range in ranges {
+                    let block_operations = operations[range.clone()].to_vec();
+                    let result = context
+                        .apply_client_command(&chain_client, |mut chain_client| {
+                            let operations = block_operations.clone();
+                            async move { chain_client.execute_operations(operations).await }
+                        })
+                        .await;
+                    match result {
+                        Ok(certificate) => {
+                            info!("Confirmed {} transfers", range.len());
+                            statuses[range].fill(TransferStatus::Confirmed(certificate.hash()));
+                        }
+                        Err(error) => {
+                            warn!("Failed to make {} transfers: {}", range.len(), error);
+                            statuses[range].fill(TransferStatus::Failed(error.to_string()));
+                            break;
+                        }
+                    }
+                }
+                let time_total = time_start.elapsed();
+                info!("Transfers processed after {} ms", time_total.as_millis());
+                match report {
+                    Some(path) => transfer_batch::write_report(
+                        std::io::BufWriter::new(fs_err::File::create(path)?),
+                        &transfers,
+                        &statuses,
+                    )?,
+                    None => transfer_batch::write_report(std::io::stdout(), &transfers, &statuses)?,
+                }
+                let confirmed = statuses
+                    .iter()
+                    .filter(|status| matches!(status, TransferStatus::Confirmed(_)))
+                    .count();
+                ensure!(
+                    confirmed == transfers.len(),
+                    "Only {} of {} transfers were confirmed",
+                    confirmed,
+                    transfers.len()
+                );
+            }
+
+            Approve {
+                owner,
+                spender,
+                amount,
+            } => {
+                let owner_id = owner
+                    .owner
+                    .context("The account to approve transfers from must have an owner")?;
+                let chain_client = context
+                    .make_chain_client(storage, owner.chain_id)
+                    .into_arc();
+                info!(
+                    "Allowing {} to transfer up to {} native tokens from {}",
+                    spender, amount, owner
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| async move {
+                        chain_client.approve(owner_id, spender, amount).await
+                    })
+                    .await
+                    .context("Failed to approve transfers")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            TransferFrom {
+                owner,
+                recipient,
+                amount,
+            } => {
+                let owner_id = owner
+                    .owner
+                    .context("The account to transfer from must have an owner")?;
+                let chain_client = context
+                    .make_chain_client(storage, owner.chain_id)
+                    .into_arc();
+                info!(
+                    "Starting transfer of {} native tokens from {} to {}",
+                    amount, owner, recipient
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| async move {
+                        let data = UserData::default();
+                        chain_client
+                            .transfer_from(owner_id, amount, Recipient::Account(recipient), data)
+                            .await
+                    })
+                    .await
+                    .context("Failed to make transfer")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
             OpenChain {
                 chain_id,
                 public_key,
@@ -222,10 +450,59 @@ impl Runnable for Job {
                     .await?
             }
 
+            TransferOwnership {
+                chain_id,
+                new_public_key,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(storage, chain_id).into_arc();
+                info!(
+                    "Transferring chain {} to {}",
+                    chain_id,
+                    Owner::from(new_public_key)
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| async move {
+                        chain_client.start_ownership_transfer(new_public_key).await
+                    })
+                    .await
+                    .context("Failed to transfer the chain")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            AcceptOwnership { chain_id, key } => {
+                context
+                    .wallet_mut()
+                    .assign_existing_chain_to_key(key, chain_id)?;
+                let mut chain_client = context.make_chain_client(storage, chain_id);
+                info!(
+                    "Accepting the transfer of chain {} to {}",
+                    chain_id,
+                    Owner::from(key)
+                );
+                let time_start = Instant::now();
+                chain_client.synchronize_from_validators().await?;
+                let chain_client = chain_client.into_arc();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| async move {
+                        chain_client.accept_ownership().await
+                    })
+                    .await
+                    .context("Failed to accept the transfer of the chain")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
             ChangeApplicationPermissions {
                 chain_id,
                 execute_operations,
+                mandatory_applications,
                 close_chain,
+                register_applications,
             } => {
                 let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
                 let chain_client = context.make_chain_client(storage, chain_id).into_arc();
@@ -235,7 +512,9 @@ impl Runnable for Job {
                     .apply_client_command(&chain_client, |mut chain_client| {
                         let permissions = ApplicationPermissions {
                             execute_operations: execute_operations.clone(),
+                            mandatory_applications: mandatory_applications.clone(),
                             close_chain: close_chain.clone(),
+                            register_applications: register_applications.clone(),
                         };
                         async move {
                             chain_client
@@ -250,13 +529,43 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
-            CloseChain { chain_id } => {
+            ChangeFeeSource {
+                chain_id,
+                fee_source,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(storage, chain_id).into_arc();
+                info!(
+                    "Changing the fee source of chain {} to {:?}",
+                    chain_id, fee_source
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| async move {
+                        chain_client.change_fee_source(fee_source).await
+                    })
+                    .await
+                    .context("Failed to change the fee source")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            CloseChain {
+                chain_id,
+                reclaim_to,
+            } => {
                 let chain_client = context.make_chain_client(storage, chain_id).into_arc();
                 info!("Closing chain {}", chain_id);
                 let time_start = Instant::now();
                 let certificate = context
                     .apply_client_command(&chain_client, |mut chain_client| async move {
-                        chain_client.close_chain().await
+                        match reclaim_to {
+                            Some(recipient) => {
+                                chain_client.reclaim_and_close_chain(recipient).await
+                            }
+                            None => chain_client.close_chain().await,
+                        }
                     })
                     .await
                     .context("Failed to close chain")?;
@@ -265,6 +574,25 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
+            ReportEquivocation { chain_id, evidence } => {
+                let bytes = fs_err::read(evidence)?;
+                let evidence: Vec<EquivocationEvidence> = serde_json::from_slice(&bytes)?;
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(storage, chain_id).into_arc();
+                info!("Reporting {} equivocations", evidence.len());
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| {
+                        let evidence = evidence.clone();
+                        async move { chain_client.report_equivocations(evidence).await }
+                    })
+                    .await
+                    .context("Failed to report the equivocations")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
             Subscribe {
                 subscriber,
                 publisher,
@@ -392,9 +720,20 @@ impl Runnable for Job {
                 );
             }
 
-            ProcessInbox { chain_id } => {
+            ProcessInbox {
+                chain_id,
+                reject,
+                skip,
+            } => {
                 let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
-                let chain_client = context.make_chain_client(storage, chain_id).into_arc();
+                let mut chain_client = context.make_chain_client(storage, chain_id);
+                for message_id in reject {
+                    chain_client.set_message_decision(message_id, MessageDecision::Reject);
+                }
+                for message_id in skip {
+                    chain_client.set_message_decision(message_id, MessageDecision::Skip);
+                }
+                let chain_client = chain_client.into_arc();
                 info!("Processing the inbox of chain {}", chain_id);
                 let time_start = Instant::now();
                 let certificates = context.process_inbox(&chain_client).await?;
@@ -423,12 +762,10 @@ impl Runnable for Job {
                 info!("Validators obtained after {} ms", time_total.as_millis());
                 info!("{:?}", committee.validators());
                 let node_provider = context.make_node_provider();
+                let genesis_config_hash = context.wallet().genesis_config().hash();
                 for (name, state) in committee.validators() {
-                    match node_provider
-                        .make_node(&state.network_address)?
-                        .get_version_info()
-                        .await
-                    {
+                    let mut node = node_provider.make_node(&state.network_address)?;
+                    match node.get_version_info().await {
                         Ok(version_info) => {
                             info!(
                                 "Version information for validator {name:?}:{}",
@@ -439,15 +776,73 @@ impl Runnable for Job {
                             warn!("Failed to get version information for validator {name:?}:\n{e}")
                         }
                     }
+                    match node.get_genesis_config_hash().await {
+                        Ok(hash) if hash == genesis_config_hash => {}
+                        Ok(hash) => warn!(
+                            "Validator {name:?} belongs to a different network: \
+                            its genesis config hash is {hash}, expected {genesis_config_hash}"
+                        ),
+                        Err(e) => {
+                            warn!(
+                                "Failed to get the genesis config hash of validator {name:?}:\n{e}"
+                            )
+                        }
+                    }
+                }
+                let outdated = chain_client
+                    .outdated_validators()
+                    .await
+                    .context("Failed to check the versions of the validators")?;
+                for (name, version) in outdated {
+                    warn!(
+                        "Validator {name:?} runs version {version} of the software, older than \
+                        the minimum version required by the admin chain"
+                    );
                 }
             }
 
+            SyncValidator { name, chain_id } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let mut chain_client = context.make_chain_client(storage, chain_id);
+                info!("Uploading the missing certificates of chain {chain_id} to validator {name}");
+                let time_start = Instant::now();
+                let result = chain_client.sync_validator(name).await;
+                context.update_and_save_wallet(&mut chain_client).await;
+                let (initial_height, final_height) = result?;
+                let time_total = time_start.elapsed();
+                info!("Validator updated after {} ms", time_total.as_millis());
+                println!(
+                    "Validator {name} was at height {initial_height} of chain {chain_id}, \
+                     and is now at height {final_height}"
+                );
+            }
+
             command @ (SetValidator { .. }
             | RemoveValidator { .. }
+            | RotateValidatorKey { .. }
             | ResourceControlPolicy { .. }) => {
                 info!("Starting operations to change validator set");
                 let time_start = Instant::now();
 
+                if let SetValidator { name, address, .. } = &command {
+                    use linera_core::node::ValidatorNode as _;
+
+                    // Refuse to add a validator of another network to the committee.
+                    let genesis_config_hash = context.wallet().genesis_config().hash();
+                    let mut node = context.make_node_provider().make_node(address)?;
+                    match node.get_genesis_config_hash().await {
+                        Ok(hash) if hash == genesis_config_hash => {}
+                        Ok(hash) => bail!(
+                            "Validator {name:?} at {address} belongs to a different network: \
+                            its genesis config hash is {hash}, expected {genesis_config_hash}"
+                        ),
+                        Err(e) => warn!(
+                            "Failed to check the genesis config of validator {name:?} \
+                            at {address}:\n{e}"
+                        ),
+                    }
+                }
+
                 // Make sure genesis chains are subscribed to the admin chain.
                 let context = Arc::new(Mutex::new(context));
                 let mut context = context.lock().await;
@@ -490,6 +885,13 @@ impl Runnable for Job {
                                         return Ok(ClientOutcome::Committed(None));
                                     }
                                 }
+                                RotateValidatorKey { name, new_name } => {
+                                    let Some(state) = validators.remove(&name) else {
+                                        warn!("Skipping key rotation of nonexistent validator");
+                                        return Ok(ClientOutcome::Committed(None));
+                                    };
+                                    validators.insert(new_name, state);
+                                }
                                 ResourceControlPolicy {
                                     block,
                                     fuel_unit,
@@ -502,8 +904,16 @@ impl Runnable for Job {
                                     operation_byte,
                                     message,
                                     message_byte,
+                                    host_call_fuel,
+                                    maximum_fuel_per_block,
                                     maximum_bytes_read_per_block,
                                     maximum_bytes_written_per_block,
+                                    maximum_bytecode_size,
+                                    maximum_call_depth,
+                                    maximum_bytes_stored_per_application,
+                                    maximum_operations_per_block,
+                                    maximum_incoming_messages_per_block,
+                                    maximum_block_size,
                                 } => {
                                     if let Some(block) = block {
                                         policy.block = block;
@@ -538,6 +948,12 @@ impl Runnable for Job {
                                     if let Some(message_byte) = message_byte {
                                         policy.message_byte = message_byte;
                                     }
+                                    if let Some(host_call_fuel) = host_call_fuel {
+                                        policy.host_call_fuel = host_call_fuel;
+                                    }
+                                    if let Some(maximum_fuel_per_block) = maximum_fuel_per_block {
+                                        policy.maximum_fuel_per_block = maximum_fuel_per_block;
+                                    }
                                     if let Some(maximum_bytes_read_per_block) =
                                         maximum_bytes_read_per_block
                                     {
@@ -550,6 +966,33 @@ impl Runnable for Job {
                                         policy.maximum_bytes_written_per_block =
                                             maximum_bytes_written_per_block;
                                     }
+                                    if let Some(maximum_bytecode_size) = maximum_bytecode_size {
+                                        policy.maximum_bytecode_size = maximum_bytecode_size;
+                                    }
+                                    if let Some(maximum_call_depth) = maximum_call_depth {
+                                        policy.maximum_call_depth = maximum_call_depth;
+                                    }
+                                    if let Some(maximum_bytes_stored_per_application) =
+                                        maximum_bytes_stored_per_application
+                                    {
+                                        policy.maximum_bytes_stored_per_application =
+                                            maximum_bytes_stored_per_application;
+                                    }
+                                    if let Some(maximum_operations_per_block) =
+                                        maximum_operations_per_block
+                                    {
+                                        policy.maximum_operations_per_block =
+                                            maximum_operations_per_block;
+                                    }
+                                    if let Some(maximum_incoming_messages_per_block) =
+                                        maximum_incoming_messages_per_block
+                                    {
+                                        policy.maximum_incoming_messages_per_block =
+                                            maximum_incoming_messages_per_block;
+                                    }
+                                    if let Some(maximum_block_size) = maximum_block_size {
+                                        policy.maximum_block_size = maximum_block_size;
+                                    }
                                     info!(
                                         "ResourceControlPolicy:\n\
                             {:.2} base cost per block\n\
@@ -563,8 +1006,16 @@ impl Runnable for Job {
                             {:.2} per byte in the argument of an operation\n\
                             {:.2} per outgoing messages\n\
                             {:.2} per byte in the argument of an outgoing messages\n\
+                            {} fuel per call to the system API\n\
+                            {} maximum fuel per block\n\
                             {:.2} maximum number bytes read per block\n\
-                            {:.2} maximum number bytes written per block",
+                            {:.2} maximum number bytes written per block\n\
+                            {} maximum bytes per published bytecode\n\
+                            {} maximum depth of nested application calls\n\
+                            {} maximum bytes stored per application\n\
+                            {} maximum operations per block\n\
+                            {} maximum incoming messages per block\n\
+                            {} maximum bytes per block",
                                         policy.block,
                                         policy.fuel_unit,
                                         policy.read_operation,
@@ -576,8 +1027,16 @@ impl Runnable for Job {
                                         policy.operation_byte,
                                         policy.message,
                                         policy.message_byte,
+                                        policy.host_call_fuel,
+                                        policy.maximum_fuel_per_block,
                                         policy.maximum_bytes_read_per_block,
-                                        policy.maximum_bytes_written_per_block
+                                        policy.maximum_bytes_written_per_block,
+                                        policy.maximum_bytecode_size,
+                                        policy.maximum_call_depth,
+                                        policy.maximum_bytes_stored_per_application,
+                                        policy.maximum_operations_per_block,
+                                        policy.maximum_incoming_messages_per_block,
+                                        policy.maximum_block_size
                                     );
                                     if block.is_none()
                                         && fuel_unit.is_none()
@@ -590,8 +1049,16 @@ impl Runnable for Job {
                                         && operation_byte.is_none()
                                         && message.is_none()
                                         && message_byte.is_none()
+                                        && host_call_fuel.is_none()
+                                        && maximum_fuel_per_block.is_none()
                                         && maximum_bytes_read_per_block.is_none()
                                         && maximum_bytes_written_per_block.is_none()
+                                        && maximum_bytecode_size.is_none()
+                                        && maximum_call_depth.is_none()
+                                        && maximum_bytes_stored_per_application.is_none()
+                                        && maximum_operations_per_block.is_none()
+                                        && maximum_incoming_messages_per_block.is_none()
+                                        && maximum_block_size.is_none()
                                     {
                                         return Ok(ClientOutcome::Committed(None));
                                     }
@@ -625,6 +1092,99 @@ impl Runnable for Job {
                 info!("Operations confirmed after {} ms", time_total.as_millis());
             }
 
+            command @ (ProposeValidator { .. }
+            | ProposeValidatorRemoval { .. }
+            | ProposeValidatorKeyRotation { .. }
+            | VoteCommitteeChange { .. }
+            | FinalizeCommitteeChange { .. }) => {
+                info!("Starting operation to change validator set");
+                let time_start = Instant::now();
+                let admin_id = context.wallet().genesis_admin_chain();
+                let chain_client = context.make_chain_client(storage, admin_id).into_arc();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| {
+                        let command = command.clone();
+                        async move {
+                            match command {
+                                ProposeValidator {
+                                    name,
+                                    address,
+                                    votes,
+                                } => {
+                                    let state = ValidatorState {
+                                        network_address: address,
+                                        votes,
+                                    };
+                                    let change = CommitteeChange::SetValidator { name, state };
+                                    chain_client.propose_committee_change(change).await
+                                }
+                                ProposeValidatorRemoval { name } => {
+                                    let change = CommitteeChange::RemoveValidator { name };
+                                    chain_client.propose_committee_change(change).await
+                                }
+                                ProposeValidatorKeyRotation { name, new_name } => {
+                                    let change =
+                                        CommitteeChange::RotateValidatorKey { name, new_name };
+                                    chain_client.propose_committee_change(change).await
+                                }
+                                VoteCommitteeChange { proposal_id } => {
+                                    chain_client.vote_committee_change(proposal_id).await
+                                }
+                                FinalizeCommitteeChange { proposal_id } => {
+                                    chain_client.finalize_committee_change(proposal_id).await
+                                }
+                                _ => unreachable!(),
+                            }
+                        }
+                    })
+                    .await
+                    .context("Failed to change the validator set")?;
+                debug!("{:?}", certificate);
+                if let FinalizeCommitteeChange { .. } = command {
+                    // Remove the old committee.
+                    context
+                        .apply_client_command(&chain_client, |mut chain_client| async move {
+                            chain_client.finalize_committee().await
+                        })
+                        .await
+                        .context("Failed to finalize committee")?;
+                }
+                let proposals = chain_client.lock().await.committee_proposals().await?;
+                info!("Pending committee proposals: {:?}", proposals);
+                let time_total = time_start.elapsed();
+                info!("Operations confirmed after {} ms", time_total.as_millis());
+            }
+
+            CommitteeProposals => {
+                let admin_id = context.wallet().genesis_admin_chain();
+                let mut chain_client = context.make_chain_client(storage, admin_id);
+                chain_client.synchronize_from_validators().await?;
+                let proposals = chain_client.committee_proposals().await?;
+                context.update_and_save_wallet(&mut chain_client).await;
+                for (proposal_id, proposal) in proposals {
+                    println!("{proposal_id}: {:?}", proposal.change);
+                    for owner in proposal.votes {
+                        println!("  voted for by {owner}");
+                    }
+                }
+            }
+
+            SetMinimumVersion { version } => {
+                info!("Setting the minimum version of the validators to {version}");
+                let time_start = Instant::now();
+                let admin_id = context.wallet().genesis_admin_chain();
+                let chain_client = context.make_chain_client(storage, admin_id).into_arc();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| async move {
+                        chain_client.set_minimum_version(version).await
+                    })
+                    .await
+                    .context("Failed to set the minimum version")?;
+                debug!("{:?}", certificate);
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+            }
+
             #[cfg(feature = "benchmark")]
             Benchmark {
                 max_in_flight,
@@ -632,7 +1192,15 @@ impl Runnable for Job {
                 tokens_per_chain,
                 transactions_per_block,
                 fungible_application_id,
+                workload,
+                target_rate,
+                num_shards,
+                output,
             } => {
+                ensure!(
+                    fungible_application_id.is_some() || !workload.contains(&Workload::Fungible),
+                    "The fungible workload requires a fungible application ID"
+                );
                 // Below all block proposals are supposed to succeed without retries, we
                 // must make sure that all incoming payments have been accepted on-chain
                 // and that no validator is missing user certificates.
@@ -656,8 +1224,9 @@ impl Runnable for Job {
                 let proposals = context.make_benchmark_block_proposals(
                     &key_pairs,
                     transactions_per_block,
+                    &workload,
                     fungible_application_id,
-                );
+                )?;
                 let num_proposal = proposals.len();
                 let mut values = HashMap::new();
 
@@ -675,8 +1244,8 @@ impl Runnable for Job {
                     }
                 }
 
-                let responses = context
-                    .mass_broadcast("block proposals", max_in_flight, proposals)
+                let (responses, proposals_report) = context
+                    .mass_broadcast("block proposals", max_in_flight, target_rate, proposals)
                     .await;
                 let votes = responses
                     .into_iter()
@@ -707,8 +1276,9 @@ impl Runnable for Job {
                         .into()
                     })
                     .collect();
-                let responses = context
-                    .mass_broadcast("certificates", max_in_flight, messages)
+                let time_start = Instant::now();
+                let (responses, certificates_report) = context
+                    .mass_broadcast("certificates", max_in_flight, target_rate, messages)
                     .await;
                 let mut confirmed = HashSet::new();
                 let num_valid = responses.into_iter().fold(0, |acc, message| {
@@ -725,6 +1295,32 @@ impl Runnable for Job {
                     num_valid,
                     confirmed.len()
                 );
+                let shards = num_shards
+                    .map(|num_shards| {
+                        ShardReport::for_chains(
+                            confirmed.iter().copied(),
+                            num_shards,
+                            time_start.elapsed(),
+                        )
+                    })
+                    .unwrap_or_default();
+                for shard in &shards {
+                    info!(
+                        "Shard {}: {} certificates ({:.0} per sec)",
+                        shard.shard, shard.certificates, shard.throughput_per_sec
+                    );
+                }
+                if let Some(output) = output {
+                    let report = BenchmarkReport {
+                        num_chains,
+                        transactions_per_block,
+                        workloads: workload,
+                        target_rate,
+                        phases: vec![proposals_report, certificates_report],
+                        shards,
+                    };
+                    fs_err::write(output, serde_json::to_string_pretty(&report)?)?;
+                }
 
                 info!("Updating local state of user chains");
                 context
@@ -733,11 +1329,16 @@ impl Runnable for Job {
                 context.save_wallet();
             }
 
-            Watch { chain_id, raw } => {
+            Watch {
+                chain_id,
+                raw,
+                json,
+            } => {
                 let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
                 let chain_client = context.make_chain_client(storage, chain_id).into_arc();
                 info!("Watching for notifications for chain {:?}", chain_id);
                 let (_listen_handle, mut notifications) = chain_client.listen().await?;
+                let mut balance = chain_client.lock().await.chain_info().await?.chain_balance;
                 while let Some(notification) = notifications.next().await {
                     if let Reason::NewBlock { .. } = notification.reason {
                         let mut guard = chain_client.lock().await;
@@ -745,14 +1346,59 @@ impl Runnable for Job {
                     }
                     if raw {
                         println!("{}", serde_json::to_string(&notification)?);
+                        continue;
+                    }
+                    let event = match notification.reason {
+                        Reason::NewBlock { hash, .. } => {
+                            let mut guard = chain_client.lock().await;
+                            let certificate =
+                                guard.storage_client().await.read_certificate(hash).await?;
+                            let new_balance = guard.chain_info().await?.chain_balance;
+                            let Some(summary) =
+                                BlockSummary::new(&certificate, new_balance, Some(balance))
+                            else {
+                                continue;
+                            };
+                            balance = new_balance;
+                            ChainEvent::NewBlock(summary)
+                        }
+                        Reason::NewIncomingMessage { origin, height } => {
+                            ChainEvent::NewIncomingMessage {
+                                chain_id: notification.chain_id,
+                                origin,
+                                height,
+                            }
+                        }
+                        Reason::NewRound { .. } => continue,
+                    };
+                    if json {
+                        println!("{}", serde_json::to_string(&event)?);
+                    } else {
+                        println!("{}", event);
                     }
                 }
                 info!("Notification stream ended.");
             }
 
-            Service { config, port } => {
+            Service {
+                config,
+                mempool_config,
+                port,
+                socket,
+                socket_token,
+            } => {
                 let default_chain = context.wallet().default_chain();
-                let service = NodeService::new(config, port, default_chain, storage, context);
+                let service = NodeService::new(
+                    config,
+                    mempool_config,
+                    port,
+                    default_chain,
+                    storage,
+                    context,
+                );
+                if let Some(socket) = socket {
+                    serve_socket_api(socket, socket_token, service.schema())?;
+                }
                 service.run().await?;
             }
 
@@ -761,6 +1407,14 @@ impl Runnable for Job {
                 port,
                 amount,
                 limit_rate_until,
+                max_claims_per_key,
+                max_claims_per_ip,
+                ip_window_secs,
+                ip_header,
+                trusted_proxies,
+                captcha_url,
+                invitation_secret,
+                used_invitations_path,
             } => {
                 let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
                 info!("Starting faucet service using chain {}", chain_id);
@@ -773,6 +1427,16 @@ impl Runnable for Job {
                     })
                     .unwrap_or_else(Timestamp::now);
                 let genesis_config = Arc::new(context.wallet().genesis_config().clone());
+                let limits = ClaimLimits {
+                    max_claims_per_key,
+                    max_claims_per_ip,
+                    ip_window: TimeDelta::from_secs(ip_window_secs),
+                    ip_header,
+                    trusted_proxies,
+                    captcha_url,
+                    invitation_secret: invitation_secret.map(String::into_bytes),
+                    used_invitations_path,
+                };
                 let faucet = FaucetService::new(
                     port,
                     chain_client,
@@ -780,11 +1444,41 @@ impl Runnable for Job {
                     amount,
                     end_timestamp,
                     genesis_config,
+                    limits,
                 )
                 .await?;
                 faucet.run().await?;
             }
 
+            Dashboard {
+                chains,
+                port,
+                interval_secs,
+            } => {
+                let chain_id = context.default_chain();
+                let mut chain_client = context.make_chain_client(storage, chain_id);
+                let result = chain_client.local_committee().await;
+                context.update_and_save_wallet(&mut chain_client).await;
+                let committee = result.context("Failed to get local committee")?;
+                let chains = if chains.is_empty() {
+                    vec![chain_id]
+                } else {
+                    chains
+                };
+                info!(
+                    "Starting the dashboard of {} validators",
+                    committee.validators().len()
+                );
+                let dashboard = DashboardService::new(
+                    port,
+                    context.make_node_provider(),
+                    &committee,
+                    chains,
+                    Duration::from_secs(interval_secs),
+                );
+                dashboard.run().await?;
+            }
+
             PublishBytecode {
                 contract,
                 service,
@@ -802,6 +1496,27 @@ impl Runnable for Job {
                 info!("Time elapsed: {} ms", start_time.elapsed().as_millis());
             }
 
+            PublishBlob {
+                blob_path,
+                publisher,
+            } => {
+                let start_time = Instant::now();
+                let bytes = fs_err::read(blob_path)?;
+                let publisher = publisher.unwrap_or_else(|| context.default_chain());
+                info!("Publishing blob on chain {}", publisher);
+                let chain_client = context.make_chain_client(storage, publisher).into_arc();
+                let (blob_id, _) = context
+                    .apply_client_command(&chain_client, |mut chain_client| {
+                        let bytes = bytes.clone();
+                        async move { chain_client.publish_blob(bytes).await }
+                    })
+                    .await
+                    .context("Failed to publish the blob")?;
+                println!("{}", blob_id);
+                info!("{}", "Blob published successfully!".green().bold());
+                info!("Time elapsed: {} ms", start_time.elapsed().as_millis());
+            }
+
             CreateApplication {
                 bytecode_id,
                 creator,
@@ -912,6 +1627,64 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
+            UpgradeApplication {
+                application_id,
+                bytecode_id,
+                json_argument,
+                json_argument_path,
+            } => {
+                let start_time = Instant::now();
+                let chain_id = application_id.creation.chain_id;
+                info!(
+                    "Upgrading application {} on chain {}",
+                    application_id, chain_id
+                );
+                let chain_client = context.make_chain_client(storage, chain_id).into_arc();
+                let argument = read_json(json_argument, json_argument_path)?;
+                let certificate = context
+                    .apply_client_command(&chain_client, move |mut chain_client| {
+                        let argument = argument.clone();
+                        async move {
+                            chain_client
+                                .upgrade_application(application_id, bytecode_id, argument)
+                                .await
+                        }
+                    })
+                    .await
+                    .context("Failed to upgrade application")?;
+                info!("{}", "Application upgraded successfully!".green().bold());
+                info!("Time elapsed: {} ms", start_time.elapsed().as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            PinApplicationVersion {
+                application_id,
+                version,
+                chain_id,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                match version {
+                    Some(version) => info!(
+                        "Pinning application {} to version {} on chain {}",
+                        application_id, version, chain_id
+                    ),
+                    None => info!(
+                        "Unpinning the version of application {} on chain {}",
+                        application_id, chain_id
+                    ),
+                }
+                let chain_client = context.make_chain_client(storage, chain_id).into_arc();
+                let certificate = context
+                    .apply_client_command(&chain_client, |mut chain_client| async move {
+                        chain_client
+                            .pin_application_version(application_id, version)
+                            .await
+                    })
+                    .await
+                    .context("Failed to pin the application version")?;
+                debug!("{:?}", certificate);
+            }
+
             Assign { key, message_id } => {
                 let chain_id = ChainId::child(message_id);
                 info!(
@@ -1001,10 +1774,58 @@ impl Runnable for Job {
                 context.update_and_save_wallet(&mut chain_client).await;
             }
 
+            PreviewBlock {
+                chain_id,
+                operations,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let operations = match operations {
+                    Some(path) => serde_json::from_str(&fs_err::read_to_string(path)?)?,
+                    None => Vec::new(),
+                };
+                let mut chain_client = context.make_chain_client(storage, chain_id);
+                let preview = chain_client.preview_block(operations).await?;
+                let limits = &preview.limits;
+                println!(
+                    "Block at height {} of chain {}",
+                    preview.block.height, chain_id
+                );
+                println!(
+                    "Incoming messages: {} (maximum {})",
+                    preview.block.incoming_messages.len(),
+                    limits.maximum_incoming_messages
+                );
+                println!(
+                    "Operations: {} (maximum {})",
+                    preview.block.operations.len(),
+                    limits.maximum_operations
+                );
+                println!(
+                    "Size: {} bytes (maximum {})",
+                    preview.size, limits.maximum_size
+                );
+                match (preview.fuel_used, preview.fees) {
+                    (Some(fuel_used), Some(fees)) => {
+                        println!(
+                            "Fuel: {} (maximum {})",
+                            fuel_used, preview.maximum_fuel_per_block
+                        );
+                        println!("Fees: {}", fees);
+                    }
+                    _ => println!("Not executed, since its contents exceed the limits"),
+                }
+                if preview.fits_limits() {
+                    println!("The block fits the limits of the committee.");
+                } else {
+                    println!("The block exceeds the limits of the committee.");
+                }
+            }
+
             Wallet(WalletCommand::Init {
                 faucet: Some(faucet_url),
                 with_new_chain: true,
                 with_other_chains,
+                faucet_invitation,
                 ..
             }) => {
                 let key_pair = context.generate_key_pair();
@@ -1016,7 +1837,14 @@ impl Runnable for Job {
                 );
                 context.wallet_mut().add_unassigned_key_pair(key_pair);
                 let faucet = cli_wrappers::Faucet::new(faucet_url);
-                let outcome = faucet.claim(&public_key).await?;
+                let outcome = match faucet_invitation {
+                    Some(invitation) => {
+                        faucet
+                            .claim_with_invitation(&public_key, invitation)
+                            .await?
+                    }
+                    None => faucet.claim(&public_key).await?,
+                };
                 let validators = faucet.current_validators().await?;
                 println!("{}", outcome.chain_id);
                 println!("{}", outcome.message_id);
@@ -1039,7 +1867,78 @@ impl Runnable for Job {
                 context.save_wallet();
             }
 
-            CreateGenesisConfig { .. } | Keygen | Net(_) | Wallet(_) | HelpMarkdown => {
+            Wallet(WalletCommand::ExportChain {
+                chain_id,
+                path,
+                without_key,
+                certificates,
+            }) => {
+                let chain = context.wallet().export_chain(chain_id, !without_key)?;
+                let ownership = storage
+                    .load_chain(chain_id)
+                    .await?
+                    .execution_state
+                    .system
+                    .ownership
+                    .get()
+                    .clone();
+                let mut bundle_certificates = Vec::new();
+                let mut next_hash = chain.block_hash;
+                while let Some(hash) =
+                    next_hash.filter(|_| bundle_certificates.len() < certificates)
+                {
+                    let certificate = storage.read_certificate(hash).await.with_context(|| {
+                        format!("Missing certificate {hash} of chain {chain_id}")
+                    })?;
+                    next_hash = certificate
+                        .value()
+                        .executed_block()
+                        .and_then(|executed_block| executed_block.block.previous_block_hash);
+                    bundle_certificates.push(certificate);
+                }
+                bundle_certificates.reverse();
+                let bundle = ChainBundle {
+                    genesis_config_hash: context.wallet().genesis_config().hash(),
+                    chain,
+                    ownership,
+                    certificates: bundle_certificates,
+                };
+                bundle.write(&path)?;
+                info!("Chain {} exported to {}", chain_id, path.display());
+            }
+
+            Wallet(WalletCommand::ImportChain { path }) => {
+                let bundle = ChainBundle::read(&path)?;
+                let chain_id = bundle.chain.chain_id;
+                ensure!(
+                    bundle.genesis_config_hash == context.wallet().genesis_config().hash(),
+                    "Chain {chain_id} belongs to another network than this wallet"
+                );
+                for certificate in &bundle.certificates {
+                    ensure!(
+                        certificate.value().is_confirmed()
+                            && certificate.value().chain_id() == chain_id,
+                        "Certificate {} is not a confirmed block of chain {chain_id}",
+                        certificate.hash()
+                    );
+                }
+                storage.write_certificates(&bundle.certificates).await?;
+                context
+                    .wallet_mut()
+                    .import_chain(bundle.chain, &bundle.ownership)?;
+                context.save_wallet();
+                info!("Chain {} imported from {}", chain_id, path.display());
+            }
+
+            CreateGenesisConfig { .. }
+            | Keygen
+            | FaucetInvitation { .. }
+            | Net(_)
+            | Wallet(_)
+            | Profile(_)
+            | Validators(_)
+            | Replay { .. }
+            | HelpMarkdown => {
                 unreachable!()
             }
         }
@@ -1163,7 +2062,7 @@ impl Job {
         }
         // This proves that once we have verified that the peg chain's tip is a block in the real
         // network, we can be confident that all downloaded chains are.
-        let config_hash = CryptoHash::new(context.wallet().genesis_config());
+        let config_hash = context.wallet().genesis_config().hash();
         let maybe_epoch = peg_chain.execution_state.system.epoch.get();
         let epoch = maybe_epoch.context("missing epoch in peg chain")?.0;
         info!(
@@ -1234,13 +2133,23 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
             operation_byte_price,
             message_price,
             message_byte_price,
+            host_call_fuel,
+            maximum_fuel_per_block,
             maximum_bytes_read_per_block,
             maximum_bytes_written_per_block,
+            maximum_bytecode_size,
+            maximum_call_depth,
+            maximum_bytes_stored_per_application,
+            maximum_operations_per_block,
+            maximum_incoming_messages_per_block,
+            maximum_block_size,
             testing_prng_seed,
             network_name,
+            overrides,
         } => {
             let committee_config = CommitteeConfig::read(committee_config_path)
                 .expect("Unable to read committee config file");
+            let maximum_fuel_per_block = maximum_fuel_per_block.unwrap_or(u64::MAX);
             let maximum_bytes_read_per_block = match *maximum_bytes_read_per_block {
                 Some(value) => value,
                 None => u64::MAX,
@@ -1249,6 +2158,14 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
                 Some(value) => value,
                 None => u64::MAX,
             };
+            let maximum_bytecode_size = maximum_bytecode_size.unwrap_or(u64::MAX);
+            let maximum_call_depth = maximum_call_depth.unwrap_or(u32::MAX);
+            let maximum_bytes_stored_per_application =
+                maximum_bytes_stored_per_application.unwrap_or(u64::MAX);
+            let maximum_operations_per_block = maximum_operations_per_block.unwrap_or(u64::MAX);
+            let maximum_incoming_messages_per_block =
+                maximum_incoming_messages_per_block.unwrap_or(u64::MAX);
+            let maximum_block_size = maximum_block_size.unwrap_or(u64::MAX);
             let policy = ResourceControlPolicy {
                 block: *block_price,
                 fuel_unit: *fuel_unit_price,
@@ -1261,8 +2178,16 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
                 operation: *operation_price,
                 message_byte: *message_byte_price,
                 message: *message_price,
+                host_call_fuel: *host_call_fuel,
+                maximum_fuel_per_block,
                 maximum_bytes_read_per_block,
                 maximum_bytes_written_per_block,
+                maximum_bytecode_size,
+                maximum_call_depth,
+                maximum_bytes_stored_per_application,
+                maximum_operations_per_block,
+                maximum_incoming_messages_per_block,
+                maximum_block_size,
             };
             let timestamp = start_timestamp
                 .map(|st| {
@@ -1278,6 +2203,11 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
             });
             let mut genesis_config =
                 GenesisConfig::new(committee_config, admin_id, timestamp, policy, network_name);
+            if let Some(overrides) = overrides {
+                GenesisOverrides::read(overrides)
+                    .context("Unable to read genesis overrides file")?
+                    .apply(&mut genesis_config)?;
+            }
             let mut rng = Box::<dyn CryptoRng>::from(*testing_prng_seed);
             let mut chains = vec![];
             for i in 0..*num_other_initial_chains {
@@ -1326,6 +2256,19 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
             Ok(())
         }
 
+        ClientCommand::FaucetInvitation {
+            ids,
+            invitation_secret,
+        } => {
+            for id in ids {
+                println!(
+                    "{}",
+                    faucet::sign_invitation(invitation_secret.as_bytes(), id)
+                );
+            }
+            Ok(())
+        }
+
         ClientCommand::Net(net_command) => match net_command {
             #[cfg(feature = "kubernetes")]
             NetCommand::Up {
@@ -1336,6 +2279,7 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
                 shards,
                 testing_prng_seed,
                 table_name: _,
+                overrides,
                 kubernetes: true,
                 binaries,
             } => {
@@ -1347,6 +2291,7 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
                     *shards,
                     *testing_prng_seed,
                     binaries,
+                    overrides.clone(),
                 )
                 .await
             }
@@ -1360,6 +2305,7 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
                 shards,
                 testing_prng_seed,
                 table_name,
+                overrides,
                 ..
             } => {
                 net_up_utils::handle_net_up_rocks_db(
@@ -1370,6 +2316,7 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
                     *shards,
                     *testing_prng_seed,
                     table_name,
+                    overrides.clone(),
                 )
                 .await
             }
@@ -1388,6 +2335,33 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
                 println!("{}", include_str!("../../template/linera_net_helper.sh"));
                 Ok(())
             }
+
+            NetCommand::GenerateCompose {
+                output_dir,
+                other_initial_chains,
+                initial_amount,
+                validators,
+                shards,
+                faucet_amount,
+                public_host,
+                image,
+                build_context,
+                testing_prng_seed,
+            } => {
+                let options = net_compose::ComposeOptions {
+                    output_dir: output_dir.clone(),
+                    num_other_initial_chains: *other_initial_chains,
+                    initial_amount: Amount::from_tokens(*initial_amount),
+                    num_validators: *validators,
+                    num_shards: *shards,
+                    faucet_amount: *faucet_amount,
+                    public_host: public_host.clone(),
+                    image: image.clone(),
+                    build_context: build_context.clone(),
+                    testing_prng_seed: *testing_prng_seed,
+                };
+                options.generate().await
+            }
         },
 
         ClientCommand::Wallet(wallet_command) => match wallet_command {
@@ -1418,12 +2392,24 @@ async fn run(options: ClientOptions) -> anyhow::Result<()> {
                 Ok(())
             }
 
+            WalletCommand::FollowChain { chain_id } => {
+                let mut context = ClientContext::from_options(&options)?;
+                context.wallet_mut().follow_chain(*chain_id)?;
+                context.save_wallet();
+                Ok(())
+            }
+
+            WalletCommand::ExportChain { .. } | WalletCommand::ImportChain { .. } => {
+                options.run_command_with_storage().await
+            }
+
             WalletCommand::Init {
                 genesis_config_path,
                 faucet,
                 with_new_chain,
                 with_other_chains,
                 testing_prng_seed,
+                ..
             } => {
                 let genesis_config = match (genesis_config_path, faucet) {
                     (Some(genesis_config_path), None) => GenesisConfig::read(genesis_config_path)?,
@@ -1474,6 +2460,59 @@ Make sure to use a Linera client compatible with this network.
             }
         },
 
+        ClientCommand::Profile(profile_command) => match profile_command {
+            ProfileCommand::List => {
+                let current = ClientContext::current_profile(&options)?;
+                for name in ClientContext::profiles()? {
+                    let marker = if current.as_ref() == Some(&name) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!("{marker} {name}");
+                }
+                Ok(())
+            }
+
+            ProfileCommand::Switch { name } => {
+                ClientContext::switch_profile(name)?;
+                info!("Switched to profile {name}");
+                Ok(())
+            }
+        },
+
+        ClientCommand::Validators(validators_command) => match validators_command {
+            ValidatorsCommand::Status => {
+                let context = ClientContext::from_options(&options)?;
+                context.wallet().pretty_print_validators();
+                Ok(())
+            }
+        },
+
+        ClientCommand::Replay { archives } => {
+            let context = ClientContext::from_options(&options)?;
+            let genesis_config = context.wallet().genesis_config().clone();
+            let storage_config = StorageConfigNamespace {
+                storage_config: StorageConfig::Memory,
+                namespace: "replay".to_string(),
+            };
+            let common_config = CommonStoreConfig {
+                max_concurrent_queries: options.max_concurrent_queries,
+                max_stream_queries: options.max_stream_queries,
+                cache_size: options.cache_size,
+            };
+            let full_storage_config = storage_config.add_common_config(common_config).await?;
+            let wasm_runtime = options.wasm_runtime.with_wasm_default();
+            let job = ReplayJob(archives.clone());
+            let reports =
+                run_with_storage(full_storage_config, &genesis_config, wasm_runtime, job).await?;
+            println!("{}", serde_json::to_string_pretty(&reports)?);
+            if let Some(report) = reports.iter().find(|report| report.divergence.is_some()) {
+                bail!("The execution of chain {} diverges", report.chain_id);
+            }
+            Ok(())
+        }
+
         _ => options.run_command_with_storage().await,
     }
 }
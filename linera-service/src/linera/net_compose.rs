@@ -0,0 +1,304 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation of a test network's configuration, together with a Docker Compose file to run
+//! it: a ScyllaDB storage, the shards and proxy of each validator, and a faucet.
+
+use std::{fmt::Write as _, path::PathBuf};
+
+use anyhow::{anyhow, ensure, Result};
+use linera_base::{
+    command::{resolve_binary, CommandExt},
+    data_types::Amount,
+};
+use tokio::process::Command;
+use tracing::info;
+
+#[cfg(test)]
+#[path = "../unit_tests/net_compose.rs"]
+mod tests;
+
+/// The port of the ScyllaDB service.
+const SCYLLA_PORT: u16 = 9042;
+
+/// The port on which the proxy of the first validator accepts requests from clients.
+const PUBLIC_PORT: u16 = 19100;
+
+/// The port on which the proxies and the shards accept requests from within the validator.
+const INTERNAL_PORT: u16 = 20100;
+
+/// The port on which the proxies and the shards expose their metrics.
+const METRICS_PORT: u16 = 21100;
+
+/// The port on which the shards accept requests from their proxy.
+const SHARD_PORT: u16 = 19100;
+
+/// The port on which the faucet accepts requests.
+const FAUCET_PORT: u16 = 8080;
+
+/// The options of `linera net generate-compose`.
+pub struct ComposeOptions {
+    pub output_dir: PathBuf,
+    pub num_other_initial_chains: u32,
+    pub initial_amount: Amount,
+    pub num_validators: usize,
+    pub num_shards: usize,
+    pub faucet_amount: Amount,
+    pub public_host: Option<String>,
+    pub image: String,
+    pub build_context: Option<PathBuf>,
+    pub testing_prng_seed: Option<u64>,
+}
+
+impl ComposeOptions {
+    /// Writes the validator, committee and genesis configurations, the faucet's wallet and
+    /// `docker-compose.yml` to the output directory.
+    pub async fn generate(&self) -> Result<()> {
+        ensure!(
+            self.num_validators > 0,
+            "The test network must have at least one validator."
+        );
+        ensure!(
+            self.num_shards > 0,
+            "The test network must have at least one shard per validator."
+        );
+        fs_err::create_dir_all(self.output_dir.join("faucet"))?;
+        let mut testing_prng_seed = self.testing_prng_seed;
+
+        let mut command = self.command_for_binary("linera-server").await?;
+        command.arg("generate");
+        if let Some(seed) = testing_prng_seed {
+            command.arg("--testing-prng-seed").arg(seed.to_string());
+            testing_prng_seed = Some(seed + 1);
+        }
+        command.arg("--validators");
+        for n in 0..self.num_validators {
+            command.arg(self.write_validator_config(n)?);
+        }
+        let validator_names = command
+            .args(["--committee", "committee.json"])
+            .spawn_and_wait_for_stdout()
+            .await?;
+
+        // The storage is discarded: the faucet initializes its own in the network.
+        let mut command = self.command_for_binary("linera").await?;
+        command
+            .args(["--wallet", "faucet/wallet.json", "--storage", "memory"])
+            .args([
+                "create-genesis-config",
+                &self.num_other_initial_chains.to_string(),
+            ])
+            .args(["--initial-funding", &self.initial_amount.to_string()])
+            .args(["--committee", "committee.json"])
+            .args(["--genesis", "genesis.json"]);
+        if let Some(seed) = testing_prng_seed {
+            command.arg("--testing-prng-seed").arg(seed.to_string());
+        }
+        command.spawn_and_wait().await?;
+
+        let compose_file = self.compose_file()?;
+        fs_err::write(self.output_dir.join("docker-compose.yml"), compose_file)?;
+        for name in validator_names.split_whitespace() {
+            println!("{}", name);
+        }
+        info!(
+            "Run `docker compose up` in {} to start the network. The faucet listens on port {}.",
+            self.output_dir.display(),
+            FAUCET_PORT
+        );
+        Ok(())
+    }
+
+    async fn command_for_binary(&self, name: &'static str) -> Result<Command> {
+        let path = resolve_binary(name, env!("CARGO_PKG_NAME")).await?;
+        let mut command = Command::new(path);
+        command.current_dir(&self.output_dir);
+        Ok(command)
+    }
+
+    /// Writes the input of `linera-server generate` for validator `n`, and returns its file
+    /// name.
+    fn write_validator_config(&self, n: usize) -> Result<String> {
+        let file_name = format!("validator_{n}.toml");
+        let proxy = proxy_service(n);
+        let host = self.public_host.as_deref().unwrap_or(&proxy);
+        let port = public_port(n);
+        let mut content = format!(
+            r#"
+                server_config_path = "server_{n}.json"
+                host = "{host}"
+                port = {port}
+                internal_host = "{proxy}"
+                internal_port = {INTERNAL_PORT}
+                metrics_host = "{proxy}"
+                metrics_port = {METRICS_PORT}
+                [external_protocol]
+                Grpc = "ClearText"
+                [internal_protocol]
+                Grpc = "ClearText"
+            "#
+        );
+        for k in 0..self.num_shards {
+            let shard = shard_service(n, k);
+            content.push_str(&format!(
+                r#"
+
+                [[shards]]
+                host = "{shard}"
+                port = {SHARD_PORT}
+                metrics_host = "{shard}"
+                metrics_port = {METRICS_PORT}
+                "#
+            ));
+        }
+        fs_err::write(self.output_dir.join(&file_name), content)?;
+        Ok(file_name)
+    }
+
+    /// Returns the content of `docker-compose.yml`.
+    fn compose_file(&self) -> Result<String> {
+        let mut file = String::new();
+        writeln!(file, "# Generated by `linera net generate-compose`.")?;
+        writeln!(file)?;
+        writeln!(file, "x-linera: &linera")?;
+        writeln!(file, "  image: {}", self.image)?;
+        if let Some(build_context) = &self.build_context {
+            let build_context = fs_err::canonicalize(build_context)?;
+            let build_context = build_context
+                .to_str()
+                .ok_or_else(|| anyhow!("invalid build context {}", build_context.display()))?;
+            writeln!(file, "  build:")?;
+            writeln!(file, "    context: {build_context:?}")?;
+            writeln!(file, "    dockerfile: docker/Dockerfile")?;
+        }
+        writeln!(file)?;
+        writeln!(file, "services:")?;
+        file.push_str(
+            r#"  scylla:
+    image: scylladb/scylla:5.4
+    command: --smp 1 --memory 1G --overprovisioned 1 --developer-mode 1
+    volumes:
+      - scylla-data:/var/lib/scylla
+    healthcheck:
+      test: ["CMD-SHELL", "cqlsh -e 'describe keyspaces' > /dev/null"]
+      interval: 10s
+      timeout: 5s
+      retries: 30
+"#,
+        );
+        for n in 0..self.num_validators {
+            let storage = storage_config(&format!("table_validator_{n}"));
+            let server_config = format!("./server_{n}.json");
+            write_init_service(&mut file, &format!("init-{n}"), &storage)?;
+            for k in 0..self.num_shards {
+                let shard = shard_service(n, k);
+                write!(
+                    file,
+                    r#"  {shard}:
+    <<: *linera
+    hostname: {shard}
+    command: ["./server-entrypoint.sh"]
+    environment:
+      LINERA_STORAGE: {storage}
+      RUST_LOG: info
+    volumes:
+      - {server_config}:/config/server.json:ro
+      - ./genesis.json:/config/genesis.json:ro
+    depends_on:
+      init-{n}:
+        condition: service_completed_successfully
+"#
+                )?;
+            }
+            let port = public_port(n);
+            write!(
+                file,
+                r#"  {proxy}:
+    <<: *linera
+    command: ["./linera-proxy", "/config/server.json", "--genesis", "/config/genesis.json"]
+    environment:
+      RUST_LOG: info
+    ports:
+      - "{port}:{port}"
+    volumes:
+      - {server_config}:/config/server.json:ro
+      - ./genesis.json:/config/genesis.json:ro
+    depends_on:
+"#,
+                proxy = proxy_service(n),
+            )?;
+            for k in 0..self.num_shards {
+                writeln!(file, "      - {}", shard_service(n, k))?;
+            }
+        }
+        let storage = storage_config("table_faucet");
+        write_init_service(&mut file, "faucet-init", &storage)?;
+        write!(
+            file,
+            r#"  faucet:
+    <<: *linera
+    command: ["./linera", "--wallet", "/faucet/wallet.json", "--storage", "{storage}", "faucet", "--port", "{FAUCET_PORT}", "--amount", "{amount}"]
+    environment:
+      RUST_LOG: info
+    ports:
+      - "{FAUCET_PORT}:{FAUCET_PORT}"
+    volumes:
+      - ./faucet:/faucet
+    depends_on:
+      faucet-init:
+        condition: service_completed_successfully
+"#,
+            amount = self.faucet_amount,
+        )?;
+        for n in 0..self.num_validators {
+            writeln!(file, "      {}:", proxy_service(n))?;
+            writeln!(file, "        condition: service_started")?;
+        }
+        writeln!(file)?;
+        writeln!(file, "volumes:")?;
+        writeln!(file, "  scylla-data:")?;
+        Ok(file)
+    }
+}
+
+/// Writes a service initializing the given storage with the genesis configuration.
+fn write_init_service(file: &mut String, name: &str, storage: &str) -> Result<()> {
+    write!(
+        file,
+        r#"  {name}:
+    <<: *linera
+    command: ["./server-init.sh"]
+    environment:
+      LINERA_STORAGE: {storage}
+      RUST_LOG: info
+    volumes:
+      - ./genesis.json:/config/genesis.json:ro
+    depends_on:
+      scylla:
+        condition: service_healthy
+"#
+    )?;
+    Ok(())
+}
+
+/// The name of the service running the proxy of validator `n`.
+fn proxy_service(n: usize) -> String {
+    format!("proxy-{n}")
+}
+
+/// The name of the service running shard `k` of validator `n`. It must end with the shard
+/// number, which `server-entrypoint.sh` extracts from the host name.
+fn shard_service(n: usize, k: usize) -> String {
+    format!("shard-{n}-{k}")
+}
+
+/// The port on which the proxy of validator `n` accepts requests from clients. The ports are
+/// distinct so that all proxies can be published on the same host.
+fn public_port(n: usize) -> usize {
+    usize::from(PUBLIC_PORT) + n
+}
+
+/// The storage configuration of the given table in the ScyllaDB service.
+fn storage_config(table: &str) -> String {
+    format!("scylladb:tcp:scylla:{SCYLLA_PORT}:{table}")
+}
@@ -1,11 +1,13 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
 use colored::Colorize as _;
 use linera_base::data_types::Amount;
 use linera_execution::ResourceControlPolicy;
+#[cfg(feature = "kubernetes")]
+use linera_service::cli_wrappers::local_kubernetes_net::LocalKubernetesNetConfig;
 use linera_service::cli_wrappers::{
     ClientWrapper, FaucetOption, LineraNet, LineraNetConfig, Network,
 };
@@ -16,11 +18,6 @@ use linera_service::{
 };
 use tokio::{signal::unix, sync::mpsc};
 use tracing::info;
-#[cfg(feature = "kubernetes")]
-use {
-    linera_service::cli_wrappers::local_kubernetes_net::LocalKubernetesNetConfig,
-    std::path::PathBuf,
-};
 
 #[cfg(feature = "kubernetes")]
 pub async fn handle_net_up_kubernetes(
@@ -31,6 +28,7 @@ pub async fn handle_net_up_kubernetes(
     num_shards: usize,
     testing_prng_seed: Option<u64>,
     binaries: &Option<Option<PathBuf>>,
+    genesis_overrides: Option<PathBuf>,
 ) -> anyhow::Result<()> {
     if num_initial_validators < 1 {
         panic!("The local test network must have at least one validator.");
@@ -51,6 +49,7 @@ pub async fn handle_net_up_kubernetes(
         num_shards,
         binaries: binaries.clone().into(),
         policy: ResourceControlPolicy::default(),
+        genesis_overrides,
     };
     let (mut net, client1) = config.instantiate().await?;
     net_up(extra_wallets, &mut net, client1).await?;
@@ -66,6 +65,7 @@ pub async fn handle_net_up_rocks_db(
     num_shards: usize,
     testing_prng_seed: Option<u64>,
     table_name: &str,
+    genesis_overrides: Option<PathBuf>,
 ) -> anyhow::Result<()> {
     if num_initial_validators < 1 {
         panic!("The local test network must have at least one validator.");
@@ -93,6 +93,7 @@ pub async fn handle_net_up_rocks_db(
         num_initial_validators,
         num_shards,
         policy: ResourceControlPolicy::default(),
+        genesis_overrides,
         storage_config_builder,
         path_provider,
     };
@@ -0,0 +1,339 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A queue of operations that the node service proposes in blocks in the background.
+//!
+//! Instead of proposing one block per operation and waiting for it to be confirmed, callers can
+//! submit operations to the mempool and query their status later. The operations queued for a
+//! chain are proposed together, in blocks that respect the configured limits on the number and
+//! total size of their operations. If another client commits a block first, the chain client
+//! proposes the operations again at the next height.
+//!
+//! If a block can't be executed, e.g. because it exceeds the fuel limit or one of its operations
+//! is invalid, it is split in two and each half is proposed separately, so that only the
+//! operations that fail on their own are rejected.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use async_graphql::{Enum, SimpleObject};
+use futures::lock::Mutex as AsyncMutex;
+use linera_base::{crypto::CryptoHash, identifiers::ChainId};
+use linera_core::{
+    client::ChainClientError,
+    data_types::ClientOutcome,
+    node::{ValidatorNode, ValidatorNodeProvider},
+};
+use linera_execution::Operation;
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    chain_listener::ClientContext,
+    node_service::{wait_for_next_round, ChainClients},
+};
+
+#[cfg(test)]
+#[path = "unit_tests/mempool.rs"]
+mod tests;
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct MempoolConfig {
+    /// The maximum number of queued operations to propose in a single block.
+    #[arg(long = "mempool-max-block-operations", default_value = "100")]
+    pub max_block_operations: usize,
+
+    /// The maximum total size in bytes of the queued operations to propose in a single block.
+    #[arg(long = "mempool-max-block-bytes", default_value = "1000000")]
+    pub max_block_bytes: usize,
+
+    /// The number of committed or failed operations whose status is remembered.
+    #[arg(long = "mempool-history-size", default_value = "10000")]
+    pub history_size: usize,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        MempoolConfig {
+            max_block_operations: 100,
+            max_block_bytes: 1_000_000,
+            history_size: 10_000,
+        }
+    }
+}
+
+/// What happened to an operation submitted to the mempool.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Enum)]
+pub enum OperationState {
+    /// The operation is waiting to be proposed.
+    Queued,
+    /// The operation is in a block that is being proposed.
+    Proposing,
+    /// The operation is in a confirmed block.
+    Committed,
+    /// The operation was rejected.
+    Failed,
+}
+
+/// The status of an operation submitted to the mempool.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct OperationStatus {
+    /// The identifier returned when the operation was submitted.
+    pub id: u64,
+    /// The chain the operation is executed on.
+    pub chain_id: ChainId,
+    /// What happened to the operation so far.
+    pub state: OperationState,
+    /// The hash of the confirmed block containing the operation, if it was committed.
+    pub certificate_hash: Option<CryptoHash>,
+    /// Why the operation was rejected, if it failed.
+    pub error: Option<String>,
+}
+
+/// The operations submitted to the node service that are not committed yet.
+pub struct Mempool {
+    config: MempoolConfig,
+    inner: Mutex<MempoolInner>,
+}
+
+#[derive(Default)]
+struct MempoolInner {
+    next_id: u64,
+    queues: BTreeMap<ChainId, VecDeque<QueuedOperation>>,
+    /// The chains whose queue is being processed.
+    processing: BTreeSet<ChainId>,
+    statuses: BTreeMap<u64, OperationStatus>,
+    /// The committed and failed operations, oldest first.
+    finished: VecDeque<u64>,
+}
+
+/// An operation waiting in the mempool.
+#[derive(Clone, Debug)]
+pub(crate) struct QueuedOperation {
+    id: u64,
+    operation: Operation,
+    size: usize,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Mempool {
+            config,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Adds the `operations` to the queue of `chain_id` and returns their identifiers.
+    ///
+    /// Also returns `true` if the caller must start processing the queue, i.e. if nobody else
+    /// is.
+    pub(crate) fn submit(
+        &self,
+        chain_id: ChainId,
+        operations: Vec<Operation>,
+    ) -> Result<(Vec<u64>, bool), bcs::Error> {
+        let sizes = operations
+            .iter()
+            .map(bcs::serialized_size)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut inner = self.inner.lock().unwrap();
+        let mut ids = Vec::with_capacity(operations.len());
+        for (operation, size) in operations.into_iter().zip(sizes) {
+            let id = inner.next_id;
+            inner.next_id += 1;
+            inner.statuses.insert(
+                id,
+                OperationStatus {
+                    id,
+                    chain_id,
+                    state: OperationState::Queued,
+                    certificate_hash: None,
+                    error: None,
+                },
+            );
+            inner
+                .queues
+                .entry(chain_id)
+                .or_default()
+                .push_back(QueuedOperation {
+                    id,
+                    operation,
+                    size,
+                });
+            ids.push(id);
+        }
+        let start_processing = !ids.is_empty() && inner.processing.insert(chain_id);
+        Ok((ids, start_processing))
+    }
+
+    /// Returns the status of the operation with the given `id`, if it is still remembered.
+    pub fn status(&self, id: u64) -> Option<OperationStatus> {
+        self.inner.lock().unwrap().statuses.get(&id).cloned()
+    }
+
+    /// Returns the operations for `chain_id` that are queued or being proposed.
+    pub fn pending(&self, chain_id: ChainId) -> Vec<OperationStatus> {
+        self.inner
+            .lock()
+            .unwrap()
+            .statuses
+            .values()
+            .filter(|status| {
+                status.chain_id == chain_id
+                    && matches!(
+                        status.state,
+                        OperationState::Queued | OperationState::Proposing
+                    )
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Removes the next operations to propose from the queue of `chain_id`, as many as fit in
+    /// a block but at least one.
+    ///
+    /// If the queue is empty, the chain is no longer considered processed, and the next
+    /// submission must start processing it again.
+    pub(crate) fn next_batch(&self, chain_id: ChainId) -> Vec<QueuedOperation> {
+        let inner = &mut *self.inner.lock().unwrap();
+        let mut batch = Vec::new();
+        let mut batch_size = 0;
+        if let Some(queue) = inner.queues.get_mut(&chain_id) {
+            while let Some(queued) = queue.front() {
+                if !batch.is_empty()
+                    && (batch.len() >= self.config.max_block_operations
+                        || batch_size + queued.size > self.config.max_block_bytes)
+                {
+                    break;
+                }
+                batch_size += queued.size;
+                batch.extend(queue.pop_front());
+            }
+            if queue.is_empty() {
+                inner.queues.remove(&chain_id);
+            }
+        }
+        if batch.is_empty() {
+            inner.processing.remove(&chain_id);
+        }
+        for queued in &batch {
+            if let Some(status) = inner.statuses.get_mut(&queued.id) {
+                status.state = OperationState::Proposing;
+            }
+        }
+        batch
+    }
+
+    /// Records that the operations of `batch` were committed in the given block.
+    pub(crate) fn commit(&self, batch: &[QueuedOperation], certificate_hash: CryptoHash) {
+        self.finish(batch, |status| {
+            status.state = OperationState::Committed;
+            status.certificate_hash = Some(certificate_hash);
+        });
+    }
+
+    /// Records that the operations of `batch` were rejected.
+    pub(crate) fn fail(&self, batch: &[QueuedOperation], error: &str) {
+        self.finish(batch, |status| {
+            status.state = OperationState::Failed;
+            status.error = Some(error.to_string());
+        });
+    }
+
+    fn finish(&self, batch: &[QueuedOperation], update: impl Fn(&mut OperationStatus)) {
+        let inner = &mut *self.inner.lock().unwrap();
+        for queued in batch {
+            if let Some(status) = inner.statuses.get_mut(&queued.id) {
+                update(status);
+                inner.finished.push_back(queued.id);
+            }
+        }
+        while inner.finished.len() > self.config.history_size {
+            if let Some(id) = inner.finished.pop_front() {
+                inner.statuses.remove(&id);
+            }
+        }
+    }
+}
+
+/// Proposes the operations queued for `chain_id` until the queue is empty.
+pub(crate) async fn process_queue<P, S, C>(
+    mempool: Arc<Mempool>,
+    chain_id: ChainId,
+    clients: ChainClients<P, S>,
+    context: Arc<AsyncMutex<C>>,
+) where
+    P: ValidatorNodeProvider + Send + Sync + 'static,
+    <<P as ValidatorNodeProvider>::Node as ValidatorNode>::NotificationStream: Send,
+    S: Storage + Clone + Send + Sync + 'static,
+    C: ClientContext<P> + Send + 'static,
+    ViewError: From<S::ContextError>,
+{
+    loop {
+        let batch = mempool.next_batch(chain_id);
+        if batch.is_empty() {
+            return;
+        }
+        // The batches left to propose, the next one last.
+        let mut batches = vec![batch];
+        while let Some(mut batch) = batches.pop() {
+            let operations = batch
+                .iter()
+                .map(|queued| queued.operation.clone())
+                .collect();
+            match execute_operations(chain_id, operations, &clients, &context).await {
+                Ok(certificate_hash) => {
+                    debug!(%chain_id, %certificate_hash, "Committed {} queued operations", batch.len());
+                    mempool.commit(&batch, certificate_hash);
+                }
+                // The block could not be executed: try to commit each half separately.
+                Err(ChainClientError::LocalNodeError(error)) if batch.len() > 1 => {
+                    debug!(%chain_id, "Splitting a block of {} queued operations: {error}", batch.len());
+                    let second_half = batch.split_off(batch.len() / 2);
+                    batches.push(second_half);
+                    batches.push(batch);
+                }
+                Err(error) => {
+                    warn!(%chain_id, "Failed to commit {} queued operations: {error}", batch.len());
+                    mempool.fail(&batch, &error.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Proposes a block with the `operations` and returns the hash of its certificate, waiting for
+/// the next round as long as the chain client asks to.
+async fn execute_operations<P, S, C>(
+    chain_id: ChainId,
+    operations: Vec<Operation>,
+    clients: &ChainClients<P, S>,
+    context: &AsyncMutex<C>,
+) -> Result<CryptoHash, ChainClientError>
+where
+    P: ValidatorNodeProvider + Send + Sync + 'static,
+    S: Storage + Clone + Send + Sync + 'static,
+    C: ClientContext<P> + Send + 'static,
+    ViewError: From<S::ContextError>,
+{
+    loop {
+        let Some(mut client) = clients.client_lock(&chain_id).await else {
+            return Err(ChainClientError::InternalError(
+                "The chain is no longer tracked by the node service",
+            ));
+        };
+        let mut stream = client.subscribe().await?;
+        let result = client.execute_operations(operations.clone()).await;
+        context.lock().await.update_wallet(&mut *client).await;
+        let timeout = match result? {
+            ClientOutcome::Committed(certificate) => return Ok(certificate.hash()),
+            ClientOutcome::WaitForTimeout(timeout) => timeout,
+        };
+        drop(client);
+        wait_for_next_round(&mut stream, timeout).await;
+    }
+}
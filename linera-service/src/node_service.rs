@@ -7,23 +7,32 @@ use async_graphql::{
     futures_util::Stream,
     parser::types::{DocumentOperations, ExecutableDocument, OperationType},
     Error, MergedObject, Object, Request, ScalarType, Schema, ServerError, SimpleObject,
-    Subscription,
+    Subscription, Variables,
 };
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
-use axum::{extract::Path, http::StatusCode, response, response::IntoResponse, Extension, Router};
+use axum::{
+    extract::{Path, Query as QueryString},
+    http::StatusCode,
+    response,
+    response::IntoResponse,
+    Extension, Json, Router,
+};
 use futures::{
     future::{self},
     lock::{Mutex, MutexGuard, OwnedMutexGuard},
-    Future,
+    stream, Future,
 };
 use linera_base::{
     crypto::{CryptoError, CryptoHash, PublicKey},
-    data_types::{Amount, ApplicationPermissions, TimeDelta, Timestamp},
-    identifiers::{ApplicationId, BytecodeId, ChainId, Owner},
+    data_types::{Amount, ApplicationPermissions, BlockHeight, TimeDelta, Timestamp},
+    identifiers::{ApplicationId, BlobId, BytecodeId, ChainId, Owner},
     ownership::{ChainOwnership, TimeoutConfig},
     BcsHexParseError,
 };
-use linera_chain::{data_types::HashedCertificateValue, ChainStateView};
+use linera_chain::{
+    data_types::{ApplicationEvent, HashedCertificateValue},
+    ChainStateView,
+};
 use linera_core::{
     client::{ArcChainClient, ChainClient, ChainClientError},
     data_types::{ClientOutcome, RoundTimeout},
@@ -47,6 +56,10 @@ use tracing::{debug, error, info};
 
 use crate::{
     chain_listener::{ChainListener, ChainListenerConfig, ClientContext},
+    mempool::{self, Mempool, MempoolConfig, OperationStatus},
+    rest_api::{
+        self, Balance, BalanceQuery, BlockResponse, ChainSummary, TransferRequest, TransferResponse,
+    },
     util,
 };
 
@@ -100,6 +113,7 @@ impl<P, S> ChainClients<P, S> {
 /// Our root GraphQL query type.
 pub struct QueryRoot<P, S> {
     clients: ChainClients<P, S>,
+    mempool: Arc<Mempool>,
     port: NonZeroU16,
     default_chain: Option<ChainId>,
 }
@@ -112,6 +126,7 @@ pub struct SubscriptionRoot<P, S> {
 /// Our root GraphQL mutation type.
 pub struct MutationRoot<P, S, C> {
     clients: ChainClients<P, S>,
+    mempool: Arc<Mempool>,
     context: Arc<Mutex<C>>,
 }
 
@@ -143,6 +158,21 @@ enum NodeServiceError {
     UnknownChainId { chain_id: String },
     #[error("malformed chain ID")]
     InvalidChainId(CryptoError),
+    #[error("malformed blob ID")]
+    InvalidBlobId(CryptoError),
+    #[error("blob not found")]
+    BlobNotFound { blob_id: BlobId },
+    #[error("name not registered")]
+    UnregisteredName { name: String },
+    #[error("no confirmed block at this height")]
+    BlockNotFound {
+        chain_id: ChainId,
+        height: BlockHeight,
+    },
+    #[error("invalid request: {error}")]
+    InvalidRequest { error: String },
+    #[error(transparent)]
+    ViewError(#[from] ViewError),
 }
 
 impl From<ServerError> for NodeServiceError {
@@ -167,6 +197,9 @@ impl IntoResponse for NodeServiceError {
             NodeServiceError::JsonError(e) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, vec![e.to_string()])
             }
+            NodeServiceError::ViewError(e) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, vec![e.to_string()])
+            }
             NodeServiceError::MalformedApplicationResponse => {
                 (StatusCode::INTERNAL_SERVER_ERROR, vec![self.to_string()])
             }
@@ -187,6 +220,27 @@ impl IntoResponse for NodeServiceError {
                 StatusCode::BAD_REQUEST,
                 vec!["invalid chain ID".to_string()],
             ),
+            NodeServiceError::InvalidBlobId(_) => {
+                (StatusCode::BAD_REQUEST, vec!["invalid blob ID".to_string()])
+            }
+            NodeServiceError::BlobNotFound { blob_id } => (
+                StatusCode::NOT_FOUND,
+                vec![format!("blob not found: {}", blob_id)],
+            ),
+            NodeServiceError::UnregisteredName { name } => (
+                StatusCode::NOT_FOUND,
+                vec![format!("name not registered: {}", name)],
+            ),
+            NodeServiceError::BlockNotFound { chain_id, height } => (
+                StatusCode::NOT_FOUND,
+                vec![format!(
+                    "no confirmed block at height {} of chain {}",
+                    height, chain_id
+                )],
+            ),
+            NodeServiceError::InvalidRequest { .. } => {
+                (StatusCode::BAD_REQUEST, vec![self.to_string()])
+            }
         };
         let tuple = (tuple.0, json!({"error": tuple.1}).to_string());
         tuple.into_response()
@@ -208,6 +262,24 @@ where
         let mut client = self.clients.try_client_lock(&chain_id).await?;
         Ok(client.subscribe().await?)
     }
+
+    /// Subscribes to notifications from several chains: the specified ones, or all chains
+    /// tracked by this node service if none are specified.
+    async fn notifications_for_chains(
+        &self,
+        chain_ids: Option<Vec<ChainId>>,
+    ) -> Result<impl Stream<Item = Notification>, Error> {
+        let chain_ids = match chain_ids {
+            Some(chain_ids) => chain_ids,
+            None => self.clients.map_lock().await.keys().cloned().collect(),
+        };
+        let mut streams = Vec::with_capacity(chain_ids.len());
+        for chain_id in chain_ids {
+            let mut client = self.clients.try_client_lock(&chain_id).await?;
+            streams.push(client.subscribe().await?);
+        }
+        Ok(stream::select_all(streams))
+    }
 }
 
 impl<P, S, C> MutationRoot<P, S, C>
@@ -273,6 +345,7 @@ where
 impl<P, S, C> MutationRoot<P, S, C>
 where
     P: ValidatorNodeProvider + Send + Sync + 'static,
+    <<P as ValidatorNodeProvider>::Node as ValidatorNode>::NotificationStream: Send,
     S: Storage + Clone + Send + Sync + 'static,
     C: ClientContext<P> + Send + 'static,
     ViewError: From<S::ContextError>,
@@ -540,10 +613,14 @@ where
         chain_id: ChainId,
         close_chain: Vec<ApplicationId>,
         execute_operations: Option<Vec<ApplicationId>>,
+        mandatory_applications: Vec<ApplicationId>,
+        register_applications: Option<Vec<Owner>>,
     ) -> Result<CryptoHash, Error> {
         let operation = SystemOperation::ChangeApplicationPermissions(ApplicationPermissions {
             execute_operations,
+            mandatory_applications,
             close_chain,
+            register_applications,
         });
         self.execute_system_operation(operation, chain_id).await
     }
@@ -592,6 +669,42 @@ where
             .await
     }
 
+    /// Subscribes to a topic of an application on another chain. The payloads that the
+    /// application publishes to it are delivered to the same application on the subscriber
+    /// chain.
+    async fn subscribe_to_topic(
+        &self,
+        subscriber_chain_id: ChainId,
+        publisher_chain_id: ChainId,
+        application_id: UserApplicationId,
+        topic: String,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::SubscribeToTopic {
+            chain_id: publisher_chain_id,
+            application_id,
+            topic,
+        };
+        self.execute_system_operation(operation, subscriber_chain_id)
+            .await
+    }
+
+    /// Unsubscribes from a topic of an application on another chain.
+    async fn unsubscribe_from_topic(
+        &self,
+        subscriber_chain_id: ChainId,
+        publisher_chain_id: ChainId,
+        application_id: UserApplicationId,
+        topic: String,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::UnsubscribeFromTopic {
+            chain_id: publisher_chain_id,
+            application_id,
+            topic,
+        };
+        self.execute_system_operation(operation, subscriber_chain_id)
+            .await
+    }
+
     /// (admin chain only) Removes a committee. Once this message is accepted by a chain,
     /// blocks from the retired epoch will not be accepted until they are followed (hence
     /// re-certified) by a block certified by a recent committee.
@@ -652,6 +765,83 @@ where
         .await
     }
 
+    /// Upgrades an application created by the chain to a published bytecode. The chains
+    /// running the application pass the migration argument to the new contract when they
+    /// migrate its state, unless they pinned an earlier version.
+    async fn upgrade_application(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        bytecode_id: BytecodeId,
+        migration_argument: String,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::UpgradeApplication {
+            application_id,
+            bytecode_id,
+            migration_argument: migration_argument.into_bytes(),
+        };
+        self.execute_system_operation(operation, chain_id).await
+    }
+
+    /// Pins the version of an application that the chain runs, so that its state is not
+    /// migrated to later upgrades. Without a version, the chain follows the upgrades again.
+    async fn pin_application_version(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        version: Option<u32>,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::PinApplicationVersion {
+            application_id,
+            version,
+        };
+        self.execute_system_operation(operation, chain_id).await
+    }
+
+    /// Executes a list of operations in a new block on the given chain, and returns the hash of
+    /// the confirmed block. User operations must be serialized by the application's ABI.
+    async fn execute_operations(
+        &self,
+        chain_id: ChainId,
+        operations: Vec<Operation>,
+    ) -> Result<CryptoHash, Error> {
+        let certificate = self
+            .apply_client_command(&chain_id, move |mut client| {
+                let operations = operations.clone();
+                async move {
+                    let result = client
+                        .execute_operations(operations)
+                        .await
+                        .map_err(Error::from);
+                    (result, client)
+                }
+            })
+            .await?;
+        Ok(certificate.hash())
+    }
+
+    /// Queues a list of operations to be executed on the given chain, and returns their
+    /// identifiers without waiting for them to be committed. Queued operations are proposed in
+    /// the background, batched into as few blocks as possible.
+    async fn submit_operations(
+        &self,
+        chain_id: ChainId,
+        operations: Vec<Operation>,
+    ) -> Result<Vec<u64>, Error> {
+        // Make sure the chain is tracked by this node service.
+        drop(self.clients.try_client_lock(&chain_id).await?);
+        let (ids, start_processing) = self.mempool.submit(chain_id, operations)?;
+        if start_processing {
+            tokio::spawn(mempool::process_queue(
+                self.mempool.clone(),
+                chain_id,
+                self.clients.clone(),
+                self.context.clone(),
+            ));
+        }
+        Ok(ids)
+    }
+
     /// Requests a `RegisterApplications` message from another chain so the application can be used
     /// on this one.
     async fn request_application(
@@ -760,6 +950,51 @@ where
         }
     }
 
+    /// Returns the events emitted by the applications in the confirmed block at the given
+    /// height of a chain, optionally only those of one application.
+    async fn events(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+        application_id: Option<ApplicationId>,
+    ) -> Result<Vec<ApplicationEvent>, Error> {
+        let client = self.clients.try_client_lock(&chain_id).await?;
+        let hash = {
+            let view = client.chain_state_view().await?;
+            let index = usize::try_from(height.0)?;
+            view.confirmed_log.get(index).await?
+        };
+        let hash = hash.ok_or_else(|| {
+            Error::new(format!(
+                "No confirmed block at height {height} of chain {chain_id}"
+            ))
+        })?;
+        let value = client.read_hashed_certificate_value(hash).await?;
+        let executed_block = value
+            .inner()
+            .executed_block()
+            .ok_or_else(|| Error::new(format!("Certificate {hash} does not contain a block")))?;
+        Ok(executed_block
+            .outcome
+            .events
+            .iter()
+            .filter(|event| application_id.map_or(true, |id| event.application_id == id))
+            .cloned()
+            .collect())
+    }
+
+    /// Returns the status of an operation submitted with `submitOperations`, if it is recent
+    /// enough to be remembered.
+    async fn operation_status(&self, id: u64) -> Option<OperationStatus> {
+        self.mempool.status(id)
+    }
+
+    /// Returns the operations submitted for the given chain that are not committed or
+    /// rejected yet.
+    async fn pending_operations(&self, chain_id: ChainId) -> Vec<OperationStatus> {
+        self.mempool.pending(chain_id)
+    }
+
     /// Returns the version information on this node service.
     async fn version(&self) -> linera_version::VersionInfo {
         linera_version::VersionInfo::default()
@@ -874,9 +1109,11 @@ fn bytes_from_list(list: &[async_graphql::Value]) -> Option<Vec<u8>> {
 }
 
 /// The `NodeService` is a server that exposes a web-server to the client.
-/// The node service is primarily used to explore the state of a chain in GraphQL.
+/// The node service is primarily used to explore the state of a chain in GraphQL. Simple
+/// integrations can use the REST API described in [`rest_api`] instead.
 pub struct NodeService<P, S, C> {
     clients: ChainClients<P, S>,
+    mempool: Arc<Mempool>,
     config: ChainListenerConfig,
     port: NonZeroU16,
     default_chain: Option<ChainId>,
@@ -888,6 +1125,7 @@ impl<P, S: Clone, C> Clone for NodeService<P, S, C> {
     fn clone(&self) -> Self {
         Self {
             clients: self.clients.clone(),
+            mempool: self.mempool.clone(),
             config: self.config.clone(),
             port: self.port,
             default_chain: self.default_chain,
@@ -908,6 +1146,7 @@ where
     /// Creates a new instance of the node service given a client chain and a port.
     pub fn new(
         config: ChainListenerConfig,
+        mempool_config: MempoolConfig,
         port: NonZeroU16,
         default_chain: Option<ChainId>,
         storage: S,
@@ -915,6 +1154,7 @@ where
     ) -> Self {
         Self {
             clients: ChainClients::default(),
+            mempool: Arc::new(Mempool::new(mempool_config)),
             config,
             port,
             default_chain,
@@ -928,11 +1168,13 @@ where
         Schema::build(
             QueryRoot {
                 clients: self.clients.clone(),
+                mempool: self.mempool.clone(),
                 port: self.port,
                 default_chain: self.default_chain,
             },
             MutationRoot {
                 clients: self.clients.clone(),
+                mempool: self.mempool.clone(),
                 context: self.context.clone(),
             },
             SubscriptionRoot {
@@ -955,6 +1197,31 @@ where
                 "/chains/:chain_id/applications/:application_id",
                 application_handler,
             )
+            .route(
+                "/chains/:chain_id/applications/:application_id/blobs/:name",
+                axum::routing::get(Self::registered_blob_handler),
+            )
+            .route("/blobs/:blob_id", axum::routing::get(Self::blob_handler))
+            .route(
+                "/api/chains/:chain_id",
+                axum::routing::get(Self::chain_handler),
+            )
+            .route(
+                "/api/chains/:chain_id/balance",
+                axum::routing::get(Self::balance_handler),
+            )
+            .route(
+                "/api/chains/:chain_id/transfers",
+                axum::routing::post(Self::transfer_handler),
+            )
+            .route(
+                "/api/chains/:chain_id/blocks/:height",
+                axum::routing::get(Self::block_handler),
+            )
+            .route(
+                "/api/openapi.json",
+                axum::routing::get(|| async { Json(rest_api::openapi()) }),
+            )
             .route("/ready", axum::routing::get(|| async { "ready!" }))
             .route_service("/ws", GraphQLSubscription::new(self.schema()))
             .layer(Extension(self.clone()))
@@ -962,6 +1229,7 @@ where
             .layer(CorsLayer::permissive());
 
         info!("GraphiQL IDE: http://localhost:{}", port);
+        info!("REST API: http://localhost:{}/api/openapi.json", port);
 
         ChainListener::new(self.config, self.clients.clone())
             .run(self.context.clone(), self.storage.clone())
@@ -1095,6 +1363,157 @@ where
 
         Ok(response.into())
     }
+
+    /// Returns the content of a blob from the storage.
+    async fn read_blob(&self, blob_id: BlobId) -> Result<Vec<u8>, NodeServiceError> {
+        if !self.storage.contains_blob(blob_id).await? {
+            return Err(NodeServiceError::BlobNotFound { blob_id });
+        }
+        Ok(self.storage.read_blob(blob_id).await?.bytes().to_vec())
+    }
+
+    /// Serves the content of a blob, given its ID.
+    async fn blob_handler(
+        Path(blob_id): Path<String>,
+        service: Extension<Self>,
+    ) -> Result<Vec<u8>, NodeServiceError> {
+        let blob_id: BlobId = blob_id.parse().map_err(NodeServiceError::InvalidBlobId)?;
+        service.0.read_blob(blob_id).await
+    }
+
+    /// Serves the content of the blob registered under a name in a registry application.
+    /// The application's service must answer the GraphQL query `resolve(name: String!)` with
+    /// the ID of the blob, or `null` if the name is not registered.
+    async fn registered_blob_handler(
+        Path((chain_id, application_id, name)): Path<(String, String, String)>,
+        service: Extension<Self>,
+    ) -> Result<Vec<u8>, NodeServiceError> {
+        let chain_id: ChainId = chain_id.parse().map_err(NodeServiceError::InvalidChainId)?;
+        let application_id: UserApplicationId = application_id.parse()?;
+        let request = Request::new("query Resolve($name: String!) { resolve(name: $name) }")
+            .variables(Variables::from_json(json!({ "name": name })));
+        let response = service
+            .0
+            .user_application_query(application_id, &request, chain_id)
+            .await?;
+        if response.is_err() {
+            let errors = response.errors.iter().map(|e| e.to_string()).collect();
+            return Err(NodeServiceError::ApplicationServiceError { errors });
+        }
+        let blob_id = match response.data.into_json()?.get("resolve") {
+            None => return Err(NodeServiceError::MalformedApplicationResponse),
+            Some(serde_json::Value::Null) => {
+                return Err(NodeServiceError::UnregisteredName { name })
+            }
+            Some(blob_id) => serde_json::from_value(blob_id.clone())?,
+        };
+        service.0.read_blob(blob_id).await
+    }
+
+    /// Returns the client of a chain given in a REST request.
+    async fn rest_client(
+        &self,
+        chain_id: &str,
+    ) -> Result<OwnedMutexGuard<ChainClient<P, S>>, NodeServiceError> {
+        let chain_id: ChainId = chain_id.parse().map_err(NodeServiceError::InvalidChainId)?;
+        self.clients
+            .client_lock(&chain_id)
+            .await
+            .ok_or_else(|| NodeServiceError::UnknownChainId {
+                chain_id: chain_id.to_string(),
+            })
+    }
+
+    /// Serves a summary of the state of a chain.
+    async fn chain_handler(
+        Path(chain_id): Path<String>,
+        service: Extension<Self>,
+    ) -> Result<Json<ChainSummary>, NodeServiceError> {
+        let mut client = service.0.rest_client(&chain_id).await?;
+        let info = client.chain_info().await.map_err(ChainClientError::from)?;
+        Ok(Json(ChainSummary::from(&*info)))
+    }
+
+    /// Serves the balance of a chain, or of one of its owners.
+    async fn balance_handler(
+        Path(chain_id): Path<String>,
+        QueryString(query): QueryString<BalanceQuery>,
+        service: Extension<Self>,
+    ) -> Result<Json<Balance>, NodeServiceError> {
+        let mut client = service.0.rest_client(&chain_id).await?;
+        let balance = match query.owner {
+            None => client.query_balance().await?,
+            Some(owner) => client.query_owner_balance(owner).await?,
+        };
+        Ok(Json(Balance {
+            chain_id: client.chain_id(),
+            owner: query.owner,
+            balance,
+        }))
+    }
+
+    /// Transfers tokens from a chain, and returns the hash of the block once it is confirmed.
+    /// Like the `transfer` mutation, this waits and retries if the round is not ours yet.
+    async fn transfer_handler(
+        Path(chain_id): Path<String>,
+        service: Extension<Self>,
+        Json(request): Json<TransferRequest>,
+    ) -> Result<Json<TransferResponse>, NodeServiceError> {
+        let recipient = request
+            .recipient()
+            .map_err(|error| NodeServiceError::InvalidRequest {
+                error: error.to_string(),
+            })?;
+        let user_data = request.user_data.unwrap_or_default();
+        let hash = loop {
+            let mut client = service.0.rest_client(&chain_id).await?;
+            let mut stream = client.subscribe().await.map_err(ChainClientError::from)?;
+            let result = client
+                .transfer(request.owner, request.amount, recipient, user_data.clone())
+                .await;
+            service
+                .0
+                .context
+                .lock()
+                .await
+                .update_wallet(&mut *client)
+                .await;
+            let timeout = match result? {
+                ClientOutcome::Committed(certificate) => break certificate.hash(),
+                ClientOutcome::WaitForTimeout(timeout) => timeout,
+            };
+            drop(client);
+            wait_for_next_round(&mut stream, timeout).await;
+        };
+        Ok(Json(TransferResponse { hash }))
+    }
+
+    /// Serves the confirmed block at a height of a chain.
+    async fn block_handler(
+        Path((chain_id, height)): Path<(String, u64)>,
+        service: Extension<Self>,
+    ) -> Result<Json<BlockResponse>, NodeServiceError> {
+        let client = service.0.rest_client(&chain_id).await?;
+        let chain_id = client.chain_id();
+        let height = BlockHeight(height);
+        let not_found = || NodeServiceError::BlockNotFound { chain_id, height };
+        let hash = {
+            let view = client
+                .chain_state_view()
+                .await
+                .map_err(ChainClientError::from)?;
+            let index = usize::try_from(height.0).map_err(|_| not_found())?;
+            view.confirmed_log.get(index).await?
+        };
+        let hash = hash.ok_or_else(not_found)?;
+        let value = client.read_hashed_certificate_value(hash).await?;
+        let block = value
+            .inner()
+            .executed_block()
+            .ok_or_else(not_found)?
+            .clone();
+        Ok(Json(BlockResponse { hash, block }))
+    }
 }
 
 /// Returns after the specified time or if we receive a notification that a new round has started.
@@ -1,27 +1,21 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{path::PathBuf, time::Duration};
-
-use anyhow::{bail, Result};
-use async_trait::async_trait;
-use futures::{SinkExt, StreamExt};
-use linera_rpc::{
-    config::{
-        NetworkProtocol, ShardConfig, ValidatorInternalNetworkPreConfig,
-        ValidatorPublicNetworkPreConfig,
-    },
-    simple::{MessageHandler, TransportProtocol},
-    RpcMessage,
+use std::{net::SocketAddr, path::PathBuf};
+
+use anyhow::Result;
+use linera_rpc::grpc::{
+    ip_filter::{IpFilterConfig, IpFilterRules},
+    peer_limits::PeerLimitsConfig,
+    public_queries::PublicQueryConfig,
 };
 use linera_service::{
-    config::{Import, ValidatorServerConfig},
-    grpc_proxy::GrpcProxy,
-    util,
+    admin_server::{self, AdminApi},
+    config::{GenesisConfig, Import, ValidatorServerConfig},
+    health_server,
+    traffic_capture::TrafficCaptureConfig,
+    validator_proxy::Proxy,
 };
-use tracing::{error, info, instrument};
-#[cfg(with_metrics)]
-use {linera_service::prometheus_server, std::net::SocketAddr};
 
 /// Options for running the proxy.
 #[derive(clap::Parser, Debug)]
@@ -34,178 +28,71 @@ pub struct ProxyOptions {
     /// Path to server configuration.
     config_path: PathBuf,
 
-    /// Timeout for sending queries (us)
-    #[arg(long = "send-timeout-ms", default_value = "4000", value_parser = util::parse_millis)]
-    send_timeout: Duration,
-
-    /// Timeout for receiving responses (us)
-    #[arg(long = "recv-timeout-ms", default_value = "4000", value_parser = util::parse_millis)]
-    recv_timeout: Duration,
+    /// Path to the genesis configuration of the validator's network. Its hash is reported
+    /// to clients, so that they can check that they talk to a validator of the right network,
+    /// and its admin chain is queried for the minimum version of the software.
+    #[arg(long = "genesis")]
+    genesis_config_path: PathBuf,
+
+    /// Timeout for sending queries (milliseconds). Defaults to the timeout policy of the
+    /// server configuration, or to 4000
+    #[arg(long)]
+    send_timeout_ms: Option<u64>,
+
+    /// Timeout for receiving responses (milliseconds). Defaults to the timeout policy of the
+    /// server configuration, or to 4000
+    #[arg(long)]
+    recv_timeout_ms: Option<u64>,
+
+    /// Configuration of the peers allowed to send requests to the proxy (gRPC only)
+    #[command(flatten)]
+    ip_filter_config: IpFilterConfig,
+
+    /// Limits on the requests of each peer of the proxy (gRPC only)
+    #[command(flatten)]
+    peer_limits_config: PeerLimitsConfig,
+
+    /// Configuration of the read-only endpoint for anonymous queries (gRPC only)
+    #[command(flatten)]
+    public_query_config: PublicQueryConfig,
+
+    /// A file with the IP ranges of the trusted peers, e.g. other validators, that can ask
+    /// for the address of the shard of a chain to send their requests to it directly, in the
+    /// format of the IP filter file. No peer can if it is not given (gRPC only)
+    #[arg(long = "shard-hints-file")]
+    shard_hints_file: Option<PathBuf>,
+
+    /// Configuration of the capture of the block proposals and certificates received by the
+    /// proxy, to replay them against another validator
+    #[command(flatten)]
+    traffic_capture_config: TrafficCaptureConfig,
+
+    /// The address on which to serve the health endpoints, `/health/live` and
+    /// `/health/ready`. The proxy is ready once it accepts connections and its shards are
+    /// reachable
+    #[arg(long, env = "LINERA_PROXY_HEALTH_ADDRESS")]
+    health_address: Option<SocketAddr>,
+
+    /// The address on which to serve the admin API, which flushes the cache of the shards
+    /// of the chains and changes the log filter. Unless an admin token is set, it is not
+    /// authenticated, so it should only be reachable by the operators
+    #[arg(long, env = "LINERA_PROXY_ADMIN_ADDRESS")]
+    admin_address: Option<SocketAddr>,
+
+    /// A secret token that the requests to the admin API must carry, in an
+    /// `Authorization: Bearer <token>` header
+    #[arg(long, env = "LINERA_PROXY_ADMIN_TOKEN", requires = "admin_address")]
+    admin_token: Option<String>,
 
     /// The number of Tokio worker threads to use.
     #[arg(long, env = "LINERA_PROXY_TOKIO_THREADS")]
     tokio_threads: Option<usize>,
 }
 
-/// A Linera Proxy, either gRPC or over 'Simple Transport', meaning TCP or UDP.
-/// The proxy can be configured to have a gRPC ingress and egress, or a combination
-/// of TCP / UDP ingress and egress.
-enum Proxy {
-    Simple(SimpleProxy),
-    Grpc(GrpcProxy),
-}
-
-impl Proxy {
-    /// Run the proxy.
-    async fn run(self) -> Result<()> {
-        match self {
-            Proxy::Simple(simple_proxy) => simple_proxy.run().await,
-            Proxy::Grpc(grpc_proxy) => grpc_proxy.run().await,
-        }
-    }
-
-    /// Constructs and configures the [`Proxy`] given [`ProxyOptions`].
-    async fn from_options(options: ProxyOptions) -> Result<Self> {
-        let config = ValidatorServerConfig::read(&options.config_path)?;
-
-        let internal_protocol = config.internal_network.protocol;
-        let external_protocol = config.validator.network.protocol;
-        let proxy = match (internal_protocol, external_protocol) {
-            (NetworkProtocol::Grpc { .. }, NetworkProtocol::Grpc(tls)) => {
-                Self::Grpc(GrpcProxy::new(
-                    config.validator.network,
-                    config.internal_network,
-                    options.send_timeout,
-                    options.recv_timeout,
-                    tls,
-                ))
-            }
-            (
-                NetworkProtocol::Simple(internal_transport),
-                NetworkProtocol::Simple(public_transport),
-            ) => Self::Simple(SimpleProxy {
-                internal_config: config
-                    .internal_network
-                    .clone_with_protocol(internal_transport),
-                public_config: config
-                    .validator
-                    .network
-                    .clone_with_protocol(public_transport),
-                send_timeout: options.send_timeout,
-                recv_timeout: options.recv_timeout,
-            }),
-            _ => {
-                bail!(
-                    "network protocol mismatch: cannot have {} and {} ",
-                    internal_protocol,
-                    external_protocol,
-                );
-            }
-        };
-
-        Ok(proxy)
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct SimpleProxy {
-    public_config: ValidatorPublicNetworkPreConfig<TransportProtocol>,
-    internal_config: ValidatorInternalNetworkPreConfig<TransportProtocol>,
-    send_timeout: Duration,
-    recv_timeout: Duration,
-}
-
-#[async_trait]
-impl MessageHandler for SimpleProxy {
-    #[instrument(skip_all, fields(chain_id = ?message.target_chain_id()))]
-    async fn handle_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
-        if let RpcMessage::VersionInfoQuery = message {
-            // We assume each shard is running the same version as the proxy
-            return Some(linera_version::VersionInfo::default().into());
-        }
-
-        let Some(chain_id) = message.target_chain_id() else {
-            error!("Can't proxy message without chain ID");
-            return None;
-        };
-
-        let shard = self.internal_config.get_shard_for(chain_id).clone();
-        let protocol = self.internal_config.protocol;
-
-        match Self::try_proxy_message(
-            message,
-            shard,
-            protocol,
-            self.send_timeout,
-            self.recv_timeout,
-        )
-        .await
-        {
-            Ok(maybe_response) => maybe_response,
-            Err(error) => {
-                error!(error = %error, "Failed to proxy message");
-                None
-            }
-        }
-    }
-}
-
-impl SimpleProxy {
-    #[instrument(skip_all, fields(port = self.public_config.port, metrics_port = self.internal_config.metrics_port), err)]
-    async fn run(self) -> Result<()> {
-        info!("Starting simple server");
-        let address = self.get_listen_address(self.public_config.port);
-
-        #[cfg(with_metrics)]
-        Self::start_metrics(&self.get_listen_address(self.internal_config.metrics_port));
-
-        self.public_config
-            .protocol
-            .spawn_server(&address, self)
-            .await?
-            .join()
-            .await?;
-        Ok(())
-    }
-
-    #[cfg(with_metrics)]
-    pub fn start_metrics(address: &String) {
-        match address.parse::<SocketAddr>() {
-            Err(err) => panic!("Invalid metrics address for {address}: {err}"),
-            Ok(address) => prometheus_server::start_metrics(address),
-        }
-    }
-
-    fn get_listen_address(&self, port: u16) -> String {
-        format!("0.0.0.0:{}", port)
-    }
-
-    async fn try_proxy_message(
-        message: RpcMessage,
-        shard: ShardConfig,
-        protocol: TransportProtocol,
-        send_timeout: Duration,
-        recv_timeout: Duration,
-    ) -> Result<Option<RpcMessage>> {
-        let shard_address = format!("{}:{}", shard.host, shard.port);
-        let mut connection = protocol.connect(shard_address).await?;
-        tokio::time::timeout(send_timeout, connection.send(message)).await??;
-        let message = tokio::time::timeout(recv_timeout, connection.next())
-            .await?
-            .transpose()?;
-        Ok(message)
-    }
-}
-
 fn main() -> Result<()> {
-    let env_filter = tracing_subscriber::EnvFilter::builder()
-        .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
-        .from_env_lossy();
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_env_filter(env_filter)
-        .init();
+    admin_server::init_tracing();
 
+    linera_version::VersionInfo::handle_verbose_version_flag();
     let options = <ProxyOptions as clap::Parser>::parse();
 
     let mut runtime = if options.tokio_threads == Some(1) {
@@ -224,5 +111,40 @@ fn main() -> Result<()> {
         .enable_all()
         .build()
         .expect("Failed to create Tokio runtime")
-        .block_on(async move { Proxy::from_options(options).await?.run().await })
+        .block_on(async move {
+            let config = ValidatorServerConfig::read(&options.config_path)?;
+            let genesis_config = GenesisConfig::read(&options.genesis_config_path)?;
+            let shard_hints = options
+                .shard_hints_file
+                .as_deref()
+                .map(IpFilterRules::load)
+                .transpose()?;
+            if let Some(address) = options.health_address {
+                let checks =
+                    Proxy::health_checks(&config.validator.network, &config.internal_network);
+                health_server::start_health_server(address, checks);
+            }
+            let timeout = config
+                .rpc_policies
+                .timeout
+                .unwrap_or_default()
+                .with_overrides(options.send_timeout_ms, options.recv_timeout_ms);
+            let proxy = Proxy::new(
+                config.validator.network,
+                config.internal_network,
+                genesis_config.hash(),
+                genesis_config.admin_id,
+                timeout,
+                &options.ip_filter_config,
+                &options.peer_limits_config,
+                &options.public_query_config,
+                shard_hints,
+                options.traffic_capture_config.start()?,
+            )?;
+            if let Some(address) = options.admin_address {
+                let api = AdminApi::new(options.admin_token).with_proxy_routing(proxy.routing());
+                admin_server::start_admin_server(address, api);
+            }
+            proxy.run().await
+        })
 }
@@ -0,0 +1,134 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Re-execution of the blocks of exported chains, to find where the execution diverges from
+//! the outcomes recorded in their certificates.
+//!
+//! The blocks are executed against a storage that only contains the genesis state and the
+//! archives replayed so far, with their own timestamp as the local time, so that the only
+//! differences with the original execution are the ones of the execution itself. Incoming
+//! messages are executed as recorded in the blocks, so a chain can be replayed without the
+//! chains sending them. The bytecodes of the applications must have been published by a chain
+//! that was replayed first.
+
+use std::io::Read;
+
+use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
+use linera_chain::data_types::CertificateValue;
+use linera_storage::Storage;
+use linera_views::views::{RootView, ViewError};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::chain_archive::ArchiveReader;
+
+#[cfg(test)]
+#[path = "unit_tests/replay.rs"]
+mod tests;
+
+/// The outcome of replaying the blocks of a chain.
+#[derive(Debug, Serialize)]
+pub struct ReplayReport {
+    pub chain_id: ChainId,
+    /// The number of blocks executed with the recorded outcome.
+    pub replayed_blocks: u64,
+    /// The first block whose execution did not have the recorded outcome, if any.
+    pub divergence: Option<Divergence>,
+}
+
+/// A block whose execution did not have the outcome recorded in its certificate.
+#[derive(Debug, Serialize)]
+pub struct Divergence {
+    pub height: BlockHeight,
+    pub certificate_hash: CryptoHash,
+    pub expected_state_hash: CryptoHash,
+    /// The state hash after executing the block again, or `None` if the execution failed.
+    pub actual_state_hash: Option<CryptoHash>,
+    /// Whether the block produced the recorded outgoing messages.
+    pub messages_match: bool,
+    /// Why the execution failed, if it did.
+    pub error: Option<String>,
+}
+
+/// Executes the blocks of an archive in order, until the first one that diverges.
+pub async fn replay_archive<S, R>(
+    storage: &S,
+    archive: ArchiveReader<R>,
+) -> anyhow::Result<ReplayReport>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    ViewError: From<S::ContextError>,
+    R: Read,
+{
+    let chain_id = archive.chain_id();
+    let mut replayed_blocks = 0;
+    for certificate in archive {
+        let certificate = certificate?;
+        let CertificateValue::ConfirmedBlock { executed_block } = certificate.value() else {
+            unreachable!("archives only contain confirmed blocks");
+        };
+        let block = &executed_block.block;
+        // The certificate provides the bytecodes published in the block to the next ones.
+        storage.write_certificate(&certificate).await?;
+        let mut chain = storage.load_chain(chain_id).await?;
+        if block.height == BlockHeight::ZERO && !chain.is_active() {
+            for message in &block.incoming_messages {
+                let is_init_message = chain
+                    .execute_init_message(
+                        message.id(),
+                        &message.event.message,
+                        message.event.timestamp,
+                        block.timestamp,
+                    )
+                    .await?;
+                if is_init_message {
+                    break;
+                }
+            }
+        }
+        // As when confirming a block, the recorded oracle responses are used.
+        let expected = &executed_block.outcome;
+        let result = chain
+            .execute_block(
+                block,
+                block.timestamp,
+                Some(expected.oracle_records.clone()),
+            )
+            .await;
+        if result.as_ref().ok() != Some(expected) {
+            let divergence = Divergence {
+                height: block.height,
+                certificate_hash: certificate.hash(),
+                expected_state_hash: expected.state_hash,
+                actual_state_hash: result.as_ref().ok().map(|outcome| outcome.state_hash),
+                messages_match: result
+                    .as_ref()
+                    .is_ok_and(|outcome| outcome.messages == expected.messages),
+                error: result.err().map(|error| error.to_string()),
+            };
+            warn!(
+                "Chain {} diverges at height {}",
+                chain_id, divergence.height
+            );
+            return Ok(ReplayReport {
+                chain_id,
+                replayed_blocks,
+                divergence: Some(divergence),
+            });
+        }
+        let tip = chain.tip_state.get_mut();
+        tip.block_hash = Some(certificate.hash());
+        tip.next_block_height.try_add_assign_one()?;
+        tip.num_incoming_messages += block.incoming_messages.len() as u32;
+        tip.num_operations += block.operations.len() as u32;
+        tip.num_outgoing_messages += expected.messages.len() as u32;
+        chain.confirmed_log.push(certificate.hash());
+        chain.save().await?;
+        replayed_blocks += 1;
+    }
+    Ok(ReplayReport {
+        chain_id,
+        replayed_blocks,
+        divergence: None,
+    })
+}
@@ -0,0 +1,274 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A minimal REST API of the node service, for integrations such as scripts, webhooks and
+//! monitoring that don't want to write GraphQL queries:
+//!
+//! - `GET /api/chains/{chain_id}` returns a summary of the state of a chain.
+//! - `GET /api/chains/{chain_id}/balance?owner={owner}` returns the balance of the chain, or of
+//!   one of its owners.
+//! - `POST /api/chains/{chain_id}/transfers` transfers tokens from the chain, and returns the
+//!   hash of the new block once it is confirmed.
+//! - `GET /api/chains/{chain_id}/blocks/{height}` returns the confirmed block at a height.
+//! - `GET /api/openapi.json` describes these endpoints in the OpenAPI format, so that clients
+//!   can be generated or tested with the usual tools.
+//!
+//! Requests and responses are JSON. Errors have the same format as for the other endpoints of
+//! the node service: `{"error": [<messages>]}`, with a `4xx` status if the request is invalid.
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, BlockHeight, Timestamp},
+    identifiers::{Account, ChainId, Owner},
+};
+use linera_chain::data_types::ExecutedBlock;
+use linera_core::data_types::ChainInfo;
+use linera_execution::{
+    committee::Epoch,
+    system::{Recipient, UserData},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[cfg(test)]
+#[path = "unit_tests/rest_api.rs"]
+mod tests;
+
+/// A summary of the state of a chain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainSummary {
+    pub chain_id: ChainId,
+    /// The current epoch of the chain, if it is active.
+    pub epoch: Option<Epoch>,
+    /// The hash of the latest block, if any.
+    pub block_hash: Option<CryptoHash>,
+    /// The height of the next block.
+    pub next_block_height: BlockHeight,
+    /// The earliest possible timestamp for the next block.
+    pub timestamp: Timestamp,
+    /// The balance of the chain account, not including its owners' balances.
+    pub balance: Amount,
+}
+
+impl From<&ChainInfo> for ChainSummary {
+    fn from(info: &ChainInfo) -> Self {
+        ChainSummary {
+            chain_id: info.chain_id,
+            epoch: info.epoch,
+            block_hash: info.block_hash,
+            next_block_height: info.next_block_height,
+            timestamp: info.timestamp,
+            balance: info.chain_balance,
+        }
+    }
+}
+
+/// The query string of a balance request.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BalanceQuery {
+    /// The owner whose balance is requested, or `None` for the chain account.
+    pub owner: Option<Owner>,
+}
+
+/// The balance of an account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Balance {
+    pub chain_id: ChainId,
+    pub owner: Option<Owner>,
+    /// The balance, including the incoming messages that are not in a block yet.
+    pub balance: Amount,
+}
+
+/// A request to transfer tokens from a chain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransferRequest {
+    /// The owner whose tokens are transferred, or `None` for the chain account.
+    #[serde(default)]
+    pub owner: Option<Owner>,
+    /// The recipient account, as `chain-id` or `chain-id:owner`.
+    pub recipient: String,
+    pub amount: Amount,
+    #[serde(default)]
+    pub user_data: Option<UserData>,
+}
+
+impl TransferRequest {
+    /// Returns the recipient of the transfer.
+    pub fn recipient(&self) -> Result<Recipient, anyhow::Error> {
+        Ok(Recipient::Account(self.recipient.parse::<Account>()?))
+    }
+}
+
+/// The outcome of a transfer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransferResponse {
+    /// The hash of the confirmed block that executed the transfer.
+    pub hash: CryptoHash,
+}
+
+/// A confirmed block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockResponse {
+    /// The hash of the certified value of the block.
+    pub hash: CryptoHash,
+    pub block: ExecutedBlock,
+}
+
+/// Returns the OpenAPI description of the REST API.
+pub fn openapi() -> serde_json::Value {
+    let chain_id = json!({
+        "name": "chain_id",
+        "in": "path",
+        "required": true,
+        "description": "The ID of a chain tracked by the node service.",
+        "schema": { "type": "string" }
+    });
+    let errors = json!({
+        "400": { "$ref": "#/components/responses/Error" },
+        "404": { "$ref": "#/components/responses/Error" },
+        "500": { "$ref": "#/components/responses/Error" }
+    });
+    let ok = |description: &str, schema: &str| {
+        json!({
+            "description": description,
+            "content": {
+                "application/json": {
+                    "schema": { "$ref": format!("#/components/schemas/{schema}") }
+                }
+            }
+        })
+    };
+    let with_errors = |ok: serde_json::Value| {
+        let mut responses = errors.clone();
+        responses["200"] = ok;
+        responses
+    };
+    let hash = json!({ "type": "string", "description": "A hexadecimal hash." });
+    let amount = json!({ "type": "string", "example": "1.5" });
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Linera node service",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": {
+            "/api/chains/{chain_id}": {
+                "get": {
+                    "summary": "Returns a summary of the state of a chain.",
+                    "parameters": [chain_id],
+                    "responses": with_errors(ok("The state of the chain.", "ChainSummary"))
+                }
+            },
+            "/api/chains/{chain_id}/balance": {
+                "get": {
+                    "summary": "Returns the balance of a chain, or of one of its owners.",
+                    "parameters": [chain_id, {
+                        "name": "owner",
+                        "in": "query",
+                        "required": false,
+                        "schema": { "type": "string" }
+                    }],
+                    "responses": with_errors(ok("The balance.", "Balance"))
+                }
+            },
+            "/api/chains/{chain_id}/transfers": {
+                "post": {
+                    "summary": "Transfers tokens from a chain.",
+                    "parameters": [chain_id],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": { "$ref": "#/components/schemas/TransferRequest" }
+                            }
+                        }
+                    },
+                    "responses": with_errors(ok("The transfer is confirmed.", "TransferResponse"))
+                }
+            },
+            "/api/chains/{chain_id}/blocks/{height}": {
+                "get": {
+                    "summary": "Returns the confirmed block at a height of a chain.",
+                    "parameters": [chain_id, {
+                        "name": "height",
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "integer", "format": "int64", "minimum": 0 }
+                    }],
+                    "responses": with_errors(ok("The block.", "BlockResponse"))
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "ChainSummary": {
+                    "type": "object",
+                    "required": ["chain_id", "next_block_height", "timestamp", "balance"],
+                    "properties": {
+                        "chain_id": { "type": "string" },
+                        "epoch": { "type": "integer", "nullable": true },
+                        "block_hash": { "allOf": [hash], "nullable": true },
+                        "next_block_height": { "type": "integer" },
+                        "timestamp": {
+                            "type": "integer",
+                            "description": "Microseconds since the Unix epoch."
+                        },
+                        "balance": amount
+                    }
+                },
+                "Balance": {
+                    "type": "object",
+                    "required": ["chain_id", "balance"],
+                    "properties": {
+                        "chain_id": { "type": "string" },
+                        "owner": { "type": "string", "nullable": true },
+                        "balance": amount
+                    }
+                },
+                "TransferRequest": {
+                    "type": "object",
+                    "required": ["recipient", "amount"],
+                    "properties": {
+                        "owner": { "type": "string", "nullable": true },
+                        "recipient": {
+                            "type": "string",
+                            "description": "The recipient account, as `chain-id` or `chain-id:owner`."
+                        },
+                        "amount": amount
+                    }
+                },
+                "TransferResponse": {
+                    "type": "object",
+                    "required": ["hash"],
+                    "properties": { "hash": hash }
+                },
+                "BlockResponse": {
+                    "type": "object",
+                    "required": ["hash", "block"],
+                    "properties": {
+                        "hash": hash,
+                        "block": {
+                            "type": "object",
+                            "description": "The block and the outcome of its execution."
+                        }
+                    }
+                }
+            },
+            "responses": {
+                "Error": {
+                    "description": "The request failed.",
+                    "content": {
+                        "application/json": {
+                            "schema": {
+                                "type": "object",
+                                "properties": {
+                                    "error": { "type": "array", "items": { "type": "string" } }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,169 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The routing of the requests received by the proxies to the shards.
+//!
+//! The shard of each chain is cached, and the requests are counted per shard and per prefix
+//! of the chain ID, so that operators can spot hot chains and imbalanced shards.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use linera_base::identifiers::ChainId;
+use linera_rpc::config::{ShardId, ValidatorInternalNetworkPreConfig};
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::IntCounterVec,
+};
+
+/// The maximum number of chains whose shard is cached. The cache is cleared when it is full.
+const MAX_CACHED_CHAINS: usize = 100_000;
+
+/// The number of hexadecimal digits of the chain IDs by which requests are counted.
+#[cfg(with_metrics)]
+const CHAIN_ID_PREFIX_LENGTH: usize = 2;
+
+#[cfg(with_metrics)]
+static PROXY_REQUESTS_PER_SHARD: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "proxy_requests_per_shard",
+        "Number of requests routed to each shard",
+        &["shard"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static PROXY_REQUESTS_PER_CHAIN_PREFIX: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "proxy_requests_per_chain_prefix",
+        "Number of requests routed to shards, by first hexadecimal digits of the chain ID",
+        &["prefix"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+/// A cache of the shards the chains are assigned to.
+#[derive(Debug, Default)]
+pub struct RoutingCache {
+    inner: Mutex<RoutingCacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct RoutingCacheInner {
+    /// The number of shards of the configuration the cached shards were computed with.
+    num_shards: usize,
+    shards: HashMap<ChainId, ShardId>,
+}
+
+impl RoutingCache {
+    /// Returns the shard of `chain_id` in the `network`, and records the request in the
+    /// metrics.
+    ///
+    /// The assignment of chains to shards only depends on the number of shards, so the cache
+    /// is invalidated whenever it is used with a configuration with a different number.
+    pub fn route<P>(
+        &self,
+        network: &ValidatorInternalNetworkPreConfig<P>,
+        chain_id: ChainId,
+    ) -> ShardId {
+        let shard_id = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.num_shards != network.shards.len() {
+                inner.num_shards = network.shards.len();
+                inner.shards.clear();
+            }
+            if inner.shards.len() >= MAX_CACHED_CHAINS {
+                inner.shards.clear();
+            }
+            *inner
+                .shards
+                .entry(chain_id)
+                .or_insert_with(|| network.get_shard_id(chain_id))
+        };
+        record_request(shard_id, chain_id);
+        shard_id
+    }
+
+    /// Forgets the shards of all the chains.
+    pub fn invalidate(&self) {
+        self.inner.lock().unwrap().shards.clear();
+    }
+}
+
+/// Returns the prefix of the chain ID by which requests are counted.
+#[cfg(with_metrics)]
+fn chain_id_prefix(chain_id: ChainId) -> String {
+    let mut prefix = chain_id.to_string();
+    prefix.truncate(CHAIN_ID_PREFIX_LENGTH);
+    prefix
+}
+
+#[cfg_attr(not(with_metrics), allow(unused_variables))]
+fn record_request(shard_id: ShardId, chain_id: ChainId) {
+    #[cfg(with_metrics)]
+    {
+        PROXY_REQUESTS_PER_SHARD
+            .with_label_values(&[&shard_id.to_string()])
+            .inc();
+        PROXY_REQUESTS_PER_CHAIN_PREFIX
+            .with_label_values(&[&chain_id_prefix(chain_id)])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_rpc::config::ShardConfig;
+
+    use super::*;
+
+    fn network(num_shards: u16) -> ValidatorInternalNetworkPreConfig<()> {
+        let shards = (0..num_shards)
+            .map(|i| ShardConfig {
+                host: "host".into(),
+                port: 9000 + i,
+                metrics_host: "host".into(),
+                metrics_port: None,
+                region: None,
+                zone: None,
+                replicas: Vec::new(),
+                read_replicas: Vec::new(),
+                shadow: None,
+                canary: None,
+                unix_socket: None,
+            })
+            .collect();
+        ValidatorInternalNetworkPreConfig {
+            protocol: (),
+            shards,
+            host: "proxy".into(),
+            port: 10000,
+            metrics_host: "proxy".into(),
+            metrics_port: 5000,
+            region: None,
+            zone: None,
+            mutual_tls: None,
+        }
+    }
+
+    #[test]
+    fn test_routing_cache() {
+        let cache = RoutingCache::default();
+        let chains = (0..100).map(ChainId::root).collect::<Vec<_>>();
+        for config in [network(4), network(7), network(4)] {
+            for chain_id in &chains {
+                assert_eq!(
+                    cache.route(&config, *chain_id),
+                    config.get_shard_id(*chain_id)
+                );
+            }
+        }
+        cache.invalidate();
+        let config = network(3);
+        assert_eq!(
+            cache.route(&config, chains[0]),
+            config.get_shard_id(chains[0])
+        );
+    }
+}
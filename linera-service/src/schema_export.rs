@@ -2,9 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use async_trait::async_trait;
-use linera_base::{crypto::KeyPair, data_types::Timestamp, identifiers::ChainId};
+use linera_base::{
+    crypto::{CryptoHash, KeyPair},
+    data_types::Timestamp,
+    identifiers::{Blob, BlobId, ChainId},
+};
 use linera_chain::data_types::{
-    BlockProposal, Certificate, HashedCertificateValue, LiteCertificate,
+    Block, BlockProposal, Certificate, ExecutedBlock, HashedCertificateValue, LiteCertificate,
 };
 use linera_core::{
     client::ChainClient,
@@ -17,6 +21,7 @@ use linera_core::{
 use linera_execution::committee::Committee;
 use linera_service::{
     chain_listener::{ChainListenerConfig, ClientContext},
+    mempool::MempoolConfig,
     node_service::NodeService,
     wallet::Wallet,
 };
@@ -64,6 +69,14 @@ impl ValidatorNode for DummyValidatorNode {
         Err(NodeError::UnexpectedMessage)
     }
 
+    async fn simulate_block_proposal(&mut self, _: Block) -> Result<ExecutedBlock, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
+    async fn download_blob(&mut self, _: ChainId, _: BlobId) -> Result<Blob, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
     async fn subscribe(&mut self, _: Vec<ChainId>) -> Result<NotificationStream, NodeError> {
         Err(NodeError::UnexpectedMessage)
     }
@@ -71,6 +84,10 @@ impl ValidatorNode for DummyValidatorNode {
     async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError> {
         Err(NodeError::UnexpectedMessage)
     }
+
+    async fn get_genesis_config_hash(&mut self) -> Result<CryptoHash, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
 }
 
 struct DummyValidatorNodeProvider;
@@ -121,6 +138,7 @@ impl ClientContext<DummyValidatorNodeProvider> for DummyContext {
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
+    linera_version::VersionInfo::handle_verbose_version_flag();
     let _options = <Options as clap::Parser>::parse();
 
     let store_config = MemoryStoreConfig::new(TEST_MEMORY_MAX_STREAM_QUERIES);
@@ -131,10 +149,13 @@ async fn main() -> std::io::Result<()> {
     let config = ChainListenerConfig {
         delay_before_ms: 0,
         delay_after_ms: 0,
+        watch_refresh_ms: 0,
+        balance_alerts: Default::default(),
     };
     let context = DummyContext;
     let service = NodeService::<DummyValidatorNodeProvider, _, _>::new(
         config,
+        MempoolConfig::default(),
         std::num::NonZeroU16::new(8080).unwrap(),
         None,
         storage,
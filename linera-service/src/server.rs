@@ -2,41 +2,115 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{path::PathBuf, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{BufReader, BufWriter},
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::bail;
 use async_trait::async_trait;
-use futures::future::join_all;
-use linera_base::crypto::{CryptoRng, KeyPair};
-use linera_core::worker::WorkerState;
-use linera_execution::{committee::ValidatorName, WasmRuntime, WithWasmDefault};
+use chrono::{DateTime, Utc};
+use futures::{channel::mpsc, future::join_all};
+use linera_base::{
+    crypto::{CryptoHash, CryptoRng, KeyPair, PublicKey},
+    data_types::{Amount, BlockHeight, Timestamp},
+    identifiers::ChainId,
+};
+use linera_core::{
+    anti_entropy::{AntiEntropy, AntiEntropyConfig},
+    node::LocalValidatorNodeProvider as _,
+    worker::{AdminCommand, WorkerState},
+};
+use linera_execution::{
+    committee::ValidatorName, set_wasm_artifact_directory, ResourceControlPolicy,
+    UserApplicationId, WasmRuntime, WithWasmDefault,
+};
 use linera_rpc::{
     config::{
-        CrossChainConfig, NetworkProtocol, NotificationConfig, ShardConfig, ShardId, TlsConfig,
+        CrossChainConfig, InternalTlsConfig, NetworkProtocol, NotificationConfig, RetryPolicy,
+        RpcPolicies, ShardConfig, ShardId, ShardMove, TimeoutPolicy, TlsConfig,
         ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
     },
-    grpc, simple,
+    grpc::{
+        self,
+        ip_filter::{IpFilterConfig, IpFilterRules},
+        peer_limits::PeerLimitsConfig,
+        public_queries::PublicQueryConfig,
+    },
+    node_provider::{NodeOptions, NodeProvider},
+    simple,
 };
+#[cfg(with_metrics)]
+use linera_service::prometheus_server;
+#[cfg(feature = "rocksdb")]
+use linera_service::storage::RocksDbTuningOptions;
 use linera_service::{
+    admin_server::{self, AdminApi, ScaleOutThresholds, ShardState},
+    chain_archive::{ArchiveReader, ArchiveWriter},
     config::{
-        CommitteeConfig, Export, GenesisConfig, Import, ValidatorConfig, ValidatorServerConfig,
+        CommitteeConfig, Export, GenesisConfig, GenesisOverrides, Import, ValidatorBootstrap,
+        ValidatorConfig, ValidatorServerConfig,
     },
+    health_server::{self, accepts_tcp, HealthChecks},
     storage::{full_initialize_storage, run_with_storage, Runnable, StorageConfigNamespace},
+    traffic_capture::{replay_capture, CaptureReader},
     util,
+    validator_proxy::Proxy,
 };
-use linera_storage::Storage;
+#[cfg(feature = "s3")]
+use linera_storage::S3ColdStore;
+use linera_storage::{archive_inactive_chains, ArchivalPolicy, ColdStore, Storage};
 use linera_views::{common::CommonStoreConfig, views::ViewError};
-use serde::Deserialize;
-use tracing::{error, info};
-#[cfg(with_metrics)]
-use {linera_service::prometheus_server, std::net::SocketAddr};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
 
 struct ServerContext {
     server_config: ValidatorServerConfig,
     cross_chain_config: CrossChainConfig,
     notification_config: NotificationConfig,
+    ip_filter_config: IpFilterConfig,
     shard: Option<usize>,
+    /// The read replica of the shard to run instead of the shard itself, if any.
+    read_replica: Option<usize>,
     grace_period: Duration,
+    clock_skew_tolerance: Duration,
+    chain_state_cache_size: usize,
+    response_cache_size: usize,
+    max_concurrent_chains: usize,
+    chain_lock_warning: Option<Duration>,
+    chain_lock_timeout: Option<Duration>,
+    checkpoint_interval: u64,
+    max_checkpoints: usize,
+    max_checkpoint_bytes: u64,
+    /// How many certificates each shard verifies at once on the blocking thread pool.
+    signature_verification_threads: usize,
+    storage_trace_token: Option<String>,
+    /// Whether the gRPC shards also accept grpc-web requests, e.g. from browsers.
+    grpc_web: bool,
+    admin_address: Option<SocketAddr>,
+    /// The token that the requests to the admin API must carry, if any.
+    admin_token: Option<String>,
+    /// The load from which the admin API reports the shards as overloaded.
+    scale_out: ScaleOutThresholds,
+    /// The proxy to run in the same process as the shards, if any.
+    proxy: Option<Proxy>,
+    health_address: Option<SocketAddr>,
+    /// How to reconcile the chains of this process with the other validators, if at all.
+    anti_entropy: Option<AntiEntropyConfig>,
+    /// How often to delete the blobs that no confirmed block published, if at all.
+    blob_collection_interval: Option<Duration>,
+    /// How long to keep a blob that no confirmed block published.
+    blob_lifetime: Duration,
+    /// Where the certificates of inactive chains are archived, if anywhere.
+    cold_store: Option<Arc<dyn ColdStore>>,
+    /// How often to archive the certificates of inactive chains, if at all.
+    archival_interval: Option<Duration>,
+    /// Which certificates to archive.
+    archival_policy: ArchivalPolicy,
 }
 
 impl ServerContext {
@@ -49,23 +123,55 @@ impl ServerContext {
     where
         S: Storage + Clone + Send + Sync + 'static,
     {
-        let shard = self.server_config.internal_network.shard(shard_id);
+        let mut shard = self.server_config.internal_network.shard(shard_id).clone();
+        if let Some(index) = self.read_replica {
+            let replica = shard
+                .read_replicas
+                .get(index)
+                .unwrap_or_else(|| panic!("Shard {shard_id} has no read replica {index}"));
+            shard = ShardConfig {
+                host: replica.host.clone(),
+                port: replica.port,
+                metrics_port: None,
+                unix_socket: None,
+                ..shard
+            };
+        }
+        let read_only = self.read_replica.is_some();
         info!("Shard booted on {}", shard.host);
+        let chain_state_cache_size = if read_only {
+            0
+        } else {
+            self.chain_state_cache_size
+        };
         let state = WorkerState::new(
             format!("Shard {} @ {}:{}", shard_id, local_ip_addr, shard.port),
             Some(self.server_config.key.copy()),
             storage,
         )
+        .with_next_key_pair(self.server_config.next_key.as_ref().map(KeyPair::copy))
         .with_allow_inactive_chains(false)
         .with_allow_messages_from_deprecated_epochs(false)
-        .with_grace_period(self.grace_period);
-        (state, shard_id, shard.clone())
+        .with_grace_period(self.grace_period)
+        .with_clock_skew_tolerance(self.clock_skew_tolerance)
+        .with_chain_state_cache_size(chain_state_cache_size)
+        .with_response_cache_size(self.response_cache_size)
+        .with_max_concurrent_chains(self.max_concurrent_chains)
+        .with_chain_lock_diagnostics(self.chain_lock_warning, self.chain_lock_timeout)
+        .with_write_ahead_log(!read_only)
+        .with_checkpoint_interval(self.checkpoint_interval)
+        .with_max_checkpoints(self.max_checkpoints)
+        .with_max_checkpoint_bytes(self.max_checkpoint_bytes)
+        .with_signature_verification_threads(self.signature_verification_threads)
+        .with_read_only(read_only);
+        (state, shard_id, shard)
     }
 
     async fn spawn_simple<S>(
         &self,
         listen_address: &str,
         states: Vec<(WorkerState<S>, ShardId, ShardConfig)>,
+        mut admin_commands: BTreeMap<ShardId, mpsc::UnboundedReceiver<AdminCommand>>,
         protocol: simple::TransportProtocol,
     ) -> Result<(), anyhow::Error>
     where
@@ -81,6 +187,7 @@ impl ServerContext {
         for (state, shard_id, shard) in states {
             let internal_network = internal_network.clone();
             let cross_chain_config = self.cross_chain_config.clone();
+            let admin_commands = admin_commands.remove(&shard_id);
             handles.push(async move {
                 #[cfg(with_metrics)]
                 if let Some(port) = shard.metrics_port {
@@ -94,7 +201,7 @@ impl ServerContext {
                     shard_id,
                     cross_chain_config,
                 );
-                let spawned_server = match server.spawn().await {
+                let spawned_server = match server.spawn(admin_commands).await {
                     Ok(server) => server,
                     Err(err) => {
                         error!("Failed to start server: {}", err);
@@ -116,15 +223,19 @@ impl ServerContext {
         &self,
         listen_address: &str,
         states: Vec<(WorkerState<S>, ShardId, ShardConfig)>,
+        mut admin_commands: BTreeMap<ShardId, mpsc::UnboundedReceiver<AdminCommand>>,
     ) -> Result<(), anyhow::Error>
     where
         S: Storage + Clone + Send + Sync + 'static,
         ViewError: From<S::ContextError>,
     {
+        let ip_filter = self.ip_filter_config.start()?;
         let mut handles = Vec::new();
         for (state, shard_id, shard) in states {
             let cross_chain_config = self.cross_chain_config.clone();
             let notification_config = self.notification_config.clone();
+            let ip_filter = ip_filter.clone();
+            let admin_commands = admin_commands.remove(&shard_id);
             handles.push(async move {
                 #[cfg(with_metrics)]
                 if let Some(port) = shard.metrics_port {
@@ -138,6 +249,10 @@ impl ServerContext {
                     self.server_config.internal_network.clone(),
                     cross_chain_config,
                     notification_config,
+                    self.storage_trace_token.clone(),
+                    self.grpc_web,
+                    ip_filter,
+                    admin_commands,
                 )
                 .await
                 {
@@ -158,6 +273,78 @@ impl ServerContext {
         Ok(())
     }
 
+    /// Starts the task comparing the chains of the shards of this process with the other
+    /// validators. The missing certificates are submitted through the public endpoint of
+    /// this validator, so that the proxy forwards them to the right shards.
+    fn spawn_anti_entropy<S>(
+        &self,
+        config: AntiEntropyConfig,
+        storage: S,
+    ) -> Result<(), anyhow::Error>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+        ViewError: From<S::ContextError>,
+    {
+        let node_provider = NodeProvider::new(NodeOptions {
+            timeout: self.server_config.rpc_policies.timeout.unwrap_or_default(),
+            notification_retry: RetryPolicy::NOTIFICATIONS,
+            direct_to_shards: false,
+        });
+        let validator = &self.server_config.validator;
+        let own_node = node_provider.make_node(&validator.network.to_string())?;
+        let internal_network = self.server_config.internal_network.clone();
+        let shard = self.shard;
+        let anti_entropy =
+            AntiEntropy::new(validator.name, own_node, node_provider, storage, config)
+                .with_chain_filter(move |chain_id| {
+                    shard.map_or(true, |shard| {
+                        internal_network.get_shard_id(chain_id) == shard
+                    })
+                });
+        tokio::spawn(anti_entropy.run());
+        Ok(())
+    }
+
+    /// Starts the task deleting the blobs that were sent with block proposals, but that no
+    /// confirmed block published within their lifetime.
+    fn spawn_blob_collection<S>(&self, interval: Duration, storage: S)
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+        ViewError: From<S::ContextError>,
+    {
+        let lifetime = self.blob_lifetime;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match storage.collect_garbage_blobs(lifetime).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Deleted {count} unpublished blobs"),
+                    Err(error) => warn!("Failed to delete the unpublished blobs: {error}"),
+                }
+            }
+        });
+    }
+
+    /// Starts the task moving the certificates of the chains that have had no new blocks for
+    /// a long time to the cold store.
+    fn spawn_archival<S>(&self, interval: Duration, storage: S)
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+        ViewError: From<S::ContextError>,
+    {
+        let policy = self.archival_policy.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match archive_inactive_chains(&storage, &policy).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Archived {count} certificates of inactive chains"),
+                    Err(error) => warn!("Failed to archive the inactive chains: {error}"),
+                }
+            }
+        });
+    }
+
     #[cfg(with_metrics)]
     fn start_metrics(host: &str, port: &u16) {
         match format!("{}:{}", host, port).parse::<SocketAddr>() {
@@ -170,19 +357,99 @@ impl ServerContext {
         // Allow local IP address to be different from the public one.
         "0.0.0.0".to_string()
     }
+
+    /// Returns the checks of the readiness endpoint: the servers of this process accept
+    /// connections, and the storage answers.
+    fn health_checks<S>(&self, storage: S) -> HealthChecks
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let internal_network = &self.server_config.internal_network;
+        let mut checks = HealthChecks::default();
+        if accepts_tcp(internal_network.protocol) {
+            let shards = match self.shard {
+                Some(shard) => shard..shard + 1,
+                None => 0..internal_network.shards.len(),
+            };
+            for shard_id in shards {
+                let shard = internal_network.shard(shard_id);
+                let port = match self.read_replica {
+                    Some(index) => match shard.read_replicas.get(index) {
+                        Some(replica) => replica.port,
+                        None => continue,
+                    },
+                    None if shard.unix_socket.is_some() => continue,
+                    None => shard.port,
+                };
+                checks =
+                    checks.with_server(format!("shard {shard_id}"), format!("127.0.0.1:{port}"));
+            }
+        }
+        let public_network = &self.server_config.validator.network;
+        if self.proxy.is_some() && accepts_tcp(public_network.protocol) {
+            let port = public_network.port;
+            checks = checks.with_server("proxy", format!("127.0.0.1:{port}"));
+        }
+        checks.with_check("storage", move || {
+            let storage = storage.clone();
+            async move {
+                // Any read from the store will do: this certificate doesn't exist.
+                storage
+                    .contains_certificate(CryptoHash::from([0; 4]))
+                    .await
+                    .map(drop)
+                    .map_err(|error| error.to_string())
+            }
+        })
+    }
 }
 
 #[async_trait]
 impl Runnable for ServerContext {
     type Output = ();
 
-    async fn run<S>(self, storage: S) -> Result<(), anyhow::Error>
+    async fn run<S>(mut self, mut storage: S) -> Result<(), anyhow::Error>
     where
         S: Storage + Clone + Send + Sync + 'static,
         ViewError: From<S::ContextError>,
     {
         let listen_address = self.get_listen_address();
 
+        // All the shards need the cold store, to read the certificates that were archived.
+        if let Some(cold_store) = self.cold_store.clone() {
+            storage.set_cold_store(cold_store);
+        }
+
+        if let Some(address) = self.health_address {
+            health_server::start_health_server(address, self.health_checks(storage.clone()));
+        }
+
+        // Read replicas never write the chains, so they don't need to catch up.
+        if let Some(config) = self
+            .anti_entropy
+            .clone()
+            .filter(|_| self.read_replica.is_none())
+        {
+            self.spawn_anti_entropy(config, storage.clone())?;
+        }
+
+        // The shards share the storage, so the first one collects the blobs for all of them.
+        if let Some(interval) = self
+            .blob_collection_interval
+            .filter(|_| self.read_replica.is_none() && self.shard.map_or(true, |shard| shard == 0))
+        {
+            self.spawn_blob_collection(interval, storage.clone());
+        }
+
+        // Similarly, the first shard archives the certificates of all the chains.
+        if let Some(interval) = self.archival_interval.filter(|_| {
+            self.cold_store.is_some()
+                && self.read_replica.is_none()
+                && self.shard.map_or(true, |shard| shard == 0)
+        }) {
+            self.spawn_archival(interval, storage.clone());
+        }
+
         // Run the server
         let states = match self.shard {
             Some(shard) => {
@@ -198,12 +465,52 @@ impl Runnable for ServerContext {
             }
         };
 
+        let mut admin_commands = BTreeMap::new();
+        if let Some(address) = self.admin_address {
+            let shards = states.iter().map(|(state, shard_id, _)| {
+                let (sender, receiver) = mpsc::unbounded();
+                admin_commands.insert(*shard_id, receiver);
+                let shard = ShardState {
+                    proposals: state.proposals().clone(),
+                    equivocations: state.equivocations().clone(),
+                    load: state.load().clone(),
+                    commands: Some(sender),
+                };
+                (*shard_id, shard)
+            });
+            let mut api = AdminApi::new(self.admin_token.clone())
+                .with_shards(shards)
+                .with_scale_out_thresholds(self.scale_out.clone());
+            if let Some(proxy) = &self.proxy {
+                api = api.with_proxy_routing(proxy.routing());
+            }
+            admin_server::start_admin_server(address, api);
+        }
+
+        if let Some(proxy) = self.proxy.take() {
+            info!("Running the proxy");
+            tokio::spawn(async move {
+                if let Err(error) = proxy.run().await {
+                    error!("Proxy ended with an error: {error}");
+                }
+            });
+        }
+
         match self.server_config.internal_network.protocol {
+            NetworkProtocol::Simple(_)
+                if self.server_config.internal_network.mutual_tls.is_some() =>
+            {
+                bail!("Mutual TLS between proxy and shards is only supported with gRPC.")
+            }
             NetworkProtocol::Simple(protocol) => {
-                self.spawn_simple(&listen_address, states, protocol).await?
+                self.spawn_simple(&listen_address, states, admin_commands, protocol)
+                    .await?
             }
             NetworkProtocol::Grpc(tls_config) => match tls_config {
-                TlsConfig::ClearText => self.spawn_grpc(&listen_address, states).await?,
+                TlsConfig::ClearText => {
+                    self.spawn_grpc(&listen_address, states, admin_commands)
+                        .await?
+                }
                 TlsConfig::Tls => bail!("TLS not supported between proxy and shards."),
             },
         };
@@ -212,6 +519,246 @@ impl Runnable for ServerContext {
     }
 }
 
+/// Checks the chains that a change of the shards of a validator moves to another shard.
+///
+/// All the shards of a validator share the same storage, and no storage key depends on
+/// the shard of a chain. So no data has to be copied: it is enough to verify that the new
+/// shards will find the state of each moved chain in the storage.
+struct RebalanceContext {
+    old_config: ValidatorInternalNetworkConfig,
+    new_config: ValidatorInternalNetworkConfig,
+    chains: Vec<ChainId>,
+}
+
+/// A chain moved to another shard, with its state as found in the storage.
+#[derive(Debug, Serialize)]
+struct MovedChain {
+    #[serde(flatten)]
+    shard_move: ShardMove,
+    /// Whether the chain is active in the storage.
+    active: bool,
+    /// The height of the next block of the chain in the storage.
+    next_block_height: BlockHeight,
+}
+
+/// The outcome of a shard rebalancing.
+#[derive(Debug, Serialize)]
+struct RebalanceReport {
+    old_shard_count: usize,
+    new_shard_count: usize,
+    checked_chains: usize,
+    /// The chains assigned to another shard.
+    moved_chains: Vec<MovedChain>,
+    /// The moved chains for which no state was found in the storage.
+    missing_chains: Vec<ChainId>,
+}
+
+#[async_trait]
+impl Runnable for RebalanceContext {
+    type Output = RebalanceReport;
+
+    async fn run<S>(self, storage: S) -> Result<RebalanceReport, anyhow::Error>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+        ViewError: From<S::ContextError>,
+    {
+        let shard_moves = self
+            .old_config
+            .shard_moves(&self.new_config, self.chains.iter().copied());
+        let mut moved_chains = Vec::new();
+        let mut missing_chains = Vec::new();
+        for shard_move in shard_moves {
+            let chain = storage.load_chain(shard_move.chain_id).await?;
+            let active = chain.is_active();
+            let next_block_height = chain.tip_state.get().next_block_height;
+            if !active && next_block_height == BlockHeight::ZERO {
+                warn!("No state found for chain {}", shard_move.chain_id);
+                missing_chains.push(shard_move.chain_id);
+            }
+            moved_chains.push(MovedChain {
+                shard_move,
+                active,
+                next_block_height,
+            });
+        }
+        Ok(RebalanceReport {
+            old_shard_count: self.old_config.shards.len(),
+            new_shard_count: self.new_config.shards.len(),
+            checked_chains: self.chains.len(),
+            moved_chains,
+            missing_chains,
+        })
+    }
+}
+
+/// The number of certificates read from the storage at once when exporting a chain.
+const EXPORT_BATCH_SIZE: usize = 100;
+
+/// Writes the certificates of all the blocks of a chain to an archive.
+struct ExportChainContext {
+    chain_id: ChainId,
+    output: PathBuf,
+}
+
+#[async_trait]
+impl Runnable for ExportChainContext {
+    type Output = u64;
+
+    async fn run<S>(self, storage: S) -> Result<u64, anyhow::Error>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+        ViewError: From<S::ContextError>,
+    {
+        let chain = storage.load_chain(self.chain_id).await?;
+        let hashes = chain.confirmed_log.read(..).await?;
+        let file = BufWriter::new(fs_err::File::create(&self.output)?);
+        let mut writer = ArchiveWriter::new(file, self.chain_id)?;
+        for batch in hashes.chunks(EXPORT_BATCH_SIZE) {
+            for certificate in storage.read_certificates(batch.to_vec()).await? {
+                writer.append(certificate)?;
+            }
+        }
+        Ok(writer.finish()?)
+    }
+}
+
+/// Executes the certificates of an archive that are not in the storage yet.
+///
+/// The certificates are handled like the ones received from clients, so their signatures are
+/// checked against the committees known to the chain and the blocks are executed again.
+struct ImportChainContext {
+    input: PathBuf,
+}
+
+#[async_trait]
+impl Runnable for ImportChainContext {
+    type Output = u64;
+
+    async fn run<S>(self, storage: S) -> Result<u64, anyhow::Error>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+        ViewError: From<S::ContextError>,
+    {
+        let file = BufReader::new(fs_err::File::open(&self.input)?);
+        let reader = ArchiveReader::new(file)?;
+        let chain_id = reader.chain_id();
+        let next_block_height = storage
+            .load_chain(chain_id)
+            .await?
+            .tip_state
+            .get()
+            .next_block_height;
+        let mut worker = WorkerState::new(format!("Import of {chain_id}"), None, storage);
+        let mut imported_count = 0;
+        for certificate in reader {
+            let certificate = certificate?;
+            if certificate.value().height() < next_block_height {
+                continue;
+            }
+            worker.fully_handle_certificate(certificate, vec![]).await?;
+            imported_count += 1;
+        }
+        Ok(imported_count)
+    }
+}
+
+/// Measures the space used in the storage by the chains, the certificates and the
+/// applications.
+///
+/// The storage is shared by all the shards, so it is read directly instead of asking each
+/// shard for its part.
+struct StatsContext {
+    /// The network configuration of the shards, to report the usage of each shard.
+    internal_network: Option<ValidatorInternalNetworkConfig>,
+    /// The number of largest chains to report.
+    top: usize,
+}
+
+/// The space used by a chain, in bytes.
+#[derive(Debug, Serialize)]
+struct ChainUsage {
+    chain_id: ChainId,
+    bytes: u64,
+}
+
+/// The space used by the chains of a shard, in bytes.
+#[derive(Debug, Default, Serialize)]
+struct ShardUsage {
+    chain_count: usize,
+    chain_state_bytes: u64,
+}
+
+/// The outcome of a storage usage measurement.
+#[derive(Debug, Serialize)]
+struct StatsReport {
+    chain_count: usize,
+    chain_state_bytes: u64,
+    certificate_count: u64,
+    certificate_bytes: u64,
+    /// The number of certificates moved to the cold store.
+    archived_certificate_count: u64,
+    blob_count: u64,
+    blob_bytes: u64,
+    /// The chains using the most space, largest first.
+    largest_chains: Vec<ChainUsage>,
+    /// The space used by the chains of each shard, if the shards are known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shards: Option<BTreeMap<ShardId, ShardUsage>>,
+    /// The number of bytes stored by each application, on all the chains.
+    applications: BTreeMap<UserApplicationId, u64>,
+}
+
+#[async_trait]
+impl Runnable for StatsContext {
+    type Output = StatsReport;
+
+    async fn run<S>(self, storage: S) -> Result<StatsReport, anyhow::Error>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+        ViewError: From<S::ContextError>,
+    {
+        let usage = storage.storage_usage().await?;
+        let mut applications = BTreeMap::<_, u64>::new();
+        for chain_id in usage.chain_state_bytes.keys() {
+            let chain = storage.load_chain(*chain_id).await?;
+            for (application_id, bytes) in chain.execution_state.application_storage().await? {
+                *applications.entry(application_id).or_default() += bytes;
+            }
+        }
+        let shards = self.internal_network.map(|network| {
+            let mut shards = BTreeMap::<_, ShardUsage>::new();
+            for (chain_id, bytes) in &usage.chain_state_bytes {
+                let shard = shards.entry(network.get_shard_id(*chain_id)).or_default();
+                shard.chain_count += 1;
+                shard.chain_state_bytes += bytes;
+            }
+            shards
+        });
+        let mut largest_chains = usage
+            .chain_state_bytes
+            .iter()
+            .map(|(chain_id, bytes)| ChainUsage {
+                chain_id: *chain_id,
+                bytes: *bytes,
+            })
+            .collect::<Vec<_>>();
+        largest_chains.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+        largest_chains.truncate(self.top);
+        Ok(StatsReport {
+            chain_count: usage.chain_state_bytes.len(),
+            chain_state_bytes: usage.chain_state_bytes.values().sum(),
+            certificate_count: usage.certificate_count,
+            certificate_bytes: usage.certificate_bytes,
+            archived_certificate_count: usage.archived_certificate_count,
+            blob_count: usage.blob_count,
+            blob_bytes: usage.blob_bytes,
+            largest_chains,
+            shards,
+            applications,
+        })
+    }
+}
+
 #[derive(clap::Parser)]
 #[command(
     name = "linera-server",
@@ -259,6 +806,202 @@ struct ValidatorOptions {
 
     /// The public name and the port of each of the shards
     shards: Vec<ShardConfig>,
+
+    /// The region the proxy is deployed in, if known.
+    #[serde(default)]
+    region: Option<String>,
+
+    /// The availability zone the proxy is deployed in, if known.
+    #[serde(default)]
+    zone: Option<String>,
+
+    /// The certificates with which the proxy and the shards authenticate each other, if the
+    /// internal network uses mutual TLS.
+    #[serde(default)]
+    mutual_tls: Option<InternalTlsConfig>,
+
+    /// The timeouts of the proxy and the retries of the cross-chain messages.
+    #[serde(default)]
+    rpc_policies: RpcPolicies,
+}
+
+/// The options of the proxy, when it runs in the same process as the shards.
+struct ProxyOptions {
+    send_timeout_ms: Option<u64>,
+    recv_timeout_ms: Option<u64>,
+    peer_limits_config: PeerLimitsConfig,
+    public_query_config: PublicQueryConfig,
+    shard_hints: Option<IpFilterRules>,
+}
+
+/// Runs the given shard, or one of its read replicas, or all of them, and the proxy if
+/// `proxy` is set.
+async fn run_shards(
+    options: RunOptions,
+    shard: Option<usize>,
+    read_replica: Option<usize>,
+    proxy: Option<ProxyOptions>,
+) {
+    let RunOptions {
+        server_config_path,
+        storage_config,
+        cross_chain_config,
+        notification_config,
+        ip_filter_config,
+        #[cfg(feature = "rocksdb")]
+        rocks_db_tuning,
+        genesis_config_path,
+        grace_period,
+        clock_skew_tolerance,
+        wasm_runtime,
+        wasm_artifact_cache,
+        max_concurrent_queries,
+        max_stream_queries,
+        cache_size,
+        chain_state_cache_size,
+        response_cache_size,
+        max_concurrent_chains,
+        chain_lock_warning,
+        chain_lock_timeout,
+        checkpoint_interval,
+        max_checkpoints,
+        max_checkpoint_bytes,
+        signature_verification_threads,
+        storage_trace_token,
+        grpc_web,
+        admin_address,
+        admin_token,
+        scale_out,
+        health_address,
+        anti_entropy_interval,
+        anti_entropy_sample_size,
+        blob_collection_interval,
+        blob_lifetime,
+        #[cfg(feature = "s3")]
+        archive,
+    } = options;
+    let genesis_config =
+        GenesisConfig::read(&genesis_config_path).expect("Fail to read initial chain config");
+    let server_config =
+        ValidatorServerConfig::read(&server_config_path).expect("Fail to read server config");
+
+    // The shards can only share a RocksDB store if they run in the same process as the proxy.
+    #[cfg(feature = "rocksdb")]
+    if proxy.is_none()
+        && server_config.internal_network.shards.len() > 1
+        && storage_config.storage_config.is_rocks_db()
+    {
+        panic!("Multiple shards not supported with RocksDB");
+    }
+
+    let cross_chain_config =
+        cross_chain_config.with_retry_policy(server_config.rpc_policies.cross_chain_retry);
+    let genesis_config_hash = genesis_config.hash();
+    let proxy = proxy.map(|proxy| {
+        let timeout = server_config
+            .rpc_policies
+            .timeout
+            .unwrap_or_default()
+            .with_overrides(proxy.send_timeout_ms, proxy.recv_timeout_ms);
+        Proxy::new(
+            server_config.validator.network.clone(),
+            server_config.internal_network.clone(),
+            genesis_config_hash,
+            genesis_config.admin_id,
+            timeout,
+            &ip_filter_config,
+            &proxy.peer_limits_config,
+            &proxy.public_query_config,
+            proxy.shard_hints,
+            None,
+        )
+        .expect("Fail to configure the proxy")
+    });
+
+    #[cfg(feature = "s3")]
+    let (cold_store, archival_interval, archival_policy) = archive.into_config().await;
+    #[cfg(not(feature = "s3"))]
+    let (cold_store, archival_interval, archival_policy) = (None, None, ArchivalPolicy::default());
+
+    let job = ServerContext {
+        server_config,
+        cross_chain_config,
+        notification_config,
+        ip_filter_config,
+        shard,
+        read_replica,
+        grace_period,
+        clock_skew_tolerance,
+        chain_state_cache_size,
+        response_cache_size,
+        max_concurrent_chains,
+        chain_lock_warning,
+        chain_lock_timeout,
+        checkpoint_interval,
+        max_checkpoints,
+        max_checkpoint_bytes,
+        signature_verification_threads,
+        storage_trace_token,
+        grpc_web,
+        admin_address,
+        admin_token,
+        scale_out,
+        proxy,
+        health_address,
+        anti_entropy: anti_entropy_interval.map(|interval| AntiEntropyConfig {
+            interval,
+            sample_size: anti_entropy_sample_size,
+            ..AntiEntropyConfig::default()
+        }),
+        blob_collection_interval,
+        blob_lifetime,
+        cold_store,
+        archival_interval,
+        archival_policy,
+    };
+    let wasm_runtime = wasm_runtime.with_wasm_default();
+    if let Some(directory) = wasm_artifact_cache {
+        set_wasm_artifact_directory(directory)
+            .expect("Failed to create the Wasm artifact directory");
+    }
+    let common_config = CommonStoreConfig {
+        max_concurrent_queries,
+        max_stream_queries,
+        cache_size,
+    };
+    let full_storage_config = storage_config
+        .add_common_config(common_config)
+        .await
+        .unwrap();
+    #[cfg(feature = "rocksdb")]
+    let full_storage_config = full_storage_config.with_rocks_db_tuning(rocks_db_tuning.into());
+    run_with_storage(full_storage_config, &genesis_config, wasm_runtime, job)
+        .await
+        .unwrap();
+}
+
+/// Parses the description of an initial chain, as `PUBLIC_KEY:AMOUNT`.
+fn parse_initial_chain(s: &str) -> anyhow::Result<(PublicKey, Amount)> {
+    let (public_key, amount) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("expected PUBLIC_KEY:AMOUNT"))?;
+    Ok((public_key.parse()?, amount.parse()?))
+}
+
+/// Returns a description of each validator address of the committee whose host name doesn't
+/// resolve.
+async fn unresolved_addresses(committee: &CommitteeConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    for validator in &committee.validators {
+        let network = &validator.network;
+        if let Err(error) = tokio::net::lookup_host((network.host.as_str(), network.port)).await {
+            problems.push(format!(
+                "the address {}:{} of validator {} doesn't resolve: {error}",
+                network.host, network.port, validator.name
+            ));
+        }
+    }
+    problems
 }
 
 fn make_server_config<R: CryptoRng>(
@@ -277,6 +1020,9 @@ fn make_server_config<R: CryptoRng>(
         port: options.internal_port,
         metrics_host: options.metrics_host,
         metrics_port: options.metrics_port,
+        region: options.region,
+        zone: options.zone,
+        mutual_tls: options.mutual_tls,
     };
     let key = KeyPair::generate_from(rng);
     let name = ValidatorName(key.public());
@@ -285,6 +1031,253 @@ fn make_server_config<R: CryptoRng>(
         validator,
         key,
         internal_network,
+        next_key: None,
+        rpc_policies: options.rpc_policies,
+    }
+}
+
+/// The options to run the shards of a validator.
+#[derive(clap::Args)]
+struct RunOptions {
+    /// Path to the file containing the server configuration of this Linera validator (including its secret key)
+    #[arg(long = "server", env = "LINERA_SERVER_CONFIG")]
+    server_config_path: PathBuf,
+
+    /// Storage configuration for the blockchain history and security states.
+    #[arg(long = "storage", env = "LINERA_SERVER_STORAGE")]
+    storage_config: StorageConfigNamespace,
+
+    /// Configuration for cross-chain requests
+    #[command(flatten)]
+    cross_chain_config: CrossChainConfig,
+
+    /// Configuration for notifications
+    #[command(flatten)]
+    notification_config: NotificationConfig,
+
+    /// Configuration of the peers allowed to send requests to the shards
+    #[command(flatten)]
+    ip_filter_config: IpFilterConfig,
+
+    /// Tuning options of RocksDB
+    #[cfg(feature = "rocksdb")]
+    #[command(flatten)]
+    rocks_db_tuning: RocksDbTuningOptions,
+
+    /// Path to the file describing the initial user chains (aka genesis state)
+    #[arg(long = "genesis", env = "LINERA_SERVER_GENESIS")]
+    genesis_config_path: PathBuf,
+
+    /// Blocks with a timestamp this far in the future will still be accepted, but the validator
+    /// will wait until that timestamp before voting.
+    #[arg(long = "grace-period-ms", default_value = "500", value_parser = util::parse_millis)]
+    grace_period: Duration,
+
+    /// How far the clock of this validator may be behind the clocks of its clients and
+    /// peers. Blocks are accepted this much further in the future than the grace period,
+    /// and block proposals and leader timeouts are voted on this much earlier.
+    #[arg(
+        long = "clock-skew-tolerance-ms",
+        default_value = "0",
+        value_parser = util::parse_millis
+    )]
+    clock_skew_tolerance: Duration,
+
+    /// The WebAssembly runtime to use: `wasmer` or `wasmtime`, depending on the features
+    /// the binary was built with, optionally followed by `-with-sanitizer`. Validators mixing
+    /// runtimes must all use a sanitizer, so that applications consume the same fuel
+    #[arg(long)]
+    wasm_runtime: Option<WasmRuntime>,
+
+    /// A directory where compiled WebAssembly modules are saved, so that they don't have
+    /// to be compiled again after a restart.
+    #[arg(long)]
+    wasm_artifact_cache: Option<PathBuf>,
+
+    /// The maximal number of simultaneous queries to the database
+    #[arg(long)]
+    max_concurrent_queries: Option<usize>,
+
+    /// The maximal number of stream queries to the database
+    #[arg(long, default_value = "10")]
+    max_stream_queries: usize,
+
+    /// The maximal number of entries in the storage cache.
+    #[arg(long, default_value = "1000")]
+    cache_size: usize,
+
+    /// The maximal number of chain states each shard keeps in memory between requests.
+    /// Zero disables the cache.
+    #[arg(long, default_value = "0")]
+    chain_state_cache_size: usize,
+
+    /// The maximal number of block proposals and certificates each shard remembers, to
+    /// answer retried requests without handling them again. Zero disables the cache.
+    #[arg(long, default_value = "1000")]
+    response_cache_size: usize,
+
+    /// The maximal number of chains for which each shard executes requests at the same
+    /// time. Requests for the same chain are always executed one at a time. Zero means no
+    /// limit.
+    #[arg(long, default_value = "0")]
+    max_concurrent_chains: usize,
+
+    /// Log a warning whenever a request holds its chain, or waits for it, longer than
+    /// this. The requests of each chain are then executed one at a time, even if the
+    /// number of concurrent chains is not limited.
+    #[arg(long = "chain-lock-warning-ms", value_parser = util::parse_millis)]
+    chain_lock_warning: Option<Duration>,
+
+    /// Fail the requests that waited this long for their chain, with a description of
+    /// the request holding it, instead of waiting forever. The requests of each chain are
+    /// then executed one at a time, even if the number of concurrent chains is not
+    /// limited.
+    #[arg(long = "chain-lock-timeout-ms", value_parser = util::parse_millis)]
+    chain_lock_timeout: Option<Duration>,
+
+    /// Copy the state of each chain into a checkpoint every this many blocks, so that
+    /// the state of a chain at an earlier height can be loaded without replaying its
+    /// blocks. Zero means no checkpoints.
+    #[arg(long, default_value = "0")]
+    checkpoint_interval: u64,
+
+    /// How many of the latest checkpoints of each chain are kept. The older ones are
+    /// deleted when a new one is written.
+    #[arg(long, default_value = "2")]
+    max_checkpoints: usize,
+
+    /// Don't checkpoint the chains whose state is larger than this many bytes. Each
+    /// checkpoint copies the whole state of the chain, so this bounds the cost of writing it.
+    #[arg(long, default_value = "67108864")]
+    max_checkpoint_bytes: u64,
+
+    /// The maximal number of certificates each shard verifies the signatures of at the
+    /// same time, on the blocking thread pool instead of the task handling the requests.
+    /// Zero verifies them on the task handling the requests.
+    #[arg(long, default_value = "0")]
+    signature_verification_threads: usize,
+
+    /// A secret token allowing administrators to request the storage keys read and
+    /// written while handling a request, by sending it in the
+    /// `linera-storage-trace-token` header. Only supported by the gRPC shards.
+    #[arg(long, env = "LINERA_STORAGE_TRACE_TOKEN")]
+    storage_trace_token: Option<String>,
+
+    /// Let the gRPC shards also accept grpc-web requests over HTTP/1.1, so that browser
+    /// clients can talk to them directly, e.g. in a test network without a proxy in front
+    /// of them. The proxy always accepts grpc-web requests on its public endpoints.
+    #[arg(long)]
+    grpc_web: bool,
+
+    /// The address on which to serve the admin API, which lists the block proposals the
+    /// shards are handling and cancels stuck ones, redelivers cross-chain messages, flushes
+    /// the caches, changes the log filter and reports the load of the shards. Unless an admin
+    /// token is set, it is not authenticated, so it should only be reachable by the operators,
+    /// e.g. on a local address.
+    #[arg(long, env = "LINERA_SERVER_ADMIN_ADDRESS")]
+    admin_address: Option<SocketAddr>,
+
+    /// A secret token that the requests to the admin API must carry, in an
+    /// `Authorization: Bearer <token>` header.
+    #[arg(long, env = "LINERA_SERVER_ADMIN_TOKEN", requires = "admin_address")]
+    admin_token: Option<String>,
+
+    /// The load from which the admin API reports a shard as overloaded, recommending to add
+    /// shards.
+    #[command(flatten)]
+    scale_out: ScaleOutThresholds,
+
+    /// The address on which to serve the health endpoints, `/health/live` and
+    /// `/health/ready`. The shards are ready once they accept connections and the storage
+    /// answers
+    #[arg(long, env = "LINERA_SERVER_HEALTH_ADDRESS")]
+    health_address: Option<SocketAddr>,
+
+    /// Every this many milliseconds, compare the tips of a sample of the chains with
+    /// other validators, and pull the certificates that this validator missed. Disabled
+    /// by default: the validator then only catches up when clients update it.
+    #[arg(long = "anti-entropy-interval-ms", value_parser = util::parse_millis)]
+    anti_entropy_interval: Option<Duration>,
+
+    /// The number of chains compared with other validators in each round of
+    /// anti-entropy.
+    #[arg(long, default_value = "10")]
+    anti_entropy_sample_size: usize,
+
+    /// Every this many milliseconds, delete the blobs that were sent with block proposals
+    /// but that no confirmed block published. Disabled by default.
+    #[arg(long = "blob-collection-interval-ms", value_parser = util::parse_millis)]
+    blob_collection_interval: Option<Duration>,
+
+    /// The number of milliseconds for which a blob that no confirmed block published is
+    /// kept, before it can be deleted.
+    #[arg(
+        long = "blob-lifetime-ms",
+        default_value = "3600000",
+        value_parser = util::parse_millis
+    )]
+    blob_lifetime: Duration,
+
+    /// How to archive the certificates of inactive chains to an object store.
+    #[cfg(feature = "s3")]
+    #[command(flatten)]
+    archive: ArchiveOptions,
+}
+
+/// The options to archive the certificates of inactive chains to an object store.
+#[cfg(feature = "s3")]
+#[derive(clap::Args)]
+struct ArchiveOptions {
+    /// The bucket that the certificates of inactive chains are archived to, in S3 or in
+    /// another object store with an S3-compatible API. The archived certificates are read
+    /// back from it when needed, so all the shards of the validator must use it.
+    #[arg(long, env = "LINERA_ARCHIVE_BUCKET")]
+    archive_bucket: Option<String>,
+
+    /// The prefix of the keys of the archived certificates in the bucket.
+    #[arg(long, default_value = "")]
+    archive_prefix: String,
+
+    /// The endpoint of the object store, if it is not AWS S3, e.g.
+    /// `https://storage.googleapis.com` for GCS.
+    #[arg(long, requires = "archive_bucket")]
+    archive_endpoint: Option<String>,
+
+    /// Every this many milliseconds, archive the certificates of the inactive chains, except
+    /// their latest one. Disabled by default.
+    #[arg(
+        long = "archive-interval-ms",
+        value_parser = util::parse_millis,
+        requires = "archive_bucket"
+    )]
+    archive_interval: Option<Duration>,
+
+    /// The number of milliseconds without new blocks after which a chain is inactive.
+    #[arg(
+        long = "archive-after-ms",
+        default_value = "2592000000",
+        value_parser = util::parse_millis
+    )]
+    archive_after: Duration,
+}
+
+#[cfg(feature = "s3")]
+impl ArchiveOptions {
+    /// Returns the cold store, how often to archive the certificates of inactive chains, and
+    /// which ones.
+    async fn into_config(self) -> (Option<Arc<dyn ColdStore>>, Option<Duration>, ArchivalPolicy) {
+        let cold_store = match self.archive_bucket {
+            Some(bucket) => {
+                let store = S3ColdStore::new(bucket, self.archive_prefix, self.archive_endpoint);
+                Some(Arc::new(store.await) as Arc<dyn ColdStore>)
+            }
+            None => None,
+        };
+        let policy = ArchivalPolicy {
+            inactivity: self.archive_after,
+            ..ArchivalPolicy::default()
+        };
+        (cold_store, self.archive_interval, policy)
     }
 }
 
@@ -293,50 +1286,51 @@ enum ServerCommand {
     /// Runs a service for each shard of the Linera validator")
     #[command(name = "run")]
     Run {
-        /// Path to the file containing the server configuration of this Linera validator (including its secret key)
-        #[arg(long = "server")]
-        server_config_path: PathBuf,
-
-        /// Storage configuration for the blockchain history and security states.
-        #[arg(long = "storage")]
-        storage_config: StorageConfigNamespace,
-
-        /// Configuration for cross-chain requests
         #[command(flatten)]
-        cross_chain_config: CrossChainConfig,
-
-        /// Configuration for notifications
-        #[command(flatten)]
-        notification_config: NotificationConfig,
-
-        /// Path to the file describing the initial user chains (aka genesis state)
-        #[arg(long = "genesis")]
-        genesis_config_path: PathBuf,
+        options: RunOptions,
 
         /// Runs a specific shard (from 0 to shards-1)
         #[arg(long)]
         shard: Option<usize>,
 
-        /// Blocks with a timestamp this far in the future will still be accepted, but the validator
-        /// will wait until that timestamp before voting.
-        #[arg(long = "grace-period-ms", default_value = "500", value_parser = util::parse_millis)]
-        grace_period: Duration,
+        /// Runs the read replica with this index of the shard instead, answering the
+        /// queries that don't vote from the storage written by the shard
+        #[arg(long, requires = "shard")]
+        read_replica: Option<usize>,
+    },
 
-        /// The WebAssembly runtime to use.
-        #[arg(long)]
-        wasm_runtime: Option<WasmRuntime>,
+    /// Runs the proxy and all the shards of the Linera validator in a single process, sharing
+    /// the same store, e.g. for small deployments and tests. The hosts of the shards in the
+    /// server configuration must be local. The main options can also be set with environment
+    /// variables
+    #[command(name = "run-all")]
+    RunAll {
+        #[command(flatten)]
+        options: RunOptions,
 
-        /// The maximal number of simultaneous queries to the database
-        #[arg(long)]
-        max_concurrent_queries: Option<usize>,
+        /// Timeout for sending queries from the proxy to the shards. Defaults to the timeout
+        /// policy of the server configuration, or to 4000
+        #[arg(long, env = "LINERA_PROXY_SEND_TIMEOUT_MS")]
+        proxy_send_timeout_ms: Option<u64>,
 
-        /// The maximal number of stream queries to the database
-        #[arg(long, default_value = "10")]
-        max_stream_queries: usize,
+        /// Timeout for receiving the responses of the shards in the proxy. Defaults to the
+        /// timeout policy of the server configuration, or to 4000
+        #[arg(long, env = "LINERA_PROXY_RECV_TIMEOUT_MS")]
+        proxy_recv_timeout_ms: Option<u64>,
 
-        /// The maximal number of entries in the storage cache.
-        #[arg(long, default_value = "1000")]
-        cache_size: usize,
+        /// Limits on the requests of each peer of the proxy (gRPC only)
+        #[command(flatten)]
+        peer_limits_config: PeerLimitsConfig,
+
+        /// Configuration of the read-only endpoint of the proxy for anonymous queries
+        /// (gRPC only)
+        #[command(flatten)]
+        public_query_config: PublicQueryConfig,
+
+        /// A file with the IP ranges of the trusted peers that can ask the proxy for the
+        /// address of the shard of a chain, in the format of the IP filter file (gRPC only)
+        #[arg(long = "shard-hints-file")]
+        shard_hints_file: Option<PathBuf>,
     },
 
     /// Act as a trusted third-party and generate all server configurations
@@ -356,6 +1350,85 @@ enum ServerCommand {
         testing_prng_seed: Option<u64>,
     },
 
+    /// Check a committee description, and write the genesis configuration of a new network
+    /// together with the bootstrap bundle of each validator. The same inputs always result
+    /// in the same genesis configuration hash
+    #[command(name = "generate-genesis")]
+    GenerateGenesis {
+        /// Path to the description of the Linera committee, as written by `generate`
+        #[arg(long)]
+        committee: PathBuf,
+
+        /// Path where to write the genesis configuration
+        #[arg(long)]
+        genesis: PathBuf,
+
+        /// Directory where to write the bootstrap bundle of each validator, containing the
+        /// genesis configuration and its hash
+        #[arg(long)]
+        bundles: Option<PathBuf>,
+
+        /// The name of the network
+        #[arg(long)]
+        network_name: String,
+
+        /// The start timestamp: no blocks can be created before this time
+        #[arg(long)]
+        start_timestamp: DateTime<Utc>,
+
+        /// The owner and balance of each initial chain, as `PUBLIC_KEY:AMOUNT`
+        #[arg(long = "chain", num_args(0..), value_parser = parse_initial_chain)]
+        chains: Vec<(PublicKey, Amount)>,
+
+        /// Index of the admin chain among the initial chains
+        #[arg(long, default_value = "0")]
+        admin_root: u32,
+
+        /// File with the protocol parameters to change from their defaults, in the format of
+        /// `linera create-genesis-config --overrides`
+        #[arg(long)]
+        overrides: Option<PathBuf>,
+
+        /// Don't check that the host names of the validators resolve, e.g. if their DNS
+        /// records are not published yet
+        #[arg(long)]
+        skip_address_check: bool,
+    },
+
+    /// Generate a new key for this validator, to be registered on the admin chain with
+    /// `linera rotate-validator-key`, and print its public key. The shards keep signing with
+    /// the current key for the chains whose committee doesn't contain the new key yet
+    #[command(name = "generate-next-key")]
+    GenerateNextKey {
+        /// Path to the file containing the server configuration of this Linera validator
+        #[arg(long = "server")]
+        server_config_path: PathBuf,
+
+        /// Force this command to generate keys using a PRNG and a given seed. USE FOR
+        /// TESTING ONLY.
+        #[arg(long)]
+        testing_prng_seed: Option<u64>,
+    },
+
+    /// Replace the key of this validator with the key generated by `generate-next-key`,
+    /// once the committees containing the previous key have been removed
+    #[command(name = "activate-next-key")]
+    ActivateNextKey {
+        /// Path to the file containing the server configuration of this Linera validator
+        #[arg(long = "server")]
+        server_config_path: PathBuf,
+    },
+
+    /// Print the metrics endpoints of the proxy and the shards of this validator, labeled
+    /// with the validator name, role, shard and location, in the JSON format of the file-based
+    /// and HTTP service discovery of Prometheus
+    #[command(name = "metrics-targets")]
+    MetricsTargets {
+        /// Path to the file containing the server configuration of this Linera validator
+        #[arg(long = "server")]
+        server_config_path: PathBuf,
+    },
+
     /// Initialize the database
     #[command(name = "initialize")]
     Initialize {
@@ -379,25 +1452,191 @@ enum ServerCommand {
         #[arg(long, default_value = "1000")]
         cache_size: usize,
     },
-}
 
-fn main() {
-    let env_filter = tracing_subscriber::EnvFilter::builder()
-        .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
-        .from_env_lossy();
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_env_filter(env_filter)
-        .init();
+    /// Compute which chains move to another shard when the shards of this validator change,
+    /// verify that their state is available to the new shards, and print a report
+    #[command(name = "rebalance")]
+    Rebalance {
+        /// Path to the current server configuration of this Linera validator
+        #[arg(long = "old-server")]
+        old_server_config_path: PathBuf,
 
-    let options = <ServerOptions as clap::Parser>::parse();
+        /// Path to the new server configuration of this Linera validator
+        #[arg(long = "new-server")]
+        new_server_config_path: PathBuf,
 
-    let mut runtime = if options.tokio_threads == Some(1) {
-        tokio::runtime::Builder::new_current_thread()
-    } else {
-        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        /// Storage configuration for the blockchain history and security states.
+        #[arg(long = "storage")]
+        storage_config: StorageConfigNamespace,
 
-        if let Some(threads) = options.tokio_threads {
+        /// Path to the file describing the initial user chains (aka genesis state)
+        #[arg(long = "genesis")]
+        genesis_config_path: PathBuf,
+
+        /// Chains to check in addition to the root chains of the genesis configuration
+        #[arg(long, num_args(0..))]
+        chains: Vec<ChainId>,
+
+        /// Exit with an error if the state of a moved chain is missing
+        #[arg(long)]
+        strict: bool,
+
+        /// The maximal number of simultaneous queries to the database
+        #[arg(long)]
+        max_concurrent_queries: Option<usize>,
+
+        /// The maximal number of stream queries to the database
+        #[arg(long, default_value = "10")]
+        max_stream_queries: usize,
+
+        /// The maximal number of entries in the storage cache.
+        #[arg(long, default_value = "1000")]
+        cache_size: usize,
+    },
+
+    /// Write the certificates of all the blocks of a chain to an archive file
+    #[command(name = "export-chain")]
+    ExportChain {
+        /// Storage configuration for the blockchain history and security states.
+        #[arg(long = "storage")]
+        storage_config: StorageConfigNamespace,
+
+        /// Path to the file describing the initial user chains (aka genesis state)
+        #[arg(long = "genesis")]
+        genesis_config_path: PathBuf,
+
+        /// The chain to export
+        #[arg(long)]
+        chain_id: ChainId,
+
+        /// Path of the archive to write
+        #[arg(long)]
+        output: PathBuf,
+
+        /// The maximal number of simultaneous queries to the database
+        #[arg(long)]
+        max_concurrent_queries: Option<usize>,
+
+        /// The maximal number of stream queries to the database
+        #[arg(long, default_value = "10")]
+        max_stream_queries: usize,
+
+        /// The maximal number of entries in the storage cache.
+        #[arg(long, default_value = "1000")]
+        cache_size: usize,
+    },
+
+    /// Verify and execute the certificates of an archive file that are missing from the
+    /// storage. A chain can only be imported after the chain that created it
+    #[command(name = "import-chain")]
+    ImportChain {
+        /// Storage configuration for the blockchain history and security states.
+        #[arg(long = "storage")]
+        storage_config: StorageConfigNamespace,
+
+        /// Path to the file describing the initial user chains (aka genesis state)
+        #[arg(long = "genesis")]
+        genesis_config_path: PathBuf,
+
+        /// Path of the archive to read
+        #[arg(long)]
+        input: PathBuf,
+
+        /// The WebAssembly runtime to use to execute the blocks.
+        #[arg(long)]
+        wasm_runtime: Option<WasmRuntime>,
+
+        /// The maximal number of simultaneous queries to the database
+        #[arg(long)]
+        max_concurrent_queries: Option<usize>,
+
+        /// The maximal number of stream queries to the database
+        #[arg(long, default_value = "10")]
+        max_stream_queries: usize,
+
+        /// The maximal number of entries in the storage cache.
+        #[arg(long, default_value = "1000")]
+        cache_size: usize,
+    },
+
+    /// Walk the storage and print the number of chains, the space used by the chains, the
+    /// certificates and the applications, and the largest chains
+    #[command(name = "stats")]
+    Stats {
+        /// Storage configuration for the blockchain history and security states.
+        #[arg(long = "storage")]
+        storage_config: StorageConfigNamespace,
+
+        /// Path to the file describing the initial user chains (aka genesis state)
+        #[arg(long = "genesis")]
+        genesis_config_path: PathBuf,
+
+        /// Path to the server configuration of this Linera validator, to also print the
+        /// space used by the chains of each shard
+        #[arg(long = "server")]
+        server_config_path: Option<PathBuf>,
+
+        /// The number of largest chains to print
+        #[arg(long, default_value = "10")]
+        top: usize,
+
+        /// The maximal number of simultaneous queries to the database
+        #[arg(long)]
+        max_concurrent_queries: Option<usize>,
+
+        /// The maximal number of stream queries to the database
+        #[arg(long, default_value = "10")]
+        max_stream_queries: usize,
+
+        /// The maximal number of entries in the storage cache.
+        #[arg(long, default_value = "1000")]
+        cache_size: usize,
+    },
+
+    /// Send the block proposals and certificates captured by a proxy with `--capture-traffic`
+    /// to a validator, e.g. a staging one, at the pace they were received, and print how many
+    /// succeeded
+    #[command(name = "replay-traffic")]
+    ReplayTraffic {
+        /// Path of the capture to read
+        #[arg(long)]
+        input: PathBuf,
+
+        /// The public address of the validator to send the requests to, e.g.
+        /// `grpc:staging.example.com:19100`
+        #[arg(long)]
+        validator: String,
+
+        /// How many times faster than they were captured to send the requests
+        #[arg(long, default_value = "1.0")]
+        speed: f64,
+
+        /// The maximal number of requests waiting for a response at the same time
+        #[arg(long, default_value = "100")]
+        max_in_flight: usize,
+
+        /// Timeout for sending requests (milliseconds). Defaults to 4000
+        #[arg(long)]
+        send_timeout_ms: Option<u64>,
+
+        /// Timeout for receiving responses (milliseconds). Defaults to 4000
+        #[arg(long)]
+        recv_timeout_ms: Option<u64>,
+    },
+}
+
+fn main() {
+    admin_server::init_tracing();
+
+    linera_version::VersionInfo::handle_verbose_version_flag();
+    let options = <ServerOptions as clap::Parser>::parse();
+
+    let mut runtime = if options.tokio_threads == Some(1) {
+        tokio::runtime::Builder::new_current_thread()
+    } else {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+
+        if let Some(threads) = options.tokio_threads {
             builder.worker_threads(threads);
         }
 
@@ -416,50 +1655,32 @@ async fn run(options: ServerOptions) {
 
     match options.command {
         ServerCommand::Run {
-            server_config_path,
-            storage_config,
-            cross_chain_config,
-            notification_config,
-            genesis_config_path,
+            options,
             shard,
-            grace_period,
-            wasm_runtime,
-            max_concurrent_queries,
-            max_stream_queries,
-            cache_size,
-        } => {
-            let genesis_config = GenesisConfig::read(&genesis_config_path)
-                .expect("Fail to read initial chain config");
-            let server_config = ValidatorServerConfig::read(&server_config_path)
-                .expect("Fail to read server config");
-
-            #[cfg(feature = "rocksdb")]
-            if server_config.internal_network.shards.len() > 1
-                && storage_config.storage_config.is_rocks_db()
-            {
-                panic!("Multiple shards not supported with RocksDB");
-            }
+            read_replica,
+        } => run_shards(options, shard, read_replica, None).await,
 
-            let job = ServerContext {
-                server_config,
-                cross_chain_config,
-                notification_config,
-                shard,
-                grace_period,
-            };
-            let wasm_runtime = wasm_runtime.with_wasm_default();
-            let common_config = CommonStoreConfig {
-                max_concurrent_queries,
-                max_stream_queries,
-                cache_size,
+        ServerCommand::RunAll {
+            options,
+            proxy_send_timeout_ms,
+            proxy_recv_timeout_ms,
+            peer_limits_config,
+            public_query_config,
+            shard_hints_file,
+        } => {
+            let shard_hints = shard_hints_file
+                .as_deref()
+                .map(IpFilterRules::load)
+                .transpose()
+                .expect("Fail to read the shard hints file");
+            let proxy = ProxyOptions {
+                send_timeout_ms: proxy_send_timeout_ms,
+                recv_timeout_ms: proxy_recv_timeout_ms,
+                peer_limits_config,
+                public_query_config,
+                shard_hints,
             };
-            let full_storage_config = storage_config
-                .add_common_config(common_config)
-                .await
-                .unwrap();
-            run_with_storage(full_storage_config, &genesis_config, wasm_runtime, job)
-                .await
-                .unwrap();
+            run_shards(options, None, None, Some(proxy)).await
         }
 
         ServerCommand::Generate {
@@ -495,6 +1716,116 @@ async fn run(options: ServerOptions) {
             }
         }
 
+        ServerCommand::GenerateGenesis {
+            committee,
+            genesis,
+            bundles,
+            network_name,
+            start_timestamp,
+            chains,
+            admin_root,
+            overrides,
+            skip_address_check,
+        } => {
+            let mut committee =
+                CommitteeConfig::read(&committee).expect("Unable to read committee config file");
+            // The genesis configuration must not depend on the order of the committee file.
+            committee.validators.sort_by_key(|validator| validator.name);
+            let micros = u64::try_from(start_timestamp.timestamp_micros())
+                .expect("Start timestamp before 1970");
+            let mut genesis_config = GenesisConfig::new(
+                committee,
+                ChainId::root(admin_root),
+                Timestamp::from(micros),
+                ResourceControlPolicy::default(),
+                network_name,
+            );
+            genesis_config.chains = chains;
+            if let Some(overrides) = overrides {
+                GenesisOverrides::read(&overrides)
+                    .expect("Unable to read genesis overrides file")
+                    .apply(&mut genesis_config)
+                    .expect("Invalid genesis overrides");
+            }
+            let mut problems = genesis_config.problems();
+            if !skip_address_check {
+                problems.extend(unresolved_addresses(&genesis_config.committee).await);
+            }
+            if !problems.is_empty() {
+                for problem in &problems {
+                    error!("Invalid genesis configuration: {problem}");
+                }
+                std::process::exit(1);
+            }
+            let genesis_config_hash = genesis_config.hash();
+            genesis_config
+                .write(&genesis)
+                .expect("Unable to write genesis config file");
+            info!("Wrote genesis config {}", genesis.display());
+            if let Some(bundles) = bundles {
+                for validator in &genesis_config.committee.validators {
+                    let directory = bundles.join(validator.name.to_string());
+                    fs_err::create_dir_all(&directory)
+                        .expect("Unable to create bootstrap bundle directory");
+                    genesis_config
+                        .write(&directory.join("genesis.json"))
+                        .expect("Unable to write genesis config file");
+                    let bootstrap = ValidatorBootstrap {
+                        validator: validator.clone(),
+                        network_name: genesis_config.network_name.clone(),
+                        genesis_config_hash,
+                    };
+                    bootstrap
+                        .write(&directory.join("bootstrap.json"))
+                        .expect("Unable to write bootstrap file");
+                    info!("Wrote bootstrap bundle {}", directory.display());
+                }
+            }
+            println!("{}", genesis_config_hash);
+        }
+
+        ServerCommand::GenerateNextKey {
+            server_config_path,
+            testing_prng_seed,
+        } => {
+            let mut server_config = ValidatorServerConfig::read(&server_config_path)
+                .expect("Fail to read server config");
+            let mut rng = Box::<dyn CryptoRng>::from(testing_prng_seed);
+            let next_key = KeyPair::generate_from(&mut rng);
+            let name = ValidatorName(next_key.public());
+            server_config.next_key = Some(next_key);
+            server_config
+                .write(&server_config_path)
+                .expect("Unable to write server config file");
+            info!("Wrote server config {}", server_config_path.display());
+            println!("{}", name);
+        }
+
+        ServerCommand::MetricsTargets { server_config_path } => {
+            let server_config = ValidatorServerConfig::read(&server_config_path)
+                .expect("Fail to read server config");
+            let targets = server_config
+                .internal_network
+                .metrics_targets(&server_config.validator.name.to_string());
+            println!("{}", serde_json::to_string_pretty(&targets).unwrap());
+        }
+
+        ServerCommand::ActivateNextKey { server_config_path } => {
+            let mut server_config = ValidatorServerConfig::read(&server_config_path)
+                .expect("Fail to read server config");
+            let next_key = server_config
+                .next_key
+                .take()
+                .expect("The server config has no next key; run `generate-next-key` first");
+            server_config.validator.name = ValidatorName(next_key.public());
+            server_config.key = next_key;
+            server_config
+                .write(&server_config_path)
+                .expect("Unable to write server config file");
+            info!("Wrote server config {}", server_config_path.display());
+            println!("{}", server_config.validator.name);
+        }
+
         ServerCommand::Initialize {
             storage_config,
             genesis_config_path,
@@ -517,12 +1848,207 @@ async fn run(options: ServerOptions) {
                 .await
                 .unwrap();
         }
+
+        ServerCommand::Rebalance {
+            old_server_config_path,
+            new_server_config_path,
+            storage_config,
+            genesis_config_path,
+            chains,
+            strict,
+            max_concurrent_queries,
+            max_stream_queries,
+            cache_size,
+        } => {
+            let genesis_config = GenesisConfig::read(&genesis_config_path)
+                .expect("Fail to read initial chain config");
+            let old_config = ValidatorServerConfig::read(&old_server_config_path)
+                .expect("Fail to read old server config");
+            let new_config = ValidatorServerConfig::read(&new_server_config_path)
+                .expect("Fail to read new server config");
+            let chains = (0..genesis_config.chains.len() as u32)
+                .map(ChainId::root)
+                .chain(chains)
+                .collect::<BTreeSet<_>>();
+            let job = RebalanceContext {
+                old_config: old_config.internal_network,
+                new_config: new_config.internal_network,
+                chains: chains.into_iter().collect(),
+            };
+            let common_config = CommonStoreConfig {
+                max_concurrent_queries,
+                max_stream_queries,
+                cache_size,
+            };
+            let full_storage_config = storage_config
+                .add_common_config(common_config)
+                .await
+                .unwrap();
+            let report = run_with_storage(full_storage_config, &genesis_config, None, job)
+                .await
+                .unwrap();
+            info!(
+                "{} of {} chains move from {} to {} shards",
+                report.moved_chains.len(),
+                report.checked_chains,
+                report.old_shard_count,
+                report.new_shard_count
+            );
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            if strict && !report.missing_chains.is_empty() {
+                error!(
+                    "Missing the state of {} moved chains",
+                    report.missing_chains.len()
+                );
+                std::process::exit(1);
+            }
+        }
+
+        ServerCommand::ExportChain {
+            storage_config,
+            genesis_config_path,
+            chain_id,
+            output,
+            max_concurrent_queries,
+            max_stream_queries,
+            cache_size,
+        } => {
+            let genesis_config = GenesisConfig::read(&genesis_config_path)
+                .expect("Fail to read initial chain config");
+            let common_config = CommonStoreConfig {
+                max_concurrent_queries,
+                max_stream_queries,
+                cache_size,
+            };
+            let full_storage_config = storage_config
+                .add_common_config(common_config)
+                .await
+                .unwrap();
+            let job = ExportChainContext {
+                chain_id,
+                output: output.clone(),
+            };
+            let count = run_with_storage(full_storage_config, &genesis_config, None, job)
+                .await
+                .unwrap();
+            info!(
+                "Exported {} certificates of chain {} to {}",
+                count,
+                chain_id,
+                output.display()
+            );
+        }
+
+        ServerCommand::ImportChain {
+            storage_config,
+            genesis_config_path,
+            input,
+            wasm_runtime,
+            max_concurrent_queries,
+            max_stream_queries,
+            cache_size,
+        } => {
+            let genesis_config = GenesisConfig::read(&genesis_config_path)
+                .expect("Fail to read initial chain config");
+            let common_config = CommonStoreConfig {
+                max_concurrent_queries,
+                max_stream_queries,
+                cache_size,
+            };
+            let full_storage_config = storage_config
+                .add_common_config(common_config)
+                .await
+                .unwrap();
+            let job = ImportChainContext {
+                input: input.clone(),
+            };
+            let wasm_runtime = wasm_runtime.with_wasm_default();
+            let count = run_with_storage(full_storage_config, &genesis_config, wasm_runtime, job)
+                .await
+                .unwrap();
+            info!("Imported {} certificates from {}", count, input.display());
+        }
+
+        ServerCommand::Stats {
+            storage_config,
+            genesis_config_path,
+            server_config_path,
+            top,
+            max_concurrent_queries,
+            max_stream_queries,
+            cache_size,
+        } => {
+            let genesis_config = GenesisConfig::read(&genesis_config_path)
+                .expect("Fail to read initial chain config");
+            let internal_network = server_config_path.map(|path| {
+                ValidatorServerConfig::read(&path)
+                    .expect("Fail to read server config")
+                    .internal_network
+            });
+            let common_config = CommonStoreConfig {
+                max_concurrent_queries,
+                max_stream_queries,
+                cache_size,
+            };
+            let full_storage_config = storage_config
+                .add_common_config(common_config)
+                .await
+                .unwrap();
+            let job = StatsContext {
+                internal_network,
+                top,
+            };
+            let report = run_with_storage(full_storage_config, &genesis_config, None, job)
+                .await
+                .unwrap();
+            info!(
+                "Found {} chains using {} bytes and {} certificates using {} bytes",
+                report.chain_count,
+                report.chain_state_bytes,
+                report.certificate_count,
+                report.certificate_bytes
+            );
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
+
+        ServerCommand::ReplayTraffic {
+            input,
+            validator,
+            speed,
+            max_in_flight,
+            send_timeout_ms,
+            recv_timeout_ms,
+        } => {
+            if speed.is_nan() || speed <= 0.0 {
+                error!("The speed of the replay must be positive");
+                std::process::exit(1);
+            }
+            let reader = fs_err::File::open(&input).expect("Fail to open the capture");
+            let reader = CaptureReader::new(BufReader::new(reader)).expect("Invalid capture");
+            let node_provider = NodeProvider::new(NodeOptions {
+                timeout: TimeoutPolicy::default().with_overrides(send_timeout_ms, recv_timeout_ms),
+                notification_retry: RetryPolicy::NOTIFICATIONS,
+                direct_to_shards: false,
+            });
+            let node = node_provider
+                .make_node(&validator)
+                .expect("Invalid validator address");
+            info!("Replaying {} against {validator}", input.display());
+            let report = replay_capture(reader, node, speed, max_in_flight)
+                .await
+                .expect("Fail to read the capture");
+            info!("{} of {} requests succeeded", report.succeeded, report.sent);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use linera_rpc::simple::TransportProtocol;
+    use linera_rpc::{
+        config::{Locality, RetryPolicy},
+        simple::TransportProtocol,
+    };
 
     use super::*;
 
@@ -550,6 +2076,10 @@ mod test {
             port = 9002
             metrics_host = "metrics_host2"
             metrics_port = 5002
+
+            [rpc_policies.cross_chain_retry]
+            max_retries = 3
+            retry_delay_ms = 500
         "#;
         let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
         assert_eq!(
@@ -570,15 +2100,422 @@ mod test {
                         port: 9001,
                         metrics_host: "metrics_host1".into(),
                         metrics_port: Some(5001),
+                        region: None,
+                        zone: None,
+                        replicas: Vec::new(),
+                        read_replicas: Vec::new(),
+                        shadow: None,
+                        canary: None,
+                        unix_socket: None,
                     },
                     ShardConfig {
                         host: "host2".into(),
                         port: 9002,
                         metrics_host: "metrics_host2".into(),
                         metrics_port: Some(5002),
+                        region: None,
+                        zone: None,
+                        replicas: Vec::new(),
+                        read_replicas: Vec::new(),
+                        shadow: None,
+                        canary: None,
+                        unix_socket: None,
                     },
                 ],
+                region: None,
+                zone: None,
+                mutual_tls: None,
+                rpc_policies: RpcPolicies {
+                    cross_chain_retry: Some(RetryPolicy {
+                        max_retries: 3,
+                        retry_delay_ms: 500,
+                    }),
+                    ..RpcPolicies::default()
+                },
             }
         );
     }
+
+    #[test]
+    fn test_shard_replicas_in_the_same_zone_are_preferred() {
+        let toml_str = r#"
+            server_config_path = "server.json"
+            host = "host"
+            port = 9000
+            internal_host = "internal_host"
+            internal_port = 10000
+            metrics_host = "metrics_host"
+            metrics_port = 5000
+            external_protocol = { Simple = "Tcp" }
+            internal_protocol = { Simple = "Udp" }
+            region = "europe"
+            zone = "europe-b"
+
+            [[shards]]
+            host = "host1"
+            port = 9001
+            metrics_host = "metrics_host1"
+            region = "europe"
+            zone = "europe-a"
+
+            [[shards.replicas]]
+            host = "host1-replica"
+            port = 9011
+            region = "europe"
+            zone = "europe-b"
+        "#;
+        let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
+        let config = make_server_config(&mut Box::<dyn CryptoRng>::from(Some(0)), options);
+        let shard = &config.internal_network.shards[0];
+        let endpoint = shard.closest_endpoint(Some("europe"), Some("europe-b"));
+        assert_eq!(endpoint.address(), "host1-replica:9011");
+        assert_eq!(endpoint.locality, Locality::SameZone);
+        let endpoint = shard.closest_endpoint(Some("europe"), Some("europe-c"));
+        assert_eq!(endpoint.address(), "host1:9001");
+        assert_eq!(endpoint.locality, Locality::CrossZone);
+        let endpoint = shard.closest_endpoint(None, None);
+        assert_eq!(endpoint.address(), "host1:9001");
+        assert_eq!(endpoint.locality, Locality::Unknown);
+        // Only the read-only requests are sent to the replicas.
+        let network = &config.internal_network;
+        let endpoint = network.get_request_endpoint(0, true, 0);
+        assert_eq!(endpoint.address(), "host1-replica:9011");
+        let endpoint = network.get_request_endpoint(0, false, 0);
+        assert_eq!(endpoint.address(), "host1:9001");
+        assert_eq!(endpoint.locality, Locality::CrossZone);
+    }
+
+    #[test]
+    fn test_read_only_queries_go_to_read_replicas() {
+        let toml_str = r#"
+            server_config_path = "server.json"
+            host = "host"
+            port = 9000
+            internal_host = "internal_host"
+            internal_port = 10000
+            metrics_host = "metrics_host"
+            metrics_port = 5000
+            external_protocol = { Simple = "Tcp" }
+            internal_protocol = { Simple = "Udp" }
+            region = "europe"
+            zone = "europe-b"
+
+            [[shards]]
+            host = "host1"
+            port = 9001
+            metrics_host = "metrics_host1"
+
+            [[shards.read_replicas]]
+            host = "host1-read-a"
+            port = 9021
+            region = "europe"
+            zone = "europe-a"
+
+            [[shards.read_replicas]]
+            host = "host1-read-b1"
+            port = 9022
+            region = "europe"
+            zone = "europe-b"
+
+            [[shards.read_replicas]]
+            host = "host1-read-b2"
+            port = 9023
+            region = "europe"
+            zone = "europe-b"
+
+            [[shards]]
+            host = "host2"
+            port = 9002
+            metrics_host = "metrics_host2"
+        "#;
+        let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
+        let config = make_server_config(&mut Box::<dyn CryptoRng>::from(Some(0)), options);
+        let network = &config.internal_network;
+        // The queries are spread over the closest read replicas.
+        let endpoint = network.get_request_endpoint(0, true, 0);
+        assert_eq!(endpoint.address(), "host1-read-b1:9022");
+        assert_eq!(endpoint.locality, Locality::SameZone);
+        let endpoint = network.get_request_endpoint(0, true, 1);
+        assert_eq!(endpoint.address(), "host1-read-b2:9023");
+        // The other requests, and the queries for shards without read replicas, go to the
+        // shards themselves.
+        let endpoint = network.get_request_endpoint(0, false, 0);
+        assert_eq!(endpoint.address(), "host1:9001");
+        let endpoint = network.get_request_endpoint(1, true, 0);
+        assert_eq!(endpoint.address(), "host2:9002");
+    }
+
+    #[test]
+    fn test_metrics_targets() {
+        let toml_str = r#"
+            server_config_path = "server.json"
+            host = "host"
+            port = 9000
+            internal_host = "internal_host"
+            internal_port = 10000
+            metrics_host = "metrics_host"
+            metrics_port = 5000
+            external_protocol = { Simple = "Tcp" }
+            internal_protocol = { Simple = "Udp" }
+            region = "europe"
+
+            [[shards]]
+            host = "host1"
+            port = 9001
+            metrics_host = "metrics_host1"
+            metrics_port = 5001
+            zone = "europe-a"
+
+            [[shards]]
+            host = "host2"
+            port = 9002
+            metrics_host = "metrics_host2"
+        "#;
+        let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
+        let config = make_server_config(&mut Box::<dyn CryptoRng>::from(Some(0)), options);
+        let targets = config.internal_network.metrics_targets("validator0");
+        // The second shard doesn't serve metrics.
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].targets, vec!["metrics_host:5000".to_string()]);
+        assert_eq!(
+            targets[0].labels.iter().collect::<Vec<_>>(),
+            vec![
+                (&"region".to_string(), &"europe".to_string()),
+                (&"role".to_string(), &"proxy".to_string()),
+                (&"validator".to_string(), &"validator0".to_string()),
+            ]
+        );
+        assert_eq!(targets[1].targets, vec!["metrics_host1:5001".to_string()]);
+        assert_eq!(targets[1].labels["role"], "shard");
+        assert_eq!(targets[1].labels["shard"], "0");
+        assert_eq!(targets[1].labels["zone"], "europe-a");
+        assert!(!targets[1].labels.contains_key("region"));
+    }
+
+    #[test]
+    fn test_shadow_shard() {
+        let toml_str = r#"
+            server_config_path = "server.json"
+            host = "host"
+            port = 9000
+            internal_host = "internal_host"
+            internal_port = 10000
+            metrics_host = "metrics_host"
+            metrics_port = 5000
+            external_protocol = { Simple = "Tcp" }
+            internal_protocol = { Simple = "Udp" }
+
+            [[shards]]
+            host = "host1"
+            port = 9001
+            metrics_host = "metrics_host1"
+
+            [shards.shadow]
+            host = "host1-shadow"
+            port = 9021
+            percentage = 10
+        "#;
+        let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
+        let config = make_server_config(&mut Box::<dyn CryptoRng>::from(Some(0)), options);
+        let shadow = config.internal_network.shards[0].shadow.as_ref().unwrap();
+        assert_eq!(shadow.address(), "host1-shadow:9021");
+        assert_eq!(
+            (0..100).filter(|sample| shadow.mirrors(*sample)).count(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_canary_shard() {
+        let toml_str = r#"
+            server_config_path = "server.json"
+            host = "host"
+            port = 9000
+            internal_host = "internal_host"
+            internal_port = 10000
+            metrics_host = "metrics_host"
+            metrics_port = 5000
+            external_protocol = { Simple = "Tcp" }
+            internal_protocol = { Simple = "Udp" }
+
+            [[shards]]
+            host = "host1"
+            port = 9001
+            metrics_host = "metrics_host1"
+
+            [shards.canary]
+            host = "host1-canary"
+            port = 9031
+            chain_id_prefixes = ["A"]
+
+            [[shards]]
+            host = "host2"
+            port = 9002
+            metrics_host = "metrics_host2"
+
+            [shards.canary]
+            host = "host2-canary"
+            port = 9032
+            percentage = 5
+        "#;
+        let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
+        let config = make_server_config(&mut Box::<dyn CryptoRng>::from(Some(0)), options);
+        let shards = &config.internal_network.shards;
+        let canary = shards[0].canary.as_ref().unwrap();
+        assert_eq!(canary.address(), "host1-canary:9031");
+        let (matching, other): (Vec<_>, Vec<_>) = (0..100)
+            .map(ChainId::root)
+            .partition(|chain_id| chain_id.to_string().starts_with('a'));
+        assert!(!matching.is_empty());
+        assert!(matching.iter().all(|chain_id| canary.routes(*chain_id)));
+        assert!(!other.iter().any(|chain_id| canary.routes(*chain_id)));
+        let canary = shards[1].canary.as_ref().unwrap();
+        assert_eq!(canary.address(), "host2-canary:9032");
+        let count = (0..1000)
+            .filter(|index| canary.routes(ChainId::root(*index)))
+            .count();
+        assert!(
+            (20..=80).contains(&count),
+            "{count} chains routed to the canary"
+        );
+    }
+
+    #[test]
+    fn test_mutual_tls() {
+        let toml_str = r#"
+            server_config_path = "server.json"
+            host = "host"
+            port = 9000
+            internal_host = "internal_host"
+            internal_port = 10000
+            metrics_host = "metrics_host"
+            metrics_port = 5000
+            external_protocol = { Grpc = "Tls" }
+            internal_protocol = { Grpc = "ClearText" }
+
+            [mutual_tls]
+            ca_certificate = "ca.pem"
+            certificate = "validator.pem"
+            private_key = "validator.key"
+            domain_name = "internal.validator"
+
+            [[shards]]
+            host = "host1"
+            port = 9001
+            metrics_host = "metrics_host1"
+        "#;
+        let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
+        let config = make_server_config(&mut Box::<dyn CryptoRng>::from(Some(0)), options);
+        let network = &config.internal_network;
+        let tls = network.mutual_tls.as_ref().unwrap();
+        assert_eq!(tls.ca_certificate, PathBuf::from("ca.pem"));
+        assert_eq!(tls.domain_name, "internal.validator");
+        assert_eq!(network.proxy_address(), "https://internal_host:10000");
+        assert_eq!(
+            network.grpc_uri(network.shards[0].address()),
+            "https://host1:9001"
+        );
+    }
+
+    #[test]
+    fn test_unix_socket() {
+        let toml_str = r#"
+            server_config_path = "server.json"
+            host = "host"
+            port = 9000
+            internal_host = "internal_host"
+            internal_port = 10000
+            metrics_host = "metrics_host"
+            metrics_port = 5000
+            external_protocol = { Grpc = "Tls" }
+            internal_protocol = { Grpc = "ClearText" }
+
+            [[shards]]
+            host = "host1"
+            port = 9001
+            metrics_host = "metrics_host1"
+            unix_socket = "/run/linera/shard1.sock"
+
+            [[shards.replicas]]
+            host = "host1-replica"
+            port = 9011
+
+            [[shards]]
+            host = "host2"
+            port = 9002
+            metrics_host = "metrics_host2"
+        "#;
+        let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
+        let config = make_server_config(&mut Box::<dyn CryptoRng>::from(Some(0)), options);
+        let network = &config.internal_network;
+        assert_eq!(
+            network.shard_grpc_uri(&network.shards[0]),
+            "unix:/run/linera/shard1.sock"
+        );
+        assert_eq!(
+            network.shard_grpc_uri(&network.shards[1]),
+            "http://host2:9002"
+        );
+        let endpoint = network.shards[0].closest_endpoint(None, None);
+        assert_eq!(
+            network.endpoint_grpc_uri(&endpoint),
+            "unix:/run/linera/shard1.sock"
+        );
+    }
+
+    #[test]
+    fn test_shard_moves() {
+        let shard = |port| ShardConfig {
+            host: "host".into(),
+            port,
+            metrics_host: "host".into(),
+            metrics_port: None,
+            region: None,
+            zone: None,
+            replicas: Vec::new(),
+            read_replicas: Vec::new(),
+            shadow: None,
+            canary: None,
+            unix_socket: None,
+        };
+        let network = |shards| ValidatorInternalNetworkConfig {
+            protocol: NetworkProtocol::Grpc(TlsConfig::ClearText),
+            shards,
+            host: "proxy".into(),
+            port: 10000,
+            metrics_host: "proxy".into(),
+            metrics_port: 5000,
+            region: None,
+            zone: None,
+            mutual_tls: None,
+        };
+        let old_config = network(vec![shard(9001), shard(9002)]);
+        let new_config = network(vec![shard(9001), shard(9002), shard(9003)]);
+        let chains = (0..100).map(ChainId::root).collect::<Vec<_>>();
+
+        assert!(old_config
+            .shard_moves(&old_config, chains.iter().copied())
+            .is_empty());
+        let moves = old_config.shard_moves(&new_config, chains.iter().copied());
+        assert!(!moves.is_empty());
+        for chain_id in chains {
+            let old_shard = old_config.get_shard_id(chain_id);
+            let new_shard = new_config.get_shard_id(chain_id);
+            let shard_move = moves
+                .iter()
+                .find(|shard_move| shard_move.chain_id == chain_id);
+            if old_shard == new_shard {
+                assert!(shard_move.is_none());
+            } else {
+                assert_eq!(
+                    shard_move,
+                    Some(&ShardMove {
+                        chain_id,
+                        old_shard,
+                        new_shard
+                    })
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,308 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A JSON-RPC interface to the node service over a Unix socket.
+//!
+//! Desktop wallets and browser extension hosts can drive the node service through this socket
+//! without linking the Linera libraries. Every line sent to the socket is a JSON-RPC 2.0
+//! request, answered with one line holding the response. A connection must first call
+//! `authenticate` with the token of the service. The methods are:
+//!
+//! * `authenticate {token}`;
+//! * `chains`: the chains of the wallet and the default chain;
+//! * `balance {chainId, owner?}`: the balance of the chain, or of one of its owners;
+//! * `transfer {chainId, owner?, recipient, amount}`: transfers tokens in a block signed with
+//!   the wallet's key, and returns the hash of its certificate;
+//! * `graphql {query, variables?}`: executes any query or mutation of the node service.
+
+use std::{
+    fs::Permissions,
+    io,
+    os::unix::fs::PermissionsExt as _,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_graphql::{Executor, Request, Variables};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::{UnixListener, UnixStream},
+};
+use tracing::{debug, info};
+
+#[cfg(test)]
+#[path = "unit_tests/socket_api.rs"]
+mod tests;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const QUERY_FAILED: i64 = -32000;
+const UNAUTHORIZED: i64 = -32001;
+
+const CHAINS_QUERY: &str = "query { chains { list default } }";
+
+const BALANCE_QUERY: &str = "query($chainId: ChainId!) { \
+    chain(chainId: $chainId) { executionState { system { balance } } } }";
+
+const OWNER_BALANCE_QUERY: &str = "query($chainId: ChainId!, $owner: Owner!) { \
+    chain(chainId: $chainId) { executionState { system { \
+    balances { entry(key: $owner) { value } } } } } }";
+
+const TRANSFER_MUTATION: &str = "mutation(\
+    $chainId: ChainId!, $owner: Owner, $recipient: Recipient!, $amount: Amount!) { \
+    transfer(chainId: $chainId, owner: $owner, recipient: $recipient, amount: $amount) }";
+
+/// The JSON-RPC interface of a node service, served on a Unix socket.
+pub struct SocketApi<E> {
+    path: PathBuf,
+    token: Arc<str>,
+    executor: E,
+}
+
+impl<E: Executor> SocketApi<E> {
+    /// Creates an interface executing the requests with `executor`, e.g. the GraphQL schema of
+    /// the node service, for the clients authenticated with `token`.
+    pub fn new(path: PathBuf, token: String, executor: E) -> Self {
+        Self {
+            path,
+            token: token.into(),
+            executor,
+        }
+    }
+
+    /// Runs the interface until an error occurs while accepting a connection.
+    pub async fn run(self) -> anyhow::Result<()> {
+        // A socket file left by a previous run would prevent binding.
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        let listener = UnixListener::bind(&self.path)?;
+        std::fs::set_permissions(&self.path, Permissions::from_mode(0o600))?;
+        info!("JSON-RPC socket: {}", self.path.display());
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let connection = Connection {
+                token: self.token.clone(),
+                executor: self.executor.clone(),
+                authenticated: false,
+            };
+            tokio::spawn(connection.serve(stream));
+        }
+    }
+}
+
+/// Generates a new token, and writes it to a file next to the socket that only the current
+/// user can read. Returns the token.
+pub fn create_token_file(socket_path: &Path) -> io::Result<String> {
+    let token = hex::encode(rand::random::<[u8; 32]>());
+    let token_path = token_path(socket_path);
+    std::fs::write(&token_path, &token)?;
+    std::fs::set_permissions(&token_path, Permissions::from_mode(0o600))?;
+    info!("JSON-RPC token written to {}", token_path.display());
+    Ok(token)
+}
+
+/// Returns the path of the file holding the generated token of a socket.
+pub fn token_path(socket_path: &Path) -> PathBuf {
+    let mut path = socket_path.as_os_str().to_owned();
+    path.push(".token");
+    path.into()
+}
+
+/// A JSON-RPC request.
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A JSON-RPC response, holding either a result or an error.
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthenticateParams {
+    token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BalanceParams {
+    chain_id: Value,
+    owner: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlParams {
+    query: String,
+    #[serde(default)]
+    variables: Value,
+}
+
+/// A connection to the socket.
+struct Connection<E> {
+    token: Arc<str>,
+    executor: E,
+    authenticated: bool,
+}
+
+impl<E: Executor> Connection<E> {
+    /// Answers the requests of the connection until it is closed.
+    async fn serve(mut self, stream: UnixStream) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = self.handle(&line).await;
+            let mut bytes = serde_json::to_vec(&response).expect("responses can be serialized");
+            bytes.push(b'\n');
+            if writer.write_all(&bytes).await.is_err() {
+                break;
+            }
+        }
+        debug!("JSON-RPC connection closed");
+    }
+
+    /// Answers one line received on the socket.
+    async fn handle(&mut self, line: &str) -> RpcResponse {
+        let (id, outcome) = match serde_json::from_str::<RpcRequest>(line) {
+            Ok(request) => (request.id.clone(), self.dispatch(request).await),
+            Err(error) => (
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, error.to_string())),
+            ),
+        };
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+
+    async fn dispatch(&mut self, request: RpcRequest) -> Result<Value, RpcError> {
+        if request.jsonrpc != "2.0" {
+            return Err(RpcError::new(
+                INVALID_REQUEST,
+                "only JSON-RPC 2.0 is supported",
+            ));
+        }
+        if request.method == "authenticate" {
+            let AuthenticateParams { token } = parse_params(request.params)?;
+            if token != *self.token {
+                return Err(RpcError::new(UNAUTHORIZED, "invalid token"));
+            }
+            self.authenticated = true;
+            return Ok(Value::Bool(true));
+        }
+        if !self.authenticated {
+            return Err(RpcError::new(
+                UNAUTHORIZED,
+                "the connection must call `authenticate` first",
+            ));
+        }
+        match request.method.as_str() {
+            "chains" => {
+                let data = self.execute(CHAINS_QUERY, Value::Null).await?;
+                Ok(select(data, "/chains"))
+            }
+            "balance" => {
+                let BalanceParams { chain_id, owner } = parse_params(request.params)?;
+                match owner {
+                    None => {
+                        let variables = serde_json::json!({ "chainId": chain_id });
+                        let data = self.execute(BALANCE_QUERY, variables).await?;
+                        Ok(select(data, "/chain/executionState/system/balance"))
+                    }
+                    Some(owner) => {
+                        let variables = serde_json::json!({ "chainId": chain_id, "owner": owner });
+                        let data = self.execute(OWNER_BALANCE_QUERY, variables).await?;
+                        Ok(select(
+                            data,
+                            "/chain/executionState/system/balances/entry/value",
+                        ))
+                    }
+                }
+            }
+            "transfer" => {
+                if !request.params.is_object() {
+                    return Err(RpcError::new(INVALID_PARAMS, "expected named parameters"));
+                }
+                let data = self.execute(TRANSFER_MUTATION, request.params).await?;
+                Ok(select(data, "/transfer"))
+            }
+            "graphql" => {
+                let GraphQlParams { query, variables } = parse_params(request.params)?;
+                self.execute(&query, variables).await
+            }
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method `{method}`"),
+            )),
+        }
+    }
+
+    /// Executes a GraphQL request, and returns its data.
+    async fn execute(&self, query: &str, variables: Value) -> Result<Value, RpcError> {
+        let request = Request::new(query).variables(Variables::from_json(variables));
+        let response = self.executor.execute(request).await;
+        if !response.errors.is_empty() {
+            let messages = response
+                .errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>();
+            return Err(RpcError::new(QUERY_FAILED, messages.join("; ")));
+        }
+        response
+            .data
+            .into_json()
+            .map_err(|error| RpcError::new(INTERNAL_ERROR, error.to_string()))
+    }
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|error| RpcError::new(INVALID_PARAMS, error.to_string()))
+}
+
+/// Returns the value at the given JSON pointer, or `null`.
+fn select(data: Value, pointer: &str) -> Value {
+    data.pointer(pointer).cloned().unwrap_or(Value::Null)
+}
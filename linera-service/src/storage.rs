@@ -5,6 +5,7 @@ use std::str::FromStr;
 
 use anyhow::{bail, format_err};
 use async_trait::async_trait;
+use linera_base::{data_types::BlockHeight, identifiers::ChainId};
 use linera_execution::WasmRuntime;
 use linera_storage::{MemoryStorage, ServiceStorage, Storage};
 use linera_storage_service::{client::ServiceStoreClient, common::ServiceStoreConfig};
@@ -29,9 +30,15 @@ use {
 };
 #[cfg(feature = "rocksdb")]
 use {
-    linera_storage::RocksDbStorage,
-    linera_views::rocks_db::{RocksDbStore, RocksDbStoreConfig},
-    std::path::PathBuf,
+    linera_storage::{value_compression_config, RocksDbStorage},
+    linera_views::{
+        group_commit::GroupCommitConfig,
+        rocks_db::{
+            RocksDbCompactionStyle, RocksDbCompression, RocksDbStore, RocksDbStoreConfig,
+            RocksDbTuningConfig,
+        },
+    },
+    std::{path::PathBuf, time::Duration},
 };
 
 use crate::config::GenesisConfig;
@@ -299,6 +306,7 @@ impl StorageConfigNamespace {
                 let config = RocksDbStoreConfig {
                     path_buf,
                     common_config,
+                    tuning: RocksDbTuningConfig::default(),
                 };
                 Ok(StoreConfig::RocksDb(config, namespace))
             }
@@ -350,7 +358,72 @@ impl std::fmt::Display for StorageConfigNamespace {
     }
 }
 
+/// The tuning options of RocksDB, ignored by the other storages.
+#[cfg(feature = "rocksdb")]
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct RocksDbTuningOptions {
+    /// The size in bytes of the block cache of RocksDB.
+    #[arg(long)]
+    pub rocksdb_block_cache_size: Option<usize>,
+
+    /// The size in bytes of the memtables of RocksDB, before they are written to disk.
+    #[arg(long)]
+    pub rocksdb_write_buffer_size: Option<usize>,
+
+    /// The compaction style of RocksDB: `level`, `universal` or `fifo`.
+    #[arg(long)]
+    pub rocksdb_compaction_style: Option<RocksDbCompactionStyle>,
+
+    /// The compression of the data written to disk by RocksDB: `none`, `snappy`, `zlib`,
+    /// `lz4` or `zstd`.
+    #[arg(long)]
+    pub rocksdb_compression: Option<RocksDbCompression>,
+
+    /// Compress the certificates, the certificate values and the blobs with Zstandard at
+    /// this level, e.g. 3, before writing them to RocksDB. The values written earlier must
+    /// first be converted with `linera-db compress_values`, or the storage fails to open.
+    #[arg(long)]
+    pub rocksdb_value_compression_level: Option<i32>,
+
+    /// Commit the write batches of concurrent writers together. When several of them are
+    /// queued, wait this many microseconds for more before committing. A batch written while
+    /// no other one is queued is committed at once.
+    #[arg(long)]
+    pub rocksdb_group_commit_delay_us: Option<u64>,
+}
+
+#[cfg(feature = "rocksdb")]
+impl From<RocksDbTuningOptions> for RocksDbTuningConfig {
+    fn from(options: RocksDbTuningOptions) -> Self {
+        RocksDbTuningConfig {
+            block_cache_size: options.rocksdb_block_cache_size,
+            write_buffer_size: options.rocksdb_write_buffer_size,
+            compaction_style: options.rocksdb_compaction_style,
+            compression: options.rocksdb_compression,
+            value_compression: options
+                .rocksdb_value_compression_level
+                .map(value_compression_config)
+                .unwrap_or_default(),
+            group_commit: options
+                .rocksdb_group_commit_delay_us
+                .map(|delay| GroupCommitConfig {
+                    max_delay: Duration::from_micros(delay),
+                    ..GroupCommitConfig::default()
+                }),
+        }
+    }
+}
+
 impl StoreConfig {
+    /// Sets the tuning options of RocksDB, if this is a RocksDB storage
+    #[cfg(feature = "rocksdb")]
+    pub fn with_rocks_db_tuning(mut self, tuning: RocksDbTuningConfig) -> Self {
+        if let StoreConfig::RocksDb(config, _namespace) = &mut self {
+            config.tuning = tuning;
+        }
+        self
+    }
+
     /// Deletes all the entries in the database
     pub async fn delete_all(self) -> Result<(), ViewError> {
         match self {
@@ -463,6 +536,66 @@ impl StoreConfig {
         }
     }
 
+    /// Compresses the certificates, the certificate values and the blobs that were written
+    /// before value compression was enabled, and returns the number of rewritten values. This
+    /// must be done before the storage is used with value compression. Running it again
+    /// resumes an interrupted conversion, or does nothing once it is complete
+    #[allow(unused_variables)]
+    pub async fn compress_values(self, level: i32) -> Result<usize, ViewError> {
+        match self {
+            #[cfg(feature = "rocksdb")]
+            StoreConfig::RocksDb(mut config, namespace) => {
+                let compression = value_compression_config(level);
+                config.tuning.value_compression = compression.clone();
+                let store = RocksDbStore::connect_to_rewrite_values(&config, &namespace).await?;
+                let mut count = 0;
+                for (key_prefix, _) in &compression.key_prefixes {
+                    count += store.rewrite_values(key_prefix).await?;
+                }
+                Ok(count)
+            }
+            _ => Err(ViewError::ContextError {
+                backend: "storage".to_string(),
+                error: "value compression is only supported for RocksDB".to_string(),
+            }),
+        }
+    }
+
+    /// Replaces the state of a chain with its latest checkpoint, and returns the next block
+    /// height of that checkpoint, if there was one. The validator must be stopped.
+    pub async fn restore_chain_checkpoint(
+        self,
+        chain_id: ChainId,
+    ) -> Result<Option<BlockHeight>, ViewError> {
+        let wasm_runtime = None;
+        match self {
+            StoreConfig::Memory(_, _) => Err(ViewError::ContextError {
+                backend: "memory".to_string(),
+                error: "restore_chain_checkpoint does not make sense for memory storage"
+                    .to_string(),
+            }),
+            StoreConfig::Service(config, namespace) => {
+                let storage = ServiceStorage::new(config, &namespace, wasm_runtime).await?;
+                storage.restore_chain_checkpoint(chain_id).await
+            }
+            #[cfg(feature = "rocksdb")]
+            StoreConfig::RocksDb(config, namespace) => {
+                let storage = RocksDbStorage::new(config, &namespace, wasm_runtime).await?;
+                storage.restore_chain_checkpoint(chain_id).await
+            }
+            #[cfg(feature = "dynamodb")]
+            StoreConfig::DynamoDb(config, namespace) => {
+                let storage = DynamoDbStorage::new(config, &namespace, wasm_runtime).await?;
+                storage.restore_chain_checkpoint(chain_id).await
+            }
+            #[cfg(feature = "scylladb")]
+            StoreConfig::ScyllaDb(config, namespace) => {
+                let storage = ScyllaDbStorage::new(config, &namespace, wasm_runtime).await?;
+                storage.restore_chain_checkpoint(chain_id).await
+            }
+        }
+    }
+
     /// Lists all the namespaces of the storage
     pub async fn list_all(self) -> Result<Vec<String>, ViewError> {
         match self {
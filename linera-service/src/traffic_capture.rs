@@ -0,0 +1,393 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Capture of the block proposals and certificates received by a proxy, to replay them
+//! against another validator, e.g. to reproduce the load of a production validator on a
+//! staging one.
+//!
+//! A capture is a sequence of entries, each serialized with BCS and prefixed with its length
+//! as a little-endian `u32`, like the chain archives. It starts with a header, and continues
+//! with the sampled requests, each with the time at which the proxy received it. The requests
+//! are written by a separate thread: if it falls behind, requests are left out of the capture
+//! rather than delaying the proxy. The capture stops when the file would exceed its maximal
+//! size.
+
+use std::{
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread,
+};
+
+use linera_base::time::{Duration, Instant};
+use linera_chain::data_types::BlockProposal;
+use linera_core::node::{CrossChainMessageDelivery, NodeError, ValidatorNode};
+use linera_rpc::{HandleCertificateRequest, HandleLiteCertRequest, RpcMessage};
+use rand::Rng as _;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::task::JoinSet;
+use tracing::{debug, error, info, warn};
+
+#[cfg(test)]
+#[path = "unit_tests/traffic_capture.rs"]
+mod tests;
+
+/// The version of the capture format written by this crate.
+pub const CAPTURE_VERSION: u32 = 1;
+
+/// The maximal size of a capture entry, to avoid allocating arbitrary amounts of memory when
+/// reading a corrupted capture.
+const MAX_ENTRY_SIZE: u32 = 1 << 30;
+
+/// The number of requests waiting to be written, beyond which new requests are left out of
+/// the capture.
+const CAPTURE_QUEUE_SIZE: usize = 1_000;
+
+/// The options of a proxy to capture its traffic.
+#[derive(Clone, Debug, clap::Args)]
+pub struct TrafficCaptureConfig {
+    /// A file to write a sample of the block proposals and certificates received by the
+    /// proxy to, so that they can be sent to another validator with `linera-server
+    /// replay-traffic`
+    #[arg(long = "capture-traffic")]
+    pub path: Option<PathBuf>,
+
+    /// The percentage of the block proposals and certificates to capture.
+    #[arg(
+        long = "capture-sample-percent",
+        default_value = "100",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub sample_percent: u8,
+
+    /// The maximal size of the capture file, in bytes. The capture stops when it is reached.
+    #[arg(long = "capture-max-bytes", default_value = "1073741824")]
+    pub max_bytes: u64,
+}
+
+impl TrafficCaptureConfig {
+    /// Starts capturing the traffic to the configured file, if any.
+    pub fn start(&self) -> Result<Option<Arc<TrafficCapture>>, CaptureError> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        info!("Capturing the traffic of the proxy to {}", path.display());
+        let writer = BufWriter::new(fs_err::File::create(path)?);
+        let capture = TrafficCapture::new(writer, self.sample_percent, self.max_bytes)?;
+        Ok(Some(Arc::new(capture)))
+    }
+}
+
+/// A request captured by a proxy.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CapturedMessage {
+    BlockProposal(Box<BlockProposal>),
+    LiteCertificate(Box<HandleLiteCertRequest<'static>>),
+    Certificate(Box<HandleCertificateRequest>),
+}
+
+/// A request captured by a proxy, with the time at which it was received.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CapturedRequest {
+    /// The time between the start of the capture and the reception of the request, in
+    /// microseconds.
+    pub offset_micros: u64,
+    pub message: CapturedMessage,
+}
+
+/// An entry of a capture.
+#[derive(Debug, Serialize, Deserialize)]
+enum CaptureEntry {
+    /// The first entry of a capture.
+    Header { version: u32 },
+    /// The next captured request.
+    Request(CapturedRequest),
+}
+
+/// An error reading or writing a capture.
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("BCS (de)serialization error: {0}")]
+    Bcs(#[from] bcs::Error),
+    #[error("Capture entry of {0} bytes is too large")]
+    EntryTooLarge(u64),
+    #[error("Unsupported capture version {0}")]
+    UnsupportedVersion(u32),
+    #[error("The capture does not start with a header")]
+    MissingHeader,
+    #[error("The capture ends with a truncated entry")]
+    Truncated,
+}
+
+/// Writes a sample of the requests received by a proxy to a capture.
+pub struct TrafficCapture {
+    sample_percent: u8,
+    start: Instant,
+    sender: SyncSender<CapturedRequest>,
+}
+
+impl TrafficCapture {
+    /// Starts a capture keeping `sample_percent` percent of the requests, and writing at most
+    /// `max_bytes` bytes.
+    pub fn new(
+        mut writer: impl Write + Send + 'static,
+        sample_percent: u8,
+        max_bytes: u64,
+    ) -> Result<Self, CaptureError> {
+        let header = CaptureEntry::Header {
+            version: CAPTURE_VERSION,
+        };
+        let bytes = encode_entry(&header)?;
+        writer.write_all(&bytes)?;
+        writer.flush()?;
+        let (sender, receiver) = mpsc::sync_channel(CAPTURE_QUEUE_SIZE);
+        let written = bytes.len() as u64;
+        thread::spawn(move || write_requests(writer, receiver, written, max_bytes));
+        Ok(TrafficCapture {
+            sample_percent,
+            start: Instant::now(),
+            sender,
+        })
+    }
+
+    /// Returns whether the next request is sampled to be captured.
+    pub fn sample(&self) -> bool {
+        rand::thread_rng().gen_range(0..100) < self.sample_percent
+    }
+
+    /// Records a copy of a message received over the simple transport, if it is a block
+    /// proposal or a certificate and if it is sampled.
+    pub fn capture(&self, message: &RpcMessage) {
+        let message = match message {
+            RpcMessage::BlockProposal(proposal) if self.sample() => {
+                CapturedMessage::BlockProposal(proposal.clone())
+            }
+            RpcMessage::LiteCertificate(request) if self.sample() => {
+                CapturedMessage::LiteCertificate(request.clone())
+            }
+            RpcMessage::Certificate(request) if self.sample() => {
+                CapturedMessage::Certificate(request.clone())
+            }
+            _ => return,
+        };
+        self.record(message);
+    }
+
+    /// Records a request that was sampled, unless the capture has stopped or is falling
+    /// behind.
+    pub fn record(&self, message: CapturedMessage) {
+        let request = CapturedRequest {
+            offset_micros: self.start.elapsed().as_micros() as u64,
+            message,
+        };
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(request) {
+            debug!("The traffic capture is falling behind: leaving a request out");
+        }
+    }
+}
+
+/// Writes the captured requests until the capture would exceed `max_bytes`.
+fn write_requests(
+    mut writer: impl Write,
+    receiver: Receiver<CapturedRequest>,
+    mut written: u64,
+    max_bytes: u64,
+) {
+    for request in receiver {
+        let bytes = match encode_entry(&CaptureEntry::Request(request)) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                warn!(%error, "Could not serialize a captured request");
+                continue;
+            }
+        };
+        if written + bytes.len() as u64 > max_bytes {
+            info!("The traffic capture reached its maximal size of {max_bytes} bytes");
+            break;
+        }
+        if let Err(error) = writer.write_all(&bytes).and_then(|()| writer.flush()) {
+            error!(%error, "Could not write the traffic capture");
+            break;
+        }
+        written += bytes.len() as u64;
+    }
+}
+
+/// Reads the requests of a capture, in order.
+pub struct CaptureReader<R> {
+    reader: R,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Reads the header of a capture.
+    pub fn new(mut reader: R) -> Result<Self, CaptureError> {
+        match read_entry(&mut reader)? {
+            Some(CaptureEntry::Header { version }) if version == CAPTURE_VERSION => {
+                Ok(CaptureReader { reader })
+            }
+            Some(CaptureEntry::Header { version }) => {
+                Err(CaptureError::UnsupportedVersion(version))
+            }
+            Some(CaptureEntry::Request(_)) | None => Err(CaptureError::MissingHeader),
+        }
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CapturedRequest, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match read_entry(&mut self.reader) {
+            Ok(Some(CaptureEntry::Request(request))) => Some(Ok(request)),
+            Ok(Some(CaptureEntry::Header { .. })) => Some(Err(CaptureError::MissingHeader)),
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// The outcome of replaying a capture.
+#[derive(Debug, Default, Serialize)]
+pub struct TrafficReplayReport {
+    /// The number of requests sent to the validator.
+    pub sent: u64,
+    /// The number of requests that the validator handled successfully.
+    pub succeeded: u64,
+    /// The number of requests that the validator rejected, or that failed to reach it.
+    pub failed: u64,
+    /// The time between the start of the capture and its last request, in milliseconds.
+    pub captured_duration_ms: u64,
+    /// The time the replay took, in milliseconds.
+    pub replay_duration_ms: u64,
+}
+
+/// Sends the requests of a capture to a validator, `speed` times faster than they were
+/// received, with at most `max_in_flight` of them waiting for a response at the same time.
+///
+/// A capture ending with a truncated request, e.g. because the proxy was stopped while writing
+/// it, is replayed up to that request.
+pub async fn replay_capture<R, N>(
+    reader: CaptureReader<R>,
+    node: N,
+    speed: f64,
+    max_in_flight: usize,
+) -> Result<TrafficReplayReport, CaptureError>
+where
+    R: Read,
+    N: ValidatorNode + Clone + Send + 'static,
+{
+    let start = tokio::time::Instant::now();
+    let mut report = TrafficReplayReport::default();
+    let mut in_flight = JoinSet::new();
+    for request in reader {
+        let request = match request {
+            Ok(request) => request,
+            Err(CaptureError::Truncated) => {
+                warn!("The capture ends with a truncated request");
+                break;
+            }
+            Err(error) => return Err(error),
+        };
+        let offset = Duration::from_micros(request.offset_micros);
+        tokio::time::sleep_until(start + offset.div_f64(speed)).await;
+        while in_flight.len() >= max_in_flight.max(1) {
+            if let Some(result) = in_flight.join_next().await {
+                report.record(result);
+            }
+        }
+        let mut node = node.clone();
+        in_flight.spawn(async move { send_request(&mut node, request.message).await });
+        report.sent += 1;
+        report.captured_duration_ms = offset.as_millis() as u64;
+    }
+    while let Some(result) = in_flight.join_next().await {
+        report.record(result);
+    }
+    report.replay_duration_ms = start.elapsed().as_millis() as u64;
+    Ok(report)
+}
+
+impl TrafficReplayReport {
+    /// Counts the outcome of a replayed request.
+    fn record(&mut self, result: Result<Result<(), NodeError>, tokio::task::JoinError>) {
+        match result {
+            Ok(Ok(())) => self.succeeded += 1,
+            Ok(Err(error)) => {
+                debug!(%error, "The validator rejected a replayed request");
+                self.failed += 1;
+            }
+            Err(error) => {
+                warn!(%error, "Could not replay a request");
+                self.failed += 1;
+            }
+        }
+    }
+}
+
+/// Sends a captured request to a validator.
+async fn send_request(
+    node: &mut impl ValidatorNode,
+    message: CapturedMessage,
+) -> Result<(), NodeError> {
+    match message {
+        CapturedMessage::BlockProposal(proposal) => {
+            node.handle_block_proposal(*proposal).await?;
+        }
+        CapturedMessage::LiteCertificate(request) => {
+            let delivery = CrossChainMessageDelivery::new(request.wait_for_outgoing_messages);
+            node.handle_lite_certificate(request.certificate, delivery)
+                .await?;
+        }
+        CapturedMessage::Certificate(request) => {
+            let delivery = CrossChainMessageDelivery::new(request.wait_for_outgoing_messages);
+            node.handle_certificate(
+                request.certificate,
+                request.hashed_certificate_values,
+                delivery,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the bytes of an entry, prefixed with its length.
+fn encode_entry(entry: &CaptureEntry) -> Result<Vec<u8>, CaptureError> {
+    let bytes = bcs::to_bytes(entry)?;
+    let length = u32::try_from(bytes.len())
+        .ok()
+        .filter(|length| *length <= MAX_ENTRY_SIZE)
+        .ok_or(CaptureError::EntryTooLarge(bytes.len() as u64))?;
+    let mut encoded = length.to_le_bytes().to_vec();
+    encoded.extend(bytes);
+    Ok(encoded)
+}
+
+/// Reads the next entry, or returns `None` at the end of the capture.
+fn read_entry(reader: &mut impl Read) -> Result<Option<CaptureEntry>, CaptureError> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => (),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    let length = u32::from_le_bytes(length);
+    if length > MAX_ENTRY_SIZE {
+        return Err(CaptureError::EntryTooLarge(length.into()));
+    }
+    let mut bytes = vec![0; length as usize];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => (),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+            return Err(CaptureError::Truncated)
+        }
+        Err(error) => return Err(error.into()),
+    }
+    Ok(Some(bcs::from_bytes(&bytes)?))
+}
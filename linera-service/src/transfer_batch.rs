@@ -0,0 +1,189 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Batches of transfers read from a CSV file, e.g. to pay out faucet or airdrop recipients.
+//!
+//! Each line of the file is `RECIPIENT,AMOUNT`, where the recipient is an account written as
+//! `CHAIN-ID` or `CHAIN-ID:OWNER`. Empty lines, lines starting with `#` and a first line
+//! `recipient,amount` are ignored. The transfers are split into as few blocks as the limits of
+//! the sending chain allow, and the outcome of each transfer is written to a report, also in
+//! CSV.
+
+use std::{io, ops::Range};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, ParseAmountError},
+    identifiers::{Account, Owner},
+};
+use linera_execution::{
+    system::{Recipient, UserData},
+    BlockLimits, Operation, SystemOperation,
+};
+use thiserror::Error;
+
+#[cfg(test)]
+#[path = "unit_tests/transfer_batch.rs"]
+mod tests;
+
+/// A transfer read from a line of a batch file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchTransfer {
+    /// The line of the file, starting at 1.
+    pub line: usize,
+    pub recipient: Account,
+    pub amount: Amount,
+}
+
+/// An error reading a batch file.
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("Line {line}: expecting `RECIPIENT,AMOUNT`")]
+    InvalidFormat { line: usize },
+    #[error("Line {line}: invalid recipient {recipient:?}: {error}")]
+    InvalidRecipient {
+        line: usize,
+        recipient: String,
+        error: anyhow::Error,
+    },
+    #[error("Line {line}: invalid amount {amount:?}: {error}")]
+    InvalidAmount {
+        line: usize,
+        amount: String,
+        error: ParseAmountError,
+    },
+    #[error("The batch file does not contain any transfer")]
+    Empty,
+}
+
+/// What happened to a transfer of a batch.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TransferStatus {
+    /// The transfer was confirmed in the block with this certificate.
+    Confirmed(CryptoHash),
+    /// The block with the transfer could not be confirmed.
+    Failed(String),
+    /// The transfer was not submitted, because an earlier block failed.
+    NotSubmitted,
+}
+
+/// Parses the transfers of a batch file. All lines are checked before any transfer is made.
+pub fn parse_transfers(input: &str) -> Result<Vec<BatchTransfer>, BatchError> {
+    let mut transfers = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || (index == 0 && line.eq_ignore_ascii_case("recipient,amount"))
+        {
+            continue;
+        }
+        let Some((recipient, amount)) = line.split_once(',') else {
+            return Err(BatchError::InvalidFormat { line: line_number });
+        };
+        let (recipient, amount) = (recipient.trim(), amount.trim());
+        let recipient_account =
+            recipient
+                .parse()
+                .map_err(|error| BatchError::InvalidRecipient {
+                    line: line_number,
+                    recipient: recipient.to_string(),
+                    error,
+                })?;
+        let amount_value = amount.parse().map_err(|error| BatchError::InvalidAmount {
+            line: line_number,
+            amount: amount.to_string(),
+            error,
+        })?;
+        transfers.push(BatchTransfer {
+            line: line_number,
+            recipient: recipient_account,
+            amount: amount_value,
+        });
+    }
+    if transfers.is_empty() {
+        return Err(BatchError::Empty);
+    }
+    Ok(transfers)
+}
+
+/// Returns the operations making the transfers from the account of `owner`, or of the chain.
+pub fn transfer_operations(owner: Option<Owner>, transfers: &[BatchTransfer]) -> Vec<Operation> {
+    transfers
+        .iter()
+        .map(|transfer| {
+            Operation::System(SystemOperation::Transfer {
+                owner,
+                recipient: Recipient::Account(transfer.recipient),
+                amount: transfer.amount,
+                user_data: UserData::default(),
+            })
+        })
+        .collect()
+}
+
+/// Splits the operations into consecutive blocks within the given limits. Only half of the
+/// maximum block size is used by the operations, leaving room for the rest of the block, e.g.
+/// its incoming messages.
+pub fn block_ranges(
+    operations: &[Operation],
+    limits: &BlockLimits,
+) -> Result<Vec<Range<usize>>, bcs::Error> {
+    let max_operations = usize::try_from(limits.maximum_operations)
+        .unwrap_or(usize::MAX)
+        .max(1);
+    let max_size = usize::try_from(limits.maximum_size / 2).unwrap_or(usize::MAX);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+    for (index, operation) in operations.iter().enumerate() {
+        let operation_size = bcs::serialized_size(operation)?;
+        if index > start && (index - start >= max_operations || size + operation_size > max_size) {
+            ranges.push(start..index);
+            start = index;
+            size = 0;
+        }
+        size += operation_size;
+    }
+    if start < operations.len() {
+        ranges.push(start..operations.len());
+    }
+    Ok(ranges)
+}
+
+/// Writes the status of each transfer as CSV, with the columns
+/// `line,recipient,amount,status,certificate`.
+pub fn write_report(
+    mut writer: impl io::Write,
+    transfers: &[BatchTransfer],
+    statuses: &[TransferStatus],
+) -> io::Result<()> {
+    writeln!(writer, "line,recipient,amount,status,certificate")?;
+    for (transfer, status) in transfers.iter().zip(statuses) {
+        let (status, certificate) = match status {
+            TransferStatus::Confirmed(hash) => ("confirmed".to_string(), hash.to_string()),
+            TransferStatus::Failed(error) => (format!("failed: {}", error), String::new()),
+            TransferStatus::NotSubmitted => ("not submitted".to_string(), String::new()),
+        };
+        writeln!(
+            writer,
+            "{},{},{},{},{}",
+            transfer.line,
+            transfer.recipient,
+            transfer.amount,
+            csv_field(&status),
+            certificate
+        )?;
+    }
+    writer.flush()
+}
+
+/// Quotes a field if it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
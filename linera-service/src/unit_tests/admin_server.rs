@@ -0,0 +1,142 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, Request, StatusCode},
+};
+use futures::{channel::mpsc, StreamExt as _};
+use linera_base::identifiers::ChainId;
+use linera_core::{load_monitor::LoadReport, worker::AdminCommand};
+use tower::ServiceExt as _;
+
+use super::{
+    cancel_proposal, get_load, list_equivocations, list_proposals, redeliver_messages, AdminApi,
+    AdminState, ScaleOutThresholds, ShardState, Shards,
+};
+
+fn shards() -> Shards {
+    Arc::new((0..2).map(|shard| (shard, Default::default())).collect())
+}
+
+#[tokio::test]
+async fn test_list_idle_shards() {
+    let proposals = list_proposals(State(shards())).await;
+    assert!(proposals.0.is_empty());
+}
+
+#[tokio::test]
+async fn test_cancel_unknown_proposal() {
+    let result = cancel_proposal(State(shards()), Path((1, 0))).await;
+    assert_eq!(result.unwrap_err(), StatusCode::NOT_FOUND);
+    let result = cancel_proposal(State(shards()), Path((2, 0))).await;
+    assert_eq!(result.unwrap_err(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_list_no_equivocations() {
+    let evidence = list_equivocations(State(shards())).await;
+    assert!(evidence.0.is_empty());
+}
+
+#[tokio::test]
+async fn test_idle_shards_are_not_overloaded() {
+    let state = AdminState {
+        shards: shards(),
+        routing: None,
+        scale_out: ScaleOutThresholds {
+            queue_depth: Some(1),
+            ..ScaleOutThresholds::default()
+        },
+    };
+    let load = get_load(State(state)).await.0;
+    assert_eq!(load.shards.len(), 2);
+    assert!(load
+        .shards
+        .iter()
+        .all(|shard| shard.load.queue_depth == 0 && !shard.overloaded));
+    assert!(!load.scale_out);
+}
+
+#[test]
+fn test_scale_out_thresholds() {
+    let load = LoadReport {
+        requests_per_second: 100.0,
+        queue_depth: 5,
+        storage_latency_ms: None,
+        busiest_chains: vec![],
+    };
+    assert!(!ScaleOutThresholds::default().is_reached(&load));
+    let thresholds = ScaleOutThresholds {
+        requests_per_second: Some(200.0),
+        queue_depth: Some(10),
+        storage_latency_ms: Some(50.0),
+    };
+    assert!(!thresholds.is_reached(&load));
+    assert!(thresholds.is_reached(&LoadReport {
+        queue_depth: 10,
+        ..load.clone()
+    }));
+    assert!(thresholds.is_reached(&LoadReport {
+        storage_latency_ms: Some(80.0),
+        ..load
+    }));
+}
+
+#[tokio::test]
+async fn test_redeliver_messages() {
+    let (sender, mut receiver) = mpsc::unbounded();
+    let running = ShardState {
+        commands: Some(sender),
+        ..ShardState::default()
+    };
+    let shards: Shards = Arc::new(
+        [(0, running), (1, ShardState::default())]
+            .into_iter()
+            .collect(),
+    );
+    let chain_id = ChainId::root(0);
+
+    let status = redeliver_messages(State(Arc::clone(&shards)), Path((0, chain_id))).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(
+        receiver.next().await,
+        Some(AdminCommand::RedeliverMessages(chain_id))
+    );
+    let status = redeliver_messages(State(Arc::clone(&shards)), Path((1, chain_id))).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let status = redeliver_messages(State(shards), Path((2, chain_id))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_requests_need_the_admin_token() {
+    let router = AdminApi::new(Some("secret".to_owned())).router();
+    let request = |authorization: Option<&str>| {
+        let mut request = Request::builder().uri("/proposals");
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        request.body(Body::empty()).unwrap()
+    };
+    for (authorization, status) in [
+        (None, StatusCode::UNAUTHORIZED),
+        (Some("Bearer wrong"), StatusCode::UNAUTHORIZED),
+        (Some("secret"), StatusCode::UNAUTHORIZED),
+        (Some("Bearer secret"), StatusCode::OK),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(request(authorization))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "{authorization:?}");
+    }
+
+    let router = AdminApi::new(None).router();
+    let response = router.oneshot(request(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,46 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{data_types::Amount, identifiers::ChainId};
+
+use super::{BalanceAlertConfig, BalanceWatcher, LowBalanceAlert};
+
+#[test]
+fn test_no_alerts_without_threshold() {
+    let mut watcher = BalanceWatcher::new(ChainId::root(0), BalanceAlertConfig::default());
+    assert!(!watcher.is_enabled());
+    assert_eq!(watcher.observe(Amount::ZERO), None);
+}
+
+#[test]
+fn test_one_alert_each_time_the_balance_drops() {
+    let chain_id = ChainId::root(0);
+    let threshold = Amount::from_tokens(10);
+    let config = BalanceAlertConfig {
+        threshold: Some(threshold),
+        webhook: None,
+    };
+    let mut watcher = BalanceWatcher::new(chain_id, config);
+    assert!(watcher.is_enabled());
+    assert_eq!(watcher.observe(Amount::from_tokens(20)), None);
+    assert_eq!(watcher.observe(threshold), None);
+    assert_eq!(
+        watcher.observe(Amount::from_tokens(9)),
+        Some(LowBalanceAlert {
+            chain_id,
+            balance: Amount::from_tokens(9),
+            threshold,
+        })
+    );
+    // The balance is still low, but this was already alerted about.
+    assert_eq!(watcher.observe(Amount::from_tokens(5)), None);
+    assert_eq!(watcher.observe(Amount::from_tokens(15)), None);
+    assert_eq!(
+        watcher.observe(Amount::ONE),
+        Some(LowBalanceAlert {
+            chain_id,
+            balance: Amount::ONE,
+            threshold,
+        })
+    );
+}
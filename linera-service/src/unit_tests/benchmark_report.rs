@@ -0,0 +1,77 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use linera_base::identifiers::ChainId;
+use linera_rpc::config;
+
+use super::{LatencyPercentiles, PhaseReport, ShardReport, Workload};
+
+#[test]
+fn test_workloads_are_assigned_in_turn() {
+    let workloads = [Workload::Transfer, Workload::Fungible];
+    let assigned = (0..5)
+        .map(|index| Workload::for_chain(&workloads, index))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        assigned,
+        [
+            Workload::Transfer,
+            Workload::Fungible,
+            Workload::Transfer,
+            Workload::Fungible,
+            Workload::Transfer,
+        ]
+    );
+    assert_eq!(Workload::for_chain(&[], 3), Workload::CrossChain);
+}
+
+#[test]
+fn test_latency_percentiles() {
+    assert_eq!(LatencyPercentiles::new(Vec::new()), None);
+
+    let latencies = (1..=100).rev().map(Duration::from_millis).collect();
+    let percentiles = LatencyPercentiles::new(latencies).unwrap();
+    assert_eq!(percentiles.samples, 100);
+    assert_eq!(percentiles.p50_ms, 50.0);
+    assert_eq!(percentiles.p90_ms, 90.0);
+    assert_eq!(percentiles.p99_ms, 99.0);
+    assert_eq!(percentiles.max_ms, 100.0);
+
+    let percentiles = LatencyPercentiles::new(vec![Duration::from_millis(7)]).unwrap();
+    assert_eq!(percentiles.p50_ms, 7.0);
+    assert_eq!(percentiles.max_ms, 7.0);
+}
+
+#[test]
+fn test_phase_report_as_json() {
+    let report = PhaseReport::new("certificates", 20, 80, Duration::from_secs(2), Vec::new());
+    assert_eq!(report.throughput_per_sec, 10.0);
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["phase"], "certificates");
+    assert_eq!(json["responses"], 80);
+    assert!(json["latency"].is_null());
+    assert_eq!(
+        serde_json::to_value(Workload::CrossChain).unwrap(),
+        "cross-chain"
+    );
+}
+
+#[test]
+fn test_shard_reports() {
+    let chain_ids = (0..20).map(ChainId::root).collect::<Vec<_>>();
+    let shards = ShardReport::for_chains(chain_ids.iter().copied(), 4, Duration::from_secs(1));
+    assert_eq!(
+        shards.iter().map(|shard| shard.certificates).sum::<usize>(),
+        20
+    );
+    for shard in &shards {
+        let expected = chain_ids
+            .iter()
+            .filter(|chain_id| config::shard_id(**chain_id, 4) == shard.shard)
+            .count();
+        assert_eq!(shard.certificates, expected);
+        assert_eq!(shard.throughput_per_sec, expected as f64);
+    }
+}
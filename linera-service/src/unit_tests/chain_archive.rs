@@ -0,0 +1,118 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use assert_matches::assert_matches;
+use linera_base::{data_types::Round, identifiers::ChainId};
+use linera_chain::{
+    data_types::{BlockExecutionOutcome, Certificate, HashedCertificateValue},
+    test::{make_child_block, make_first_block},
+};
+
+use super::{write_entry, ArchiveEntry, ArchiveError, ArchiveReader, ArchiveWriter};
+
+/// Returns the confirmed certificates of the first `count` blocks of a chain.
+fn make_certificates(chain_id: ChainId, count: usize) -> Vec<Certificate> {
+    let mut certificates: Vec<Certificate> = Vec::new();
+    for _ in 0..count {
+        let block = match certificates.last() {
+            Some(certificate) => make_child_block(&certificate.value),
+            None => make_first_block(chain_id),
+        };
+        let value =
+            HashedCertificateValue::new_confirmed(BlockExecutionOutcome::default().with(block));
+        certificates.push(Certificate::new(value, Round::Fast, Vec::new()));
+    }
+    certificates
+}
+
+fn write_archive(chain_id: ChainId, certificates: &[Certificate]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut writer = ArchiveWriter::new(&mut bytes, chain_id).unwrap();
+    for certificate in certificates {
+        writer.append(certificate.clone()).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), certificates.len() as u64);
+    bytes
+}
+
+fn read_archive(bytes: &[u8]) -> Result<Vec<Certificate>, ArchiveError> {
+    ArchiveReader::new(bytes)?.collect()
+}
+
+/// Tests that the certificates written to an archive are read back in order.
+#[test]
+fn archive_round_trip() {
+    let chain_id = ChainId::root(1);
+    let certificates = make_certificates(chain_id, 3);
+    let bytes = write_archive(chain_id, &certificates);
+
+    let reader = ArchiveReader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.chain_id(), chain_id);
+    let hashes = read_archive(&bytes)
+        .unwrap()
+        .iter()
+        .map(Certificate::hash)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        hashes,
+        certificates
+            .iter()
+            .map(Certificate::hash)
+            .collect::<Vec<_>>()
+    );
+    assert!(read_archive(&write_archive(chain_id, &[]))
+        .unwrap()
+        .is_empty());
+}
+
+/// Tests that the writer only accepts the certificates of the next block of the chain.
+#[test]
+fn writer_rejects_gaps() {
+    let chain_id = ChainId::root(1);
+    let certificates = make_certificates(chain_id, 2);
+    let mut writer = ArchiveWriter::new(Vec::new(), chain_id).unwrap();
+    assert_matches!(
+        writer.append(certificates[1].clone()),
+        Err(ArchiveError::UnexpectedCertificate { .. })
+    );
+    let mut writer = ArchiveWriter::new(Vec::new(), ChainId::root(2)).unwrap();
+    assert_matches!(
+        writer.append(certificates[0].clone()),
+        Err(ArchiveError::UnexpectedCertificate { .. })
+    );
+}
+
+/// Tests that truncated and modified archives are rejected.
+#[test]
+fn corrupted_archives_are_rejected() {
+    let chain_id = ChainId::root(1);
+    let bytes = write_archive(chain_id, &make_certificates(chain_id, 2));
+
+    assert_matches!(read_archive(&[]), Err(ArchiveError::MissingHeader));
+    let mut truncated = bytes.clone();
+    truncated.truncate(bytes.len() - 1);
+    assert_matches!(read_archive(&truncated), Err(ArchiveError::Io(_)));
+
+    // Replace the trailer with one for an archive without the last certificate.
+    let modified = {
+        let mut reader = ArchiveReader::new(&bytes[..]).unwrap();
+        reader.next().unwrap().unwrap();
+        let first_hash = reader.last_hash;
+        reader.next().unwrap().unwrap();
+        let mut modified = bytes[..bytes.len() - reader.reader.len()].to_vec();
+        let trailer = ArchiveEntry::Trailer {
+            certificate_count: 1,
+            last_hash: first_hash,
+        };
+        write_entry(&mut modified, &trailer).unwrap();
+        modified
+    };
+    assert_matches!(
+        read_archive(&modified),
+        Err(ArchiveError::TrailerMismatch { .. })
+    );
+
+    let mut extended = bytes.clone();
+    extended.extend_from_slice(&bytes);
+    assert_matches!(read_archive(&extended), Err(ArchiveError::DataAfterTrailer));
+}
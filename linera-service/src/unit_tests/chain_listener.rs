@@ -1,10 +1,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use futures::lock::Mutex;
+use futures::{lock::Mutex, StreamExt as _};
 use linera_base::{
     crypto::{KeyPair, PublicKey},
     data_types::{Amount, BlockHeight, TimeDelta, Timestamp},
@@ -15,6 +15,7 @@ use linera_core::{
     client::{ChainClient, ChainClientBuilder},
     node::CrossChainMessageDelivery,
     test_utils::{MemoryStorageBuilder, NodeProvider, StorageBuilder as _, TestBuilder},
+    worker::Reason,
 };
 use linera_execution::{
     system::{Recipient, UserData},
@@ -31,6 +32,7 @@ use rand::SeedableRng as _;
 use crate::{
     chain_listener::{self, ChainListener, ChainListenerConfig, ClientContext as _},
     config::{CommitteeConfig, GenesisConfig, ValidatorConfig},
+    node_service::ChainClients,
     wallet::{UserChain, Wallet},
 };
 
@@ -86,6 +88,7 @@ impl chain_listener::ClientContext<NodeProvider<MemoryStorage<TestClock>>> for C
                 timestamp,
                 next_block_height: BlockHeight::ZERO,
                 pending_block: None,
+                balance: None,
             });
         }
     }
@@ -188,3 +191,68 @@ async fn test_chain_listener() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Tests that the chain listener periodically refreshes a chain that it follows but doesn't
+/// own, and notifies the subscribers to that chain of its new blocks.
+#[test_log::test(tokio::test)]
+async fn test_chain_listener_refreshes_followed_chain() -> anyhow::Result<()> {
+    let config = ChainListenerConfig {
+        watch_refresh_ms: 1000,
+        ..ChainListenerConfig::default()
+    };
+    let storage_builder = MemoryStorageBuilder::default();
+    let clock = storage_builder.clock().clone();
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let description0 = ChainDescription::Root(0);
+    let description1 = ChainDescription::Root(1);
+    let chain_id0 = ChainId::from(description0);
+    let mut client0 = builder.add_initial_chain(description0, Amount::ONE).await?;
+    builder.add_initial_chain(description1, Amount::ONE).await?;
+
+    // Start a chain listener that follows chain 0, without owning it.
+    let genesis_config = make_genesis_config(&builder);
+    let storage = builder.make_storage().await?;
+    let delivery = CrossChainMessageDelivery::NonBlocking;
+    let mut context = ClientContext {
+        wallet: Wallet::new(genesis_config, Some(37)),
+        chain_client_builder: ChainClientBuilder::new(builder.make_node_provider(), 10, delivery),
+    };
+    context.update_wallet_for_new_chain(chain_id0, None, clock.current_time());
+    let context = Arc::new(Mutex::new(context));
+    let clients = ChainClients::default();
+    let listener = ChainListener::new(config, clients.clone());
+    listener.run(context, storage).await;
+
+    // Subscribe to the chain through the listener's client, like a follower of the chain.
+    let client = loop {
+        if let Some(client) = clients.map_lock().await.get(&chain_id0).cloned() {
+            break client;
+        }
+        tokio::task::yield_now().await;
+    };
+    let mut notifications = client.lock().await.subscribe().await?;
+
+    // The owner adds a block to chain 0. The listener only sees it when it refreshes the chain.
+    let recipient1 = Recipient::chain(ChainId::from(description1));
+    client0
+        .transfer(None, Amount::ONE, recipient1, UserData::default())
+        .await?;
+    for i in 0.. {
+        clock.add(TimeDelta::from_secs(1));
+        let notification =
+            tokio::time::timeout(Duration::from_millis(100), notifications.next()).await;
+        if let Ok(Some(notification)) = notification {
+            assert_eq!(notification.chain_id, chain_id0);
+            assert!(matches!(
+                notification.reason,
+                Reason::NewBlock { height, .. } if height == BlockHeight::ZERO
+            ));
+            break;
+        }
+        if i == 30 {
+            panic!("The refresh of the followed chain was not notified");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    data_types::{Amount, BlockHeight, Round},
+    identifiers::ChainId,
+};
+use linera_chain::{
+    data_types::{Block, Origin},
+    test::{make_certificate, make_first_block, BlockTestExt},
+};
+
+use super::{BlockSummary, ChainEvent};
+
+/// Returns the first block of a chain, with a transfer.
+fn make_transfer_block() -> Block {
+    make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::ONE)
+        .with_timestamp(0)
+}
+
+#[test]
+fn test_block_summary() {
+    let certificate = make_certificate(make_transfer_block(), Vec::new(), Round::Fast, &[]);
+    let summary = BlockSummary::new(
+        &certificate,
+        Amount::from_tokens(4),
+        Some(Amount::from_tokens(5)),
+    )
+    .unwrap();
+    assert_eq!(summary.chain_id, ChainId::root(1));
+    assert_eq!(summary.height, BlockHeight::ZERO);
+    assert_eq!(summary.hash, certificate.hash());
+    assert_eq!(summary.operations.len(), 1);
+    assert_eq!(summary.outgoing_message_count, 0);
+
+    let text = ChainEvent::NewBlock(summary).to_string();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with(&format!("Block 0 of chain {}", ChainId::root(1))));
+    assert!(lines[1].starts_with("  Operation: "));
+    assert_eq!(lines[2], "  Balance: 4. (was 5.)");
+}
+
+#[test]
+fn test_chain_events_as_json() {
+    let certificate = make_certificate(make_transfer_block(), Vec::new(), Round::Fast, &[]);
+    let summary = BlockSummary::new(&certificate, Amount::ONE, None).unwrap();
+    let json = serde_json::to_value(ChainEvent::NewBlock(summary)).unwrap();
+    assert_eq!(json["event"], "new_block");
+    assert_eq!(json["height"], 0);
+    assert_eq!(json["balance"], "1.");
+    assert!(json["previous_balance"].is_null());
+
+    let event = ChainEvent::NewIncomingMessage {
+        chain_id: ChainId::root(2),
+        origin: Origin::chain(ChainId::root(1)),
+        height: BlockHeight(3),
+    };
+    assert_eq!(
+        event.to_string(),
+        format!(
+            "New incoming message for chain {} from chain {} at height 3",
+            ChainId::root(2),
+            ChainId::root(1)
+        )
+    );
+    let json = serde_json::to_value(event).unwrap();
+    assert_eq!(json["event"], "new_incoming_message");
+    assert_eq!(json["height"], 3);
+}
@@ -0,0 +1,238 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use linera_base::{
+    crypto::KeyPair,
+    data_types::{Amount, TimeDelta, Timestamp},
+    identifiers::ChainId,
+    ownership::TimeoutConfig,
+};
+use linera_execution::{committee::ValidatorName, ResourceControlPolicy};
+use linera_rpc::config::{NetworkProtocol, TlsConfig, ValidatorPublicNetworkConfig};
+
+use super::{
+    CommitteeConfig, ConfigError, Export as _, GenesisConfig, GenesisOverrides, Import,
+    ValidatorConfig, Wallet, WalletState, WALLET_HEADER,
+};
+
+fn test_genesis_config() -> GenesisConfig {
+    GenesisConfig::new(
+        CommitteeConfig::default(),
+        ChainId::root(0),
+        Timestamp::from(0),
+        ResourceControlPolicy::default(),
+        "test".into(),
+    )
+}
+
+fn validator_config(key_pair: &KeyPair, host: &str) -> ValidatorConfig {
+    ValidatorConfig {
+        name: ValidatorName(key_pair.public()),
+        network: ValidatorPublicNetworkConfig {
+            protocol: NetworkProtocol::Grpc(TlsConfig::ClearText),
+            host: host.into(),
+            port: 19100,
+        },
+    }
+}
+
+#[test]
+fn test_config_files_are_versioned() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("committee.json");
+    CommitteeConfig::default().write(&path).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&fs_err::read(&path).unwrap()).unwrap();
+    assert_eq!(value["version"], CommitteeConfig::VERSION);
+    let committee = CommitteeConfig::read(&path).unwrap();
+    assert!(committee.validators.is_empty());
+}
+
+#[test]
+fn test_unversioned_config_files_are_upgraded() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("committee.json");
+    fs_err::write(&path, r#"{ "validators": [] }"#).unwrap();
+    let committee = CommitteeConfig::read(&path).unwrap();
+    assert!(committee.validators.is_empty());
+}
+
+#[test]
+fn test_future_config_versions_are_rejected() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("committee.json");
+    let version = CommitteeConfig::VERSION + 1;
+    fs_err::write(
+        &path,
+        format!(r#"{{ "version": {version}, "validators": [] }}"#),
+    )
+    .unwrap();
+    let error = CommitteeConfig::read(&path).unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigError::UnsupportedVersion { found, .. } if found == version
+    ));
+}
+
+#[test]
+fn test_ephemeral_wallets_are_not_locked_or_written() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("wallet.json");
+    let genesis_config = GenesisConfig::new(
+        CommitteeConfig::default(),
+        ChainId::root(0),
+        Timestamp::from(0),
+        ResourceControlPolicy::default(),
+        "test".into(),
+    );
+    let mut wallet_state = WalletState::create(&path, genesis_config, Some(1)).unwrap();
+    wallet_state.write().unwrap();
+    let content = fs_err::read(&path).unwrap();
+
+    // The wallet file is still locked by `wallet_state`.
+    let mut ephemeral1 = WalletState::read_ephemeral(&path, 37).unwrap();
+    let ephemeral2 = WalletState::read_ephemeral(&path, 37).unwrap();
+    assert!(ephemeral1.is_ephemeral());
+    assert!(!wallet_state.is_ephemeral());
+    let key_pair1 = KeyPair::generate_from(&mut ephemeral1.inner().make_prng());
+    let key_pair2 = KeyPair::generate_from(&mut ephemeral2.inner().make_prng());
+    assert_eq!(key_pair1.public(), key_pair2.public());
+
+    ephemeral1.inner_mut().reseed_prng(38);
+    ephemeral1.write().unwrap();
+    assert_eq!(fs_err::read(&path).unwrap(), content);
+}
+
+#[test]
+fn test_wallets_stay_locked_after_writes() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("wallet.json");
+    let mut wallet_state = WalletState::create(&path, test_genesis_config(), Some(1)).unwrap();
+    wallet_state.write().unwrap();
+    wallet_state.write().unwrap();
+    assert!(WalletState::from_file(&path).is_err());
+    drop(wallet_state);
+    WalletState::from_file(&path).unwrap();
+}
+
+#[test]
+fn test_corrupted_wallets_are_recovered_from_backups() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("wallet.json");
+    let key_pair1 = KeyPair::generate();
+    let key_pair2 = KeyPair::generate();
+    let public1 = key_pair1.public();
+    let public2 = key_pair2.public();
+    let mut wallet_state = WalletState::create(&path, test_genesis_config(), Some(1)).unwrap();
+    wallet_state.inner_mut().add_unassigned_key_pair(key_pair1);
+    wallet_state.write().unwrap();
+    wallet_state.inner_mut().add_unassigned_key_pair(key_pair2);
+    wallet_state.write().unwrap();
+    drop(wallet_state);
+    let wallet = WalletState::from_file(&path).unwrap().into_inner();
+    assert!(wallet.key_pair_for_pk(&public2).is_some());
+
+    // A changed byte is detected, and the previous version is used instead.
+    let mut bytes = fs_err::read(&path).unwrap();
+    let last = bytes.len() - 2;
+    bytes[last] ^= 1;
+    fs_err::write(&path, &bytes).unwrap();
+    let mut wallet_state = WalletState::from_file(&path).unwrap();
+    assert!(wallet_state.inner().key_pair_for_pk(&public1).is_some());
+    assert!(wallet_state.inner().key_pair_for_pk(&public2).is_none());
+
+    // Saving the recovered wallet doesn't replace the backup with the corrupted file.
+    wallet_state.write().unwrap();
+    drop(wallet_state);
+    fs_err::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+    let wallet = WalletState::from_file(&path).unwrap().into_inner();
+    assert!(wallet.key_pair_for_pk(&public1).is_some());
+}
+
+#[test]
+fn test_json_wallets_are_still_read() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("wallet.json");
+    let key_pair = KeyPair::generate();
+    let public = key_pair.public();
+    let mut wallet = Wallet::new(test_genesis_config(), Some(1));
+    wallet.add_unassigned_key_pair(key_pair);
+    fs_err::write(&path, serde_json::to_vec_pretty(&wallet).unwrap()).unwrap();
+    let mut wallet_state = WalletState::from_file(&path).unwrap();
+    assert!(wallet_state.inner().key_pair_for_pk(&public).is_some());
+    wallet_state.write().unwrap();
+    assert!(fs_err::read(&path).unwrap().starts_with(WALLET_HEADER));
+}
+
+#[test]
+fn test_genesis_overrides() {
+    let mut genesis_config = GenesisConfig::new(
+        CommitteeConfig::default(),
+        ChainId::root(0),
+        Timestamp::from(0),
+        ResourceControlPolicy::default(),
+        "test".into(),
+    );
+    let path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../configuration/local/dev_overrides.json");
+    let overrides = GenesisOverrides::read(&path).unwrap();
+    overrides.apply(&mut genesis_config).unwrap();
+    assert_eq!(genesis_config.policy.block, Amount::from_micros(1));
+    assert_eq!(genesis_config.policy.fuel_unit, Amount::from_nanos(1));
+    assert_eq!(genesis_config.policy.maximum_bytes_read_per_block, u64::MAX);
+    assert_eq!(
+        genesis_config.timeout_config,
+        TimeoutConfig {
+            base_timeout: TimeDelta::from_secs(1),
+            timeout_increment: TimeDelta::from_millis(100),
+            ..TimeoutConfig::default()
+        }
+    );
+
+    // Misspelled parameters are rejected instead of being ignored.
+    let overrides: GenesisOverrides =
+        serde_json::from_str(r#"{ "policy": { "blocks": "1" } }"#).unwrap();
+    assert!(overrides.apply(&mut genesis_config).is_err());
+    assert!(serde_json::from_str::<GenesisOverrides>(r#"{ "policies": {} }"#).is_err());
+}
+
+#[test]
+fn test_genesis_config_problems() {
+    let (key_pair1, key_pair2) = (KeyPair::generate(), KeyPair::generate());
+    let committee = CommitteeConfig {
+        validators: vec![
+            validator_config(&key_pair1, "validator-1"),
+            validator_config(&key_pair2, "validator-2"),
+        ],
+    };
+    let mut genesis_config = GenesisConfig::new(
+        committee,
+        ChainId::root(0),
+        Timestamp::from(0),
+        ResourceControlPolicy::default(),
+        "test".into(),
+    );
+    genesis_config
+        .chains
+        .push((key_pair1.public(), Amount::from_tokens(10)));
+    assert!(genesis_config.problems().is_empty());
+    let hash = genesis_config.hash();
+    assert_eq!(genesis_config.clone().hash(), hash);
+
+    genesis_config.admin_id = ChainId::root(1);
+    genesis_config
+        .chains
+        .push((key_pair2.public(), Amount::MAX));
+    assert_eq!(genesis_config.problems().len(), 1);
+    assert_ne!(genesis_config.hash(), hash);
+
+    genesis_config.admin_id = ChainId::root(2);
+    genesis_config
+        .committee
+        .validators
+        .push(validator_config(&key_pair1, "validator-2"));
+    let problems = genesis_config.problems();
+    assert_eq!(problems.len(), 4, "{problems:?}");
+    assert_eq!(CommitteeConfig::default().problems().len(), 1);
+}
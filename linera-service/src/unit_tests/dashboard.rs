@@ -0,0 +1,56 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{num::NonZeroU16, time::Duration};
+
+use linera_base::{
+    data_types::{Amount, BlockHeight},
+    identifiers::{ChainDescription, ChainId},
+};
+use linera_core::test_utils::{FaultType, MemoryStorageBuilder, TestBuilder};
+
+use super::{render_page, DashboardService};
+
+#[tokio::test]
+async fn test_dashboard_polls_validators() {
+    let storage_builder = MemoryStorageBuilder::default();
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await.unwrap();
+    builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::ONE)
+        .await
+        .unwrap();
+    builder.set_fault_type(..1, FaultType::Offline).await;
+    let dashboard = DashboardService::new(
+        NonZeroU16::new(8082).unwrap(),
+        builder.make_node_provider(),
+        &builder.initial_committee,
+        vec![ChainId::root(1)],
+        Duration::from_secs(5),
+    );
+    dashboard.poll().await;
+    dashboard.poll().await;
+
+    let activities = dashboard.activities.lock().await;
+    assert_eq!(activities.len(), 4);
+    let (offline, online): (Vec<_>, Vec<_>) = activities
+        .values()
+        .partition(|activity| activity.errors > 0);
+    assert_eq!(offline.len(), 1);
+    assert_eq!(offline[0].queries, 2);
+    assert_eq!(offline[0].error_rate(), 1.0);
+    assert!(offline[0].next_block_heights.is_empty());
+    assert!(offline[0].latest_error.is_some());
+    for activity in online {
+        assert_eq!(activity.queries, 2);
+        assert_eq!(
+            activity.next_block_heights.get(&ChainId::root(1)),
+            Some(&BlockHeight::ZERO)
+        );
+        assert!(activity.average_latency_ms.is_some());
+    }
+
+    let page = render_page(&activities);
+    for name in activities.keys() {
+        assert!(page.contains(&name.to_string()));
+    }
+}
@@ -1,13 +1,18 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
+use std::{
+    collections::BTreeSet,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderValue};
 use futures::lock::Mutex;
 use linera_base::{
     crypto::KeyPair,
-    data_types::{Amount, Timestamp},
+    data_types::{Amount, TimeDelta, Timestamp},
     identifiers::{ChainDescription, ChainId},
 };
 use linera_core::{
@@ -17,7 +22,7 @@ use linera_core::{
 use linera_storage::{DbStorage, Storage, TestClock};
 use linera_views::{memory::MemoryStore, views::ViewError};
 
-use super::MutationRoot;
+use super::{sign_invitation, ClaimLimits, ClaimTracker, MutationRoot};
 use crate::{chain_listener, wallet::Wallet};
 
 #[derive(Default)]
@@ -75,25 +80,306 @@ async fn test_faucet_rate_limiting() {
         end_timestamp: Timestamp::from(6000),
         start_timestamp: Timestamp::from(0),
         start_balance: Amount::from_tokens(6),
+        limits: Arc::default(),
+        tracker: Arc::default(),
     };
     // The faucet is releasing one token every 1000 microseconds. So at 1000 one claim should
     // succeed. At 3000, two more should have been unlocked.
     clock.set(Timestamp::from(999));
-    assert!(root.do_claim(KeyPair::generate().public()).await.is_err());
+    assert!(root
+        .do_claim_many(vec![KeyPair::generate().public()])
+        .await
+        .is_err());
     clock.set(Timestamp::from(1000));
-    assert!(root.do_claim(KeyPair::generate().public()).await.is_ok());
-    assert!(root.do_claim(KeyPair::generate().public()).await.is_err());
+    assert!(root
+        .do_claim_many(vec![KeyPair::generate().public()])
+        .await
+        .is_ok());
+    assert!(root
+        .do_claim_many(vec![KeyPair::generate().public()])
+        .await
+        .is_err());
     clock.set(Timestamp::from(3000));
-    assert!(root.do_claim(KeyPair::generate().public()).await.is_ok());
-    assert!(root.do_claim(KeyPair::generate().public()).await.is_ok());
-    assert!(root.do_claim(KeyPair::generate().public()).await.is_err());
+    assert!(root
+        .do_claim_many(vec![KeyPair::generate().public()])
+        .await
+        .is_ok());
+    assert!(root
+        .do_claim_many(vec![KeyPair::generate().public()])
+        .await
+        .is_ok());
+    assert!(root
+        .do_claim_many(vec![KeyPair::generate().public()])
+        .await
+        .is_err());
     // If a validator is offline, it will create a pending block and then fail.
     clock.set(Timestamp::from(6000));
     builder.set_fault_type(0..2, FaultType::Offline).await;
-    assert!(root.do_claim(KeyPair::generate().public()).await.is_err());
+    assert!(root
+        .do_claim_many(vec![KeyPair::generate().public()])
+        .await
+        .is_err());
     assert_eq!(context.lock().await.update_calls, 4); // Also called in the last error case.
 }
 
+#[tokio::test]
+async fn test_faucet_claim_many() {
+    let storage_builder = MemoryStorageBuilder::default();
+    let clock = storage_builder.clock().clone();
+    clock.set(Timestamp::from(0));
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await.unwrap();
+    let client = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(6))
+        .await
+        .unwrap();
+    let client = Arc::new(Mutex::new(client));
+    let context = Arc::new(Mutex::new(ClientContext::default()));
+    let root = MutationRoot {
+        client,
+        context: context.clone(),
+        amount: Amount::from_tokens(1),
+        end_timestamp: Timestamp::from(6000),
+        start_timestamp: Timestamp::from(0),
+        start_balance: Amount::from_tokens(6),
+        limits: Arc::default(),
+        tracker: Arc::default(),
+    };
+    let public_keys = |count| (0..count).map(|_| KeyPair::generate().public()).collect();
+    // At 3000, three tokens are unlocked: enough for three chains, but not for four.
+    clock.set(Timestamp::from(3000));
+    assert!(root.do_claim_many(public_keys(4)).await.is_err());
+    let outcomes = root.do_claim_many(public_keys(3)).await.unwrap();
+    assert_eq!(outcomes.len(), 3);
+    let chain_ids = outcomes
+        .iter()
+        .map(|outcome| outcome.chain_id)
+        .collect::<BTreeSet<_>>();
+    assert_eq!(chain_ids.len(), 3);
+    // All chains were created in the same block.
+    assert!(outcomes
+        .iter()
+        .all(|outcome| outcome.certificate_hash == outcomes[0].certificate_hash));
+    assert!(root
+        .do_claim_many(vec![KeyPair::generate().public()])
+        .await
+        .is_err());
+    assert_eq!(context.lock().await.update_calls, 1);
+}
+
+#[tokio::test]
+async fn test_faucet_claim_limits() {
+    let storage_builder = MemoryStorageBuilder::default();
+    let clock = storage_builder.clock().clone();
+    clock.set(Timestamp::from(0));
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await.unwrap();
+    let client = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(6))
+        .await
+        .unwrap();
+    let limits = ClaimLimits {
+        max_claims_per_key: Some(1),
+        max_claims_per_ip: Some(2),
+        ip_window: TimeDelta::from_micros(1000),
+        ..ClaimLimits::default()
+    };
+    let root = MutationRoot {
+        client: Arc::new(Mutex::new(client)),
+        context: Arc::new(Mutex::new(ClientContext::default())),
+        amount: Amount::from_tokens(1),
+        end_timestamp: Timestamp::from(0),
+        start_timestamp: Timestamp::from(0),
+        start_balance: Amount::from_tokens(6),
+        limits: Arc::new(limits),
+        tracker: Arc::default(),
+    };
+    let ip1 = Some(IpAddr::from([10, 0, 0, 1]));
+    let ip2 = Some(IpAddr::from([10, 0, 0, 2]));
+    let key1 = KeyPair::generate().public();
+    let key2 = KeyPair::generate().public();
+    let key3 = KeyPair::generate().public();
+
+    // Each key can claim only one chain.
+    assert!(root
+        .do_limited_claim(vec![key1], vec![], ip1, None)
+        .await
+        .is_ok());
+    assert!(root
+        .do_limited_claim(vec![key1], vec![], ip2, None)
+        .await
+        .is_err());
+    assert!(root
+        .do_limited_claim(vec![key2, key2], vec![], ip2, None)
+        .await
+        .is_err());
+    // Each IP address can claim two chains per millisecond.
+    assert!(root
+        .do_limited_claim(vec![key2], vec![], ip1, None)
+        .await
+        .is_ok());
+    assert!(root
+        .do_limited_claim(vec![key3], vec![], ip1, None)
+        .await
+        .is_err());
+    assert!(root
+        .do_limited_claim(vec![key3], vec![], None, None)
+        .await
+        .is_err());
+    clock.set(Timestamp::from(1000));
+    assert!(root
+        .do_limited_claim(vec![key3], vec![], ip1, None)
+        .await
+        .is_ok());
+    // The addresses whose claims all expired are forgotten.
+    clock.set(Timestamp::from(2000));
+    let key4 = KeyPair::generate().public();
+    assert!(root
+        .do_limited_claim(vec![key4], vec![], ip2, None)
+        .await
+        .is_ok());
+    let tracker = root.tracker.lock().await;
+    assert_eq!(
+        tracker.claims_per_ip.keys().copied().collect::<Vec<_>>(),
+        vec![IpAddr::from([10, 0, 0, 2])]
+    );
+}
+
+#[test]
+fn test_faucet_client_ip() {
+    let address = SocketAddr::from(([10, 0, 0, 1], 1234));
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-forwarded-for",
+        HeaderValue::from_static("1.1.1.1, 2.2.2.2, 3.3.3.3"),
+    );
+    let limits = ClaimLimits::default();
+    assert_eq!(
+        limits.client_ip(address, &headers),
+        Some(IpAddr::from([10, 0, 0, 1]))
+    );
+    // The client could have sent the leftmost entries itself.
+    let mut limits = ClaimLimits {
+        ip_header: Some("X-Forwarded-For".to_string()),
+        ..ClaimLimits::default()
+    };
+    assert_eq!(
+        limits.client_ip(address, &headers),
+        Some(IpAddr::from([3, 3, 3, 3]))
+    );
+    limits.trusted_proxies = 2;
+    assert_eq!(
+        limits.client_ip(address, &headers),
+        Some(IpAddr::from([2, 2, 2, 2]))
+    );
+    limits.trusted_proxies = 4;
+    assert_eq!(limits.client_ip(address, &headers), None);
+}
+
+#[tokio::test]
+async fn test_faucet_invitations() {
+    let storage_builder = MemoryStorageBuilder::default();
+    let clock = storage_builder.clock().clone();
+    clock.set(Timestamp::from(0));
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await.unwrap();
+    let client = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(6))
+        .await
+        .unwrap();
+    let secret = b"faucet secret".to_vec();
+    let limits = ClaimLimits {
+        invitation_secret: Some(secret.clone()),
+        ..ClaimLimits::default()
+    };
+    let root = MutationRoot {
+        client: Arc::new(Mutex::new(client)),
+        context: Arc::new(Mutex::new(ClientContext::default())),
+        amount: Amount::from_tokens(1),
+        end_timestamp: Timestamp::from(0),
+        start_timestamp: Timestamp::from(0),
+        start_balance: Amount::from_tokens(6),
+        limits: Arc::new(limits),
+        tracker: Arc::default(),
+    };
+    let key = || KeyPair::generate().public();
+    let alice = sign_invitation(&secret, "alice");
+    let bob = sign_invitation(&secret, "bob");
+    let forged = sign_invitation(b"other secret", "carol");
+    let tampered = format!("carol:{}", alice.split_once(':').unwrap().1);
+
+    // Invitations are required, and must be signed with the faucet's secret.
+    assert!(root
+        .do_limited_claim(vec![key()], vec![], None, None)
+        .await
+        .is_err());
+    for invitation in [forged, tampered, "alice".to_string()] {
+        assert!(root
+            .do_limited_claim(vec![key()], vec![invitation], None, None)
+            .await
+            .is_err());
+    }
+    // Each invitation allows claiming one chain, once.
+    assert!(root
+        .do_limited_claim(vec![key(), key()], vec![alice.clone()], None, None)
+        .await
+        .is_err());
+    assert!(root
+        .do_limited_claim(
+            vec![key(), key()],
+            vec![bob.clone(), bob.clone()],
+            None,
+            None
+        )
+        .await
+        .is_err());
+    assert!(root
+        .do_limited_claim(vec![key()], vec![alice.clone()], None, None)
+        .await
+        .is_ok());
+    assert!(root
+        .do_limited_claim(vec![key()], vec![alice], None, None)
+        .await
+        .is_err());
+    assert!(root
+        .do_limited_claim(vec![key()], vec![bob], None, None)
+        .await
+        .is_ok());
+}
+
+#[test]
+fn test_faucet_used_invitations_are_persisted() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let secret = b"faucet secret".to_vec();
+    let limits = ClaimLimits {
+        invitation_secret: Some(secret.clone()),
+        used_invitations_path: Some(directory.path().join("used_invitations")),
+        ..ClaimLimits::default()
+    };
+    let key = || KeyPair::generate().public();
+    let now = Timestamp::from(0);
+    let alice = vec![sign_invitation(&secret, "alice")];
+    let bob = vec![sign_invitation(&secret, "bob:\n")];
+
+    let mut tracker = ClaimTracker::load(&limits)?;
+    for invitations in [&alice, &bob] {
+        assert!(tracker
+            .check(&limits, &[key()], invitations, None, now)
+            .is_ok());
+        tracker.record(&limits, &[key()], invitations, None, now);
+    }
+
+    // After a restart, the invitations still can't be used again.
+    let mut tracker = ClaimTracker::load(&limits)?;
+    for invitations in [&alice, &bob] {
+        assert!(tracker
+            .check(&limits, &[key()], invitations, None, now)
+            .is_err());
+    }
+    let carol = vec![sign_invitation(&secret, "carol")];
+    assert!(tracker
+        .check(&limits, &[key()], &carol, None, now)
+        .is_ok());
+    Ok(())
+}
+
 #[test]
 fn test_multiply() {
     let mul = MutationRoot::<(), (), ()>::multiply;
@@ -0,0 +1,74 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Fault, FaultScenario, ScenarioStep};
+use crate::cli_wrappers::local_net::CrossChainFaults;
+
+#[test]
+fn test_parse_fault_scenario() {
+    let scenario = r#"
+        validators = 5
+
+        [[steps]]
+        wait_ms = 1500
+        action = "kill_shard"
+        validator = 3
+        shard = 1
+
+        [[steps]]
+        action = "partition_validator"
+        validator = 4
+
+        [[steps]]
+        wait_ms = 200
+        action = "cross_chain"
+        validator = 0
+        delay_ms = 500
+        duplicate_rate = 0.5
+    "#
+    .parse::<FaultScenario>()
+    .unwrap();
+    assert_eq!(
+        scenario,
+        FaultScenario {
+            validators: Some(5),
+            shards: None,
+            steps: vec![
+                ScenarioStep {
+                    wait_ms: 1500,
+                    fault: Fault::KillShard {
+                        validator: 3,
+                        shard: 1
+                    },
+                },
+                ScenarioStep {
+                    wait_ms: 0,
+                    fault: Fault::PartitionValidator { validator: 4 },
+                },
+                ScenarioStep {
+                    wait_ms: 200,
+                    fault: Fault::CrossChain {
+                        validator: 0,
+                        faults: CrossChainFaults {
+                            delay_ms: 500,
+                            drop_rate: 0.0,
+                            duplicate_rate: 0.5,
+                        },
+                    },
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn test_reject_invalid_fault_scenario() {
+    assert!(r#"
+        [[steps]]
+        action = "explode"
+        validator = 0
+    "#
+    .parse::<FaultScenario>()
+    .is_err());
+    assert!("validator = 4".parse::<FaultScenario>().is_err());
+}
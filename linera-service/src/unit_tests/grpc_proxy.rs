@@ -0,0 +1,303 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex},
+};
+
+use linera_base::{
+    crypto::CryptoHash,
+    identifiers::{Blob, ChainId},
+};
+use linera_core::node::NodeError;
+use linera_rpc::{
+    config::{
+        CanaryShardConfig, NetworkProtocol, ShardConfig, TimeoutPolicy, TlsConfig,
+        ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+    },
+    grpc::{
+        api::{self, validator_node_server::ValidatorNode as _},
+        ip_filter::IpFilter,
+        peer_limits::{PeerLimits, PeerLimitsConfig},
+        public_queries::PublicQueryConfig,
+    },
+    simple::TransportProtocol,
+    RpcMessage,
+};
+use test_case::test_case;
+use tonic::{codegen::http, transport::Body, Code, Request, Response, Status};
+use tower::{layer::layer_fn, service_fn, Layer as _, ServiceExt as _};
+
+use super::{blob_result, chain_info_result, GrpcProxy, ProxyLayer, ProxyLayers, ProxyService};
+
+/// A layer recording its `name` in the `log` when it handles a request.
+fn recording_layer(name: &'static str, log: Arc<Mutex<Vec<&'static str>>>) -> ProxyLayer {
+    ProxyLayer::new(layer_fn(move |service: ProxyService| {
+        let log = log.clone();
+        service_fn(move |request: http::Request<Body>| {
+            log.lock().unwrap().push(name);
+            service.clone().oneshot(request)
+        })
+    }))
+}
+
+#[tokio::test]
+async fn test_custom_layers_handle_requests_in_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let layers = ProxyLayers::default()
+        .with_layer(recording_layer("first", log.clone()))
+        .with_layer(recording_layer("second", log.clone()));
+    let service_log = log.clone();
+    let service = service_fn(move |_request: http::Request<Body>| {
+        service_log.lock().unwrap().push("service");
+        async { Ok::<_, Infallible>(http::Response::new(tonic::body::empty_body())) }
+    });
+
+    let response = layers
+        .layer(service)
+        .oneshot(http::Request::new(Body::empty()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(*log.lock().unwrap(), vec!["first", "second", "service"]);
+}
+
+/// Tests that the responses of shards using the simple transport are converted to the results
+/// of the gRPC API, and that responses not matching the request are rejected.
+#[test]
+fn test_simple_shard_responses_are_converted() {
+    let blob = Blob::new(b"blob".to_vec());
+    let result = blob_result(RpcMessage::DownloadBlobResponse(Box::new(blob.clone()))).unwrap();
+    assert_eq!(result, api::BlobResult::from(blob));
+
+    let error = NodeError::InvalidChainInfoResponse;
+    let result = blob_result(RpcMessage::Error(Box::new(error.clone()))).unwrap();
+    assert_eq!(result, api::BlobResult::try_from(error).unwrap());
+
+    let status = chain_info_result(RpcMessage::VersionInfoQuery).unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Internal);
+}
+
+/// Returns a proxy in front of a single shard using the given protocol. Nothing listens on
+/// the address of the shard, so only the requests rejected by the proxy itself get an answer.
+fn make_proxy(protocol: NetworkProtocol) -> GrpcProxy {
+    make_proxy_with_canary(protocol, None)
+}
+
+/// Returns a proxy in front of a single shard with the given canary.
+fn make_proxy_with_canary(
+    protocol: NetworkProtocol,
+    canary: Option<CanaryShardConfig>,
+) -> GrpcProxy {
+    let shard = ShardConfig {
+        host: "127.0.0.1".into(),
+        port: 1,
+        metrics_host: "127.0.0.1".into(),
+        metrics_port: None,
+        region: None,
+        zone: None,
+        replicas: Vec::new(),
+        read_replicas: Vec::new(),
+        shadow: None,
+        canary,
+        unix_socket: None,
+    };
+    let internal_config = ValidatorInternalNetworkConfig {
+        protocol,
+        shards: vec![shard],
+        host: "127.0.0.1".into(),
+        port: 2,
+        metrics_host: "127.0.0.1".into(),
+        metrics_port: 3,
+        region: None,
+        zone: None,
+        mutual_tls: None,
+    };
+    let public_config = ValidatorPublicNetworkConfig {
+        protocol: NetworkProtocol::Grpc(TlsConfig::ClearText),
+        host: "127.0.0.1".into(),
+        port: 4,
+    };
+    GrpcProxy::new(
+        public_config,
+        internal_config,
+        CryptoHash::test_hash("genesis"),
+        ChainId::root(0),
+        TimeoutPolicy::default(),
+        TlsConfig::ClearText,
+        Arc::new(IpFilter::default()),
+        PeerLimits::new(&PeerLimitsConfig::default()),
+        PublicQueryConfig::default(),
+        None,
+        None,
+    )
+    .unwrap()
+}
+
+/// Returns the code of the status with which a request was rejected.
+fn rejection_code<T>(result: Result<Response<T>, Status>) -> Code {
+    result.err().expect("the request should be rejected").code()
+}
+
+/// Tests that requests without a chain ID, as a Byzantine client could send, are rejected
+/// by the proxy instead of being forwarded to a shard.
+#[test_case(NetworkProtocol::Simple(TransportProtocol::Tcp); "simple")]
+#[test_case(NetworkProtocol::Grpc(TlsConfig::ClearText); "grpc")]
+#[tokio::test]
+async fn test_requests_without_chain_id_are_rejected(protocol: NetworkProtocol) {
+    let proxy = make_proxy(protocol);
+    let code = rejection_code(
+        proxy
+            .handle_block_proposal(Request::new(api::BlockProposal::default()))
+            .await,
+    );
+    assert_eq!(code, Code::NotFound);
+    let code = rejection_code(
+        proxy
+            .handle_lite_certificate(Request::new(api::LiteCertificate::default()))
+            .await,
+    );
+    assert_eq!(code, Code::NotFound);
+    let code = rejection_code(
+        proxy
+            .handle_certificate(Request::new(api::Certificate::default()))
+            .await,
+    );
+    assert_eq!(code, Code::NotFound);
+    let code = rejection_code(
+        proxy
+            .handle_chain_info_query(Request::new(api::ChainInfoQuery::default()))
+            .await,
+    );
+    assert_eq!(code, Code::NotFound);
+    let code = rejection_code(
+        proxy
+            .simulate_block_proposal(Request::new(api::BlockSimulationRequest::default()))
+            .await,
+    );
+    assert_eq!(code, Code::NotFound);
+    let code = rejection_code(
+        proxy
+            .download_blob(Request::new(api::BlobRequest::default()))
+            .await,
+    );
+    assert_eq!(code, Code::NotFound);
+}
+
+/// Tests that malformed requests for shards using the simple transport are rejected as
+/// invalid by the proxy, which fails to convert them before sending anything to the shard.
+#[tokio::test]
+async fn test_malformed_requests_for_simple_shards_are_rejected() {
+    let proxy = make_proxy(NetworkProtocol::Simple(TransportProtocol::Tcp));
+    let chain_id = Some(api::ChainId::from(ChainId::root(0)));
+    let invalid_bytes = vec![0xff; 8];
+
+    let code = rejection_code(
+        proxy
+            .handle_block_proposal(Request::new(api::BlockProposal {
+                chain_id: chain_id.clone(),
+                content: invalid_bytes.clone(),
+                ..api::BlockProposal::default()
+            }))
+            .await,
+    );
+    assert_eq!(code, Code::InvalidArgument);
+    let code = rejection_code(
+        proxy
+            .handle_lite_certificate(Request::new(api::LiteCertificate {
+                chain_id: chain_id.clone(),
+                hash: invalid_bytes.clone(),
+                ..api::LiteCertificate::default()
+            }))
+            .await,
+    );
+    assert_eq!(code, Code::InvalidArgument);
+    let code = rejection_code(
+        proxy
+            .handle_certificate(Request::new(api::Certificate {
+                chain_id: chain_id.clone(),
+                value: invalid_bytes.clone(),
+                ..api::Certificate::default()
+            }))
+            .await,
+    );
+    assert_eq!(code, Code::InvalidArgument);
+    let code = rejection_code(
+        proxy
+            .handle_chain_info_query(Request::new(api::ChainInfoQuery {
+                chain_id: chain_id.clone(),
+                request_sent_certificates_in_range: Some(invalid_bytes.clone()),
+                ..api::ChainInfoQuery::default()
+            }))
+            .await,
+    );
+    assert_eq!(code, Code::InvalidArgument);
+    let code = rejection_code(
+        proxy
+            .simulate_block_proposal(Request::new(api::BlockSimulationRequest {
+                chain_id: chain_id.clone(),
+                block: invalid_bytes,
+            }))
+            .await,
+    );
+    assert_eq!(code, Code::InvalidArgument);
+    let code = rejection_code(
+        proxy
+            .download_blob(Request::new(api::BlobRequest {
+                chain_id,
+                blob_id: None,
+            }))
+            .await,
+    );
+    assert_eq!(code, Code::InvalidArgument);
+}
+
+/// Tests that subscriptions to malformed chain IDs are rejected as invalid.
+#[tokio::test]
+async fn test_subscriptions_to_malformed_chain_ids_are_rejected() {
+    let proxy = make_proxy(NetworkProtocol::Grpc(TlsConfig::ClearText));
+    let request = api::SubscriptionRequest {
+        chain_ids: vec![api::ChainId {
+            bytes: vec![1, 2, 3],
+        }],
+    };
+    let code = rejection_code(proxy.subscribe(Request::new(request)).await);
+    assert_eq!(code, Code::InvalidArgument);
+}
+
+/// Tests that the requests for the chains matching a prefix of the canary of their shard are
+/// sent to the canary, and the other requests to the shard itself.
+#[test_case(NetworkProtocol::Simple(TransportProtocol::Tcp), "127.0.0.1:5", "127.0.0.1:1"; "simple")]
+#[test_case(NetworkProtocol::Grpc(TlsConfig::ClearText), "http://127.0.0.1:5", "http://127.0.0.1:1"; "grpc")]
+#[tokio::test]
+async fn test_requests_are_routed_to_canary(
+    protocol: NetworkProtocol,
+    canary_address: &str,
+    shard_address: &str,
+) {
+    let canary_chain_id = ChainId::root(0);
+    let prefix = canary_chain_id.to_string()[..4].to_uppercase();
+    let other_chain_id = (1..)
+        .map(ChainId::root)
+        .find(|chain_id| !chain_id.to_string().to_uppercase().starts_with(&prefix))
+        .unwrap();
+    let canary = CanaryShardConfig {
+        host: "127.0.0.1".into(),
+        port: 5,
+        percentage: 0,
+        chain_id_prefixes: vec![prefix],
+    };
+    let proxy = make_proxy_with_canary(protocol, Some(canary));
+    let proposal = |chain_id: ChainId| api::BlockProposal {
+        chain_id: Some(chain_id.into()),
+        ..api::BlockProposal::default()
+    };
+
+    let (address, target) = proxy.shard_address_for(&proposal(canary_chain_id)).unwrap();
+    assert_eq!(address, canary_address);
+    assert!(target.canary);
+    let (address, target) = proxy.shard_address_for(&proposal(other_chain_id)).unwrap();
+    assert_eq!(address, shard_address);
+    assert!(!target.canary);
+}
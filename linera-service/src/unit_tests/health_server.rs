@@ -0,0 +1,36 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode};
+
+use super::{ready, HealthChecks};
+
+#[tokio::test]
+async fn test_readiness() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let listening = listener.local_addr().unwrap();
+    let closed = {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    };
+
+    let checks = HealthChecks::default()
+        .with_server("shard 0", listening.to_string())
+        .with_check("storage", || async { Ok(()) });
+    assert_eq!(
+        ready(State(Arc::new(checks))).await.unwrap(),
+        StatusCode::OK
+    );
+
+    let checks = HealthChecks::default()
+        .with_server("shard 0", listening.to_string())
+        .with_server("shard 1", closed.to_string())
+        .with_check("storage", || async { Err("unreachable".to_owned()) });
+    let (status, failures) = ready(State(Arc::new(checks))).await.unwrap_err();
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(failures.0.len(), 2);
+    assert!(failures.0[0].starts_with("shard 1: "));
+    assert_eq!(failures.0[1], "storage: unreachable");
+}
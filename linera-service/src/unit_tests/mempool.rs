@@ -0,0 +1,81 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{crypto::CryptoHash, identifiers::ChainId};
+use linera_execution::{Operation, SystemOperation};
+
+use super::{Mempool, MempoolConfig, OperationState};
+
+fn operations(count: usize) -> Vec<Operation> {
+    vec![Operation::System(SystemOperation::CloseChain); count]
+}
+
+/// Tests that queued operations are batched according to the limits, in order.
+#[test]
+fn batches_respect_limits() {
+    let size = bcs::serialized_size(&operations(1)[0]).unwrap();
+    let mempool = Mempool::new(MempoolConfig {
+        max_block_operations: 3,
+        max_block_bytes: 2 * size,
+        history_size: 100,
+    });
+    let chain_id = ChainId::root(0);
+    let (ids, start_processing) = mempool.submit(chain_id, operations(3)).unwrap();
+    assert!(start_processing);
+    let (more_ids, start_processing) = mempool.submit(chain_id, operations(1)).unwrap();
+    assert!(!start_processing);
+
+    let batch = mempool.next_batch(chain_id);
+    assert_eq!(
+        batch.iter().map(|queued| queued.id).collect::<Vec<_>>(),
+        ids[..2]
+    );
+    assert_eq!(
+        mempool.status(ids[0]).unwrap().state,
+        OperationState::Proposing
+    );
+    assert_eq!(
+        mempool.status(ids[2]).unwrap().state,
+        OperationState::Queued
+    );
+    assert_eq!(mempool.pending(chain_id).len(), 4);
+    assert!(mempool.pending(ChainId::root(1)).is_empty());
+
+    let batch = mempool.next_batch(chain_id);
+    assert_eq!(
+        batch.iter().map(|queued| queued.id).collect::<Vec<_>>(),
+        [ids[2], more_ids[0]]
+    );
+    assert!(mempool.next_batch(chain_id).is_empty());
+
+    // The queue is not processed anymore, so the next submission must start processing it.
+    let (_, start_processing) = mempool.submit(chain_id, operations(1)).unwrap();
+    assert!(start_processing);
+}
+
+/// Tests that the status of finished operations is remembered up to the history size.
+#[test]
+fn finished_operations_are_forgotten() {
+    let mempool = Mempool::new(MempoolConfig {
+        history_size: 2,
+        ..MempoolConfig::default()
+    });
+    let chain_id = ChainId::root(0);
+    let (ids, _) = mempool.submit(chain_id, operations(3)).unwrap();
+    let batch = mempool.next_batch(chain_id);
+    assert_eq!(batch.len(), 3);
+
+    let hash = CryptoHash::test_hash("block");
+    mempool.commit(&batch[..2], hash);
+    let status = mempool.status(ids[1]).unwrap();
+    assert_eq!(status.state, OperationState::Committed);
+    assert_eq!(status.certificate_hash, Some(hash));
+    assert_eq!(mempool.pending(chain_id).len(), 1);
+
+    mempool.fail(&batch[2..], "invalid operation");
+    let status = mempool.status(ids[2]).unwrap();
+    assert_eq!(status.state, OperationState::Failed);
+    assert_eq!(status.error.as_deref(), Some("invalid operation"));
+    assert!(mempool.status(ids[0]).is_none());
+    assert!(mempool.pending(chain_id).is_empty());
+}
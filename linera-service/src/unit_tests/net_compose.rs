@@ -0,0 +1,124 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::Path;
+
+use linera_base::data_types::Amount;
+
+use super::{ComposeOptions, FAUCET_PORT, PUBLIC_PORT, SHARD_PORT};
+
+fn options(output_dir: &Path, num_validators: usize, num_shards: usize) -> ComposeOptions {
+    ComposeOptions {
+        output_dir: output_dir.to_path_buf(),
+        num_other_initial_chains: 2,
+        initial_amount: Amount::from_tokens(1000),
+        num_validators,
+        num_shards,
+        faucet_amount: Amount::ONE,
+        public_host: None,
+        image: "linera".to_string(),
+        build_context: None,
+        testing_prng_seed: Some(37),
+    }
+}
+
+fn read_toml(path: &Path) -> toml::Value {
+    fs_err::read_to_string(path).unwrap().parse().unwrap()
+}
+
+#[test]
+fn test_validator_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = options(dir.path(), 2, 3);
+    let file_name = options.write_validator_config(1).unwrap();
+    assert_eq!(file_name, "validator_1.toml");
+
+    let config = read_toml(&dir.path().join(file_name));
+    assert_eq!(config["server_config_path"].as_str(), Some("server_1.json"));
+    assert_eq!(config["host"].as_str(), Some("proxy-1"));
+    assert_eq!(
+        config["port"].as_integer(),
+        Some(i64::from(PUBLIC_PORT) + 1)
+    );
+    assert_eq!(config["internal_host"].as_str(), Some("proxy-1"));
+    let shards = config["shards"].as_array().unwrap();
+    let hosts = shards
+        .iter()
+        .map(|shard| shard["host"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(hosts, ["shard-1-0", "shard-1-1", "shard-1-2"]);
+    for shard in shards {
+        assert_eq!(shard["port"].as_integer(), Some(i64::from(SHARD_PORT)));
+    }
+}
+
+#[test]
+fn test_validator_config_with_public_host() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut options = options(dir.path(), 1, 1);
+    options.public_host = Some("linera.example.com".to_string());
+    let file_name = options.write_validator_config(0).unwrap();
+
+    let config = read_toml(&dir.path().join(file_name));
+    assert_eq!(config["host"].as_str(), Some("linera.example.com"));
+    // The proxy is still reached through the service name from within the network.
+    assert_eq!(config["internal_host"].as_str(), Some("proxy-0"));
+}
+
+#[test]
+fn test_compose_file_services() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = options(dir.path(), 2, 2).compose_file().unwrap();
+
+    for service in [
+        "scylla",
+        "init-0",
+        "shard-0-0",
+        "shard-0-1",
+        "proxy-0",
+        "init-1",
+        "shard-1-0",
+        "shard-1-1",
+        "proxy-1",
+        "faucet-init",
+        "faucet",
+    ] {
+        assert!(
+            file.contains(&format!("\n  {service}:\n")),
+            "missing service {service}"
+        );
+    }
+    assert!(!file.contains("shard-0-2"));
+    assert!(!file.contains("proxy-2"));
+    // Each proxy is published on its own port.
+    assert!(file.contains(&format!("\"{0}:{0}\"", PUBLIC_PORT)));
+    assert!(file.contains(&format!("\"{0}:{0}\"", PUBLIC_PORT + 1)));
+    assert!(file.contains(&format!("\"{FAUCET_PORT}:{FAUCET_PORT}\"")));
+    // Each validator has its own table, distinct from the faucet's.
+    assert!(file.contains("scylladb:tcp:scylla:9042:table_validator_0"));
+    assert!(file.contains("scylladb:tcp:scylla:9042:table_validator_1"));
+    assert!(file.contains("scylladb:tcp:scylla:9042:table_faucet"));
+    assert!(!file.contains("build:"));
+}
+
+#[test]
+fn test_compose_file_with_build_context() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut options = options(dir.path(), 1, 1);
+    options.build_context = Some(dir.path().to_path_buf());
+    let file = options.compose_file().unwrap();
+
+    let context = fs_err::canonicalize(dir.path()).unwrap();
+    assert!(file.contains("  build:\n"));
+    assert!(file.contains(&format!("    context: {:?}\n", context.to_str().unwrap())));
+    assert!(file.contains("    dockerfile: docker/Dockerfile\n"));
+}
+
+#[tokio::test]
+async fn test_generate_requires_validators_and_shards() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(options(dir.path(), 0, 1).generate().await.is_err());
+    assert!(options(dir.path(), 1, 0).generate().await.is_err());
+    // Nothing was written.
+    assert!(!dir.path().join("docker-compose.yml").exists());
+}
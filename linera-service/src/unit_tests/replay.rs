@@ -0,0 +1,90 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::{CryptoHash, KeyPair},
+    data_types::{Amount, Round, Timestamp},
+    identifiers::{ChainDescription, ChainId},
+    ownership::ChainOwnership,
+};
+use linera_chain::{
+    data_types::{BlockExecutionOutcome, Certificate, HashedCertificateValue},
+    test::make_first_block,
+};
+use linera_execution::committee::{Committee, ValidatorName};
+use linera_storage::{MemoryStorage, Storage, TestClock};
+
+use super::replay_archive;
+use crate::chain_archive::{ArchiveReader, ArchiveWriter};
+
+/// Returns a storage with a single root chain.
+async fn make_storage(key_pair: &KeyPair) -> MemoryStorage<TestClock> {
+    let storage = MemoryStorage::make_test_storage(None).await;
+    storage
+        .create_chain(
+            Committee::make_simple(vec![ValidatorName(key_pair.public())]),
+            ChainId::root(0),
+            ChainDescription::Root(0),
+            ChainOwnership::single(key_pair.public()),
+            Amount::from_tokens(10),
+            Timestamp::from(0),
+        )
+        .await
+        .unwrap();
+    storage
+}
+
+/// Returns an archive with a single certificate for a block with the given outcome.
+fn make_archive(outcome: BlockExecutionOutcome) -> Vec<u8> {
+    let block = make_first_block(ChainId::root(0));
+    let value = HashedCertificateValue::new_confirmed(outcome.with(block));
+    let mut bytes = Vec::new();
+    let mut writer = ArchiveWriter::new(&mut bytes, ChainId::root(0)).unwrap();
+    writer
+        .append(Certificate::new(value, Round::Fast, Vec::new()))
+        .unwrap();
+    writer.finish().unwrap();
+    bytes
+}
+
+/// Tests that blocks with the recorded outcome are replayed, and that the first divergent
+/// block is reported.
+#[tokio::test]
+async fn test_replay_finds_divergence() {
+    let key_pair = KeyPair::generate();
+    let storage = make_storage(&key_pair).await;
+    let block = make_first_block(ChainId::root(0));
+    let outcome = storage
+        .load_chain(ChainId::root(0))
+        .await
+        .unwrap()
+        .execute_block(&block, block.timestamp, None)
+        .await
+        .unwrap();
+
+    let archive = make_archive(outcome.clone());
+    let storage = make_storage(&key_pair).await;
+    let report = replay_archive(&storage, ArchiveReader::new(&archive[..]).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(report.replayed_blocks, 1);
+    assert!(report.divergence.is_none());
+    let chain = storage.load_chain(ChainId::root(0)).await.unwrap();
+    assert_eq!(chain.tip_state.get().next_block_height.0, 1);
+
+    let expected_state_hash = CryptoHash::test_hash("wrong state");
+    let archive = make_archive(BlockExecutionOutcome {
+        state_hash: expected_state_hash,
+        ..outcome.clone()
+    });
+    let storage = make_storage(&key_pair).await;
+    let report = replay_archive(&storage, ArchiveReader::new(&archive[..]).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(report.replayed_blocks, 0);
+    let divergence = report.divergence.unwrap();
+    assert_eq!(divergence.expected_state_hash, expected_state_hash);
+    assert_eq!(divergence.actual_state_hash, Some(outcome.state_hash));
+    assert!(divergence.messages_match);
+    assert!(divergence.error.is_none());
+}
@@ -0,0 +1,72 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::Amount,
+    identifiers::{Account, ChainId, Owner},
+};
+use linera_execution::system::Recipient;
+use serde_json::json;
+
+use super::{openapi, TransferRequest};
+
+/// Returns the `$ref` targets used anywhere in the `value`.
+fn references(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .flat_map(|(key, value)| match (key.as_str(), value) {
+                ("$ref", serde_json::Value::String(target)) => vec![target.clone()],
+                _ => references(value),
+            })
+            .collect(),
+        serde_json::Value::Array(values) => values.iter().flat_map(references).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn test_openapi_describes_all_endpoints() {
+    let document = openapi();
+    let paths = document["paths"].as_object().unwrap();
+    for path in [
+        "/api/chains/{chain_id}",
+        "/api/chains/{chain_id}/balance",
+        "/api/chains/{chain_id}/transfers",
+        "/api/chains/{chain_id}/blocks/{height}",
+    ] {
+        assert!(paths.contains_key(path), "{path} is not described");
+    }
+    assert!(document["paths"]["/api/chains/{chain_id}/transfers"]["post"].is_object());
+
+    // All the references point to existing components.
+    for target in references(&document) {
+        let pointer = target.strip_prefix('#').unwrap();
+        assert!(document.pointer(pointer).is_some(), "{target} is undefined");
+    }
+}
+
+#[test]
+fn test_transfer_request_from_json() {
+    let chain_id = ChainId::root(1);
+    let owner = Owner(CryptoHash::test_hash("owner"));
+    let request: TransferRequest = serde_json::from_value(json!({
+        "recipient": format!("{chain_id}:{owner}"),
+        "amount": "1.5",
+    }))
+    .unwrap();
+    assert_eq!(request.owner, None);
+    assert_eq!(request.amount, Amount::from_millis(1500));
+    assert_eq!(
+        request.recipient().unwrap(),
+        Recipient::Account(Account::owner(chain_id, owner))
+    );
+
+    let request: TransferRequest = serde_json::from_value(json!({
+        "recipient": "not a chain",
+        "amount": "1",
+    }))
+    .unwrap();
+    assert!(request.recipient().is_err());
+}
@@ -0,0 +1,138 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{sync::Arc, time::Duration};
+
+use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+use linera_base::identifiers::ChainId;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::UnixStream,
+};
+
+use super::{create_token_file, token_path, Connection, SocketApi};
+use crate::node_service::Chains;
+
+struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn chains(&self) -> Chains {
+        Chains {
+            list: vec![ChainId::root(0), ChainId::root(1)],
+            default: Some(ChainId::root(0)),
+        }
+    }
+}
+
+type TestSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+fn connection() -> Connection<TestSchema> {
+    Connection {
+        token: Arc::from("secret"),
+        executor: Schema::new(QueryRoot, EmptyMutation, EmptySubscription),
+        authenticated: false,
+    }
+}
+
+async fn call(connection: &mut Connection<TestSchema>, request: Value) -> Value {
+    let response = connection.handle(&request.to_string()).await;
+    serde_json::to_value(response).unwrap()
+}
+
+fn authenticate(token: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": 0, "method": "authenticate", "params": { "token": token } })
+}
+
+#[tokio::test]
+async fn test_requests_need_authentication() {
+    let mut connection = connection();
+    let chains = json!({ "jsonrpc": "2.0", "id": 1, "method": "chains" });
+    let response = call(&mut connection, chains.clone()).await;
+    assert_eq!(response["error"]["code"], -32001);
+
+    let response = call(&mut connection, authenticate("wrong")).await;
+    assert_eq!(response["error"]["code"], -32001);
+    let response = call(&mut connection, chains.clone()).await;
+    assert_eq!(response["error"]["code"], -32001);
+
+    let response = call(&mut connection, authenticate("secret")).await;
+    assert_eq!(response["result"], true);
+    let response = call(&mut connection, chains).await;
+    assert_eq!(response["id"], 1);
+    assert_eq!(
+        response["result"],
+        json!({
+            "list": [ChainId::root(0), ChainId::root(1)],
+            "default": ChainId::root(0),
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_requests() {
+    let mut connection = connection();
+    let response = connection.handle("{ not json").await;
+    assert_eq!(response.error.unwrap().code, -32700);
+
+    let request = json!({ "jsonrpc": "1.0", "id": 1, "method": "chains" });
+    let response = call(&mut connection, request).await;
+    assert_eq!(response["error"]["code"], -32600);
+
+    call(&mut connection, authenticate("secret")).await;
+    let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "mine" });
+    let response = call(&mut connection, request).await;
+    assert_eq!(response["error"]["code"], -32601);
+
+    let request = json!({ "jsonrpc": "2.0", "id": 3, "method": "graphql", "params": [] });
+    let response = call(&mut connection, request).await;
+    assert_eq!(response["error"]["code"], -32602);
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 4,
+        "method": "graphql",
+        "params": { "query": "query { unknown }" },
+    });
+    let response = call(&mut connection, request).await;
+    assert_eq!(response["error"]["code"], -32000);
+}
+
+#[tokio::test]
+async fn test_socket_round_trip() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let path = directory.path().join("linera.sock");
+    let token = create_token_file(&path)?;
+    assert_eq!(std::fs::read_to_string(token_path(&path))?, token);
+
+    let schema = Schema::new(QueryRoot, EmptyMutation, EmptySubscription);
+    tokio::spawn(SocketApi::new(path.clone(), token.clone(), schema).run());
+    let stream = loop {
+        match UnixStream::connect(&path).await {
+            Ok(stream) => break stream,
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    };
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": "a",
+        "method": "graphql",
+        "params": { "query": "query { chains { default } }" },
+    });
+    for request in [authenticate(&token), request] {
+        writer.write_all(format!("{request}\n").as_bytes()).await?;
+    }
+    let response: Value = serde_json::from_str(&lines.next_line().await?.unwrap())?;
+    assert_eq!(response["result"], true);
+    let response: Value = serde_json::from_str(&lines.next_line().await?.unwrap())?;
+    assert_eq!(response["id"], "a");
+    assert_eq!(
+        response["result"],
+        json!({ "chains": { "default": ChainId::root(0) } })
+    );
+    Ok(())
+}
@@ -0,0 +1,89 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::mpsc;
+
+use assert_matches::assert_matches;
+use linera_base::{crypto::KeyPair, identifiers::ChainId};
+use linera_chain::test::{make_first_block, BlockTestExt as _};
+
+use super::{
+    encode_entry, write_requests, CaptureEntry, CaptureError, CaptureReader, CapturedMessage,
+    CapturedRequest, CAPTURE_VERSION,
+};
+
+/// Returns captured block proposals for the first block of `count` chains, one millisecond
+/// apart.
+fn make_requests(count: u32) -> Vec<CapturedRequest> {
+    let key_pair = KeyPair::generate();
+    (0..count)
+        .map(|index| {
+            let proposal = make_first_block(ChainId::root(index)).into_fast_proposal(&key_pair);
+            CapturedRequest {
+                offset_micros: u64::from(index) * 1_000,
+                message: CapturedMessage::BlockProposal(Box::new(proposal)),
+            }
+        })
+        .collect()
+}
+
+/// Writes a capture of the `requests`, stopping before it exceeds `max_bytes`.
+fn write_capture(requests: &[CapturedRequest], max_bytes: u64) -> Vec<u8> {
+    let header = CaptureEntry::Header {
+        version: CAPTURE_VERSION,
+    };
+    let mut bytes = encode_entry(&header).unwrap();
+    let (sender, receiver) = mpsc::sync_channel(requests.len());
+    for request in requests {
+        sender.send(request.clone()).unwrap();
+    }
+    drop(sender);
+    let written = bytes.len() as u64;
+    write_requests(&mut bytes, receiver, written, max_bytes);
+    bytes
+}
+
+/// Returns the offset and the chain of each captured block proposal.
+fn summarize(requests: &[CapturedRequest]) -> Vec<(u64, ChainId)> {
+    requests
+        .iter()
+        .map(|request| {
+            let CapturedMessage::BlockProposal(proposal) = &request.message else {
+                panic!("unexpected captured message: {:?}", request.message);
+            };
+            (request.offset_micros, proposal.content.block.chain_id)
+        })
+        .collect()
+}
+
+#[test]
+fn test_capture_round_trip() {
+    let requests = make_requests(3);
+    let bytes = write_capture(&requests, u64::MAX);
+    let read = CaptureReader::new(&bytes[..])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(summarize(&read), summarize(&requests));
+}
+
+#[test]
+fn test_capture_stops_at_its_maximal_size() {
+    let requests = make_requests(3);
+    let full_size = write_capture(&requests, u64::MAX).len() as u64;
+    let bytes = write_capture(&requests, full_size - 1);
+    let read = CaptureReader::new(&bytes[..])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(summarize(&read), summarize(&requests[..2]));
+}
+
+#[test]
+fn test_truncated_capture() {
+    let requests = make_requests(2);
+    let bytes = write_capture(&requests, u64::MAX);
+    let mut reader = CaptureReader::new(&bytes[..bytes.len() - 1]).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    assert_matches!(reader.next(), Some(Err(CaptureError::Truncated)));
+}
@@ -0,0 +1,140 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use assert_matches::assert_matches;
+use linera_base::{
+    crypto::{CryptoHash, KeyPair},
+    data_types::Amount,
+    identifiers::{Account, ChainId, Owner},
+};
+use linera_execution::BlockLimits;
+
+use super::{
+    block_ranges, parse_transfers, transfer_operations, write_report, BatchError, BatchTransfer,
+    TransferStatus,
+};
+
+fn batch_file(lines: &[String]) -> String {
+    lines.join("\n")
+}
+
+#[test]
+fn test_parse_transfers() {
+    let owner = Owner::from(KeyPair::generate().public());
+    let lines = [
+        "recipient,amount".to_string(),
+        String::new(),
+        "# Airdrop".to_string(),
+        format!("{},1.5", ChainId::root(1)),
+        format!(" {}:{} , 2 ", ChainId::root(2), owner),
+    ];
+    let transfers = parse_transfers(&batch_file(&lines)).unwrap();
+    assert_eq!(
+        transfers,
+        [
+            BatchTransfer {
+                line: 4,
+                recipient: Account::chain(ChainId::root(1)),
+                amount: Amount::from_millis(1500),
+            },
+            BatchTransfer {
+                line: 5,
+                recipient: Account::owner(ChainId::root(2), owner),
+                amount: Amount::from_tokens(2),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_transfers_reports_the_invalid_line() {
+    let lines = [
+        format!("{},1", ChainId::root(1)),
+        format!("{},one", ChainId::root(1)),
+    ];
+    assert_matches!(
+        parse_transfers(&batch_file(&lines)),
+        Err(BatchError::InvalidAmount { line: 2, .. })
+    );
+    let lines = [
+        format!("{},1", ChainId::root(1)),
+        "not a chain,1".to_string(),
+    ];
+    assert_matches!(
+        parse_transfers(&batch_file(&lines)),
+        Err(BatchError::InvalidRecipient { line: 2, .. })
+    );
+    let lines = [format!("{}", ChainId::root(1))];
+    assert_matches!(
+        parse_transfers(&batch_file(&lines)),
+        Err(BatchError::InvalidFormat { line: 1 })
+    );
+    assert_matches!(
+        parse_transfers("recipient,amount\n"),
+        Err(BatchError::Empty)
+    );
+}
+
+#[test]
+fn test_block_ranges_respect_the_limits() {
+    let transfers = (0..5)
+        .map(|index| BatchTransfer {
+            line: index + 1,
+            recipient: Account::chain(ChainId::root(index as u32)),
+            amount: Amount::ONE,
+        })
+        .collect::<Vec<_>>();
+    let operations = transfer_operations(None, &transfers);
+    let limits = BlockLimits {
+        maximum_operations: 2,
+        ..BlockLimits::default()
+    };
+    assert_eq!(
+        block_ranges(&operations, &limits).unwrap(),
+        [0..2, 2..4, 4..5]
+    );
+
+    // Only half of the block size is used by the operations.
+    let operation_size = bcs::serialized_size(&operations[0]).unwrap() as u64;
+    let limits = BlockLimits {
+        maximum_size: 6 * operation_size,
+        ..BlockLimits::default()
+    };
+    assert_eq!(block_ranges(&operations, &limits).unwrap(), [0..3, 3..5]);
+
+    // An operation is never left out, even if it does not fit.
+    let limits = BlockLimits {
+        maximum_size: 0,
+        ..BlockLimits::default()
+    };
+    assert_eq!(block_ranges(&operations, &limits).unwrap().len(), 5);
+}
+
+#[test]
+fn test_write_report() {
+    let transfers = (0..3)
+        .map(|index| BatchTransfer {
+            line: index + 1,
+            recipient: Account::chain(ChainId::root(0)),
+            amount: Amount::ONE,
+        })
+        .collect::<Vec<_>>();
+    let hash = CryptoHash::test_hash("block");
+    let statuses = [
+        TransferStatus::Confirmed(hash),
+        TransferStatus::Failed("insufficient funding, really".to_string()),
+        TransferStatus::NotSubmitted,
+    ];
+    let mut report = Vec::new();
+    write_report(&mut report, &transfers, &statuses).unwrap();
+    let chain_id = ChainId::root(0);
+    assert_eq!(
+        String::from_utf8(report).unwrap(),
+        format!(
+            "line,recipient,amount,status,certificate\n\
+            1,{chain_id},1.,confirmed,{hash}\n\
+            2,{chain_id},1.,\"failed: insufficient funding, really\",\n\
+            3,{chain_id},1.,not submitted,\n"
+        )
+    );
+}
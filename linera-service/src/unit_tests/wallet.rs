@@ -0,0 +1,76 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::KeyPair,
+    data_types::Timestamp,
+    identifiers::{ChainDescription, ChainId},
+    ownership::{ChainOwnership, TimeoutConfig},
+};
+use linera_execution::ResourceControlPolicy;
+
+use super::{ChainBundle, UserChain, Wallet};
+use crate::config::{CommitteeConfig, Export as _, GenesisConfig, Import as _};
+
+fn make_wallet() -> Wallet {
+    let genesis_config = GenesisConfig::new(
+        CommitteeConfig::default(),
+        ChainId::root(0),
+        Timestamp::from(0),
+        ResourceControlPolicy::default(),
+        "test".into(),
+    );
+    Wallet::new(genesis_config, Some(37))
+}
+
+#[test]
+fn test_export_and_import_multi_owner_chain() {
+    let mut rng = make_wallet().make_prng();
+    let chain = UserChain::make_initial(&mut rng, ChainDescription::Root(1), Timestamp::from(0));
+    let chain_id = chain.chain_id;
+    let owner_key_pair = chain.key_pair.as_ref().unwrap().copy();
+    let co_owner_key_pair = KeyPair::generate_from(&mut rng);
+    let ownership = ChainOwnership::multiple(
+        [
+            (owner_key_pair.public(), 100),
+            (co_owner_key_pair.public(), 100),
+        ],
+        10,
+        TimeoutConfig::default(),
+    );
+    let mut wallet = make_wallet();
+    wallet.insert(chain);
+
+    // Without the key, the bundle can be shared with the other owners.
+    let bundle = ChainBundle {
+        genesis_config_hash: wallet.genesis_config().hash(),
+        chain: wallet.export_chain(chain_id, false).unwrap(),
+        ownership,
+        certificates: vec![],
+    };
+    assert!(bundle.chain.key_pair.is_none());
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("chain.json");
+    bundle.write(&path).unwrap();
+    let bundle = ChainBundle::read(&path).unwrap();
+
+    // The co-owner generated its key beforehand, and the chain is assigned to it.
+    let mut other_wallet = make_wallet();
+    other_wallet.add_unassigned_key_pair(co_owner_key_pair.copy());
+    other_wallet
+        .import_chain(bundle.chain, &bundle.ownership)
+        .unwrap();
+    let imported = other_wallet.get(chain_id).unwrap();
+    assert_eq!(
+        imported.key_pair.as_ref().map(KeyPair::public),
+        Some(co_owner_key_pair.public())
+    );
+    assert_eq!(other_wallet.default_chain(), Some(chain_id));
+
+    // A chain cannot be imported twice with a key.
+    let chain = wallet.export_chain(chain_id, true).unwrap();
+    assert!(chain.key_pair.is_some());
+    assert!(other_wallet
+        .import_chain(chain, &ChainOwnership::single(owner_key_pair.public()))
+        .is_err());
+}
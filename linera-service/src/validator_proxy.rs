@@ -0,0 +1,406 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The proxy of a validator, forwarding the requests of its clients to its shards.
+//!
+//! The transport of the clients and the transport of the shards are independent: a proxy
+//! forwards the requests unchanged if they are the same, or converts them and their responses
+//! otherwise, so that e.g. the clients can use gRPC while the shards use the simple transport.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use linera_base::{crypto::CryptoHash, data_types::SoftwareVersion, identifiers::ChainId};
+use linera_core::data_types::ChainInfoQuery;
+use linera_rpc::{
+    config::{
+        NetworkProtocol, TimeoutPolicy, ValidatorInternalNetworkConfig,
+        ValidatorPublicNetworkConfig, ValidatorPublicNetworkPreConfig,
+    },
+    grpc::{
+        self,
+        api::{
+            notifier_service_server::{NotifierService, NotifierServiceServer},
+            validator_worker_client::ValidatorWorkerClient,
+            Notification,
+        },
+        ip_filter::{IpFilterConfig, IpFilterRules},
+        peer_limits::{PeerLimits, PeerLimitsConfig},
+        pool::GrpcConnectionPool,
+        public_queries::PublicQueryConfig,
+        GRPC_MAX_MESSAGE_SIZE,
+    },
+    simple::{MessageHandler, TransportProtocol},
+    RpcMessage,
+};
+use tokio::select;
+use tonic::{transport::Server, Request, Response, Status};
+use tracing::{error, info, instrument, warn};
+
+#[cfg(with_metrics)]
+use crate::prometheus_server;
+
+use crate::{
+    grpc_proxy::{self, GrpcProxy},
+    health_server::{accepts_tcp, HealthChecks},
+    routing::RoutingCache,
+    traffic_capture::TrafficCapture,
+};
+
+/// A Linera Proxy, either gRPC or over 'Simple Transport', meaning TCP or UDP.
+/// The ingress and the egress of the proxy can each be gRPC or TCP / UDP: the requests are
+/// converted when they differ.
+pub enum Proxy {
+    Simple(SimpleProxy),
+    Grpc(GrpcProxy),
+}
+
+impl Proxy {
+    /// Run the proxy.
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Proxy::Simple(simple_proxy) => simple_proxy.run().await,
+            Proxy::Grpc(grpc_proxy) => grpc_proxy.run().await,
+        }
+    }
+
+    /// Returns the cache of the shards of the chains, e.g. to clear it.
+    pub fn routing(&self) -> Arc<RoutingCache> {
+        match self {
+            Proxy::Simple(simple_proxy) => simple_proxy.routing.clone(),
+            Proxy::Grpc(grpc_proxy) => grpc_proxy.routing(),
+        }
+    }
+
+    /// Constructs and configures the [`Proxy`] of a validator with the given network
+    /// configurations.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        public_config: ValidatorPublicNetworkConfig,
+        internal_config: ValidatorInternalNetworkConfig,
+        genesis_config_hash: CryptoHash,
+        admin_id: ChainId,
+        timeout: TimeoutPolicy,
+        ip_filter_config: &IpFilterConfig,
+        peer_limits_config: &PeerLimitsConfig,
+        public_query_config: &PublicQueryConfig,
+        shard_hints: Option<IpFilterRules>,
+        capture: Option<Arc<TrafficCapture>>,
+    ) -> Result<Self> {
+        let proxy = match (internal_config.protocol, public_config.protocol) {
+            (NetworkProtocol::Simple(_), _) if internal_config.mutual_tls.is_some() => {
+                bail!("Mutual TLS between proxy and shards is only supported with gRPC.");
+            }
+            (_, NetworkProtocol::Grpc(tls)) => Self::Grpc(GrpcProxy::new(
+                public_config,
+                internal_config,
+                genesis_config_hash,
+                admin_id,
+                timeout,
+                tls,
+                ip_filter_config.start()?,
+                PeerLimits::new(peer_limits_config),
+                public_query_config.clone(),
+                shard_hints,
+                capture,
+            )?),
+            (_, NetworkProtocol::Simple(_)) if public_query_config.port.is_some() => {
+                bail!("The public query endpoint is only supported with gRPC.");
+            }
+            (_, NetworkProtocol::Simple(_)) if shard_hints.is_some() => {
+                bail!("Shard addresses can only be given to clients with gRPC.");
+            }
+            (internal_protocol, NetworkProtocol::Simple(public_transport)) => {
+                let shard_transport = match internal_protocol {
+                    NetworkProtocol::Simple(internal_transport) => {
+                        ShardTransport::Simple(internal_transport)
+                    }
+                    NetworkProtocol::Grpc(_) => {
+                        let shard_tls = internal_config
+                            .mutual_tls
+                            .as_ref()
+                            .map(|tls| tls.client_config())
+                            .transpose()?;
+                        ShardTransport::Grpc(
+                            GrpcConnectionPool::default()
+                                .with_connect_timeout(timeout.send_timeout())
+                                .with_timeout(timeout.recv_timeout())
+                                .with_tls(shard_tls),
+                        )
+                    }
+                };
+                Self::Simple(SimpleProxy {
+                    public_config: public_config.clone_with_protocol(public_transport),
+                    internal_config,
+                    shard_transport,
+                    timeout,
+                    capture,
+                    routing: Arc::default(),
+                    genesis_config_hash,
+                    admin_id,
+                })
+            }
+        };
+
+        Ok(proxy)
+    }
+
+    /// Returns the checks of the readiness endpoint of a proxy: it accepts connections, and
+    /// it can connect to the endpoint of each shard it uses, unless that's a Unix socket.
+    pub fn health_checks(
+        public_config: &ValidatorPublicNetworkConfig,
+        internal_config: &ValidatorInternalNetworkConfig,
+    ) -> HealthChecks {
+        let mut checks = HealthChecks::default();
+        if accepts_tcp(public_config.protocol) {
+            let port = public_config.port;
+            checks = checks.with_server("proxy", format!("127.0.0.1:{port}"));
+        }
+        if accepts_tcp(internal_config.protocol) {
+            for (shard_id, shard) in internal_config.shards.iter().enumerate() {
+                if shard.unix_socket.is_none() {
+                    let endpoint = internal_config.get_shard_endpoint(shard_id);
+                    checks = checks.with_server(format!("shard {shard_id}"), endpoint.address());
+                }
+            }
+        }
+        checks
+    }
+}
+
+/// A proxy receiving requests over TCP or UDP, and forwarding them to the shards over the
+/// transport of the internal network.
+#[derive(Clone)]
+pub struct SimpleProxy {
+    public_config: ValidatorPublicNetworkPreConfig<TransportProtocol>,
+    internal_config: ValidatorInternalNetworkConfig,
+    shard_transport: ShardTransport,
+    timeout: TimeoutPolicy,
+    /// Where to record a sample of the block proposals and certificates, if anywhere.
+    capture: Option<Arc<TrafficCapture>>,
+    routing: Arc<RoutingCache>,
+    genesis_config_hash: CryptoHash,
+    admin_id: ChainId,
+}
+
+/// How a [`SimpleProxy`] sends the requests to the shards.
+#[derive(Clone)]
+enum ShardTransport {
+    /// Over TCP or UDP, unchanged.
+    Simple(TransportProtocol),
+    /// Over gRPC, converted to the requests of the shards' gRPC API.
+    Grpc(GrpcConnectionPool),
+}
+
+impl ShardTransport {
+    /// Sends a message to the shard server at `address`, and returns its response, if any.
+    async fn send(
+        &self,
+        message: RpcMessage,
+        address: String,
+        timeout: TimeoutPolicy,
+    ) -> Result<Option<RpcMessage>> {
+        match self {
+            ShardTransport::Simple(protocol) => {
+                grpc_proxy::send_to_simple_shard(*protocol, message, address, timeout).await
+            }
+            ShardTransport::Grpc(pool) => {
+                let mut client = ValidatorWorkerClient::new(pool.channel(address)?)
+                    .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
+                    .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
+                Ok(grpc::forward_to_worker(&mut client, message, timeout.recv_timeout()).await?)
+            }
+        }
+    }
+}
+
+/// Accepts the notifications that gRPC shards send to a [`SimpleProxy`], and drops them:
+/// the clients of the simple transport can't subscribe to notifications.
+struct DroppedNotifications;
+
+#[async_trait]
+impl NotifierService for DroppedNotifications {
+    async fn notify(&self, _request: Request<Notification>) -> Result<Response<()>, Status> {
+        Ok(Response::new(()))
+    }
+}
+
+#[async_trait]
+impl MessageHandler for SimpleProxy {
+    #[instrument(skip_all, fields(chain_id = ?message.target_chain_id()))]
+    async fn handle_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
+        if let RpcMessage::VersionInfoQuery = message {
+            // We assume each shard is running the same version as the proxy. The ones that
+            // don't are logged when the proxy starts.
+            return Some(linera_version::VersionInfo::default().into());
+        }
+        if let RpcMessage::GenesisConfigHashQuery = message {
+            return Some(self.genesis_config_hash.into());
+        }
+        if let Some(capture) = &self.capture {
+            capture.capture(&message);
+        }
+
+        let Some(chain_id) = message.target_chain_id() else {
+            error!("Can't proxy message without chain ID");
+            return None;
+        };
+
+        let shard_id = self.routing.route(&self.internal_config, chain_id);
+        let shard = self.internal_config.shard(shard_id);
+        let target = grpc_proxy::ShardTarget::choose(shard_id, shard, chain_id);
+        let shard_address = if let Some(canary) = target.canary(shard) {
+            match self.shard_transport {
+                ShardTransport::Simple(_) => canary.address(),
+                ShardTransport::Grpc(_) => self.internal_config.grpc_uri(canary.address()),
+            }
+        } else {
+            let endpoint = self.internal_config.get_request_endpoint(
+                shard_id,
+                message.is_read_only(),
+                rand::random(),
+            );
+            grpc_proxy::record_shard_request(endpoint.locality);
+            match self.shard_transport {
+                ShardTransport::Simple(_) => endpoint.address(),
+                ShardTransport::Grpc(_) => self.internal_config.endpoint_grpc_uri(&endpoint),
+            }
+        };
+
+        if let Some(shadow) = grpc_proxy::sample_shadow(shard) {
+            let mirrored_message = message.clone();
+            let shadow_address = match self.shard_transport {
+                ShardTransport::Simple(_) => shadow.address(),
+                ShardTransport::Grpc(_) => self.internal_config.grpc_uri(shadow.address()),
+            };
+            let shard_transport = self.shard_transport.clone();
+            let timeout = self.timeout;
+            tokio::spawn(async move {
+                let result = shard_transport
+                    .send(mirrored_message, shadow_address, timeout)
+                    .await;
+                grpc_proxy::record_mirrored_request(result.is_ok());
+            });
+        }
+
+        match target
+            .measure(
+                self.shard_transport
+                    .send(message, shard_address, self.timeout),
+            )
+            .await
+        {
+            Ok(maybe_response) => maybe_response,
+            Err(error) => {
+                error!(error = %error, "Failed to proxy message");
+                None
+            }
+        }
+    }
+}
+
+impl SimpleProxy {
+    #[instrument(skip_all, fields(port = self.public_config.port, metrics_port = self.internal_config.metrics_port), err)]
+    async fn run(self) -> Result<()> {
+        info!("Starting simple server");
+        let address = self.get_listen_address(self.public_config.port);
+
+        #[cfg(with_metrics)]
+        Self::start_metrics(&self.get_listen_address(self.internal_config.metrics_port));
+
+        tokio::spawn(self.clone().check_shard_versions());
+
+        let server = self
+            .public_config
+            .protocol
+            .spawn_server(&address, self.clone())
+            .await?;
+        match self.shard_transport {
+            ShardTransport::Simple(_) => server.join().await?,
+            ShardTransport::Grpc(_) => select! {
+                result = server.join() => result?,
+                result = self.serve_notifications() => result?,
+            },
+        }
+        Ok(())
+    }
+
+    /// Serves the notifications of the gRPC shards on the internal network, to drop them.
+    async fn serve_notifications(&self) -> Result<()> {
+        let address = SocketAddr::from(([0, 0, 0, 0], self.internal_config.port));
+        let mut server = match &self.internal_config.mutual_tls {
+            Some(tls) => Server::builder().tls_config(tls.server_config()?)?,
+            None => Server::builder(),
+        };
+        server
+            .add_service(NotifierServiceServer::new(DroppedNotifications))
+            .serve(address)
+            .await?;
+        Ok(())
+    }
+
+    #[cfg(with_metrics)]
+    pub fn start_metrics(address: &String) {
+        match address.parse::<SocketAddr>() {
+            Err(err) => panic!("Invalid metrics address for {address}: {err}"),
+            Ok(address) => prometheus_server::start_metrics(address),
+        }
+    }
+
+    /// Queries the shard of the admin chain for the minimum version of the software that the
+    /// validators must run.
+    async fn minimum_version(&self) -> Result<Option<SoftwareVersion>> {
+        let shard = self.internal_config.get_shard_for(self.admin_id);
+        let address = match self.shard_transport {
+            ShardTransport::Simple(_) => shard.address(),
+            ShardTransport::Grpc(_) => self.internal_config.shard_grpc_uri(shard),
+        };
+        let query = ChainInfoQuery::new(self.admin_id);
+        let message = RpcMessage::ChainInfoQuery(Box::new(query));
+        match self
+            .shard_transport
+            .send(message, address, self.timeout)
+            .await?
+        {
+            Some(RpcMessage::ChainInfoResponse(response)) => Ok(response.info.minimum_version),
+            response => bail!("unexpected response: {response:?}"),
+        }
+    }
+
+    /// Queries the version of every shard, to log the ones that differ from the proxy's or
+    /// are older than the minimum version required by the admin chain.
+    async fn check_shard_versions(self) {
+        let minimum_version = match self.minimum_version().await {
+            Ok(minimum_version) => minimum_version,
+            Err(error) => {
+                warn!(%error, "Could not get the minimum version required by the admin chain");
+                None
+            }
+        };
+        grpc_proxy::log_minimum_version(minimum_version);
+        for shard in &self.internal_config.shards {
+            let address = match self.shard_transport {
+                ShardTransport::Simple(_) => shard.address(),
+                ShardTransport::Grpc(_) => self.internal_config.shard_grpc_uri(shard),
+            };
+            let result = self
+                .shard_transport
+                .send(RpcMessage::VersionInfoQuery, address.clone(), self.timeout)
+                .await;
+            match result {
+                Ok(Some(RpcMessage::VersionInfoResponse(version_info))) => {
+                    grpc_proxy::log_shard_version(&address, &version_info, minimum_version)
+                }
+                Ok(response) => {
+                    warn!(address, ?response, "Unexpected response to a version query")
+                }
+                Err(error) => warn!(address, %error, "Could not get the version of a shard"),
+            }
+        }
+    }
+
+    fn get_listen_address(&self, port: u16) -> String {
+        format!("0.0.0.0:{}", port)
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use anyhow::Context as _;
 use comfy_table::{
@@ -10,17 +10,26 @@ use comfy_table::{
 };
 use linera_base::{
     crypto::{CryptoHash, CryptoRng, KeyPair, PublicKey},
-    data_types::{BlockHeight, Timestamp},
+    data_types::{Amount, BlockHeight, Timestamp},
     identifiers::{ChainDescription, ChainId, Owner},
+    ownership::ChainOwnership,
 };
-use linera_chain::data_types::Block;
-use linera_core::{client::ChainClient, node::ValidatorNodeProvider};
+use linera_chain::data_types::{Block, Certificate};
+use linera_core::{
+    client::ChainClient, node::ValidatorNodeProvider, validator_scores::ValidatorReputation,
+};
+use linera_execution::committee::ValidatorName;
+use linera_rpc::config::RpcPolicies;
 use linera_storage::Storage;
 use linera_views::views::ViewError;
 use rand::Rng as _;
 use serde::{Deserialize, Serialize};
 
-use crate::config::GenesisConfig;
+use crate::config::{Export, GenesisConfig, Import};
+
+#[cfg(test)]
+#[path = "unit_tests/wallet.rs"]
+mod tests;
 
 #[derive(Serialize, Deserialize)]
 pub struct Wallet {
@@ -29,6 +38,13 @@ pub struct Wallet {
     default: Option<ChainId>,
     genesis_config: GenesisConfig,
     testing_prng_seed: Option<u64>,
+    /// The timeouts and retries of the requests to the validators, unless set on the command
+    /// line.
+    #[serde(default, skip_serializing_if = "RpcPolicies::is_empty")]
+    rpc_policies: RpcPolicies,
+    /// How the validators answered the requests of the client in the previous runs.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    validator_reputations: BTreeMap<ValidatorName, ValidatorReputation>,
 }
 
 impl Wallet {
@@ -39,6 +55,8 @@ impl Wallet {
             default: None,
             genesis_config,
             testing_prng_seed,
+            rpc_policies: RpcPolicies::default(),
+            validator_reputations: BTreeMap::new(),
         }
     }
 
@@ -53,6 +71,19 @@ impl Wallet {
         self.chains.insert(chain.chain_id, chain);
     }
 
+    /// Starts following a chain that we don't own. Its latest height and balance are cached
+    /// in the wallet whenever the chain is synchronized.
+    pub fn follow_chain(&mut self, chain_id: ChainId) -> Result<(), anyhow::Error> {
+        anyhow::ensure!(
+            !self.chains.contains_key(&chain_id),
+            "Chain {} is already in the wallet.",
+            chain_id
+        );
+        let timestamp = self.genesis_config.timestamp;
+        self.insert(UserChain::make_other(chain_id, timestamp));
+        Ok(())
+    }
+
     pub fn forget_keys(&mut self, chain_id: &ChainId) -> Result<KeyPair, anyhow::Error> {
         let chain = self
             .chains
@@ -67,6 +98,62 @@ impl Wallet {
             .context(format!("Failed to remove chain: {}", chain_id))
     }
 
+    /// Returns a copy of the entry of a chain, without its key pair unless `with_key` is set.
+    pub fn export_chain(
+        &self,
+        chain_id: ChainId,
+        with_key: bool,
+    ) -> Result<UserChain, anyhow::Error> {
+        let chain = self
+            .chains
+            .get(&chain_id)
+            .with_context(|| format!("Chain {} is not in the wallet.", chain_id))?;
+        Ok(UserChain {
+            chain_id,
+            key_pair: chain
+                .key_pair
+                .as_ref()
+                .filter(|_| with_key)
+                .map(KeyPair::copy),
+            block_hash: chain.block_hash,
+            timestamp: chain.timestamp,
+            next_block_height: chain.next_block_height,
+            pending_block: chain.pending_block.clone(),
+            balance: chain.balance,
+        })
+    }
+
+    /// Adds a chain exported from another wallet. If it comes without a key pair, an
+    /// unassigned key pair of one of its owners is used, if any.
+    pub fn import_chain(
+        &mut self,
+        mut chain: UserChain,
+        ownership: &ChainOwnership,
+    ) -> Result<(), anyhow::Error> {
+        let chain_id = chain.chain_id;
+        if let Some(existing) = self.chains.get(&chain_id) {
+            anyhow::ensure!(
+                existing.key_pair.is_none(),
+                "Chain {} already has a key in the wallet.",
+                chain_id
+            );
+        }
+        if chain.key_pair.is_none() {
+            let public_keys = ownership
+                .super_owners
+                .values()
+                .chain(ownership.owners.values().map(|(public_key, _)| public_key));
+            for public_key in public_keys {
+                if let Some(key_pair) = self.unassigned_key_pairs.remove(public_key) {
+                    chain.key_pair = Some(key_pair);
+                    break;
+                }
+            }
+        }
+        self.insert(chain);
+        Ok(())
+    }
+
     pub fn default_chain(&self) -> Option<ChainId> {
         self.default
     }
@@ -131,11 +218,42 @@ impl Wallet {
             timestamp,
             next_block_height: BlockHeight(0),
             pending_block: None,
+            balance: None,
         };
         self.insert(user_chain);
         Ok(())
     }
 
+    /// Links an unassigned key to a chain that already exists, e.g. one being transferred to
+    /// that key.
+    pub fn assign_existing_chain_to_key(
+        &mut self,
+        key: PublicKey,
+        chain_id: ChainId,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(chain) = self.chains.get(&chain_id) {
+            anyhow::ensure!(
+                chain.key_pair.is_none(),
+                "Chain {} already has a key in the wallet.",
+                chain_id
+            );
+        }
+        let key_pair = self
+            .unassigned_key_pairs
+            .remove(&key)
+            .context("could not assign chain to key as unassigned key was not found")?;
+        if !self.chains.contains_key(&chain_id) {
+            let timestamp = self.genesis_config.timestamp;
+            self.insert(UserChain::make_other(chain_id, timestamp));
+        }
+        let chain = self
+            .chains
+            .get_mut(&chain_id)
+            .expect("chain was just inserted");
+        chain.key_pair = Some(key_pair);
+        Ok(())
+    }
+
     pub fn set_default_chain(&mut self, chain_id: ChainId) -> Result<(), anyhow::Error> {
         anyhow::ensure!(
             self.chains.contains_key(&chain_id),
@@ -162,6 +280,7 @@ impl Wallet {
                 next_block_height: state.next_block_height(),
                 timestamp: state.timestamp(),
                 pending_block: state.pending_block().clone(),
+                balance: state.local_balance().await.ok(),
             },
         );
     }
@@ -174,10 +293,30 @@ impl Wallet {
         &self.genesis_config
     }
 
+    pub fn rpc_policies(&self) -> &RpcPolicies {
+        &self.rpc_policies
+    }
+
+    pub fn validator_reputations(&self) -> &BTreeMap<ValidatorName, ValidatorReputation> {
+        &self.validator_reputations
+    }
+
+    pub fn set_validator_reputations(
+        &mut self,
+        reputations: BTreeMap<ValidatorName, ValidatorReputation>,
+    ) {
+        self.validator_reputations = reputations;
+    }
+
     pub fn make_prng(&self) -> Box<dyn CryptoRng> {
         self.testing_prng_seed.into()
     }
 
+    /// Derives new key pairs deterministically from the given seed from now on.
+    pub fn reseed_prng(&mut self, seed: u64) {
+        self.testing_prng_seed = Some(seed);
+    }
+
     pub fn refresh_prng_seed<R: CryptoRng>(&mut self, rng: &mut R) {
         if self.testing_prng_seed.is_some() {
             self.testing_prng_seed = Some(rng.gen());
@@ -236,7 +375,8 @@ impl Wallet {
 Owner:              {}
 Block Hash:         {}
 Timestamp:          {}
-Next Block Height:  {}"#,
+Next Block Height:  {}
+Balance:            {}"#,
                 user_chain
                     .key_pair
                     .as_ref()
@@ -253,10 +393,67 @@ Next Block Height:  {}"#,
                     .map(|bh| bh.to_string())
                     .unwrap_or_else(|| "-".to_string()),
                 user_chain.timestamp,
-                user_chain.next_block_height
+                user_chain.next_block_height,
+                user_chain
+                    .balance
+                    .map(|balance| balance.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
             )),
         ]);
     }
+
+    /// Prints how the validators of the genesis committee, and any other validator the client
+    /// talked to, answered the requests of the client in the previous runs.
+    pub fn pretty_print_validators(&self) {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                Cell::new("Validator").add_attribute(Attribute::Bold),
+                Cell::new("Address").add_attribute(Attribute::Bold),
+                Cell::new("Average Latency").add_attribute(Attribute::Bold),
+                Cell::new("Successes").add_attribute(Attribute::Bold),
+                Cell::new("Failures").add_attribute(Attribute::Bold),
+            ]);
+        let mut addresses = BTreeMap::new();
+        for validator in &self.genesis_config.committee.validators {
+            addresses.insert(validator.name, validator.network.to_string());
+        }
+        let names = addresses
+            .keys()
+            .chain(self.validator_reputations.keys())
+            .copied()
+            .collect::<BTreeSet<_>>();
+        for name in names {
+            let reputation = self
+                .validator_reputations
+                .get(&name)
+                .cloned()
+                .unwrap_or_default();
+            let failures = if reputation.consecutive_failures > 0 {
+                Cell::new(format!(
+                    "{} ({} in a row)",
+                    reputation.failures, reputation.consecutive_failures
+                ))
+                .fg(Color::Red)
+            } else {
+                Cell::new(reputation.failures)
+            };
+            table.add_row(vec![
+                Cell::new(name),
+                Cell::new(addresses.get(&name).map_or("-", String::as_str)),
+                Cell::new(reputation.latency.map_or_else(
+                    || "-".to_string(),
+                    |latency| format!("{} ms", latency.as_millis()),
+                )),
+                Cell::new(reputation.successes),
+                failures,
+            ]);
+        }
+        println!("{}", table);
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -267,6 +464,9 @@ pub struct UserChain {
     pub timestamp: Timestamp,
     pub next_block_height: BlockHeight,
     pub pending_block: Option<Block>,
+    /// The balance of the chain account as of the last synchronization, if known.
+    #[serde(default)]
+    pub balance: Option<Amount>,
 }
 
 impl UserChain {
@@ -284,6 +484,7 @@ impl UserChain {
             timestamp,
             next_block_height: BlockHeight::ZERO,
             pending_block: None,
+            balance: None,
         }
     }
 
@@ -297,6 +498,25 @@ impl UserChain {
             timestamp,
             next_block_height: BlockHeight::ZERO,
             pending_block: None,
+            balance: None,
         }
     }
 }
+
+/// A chain exported from a wallet, to be imported into another one, e.g. on another machine
+/// or by another owner of a multi-owner chain.
+#[derive(Serialize, Deserialize)]
+pub struct ChainBundle {
+    /// The hash of the genesis configuration of the network of the chain.
+    pub genesis_config_hash: CryptoHash,
+    /// The entry of the chain in the wallet, without its key pair if it is shared with other
+    /// owners.
+    pub chain: UserChain,
+    /// The ownership of the chain, as known by the exporting client.
+    pub ownership: ChainOwnership,
+    /// The latest known certificates of the chain, oldest first.
+    pub certificates: Vec<Certificate>,
+}
+
+impl Import for ChainBundle {}
+impl Export for ChainBundle {}
@@ -25,6 +25,7 @@ use linera_service::cli_wrappers::{
 };
 use linera_service::{
     cli_wrappers::{
+        fault_injection::{Fault, FaultScenario},
         local_net::{Database, LocalNet, LocalNetConfig, PathProvider},
         ApplicationWrapper, ClientWrapper, FaucetOption, LineraNet, LineraNetConfig, Network,
     },
@@ -2945,6 +2946,67 @@ async fn test_end_to_end_retry_pending_block(config: LocalNetConfig) -> Result<(
     Ok(())
 }
 
+#[test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "service_grpc")]
+#[test_case(LocalNetConfig::new_test(Database::Service, Network::Tcp) ; "service_tcp")]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_fault_scenario(config: LocalNetConfig) -> Result<()> {
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    // One validator is faulty in every way, another one delays and duplicates its
+    // cross-chain requests: the others still make a quorum.
+    let scenario = r#"
+        [[steps]]
+        action = "kill_shard"
+        validator = 3
+        shard = 0
+
+        [[steps]]
+        action = "partition_validator"
+        validator = 3
+
+        [[steps]]
+        action = "cross_chain"
+        validator = 0
+        delay_ms = 200
+        duplicate_rate = 1.0
+    "#
+    .parse::<FaultScenario>()?;
+    let (mut net, client) = scenario.configure(config).instantiate().await?;
+    scenario.run(&mut net).await?;
+
+    let chain1 = ChainId::root(0);
+    let chain2 = ChainId::root(1);
+    let balance = client.local_balance(Account::chain(chain2)).await?;
+    client
+        .transfer(Amount::from_tokens(2), chain1, chain2)
+        .await?;
+    client.sync(chain2).await?;
+    client.process_inbox(chain2).await?;
+    // The duplicated messages are only received once.
+    assert_eq!(
+        client.local_balance(Account::chain(chain2)).await?,
+        balance + Amount::from_tokens(2)
+    );
+
+    // After the faulty validator recovers, the network is still live.
+    net.inject_fault(&Fault::HealValidator { validator: 3 })
+        .await?;
+    net.inject_fault(&Fault::RestartShard {
+        validator: 3,
+        shard: 0,
+    })
+    .await?;
+    client
+        .transfer(Amount::from_tokens(1), chain2, chain1)
+        .await?;
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
 #[cfg(feature = "benchmark")]
 #[test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "service_grpc")]
 #[test_case(LocalNetConfig::new_test(Database::Service, Network::Tcp) ; "service_tcp")]
@@ -12,6 +12,7 @@ use tokio::{process::Command, time::Duration};
 
 #[test_case::test_case(".." ; "main")]
 #[test_case::test_case("../examples/amm" ; "amm")]
+#[test_case::test_case("../examples/blob-registry" ; "blob registry")]
 #[test_case::test_case("../examples/counter" ; "counter")]
 #[test_case::test_case("../examples/crowd-funding" ; "crowd funding")]
 #[test_case::test_case("../examples/fungible" ; "fungible")]
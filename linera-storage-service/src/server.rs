@@ -13,7 +13,7 @@ use linera_views::{
 #[cfg(feature = "rocksdb")]
 use linera_views::{
     common::AdminKeyValueStore,
-    rocks_db::{RocksDbStore, RocksDbStoreConfig},
+    rocks_db::{RocksDbStore, RocksDbStoreConfig, RocksDbTuningConfig},
 };
 use serde::Serialize;
 use tonic::{transport::Server, Request, Response, Status};
@@ -477,6 +477,7 @@ impl StoreProcessor for ServiceStoreServer {
 
 #[tokio::main]
 async fn main() {
+    linera_version::VersionInfo::handle_verbose_version_flag();
     let options = <ServiceStoreServerOptions as clap::Parser>::parse();
     let common_config = CommonStoreConfig::default();
     let (store, endpoint) = match options {
@@ -491,6 +492,7 @@ async fn main() {
             let config = RocksDbStoreConfig {
                 path_buf,
                 common_config,
+                tuning: RocksDbTuningConfig::default(),
             };
             let namespace = "linera";
             let store = RocksDbStore::maybe_create_and_connect(&config, namespace)
@@ -8,6 +8,7 @@ fn main() {
         with_dynamodb: { all(not(target_arch = "wasm32"), feature = "dynamodb") },
         with_rocksdb: { all(not(target_arch = "wasm32"), feature = "rocksdb") },
         with_scylladb: { all(not(target_arch = "wasm32"), feature = "scylladb") },
+        with_s3: { all(not(target_arch = "wasm32"), feature = "s3") },
         with_wasmer: { all(not(target_arch = "wasm32"), feature = "wasmer") },
         with_wasmtime: { all(not(target_arch = "wasm32"), feature = "wasmtime") },
         with_wasm_runtime: { any(with_wasmer, with_wasmtime) },
@@ -69,6 +69,17 @@ impl ChainGuards {
         }
     }
 
+    /// Obtains a guard for a specified chain, unless there's already a live guard for it.
+    pub fn try_guard(&self, chain_id: ChainId) -> Option<ChainGuard> {
+        let guard = self.get_or_create_lock(chain_id);
+        let guard = guard.try_lock_owned().ok()?;
+        Some(ChainGuard {
+            chain_id,
+            guards: self.guards.clone(),
+            guard: Some(guard),
+        })
+    }
+
     /// Obtains the lock used for guarding a chain.
     ///
     /// When obtaining a lock, first a write lock to the map entry is obtained. If there is no
@@ -140,8 +151,8 @@ impl Drop for ChainGuard {
     ///    entry.
     /// 2. The [`ChainGuards::get_or_create_lock`] method's body is only executed after obtaining a
     ///    write lock to the entry.
-    /// 3. The mutex is only locked in [`ChainGuards::guard`], which does not hold any locks to the
-    ///    map.
+    /// 3. The mutex is only locked in [`ChainGuards::guard`] and [`ChainGuards::try_guard`],
+    ///    which do not hold any locks to the map.
     fn drop(&mut self) {
         self.guards.remove_if(&self.chain_id, |_, _| {
             let mutex = Arc::downgrade(OwnedMutexGuard::mutex(
@@ -0,0 +1,199 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Archival of the certificates of inactive chains to a cheaper object store.
+//!
+//! A validator keeps all the certificates of all the chains forever, although those of the
+//! chains that have had no new blocks for a long time are rarely read again.
+//! [`archive_inactive_chains`] moves them to a [`ColdStore`], e.g. an S3 or GCS bucket, and only
+//! keeps a marker for each of them in the storage. Reading an archived certificate fetches it
+//! from the cold store transparently.
+
+use std::{collections::BTreeMap, fmt::Display, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use linera_base::crypto::CryptoHash;
+use linera_views::views::ViewError;
+use tracing::{debug, warn};
+
+use crate::Storage;
+
+#[cfg(test)]
+#[path = "unit_tests/cold_storage.rs"]
+mod tests;
+
+/// An object store where archived certificates are kept.
+#[async_trait]
+pub trait ColdStore: Send + Sync {
+    /// Writes an object, replacing any previous one with the same key.
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), ViewError>;
+
+    /// Reads an object, if it exists.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, ViewError>;
+}
+
+/// Which certificates to move to the cold store.
+#[derive(Clone, Debug)]
+pub struct ArchivalPolicy {
+    /// How long a chain must have had no new blocks for its certificates to be archived.
+    pub inactivity: Duration,
+    /// The number of latest certificates of each chain that are always kept in the storage.
+    pub keep_latest: usize,
+}
+
+impl Default for ArchivalPolicy {
+    fn default() -> Self {
+        Self {
+            inactivity: Duration::from_secs(30 * 24 * 60 * 60),
+            keep_latest: 1,
+        }
+    }
+}
+
+/// Moves the confirmed certificates of the chains that have had no new blocks for the
+/// inactivity period of the `policy` to the cold store of the `storage`, and returns how many
+/// were moved. The chains that are locked, e.g. because a worker caches their state, are
+/// skipped instead of waiting for them, and left for a later run.
+pub async fn archive_inactive_chains<S>(
+    storage: &S,
+    policy: &ArchivalPolicy,
+) -> Result<u64, ViewError>
+where
+    S: Storage,
+    ViewError: From<S::ContextError>,
+{
+    let now = storage.clock().current_time();
+    let mut count = 0;
+    for chain_id in storage.list_chain_ids().await? {
+        let hashes = {
+            let Some(chain) = storage.try_load_chain(chain_id).await? else {
+                debug!("Not archiving chain {chain_id}, which is in use");
+                continue;
+            };
+            let block_count = chain.confirmed_log.count();
+            let last_block_time = *chain.execution_state.system.timestamp.get();
+            if block_count <= policy.keep_latest
+                || now.duration_since(last_block_time) < policy.inactivity
+            {
+                continue;
+            }
+            chain
+                .confirmed_log
+                .read(..block_count - policy.keep_latest)
+                .await?
+        };
+        match storage.archive_certificates(&hashes).await {
+            Ok(archived) => count += archived,
+            Err(error) => warn!("Failed to archive the certificates of chain {chain_id}: {error}"),
+        }
+    }
+    Ok(count)
+}
+
+/// A cold store keeping the objects in memory, e.g. for tests.
+#[derive(Default)]
+pub struct MemoryColdStore {
+    objects: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryColdStore {
+    /// Returns the number of objects in the store.
+    pub fn object_count(&self) -> usize {
+        self.objects.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl ColdStore for MemoryColdStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), ViewError> {
+        self.objects.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, ViewError> {
+        Ok(self.objects.lock().unwrap().get(key).cloned())
+    }
+}
+
+/// A cold store keeping the objects in an S3 bucket, or in another object store with an
+/// S3-compatible API, such as GCS.
+#[cfg(with_s3)]
+pub struct S3ColdStore {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    /// The prefix of the keys of all the objects.
+    prefix: String,
+}
+
+#[cfg(with_s3)]
+impl S3ColdStore {
+    /// Connects to the `bucket`, with the AWS configuration of the environment. Another
+    /// `endpoint` than AWS S3 can be used, e.g. `https://storage.googleapis.com` for GCS.
+    pub async fn new(bucket: String, prefix: String, endpoint: Option<String>) -> Self {
+        let base_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let mut config = aws_sdk_s3::config::Builder::from(&base_config);
+        if let Some(endpoint) = endpoint {
+            config = config.endpoint_url(endpoint).force_path_style(true);
+        }
+        Self {
+            client: aws_sdk_s3::Client::from_conf(config.build()),
+            bucket,
+            prefix,
+        }
+    }
+}
+
+#[cfg(with_s3)]
+#[async_trait]
+impl ColdStore for S3ColdStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), ViewError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(format!("{}{key}", self.prefix))
+            .body(bytes.into())
+            .send()
+            .await
+            .map_err(|error| cold_store_error(aws_sdk_s3::error::DisplayErrorContext(error)))?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, ViewError> {
+        let result = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(format!("{}{key}", self.prefix))
+            .send()
+            .await;
+        match result {
+            Ok(output) => {
+                let bytes = output.body.collect().await.map_err(cold_store_error)?;
+                Ok(Some(bytes.into_bytes().to_vec()))
+            }
+            Err(error)
+                if error
+                    .as_service_error()
+                    .map_or(false, |error| error.is_no_such_key()) =>
+            {
+                Ok(None)
+            }
+            Err(error) => Err(cold_store_error(aws_sdk_s3::error::DisplayErrorContext(
+                error,
+            ))),
+        }
+    }
+}
+
+/// Returns the key of the object holding an archived certificate.
+pub(crate) fn certificate_key(hash: CryptoHash) -> String {
+    format!("certificates/{hash}")
+}
+
+/// Returns an error of the cold store.
+pub(crate) fn cold_store_error(error: impl Display) -> ViewError {
+    ViewError::ContextError {
+        backend: "cold store".to_string(),
+        error: error.to_string(),
+    }
+}
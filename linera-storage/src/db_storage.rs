@@ -1,13 +1,18 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Debug,
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{TimeDelta, Timestamp},
+    data_types::{BlockHeight, Timestamp},
     identifiers::{Blob, BlobId, ChainId},
 };
 use linera_chain::{
@@ -15,20 +20,20 @@ use linera_chain::{
     ChainStateView,
 };
 use linera_execution::{
-    ExecutionRuntimeConfig, UserApplicationId, UserContractCode, UserServiceCode, WasmRuntime,
+    committee::ValidatorName, ContractLimits, ExecutionRuntimeConfig, QueryLimits,
+    UserApplicationId, UserContractCode, UserServiceCode, WasmRuntime,
 };
+#[cfg(not(target_arch = "wasm32"))]
+use linera_views::compression::CompressionConfig;
 use linera_views::{
     batch::Batch,
-    common::{AdminKeyValueStore, ContextFromStore, KeyValueStore},
+    common::{AdminKeyValueStore, ContextFromStore, KeyIterable, KeyValueIterable, KeyValueStore},
+    trace::{self, StorageAccess},
+    transaction::Transaction,
     value_splitting::DatabaseConsistencyError,
     views::{View, ViewError},
 };
 use serde::{Deserialize, Serialize};
-#[cfg(with_testing)]
-use {
-    futures::channel::oneshot::{self, Receiver},
-    std::{cmp::Reverse, collections::BTreeMap},
-};
 #[cfg(with_metrics)]
 use {
     linera_base::{
@@ -38,7 +43,11 @@ use {
     prometheus::{HistogramVec, IntCounterVec},
 };
 
-use crate::{chain_guards::ChainGuards, ChainRuntimeContext, Storage};
+use crate::{
+    chain_guards::{ChainGuard, ChainGuards},
+    cold_storage::{self, ColdStore},
+    ChainRuntimeContext, Clock, Storage, StorageUsage, WalEntry, WallClock,
+};
 
 /// The metric counting how often a hashed certificate value is tested for existence from storage.
 #[cfg(with_metrics)]
@@ -145,6 +154,17 @@ pub static WRITE_CERTIFICATE_COUNTER: Lazy<IntCounterVec> = Lazy::new(|| {
     .expect("Counter creation should not fail")
 });
 
+/// The metric counting how often an archived certificate is read from the cold store.
+#[cfg(with_metrics)]
+static READ_ARCHIVED_CERTIFICATE_COUNTER: Lazy<IntCounterVec> = Lazy::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "read_archived_certificate",
+        "The metric counting how often an archived certificate is read from the cold store",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
 /// The latency to load a chain state.
 #[cfg(with_metrics)]
 #[doc(hidden)]
@@ -223,12 +243,22 @@ where
     }
 }
 
+/// The number of values read at once when measuring the storage usage.
+const USAGE_BATCH_SIZE: usize = 100;
+
+/// The number of certificates removed from the storage in one batch once they are archived.
+const ARCHIVE_BATCH_SIZE: usize = 100;
+
 /// A DbStorage wrapping with Arc
 #[derive(Clone)]
 pub struct DbStorage<Client, Clock> {
     pub(crate) client: Arc<DbStorageInner<Client>>,
     pub clock: Clock,
     pub execution_runtime_config: ExecutionRuntimeConfig,
+    pub query_limits: QueryLimits,
+    pub contract_limits: ContractLimits,
+    /// Where the certificates of inactive chains are archived, if anywhere.
+    cold_store: Option<Arc<dyn ColdStore>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -237,120 +267,49 @@ enum BaseKey {
     Certificate(CryptoHash),
     Value(CryptoHash),
     BlobId(BlobId),
+    WalEntry(ChainId),
+    ChainCheckpoint(ChainId, BlockHeight),
+    /// The time at which a blob that is not published yet was written.
+    PendingBlob(BlobId),
+    /// A marker for a blob that was published by a confirmed block.
+    PublishedBlob(BlobId),
+    /// A marker for a certificate that was moved to the cold store.
+    ArchivedCertificate(CryptoHash),
+    /// The progress of a client in the received logs of a chain.
+    ReceivedCertificateTrackers(ChainId),
 }
 
-/// A clock that can be used to get the current `Timestamp`.
-#[async_trait]
-pub trait Clock {
-    fn current_time(&self) -> Timestamp;
-
-    async fn sleep(&self, delta: TimeDelta);
-
-    async fn sleep_until(&self, timestamp: Timestamp);
-}
-
-/// A `Clock` implementation using the system clock.
-#[derive(Clone)]
-pub struct WallClock;
-
-#[async_trait]
-impl Clock for WallClock {
-    fn current_time(&self) -> Timestamp {
-        Timestamp::now()
-    }
-
-    async fn sleep(&self, delta: TimeDelta) {
-        tokio::time::sleep(delta.as_duration()).await
+impl BaseKey {
+    /// Returns the prefix shared by the serialized base keys of the same kind.
+    fn tag(&self) -> Result<Vec<u8>, bcs::Error> {
+        let mut bytes = bcs::to_bytes(self)?;
+        bytes.truncate(1);
+        Ok(bytes)
     }
-
-    async fn sleep_until(&self, timestamp: Timestamp) {
-        let delta = timestamp.delta_since(Timestamp::now());
-        if delta > TimeDelta::ZERO {
-            self.sleep(delta).await
-        }
-    }
-}
-
-#[cfg(with_testing)]
-#[derive(Default)]
-struct TestClockInner {
-    time: Timestamp,
-    sleeps: BTreeMap<Reverse<Timestamp>, Vec<oneshot::Sender<()>>>,
 }
 
-#[cfg(with_testing)]
-impl TestClockInner {
-    fn set(&mut self, time: Timestamp) {
-        self.time = time;
-        let senders = self.sleeps.split_off(&Reverse(time));
-        for sender in senders.into_values().flatten() {
-            let _ = sender.send(());
-        }
-    }
-
-    fn add_sleep(&mut self, delta: TimeDelta) -> Receiver<()> {
-        self.add_sleep_until(self.time.saturating_add(delta))
-    }
-
-    fn add_sleep_until(&mut self, time: Timestamp) -> Receiver<()> {
-        let (sender, receiver) = oneshot::channel();
-        if self.time >= time {
-            let _ = sender.send(());
-        } else {
-            self.sleeps.entry(Reverse(time)).or_default().push(sender);
-        }
-        receiver
-    }
-}
-
-/// A clock implementation that uses a stored number of microseconds and that can be updated
-/// explicitly. All clones share the same time, and setting it in one clone updates all the others.
-#[cfg(with_testing)]
-#[derive(Clone, Default)]
-pub struct TestClock(Arc<std::sync::Mutex<TestClockInner>>);
-
-#[cfg(with_testing)]
-#[async_trait]
-impl Clock for TestClock {
-    fn current_time(&self) -> Timestamp {
-        self.lock().time
-    }
-
-    async fn sleep(&self, delta: TimeDelta) {
-        if delta == TimeDelta::ZERO {
-            return;
-        }
-        let receiver = self.lock().add_sleep(delta);
-        let _ = receiver.await;
-    }
-
-    async fn sleep_until(&self, timestamp: Timestamp) {
-        let receiver = self.lock().add_sleep_until(timestamp);
-        let _ = receiver.await;
-    }
-}
-
-#[cfg(with_testing)]
-impl TestClock {
-    /// Creates a new clock with its time set to 0, i.e. the Unix epoch.
-    pub fn new() -> Self {
-        TestClock(Arc::default())
-    }
-
-    /// Sets the current time.
-    pub fn set(&self, time: Timestamp) {
-        self.lock().set(time);
-    }
-
-    /// Advances the current time by the specified delta.
-    pub fn add(&self, delta: TimeDelta) {
-        let mut guard = self.lock();
-        let time = guard.time.saturating_add(delta);
-        guard.set(time);
-    }
-
-    fn lock(&self) -> std::sync::MutexGuard<TestClockInner> {
-        self.0.lock().expect("poisoned TestClock mutex")
+/// Returns the configuration of the value compression of a store that compresses the
+/// certificates, the certificate values (which include bytecode) and the blobs with the given
+/// Zstandard level.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn value_compression_config(level: i32) -> CompressionConfig {
+    let hash = CryptoHash::from([0; 4]);
+    let key_prefixes = [
+        BaseKey::Certificate(hash),
+        BaseKey::Value(hash),
+        BaseKey::BlobId(BlobId(hash)),
+    ]
+    .iter()
+    .map(|base_key| {
+        let tag = base_key
+            .tag()
+            .expect("serializing a base key should not fail");
+        (tag, level)
+    })
+    .collect();
+    CompressionConfig {
+        key_prefixes,
+        ..CompressionConfig::default()
     }
 }
 
@@ -378,32 +337,33 @@ where
         let _metric = LOAD_CHAIN_LATENCY.measure_latency();
         tracing::trace!("Acquiring lock on {:?}", chain_id);
         let guard = self.client.guards.guard(chain_id).await;
-        let runtime_context = ChainRuntimeContext {
-            storage: self.clone(),
-            chain_id,
-            execution_runtime_config: self.execution_runtime_config,
-            user_contracts: self.client.user_contracts.clone(),
-            user_services: self.client.user_services.clone(),
-            _chain_guard: Arc::new(guard),
+        Box::pin(self.load_chain_with_guard(chain_id, guard)).await
+    }
+
+    async fn try_load_chain(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<Option<ChainStateView<Self::Context>>, ViewError> {
+        let Some(guard) = self.client.guards.try_guard(chain_id) else {
+            return Ok(None);
         };
-        let client = self.client.client.clone();
-        let base_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
-        let context = ContextFromStore::create(client, base_key, runtime_context).await?;
-        ChainStateView::load(context).await
+        Ok(Some(self.load_chain_with_guard(chain_id, guard).await?))
     }
 
     async fn contains_hashed_certificate_value(&self, hash: CryptoHash) -> Result<bool, ViewError> {
         let value_key = bcs::to_bytes(&BaseKey::Value(hash))?;
+        trace::record_access(|| StorageAccess::Read(value_key.clone()));
         let test = self.client.client.contains_key(&value_key).await?;
         #[cfg(with_metrics)]
         CONTAINS_HASHED_CERTIFICATE_VALUE_COUNTER
             .with_label_values(&[])
             .inc();
-        Ok(test)
+        Ok(test || self.is_archived(hash).await?)
     }
 
     async fn contains_blob(&self, blob_id: BlobId) -> Result<bool, ViewError> {
         let blob_key = bcs::to_bytes(&BaseKey::BlobId(blob_id))?;
+        trace::record_access(|| StorageAccess::Read(blob_key.clone()));
         let test = self.client.client.contains_key(&blob_key).await?;
         #[cfg(with_metrics)]
         CONTAINS_BLOB_COUNTER.with_label_values(&[]).inc();
@@ -415,6 +375,7 @@ where
         hash: CryptoHash,
     ) -> Result<HashedCertificateValue, ViewError> {
         let value_key = bcs::to_bytes(&BaseKey::Value(hash))?;
+        trace::record_access(|| StorageAccess::Read(value_key.clone()));
         let maybe_value = self
             .client
             .client
@@ -424,12 +385,19 @@ where
         READ_HASHED_CERTIFICATE_VALUE_COUNTER
             .with_label_values(&[])
             .inc();
-        let value = maybe_value.ok_or_else(|| ViewError::not_found("value for hash", hash))?;
-        Ok(value.with_hash_unchecked(hash))
+        if let Some(value) = maybe_value {
+            return Ok(value.with_hash_unchecked(hash));
+        }
+        let certificate = self
+            .read_archived_certificate(hash)
+            .await?
+            .ok_or_else(|| ViewError::not_found("value for hash", hash))?;
+        Ok(certificate.value)
     }
 
     async fn read_blob(&self, blob_id: BlobId) -> Result<Blob, ViewError> {
         let blob_key = bcs::to_bytes(&BaseKey::BlobId(blob_id))?;
+        trace::record_access(|| StorageAccess::Read(blob_key.clone()));
         let maybe_value = self.client.client.read_value::<Blob>(&blob_key).await?;
         #[cfg(with_metrics)]
         READ_BLOB_COUNTER.with_label_values(&[]).inc();
@@ -468,7 +436,7 @@ where
 
     async fn write_blob(&self, blob: &Blob) -> Result<BlobId, ViewError> {
         let mut batch = Batch::new();
-        let blob_id = self.add_blob_to_batch(blob, &mut batch)?;
+        let blob_id = self.add_pending_blob_to_batch(blob, &mut batch)?;
         self.write_batch(batch).await?;
         Ok(blob_id)
     }
@@ -487,26 +455,71 @@ where
     async fn write_blobs(&self, blobs: &[Blob]) -> Result<(), ViewError> {
         let mut batch = Batch::new();
         for blob in blobs {
-            self.add_blob_to_batch(blob, &mut batch)?;
+            self.add_pending_blob_to_batch(blob, &mut batch)?;
+        }
+        self.write_batch(batch).await
+    }
+
+    async fn publish_blobs(&self, blob_ids: &[BlobId]) -> Result<(), ViewError> {
+        let mut batch = Batch::new();
+        for blob_id in blob_ids {
+            batch.put_key_value_bytes(bcs::to_bytes(&BaseKey::PublishedBlob(*blob_id))?, vec![]);
+            batch.delete_key(bcs::to_bytes(&BaseKey::PendingBlob(*blob_id))?);
         }
         self.write_batch(batch).await
     }
 
+    async fn collect_garbage_blobs(&self, lifetime: Duration) -> Result<u64, ViewError> {
+        let tag = BaseKey::PendingBlob(BlobId(CryptoHash::from([0; 4]))).tag()?;
+        let now = self.clock.current_time();
+        let mut expired_blob_ids = Vec::new();
+        for entry in self
+            .client
+            .client
+            .find_key_values_by_prefix(&tag)
+            .await?
+            .iterator()
+        {
+            let (key, value) = entry?;
+            let written: Timestamp = bcs::from_bytes(value)?;
+            if now.duration_since(written) >= lifetime {
+                expired_blob_ids.push(bcs::from_bytes::<BlobId>(key)?);
+            }
+        }
+        let mut batch = Batch::new();
+        let mut count = 0;
+        for blob_id in expired_blob_ids {
+            batch.delete_key(bcs::to_bytes(&BaseKey::PendingBlob(blob_id))?);
+            // A blob that was written again after it was published only loses its marker.
+            let published_key = bcs::to_bytes(&BaseKey::PublishedBlob(blob_id))?;
+            if !self.client.client.contains_key(&published_key).await? {
+                batch.delete_key(bcs::to_bytes(&BaseKey::BlobId(blob_id))?);
+                count += 1;
+            }
+        }
+        self.write_batch(batch).await?;
+        Ok(count)
+    }
+
     async fn contains_certificate(&self, hash: CryptoHash) -> Result<bool, ViewError> {
         let cert_key = bcs::to_bytes(&BaseKey::Certificate(hash))?;
         let value_key = bcs::to_bytes(&BaseKey::Value(hash))?;
+        trace::record_access(|| StorageAccess::Read(cert_key.clone()));
+        trace::record_access(|| StorageAccess::Read(value_key.clone()));
         let (cert_test, value_test) = tokio::join!(
             self.client.client.contains_key(&cert_key),
             self.client.client.contains_key(&value_key)
         );
         #[cfg(with_metrics)]
         CONTAINS_CERTIFICATE_COUNTER.with_label_values(&[]).inc();
-        Ok(cert_test? && value_test?)
+        Ok((cert_test? && value_test?) || self.is_archived(hash).await?)
     }
 
     async fn read_certificate(&self, hash: CryptoHash) -> Result<Certificate, ViewError> {
         let cert_key = bcs::to_bytes(&BaseKey::Certificate(hash))?;
         let value_key = bcs::to_bytes(&BaseKey::Value(hash))?;
+        trace::record_access(|| StorageAccess::Read(cert_key.clone()));
+        trace::record_access(|| StorageAccess::Read(value_key.clone()));
         let (cert_result, value_result) = tokio::join!(
             self.client.client.read_value::<LiteCertificate>(&cert_key),
             self.client
@@ -517,8 +530,12 @@ where
             #[cfg(with_metrics)]
             READ_CERTIFICATE_COUNTER.with_label_values(&[]).inc();
         }
-        let value: CertificateValue =
-            value_result?.ok_or_else(|| ViewError::not_found("value for hash", hash))?;
+        let Some(value) = value_result? else {
+            return self
+                .read_archived_certificate(hash)
+                .await?
+                .ok_or_else(|| ViewError::not_found("value for hash", hash));
+        };
         let cert: LiteCertificate =
             cert_result?.ok_or_else(|| ViewError::not_found("certificate for hash", hash))?;
         Ok(cert
@@ -540,11 +557,289 @@ where
         self.write_batch(batch).await
     }
 
+    fn set_cold_store(&mut self, cold_store: Arc<dyn ColdStore>) {
+        self.cold_store = Some(cold_store);
+    }
+
+    async fn archive_certificates(&self, hashes: &[CryptoHash]) -> Result<u64, ViewError> {
+        let cold_store = self
+            .cold_store
+            .as_ref()
+            .ok_or_else(|| cold_storage::cold_store_error("no cold store is configured"))?;
+        let mut count = 0;
+        for hashes in hashes.chunks(ARCHIVE_BATCH_SIZE) {
+            let mut batch = Batch::new();
+            for hash in hashes {
+                let cert_key = bcs::to_bytes(&BaseKey::Certificate(*hash))?;
+                if !self.client.client.contains_key(&cert_key).await? {
+                    continue;
+                }
+                let certificate = self.read_certificate(*hash).await?;
+                let bytes = bcs::to_bytes(&certificate)?;
+                cold_store
+                    .put(&cold_storage::certificate_key(*hash), bytes)
+                    .await?;
+                let archived_key = bcs::to_bytes(&BaseKey::ArchivedCertificate(*hash))?;
+                batch.put_key_value_bytes(archived_key, vec![]);
+                batch.delete_key(cert_key);
+                batch.delete_key(bcs::to_bytes(&BaseKey::Value(*hash))?);
+                count += 1;
+            }
+            self.write_batch(batch).await?;
+        }
+        Ok(count)
+    }
+
+    async fn storage_usage(&self) -> Result<StorageUsage, ViewError> {
+        let hash = CryptoHash::from([0; 4]);
+        let mut chain_state_bytes = BTreeMap::new();
+        let chain_tag = BaseKey::ChainState(ChainId(hash)).tag()?;
+        for chain_id in self.chain_ids(&chain_tag).await? {
+            let base_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+            let key_values = self
+                .client
+                .client
+                .find_key_values_by_prefix(&base_key)
+                .await?;
+            let mut bytes = 0;
+            for entry in key_values.iterator() {
+                let (key, value) = entry?;
+                bytes += (base_key.len() + key.len() + value.len()) as u64;
+            }
+            chain_state_bytes.insert(chain_id, bytes);
+        }
+        let (certificate_count, certificate_bytes) = self
+            .prefix_usage(&BaseKey::Certificate(hash).tag()?)
+            .await?;
+        let (_, value_bytes) = self.prefix_usage(&BaseKey::Value(hash).tag()?).await?;
+        let (blob_count, blob_bytes) = self
+            .prefix_usage(&BaseKey::BlobId(BlobId(hash)).tag()?)
+            .await?;
+        let (archived_certificate_count, _) = self
+            .prefix_usage(&BaseKey::ArchivedCertificate(hash).tag()?)
+            .await?;
+        Ok(StorageUsage {
+            chain_state_bytes,
+            certificate_count,
+            certificate_bytes: certificate_bytes + value_bytes,
+            archived_certificate_count,
+            blob_count,
+            blob_bytes,
+        })
+    }
+
+    async fn list_chain_ids(&self) -> Result<Vec<ChainId>, ViewError> {
+        let tag = BaseKey::ChainState(ChainId(CryptoHash::from([0; 4]))).tag()?;
+        Ok(self.chain_ids(&tag).await?.into_iter().collect())
+    }
+
+    async fn write_chain_checkpoint(
+        &self,
+        chain_id: ChainId,
+        next_block_height: BlockHeight,
+        max_checkpoints: usize,
+        max_bytes: u64,
+    ) -> Result<bool, ViewError> {
+        let base_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+        let checkpoint_key = bcs::to_bytes(&BaseKey::ChainCheckpoint(chain_id, next_block_height))?;
+        // The chain state that was just saved is copied, and the commit fails if it was
+        // written to again in the meantime.
+        let mut transaction = Transaction::new(&self.client.client, Vec::new());
+        let mut checkpoint = Vec::new();
+        let mut bytes = 0u64;
+        for (key, value) in transaction.find_key_values_by_prefix(&base_key).await? {
+            bytes += (key.len() + value.len()) as u64;
+            if bytes > max_bytes {
+                tracing::warn!(
+                    "Not writing the checkpoint of chain {chain_id} at height \
+                    {next_block_height}: its state exceeds {max_bytes} bytes"
+                );
+                return Ok(false);
+            }
+            checkpoint.push(([&checkpoint_key[..], &key[..]].concat(), value));
+        }
+        let prefix = Self::checkpoint_prefix(chain_id)?;
+        let heights = Self::heights_from_keys(transaction.find_keys_by_prefix(&prefix).await?)?;
+        let obsolete_count = (heights.len() + 1).saturating_sub(max_checkpoints.max(1));
+        for height in heights
+            .into_iter()
+            .filter(|height| *height != next_block_height)
+            .take(obsolete_count)
+        {
+            transaction
+                .delete_key_prefix(bcs::to_bytes(&BaseKey::ChainCheckpoint(chain_id, height))?);
+        }
+        transaction.delete_key_prefix(checkpoint_key);
+        for (key, value) in checkpoint {
+            transaction.put_key_value_bytes(key, value);
+        }
+        transaction.commit().await?;
+        Ok(true)
+    }
+
+    async fn chain_checkpoint_heights(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<Vec<BlockHeight>, ViewError> {
+        self.checkpoint_heights(chain_id).await
+    }
+
+    async fn restore_chain_checkpoint(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<Option<BlockHeight>, ViewError> {
+        let _guard = self.client.guards.guard(chain_id).await;
+        let Some(height) = self.checkpoint_heights(chain_id).await?.pop() else {
+            return Ok(None);
+        };
+        tracing::warn!(
+            "Restoring the state of chain {chain_id} from its checkpoint at height {height}"
+        );
+        let base_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+        let checkpoint_key = bcs::to_bytes(&BaseKey::ChainCheckpoint(chain_id, height))?;
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(base_key.clone());
+        for entry in self
+            .client
+            .client
+            .find_key_values_by_prefix(&checkpoint_key)
+            .await?
+            .iterator()
+        {
+            let (key, value) = entry?;
+            batch.put_key_value_bytes([&base_key[..], key].concat(), value.to_vec());
+        }
+        self.write_batch(batch).await?;
+        Ok(Some(height))
+    }
+
+    async fn load_chain_checkpoint(
+        &self,
+        chain_id: ChainId,
+        next_block_height: BlockHeight,
+    ) -> Result<Option<ChainStateView<Self::Context>>, ViewError> {
+        let heights = self.chain_checkpoint_heights(chain_id).await?;
+        let Some(height) = heights
+            .into_iter()
+            .rev()
+            .find(|height| *height <= next_block_height)
+        else {
+            return Ok(None);
+        };
+        // The checkpoint is never written to again, so the chain doesn't need to be locked.
+        let runtime_context = ChainRuntimeContext {
+            storage: self.clone(),
+            chain_id,
+            execution_runtime_config: self.execution_runtime_config,
+            query_limits: self.query_limits,
+            contract_limits: self.contract_limits,
+            user_contracts: self.client.user_contracts.clone(),
+            user_services: self.client.user_services.clone(),
+            _chain_guard: None,
+        };
+        let client = self.client.client.clone();
+        let base_key = bcs::to_bytes(&BaseKey::ChainCheckpoint(chain_id, height))?;
+        let context = ContextFromStore::create(client, base_key, runtime_context).await?;
+        Ok(Some(ChainStateView::load(context).await?))
+    }
+
+    async fn write_wal_entry(&self, chain_id: ChainId, entry: &WalEntry) -> Result<(), ViewError> {
+        let wal_key = bcs::to_bytes(&BaseKey::WalEntry(chain_id))?;
+        let mut batch = Batch::new();
+        batch.put_key_value(wal_key, entry)?;
+        self.write_batch(batch).await
+    }
+
+    async fn remove_wal_entry(&self, chain_id: ChainId) -> Result<(), ViewError> {
+        let wal_key = bcs::to_bytes(&BaseKey::WalEntry(chain_id))?;
+        let mut batch = Batch::new();
+        batch.delete_key(wal_key);
+        self.write_batch(batch).await
+    }
+
+    async fn read_wal_entries(&self) -> Result<BTreeMap<ChainId, WalEntry>, ViewError> {
+        let tag = BaseKey::WalEntry(ChainId(CryptoHash::from([0; 4]))).tag()?;
+        let key_values = self.client.client.find_key_values_by_prefix(&tag).await?;
+        let mut entries = BTreeMap::new();
+        for entry in key_values.iterator() {
+            let (key, value) = entry?;
+            entries.insert(bcs::from_bytes(key)?, bcs::from_bytes(value)?);
+        }
+        Ok(entries)
+    }
+
+    async fn read_received_certificate_trackers(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<HashMap<ValidatorName, u64>, ViewError> {
+        let trackers_key = bcs::to_bytes(&BaseKey::ReceivedCertificateTrackers(chain_id))?;
+        let trackers = self
+            .client
+            .client
+            .read_value::<HashMap<ValidatorName, u64>>(&trackers_key)
+            .await?;
+        Ok(trackers.unwrap_or_default())
+    }
+
+    async fn write_received_certificate_trackers(
+        &self,
+        chain_id: ChainId,
+        trackers: &HashMap<ValidatorName, u64>,
+    ) -> Result<(), ViewError> {
+        let trackers_key = bcs::to_bytes(&BaseKey::ReceivedCertificateTrackers(chain_id))?;
+        let mut batch = Batch::new();
+        batch.put_key_value(trackers_key, trackers)?;
+        self.write_batch(batch).await
+    }
+
     fn wasm_runtime(&self) -> Option<WasmRuntime> {
         self.client.wasm_runtime
     }
 }
 
+impl<Client, C> DbStorage<Client, C>
+where
+    Client: KeyValueStore + Clone + Send + Sync + 'static,
+    C: Clock + Clone + Send + Sync + 'static,
+    ViewError: From<<Client as KeyValueStore>::Error>,
+    <Client as KeyValueStore>::Error:
+        From<bcs::Error> + From<DatabaseConsistencyError> + Send + Sync + serde::ser::StdError,
+{
+    /// Loads the view of a chain state, for which the caller holds the guard.
+    async fn load_chain_with_guard(
+        &self,
+        chain_id: ChainId,
+        guard: ChainGuard,
+    ) -> Result<ChainStateView<<Self as Storage>::Context>, ViewError> {
+        let runtime_context = ChainRuntimeContext {
+            storage: self.clone(),
+            chain_id,
+            execution_runtime_config: self.execution_runtime_config,
+            query_limits: self.query_limits,
+            contract_limits: self.contract_limits,
+            user_contracts: self.client.user_contracts.clone(),
+            user_services: self.client.user_services.clone(),
+            _chain_guard: Some(Arc::new(guard)),
+        };
+        let client = self.client.client.clone();
+        let base_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+        let context = ContextFromStore::create(client, base_key, runtime_context).await?;
+        let chain = ChainStateView::load(context).await?;
+        if chain.execution_state.system.description.get().is_none()
+            && !self.checkpoint_heights(chain_id).await?.is_empty()
+        {
+            // Restoring the checkpoint would silently roll back the chain and the votes of
+            // its chain manager, so this is left to the operator.
+            tracing::error!(
+                "The state of chain {chain_id} is missing but it has checkpoints; \
+                restore it with `linera-db restore_chain_checkpoint`"
+            );
+            return Err(ViewError::MissingEntries);
+        }
+        Ok(chain)
+    }
+}
+
 impl<Client, C> DbStorage<Client, C>
 where
     Client: KeyValueStore + Clone + Send + Sync + 'static,
@@ -566,10 +861,23 @@ where
         Ok(())
     }
 
+    /// Adds a blob to the batch, together with the time at which it was written, so that
+    /// it gets collected as garbage unless a confirmed block publishes it.
+    fn add_pending_blob_to_batch(
+        &self,
+        blob: &Blob,
+        batch: &mut Batch,
+    ) -> Result<BlobId, ViewError> {
+        let blob_id = self.add_blob_to_batch(blob, batch)?;
+        let pending_key = bcs::to_bytes(&BaseKey::PendingBlob(blob_id))?;
+        batch.put_key_value(pending_key, &self.clock.current_time())?;
+        Ok(blob_id)
+    }
+
     fn add_blob_to_batch(&self, blob: &Blob, batch: &mut Batch) -> Result<BlobId, ViewError> {
         #[cfg(with_metrics)]
         WRITE_BLOB_COUNTER.with_label_values(&[]).inc();
-        let blob_id = BlobId(CryptoHash::new(blob));
+        let blob_id = blob.id();
         let blob_key = bcs::to_bytes(&BaseKey::BlobId(blob_id))?;
         batch.put_key_value(blob_key.to_vec(), blob)?;
         Ok(blob_id)
@@ -590,7 +898,115 @@ where
         Ok(())
     }
 
+    /// Returns whether the certificate with the given hash was moved to the cold store. This
+    /// is only checked if there is a cold store to read it from.
+    async fn is_archived(&self, hash: CryptoHash) -> Result<bool, ViewError> {
+        if self.cold_store.is_none() {
+            return Ok(false);
+        }
+        let archived_key = bcs::to_bytes(&BaseKey::ArchivedCertificate(hash))?;
+        Ok(self.client.client.contains_key(&archived_key).await?)
+    }
+
+    /// Reads the certificate with the given hash from the cold store, if it was archived.
+    async fn read_archived_certificate(
+        &self,
+        hash: CryptoHash,
+    ) -> Result<Option<Certificate>, ViewError> {
+        let Some(cold_store) = &self.cold_store else {
+            return Ok(None);
+        };
+        if !self.is_archived(hash).await? {
+            return Ok(None);
+        }
+        let bytes = cold_store
+            .get(&cold_storage::certificate_key(hash))
+            .await?
+            .ok_or(ViewError::MissingEntries)?;
+        #[cfg(with_metrics)]
+        READ_ARCHIVED_CERTIFICATE_COUNTER
+            .with_label_values(&[])
+            .inc();
+        let certificate: Certificate = bcs::from_bytes(&bytes)?;
+        if certificate.hash() != hash {
+            return Err(ViewError::InconsistentEntries);
+        }
+        Ok(Some(certificate))
+    }
+
+    /// Returns the IDs of the chains with a state in the storage, given the `tag` of their
+    /// base keys.
+    async fn chain_ids(&self, tag: &[u8]) -> Result<BTreeSet<ChainId>, ViewError> {
+        let id_length = bcs::serialized_size(&ChainId(CryptoHash::from([0; 4])))?;
+        let mut chain_ids = BTreeSet::new();
+        let keys = self.client.client.find_keys_by_prefix(tag).await?;
+        for key in keys.iterator() {
+            let key = key?;
+            let chain_id =
+                bcs::from_bytes(key.get(..id_length).ok_or(ViewError::InconsistentEntries)?)?;
+            chain_ids.insert(chain_id);
+        }
+        Ok(chain_ids)
+    }
+
+    /// Returns the number of entries whose key starts with `prefix`, and their total size.
+    async fn prefix_usage(&self, prefix: &[u8]) -> Result<(u64, u64), ViewError> {
+        let keys = self
+            .client
+            .client
+            .find_keys_by_prefix(prefix)
+            .await?
+            .iterator()
+            .map(|key| key.map(|key| [prefix, key].concat()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut bytes = 0;
+        for batch in keys.chunks(USAGE_BATCH_SIZE) {
+            let values = self
+                .client
+                .client
+                .read_multi_values_bytes(batch.to_vec())
+                .await?;
+            for (key, value) in batch.iter().zip(values) {
+                bytes += (key.len() + value.map_or(0, |value| value.len())) as u64;
+            }
+        }
+        Ok((keys.len() as u64, bytes))
+    }
+
+    /// Returns the next block heights of the checkpoints of a chain, in increasing order.
+    async fn checkpoint_heights(&self, chain_id: ChainId) -> Result<Vec<BlockHeight>, ViewError> {
+        let prefix = Self::checkpoint_prefix(chain_id)?;
+        let keys = self.client.client.find_keys_by_prefix(&prefix).await?;
+        let keys = keys
+            .iterator()
+            .map(|key| key.map(<[u8]>::to_vec))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::heights_from_keys(keys)
+    }
+
+    /// Returns the sorted heights of the checkpoints whose keys, without the prefix of the
+    /// chain's checkpoints, are given.
+    fn heights_from_keys(keys: Vec<Vec<u8>>) -> Result<Vec<BlockHeight>, ViewError> {
+        let height_length = bcs::serialized_size(&BlockHeight::ZERO)?;
+        let mut heights = BTreeSet::new();
+        for key in keys {
+            let height = key
+                .get(..height_length)
+                .ok_or(ViewError::InconsistentEntries)?;
+            heights.insert(bcs::from_bytes(height)?);
+        }
+        Ok(heights.into_iter().collect())
+    }
+
+    /// Returns the prefix of the keys of all the checkpoints of a chain.
+    fn checkpoint_prefix(chain_id: ChainId) -> Result<Vec<u8>, bcs::Error> {
+        let mut prefix = bcs::to_bytes(&BaseKey::ChainCheckpoint(chain_id, BlockHeight::ZERO))?;
+        prefix.truncate(prefix.len() - bcs::serialized_size(&BlockHeight::ZERO)?);
+        Ok(prefix)
+    }
+
     async fn write_batch(&self, batch: Batch) -> Result<(), ViewError> {
+        trace::record_batch(&batch);
         self.client.client.write_batch(batch, &[]).await?;
         Ok(())
     }
@@ -600,6 +1016,9 @@ where
             client: Arc::new(storage),
             clock,
             execution_runtime_config: ExecutionRuntimeConfig::default(),
+            query_limits: QueryLimits::default(),
+            contract_limits: ContractLimits::default(),
+            cold_store: None,
         }
     }
 }
@@ -4,7 +4,7 @@
 use linera_views::dynamo_db::DynamoDbStore;
 #[cfg(with_testing)]
 use {
-    crate::db_storage::{DbStorageInner, TestClock},
+    crate::{db_storage::DbStorageInner, TestClock},
     linera_execution::WasmRuntime,
     linera_views::{
         dynamo_db::{create_dynamo_db_test_config, DynamoDbContextError, DynamoDbStoreConfig},
@@ -4,6 +4,7 @@
 //! This module defines the storage abstractions for individual chains and certificates.
 
 mod chain_guards;
+mod cold_storage;
 mod db_storage;
 #[cfg(with_dynamodb)]
 mod dynamo_db;
@@ -15,14 +16,18 @@ mod scylla_db;
 #[cfg(not(target_arch = "wasm32"))]
 mod service;
 
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use chain_guards::ChainGuard;
 use dashmap::{mapref::entry::Entry, DashMap};
-use futures::future;
 use linera_base::{
-    crypto::{CryptoHash, PublicKey},
+    crypto::CryptoHash,
     data_types::{Amount, BlockHeight, Timestamp},
     identifiers::{Blob, BlobId, ChainDescription, ChainId, GenericApplicationId},
     ownership::ChainOwnership,
@@ -32,23 +37,27 @@ use linera_chain::{
     ChainError, ChainStateView,
 };
 use linera_execution::{
-    committee::{Committee, Epoch},
+    committee::{Committee, Epoch, ValidatorName},
     system::SystemChannel,
-    ChannelSubscription, ExecutionError, ExecutionRuntimeConfig, ExecutionRuntimeContext,
-    UserApplicationDescription, UserApplicationId, UserContractCode, UserServiceCode, WasmRuntime,
+    Bytecode, ChannelSubscription, ContractLimits, ExecutionError, ExecutionRuntimeConfig,
+    ExecutionRuntimeContext, QueryLimits, UserApplicationDescription, UserApplicationId,
+    UserContractCode, UserServiceCode, WasmRuntime,
 };
 use linera_views::{
     common::Context,
     views::{CryptoHashView, RootView, ViewError},
 };
+use serde::{Deserialize, Serialize};
 #[cfg(with_wasm_runtime)]
 use {
     linera_chain::data_types::CertificateValue,
-    linera_execution::{Operation, SystemOperation, WasmContractModule, WasmServiceModule},
+    linera_execution::{
+        Operation, SystemExecutionError, SystemOperation, WasmContractModule, WasmServiceModule,
+    },
 };
 
-#[cfg(with_testing)]
-pub use crate::db_storage::TestClock;
+#[cfg(with_s3)]
+pub use crate::cold_storage::S3ColdStore;
 #[cfg(with_metrics)]
 pub use crate::db_storage::{
     READ_CERTIFICATE_COUNTER, READ_HASHED_CERTIFICATE_VALUE_COUNTER, WRITE_CERTIFICATE_COUNTER,
@@ -60,12 +69,43 @@ pub use crate::dynamo_db::DynamoDbStorage;
 pub use crate::rocks_db::RocksDbStorage;
 #[cfg(with_scylladb)]
 pub use crate::scylla_db::ScyllaDbStorage;
-#[cfg(not(target_arch = "wasm32"))]
-pub use crate::service::ServiceStorage;
 pub use crate::{
-    db_storage::{Clock, DbStorage, WallClock},
+    cold_storage::{archive_inactive_chains, ArchivalPolicy, ColdStore, MemoryColdStore},
+    db_storage::DbStorage,
     memory::MemoryStorage,
 };
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::{db_storage::value_compression_config, service::ServiceStorage};
+#[cfg(with_testing)]
+pub use linera_base::clock::TestClock;
+pub use linera_base::clock::{Clock, WallClock};
+
+/// The space used in a storage, counting the bytes of both keys and values.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StorageUsage {
+    /// The number of bytes used by the state of each chain.
+    pub chain_state_bytes: BTreeMap<ChainId, u64>,
+    /// The number of certificates.
+    pub certificate_count: u64,
+    /// The number of bytes used by the certificates and the values they certify.
+    pub certificate_bytes: u64,
+    /// The number of certificates moved to the cold store.
+    pub archived_certificate_count: u64,
+    /// The number of blobs.
+    pub blob_count: u64,
+    /// The number of bytes used by the blobs.
+    pub blob_bytes: u64,
+}
+
+/// The entry of a chain in the write-ahead log: the work that a worker accepted but didn't
+/// complete yet, to be resumed if the worker restarts.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalEntry {
+    /// The confirmed certificate that is being executed, if any.
+    pub certificate: Option<CryptoHash>,
+    /// Whether some outgoing messages of the chain are not confirmed as delivered yet.
+    pub pending_deliveries: bool,
+}
 
 /// Communicate with a persistent storage using the "views" abstraction.
 #[async_trait]
@@ -83,11 +123,24 @@ pub trait Storage: Sized {
     /// Returns the current wall clock time.
     fn clock(&self) -> &dyn Clock;
 
-    /// Loads the view of a chain state.
+    /// Loads the view of a chain state. If the state of the chain is missing but the chain
+    /// has checkpoints, e.g. because its state was deleted after a corruption, this fails
+    /// with [`ViewError::MissingEntries`] until the state is restored with
+    /// [`Storage::restore_chain_checkpoint`].
     async fn load_chain(&self, id: ChainId) -> Result<ChainStateView<Self::Context>, ViewError>
     where
         ViewError: From<Self::ContextError>;
 
+    /// Loads the view of a chain state like [`Storage::load_chain`], unless the chain is
+    /// already locked, e.g. because its state is cached by a worker, in which case this
+    /// returns `None` instead of waiting.
+    async fn try_load_chain(
+        &self,
+        id: ChainId,
+    ) -> Result<Option<ChainStateView<Self::Context>>, ViewError>
+    where
+        ViewError: From<Self::ContextError>;
+
     /// Tests existence of a hashed certificate value with the given hash.
     async fn contains_hashed_certificate_value(&self, hash: CryptoHash) -> Result<bool, ViewError>;
 
@@ -116,7 +169,8 @@ pub trait Storage: Sized {
         value: &HashedCertificateValue,
     ) -> Result<(), ViewError>;
 
-    /// Writes the given blob.
+    /// Writes the given blob. It is pending, i.e. it can be deleted by
+    /// [`Storage::collect_garbage_blobs`], until a confirmed block publishes it.
     async fn write_blob(&self, blob: &Blob) -> Result<BlobId, ViewError>;
 
     /// Writes several hashed certificate values
@@ -125,9 +179,17 @@ pub trait Storage: Sized {
         values: &[HashedCertificateValue],
     ) -> Result<(), ViewError>;
 
-    /// Writes several blobs
+    /// Writes several blobs. They are pending until a confirmed block publishes them.
     async fn write_blobs(&self, blobs: &[Blob]) -> Result<(), ViewError>;
 
+    /// Marks the given blobs as published by a confirmed block, so that they are never
+    /// collected as garbage.
+    async fn publish_blobs(&self, blob_ids: &[BlobId]) -> Result<(), ViewError>;
+
+    /// Deletes the blobs that were written more than `lifetime` ago and that no confirmed
+    /// block published, and returns how many were deleted.
+    async fn collect_garbage_blobs(&self, lifetime: Duration) -> Result<u64, ViewError>;
+
     /// Tests existence of the certificate with the given hash.
     async fn contains_certificate(&self, hash: CryptoHash) -> Result<bool, ViewError>;
 
@@ -140,6 +202,85 @@ pub trait Storage: Sized {
     /// Writes a vector of certificates.
     async fn write_certificates(&self, certificate: &[Certificate]) -> Result<(), ViewError>;
 
+    /// Uses the given cold store to archive certificates, and to read the archived ones.
+    fn set_cold_store(&mut self, cold_store: Arc<dyn ColdStore>);
+
+    /// Moves the given certificates to the cold store, only keeping a marker for each of
+    /// them, and returns how many were moved. The certificates that are not in the storage,
+    /// e.g. because they were already archived, are skipped.
+    async fn archive_certificates(&self, hashes: &[CryptoHash]) -> Result<u64, ViewError>;
+
+    /// Walks the whole storage and returns how much space is used by each kind of data.
+    async fn storage_usage(&self) -> Result<StorageUsage, ViewError>;
+
+    /// Returns the IDs of all the chains that have a state in storage.
+    async fn list_chain_ids(&self) -> Result<Vec<ChainId>, ViewError>;
+
+    /// Copies the saved state of a chain into a checkpoint, identified by the next block
+    /// height of the chain, and deletes the oldest checkpoints so that at most
+    /// `max_checkpoints` are kept. The whole state is copied, so the cost grows with the
+    /// size of the chain: if the state exceeds `max_bytes`, no checkpoint is written and
+    /// this returns `false`.
+    async fn write_chain_checkpoint(
+        &self,
+        chain_id: ChainId,
+        next_block_height: BlockHeight,
+        max_checkpoints: usize,
+        max_bytes: u64,
+    ) -> Result<bool, ViewError>;
+
+    /// Replaces the state of a chain with its latest checkpoint, and returns the next block
+    /// height of that checkpoint, if there was one. This rolls back the chain, including the
+    /// votes of its chain manager, so it is only meant to be run by an operator while the
+    /// validator is stopped.
+    async fn restore_chain_checkpoint(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<Option<BlockHeight>, ViewError>;
+
+    /// Returns the next block heights of the checkpoints of a chain, in increasing order.
+    async fn chain_checkpoint_heights(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<Vec<BlockHeight>, ViewError>;
+
+    /// Loads the view of the latest checkpoint of a chain whose next block height is at
+    /// most `next_block_height`, if any. This is the state of the chain after that many
+    /// blocks, read without replaying them. The view must not be saved. Unlike
+    /// [`Storage::load_chain`], this does not wait for the chain to be unlocked.
+    async fn load_chain_checkpoint(
+        &self,
+        chain_id: ChainId,
+        next_block_height: BlockHeight,
+    ) -> Result<Option<ChainStateView<Self::Context>>, ViewError>
+    where
+        ViewError: From<Self::ContextError>;
+
+    /// Writes the entry of a chain in the write-ahead log, replacing the previous one.
+    async fn write_wal_entry(&self, chain_id: ChainId, entry: &WalEntry) -> Result<(), ViewError>;
+
+    /// Removes the entry of a chain from the write-ahead log, once its work is complete.
+    async fn remove_wal_entry(&self, chain_id: ChainId) -> Result<(), ViewError>;
+
+    /// Reads the entries of all chains in the write-ahead log.
+    async fn read_wal_entries(&self) -> Result<BTreeMap<ChainId, WalEntry>, ViewError>;
+
+    /// Reads how many entries of the received log of a chain were already processed, for each
+    /// validator. This lets a client resume the synchronization of received certificates
+    /// after a restart.
+    async fn read_received_certificate_trackers(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<HashMap<ValidatorName, u64>, ViewError>;
+
+    /// Writes how many entries of the received log of a chain were already processed, for
+    /// each validator, replacing the previous values.
+    async fn write_received_certificate_trackers(
+        &self,
+        chain_id: ChainId,
+        trackers: &HashMap<ValidatorName, u64>,
+    ) -> Result<(), ViewError>;
+
     /// Loads the view of a chain state and checks that it is active.
     async fn load_active_chain(
         &self,
@@ -162,20 +303,26 @@ pub trait Storage: Sized {
     where
         Self: Clone + Send + 'static,
     {
-        let mut tasks = Vec::new();
-        for key in keys {
+        // The tasks are aborted if this future is dropped, e.g. because the request that
+        // needed the certificates was cancelled.
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut certs = Vec::new();
+        for (index, key) in keys.into_iter().enumerate() {
             // TODO: remove clone using scoped threads
             let client = self.clone();
-            tasks.push(tokio::task::spawn(async move {
-                client.read_certificate(key).await
+            tasks.spawn(linera_views::trace::propagate(async move {
+                (index, client.read_certificate(key).await)
             }));
+            certs.push(None);
         }
-        let results = future::join_all(tasks).await;
-        let mut certs = Vec::new();
-        for result in results {
-            certs.push(result.expect("storage access should not cancel or crash")?);
+        while let Some(result) = tasks.join_next().await {
+            let (index, result) = result.expect("storage access should not cancel or crash");
+            certs[index] = Some(result?);
         }
-        Ok(certs)
+        Ok(certs
+            .into_iter()
+            .map(|cert| cert.expect("all the certificates should have been read"))
+            .collect())
     }
 
     /// Initializes a chain in a simple way (used for testing and to create a genesis state).
@@ -184,7 +331,7 @@ pub trait Storage: Sized {
         committee: Committee,
         admin_id: ChainId,
         description: ChainDescription,
-        public_key: PublicKey,
+        ownership: ChainOwnership,
         balance: Amount,
         timestamp: Timestamp,
     ) -> Result<(), ChainError>
@@ -196,7 +343,7 @@ pub trait Storage: Sized {
         let mut chain = self.load_chain(id).await?;
         assert!(!chain.is_active(), "Attempting to create a chain twice");
         chain.manager.get_mut().reset(
-            &ChainOwnership::single(public_key),
+            &ownership,
             BlockHeight(0),
             self.clock().current_time(),
             committee.keys_and_weights(),
@@ -209,9 +356,7 @@ pub trait Storage: Sized {
             .committees
             .get_mut()
             .insert(Epoch::ZERO, committee);
-        system_state
-            .ownership
-            .set(ChainOwnership::single(public_key));
+        system_state.ownership.set(ownership);
         system_state.balance.set(balance);
         system_state.timestamp.set(timestamp);
 
@@ -243,19 +388,23 @@ pub trait Storage: Sized {
     fn wasm_runtime(&self) -> Option<WasmRuntime>;
 
     /// Creates a [`UserContractCode`] instance using the bytecode in storage referenced
-    /// by the `application_description`.
+    /// by the `application_description` for the given `version`.
     #[cfg(with_wasm_runtime)]
     async fn load_contract(
         &self,
         application_description: &UserApplicationDescription,
+        version: u32,
     ) -> Result<UserContractCode, ExecutionError> {
         let Some(wasm_runtime) = self.wasm_runtime() else {
             panic!("A Wasm runtime is required to load user applications.");
         };
-        let SystemOperation::PublishBytecode { contract, .. } =
-            read_publish_bytecode_operation(self, application_description).await?
-        else {
-            unreachable!("unexpected bytecode operation");
+        let operation = read_publish_bytecode_operation(self, application_description, version);
+        let contract = match operation.await? {
+            SystemOperation::PublishBytecode { contract, .. } => contract,
+            SystemOperation::PublishBytecodeBlobs { contract, .. } => {
+                read_bytecode_blob(self, contract).await?
+            }
+            _ => unreachable!("unexpected bytecode operation"),
         };
         Ok(Arc::new(
             WasmContractModule::new(contract, wasm_runtime).await?,
@@ -267,6 +416,7 @@ pub trait Storage: Sized {
     async fn load_contract(
         &self,
         _application_description: &UserApplicationDescription,
+        _version: u32,
     ) -> Result<UserContractCode, ExecutionError> {
         panic!(
             "A Wasm runtime is required to load user applications. \
@@ -275,20 +425,48 @@ pub trait Storage: Sized {
         );
     }
 
+    /// Compiles the `contract` and `service` bytecodes of an application and adds them to the
+    /// module caches of the WebAssembly runtime, if any.
+    #[cfg(with_wasm_runtime)]
+    async fn prepare_bytecodes(
+        &self,
+        contract: &Bytecode,
+        service: &Bytecode,
+    ) -> Result<(), ExecutionError> {
+        if let Some(wasm_runtime) = self.wasm_runtime() {
+            WasmContractModule::new(contract.clone(), wasm_runtime).await?;
+            WasmServiceModule::new(service.clone(), wasm_runtime).await?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(with_wasm_runtime))]
+    async fn prepare_bytecodes(
+        &self,
+        _contract: &Bytecode,
+        _service: &Bytecode,
+    ) -> Result<(), ExecutionError> {
+        Ok(())
+    }
+
     /// Creates a [`linera-sdk::UserContract`] instance using the bytecode in storage referenced
-    /// by the `application_description`.
+    /// by the `application_description` for the given `version`.
     #[cfg(with_wasm_runtime)]
     async fn load_service(
         &self,
         application_description: &UserApplicationDescription,
+        version: u32,
     ) -> Result<UserServiceCode, ExecutionError> {
         let Some(wasm_runtime) = self.wasm_runtime() else {
             panic!("A Wasm runtime is required to load user applications.");
         };
-        let SystemOperation::PublishBytecode { service, .. } =
-            read_publish_bytecode_operation(self, application_description).await?
-        else {
-            unreachable!("unexpected bytecode operation");
+        let operation = read_publish_bytecode_operation(self, application_description, version);
+        let service = match operation.await? {
+            SystemOperation::PublishBytecode { service, .. } => service,
+            SystemOperation::PublishBytecodeBlobs { service, .. } => {
+                read_bytecode_blob(self, service).await?
+            }
+            _ => unreachable!("unexpected bytecode operation"),
         };
         Ok(Arc::new(
             WasmServiceModule::new(service, wasm_runtime).await?,
@@ -300,6 +478,7 @@ pub trait Storage: Sized {
     async fn load_service(
         &self,
         _application_description: &UserApplicationDescription,
+        _version: u32,
     ) -> Result<UserServiceCode, ExecutionError> {
         panic!(
             "A Wasm runtime is required to load user applications. \
@@ -313,12 +492,14 @@ pub trait Storage: Sized {
 async fn read_publish_bytecode_operation(
     storage: &impl Storage,
     application_description: &UserApplicationDescription,
+    version: u32,
 ) -> Result<SystemOperation, ExecutionError> {
-    let UserApplicationDescription {
-        bytecode_id,
-        bytecode_location,
-        ..
-    } = application_description;
+    let (bytecode_id, bytecode_location) = application_description
+        .bytecode_at(version)
+        .ok_or_else(|| SystemExecutionError::UnknownApplicationVersion {
+            application_id: Box::new(application_description.into()),
+            version,
+        })?;
     let value = storage
         .read_hashed_certificate_value(bytecode_location.certificate_hash)
         .await
@@ -331,15 +512,30 @@ async fn read_publish_bytecode_operation(
         .into_inner();
     let operations = match value {
         CertificateValue::ConfirmedBlock { executed_block, .. } => executed_block.block.operations,
-        _ => return Err(ExecutionError::InvalidBytecodeId(*bytecode_id)),
+        _ => return Err(ExecutionError::InvalidBytecodeId(bytecode_id)),
     };
     let index = usize::try_from(bytecode_location.operation_index)
         .map_err(|_| linera_base::data_types::ArithmeticError::Overflow)?;
     match operations.into_iter().nth(index) {
-        Some(Operation::System(operation @ SystemOperation::PublishBytecode { .. })) => {
-            Ok(operation)
-        }
-        _ => Err(ExecutionError::InvalidBytecodeId(*bytecode_id)),
+        Some(Operation::System(
+            operation @ (SystemOperation::PublishBytecode { .. }
+            | SystemOperation::PublishBytecodeBlobs { .. }),
+        )) => Ok(operation),
+        _ => Err(ExecutionError::InvalidBytecodeId(bytecode_id)),
+    }
+}
+
+/// Reads a bytecode published in a blob. If it is missing, the returned
+/// [`ExecutionError::BlobNotFound`] lets the worker ask for it, so that it is fetched on demand.
+#[cfg(with_wasm_runtime)]
+async fn read_bytecode_blob(
+    storage: &impl Storage,
+    blob_id: BlobId,
+) -> Result<Bytecode, ExecutionError> {
+    match storage.read_blob(blob_id).await {
+        Ok(blob) => Ok(blob.into()),
+        Err(ViewError::NotFound(_)) => Err(ExecutionError::BlobNotFound(blob_id)),
+        Err(error) => Err(error.into()),
     }
 }
 
@@ -348,9 +544,12 @@ pub struct ChainRuntimeContext<S> {
     storage: S,
     chain_id: ChainId,
     execution_runtime_config: ExecutionRuntimeConfig,
+    query_limits: QueryLimits,
+    contract_limits: ContractLimits,
     user_contracts: Arc<DashMap<UserApplicationId, UserContractCode>>,
     user_services: Arc<DashMap<UserApplicationId, UserServiceCode>>,
-    _chain_guard: Arc<ChainGuard>,
+    /// The guard of the chain, unless the context is only used to read a checkpoint.
+    _chain_guard: Option<Arc<ChainGuard>>,
 }
 
 #[async_trait]
@@ -366,6 +565,14 @@ where
         self.execution_runtime_config
     }
 
+    fn query_limits(&self) -> QueryLimits {
+        self.query_limits
+    }
+
+    fn contract_limits(&self) -> ContractLimits {
+        self.contract_limits
+    }
+
     fn user_contracts(&self) -> &Arc<DashMap<UserApplicationId, UserContractCode>> {
         &self.user_contracts
     }
@@ -377,11 +584,17 @@ where
     async fn get_user_contract(
         &self,
         description: &UserApplicationDescription,
+        version: u32,
     ) -> Result<UserContractCode, ExecutionError> {
+        // Only the original bytecodes are cached by application ID. The upgraded ones are read
+        // from the storage every time, but the module caches avoid compiling them again.
+        if version > 0 {
+            return self.storage.load_contract(description, version).await;
+        }
         match self.user_contracts.entry(description.into()) {
             Entry::Occupied(entry) => Ok(entry.get().clone()),
             Entry::Vacant(entry) => {
-                let contract = self.storage.load_contract(description).await?;
+                let contract = self.storage.load_contract(description, version).await?;
                 entry.insert(contract.clone());
                 Ok(contract)
             }
@@ -391,14 +604,34 @@ where
     async fn get_user_service(
         &self,
         description: &UserApplicationDescription,
+        version: u32,
     ) -> Result<UserServiceCode, ExecutionError> {
+        if version > 0 {
+            return self.storage.load_service(description, version).await;
+        }
         match self.user_services.entry(description.into()) {
             Entry::Occupied(entry) => Ok(entry.get().clone()),
             Entry::Vacant(entry) => {
-                let service = self.storage.load_service(description).await?;
+                let service = self.storage.load_service(description, version).await?;
                 entry.insert(service.clone());
                 Ok(service)
             }
         }
     }
+
+    async fn prepare_bytecodes(
+        &self,
+        contract: &Bytecode,
+        service: &Bytecode,
+    ) -> Result<(), ExecutionError> {
+        self.storage.prepare_bytecodes(contract, service).await
+    }
+
+    async fn get_blob(&self, blob_id: BlobId) -> Result<Blob, ExecutionError> {
+        match self.storage.read_blob(blob_id).await {
+            Ok(blob) => Ok(blob),
+            Err(ViewError::NotFound(_)) => Err(ExecutionError::BlobNotFound(blob_id)),
+            Err(error) => Err(error.into()),
+        }
+    }
 }
@@ -14,7 +14,7 @@ use crate::db_storage::DbStorage;
 pub type MemoryStorage<C> = DbStorage<MemoryStore, C>;
 
 #[cfg(with_testing)]
-use crate::db_storage::TestClock;
+use crate::TestClock;
 
 #[cfg(with_testing)]
 impl MemoryStorage<crate::TestClock> {
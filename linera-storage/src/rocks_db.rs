@@ -4,7 +4,7 @@
 use linera_views::rocks_db::RocksDbStore;
 #[cfg(with_testing)]
 use {
-    crate::db_storage::{DbStorageInner, TestClock},
+    crate::{db_storage::DbStorageInner, TestClock},
     linera_execution::WasmRuntime,
     linera_views::rocks_db::{
         create_rocks_db_test_config, RocksDbContextError, RocksDbStoreConfig,
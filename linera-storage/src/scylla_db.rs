@@ -4,7 +4,7 @@
 use linera_views::scylla_db::ScyllaDbStore;
 #[cfg(with_testing)]
 use {
-    crate::db_storage::{DbStorageInner, TestClock},
+    crate::{db_storage::DbStorageInner, TestClock},
     linera_execution::WasmRuntime,
     linera_views::scylla_db::{
         create_scylla_db_test_config, ScyllaDbContextError, ScyllaDbStoreConfig,
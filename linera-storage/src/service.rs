@@ -4,7 +4,7 @@
 use linera_storage_service::client::ServiceStoreClient;
 #[cfg(with_testing)]
 use {
-    crate::db_storage::{DbStorageInner, TestClock},
+    crate::{db_storage::DbStorageInner, TestClock},
     linera_execution::WasmRuntime,
     linera_storage_service::{
         client::service_config_from_endpoint,
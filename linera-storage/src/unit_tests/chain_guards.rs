@@ -40,6 +40,19 @@ async fn guard_can_be_obtained_later_again() {
     assert!(guards.guard(chain_id).now_or_never().is_some());
 }
 
+/// Tests if a chain guard can only be tried while there's no live guard for the chain.
+#[tokio::test]
+async fn try_guard_fails_while_the_chain_is_guarded() {
+    let chain_id = ChainId::root(0);
+    let guards = ChainGuards::default();
+    let guard = guards.guard(chain_id).await;
+    assert!(guards.try_guard(chain_id).is_none());
+    assert!(guards.try_guard(ChainId::root(1)).is_some());
+    mem::drop(guard);
+    assert!(guards.try_guard(chain_id).is_some());
+    assert_eq!(guards.active_guards(), 0);
+}
+
 /// Tests if two tasks obtaining a guard for the same chain obtain them sequentially.
 #[tokio::test(start_paused = true)]
 async fn prevents_concurrent_access_to_the_same_chain() {
@@ -0,0 +1,136 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{sync::Arc, time::Duration};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Round, Timestamp},
+    identifiers::ChainId,
+};
+use linera_chain::test::{make_certificate, make_child_block, make_first_block, BlockTestExt as _};
+use linera_views::views::{RootView as _, ViewError};
+
+use super::{archive_inactive_chains, ArchivalPolicy, MemoryColdStore};
+use crate::{MemoryStorage, Storage, TestClock};
+
+/// One day, in microseconds.
+const DAY: u64 = 24 * 60 * 60 * 1_000_000;
+
+/// Writes the certificates of the first `count` blocks of a chain, all at `timestamp`, and
+/// returns their hashes.
+async fn write_chain(
+    storage: &MemoryStorage<TestClock>,
+    chain_id: ChainId,
+    count: usize,
+    timestamp: Timestamp,
+) -> Result<Vec<CryptoHash>, ViewError> {
+    let mut chain = storage.load_chain(chain_id).await?;
+    let mut block = make_first_block(chain_id).with_timestamp(timestamp);
+    let mut hashes = Vec::new();
+    for _ in 0..count {
+        let certificate = make_certificate(block, Vec::new(), Round::Fast, &[]);
+        storage.write_certificate(&certificate).await?;
+        chain.confirmed_log.push(certificate.hash());
+        hashes.push(certificate.hash());
+        block = make_child_block(&certificate.value);
+    }
+    chain.execution_state.system.timestamp.set(timestamp);
+    chain.save().await?;
+    Ok(hashes)
+}
+
+#[tokio::test]
+async fn test_archived_certificates_are_read_from_the_cold_store() -> Result<(), anyhow::Error> {
+    let mut storage = MemoryStorage::make_test_storage(None).await;
+    let cold_store = Arc::new(MemoryColdStore::default());
+    storage.set_cold_store(cold_store.clone());
+    let certificate = make_certificate(
+        make_first_block(ChainId::root(0)),
+        Vec::new(),
+        Round::Fast,
+        &[],
+    );
+    let hash = certificate.hash();
+    storage.write_certificate(&certificate).await?;
+
+    assert_eq!(storage.archive_certificates(&[hash]).await?, 1);
+    assert_eq!(cold_store.object_count(), 1);
+    let usage = storage.storage_usage().await?;
+    assert_eq!(usage.certificate_count, 0);
+    assert_eq!(usage.archived_certificate_count, 1);
+
+    assert!(storage.contains_certificate(hash).await?);
+    assert!(storage.contains_hashed_certificate_value(hash).await?);
+    assert_eq!(storage.read_certificate(hash).await?, certificate);
+    assert_eq!(
+        storage.read_hashed_certificate_value(hash).await?,
+        certificate.value
+    );
+    assert_eq!(storage.archive_certificates(&[hash]).await?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_archived_certificates_need_a_cold_store() -> Result<(), anyhow::Error> {
+    let storage = MemoryStorage::make_test_storage(None).await;
+    let certificate = make_certificate(
+        make_first_block(ChainId::root(0)),
+        Vec::new(),
+        Round::Fast,
+        &[],
+    );
+    storage.write_certificate(&certificate).await?;
+    assert!(storage
+        .archive_certificates(&[certificate.hash()])
+        .await
+        .is_err());
+    assert_eq!(storage.storage_usage().await?.certificate_count, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_the_old_certificates_of_inactive_chains_are_archived(
+) -> Result<(), anyhow::Error> {
+    let mut storage = MemoryStorage::make_test_storage(None).await;
+    storage.set_cold_store(Arc::new(MemoryColdStore::default()));
+    let inactive = write_chain(&storage, ChainId::root(0), 3, Timestamp::from(0)).await?;
+    write_chain(&storage, ChainId::root(1), 3, Timestamp::from(DAY)).await?;
+    storage.clock.set(Timestamp::from(DAY + 1));
+    let policy = ArchivalPolicy {
+        inactivity: Duration::from_micros(DAY),
+        keep_latest: 1,
+    };
+
+    assert_eq!(archive_inactive_chains(&storage, &policy).await?, 2);
+    let usage = storage.storage_usage().await?;
+    assert_eq!(usage.certificate_count, 4);
+    assert_eq!(usage.archived_certificate_count, 2);
+    // Only the latest certificate of the inactive chain is left in the storage.
+    assert_eq!(storage.archive_certificates(&inactive).await?, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_locked_chains_are_not_archived() -> Result<(), anyhow::Error> {
+    let mut storage = MemoryStorage::make_test_storage(None).await;
+    storage.set_cold_store(Arc::new(MemoryColdStore::default()));
+    let locked = write_chain(&storage, ChainId::root(0), 3, Timestamp::from(0)).await?;
+    write_chain(&storage, ChainId::root(1), 3, Timestamp::from(0)).await?;
+    storage.clock.set(Timestamp::from(DAY + 1));
+    let policy = ArchivalPolicy {
+        inactivity: Duration::from_micros(DAY),
+        keep_latest: 1,
+    };
+
+    // The state of chain 0 is held, e.g. by the chain state cache of a worker.
+    let chain = storage.load_chain(ChainId::root(0)).await?;
+    assert_eq!(archive_inactive_chains(&storage, &policy).await?, 2);
+    assert_eq!(storage.storage_usage().await?.archived_certificate_count, 2);
+
+    // Once it is released, it is archived by the next run.
+    drop(chain);
+    assert_eq!(archive_inactive_chains(&storage, &policy).await?, 2);
+    assert_eq!(storage.archive_certificates(&locked).await?, 1);
+    Ok(())
+}
@@ -21,6 +21,8 @@ fn main() {
         rpc_hash,
         graphql_hash,
         wit_hash,
+        build_timestamp,
+        features,
     } = {
         let mut paths = vec![];
         let version_info = VersionInfo::trace_get(
@@ -32,10 +34,14 @@ fn main() {
         for path in paths {
             println!("cargo:rerun-if-changed={}", path.display());
         }
+        println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+        println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
 
         version_info
     };
 
+    let features = features.iter().map(|feature| &**feature);
+
     let static_code = quote::quote! {
         VersionInfo {
             crate_version: crate::serde_pretty::Pretty::new(
@@ -46,6 +52,10 @@ fn main() {
             rpc_hash: ::std::borrow::Cow::Borrowed(#rpc_hash),
             graphql_hash: ::std::borrow::Cow::Borrowed(#graphql_hash),
             wit_hash: ::std::borrow::Cow::Borrowed(#wit_hash),
+            build_timestamp: #build_timestamp,
+            features: ::std::borrow::Cow::Borrowed(
+                &[#(::std::borrow::Cow::Borrowed(#features)),*],
+            ),
         }
     };
 
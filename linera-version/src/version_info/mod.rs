@@ -8,6 +8,14 @@ pub static VERSION_INFO: VersionInfo = include!(env!("LINERA_VERSION_STATIC_PATH
 
 use crate::serde_pretty::Pretty;
 
+#[async_graphql::ComplexObject]
+impl VersionInfo {
+    /// The optional features enabled in the build
+    async fn features(&self) -> &[Hash] {
+        &self.features
+    }
+}
+
 impl std::fmt::Display for VersionInfo {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
@@ -74,6 +82,36 @@ impl VersionInfo {
         STRING.as_str()
     }
 
+    /// A human-readable listing of the version information, followed by how this build was
+    /// made.
+    pub fn verbose(&self) -> String {
+        let features = if self.features.is_empty() {
+            "none".to_owned()
+        } else {
+            self.features.join(", ")
+        };
+        format!(
+            "\
+            {self}\
+            Build timestamp: {build_timestamp} (seconds since the Unix epoch)\n\
+            Features: {features}\n\
+            ",
+            build_timestamp = self.build_timestamp,
+        )
+    }
+
+    /// Prints the verbose version information and exits if the process was started with
+    /// `--version --verbose`, in any order. This must be called before parsing the command
+    /// line with `clap`, which only handles `--version` on its own.
+    pub fn handle_verbose_version_flag() {
+        let mut arguments = std::env::args().skip(1).collect::<Vec<_>>();
+        arguments.sort();
+        if arguments == ["--verbose", "--version"] {
+            print!("{}", VersionInfo::default().verbose());
+            std::process::exit(0);
+        }
+    }
+
     fn api_hashes(&self) -> (&Hash, &Hash, &Hash) {
         (&self.rpc_hash, &self.graphql_hash, &self.wit_hash)
     }
@@ -47,7 +47,8 @@ pub type Hash = std::borrow::Cow<'static, str>;
 
 #[cfg_attr(
     linera_version_building,
-    derive(async_graphql::SimpleObject, serde::Deserialize, serde::Serialize)
+    derive(async_graphql::SimpleObject, serde::Deserialize, serde::Serialize),
+    graphql(complex)
 )]
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 /// The version info of a build of Linera.
@@ -64,6 +65,11 @@ pub struct VersionInfo {
     pub graphql_hash: Hash,
     /// A hash of the WIT API
     pub wit_hash: Hash,
+    /// When the build's source code was last changed, in seconds since the Unix epoch
+    pub build_timestamp: u64,
+    /// The optional features enabled in the build
+    #[cfg_attr(linera_version_building, graphql(skip))]
+    pub features: std::borrow::Cow<'static, [Hash]>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -78,6 +84,8 @@ pub enum Error {
     Glob(#[from] glob::GlobError),
     #[error("pattern error: {0}")]
     Pattern(#[from] glob::PatternError),
+    #[error("invalid build timestamp: {0}")]
+    Timestamp(#[from] std::num::ParseIntError),
 }
 
 struct Outcome {
@@ -132,6 +140,35 @@ fn run(cmd: &str, args: &[&str]) -> Result<Outcome, Error> {
     })
 }
 
+/// Returns when the source code was last changed, which unlike the current time doesn't
+/// prevent builds from being reproducible. `SOURCE_DATE_EPOCH` takes precedence over the
+/// time of the current git commit, following <https://reproducible-builds.org/specs/source-date-epoch/>.
+fn get_build_timestamp() -> Result<u64, Error> {
+    if let Ok(timestamp) = std::env::var("SOURCE_DATE_EPOCH") {
+        return Ok(timestamp.trim().parse()?);
+    }
+    let git_outcome = run("git", &["log", "-1", "--format=%ct"])?;
+    if git_outcome.status.success() {
+        Ok(git_outcome.output.trim().parse()?)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Returns the features of this crate enabled in the build, which are the features of the
+/// Linera crates that enable them, since Cargo unifies the features of a dependency across the
+/// whole build. They are only known in a build script.
+fn get_features() -> Vec<String> {
+    let mut features = std::env::vars()
+        .filter_map(|(name, _)| {
+            let feature = name.strip_prefix("CARGO_FEATURE_")?;
+            Some(feature.to_lowercase().replace('_', "-"))
+        })
+        .collect::<Vec<_>>();
+    features.sort();
+    features
+}
+
 fn get_package<'r>(
     metadata: &'r cargo_metadata::Metadata,
     package_name: &str,
@@ -204,6 +241,10 @@ impl VersionInfo {
 
         let wit_hash = get_hash(paths, &metadata, "linera-sdk", "*.wit")?.into();
 
+        let build_timestamp = get_build_timestamp()?;
+
+        let features = get_features().into_iter().map(Hash::from).collect();
+
         Ok(Self {
             crate_version,
             git_commit,
@@ -211,6 +252,8 @@ impl VersionInfo {
             rpc_hash,
             graphql_hash,
             wit_hash,
+            build_timestamp,
+            features,
         })
     }
 }
@@ -259,10 +259,17 @@ fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
 
     let mut field_hashes_mut = Vec::new();
     let mut field_hashes = Vec::new();
+    let mut field_names = Vec::new();
+    let mut field_hash_values = Vec::new();
     for e in input.fields {
         let name = e.clone().ident.unwrap();
         field_hashes_mut.push(quote! { hasher.write_all(self.#name.hash_mut().await?.as_ref())?; });
         field_hashes.push(quote! { hasher.write_all(self.#name.hash().await?.as_ref())?; });
+        field_names.push(name.to_string());
+        field_hash_values.push(quote! {
+            let hash = self.#name.hash().await?;
+            field_hashes.push(linera_views::common::HasherOutput::clone_from_slice(hash.as_ref()));
+        });
     }
 
     quote! {
@@ -288,6 +295,20 @@ fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
                 Ok(hasher.finalize())
             }
         }
+
+        #[linera_views::async_trait]
+        impl #impl_generics linera_views::merkle::FieldHashes for #struct_name #type_generics
+        #where_clause
+        {
+            const FIELD_NAMES: &'static [&'static str] = &[#(#field_names),*];
+
+            async fn field_hashes(&self) -> Result<Vec<linera_views::common::HasherOutput>, linera_views::views::ViewError> {
+                use linera_views::views::HashableView;
+                let mut field_hashes = Vec::new();
+                #(#field_hash_values)*
+                Ok(field_hashes)
+            }
+        }
     }
 }
 
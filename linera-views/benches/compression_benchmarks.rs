@@ -0,0 +1,72 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use linera_views::{
+    batch::Batch,
+    common::KeyValueStore,
+    compression::{CompressionConfig, CompressionStore},
+    memory::create_memory_store,
+};
+use tokio::runtime::Runtime;
+
+/// The number of values written and read by each iteration.
+const NUM_VALUES: u8 = 100;
+
+/// The size of each value.
+const VALUE_SIZE: usize = 64 * 1024;
+
+/// Returns a value resembling serialized blocks or bytecode: repetitive, with some noise.
+fn make_value(seed: u8) -> Vec<u8> {
+    let mut state = u32::from(seed) + 1;
+    (0..VALUE_SIZE)
+        .map(|index| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            if index % 8 == 0 {
+                state as u8
+            } else {
+                (index / 64) as u8
+            }
+        })
+        .collect()
+}
+
+/// Writes the values in one batch, then reads them back.
+async fn write_and_read_values<S: KeyValueStore>(store: &S, values: &[Vec<u8>]) {
+    let mut batch = Batch::new();
+    for (index, value) in (0..NUM_VALUES).zip(values) {
+        batch.put_key_value_bytes(vec![index], value.clone());
+    }
+    store.write_batch(batch, &[]).await.unwrap();
+    let keys = (0..NUM_VALUES).map(|index| vec![index]).collect();
+    let read_values = store.read_multi_values_bytes(keys).await.unwrap();
+    assert_eq!(read_values.len(), values.len());
+}
+
+fn bench_compression(criterion: &mut Criterion) {
+    let values = (0..NUM_VALUES).map(make_value).collect::<Vec<_>>();
+    let mut group = criterion.benchmark_group("compression");
+    group.bench_function("uncompressed", |bencher| {
+        bencher
+            .to_async(Runtime::new().unwrap())
+            .iter(|| async { write_and_read_values(&create_memory_store(), &values).await })
+    });
+    for level in [1, 3, 9] {
+        let config = CompressionConfig {
+            key_prefixes: vec![(Vec::new(), level)],
+            ..CompressionConfig::default()
+        };
+        group.bench_function(format!("zstd_level_{level}"), |bencher| {
+            bencher.to_async(Runtime::new().unwrap()).iter(|| async {
+                let store = CompressionStore::new(create_memory_store(), config.clone());
+                write_and_read_values(&store, &values).await
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_compression);
+criterion_main!(benches);
@@ -622,11 +622,41 @@ impl BatchValueWriter<UnorderedBatch> for UnorderedBatchIter {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use linera_views::{
-        batch::{Batch, SimpleUnorderedBatch, UnorderedBatch},
+        batch::{Batch, SimpleUnorderedBatch, UnorderedBatch, WriteOperation},
         common::Context,
         memory::create_memory_context,
     };
+    use proptest::{collection::vec, prelude::*};
+    use test_strategy::proptest;
+
+    /// Generates write operations on a few short keys, so that they often overlap.
+    fn write_operation() -> impl Strategy<Value = WriteOperation> {
+        let key = || vec(0u8..3, 0..4);
+        prop_oneof![
+            key().prop_map(|key| WriteOperation::Delete { key }),
+            key().prop_map(|key_prefix| WriteOperation::DeletePrefix { key_prefix }),
+            (key(), vec(any::<u8>(), 0..4))
+                .prop_map(|(key, value)| WriteOperation::Put { key, value }),
+        ]
+    }
+
+    /// Applies a write operation to a model of the storage.
+    fn apply(store: &mut BTreeMap<Vec<u8>, Vec<u8>>, operation: WriteOperation) {
+        match operation {
+            WriteOperation::Delete { key } => {
+                store.remove(&key);
+            }
+            WriteOperation::DeletePrefix { key_prefix } => {
+                store.retain(|key, _| !key.starts_with(&key_prefix));
+            }
+            WriteOperation::Put { key, value } => {
+                store.insert(key, value);
+            }
+        }
+    }
 
     #[test]
     fn test_simplify_batch1() {
@@ -727,4 +757,37 @@ mod tests {
         );
         assert!(unordered_batch.key_prefix_deletions.is_empty());
     }
+
+    /// Tests that simplifying an arbitrary batch does not change its effect on the storage.
+    #[proptest]
+    fn simplified_batch_has_the_same_effect(
+        #[strategy(vec((vec(0u8..3, 0..4), vec(any::<u8>(), 0..4)), 0..8))] initial: Vec<(
+            Vec<u8>,
+            Vec<u8>,
+        )>,
+        #[strategy(vec(write_operation(), 0..16))] operations: Vec<WriteOperation>,
+    ) {
+        let initial = initial.into_iter().collect::<BTreeMap<_, _>>();
+        let mut expected = initial.clone();
+        for operation in operations.clone() {
+            apply(&mut expected, operation);
+        }
+
+        let unordered_batch = Batch { operations }.simplify();
+        let mut actual = initial;
+        for key_prefix in unordered_batch.key_prefix_deletions {
+            apply(&mut actual, WriteOperation::DeletePrefix { key_prefix });
+        }
+        let SimpleUnorderedBatch {
+            deletions,
+            insertions,
+        } = unordered_batch.simple_unordered_batch;
+        for key in deletions {
+            apply(&mut actual, WriteOperation::Delete { key });
+        }
+        for (key, value) in insertions {
+            apply(&mut actual, WriteOperation::Put { key, value });
+        }
+        prop_assert_eq!(actual, expected);
+    }
 }
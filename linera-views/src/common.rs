@@ -24,7 +24,11 @@ use std::{
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{batch::Batch, views::ViewError};
+use crate::{
+    batch::Batch,
+    trace::{self, StorageAccess},
+    views::ViewError,
+};
 
 #[cfg(test)]
 #[path = "unit_tests/common_tests.rs"]
@@ -357,6 +361,36 @@ pub trait LocalReadableKeyValueStore<E> {
             Ok(values)
         }
     }
+
+    /// Finds up to `limit` keys matching the prefix that are not smaller than `start`, in
+    /// increasing order. Neither `start` nor the returned keys include the prefix. This
+    /// reads all the keys matching the prefix, unless the store can seek to `start`.
+    fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<Vec<u8>>, E>>
+    where
+        Self: Sync,
+    {
+        async move {
+            let mut keys = Vec::new();
+            if limit == 0 {
+                return Ok(keys);
+            }
+            for key in self.find_keys_by_prefix(key_prefix).await?.iterator() {
+                let key = key?;
+                if key >= start {
+                    keys.push(key.to_vec());
+                    if keys.len() == limit {
+                        break;
+                    }
+                }
+            }
+            Ok(keys)
+        }
+    }
 }
 
 /// Low-level, asynchronous write key-value operations. Useful for storage APIs not based on views.
@@ -582,6 +616,15 @@ pub trait Context: Clone {
         key_prefix: &[u8],
     ) -> Result<Self::KeyValues, Self::Error>;
 
+    /// Finds up to `limit` keys matching the `key_prefix` that are not smaller than `start`,
+    /// in increasing order. Neither `start` nor the returned keys include the `key_prefix`.
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error>;
+
     /// Applies the operations from the `batch`, persisting the changes.
     async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error>;
 
@@ -762,10 +805,12 @@ where
     }
 
     async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        trace::record_access(|| StorageAccess::Read(key.to_vec()));
         log_time_async(self.store.read_value_bytes(key), "read_value_bytes").await
     }
 
     async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        trace::record_access(|| StorageAccess::Read(key.to_vec()));
         log_time_async(self.store.contains_key(key), "contains_key").await
     }
 
@@ -773,6 +818,9 @@ where
         &self,
         keys: Vec<Vec<u8>>,
     ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        for key in &keys {
+            trace::record_access(|| StorageAccess::Read(key.clone()));
+        }
         log_time_async(
             self.store.read_multi_values_bytes(keys),
             "read_multi_values_bytes",
@@ -781,6 +829,7 @@ where
     }
 
     async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        trace::record_access(|| StorageAccess::FindKeys(key_prefix.to_vec()));
         log_time_async(
             self.store.find_keys_by_prefix(key_prefix),
             "find_keys_by_prefix",
@@ -792,6 +841,7 @@ where
         &self,
         key_prefix: &[u8],
     ) -> Result<Self::KeyValues, Self::Error> {
+        trace::record_access(|| StorageAccess::FindKeyValues(key_prefix.to_vec()));
         log_time_async(
             self.store.find_key_values_by_prefix(key_prefix),
             "find_key_values_by_prefix",
@@ -799,7 +849,23 @@ where
         .await
     }
 
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        trace::record_access(|| StorageAccess::FindKeys(key_prefix.to_vec()));
+        log_time_async(
+            self.store
+                .find_keys_by_prefix_from(key_prefix, start, limit),
+            "find_keys_by_prefix_from",
+        )
+        .await
+    }
+
     async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        trace::record_batch(&batch);
         log_time_async(self.store.write_batch(batch, &self.base_key), "write_batch").await
     }
 
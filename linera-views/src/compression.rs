@@ -0,0 +1,359 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transparent compression of the values of a key-value store.
+//!
+//! The [`CompressionStore`] compresses the values written under the configured key prefixes
+//! with Zstandard, e.g. the certificates and the bytecode of a validator, which are large and
+//! compress well. The values under these prefixes start with a tag byte telling whether the
+//! rest is compressed, so that they are never misread. The values under the other prefixes
+//! are stored and read as they are.
+//!
+//! The values written under a prefix before compression was enabled for it have no tag: they
+//! must be converted with [`rewrite_values`] before the store is used with compression. Its
+//! progress is recorded in the store, so it can be resumed or run again safely, and
+//! [`check_values_converted`] refuses the stores whose conversion is not complete.
+
+use std::{io::Write as _, sync::Arc};
+
+use crate::{
+    batch::{Batch, WriteOperation},
+    common::{
+        KeyIterable, KeyValueIterable, KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
+    },
+    value_splitting::DatabaseConsistencyError,
+};
+
+/// The tag of the values under a compressed prefix that are stored as they are, e.g.
+/// because they are too small to shrink.
+const UNCOMPRESSED_TAG: u8 = 0;
+
+/// The tag of the values under a compressed prefix that are stored as a Zstandard frame.
+const ZSTD_TAG: u8 = 1;
+
+/// The number of values read and written together by [`rewrite_values`].
+const REWRITE_BATCH_SIZE: usize = 1000;
+
+/// The prefix of the keys recording the progress of [`rewrite_values`], followed by the
+/// rewritten key prefix. No other key of the store may start with it.
+pub const REWRITE_PROGRESS_KEY_PREFIX: &[u8] = b"\xffrewrite_values";
+
+/// The tag of the progress of [`rewrite_values`] once all the values of a prefix are
+/// converted.
+const REWRITE_DONE_TAG: u8 = 0;
+
+/// The tag of the progress of [`rewrite_values`] while it is converting the values of a
+/// prefix, followed by the last converted key.
+const REWRITE_IN_PROGRESS_TAG: u8 = 1;
+
+/// The configuration of a [`CompressionStore`].
+#[derive(Clone, Debug)]
+pub struct CompressionConfig {
+    /// The Zstandard compression level of the values whose keys start with each prefix. If
+    /// several prefixes match a key, the longest one applies. The values of the other keys
+    /// are not compressed.
+    pub key_prefixes: Vec<(Vec<u8>, i32)>,
+    /// The values smaller than this are not compressed, as they would barely shrink.
+    pub min_value_size: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            key_prefixes: Vec::new(),
+            min_value_size: 128,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Returns whether no value is compressed.
+    pub fn is_empty(&self) -> bool {
+        self.key_prefixes.is_empty()
+    }
+
+    /// Returns the compression level of the value with the key `key_prefix` followed by
+    /// `key`, if it is under a compressed prefix.
+    fn level(&self, key_prefix: &[u8], key: &[u8]) -> Option<i32> {
+        self.key_prefixes
+            .iter()
+            .filter(|(prefix, _)| {
+                if prefix.len() <= key_prefix.len() {
+                    key_prefix.starts_with(prefix)
+                } else {
+                    prefix.starts_with(key_prefix) && key.starts_with(&prefix[key_prefix.len()..])
+                }
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+    }
+}
+
+/// A key-value store that compresses the values under some key prefixes. Without any
+/// configured prefix, all the operations are passed to the inner store unchanged.
+#[derive(Clone)]
+pub struct CompressionStore<K> {
+    /// The inner store.
+    pub store: K,
+    config: Arc<CompressionConfig>,
+}
+
+impl<K> ReadableKeyValueStore<K::Error> for CompressionStore<K>
+where
+    K: KeyValueStore + Send + Sync,
+    K::Error: From<DatabaseConsistencyError>,
+{
+    const MAX_KEY_SIZE: usize = K::MAX_KEY_SIZE;
+    type Keys = K::Keys;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, K::Error> {
+        let value = self.store.read_value_bytes(key).await?;
+        Ok(value
+            .map(|value| self.decompress(key, &[], value))
+            .transpose()?)
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, K::Error> {
+        self.store.contains_key(key).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, K::Error> {
+        if self.config.is_empty() {
+            return self.store.read_multi_values_bytes(keys).await;
+        }
+        let values = self.store.read_multi_values_bytes(keys.clone()).await?;
+        let values = keys
+            .iter()
+            .zip(values)
+            .map(|(key, value)| {
+                value
+                    .map(|value| self.decompress(key, &[], value))
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(values)
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, K::Error> {
+        self.store.find_keys_by_prefix(key_prefix).await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, K::Error> {
+        let key_values = self.store.find_key_values_by_prefix(key_prefix).await?;
+        let mut decompressed_key_values = Vec::new();
+        for key_value in key_values.into_iterator_owned() {
+            let (key, value) = key_value?;
+            let value = self.decompress(key_prefix, &key, value)?;
+            decompressed_key_values.push((key, value));
+        }
+        Ok(decompressed_key_values)
+    }
+
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, K::Error> {
+        self.store
+            .find_keys_by_prefix_from(key_prefix, start, limit)
+            .await
+    }
+}
+
+impl<K> WritableKeyValueStore<K::Error> for CompressionStore<K>
+where
+    K: KeyValueStore + Send + Sync,
+    K::Error: From<DatabaseConsistencyError>,
+{
+    // The values under a compressed prefix take one more byte, for their tag, if they don't
+    // shrink: this store is meant to be used above a `ValueSplittingStore`.
+    const MAX_VALUE_SIZE: usize = K::MAX_VALUE_SIZE - 1;
+
+    async fn write_batch(&self, batch: Batch, base_key: &[u8]) -> Result<(), K::Error> {
+        if self.config.is_empty() {
+            return self.store.write_batch(batch, base_key).await;
+        }
+        let mut compressed_batch = Batch::new();
+        for operation in batch.operations {
+            match operation {
+                WriteOperation::Put { key, value } => {
+                    let value = self.compress(&key, value)?;
+                    compressed_batch.put_key_value_bytes(key, value);
+                }
+                operation => compressed_batch.operations.push(operation),
+            }
+        }
+        self.store.write_batch(compressed_batch, base_key).await
+    }
+
+    async fn clear_journal(&self, base_key: &[u8]) -> Result<(), K::Error> {
+        self.store.clear_journal(base_key).await
+    }
+}
+
+impl<K> KeyValueStore for CompressionStore<K>
+where
+    K: KeyValueStore + Send + Sync,
+    K::Error: From<DatabaseConsistencyError>,
+{
+    type Error = K::Error;
+}
+
+impl<K> CompressionStore<K>
+where
+    K: KeyValueStore + Send + Sync,
+    K::Error: From<DatabaseConsistencyError>,
+{
+    /// Creates a new key-value store that compresses the values written to the given store.
+    pub fn new(store: K, config: CompressionConfig) -> Self {
+        CompressionStore {
+            store,
+            config: Arc::new(config),
+        }
+    }
+
+    /// Returns the value to store for the given key and value.
+    fn compress(&self, key: &[u8], value: Vec<u8>) -> Result<Vec<u8>, DatabaseConsistencyError> {
+        let Some(level) = self.config.level(key, &[]) else {
+            return Ok(value);
+        };
+        if value.len() >= self.config.min_value_size {
+            let compressed_value = compress(&value, level)?;
+            if compressed_value.len() < value.len() {
+                return Ok([&[ZSTD_TAG], compressed_value.as_slice()].concat());
+            }
+        }
+        Ok([&[UNCOMPRESSED_TAG], value.as_slice()].concat())
+    }
+
+    /// Returns the original value of the value stored with the key `key_prefix` followed by
+    /// `key`.
+    fn decompress(
+        &self,
+        key_prefix: &[u8],
+        key: &[u8],
+        value: Vec<u8>,
+    ) -> Result<Vec<u8>, DatabaseConsistencyError> {
+        if self.config.level(key_prefix, key).is_none() {
+            return Ok(value);
+        }
+        match value.split_first() {
+            Some((&UNCOMPRESSED_TAG, rest)) => Ok(rest.to_vec()),
+            Some((&ZSTD_TAG, rest)) => zstd::stream::decode_all(rest)
+                .map_err(|_| DatabaseConsistencyError::InvalidCompressedValue),
+            _ => Err(DatabaseConsistencyError::InvalidCompressedValue),
+        }
+    }
+}
+
+/// Compresses a value as a Zstandard frame, with a checksum of its content.
+fn compress(value: &[u8], level: i32) -> Result<Vec<u8>, DatabaseConsistencyError> {
+    let encode = || {
+        let mut encoder = zstd::stream::Encoder::new(Vec::new(), level)?;
+        encoder.include_checksum(true)?;
+        encoder.write_all(value)?;
+        encoder.finish()
+    };
+    encode().map_err(|_| DatabaseConsistencyError::InvalidCompressedValue)
+}
+
+/// Converts the values whose keys start with `key_prefix` that were written to the inner
+/// store before compression was enabled for that prefix, and returns how many were converted.
+///
+/// This must be done before the values are read through the [`CompressionStore`]. The
+/// progress is recorded under [`REWRITE_PROGRESS_KEY_PREFIX`] in the same batches as the
+/// converted values: if the conversion is interrupted, running it again resumes it, and once
+/// it is complete, running it again does nothing.
+pub async fn rewrite_values<K>(
+    store: &CompressionStore<K>,
+    key_prefix: &[u8],
+) -> Result<usize, K::Error>
+where
+    K: KeyValueStore + Send + Sync,
+    K::Error: From<DatabaseConsistencyError>,
+{
+    let progress_key = [REWRITE_PROGRESS_KEY_PREFIX, key_prefix].concat();
+    let last_key = match store.store.read_value_bytes(&progress_key).await? {
+        None => None,
+        Some(progress) => match progress.split_first() {
+            Some((&REWRITE_DONE_TAG, [])) => return Ok(0),
+            Some((&REWRITE_IN_PROGRESS_TAG, last_key)) => Some(last_key.to_vec()),
+            _ => return Err(DatabaseConsistencyError::InvalidRewriteProgress.into()),
+        },
+    };
+    let mut keys = store
+        .store
+        .find_keys_by_prefix(key_prefix)
+        .await?
+        .iterator()
+        .map(|key| key.map(|key| [key_prefix, key].concat()))
+        .collect::<Result<Vec<_>, _>>()?;
+    keys.retain(|key| {
+        !key.starts_with(REWRITE_PROGRESS_KEY_PREFIX)
+            && last_key.as_ref().map_or(true, |last_key| key > last_key)
+    });
+    keys.sort();
+    for keys in keys.chunks(REWRITE_BATCH_SIZE) {
+        let values = store.store.read_multi_values_bytes(keys.to_vec()).await?;
+        let mut batch = Batch::new();
+        for (key, value) in keys.iter().zip(values) {
+            if let Some(value) = value {
+                batch.put_key_value_bytes(key.clone(), store.compress(key, value)?);
+            }
+        }
+        let last_key = keys.last().expect("chunks are not empty");
+        let progress = [&[REWRITE_IN_PROGRESS_TAG], last_key.as_slice()].concat();
+        batch.put_key_value_bytes(progress_key.clone(), progress);
+        store.store.write_batch(batch, &[]).await?;
+    }
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(progress_key, vec![REWRITE_DONE_TAG]);
+    store.store.write_batch(batch, &[]).await?;
+    Ok(keys.len())
+}
+
+/// Checks that the values under each compressed prefix were converted by [`rewrite_values`],
+/// so that none of them is misread. A prefix without any value is marked as converted, since
+/// all the values written to it from now on are tagged.
+pub async fn check_values_converted<K>(store: &CompressionStore<K>) -> Result<(), K::Error>
+where
+    K: KeyValueStore + Send + Sync,
+    K::Error: From<DatabaseConsistencyError>,
+{
+    for (key_prefix, _) in &store.config.key_prefixes {
+        let progress_key = [REWRITE_PROGRESS_KEY_PREFIX, key_prefix].concat();
+        match store.store.read_value_bytes(&progress_key).await? {
+            None => {}
+            Some(progress) if progress == [REWRITE_DONE_TAG] => continue,
+            Some(progress) if progress.first() == Some(&REWRITE_IN_PROGRESS_TAG) => {
+                return Err(DatabaseConsistencyError::UnconvertedValues(key_prefix.clone()).into())
+            }
+            Some(_) => return Err(DatabaseConsistencyError::InvalidRewriteProgress.into()),
+        }
+        let keys = store.store.find_keys_by_prefix(key_prefix).await?;
+        for key in keys.iterator() {
+            if ![key_prefix, key?]
+                .concat()
+                .starts_with(REWRITE_PROGRESS_KEY_PREFIX)
+            {
+                return Err(DatabaseConsistencyError::UnconvertedValues(key_prefix.clone()).into());
+            }
+        }
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(progress_key, vec![REWRITE_DONE_TAG]);
+        store.store.write_batch(batch, &[]).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,309 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Coalescing of concurrent write batches into group commits.
+//!
+//! Under load, many chains save their state at the same time. Instead of issuing one commit per
+//! batch, the [`GroupCommitStore`] queues the batches written concurrently and commits them
+//! together, which reduces the number of round-trips to (and syncs of) the database.
+
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[cfg(with_metrics)]
+use linera_base::{prometheus_util, sync::Lazy};
+#[cfg(with_metrics)]
+use prometheus::HistogramVec;
+use tokio::sync::oneshot;
+
+use crate::{
+    batch::Batch,
+    common::{KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore},
+};
+
+#[cfg(with_metrics)]
+/// The number of batches committed together.
+static GROUP_COMMIT_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "group_commit_size",
+        "Number of batches committed together",
+        &[],
+        Some(vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+/// The configuration of a [`GroupCommitStore`].
+#[derive(Clone, Copy, Debug)]
+pub struct GroupCommitConfig {
+    /// How long to wait for more batches before starting a commit, if other batches are
+    /// already queued. A batch written while no other one is queued is committed at once.
+    pub max_delay: Duration,
+    /// The maximal number of write operations in a group commit. A single batch exceeding it
+    /// is still committed, on its own.
+    pub max_operations: usize,
+}
+
+impl Default for GroupCommitConfig {
+    fn default() -> Self {
+        GroupCommitConfig {
+            max_delay: Duration::from_millis(2),
+            max_operations: 10_000,
+        }
+    }
+}
+
+/// A batch waiting to be committed, and the channel to report the outcome to its writer.
+struct PendingBatch<E> {
+    batch: Batch,
+    base_key: Vec<u8>,
+    sender: oneshot::Sender<Result<(), E>>,
+}
+
+/// The batches waiting to be committed.
+struct Queue<E> {
+    pending: Vec<PendingBatch<E>>,
+    /// Whether a task is currently committing the queued batches.
+    is_committing: bool,
+}
+
+/// A key-value store that commits concurrently written batches together.
+///
+/// The merged batch is written with the base key of its first batch. This is only correct if
+/// the inner store applies every batch atomically, whatever its size, i.e. if it does not rely
+/// on a journal keyed by the base key.
+pub struct GroupCommitStore<K: KeyValueStore> {
+    /// The inner store.
+    pub store: K,
+    /// The configuration, or `None` if the batches are committed one by one.
+    config: Option<GroupCommitConfig>,
+    queue: Arc<Mutex<Queue<K::Error>>>,
+}
+
+impl<K: KeyValueStore + Clone> Clone for GroupCommitStore<K> {
+    fn clone(&self) -> Self {
+        GroupCommitStore {
+            store: self.store.clone(),
+            config: self.config,
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<K> ReadableKeyValueStore<K::Error> for GroupCommitStore<K>
+where
+    K: KeyValueStore + Send + Sync,
+    K::Error: Send,
+{
+    // Group commits do not change the underlying store's size limits.
+    const MAX_KEY_SIZE: usize = K::MAX_KEY_SIZE;
+    type Keys = K::Keys;
+    type KeyValues = K::KeyValues;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, K::Error> {
+        self.store.read_value_bytes(key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, K::Error> {
+        self.store.contains_key(key).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, K::Error> {
+        self.store.read_multi_values_bytes(keys).await
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, K::Error> {
+        self.store.find_keys_by_prefix(key_prefix).await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, K::Error> {
+        self.store.find_key_values_by_prefix(key_prefix).await
+    }
+
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, K::Error> {
+        self.store
+            .find_keys_by_prefix_from(key_prefix, start, limit)
+            .await
+    }
+}
+
+impl<K> WritableKeyValueStore<K::Error> for GroupCommitStore<K>
+where
+    K: KeyValueStore + Clone + Send + Sync + 'static,
+    K::Error: Send + 'static,
+{
+    // Group commits do not change the underlying store's size limits.
+    const MAX_VALUE_SIZE: usize = K::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch, base_key: &[u8]) -> Result<(), K::Error> {
+        if batch.operations.is_empty() {
+            return Ok(());
+        }
+        let Some(config) = self.config else {
+            return self.store.write_batch(batch, base_key).await;
+        };
+        let (sender, receiver) = oneshot::channel();
+        let must_spawn = {
+            let mut queue = self.queue.lock().unwrap();
+            queue.pending.push(PendingBatch {
+                batch,
+                base_key: base_key.to_vec(),
+                sender,
+            });
+            !mem::replace(&mut queue.is_committing, true)
+        };
+        if must_spawn {
+            tokio::spawn(Self::commit_queued_batches(
+                self.store.clone(),
+                config,
+                self.queue.clone(),
+            ));
+        }
+        // The commit runs in its own task, so that it completes even if this future is dropped.
+        receiver
+            .await
+            .expect("the group commit task should not panic")
+    }
+
+    async fn clear_journal(&self, base_key: &[u8]) -> Result<(), K::Error> {
+        self.store.clear_journal(base_key).await
+    }
+}
+
+impl<K> KeyValueStore for GroupCommitStore<K>
+where
+    K: KeyValueStore + Clone + Send + Sync + 'static,
+    K::Error: Send + 'static,
+{
+    type Error = K::Error;
+}
+
+impl<K> GroupCommitStore<K>
+where
+    K: KeyValueStore + Clone + Send + Sync + 'static,
+    K::Error: Send + 'static,
+{
+    /// Creates a new key-value store that groups the commits to the given store.
+    pub fn new(store: K, config: GroupCommitConfig) -> Self {
+        Self::with_config(store, Some(config))
+    }
+
+    /// Creates a new key-value store that passes each batch to the given store on its own.
+    pub fn disabled(store: K) -> Self {
+        Self::with_config(store, None)
+    }
+
+    /// Creates a new key-value store that groups the commits to the given store if a
+    /// configuration is given.
+    pub fn with_config(store: K, config: Option<GroupCommitConfig>) -> Self {
+        let queue = Queue {
+            pending: Vec::new(),
+            is_committing: false,
+        };
+        GroupCommitStore {
+            store,
+            config,
+            queue: Arc::new(Mutex::new(queue)),
+        }
+    }
+
+    /// Commits the queued batches, group by group, until the queue is empty.
+    ///
+    /// A single queued batch is committed at once. If several writers are queued, e.g.
+    /// because they arrived during the previous commit, the commit waits up to the maximal
+    /// delay for more of them.
+    async fn commit_queued_batches(
+        store: K,
+        config: GroupCommitConfig,
+        queue: Arc<Mutex<Queue<K::Error>>>,
+    ) {
+        loop {
+            let is_contended = queue.lock().unwrap().pending.len() > 1;
+            if is_contended && !config.max_delay.is_zero() {
+                tokio::time::sleep(config.max_delay).await;
+            }
+            let group = {
+                let mut queue = queue.lock().unwrap();
+                if queue.pending.is_empty() {
+                    queue.is_committing = false;
+                    return;
+                }
+                Self::take_group(&mut queue.pending, config.max_operations)
+            };
+            Self::commit_group(&store, group).await;
+        }
+    }
+
+    /// Removes the oldest pending batches, up to `max_operations` in total but at least one.
+    fn take_group(
+        pending: &mut Vec<PendingBatch<K::Error>>,
+        max_operations: usize,
+    ) -> Vec<PendingBatch<K::Error>> {
+        let mut num_operations = 0;
+        let count = pending
+            .iter()
+            .take_while(|pending_batch| {
+                num_operations += pending_batch.batch.operations.len();
+                num_operations <= max_operations
+            })
+            .count()
+            .max(1);
+        pending.drain(..count).collect()
+    }
+
+    /// Commits a group of batches together, and reports the outcome to their writers.
+    ///
+    /// If the group commit fails, the batches are retried one by one, so that each writer gets
+    /// its own error.
+    async fn commit_group(store: &K, group: Vec<PendingBatch<K::Error>>) {
+        #[cfg(with_metrics)]
+        GROUP_COMMIT_SIZE
+            .with_label_values(&[])
+            .observe(group.len() as f64);
+        if group.len() == 1 {
+            let pending_batch = group.into_iter().next().unwrap();
+            let result = store
+                .write_batch(pending_batch.batch, &pending_batch.base_key)
+                .await;
+            // The writer may have stopped waiting, which is fine.
+            let _ = pending_batch.sender.send(result);
+            return;
+        }
+        let mut merged = Batch::new();
+        for pending_batch in &group {
+            merged
+                .operations
+                .extend(pending_batch.batch.operations.iter().cloned());
+        }
+        if store.write_batch(merged, &group[0].base_key).await.is_ok() {
+            for pending_batch in group {
+                let _ = pending_batch.sender.send(Ok(()));
+            }
+            return;
+        }
+        for pending_batch in group {
+            let result = store
+                .write_batch(pending_batch.batch, &pending_batch.base_key)
+                .await;
+            let _ = pending_batch.sender.send(result);
+        }
+    }
+}
@@ -98,6 +98,9 @@ pub mod map_view;
 /// The `SetView` implements a set with ordered entries.
 pub mod set_view;
 
+/// Merkle commitments to the entries of a map, with inclusion proofs.
+pub mod merkle;
+
 mod graphql;
 
 /// The `CollectionView` implements a map structure whose keys are ordered and the values are views.
@@ -109,6 +112,14 @@ pub mod memory;
 /// The LRU (least recently used) caching.
 pub mod lru_caching;
 
+/// The grouping of concurrent writes into fewer commits.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod group_commit;
+
+/// The compression of the values of a key-value store.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compression;
+
 /// The `ReentrantCollectionView` implements a map structure whose keys are ordered and the values are views with concurrent access.
 pub mod reentrant_collection_view;
 
@@ -118,6 +129,12 @@ pub mod key_value_store_view;
 /// Wrapping a view to compute a hash.
 pub mod hashable_wrapper;
 
+/// The recording of the storage keys accessed while handling a request.
+pub mod trace;
+
+/// Transactions buffering their writes until they are committed.
+pub mod transaction;
+
 /// A storage backend for views based on ScyllaDB
 #[cfg(with_scylladb)]
 pub mod scylla_db;
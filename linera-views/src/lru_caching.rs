@@ -205,6 +205,17 @@ where
     ) -> Result<Self::KeyValues, K::Error> {
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, K::Error> {
+        self.store
+            .find_keys_by_prefix_from(key_prefix, start, limit)
+            .await
+    }
 }
 
 impl<K> WritableKeyValueStore<K::Error> for LruCachingStore<K>
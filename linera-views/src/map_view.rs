@@ -43,6 +43,7 @@ use std::{
     fmt::Debug,
     marker::PhantomData,
     mem,
+    ops::{Bound, RangeBounds},
 };
 
 use async_trait::async_trait;
@@ -638,6 +639,277 @@ where
         )
         .await
     }
+
+    /// Applies a function f on each key in the given range. Keys are visited in the
+    /// lexicographic order. Only the keys sharing the common prefix of the bounds of
+    /// the range are read from storage. If the function returns false, then the loop
+    /// ends prematurely.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use std::ops::Bound;
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map = ByteMapView::load(context).await.unwrap();
+    ///   map.insert(vec![0,1], String::from("Hello"));
+    ///   map.insert(vec![1,2], String::from("Bonjour"));
+    ///   map.insert(vec![1,3], String::from("Hallo"));
+    ///   let mut keys = Vec::new();
+    ///   let range = (Bound::Excluded(vec![0,1]), Bound::Included(vec![1,3]));
+    ///   map.for_each_key_in_range_while(|key| {
+    ///     keys.push(key.to_vec());
+    ///     Ok(true)
+    ///   }, range).await.unwrap();
+    ///   assert_eq!(keys, vec![vec![1,2], vec![1,3]]);
+    /// # })
+    /// ```
+    pub async fn for_each_key_in_range_while<F>(
+        &self,
+        mut f: F,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+    ) -> Result<(), ViewError>
+    where
+        F: FnMut(&[u8]) -> Result<bool, ViewError> + Send,
+    {
+        let prefix = range_prefix(&range);
+        let prefix_len = prefix.len();
+        let mut key = prefix.clone();
+        self.for_each_key_while(
+            |suffix| {
+                key.truncate(prefix_len);
+                key.extend_from_slice(suffix);
+                if is_past_end(&range.1, &key) {
+                    return Ok(false);
+                }
+                if !range.contains(&key) {
+                    return Ok(true);
+                }
+                f(&key)
+            },
+            prefix,
+        )
+        .await
+    }
+
+    /// Applies a function f on each key/value pair whose key is in the given range.
+    /// Keys and values are visited in the lexicographic order. Only the keys sharing
+    /// the common prefix of the bounds of the range are read from storage. If the
+    /// function returns false, then the loop ends prematurely.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use std::ops::Bound;
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map = ByteMapView::load(context).await.unwrap();
+    ///   map.insert(vec![0,1], String::from("Hello"));
+    ///   map.insert(vec![1,2], String::from("Bonjour"));
+    ///   map.insert(vec![1,3], String::from("Hallo"));
+    ///   let mut keys = Vec::new();
+    ///   let range = (Bound::Included(vec![1]), Bound::Unbounded);
+    ///   map.for_each_key_value_in_range_while(|key, _value| {
+    ///     keys.push(key.to_vec());
+    ///     Ok(keys.len() < 1)
+    ///   }, range).await.unwrap();
+    ///   assert_eq!(keys, vec![vec![1,2]]);
+    /// # })
+    /// ```
+    pub async fn for_each_key_value_in_range_while<F>(
+        &self,
+        mut f: F,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+    ) -> Result<(), ViewError>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool, ViewError> + Send,
+    {
+        let prefix = range_prefix(&range);
+        let prefix_len = prefix.len();
+        let mut key = prefix.clone();
+        self.for_each_key_value_while(
+            |suffix, value| {
+                key.truncate(prefix_len);
+                key.extend_from_slice(suffix);
+                if is_past_end(&range.1, &key) {
+                    return Ok(false);
+                }
+                if !range.contains(&key) {
+                    return Ok(true);
+                }
+                f(&key, value)
+            },
+            prefix,
+        )
+        .await
+    }
+
+    /// Applies a function f on each key/value pair whose key is in the given range.
+    /// Keys and values are visited in the lexicographic order.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use std::ops::Bound;
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map = ByteMapView::load(context).await.unwrap();
+    ///   map.insert(vec![0,1], String::from("Hello"));
+    ///   map.insert(vec![1,2], String::from("Bonjour"));
+    ///   let mut count = 0;
+    ///   let range = (Bound::Included(vec![1]), Bound::Excluded(vec![2]));
+    ///   map.for_each_key_value_in_range(|_key, _value| {
+    ///     count += 1;
+    ///     Ok(())
+    ///   }, range).await.unwrap();
+    ///   assert_eq!(count, 1);
+    /// # })
+    /// ```
+    pub async fn for_each_key_value_in_range<F>(
+        &self,
+        mut f: F,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+    ) -> Result<(), ViewError>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<(), ViewError> + Send,
+    {
+        self.for_each_key_value_in_range_while(
+            |key, value| {
+                f(key, value)?;
+                Ok(true)
+            },
+            range,
+        )
+        .await
+    }
+
+    /// Returns up to `limit` keys of the map following `cursor`, or the first keys if
+    /// `cursor` is `None`, in lexicographic order.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map = ByteMapView::load(context).await.unwrap();
+    ///   map.insert(vec![0,1], String::from("Hello"));
+    ///   map.insert(vec![1,2], String::from("Bonjour"));
+    ///   map.insert(vec![2,2], String::from("Hallo"));
+    ///   assert_eq!(map.keys_after(None, 2).await.unwrap(), vec![vec![0,1], vec![1,2]]);
+    ///   assert_eq!(map.keys_after(Some(&[1,2]), 2).await.unwrap(), vec![vec![2,2]]);
+    /// # })
+    /// ```
+    pub async fn keys_after(
+        &self,
+        cursor: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, ViewError> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let mut start = match cursor {
+            Some(cursor) => [cursor, &[0]].concat(),
+            None => Vec::new(),
+        };
+        let mut keys = self
+            .updates
+            .range(start.clone()..)
+            .filter(|(_, update)| matches!(update, Update::Set(_)))
+            .map(|(key, _)| key.clone())
+            .take(limit)
+            .collect::<Vec<_>>();
+        if !self.delete_storage_first {
+            // The stored keys are read from the cursor on, in batches of `limit`, until
+            // there are `limit` of them that are neither updated nor deleted.
+            let base = self.context.base_key();
+            let mut stored_count = 0;
+            loop {
+                let stored_keys = self
+                    .context
+                    .find_keys_by_prefix_from(&base, &start, limit)
+                    .await?;
+                for key in &stored_keys {
+                    if stored_count < limit
+                        && !self.updates.contains_key(key)
+                        && !contains_key(&self.deleted_prefixes, key)
+                    {
+                        keys.push(key.clone());
+                        stored_count += 1;
+                    }
+                }
+                match stored_keys.last() {
+                    Some(key) if stored_count < limit && stored_keys.len() == limit => {
+                        start = [key.as_slice(), &[0]].concat();
+                    }
+                    _ => break,
+                }
+            }
+        }
+        keys.sort();
+        keys.truncate(limit);
+        Ok(keys)
+    }
+
+    /// Returns the given keys that are in the map, with their serialized values.
+    async fn key_value_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ViewError> {
+        let stored_keys = keys
+            .iter()
+            .filter(|key| !self.updates.contains_key(*key))
+            .map(|key| self.context.base_index(key))
+            .collect();
+        let mut stored_values = self
+            .context
+            .read_multi_values_bytes(stored_keys)
+            .await?
+            .into_iter();
+        let mut key_values = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = match self.updates.get(&key) {
+                Some(Update::Set(value)) => Some(bcs::to_bytes(value)?),
+                Some(Update::Removed) => None,
+                None => stored_values.next().flatten(),
+            };
+            if let Some(value) = value {
+                key_values.push((key, value));
+            }
+        }
+        Ok(key_values)
+    }
+
+    /// Returns the number of keys of the map in the given range.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use std::ops::Bound;
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map = ByteMapView::load(context).await.unwrap();
+    ///   map.insert(vec![0,1], String::from("Hello"));
+    ///   map.insert(vec![1,2], String::from("Bonjour"));
+    ///   map.insert(vec![2,2], String::from("Hallo"));
+    ///   let range = (Bound::Included(vec![1]), Bound::Unbounded);
+    ///   assert_eq!(map.count_in_range(range).await.unwrap(), 2);
+    /// # })
+    /// ```
+    pub async fn count_in_range(
+        &self,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+    ) -> Result<usize, ViewError> {
+        let mut count = 0;
+        self.for_each_key_in_range_while(
+            |_key| {
+                count += 1;
+                Ok(true)
+            },
+            range,
+        )
+        .await?;
+        Ok(count)
+    }
 }
 
 impl<C, V> ByteMapView<C, V>
@@ -695,6 +967,34 @@ where
     pub async fn key_values(&self) -> Result<Vec<(Vec<u8>, V)>, ViewError> {
         self.key_values_by_prefix(Vec::new()).await
     }
+
+    /// Returns up to `limit` keys and values of the map following `cursor`, or the
+    /// first ones if `cursor` is `None`, in lexicographic order.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map = ByteMapView::load(context).await.unwrap();
+    ///   map.insert(vec![0,1], String::from("Hello"));
+    ///   map.insert(vec![1,2], String::from("Bonjour"));
+    ///   let key_values = map.key_values_after(Some(&[0,1]), 10).await.unwrap();
+    ///   assert_eq!(key_values, vec![(vec![1,2], String::from("Bonjour"))]);
+    /// # })
+    /// ```
+    pub async fn key_values_after(
+        &self,
+        cursor: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Vec<u8>, V)>, ViewError> {
+        let keys = self.keys_after(cursor, limit).await?;
+        let mut key_values = Vec::with_capacity(keys.len());
+        for (key, value) in self.key_value_bytes(keys).await? {
+            key_values.push((key, bcs::from_bytes(&value)?));
+        }
+        Ok(key_values)
+    }
 }
 
 impl<C, V> ByteMapView<C, V>
@@ -1146,6 +1446,95 @@ where
             .await?;
         Ok(())
     }
+
+    /// Returns up to `limit` indices of the map following `cursor`, or the first ones
+    /// if `cursor` is `None`. The order is determined by serialization.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::MapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map : MapView<_, u128, String> = MapView::load(context).await.unwrap();
+    ///   map.insert(&(34 as u128), String::from("Thanks"));
+    ///   map.insert(&(37 as u128), String::from("Spasiba"));
+    ///   map.insert(&(38 as u128), String::from("Merci"));
+    ///   assert_eq!(map.indices_after(Some(&(34 as u128)), 1).await.unwrap(), vec![37 as u128]);
+    /// # })
+    /// ```
+    pub async fn indices_after<Q>(
+        &self,
+        cursor: Option<&Q>,
+        limit: usize,
+    ) -> Result<Vec<I>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let cursor = cursor
+            .map(|cursor| C::derive_short_key(cursor))
+            .transpose()?;
+        let keys = self.map.keys_after(cursor.as_deref(), limit).await?;
+        let mut indices = Vec::with_capacity(keys.len());
+        for key in keys {
+            indices.push(C::deserialize_value(&key)?);
+        }
+        Ok(indices)
+    }
+
+    /// Returns up to `limit` index/value pairs of the map following `cursor`, or the
+    /// first ones if `cursor` is `None`. The order is determined by serialization.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::MapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map : MapView<_, u128, String> = MapView::load(context).await.unwrap();
+    ///   map.insert(&(34 as u128), String::from("Thanks"));
+    ///   map.insert(&(37 as u128), String::from("Spasiba"));
+    ///   let index_values = map.index_values_after(None::<&u128>, 10).await.unwrap();
+    ///   assert_eq!(index_values.len(), 2);
+    /// # })
+    /// ```
+    pub async fn index_values_after<Q>(
+        &self,
+        cursor: Option<&Q>,
+        limit: usize,
+    ) -> Result<Vec<(I, V)>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let cursor = cursor
+            .map(|cursor| C::derive_short_key(cursor))
+            .transpose()?;
+        let keys = self.map.keys_after(cursor.as_deref(), limit).await?;
+        let mut index_values = Vec::with_capacity(keys.len());
+        for (key, bytes) in self.map.key_value_bytes(keys).await? {
+            let index = C::deserialize_value(&key)?;
+            let value = C::deserialize_value(&bytes)?;
+            index_values.push((index, value));
+        }
+        Ok(index_values)
+    }
+
+    /// Returns the number of indices of the map.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::MapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map : MapView<_, u128, String> = MapView::load(context).await.unwrap();
+    ///   map.insert(&(34 as u128), String::from("Thanks"));
+    ///   map.insert(&(37 as u128), String::from("Spasiba"));
+    ///   assert_eq!(map.count().await.unwrap(), 2);
+    /// # })
+    /// ```
+    pub async fn count(&self) -> Result<usize, ViewError> {
+        self.map.count().await
+    }
 }
 
 impl<C, I, V> MapView<C, I, V>
@@ -1561,6 +1950,91 @@ where
             .await?;
         Ok(())
     }
+
+    /// Returns up to `limit` indices of the map following `cursor`, or the first ones
+    /// if `cursor` is `None`. The order is determined by the custom serialization.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map : CustomMapView<_, u128, String> = CustomMapView::load(context).await.unwrap();
+    ///   map.insert(&(34 as u128), String::from("Thanks"));
+    ///   map.insert(&(37 as u128), String::from("Spasiba"));
+    ///   map.insert(&(38 as u128), String::from("Merci"));
+    ///   assert_eq!(map.indices_after(Some(&(34 as u128)), 1).await.unwrap(), vec![37 as u128]);
+    /// # })
+    /// ```
+    pub async fn indices_after<Q>(
+        &self,
+        cursor: Option<&Q>,
+        limit: usize,
+    ) -> Result<Vec<I>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: CustomSerialize + ?Sized,
+    {
+        let cursor = cursor.map(|cursor| cursor.to_custom_bytes()).transpose()?;
+        let keys = self.map.keys_after(cursor.as_deref(), limit).await?;
+        let mut indices = Vec::with_capacity(keys.len());
+        for key in keys {
+            indices.push(I::from_custom_bytes(&key)?);
+        }
+        Ok(indices)
+    }
+
+    /// Returns up to `limit` index/value pairs of the map following `cursor`, or the
+    /// first ones if `cursor` is `None`. The order is determined by the custom serialization.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map : CustomMapView<_, u128, String> = CustomMapView::load(context).await.unwrap();
+    ///   map.insert(&(34 as u128), String::from("Thanks"));
+    ///   map.insert(&(37 as u128), String::from("Spasiba"));
+    ///   let index_values = map.index_values_after(None::<&u128>, 10).await.unwrap();
+    ///   assert_eq!(index_values.len(), 2);
+    /// # })
+    /// ```
+    pub async fn index_values_after<Q>(
+        &self,
+        cursor: Option<&Q>,
+        limit: usize,
+    ) -> Result<Vec<(I, V)>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: CustomSerialize + ?Sized,
+    {
+        let cursor = cursor.map(|cursor| cursor.to_custom_bytes()).transpose()?;
+        let keys = self.map.keys_after(cursor.as_deref(), limit).await?;
+        let mut index_values = Vec::with_capacity(keys.len());
+        for (key, bytes) in self.map.key_value_bytes(keys).await? {
+            let index = I::from_custom_bytes(&key)?;
+            let value = C::deserialize_value(&bytes)?;
+            index_values.push((index, value));
+        }
+        Ok(index_values)
+    }
+
+    /// Returns the number of indices of the map.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut map : CustomMapView<_, u128, String> = CustomMapView::load(context).await.unwrap();
+    ///   map.insert(&(34 as u128), String::from("Thanks"));
+    ///   map.insert(&(37 as u128), String::from("Spasiba"));
+    ///   assert_eq!(map.count().await.unwrap(), 2);
+    /// # })
+    /// ```
+    pub async fn count(&self) -> Result<usize, ViewError> {
+        self.map.count().await
+    }
 }
 
 impl<C, I, V> CustomMapView<C, I, V>
@@ -1611,6 +2085,31 @@ where
     }
 }
 
+/// Returns the longest prefix shared by all the keys in the given range.
+fn range_prefix(range: &(Bound<Vec<u8>>, Bound<Vec<u8>>)) -> Vec<u8> {
+    match range {
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) => start
+            .iter()
+            .zip(end)
+            .take_while(|(start_byte, end_byte)| start_byte == end_byte)
+            .map(|(byte, _)| *byte)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns whether `key`, and hence all the keys after it, are beyond the given end bound.
+fn is_past_end(end: &Bound<Vec<u8>>, key: &[u8]) -> bool {
+    match end {
+        Bound::Included(end) => key > end.as_slice(),
+        Bound::Excluded(end) => key >= end.as_slice(),
+        Bound::Unbounded => false,
+    }
+}
+
 /// Type wrapping `ByteMapView` while memoizing the hash.
 pub type HashedByteMapView<C, V> = WrappedHashableContainerView<C, ByteMapView<C, V>, HasherOutput>;
 
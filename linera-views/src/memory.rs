@@ -1,7 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
+use std::{collections::BTreeMap, fmt::Debug, ops::Bound::Included, sync::Arc};
 
 use async_lock::{Mutex, MutexGuardArc, RwLock};
 use futures::FutureExt;
@@ -10,8 +10,8 @@ use thiserror::Error;
 use crate::{
     batch::{Batch, DeletePrefixExpander, WriteOperation},
     common::{
-        get_interval, AdminKeyValueStore, CommonStoreConfig, Context, ContextFromStore,
-        KeyIterable, KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
+        get_interval, get_upper_bound, AdminKeyValueStore, CommonStoreConfig, Context,
+        ContextFromStore, KeyIterable, KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
     },
     value_splitting::DatabaseConsistencyError,
     views::ViewError,
@@ -109,6 +109,23 @@ impl ReadableKeyValueStore<MemoryContextError> for MemoryStore {
         }
         Ok(key_values)
     }
+
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, MemoryContextError> {
+        let map = self.map.read().await;
+        let len = key_prefix.len();
+        let lower_bound = Included([key_prefix, start].concat());
+        let upper_bound = get_upper_bound(key_prefix);
+        Ok(map
+            .range((lower_bound, upper_bound))
+            .take(limit)
+            .map(|(key, _value)| key[len..].to_vec())
+            .collect())
+    }
 }
 
 impl WritableKeyValueStore<MemoryContextError> for MemoryStore {
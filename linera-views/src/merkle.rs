@@ -0,0 +1,396 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Merkle commitments to the entries of a map, with inclusion proofs.
+//!
+//! The hash of a [`MerkleMapView`] is the root of a Merkle tree over its entries, so that the
+//! presence of a single entry can be proven with a logarithmic number of hashes. Combined with
+//! [`FieldsProof`] steps from the map up to a root view, a [`KeyInclusionProof`] lets a light
+//! client check one entry against the state hash of a certificate, without the rest of the
+//! state.
+
+use std::{
+    borrow::Borrow,
+    ops::{Deref, DerefMut},
+};
+
+use async_trait::async_trait;
+use linera_base::crypto::CryptoHash;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::{
+    batch::Batch,
+    common::{Context, HasherOutput},
+    hashable_wrapper::WrappedHashableContainerView,
+    map_view::MapView,
+    views::{ClonableView, HashableView, Hasher, View, ViewError},
+};
+
+/// Domain separation tags, so that leaves, inner nodes and roots cannot be confused.
+#[repr(u8)]
+enum NodeTag {
+    Leaf,
+    Inner,
+    Root,
+}
+
+fn hash_parts(parts: &[&[u8]]) -> HasherOutput {
+    let mut hasher = Sha3_256::default();
+    for part in parts {
+        hasher.update(part);
+    }
+    Digest::finalize(hasher)
+}
+
+/// Returns the hash of the leaf for the entry with the given serialized key and value.
+pub fn leaf_hash(key: &[u8], value: &[u8]) -> HasherOutput {
+    let key_len = (key.len() as u64).to_le_bytes();
+    hash_parts(&[&[NodeTag::Leaf as u8], &key_len, key, value])
+}
+
+fn inner_hash(left: &HasherOutput, right: &HasherOutput) -> HasherOutput {
+    hash_parts(&[&[NodeTag::Inner as u8], left, right])
+}
+
+fn root_hash(leaf_count: u64, tree_hash: Option<&HasherOutput>) -> HasherOutput {
+    let count = leaf_count.to_le_bytes();
+    match tree_hash {
+        Some(tree_hash) => hash_parts(&[&[NodeTag::Root as u8], &count, tree_hash]),
+        None => hash_parts(&[&[NodeTag::Root as u8], &count]),
+    }
+}
+
+/// Returns the nodes of the next level of a Merkle tree. A node without sibling is moved up
+/// unchanged.
+fn next_level(level: &[HasherOutput]) -> Vec<HasherOutput> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => inner_hash(left, right),
+            [node] => *node,
+            _ => unreachable!("chunks have one or two elements"),
+        })
+        .collect()
+}
+
+/// Returns the Merkle root of the given leaves. The number of leaves is included in the root.
+pub fn merkle_root(leaves: &[HasherOutput]) -> HasherOutput {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    root_hash(leaves.len() as u64, level.first())
+}
+
+/// A proof that a leaf is part of a Merkle tree.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// The number of leaves of the tree.
+    pub leaf_count: u64,
+    /// The position of the leaf.
+    pub index: u64,
+    /// The siblings of the nodes on the path from the leaf to the root, bottom up.
+    pub siblings: Vec<HasherOutput>,
+}
+
+impl MerkleProof {
+    /// Creates the proof for the leaf at position `index`, or returns `None` if there is no
+    /// such leaf.
+    pub fn new(leaves: &[HasherOutput], index: usize) -> Option<Self> {
+        if index >= leaves.len() {
+            return None;
+        }
+        let mut siblings = Vec::new();
+        let mut level = leaves.to_vec();
+        let mut position = index;
+        while level.len() > 1 {
+            let sibling = position ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            level = next_level(&level);
+            position /= 2;
+        }
+        Some(MerkleProof {
+            leaf_count: leaves.len() as u64,
+            index: index as u64,
+            siblings,
+        })
+    }
+
+    /// Returns the Merkle root obtained from the given leaf, or `None` if the proof is
+    /// malformed.
+    pub fn root(&self, leaf: HasherOutput) -> Option<HasherOutput> {
+        if self.index >= self.leaf_count {
+            return None;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut node = leaf;
+        let mut position = self.index;
+        let mut count = self.leaf_count;
+        while count > 1 {
+            let has_sibling = position % 2 == 1 || position + 1 < count;
+            if has_sibling {
+                let sibling = siblings.next()?;
+                node = if position % 2 == 0 {
+                    inner_hash(&node, sibling)
+                } else {
+                    inner_hash(sibling, &node)
+                };
+            }
+            position /= 2;
+            count = count.div_ceil(2);
+        }
+        if siblings.next().is_some() {
+            return None;
+        }
+        Some(root_hash(self.leaf_count, Some(&node)))
+    }
+}
+
+/// A view struct whose hash is the hash of the concatenated hashes of its fields. This is
+/// implemented by `#[derive(HashableView)]`.
+#[async_trait]
+pub trait FieldHashes {
+    /// The names of the fields, in order.
+    const FIELD_NAMES: &'static [&'static str];
+
+    /// Returns the hashes of the fields, in order.
+    async fn field_hashes(&self) -> Result<Vec<HasherOutput>, ViewError>;
+}
+
+/// A proof that a view is a field of a view struct, e.g. one derived with `HashableView`.
+///
+/// The hash of such a struct is the hash of the concatenated hashes of its fields.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldsProof {
+    /// The hashes of all the fields of the struct, in order.
+    pub field_hashes: Vec<HasherOutput>,
+    /// The position of the field.
+    pub index: usize,
+}
+
+impl FieldsProof {
+    /// Creates the proof that the field `field_name` is part of `view`.
+    pub async fn new<V: FieldHashes + Sync>(view: &V, field_name: &str) -> Result<Self, ViewError> {
+        let index = V::FIELD_NAMES
+            .iter()
+            .position(|name| *name == field_name)
+            .ok_or_else(|| ViewError::NotFound(format!("field {field_name}")))?;
+        Ok(FieldsProof {
+            field_hashes: view.field_hashes().await?,
+            index,
+        })
+    }
+
+    /// Returns the hash of the struct if the field at `index` has the hash `field_hash`.
+    pub fn parent_hash(&self, field_hash: &HasherOutput) -> Option<HasherOutput> {
+        if self.field_hashes.get(self.index)? != field_hash {
+            return None;
+        }
+        let parts = self
+            .field_hashes
+            .iter()
+            .map(|hash| hash.as_slice())
+            .collect::<Vec<_>>();
+        Some(hash_parts(&parts))
+    }
+}
+
+/// A proof that an entry is part of the state of a root view.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyInclusionProof {
+    /// The serialized key of the entry.
+    pub key: Vec<u8>,
+    /// The serialized value of the entry.
+    pub value: Vec<u8>,
+    /// The proof that the entry is in its [`MerkleMapView`].
+    pub map_proof: MerkleProof,
+    /// The proofs that each view is a field of the next one, from the map up to the root.
+    pub path: Vec<FieldsProof>,
+}
+
+impl KeyInclusionProof {
+    /// Returns the hash of the root view implied by the proof, or `None` if the proof is
+    /// malformed.
+    pub fn root_hash(&self) -> Option<HasherOutput> {
+        let mut hash = self.map_proof.root(leaf_hash(&self.key, &self.value))?;
+        for step in &self.path {
+            hash = step.parent_hash(&hash)?;
+        }
+        Some(hash)
+    }
+
+    /// Returns whether the proof shows that the entry is part of a root view with the given
+    /// hash.
+    pub fn verify(&self, root_hash: &HasherOutput) -> bool {
+        self.root_hash().as_ref() == Some(root_hash)
+    }
+
+    /// Returns whether the proof shows that the entry is part of a root view with the given
+    /// [`CryptoHash`], as computed by `CryptoHashView`. `type_name` is the name of the type
+    /// of the root view, e.g. `ExecutionStateView`.
+    pub fn verify_crypto_hash(&self, type_name: &str, crypto_hash: &CryptoHash) -> bool {
+        let Some(root_hash) = self.root_hash() else {
+            return false;
+        };
+        let seed = format!("{}Hash::", type_name);
+        hash_parts(&[seed.as_bytes(), &root_hash]) == *crypto_hash.as_bytes()
+    }
+}
+
+/// A [`MapView`] whose hash is a Merkle commitment to its entries, so that inclusion proofs
+/// can be generated for them.
+#[derive(Debug)]
+pub struct MerkleMapView<C, I, V> {
+    map: MapView<C, I, V>,
+}
+
+#[async_trait]
+impl<C, I, V> View<C> for MerkleMapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync + Serialize,
+    V: Send + Sync + Serialize,
+{
+    fn context(&self) -> &C {
+        self.map.context()
+    }
+
+    async fn load(context: C) -> Result<Self, ViewError> {
+        let map = MapView::load(context).await?;
+        Ok(MerkleMapView { map })
+    }
+
+    fn rollback(&mut self) {
+        self.map.rollback()
+    }
+
+    fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError> {
+        self.map.flush(batch)
+    }
+
+    fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl<C, I, V> ClonableView<C> for MerkleMapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync + Serialize,
+    V: Clone + Send + Sync + Serialize,
+{
+    fn clone_unchecked(&mut self) -> Result<Self, ViewError> {
+        Ok(MerkleMapView {
+            map: self.map.clone_unchecked()?,
+        })
+    }
+}
+
+impl<C, I, V> MerkleMapView<C, I, V>
+where
+    C: Context + Sync,
+    ViewError: From<C::Error>,
+    I: Sync + Send + Serialize + DeserializeOwned,
+    V: Clone + Sync + Serialize + DeserializeOwned + 'static,
+{
+    /// Returns the serialized keys and the leaf hashes of the entries, in order.
+    async fn leaves(&self) -> Result<(Vec<Vec<u8>>, Vec<HasherOutput>), ViewError> {
+        let mut keys = Vec::new();
+        let mut leaves = Vec::new();
+        self.map
+            .for_each_index_value(|index, value| {
+                let key = C::derive_short_key(&index)?;
+                let value = bcs::to_bytes(&value)?;
+                leaves.push(leaf_hash(&key, &value));
+                keys.push(key);
+                Ok(())
+            })
+            .await?;
+        Ok((keys, leaves))
+    }
+
+    /// Returns a proof that the entry at `index` is in the map, or `None` if there is no
+    /// such entry. `path` leads from the map up to the root view to prove the entry against.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::merkle::MerkleMapView;
+    /// # use crate::linera_views::views::{HashableView, View};
+    /// # let context = create_memory_context();
+    ///   let mut map : MerkleMapView<_, u32, String> = MerkleMapView::load(context).await.unwrap();
+    ///   map.insert(&(34 as u32), String::from("Hello"));
+    ///   map.insert(&(37 as u32), String::from("Bonjour"));
+    ///   let proof = map.inclusion_proof(&(37 as u32), Vec::new()).await.unwrap().unwrap();
+    ///   assert!(proof.verify(&map.hash().await.unwrap()));
+    /// # })
+    /// ```
+    pub async fn inclusion_proof<Q>(
+        &self,
+        index: &Q,
+        path: Vec<FieldsProof>,
+    ) -> Result<Option<KeyInclusionProof>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let key = C::derive_short_key(index)?;
+        let Some(value) = self.map.get(index).await? else {
+            return Ok(None);
+        };
+        let value = bcs::to_bytes(&value)?;
+        let (keys, leaves) = self.leaves().await?;
+        let position = keys
+            .binary_search(&key)
+            .expect("the keys of an entry should be listed");
+        let map_proof = MerkleProof::new(&leaves, position).expect("the position is valid");
+        Ok(Some(KeyInclusionProof {
+            key,
+            value,
+            map_proof,
+            path,
+        }))
+    }
+}
+
+#[async_trait]
+impl<C, I, V> HashableView<C> for MerkleMapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync + Serialize + DeserializeOwned,
+    V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    type Hasher = sha3::Sha3_256;
+
+    async fn hash_mut(&mut self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        self.hash().await
+    }
+
+    async fn hash(&self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        let (_, leaves) = self.leaves().await?;
+        Ok(merkle_root(&leaves))
+    }
+}
+
+impl<C, I, V> Deref for MerkleMapView<C, I, V> {
+    type Target = MapView<C, I, V>;
+
+    fn deref(&self) -> &MapView<C, I, V> {
+        &self.map
+    }
+}
+
+impl<C, I, V> DerefMut for MerkleMapView<C, I, V> {
+    fn deref_mut(&mut self) -> &mut MapView<C, I, V> {
+        &mut self.map
+    }
+}
+
+/// Type wrapping `MerkleMapView` while memoizing the hash.
+pub type HashedMerkleMapView<C, I, V> =
+    WrappedHashableContainerView<C, MerkleMapView<C, I, V>, HasherOutput>;
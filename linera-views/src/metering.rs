@@ -151,6 +151,18 @@ where
         let _metric = self.counter.find_key_values_by_prefix.measure_latency();
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, E> {
+        let _metric = self.counter.find_keys_by_prefix.measure_latency();
+        self.store
+            .find_keys_by_prefix_from(key_prefix, start, limit)
+            .await
+    }
 }
 
 impl<K, E> WritableKeyValueStore<E> for MeteredStore<K>
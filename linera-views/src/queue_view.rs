@@ -233,6 +233,28 @@ where
         }
     }
 
+    /// Removes the front value and returns it, if any.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::queue_view::QueueView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut queue = QueueView::load(context).await.unwrap();
+    ///   queue.push_back(34);
+    ///   queue.push_back(42);
+    ///   assert_eq!(queue.pop_front().await.unwrap(), Some(34));
+    ///   assert_eq!(queue.elements().await.unwrap(), vec![42]);
+    /// # })
+    /// ```
+    pub async fn pop_front(&mut self) -> Result<Option<T>, ViewError> {
+        let value = self.front().await?;
+        if value.is_some() {
+            self.delete_front();
+        }
+        Ok(value)
+    }
+
     /// Pushes a value to the end of the queue.
     /// ```rust
     /// # tokio_test::block_on(async {
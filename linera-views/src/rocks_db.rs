@@ -5,6 +5,7 @@ use std::{
     ffi::OsString,
     ops::{Bound, Bound::Excluded},
     path::PathBuf,
+    str::FromStr,
     sync::Arc,
 };
 
@@ -15,6 +16,12 @@ use {
     crate::{lru_caching::TEST_CACHE_SIZE, test_utils::generate_test_namespace},
     tempfile::TempDir,
 };
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::IntGaugeVec,
+    std::{sync::Weak, time::Duration},
+};
 
 #[cfg(with_metrics)]
 use crate::metering::{
@@ -26,6 +33,8 @@ use crate::{
         get_upper_bound, AdminKeyValueStore, CommonStoreConfig, ContextFromStore, KeyValueStore,
         ReadableKeyValueStore, WritableKeyValueStore,
     },
+    compression::{self, CompressionConfig, CompressionStore},
+    group_commit::{GroupCommitConfig, GroupCommitStore},
     lru_caching::LruCachingStore,
     value_splitting::{DatabaseConsistencyError, ValueSplittingStore},
 };
@@ -42,6 +51,31 @@ const MAX_VALUE_SIZE: usize = 3221225072;
 // 8388608 and so for offset reason we decrease by 400
 const MAX_KEY_SIZE: usize = 8388208;
 
+/// The interval between two reports of the statistics of RocksDB in the metrics.
+#[cfg(with_metrics)]
+const STATISTICS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The properties of RocksDB reported in the metrics.
+#[cfg(with_metrics)]
+const REPORTED_PROPERTIES: [&str; 6] = [
+    "rocksdb.estimate-num-keys",
+    "rocksdb.estimate-live-data-size",
+    "rocksdb.total-sst-files-size",
+    "rocksdb.cur-size-all-mem-tables",
+    "rocksdb.block-cache-usage",
+    "rocksdb.estimate-pending-compaction-bytes",
+];
+
+#[cfg(with_metrics)]
+static ROCKS_DB_PROPERTIES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    prometheus_util::register_int_gauge_vec(
+        "rocks_db_properties",
+        "The values of the statistics of RocksDB, by namespace",
+        &["namespace", "property"],
+    )
+    .expect("Gauge creation should not fail")
+});
+
 /// The RocksDB client that we use.
 pub type DB = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
 
@@ -59,6 +93,118 @@ pub struct RocksDbStoreConfig {
     pub path_buf: PathBuf,
     /// The common configuration of the key value store
     pub common_config: CommonStoreConfig,
+    /// The tuning options of RocksDB
+    pub tuning: RocksDbTuningConfig,
+}
+
+/// The options of RocksDB that operators may tune for their disk and memory. The options
+/// that are not set keep the defaults of RocksDB.
+#[derive(Clone, Debug, Default)]
+pub struct RocksDbTuningConfig {
+    /// The size in bytes of the block cache of each namespace
+    pub block_cache_size: Option<usize>,
+    /// The size in bytes of a memtable, before it is written to disk
+    pub write_buffer_size: Option<usize>,
+    /// The compaction style
+    pub compaction_style: Option<RocksDbCompactionStyle>,
+    /// The compression of the data written to disk
+    pub compression: Option<RocksDbCompression>,
+    /// The compression of the values by the store itself, before they are split and
+    /// written to RocksDB
+    pub value_compression: CompressionConfig,
+    /// The grouping of the concurrent write batches into shared commits, if enabled
+    pub group_commit: Option<GroupCommitConfig>,
+}
+
+/// The compaction styles of RocksDB.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RocksDbCompactionStyle {
+    /// Leveled compaction, the default
+    Level,
+    /// Universal compaction, trading space for a lower write amplification
+    Universal,
+    /// FIFO compaction, deleting the oldest files. Only suitable for caches.
+    Fifo,
+}
+
+/// The compression algorithms of RocksDB.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RocksDbCompression {
+    /// No compression
+    None,
+    /// Snappy compression, the default
+    Snappy,
+    /// Zlib compression
+    Zlib,
+    /// LZ4 compression
+    Lz4,
+    /// Zstandard compression
+    Zstd,
+}
+
+impl FromStr for RocksDbCompactionStyle {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "level" => Ok(Self::Level),
+            "universal" => Ok(Self::Universal),
+            "fifo" => Ok(Self::Fifo),
+            _ => Err(format!(
+                "invalid compaction style {input:?}: expected level, universal or fifo"
+            )),
+        }
+    }
+}
+
+impl FromStr for RocksDbCompression {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "none" => Ok(Self::None),
+            "snappy" => Ok(Self::Snappy),
+            "zlib" => Ok(Self::Zlib),
+            "lz4" => Ok(Self::Lz4),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!(
+                "invalid compression {input:?}: expected none, snappy, zlib, lz4 or zstd"
+            )),
+        }
+    }
+}
+
+impl RocksDbTuningConfig {
+    /// Returns the options to open a database with.
+    fn options(&self) -> rocksdb::Options {
+        let mut options = rocksdb::Options::default();
+        if let Some(size) = self.block_cache_size {
+            let cache = rocksdb::Cache::new_lru_cache(size);
+            let mut block_options = rocksdb::BlockBasedOptions::default();
+            block_options.set_block_cache(&cache);
+            options.set_block_based_table_factory(&block_options);
+        }
+        if let Some(size) = self.write_buffer_size {
+            options.set_write_buffer_size(size);
+        }
+        if let Some(style) = self.compaction_style {
+            options.set_compaction_style(match style {
+                RocksDbCompactionStyle::Level => rocksdb::DBCompactionStyle::Level,
+                RocksDbCompactionStyle::Universal => rocksdb::DBCompactionStyle::Universal,
+                RocksDbCompactionStyle::Fifo => rocksdb::DBCompactionStyle::Fifo,
+            });
+        }
+        if let Some(compression) = self.compression {
+            options.set_compression_type(match compression {
+                RocksDbCompression::None => rocksdb::DBCompressionType::None,
+                RocksDbCompression::Snappy => rocksdb::DBCompressionType::Snappy,
+                RocksDbCompression::Zlib => rocksdb::DBCompressionType::Zlib,
+                RocksDbCompression::Lz4 => rocksdb::DBCompressionType::Lz4,
+                RocksDbCompression::Zstd => rocksdb::DBCompressionType::Zstd,
+            });
+        }
+        options
+    }
 }
 
 impl RocksDbStoreInternal {
@@ -71,6 +217,27 @@ impl RocksDbStoreInternal {
         }
         Ok(())
     }
+
+    /// Reports the statistics of the database of `namespace` in the metrics, until the
+    /// database is closed.
+    #[cfg(with_metrics)]
+    fn report_statistics(db: Weak<DB>, namespace: String) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(STATISTICS_INTERVAL).await;
+                let Some(db) = db.upgrade() else {
+                    break;
+                };
+                for property in REPORTED_PROPERTIES {
+                    if let Ok(Some(value)) = db.property_int_value(property) {
+                        ROCKS_DB_PROPERTIES
+                            .with_label_values(&[&namespace, property])
+                            .set(i64::try_from(value).unwrap_or(i64::MAX));
+                    }
+                }
+            }
+        });
+    }
 }
 
 impl ReadableKeyValueStore<RocksDbContextError> for RocksDbStoreInternal {
@@ -144,6 +311,39 @@ impl ReadableKeyValueStore<RocksDbContextError> for RocksDbStoreInternal {
         Ok(keys)
     }
 
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, RocksDbContextError> {
+        ensure!(
+            key_prefix.len() <= MAX_KEY_SIZE,
+            RocksDbContextError::KeyTooLong
+        );
+        let client = self.clone();
+        let prefix = key_prefix.to_vec();
+        let len = prefix.len();
+        let start = [key_prefix, start].concat();
+        let keys = tokio::task::spawn_blocking(move || {
+            let mut iter = client.db.raw_iterator();
+            let mut keys = Vec::new();
+            iter.seek(&start);
+            let mut next_key = iter.key();
+            while let Some(key) = next_key {
+                if keys.len() == limit || !key.starts_with(&prefix) {
+                    break;
+                }
+                keys.push(key[len..].to_vec());
+                iter.next();
+                next_key = iter.key();
+            }
+            keys
+        })
+        .await?;
+        Ok(keys)
+    }
+
     async fn find_key_values_by_prefix(
         &self,
         key_prefix: &[u8],
@@ -248,13 +448,15 @@ impl AdminKeyValueStore for RocksDbStoreInternal {
 
     async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, RocksDbContextError> {
         Self::check_namespace(namespace)?;
-        let options = rocksdb::Options::default();
+        let options = config.tuning.options();
         let mut path_buf = config.path_buf.clone();
         path_buf.push(namespace);
-        let db = DB::open(&options, path_buf)?;
+        let db = Arc::new(DB::open(&options, path_buf)?);
+        #[cfg(with_metrics)]
+        Self::report_statistics(Arc::downgrade(&db), namespace.to_string());
         let max_stream_queries = config.common_config.max_stream_queries;
         Ok(RocksDbStoreInternal {
-            db: Arc::new(db),
+            db,
             max_stream_queries,
         })
     }
@@ -308,7 +510,7 @@ impl AdminKeyValueStore for RocksDbStoreInternal {
 
     async fn create(config: &Self::Config, namespace: &str) -> Result<(), RocksDbContextError> {
         Self::check_namespace(namespace)?;
-        let mut options = rocksdb::Options::default();
+        let mut options = config.tuning.options();
         options.create_if_missing(true);
         let mut path_buf = config.path_buf.clone();
         path_buf.push(namespace);
@@ -335,10 +537,18 @@ impl KeyValueStore for RocksDbStoreInternal {
 pub struct RocksDbStore {
     #[cfg(with_metrics)]
     store: MeteredStore<
-        LruCachingStore<MeteredStore<ValueSplittingStore<MeteredStore<RocksDbStoreInternal>>>>,
+        LruCachingStore<
+            CompressionStore<
+                MeteredStore<
+                    ValueSplittingStore<MeteredStore<GroupCommitStore<RocksDbStoreInternal>>>,
+                >,
+            >,
+        >,
     >,
     #[cfg(not(with_metrics))]
-    store: LruCachingStore<ValueSplittingStore<RocksDbStoreInternal>>,
+    store: LruCachingStore<
+        CompressionStore<ValueSplittingStore<GroupCommitStore<RocksDbStoreInternal>>>,
+    >,
 }
 
 /// Creates the common initialization for RocksDB
@@ -367,6 +577,7 @@ pub async fn create_rocks_db_test_config() -> (RocksDbStoreConfig, TempDir) {
     let store_config = RocksDbStoreConfig {
         path_buf,
         common_config,
+        tuning: RocksDbTuningConfig::default(),
     };
     (store_config, tmp_dir)
 }
@@ -424,6 +635,17 @@ impl ReadableKeyValueStore<RocksDbContextError> for RocksDbStore {
     ) -> Result<Self::KeyValues, RocksDbContextError> {
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, RocksDbContextError> {
+        self.store
+            .find_keys_by_prefix_from(key_prefix, start, limit)
+            .await
+    }
 }
 
 impl WritableKeyValueStore<RocksDbContextError> for RocksDbStore {
@@ -443,17 +665,7 @@ impl AdminKeyValueStore for RocksDbStore {
     type Config = RocksDbStoreConfig;
 
     async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, RocksDbContextError> {
-        let store = RocksDbStoreInternal::connect(config, namespace).await?;
-        let cache_size = config.common_config.cache_size;
-        #[cfg(with_metrics)]
-        let store = MeteredStore::new(&ROCKS_DB_METRICS, store);
-        let store = ValueSplittingStore::new(store);
-        #[cfg(with_metrics)]
-        let store = MeteredStore::new(&VALUE_SPLITTING_METRICS, store);
-        let store = LruCachingStore::new(store, cache_size);
-        #[cfg(with_metrics)]
-        let store = MeteredStore::new(&LRU_CACHING_METRICS, store);
-        Ok(Self { store })
+        Self::connect_internal(config, namespace, true).await
     }
 
     async fn list_all(config: &Self::Config) -> Result<Vec<String>, RocksDbContextError> {
@@ -477,6 +689,52 @@ impl AdminKeyValueStore for RocksDbStore {
     }
 }
 
+impl RocksDbStore {
+    /// Connects to the namespace like [`AdminKeyValueStore::connect`], but without checking
+    /// that the values written before value compression was enabled were converted, so that
+    /// they can be converted with [`Self::rewrite_values`].
+    pub async fn connect_to_rewrite_values(
+        config: &RocksDbStoreConfig,
+        namespace: &str,
+    ) -> Result<Self, RocksDbContextError> {
+        Self::connect_internal(config, namespace, false).await
+    }
+
+    async fn connect_internal(
+        config: &RocksDbStoreConfig,
+        namespace: &str,
+        check_values_converted: bool,
+    ) -> Result<Self, RocksDbContextError> {
+        let store = RocksDbStoreInternal::connect(config, namespace).await?;
+        let cache_size = config.common_config.cache_size;
+        // RocksDB applies each write batch atomically, so batches can be committed together.
+        let store = GroupCommitStore::with_config(store, config.tuning.group_commit);
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&ROCKS_DB_METRICS, store);
+        let store = ValueSplittingStore::new(store);
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&VALUE_SPLITTING_METRICS, store);
+        let store = CompressionStore::new(store, config.tuning.value_compression.clone());
+        if check_values_converted {
+            compression::check_values_converted(&store).await?;
+        }
+        let store = LruCachingStore::new(store, cache_size);
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&LRU_CACHING_METRICS, store);
+        Ok(Self { store })
+    }
+
+    /// Converts the values under `key_prefix` that were written before value compression was
+    /// enabled for it, and returns their number. See [`compression::rewrite_values`].
+    pub async fn rewrite_values(&self, key_prefix: &[u8]) -> Result<usize, RocksDbContextError> {
+        #[cfg(with_metrics)]
+        let store = &self.store.store.store;
+        #[cfg(not(with_metrics))]
+        let store = &self.store.store;
+        compression::rewrite_values(store, key_prefix).await
+    }
+}
+
 impl KeyValueStore for RocksDbStore {
     type Error = RocksDbContextError;
 }
@@ -316,6 +316,48 @@ pub async fn run_reads<S: LocalKeyValueStore>(store: S, key_values: Vec<(Vec<u8>
     }
 }
 
+/// Reads the keys of the given key-values page by page, starting from each of them.
+pub async fn run_reads_from<S: LocalKeyValueStore + Sync>(
+    store: S,
+    key_values: Vec<(Vec<u8>, Vec<u8>)>,
+) {
+    let mut batch = Batch::new();
+    for (key, value) in &key_values {
+        batch.put_key_value_bytes(key.clone(), value.clone());
+    }
+    store.write_batch(batch, &[]).await.unwrap();
+    let key_prefix = &[0];
+    let mut keys = key_values
+        .iter()
+        .map(|(key, _)| key[1..].to_vec())
+        .collect::<Vec<_>>();
+    keys.sort();
+    for limit in 1..4 {
+        for start in keys.iter().map(Vec::as_slice).chain([&[][..], &[255][..]]) {
+            let expected_keys = keys
+                .iter()
+                .filter(|key| key.as_slice() >= start)
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut read_keys = Vec::new();
+            let mut page_start = start.to_vec();
+            loop {
+                let page = store
+                    .find_keys_by_prefix_from(key_prefix, &page_start, limit)
+                    .await
+                    .unwrap();
+                assert!(page.len() <= limit);
+                let Some(last_key) = page.last() else {
+                    break;
+                };
+                page_start = [last_key.as_slice(), &[0]].concat();
+                read_keys.extend(page);
+            }
+            assert_eq!(read_keys, expected_keys);
+        }
+    }
+}
+
 fn get_random_key_values1(len_value: usize) -> Vec<(Vec<u8>, Vec<u8>)> {
     let key_prefix = vec![0];
     let n = 30;
@@ -0,0 +1,127 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recording of the storage keys accessed while handling a request.
+//!
+//! A future run with [`record`] records every key read or written through a
+//! [`ContextFromStore`](crate::common::ContextFromStore) while it is polled, including by the
+//! futures it spawns with [`propagate`]. Outside of [`record`], nothing is recorded.
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::batch::{Batch, WriteOperation};
+
+#[cfg(test)]
+#[path = "unit_tests/trace_tests.rs"]
+mod trace_tests;
+
+/// The maximal number of accesses kept in a trace. Further accesses are only counted.
+pub const MAX_TRACED_ACCESSES: usize = 1000;
+
+/// An access to the storage.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageAccess {
+    /// The value of the key was read, or its existence was checked.
+    Read(Vec<u8>),
+    /// The keys with the given prefix were listed.
+    FindKeys(Vec<u8>),
+    /// The keys and values with the given prefix were listed.
+    FindKeyValues(Vec<u8>),
+    /// The value of the key was written.
+    Write(Vec<u8>),
+    /// The key was deleted.
+    Delete(Vec<u8>),
+    /// The keys with the given prefix were deleted.
+    DeletePrefix(Vec<u8>),
+}
+
+/// The storage accesses recorded while handling a request, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageTrace {
+    /// The first [`MAX_TRACED_ACCESSES`] accesses.
+    pub accesses: Vec<StorageAccess>,
+    /// The number of accesses that were not kept.
+    pub omitted: usize,
+}
+
+impl StorageTrace {
+    /// Returns the number of recorded accesses that read the storage.
+    pub fn reads(&self) -> usize {
+        self.accesses
+            .iter()
+            .filter(|access| {
+                matches!(
+                    access,
+                    StorageAccess::Read(_)
+                        | StorageAccess::FindKeys(_)
+                        | StorageAccess::FindKeyValues(_)
+                )
+            })
+            .count()
+    }
+
+    /// Returns the number of recorded accesses that modified the storage.
+    pub fn writes(&self) -> usize {
+        self.accesses.len() - self.reads()
+    }
+
+    fn push(&mut self, access: StorageAccess) {
+        if self.accesses.len() < MAX_TRACED_ACCESSES {
+            self.accesses.push(access);
+        } else {
+            self.omitted += 1;
+        }
+    }
+}
+
+type SharedTrace = Arc<Mutex<StorageTrace>>;
+
+tokio::task_local! {
+    static TRACE: SharedTrace;
+}
+
+/// Runs `future`, and returns its output together with the storage accesses it made.
+pub async fn record<F: Future>(future: F) -> (F::Output, StorageTrace) {
+    let trace = SharedTrace::default();
+    let output = TRACE.scope(trace.clone(), future).await;
+    let trace = std::mem::take(&mut *trace.lock().unwrap());
+    (output, trace)
+}
+
+/// Returns a future recording its storage accesses in the same trace as the current task, if
+/// it is being recorded. This must be used for the futures that are spawned as separate tasks.
+pub fn propagate<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let trace = TRACE.try_with(Arc::clone).ok();
+    async move {
+        match trace {
+            Some(trace) => TRACE.scope(trace, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// Records an access in the current trace, if any. The access is only built if it is recorded.
+pub fn record_access(access: impl FnOnce() -> StorageAccess) {
+    let _ = TRACE.try_with(|trace| trace.lock().unwrap().push(access()));
+}
+
+/// Records the writes of a batch in the current trace, if any.
+pub fn record_batch(batch: &Batch) {
+    let _ = TRACE.try_with(|trace| {
+        let mut trace = trace.lock().unwrap();
+        for operation in &batch.operations {
+            trace.push(match operation {
+                WriteOperation::Put { key, .. } => StorageAccess::Write(key.clone()),
+                WriteOperation::Delete { key } => StorageAccess::Delete(key.clone()),
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    StorageAccess::DeletePrefix(key_prefix.clone())
+                }
+            });
+        }
+    });
+}
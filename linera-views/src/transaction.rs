@@ -0,0 +1,258 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transactions buffering their writes to a key-value store.
+//!
+//! A [`Transaction`] serves its reads from its own buffered writes first, so that code can
+//! observe what it wrote without writing to the store. Nothing is written until
+//! [`Transaction::commit`], which writes all the changes in one batch.
+//!
+//! The values read from the store are remembered. When committing, they are read again, and
+//! the transaction fails with [`TransactionError::Conflict`] if any of them changed in the
+//! meantime. The check and the write are not atomic: writers must still be serialized (e.g.
+//! per chain) for the detection to be complete.
+
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use crate::{
+    batch::Batch,
+    common::{KeyValueIterable, KeyValueStore},
+    views::ViewError,
+};
+
+/// Key-value pairs, without their common prefix.
+type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// The error returned by the operations of a [`Transaction`].
+#[derive(Debug, Error)]
+pub enum TransactionError<E> {
+    /// The store failed.
+    #[error("store error: {0}")]
+    Store(E),
+
+    /// A value read by the transaction was modified before the transaction was committed.
+    #[error("the transaction conflicts with a concurrent write")]
+    Conflict,
+}
+
+impl<E> From<TransactionError<E>> for ViewError
+where
+    ViewError: From<E>,
+{
+    fn from(error: TransactionError<E>) -> Self {
+        match error {
+            TransactionError::Store(error) => error.into(),
+            TransactionError::Conflict => ViewError::TransactionConflict,
+        }
+    }
+}
+
+/// A transaction on a key-value store, buffering its writes until it is committed.
+pub struct Transaction<'a, S> {
+    store: &'a S,
+    /// The base key of the journal of the store, if any.
+    base_key: Vec<u8>,
+    /// The values of the keys read from the store.
+    reads: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// The key-value pairs found in the store for each prefix searched.
+    prefix_reads: BTreeMap<Vec<u8>, KeyValues>,
+    /// The new value of each key written, or `None` if it was deleted.
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// The prefixes deleted, before the `writes` are applied.
+    deleted_prefixes: Vec<Vec<u8>>,
+}
+
+impl<'a, S> Transaction<'a, S>
+where
+    S: KeyValueStore + Sync,
+{
+    /// Starts a transaction on the `store`, whose journal, if any, is at `base_key`.
+    pub fn new(store: &'a S, base_key: Vec<u8>) -> Self {
+        Transaction {
+            store,
+            base_key,
+            reads: BTreeMap::new(),
+            prefix_reads: BTreeMap::new(),
+            writes: BTreeMap::new(),
+            deleted_prefixes: Vec::new(),
+        }
+    }
+
+    /// Returns whether the transaction has no changes to commit.
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty() && self.deleted_prefixes.is_empty()
+    }
+
+    /// Returns whether `key` is in a prefix deleted by the transaction.
+    fn is_deleted(&self, key: &[u8]) -> bool {
+        self.deleted_prefixes
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+    }
+
+    /// Reads the value of `key`, as written by the transaction if it was.
+    pub async fn read_value_bytes(
+        &mut self,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, TransactionError<S::Error>> {
+        if let Some(value) = self.writes.get(key) {
+            return Ok(value.clone());
+        }
+        if self.is_deleted(key) {
+            return Ok(None);
+        }
+        if let Some(value) = self.reads.get(key) {
+            return Ok(value.clone());
+        }
+        let value = self
+            .store
+            .read_value_bytes(key)
+            .await
+            .map_err(TransactionError::Store)?;
+        self.reads.insert(key.to_vec(), value.clone());
+        Ok(value)
+    }
+
+    /// Tests whether `key` exists, as written by the transaction if it was.
+    pub async fn contains_key(&mut self, key: &[u8]) -> Result<bool, TransactionError<S::Error>> {
+        Ok(self.read_value_bytes(key).await?.is_some())
+    }
+
+    /// Finds the key-value pairs matching the prefix, including the writes of the
+    /// transaction. The prefix is not included in the returned keys.
+    pub async fn find_key_values_by_prefix(
+        &mut self,
+        key_prefix: &[u8],
+    ) -> Result<KeyValues, TransactionError<S::Error>> {
+        let mut entries = BTreeMap::new();
+        if !self.is_deleted(key_prefix) {
+            for (key, value) in self.read_prefix(key_prefix).await? {
+                let mut full_key = key_prefix.to_vec();
+                full_key.extend_from_slice(&key);
+                if !self.is_deleted(&full_key) {
+                    entries.insert(full_key, value);
+                }
+            }
+        }
+        for (key, value) in self.writes.range(key_prefix.to_vec()..) {
+            if !key.starts_with(key_prefix) {
+                break;
+            }
+            match value {
+                Some(value) => entries.insert(key.clone(), value.clone()),
+                None => entries.remove(key),
+            };
+        }
+        Ok(entries
+            .into_iter()
+            .map(|(key, value)| (key[key_prefix.len()..].to_vec(), value))
+            .collect())
+    }
+
+    /// Finds the keys matching the prefix, including the writes of the transaction. The
+    /// prefix is not included in the returned keys.
+    pub async fn find_keys_by_prefix(
+        &mut self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<Vec<u8>>, TransactionError<S::Error>> {
+        Ok(self
+            .find_key_values_by_prefix(key_prefix)
+            .await?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    /// Returns the key-value pairs found in the store for `key_prefix`, and remembers them.
+    async fn read_prefix(
+        &mut self,
+        key_prefix: &[u8],
+    ) -> Result<KeyValues, TransactionError<S::Error>> {
+        if let Some(entries) = self.prefix_reads.get(key_prefix) {
+            return Ok(entries.clone());
+        }
+        let entries = read_prefix_from_store(self.store, key_prefix).await?;
+        self.prefix_reads
+            .insert(key_prefix.to_vec(), entries.clone());
+        Ok(entries)
+    }
+
+    /// Sets the value of `key`.
+    pub fn put_key_value_bytes(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.writes.insert(key, Some(value));
+    }
+
+    /// Deletes `key`.
+    pub fn delete_key(&mut self, key: Vec<u8>) {
+        self.writes.insert(key, None);
+    }
+
+    /// Deletes all the keys starting with `key_prefix`.
+    pub fn delete_key_prefix(&mut self, key_prefix: Vec<u8>) {
+        self.writes.retain(|key, _| !key.starts_with(&key_prefix));
+        self.deleted_prefixes
+            .retain(|prefix| !prefix.starts_with(&key_prefix));
+        self.deleted_prefixes.push(key_prefix);
+    }
+
+    /// Checks that the values read by the transaction are unchanged, and writes its
+    /// changes to the store.
+    pub async fn commit(self) -> Result<(), TransactionError<S::Error>> {
+        for (key, value) in &self.reads {
+            let current = self
+                .store
+                .read_value_bytes(key)
+                .await
+                .map_err(TransactionError::Store)?;
+            if current != *value {
+                return Err(TransactionError::Conflict);
+            }
+        }
+        for (key_prefix, entries) in &self.prefix_reads {
+            if read_prefix_from_store(self.store, key_prefix).await? != *entries {
+                return Err(TransactionError::Conflict);
+            }
+        }
+        if self.is_empty() {
+            return Ok(());
+        }
+        let mut batch = Batch::new();
+        for key_prefix in self.deleted_prefixes {
+            batch.delete_key_prefix(key_prefix);
+        }
+        for (key, value) in self.writes {
+            match value {
+                Some(value) => batch.put_key_value_bytes(key, value),
+                None => batch.delete_key(key),
+            }
+        }
+        self.store
+            .write_batch(batch, &self.base_key)
+            .await
+            .map_err(TransactionError::Store)
+    }
+}
+
+/// Returns the key-value pairs of the `store` matching `key_prefix`.
+async fn read_prefix_from_store<S>(
+    store: &S,
+    key_prefix: &[u8],
+) -> Result<KeyValues, TransactionError<S::Error>>
+where
+    S: KeyValueStore + Sync,
+{
+    let key_values = store
+        .find_key_values_by_prefix(key_prefix)
+        .await
+        .map_err(TransactionError::Store)?;
+    key_values
+        .iterator()
+        .map(|entry| {
+            entry
+                .map(|(key, value)| (key.to_vec(), value.to_vec()))
+                .map_err(TransactionError::Store)
+        })
+        .collect()
+}
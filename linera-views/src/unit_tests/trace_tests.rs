@@ -0,0 +1,45 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{propagate, record, StorageAccess, StorageTrace};
+use crate::{batch::Batch, common::Context, memory::create_memory_context};
+
+#[tokio::test]
+async fn test_record_storage_accesses() {
+    let context = create_memory_context();
+    let ((), trace) = record(async {
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1, 2], vec![3]);
+        batch.delete_key_prefix(vec![4]);
+        context.write_batch(batch).await.unwrap();
+        context.read_value_bytes(&[1, 2]).await.unwrap();
+        let spawned = tokio::spawn(propagate({
+            let context = context.clone();
+            async move { context.find_keys_by_prefix(&[1]).await.unwrap() }
+        }));
+        spawned.await.unwrap();
+    })
+    .await;
+    assert_eq!(
+        trace,
+        StorageTrace {
+            accesses: vec![
+                StorageAccess::Write(vec![1, 2]),
+                StorageAccess::DeletePrefix(vec![4]),
+                StorageAccess::Read(vec![1, 2]),
+                StorageAccess::FindKeys(vec![1]),
+            ],
+            omitted: 0,
+        }
+    );
+    assert_eq!(trace.reads(), 2);
+    assert_eq!(trace.writes(), 2);
+}
+
+#[tokio::test]
+async fn test_nothing_is_recorded_outside_of_a_trace() {
+    let context = create_memory_context();
+    context.read_value_bytes(&[1]).await.unwrap();
+    let ((), trace) = record(async {}).await;
+    assert_eq!(trace, StorageTrace::default());
+}
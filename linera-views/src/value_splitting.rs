@@ -30,6 +30,21 @@ pub enum DatabaseConsistencyError {
     /// no count of size u32 is available in the value
     #[error("no count of size u32 is available in the value")]
     NoCountAvailable,
+
+    /// a value could not be compressed or decompressed
+    #[error("a value could not be compressed or decompressed")]
+    InvalidCompressedValue,
+
+    /// the recorded progress of the compression of existing values is invalid
+    #[error("the recorded progress of the compression of existing values is invalid")]
+    InvalidRewriteProgress,
+
+    /// values written before value compression was enabled have not been converted
+    #[error(
+        "the values under the key prefix {0:?} were written before value compression was \
+        enabled and must be converted first, e.g. with `linera-db compress_values`"
+    )]
+    UnconvertedValues(Vec<u8>),
 }
 
 /// A key-value store with no size limit for values.
@@ -192,6 +207,38 @@ where
         }
         Ok(key_values)
     }
+
+    async fn find_keys_by_prefix_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, K::Error> {
+        // The first segments of the values are in the same order as their keys, and the
+        // first one of a key not smaller than `start` is not smaller than this.
+        let mut big_start = start.to_vec();
+        big_start.extend(&[0, 0, 0, 0]);
+        let mut keys = Vec::new();
+        while keys.len() < limit {
+            let big_keys = self
+                .store
+                .find_keys_by_prefix_from(key_prefix, &big_start, limit)
+                .await?;
+            for big_key in &big_keys {
+                if keys.len() < limit && Self::read_index_from_key(big_key)? == 0 {
+                    keys.push(big_key[0..big_key.len() - 4].to_vec());
+                }
+            }
+            match big_keys.last() {
+                Some(big_key) if big_keys.len() == limit => {
+                    big_start = big_key.clone();
+                    big_start.push(0);
+                }
+                _ => break,
+            }
+        }
+        Ok(keys)
+    }
 }
 
 impl<K> WritableKeyValueStore<K::Error> for ValueSplittingStore<K>
@@ -101,6 +101,10 @@ pub enum ViewError {
     /// The value is too large for the client
     #[error("The value is too large for the client")]
     TooLargeValue,
+
+    /// A value read by a transaction was modified before the transaction was committed.
+    #[error("The transaction conflicts with a concurrent write")]
+    TransactionConflict,
 }
 
 impl ViewError {
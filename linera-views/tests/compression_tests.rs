@@ -0,0 +1,246 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_views::{
+    batch::Batch,
+    common::{ReadableKeyValueStore, WritableKeyValueStore},
+    compression::{
+        check_values_converted, rewrite_values, CompressionConfig, CompressionStore,
+        REWRITE_PROGRESS_KEY_PREFIX,
+    },
+    memory::create_memory_store,
+    test_utils::{get_random_test_scenarios, run_reads, run_writes_from_blank},
+};
+
+/// Returns a configuration compressing all the values, however small.
+fn compress_everything() -> CompressionConfig {
+    CompressionConfig {
+        key_prefixes: vec![(Vec::new(), 3)],
+        min_value_size: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_reads_compression_memory() {
+    for scenario in get_random_test_scenarios() {
+        let key_value_store = CompressionStore::new(create_memory_store(), compress_everything());
+        run_reads(key_value_store, scenario).await;
+    }
+}
+
+#[tokio::test]
+async fn test_compression_memory_writes_from_blank() {
+    let key_value_store = CompressionStore::new(create_memory_store(), compress_everything());
+    run_writes_from_blank(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_compression_of_prefixes() {
+    let config = CompressionConfig {
+        key_prefixes: vec![(vec![1], 3)],
+        ..CompressionConfig::default()
+    };
+    let store = CompressionStore::new(create_memory_store(), config);
+    let value = vec![7; 10_000];
+    let small_value = vec![7; 10];
+    let compressed_value = zstd::stream::encode_all(value.as_slice(), 3).unwrap();
+
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 0], value.clone());
+    batch.put_key_value_bytes(vec![1, 1], small_value.clone());
+    batch.put_key_value_bytes(vec![1, 2], compressed_value.clone());
+    batch.put_key_value_bytes(vec![2, 0], value.clone());
+    batch.put_key_value_bytes(vec![2, 1], compressed_value.clone());
+    store.write_batch(batch, &[]).await.unwrap();
+
+    // The values under the configured prefix are tagged, and only those that shrink are
+    // compressed. The other values are stored as they are, even if they look compressed.
+    let stored = |key: Vec<u8>| {
+        let inner = &store.store;
+        async move { inner.read_value_bytes(&key).await.unwrap().unwrap() }
+    };
+    assert!(stored(vec![1, 0]).await.len() < 100);
+    assert_eq!(
+        stored(vec![1, 1]).await,
+        [&[0], small_value.as_slice()].concat()
+    );
+    assert_eq!(stored(vec![2, 0]).await, value);
+    assert_eq!(stored(vec![2, 1]).await, compressed_value);
+
+    let keys = vec![vec![1, 0], vec![1, 1], vec![1, 2], vec![2, 0], vec![2, 1]];
+    let values = store.read_multi_values_bytes(keys).await.unwrap();
+    assert_eq!(
+        values,
+        vec![
+            Some(value.clone()),
+            Some(small_value.clone()),
+            Some(compressed_value.clone()),
+            Some(value.clone()),
+            Some(compressed_value.clone()),
+        ]
+    );
+    let key_values = store.find_key_values_by_prefix(&[1]).await.unwrap();
+    assert_eq!(
+        key_values,
+        vec![
+            (vec![0], value.clone()),
+            (vec![1], small_value),
+            (vec![2], compressed_value.clone()),
+        ]
+    );
+    let key_values = store.find_key_values_by_prefix(&[]).await.unwrap();
+    assert_eq!(key_values[3], (vec![2, 0], value));
+    assert_eq!(key_values[4], (vec![2, 1], compressed_value));
+
+    // An untagged value under the configured prefix is an error.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 3], vec![2, 3]);
+    store.store.write_batch(batch, &[]).await.unwrap();
+    assert!(store.read_value_bytes(&[1, 3]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_compression_of_existing_values() {
+    let config = CompressionConfig {
+        key_prefixes: vec![(vec![1], 3)],
+        ..CompressionConfig::default()
+    };
+    let store = CompressionStore::new(create_memory_store(), config);
+    let value = vec![7; 10_000];
+
+    // Values written before compression was enabled.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 0], value.clone());
+    batch.put_key_value_bytes(vec![1, 1], vec![0]);
+    batch.put_key_value_bytes(vec![2, 0], value.clone());
+    store.store.write_batch(batch, &[]).await.unwrap();
+
+    // Rewriting them converts those under the configured prefix only.
+    assert_eq!(rewrite_values(&store, &[1]).await.unwrap(), 2);
+    let stored = |key: Vec<u8>| {
+        let inner = &store.store;
+        async move { inner.read_value_bytes(&key).await.unwrap().unwrap() }
+    };
+    assert!(stored(vec![1, 0]).await.len() < 100);
+    assert_eq!(stored(vec![2, 0]).await, value);
+    assert_eq!(store.read_value_bytes(&[1, 0]).await.unwrap(), Some(value));
+    assert_eq!(
+        store.read_value_bytes(&[1, 1]).await.unwrap(),
+        Some(vec![0])
+    );
+}
+
+#[tokio::test]
+async fn test_compression_of_existing_values_is_idempotent() {
+    let config = CompressionConfig {
+        key_prefixes: vec![(vec![1], 3)],
+        ..CompressionConfig::default()
+    };
+    let store = CompressionStore::new(create_memory_store(), config);
+    let value = vec![7; 10_000];
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 0], value.clone());
+    batch.put_key_value_bytes(vec![1, 1], vec![0]);
+    store.store.write_batch(batch, &[]).await.unwrap();
+
+    // Running the conversion again doesn't convert the tagged values a second time.
+    assert_eq!(rewrite_values(&store, &[1]).await.unwrap(), 2);
+    assert_eq!(rewrite_values(&store, &[1]).await.unwrap(), 0);
+    assert_eq!(
+        store.read_value_bytes(&[1, 0]).await.unwrap(),
+        Some(value.clone())
+    );
+    assert_eq!(
+        store.read_value_bytes(&[1, 1]).await.unwrap(),
+        Some(vec![0])
+    );
+
+    // The values written through the store afterwards are not converted either.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 2], value.clone());
+    store.write_batch(batch, &[]).await.unwrap();
+    assert_eq!(rewrite_values(&store, &[1]).await.unwrap(), 0);
+    assert_eq!(store.read_value_bytes(&[1, 2]).await.unwrap(), Some(value));
+}
+
+#[tokio::test]
+async fn test_interrupted_compression_of_existing_values_is_resumed() {
+    let config = CompressionConfig {
+        key_prefixes: vec![(vec![1], 3)],
+        ..CompressionConfig::default()
+    };
+    let store = CompressionStore::new(create_memory_store(), config);
+    let value = vec![7; 10_000];
+
+    // The conversion was interrupted after the first value: it is tagged, and recorded as the
+    // last converted one, but the second one is not.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 0], value.clone());
+    store.write_batch(batch, &[]).await.unwrap();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 1], value.clone());
+    batch.put_key_value_bytes([REWRITE_PROGRESS_KEY_PREFIX, &[1]].concat(), vec![1, 1, 0]);
+    store.store.write_batch(batch, &[]).await.unwrap();
+
+    assert_eq!(rewrite_values(&store, &[1]).await.unwrap(), 1);
+    assert_eq!(
+        store.read_value_bytes(&[1, 0]).await.unwrap(),
+        Some(value.clone())
+    );
+    assert_eq!(store.read_value_bytes(&[1, 1]).await.unwrap(), Some(value));
+}
+
+#[tokio::test]
+async fn test_check_values_converted() {
+    let config = CompressionConfig {
+        key_prefixes: vec![(vec![1], 3), (vec![2], 3)],
+        ..CompressionConfig::default()
+    };
+    let store = CompressionStore::new(create_memory_store(), config);
+    let value = vec![7; 10_000];
+
+    // A value written before compression was enabled under the first prefix.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 0], value.clone());
+    store.store.write_batch(batch, &[]).await.unwrap();
+    assert!(check_values_converted(&store).await.is_err());
+
+    // An interrupted conversion is not enough.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes([REWRITE_PROGRESS_KEY_PREFIX, &[1]].concat(), vec![1, 1]);
+    store.store.write_batch(batch, &[]).await.unwrap();
+    assert!(check_values_converted(&store).await.is_err());
+
+    // Once converted, the store can be used. The empty prefix is marked as converted.
+    assert_eq!(rewrite_values(&store, &[1]).await.unwrap(), 1);
+    check_values_converted(&store).await.unwrap();
+    let progress_key = [REWRITE_PROGRESS_KEY_PREFIX, &[2]].concat();
+    assert_eq!(
+        store.store.read_value_bytes(&progress_key).await.unwrap(),
+        Some(vec![0])
+    );
+
+    // The values written to the empty prefix afterwards are tagged: they are not converted
+    // again.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![2, 0], value.clone());
+    store.write_batch(batch, &[]).await.unwrap();
+    check_values_converted(&store).await.unwrap();
+    assert_eq!(rewrite_values(&store, &[2]).await.unwrap(), 0);
+    assert_eq!(store.read_value_bytes(&[2, 0]).await.unwrap(), Some(value));
+}
+
+#[tokio::test]
+async fn test_no_compression() {
+    let store = CompressionStore::new(create_memory_store(), CompressionConfig::default());
+    let value = vec![7; 10_000];
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 0], value.clone());
+    store.write_batch(batch, &[]).await.unwrap();
+    assert_eq!(
+        store.store.read_value_bytes(&[1, 0]).await.unwrap(),
+        Some(value)
+    );
+}
@@ -0,0 +1,89 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::time::Duration;
+
+use linera_views::{
+    batch::Batch,
+    common::{ReadableKeyValueStore, WritableKeyValueStore},
+    group_commit::{GroupCommitConfig, GroupCommitStore},
+    memory::create_memory_store,
+    test_utils::{get_random_test_scenarios, run_reads, run_writes_from_blank},
+};
+
+#[tokio::test]
+async fn test_reads_group_commit_memory() {
+    for scenario in get_random_test_scenarios() {
+        let key_value_store =
+            GroupCommitStore::new(create_memory_store(), GroupCommitConfig::default());
+        run_reads(key_value_store, scenario).await;
+    }
+}
+
+#[tokio::test]
+async fn test_group_commit_memory_writes_from_blank() {
+    let key_value_store =
+        GroupCommitStore::new(create_memory_store(), GroupCommitConfig::default());
+    run_writes_from_blank(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_group_commit_concurrent_writes() {
+    let key_value_store = GroupCommitStore::new(
+        create_memory_store(),
+        GroupCommitConfig {
+            max_operations: 10,
+            ..GroupCommitConfig::default()
+        },
+    );
+    let writes = (0..100u8).map(|index| {
+        let key_value_store = key_value_store.clone();
+        async move {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![index, 0], vec![index]);
+            batch.put_key_value_bytes(vec![index, 1], vec![index]);
+            batch.delete_key(vec![index, 0]);
+            key_value_store.write_batch(batch, &[index]).await
+        }
+    });
+    for result in futures::future::join_all(writes).await {
+        result.unwrap();
+    }
+    for index in 0..100u8 {
+        let values = key_value_store
+            .read_multi_values_bytes(vec![vec![index, 0], vec![index, 1]])
+            .await
+            .unwrap();
+        assert_eq!(values, vec![None, Some(vec![index])]);
+    }
+}
+
+#[tokio::test]
+async fn test_group_commit_does_not_delay_uncontended_writes() {
+    let key_value_store = GroupCommitStore::new(
+        create_memory_store(),
+        GroupCommitConfig {
+            max_delay: Duration::from_secs(3600),
+            ..GroupCommitConfig::default()
+        },
+    );
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![0], vec![1]);
+    let write = key_value_store.write_batch(batch, &[]);
+    tokio::time::timeout(Duration::from_secs(10), write)
+        .await
+        .expect("a single batch should be committed at once")
+        .unwrap();
+    assert_eq!(
+        key_value_store.read_value_bytes(&[0]).await.unwrap(),
+        Some(vec![1])
+    );
+}
+
+#[tokio::test]
+async fn test_disabled_group_commit_memory_writes_from_blank() {
+    let key_value_store = GroupCommitStore::disabled(create_memory_store());
+    run_writes_from_blank(&key_value_store).await;
+}
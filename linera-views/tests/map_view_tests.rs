@@ -1,7 +1,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::{Bound, RangeBounds},
+};
 
 use linera_views::{
     map_view::HashedByteMapView,
@@ -16,6 +19,20 @@ pub struct StateView<C> {
     pub map: HashedByteMapView<C, u8>,
 }
 
+fn random_bound<R: RngCore + Clone>(rng: &mut R) -> Bound<Vec<u8>> {
+    let len = rng.gen_range(1..4);
+    let key = rng
+        .clone()
+        .sample_iter(Uniform::from(0..4))
+        .take(len)
+        .collect::<Vec<_>>();
+    match rng.gen_range(0..3) {
+        0 => Bound::Included(key),
+        1 => Bound::Excluded(key),
+        _ => Bound::Unbounded,
+    }
+}
+
 fn remove_by_prefix<V>(map: &mut BTreeMap<Vec<u8>, V>, key_prefix: Vec<u8>) {
     map.retain(|key, _| !key.starts_with(&key_prefix));
 }
@@ -135,6 +152,49 @@ async fn run_map_view_mutability<R: RngCore + Clone>(rng: &mut R) {
                 let part_key_values = view.map.key_values_by_prefix(vec![u]).await.unwrap();
                 assert_eq!(part_state_vec, part_key_values);
             }
+            let range = (random_bound(rng), random_bound(rng));
+            let range_state_vec = new_state_vec
+                .iter()
+                .filter(|(key, _)| range.contains(key))
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut range_key_values = Vec::new();
+            view.map
+                .for_each_key_value_in_range(
+                    |key, value| {
+                        range_key_values.push((key.to_vec(), bcs::from_bytes(value)?));
+                        Ok(())
+                    },
+                    range.clone(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(range_state_vec, range_key_values);
+            let range_count = view.map.count_in_range(range).await.unwrap();
+            assert_eq!(range_state_vec.len(), range_count);
+            let limit = rng.gen_range(1..4);
+            let mut paginated_key_values = Vec::new();
+            loop {
+                let cursor = paginated_key_values
+                    .last()
+                    .map(|(key, _): &(Vec<u8>, u8)| key.clone());
+                let page = view
+                    .map
+                    .key_values_after(cursor.as_deref(), limit)
+                    .await
+                    .unwrap();
+                let keys = view.map.keys_after(cursor.as_deref(), limit).await.unwrap();
+                assert_eq!(
+                    keys,
+                    page.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>()
+                );
+                assert!(page.len() <= limit);
+                if page.is_empty() {
+                    break;
+                }
+                paginated_key_values.extend(page);
+            }
+            assert_eq!(new_state_vec, paginated_key_values);
             for key in &all_keys {
                 let test_map = new_state_map.contains_key(key);
                 let test_view = view.map.get(key).await.unwrap().is_some();
@@ -0,0 +1,93 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_views::{
+    common::HasherOutput,
+    memory::create_memory_context,
+    merkle::{self, FieldsProof, HashedMerkleMapView, MerkleProof},
+    register_view::HashedRegisterView,
+    views::{CryptoHashRootView, CryptoHashView, HashableView, View},
+};
+
+#[derive(CryptoHashRootView)]
+pub struct StateView<C> {
+    pub counter: HashedRegisterView<C, u64>,
+    pub balances: HashedMerkleMapView<C, u32, u128>,
+}
+
+fn leaves(count: usize) -> Vec<HasherOutput> {
+    (0..count)
+        .map(|i| merkle::leaf_hash(&i.to_le_bytes(), b"value"))
+        .collect()
+}
+
+#[test]
+fn test_merkle_proofs() {
+    for count in 1..20 {
+        let leaves = leaves(count);
+        let root = merkle::merkle_root(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = MerkleProof::new(&leaves, index).unwrap();
+            assert_eq!(proof.root(*leaf), Some(root));
+            let other_leaf = merkle::leaf_hash(b"other key", b"value");
+            assert_ne!(proof.root(other_leaf), Some(root));
+            let mut truncated_proof = proof.clone();
+            if truncated_proof.siblings.pop().is_some() {
+                assert_eq!(truncated_proof.root(*leaf), None);
+            }
+        }
+        assert!(MerkleProof::new(&leaves, count).is_none());
+    }
+}
+
+#[test]
+fn test_merkle_root_depends_on_leaf_count() {
+    let leaves = leaves(2);
+    let inner = merkle::merkle_root(&leaves[..1]);
+    assert_ne!(merkle::merkle_root(&leaves), inner);
+    assert_ne!(merkle::merkle_root(&[]), merkle::merkle_root(&leaves[..1]));
+}
+
+#[tokio::test]
+async fn test_key_inclusion_proof() {
+    let context = create_memory_context();
+    let mut view = StateView::load(context).await.unwrap();
+    view.counter.set(7);
+    for i in 0..10u32 {
+        view.balances.insert(&i, u128::from(i) * 100).unwrap();
+    }
+    let path = vec![FieldsProof::new(&view, "balances").await.unwrap()];
+    assert_eq!(
+        path[0],
+        FieldsProof {
+            field_hashes: vec![
+                view.counter.hash().await.unwrap(),
+                view.balances.hash().await.unwrap(),
+            ],
+            index: 1,
+        }
+    );
+    assert!(FieldsProof::new(&view, "missing").await.is_err());
+    let mut proof = view
+        .balances
+        .inclusion_proof(&3u32, path)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(proof.value, bcs::to_bytes(&300u128).unwrap());
+    let root_hash = view.hash().await.unwrap();
+    assert!(proof.verify(&root_hash));
+    let crypto_hash = view.crypto_hash().await.unwrap();
+    assert!(proof.verify_crypto_hash("StateView", &crypto_hash));
+    assert!(!proof.verify_crypto_hash("OtherView", &crypto_hash));
+
+    proof.value = bcs::to_bytes(&301u128).unwrap();
+    assert!(!proof.verify(&root_hash));
+
+    assert!(view
+        .balances
+        .inclusion_proof(&10u32, Vec::new())
+        .await
+        .unwrap()
+        .is_none());
+}
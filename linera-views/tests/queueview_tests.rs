@@ -91,3 +91,32 @@ async fn queue_view_mutability_check() {
         }
     }
 }
+
+#[tokio::test]
+async fn queue_view_pop_front_check() {
+    let context = create_memory_context();
+    let mut rng = test_utils::make_deterministic_rng();
+    let mut vector = Vec::new();
+    for _ in 0..20 {
+        let mut view = StateView::load(context.clone()).await.unwrap();
+        let mut new_vector = vector.clone();
+        for _ in 0..rng.gen_range(0..25) {
+            if rng.gen::<bool>() {
+                let val = rng.gen::<u8>();
+                view.queue.push_back(val);
+                new_vector.push(val);
+            } else {
+                // Popping from both the stored and the newly pushed entries.
+                let value = view.queue.pop_front().await.unwrap();
+                let expected = (!new_vector.is_empty()).then(|| new_vector.remove(0));
+                assert_eq!(value, expected);
+            }
+            assert_eq!(view.queue.count(), new_vector.len());
+        }
+        assert_eq!(view.queue.elements().await.unwrap(), new_vector);
+        if rng.gen::<bool>() {
+            vector = new_vector;
+            view.save().await.unwrap();
+        }
+    }
+}
@@ -7,8 +7,8 @@ use linera_views::{
     key_value_store_view::ViewContainer,
     memory::{create_memory_context, create_memory_store},
     test_utils::{
-        self, get_random_test_scenarios, run_big_write_read, run_reads, run_writes_from_blank,
-        run_writes_from_state,
+        self, get_random_test_scenarios, run_big_write_read, run_reads, run_reads_from,
+        run_writes_from_blank, run_writes_from_state,
     },
     value_splitting::create_test_memory_store,
 };
@@ -43,6 +43,31 @@ async fn test_reads_rocks_db() {
     }
 }
 
+#[tokio::test]
+async fn test_reads_from_test_memory() {
+    for scenario in get_random_test_scenarios() {
+        let key_value_store = create_test_memory_store();
+        run_reads_from(key_value_store, scenario).await;
+    }
+}
+
+#[tokio::test]
+async fn test_reads_from_memory() {
+    for scenario in get_random_test_scenarios() {
+        let key_value_store = create_memory_store();
+        run_reads_from(key_value_store, scenario).await;
+    }
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_reads_from_rocks_db() {
+    for scenario in get_random_test_scenarios() {
+        let (key_value_store, _dir) = linera_views::rocks_db::create_rocks_db_test_store().await;
+        run_reads_from(key_value_store, scenario).await;
+    }
+}
+
 #[cfg(with_dynamodb)]
 #[tokio::test]
 async fn test_reads_dynamo_db() {
@@ -117,6 +142,83 @@ async fn test_rocks_db_writes_from_blank() {
     run_writes_from_blank(&key_value_store).await;
 }
 
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_tuned_rocks_db_writes_from_blank() {
+    use linera_views::{
+        common::AdminKeyValueStore,
+        compression::CompressionConfig,
+        group_commit::GroupCommitConfig,
+        rocks_db::{
+            create_rocks_db_test_config, RocksDbCompactionStyle, RocksDbCompression, RocksDbStore,
+            RocksDbTuningConfig,
+        },
+        test_utils::generate_test_namespace,
+    };
+
+    let (mut store_config, _dir) = create_rocks_db_test_config().await;
+    store_config.tuning = RocksDbTuningConfig {
+        block_cache_size: Some(1 << 20),
+        write_buffer_size: Some(1 << 20),
+        compaction_style: Some(RocksDbCompactionStyle::Universal),
+        compression: Some(RocksDbCompression::None),
+        value_compression: CompressionConfig {
+            key_prefixes: vec![(Vec::new(), 3)],
+            min_value_size: 0,
+        },
+        group_commit: Some(GroupCommitConfig::default()),
+    };
+    let namespace = generate_test_namespace();
+    let key_value_store = RocksDbStore::recreate_and_connect(&store_config, &namespace)
+        .await
+        .unwrap();
+    run_writes_from_blank(&key_value_store).await;
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_rocks_db_value_compression_of_existing_values() {
+    use linera_views::{
+        batch::Batch,
+        common::{AdminKeyValueStore, ReadableKeyValueStore, WritableKeyValueStore},
+        compression::CompressionConfig,
+        rocks_db::{create_rocks_db_test_config, RocksDbStore},
+        test_utils::generate_test_namespace,
+    };
+
+    let (mut store_config, _dir) = create_rocks_db_test_config().await;
+    let namespace = generate_test_namespace();
+    let value = vec![0; 1000];
+    {
+        let store = RocksDbStore::recreate_and_connect(&store_config, &namespace)
+            .await
+            .unwrap();
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1, 0], value.clone());
+        store.write_batch(batch, &[]).await.unwrap();
+    }
+
+    // The values written without compression must be converted before the store is opened
+    // with compression.
+    store_config.tuning.value_compression = CompressionConfig {
+        key_prefixes: vec![(vec![1], 3)],
+        ..CompressionConfig::default()
+    };
+    assert!(RocksDbStore::connect(&store_config, &namespace)
+        .await
+        .is_err());
+    {
+        let store = RocksDbStore::connect_to_rewrite_values(&store_config, &namespace)
+            .await
+            .unwrap();
+        assert_eq!(store.rewrite_values(&[1]).await.unwrap(), 1);
+    }
+    let store = RocksDbStore::connect(&store_config, &namespace)
+        .await
+        .unwrap();
+    assert_eq!(store.read_value_bytes(&[1, 0]).await.unwrap(), Some(value));
+}
+
 #[cfg(with_dynamodb)]
 #[tokio::test]
 async fn test_dynamo_db_writes_from_blank() {
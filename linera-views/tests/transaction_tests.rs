@@ -0,0 +1,82 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_views::{
+    batch::Batch,
+    common::{ReadableKeyValueStore, WritableKeyValueStore},
+    memory::create_memory_store,
+    transaction::{Transaction, TransactionError},
+};
+
+#[tokio::test]
+async fn test_transaction_reads_its_writes() {
+    let store = create_memory_store();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![0, 1], vec![1]);
+    batch.put_key_value_bytes(vec![0, 2], vec![2]);
+    batch.put_key_value_bytes(vec![1, 1], vec![3]);
+    store.write_batch(batch, &[]).await.unwrap();
+
+    let mut transaction = Transaction::new(&store, Vec::new());
+    transaction.put_key_value_bytes(vec![0, 3], vec![4]);
+    transaction.delete_key(vec![0, 1]);
+    assert_eq!(
+        transaction.read_value_bytes(&[0, 3]).await.unwrap(),
+        Some(vec![4])
+    );
+    assert!(!transaction.contains_key(&[0, 1]).await.unwrap());
+    assert_eq!(
+        transaction.find_key_values_by_prefix(&[0]).await.unwrap(),
+        vec![(vec![2], vec![2]), (vec![3], vec![4])]
+    );
+    transaction.delete_key_prefix(vec![0]);
+    transaction.put_key_value_bytes(vec![0, 5], vec![5]);
+    assert_eq!(
+        transaction.find_keys_by_prefix(&[]).await.unwrap(),
+        vec![vec![0, 5], vec![1, 1]]
+    );
+    // Nothing is written before the commit.
+    assert_eq!(store.read_value_bytes(&[0, 3]).await.unwrap(), None);
+    transaction.commit().await.unwrap();
+
+    let keys = store.find_keys_by_prefix(&[]).await.unwrap();
+    assert_eq!(keys, vec![vec![0, 5], vec![1, 1]]);
+}
+
+#[tokio::test]
+async fn test_transaction_conflicts() {
+    let store = create_memory_store();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![0], vec![1]);
+    store.write_batch(batch, &[]).await.unwrap();
+
+    let mut transaction = Transaction::new(&store, Vec::new());
+    assert_eq!(
+        transaction.read_value_bytes(&[0]).await.unwrap(),
+        Some(vec![1])
+    );
+    transaction.put_key_value_bytes(vec![1], vec![2]);
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![0], vec![3]);
+    store.write_batch(batch, &[]).await.unwrap();
+    assert!(matches!(
+        transaction.commit().await,
+        Err(TransactionError::Conflict)
+    ));
+    assert_eq!(store.read_value_bytes(&[1]).await.unwrap(), None);
+
+    let mut transaction = Transaction::new(&store, Vec::new());
+    assert_eq!(
+        transaction.find_keys_by_prefix(&[]).await.unwrap(),
+        vec![vec![0]]
+    );
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![2], vec![4]);
+    store.write_batch(batch, &[]).await.unwrap();
+    assert!(matches!(
+        transaction.commit().await,
+        Err(TransactionError::Conflict)
+    ));
+}
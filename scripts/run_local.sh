@@ -43,7 +43,7 @@ done
 # Start servers and create initial chains in DB
 for I in $(seq 1 $NUM_VALIDATORS)
 do
-    ./linera-proxy server_"$I".json &
+    ./linera-proxy server_"$I".json --genesis genesis.json &
 
     for J in $(seq 0 $((SHARDS_PER_VALIDATOR - 1)))
     do